@@ -0,0 +1,53 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Benchmarks for pushing to and popping from the shared memory rings of Catmem.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use ::criterion::{
+    black_box,
+    criterion_group,
+    criterion_main,
+    BenchmarkId,
+    Criterion,
+    Throughput,
+};
+use ::demikernel::CatmemRing;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Sizes of messages.
+const SIZES: [usize; 3] = [64, 1024, 4096];
+
+//======================================================================================================================
+// Benchmarks
+//======================================================================================================================
+
+/// Pushes a message to a ring and pops it from the other end, within the same process.
+fn bench_push_pop(c: &mut Criterion) {
+    let name: String = format!("demikernel-bench-catmem-{}", std::process::id());
+    let mut creator: CatmemRing = CatmemRing::create(&name).unwrap();
+    let mut opener: CatmemRing = CatmemRing::open(&name).unwrap();
+
+    let mut group = c.benchmark_group("catmem/push_pop");
+    for size in SIZES {
+        let msg: Vec<u8> = vec![0xab; size];
+        let mut buf: Vec<u8> = vec![0; size];
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_function(BenchmarkId::from_parameter(size), |b| {
+            b.iter(|| {
+                creator.try_push(black_box(&msg)).unwrap();
+                black_box(opener.try_pop(&mut buf).unwrap())
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_push_pop);
+criterion_main!(benches);
@@ -0,0 +1,91 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Benchmarks for the allocation and manipulation of [DemiBuffer]s, which every packet goes through.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use ::criterion::{
+    black_box,
+    criterion_group,
+    criterion_main,
+    BatchSize,
+    BenchmarkId,
+    Criterion,
+    Throughput,
+};
+use ::demikernel::runtime::memory::DemiBuffer;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Sizes of buffers: a small request, a full Ethernet frame and a jumbo frame.
+const SIZES: [u16; 3] = [64, 1514, 9014];
+
+/// Size of the Ethernet, IPv4 and TCP headers that are stripped from received frames.
+const HEADERS_SIZE: usize = 14 + 20 + 20;
+
+//======================================================================================================================
+// Benchmarks
+//======================================================================================================================
+
+/// Allocates and frees buffers on the heap.
+fn bench_alloc(c: &mut Criterion) {
+    let mut group = c.benchmark_group("demibuffer/alloc");
+    for size in SIZES {
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, size| {
+            b.iter(|| black_box(DemiBuffer::new(*size)));
+        });
+    }
+    group.finish();
+}
+
+/// Allocates buffers and copies data into them, as done when applications push data.
+fn bench_from_slice(c: &mut Criterion) {
+    let mut group = c.benchmark_group("demibuffer/from_slice");
+    for size in SIZES {
+        let data: Vec<u8> = vec![0xab; size as usize];
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &data, |b, data| {
+            b.iter(|| black_box(DemiBuffer::from_slice(data).unwrap()));
+        });
+    }
+    group.finish();
+}
+
+/// Clones and drops buffers, which shares their data instead of copying it.
+fn bench_clone(c: &mut Criterion) {
+    let mut group = c.benchmark_group("demibuffer/clone");
+    for size in SIZES {
+        let buf: DemiBuffer = DemiBuffer::new(size);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &buf, |b, buf| {
+            b.iter(|| black_box(buf.clone()));
+        });
+    }
+    group.finish();
+}
+
+/// Strips the headers off the front of buffers and trims their tail, as done on the receive path.
+fn bench_adjust_trim(c: &mut Criterion) {
+    let mut group = c.benchmark_group("demibuffer/adjust_trim");
+    for size in SIZES {
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, size| {
+            b.iter_batched(
+                || DemiBuffer::new(*size),
+                |mut buf| {
+                    buf.adjust(HEADERS_SIZE).unwrap();
+                    buf.trim(4).unwrap();
+                    buf
+                },
+                BatchSize::SmallInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_alloc, bench_from_slice, bench_clone, bench_adjust_trim);
+criterion_main!(benches);
@@ -0,0 +1,232 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Benchmarks for parsing and serializing the headers of the network stack and for computing checksums.
+
+#[cfg(any(
+    feature = "catnap-libos",
+    feature = "catnip-libos",
+    feature = "catpowder-libos",
+    feature = "catloop-libos"
+))]
+mod bench {
+
+    //==================================================================================================================
+    // Imports
+    //==================================================================================================================
+
+    use ::criterion::{
+        black_box,
+        BatchSize,
+        BenchmarkId,
+        Criterion,
+        Throughput,
+    };
+    use ::demikernel::{
+        inetstack::protocols::{
+            checksum,
+            compute_generic_checksum,
+            ethernet2::{
+                EtherType2,
+                Ethernet2Header,
+                ETHERNET2_HEADER_SIZE,
+            },
+            fold16,
+            ip::IpProtocol,
+            ipv4::{
+                Ipv4Header,
+                IPV4_HEADER_MIN_SIZE,
+            },
+            tcp::{
+                segment::TcpHeader,
+                MIN_TCP_HEADER_SIZE,
+            },
+            udp::UdpHeader,
+        },
+        runtime::memory::DemiBuffer,
+        MacAddress,
+    };
+    use ::std::net::Ipv4Addr;
+
+    //==================================================================================================================
+    // Constants
+    //==================================================================================================================
+
+    const ALICE_MAC: MacAddress = MacAddress::new([0x12, 0x23, 0x45, 0x67, 0x89, 0xab]);
+    const BOB_MAC: MacAddress = MacAddress::new([0xab, 0x89, 0x67, 0x45, 0x23, 0x12]);
+    const ALICE_IPV4: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 1);
+    const BOB_IPV4: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 2);
+
+    /// Size of UDP headers.
+    const UDP_HEADER_SIZE: usize = 8;
+
+    /// Sizes of payloads: a small request, a full TCP segment and a jumbo frame.
+    const PAYLOAD_SIZES: [usize; 3] = [64, 1460, 8960];
+
+    /// Parsers consume clones of a buffer, which are made ahead of each batch. Batches are kept small, since the
+    /// reference count of a buffer is 16 bits wide and wraps around.
+    const CLONES_PER_BATCH: BatchSize = BatchSize::NumIterations(1024);
+
+    //==================================================================================================================
+    // Standalone Functions
+    //==================================================================================================================
+
+    /// Builds a segment of [protocol] that carries [payload_len] bytes, and returns the IPv4 header and the segment.
+    fn build_segment(protocol: IpProtocol, payload_len: usize) -> (Ipv4Header, Vec<u8>) {
+        let ipv4_hdr: Ipv4Header = Ipv4Header::new(ALICE_IPV4, BOB_IPV4, protocol);
+        let data: Vec<u8> = (0..payload_len).map(|i| i as u8).collect();
+        let header_size: usize = match protocol {
+            IpProtocol::TCP => MIN_TCP_HEADER_SIZE,
+            _ => UDP_HEADER_SIZE,
+        };
+        let mut segment: Vec<u8> = vec![0; header_size + payload_len];
+        match protocol {
+            IpProtocol::TCP => {
+                let mut tcp_hdr: TcpHeader = TcpHeader::new(49152, 80);
+                tcp_hdr.ack = true;
+                tcp_hdr.window_size = 65535;
+                tcp_hdr.serialize(&mut segment[..header_size], &ipv4_hdr, &data, false);
+            },
+            _ => UdpHeader::new(49152, 80).serialize(&mut segment[..header_size], &ipv4_hdr, &data, false),
+        }
+        segment[header_size..].copy_from_slice(&data);
+        (ipv4_hdr, segment)
+    }
+
+    /// Builds an Ethernet frame that carries [segment] after [ipv4_hdr].
+    fn build_frame(ipv4_hdr: &Ipv4Header, segment: &[u8]) -> Vec<u8> {
+        let mut frame: Vec<u8> = vec![0; ETHERNET2_HEADER_SIZE + IPV4_HEADER_MIN_SIZE as usize + segment.len()];
+        let (eth_buf, rest): (&mut [u8], &mut [u8]) = frame.split_at_mut(ETHERNET2_HEADER_SIZE);
+        Ethernet2Header::new(BOB_MAC, ALICE_MAC, EtherType2::Ipv4).serialize(eth_buf);
+        let (ipv4_buf, segment_buf): (&mut [u8], &mut [u8]) = rest.split_at_mut(IPV4_HEADER_MIN_SIZE as usize);
+        ipv4_hdr.serialize(ipv4_buf, segment.len());
+        segment_buf.copy_from_slice(segment);
+        frame
+    }
+
+    //==================================================================================================================
+    // Benchmarks
+    //==================================================================================================================
+
+    /// Parses the Ethernet and IPv4 headers of frames.
+    pub fn bench_link_network(c: &mut Criterion) {
+        let (ipv4_hdr, segment): (Ipv4Header, Vec<u8>) = build_segment(IpProtocol::UDP, PAYLOAD_SIZES[0]);
+        let frame: DemiBuffer = DemiBuffer::from_slice(&build_frame(&ipv4_hdr, &segment)).unwrap();
+        let mut group = c.benchmark_group("headers");
+        group.bench_function("ethernet2/parse", |b| {
+            b.iter_batched(
+                || frame.clone(),
+                |buf| black_box(Ethernet2Header::parse(buf).unwrap()),
+                CLONES_PER_BATCH,
+            )
+        });
+        group.bench_function("ethernet2/serialize", |b| {
+            let header: Ethernet2Header = Ethernet2Header::new(BOB_MAC, ALICE_MAC, EtherType2::Ipv4);
+            let mut buf: [u8; ETHERNET2_HEADER_SIZE] = [0; ETHERNET2_HEADER_SIZE];
+            b.iter(|| header.serialize(black_box(&mut buf)))
+        });
+        let (_, datagram): (Ethernet2Header, DemiBuffer) = Ethernet2Header::parse(frame.clone()).unwrap();
+        group.bench_function("ipv4/parse", |b| {
+            b.iter_batched(
+                || datagram.clone(),
+                |buf| black_box(Ipv4Header::parse(buf).unwrap()),
+                CLONES_PER_BATCH,
+            )
+        });
+        group.bench_function("ipv4/serialize", |b| {
+            let mut buf: [u8; IPV4_HEADER_MIN_SIZE as usize] = [0; IPV4_HEADER_MIN_SIZE as usize];
+            b.iter(|| ipv4_hdr.serialize(black_box(&mut buf), segment.len()))
+        });
+        group.finish();
+    }
+
+    /// Parses and serializes the headers of UDP datagrams and TCP segments, checksum included.
+    pub fn bench_transport(c: &mut Criterion) {
+        for (name, protocol) in [("udp", IpProtocol::UDP), ("tcp", IpProtocol::TCP)] {
+            let mut group = c.benchmark_group(format!("headers/{}", name));
+            for payload_len in PAYLOAD_SIZES {
+                let (ipv4_hdr, segment): (Ipv4Header, Vec<u8>) = build_segment(protocol, payload_len);
+                let buf: DemiBuffer = DemiBuffer::from_slice(&segment).unwrap();
+                group.throughput(Throughput::Bytes(segment.len() as u64));
+                group.bench_with_input(BenchmarkId::new("parse", payload_len), &buf, |b, buf| match protocol {
+                    IpProtocol::TCP => b.iter_batched(
+                        || buf.clone(),
+                        |buf| black_box(TcpHeader::parse(&ipv4_hdr, buf, false).unwrap()),
+                        CLONES_PER_BATCH,
+                    ),
+                    _ => b.iter_batched(
+                        || buf.clone(),
+                        |buf| black_box(UdpHeader::parse(&ipv4_hdr, buf, false).unwrap()),
+                        CLONES_PER_BATCH,
+                    ),
+                });
+                let (header, data): (&[u8], &[u8]) = match protocol {
+                    IpProtocol::TCP => segment.split_at(MIN_TCP_HEADER_SIZE),
+                    _ => segment.split_at(UDP_HEADER_SIZE),
+                };
+                let mut header: Vec<u8> = header.to_vec();
+                group.bench_with_input(
+                    BenchmarkId::new("serialize", payload_len),
+                    &data,
+                    |b, data| match protocol {
+                        IpProtocol::TCP => {
+                            let tcp_hdr: TcpHeader = TcpHeader::new(49152, 80);
+                            b.iter(|| tcp_hdr.serialize(black_box(&mut header), &ipv4_hdr, data, false))
+                        },
+                        _ => {
+                            let udp_hdr: UdpHeader = UdpHeader::new(49152, 80);
+                            b.iter(|| udp_hdr.serialize(black_box(&mut header), &ipv4_hdr, data, false))
+                        },
+                    },
+                );
+            }
+            group.finish();
+        }
+    }
+
+    /// Computes the Internet checksum of payloads, with the widest instruction set of the CPU and with the scalar
+    /// fallback.
+    pub fn bench_checksum(c: &mut Criterion) {
+        let mut group = c.benchmark_group("checksum");
+        for payload_len in PAYLOAD_SIZES {
+            let data: Vec<u8> = (0..payload_len).map(|i| i as u8).collect();
+            group.throughput(Throughput::Bytes(payload_len as u64));
+            group.bench_with_input(BenchmarkId::new("simd", payload_len), &data, |b, data| {
+                b.iter(|| black_box(fold16(compute_generic_checksum(black_box(data), None))))
+            });
+            group.bench_with_input(BenchmarkId::new("scalar", payload_len), &data, |b, data| {
+                b.iter(|| black_box(fold16(0xffff + checksum::sum_scalar(black_box(data)))))
+            });
+        }
+        group.finish();
+    }
+}
+
+#[cfg(any(
+    feature = "catnap-libos",
+    feature = "catnip-libos",
+    feature = "catpowder-libos",
+    feature = "catloop-libos"
+))]
+criterion::criterion_group!(
+    benches,
+    bench::bench_link_network,
+    bench::bench_transport,
+    bench::bench_checksum
+);
+#[cfg(any(
+    feature = "catnap-libos",
+    feature = "catnip-libos",
+    feature = "catpowder-libos",
+    feature = "catloop-libos"
+))]
+criterion::criterion_main!(benches);
+
+/// The network stack is not built without a LibOS that uses it.
+#[cfg(not(any(
+    feature = "catnap-libos",
+    feature = "catnip-libos",
+    feature = "catpowder-libos",
+    feature = "catloop-libos"
+)))]
+fn main() {}
@@ -0,0 +1,93 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Benchmarks for inserting, removing and polling the tasks of the scheduler, which runs every coroutine.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use ::criterion::{
+    black_box,
+    criterion_group,
+    criterion_main,
+    BenchmarkId,
+    Criterion,
+    Throughput,
+};
+use ::demikernel::runtime::scheduler::{
+    scheduler::Scheduler,
+    TaskId,
+    TaskWithResult,
+};
+use ::futures::FutureExt;
+use ::std::{
+    future::Future,
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Numbers of tasks that are ready to run.
+const NUM_TASKS: [usize; 3] = [1, 64, 1024];
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Coroutine that never completes and wakes itself whenever it runs, so that it is ready every time the scheduler
+/// polls, like a coroutine that polls a device.
+struct Spin;
+
+impl Future for Spin {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+fn spin_task() -> TaskWithResult<()> {
+    TaskWithResult::<()>::new("bench::spin", Box::pin(Spin.fuse()))
+}
+
+//======================================================================================================================
+// Benchmarks
+//======================================================================================================================
+
+/// Inserts a task and removes it.
+fn bench_insert_remove(c: &mut Criterion) {
+    let mut scheduler: Scheduler = Scheduler::default();
+    c.bench_function("scheduler/insert_remove", |b| {
+        b.iter(|| {
+            let task_id: TaskId = scheduler.insert_task(spin_task()).unwrap();
+            black_box(scheduler.remove_task(task_id))
+        })
+    });
+}
+
+/// Polls every task once, when all of them are ready.
+fn bench_poll_all(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scheduler/poll_all");
+    for num_tasks in NUM_TASKS {
+        let mut scheduler: Scheduler = Scheduler::default();
+        for _ in 0..num_tasks {
+            scheduler.insert_task(spin_task()).unwrap();
+        }
+        group.throughput(Throughput::Elements(num_tasks as u64));
+        group.bench_function(BenchmarkId::from_parameter(num_tasks), |b| {
+            b.iter(|| black_box(scheduler.poll_all()))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_insert_remove, bench_poll_all);
+criterion_main!(benches);
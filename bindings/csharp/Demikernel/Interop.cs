@@ -0,0 +1,164 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+// Raw P/Invoke declarations for the C API declared in include/demi/*.h. Applications should use the safe wrappers in
+// LibOS.cs instead.
+
+using System;
+using System.Runtime.InteropServices;
+
+namespace Demikernel
+{
+    //==================================================================================================================
+    // Structures
+    //==================================================================================================================
+
+    public enum Opcode : int
+    {
+        Invalid = 0,
+        Push,
+        Pop,
+        Accept,
+        Connect,
+        Close,
+        Failed,
+        Task,
+    }
+
+    [StructLayout(LayoutKind.Sequential, Pack = 1)]
+    public struct SockAddrIn
+    {
+        public ushort Family;
+        /// Port, in network byte order.
+        public ushort Port;
+        /// IPv4 address, in network byte order.
+        public uint Addr;
+        public ulong Zero;
+    }
+
+    [StructLayout(LayoutKind.Sequential, Pack = 1)]
+    public struct SgaSeg
+    {
+        public IntPtr Buf;
+        public uint Len;
+    }
+
+    /// Flags that tell which fields of Ancillary are valid.
+    [Flags]
+    public enum AncillaryFlags : uint
+    {
+        DstAddr = 1 << 0,
+        Ttl = 1 << 1,
+        Tos = 1 << 2,
+        Timestamp = 1 << 3,
+        HwTimestamp = 1 << 4,
+    }
+
+    [StructLayout(LayoutKind.Sequential, Pack = 1)]
+    public struct Ancillary
+    {
+        public AncillaryFlags Flags;
+        /// Destination IPv4 address, in network byte order.
+        public uint DstAddr;
+        /// Receive time, in nanoseconds since the Unix epoch.
+        public ulong TimestampNs;
+        /// Receive time, in nanoseconds of the clock of the device.
+        public ulong HwTimestampNs;
+        public byte Ttl;
+        public byte Tos;
+        private ushort reserved0;
+        private uint reserved1;
+    }
+
+    [StructLayout(LayoutKind.Sequential, Pack = 1)]
+    public struct RawSgArray
+    {
+        public IntPtr Buf;
+        public uint NumSegs;
+        public SgaSeg Seg0;
+        public SockAddrIn Addr;
+        public Ancillary Ancillary;
+    }
+
+    [StructLayout(LayoutKind.Sequential, Pack = 1)]
+    public struct AcceptResult
+    {
+        public int Qd;
+        public SockAddrIn Addr;
+    }
+
+    [StructLayout(LayoutKind.Explicit, Pack = 1)]
+    public struct RawQResult
+    {
+        [FieldOffset(0)] public Opcode Opcode;
+        [FieldOffset(4)] public int Qd;
+        [FieldOffset(8)] public ulong Qt;
+        [FieldOffset(16)] public long Ret;
+        [FieldOffset(24)] public RawSgArray Sga;
+        [FieldOffset(24)] public AcceptResult Ares;
+    }
+
+    [StructLayout(LayoutKind.Sequential)]
+    public struct TimeSpec
+    {
+        public long Sec;
+        public long Nsec;
+    }
+
+    //==================================================================================================================
+    // Functions
+    //==================================================================================================================
+
+    public static unsafe class Interop
+    {
+        private const string Library = "demikernel";
+
+        [DllImport(Library, EntryPoint = "demi_init")]
+        public static extern int Init(int argc, IntPtr[] argv);
+
+        [DllImport(Library, EntryPoint = "demi_socket")]
+        public static extern int Socket(out int qd, int domain, int type, int protocol);
+
+        [DllImport(Library, EntryPoint = "demi_listen")]
+        public static extern int Listen(int qd, int backlog);
+
+        [DllImport(Library, EntryPoint = "demi_bind")]
+        public static extern int Bind(int qd, in SockAddrIn addr, uint size);
+
+        [DllImport(Library, EntryPoint = "demi_accept")]
+        public static extern int Accept(out ulong qt, int qd);
+
+        [DllImport(Library, EntryPoint = "demi_connect")]
+        public static extern int Connect(out ulong qt, int qd, in SockAddrIn addr, uint size);
+
+        [DllImport(Library, EntryPoint = "demi_close")]
+        public static extern int Close(int qd);
+
+        [DllImport(Library, EntryPoint = "demi_push")]
+        public static extern int Push(out ulong qt, int qd, in RawSgArray sga);
+
+        [DllImport(Library, EntryPoint = "demi_pushto")]
+        public static extern int PushTo(out ulong qt, int qd, in RawSgArray sga, in SockAddrIn addr, uint size);
+
+        [DllImport(Library, EntryPoint = "demi_pop")]
+        public static extern int Pop(out ulong qt, int qd);
+
+        [DllImport(Library, EntryPoint = "demi_wait")]
+        public static extern int Wait(out RawQResult qr, ulong qt, TimeSpec* timeout);
+
+        [DllImport(Library, EntryPoint = "demi_wait_any")]
+        public static extern int WaitAny(out RawQResult qr, out int offset, ulong[] qts, int numQts, TimeSpec* timeout);
+
+        [DllImport(Library, EntryPoint = "demi_cancel")]
+        public static extern int Cancel(ulong qt);
+
+        [DllImport(Library, EntryPoint = "demi_get_eventfd")]
+        public static extern int GetEventFd(out int fd);
+
+        [DllImport(Library, EntryPoint = "demi_sgaalloc")]
+        public static extern RawSgArray SgaAlloc(UIntPtr size);
+
+        [DllImport(Library, EntryPoint = "demi_sgafree")]
+        public static extern int SgaFree(ref RawSgArray sga);
+    }
+}
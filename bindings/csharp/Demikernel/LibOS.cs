@@ -0,0 +1,410 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+// Safe wrappers around the Demikernel C API. Scatter-gather arrays and queue tokens tie the lifetime of the underlying
+// memory to the operations that use it:
+//
+// - An SgArray that was handed to a push is kept alive until that push completes, and it cannot be disposed while the
+//   push is in flight.
+// - A QToken can be waited on at most once. The SgArray that was popped (if any) is owned by the returned QResult.
+
+using System;
+using System.Net;
+using System.Net.Sockets;
+using System.Runtime.InteropServices;
+
+namespace Demikernel
+{
+    //==================================================================================================================
+    // Exceptions
+    //==================================================================================================================
+
+    public sealed class DemikernelException : Exception
+    {
+        public int Errno { get; }
+
+        public DemikernelException(int errno, string message) : base($"{message} (errno={errno})")
+        {
+            Errno = errno;
+        }
+
+        internal static void Check(int ret, string what)
+        {
+            if (ret != 0)
+            {
+                throw new DemikernelException(ret, $"{what}() failed");
+            }
+        }
+    }
+
+    //==================================================================================================================
+    // Scatter-Gather Arrays
+    //==================================================================================================================
+
+    /// Scatter-gather array allocated by Demikernel. It is released on Dispose() or by the finalizer, unless a push
+    /// that uses it is still in flight.
+    public sealed class SgArray : IDisposable
+    {
+        private RawSgArray sga;
+        private int inFlight;
+        private bool freed;
+
+        internal SgArray(RawSgArray sga)
+        {
+            this.sga = sga;
+        }
+
+        /// Allocates a scatter-gather array that holds [size] bytes.
+        public static SgArray Alloc(int size)
+        {
+            RawSgArray sga = Interop.SgaAlloc((UIntPtr)size);
+            if (sga.NumSegs == 0)
+            {
+                throw new DemikernelException(12, "demi_sgaalloc() failed");
+            }
+            return new SgArray(sga);
+        }
+
+        /// Allocates a scatter-gather array and copies [data] into it.
+        public static SgArray FromBytes(ReadOnlySpan<byte> data)
+        {
+            SgArray sga = Alloc(data.Length);
+            data.CopyTo(sga.AsSpan());
+            return sga;
+        }
+
+        public int Length
+        {
+            get
+            {
+                CheckAlive();
+                return (int)sga.Seg0.Len;
+            }
+        }
+
+        /// Returns a view of the array. The view must not outlive the array.
+        public unsafe Span<byte> AsSpan()
+        {
+            CheckAlive();
+            return new Span<byte>((void*)sga.Seg0.Buf, (int)sga.Seg0.Len);
+        }
+
+        /// Copies the contents of the array out of Demikernel memory.
+        public byte[] ToArray() => AsSpan().ToArray();
+
+        /// Address that this array was received from, for arrays that were popped from UDP sockets.
+        public IPEndPoint Source
+        {
+            get
+            {
+                CheckAlive();
+                return LibOS.FromSockAddr(sga.Addr);
+            }
+        }
+
+        /// Ancillary data (destination address, TTL, TOS and receive timestamp) of arrays that were popped from UDP
+        /// sockets. Only the fields that are set in its flags are valid.
+        public Ancillary Ancillary
+        {
+            get
+            {
+                CheckAlive();
+                return sga.Ancillary;
+            }
+        }
+
+        internal ref readonly RawSgArray Raw
+        {
+            get
+            {
+                CheckAlive();
+                return ref sga;
+            }
+        }
+
+        internal void Acquire() => inFlight++;
+
+        internal void Release() => inFlight--;
+
+        public void Dispose()
+        {
+            if (freed)
+            {
+                return;
+            }
+            if (inFlight > 0)
+            {
+                throw new DemikernelException(16, "cannot free a scatter-gather array while a push is in flight");
+            }
+            freed = true;
+            DemikernelException.Check(Interop.SgaFree(ref sga), "demi_sgafree");
+            GC.SuppressFinalize(this);
+        }
+
+        ~SgArray()
+        {
+            // In-flight arrays are referenced by their token, so they never get here.
+            if (!freed)
+            {
+                freed = true;
+                Interop.SgaFree(ref sga);
+            }
+        }
+
+        private void CheckAlive()
+        {
+            if (freed)
+            {
+                throw new ObjectDisposedException(nameof(SgArray));
+            }
+        }
+    }
+
+    //==================================================================================================================
+    // Queue Results
+    //==================================================================================================================
+
+    /// Result of a completed operation.
+    public sealed class QResult
+    {
+        public Opcode Opcode { get; }
+        public int Qd { get; }
+        public ulong Qt { get; }
+        public long Ret { get; }
+        /// Popped data, owned by this result.
+        public SgArray? Sga { get; }
+        /// Queue descriptor of an accepted connection.
+        public int AcceptedQd { get; }
+        /// Address of an accepted connection.
+        public IPEndPoint? AcceptedAddr { get; }
+
+        internal QResult(in RawQResult qr)
+        {
+            Opcode = qr.Opcode;
+            Qd = qr.Qd;
+            Qt = qr.Qt;
+            Ret = qr.Ret;
+            if (Opcode == Opcode.Pop)
+            {
+                Sga = new SgArray(qr.Sga);
+            }
+            else if (Opcode == Opcode.Accept)
+            {
+                AcceptedQd = qr.Ares.Qd;
+                AcceptedAddr = LibOS.FromSockAddr(qr.Ares.Addr);
+            }
+        }
+
+        /// Throws if the operation failed, otherwise returns this result.
+        public QResult Check()
+        {
+            if (Opcode == Opcode.Failed)
+            {
+                throw new DemikernelException((int)Ret, "operation failed");
+            }
+            return this;
+        }
+    }
+
+    //==================================================================================================================
+    // Queue Tokens
+    //==================================================================================================================
+
+    /// Token of an asynchronous operation. It can be waited on at most once, and it keeps the memory that the operation
+    /// uses alive until the operation completes.
+    public sealed class QToken
+    {
+        public ulong Qt { get; }
+        private SgArray? keepalive;
+        private bool done;
+
+        internal QToken(ulong qt, SgArray? keepalive = null)
+        {
+            Qt = qt;
+            this.keepalive = keepalive;
+            keepalive?.Acquire();
+        }
+
+        /// Waits for the operation to complete, for at most [timeout] if one is given.
+        public unsafe QResult Wait(TimeSpan? timeout = null)
+        {
+            CheckPending();
+            TimeSpec ts = LibOS.ToTimeSpec(timeout ?? TimeSpan.Zero);
+            DemikernelException.Check(
+                Interop.Wait(out RawQResult qr, Qt, timeout.HasValue ? &ts : null), "demi_wait");
+            Complete();
+            return new QResult(qr);
+        }
+
+        /// Cancels the operation. It still has to be waited on to collect its result.
+        public void Cancel()
+        {
+            CheckPending();
+            DemikernelException.Check(Interop.Cancel(Qt), "demi_cancel");
+        }
+
+        /// Waits for the first of [qts] to complete and returns its offset along with its result.
+        public static unsafe (int, QResult) WaitAny(QToken[] qts, TimeSpan? timeout = null)
+        {
+            ulong[] raw = new ulong[qts.Length];
+            for (int i = 0; i < qts.Length; i++)
+            {
+                qts[i].CheckPending();
+                raw[i] = qts[i].Qt;
+            }
+            TimeSpec ts = LibOS.ToTimeSpec(timeout ?? TimeSpan.Zero);
+            DemikernelException.Check(
+                Interop.WaitAny(out RawQResult qr, out int offset, raw, raw.Length, timeout.HasValue ? &ts : null),
+                "demi_wait_any");
+            qts[offset].Complete();
+            return (offset, new QResult(qr));
+        }
+
+        private void CheckPending()
+        {
+            if (done)
+            {
+                throw new InvalidOperationException("queue token was already waited on");
+            }
+        }
+
+        private void Complete()
+        {
+            done = true;
+            keepalive?.Release();
+            keepalive = null;
+        }
+    }
+
+    //==================================================================================================================
+    // LibOS
+    //==================================================================================================================
+
+    public static class LibOS
+    {
+        /// Initializes Demikernel. This must be called once, before any other function.
+        public static void Init(string[]? args = null)
+        {
+            args ??= new[] { "demikernel" };
+            IntPtr[] argv = new IntPtr[args.Length];
+            try
+            {
+                for (int i = 0; i < args.Length; i++)
+                {
+                    argv[i] = Marshal.StringToCoTaskMemUTF8(args[i]);
+                }
+                DemikernelException.Check(Interop.Init(args.Length, argv), "demi_init");
+            }
+            finally
+            {
+                foreach (IntPtr arg in argv)
+                {
+                    Marshal.FreeCoTaskMem(arg);
+                }
+            }
+        }
+
+        /// Returns a file descriptor that becomes readable when Demikernel has work to do.
+        public static int GetEventFd()
+        {
+            DemikernelException.Check(Interop.GetEventFd(out int fd), "demi_get_eventfd");
+            return fd;
+        }
+
+        /// Creates a socket and returns its queue descriptor.
+        public static int Socket(AddressFamily domain = AddressFamily.InterNetwork, SocketType type = SocketType.Stream)
+        {
+            // Socket types follow the Linux numbering on every platform.
+            int rawType = type == SocketType.Stream ? 1 : 2;
+            DemikernelException.Check(Interop.Socket(out int qd, 2, rawType, 0), "demi_socket");
+            return qd;
+        }
+
+        /// Binds a socket to a local address.
+        public static void Bind(int qd, IPEndPoint addr)
+        {
+            SockAddrIn saddr = ToSockAddr(addr);
+            DemikernelException.Check(Interop.Bind(qd, saddr, (uint)Marshal.SizeOf<SockAddrIn>()), "demi_bind");
+        }
+
+        /// Marks a socket as passive.
+        public static void Listen(int qd, int backlog = 16)
+        {
+            DemikernelException.Check(Interop.Listen(qd, backlog), "demi_listen");
+        }
+
+        /// Starts accepting a connection on a passive socket.
+        public static QToken Accept(int qd)
+        {
+            DemikernelException.Check(Interop.Accept(out ulong qt, qd), "demi_accept");
+            return new QToken(qt);
+        }
+
+        /// Starts connecting a socket to a remote address.
+        public static QToken Connect(int qd, IPEndPoint addr)
+        {
+            SockAddrIn saddr = ToSockAddr(addr);
+            DemikernelException.Check(
+                Interop.Connect(out ulong qt, qd, saddr, (uint)Marshal.SizeOf<SockAddrIn>()), "demi_connect");
+            return new QToken(qt);
+        }
+
+        /// Closes a queue descriptor.
+        public static void Close(int qd)
+        {
+            DemikernelException.Check(Interop.Close(qd), "demi_close");
+        }
+
+        /// Starts pushing a scatter-gather array. The array stays alive until the returned token completes.
+        public static QToken Push(int qd, SgArray sga)
+        {
+            DemikernelException.Check(Interop.Push(out ulong qt, qd, sga.Raw), "demi_push");
+            return new QToken(qt, sga);
+        }
+
+        /// Starts pushing a scatter-gather array to a remote address. The array stays alive until the token completes.
+        public static QToken PushTo(int qd, SgArray sga, IPEndPoint addr)
+        {
+            SockAddrIn saddr = ToSockAddr(addr);
+            DemikernelException.Check(
+                Interop.PushTo(out ulong qt, qd, sga.Raw, saddr, (uint)Marshal.SizeOf<SockAddrIn>()), "demi_pushto");
+            return new QToken(qt, sga);
+        }
+
+        /// Starts popping data. The popped scatter-gather array is owned by the result of the returned token.
+        public static QToken Pop(int qd)
+        {
+            DemikernelException.Check(Interop.Pop(out ulong qt, qd), "demi_pop");
+            return new QToken(qt);
+        }
+
+        internal static SockAddrIn ToSockAddr(IPEndPoint addr)
+        {
+            if (addr.AddressFamily != AddressFamily.InterNetwork)
+            {
+                throw new ArgumentException("only IPv4 addresses are supported", nameof(addr));
+            }
+            return new SockAddrIn
+            {
+                Family = 2,
+                Port = (ushort)IPAddress.HostToNetworkOrder((short)addr.Port),
+                Addr = BitConverter.ToUInt32(addr.Address.GetAddressBytes(), 0),
+            };
+        }
+
+        internal static IPEndPoint FromSockAddr(in SockAddrIn saddr)
+        {
+            return new IPEndPoint(new IPAddress(saddr.Addr), (ushort)IPAddress.NetworkToHostOrder((short)saddr.Port));
+        }
+
+        internal static TimeSpec ToTimeSpec(TimeSpan timeout)
+        {
+            long ticks = timeout.Ticks;
+            return new TimeSpec
+            {
+                Sec = ticks / TimeSpan.TicksPerSecond,
+                Nsec = (ticks % TimeSpan.TicksPerSecond) * 100,
+            };
+        }
+    }
+}
@@ -0,0 +1,451 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT license.
+
+"""
+Python bindings for Demikernel.
+
+This module loads the Demikernel shared library through cffi (ABI mode) and wraps the C API declared in
+include/demi/*.h. Scatter-gather arrays and queue tokens are wrapped in objects that tie the lifetime of the
+underlying memory to the operations that use it:
+
+- An SgArray that was handed to a push is kept alive until that push completes, even if the application drops
+  every reference to it, and it cannot be freed while the push is in flight.
+- A QToken can be waited on at most once. Once it completes, the SgArray that was popped (if any) is owned by the
+  returned QResult and is released when that object is garbage collected or explicitly freed.
+
+The shared library is looked up as "libdemikernel.so", unless the DEMIKERNEL_LIB environment variable points
+elsewhere.
+"""
+
+# ======================================================================================================================
+# Imports
+# ======================================================================================================================
+
+import ipaddress
+import os
+import socket as _socket
+from typing import Dict, List, Optional, Tuple
+
+from cffi import FFI
+
+# ======================================================================================================================
+# C ABI
+# ======================================================================================================================
+
+_CDEF: str = """
+struct in_addr {
+    uint32_t s_addr;
+};
+
+struct sockaddr_in {
+    uint16_t sin_family;
+    uint16_t sin_port;
+    struct in_addr sin_addr;
+    uint8_t sin_zero[8];
+};
+
+struct sockaddr;
+typedef uint32_t socklen_t;
+
+struct timespec {
+    long tv_sec;
+    long tv_nsec;
+};
+
+typedef uint64_t demi_qtoken_t;
+
+typedef struct demi_sgaseg {
+    void *sgaseg_buf;
+    uint32_t sgaseg_len;
+} demi_sgaseg_t;
+
+typedef struct demi_ancillary {
+    uint32_t flags;
+    struct in_addr dst_addr;
+    uint64_t timestamp_ns;
+    uint64_t hw_timestamp_ns;
+    uint8_t ttl;
+    uint8_t tos;
+    uint8_t reserved[6];
+} demi_ancillary_t;
+
+typedef struct demi_sgarray {
+    void *sga_buf;
+    uint32_t sga_numsegs;
+    demi_sgaseg_t sga_segs[1];
+    struct sockaddr_in sga_addr;
+    demi_ancillary_t sga_ancillary;
+} demi_sgarray_t;
+
+typedef enum demi_opcode {
+    DEMI_OPC_INVALID = 0,
+    DEMI_OPC_PUSH,
+    DEMI_OPC_POP,
+    DEMI_OPC_ACCEPT,
+    DEMI_OPC_CONNECT,
+    DEMI_OPC_CLOSE,
+    DEMI_OPC_FAILED,
+    DEMI_OPC_TASK,
+    DEMI_OPC_SPLICE,
+} demi_opcode_t;
+
+typedef struct demi_accept_result {
+    int32_t qd;
+    struct sockaddr_in addr;
+} demi_accept_result_t;
+
+typedef struct demi_qresult {
+    demi_opcode_t qr_opcode;
+    int32_t qr_qd;
+    demi_qtoken_t qr_qt;
+    int64_t qr_ret;
+    union {
+        demi_sgarray_t sga;
+        demi_accept_result_t ares;
+    } qr_value;
+} demi_qresult_t;
+
+int demi_init(int argc, char *const argv[]);
+int demi_socket(int *sockqd_out, int domain, int type, int protocol);
+int demi_listen(int sockqd, int backlog);
+int demi_bind(int sockqd, const struct sockaddr *addr, socklen_t size);
+int demi_accept(demi_qtoken_t *qt_out, int sockqd);
+int demi_connect(demi_qtoken_t *qt_out, int sockqd, const struct sockaddr *addr, socklen_t size);
+int demi_close(int qd);
+int demi_push(demi_qtoken_t *qt_out, int qd, const demi_sgarray_t *sga);
+int demi_pushto(demi_qtoken_t *qt_out, int sockqd, const demi_sgarray_t *sga, const struct sockaddr *dest_addr,
+                socklen_t size);
+int demi_pop(demi_qtoken_t *qt_out, int qd);
+int demi_wait(demi_qresult_t *qr_out, demi_qtoken_t qt, const struct timespec *timeout);
+int demi_wait_any(demi_qresult_t *qr_out, int *ready_offset, const demi_qtoken_t qts[], int num_qts,
+                  const struct timespec *timeout);
+int demi_cancel(demi_qtoken_t qt);
+int demi_get_eventfd(int *fd_out);
+demi_sgarray_t demi_sgaalloc(size_t size);
+int demi_sgafree(demi_sgarray_t *sga);
+"""
+
+ffi: FFI = FFI()
+# All Demikernel structures are packed.
+ffi.cdef(_CDEF, packed=True)
+
+# Handle to the shared library. It is opened lazily, so that importing this module does not require it.
+_lib_handle = None
+
+# ======================================================================================================================
+# Public Constants
+# ======================================================================================================================
+
+DEMI_OPC_INVALID: int = 0
+DEMI_OPC_PUSH: int = 1
+DEMI_OPC_POP: int = 2
+DEMI_OPC_ACCEPT: int = 3
+DEMI_OPC_CONNECT: int = 4
+DEMI_OPC_CLOSE: int = 5
+DEMI_OPC_FAILED: int = 6
+DEMI_OPC_TASK: int = 7
+DEMI_OPC_SPLICE: int = 8
+
+DEMI_ANCILLARY_DST_ADDR: int = 1 << 0
+DEMI_ANCILLARY_TTL: int = 1 << 1
+DEMI_ANCILLARY_TOS: int = 1 << 2
+DEMI_ANCILLARY_TIMESTAMP: int = 1 << 3
+DEMI_ANCILLARY_HW_TIMESTAMP: int = 1 << 4
+
+# ======================================================================================================================
+# Private Functions
+# ======================================================================================================================
+
+
+def _lib():
+    """Returns a handle to the Demikernel shared library, opening it on first use."""
+    global _lib_handle
+    if _lib_handle is None:
+        _lib_handle = ffi.dlopen(os.environ.get("DEMIKERNEL_LIB", "libdemikernel.so"))
+    return _lib_handle
+
+
+def _check(ret: int, what: str) -> None:
+    """Raises a DemikernelError if a C API call failed."""
+    if ret != 0:
+        raise DemikernelError(ret, f"{what}() failed: {os.strerror(ret)}")
+
+
+def _to_sockaddr(addr: Tuple[str, int]):
+    """Converts a (host, port) tuple into a struct sockaddr_in."""
+    host, port = addr
+    saddr = ffi.new("struct sockaddr_in *")
+    saddr.sin_family = _socket.AF_INET
+    saddr.sin_port = _socket.htons(port)
+    saddr.sin_addr.s_addr = _socket.htonl(int(ipaddress.IPv4Address(host)))
+    return saddr
+
+
+def _from_sockaddr(saddr) -> Tuple[str, int]:
+    """Converts a struct sockaddr_in into a (host, port) tuple."""
+    host: str = str(ipaddress.IPv4Address(_socket.ntohl(saddr.sin_addr.s_addr)))
+    return (host, _socket.ntohs(saddr.sin_port))
+
+
+def _to_timespec(timeout: Optional[float]):
+    """Converts a timeout in seconds into a struct timespec, or NULL if there is no timeout."""
+    if timeout is None:
+        return ffi.NULL
+    ts = ffi.new("struct timespec *")
+    ts.tv_sec = int(timeout)
+    ts.tv_nsec = int((timeout - int(timeout)) * 1_000_000_000)
+    return ts
+
+# ======================================================================================================================
+# Public Classes
+# ======================================================================================================================
+
+
+class DemikernelError(OSError):
+    """Error raised when a call into Demikernel fails. The errno attribute carries the error code."""
+
+
+class SgArray:
+    """
+    Scatter-gather array allocated by Demikernel.
+
+    The memory is released when free() is called, when the object is used as a context manager and goes out of
+    scope, or when it is garbage collected. It cannot be freed while a push that uses it is in flight.
+    """
+
+    def __init__(self, sga) -> None:
+        # Owned copy of the C structure.
+        self._sga = sga
+        # Number of in-flight operations that use this array.
+        self._in_flight: int = 0
+        self._freed: bool = False
+
+    @classmethod
+    def alloc(cls, size: int) -> "SgArray":
+        """Allocates a scatter-gather array that holds size bytes."""
+        sga = ffi.new("demi_sgarray_t *", _lib().demi_sgaalloc(size))
+        if sga.sga_numsegs == 0:
+            raise DemikernelError(12, "demi_sgaalloc() failed: cannot allocate memory")
+        return cls(sga)
+
+    @classmethod
+    def from_bytes(cls, data: bytes) -> "SgArray":
+        """Allocates a scatter-gather array and copies data into it."""
+        sga: SgArray = cls.alloc(len(data))
+        ffi.memmove(sga._sga.sga_segs[0].sgaseg_buf, data, len(data))
+        return sga
+
+    def __len__(self) -> int:
+        self._check_alive()
+        return sum(self._sga.sga_segs[i].sgaseg_len for i in range(self._sga.sga_numsegs))
+
+    def to_bytes(self) -> bytes:
+        """Copies the contents of the array out of Demikernel memory."""
+        self._check_alive()
+        return b"".join(ffi.buffer(self._sga.sga_segs[i].sgaseg_buf, self._sga.sga_segs[i].sgaseg_len)[:]
+                        for i in range(self._sga.sga_numsegs))
+
+    def source(self) -> Tuple[str, int]:
+        """Returns the address that this array was received from, for arrays that were popped from UDP sockets."""
+        self._check_alive()
+        return _from_sockaddr(self._sga.sga_addr)
+
+    def ancillary(self) -> Dict[str, object]:
+        """
+        Returns the ancillary data of an array that was popped from a UDP socket. Only the fields that the transport
+        knows are present: "dst_addr" (str), "ttl" (int), "tos" (int), "timestamp_ns" (int, since the Unix epoch) and
+        "hw_timestamp_ns" (int, on the clock of the device).
+        """
+        self._check_alive()
+        anc = self._sga.sga_ancillary
+        result: Dict[str, object] = {}
+        if anc.flags & DEMI_ANCILLARY_DST_ADDR:
+            result["dst_addr"] = str(ipaddress.IPv4Address(_socket.ntohl(anc.dst_addr.s_addr)))
+        if anc.flags & DEMI_ANCILLARY_TTL:
+            result["ttl"] = anc.ttl
+        if anc.flags & DEMI_ANCILLARY_TOS:
+            result["tos"] = anc.tos
+        if anc.flags & DEMI_ANCILLARY_TIMESTAMP:
+            result["timestamp_ns"] = anc.timestamp_ns
+        if anc.flags & DEMI_ANCILLARY_HW_TIMESTAMP:
+            result["hw_timestamp_ns"] = anc.hw_timestamp_ns
+        return result
+
+    def free(self) -> None:
+        """Releases the array. This is a no-op if it was already released."""
+        if self._freed:
+            return
+        if self._in_flight > 0:
+            raise DemikernelError(16, "cannot free a scatter-gather array while a push is in flight")
+        self._freed = True
+        _check(_lib().demi_sgafree(self._sga), "demi_sgafree")
+
+    def __enter__(self) -> "SgArray":
+        return self
+
+    def __exit__(self, *_) -> None:
+        self.free()
+
+    def __del__(self) -> None:
+        # In-flight arrays are referenced by their token, so they never get here.
+        if not self._freed and _lib_handle is not None:
+            self._freed = True
+            _lib_handle.demi_sgafree(self._sga)
+
+    def _check_alive(self) -> None:
+        if self._freed:
+            raise DemikernelError(9, "scatter-gather array was already freed")
+
+
+class QResult:
+    """Result of a completed operation."""
+
+    def __init__(self, qr) -> None:
+        self.opcode: int = qr.qr_opcode
+        self.qd: int = qr.qr_qd
+        self.qt: int = qr.qr_qt
+        self.ret: int = qr.qr_ret
+        # Popped data, owned by this result.
+        self.sga: Optional[SgArray] = None
+        # Queue descriptor and address of an accepted connection.
+        self.accepted: Optional[Tuple[int, Tuple[str, int]]] = None
+        if self.opcode == DEMI_OPC_POP:
+            self.sga = SgArray(ffi.new("demi_sgarray_t *", qr.qr_value.sga))
+        elif self.opcode == DEMI_OPC_ACCEPT:
+            self.accepted = (qr.qr_value.ares.qd, _from_sockaddr(qr.qr_value.ares.addr))
+
+    def check(self) -> "QResult":
+        """Raises a DemikernelError if the operation failed, otherwise returns this result."""
+        if self.opcode == DEMI_OPC_FAILED:
+            raise DemikernelError(self.ret, f"operation failed: {os.strerror(self.ret)}")
+        return self
+
+
+class QToken:
+    """
+    Token of an asynchronous operation. It can be waited on at most once, and it keeps the memory that the operation
+    uses alive until the operation completes.
+    """
+
+    def __init__(self, qt: int, keepalive: Optional[SgArray] = None) -> None:
+        self.qt: int = qt
+        self._keepalive: Optional[SgArray] = keepalive
+        self._done: bool = False
+        if keepalive is not None:
+            keepalive._in_flight += 1
+
+    def wait(self, timeout: Optional[float] = None) -> QResult:
+        """Waits for the operation to complete, for at most timeout seconds if one is given."""
+        self._check_pending()
+        qr = ffi.new("demi_qresult_t *")
+        _check(_lib().demi_wait(qr, self.qt, _to_timespec(timeout)), "demi_wait")
+        self._complete()
+        return QResult(qr)
+
+    def cancel(self) -> None:
+        """Cancels the operation. It still has to be waited on to collect its result."""
+        self._check_pending()
+        _check(_lib().demi_cancel(self.qt), "demi_cancel")
+
+    @staticmethod
+    def wait_any(qts: List["QToken"], timeout: Optional[float] = None) -> Tuple[int, QResult]:
+        """Waits for the first of qts to complete and returns its offset in the list along with its result."""
+        for qt in qts:
+            qt._check_pending()
+        c_qts = ffi.new("demi_qtoken_t[]", [qt.qt for qt in qts])
+        qr = ffi.new("demi_qresult_t *")
+        offset = ffi.new("int *")
+        _check(_lib().demi_wait_any(qr, offset, c_qts, len(qts), _to_timespec(timeout)), "demi_wait_any")
+        qts[offset[0]]._complete()
+        return (offset[0], QResult(qr))
+
+    def _check_pending(self) -> None:
+        if self._done:
+            raise DemikernelError(22, "queue token was already waited on")
+
+    def _complete(self) -> None:
+        self._done = True
+        if self._keepalive is not None:
+            self._keepalive._in_flight -= 1
+            self._keepalive = None
+
+# ======================================================================================================================
+# Public Functions
+# ======================================================================================================================
+
+
+def init(args: Optional[List[str]] = None) -> None:
+    """Initializes Demikernel. This must be called once, before any other function."""
+    argv_strings = [ffi.new("char[]", arg.encode()) for arg in (args or ["demikernel"])]
+    argv = ffi.new("char *[]", argv_strings)
+    _check(_lib().demi_init(len(argv_strings), argv), "demi_init")
+
+
+def get_eventfd() -> int:
+    """Returns a file descriptor that becomes readable when Demikernel has work to do."""
+    fd = ffi.new("int *")
+    _check(_lib().demi_get_eventfd(fd), "demi_get_eventfd")
+    return fd[0]
+
+
+def socket(domain: int = _socket.AF_INET, type: int = _socket.SOCK_STREAM, protocol: int = 0) -> int:
+    """Creates a socket and returns its queue descriptor."""
+    qd = ffi.new("int *")
+    _check(_lib().demi_socket(qd, domain, type, protocol), "demi_socket")
+    return qd[0]
+
+
+def bind(qd: int, addr: Tuple[str, int]) -> None:
+    """Binds a socket to a local address."""
+    saddr = _to_sockaddr(addr)
+    _check(_lib().demi_bind(qd, ffi.cast("struct sockaddr *", saddr), ffi.sizeof(saddr[0])), "demi_bind")
+
+
+def listen(qd: int, backlog: int = 16) -> None:
+    """Marks a socket as passive."""
+    _check(_lib().demi_listen(qd, backlog), "demi_listen")
+
+
+def accept(qd: int) -> QToken:
+    """Starts accepting a connection on a passive socket."""
+    qt = ffi.new("demi_qtoken_t *")
+    _check(_lib().demi_accept(qt, qd), "demi_accept")
+    return QToken(qt[0])
+
+
+def connect(qd: int, addr: Tuple[str, int]) -> QToken:
+    """Starts connecting a socket to a remote address."""
+    saddr = _to_sockaddr(addr)
+    qt = ffi.new("demi_qtoken_t *")
+    _check(_lib().demi_connect(qt, qd, ffi.cast("struct sockaddr *", saddr), ffi.sizeof(saddr[0])), "demi_connect")
+    return QToken(qt[0])
+
+
+def close(qd: int) -> None:
+    """Closes a queue descriptor."""
+    _check(_lib().demi_close(qd), "demi_close")
+
+
+def push(qd: int, sga: SgArray) -> QToken:
+    """Starts pushing a scatter-gather array. The array stays alive until the returned token completes."""
+    sga._check_alive()
+    qt = ffi.new("demi_qtoken_t *")
+    _check(_lib().demi_push(qt, qd, sga._sga), "demi_push")
+    return QToken(qt[0], sga)
+
+
+def pushto(qd: int, sga: SgArray, addr: Tuple[str, int]) -> QToken:
+    """Starts pushing a scatter-gather array to a remote address. The array stays alive until the token completes."""
+    sga._check_alive()
+    saddr = _to_sockaddr(addr)
+    qt = ffi.new("demi_qtoken_t *")
+    _check(_lib().demi_pushto(qt, qd, sga._sga, ffi.cast("struct sockaddr *", saddr), ffi.sizeof(saddr[0])),
+           "demi_pushto")
+    return QToken(qt[0], sga)
+
+
+def pop(qd: int) -> QToken:
+    """Starts popping data. The popped scatter-gather array is owned by the result of the returned token."""
+    qt = ffi.new("demi_qtoken_t *")
+    _check(_lib().demi_pop(qt, qd), "demi_pop")
+    return QToken(qt[0])
@@ -110,6 +110,7 @@ fn os_build() -> Result<()> {
         .allowlist_var("RTE_ETH_RX_OFFLOAD_UDP_CKSUM")
         .allowlist_var("RTE_ETH_MQ_RX_RSS")
         .allowlist_var("RTE_ETH_MQ_TX_NONE")
+        .allowlist_var("RTE_ETH_RETA_GROUP_SIZE")
         .allowlist_function("rte_eth_find_next_owned_by")
         .allowlist_function("rte_eth_dev_info_get")
         .allowlist_function("rte_eth_macaddr_get")
@@ -149,6 +150,8 @@ fn os_build() -> Result<()> {
         .allowlist_function("rte_eth_tx_burst")
         .allowlist_function("rte_eth_rx_burst")
         .allowlist_function("rte_eal_init")
+        .allowlist_function("rte_eal_process_type")
+        .allowlist_function("rte_eth_dev_rss_reta_update")
         .clang_arg(cflags)
         .header("wrapper.h")
         .parse_callbacks(Box::new(bindgen::CargoCallbacks))
@@ -259,6 +262,7 @@ fn os_build() -> Result<()> {
         .allowlist_var("RTE_ETH_RX_OFFLOAD_UDP_CKSUM")
         .allowlist_var("RTE_ETH_MQ_RX_RSS")
         .allowlist_var("RTE_ETH_MQ_TX_NONE")
+        .allowlist_var("RTE_ETH_RETA_GROUP_SIZE")
         .allowlist_function("rte_eth_find_next_owned_by")
         .allowlist_function("rte_eth_dev_info_get")
         .allowlist_function("rte_eth_macaddr_get")
@@ -298,6 +302,8 @@ fn os_build() -> Result<()> {
         .allowlist_function("rte_eth_tx_burst")
         .allowlist_function("rte_eth_rx_burst")
         .allowlist_function("rte_eal_init")
+        .allowlist_function("rte_eal_process_type")
+        .allowlist_function("rte_eth_dev_rss_reta_update")
         .clang_arg("-mavx")
         .header("wrapper.h")
         .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
@@ -3,10 +3,23 @@
  * Licensed under the MIT license.
  */
 
+#include <stdio.h>
+#include <unistd.h>
 #include <rte_errno.h>
 #include <rte_ethdev.h>
 #include <rte_ether.h>
+#include <rte_ip.h>
 #include <rte_mbuf.h>
+#include <rte_mbuf_dyn.h>
+#include <rte_tcp.h>
+#include <rte_udp.h>
+#include <rte_version.h>
+
+// Inline encryption of TLS records is only available from DPDK 23.11 onwards.
+#if RTE_VERSION >= RTE_VERSION_NUM(23, 11, 0, 0)
+#include <rte_security.h>
+#define HAS_TLS_RECORD_OFFLOAD 1
+#endif
 
 void rte_pktmbuf_free_(struct rte_mbuf *packet)
 {
@@ -90,10 +103,259 @@ int rte_eth_tx_offload_udp_cksum_()
 
 int rte_eth_rx_offload_udp_cksum_()
 {
-    return RTE_ETH_RX_OFFLOAD_TCP_CKSUM;
+    return RTE_ETH_RX_OFFLOAD_UDP_CKSUM;
 }
 
 int rte_eth_tx_offload_multi_segs_()
 {
     return RTE_ETH_TX_OFFLOAD_MULTI_SEGS;
 }
+
+int rte_eth_tx_offload_ipv4_cksum_()
+{
+    return RTE_ETH_TX_OFFLOAD_IPV4_CKSUM;
+}
+
+int rte_eth_tx_offload_tcp_tso_()
+{
+    return RTE_ETH_TX_OFFLOAD_TCP_TSO;
+}
+
+void rte_mbuf_tx_cksum_offload_(struct rte_mbuf *m, uint16_t l2_len, uint16_t l3_len, uint8_t l4_proto)
+{
+    struct rte_ipv4_hdr *ipv4_hdr = rte_pktmbuf_mtod_offset(m, struct rte_ipv4_hdr *, l2_len);
+
+    m->l2_len = l2_len;
+    m->l3_len = l3_len;
+    m->ol_flags |= RTE_MBUF_F_TX_IPV4;
+
+    // The device expects the checksum field to be seeded with the checksum of the pseudo-header.
+    if (l4_proto == IPPROTO_TCP)
+    {
+        struct rte_tcp_hdr *tcp_hdr = rte_pktmbuf_mtod_offset(m, struct rte_tcp_hdr *, l2_len + l3_len);
+        m->ol_flags |= RTE_MBUF_F_TX_TCP_CKSUM;
+        tcp_hdr->cksum = rte_ipv4_phdr_cksum(ipv4_hdr, m->ol_flags);
+    }
+    else if (l4_proto == IPPROTO_UDP)
+    {
+        struct rte_udp_hdr *udp_hdr = rte_pktmbuf_mtod_offset(m, struct rte_udp_hdr *, l2_len + l3_len);
+        m->ol_flags |= RTE_MBUF_F_TX_UDP_CKSUM;
+        udp_hdr->dgram_cksum = rte_ipv4_phdr_cksum(ipv4_hdr, m->ol_flags);
+    }
+}
+
+void rte_mbuf_tx_tcp_seg_offload_(struct rte_mbuf *m, uint16_t l2_len, uint16_t l3_len, uint16_t l4_len, uint16_t tso_segsz)
+{
+    struct rte_ipv4_hdr *ipv4_hdr = rte_pktmbuf_mtod_offset(m, struct rte_ipv4_hdr *, l2_len);
+    struct rte_tcp_hdr *tcp_hdr = rte_pktmbuf_mtod_offset(m, struct rte_tcp_hdr *, l2_len + l3_len);
+
+    m->l2_len = l2_len;
+    m->l3_len = l3_len;
+    m->l4_len = l4_len;
+    m->tso_segsz = tso_segsz;
+    m->ol_flags |= RTE_MBUF_F_TX_IPV4 | RTE_MBUF_F_TX_IP_CKSUM | RTE_MBUF_F_TX_TCP_SEG;
+
+    // The device computes the IPv4 checksum of every segment, and expects the TCP checksum field to be seeded with the
+    // checksum of the pseudo-header, without the length.
+    ipv4_hdr->hdr_checksum = 0;
+    tcp_hdr->cksum = rte_ipv4_phdr_cksum(ipv4_hdr, m->ol_flags);
+}
+
+int rte_mbuf_rx_l4_cksum_good_(const struct rte_mbuf *m)
+{
+    return (m->ol_flags & RTE_MBUF_F_RX_L4_CKSUM_MASK) == RTE_MBUF_F_RX_L4_CKSUM_GOOD;
+}
+
+int rte_mbuf_rx_l4_cksum_bad_(const struct rte_mbuf *m)
+{
+    return (m->ol_flags & RTE_MBUF_F_RX_L4_CKSUM_MASK) == RTE_MBUF_F_RX_L4_CKSUM_BAD;
+}
+
+int rte_eth_tx_offload_vlan_insert_()
+{
+    return RTE_ETH_TX_OFFLOAD_VLAN_INSERT;
+}
+
+int rte_eth_rx_offload_vlan_strip_()
+{
+    return RTE_ETH_RX_OFFLOAD_VLAN_STRIP;
+}
+
+void rte_mbuf_tx_vlan_offload_(struct rte_mbuf *m, uint16_t vlan_tci)
+{
+    m->vlan_tci = vlan_tci;
+    m->ol_flags |= RTE_MBUF_F_TX_VLAN;
+}
+
+int rte_mbuf_rx_vlan_tci_(const struct rte_mbuf *m)
+{
+    // The tag control information is only valid if the device stripped the tag from the frame.
+    if (m->ol_flags & RTE_MBUF_F_RX_VLAN_STRIPPED)
+    {
+        return m->vlan_tci;
+    }
+    return -1;
+}
+
+int rte_eth_rx_offload_timestamp_()
+{
+    return RTE_ETH_RX_OFFLOAD_TIMESTAMP;
+}
+
+// Offset and flag of the dynamic field of mbufs in which devices store receive timestamps.
+static int rx_timestamp_offset = -1;
+static uint64_t rx_timestamp_flag = 0;
+
+int rte_mbuf_rx_timestamp_register_()
+{
+    return rte_mbuf_dyn_rx_timestamp_register(&rx_timestamp_offset, &rx_timestamp_flag);
+}
+
+int rte_mbuf_rx_timestamp_(const struct rte_mbuf *m, uint64_t *timestamp)
+{
+    // The timestamp is only valid if the device stamped the frame.
+    if (rx_timestamp_offset < 0 || !(m->ol_flags & rx_timestamp_flag))
+    {
+        return 0;
+    }
+    *timestamp = *RTE_MBUF_DYNFIELD(m, rx_timestamp_offset, rte_mbuf_timestamp_t *);
+    return 1;
+}
+
+void rte_mbuf_tx_timestamp_(struct rte_mbuf *m)
+{
+    m->ol_flags |= RTE_MBUF_F_TX_IEEE1588_TMST;
+}
+
+int rte_eth_timesync_enable_(uint16_t port_id)
+{
+    return rte_eth_timesync_enable(port_id);
+}
+
+int rte_eth_timesync_read_tx_timestamp_(uint16_t port_id, uint64_t *timestamp)
+{
+    struct timespec ts;
+    int ret = rte_eth_timesync_read_tx_timestamp(port_id, &ts);
+    if (ret == 0)
+    {
+        *timestamp = (uint64_t)ts.tv_sec * 1000000000ULL + (uint64_t)ts.tv_nsec;
+    }
+    return ret;
+}
+
+int rte_eth_tx_offload_security_()
+{
+    return RTE_ETH_TX_OFFLOAD_SECURITY;
+}
+
+#ifdef HAS_TLS_RECORD_OFFLOAD
+// Pool of the security sessions of the TLS connections of this process.
+static struct rte_mempool *tls_session_pool = NULL;
+
+static struct rte_mempool *tls_session_pool_(void *ctx)
+{
+    if (tls_session_pool == NULL)
+    {
+        char name[RTE_MEMPOOL_NAMESIZE];
+        snprintf(name, sizeof(name), "tls_sessions_%d", getpid());
+        tls_session_pool = rte_mempool_create(name, 1024, rte_security_session_get_size(ctx), 0, 0, NULL, NULL, NULL,
+                                              NULL, SOCKET_ID_ANY, 0);
+    }
+    return tls_session_pool;
+}
+#endif
+
+int rte_security_tls_tx_capable_(uint16_t port_id)
+{
+#ifdef HAS_TLS_RECORD_OFFLOAD
+    void *ctx = rte_eth_dev_get_sec_ctx(port_id);
+    if (ctx == NULL)
+    {
+        return 0;
+    }
+    struct rte_security_capability_idx idx = {
+        .action = RTE_SECURITY_ACTION_TYPE_INLINE_PROTOCOL,
+        .protocol = RTE_SECURITY_PROTOCOL_TLS_RECORD,
+        .tls_record = {
+            .ver = RTE_SECURITY_VERSION_TLS_1_3,
+            .type = RTE_SECURITY_TLS_SESS_TYPE_WRITE,
+        },
+    };
+    const struct rte_security_capability *capability = rte_security_capability_get(ctx, &idx);
+    if (capability == NULL)
+    {
+        return 0;
+    }
+    // TLS sessions only negotiate TLS_CHACHA20_POLY1305_SHA256.
+    for (const struct rte_cryptodev_capabilities *crypto = capability->crypto_capabilities;
+         crypto->op != RTE_CRYPTO_OP_TYPE_UNDEFINED; crypto++)
+    {
+        if (crypto->op == RTE_CRYPTO_OP_TYPE_SYMMETRIC && crypto->sym.xform_type == RTE_CRYPTO_SYM_XFORM_AEAD &&
+            crypto->sym.aead.algo == RTE_CRYPTO_AEAD_CHACHA20_POLY1305)
+        {
+            return 1;
+        }
+    }
+#endif
+    return 0;
+}
+
+void *rte_security_tls_tx_session_create_(uint16_t port_id, const uint8_t *key, const uint8_t *iv, uint64_t seq_no)
+{
+#ifdef HAS_TLS_RECORD_OFFLOAD
+    void *ctx = rte_eth_dev_get_sec_ctx(port_id);
+    struct rte_mempool *pool = ctx == NULL ? NULL : tls_session_pool_(ctx);
+    if (pool == NULL)
+    {
+        return NULL;
+    }
+    struct rte_crypto_sym_xform aead = {
+        .type = RTE_CRYPTO_SYM_XFORM_AEAD,
+        .aead = {
+            .op = RTE_CRYPTO_AEAD_OP_ENCRYPT,
+            .algo = RTE_CRYPTO_AEAD_CHACHA20_POLY1305,
+            .key = {.data = key, .length = 32},
+            .iv = {.offset = 0, .length = 12},
+            .digest_length = 16,
+            .aad_length = 5,
+        },
+    };
+    struct rte_security_session_conf conf = {
+        .action_type = RTE_SECURITY_ACTION_TYPE_INLINE_PROTOCOL,
+        .protocol = RTE_SECURITY_PROTOCOL_TLS_RECORD,
+        .tls_record = {
+            .ver = RTE_SECURITY_VERSION_TLS_1_3,
+            .type = RTE_SECURITY_TLS_SESS_TYPE_WRITE,
+            .seq_no = seq_no,
+        },
+        .crypto_xform = &aead,
+    };
+    rte_memcpy(conf.tls_record.tls_1_3.imp_nonce, iv, 12);
+    return rte_security_session_create(ctx, &conf, pool);
+#else
+    return NULL;
+#endif
+}
+
+void rte_security_tls_tx_session_destroy_(uint16_t port_id, void *session)
+{
+#ifdef HAS_TLS_RECORD_OFFLOAD
+    void *ctx = rte_eth_dev_get_sec_ctx(port_id);
+    if (ctx != NULL)
+    {
+        rte_security_session_destroy(ctx, session);
+    }
+#endif
+}
+
+void rte_mbuf_tx_tls_offload_(struct rte_mbuf *m, uint16_t port_id, void *session)
+{
+#ifdef HAS_TLS_RECORD_OFFLOAD
+    void *ctx = rte_eth_dev_get_sec_ctx(port_id);
+    if (ctx != NULL)
+    {
+        rte_security_set_pkt_metadata(ctx, session, m, NULL);
+        m->ol_flags |= RTE_MBUF_F_TX_SEC_OFFLOAD;
+    }
+#endif
+}
@@ -7,7 +7,7 @@
 #![allow(non_snake_case)]
 #![allow(unused)]
 
-use ::std::os::raw::{c_char, c_int};
+use ::std::os::raw::{c_char, c_int, c_void};
 
 #[link(name = "inlined")]
 extern "C" {
@@ -29,6 +29,27 @@ extern "C" {
     fn rte_eth_rx_offload_tcp_cksum_() -> c_int;
     fn rte_eth_rx_offload_udp_cksum_() -> c_int;
     fn rte_eth_tx_offload_multi_segs_() -> c_int;
+    fn rte_eth_tx_offload_ipv4_cksum_() -> c_int;
+    fn rte_eth_tx_offload_tcp_tso_() -> c_int;
+    fn rte_mbuf_tx_cksum_offload_(m: *mut rte_mbuf, l2_len: u16, l3_len: u16, l4_proto: u8);
+    fn rte_mbuf_tx_tcp_seg_offload_(m: *mut rte_mbuf, l2_len: u16, l3_len: u16, l4_len: u16, tso_segsz: u16);
+    fn rte_mbuf_rx_l4_cksum_good_(m: *const rte_mbuf) -> c_int;
+    fn rte_mbuf_rx_l4_cksum_bad_(m: *const rte_mbuf) -> c_int;
+    fn rte_eth_tx_offload_vlan_insert_() -> c_int;
+    fn rte_eth_rx_offload_vlan_strip_() -> c_int;
+    fn rte_mbuf_tx_vlan_offload_(m: *mut rte_mbuf, vlan_tci: u16);
+    fn rte_mbuf_rx_vlan_tci_(m: *const rte_mbuf) -> c_int;
+    fn rte_eth_rx_offload_timestamp_() -> c_int;
+    fn rte_mbuf_rx_timestamp_register_() -> c_int;
+    fn rte_mbuf_rx_timestamp_(m: *const rte_mbuf, timestamp: *mut u64) -> c_int;
+    fn rte_mbuf_tx_timestamp_(m: *mut rte_mbuf);
+    fn rte_eth_timesync_enable_(port_id: u16) -> c_int;
+    fn rte_eth_timesync_read_tx_timestamp_(port_id: u16, timestamp: *mut u64) -> c_int;
+    fn rte_eth_tx_offload_security_() -> c_int;
+    fn rte_security_tls_tx_capable_(port_id: u16) -> c_int;
+    fn rte_security_tls_tx_session_create_(port_id: u16, key: *const u8, iv: *const u8, seq_no: u64) -> *mut c_void;
+    fn rte_security_tls_tx_session_destroy_(port_id: u16, session: *mut c_void);
+    fn rte_mbuf_tx_tls_offload_(m: *mut rte_mbuf, port_id: u16, session: *mut c_void);
 }
 
 #[cfg(all(feature = "mlx5", target_os = "windows"))]
@@ -150,3 +171,113 @@ pub unsafe fn rte_eth_rx_offload_udp_cksum() -> c_int {
 pub unsafe fn rte_eth_tx_offload_multi_segs() -> c_int {
     rte_eth_tx_offload_multi_segs_()
 }
+
+#[inline]
+pub unsafe fn rte_eth_tx_offload_ipv4_cksum() -> c_int {
+    rte_eth_tx_offload_ipv4_cksum_()
+}
+
+#[inline]
+pub unsafe fn rte_eth_tx_offload_tcp_tso() -> c_int {
+    rte_eth_tx_offload_tcp_tso_()
+}
+
+#[inline]
+pub unsafe fn rte_mbuf_tx_cksum_offload(m: *mut rte_mbuf, l2_len: u16, l3_len: u16, l4_proto: u8) {
+    rte_mbuf_tx_cksum_offload_(m, l2_len, l3_len, l4_proto)
+}
+
+#[inline]
+pub unsafe fn rte_mbuf_tx_tcp_seg_offload(m: *mut rte_mbuf, l2_len: u16, l3_len: u16, l4_len: u16, tso_segsz: u16) {
+    rte_mbuf_tx_tcp_seg_offload_(m, l2_len, l3_len, l4_len, tso_segsz)
+}
+
+#[inline]
+pub unsafe fn rte_mbuf_rx_l4_cksum_good(m: *const rte_mbuf) -> c_int {
+    rte_mbuf_rx_l4_cksum_good_(m)
+}
+
+#[inline]
+pub unsafe fn rte_mbuf_rx_l4_cksum_bad(m: *const rte_mbuf) -> c_int {
+    rte_mbuf_rx_l4_cksum_bad_(m)
+}
+
+#[inline]
+pub unsafe fn rte_eth_tx_offload_vlan_insert() -> c_int {
+    rte_eth_tx_offload_vlan_insert_()
+}
+
+#[inline]
+pub unsafe fn rte_eth_rx_offload_vlan_strip() -> c_int {
+    rte_eth_rx_offload_vlan_strip_()
+}
+
+#[inline]
+pub unsafe fn rte_mbuf_tx_vlan_offload(m: *mut rte_mbuf, vlan_tci: u16) {
+    rte_mbuf_tx_vlan_offload_(m, vlan_tci)
+}
+
+#[inline]
+pub unsafe fn rte_mbuf_rx_vlan_tci(m: *const rte_mbuf) -> c_int {
+    rte_mbuf_rx_vlan_tci_(m)
+}
+
+#[inline]
+pub unsafe fn rte_eth_rx_offload_timestamp() -> c_int {
+    rte_eth_rx_offload_timestamp_()
+}
+
+#[inline]
+pub unsafe fn rte_mbuf_rx_timestamp_register() -> c_int {
+    rte_mbuf_rx_timestamp_register_()
+}
+
+#[inline]
+pub unsafe fn rte_mbuf_rx_timestamp(m: *const rte_mbuf, timestamp: *mut u64) -> c_int {
+    rte_mbuf_rx_timestamp_(m, timestamp)
+}
+
+#[inline]
+pub unsafe fn rte_mbuf_tx_timestamp(m: *mut rte_mbuf) {
+    rte_mbuf_tx_timestamp_(m)
+}
+
+#[inline]
+pub unsafe fn rte_eth_timesync_enable(port_id: u16) -> c_int {
+    rte_eth_timesync_enable_(port_id)
+}
+
+#[inline]
+pub unsafe fn rte_eth_timesync_read_tx_timestamp(port_id: u16, timestamp: *mut u64) -> c_int {
+    rte_eth_timesync_read_tx_timestamp_(port_id, timestamp)
+}
+
+#[inline]
+pub unsafe fn rte_eth_tx_offload_security() -> c_int {
+    rte_eth_tx_offload_security_()
+}
+
+#[inline]
+pub unsafe fn rte_security_tls_tx_capable(port_id: u16) -> c_int {
+    rte_security_tls_tx_capable_(port_id)
+}
+
+#[inline]
+pub unsafe fn rte_security_tls_tx_session_create(
+    port_id: u16,
+    key: *const u8,
+    iv: *const u8,
+    seq_no: u64,
+) -> *mut c_void {
+    rte_security_tls_tx_session_create_(port_id, key, iv, seq_no)
+}
+
+#[inline]
+pub unsafe fn rte_security_tls_tx_session_destroy(port_id: u16, session: *mut c_void) {
+    rte_security_tls_tx_session_destroy_(port_id, session)
+}
+
+#[inline]
+pub unsafe fn rte_mbuf_tx_tls_offload(m: *mut rte_mbuf, port_id: u16, session: *mut c_void) {
+    rte_mbuf_tx_tls_offload_(m, port_id, session)
+}
@@ -0,0 +1,353 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    stats::Stats,
+    LoadMode,
+    Protocol,
+    AF_INET,
+    DEFAULT_TIMEOUT,
+    SOCK_DGRAM,
+    SOCK_STREAM,
+};
+use anyhow::Result;
+use demikernel::{
+    demi_sgarray_t,
+    runtime::types::{
+        demi_opcode_t,
+        demi_qresult_t,
+    },
+    LibOS,
+    QDesc,
+    QToken,
+};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    slice,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Size of the timestamp that leads every request.
+const TIMESTAMP_SIZE: usize = std::mem::size_of::<u64>();
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// An echo client that generates load on a server and measures the latency of its responses. Every request carries the
+/// time at which it was due, so that responses do not have to be matched to requests.
+pub struct EchoClient {
+    /// Underlying libOS.
+    libos: LibOS,
+    /// Transport protocol.
+    protocol: Protocol,
+    /// Size of requests (in bytes).
+    bufsize: usize,
+    /// Local address that UDP sockets are bound to.
+    local: Option<SocketAddr>,
+    /// Address of remote peer.
+    remote: SocketAddr,
+    /// Sockets of the clients and the bytes of the responses that they are receiving.
+    clients: HashMap<QDesc, Vec<u8>>,
+    /// List of pending operations.
+    qts: Vec<QToken>,
+    /// Start time, which the timestamps of requests are relative to.
+    start: Instant,
+    /// Time from which latencies are recorded.
+    warmup_end: Instant,
+    /// Statistics of the run.
+    stats: Stats,
+    /// Statistics since the last log.
+    log_stats: Stats,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl EchoClient {
+    /// Instantiates a new echo client.
+    pub fn new(
+        libos: LibOS,
+        protocol: Protocol,
+        bufsize: usize,
+        local: Option<SocketAddr>,
+        remote: SocketAddr,
+    ) -> Result<Self> {
+        let now: Instant = Instant::now();
+        Ok(Self {
+            libos,
+            protocol,
+            bufsize,
+            local,
+            remote,
+            clients: HashMap::default(),
+            qts: Vec::default(),
+            start: now,
+            warmup_end: now,
+            stats: Stats::new()?,
+            log_stats: Stats::new()?,
+        })
+    }
+
+    /// Runs [nclients] clients that generate load in [mode] for [duration] and prints the statistics of the requests
+    /// that were due after [warmup].
+    pub fn run(
+        &mut self,
+        nclients: usize,
+        mode: LoadMode,
+        duration: Duration,
+        warmup: Duration,
+        log_interval: Option<u64>,
+    ) -> Result<()> {
+        for i in 0..nclients {
+            let qd: QDesc = self.open(i)?;
+            self.clients.insert(qd, Vec::with_capacity(self.bufsize));
+            self.issue_pop(qd)?;
+        }
+        println!("INFO: {} clients connected", self.clients.len());
+        let qds: Vec<QDesc> = self.clients.keys().copied().collect();
+
+        self.start = Instant::now();
+        self.warmup_end = self.start + warmup;
+        let end: Instant = self.start + duration;
+        let mut last_log: Instant = self.start;
+
+        // Open-loop mode: requests are due at fixed intervals and are spread over clients in a round-robin fashion.
+        let interval: Duration = match mode {
+            LoadMode::Closed => Duration::ZERO,
+            LoadMode::Open(rate) => Duration::from_secs_f64(1.0 / rate as f64),
+        };
+        let mut next_due: Instant = self.start;
+        let mut next_client: usize = 0;
+
+        // Closed-loop mode: each client issues its first request.
+        if mode == LoadMode::Closed {
+            for qd in &qds {
+                self.issue_request(*qd, self.start)?;
+            }
+        }
+
+        loop {
+            let now: Instant = Instant::now();
+            if now >= end {
+                break;
+            }
+
+            // Dump statistics.
+            if let Some(log_interval) = log_interval {
+                if now - last_log > Duration::from_secs(log_interval) {
+                    self.log_stats.log(now - last_log)?;
+                    self.log_stats = Stats::new()?;
+                    last_log = now;
+                }
+            }
+
+            // Issue requests that are due. Latencies are measured from the time at which requests were due, so that
+            // the client does not hide the queueing delay of a slow server (coordinated omission).
+            let deadline: Instant = match mode {
+                LoadMode::Closed => end,
+                LoadMode::Open(_) => {
+                    while next_due <= now {
+                        self.issue_request(qds[next_client], next_due)?;
+                        next_client = (next_client + 1) % qds.len();
+                        next_due += interval;
+                    }
+                    next_due.min(end)
+                },
+            };
+
+            let qr: demi_qresult_t = match self.libos.wait_any(&self.qts, Some(deadline - now)) {
+                Ok((index, qr)) => {
+                    self.qts.swap_remove(index);
+                    qr
+                },
+                Err(e) if e.errno == libc::ETIMEDOUT => continue,
+                Err(e) => anyhow::bail!("wait_any() failed: {:?}", e),
+            };
+
+            match qr.qr_opcode {
+                demi_opcode_t::DEMI_OPC_POP => self.handle_pop(&qr, mode)?,
+                demi_opcode_t::DEMI_OPC_PUSH => (),
+                demi_opcode_t::DEMI_OPC_FAILED => {
+                    let qd: QDesc = qr.qr_qd.into();
+                    anyhow::bail!("operation failed (qd={:?}, errno={:?})", qd, qr.qr_ret)
+                },
+                demi_opcode_t::DEMI_OPC_INVALID => self.handle_unexpected("invalid", &qr)?,
+                demi_opcode_t::DEMI_OPC_CLOSE => self.handle_unexpected("close", &qr)?,
+                demi_opcode_t::DEMI_OPC_TASK => self.handle_unexpected("task", &qr)?,
+                demi_opcode_t::DEMI_OPC_SPLICE => self.handle_unexpected("splice", &qr)?,
+                demi_opcode_t::DEMI_OPC_POP_MANY => self.handle_unexpected("pop_many", &qr)?,
+                demi_opcode_t::DEMI_OPC_CONNECT => self.handle_unexpected("connect", &qr)?,
+                demi_opcode_t::DEMI_OPC_ACCEPT => self.handle_unexpected("accept", &qr)?,
+            }
+        }
+
+        println!(
+            "INFO: {:?} {:?} run with {} clients and {} byte requests",
+            self.protocol,
+            mode,
+            qds.len(),
+            self.bufsize
+        );
+        self.stats.report(end - self.warmup_end)
+    }
+
+    /// Opens the socket of the [i]-th client.
+    fn open(&mut self, i: usize) -> Result<QDesc> {
+        match self.protocol {
+            Protocol::Tcp => {
+                let qd: QDesc = self.libos.socket(AF_INET, SOCK_STREAM, 0)?;
+                let qt: QToken = self.libos.connect(qd, self.remote)?;
+                let qr: demi_qresult_t = self.libos.wait(qt, Some(DEFAULT_TIMEOUT))?;
+                if qr.qr_opcode != demi_opcode_t::DEMI_OPC_CONNECT {
+                    self.libos.close(qd)?;
+                    anyhow::bail!("failed to connect to server (errno={:?})", qr.qr_ret)
+                }
+                Ok(qd)
+            },
+            Protocol::Udp => {
+                // Clients are bound to consecutive ports.
+                let mut local: SocketAddr = self.local.ok_or(anyhow::anyhow!("missing local address"))?;
+                local.set_port(local.port() + i as u16);
+                let qd: QDesc = self.libos.socket(AF_INET, SOCK_DGRAM, 0)?;
+                if let Err(e) = self.libos.bind(qd, local) {
+                    self.libos.close(qd)?;
+                    anyhow::bail!("failed to bind socket: {:?}", e)
+                }
+                Ok(qd)
+            },
+        }
+    }
+
+    /// Issues a request on [qd] that was due at [due].
+    fn issue_request(&mut self, qd: QDesc, due: Instant) -> Result<()> {
+        let sga: demi_sgarray_t = self.mksga(due)?;
+        let qt: QToken = match self.protocol {
+            Protocol::Tcp => self.libos.push(qd, &sga),
+            Protocol::Udp => self.libos.pushto(qd, &sga, self.remote),
+        }?;
+        self.libos.sgafree(sga)?;
+        self.qts.push(qt);
+        if due >= self.warmup_end {
+            self.stats.record_sent();
+        }
+        self.log_stats.record_sent();
+        Ok(())
+    }
+
+    /// Issues a pop operation.
+    fn issue_pop(&mut self, qd: QDesc) -> Result<()> {
+        let qt: QToken = self.libos.pop(qd, None)?;
+        self.qts.push(qt);
+        Ok(())
+    }
+
+    /// Creates a scatter-gather array for a request that was due at [due].
+    fn mksga(&mut self, due: Instant) -> Result<demi_sgarray_t> {
+        let sga: demi_sgarray_t = self.libos.sgaalloc(self.bufsize)?;
+        let ptr: *mut u8 = sga.sga_segs[0].sgaseg_buf as *mut u8;
+        let len: usize = sga.sga_segs[0].sgaseg_len as usize;
+        let slice: &mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, len) };
+        let timestamp: u64 = due.duration_since(self.start).as_nanos() as u64;
+        slice.fill(0);
+        slice[0..TIMESTAMP_SIZE].copy_from_slice(&timestamp.to_le_bytes());
+        Ok(sga)
+    }
+
+    /// Handles the completion of a pop operation. TCP responses may arrive split or coalesced, so bytes are accumulated
+    /// until whole responses are received, whereas every UDP datagram is a response.
+    fn handle_pop(&mut self, qr: &demi_qresult_t, mode: LoadMode) -> Result<()> {
+        let qd: QDesc = qr.qr_qd.into();
+        let sga: demi_sgarray_t = unsafe { qr.qr_value.sga };
+        let ptr: *mut u8 = sga.sga_segs[0].sgaseg_buf as *mut u8;
+        let len: usize = sga.sga_segs[0].sgaseg_len as usize;
+        if len == 0 {
+            self.libos.sgafree(sga)?;
+            anyhow::bail!("server closed connection (qd={:?})", qd);
+        }
+        let data: &[u8] = unsafe { slice::from_raw_parts(ptr, len) };
+
+        let mut timestamps: Vec<u64> = Vec::new();
+        match self.protocol {
+            Protocol::Tcp => {
+                let buf: &mut Vec<u8> = self
+                    .clients
+                    .get_mut(&qd)
+                    .ok_or(anyhow::anyhow!("unknown client (qd={:?})", qd))?;
+                buf.extend_from_slice(data);
+                while buf.len() >= self.bufsize {
+                    timestamps.push(parse_timestamp(&buf[..TIMESTAMP_SIZE]));
+                    buf.drain(..self.bufsize);
+                }
+            },
+            Protocol::Udp if len >= TIMESTAMP_SIZE => timestamps.push(parse_timestamp(&data[..TIMESTAMP_SIZE])),
+            Protocol::Udp => println!("WARN: ignoring truncated response (qd={:?}, len={:?})", qd, len),
+        }
+        self.libos.sgafree(sga)?;
+
+        let now: Instant = Instant::now();
+        for timestamp in timestamps {
+            let due: Instant = self.start + Duration::from_nanos(timestamp);
+            if due >= self.warmup_end {
+                self.stats.record_completed(now - due)?;
+            }
+            self.log_stats.record_completed(now - due)?;
+
+            // Closed-loop mode: issue the next request.
+            if mode == LoadMode::Closed {
+                self.issue_request(qd, now)?;
+            }
+        }
+
+        self.issue_pop(qd)
+    }
+
+    /// Handles the completion of an unexpected operation.
+    fn handle_unexpected(&mut self, op_name: &str, qr: &demi_qresult_t) -> Result<()> {
+        let qd: QDesc = qr.qr_qd.into();
+        let qt: QToken = qr.qr_qt.into();
+        println!(
+            "WARN: unexpected {} operation completed, ignoring (qd={:?}, qt={:?})",
+            op_name, qd, qt
+        );
+        Ok(())
+    }
+}
+
+//======================================================================================================================
+// Standalone functions
+//======================================================================================================================
+
+/// Parses the timestamp that leads a response.
+fn parse_timestamp(bytes: &[u8]) -> u64 {
+    let mut timestamp: [u8; TIMESTAMP_SIZE] = [0; TIMESTAMP_SIZE];
+    timestamp.copy_from_slice(bytes);
+    u64::from_le_bytes(timestamp)
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Drop for EchoClient {
+    fn drop(&mut self) {
+        for qd in self.clients.drain().map(|(qd, _)| qd).collect::<Vec<_>>() {
+            if let Err(e) = self.libos.close(qd) {
+                println!("ERROR: close() failed (error={:?})", e);
+            }
+        }
+    }
+}
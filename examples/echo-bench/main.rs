@@ -0,0 +1,360 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+#![cfg_attr(feature = "strict", deny(warnings))]
+#![deny(clippy::all)]
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use anyhow::Result;
+use clap::{
+    Arg,
+    ArgMatches,
+    Command,
+};
+use client::EchoClient;
+use demikernel::{
+    LibOS,
+    LibOSName,
+};
+use server::EchoServer;
+use std::{
+    net::{
+        Ipv4Addr,
+        SocketAddr,
+        SocketAddrV4,
+    },
+    str::FromStr,
+    time::Duration,
+};
+#[cfg(target_os = "windows")]
+use windows::Win32::Networking::WinSock::{
+    SOCKADDR,
+    SOCKADDR_IN,
+};
+
+#[cfg(target_os = "windows")]
+pub const AF_INET: i32 = windows::Win32::Networking::WinSock::AF_INET.0 as i32;
+
+#[cfg(target_os = "windows")]
+pub const SOCK_STREAM: i32 = windows::Win32::Networking::WinSock::SOCK_STREAM.0 as i32;
+
+#[cfg(target_os = "windows")]
+pub const SOCK_DGRAM: i32 = windows::Win32::Networking::WinSock::SOCK_DGRAM.0 as i32;
+
+#[cfg(target_os = "linux")]
+pub const AF_INET: i32 = libc::AF_INET;
+
+#[cfg(target_os = "linux")]
+pub const SOCK_STREAM: i32 = libc::SOCK_STREAM;
+
+#[cfg(target_os = "linux")]
+pub const SOCK_DGRAM: i32 = libc::SOCK_DGRAM;
+
+mod client;
+mod server;
+mod stats;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default size of requests (in bytes).
+const DEFAULT_BUFSIZE: usize = 64;
+
+/// Requests carry the time at which they were issued, so they cannot be smaller than it.
+const MIN_BUFSIZE: usize = std::mem::size_of::<u64>();
+
+/// Default duration of a run (in seconds).
+const DEFAULT_DURATION: u64 = 10;
+
+//======================================================================================================================
+// Program Arguments
+//======================================================================================================================
+
+/// Transport protocol that is benchmarked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Protocol {
+    Tcp,
+    Udp,
+}
+
+/// How the client generates load.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadMode {
+    /// Each client has a single request in flight and issues the next one when the response arrives.
+    Closed,
+    /// Requests are issued at a fixed rate (in requests per second), regardless of when responses arrive.
+    Open(u64),
+}
+
+/// Program Arguments
+#[derive(Debug)]
+pub struct ProgramArguments {
+    /// Peer type.
+    peer_type: String,
+    /// Transport protocol.
+    protocol: Protocol,
+    /// Address of the server.
+    addr: SocketAddr,
+    /// Local address of the client, which UDP sockets are bound to.
+    local: Option<SocketAddr>,
+    /// Buffer size (in bytes).
+    bufsize: usize,
+    /// Number of clients.
+    nclients: usize,
+    /// Load generation mode.
+    mode: LoadMode,
+    /// Duration of the run, warmup included.
+    duration: Duration,
+    /// Duration of the warmup, during which latencies are not recorded.
+    warmup: Duration,
+    /// Log interval.
+    log_interval: Option<u64>,
+}
+
+/// Associate functions for Program Arguments
+impl ProgramArguments {
+    /// Parses the program arguments from the command line interface.
+    pub fn new(app_name: &'static str, app_author: &'static str, app_about: &'static str) -> Result<Self> {
+        let matches: ArgMatches = Command::new(app_name)
+            .author(app_author)
+            .about(app_about)
+            .arg(
+                Arg::new("addr")
+                    .long("address")
+                    .value_parser(clap::value_parser!(String))
+                    .required(true)
+                    .value_name("ADDRESS:PORT")
+                    .help("Sets address of the server"),
+            )
+            .arg(
+                Arg::new("local")
+                    .long("local")
+                    .value_parser(clap::value_parser!(String))
+                    .required(false)
+                    .value_name("ADDRESS:PORT")
+                    .help("Sets local address of the first client (UDP only)"),
+            )
+            .arg(
+                Arg::new("peer")
+                    .long("peer")
+                    .value_parser(clap::value_parser!(String))
+                    .required(true)
+                    .value_name("server|client")
+                    .default_value("server")
+                    .help("Sets peer type"),
+            )
+            .arg(
+                Arg::new("protocol")
+                    .long("protocol")
+                    .value_parser(clap::value_parser!(String))
+                    .required(false)
+                    .value_name("tcp|udp")
+                    .default_value("tcp")
+                    .help("Sets transport protocol"),
+            )
+            .arg(
+                Arg::new("bufsize")
+                    .long("bufsize")
+                    .value_parser(clap::value_parser!(usize))
+                    .required(false)
+                    .value_name("SIZE")
+                    .help("Sets size of requests"),
+            )
+            .arg(
+                Arg::new("nclients")
+                    .long("nclients")
+                    .value_parser(clap::value_parser!(usize))
+                    .required(false)
+                    .value_name("NUMBER")
+                    .help("Sets number of clients"),
+            )
+            .arg(
+                Arg::new("mode")
+                    .long("mode")
+                    .value_parser(clap::value_parser!(String))
+                    .required(false)
+                    .value_name("closed|open")
+                    .default_value("closed")
+                    .help("Sets load generation mode"),
+            )
+            .arg(
+                Arg::new("rate")
+                    .long("rate")
+                    .value_parser(clap::value_parser!(u64))
+                    .required(false)
+                    .value_name("RPS")
+                    .help("Sets request rate of the open-loop mode"),
+            )
+            .arg(
+                Arg::new("duration")
+                    .long("duration")
+                    .value_parser(clap::value_parser!(u64))
+                    .required(false)
+                    .value_name("SECONDS")
+                    .help("Sets duration of the run"),
+            )
+            .arg(
+                Arg::new("warmup")
+                    .long("warmup")
+                    .value_parser(clap::value_parser!(u64))
+                    .required(false)
+                    .value_name("SECONDS")
+                    .help("Sets duration of the warmup"),
+            )
+            .arg(
+                Arg::new("log")
+                    .long("log")
+                    .value_parser(clap::value_parser!(u64))
+                    .required(false)
+                    .value_name("INTERVAL")
+                    .help("Enables logging"),
+            )
+            .get_matches();
+
+        // Socket address.
+        let addr: SocketAddr = {
+            let addr: &String = matches.get_one::<String>("addr").expect("missing address");
+            SocketAddr::from_str(addr)?
+        };
+
+        // Local address.
+        let local: Option<SocketAddr> = match matches.get_one::<String>("local") {
+            Some(local) => Some(SocketAddr::from_str(local)?),
+            None => None,
+        };
+
+        // Peer type.
+        let peer_type: String = matches
+            .get_one::<String>("peer")
+            .expect("missing peer type")
+            .to_string();
+        if peer_type != "server" && peer_type != "client" {
+            anyhow::bail!("invalid peer type");
+        }
+
+        // Transport protocol.
+        let protocol: Protocol = match matches.get_one::<String>("protocol").map(|s| s.as_str()) {
+            Some("tcp") => Protocol::Tcp,
+            Some("udp") => Protocol::Udp,
+            _ => anyhow::bail!("invalid protocol"),
+        };
+
+        // Buffer size.
+        let bufsize: usize = *matches.get_one::<usize>("bufsize").unwrap_or(&DEFAULT_BUFSIZE);
+        if bufsize < MIN_BUFSIZE {
+            anyhow::bail!("buffer size must be at least {} bytes", MIN_BUFSIZE);
+        }
+
+        // Number of clients.
+        let nclients: usize = *matches.get_one::<usize>("nclients").unwrap_or(&1);
+        if nclients == 0 {
+            anyhow::bail!("number of clients must be positive");
+        }
+
+        // Load generation mode.
+        let mode: LoadMode = match matches.get_one::<String>("mode").map(|s| s.as_str()) {
+            Some("closed") => LoadMode::Closed,
+            Some("open") => match matches.get_one::<u64>("rate") {
+                Some(rate) if *rate > 0 => LoadMode::Open(*rate),
+                _ => anyhow::bail!("open-loop mode requires a positive request rate"),
+            },
+            _ => anyhow::bail!("invalid load generation mode"),
+        };
+
+        // Duration and warmup.
+        let duration: Duration = Duration::from_secs(*matches.get_one::<u64>("duration").unwrap_or(&DEFAULT_DURATION));
+        let warmup: Duration = Duration::from_secs(*matches.get_one::<u64>("warmup").unwrap_or(&0));
+        if warmup >= duration {
+            anyhow::bail!("warmup must be shorter than the run");
+        }
+
+        // Log interval.
+        let log_interval: Option<u64> = matches.get_one::<u64>("log").copied().filter(|interval| *interval > 0);
+
+        // UDP clients are bound to consecutive ports from the local address.
+        if peer_type == "client" && protocol == Protocol::Udp {
+            match local {
+                Some(local) if local.port() != 0 && local.port() as usize + nclients <= u16::MAX as usize + 1 => (),
+                _ => anyhow::bail!("udp client requires a local address with room for {} ports", nclients),
+            }
+        }
+
+        Ok(Self {
+            peer_type,
+            protocol,
+            addr,
+            local,
+            bufsize,
+            nclients,
+            mode,
+            duration,
+            warmup,
+            log_interval,
+        })
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+#[cfg(target_os = "linux")]
+/// Converts the source address of a popped UDP datagram into a [SocketAddr].
+pub fn sockaddr_to_socketaddr(saddr: *const libc::sockaddr) -> Result<SocketAddr> {
+    let sin: libc::sockaddr_in = unsafe { *(saddr as *const libc::sockaddr_in) };
+    if sin.sin_family != libc::AF_INET as u16 {
+        anyhow::bail!("communication domain not supported");
+    };
+    let addr: Ipv4Addr = Ipv4Addr::from(u32::from_be(sin.sin_addr.s_addr));
+    let port: u16 = u16::from_be(sin.sin_port);
+    Ok(SocketAddr::V4(SocketAddrV4::new(addr, port)))
+}
+
+#[cfg(target_os = "windows")]
+/// Converts the source address of a popped UDP datagram into a [SocketAddr].
+pub fn sockaddr_to_socketaddr(saddr: *const SOCKADDR) -> Result<SocketAddr> {
+    let sin: SOCKADDR_IN = unsafe { *(saddr as *const SOCKADDR_IN) };
+    if sin.sin_family != windows::Win32::Networking::WinSock::AF_INET {
+        anyhow::bail!("communication domain not supported");
+    };
+    let addr: Ipv4Addr = Ipv4Addr::from(u32::from_be(unsafe { sin.sin_addr.S_un.S_addr }));
+    let port: u16 = u16::from_be(sin.sin_port);
+    Ok(SocketAddr::V4(SocketAddrV4::new(addr, port)))
+}
+
+//======================================================================================================================
+
+fn main() -> Result<()> {
+    let args: ProgramArguments = ProgramArguments::new(
+        "echo-bench",
+        "Microsoft Corporation",
+        "Measures the latency and throughput of echoing TCP and UDP requests.",
+    )?;
+
+    let libos_name: LibOSName = match LibOSName::from_env() {
+        Ok(libos_name) => libos_name.into(),
+        Err(e) => anyhow::bail!("{:?}", e),
+    };
+    let libos: LibOS = match LibOS::new(libos_name) {
+        Ok(libos) => libos,
+        Err(e) => anyhow::bail!("failed to initialize libos: {:?}", e.cause),
+    };
+
+    match args.peer_type.as_str() {
+        "server" => {
+            let mut server: EchoServer = EchoServer::new(libos, args.protocol, args.addr)?;
+            server.run(args.log_interval)
+        },
+        "client" => {
+            let mut client: EchoClient = EchoClient::new(libos, args.protocol, args.bufsize, args.local, args.addr)?;
+            client.run(args.nclients, args.mode, args.duration, args.warmup, args.log_interval)
+        },
+        _ => unreachable!("peer type was checked when parsing arguments"),
+    }
+}
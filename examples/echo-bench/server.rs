@@ -0,0 +1,257 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    sockaddr_to_socketaddr,
+    Protocol,
+    AF_INET,
+    SOCK_DGRAM,
+    SOCK_STREAM,
+};
+use anyhow::Result;
+use demikernel::{
+    demi_sgarray_t,
+    runtime::types::{
+        demi_opcode_t,
+        demi_qresult_t,
+    },
+    LibOS,
+    QDesc,
+    QToken,
+};
+use std::{
+    collections::{
+        HashMap,
+        HashSet,
+    },
+    net::SocketAddr,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// An echo server that sends back every request it receives.
+pub struct EchoServer {
+    /// Underlying libOS.
+    libos: LibOS,
+    /// Transport protocol.
+    protocol: Protocol,
+    /// Local socket descriptor.
+    sockqd: QDesc,
+    /// Set of connected clients (TCP only).
+    clients: HashSet<QDesc>,
+    /// List of pending operations.
+    qts: Vec<QToken>,
+    /// Reverse lookup table of pending operations.
+    qts_reverse: HashMap<QToken, QDesc>,
+    /// Number of requests echoed since the last log.
+    nechoed: usize,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl EchoServer {
+    /// Instantiates a new echo server.
+    pub fn new(mut libos: LibOS, protocol: Protocol, local: SocketAddr) -> Result<Self> {
+        let socket_type: i32 = match protocol {
+            Protocol::Tcp => SOCK_STREAM,
+            Protocol::Udp => SOCK_DGRAM,
+        };
+        let sockqd: QDesc = libos.socket(AF_INET, socket_type, 0)?;
+
+        // Bind the socket to a local address.
+        if let Err(e) = libos.bind(sockqd, local) {
+            println!("ERROR: {:?}", e);
+            libos.close(sockqd)?;
+            anyhow::bail!("{:?}", e);
+        }
+
+        // Enable the socket to accept incoming connections.
+        if protocol == Protocol::Tcp {
+            if let Err(e) = libos.listen(sockqd, 1024) {
+                println!("ERROR: {:?}", e);
+                libos.close(sockqd)?;
+                anyhow::bail!("{:?}", e);
+            }
+        }
+
+        println!("INFO: listening on {:?} ({:?})", local, protocol);
+
+        Ok(Self {
+            libos,
+            protocol,
+            sockqd,
+            clients: HashSet::default(),
+            qts: Vec::default(),
+            qts_reverse: HashMap::default(),
+            nechoed: 0,
+        })
+    }
+
+    /// Runs the echo server until it is killed.
+    pub fn run(&mut self, log_interval: Option<u64>) -> Result<()> {
+        let mut last_log: Instant = Instant::now();
+
+        match self.protocol {
+            Protocol::Tcp => self.issue_accept()?,
+            Protocol::Udp => self.issue_pop(self.sockqd)?,
+        }
+
+        loop {
+            // Dump statistics.
+            if let Some(log_interval) = log_interval {
+                if last_log.elapsed() > Duration::from_secs(log_interval) {
+                    let rps: f64 = self.nechoed as f64 / last_log.elapsed().as_secs_f64();
+                    println!("INFO: {} clients connected, {:.2} rps", self.clients.len(), rps);
+                    last_log = Instant::now();
+                    self.nechoed = 0;
+                }
+            }
+
+            // Wait for any operation to complete. Time out periodically, so that statistics are dumped when idle.
+            let qr: demi_qresult_t = match self.libos.wait_any(&self.qts, Some(Duration::from_secs(1))) {
+                Ok((index, qr)) => {
+                    self.unregister_operation(index);
+                    qr
+                },
+                Err(e) if e.errno == libc::ETIMEDOUT => continue,
+                Err(e) => anyhow::bail!("wait_any() failed: {:?}", e),
+            };
+
+            // Parse result.
+            match qr.qr_opcode {
+                demi_opcode_t::DEMI_OPC_ACCEPT => self.handle_accept(&qr)?,
+                demi_opcode_t::DEMI_OPC_POP => self.handle_pop(&qr)?,
+                demi_opcode_t::DEMI_OPC_PUSH => (),
+                demi_opcode_t::DEMI_OPC_FAILED => self.handle_fail(&qr)?,
+                demi_opcode_t::DEMI_OPC_INVALID => self.handle_unexpected("invalid", &qr)?,
+                demi_opcode_t::DEMI_OPC_CLOSE => self.handle_unexpected("close", &qr)?,
+                demi_opcode_t::DEMI_OPC_TASK => self.handle_unexpected("task", &qr)?,
+                demi_opcode_t::DEMI_OPC_SPLICE => self.handle_unexpected("splice", &qr)?,
+                demi_opcode_t::DEMI_OPC_POP_MANY => self.handle_unexpected("pop_many", &qr)?,
+                demi_opcode_t::DEMI_OPC_CONNECT => self.handle_unexpected("connect", &qr)?,
+            }
+        }
+    }
+
+    /// Issues an accept operation.
+    fn issue_accept(&mut self) -> Result<()> {
+        let qt: QToken = self.libos.accept(self.sockqd)?;
+        self.register_operation(self.sockqd, qt);
+        Ok(())
+    }
+
+    /// Issues a pop operation.
+    fn issue_pop(&mut self, qd: QDesc) -> Result<()> {
+        let qt: QToken = self.libos.pop(qd, None)?;
+        self.register_operation(qd, qt);
+        Ok(())
+    }
+
+    /// Handles the completion of an accept operation.
+    fn handle_accept(&mut self, qr: &demi_qresult_t) -> Result<()> {
+        let new_qd: QDesc = unsafe { qr.qr_value.ares.qd.into() };
+        self.clients.insert(new_qd);
+        println!("INFO: {} clients connected", self.clients.len());
+        self.issue_pop(new_qd)?;
+        self.issue_accept()
+    }
+
+    /// Handles the completion of a pop operation by echoing the data back.
+    fn handle_pop(&mut self, qr: &demi_qresult_t) -> Result<()> {
+        let qd: QDesc = qr.qr_qd.into();
+        let sga: demi_sgarray_t = unsafe { qr.qr_value.sga };
+
+        if sga.sga_segs[0].sgaseg_len == 0 {
+            println!("INFO: client closed connection (qd={:?})", qd);
+            self.handle_close(qd)?;
+        } else {
+            let qt: QToken = match self.protocol {
+                Protocol::Tcp => self.libos.push(qd, &sga)?,
+                Protocol::Udp => {
+                    let remote: SocketAddr = sockaddr_to_socketaddr(&unsafe { qr.qr_value.sga.sga_addr })?;
+                    self.libos.pushto(qd, &sga, remote)?
+                },
+            };
+            self.register_operation(qd, qt);
+            self.nechoed += 1;
+            self.issue_pop(qd)?;
+        }
+
+        self.libos.sgafree(sga)?;
+        Ok(())
+    }
+
+    /// Handles an operation that failed.
+    fn handle_fail(&mut self, qr: &demi_qresult_t) -> Result<()> {
+        let qd: QDesc = qr.qr_qd.into();
+        let errno: i64 = qr.qr_ret;
+        match errno as i32 {
+            libc::ECONNRESET | libc::ENOTCONN | libc::ECANCELED | libc::EBADF if self.clients.contains(&qd) => {
+                self.handle_close(qd)
+            },
+            _ => anyhow::bail!("operation failed (qd={:?}, errno={:?})", qd, errno),
+        }
+    }
+
+    /// Handles the completion of an unexpected operation.
+    fn handle_unexpected(&mut self, op_name: &str, qr: &demi_qresult_t) -> Result<()> {
+        let qd: QDesc = qr.qr_qd.into();
+        let qt: QToken = qr.qr_qt.into();
+        println!(
+            "WARN: unexpected {} operation completed, ignoring (qd={:?}, qt={:?})",
+            op_name, qd, qt
+        );
+        Ok(())
+    }
+
+    /// Closes the connection of a client and forgets its pending operations.
+    fn handle_close(&mut self, qd: QDesc) -> Result<()> {
+        self.qts_reverse.retain(|_, other| *other != qd);
+        let qts_reverse: &HashMap<QToken, QDesc> = &self.qts_reverse;
+        self.qts.retain(|qt| qts_reverse.contains_key(qt));
+        self.clients.remove(&qd);
+        self.libos.close(qd)?;
+        Ok(())
+    }
+
+    /// Registers an asynchronous I/O operation.
+    fn register_operation(&mut self, qd: QDesc, qt: QToken) {
+        self.qts_reverse.insert(qt, qd);
+        self.qts.push(qt);
+    }
+
+    /// Unregisters an asynchronous I/O operation.
+    fn unregister_operation(&mut self, index: usize) {
+        let qt: QToken = self.qts.swap_remove(index);
+        self.qts_reverse.remove(&qt);
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Drop for EchoServer {
+    fn drop(&mut self) {
+        for qd in self.clients.drain().collect::<Vec<_>>() {
+            if let Err(e) = self.libos.close(qd) {
+                println!("ERROR: close() failed (error={:?})", e);
+            }
+        }
+        if let Err(e) = self.libos.close(self.sockqd) {
+            println!("ERROR: close() failed (error={:?})", e);
+        }
+    }
+}
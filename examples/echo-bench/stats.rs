@@ -0,0 +1,124 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use anyhow::Result;
+use histogram::Histogram;
+use std::time::Duration;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Grouping power of the histogram, which bounds the relative error of percentiles to 2^-7 (less than 1%).
+const GROUPING_POWER: u8 = 7;
+
+/// Maximum value power of the histogram, so that latencies up to 2^64 nanoseconds are recorded.
+const MAX_VALUE_POWER: u8 = 64;
+
+/// Percentiles that are reported.
+const PERCENTILES: [f64; 5] = [50.0, 90.0, 99.0, 99.9, 99.99];
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Latency histogram and counters of a benchmark run.
+pub struct Stats {
+    /// Latencies of the requests that completed (in nanoseconds).
+    histogram: Histogram,
+    /// Number of requests that were issued.
+    nsent: u64,
+    /// Number of requests that completed.
+    ncompleted: u64,
+    /// Lowest latency (in nanoseconds).
+    min: u64,
+    /// Highest latency (in nanoseconds).
+    max: u64,
+    /// Sum of latencies (in nanoseconds).
+    sum: u128,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Stats {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            histogram: Histogram::new(GROUPING_POWER, MAX_VALUE_POWER)?,
+            nsent: 0,
+            ncompleted: 0,
+            min: u64::MAX,
+            max: 0,
+            sum: 0,
+        })
+    }
+
+    /// Records that a request was issued.
+    pub fn record_sent(&mut self) {
+        self.nsent += 1;
+    }
+
+    /// Records the latency of a request that completed.
+    pub fn record_completed(&mut self, latency: Duration) -> Result<()> {
+        let latency: u64 = latency.as_nanos() as u64;
+        self.histogram.increment(latency)?;
+        self.ncompleted += 1;
+        self.min = self.min.min(latency);
+        self.max = self.max.max(latency);
+        self.sum += latency as u128;
+        Ok(())
+    }
+
+    /// Prints a one-line summary of the requests that completed within [elapsed].
+    pub fn log(&self, elapsed: Duration) -> Result<()> {
+        println!(
+            "INFO: {} requests, {:.2} rps, p50 {} ns, p99 {} ns",
+            self.ncompleted,
+            self.ncompleted as f64 / elapsed.as_secs_f64(),
+            self.percentile(50.0)?,
+            self.percentile(99.0)?,
+        );
+        Ok(())
+    }
+
+    /// Prints the report of a run that lasted [elapsed]. The last line is machine-readable, so that scripts can
+    /// compare runs to detect regressions.
+    pub fn report(&self, elapsed: Duration) -> Result<()> {
+        let rps: f64 = self.ncompleted as f64 / elapsed.as_secs_f64();
+        let mean: u64 = match self.ncompleted {
+            0 => 0,
+            n => (self.sum / n as u128) as u64,
+        };
+        let min: u64 = if self.ncompleted == 0 { 0 } else { self.min };
+        println!(
+            "INFO: {} requests sent, {} completed, {} outstanding in {:?}",
+            self.nsent,
+            self.ncompleted,
+            self.nsent - self.ncompleted,
+            elapsed
+        );
+        println!("INFO: throughput {:.2} rps", rps);
+        println!("INFO: latency min {} ns, mean {} ns, max {} ns", min, mean, self.max);
+        let mut result: String = format!("RESULT rps={:.2} min={} mean={} max={}", rps, min, mean, self.max);
+        for percentile in PERCENTILES {
+            let value: u64 = self.percentile(percentile)?;
+            println!("INFO: latency p{} {} ns", percentile, value);
+            result.push_str(&format!(" p{}={}", percentile, value));
+        }
+        println!("{}", result);
+        Ok(())
+    }
+
+    /// Gets the lower bound of the bucket of [percentile], or zero if no request completed.
+    fn percentile(&self, percentile: f64) -> Result<u64> {
+        if self.ncompleted == 0 {
+            return Ok(0);
+        }
+        Ok(self.histogram.percentile(percentile)?.start())
+    }
+}
@@ -22,6 +22,7 @@ all: all-examples
 	cp -f $(BUILD_DIR)/examples/tcp-close $(BINDIR)/examples/rust/tcp-close.$(EXEC_SUFFIX)
 	cp -f $(BUILD_DIR)/examples/pipe-open $(BINDIR)/examples/rust/pipe-open.$(EXEC_SUFFIX)
 	cp -f $(BUILD_DIR)/examples/tcp-wait $(BINDIR)/examples/rust/tcp-wait.$(EXEC_SUFFIX)
+	cp -f $(BUILD_DIR)/examples/echo-bench $(BINDIR)/examples/rust/echo-bench.$(EXEC_SUFFIX)
 
 all-examples:
 	@echo "$(CARGO) build --examples $(CARGO_FEATURES) $(CARGO_FLAGS)"
@@ -44,3 +45,4 @@ clean:
 	@rm -rf $(BINDIR)/examples/rust/tcp-close.$(EXEC_SUFFIX)
 	@rm -rf $(BINDIR)/examples/rust/pipe-open.$(EXEC_SUFFIX)
 	@rm -rf $(BINDIR)/examples/rust/tcp-wait.$(EXEC_SUFFIX)
+	@rm -rf $(BINDIR)/examples/rust/echo-bench.$(EXEC_SUFFIX)
@@ -14,6 +14,7 @@ all: all-examples
 	copy /Y $(BUILD_DIR)\examples\tcp-pktgen.exe $(BINDIR)\examples\rust\tcp-pktgen.exe
 	copy /Y $(BUILD_DIR)\examples\tcp-push-pop.exe $(BINDIR)\examples\rust\tcp-push-pop.exe
 	copy /Y $(BUILD_DIR)\examples\tcp-ping-pong.exe $(BINDIR)\examples\rust\tcp-ping-pong.exe
+	copy /Y $(BUILD_DIR)\examples\echo-bench.exe $(BINDIR)\examples\rust\echo-bench.exe
 
 all-examples:
 	@echo "$(CARGO) build --examples $(CARGO_FEATURES) $(CARGO_FLAGS)"
@@ -31,3 +32,4 @@ clean:
 	IF EXIST $(BINDIR)\examples\rust\tcp-pktgen.exe del /S /Q $(BINDIR)\examples\rust\tcp-pktgen.exe
 	IF EXIST $(BINDIR)\examples\rust\tcp-push-pop.exe del /S /Q $(BINDIR)\examples\rust\tcp-push-pop.exe
 	IF EXIST $(BINDIR)\examples\rust\tcp-ping-pong.exe del /S /Q $(BINDIR)\examples\rust\tcp-ping-pong.exe
+	IF EXIST $(BINDIR)\examples\rust\echo-bench.exe del /S /Q $(BINDIR)\examples\rust\echo-bench.exe
@@ -138,6 +138,9 @@ impl TcpEchoServer {
                 demi_opcode_t::DEMI_OPC_FAILED => self.handle_fail(&qr)?,
                 demi_opcode_t::DEMI_OPC_INVALID => self.handle_unexpected("invalid", &qr)?,
                 demi_opcode_t::DEMI_OPC_CLOSE => self.handle_unexpected("close", &qr)?,
+                demi_opcode_t::DEMI_OPC_TASK => self.handle_unexpected("task", &qr)?,
+                demi_opcode_t::DEMI_OPC_SPLICE => self.handle_unexpected("splice", &qr)?,
+                demi_opcode_t::DEMI_OPC_POP_MANY => self.handle_unexpected("pop_many", &qr)?,
                 demi_opcode_t::DEMI_OPC_CONNECT => self.handle_unexpected("connect", &qr)?,
             }
         }
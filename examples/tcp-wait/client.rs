@@ -84,7 +84,7 @@ impl TcpClient {
 
             // Wait for push().
             match self.libos.wait(push_qt, Some(DEFAULT_TIMEOUT)) {
-                Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_PUSH && qr.qr_ret == 0 => {},
+                Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_PUSH => {},
                 Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_FAILED && qr.qr_ret == libc::ECANCELED as i64 => {},
                 Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_FAILED && qr.qr_ret == libc::EBADF as i64 => {},
                 _ => anyhow::bail!("wait() should succeed with push() after async_close()"),
@@ -149,7 +149,7 @@ impl TcpClient {
 
             // Wait for push().
             match self.libos.wait(push_qt, Some(DEFAULT_TIMEOUT)) {
-                Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_PUSH && qr.qr_ret == 0 => {},
+                Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_PUSH => {},
                 Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_FAILED && qr.qr_ret == libc::ECANCELED as i64 => {},
                 Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_FAILED && qr.qr_ret == libc::EBADF as i64 => {},
                 _ => anyhow::bail!("wait() should not succeed with push() after close()"),
@@ -208,7 +208,7 @@ impl TcpClient {
 
             // Wait for push().
             match self.libos.wait(push_qt, Some(DEFAULT_TIMEOUT)) {
-                Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_PUSH && qr.qr_ret == 0 => {},
+                Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_PUSH => {},
                 Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_FAILED && qr.qr_ret == libc::ECANCELED as i64 => {},
                 Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_FAILED && qr.qr_ret == libc::EBADF as i64 => {},
                 _ => anyhow::bail!("wait() should succeed with push() after issuing async_close()"),
@@ -14,6 +14,7 @@
 #define _In_reads_bytes_(b)
 #define _Out_
 #define _Out_writes_to_(s, c)
+#define _Out_writes_z_(s)
 #define _Deref_pre_z_
 #endif
 
@@ -6,6 +6,7 @@
 
 #include <demi/types.h>
 #include <stddef.h>
+#include <time.h>
 #include <demi/cc.h>
 
 #ifdef __linux__
@@ -25,6 +26,13 @@ extern "C"
     /**
      * @brief Initializes Demikernel.
      *
+     * @details A child created by fork() does not inherit the network LibOS of its parent, which keeps owning the
+     * network device and its sockets: every call fails with ENOTSUP in the child until this function is called again,
+     * which initializes a new LibOS for the child. Catnip can only be initialized once per process, so a child of a
+     * process that uses it must exec() a new program first. Memory queues remain usable in the child, as they live in
+     * shared memory, and each process should keep only the queues that it owns, releasing the others with
+     * demi_release_pipe().
+     *
      * @param argc Number of arguments.
      * @param argv Argument values.
      *
@@ -33,6 +41,21 @@ extern "C"
     ATTR_NONNULL(2)
     extern int demi_init(_In_ int argc, _In_reads_(argc) _Deref_pre_z_ char *const argv[]);
 
+    /**
+     * @brief Shuts Demikernel down, draining all connections.
+     *
+     * @details New connections are refused right away, while every other I/O queue is closed gracefully, so that TCP
+     * connections send a FIN and wait for their peer to close as well. Queues that are still open once @p grace
+     * expires are aborted, which resets their TCP connections. Pending transmissions are then flushed and the
+     * resources of the network device are released before this function returns. Demikernel may be initialized again
+     * afterwards, and all I/O queue descriptors, queue tokens and scatter-gather arrays are invalid.
+     *
+     * @param grace Time that connections are given to close, or NULL to wait until they do.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_shutdown_all(_In_opt_ const struct timespec *grace);
+
 
     /**
      * @brief Creates a new memory I/O queue.
@@ -56,6 +79,34 @@ extern "C"
     ATTR_NONNULL(1, 2)
     extern int demi_open_pipe(_Out_ int *memqd_out, _In_z_ const char *name);
 
+    /**
+     * @brief Opens a file I/O queue. Pushes to the queue write the file and pops read it, asynchronously, from the
+     * position where the previous operation left off.
+     *
+     * @param qd_out Storage location for the file I/O queue descriptor.
+     * @param path   Path of the target file.
+     * @param flags  Open flags: O_RDONLY, O_WRONLY or O_RDWR, optionally along with O_CREAT, O_EXCL, O_TRUNC and
+     *               O_APPEND.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1, 2)
+    extern int demi_open_file(_Out_ int *qd_out, _In_z_ const char *path, _In_ int flags);
+
+    /**
+     * @brief Opens a block I/O queue on a namespace of an NVMe device, which SPDK drives from user space. Pushes to the
+     * queue write whole sectors of the namespace and pops read them, asynchronously, from the position where the
+     * previous operation left off. Only available when Demikernel is built with SPDK support.
+     *
+     * @param qd_out Storage location for the block I/O queue descriptor.
+     * @param trid   SPDK transport ID of the NVMe controller, such as "trtype:PCIe traddr:0000:04:00.0".
+     * @param nsid   Identifier of the target namespace.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1, 2)
+    extern int demi_open_block(_Out_ int *qd_out, _In_z_ const char *trid, _In_ uint32_t nsid);
+
     /**
      * @brief Creates a socket I/O queue.
      *
@@ -91,6 +142,18 @@ extern "C"
     ATTR_NONNULL(2)
     extern int demi_bind(_In_ int sockqd, _In_reads_bytes_(size) const struct sockaddr *addr, _In_ socklen_t size);
 
+    /**
+     * @brief Binds a name to a socket I/O queue. Processes on the same host connect to the socket with
+     * demi_connect_name(), over shared memory, instead of through the network stack.
+     *
+     * @param sockqd I/O queue descriptor of the target socket.
+     * @param name   Bind name.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(2)
+    extern int demi_bind_name(_In_ int sockqd, _In_z_ const char *name);
+
     /**
      * @brief Asynchronously accepts a connection request on a socket I/O queue.
      *
@@ -102,6 +165,18 @@ extern "C"
     ATTR_NONNULL(1)
     extern int demi_accept(_Out_ demi_qtoken_t *qt_out, _In_ int sockqd);
 
+    /**
+     * @brief Asynchronously accepts a connection request on a socket I/O queue, unless @p timeout passes first.
+     *
+     * @param qt_out  Store location for I/O queue token.
+     * @param sockqd  I/O queue descriptor of the target socket.
+     * @param timeout Time after which the operation completes with ETIMEDOUT, or NULL to never time out.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1)
+    extern int demi_timedaccept(_Out_ demi_qtoken_t *qt_out, _In_ int sockqd, _In_opt_ const struct timespec *timeout);
+
     /**
      * @brief Asynchronously initiates a connection on a socket I/O queue.
      *
@@ -116,6 +191,607 @@ extern "C"
     extern int demi_connect(_Out_ demi_qtoken_t *qt_out, _In_ int sockqd,
                             _In_reads_bytes_(size) const struct sockaddr *addr, _In_ socklen_t size);
 
+    /**
+     * @brief Asynchronously initiates a connection on a socket I/O queue, unless @p timeout passes first. The socket
+     * should be closed after a connection attempt that timed out.
+     *
+     * @param qt_out  Store location for I/O queue token.
+     * @param sockqd  I/O queue descriptor of the target socket.
+     * @param addr    Address of remote host.
+     * @param size    Effective size of the socked address data structure.
+     * @param timeout Time after which the operation completes with ETIMEDOUT, or NULL to never time out.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1, 3)
+    extern int demi_timedconnect(_Out_ demi_qtoken_t *qt_out, _In_ int sockqd,
+                                 _In_reads_bytes_(size) const struct sockaddr *addr, _In_ socklen_t size,
+                                 _In_opt_ const struct timespec *timeout);
+
+    /**
+     * @brief Asynchronously initiates a connection on a socket I/O queue to the socket that is bound to @p name with
+     * demi_bind_name().
+     *
+     * @param qt_out Store location for I/O queue token.
+     * @param sockqd I/O queue descriptor of the target socket.
+     * @param name   Name of the remote socket.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1, 3)
+    extern int demi_connect_name(_Out_ demi_qtoken_t *qt_out, _In_ int sockqd, _In_z_ const char *name);
+
+    /**
+     * @brief Starts a TLS 1.3 session as a client on a connected socket I/O queue. Data that is pushed to or popped
+     * from the new I/O queue descriptor is transparently encrypted or decrypted. The handshake runs along with the
+     * first push or pop, and @p sockqd is no longer valid on success.
+     *
+     * @param qd_out Store location for the I/O queue descriptor of the TLS session.
+     * @param sockqd I/O queue descriptor of the target socket.
+     * @param config Configuration of the TLS session.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1, 3)
+    extern int demi_tls_connect(_Out_ int *qd_out, _In_ int sockqd, _In_ const demi_tls_config_t *config);
+
+    /**
+     * @brief Starts a TLS 1.3 session as a server on an accepted socket I/O queue. Data that is pushed to or popped
+     * from the new I/O queue descriptor is transparently encrypted or decrypted. The handshake runs along with the
+     * first push or pop, and @p sockqd is no longer valid on success.
+     *
+     * @param qd_out Store location for the I/O queue descriptor of the TLS session.
+     * @param sockqd I/O queue descriptor of the target socket.
+     * @param config Configuration of the TLS session.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1, 3)
+    extern int demi_tls_accept(_Out_ int *qd_out, _In_ int sockqd, _In_ const demi_tls_config_t *config);
+
+    /**
+     * @brief Frames the data of a connected socket I/O queue into HTTP/1.1 messages, as a client. Each push to the new
+     * I/O queue descriptor must carry exactly one request and each pop returns exactly one response, as its head
+     * followed by its body. Chunked bodies are decoded and their head carries a Content-Length instead. Messages must
+     * fit in 64 KiB. Framing runs over TLS if @p sockqd runs a TLS session, and @p sockqd is no longer valid on success.
+     *
+     * @param qd_out Store location for the I/O queue descriptor of the HTTP connection.
+     * @param sockqd I/O queue descriptor of the target socket.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1)
+    extern int demi_http_connect(_Out_ int *qd_out, _In_ int sockqd);
+
+    /**
+     * @brief Frames the data of an accepted socket I/O queue into HTTP/1.1 messages, as a server. Each pop from the new
+     * I/O queue descriptor returns exactly one request and each push must carry exactly one response. Messages are laid
+     * out as in demi_http_connect(), and @p sockqd is no longer valid on success.
+     *
+     * @param qd_out Store location for the I/O queue descriptor of the HTTP connection.
+     * @param sockqd I/O queue descriptor of the target socket.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1)
+    extern int demi_http_accept(_Out_ int *qd_out, _In_ int sockqd);
+
+    /**
+     * @brief Frames the data of a connected socket I/O queue with a codec, like length prefixes, lines, RESP or the
+     * binary protocol of memcached. Each pop from the new I/O queue descriptor returns exactly one frame, which is a
+     * slice of the received data unless it spans several receives, and each push must carry exactly one frame. Frames
+     * must fit in 64 KiB. Framing runs over TLS if @p sockqd runs a TLS session, and @p sockqd is no longer valid on
+     * success.
+     *
+     * @param qd_out Store location for the I/O queue descriptor of the framed connection.
+     * @param sockqd I/O queue descriptor of the target socket.
+     * @param codec  Codec that frames the data.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1, 3)
+    extern int demi_frame(_Out_ int *qd_out, _In_ int sockqd, _In_ const demi_codec_t *codec);
+
+    /**
+     * @brief Starts a DTLS 1.3 association with a remote server on a bound UDP socket I/O queue. Datagrams that are
+     * pushed to or popped from the new I/O queue descriptor are transparently encrypted or decrypted. The handshake
+     * runs along with the first push or pop, and @p sockqd is no longer valid on success.
+     *
+     * @param qd_out Store location for the I/O queue descriptor of the DTLS association.
+     * @param sockqd I/O queue descriptor of the target socket.
+     * @param saddr  Address of the remote server.
+     * @param size   Effective size of the socket address data structure.
+     * @param config Configuration of the DTLS association.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1, 3, 5)
+    extern int demi_dtls_connect(_Out_ int *qd_out, _In_ int sockqd, _In_reads_bytes_(size) const struct sockaddr *saddr,
+                                 _In_ socklen_t size, _In_ const demi_tls_config_t *config);
+
+    /**
+     * @brief Waits for a DTLS 1.3 client on a bound UDP socket I/O queue. Clients must echo a cookie that proves their
+     * address before the server does any work on the handshake. The new I/O queue descriptor only talks to the first
+     * client that completes this exchange, and @p sockqd is no longer valid on success.
+     *
+     * @param qd_out Store location for the I/O queue descriptor of the DTLS association.
+     * @param sockqd I/O queue descriptor of the target socket.
+     * @param config Configuration of the DTLS association.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1, 3)
+    extern int demi_dtls_accept(_Out_ int *qd_out, _In_ int sockqd, _In_ const demi_tls_config_t *config);
+
+    /**
+     * @brief Runs QUIC over a bound UDP socket I/O queue. The new I/O queue descriptor connects to a QUIC server with
+     * demi_connect(), or listens for QUIC clients with demi_listen() and demi_accept(). Calling demi_accept() on a
+     * connection accepts the streams that the peer opens. @p sockqd is no longer valid on success.
+     *
+     * @param qd_out Store location for the I/O queue descriptor of the QUIC socket.
+     * @param sockqd I/O queue descriptor of the target UDP socket.
+     * @param config Configuration of the QUIC connections.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1, 3)
+    extern int demi_quic_socket(_Out_ int *qd_out, _In_ int sockqd, _In_ const demi_quic_config_t *config);
+
+    /**
+     * @brief Opens a bidirectional stream on an established QUIC connection. Data is pushed to and popped from the
+     * stream through the new I/O queue descriptor, and a pop of zero bytes means that the peer ended the stream.
+     *
+     * @param qd_out Store location for the I/O queue descriptor of the stream.
+     * @param qd     I/O queue descriptor of the target QUIC connection.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1)
+    extern int demi_quic_open_stream(_Out_ int *qd_out, _In_ int qd);
+
+    /**
+     * @brief Creates an I/O queue that runs a reliable connection over RDMA, such as RoCEv2. The queue is bound,
+     * listens, accepts and connects with IPv4 addresses like a socket I/O queue, and each push and pop sends and
+     * receives one whole message of up to 8 KiB. A pop of zero bytes means that the peer disconnected. Only available
+     * when Demikernel is built with RDMA support.
+     *
+     * @param qd_out Store location for the I/O queue descriptor.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1)
+    extern int demi_rdma_socket(_Out_ int *qd_out);
+
+    /**
+     * @brief Lets the peer of a connected RDMA I/O queue read and write the memory of a scatter-gather array with
+     * one-sided verbs, without involving the local CPU. The region is handed to the peer, for instance in a message,
+     * and stays valid until it is withdrawn or the queue is closed. Only available when Demikernel is built with RDMA
+     * support.
+     *
+     * @param region_out Store location for the exposed region.
+     * @param qd         I/O queue descriptor of the target RDMA connection.
+     * @param sga        Scatter-gather array that holds the exposed memory.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1, 3)
+    extern int demi_rdma_expose(_Out_ demi_rdma_region_t *region_out, _In_ int qd, _In_ const demi_sgarray_t *sga);
+
+    /**
+     * @brief Withdraws a region that was exposed to the peer of an RDMA I/O queue. Only available when Demikernel is
+     * built with RDMA support.
+     *
+     * @param qd   I/O queue descriptor of the target RDMA connection.
+     * @param rkey Remote key of the target region.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_rdma_withdraw(_In_ int qd, _In_ uint32_t rkey);
+
+    /**
+     * @brief Asynchronously writes a scatter-gather array to memory that the peer of an RDMA I/O queue exposed. The
+     * operation completes like a push. Only available when Demikernel is built with RDMA support.
+     *
+     * @param qtok_out Store location for the queue token.
+     * @param qd       I/O queue descriptor of the target RDMA connection.
+     * @param sga      Scatter-gather array to write.
+     * @param region   Remote region that the data is written to.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1, 3, 4)
+    extern int demi_rdma_write(_Out_ demi_qtoken_t *qtok_out, _In_ int qd, _In_ const demi_sgarray_t *sga,
+                               _In_ const demi_rdma_region_t *region);
+
+    /**
+     * @brief Asynchronously reads memory that the peer of an RDMA I/O queue exposed. The operation completes like a
+     * pop that returns the data of the region. Only available when Demikernel is built with RDMA support.
+     *
+     * @param qtok_out Store location for the queue token.
+     * @param qd       I/O queue descriptor of the target RDMA connection.
+     * @param region   Remote region to read.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1, 3)
+    extern int demi_rdma_read(_Out_ demi_qtoken_t *qtok_out, _In_ int qd, _In_ const demi_rdma_region_t *region);
+
+    /**
+     * @brief Adds a static security association of transport-mode ESP. IPsec must be enabled in the configuration
+     * file, so that the network stack does not offload checksums or segmentation of the protected traffic.
+     *
+     * @param sa Security association.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1)
+    extern int demi_ipsec_add_sa(_In_ const demi_ipsec_sa_t *sa);
+
+    /**
+     * @brief Removes a security association of transport-mode ESP.
+     *
+     * @param spi       Security parameter index of the security association.
+     * @param direction Direction of the security association.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_ipsec_remove_sa(_In_ uint32_t spi, _In_ uint32_t direction);
+
+    /**
+     * @brief Adds a route to the routing table. Traffic toward an IPv4 address goes through the route with the longest
+     * prefix that contains the address: to the gateway of the route, or straight to the address if the route has no
+     * gateway. Addresses that no route contains are unreachable.
+     *
+     * @param route Route.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1)
+    extern int demi_route_add(_In_ const demi_route_t *route);
+
+    /**
+     * @brief Removes a route from the routing table.
+     *
+     * @param destination Address of the subnet of the route, in network byte order.
+     * @param prefix_len  Length of the prefix of the subnet of the route, in bits.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_route_remove(_In_ uint32_t destination, _In_ uint32_t prefix_len);
+
+    /**
+     * @brief Sets a tunable at runtime.
+     *
+     * @details Tunables are named after their dotted path in the "tunables" section of the configuration file.
+     * "log.level" takes a logging specification in the syntax of the RUST_LOG environment variable, and "log.sink"
+     * tells whether log records are written to the standard error ("stderr") or kept in an in-memory flight recorder
+     * ("ring"), which is dumped on panic or with demi_dump_log(). "arp.*" (cache_ttl_ms, request_timeout_ms,
+     * retry_count) and "tcp.*" (handshake_retries, handshake_timeout_ms, receive_window_size, window_scale,
+     * ack_delay_timeout_ms) tune the network stack of LibOSes that have one.
+     * Changes to TCP tunables only apply to connections that are created afterwards. "arp.max_pending" bounds the
+     * packets that wait on the resolution of an address, past which sends fail with ENOBUFS, and "arp.request_rate"
+     * the requests sent per second to each destination (0 for no limit). The read-only "arp.pending_drops" and
+     * "arp.requests_suppressed" count the packets and requests that were held back.
+     * "icmp.rate" and "icmp.burst" limit the ICMP messages that the network stack generates, 1000 per second in bursts
+     * of 50 by default, while "icmp.*_rate" (echo_reply_rate, unreachable_rate, time_exceeded_rate) limit each class of
+     * them, and "icmp.disabled" takes a comma-separated list of classes that are never sent (e.g. "echo_reply").
+     * "admission.*" (connection_rate, connection_burst, datagram_rate, datagram_burst) limits the connection requests
+     * and datagrams that each source gets through per second, while "admission.blocklist" takes a comma-separated list
+     * of subnets (e.g. "10.0.0.0/8,192.0.2.7") whose hosts are refused. Refused traffic is dropped without an answer.
+     * "flows.max_entries" caps the TCP connections and UDP flows that the network stack tracks. Once the cap is
+     * reached, flows idle for longer than "flows.idle_timeout_ms" are evicted first, then "flows.policy" either evicts
+     * the least recently used flow ("evict_lru", which aborts evicted connections) or refuses the new one ("refuse").
+     * The read-only "flows.entries", "flows.evictions" and "flows.refusals" report the state of the table.
+     * "wait.policy" selects whether demi_wait() busy-polls ("busy_poll"), sleeps once nothing has been ready to run for
+     * "wait.spin_micros" ("hybrid"), or sleeps right away ("block", the default) until epoll events on catnap or a
+     * doorbell on catmem wake it up. LibOSes that busy-poll a network device never sleep.
+     *
+     * @param name  Name of the tunable.
+     * @param value New value of the tunable.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1, 2)
+    extern int demi_set_config(_In_z_ const char *name, _In_z_ const char *value);
+
+    /**
+     * @brief Gets the current value of a tunable.
+     *
+     * @param name      Name of the tunable.
+     * @param value_out Storage location for the value of the tunable, as a null-terminated string.
+     * @param size      Size of the storage location, in bytes.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     * ERANGE is returned if the value does not fit in the storage location.
+     */
+    ATTR_NONNULL(1, 2)
+    extern int demi_get_config(_In_z_ const char *name, _Out_writes_z_(size) char *value_out, _In_ size_t size);
+
+    /**
+     * @brief Reloads the tunables from the configuration file.
+     *
+     * @details The other parameters of the configuration file only take effect at startup. Setting
+     * "tunables.reload_on_sighup" in the configuration file also reloads the tunables whenever the process receives
+     * SIGHUP.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_reload_config(void);
+
+    /**
+     * @brief Dumps the in-memory flight recorder of log records.
+     *
+     * @details When "tunables.log.sink" is "ring", log records are kept in an in-memory ring of the most recent ones
+     * instead of being written to the standard error, so that logging does not perturb latencies. The ring is dumped
+     * to the standard error on panic, and with this call on demand. Records are written from the oldest to the newest
+     * and taken out of the ring.
+     *
+     * @param fd File descriptor that the records are written to.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_dump_log(_In_ int fd);
+
+    /**
+     * @brief Binds the thread that runs the engine to cores and sets how the kernel schedules it.
+     *
+     * @details The engine runs on the thread that initialized Demikernel. Only the fields whose DEMI_BINDING_* flag
+     * is set are changed. The engine may also be bound at startup, through the "engine" section of the configuration
+     * file. Real-time priorities and negative niceness usually require privileges, in which case EPERM is returned.
+     *
+     * @param binding Binding of the engine.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1)
+    extern int demi_bind_engine(_In_ const demi_binding_t *binding);
+
+    /**
+     * @brief Gets how the thread that runs the engine is bound to cores and scheduled by the kernel.
+     *
+     * @param binding_out Storage location for the binding.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1)
+    extern int demi_get_engine_binding(_Out_ demi_binding_t *binding_out);
+
+    /**
+     * @brief Gets the number of incoming packets that were dropped, for each reason.
+     *
+     * @details Drops are counted for the whole network stack and for each TCP and UDP socket. Only LibOSes that
+     * have a network stack count drops.
+     *
+     * @param qd        Target I/O queue descriptor, or a negative value for the whole network stack.
+     * @param stats_out Storage location for the counts.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(2)
+    extern int demi_get_drop_stats(_In_ int qd, _Out_ demi_drop_stats_t *stats_out);
+
+    /**
+     * @brief Gets where the network device placed its buffer pools and the state of its queues, relative to the NUMA
+     * node of the device.
+     *
+     * @details Only catnip places them on purpose, as set in the "dpdk.numa" section of the configuration file.
+     * Allocations that land on another node than the one of the device slow down every packet.
+     *
+     * @param stats_out Storage location for the statistics.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1)
+    extern int demi_get_numa_stats(_Out_ demi_numa_stats_t *stats_out);
+
+    /**
+     * @brief Pops the oldest record from the error queue of a socket.
+     *
+     * @details The error queue is enabled by setting IP_RECVERR on the socket. It holds the most recent packets that
+     * were dropped on their way to the socket, so that the application can learn why its traffic disappears.
+     *
+     * @param qd         Target I/O queue descriptor.
+     * @param record_out Storage location for the record.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     * EAGAIN is returned if the error queue is empty.
+     */
+    ATTR_NONNULL(2)
+    extern int demi_pop_drop(_In_ int qd, _Out_ demi_drop_record_t *record_out);
+
+    /**
+     * @brief Pops the oldest transmit timestamp of a UDP socket.
+     *
+     * @details Transmit timestamps are enabled by setting SO_TIMESTAMPING on the socket with
+     * SOF_TIMESTAMPING_TX_SOFTWARE, SOF_TIMESTAMPING_TX_HARDWARE or both. The socket then stamps each datagram that it sends, and holds the most
+     * recent timestamps. Hardware timestamps are only reported by devices that support them. Receive timestamps are
+     * always reported in the ancillary data of popped datagrams.
+     *
+     * @param qd            Target I/O queue descriptor.
+     * @param timestamp_out Storage location for the timestamp.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     * EAGAIN is returned if no datagram was stamped since the last call.
+     */
+    ATTR_NONNULL(2)
+    extern int demi_pop_tx_timestamp(_In_ int qd, _Out_ demi_tx_timestamp_t *timestamp_out);
+
+    /**
+     * @brief Starts a PTP (IEEE 1588) client, which synchronizes a clock of the network stack to a master.
+     *
+     * @details The client runs an ordinary clock in the slave state over UDP ports 319 and 320 of the local address,
+     * and measures its offset from the master with the delay request-response mechanism. Since multicast is not
+     * received, the master must send to the unicast address of the client: the client asks it to, unless
+     * DEMI_PTP_NO_NEGOTIATION is set. Messages are stamped in software. The client runs until demi_ptp_stop().
+     *
+     * @param config Configuration of the client.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     * EALREADY is returned if a client is already running.
+     */
+    ATTR_NONNULL(1)
+    extern int demi_ptp_start(_In_ const demi_ptp_config_t *config);
+
+    /**
+     * @brief Stops the PTP client and closes its sockets.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     * ENOENT is returned if no client is running.
+     */
+    extern int demi_ptp_stop(void);
+
+    /**
+     * @brief Gets the current time of the clock that the PTP client steers, in UTC.
+     *
+     * @param ts_out Storage location for the time since the Unix epoch.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     * EAGAIN is returned if the clock was not set to the time of the master yet.
+     */
+    ATTR_NONNULL(1)
+    extern int demi_ptp_gettime(_Out_ struct timespec *ts_out);
+
+    /**
+     * @brief Converts a time of the clock that the PTP client steers into local time, e.g. to wait until a deadline
+     * that is set by the master with timeouts that are relative to the local clock.
+     *
+     * @param ts        Time of the clock since the Unix epoch.
+     * @param local_out Storage location for the local time since the Unix epoch.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     * EAGAIN is returned if the clock was not set to the time of the master yet.
+     */
+    ATTR_NONNULL(1, 2)
+    extern int demi_ptp_to_local(_In_ const struct timespec *ts, _Out_ struct timespec *local_out);
+
+    /**
+     * @brief Gets the state of the clock that the PTP client steers: whether it is locked to the master, its last
+     * offset from the master, the delay of the path and the correction of its rate.
+     *
+     * @param status_out Storage location for the state.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1)
+    extern int demi_ptp_get_status(_Out_ demi_ptp_status_t *status_out);
+
+    /**
+     * @brief Gets a snapshot of the state of a TCP connection.
+     *
+     * @details The snapshot holds the congestion window and slow start threshold, the round-trip time estimates,
+     * retransmission counts, the depths of the send and receive queues, whether a zero window from the peer stalls
+     * the connection and the state of the state machine. Sockets that are not connected yet report their state with
+     * empty queues.
+     *
+     * @param qd       Target I/O queue descriptor.
+     * @param info_out Storage location for the snapshot.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(2)
+    extern int demi_get_tcp_info(_In_ int qd, _Out_ demi_tcp_info_t *info_out);
+
+    /**
+     * @brief Gets the recent retransmission and flow control events of a TCP connection.
+     *
+     * @details Each connection keeps a small ring of its most recent retransmission timeouts, fast retransmissions,
+     * back offs of the retransmission timer, window stalls and zero windows, stamped with the time since the
+     * connection was established, which helps to tell after the fact why a connection was slow or failed. The same
+     * timeline is logged when the connection closes. Events are laid out from the oldest to the newest, and only the
+     * most recent ones are laid out if they do not fit. Sockets that are not connected have no events.
+     *
+     * @param qd         Target I/O queue descriptor.
+     * @param events_out Storage location for the events.
+     * @param capacity   Number of events that fit in @p events_out.
+     * @param count_out  Storage location for the number of events that were laid out.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(4)
+    extern int demi_get_tcp_timeline(_In_ int qd, _Out_writes_to_(capacity, *count_out) demi_tcp_event_t *events_out,
+                                     _In_ size_t capacity, _Out_ size_t *count_out);
+
+
+    /**
+     * @brief Releases a memory queue that this process shares with another one after fork().
+     *
+     * @details The queue is handed off to the other process, which keeps using it, so neither the queue nor its peer
+     * are closed and the queue descriptor is invalid in this process afterwards. Pending operations on the queue
+     * should be cancelled beforehand.
+     *
+     * @param memqd Target memory queue descriptor.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_release_pipe(_In_ int memqd);
+
+    /**
+     * @brief Hands a TCP connection or memory queue off to another process.
+     *
+     * @details The queue is sent through @p channel, a connected Unix domain socket, to a process that takes it over
+     * with demi_import_queue(), so that a process may accept connections and dispatch them to worker processes. The
+     * queue descriptor is then released in this process, without closing the connection or memory queue. Pending
+     * operations on the queue should complete or be cancelled beforehand. On Catnap, data that was received but not
+     * popped yet is handed off along with the connection, whereas data that is still being pushed fails this call with
+     * EBUSY. Connections that use TLS or framing cannot be handed off, and neither can connections of LibOSes that
+     * run their own network stack, which fail this call with ENOTSUP.
+     *
+     * @param qd      Target I/O queue descriptor.
+     * @param channel Unix domain socket through which the queue is handed off.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_export_queue(_In_ int qd, _In_ int channel);
+
+    /**
+     * @brief Takes over a TCP connection or memory queue that another process handed off.
+     *
+     * @details This receives the queue that another process sent through @p channel with demi_export_queue(). It
+     * blocks until the queue arrives, unless @p channel is non-blocking.
+     *
+     * @param qd_out  Storage location for the I/O queue descriptor of the queue.
+     * @param channel Unix domain socket through which the queue is handed off.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1)
+    extern int demi_import_queue(_Out_ int *qd_out, _In_ int channel);
+
+    /**
+     * @brief Freezes a TCP connection into a blob that another instance of the LibOS may thaw.
+     *
+     * @details This captures the state of the connection, such as its sequence numbers, window scaling and the data
+     * that was received but not popped yet or pushed but not acknowledged yet, so that the connection survives a
+     * maintenance restart or moves to another engine with demi_thaw_queue(). The queue descriptor is then released
+     * without letting the peer know. Pending operations on the queue should complete or be cancelled beforehand, and
+     * segments of the connection should be steered away from this engine, which would otherwise reset it. Congestion
+     * control and retransmission timers start over once the connection is thawed, and socket options are not carried
+     * along. Connections that are closing, or that use TLS or framing, cannot be frozen. Only LibOSes that run their
+     * own network stack support this call, whereas the others fail it with ENOTSUP.
+     *
+     * @param sockqd   Target TCP connection.
+     * @param blob_out Storage location for the blob, which is allocated with malloc() and released with free().
+     * @param len_out  Storage location for the length of the blob, in bytes.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(2, 3)
+    extern int demi_freeze_queue(_In_ int sockqd, _Out_ void **blob_out, _Out_ size_t *len_out);
+
+    /**
+     * @brief Thaws a TCP connection that another instance of the LibOS froze.
+     *
+     * @details The local address of the connection should belong to this instance. Data that the peer did not
+     * acknowledge yet is sent again, and data that was received but not popped yet can be popped right away.
+     *
+     * @param qd_out Storage location for the I/O queue descriptor of the connection.
+     * @param blob   Blob that demi_freeze_queue() laid out.
+     * @param len    Length of the blob, in bytes.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1, 2)
+    extern int demi_thaw_queue(_Out_ int *qd_out, _In_reads_bytes_(len) const void *blob, _In_ size_t len);
 
     /**
      * @brief Closes an I/O queue descriptor.
@@ -129,6 +805,10 @@ extern "C"
     /**
      * @brief Asynchronously pushes a scatter-gather array to an I/O queue.
      *
+     * The push completes with the number of bytes that it accepted in qr_ret. Sockets with the SO_TXCOMPLETE option
+     * (0x1002 at level SOL_SOCKET) set complete their pushes only once the data is delivered, that is, once the peer
+     * acknowledges it on TCP.
+     *
      * @param qt_out Store location for I/O queue token.
      * @param qd     Target I/O queue descriptor.
      * @param sga    Scatter-gather array to push.
@@ -138,6 +818,20 @@ extern "C"
     ATTR_NONNULL(1, 3)
     extern int demi_push(_Out_ demi_qtoken_t *qt_out, _In_ int qd, _In_ const demi_sgarray_t *sga);
 
+    /**
+     * @brief Asynchronously pushes a scatter-gather array to an I/O queue, unless @p timeout passes first.
+     *
+     * @param qt_out  Store location for I/O queue token.
+     * @param qd      Target I/O queue descriptor.
+     * @param sga     Scatter-gather array to push.
+     * @param timeout Time after which the operation completes with ETIMEDOUT, or NULL to never time out.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1, 3)
+    extern int demi_timedpush(_Out_ demi_qtoken_t *qt_out, _In_ int qd, _In_ const demi_sgarray_t *sga,
+                              _In_opt_ const struct timespec *timeout);
+
     /**
      * @brief Asynchronously pushes a scatter-gather array to a socket I/O queue.
      *
@@ -153,6 +847,57 @@ extern "C"
     extern int demi_pushto(_Out_ demi_qtoken_t *qt_out, _In_ int sockqd, _In_ const demi_sgarray_t *sga,
                            _In_reads_bytes_(size) const struct sockaddr *dest_addr, _In_ socklen_t size);
 
+    /**
+     * @brief Asynchronously pushes a scatter-gather array to a socket I/O queue, unless @p timeout passes first.
+     *
+     * @param qt_out    Store location for I/O queue token.
+     * @param sockqd    I/O queue descriptor of the target socket.
+     * @param sga       Scatter-gather array to push.
+     * @param dest_addr Address of destination host.
+     * @param size      Effective size of the socked address data structure.
+     * @param timeout   Time after which the operation completes with ETIMEDOUT, or NULL to never time out.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1, 3, 4)
+    extern int demi_timedpushto(_Out_ demi_qtoken_t *qt_out, _In_ int sockqd, _In_ const demi_sgarray_t *sga,
+                                _In_reads_bytes_(size) const struct sockaddr *dest_addr, _In_ socklen_t size,
+                                _In_opt_ const struct timespec *timeout);
+
+    /**
+     * @brief Asynchronously moves data from a file, block or TCP socket I/O queue to a TCP socket I/O queue, without
+     * copying it through application buffers. The operation completes with DEMI_OPC_SPLICE and the number of bytes
+     * that it moved in qr_ret, which is less than @p size if the end of the file was reached or the peer of a source
+     * socket closed the connection. It fails with EBADF if @p srcqd is closed before it completes. Proxies forward a
+     * whole connection by passing SIZE_MAX as @p size. Block I/O queues take a @p size that is a multiple of their
+     * sector size.
+     *
+     * @param qt_out Store location for I/O queue token.
+     * @param sockqd I/O queue descriptor of the target socket.
+     * @param srcqd  I/O queue descriptor of the source file, block device or socket.
+     * @param size   Number of bytes to move.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1)
+    extern int demi_splice(_Out_ demi_qtoken_t *qt_out, _In_ int sockqd, _In_ int srcqd, _In_ size_t size);
+
+    /**
+     * @brief Asynchronously moves data from a file, block or TCP socket I/O queue to a TCP socket I/O queue, unless
+     * @p timeout passes first.
+     *
+     * @param qt_out  Store location for I/O queue token.
+     * @param sockqd  I/O queue descriptor of the target socket.
+     * @param srcqd   I/O queue descriptor of the source file, block device or socket.
+     * @param size    Number of bytes to move.
+     * @param timeout Time after which the operation completes with ETIMEDOUT, or NULL to never time out.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1)
+    extern int demi_timedsplice(_Out_ demi_qtoken_t *qt_out, _In_ int sockqd, _In_ int srcqd, _In_ size_t size,
+                                _In_opt_ const struct timespec *timeout);
+
     /**
      * @brief Asynchronously pops a scatter-gather array from an I/O queue.
      *
@@ -164,6 +909,46 @@ extern "C"
     ATTR_NONNULL(1)
     extern int demi_pop(_Out_ demi_qtoken_t *qt_out, _In_ int qd);
 
+    /**
+     * @brief Asynchronously pops a scatter-gather array from an I/O queue, unless @p timeout passes first.
+     *
+     * @param qt_out  Store location for I/O queue token.
+     * @param qd      Target I/O queue descriptor.
+     * @param timeout Time after which the operation completes with ETIMEDOUT, or NULL to never time out.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1)
+    extern int demi_timedpop(_Out_ demi_qtoken_t *qt_out, _In_ int qd, _In_opt_ const struct timespec *timeout);
+
+    /**
+     * @brief Asynchronously pops, in one completion, up to @p max datagrams that a socket already received, or the
+     * bytes of a stream coalesced into one scatter-gather array. The operation waits only for the first datagram or
+     * bytes to arrive, and completes with DEMI_OPC_POP_MANY. Release the batch with demi_sgafree_batch().
+     *
+     * @param qt_out Store location for I/O queue token.
+     * @param qd     Target I/O queue descriptor.
+     * @param max    Maximum number of datagrams to pop.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1)
+    extern int demi_pop_many(_Out_ demi_qtoken_t *qt_out, _In_ int qd, _In_ uint32_t max);
+
+    /**
+     * @brief Asynchronously pops like demi_pop_many(), unless @p timeout passes before anything arrives.
+     *
+     * @param qt_out  Store location for I/O queue token.
+     * @param qd      Target I/O queue descriptor.
+     * @param max     Maximum number of datagrams to pop.
+     * @param timeout Time after which the operation completes with ETIMEDOUT, or NULL to never time out.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    ATTR_NONNULL(1)
+    extern int demi_timedpop_many(_Out_ demi_qtoken_t *qt_out, _In_ int qd, _In_ uint32_t max,
+                                  _In_opt_ const struct timespec *timeout);
+
 #ifdef __cplusplus
 }
 #endif
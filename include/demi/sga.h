@@ -33,6 +33,15 @@ extern "C"
      */
     extern int demi_sgafree(_In_ demi_sgarray_t *sga);
 
+    /**
+     * @brief Releases the scatter-gather arrays of a batched pop, and the array that holds them.
+     *
+     * @param batch Target batch.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     */
+    extern int demi_sgafree_batch(_In_ demi_sgarray_batch_t *batch);
+
 #ifdef __cplusplus
 }
 #endif
@@ -50,6 +50,38 @@ extern "C"
     #pragma pack(pop)
     #endif
 
+    /**
+     * @brief Flags that tell which fields of the ancillary data of a popped datagram are valid.
+     */
+#define DEMI_ANCILLARY_DST_ADDR (1 << 0)     /**< Destination address is valid.        */
+#define DEMI_ANCILLARY_TTL (1 << 1)          /**< Time to live is valid.               */
+#define DEMI_ANCILLARY_TOS (1 << 2)          /**< Type of service is valid.            */
+#define DEMI_ANCILLARY_TIMESTAMP (1 << 3)    /**< Receive timestamp is valid.          */
+#define DEMI_ANCILLARY_HW_TIMESTAMP (1 << 4) /**< Hardware receive timestamp is valid. */
+
+    /**
+     * @brief Ancillary data of a popped datagram.
+     */
+    #ifdef _WIN32
+    #pragma pack(push, 1)
+    typedef struct demi_ancillary
+    #endif
+    #ifdef __linux__
+    typedef struct __attribute__((__packed__)) demi_ancillary
+    #endif
+    {
+        uint32_t flags;           /**< Which of the fields below are valid (DEMI_ANCILLARY_*).  */
+        struct in_addr dst_addr;  /**< Destination address of the datagram.                     */
+        uint64_t timestamp_ns;    /**< Receive time, in nanoseconds since the Unix epoch.       */
+        uint64_t hw_timestamp_ns; /**< Receive time, in nanoseconds of the clock of the device. */
+        uint8_t ttl;              /**< Time to live of the datagram when it arrived.            */
+        uint8_t tos;              /**< Type of service of the datagram.                         */
+        uint8_t reserved[6];      /**< Reserved.                                                */
+    } demi_ancillary_t;
+    #ifdef _WIN32
+    #pragma pack(pop)
+    #endif
+
     /**
      * @brief A scatter-gather array.
      */
@@ -65,6 +97,7 @@ extern "C"
         uint32_t sga_numsegs;                         /**< Number of segments in the scatter-gather array. */
         demi_sgaseg_t sga_segs[DEMI_SGARRAY_MAXSIZE]; /**< Scatter-gather array segments.                  */
         struct sockaddr_in sga_addr;                  /**< Source address of scatter-gather array.         */
+        demi_ancillary_t sga_ancillary;               /**< Ancillary data of a popped datagram.            */
     } demi_sgarray_t;
     #ifdef _WIN32
     #pragma pack(pop)
@@ -82,6 +115,9 @@ extern "C"
         DEMI_OPC_CONNECT,     /**< Connect operation. */
         DEMI_OPC_CLOSE,       /**< Close operation. */
         DEMI_OPC_FAILED,      /**< Operation failed.  */
+        DEMI_OPC_TASK,        /**< Application coroutine. */
+        DEMI_OPC_SPLICE,      /**< Splice operation. */
+        DEMI_OPC_POP_MANY,    /**< Batched pop operation. */
     } demi_opcode_t;
 
     /**
@@ -102,6 +138,24 @@ extern "C"
     #pragma pack(pop)
     #endif
 
+    /**
+     * @brief Result value for a batched pop operation.
+     */
+    #ifdef _WIN32
+    #pragma pack(push, 1)
+    typedef struct demi_sgarray_batch
+    #endif
+    #ifdef __linux__
+    typedef struct __attribute__((__packed__)) demi_sgarray_batch
+    #endif
+    {
+        uint32_t count;       /**< Number of popped scatter-gather arrays. */
+        demi_sgarray_t *sgas; /**< Popped scatter-gather arrays.           */
+    } demi_sgarray_batch_t;
+    #ifdef _WIN32
+    #pragma pack(pop)
+    #endif
+
     /**
      * @brief Result value for an asynchronous I/O operation.
      */
@@ -116,7 +170,7 @@ extern "C"
         enum demi_opcode qr_opcode; /**< Opcode of completed operation.                              */
         int32_t qr_qd;              /**< I/O queue descriptor associated to the completed operation. */
         demi_qtoken_t qr_qt;        /**< I/O queue token of the completed operation.                 */
-        int64_t qr_ret;             /**< Return code.                                                */
+        int64_t qr_ret;             /**< Return code, or number of bytes that a push accepted.       */
 
         /**
          * @brief Result value.
@@ -125,11 +179,278 @@ extern "C"
         {
             demi_sgarray_t sga;        /**< Pushed/popped scatter-gather array. */
             demi_accept_result_t ares; /**< Accept result.                      */
+            demi_sgarray_batch_t batch; /**< Batched pop result.                */
         } qr_value;
     } demi_qresult_t;
     #ifdef _WIN32
     #pragma pack(pop)
     #endif
+
+    /**
+     * @brief Configuration of a TLS session that authenticates both peers with an external pre-shared key.
+     */
+    typedef struct demi_tls_config
+    {
+        const uint8_t *psk_identity; /**< Identity of the pre-shared key.               */
+        size_t psk_identity_len;     /**< Length of the identity of the pre-shared key. */
+        const uint8_t *psk;          /**< Pre-shared key (at least 16 bytes).           */
+        size_t psk_len;              /**< Length of the pre-shared key.                 */
+    } demi_tls_config_t;
+
+    /**
+     * @brief Configuration of QUIC connections. Limits that are zero keep their default values.
+     */
+    typedef struct demi_quic_config
+    {
+        demi_tls_config_t tls;    /**< Configuration of the TLS handshake, which authenticates peers.   */
+        uint64_t idle_timeout_ms; /**< Time without activity after which a connection is dropped.       */
+        uint64_t max_data;        /**< Bytes that the peer may send on all streams ahead of the reader. */
+        uint64_t max_stream_data; /**< Bytes that the peer may send on each stream ahead of the reader. */
+        uint64_t max_streams;     /**< Streams that the peer may have open at once.                     */
+    } demi_quic_config_t;
+
+/**
+ * @brief Codecs that frame the data of TCP sockets.
+ */
+#define DEMI_CODEC_LENGTH 0    /**< Frames that start with the length of their payload. */
+#define DEMI_CODEC_LINE 1      /**< Frames that end with a delimiter byte.              */
+#define DEMI_CODEC_RESP 2      /**< Values of RESP, the protocol of Redis.              */
+#define DEMI_CODEC_MEMCACHED 3 /**< Packets of the binary protocol of memcached.        */
+
+    /**
+     * @brief Codec that frames the data of a TCP socket.
+     */
+    typedef struct demi_codec
+    {
+        uint32_t type;         /**< Codec.                                                           */
+        uint32_t length_size;  /**< Size of length prefixes (1, 2, 4 or 8 bytes), for DEMI_CODEC_LENGTH. */
+        uint8_t little_endian; /**< Whether length prefixes are little endian, for DEMI_CODEC_LENGTH. */
+        uint8_t delimiter;     /**< Byte that ends frames, for DEMI_CODEC_LINE.                      */
+    } demi_codec_t;
+
+    /**
+     * @brief Memory that the peer of an RDMA queue exposed for one-sided reads and writes.
+     */
+    typedef struct demi_rdma_region
+    {
+        uint64_t addr; /**< Address of the memory in the address space of the peer. */
+        uint32_t len;  /**< Length of the memory in bytes.                           */
+        uint32_t rkey; /**< Remote key that grants access to the memory.             */
+    } demi_rdma_region_t;
+
+/**
+ * @brief Directions of IPsec security associations.
+ */
+#define DEMI_IPSEC_INBOUND 0  /**< Protects the traffic that is received from the peer. */
+#define DEMI_IPSEC_OUTBOUND 1 /**< Protects the traffic that is sent to the peer.       */
+
+/**
+ * @brief AEAD algorithms of IPsec security associations.
+ */
+#define DEMI_IPSEC_AES_128_GCM 0        /**< AES-128 in Galois/Counter Mode (16-byte keys). */
+#define DEMI_IPSEC_CHACHA20_POLY1305 1  /**< ChaCha20 with Poly1305 (32-byte keys).         */
+
+    /**
+     * @brief Static security association of transport-mode ESP.
+     */
+    typedef struct demi_ipsec_sa
+    {
+        uint32_t spi;       /**< Security parameter index (at least 256).          */
+        uint32_t direction; /**< Direction of the protected traffic.               */
+        uint32_t peer;      /**< IPv4 address of the peer, in network byte order. */
+        uint32_t algorithm; /**< AEAD algorithm.                                   */
+        const uint8_t *key; /**< Key of the AEAD algorithm.                        */
+        size_t key_len;     /**< Length of the key.                                */
+        uint8_t salt[4];    /**< Salt of the AEAD nonce.                           */
+    } demi_ipsec_sa_t;
+
+    /**
+     * @brief Route toward an IPv4 subnet.
+     */
+    typedef struct demi_route
+    {
+        uint32_t destination; /**< Address of the subnet, in network byte order.                                  */
+        uint32_t prefix_len;  /**< Length of the prefix of the subnet, in bits (at most 32).                      */
+        uint32_t gateway;     /**< Address of the gateway, in network byte order, or zero if directly connected. */
+        uint32_t interface;   /**< Index of the network interface that the traffic leaves through (0 is primary). */
+    } demi_route_t;
+
+/**
+ * @brief Reasons for which incoming packets are dropped.
+ */
+#define DEMI_DROP_MALFORMED 0           /**< The packet could not be parsed.                                    */
+#define DEMI_DROP_BAD_CHECKSUM 1        /**< The checksum of the packet does not match its contents.            */
+#define DEMI_DROP_TTL_EXPIRED 2         /**< The time to live of the packet expired.                            */
+#define DEMI_DROP_UNSUPPORTED 3         /**< The packet uses an unsupported feature (e.g. IPv4 fragmentation).  */
+#define DEMI_DROP_REASSEMBLY_TIMEOUT 4  /**< The fragments of the packet did not arrive in time.                */
+#define DEMI_DROP_ADDRESS_MISMATCH 5    /**< The packet is addressed to another host.                           */
+#define DEMI_DROP_VLAN_MISMATCH 6       /**< The frame belongs to another VLAN.                                 */
+#define DEMI_DROP_INVALID_ADDRESS 7     /**< The source address of the packet is not a valid unicast address.   */
+#define DEMI_DROP_NO_SOCKET 8           /**< No socket is bound to the destination of the packet.               */
+#define DEMI_DROP_INVALID_SEGMENT 9     /**< The segment does not fit in the state of its connection.           */
+#define DEMI_DROP_BACKLOG_FULL 10       /**< The listening socket has too many pending connections.             */
+#define DEMI_DROP_QUEUE_FULL 11         /**< The receive queue of the socket is full.                           */
+#define DEMI_DROP_FILTERED 12           /**< The receive filter of the socket rejected the packet.              */
+#define DEMI_DROP_MEMORY_LIMIT 13       /**< Buffering the packet would exceed the memory limit of the stack.   */
+#define DEMI_DROP_ADMISSION 14          /**< The source of the packet is blocked or exceeded its rate.          */
+#define DEMI_DROP_FLOW_LIMIT 15         /**< The flow table of the stack has no room for the flow.              */
+#define DEMI_DROP_BAD_SIGNATURE 16      /**< The TCP signature of the segment is missing, unexpected or wrong.  */
+#define DEMI_DROP_REASONS 17            /**< Number of reasons.                                                 */
+
+    /**
+     * @brief Number of incoming packets that were dropped, for each reason.
+     */
+    typedef struct demi_drop_stats
+    {
+        uint64_t drops[DEMI_DROP_REASONS]; /**< Counts, indexed by reason. */
+    } demi_drop_stats_t;
+
+    /**
+     * @brief An incoming packet that was dropped on its way to a socket.
+     */
+    typedef struct demi_drop_record
+    {
+        uint32_t reason;         /**< Why the packet was dropped.                                 */
+        uint32_t len;            /**< Length of the payload of the packet, in bytes.              */
+        struct sockaddr_in addr; /**< Address of the sender, or all zeros if it is not known.    */
+    } demi_drop_record_t;
+
+    /**
+     * @brief Where the network device placed its buffer pools and the state of its queues, relative to its NUMA node.
+     */
+    typedef struct demi_numa_stats
+    {
+        int32_t device_node;         /**< NUMA node of the network device, or -1 if it is not known.          */
+        int32_t core_node;           /**< NUMA node of the core that runs the engine, or -1 if it is not known. */
+        uint64_t local_allocations;  /**< Allocations placed on the node of the network device.               */
+        uint64_t remote_allocations; /**< Allocations placed on another node.                                 */
+    } demi_numa_stats_t;
+
+    /**
+     * @brief Flags that tell which timestamps of an outgoing packet are valid.
+     */
+#define DEMI_TX_TIMESTAMP_SOFTWARE (1 << 0) /**< Software transmit timestamp is valid. */
+#define DEMI_TX_TIMESTAMP_HARDWARE (1 << 1) /**< Hardware transmit timestamp is valid. */
+
+    /**
+     * @brief Times at which an outgoing packet was transmitted.
+     */
+    typedef struct demi_tx_timestamp
+    {
+        uint32_t id;              /**< Number of the packet, counting from when stamping was enabled.    */
+        uint32_t flags;           /**< Which of the timestamps below are valid (DEMI_TX_TIMESTAMP_*).    */
+        uint64_t timestamp_ns;    /**< Time at which the stack handed it to the device, since the epoch. */
+        uint64_t hw_timestamp_ns; /**< Time at which the device transmitted it, on its clock.            */
+    } demi_tx_timestamp_t;
+
+    /**
+     * @brief Flags of the configuration of a PTP client.
+     */
+#define DEMI_PTP_NO_NEGOTIATION (1 << 0) /**< Do not ask the master for unicast transmission. */
+
+    /**
+     * @brief Configuration of a PTP client. Fields that are zero keep their default values.
+     */
+    typedef struct demi_ptp_config
+    {
+        uint32_t local;                 /**< IPv4 address that the ports are bound to, in network byte order. */
+        uint32_t master;                /**< IPv4 address of the master, in network byte order.               */
+        uint32_t flags;                 /**< Combination of the DEMI_PTP_* configuration flags.               */
+        uint32_t grant_duration_s;      /**< Duration of the requested unicast transmissions, in seconds.     */
+        uint8_t domain;                 /**< PTP domain of the master.                                        */
+        int8_t log_sync_interval;       /**< Interval of Sync messages, as a logarithm to the base 2 of s.    */
+        int8_t log_delay_req_interval;  /**< Interval of Delay_Req messages, as a logarithm to the base 2.    */
+        uint8_t reserved;               /**< Reserved, must be zero.                                          */
+        uint8_t clock_identity[8];      /**< Identity of the clock of the client, or zero for a random one.   */
+    } demi_ptp_config_t;
+
+    /**
+     * @brief States of the clock that a PTP client steers.
+     */
+#define DEMI_PTP_LISTENING 0    /**< No offset from a master was measured yet.        */
+#define DEMI_PTP_UNCALIBRATED 1 /**< Stepped to the time of the master, not locked.   */
+#define DEMI_PTP_SLAVE 2        /**< Locked to the time of the master.                */
+
+    /**
+     * @brief State of the clock that a PTP client steers.
+     */
+    typedef struct demi_ptp_status
+    {
+        uint32_t state;                   /**< One of the DEMI_PTP_* states.                                  */
+        uint32_t reserved;                /**< Reserved.                                                      */
+        int64_t offset_ns;                /**< Offset from the master when it was last sampled.               */
+        int64_t mean_path_delay_ns;       /**< Mean delay of the path to the master.                          */
+        int64_t freq_ppb;                 /**< Correction of the rate relative to the local clock, in ppb.    */
+        uint64_t num_samples;             /**< Number of times that the offset from the master was measured.  */
+        uint8_t master_clock_identity[8]; /**< Identity of the clock of the master.                           */
+        uint16_t master_port_number;      /**< Port number of the master.                                     */
+        uint8_t reserved2[6];             /**< Reserved.                                                      */
+    } demi_ptp_status_t;
+
+    /**
+     * @brief Fields of the binding of the engine that are set.
+     */
+#define DEMI_BINDING_CORES (1 << 0)             /**< The cores are set.                 */
+#define DEMI_BINDING_NICE (1 << 1)              /**< The niceness is set.               */
+#define DEMI_BINDING_REALTIME_PRIORITY (1 << 2) /**< The real-time priority is set.     */
+#define DEMI_BINDING_TIMER_SLACK (1 << 3)       /**< The timer slack is set.            */
+
+    /**
+     * @brief How the thread that runs the engine is bound to cores and scheduled by the kernel.
+     */
+    typedef struct demi_binding
+    {
+        uint64_t cores[16];         /**< Mask of cores that the engine may run on, core i is bit i % 64 of word i / 64. */
+        uint32_t flags;             /**< Combination of the DEMI_BINDING_* flags.                                       */
+        uint32_t current_core;      /**< Core that the engine runs on right now, ignored when binding.                 */
+        int32_t nice;               /**< Niceness, from -20 (highest priority) to 19 (lowest priority).                */
+        uint32_t realtime_priority; /**< Priority under the FIFO policy, or zero for the default policy.               */
+        uint64_t timer_slack_ns;    /**< Slack that the kernel may add to timers, one keeps wakeups from being batched. */
+    } demi_binding_t;
+
+    /**
+     * @brief Snapshot of the state of a TCP connection.
+     */
+    typedef struct demi_tcp_info
+    {
+        uint32_t state;                /**< State of the state machine, numbered as in Linux (e.g. TCP_ESTABLISHED). */
+        uint32_t mss;                  /**< Maximum segment size that is used to send data, in bytes.                */
+        uint32_t cwnd;                 /**< Congestion window, in bytes.                                             */
+        uint32_t ssthresh;             /**< Slow start threshold, in bytes.                                          */
+        uint32_t srtt_us;              /**< Smoothed round-trip time, in microseconds.                               */
+        uint32_t rttvar_us;            /**< Round-trip time variation, in microseconds.                              */
+        uint32_t rto_us;               /**< Retransmission timeout, in microseconds.                                 */
+        uint32_t bytes_in_flight;      /**< Bytes that were sent but not acknowledged yet.                           */
+        uint32_t send_queue;           /**< Bytes that were pushed but not sent yet.                                 */
+        uint32_t recv_queue;           /**< Bytes that were received but not popped yet.                             */
+        uint32_t recv_space;           /**< Receive buffer, in bytes, which bounds the advertised window.            */
+        uint32_t flow_control_stalled; /**< Non-zero if the peer advertises a zero window.                           */
+        uint32_t window_probes;        /**< Number of window probes.                                                 */
+        uint64_t retransmits;          /**< Number of segments that were retransmitted.                              */
+        uint64_t timeouts;             /**< Number of times that the retransmission timer expired.                   */
+        uint64_t fast_retransmits;     /**< Number of fast retransmissions.                                          */
+    } demi_tcp_info_t;
+
+/**
+ * @brief Kinds of events in the timeline of a TCP connection.
+ */
+#define DEMI_TCP_EVENT_RETRANSMIT_TIMEOUT 0 /**< The retransmission timer expired. Value: bytes in flight.            */
+#define DEMI_TCP_EVENT_FAST_RETRANSMIT 1    /**< Duplicate ACKs triggered a retransmission. Value: bytes in flight.   */
+#define DEMI_TCP_EVENT_RTO_BACKOFF 2        /**< The retransmission timer backed off. Value: new timeout, in us.      */
+#define DEMI_TCP_EVENT_WINDOW_STALL 3       /**< Data does not fit in the window of the peer. Value: send window.     */
+#define DEMI_TCP_EVENT_ZERO_WINDOW 4        /**< The peer advertises a zero window. Value: bytes waiting to be sent.  */
+#define DEMI_TCP_EVENT_USER_TIMEOUT 5       /**< The user timeout aborted the connection. Value: bytes in flight.     */
+
+    /**
+     * @brief Event in the timeline of a TCP connection.
+     */
+    typedef struct demi_tcp_event
+    {
+        uint32_t kind;    /**< Kind of event (e.g. DEMI_TCP_EVENT_RETRANSMIT_TIMEOUT).      */
+        uint32_t seq_no;  /**< Oldest unacknowledged sequence number when the event happened. */
+        uint64_t time_us; /**< Time since the connection was established, in microseconds.    */
+        uint64_t value;   /**< Value that depends on the kind of event.                       */
+    } demi_tcp_event_t;
 #ifdef __cplusplus
 }
 #endif
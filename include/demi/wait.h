@@ -57,6 +57,36 @@ extern "C"
     extern int demi_wait_next_n(_Out_writes_to_(num_qrs, *ready_offset) demi_qresult_t *qr_out, _In_ int num_qrs,
                                 _Out_ int *num_qrs_out, _In_opt_ const struct timespec *timeout);
 
+    /**
+     * @brief Cancels an asynchronous I/O operation.
+     *
+     * @details The target operation stops running and releases any state that it held on its I/O queue. Waiting on
+     * its I/O queue token then returns a failed result with ECANCELED.
+     *
+     * @param qt I/O queue token of the target operation to cancel.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     * EALREADY means that the operation completed before it could be cancelled.
+     */
+    extern int demi_cancel(_In_ demi_qtoken_t qt);
+
+    /**
+     * @brief Gets a file descriptor for embedding Demikernel in an external event loop.
+     *
+     * @details The file descriptor becomes readable when waiting would return, that is, while results of completed
+     * operations are waiting to be collected or while a doorbell that Demikernel waits on has rung. It is only brought
+     * up to date when Demikernel is polled, so callers should wait with a zero timeout after it becomes readable and
+     * after issuing new operations. It may be readable spuriously. Timers and busy-polled devices do not signal it.
+     * The file descriptor is owned by Demikernel and must not be closed or read from.
+     *
+     * @param fd_out Storage location for the file descriptor.
+     *
+     * @return On successful completion, zero is returned. On failure, a positive error code is returned instead.
+     * ENOTSUP means that the platform does not support pollable file descriptors.
+     */
+    ATTR_NONNULL(1)
+    extern int demi_get_eventfd(_Out_ int *fd_out);
+
 #ifdef __cplusplus
 }
 #endif
@@ -74,6 +74,24 @@ ifeq ($(PROFILER),yes)
 CARGO_FEATURES += --features=profiler
 endif
 
+# Switch for tracing.
+export TRACING ?= no
+ifeq ($(TRACING),yes)
+CARGO_FEATURES += --features=tracing
+endif
+
+# Switch for SPDK block queues.
+export SPDK ?= no
+ifeq ($(SPDK),yes)
+CARGO_FEATURES += --features=libspdk
+endif
+
+# Switch for RDMA queues.
+export RDMA ?= no
+ifeq ($(RDMA),yes)
+CARGO_FEATURES += --features=librdma
+endif
+
 CARGO_FEATURES += $(FEATURES)
 
 #=======================================================================================================================
@@ -213,7 +231,7 @@ clean: clean-examples clean-tests clean-libs
 
 export CONFIG_PATH ?= $(HOME)/config.yaml
 export MTU ?= 1500
-export MSS ?= 1500
+export MSS ?= 1460
 export PEER ?= server
 export TEST ?= udp-push-pop
 export TEST_INTEGRATION ?= tcp-test
@@ -270,3 +288,7 @@ test-clean:
 # C unit benchmarks.
 run-benchmarks-c: all-benchmarks-c $(BINDIR)/syscalls.elf
 	timeout $(TIMEOUT) $(BINDIR)/benchmarks.elf
+
+# Rust microbenchmarks.
+run-benchmarks-rust:
+	$(CARGO) bench --bench '*' $(CARGO_FEATURES) --features=bench -- $(ARGS)
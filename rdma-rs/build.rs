@@ -0,0 +1,150 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+use anyhow::Result;
+use bindgen::{
+    Bindings,
+    Builder,
+};
+use cc::Build;
+use std::{
+    env,
+    path::Path,
+};
+
+#[cfg(target_os = "windows")]
+fn os_build() -> Result<()> {
+    anyhow::bail!("RDMA is only supported on Linux")
+}
+
+#[cfg(target_os = "linux")]
+fn os_build() -> Result<()> {
+    use std::process::Command;
+
+    let out_dir_s = env::var("OUT_DIR").unwrap();
+    let out_dir = Path::new(&out_dir_s);
+
+    // rdma-core ships a package for the verbs library and one for the connection manager.
+    let packages: [&str; 2] = ["libibverbs", "librdmacm"];
+
+    println!("cargo:rerun-if-env-changed=PKG_CONFIG_PATH");
+    let cflags_bytes = Command::new("pkg-config")
+        .arg("--cflags")
+        .args(&packages)
+        .output()
+        .unwrap_or_else(|e| panic!("Failed pkg-config cflags: {:?}", e))
+        .stdout;
+    let cflags = String::from_utf8(cflags_bytes).unwrap();
+
+    let mut header_locations = vec![];
+
+    for flag in cflags.split(' ') {
+        if flag.starts_with("-I") {
+            let header_location = flag[2..].trim();
+            header_locations.push(header_location);
+        }
+    }
+
+    let ldflags_bytes = Command::new("pkg-config")
+        .arg("--libs")
+        .args(&packages)
+        .output()
+        .unwrap_or_else(|e| panic!("Failed pkg-config ldflags: {:?}", e))
+        .stdout;
+    let ldflags = String::from_utf8(ldflags_bytes).unwrap();
+
+    let mut library_location = None;
+    let mut lib_names = vec![];
+
+    for flag in ldflags.split(' ') {
+        if flag.starts_with("-L") {
+            library_location = Some(flag[2..].trim());
+        } else if flag.starts_with("-l") {
+            lib_names.push(flag[2..].trim());
+        }
+    }
+
+    // Step 1: Point cargo to the libraries.
+    if let Some(location) = library_location {
+        println!("cargo:rustc-link-search=native={}", location);
+    }
+
+    for lib_name in &lib_names {
+        println!("cargo:rustc-link-lib=dylib={}", lib_name);
+    }
+
+    // Step 2: Generate bindings for the verbs and connection manager headers.
+    let mut builder: Builder = Builder::default();
+    for header_location in &header_locations {
+        builder = builder.clang_arg(&format!("-I{}", header_location));
+    }
+    let bindings: Bindings = builder
+        .allowlist_recursively(true)
+        .prepend_enum_name(false)
+        .allowlist_type("ibv_wc")
+        .allowlist_type("ibv_sge")
+        .allowlist_type("ibv_send_wr")
+        .allowlist_type("ibv_recv_wr")
+        .allowlist_type("ibv_qp_init_attr")
+        .allowlist_type("rdma_conn_param")
+        .allowlist_type("rdma_cm_event")
+        .allowlist_type("rdma_event_channel")
+        .allowlist_type("rdma_cm_id")
+        .allowlist_type("ibv_access_flags")
+        .allowlist_type("ibv_send_flags")
+        .allowlist_type("ibv_wr_opcode")
+        .allowlist_type("ibv_wc_status")
+        .allowlist_type("ibv_wc_opcode")
+        .allowlist_type("ibv_qp_type")
+        .allowlist_type("rdma_cm_event_type")
+        .allowlist_type("rdma_port_space")
+        .allowlist_function("ibv_alloc_pd")
+        .allowlist_function("ibv_dealloc_pd")
+        .allowlist_function("ibv_reg_mr")
+        .allowlist_function("ibv_dereg_mr")
+        .allowlist_function("ibv_create_cq")
+        .allowlist_function("ibv_destroy_cq")
+        .allowlist_function("rdma_create_event_channel")
+        .allowlist_function("rdma_destroy_event_channel")
+        .allowlist_function("rdma_create_id")
+        .allowlist_function("rdma_destroy_id")
+        .allowlist_function("rdma_migrate_id")
+        .allowlist_function("rdma_bind_addr")
+        .allowlist_function("rdma_listen")
+        .allowlist_function("rdma_resolve_addr")
+        .allowlist_function("rdma_resolve_route")
+        .allowlist_function("rdma_create_qp")
+        .allowlist_function("rdma_destroy_qp")
+        .allowlist_function("rdma_connect")
+        .allowlist_function("rdma_accept")
+        .allowlist_function("rdma_reject")
+        .allowlist_function("rdma_disconnect")
+        .allowlist_function("rdma_get_cm_event")
+        .allowlist_function("rdma_ack_cm_event")
+        .header("wrapper.h")
+        .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
+        .generate_comments(false)
+        .generate()
+        .unwrap_or_else(|e| panic!("Failed to generate bindings: {:?}", e));
+    let bindings_out = out_dir.join("bindings.rs");
+    bindings.write_to_file(bindings_out).expect("Failed to write bindings");
+
+    // Step 3: Compile a stub file so Rust can access `inline` functions in the headers that aren't compiled into the
+    // libraries.
+    let mut builder: Build = cc::Build::new();
+    builder.opt_level(3);
+    builder.pic(true);
+    builder.file("inlined.c");
+    for header_location in &header_locations {
+        builder.include(header_location);
+    }
+    builder.compile("inlined");
+    Ok(())
+}
+
+fn main() {
+    match os_build() {
+        Ok(()) => {},
+        Err(e) => panic!("Failed to generate bindings: {:?}", e),
+    }
+}
@@ -0,0 +1,27 @@
+/*
+ * Copyright (c) Microsoft Corporation.
+ * Licensed under the MIT license.
+ */
+
+#include <infiniband/verbs.h>
+#include <rdma/rdma_cma.h>
+
+int ibv_post_send_(struct ibv_qp *qp, struct ibv_send_wr *wr, struct ibv_send_wr **bad_wr)
+{
+    return ibv_post_send(qp, wr, bad_wr);
+}
+
+int ibv_post_recv_(struct ibv_qp *qp, struct ibv_recv_wr *wr, struct ibv_recv_wr **bad_wr)
+{
+    return ibv_post_recv(qp, wr, bad_wr);
+}
+
+int ibv_poll_cq_(struct ibv_cq *cq, int num_entries, struct ibv_wc *wc)
+{
+    return ibv_poll_cq(cq, num_entries, wc);
+}
+
+struct sockaddr *rdma_get_peer_addr_(struct rdma_cm_id *id)
+{
+    return rdma_get_peer_addr(id);
+}
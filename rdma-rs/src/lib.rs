@@ -0,0 +1,50 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+#![cfg_attr(feature = "strict", deny(clippy:all))]
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(unused)]
+
+#[link(name = "inlined")]
+extern "C" {
+    fn ibv_post_send_(qp: *mut ibv_qp, wr: *mut ibv_send_wr, bad_wr: *mut *mut ibv_send_wr) -> ::std::os::raw::c_int;
+    fn ibv_post_recv_(qp: *mut ibv_qp, wr: *mut ibv_recv_wr, bad_wr: *mut *mut ibv_recv_wr) -> ::std::os::raw::c_int;
+    fn ibv_poll_cq_(cq: *mut ibv_cq, num_entries: ::std::os::raw::c_int, wc: *mut ibv_wc) -> ::std::os::raw::c_int;
+    fn rdma_get_peer_addr_(id: *mut rdma_cm_id) -> *mut sockaddr;
+}
+
+include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+
+#[inline]
+pub unsafe fn ibv_post_send(
+    qp: *mut ibv_qp,
+    wr: *mut ibv_send_wr,
+    bad_wr: *mut *mut ibv_send_wr,
+) -> ::std::os::raw::c_int {
+    ibv_post_send_(qp, wr, bad_wr)
+}
+
+#[inline]
+pub unsafe fn ibv_post_recv(
+    qp: *mut ibv_qp,
+    wr: *mut ibv_recv_wr,
+    bad_wr: *mut *mut ibv_recv_wr,
+) -> ::std::os::raw::c_int {
+    ibv_post_recv_(qp, wr, bad_wr)
+}
+
+#[inline]
+pub unsafe fn ibv_poll_cq(
+    cq: *mut ibv_cq,
+    num_entries: ::std::os::raw::c_int,
+    wc: *mut ibv_wc,
+) -> ::std::os::raw::c_int {
+    ibv_poll_cq_(cq, num_entries, wc)
+}
+
+#[inline]
+pub unsafe fn rdma_get_peer_addr(id: *mut rdma_cm_id) -> *mut sockaddr {
+    rdma_get_peer_addr_(id)
+}
@@ -0,0 +1,7 @@
+/*
+ * Copyright (c) Microsoft Corporation.
+ * Licensed under the MIT license.
+ */
+
+#include <infiniband/verbs.h>
+#include <rdma/rdma_cma.h>
@@ -44,7 +44,8 @@ ssize_t __send(int sockfd, const void *buf, size_t len, int flags)
     assert(qr.qr_opcode == DEMI_OPC_PUSH);
     __demi_sgafree(&sga);
 
-    return (len);
+    // The push reports the bytes that it accepted.
+    return (qr.qr_ret);
 }
 
 ssize_t __write(int sockfd, const void *buf, size_t count)
@@ -0,0 +1,135 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+use anyhow::Result;
+use bindgen::{
+    Bindings,
+    Builder,
+};
+use cc::Build;
+use std::{
+    env,
+    path::Path,
+};
+
+#[cfg(target_os = "windows")]
+fn os_build() -> Result<()> {
+    anyhow::bail!("SPDK is only supported on Linux")
+}
+
+#[cfg(target_os = "linux")]
+fn os_build() -> Result<()> {
+    use std::process::Command;
+
+    let out_dir_s = env::var("OUT_DIR").unwrap();
+    let out_dir = Path::new(&out_dir_s);
+
+    // SPDK ships a package for each of its libraries. The environment must be the DPDK-based one, so that SPDK shares
+    // DPDK with Catnip when both run in the same process.
+    let packages: [&str; 3] = ["spdk_nvme", "spdk_env_dpdk", "spdk_syslibs"];
+
+    println!("cargo:rerun-if-env-changed=PKG_CONFIG_PATH");
+    let cflags_bytes = Command::new("pkg-config")
+        .arg("--cflags")
+        .args(&packages)
+        .output()
+        .unwrap_or_else(|e| panic!("Failed pkg-config cflags: {:?}", e))
+        .stdout;
+    let cflags = String::from_utf8(cflags_bytes).unwrap();
+
+    let mut header_locations = vec![];
+
+    for flag in cflags.split(' ') {
+        if flag.starts_with("-I") {
+            let header_location = flag[2..].trim();
+            header_locations.push(header_location);
+        }
+    }
+
+    let ldflags_bytes = Command::new("pkg-config")
+        .arg("--libs")
+        .args(&packages)
+        .output()
+        .unwrap_or_else(|e| panic!("Failed pkg-config ldflags: {:?}", e))
+        .stdout;
+    let ldflags = String::from_utf8(ldflags_bytes).unwrap();
+
+    let mut library_location = None;
+    let mut lib_names = vec![];
+
+    for flag in ldflags.split(' ') {
+        if flag.starts_with("-L") {
+            library_location = Some(flag[2..].trim());
+        } else if flag.starts_with("-l") {
+            lib_names.push(flag[2..].trim());
+        }
+    }
+
+    // Step 1: Now that we've compiled and installed SPDK, point cargo to the libraries.
+    if let Some(location) = library_location {
+        println!("cargo:rustc-link-search=native={}", location);
+    }
+
+    for lib_name in &lib_names {
+        println!("cargo:rustc-link-lib=dylib={}", lib_name);
+    }
+
+    // Step 2: Generate bindings for the SPDK headers.
+    let mut builder: Builder = Builder::default();
+    for header_location in &header_locations {
+        builder = builder.clang_arg(&format!("-I{}", header_location));
+    }
+    let bindings: Bindings = builder
+        .allowlist_recursively(true)
+        .allowlist_type("spdk_env_opts")
+        .allowlist_type("spdk_nvme_transport_id")
+        .allowlist_type("spdk_nvme_cpl")
+        .allowlist_type("spdk_nvme_ctrlr")
+        .allowlist_type("spdk_nvme_ns")
+        .allowlist_type("spdk_nvme_qpair")
+        .allowlist_var("SPDK_ENV_SOCKET_ID_ANY")
+        .allowlist_var("SPDK_MALLOC_DMA")
+        .allowlist_function("spdk_env_opts_init")
+        .allowlist_function("spdk_env_init")
+        .allowlist_function("spdk_env_dpdk_post_init")
+        .allowlist_function("spdk_zmalloc")
+        .allowlist_function("spdk_free")
+        .allowlist_function("spdk_nvme_transport_id_parse")
+        .allowlist_function("spdk_nvme_connect")
+        .allowlist_function("spdk_nvme_detach")
+        .allowlist_function("spdk_nvme_ctrlr_get_ns")
+        .allowlist_function("spdk_nvme_ctrlr_alloc_io_qpair")
+        .allowlist_function("spdk_nvme_ctrlr_free_io_qpair")
+        .allowlist_function("spdk_nvme_ns_is_active")
+        .allowlist_function("spdk_nvme_ns_get_sector_size")
+        .allowlist_function("spdk_nvme_ns_get_num_sectors")
+        .allowlist_function("spdk_nvme_ns_cmd_read")
+        .allowlist_function("spdk_nvme_ns_cmd_write")
+        .allowlist_function("spdk_nvme_qpair_process_completions")
+        .header("wrapper.h")
+        .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
+        .generate_comments(false)
+        .generate()
+        .unwrap_or_else(|e| panic!("Failed to generate bindings: {:?}", e));
+    let bindings_out = out_dir.join("bindings.rs");
+    bindings.write_to_file(bindings_out).expect("Failed to write bindings");
+
+    // Step 3: Compile a stub file so Rust can access `inline` functions and macros in the headers that aren't compiled
+    // into the libraries.
+    let mut builder: Build = cc::Build::new();
+    builder.opt_level(3);
+    builder.pic(true);
+    builder.file("inlined.c");
+    for header_location in &header_locations {
+        builder.include(header_location);
+    }
+    builder.compile("inlined");
+    Ok(())
+}
+
+fn main() {
+    match os_build() {
+        Ok(()) => {},
+        Err(e) => panic!("Failed to generate bindings: {:?}", e),
+    }
+}
@@ -0,0 +1,12 @@
+/*
+ * Copyright (c) Microsoft Corporation.
+ * Licensed under the MIT license.
+ */
+
+#include <stdbool.h>
+#include <spdk/nvme.h>
+
+bool spdk_nvme_cpl_is_error_(const struct spdk_nvme_cpl *cpl)
+{
+    return spdk_nvme_cpl_is_error(cpl);
+}
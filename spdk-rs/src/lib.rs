@@ -0,0 +1,20 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+#![cfg_attr(feature = "strict", deny(clippy:all))]
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(unused)]
+
+#[link(name = "inlined")]
+extern "C" {
+    fn spdk_nvme_cpl_is_error_(cpl: *const spdk_nvme_cpl) -> bool;
+}
+
+include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+
+#[inline]
+pub unsafe fn spdk_nvme_cpl_is_error(cpl: *const spdk_nvme_cpl) -> bool {
+    spdk_nvme_cpl_is_error_(cpl)
+}
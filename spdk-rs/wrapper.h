@@ -0,0 +1,8 @@
+/*
+ * Copyright (c) Microsoft Corporation.
+ * Licensed under the MIT license.
+ */
+
+#include <spdk/env.h>
+#include <spdk/env_dpdk.h>
+#include <spdk/nvme.h>
@@ -0,0 +1,397 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Idiomatic async/await API on top of [LibOS]. Sockets are exposed as [TcpListener], [TcpStream] and [UdpSocket],
+//! whose methods return futures instead of queue tokens. Each future drives the Demikernel scheduler whenever it is
+//! polled, so it makes progress under any executor (e.g. `futures::executor::block_on`). These futures must not be
+//! polled from coroutines that run on the Demikernel scheduler itself, such as the ones spawned with
+//! [LibOS::spawn].
+
+//======================================================================================================================
+// Exports
+//======================================================================================================================
+
+mod tcp;
+mod udp;
+
+pub use self::{
+    tcp::{
+        TcpListener,
+        TcpStream,
+    },
+    udp::UdpSocket,
+};
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    demikernel::libos::LibOS,
+    pal::data_structures::SockAddr,
+    runtime::{
+        fail::Fail,
+        types::{
+            demi_opcode_t,
+            demi_qresult_t,
+            demi_sgarray_t,
+        },
+        QDesc,
+        QToken,
+        SharedObject,
+    },
+};
+use ::socket2::{
+    Domain,
+    Type,
+};
+use ::std::{
+    future::Future,
+    net::SocketAddr,
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    pin::Pin,
+    ptr,
+    slice,
+    task::{
+        Context,
+        Poll,
+    },
+    time::Duration,
+};
+
+#[cfg(target_os = "linux")]
+use crate::pal::linux::sockaddr_to_socketaddrv4;
+
+#[cfg(target_os = "windows")]
+use crate::pal::functions::sockaddr_to_socketaddrv4;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Largest number of bytes that we push in a single operation.
+const MAX_PUSH_SIZE: usize = u16::MAX as usize;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// A [LibOS] that is shared by the sockets of the async/await API.
+#[derive(Clone)]
+pub struct AsyncLibOS(SharedObject<LibOS>);
+
+/// A future that completes once the I/O operation behind a queue token completes. Dropping the future before then
+/// cancels the operation.
+struct OperationFuture {
+    libos: AsyncLibOS,
+    qt: Option<QToken>,
+    /// Data that the operation pushes, which is released along with the future.
+    sga: Option<demi_sgarray_t>,
+}
+
+/// A socket descriptor that is closed when the last reference to it is dropped.
+struct SocketDescriptor {
+    libos: AsyncLibOS,
+    qd: QDesc,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl AsyncLibOS {
+    pub fn new(libos: LibOS) -> Self {
+        Self(SharedObject::new(libos))
+    }
+
+    /// Returns a future that completes with the result of the operation behind [qt]. Failed operations complete with
+    /// their error.
+    fn operation(&self, qt: QToken) -> OperationFuture {
+        OperationFuture {
+            libos: self.clone(),
+            qt: Some(qt),
+            sga: None,
+        }
+    }
+
+    /// Creates a socket of type [typ] for the address family of [addr].
+    fn new_socket(&self, addr: &SocketAddr, typ: Type) -> Result<SharedObject<SocketDescriptor>, Fail> {
+        let domain: Domain = Domain::for_address(*addr);
+        let qd: QDesc = self.clone().socket(domain.into(), typ.into(), 0)?;
+        Ok(SocketDescriptor::new(self.clone(), qd))
+    }
+
+    /// Copies [data] into a new scatter-gather array.
+    fn sga_from_slice(&mut self, data: &[u8]) -> Result<demi_sgarray_t, Fail> {
+        let sga: demi_sgarray_t = self.sgaalloc(data.len())?;
+        unsafe {
+            ptr::copy_nonoverlapping(
+                data.as_ptr(),
+                sga.sga_segs[0].sgaseg_buf as *mut u8,
+                data.len().min(sga.sga_segs[0].sgaseg_len as usize),
+            )
+        };
+        Ok(sga)
+    }
+
+    /// Copies the contents of [sga] out and releases it.
+    fn sga_to_vec(&mut self, sga: demi_sgarray_t) -> Result<Vec<u8>, Fail> {
+        let data: Vec<u8> = unsafe {
+            slice::from_raw_parts(
+                sga.sga_segs[0].sgaseg_buf as *const u8,
+                sga.sga_segs[0].sgaseg_len as usize,
+            )
+        }
+        .to_vec();
+        self.sgafree(sga)?;
+        Ok(data)
+    }
+
+    /// Pushes [data] to [qd], either to the connected peer or to [remote], and waits for the push to complete.
+    async fn push_data(&self, qd: QDesc, data: &[u8], remote: Option<SocketAddr>) -> Result<usize, Fail> {
+        let data: &[u8] = &data[..data.len().min(MAX_PUSH_SIZE)];
+        let mut libos: AsyncLibOS = self.clone();
+        let sga: demi_sgarray_t = libos.sga_from_slice(data)?;
+        let result: Result<QToken, Fail> = match remote {
+            Some(remote) => libos.pushto(qd, &sga, remote),
+            None => libos.push(qd, &sga),
+        };
+        let qt: QToken = match result {
+            Ok(qt) => qt,
+            Err(e) => {
+                libos.sgafree(sga)?;
+                return Err(e);
+            },
+        };
+        let qr: demi_qresult_t = OperationFuture {
+            libos,
+            qt: Some(qt),
+            sga: Some(sga),
+        }
+        .await?;
+        Ok(qr.qr_ret as usize)
+    }
+
+    /// Pops at most [size] bytes from [qd] and waits for them. Returns the data and the address of the sender.
+    async fn pop_data(&self, qd: QDesc, size: Option<usize>) -> Result<(Vec<u8>, Option<SocketAddr>), Fail> {
+        let qt: QToken = self.clone().pop(qd, size)?;
+        let qr: demi_qresult_t = self.operation(qt).await?;
+        let sga: demi_sgarray_t = unsafe { qr.qr_value.sga };
+        let addr: Option<SocketAddr> = sockaddr_to_socketaddr(sga.sga_addr).ok();
+        Ok((self.clone().sga_to_vec(sga)?, addr))
+    }
+}
+
+impl OperationFuture {
+    /// Releases the data that the operation pushed, once the operation is over.
+    fn release_sga(&mut self) {
+        if let Some(sga) = self.sga.take() {
+            if let Err(e) = self.libos.sgafree(sga) {
+                warn!("release_sga(): failed to release pushed data (error={:?})", e);
+            }
+        }
+    }
+}
+
+impl SocketDescriptor {
+    fn new(libos: AsyncLibOS, qd: QDesc) -> SharedObject<Self> {
+        SharedObject::new(Self { libos, qd })
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Converts the address that Demikernel reports in results into a [SocketAddr].
+fn sockaddr_to_socketaddr(saddr: SockAddr) -> Result<SocketAddr, Fail> {
+    match sockaddr_to_socketaddrv4(&saddr) {
+        Some(addr) => Ok(SocketAddr::V4(addr)),
+        None => Err(Fail::new(libc::ENOTSUP, "communication domain not supported")),
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Deref for AsyncLibOS {
+    type Target = LibOS;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl DerefMut for AsyncLibOS {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+impl Future for OperationFuture {
+    type Output = Result<demi_qresult_t, Fail>;
+
+    /// Runs the scheduler for one quanta and checks whether the operation completed. If it did not, the future wakes
+    /// itself up right away, because there is no other source of wake ups.
+    fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+        let self_: &mut Self = self.get_mut();
+        let qt: QToken = match self_.qt {
+            Some(qt) => qt,
+            None => return Poll::Ready(Err(Fail::new(libc::EINVAL, "operation already completed"))),
+        };
+        match self_.libos.wait(qt, Some(Duration::ZERO)) {
+            Ok(qr) => {
+                self_.qt = None;
+                self_.release_sga();
+                match qr.qr_opcode {
+                    demi_opcode_t::DEMI_OPC_FAILED => Poll::Ready(Err(Fail::new(qr.qr_ret as i32, "operation failed"))),
+                    _ => Poll::Ready(Ok(qr)),
+                }
+            },
+            Err(e) if e.errno == libc::ETIMEDOUT => {
+                context.waker().wake_by_ref();
+                Poll::Pending
+            },
+            Err(e) => {
+                self_.qt = None;
+                self_.release_sga();
+                Poll::Ready(Err(e))
+            },
+        }
+    }
+}
+
+impl Drop for OperationFuture {
+    fn drop(&mut self) {
+        if let Some(qt) = self.qt.take() {
+            // Cancel the operation and collect its result, so that it does not linger in the runtime. If the
+            // operation completed in the meantime, release whatever it produced.
+            if let Err(e) = self.libos.cancel(qt) {
+                if e.errno != libc::EALREADY {
+                    warn!("drop(): failed to cancel operation (qt={:?}, error={:?})", qt, e);
+                    return;
+                }
+            }
+            match self.libos.wait(qt, Some(Duration::ZERO)) {
+                Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_POP => {
+                    if let Err(e) = self.libos.sgafree(unsafe { qr.qr_value.sga }) {
+                        warn!("drop(): failed to release popped data (qt={:?}, error={:?})", qt, e);
+                    }
+                },
+                Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_ACCEPT => {
+                    let qd: QDesc = QDesc::from(unsafe { qr.qr_value.ares.qd });
+                    if let Err(e) = self.libos.close(qd) {
+                        warn!("drop(): failed to close accepted socket (qd={:?}, error={:?})", qd, e);
+                    }
+                },
+                Ok(_) => (),
+                Err(e) => warn!(
+                    "drop(): failed to collect cancelled operation (qt={:?}, error={:?})",
+                    qt, e
+                ),
+            }
+        }
+        self.release_sga();
+    }
+}
+
+impl Drop for SocketDescriptor {
+    fn drop(&mut self) {
+        if let Err(e) = self.libos.close(self.qd) {
+            warn!("drop(): failed to close socket (qd={:?}, error={:?})", self.qd, e);
+        }
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(all(test, feature = "catnap-libos", target_os = "linux"))]
+mod tests {
+    use super::{
+        AsyncLibOS,
+        TcpListener,
+        TcpStream,
+        UdpSocket,
+    };
+    use crate::{
+        catnap::transport::SharedCatnapTransport,
+        demikernel::{
+            config::Config,
+            libos::{
+                network::{
+                    libos::SharedNetworkLibOS,
+                    NetworkLibOSWrapper,
+                },
+                LibOS,
+            },
+        },
+        runtime::{
+            fail::Fail,
+            SharedDemiRuntime,
+        },
+    };
+    use ::anyhow::Result;
+    use ::futures::executor::block_on;
+    use ::std::net::SocketAddr;
+    use ::yaml_rust::Yaml;
+
+    /// Catnap does not read its configuration, so we do not need a configuration file.
+    fn new_libos() -> AsyncLibOS {
+        let mut runtime: SharedDemiRuntime = SharedDemiRuntime::default();
+        let transport: SharedCatnapTransport = SharedCatnapTransport::new(&Config(Yaml::Null), &mut runtime);
+        AsyncLibOS::new(LibOS::NetworkLibOS(NetworkLibOSWrapper::Catnap(SharedNetworkLibOS::<
+            SharedCatnapTransport,
+        >::new(
+            runtime, transport
+        ))))
+    }
+
+    #[test]
+    fn udp_send_recv() -> Result<()> {
+        let libos: AsyncLibOS = new_libos();
+        let alice_addr: SocketAddr = "127.0.0.1:23471".parse()?;
+        let bob_addr: SocketAddr = "127.0.0.1:23472".parse()?;
+        let alice: UdpSocket = UdpSocket::bind(&libos, alice_addr)?;
+        let bob: UdpSocket = UdpSocket::bind(&libos, bob_addr)?;
+
+        let (sent, received): (Result<usize, Fail>, Result<(Vec<u8>, SocketAddr), Fail>) =
+            block_on(async { (alice.send_to(b"ping", bob_addr).await, bob.recv_from().await) });
+        crate::ensure_eq!(sent?, 4);
+        let (data, remote): (Vec<u8>, SocketAddr) = received?;
+        crate::ensure_eq!(data, b"ping".to_vec());
+        crate::ensure_eq!(remote, alice_addr);
+
+        Ok(())
+    }
+
+    #[test]
+    fn tcp_connect_accept() -> Result<()> {
+        let libos: AsyncLibOS = new_libos();
+        let server_addr: SocketAddr = "127.0.0.1:23473".parse()?;
+        let listener: TcpListener = TcpListener::bind(&libos, server_addr)?;
+
+        let server = async {
+            let (stream, _): (TcpStream, SocketAddr) = listener.accept().await?;
+            let data: Vec<u8> = stream.read(None).await?;
+            stream.write_all(&data).await?;
+            Ok::<(), Fail>(())
+        };
+        let client = async {
+            let stream: TcpStream = TcpStream::connect(&libos, server_addr).await?;
+            stream.write_all(b"hello").await?;
+            stream.read(None).await
+        };
+        let (server, client): (Result<(), Fail>, Result<Vec<u8>, Fail>) =
+            block_on(async { ::futures::join!(server, client) });
+        server?;
+        crate::ensure_eq!(client?, b"hello".to_vec());
+
+        Ok(())
+    }
+}
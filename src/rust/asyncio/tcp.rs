@@ -0,0 +1,118 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    asyncio::{
+        sockaddr_to_socketaddr,
+        AsyncLibOS,
+        SocketDescriptor,
+    },
+    pal::constants::SOMAXCONN,
+    runtime::{
+        fail::Fail,
+        types::demi_qresult_t,
+        QDesc,
+        QToken,
+        SharedObject,
+    },
+};
+use ::socket2::Type;
+use ::std::net::SocketAddr;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// A TCP socket that listens for incoming connections.
+pub struct TcpListener {
+    libos: AsyncLibOS,
+    socket: SharedObject<SocketDescriptor>,
+    local: SocketAddr,
+}
+
+/// A TCP connection. Clones refer to the same connection, which is closed when the last one is dropped.
+#[derive(Clone)]
+pub struct TcpStream {
+    libos: AsyncLibOS,
+    socket: SharedObject<SocketDescriptor>,
+    remote: SocketAddr,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl TcpListener {
+    /// Creates a socket that is bound to [local] and listens for incoming connections.
+    pub fn bind(libos: &AsyncLibOS, local: SocketAddr) -> Result<Self, Fail> {
+        let socket: SharedObject<SocketDescriptor> = libos.new_socket(&local, Type::STREAM)?;
+        let mut libos: AsyncLibOS = libos.clone();
+        libos.bind(socket.qd, local)?;
+        libos.listen(socket.qd, SOMAXCONN as usize)?;
+        Ok(Self { libos, socket, local })
+    }
+
+    /// Waits for an incoming connection.
+    pub async fn accept(&self) -> Result<(TcpStream, SocketAddr), Fail> {
+        let qt: QToken = self.libos.clone().accept(self.socket.qd)?;
+        let qr: demi_qresult_t = self.libos.operation(qt).await?;
+        let qd: QDesc = QDesc::from(unsafe { qr.qr_value.ares.qd });
+        let socket: SharedObject<SocketDescriptor> = SocketDescriptor::new(self.libos.clone(), qd);
+        let remote: SocketAddr = sockaddr_to_socketaddr(unsafe { qr.qr_value.ares.addr })?;
+        let stream: TcpStream = TcpStream {
+            libos: self.libos.clone(),
+            socket,
+            remote,
+        };
+        Ok((stream, remote))
+    }
+
+    /// Returns the address that this listener is bound to.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local
+    }
+}
+
+impl TcpStream {
+    /// Opens a connection to [remote].
+    pub async fn connect(libos: &AsyncLibOS, remote: SocketAddr) -> Result<Self, Fail> {
+        let socket: SharedObject<SocketDescriptor> = libos.new_socket(&remote, Type::STREAM)?;
+        let qt: QToken = libos.clone().connect(socket.qd, remote)?;
+        libos.operation(qt).await?;
+        Ok(Self {
+            libos: libos.clone(),
+            socket,
+            remote,
+        })
+    }
+
+    /// Reads up to [size] bytes, or as many as are available if not set. An empty result means that the peer closed
+    /// the connection.
+    pub async fn read(&self, size: Option<usize>) -> Result<Vec<u8>, Fail> {
+        let (data, _): (Vec<u8>, Option<SocketAddr>) = self.libos.pop_data(self.socket.qd, size).await?;
+        Ok(data)
+    }
+
+    /// Writes a prefix of [data] and returns its length, once the peer has it.
+    pub async fn write(&self, data: &[u8]) -> Result<usize, Fail> {
+        self.libos.push_data(self.socket.qd, data, None).await
+    }
+
+    /// Writes all of [data].
+    pub async fn write_all(&self, mut data: &[u8]) -> Result<(), Fail> {
+        while !data.is_empty() {
+            let n: usize = self.write(data).await?;
+            data = &data[n..];
+        }
+        Ok(())
+    }
+
+    /// Returns the address of the peer.
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.remote
+    }
+}
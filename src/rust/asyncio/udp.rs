@@ -0,0 +1,63 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    asyncio::{
+        AsyncLibOS,
+        SocketDescriptor,
+    },
+    runtime::{
+        fail::Fail,
+        SharedObject,
+    },
+};
+use ::socket2::Type;
+use ::std::net::SocketAddr;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// A UDP socket. Clones refer to the same socket, which is closed when the last one is dropped.
+#[derive(Clone)]
+pub struct UdpSocket {
+    libos: AsyncLibOS,
+    socket: SharedObject<SocketDescriptor>,
+    local: SocketAddr,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl UdpSocket {
+    /// Creates a socket that is bound to [local].
+    pub fn bind(libos: &AsyncLibOS, local: SocketAddr) -> Result<Self, Fail> {
+        let socket: SharedObject<SocketDescriptor> = libos.new_socket(&local, Type::DGRAM)?;
+        let mut libos: AsyncLibOS = libos.clone();
+        libos.bind(socket.qd, local)?;
+        Ok(Self { libos, socket, local })
+    }
+
+    /// Sends [data] as a single datagram to [remote]. Returns the number of bytes sent.
+    pub async fn send_to(&self, data: &[u8], remote: SocketAddr) -> Result<usize, Fail> {
+        self.libos.push_data(self.socket.qd, data, Some(remote)).await
+    }
+
+    /// Waits for a datagram. Returns its data and the address of the sender.
+    pub async fn recv_from(&self) -> Result<(Vec<u8>, SocketAddr), Fail> {
+        match self.libos.pop_data(self.socket.qd, None).await? {
+            (data, Some(remote)) => Ok((data, remote)),
+            (_, None) => Err(Fail::new(libc::EBADMSG, "datagram has no source address")),
+        }
+    }
+
+    /// Returns the address that this socket is bound to.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local
+    }
+}
@@ -0,0 +1,1038 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    demikernel::config::Config,
+    expect_ok,
+    expect_some,
+    pal::linux::{
+        self,
+        iouring::{
+            BufferRing,
+            IoUring,
+            IoUringCqe,
+            IoUringSqe,
+            IORING_ACCEPT_MULTISHOT,
+            IORING_ASYNC_CANCEL_ALL,
+            IORING_ASYNC_CANCEL_FD,
+            IORING_CQE_F_MORE,
+            IORING_OP_ACCEPT,
+            IORING_OP_ASYNC_CANCEL,
+            IORING_OP_CONNECT,
+            IORING_OP_RECV,
+            IORING_OP_RECVMSG,
+            IORING_OP_SEND,
+            IORING_OP_SENDMSG,
+            IOSQE_BUFFER_SELECT,
+        },
+    },
+    runtime::{
+        fail::Fail,
+        limits,
+        memory::{
+            DemiBuffer,
+            MemoryRuntime,
+        },
+        network::{
+            ancillary::AncillaryData,
+            socket::{
+                filter::SocketFilter,
+                option::SocketOption,
+            },
+            transport::NetworkTransport,
+        },
+        poll_yield,
+        SharedDemiRuntime,
+        SharedObject,
+    },
+};
+use ::futures::FutureExt;
+use ::slab::Slab;
+use ::socket2::{
+    Domain,
+    Protocol,
+    SockAddr,
+    Socket,
+    Type,
+};
+use ::std::{
+    collections::VecDeque,
+    future::Future,
+    io,
+    mem,
+    net::{
+        Shutdown,
+        SocketAddr,
+    },
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    os::fd::{
+        AsRawFd,
+        FromRawFd,
+        RawFd,
+    },
+    pin::Pin,
+    ptr,
+    task::{
+        Context,
+        Poll,
+        Waker,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Number of entries in the submission queue.
+const IO_URING_SIZE: u32 = 1024;
+
+/// Number of buffers provided to the kernel for receiving data. This must be a power of two.
+const NUM_PROVIDED_BUFFERS: u16 = 256;
+
+/// Buffer group that receive operations pick their buffers from.
+const PROVIDED_BUFFER_GROUP: u16 = 0;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Message header of a datagram that is being sent or received.
+struct Message {
+    hdr: libc::msghdr,
+    iov: libc::iovec,
+    addr: libc::sockaddr_storage,
+    /// Buffer being sent, if any.
+    buf: Option<DemiBuffer>,
+}
+
+/// Memory that the kernel accesses while an operation is in flight. This lives as long as the operation does, even if
+/// the coroutine that issued the operation goes away. Most of it is never read, only kept alive.
+#[allow(dead_code)]
+enum Resources {
+    None,
+    /// Buffer being sent.
+    Buffer(DemiBuffer),
+    /// Address being connected to.
+    Address(Box<SockAddr>),
+    /// Datagram being sent or received.
+    Message(Box<Message>),
+}
+
+/// An operation submitted to the io_uring.
+struct Operation {
+    opcode: u8,
+    /// Completions that were not yet consumed. Multishot operations may have several.
+    completions: VecDeque<(i32, u32)>,
+    /// Will the kernel post more completions for this operation?
+    in_flight: bool,
+    /// Coroutine waiting on this operation.
+    waker: Option<Waker>,
+    resources: Resources,
+    /// Is anyone still interested in this operation? If not, it is released as soon as the kernel is done with it.
+    orphaned: bool,
+}
+
+/// A socket on the io_uring transport.
+struct CatcollarSocket {
+    socket: Socket,
+    typ: Type,
+    /// Multishot accept operation, for listening sockets.
+    accept: Option<usize>,
+}
+
+/// Underlying network transport.
+pub struct CatcollarTransport {
+    io_uring: IoUring,
+    buffers: BufferRing,
+    socket_table: Slab<CatcollarSocket>,
+    operations: Slab<Operation>,
+    runtime: SharedDemiRuntime,
+}
+
+/// Shared network transport across coroutines.
+#[derive(Clone)]
+pub struct SharedCatcollarTransport(SharedObject<CatcollarTransport>);
+
+/// Future that waits for the next completion of an operation.
+struct CompletionFuture {
+    transport: SharedCatcollarTransport,
+    id: usize,
+    /// Does this future own the operation? If so, it releases the operation once the operation completes and orphans
+    /// the operation if it is dropped early.
+    owner: bool,
+    done: bool,
+}
+
+/// Short-hand for our socket descriptor.
+type SockDesc = <SharedCatcollarTransport as NetworkTransport>::SocketDescriptor;
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl SharedCatcollarTransport {
+    /// Create a new io_uring-based network transport.
+    pub fn new(_config: &Config, runtime: &mut SharedDemiRuntime) -> Self {
+        let io_uring: IoUring = expect_ok!(IoUring::new(IO_URING_SIZE), "could not create io_uring");
+        let buffers: BufferRing = expect_ok!(
+            BufferRing::new(
+                &io_uring,
+                PROVIDED_BUFFER_GROUP,
+                NUM_PROVIDED_BUFFERS,
+                limits::RECVBUF_SIZE_MAX
+            ),
+            "could not register provided buffers"
+        );
+
+        // Set up background task for submitting operations and reaping completions.
+        let me: Self = Self(SharedObject::new(CatcollarTransport {
+            io_uring,
+            buffers,
+            socket_table: Slab::<CatcollarSocket>::new(),
+            operations: Slab::<Operation>::new(),
+            runtime: runtime.clone(),
+        }));
+        let mut me2: Self = me.clone();
+        expect_ok!(
+            runtime.insert_background_coroutine(
+                "catcollar::transport::io_uring",
+                Box::pin(async move { me2.poll().await }.fuse()),
+            ),
+            "should be able to insert background coroutine"
+        );
+        me
+    }
+
+    /// Background function for submitting queued operations and dispatching their completions.
+    async fn poll(&mut self) {
+        loop {
+            if let Err(e) = self.io_uring.submit() {
+                warn!("poll(): failed to submit operations: {:?}", e);
+            }
+            while let Some(cqe) = self.io_uring.pop() {
+                self.complete(cqe);
+            }
+            // Yield for one iteration.
+            poll_yield().await;
+        }
+    }
+
+    /// Queues an operation for submission. The operation is submitted the next time that the background coroutine
+    /// runs, so that operations issued by different coroutines are submitted in batch.
+    fn submit(&mut self, mut sqe: IoUringSqe, resources: Resources) -> Result<usize, Fail> {
+        let opcode: u8 = sqe.opcode;
+        let id: usize = self.operations.insert(Operation {
+            opcode,
+            completions: VecDeque::new(),
+            in_flight: true,
+            waker: None,
+            resources,
+            orphaned: false,
+        });
+        sqe.user_data = id as u64;
+        if let Err(e) = self.io_uring.push(sqe) {
+            self.operations.remove(id);
+            return Err(e);
+        }
+        Ok(id)
+    }
+
+    /// Submits an operation and waits for it to complete.
+    async fn submit_and_wait(&mut self, sqe: IoUringSqe, resources: Resources) -> Result<(i32, u32, Resources), Fail> {
+        let id: usize = self.submit(sqe, resources)?;
+        let (res, flags): (i32, u32) = CompletionFuture {
+            transport: self.clone(),
+            id,
+            owner: true,
+            done: false,
+        }
+        .await;
+        // The future released the operation, thus reclaim its resources. This cannot fail, because the kernel is done.
+        Ok((res, flags, self.take_resources(id)))
+    }
+
+    /// Dispatches a completion to the operation that it belongs to.
+    fn complete(&mut self, cqe: IoUringCqe) {
+        let id: usize = cqe.user_data as usize;
+        let orphaned: bool = match self.operations.get_mut(id) {
+            Some(op) => {
+                op.in_flight = cqe.flags & IORING_CQE_F_MORE != 0;
+                if !op.orphaned {
+                    op.completions.push_back((cqe.res, cqe.flags));
+                    if let Some(waker) = op.waker.take() {
+                        waker.wake();
+                    }
+                }
+                op.orphaned
+            },
+            None => {
+                warn!("complete(): completion for unknown operation (id={:?})", id);
+                return;
+            },
+        };
+        if orphaned {
+            let opcode: u8 = self.operations[id].opcode;
+            self.discard(opcode, cqe.res, cqe.flags);
+            if !self.operations[id].in_flight {
+                self.operations.remove(id);
+            }
+        }
+    }
+
+    /// Releases anything that a completion handed over to us, because nobody is going to consume it.
+    fn discard(&mut self, opcode: u8, res: i32, flags: u32) {
+        match opcode {
+            IORING_OP_ACCEPT if res >= 0 => {
+                unsafe { libc::close(res) };
+            },
+            IORING_OP_RECV | IORING_OP_RECVMSG => {
+                if let Some((bid, _)) = self.buffers.get(flags, 0) {
+                    self.buffers.recycle(bid);
+                }
+            },
+            _ => (),
+        }
+    }
+
+    /// Marks an operation as orphaned, discarding completions that were not consumed yet.
+    fn orphan(&mut self, id: usize) {
+        let op: &mut Operation = expect_some!(self.operations.get_mut(id), "operation should exist");
+        op.orphaned = true;
+        op.waker = None;
+        let opcode: u8 = op.opcode;
+        let completions: VecDeque<(i32, u32)> = mem::take(&mut op.completions);
+        let in_flight: bool = op.in_flight;
+        for (res, flags) in completions {
+            self.discard(opcode, res, flags);
+        }
+        if !in_flight {
+            self.operations.remove(id);
+        }
+    }
+
+    /// Takes the resources of an operation that was released.
+    fn take_resources(&mut self, id: usize) -> Resources {
+        match self.operations.try_remove(id) {
+            Some(op) => op.resources,
+            None => Resources::None,
+        }
+    }
+
+    /// Copies the data received by a completion out of its provided buffer and gives the buffer back to the kernel.
+    fn take_buffer(&mut self, flags: u32, len: usize) -> Result<DemiBuffer, Fail> {
+        let (bid, data): (u16, &[u8]) = match self.buffers.get(flags, len) {
+            Some(buf) => buf,
+            // Nothing was received, thus the kernel did not pick a buffer.
+            None => return Ok(DemiBuffer::new(0)),
+        };
+        let buf: Result<DemiBuffer, Fail> = if data.is_empty() {
+            Ok(DemiBuffer::new(0))
+        } else {
+            DemiBuffer::from_slice(data)
+        };
+        self.buffers.recycle(bid);
+        buf
+    }
+
+    /// Starts accepting connections on a listening socket.
+    fn arm_accept(&mut self, sd: &SockDesc) -> Result<(), Fail> {
+        let sqe: IoUringSqe = IoUringSqe {
+            opcode: IORING_OP_ACCEPT,
+            ioprio: IORING_ACCEPT_MULTISHOT,
+            fd: self.raw_fd_from_sd(sd),
+            op_flags: libc::SOCK_CLOEXEC as u32,
+            ..Default::default()
+        };
+        let id: usize = self.submit(sqe, Resources::None)?;
+        self.data_from_sd(sd).accept = Some(id);
+        Ok(())
+    }
+
+    /// Returns the multishot accept operation of a listening socket, rearming it if the kernel stopped it.
+    fn accept_operation(&mut self, sd: &SockDesc) -> Result<usize, Fail> {
+        if let Some(id) = self.data_from_sd(sd).accept {
+            let op: &Operation = expect_some!(self.operations.get(id), "operation should exist");
+            if op.in_flight || !op.completions.is_empty() {
+                return Ok(id);
+            }
+            self.operations.remove(id);
+        }
+        self.arm_accept(sd)?;
+        Ok(expect_some!(self.data_from_sd(sd).accept, "should have armed accept"))
+    }
+
+    /// Builds an operation that cancels all operations in flight on a socket.
+    fn cancel_sqe(&self, sd: &SockDesc) -> IoUringSqe {
+        IoUringSqe {
+            opcode: IORING_OP_ASYNC_CANCEL,
+            fd: self.raw_fd_from_sd(sd),
+            op_flags: IORING_ASYNC_CANCEL_ALL | IORING_ASYNC_CANCEL_FD,
+            ..Default::default()
+        }
+    }
+
+    /// Shuts down a socket and removes it from the socket table.
+    fn release(&mut self, sd: &SockDesc) -> Result<(), Fail> {
+        let result: Result<(), Fail> = match self.socket_from_sd(sd).shutdown(Shutdown::Both) {
+            Ok(()) => Ok(()),
+            Err(e) => match get_libc_err(e) {
+                libc::ENOTCONN => Ok(()),
+                errno => Err(Fail::new(errno, "failed to shutdown socket")),
+            },
+        };
+        if let Some(id) = self.data_from_sd(sd).accept.take() {
+            self.orphan(id);
+        }
+        self.socket_table.remove(*sd);
+        result
+    }
+
+    /// Internal function to get the raw file descriptor from a socket, given the socket descriptor.
+    fn raw_fd_from_sd(&self, sd: &SockDesc) -> RawFd {
+        expect_some!(self.socket_table.get(*sd), "should have been allocated")
+            .socket
+            .as_raw_fd()
+    }
+
+    /// Internal function to get the Socket from the metadata structure, given the socket descriptor.
+    fn socket_from_sd(&mut self, sd: &SockDesc) -> &mut Socket {
+        &mut self.data_from_sd(sd).socket
+    }
+
+    /// Internal function to get the metadata for the socket, given the socket descriptor.
+    fn data_from_sd(&mut self, sd: &SockDesc) -> &mut CatcollarSocket {
+        expect_some!(self.socket_table.get_mut(*sd), "should have been allocated")
+    }
+}
+
+impl Message {
+    /// Builds the message header of an outgoing datagram.
+    fn new_outgoing(buf: &DemiBuffer, addr: Option<SocketAddr>) -> Box<Self> {
+        let mut msg: Box<Self> = Self::new();
+        msg.iov.iov_base = buf.as_ptr() as *mut libc::c_void;
+        msg.iov.iov_len = buf.len();
+        msg.buf = Some(buf.clone());
+        if let Some(addr) = addr {
+            let addr: SockAddr = addr.into();
+            msg.hdr.msg_namelen = addr.len();
+            msg.addr = addr.as_storage();
+        } else {
+            msg.hdr.msg_name = ptr::null_mut();
+            msg.hdr.msg_namelen = 0;
+        }
+        msg
+    }
+
+    /// Builds the message header of an incoming datagram of at most [size] bytes. The data itself is placed in a
+    /// provided buffer.
+    fn new_incoming(size: usize) -> Box<Self> {
+        let mut msg: Box<Self> = Self::new();
+        msg.iov.iov_len = size;
+        msg
+    }
+
+    fn new() -> Box<Self> {
+        // Safety: all fields are plain old data, for which zero is a valid value.
+        let mut msg: Box<Self> = Box::new(Self {
+            hdr: unsafe { mem::zeroed() },
+            iov: unsafe { mem::zeroed() },
+            addr: unsafe { mem::zeroed() },
+            buf: None,
+        });
+        // The message is boxed, thus these pointers remain valid as long as the message lives.
+        msg.hdr.msg_name = &mut msg.addr as *mut libc::sockaddr_storage as *mut libc::c_void;
+        msg.hdr.msg_namelen = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+        msg.hdr.msg_iov = &mut msg.iov as *mut libc::iovec;
+        msg.hdr.msg_iovlen = 1;
+        msg
+    }
+
+    /// Returns the address that a datagram was received from.
+    fn source(&self) -> Option<SocketAddr> {
+        // Safety: the kernel initialized the address and its length.
+        unsafe { SockAddr::new(self.addr, self.hdr.msg_namelen) }.as_socket()
+    }
+}
+
+//======================================================================================================================
+// Standalone functions
+//======================================================================================================================
+
+/// Internal function to extract the raw OS error code.
+fn get_libc_err(e: io::Error) -> i32 {
+    expect_some!(e.raw_os_error(), "should have an os error code")
+}
+
+/// Builds a [Fail] out of a failed completion.
+fn completion_fail(res: i32, op: &str) -> Fail {
+    let cause: String = format!("{} failed (errno={:?})", op, -res);
+    error!("{}", cause);
+    Fail::new(-res, &cause)
+}
+
+//======================================================================================================================
+// Trait implementation
+//======================================================================================================================
+
+/// Dereference a shared reference to the underlying transport.
+impl Deref for SharedCatcollarTransport {
+    type Target = CatcollarTransport;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+/// Dereference a shared mutable reference to the underlying transport.
+impl DerefMut for SharedCatcollarTransport {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+impl Future for CompletionFuture {
+    type Output = (i32, u32);
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        let self_: &mut Self = self.get_mut();
+        let mut transport: SharedCatcollarTransport = self_.transport.clone();
+        let op: &mut Operation = expect_some!(transport.operations.get_mut(self_.id), "operation should exist");
+        match op.completions.pop_front() {
+            Some(completion) => {
+                if self_.owner && !op.in_flight {
+                    self_.done = true;
+                }
+                Poll::Ready(completion)
+            },
+            None => {
+                op.waker = Some(context.waker().clone());
+                Poll::Pending
+            },
+        }
+    }
+}
+
+impl Drop for CompletionFuture {
+    fn drop(&mut self) {
+        // If the coroutine that waits on this operation goes away, the kernel may still be accessing the resources of
+        // the operation, thus we cannot release it right away.
+        if self.owner && !self.done {
+            self.transport.orphan(self.id);
+        }
+    }
+}
+
+impl NetworkTransport for SharedCatcollarTransport {
+    type SocketDescriptor = usize;
+
+    /// Creates a new socket on the underlying network transport. We only support IPv4 and UDP and TCP sockets for now.
+    fn socket(&mut self, domain: Domain, typ: Type, _: Protocol) -> Result<Self::SocketDescriptor, Fail> {
+        // Select protocol.
+        let protocol: Protocol = match typ {
+            Type::STREAM => Protocol::TCP,
+            Type::DGRAM => Protocol::UDP,
+            _ => {
+                return Err(Fail::new(libc::ENOTSUP, "socket type not supported"));
+            },
+        };
+
+        // Create socket. Sockets are left in blocking mode, otherwise io_uring fails operations that would block
+        // instead of waiting for them to complete.
+        let socket: Socket = match socket2::Socket::new(domain, typ, Some(protocol)) {
+            Ok(socket) => socket,
+            Err(e) => {
+                let cause: String = format!("failed to create socket: {:?}", e);
+                error!("socket(): {}", cause);
+                return Err(Fail::new(get_libc_err(e), &cause));
+            },
+        };
+        if let Err(e) = socket.set_reuse_address(true) {
+            let cause: String = format!("cannot set REUSE_ADDRESS option: {:?}", e);
+            error!("socket(): {}", cause);
+            return Err(Fail::new(get_libc_err(e), &cause));
+        }
+        if typ == Type::STREAM {
+            if let Err(e) = socket.set_nodelay(true) {
+                let cause: String = format!("cannot set TCP_NODELAY option: {:?}", e);
+                error!("socket(): {}", cause);
+                return Err(Fail::new(get_libc_err(e), &cause));
+            }
+        }
+
+        Ok(self.socket_table.insert(CatcollarSocket {
+            socket,
+            typ,
+            accept: None,
+        }))
+    }
+
+    /// Binds a socket to [local] on the underlying network transport.
+    fn bind(&mut self, sd: &mut Self::SocketDescriptor, local: SocketAddr) -> Result<(), Fail> {
+        trace!("Bind to {:?}", local);
+        let socket: &mut Socket = self.socket_from_sd(sd);
+
+        // Set SO_REUSE_PORT.
+        let optval: libc::c_int = 1;
+        let optval_len: libc::socklen_t = mem::size_of_val(&optval) as libc::socklen_t;
+        if unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_REUSEPORT,
+                &optval as *const _ as *const libc::c_void,
+                optval_len,
+            )
+        } < 0
+        {
+            let e: i32 = get_libc_err(io::Error::last_os_error());
+            let cause: String = format!("cannot set REUSE_PORT option: {:?}", e);
+            error!("bind(): {}", cause);
+            return Err(Fail::new(e, &cause));
+        }
+
+        if let Err(e) = socket.bind(&local.into()) {
+            let cause: String = format!("failed to bind socket: {:?}", e);
+            error!("bind(): {}", cause);
+            Err(Fail::new(get_libc_err(e), &cause))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sets a socket to passive listening on the underlying transport and starts accepting incoming connections in the
+    /// background with a single multishot accept operation.
+    fn listen(&mut self, sd: &mut Self::SocketDescriptor, backlog: usize) -> Result<(), Fail> {
+        trace!("Listen to");
+        if let Err(e) = self.socket_from_sd(sd).listen(backlog as i32) {
+            let cause: String = format!("failed to listen on socket: {:?}", e);
+            error!("listen(): {}", cause);
+            return Err(Fail::new(get_libc_err(e), &cause));
+        }
+        self.arm_accept(sd)
+    }
+
+    /// Accept the next incoming connection. This function blocks until a new connection arrives from the underlying
+    /// transport.
+    async fn accept(&mut self, sd: &mut Self::SocketDescriptor) -> Result<(Self::SocketDescriptor, SocketAddr), Fail> {
+        let id: usize = self.accept_operation(sd)?;
+        let (res, _): (i32, u32) = CompletionFuture {
+            transport: self.clone(),
+            id,
+            owner: false,
+            done: false,
+        }
+        .await;
+        if res < 0 {
+            return Err(completion_fail(res, "accept()"));
+        }
+
+        // Safety: the kernel handed over a new file descriptor that nobody else owns.
+        let new_socket: Socket = unsafe { Socket::from_raw_fd(res) };
+        if let Err(e) = new_socket.set_nodelay(true) {
+            let cause: String = format!("cannot set TCP_NODELAY option: {:?}", e);
+            error!("accept(): {}", cause);
+            return Err(Fail::new(get_libc_err(e), &cause));
+        }
+        let addr: SocketAddr = match new_socket.peer_addr().map(|addr| addr.as_socket()) {
+            Ok(Some(addr)) => addr,
+            Ok(None) => return Err(Fail::new(libc::EAFNOSUPPORT, "unsupported address family")),
+            Err(e) => {
+                let cause: String = format!("failed to get address of peer: {:?}", e);
+                error!("accept(): {}", cause);
+                return Err(Fail::new(get_libc_err(e), &cause));
+            },
+        };
+
+        let new_sd: usize = self.socket_table.insert(CatcollarSocket {
+            socket: new_socket,
+            typ: Type::STREAM,
+            accept: None,
+        });
+        Ok((new_sd, addr))
+    }
+
+    /// Connect to [remote] through the underlying transport. This function blocks until the connect succeeds or fails
+    /// with an error.
+    async fn connect(&mut self, sd: &mut Self::SocketDescriptor, remote: SocketAddr) -> Result<(), Fail> {
+        let addr: Box<SockAddr> = Box::new(remote.into());
+        let sqe: IoUringSqe = IoUringSqe {
+            opcode: IORING_OP_CONNECT,
+            fd: self.raw_fd_from_sd(sd),
+            addr: addr.as_ptr() as u64,
+            off: addr.len() as u64,
+            ..Default::default()
+        };
+        match self.submit_and_wait(sqe, Resources::Address(addr)).await? {
+            (res, _, _) if res < 0 => Err(completion_fail(res, "connect()")),
+            _ => Ok(()),
+        }
+    }
+
+    /// Cancel all operations in flight on the socket, then close it.
+    async fn close(&mut self, sd: &mut Self::SocketDescriptor) -> Result<(), Fail> {
+        let sqe: IoUringSqe = self.cancel_sqe(sd);
+        // This fails with ENOENT if there was nothing to cancel, which is fine.
+        self.submit_and_wait(sqe, Resources::None).await?;
+        self.release(sd)
+    }
+
+    /// Push [buf] to the underlying transport. This function blocks until the entire buffer has been written to the
+    /// socket. Returns Ok if successfully sent and an error if not.
+    async fn push(
+        &mut self,
+        sd: &mut Self::SocketDescriptor,
+        buf: &mut DemiBuffer,
+        addr: Option<SocketAddr>,
+    ) -> Result<(), Fail> {
+        let fd: RawFd = self.raw_fd_from_sd(sd);
+        if self.data_from_sd(sd).typ == Type::DGRAM {
+            let msg: Box<Message> = Message::new_outgoing(buf, addr);
+            let sqe: IoUringSqe = IoUringSqe {
+                opcode: IORING_OP_SENDMSG,
+                fd,
+                addr: &msg.hdr as *const libc::msghdr as u64,
+                len: 1,
+                op_flags: libc::MSG_NOSIGNAL as u32,
+                ..Default::default()
+            };
+            let (res, _, _): (i32, u32, Resources) = self.submit_and_wait(sqe, Resources::Message(msg)).await?;
+            if res < 0 {
+                return Err(completion_fail(res, "sendmsg()"));
+            }
+        } else {
+            // Streams may accept fewer bytes than requested, thus keep sending until the entire buffer is out.
+            let mut sent: usize = 0;
+            while sent < buf.len() {
+                let sqe: IoUringSqe = IoUringSqe {
+                    opcode: IORING_OP_SEND,
+                    fd,
+                    addr: unsafe { buf.as_ptr().add(sent) } as u64,
+                    len: (buf.len() - sent) as u32,
+                    op_flags: libc::MSG_NOSIGNAL as u32,
+                    ..Default::default()
+                };
+                let (res, _, _): (i32, u32, Resources) =
+                    self.submit_and_wait(sqe, Resources::Buffer(buf.clone())).await?;
+                if res < 0 {
+                    return Err(completion_fail(res, "send()"));
+                }
+                sent += res as usize;
+            }
+        }
+        // Clear out the original buffer.
+        expect_ok!(buf.trim(buf.len()), "Should be able to empty the buffer");
+        Ok(())
+    }
+
+    /// Pop a [buf] of at most [size] from the underlying transport. This function blocks until the socket has data to
+    /// be read. For connected (i.e., TCP) sockets, this function returns Ok(None). For datagram (i.e., UDP) sockets,
+    /// this function returns the remote address that is the source of the incoming data. Data is received into buffers
+    /// provided to the kernel upfront, so waiting for data does not pin any memory.
+    async fn pop(
+        &mut self,
+        sd: &mut Self::SocketDescriptor,
+        size: usize,
+    ) -> Result<(Option<SocketAddr>, DemiBuffer, AncillaryData), Fail> {
+        let fd: RawFd = self.raw_fd_from_sd(sd);
+        let is_dgram: bool = self.data_from_sd(sd).typ == Type::DGRAM;
+        let size: usize = size.min(self.buffers.buffer_size());
+        loop {
+            let (sqe, resources): (IoUringSqe, Resources) = if is_dgram {
+                let msg: Box<Message> = Message::new_incoming(size);
+                let sqe: IoUringSqe = IoUringSqe {
+                    opcode: IORING_OP_RECVMSG,
+                    flags: IOSQE_BUFFER_SELECT,
+                    fd,
+                    addr: &msg.hdr as *const libc::msghdr as u64,
+                    len: 1,
+                    buf_group: self.buffers.bgid(),
+                    ..Default::default()
+                };
+                (sqe, Resources::Message(msg))
+            } else {
+                let sqe: IoUringSqe = IoUringSqe {
+                    opcode: IORING_OP_RECV,
+                    flags: IOSQE_BUFFER_SELECT,
+                    fd,
+                    len: size as u32,
+                    buf_group: self.buffers.bgid(),
+                    ..Default::default()
+                };
+                (sqe, Resources::None)
+            };
+
+            match self.submit_and_wait(sqe, resources).await? {
+                (res, flags, resources) if res >= 0 => {
+                    let buf: DemiBuffer = self.take_buffer(flags, res as usize)?;
+                    let addr: Option<SocketAddr> = match resources {
+                        Resources::Message(msg) => msg.source(),
+                        _ => None,
+                    };
+                    return Ok((addr, buf, AncillaryData::default()));
+                },
+                // All provided buffers are in use, thus wait for some to be given back.
+                (res, _, _) if res == -libc::ENOBUFS => poll_yield().await,
+                (res, _, _) => return Err(completion_fail(res, "recv()")),
+            }
+        }
+    }
+
+    /// Close the socket on the underlying transport. Operations in flight on the socket are canceled in the background.
+    /// Sets [option] on the underlying socket.
+    fn set_socket_option(&mut self, sd: &mut Self::SocketDescriptor, option: SocketOption) -> Result<(), Fail> {
+        let socket: &mut Socket = self.socket_from_sd(sd);
+        let result: io::Result<()> = match option {
+            SocketOption::IP_TOS(tos) => socket.set_tos(tos as u32),
+            SocketOption::IP_TTL(ttl) => socket.set_ttl(ttl as u32),
+            SocketOption::SO_PRIORITY(priority) => {
+                let optval: libc::c_int = priority as libc::c_int;
+                let optval_len: libc::socklen_t = mem::size_of_val(&optval) as libc::socklen_t;
+                match unsafe {
+                    libc::setsockopt(
+                        socket.as_raw_fd(),
+                        libc::SOL_SOCKET,
+                        libc::SO_PRIORITY,
+                        &optval as *const _ as *const libc::c_void,
+                        optval_len,
+                    )
+                } {
+                    0 => Ok(()),
+                    _ => Err(io::Error::last_os_error()),
+                }
+            },
+            SocketOption::SO_MAX_PACING_RATE(rate) => {
+                let optval: u64 = rate;
+                let optval_len: libc::socklen_t = mem::size_of_val(&optval) as libc::socklen_t;
+                match unsafe {
+                    libc::setsockopt(
+                        socket.as_raw_fd(),
+                        libc::SOL_SOCKET,
+                        libc::SO_MAX_PACING_RATE,
+                        &optval as *const _ as *const libc::c_void,
+                        optval_len,
+                    )
+                } {
+                    0 => Ok(()),
+                    _ => Err(io::Error::last_os_error()),
+                }
+            },
+            SocketOption::SO_NO_CHECK(no_check) => {
+                let optval: libc::c_int = no_check as libc::c_int;
+                let optval_len: libc::socklen_t = mem::size_of_val(&optval) as libc::socklen_t;
+                match unsafe {
+                    libc::setsockopt(
+                        socket.as_raw_fd(),
+                        libc::SOL_SOCKET,
+                        libc::SO_NO_CHECK,
+                        &optval as *const _ as *const libc::c_void,
+                        optval_len,
+                    )
+                } {
+                    0 => Ok(()),
+                    _ => Err(io::Error::last_os_error()),
+                }
+            },
+            SocketOption::UDP_SEGMENT(size) => {
+                let optval: libc::c_int = size as libc::c_int;
+                let optval_len: libc::socklen_t = mem::size_of_val(&optval) as libc::socklen_t;
+                match unsafe {
+                    libc::setsockopt(
+                        socket.as_raw_fd(),
+                        libc::SOL_UDP,
+                        libc::UDP_SEGMENT,
+                        &optval as *const _ as *const libc::c_void,
+                        optval_len,
+                    )
+                } {
+                    0 => Ok(()),
+                    _ => Err(io::Error::last_os_error()),
+                }
+            },
+            // The kernel aborts its connections on user timeouts as the inetstack does.
+            SocketOption::TCP_USER_TIMEOUT(millis) => {
+                let optval: libc::c_uint = millis;
+                let optval_len: libc::socklen_t = mem::size_of_val(&optval) as libc::socklen_t;
+                match unsafe {
+                    libc::setsockopt(
+                        socket.as_raw_fd(),
+                        libc::IPPROTO_TCP,
+                        libc::TCP_USER_TIMEOUT,
+                        &optval as *const _ as *const libc::c_void,
+                        optval_len,
+                    )
+                } {
+                    0 => Ok(()),
+                    _ => Err(io::Error::last_os_error()),
+                }
+            },
+            // Error queues are kept by the inetstack, so they cannot be enabled on kernel sockets.
+            SocketOption::IP_RECVERR(_) => Err(io::Error::from_raw_os_error(libc::ENOTSUP)),
+            // Transmit timestamps are queued by the inetstack, so they cannot be read back from kernel sockets.
+            SocketOption::SO_TIMESTAMPING(_) => Err(io::Error::from_raw_os_error(libc::ENOTSUP)),
+            // Interfaces are numbered by the inetstack, so their indices do not match those of the kernel.
+            SocketOption::SO_BINDTODEVICE(_) => Err(io::Error::from_raw_os_error(libc::ENOTSUP)),
+            // Kernel sockets do not tell when their data is acknowledged, so pushes complete once it is accepted.
+            SocketOption::SO_TXCOMPLETE(false) => Ok(()),
+            SocketOption::SO_TXCOMPLETE(true) => Err(io::Error::from_raw_os_error(libc::ENOTSUP)),
+            SocketOption::SO_DOORBELL(_) => Err(io::Error::from_raw_os_error(libc::ENOPROTOOPT)),
+            SocketOption::TCP_MD5SIG(key) => {
+                linux::set_tcp_md5_key(socket.as_raw_fd(), key.addr(), key.prefix_len(), key.key())
+            },
+            // Authentication keys of kernel sockets are not wired up.
+            SocketOption::TCP_AO_ADD_KEY(_) | SocketOption::TCP_AO_DEL_KEY(_) | SocketOption::TCP_AO_INFO(_) => {
+                Err(io::Error::from_raw_os_error(libc::ENOTSUP))
+            },
+            // Congestion control and retransmission timeouts are tuned in the inetstack, so kernel sockets do not take them.
+            SocketOption::TCP_INIT_CWND(_)
+            | SocketOption::TCP_RTO_INIT_US(_)
+            | SocketOption::TCP_RTO_MIN_US(_)
+            | SocketOption::TCP_RTO_MAX_MS(_) => Err(io::Error::from_raw_os_error(libc::ENOTSUP)),
+        };
+        if let Err(e) = result {
+            let cause: String = format!("failed to set socket option (option={:?}): {:?}", option, e);
+            error!("set_socket_option(): {}", cause);
+            return Err(Fail::new(get_libc_err(e), &cause));
+        }
+        Ok(())
+    }
+
+    /// Receive filters run in the inetstack, so they cannot be attached to kernel sockets.
+    fn set_receive_filter(&mut self, _: &mut Self::SocketDescriptor, _: Option<SocketFilter>) -> Result<(), Fail> {
+        let cause: &str = "receive filters are not supported";
+        error!("set_receive_filter(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, cause))
+    }
+
+    /// Gets the current value of [option] on the underlying socket.
+    fn get_socket_option(
+        &mut self,
+        sd: &mut Self::SocketDescriptor,
+        option: SocketOption,
+    ) -> Result<SocketOption, Fail> {
+        let socket: &mut Socket = self.socket_from_sd(sd);
+        let result: io::Result<SocketOption> = match option {
+            SocketOption::IP_TOS(_) => socket.tos().map(|tos| SocketOption::IP_TOS(tos as u8)),
+            SocketOption::IP_TTL(_) => socket.ttl().map(|ttl| SocketOption::IP_TTL(ttl as u8)),
+            SocketOption::SO_PRIORITY(_) => {
+                let mut optval: libc::c_int = 0;
+                let mut optval_len: libc::socklen_t = mem::size_of_val(&optval) as libc::socklen_t;
+                match unsafe {
+                    libc::getsockopt(
+                        socket.as_raw_fd(),
+                        libc::SOL_SOCKET,
+                        libc::SO_PRIORITY,
+                        &mut optval as *mut _ as *mut libc::c_void,
+                        &mut optval_len,
+                    )
+                } {
+                    0 => Ok(SocketOption::SO_PRIORITY(optval as u8)),
+                    _ => Err(io::Error::last_os_error()),
+                }
+            },
+            SocketOption::SO_MAX_PACING_RATE(_) => {
+                let mut optval: u64 = 0;
+                let mut optval_len: libc::socklen_t = mem::size_of_val(&optval) as libc::socklen_t;
+                match unsafe {
+                    libc::getsockopt(
+                        socket.as_raw_fd(),
+                        libc::SOL_SOCKET,
+                        libc::SO_MAX_PACING_RATE,
+                        &mut optval as *mut _ as *mut libc::c_void,
+                        &mut optval_len,
+                    )
+                } {
+                    0 => Ok(SocketOption::SO_MAX_PACING_RATE(optval)),
+                    _ => Err(io::Error::last_os_error()),
+                }
+            },
+            SocketOption::IP_RECVERR(_) | SocketOption::SO_TIMESTAMPING(_) | SocketOption::SO_BINDTODEVICE(_) => {
+                Err(io::Error::from_raw_os_error(libc::ENOTSUP))
+            },
+            SocketOption::SO_TXCOMPLETE(_) => Ok(SocketOption::SO_TXCOMPLETE(false)),
+            SocketOption::SO_DOORBELL(_) => Ok(SocketOption::SO_DOORBELL(false)),
+            // Keys cannot be read back, like on Linux.
+            SocketOption::TCP_MD5SIG(_) | SocketOption::TCP_AO_ADD_KEY(_) | SocketOption::TCP_AO_DEL_KEY(_) => {
+                Err(io::Error::from_raw_os_error(libc::ENOPROTOOPT))
+            },
+            SocketOption::TCP_AO_INFO(_) => Err(io::Error::from_raw_os_error(libc::ENOTSUP)),
+            SocketOption::SO_NO_CHECK(_) => {
+                let mut optval: libc::c_int = 0;
+                let mut optval_len: libc::socklen_t = mem::size_of_val(&optval) as libc::socklen_t;
+                match unsafe {
+                    libc::getsockopt(
+                        socket.as_raw_fd(),
+                        libc::SOL_SOCKET,
+                        libc::SO_NO_CHECK,
+                        &mut optval as *mut _ as *mut libc::c_void,
+                        &mut optval_len,
+                    )
+                } {
+                    0 => Ok(SocketOption::SO_NO_CHECK(optval != 0)),
+                    _ => Err(io::Error::last_os_error()),
+                }
+            },
+            SocketOption::UDP_SEGMENT(_) => {
+                let mut optval: libc::c_int = 0;
+                let mut optval_len: libc::socklen_t = mem::size_of_val(&optval) as libc::socklen_t;
+                match unsafe {
+                    libc::getsockopt(
+                        socket.as_raw_fd(),
+                        libc::SOL_UDP,
+                        libc::UDP_SEGMENT,
+                        &mut optval as *mut _ as *mut libc::c_void,
+                        &mut optval_len,
+                    )
+                } {
+                    0 => Ok(SocketOption::UDP_SEGMENT(optval as u16)),
+                    _ => Err(io::Error::last_os_error()),
+                }
+            },
+            SocketOption::TCP_USER_TIMEOUT(_) => {
+                let mut optval: libc::c_uint = 0;
+                let mut optval_len: libc::socklen_t = mem::size_of_val(&optval) as libc::socklen_t;
+                match unsafe {
+                    libc::getsockopt(
+                        socket.as_raw_fd(),
+                        libc::IPPROTO_TCP,
+                        libc::TCP_USER_TIMEOUT,
+                        &mut optval as *mut _ as *mut libc::c_void,
+                        &mut optval_len,
+                    )
+                } {
+                    0 => Ok(SocketOption::TCP_USER_TIMEOUT(optval)),
+                    _ => Err(io::Error::last_os_error()),
+                }
+            },
+            // Congestion control and retransmission timeouts are tuned in the inetstack, so kernel sockets do not take them.
+            SocketOption::TCP_INIT_CWND(_)
+            | SocketOption::TCP_RTO_INIT_US(_)
+            | SocketOption::TCP_RTO_MIN_US(_)
+            | SocketOption::TCP_RTO_MAX_MS(_) => Err(io::Error::from_raw_os_error(libc::ENOTSUP)),
+        };
+        result.map_err(|e| {
+            let cause: String = format!("failed to get socket option (option={:?}): {:?}", option, e);
+            error!("get_socket_option(): {}", cause);
+            Fail::new(get_libc_err(e), &cause)
+        })
+    }
+
+    fn hard_close(&mut self, sd: &mut Self::SocketDescriptor) -> Result<(), Fail> {
+        let sqe: IoUringSqe = self.cancel_sqe(sd);
+        let id: usize = self.submit(sqe, Resources::None)?;
+        self.orphan(id);
+        self.release(sd)
+    }
+
+    fn get_runtime(&self) -> &SharedDemiRuntime {
+        &self.runtime
+    }
+}
+
+impl MemoryRuntime for SharedCatcollarTransport {}
@@ -0,0 +1,521 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    catloop::{
+        socket::{
+            ConnectRequest,
+            SharedMemorySocket,
+        },
+        transport::SharedCatloopTransport,
+    },
+    demikernel::config::Config,
+    expect_some,
+    runtime::{
+        fail::Fail,
+        memory::{
+            DemiBuffer,
+            MemoryRuntime,
+        },
+        network::{
+            ancillary::AncillaryData,
+            config::{
+                IpsecDirection,
+                Route,
+                SecurityAssociation,
+            },
+            drops::{
+                DropRecord,
+                DropStats,
+            },
+            numa::NumaStats,
+            socket::{
+                filter::SocketFilter,
+                option::SocketOption,
+            },
+            tcp_info::{
+                TcpEvent,
+                TcpInfo,
+            },
+            timestamp::TxTimestamp,
+            transport::NetworkTransport,
+            unwrap_socketaddr,
+        },
+        types::demi_sgarray_t,
+        SharedDemiRuntime,
+        SharedObject,
+    },
+};
+use ::futures::FutureExt;
+use ::socket2::{
+    Domain,
+    Protocol,
+    Type,
+};
+use ::std::{
+    fmt::Debug,
+    net::{
+        Ipv4Addr,
+        SocketAddr,
+        SocketAddrV4,
+    },
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    os::fd::RawFd,
+    pin::pin,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// [DispatchTransport] puts a network transport and memory queues behind the same sockets, and picks one of them for
+/// every connection. Connections to an address that a passive socket on this host listens on go over memory queues,
+/// while the other ones go over the network transport. Listening sockets accept connections from both.
+///
+/// The namespace of local addresses is the one of Catloop: passive memory sockets create a control queue that is named
+/// after their address, which clients look up before connecting. Datagram sockets always use the network transport.
+pub struct DispatchTransport<T: NetworkTransport> {
+    /// Transport of connections to addresses on this host.
+    catloop: SharedCatloopTransport,
+    /// Transport of the other connections.
+    transport: T,
+    /// Address of this host, which memory sockets bind to when sockets are bound to the unspecified or loopback
+    /// address.
+    local_ipv4_addr: Option<Ipv4Addr>,
+    /// Underlying coroutine runtime.
+    runtime: SharedDemiRuntime,
+}
+
+#[derive(Clone)]
+pub struct SharedDispatchTransport<T: NetworkTransport>(SharedObject<DispatchTransport<T>>);
+
+/// A socket of a [DispatchTransport]. Until a stream socket is connected, it has both a network socket and a memory
+/// socket. Connecting or accepting keeps only the one that carries the connection. Sockets that are bound to or connect
+/// to a name only have a memory socket.
+pub struct DispatchSocket<S: Debug> {
+    /// Socket of the network transport.
+    remote: Option<S>,
+    /// Socket of the memory transport.
+    local: Option<SharedMemorySocket>,
+}
+
+/// Incoming connection of a passive [DispatchSocket].
+enum Incoming<S> {
+    /// Connection request over memory queues, which is yet to be accepted.
+    Local(ConnectRequest),
+    /// Connection that the network transport accepted.
+    Remote(S, SocketAddr),
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl<T: NetworkTransport> SharedDispatchTransport<T> {
+    pub fn new(config: &Config, runtime: SharedDemiRuntime, transport: T) -> Self {
+        Self(SharedObject::new(DispatchTransport {
+            catloop: SharedCatloopTransport::new(config, runtime.clone()),
+            transport,
+            local_ipv4_addr: config.local_ipv4_addr().ok(),
+            runtime,
+        }))
+    }
+
+    /// Gets the address that memory queues name [addr] with. Sockets that are bound to the unspecified or loopback
+    /// address listen on the address of this host, and connections to the loopback address look them up there.
+    fn memory_addr(&self, addr: SocketAddrV4) -> Option<SocketAddrV4> {
+        if addr.ip().is_unspecified() || addr.ip().is_loopback() {
+            self.local_ipv4_addr.map(|ipv4| SocketAddrV4::new(ipv4, addr.port()))
+        } else {
+            Some(addr)
+        }
+    }
+}
+
+impl<S: Debug> DispatchSocket<S> {
+    /// Gets the socket that carries the traffic of the target [DispatchSocket]: the network socket, unless the socket
+    /// is connected over memory queues.
+    fn target(&mut self) -> Result<&mut S, &mut SharedMemorySocket> {
+        match (self.remote.as_mut(), self.local.as_mut()) {
+            (Some(remote), _) => Ok(remote),
+            (None, local) => Err(expect_some!(local, "socket should have a memory socket")),
+        }
+    }
+
+    /// Gets the memory socket of the target [DispatchSocket], which only stream sockets have.
+    fn memory_socket(&mut self) -> Result<&mut SharedMemorySocket, Fail> {
+        match self.local.as_mut() {
+            Some(local) => Ok(local),
+            None => {
+                let cause: &str = "named endpoints are only supported on stream sockets";
+                error!("memory_socket(): {}", cause);
+                Err(Fail::new(libc::ENOTSUP, cause))
+            },
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl<T: NetworkTransport> NetworkTransport for SharedDispatchTransport<T> {
+    type SocketDescriptor = DispatchSocket<T::SocketDescriptor>;
+
+    /// Creates a network socket, along with a memory socket for stream sockets.
+    fn socket(&mut self, domain: Domain, typ: Type, protocol: Protocol) -> Result<Self::SocketDescriptor, Fail> {
+        let remote: T::SocketDescriptor = self.transport.socket(domain, typ, protocol)?;
+        let local: Option<SharedMemorySocket> = match typ {
+            Type::STREAM => Some(self.catloop.socket(domain, typ, protocol)?),
+            _ => None,
+        };
+        Ok(DispatchSocket {
+            remote: Some(remote),
+            local,
+        })
+    }
+
+    /// Binds both sockets. If the memory socket cannot be bound to the same address, connections to the socket only go
+    /// over the network.
+    fn bind(&mut self, sd: &mut Self::SocketDescriptor, local: SocketAddr) -> Result<(), Fail> {
+        let remote: &mut T::SocketDescriptor = expect_some!(sd.remote.as_mut(), "unbound socket should be remote");
+        self.transport.bind(remote, local)?;
+        if let Some(mut socket) = sd.local.take() {
+            match self.memory_addr(unwrap_socketaddr(local)?) {
+                Some(addr) if addr.port() != 0 && self.catloop.bind(&mut socket, addr.into()).is_ok() => {
+                    sd.local = Some(socket)
+                },
+                _ => warn!("bind(): connections to {:?} will only go over the network", local),
+            }
+        }
+        Ok(())
+    }
+
+    /// Binds the memory socket to [name]. Named sockets are only reachable over memory queues, so the network socket is
+    /// closed.
+    fn bind_name(&mut self, sd: &mut Self::SocketDescriptor, name: &str) -> Result<(), Fail> {
+        self.catloop.bind_name(sd.memory_socket()?, name)?;
+        if let Some(mut socket) = sd.remote.take() {
+            self.transport.hard_close(&mut socket)?;
+        }
+        Ok(())
+    }
+
+    /// Listens on both sockets. If the address is taken in the namespace of memory queues, connections to the socket
+    /// only go over the network. Named sockets only listen on memory queues.
+    fn listen(&mut self, sd: &mut Self::SocketDescriptor, backlog: usize) -> Result<(), Fail> {
+        let remote: &mut T::SocketDescriptor = match sd.remote.as_mut() {
+            Some(remote) => remote,
+            None => return self.catloop.listen(sd.memory_socket()?, backlog),
+        };
+        self.transport.listen(remote, backlog)?;
+        if let Some(mut socket) = sd.local.take() {
+            match self.catloop.listen(&mut socket, backlog) {
+                Ok(()) => sd.local = Some(socket),
+                Err(e) => warn!("listen(): connections will only go over the network (error={:?})", e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Accepts the next connection from either transport. Only the first step of memory connections races with the
+    /// network transport, so that a connection is never dropped halfway through its setup.
+    async fn accept(&mut self, sd: &mut Self::SocketDescriptor) -> Result<(Self::SocketDescriptor, SocketAddr), Fail> {
+        let mut transport: T = self.transport.clone();
+        let mut catloop: SharedCatloopTransport = self.catloop.clone();
+        let incoming: Incoming<T::SocketDescriptor> = match (sd.remote.as_mut(), sd.local.as_mut()) {
+            (Some(remote), Some(local)) => futures::select_biased! {
+                request = pin!(catloop.next_connect_request(local).fuse()) => Incoming::Local(request?),
+                result = pin!(transport.accept(remote).fuse()) => {
+                    let (new_sd, addr) = result?;
+                    Incoming::Remote(new_sd, addr)
+                },
+            },
+            (Some(remote), None) => {
+                let (new_sd, addr) = transport.accept(remote).await?;
+                Incoming::Remote(new_sd, addr)
+            },
+            (None, local) => {
+                let local: &mut SharedMemorySocket = expect_some!(local, "named socket should have a memory socket");
+                Incoming::Local(catloop.next_connect_request(local).await?)
+            },
+        };
+
+        match incoming {
+            Incoming::Local(request) => {
+                let local: &mut SharedMemorySocket = expect_some!(sd.local.as_mut(), "socket should be listening");
+                let (new_socket, addr) = catloop.accept_request(local, request).await?;
+                let new_sd: Self::SocketDescriptor = DispatchSocket {
+                    remote: None,
+                    local: Some(new_socket),
+                };
+                Ok((new_sd, addr))
+            },
+            Incoming::Remote(new_sd, addr) => {
+                let new_sd: Self::SocketDescriptor = DispatchSocket {
+                    remote: Some(new_sd),
+                    local: None,
+                };
+                Ok((new_sd, addr))
+            },
+        }
+    }
+
+    /// Connects over memory queues if a passive socket on this host listens on [remote], or else over the network.
+    /// The socket of the other transport is then closed.
+    async fn connect(&mut self, sd: &mut Self::SocketDescriptor, remote: SocketAddr) -> Result<(), Fail> {
+        let addr: Option<SocketAddrV4> = self.memory_addr(unwrap_socketaddr(remote)?);
+        if let (Some(local), Some(addr)) = (sd.local.as_mut(), addr) {
+            if self.catloop.is_listening(addr) {
+                self.catloop.connect(local, addr.into()).await?;
+                if let Some(mut socket) = sd.remote.take() {
+                    self.transport.hard_close(&mut socket)?;
+                }
+                return Ok(());
+            }
+        }
+
+        let socket: &mut T::SocketDescriptor = expect_some!(sd.remote.as_mut(), "unconnected socket should be remote");
+        self.transport.connect(socket, remote).await?;
+        if let Some(mut socket) = sd.local.take() {
+            self.catloop.hard_close(&mut socket)?;
+        }
+        Ok(())
+    }
+
+    /// Connects the memory socket to the socket that is bound to [name], and closes the network socket.
+    async fn connect_name(&mut self, sd: &mut Self::SocketDescriptor, name: &str) -> Result<(), Fail> {
+        self.catloop.connect_name(sd.memory_socket()?, name).await?;
+        if let Some(mut socket) = sd.remote.take() {
+            self.transport.hard_close(&mut socket)?;
+        }
+        Ok(())
+    }
+
+    async fn close(&mut self, sd: &mut Self::SocketDescriptor) -> Result<(), Fail> {
+        if let Some(socket) = sd.remote.as_mut() {
+            self.transport.close(socket).await?;
+        }
+        if let Some(socket) = sd.local.as_mut() {
+            self.catloop.close(socket).await?;
+        }
+        Ok(())
+    }
+
+    fn hard_close(&mut self, sd: &mut Self::SocketDescriptor) -> Result<(), Fail> {
+        if let Some(socket) = sd.remote.as_mut() {
+            self.transport.hard_close(socket)?;
+        }
+        if let Some(socket) = sd.local.as_mut() {
+            self.catloop.hard_close(socket)?;
+        }
+        Ok(())
+    }
+
+    fn set_socket_option(&mut self, sd: &mut Self::SocketDescriptor, option: SocketOption) -> Result<(), Fail> {
+        match sd.target() {
+            Ok(socket) => self.transport.set_socket_option(socket, option),
+            Err(socket) => self.catloop.set_socket_option(socket, option),
+        }
+    }
+
+    fn set_receive_filter(
+        &mut self,
+        sd: &mut Self::SocketDescriptor,
+        filter: Option<SocketFilter>,
+    ) -> Result<(), Fail> {
+        match sd.target() {
+            Ok(socket) => self.transport.set_receive_filter(socket, filter),
+            Err(socket) => self.catloop.set_receive_filter(socket, filter),
+        }
+    }
+
+    fn get_socket_option(
+        &mut self,
+        sd: &mut Self::SocketDescriptor,
+        option: SocketOption,
+    ) -> Result<SocketOption, Fail> {
+        match sd.target() {
+            Ok(socket) => self.transport.get_socket_option(socket, option),
+            Err(socket) => self.catloop.get_socket_option(socket, option),
+        }
+    }
+
+    async fn push(
+        &mut self,
+        sd: &mut Self::SocketDescriptor,
+        buf: &mut DemiBuffer,
+        addr: Option<SocketAddr>,
+    ) -> Result<(), Fail> {
+        match sd.target() {
+            Ok(socket) => self.transport.push(socket, buf, addr).await,
+            Err(socket) => self.catloop.push(socket, buf, addr).await,
+        }
+    }
+
+    async fn pop(
+        &mut self,
+        sd: &mut Self::SocketDescriptor,
+        size: usize,
+    ) -> Result<(Option<SocketAddr>, DemiBuffer, AncillaryData), Fail> {
+        match sd.target() {
+            Ok(socket) => self.transport.pop(socket, size).await,
+            Err(socket) => self.catloop.pop(socket, size).await,
+        }
+    }
+
+    fn ipsec_add_security_association(&mut self, sa: SecurityAssociation) -> Result<(), Fail> {
+        self.transport.ipsec_add_security_association(sa)
+    }
+
+    fn ipsec_remove_security_association(&mut self, spi: u32, direction: IpsecDirection) -> Result<(), Fail> {
+        self.transport.ipsec_remove_security_association(spi, direction)
+    }
+
+    fn add_route(&mut self, route: Route) -> Result<(), Fail> {
+        self.transport.add_route(route)
+    }
+
+    fn remove_route(&mut self, destination: Ipv4Addr, prefix_len: u8) -> Result<(), Fail> {
+        self.transport.remove_route(destination, prefix_len)
+    }
+
+    fn set_tunable(&mut self, name: &str, value: &str) -> Result<(), Fail> {
+        self.transport.set_tunable(name, value)
+    }
+
+    fn get_tunable(&self, name: &str) -> Result<String, Fail> {
+        self.transport.get_tunable(name)
+    }
+
+    fn get_drop_stats(&self) -> Result<DropStats, Fail> {
+        self.transport.get_drop_stats()
+    }
+
+    fn get_numa_stats(&self) -> Result<NumaStats, Fail> {
+        self.transport.get_numa_stats()
+    }
+
+    fn shutdown(&mut self) -> Result<(), Fail> {
+        self.transport.shutdown()
+    }
+
+    fn get_socket_drop_stats(&mut self, sd: &mut Self::SocketDescriptor) -> Result<DropStats, Fail> {
+        match sd.target() {
+            Ok(socket) => self.transport.get_socket_drop_stats(socket),
+            Err(socket) => self.catloop.get_socket_drop_stats(socket),
+        }
+    }
+
+    fn pop_drop_record(&mut self, sd: &mut Self::SocketDescriptor) -> Result<Option<DropRecord>, Fail> {
+        match sd.target() {
+            Ok(socket) => self.transport.pop_drop_record(socket),
+            Err(socket) => self.catloop.pop_drop_record(socket),
+        }
+    }
+
+    fn pop_tx_timestamp(&mut self, sd: &mut Self::SocketDescriptor) -> Result<Option<TxTimestamp>, Fail> {
+        match sd.target() {
+            Ok(socket) => self.transport.pop_tx_timestamp(socket),
+            Err(socket) => self.catloop.pop_tx_timestamp(socket),
+        }
+    }
+
+    fn get_tcp_info(&mut self, sd: &mut Self::SocketDescriptor) -> Result<TcpInfo, Fail> {
+        match sd.target() {
+            Ok(socket) => self.transport.get_tcp_info(socket),
+            Err(socket) => self.catloop.get_tcp_info(socket),
+        }
+    }
+
+    fn get_tcp_timeline(&mut self, sd: &mut Self::SocketDescriptor) -> Result<Vec<TcpEvent>, Fail> {
+        match sd.target() {
+            Ok(socket) => self.transport.get_tcp_timeline(socket),
+            Err(socket) => self.catloop.get_tcp_timeline(socket),
+        }
+    }
+
+    fn export(&mut self, sd: &mut Self::SocketDescriptor, channel: RawFd) -> Result<(), Fail> {
+        match sd.target() {
+            Ok(socket) => self.transport.export(socket, channel),
+            Err(socket) => self.catloop.export(socket, channel),
+        }
+    }
+
+    /// Connections are always handed off over the network transport.
+    fn import(&mut self, channel: RawFd) -> Result<(Self::SocketDescriptor, SocketAddr, SocketAddr), Fail> {
+        let (socket, local, remote) = self.transport.import(channel)?;
+        let sd: Self::SocketDescriptor = DispatchSocket {
+            remote: Some(socket),
+            local: None,
+        };
+        Ok((sd, local, remote))
+    }
+
+    fn freeze(&mut self, sd: &mut Self::SocketDescriptor) -> Result<Vec<u8>, Fail> {
+        match sd.target() {
+            Ok(socket) => self.transport.freeze(socket),
+            Err(socket) => self.catloop.freeze(socket),
+        }
+    }
+
+    /// Connections are always thawed on the network transport.
+    fn thaw(&mut self, blob: &[u8]) -> Result<(Self::SocketDescriptor, SocketAddr, SocketAddr), Fail> {
+        let (socket, local, remote) = self.transport.thaw(blob)?;
+        let sd: Self::SocketDescriptor = DispatchSocket {
+            remote: Some(socket),
+            local: None,
+        };
+        Ok((sd, local, remote))
+    }
+
+    fn get_runtime(&self) -> &SharedDemiRuntime {
+        &self.runtime
+    }
+}
+
+impl<T: NetworkTransport> Deref for SharedDispatchTransport<T> {
+    type Target = DispatchTransport<T>;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl<T: NetworkTransport> DerefMut for SharedDispatchTransport<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+/// Buffers come from the network transport, which may need specialized memory.
+impl<T: NetworkTransport> MemoryRuntime for SharedDispatchTransport<T> {
+    fn clone_sgarray(&self, sga: &demi_sgarray_t) -> Result<DemiBuffer, Fail> {
+        self.transport.clone_sgarray(sga)
+    }
+
+    fn into_sgarray(&self, buf: DemiBuffer) -> Result<demi_sgarray_t, Fail> {
+        self.transport.into_sgarray(buf)
+    }
+
+    fn sgaalloc(&self, size: usize) -> Result<demi_sgarray_t, Fail> {
+        self.transport.sgaalloc(size)
+    }
+
+    fn sgafree(&self, sga: demi_sgarray_t) -> Result<(), Fail> {
+        self.transport.sgafree(sga)
+    }
+}
+
+impl<S: Debug> Debug for DispatchSocket<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Dispatch socket: remote={:?} local={:?}", self.remote, self.local)
+    }
+}
@@ -5,5 +5,7 @@
 // Exports
 //======================================================================================================================
 
+#[cfg(feature = "catnap-libos")]
+pub mod dispatch;
 mod socket;
 pub mod transport;
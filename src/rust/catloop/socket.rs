@@ -13,7 +13,10 @@ use crate::{
         conditional_yield_with_timeout,
         fail::Fail,
         memory::DemiBuffer,
-        network::unwrap_socketaddr,
+        network::{
+            socket::option::SocketOption,
+            unwrap_socketaddr,
+        },
         queue::QDesc,
         OperationResult,
         SharedObject,
@@ -55,13 +58,28 @@ const DEFAULT_TIMEOUT: Duration = Duration::from_secs(120);
 //======================================================================================================================
 
 /// A socket.
+///
+/// Connections are set up like TCP ones, but over memory queues. Passive sockets create a control queue that is named
+/// after their local address. To connect, a client opens this queue and sends a [ConnectRequest] with a random id and
+/// its own address. The server then creates a queue for the connection, named after the server address and a new
+/// ephemeral port, and sends that port back through the control queue. The client opens the new queue and
+/// acknowledges the connection by sending its request id through it. Connections are torn down by closing their queue,
+/// which sends an end-of-file to the peer.
+///
+/// Sockets may also be bound to a name instead of an address, so that processes on the same host reach each other
+/// without an IP address. The control queue is then named after the name itself.
 pub struct MemorySocket {
     /// Underlying shared memory pipe.
     catmem_qd: Option<QDesc>,
     /// The local address to which the socket is bound.
     local: Option<SocketAddrV4>,
+    /// The name to which the socket is bound, if it is bound to a name instead of an address.
+    name: Option<String>,
     /// The remote address to which the socket is connected.
     remote: Option<SocketAddrV4>,
+    /// Ephemeral port that the socket holds until it is closed: the port that names the queue of an accepted
+    /// connection, or the local port of an active socket that was not bound.
+    ephemeral_port: Option<u16>,
     /// Maximum backlog length for passive sockets.
     backlog: usize,
     /// Pending connect requests for passive sockets.
@@ -76,6 +94,24 @@ pub struct SharedMemorySocket(SharedObject<MemorySocket>);
 #[derive(Hash, Eq, PartialEq, Copy, Clone)]
 pub struct RequestId(u64);
 
+/// Endpoint that passive sockets listen on.
+#[derive(Clone, Debug)]
+enum Endpoint {
+    /// Endpoint that is named after its address.
+    Addr(SocketAddrV4),
+    /// Endpoint that is bound to a name.
+    Name(String),
+}
+
+/// Connection request that a client sends through the control queue of a passive socket.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct ConnectRequest {
+    /// Identifier that the client acknowledges the connection with.
+    request_id: u64,
+    /// Address of the client.
+    addr: SocketAddrV4,
+}
+
 //======================================================================================================================
 // Associated Functions
 //======================================================================================================================
@@ -86,7 +122,9 @@ impl SharedMemorySocket {
         Self(SharedObject::new(MemorySocket {
             catmem_qd: None,
             local: None,
+            name: None,
             remote: None,
+            ephemeral_port: None,
             backlog: 1,
             pending_request_ids: HashSet::<RequestId>::new(),
             #[cfg(debug_assertions)]
@@ -96,12 +134,14 @@ impl SharedMemorySocket {
         }))
     }
 
-    /// Allocates a new socket that is bound to [local].
-    fn alloc(catmem_qd: QDesc, local: Option<SocketAddrV4>, remote: Option<SocketAddrV4>) -> Self {
+    /// Allocates a new socket for a connection from [local] to [remote], over the queue named after [ephemeral_port].
+    fn alloc(catmem_qd: QDesc, local: Option<SocketAddrV4>, remote: SocketAddrV4, ephemeral_port: u16) -> Self {
         Self(SharedObject::new(MemorySocket {
             catmem_qd: Some(catmem_qd),
             local,
-            remote,
+            name: None,
+            remote: Some(remote),
+            ephemeral_port: Some(ephemeral_port),
             backlog: 1,
             pending_request_ids: HashSet::<RequestId>::new(),
             #[cfg(debug_assertions)]
@@ -112,27 +152,35 @@ impl SharedMemorySocket {
     }
 
     /// Binds the target socket to `local` address.
-    /// TODO: Should probably move the create of the duplex pipe to listen.
-    pub fn bind(&mut self, local: SocketAddrV4, catmem: &mut SharedCatmemLibOS) -> Result<(), Fail> {
-        // Create underlying memory channels.
-        let ipv4: &Ipv4Addr = local.ip();
-        let port: u16 = local.port();
-        self.catmem_qd = Some(catmem.create_pipe(&format_pipe_str(ipv4, port))?);
+    pub fn bind(&mut self, local: SocketAddrV4) -> Result<(), Fail> {
         self.local = Some(local);
         Ok(())
     }
 
-    /// Enables this socket to accept incoming connections.
-    pub fn listen(&mut self, backlog: usize) -> Result<(), Fail> {
+    /// Binds the target socket to [name].
+    pub fn bind_name(&mut self, name: &str) -> Result<(), Fail> {
+        self.name = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Enables this socket to accept incoming connections, by creating the control queue that clients connect through.
+    pub fn listen(&mut self, backlog: usize, catmem: &mut SharedCatmemLibOS) -> Result<(), Fail> {
+        self.catmem_qd = Some(catmem.create_pipe(&self.endpoint().control_pipe())?);
         self.backlog = backlog;
         Ok(())
     }
 
-    /// Attempts to accept a new connection on this socket. On success, returns a new Socket for the accepted connection.
-    pub async fn accept(&mut self, new_port: u16, mut catmem: SharedCatmemLibOS) -> Result<(Self, SocketAddr), Fail> {
-        // Allocate ephemeral port.
-        let ipv4: Ipv4Addr = *expect_some!(self.local, "Should be bound to a local address to accept connections").ip();
-        let new_qd: QDesc = loop {
+    /// Attempts to accept a new connection on this socket. On success, returns a new Socket for the accepted connection
+    /// and the address of the client.
+    pub async fn accept(&mut self, new_port: u16, catmem: SharedCatmemLibOS) -> Result<(Self, SocketAddr), Fail> {
+        let request: ConnectRequest = self.next_connect_request(catmem.clone()).await?;
+        self.accept_request(request, new_port, catmem).await
+    }
+
+    /// Waits for the next connection request on this socket, skipping duplicates of the pending ones. Dropping the
+    /// returned future before it completes loses no request.
+    pub async fn next_connect_request(&mut self, catmem: SharedCatmemLibOS) -> Result<ConnectRequest, Fail> {
+        loop {
             // Check if backlog is full.
             if self.pending_request_ids.len() >= self.backlog {
                 // It is, thus just log a debug message, pending requests will still be queued anyways.
@@ -145,28 +193,47 @@ impl SharedMemorySocket {
             }
 
             // Grab next request from the control duplex pipe.
-            let request_id: RequestId =
-                pop_request_id(catmem.clone(), expect_some!(self.catmem_qd, "should be connected")).await?;
+            let request: ConnectRequest =
+                pop_connect_request(catmem.clone(), expect_some!(self.catmem_qd, "should be listening")).await?;
+            let request_id: RequestId = RequestId(request.request_id);
 
-            // Received a request id so create the new connection. This involves create the new duplex pipe
-            // and sending the port number to the remote.
             if self.pending_request_ids.contains(&request_id) {
                 debug!("do_accept(): duplicate request (request_id={:?})", request_id.0);
                 continue;
-            } else {
-                self.pending_request_ids.insert(request_id);
-                break create_pipe(
-                    expect_some!(self.catmem_qd, "pipe should have been created"),
-                    catmem.clone(),
-                    &ipv4,
-                    new_port,
-                )
-                .await?;
             }
+            self.pending_request_ids.insert(request_id);
+            break Ok(request);
+        }
+    }
+
+    /// Accepts the connection that [request] asks for, over a new memory queue that is named after [new_port].
+    pub async fn accept_request(
+        &mut self,
+        request: ConnectRequest,
+        new_port: u16,
+        mut catmem: SharedCatmemLibOS,
+    ) -> Result<(Self, SocketAddr), Fail> {
+        let remote: SocketAddrV4 = request.addr;
+
+        // Create the new connection. This involves create the new duplex pipe and sending the port number to the
+        // remote.
+        let new_qd: QDesc = match create_pipe(
+            expect_some!(self.catmem_qd, "pipe should have been created"),
+            catmem.clone(),
+            &self.endpoint().connection_pipe(new_port),
+            new_port,
+        )
+        .await
+        {
+            Ok(new_qd) => new_qd,
+            Err(e) => {
+                self.pending_request_ids.remove(&RequestId(request.request_id));
+                return Err(e);
+            },
         };
 
-        let new_addr: SocketAddrV4 = SocketAddrV4::new(ipv4, new_port);
-        let new_socket: Self = Self::alloc(new_qd, Some(new_addr), None);
+        // Like TCP connections, accepted connections share the local address of the passive socket.
+        let new_socket: Self = Self::alloc(new_qd, self.local, remote, new_port);
 
         // Check that the remote has retrieved the port number and responded with a valid request id.
         match pop_request_id(catmem.clone(), new_qd).await {
@@ -175,56 +242,66 @@ impl SharedMemorySocket {
                 // If we've never seen this before, something has gone very wrong.
                 assert!(self.pending_request_ids.contains(&request_id));
                 self.pending_request_ids.remove(&request_id);
-                Ok((new_socket, new_addr.into()))
+                Ok((new_socket, remote.into()))
             },
             // Some error.
             Err(e) => {
                 // Clean up newly allocated duplex pipe.
+                self.pending_request_ids.remove(&RequestId(request.request_id));
                 catmem.close(new_qd)?;
                 Err(e)
             },
         }
     }
 
-    /// Connects this socket to [remote].
-    pub async fn connect(&mut self, mut catmem: SharedCatmemLibOS, remote: SocketAddr) -> Result<(), Fail> {
-        let ipv4: Ipv4Addr = *unwrap_socketaddr(remote)?.ip();
-        let port: u16 = remote.port().into();
-        let request_id: RequestId = RequestId(self.rng.next_u64());
+    /// Connects this socket from [local] to [remote]. [ephemeral_port] is the port of [local] if the transport allocated
+    /// it for this connection, and the socket holds it until it is closed.
+    pub async fn connect(
+        &mut self,
+        catmem: SharedCatmemLibOS,
+        local: SocketAddrV4,
+        ephemeral_port: Option<u16>,
+        remote: SocketAddr,
+    ) -> Result<(), Fail> {
+        let remote: SocketAddrV4 = unwrap_socketaddr(remote)?;
+        self.catmem_qd = Some(self.do_connect(catmem, local, Endpoint::Addr(remote)).await?);
+        self.local = Some(local);
+        self.remote = Some(remote);
+        self.ephemeral_port = ephemeral_port;
+        Ok(())
+    }
+
+    /// Connects this socket to the socket that is bound to [name]. Unless this socket is bound, the server sees it as
+    /// coming from the unspecified address.
+    pub async fn connect_name(&mut self, catmem: SharedCatmemLibOS, name: &str) -> Result<(), Fail> {
+        let local: SocketAddrV4 = self.local.unwrap_or(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0));
+        self.catmem_qd = Some(self.do_connect(catmem, local, Endpoint::Name(name.to_string())).await?);
+        Ok(())
+    }
+
+    /// Runs the client side of the connection setup to [endpoint], from [local]. Returns the queue of the new
+    /// connection.
+    async fn do_connect(
+        &mut self,
+        mut catmem: SharedCatmemLibOS,
+        local: SocketAddrV4,
+        endpoint: Endpoint,
+    ) -> Result<QDesc, Fail> {
+        let request: ConnectRequest = ConnectRequest {
+            request_id: self.rng.next_u64(),
+            addr: local,
+        };
+        let request_id: RequestId = RequestId(request.request_id);
 
         // Gets the port for the new connection from the server by sending a connection request repeatedly until a port
         // comes back.
-        let result: Result<(QDesc, SocketAddrV4), Fail> = {
-            let new_port: u16 = match get_port(catmem.clone(), &ipv4, port, &request_id).await {
-                Ok(new_port) => new_port,
-                Err(e) => {
-                    return Err(e);
-                },
-            };
-
-            // Open underlying pipes.
-            let remote: SocketAddrV4 = SocketAddrV4::new(ipv4, new_port);
-            let new_qd: QDesc = match catmem.open_pipe(&format_pipe_str(&ipv4, new_port)) {
-                Ok(new_qd) => new_qd,
-                Err(e) => {
-                    return Err(e);
-                },
-            };
-            // Send an ack to the server over the new pipe.
-            if let Err(e) = send_ack(catmem.clone(), new_qd, &request_id).await {
-                return Err(e);
-            }
-            Ok((new_qd, remote))
-        };
+        let new_port: u16 = get_port(catmem.clone(), &endpoint, &request).await?;
 
-        match result {
-            Ok((new_qd, remote)) => {
-                self.catmem_qd = Some(new_qd);
-                self.remote = Some(remote);
-                Ok(())
-            },
-            Err(e) => Err(e),
-        }
+        // Open underlying pipes.
+        let new_qd: QDesc = catmem.open_pipe(&endpoint.connection_pipe(new_port))?;
+        // Send an ack to the server over the new pipe.
+        send_ack(catmem.clone(), new_qd, &request_id).await?;
+        Ok(new_qd)
     }
 
     /// Closes `socket`.
@@ -255,7 +332,7 @@ impl SharedMemorySocket {
         let qd: QDesc = expect_some!(self.catmem_qd, "socket should be connected");
         // TODO: Remove the copy eventually.
         match catmem.push_coroutine(qd, buf.clone()).await {
-            (_, OperationResult::Push) => {
+            (_, OperationResult::Push(_)) => {
                 buf.trim(buf.len())?;
                 Ok(())
             },
@@ -275,7 +352,7 @@ impl SharedMemorySocket {
         // was not correctly driven.
         let qd: QDesc = expect_some!(self.catmem_qd, "socket should be connected");
         match catmem.pop_coroutine(qd, Some(size)).await {
-            (_, OperationResult::Pop(_, incoming)) => {
+            (_, OperationResult::Pop(_, incoming, _)) => {
                 // TODO: Remove this copy. Our API should support passing back a buffer without sending in a buffer.
                 // We do not keep a socket address for the remote socket, so none to return.
                 Ok((None, incoming))
@@ -294,6 +371,99 @@ impl SharedMemorySocket {
     pub fn remote(&self) -> Option<SocketAddrV4> {
         self.remote
     }
+
+    /// Sets [option] on the underlying memory queue of [self].
+    pub fn set_socket_option(&self, catmem: &mut SharedCatmemLibOS, option: SocketOption) -> Result<(), Fail> {
+        catmem.set_socket_option(self.queue()?, option)
+    }
+
+    /// Gets the current value of [option] on the underlying memory queue of [self].
+    pub fn get_socket_option(
+        &self,
+        catmem: &mut SharedCatmemLibOS,
+        option: SocketOption,
+    ) -> Result<SocketOption, Fail> {
+        catmem.get_socket_option(self.queue()?, option)
+    }
+
+    /// Gets the underlying memory queue of [self], which sockets only have once they listen or connect.
+    fn queue(&self) -> Result<QDesc, Fail> {
+        match self.catmem_qd {
+            Some(qd) => Ok(qd),
+            None => {
+                let cause: &str = "socket has no memory queue yet";
+                error!("queue(): {}", cause);
+                Err(Fail::new(libc::ENOTCONN, cause))
+            },
+        }
+    }
+
+    /// Checks whether a passive socket on this host is bound to [addr], by looking up its control queue in the namespace
+    /// of memory queues.
+    pub fn is_listening(addr: SocketAddrV4) -> bool {
+        SharedCatmemLibOS::pipe_exists(&format_pipe_str(addr.ip(), addr.port()))
+    }
+
+    /// Gets the endpoint that [self] listens on.
+    fn endpoint(&self) -> Endpoint {
+        match &self.name {
+            Some(name) => Endpoint::Name(name.clone()),
+            None => Endpoint::Addr(expect_some!(self.local, "should be bound to a local address to listen")),
+        }
+    }
+
+    /// Takes the ephemeral port that [self] holds, so that it can be released.
+    pub fn take_ephemeral_port(&mut self) -> Option<u16> {
+        self.ephemeral_port.take()
+    }
+}
+
+impl Endpoint {
+    /// Gets the name of the control queue that clients connect through.
+    fn control_pipe(&self) -> String {
+        match self {
+            Endpoint::Addr(addr) => format_pipe_str(addr.ip(), addr.port()),
+            Endpoint::Name(name) => name.clone(),
+        }
+    }
+
+    /// Gets the name of the queue of a connection to this endpoint, given the port that the server picked for it.
+    fn connection_pipe(&self, port: u16) -> String {
+        match self {
+            Endpoint::Addr(addr) => format_pipe_str(addr.ip(), port),
+            Endpoint::Name(name) => format!("{}:{}", name, port),
+        }
+    }
+}
+
+impl ConnectRequest {
+    /// Size of a serialized connection request: the request id, followed by the IPv4 address and port of the client.
+    const SIZE: usize = mem::size_of::<u64>() + 4 + mem::size_of::<u16>();
+
+    /// Serializes the target [ConnectRequest].
+    fn serialize(&self) -> Result<DemiBuffer, Fail> {
+        let mut bytes: Vec<u8> = Vec::with_capacity(Self::SIZE);
+        bytes.extend_from_slice(&self.request_id.to_ne_bytes());
+        bytes.extend_from_slice(&self.addr.ip().octets());
+        bytes.extend_from_slice(&self.addr.port().to_ne_bytes());
+        DemiBuffer::from_slice(&bytes)
+    }
+
+    /// Parses a connection request out of [buf].
+    fn parse(buf: DemiBuffer) -> Result<Self, Fail> {
+        if buf.len() != Self::SIZE {
+            let cause: String = format!("invalid connect request (len={:?})", buf.len());
+            error!("parse(): {:?}", &cause);
+            return Err(Fail::new(libc::ECONNREFUSED, &cause));
+        }
+        let request_id: u64 = u64::from_ne_bytes(expect_ok!(buf[0..8].try_into(), "should be the right size"));
+        let ipv4: Ipv4Addr = Ipv4Addr::new(buf[8], buf[9], buf[10], buf[11]);
+        let port: u16 = u16::from_ne_bytes(expect_ok!(buf[12..14].try_into(), "should be the right size"));
+        Ok(Self {
+            request_id,
+            addr: SocketAddrV4::new(ipv4, port),
+        })
+    }
 }
 
 //======================================================================================================================
@@ -301,23 +471,18 @@ impl SharedMemorySocket {
 //======================================================================================================================
 
 /// Create a memory pipe and send the port number to the peer process.
-async fn create_pipe(
-    catmem_qd: QDesc,
-    mut catmem: SharedCatmemLibOS,
-    ipv4: &Ipv4Addr,
-    port: u16,
-) -> Result<QDesc, Fail> {
+async fn create_pipe(catmem_qd: QDesc, mut catmem: SharedCatmemLibOS, name: &str, port: u16) -> Result<QDesc, Fail> {
     // Create underlying pipes before sending the port number through the
     // control duplex pipe. This prevents us from running into a race
     // condition were the remote makes progress faster than us and attempts
     // to open the duplex pipe before it is created.
-    let new_qd: QDesc = catmem.create_pipe(&format_pipe_str(ipv4, port))?;
+    let new_qd: QDesc = catmem.create_pipe(name)?;
     // Allocate a scatter-gather array and send the port number to the remote.
     let buf: DemiBuffer = DemiBuffer::from_slice(&port.to_ne_bytes())?;
 
     // Push the port number.
     match catmem.push_coroutine(catmem_qd, buf).await {
-        (_, OperationResult::Push) => Ok(new_qd),
+        (_, OperationResult::Push(_)) => Ok(new_qd),
         (qd, OperationResult::Failed(e)) => {
             debug_assert_eq!(new_qd, qd);
 
@@ -330,19 +495,25 @@ async fn create_pipe(
 }
 
 /// Gets the next connection request.
+async fn pop_connect_request(catmem: SharedCatmemLibOS, catmem_qd: QDesc) -> Result<ConnectRequest, Fail> {
+    ConnectRequest::parse(pop_message(catmem, catmem_qd, ConnectRequest::SIZE).await?)
+}
+
+/// Gets the request id that acknowledges a connection.
 async fn pop_request_id(catmem: SharedCatmemLibOS, catmem_qd: QDesc) -> Result<RequestId, Fail> {
+    get_connect_id(pop_message(catmem, catmem_qd, mem::size_of::<RequestId>()).await?)
+}
+
+/// Pops the next message of at most [size] bytes from a control pipe.
+async fn pop_message(catmem: SharedCatmemLibOS, catmem_qd: QDesc, size: usize) -> Result<DemiBuffer, Fail> {
     // Issue pop. No need to bound the pop because we've quantized it already in the concurrent ring buffer.
-    match catmem.pop_coroutine(catmem_qd, Some(mem::size_of::<RequestId>())).await {
+    match catmem.pop_coroutine(catmem_qd, Some(size)).await {
         // We expect a successful completion for previous pop().
-        (_, OperationResult::Pop(_, incoming)) => {
-            // Parse and check request.
-            let result: Result<RequestId, Fail> = get_connect_id(incoming);
-            result
-        },
+        (_, OperationResult::Pop(_, incoming, _)) => Ok(incoming),
         // We may get some error.
         (qd, OperationResult::Failed(e)) => {
             let cause: String = format!("failed to establish connection (qd={:?}, errno={:?})", qd, e);
-            error!("pop_request_id(): {:?}", &cause);
+            error!("pop_message(): {:?}", &cause);
             Err(e)
         },
         // We do not expect anything else.
@@ -354,19 +525,17 @@ async fn pop_request_id(catmem: SharedCatmemLibOS, catmem_qd: QDesc) -> Result<R
     }
 }
 
-// Send a request id through a pipe.
+// Send a connection request through a pipe.
 async fn send_connection_request(
     catmem: SharedCatmemLibOS,
     connect_qd: QDesc,
-    request_id: &RequestId,
+    request: &ConnectRequest,
 ) -> Result<(), Fail> {
-    // Create a message containing the magic number.
-    // Create message with magic connect.
-    let buf: DemiBuffer = DemiBuffer::from_slice(&request_id.0.to_ne_bytes())?;
+    let buf: DemiBuffer = request.serialize()?;
 
     // Send to server.
     match catmem.push_coroutine(connect_qd, buf).await {
-        (_, OperationResult::Push) => Ok(()),
+        (_, OperationResult::Push(_)) => Ok(()),
         (qd, OperationResult::Failed(e)) => {
             debug_assert_eq!(connect_qd, qd);
 
@@ -378,21 +547,16 @@ async fn send_connection_request(
     }
 }
 
-async fn get_port(
-    mut catmem: SharedCatmemLibOS,
-    ipv4: &Ipv4Addr,
-    port: u16,
-    request_id: &RequestId,
-) -> Result<u16, Fail> {
+async fn get_port(mut catmem: SharedCatmemLibOS, endpoint: &Endpoint, request: &ConnectRequest) -> Result<u16, Fail> {
     // Issue receive operation to wait for connect request ack.
     let size: usize = mem::size_of::<u16>();
     // Open connection to server.
-    let connect_qd: QDesc = match catmem.open_pipe(&format_pipe_str(ipv4, port)) {
+    let connect_qd: QDesc = match catmem.open_pipe(&endpoint.control_pipe()) {
         Ok(qd) => qd,
         Err(e) => {
             // Interpose error.
             if e.errno == libc::ENOENT {
-                let cause: String = format!("failed to establish connection (ipv4={:?}, port={:?})", ipv4, port);
+                let cause: String = format!("failed to establish connection (endpoint={:?})", endpoint);
                 error!("get_port(): {:?}", &cause);
                 return Err(Fail::new(libc::ECONNREFUSED, &cause));
             }
@@ -402,12 +566,12 @@ async fn get_port(
     };
 
     // Send the connection request to the server.
-    send_connection_request(catmem.clone(), connect_qd, request_id).await?;
+    send_connection_request(catmem.clone(), connect_qd, request).await?;
 
     // Wait for response until some timeout.
     match conditional_yield_with_timeout(catmem.pop_coroutine(connect_qd, Some(size)), DEFAULT_TIMEOUT).await? {
         // We expect a successful completion for previous pop().
-        (_, OperationResult::Pop(_, incoming)) => extract_port_number(incoming),
+        (_, OperationResult::Pop(_, incoming, _)) => extract_port_number(incoming),
         // We may get some error.
         (qd, OperationResult::Failed(e)) => {
             let cause: String = format!("failed to establish connection (qd={:?}, errno={:?})", qd, e);
@@ -430,7 +594,7 @@ async fn send_ack(catmem: SharedCatmemLibOS, new_qd: QDesc, request_id: &Request
 
     match catmem.push_coroutine(new_qd, buf).await {
         // We expect a successful completion for previous push().
-        (_, OperationResult::Push) => Ok(()),
+        (_, OperationResult::Push(_)) => Ok(()),
         (qd, OperationResult::Failed(e)) => {
             let cause: String = format!("failed to establish connection (qd={:?}, errno={:?})", qd, e);
             error!("send_ack(): {:?}", &cause);
@@ -480,6 +644,10 @@ fn format_pipe_str(ip: &Ipv4Addr, port: u16) -> String {
     format!("{}:{}", ip, port)
 }
 
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
 impl Deref for SharedMemorySocket {
     type Target = MemorySocket;
 
@@ -498,8 +666,61 @@ impl Debug for SharedMemorySocket {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Memory socket: local={:?} remote={:?} catmem_qd={:?}",
-            self.local, self.remote, self.catmem_qd
+            "Memory socket: local={:?} name={:?} remote={:?} catmem_qd={:?}",
+            self.local, self.name, self.remote, self.catmem_qd
         )
     }
 }
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ConnectRequest,
+        Endpoint,
+    };
+    use ::anyhow::Result;
+    use ::std::net::{
+        Ipv4Addr,
+        SocketAddrV4,
+    };
+
+    /// Tests that connection requests carry the address of the client, and that truncated ones are refused.
+    #[test]
+    fn test_connect_request() -> Result<()> {
+        let request: ConnectRequest = ConnectRequest {
+            request_id: 0x0123_4567_89ab_cdef,
+            addr: SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 2), 49152),
+        };
+        let buf = request.serialize()?;
+        crate::ensure_eq!(buf.len(), ConnectRequest::SIZE);
+        crate::ensure_eq!(ConnectRequest::parse(buf.clone())?, request);
+
+        let mut truncated = buf.clone();
+        truncated.trim(2)?;
+        crate::ensure_eq!(ConnectRequest::parse(truncated).unwrap_err().errno, libc::ECONNREFUSED);
+
+        Ok(())
+    }
+
+    /// Tests that named endpoints name their queues after the name, and others after their address.
+    #[test]
+    fn test_endpoint_pipes() -> Result<()> {
+        let endpoint: Endpoint = Endpoint::Name("control".to_string());
+        crate::ensure_eq!(endpoint.control_pipe(), "control");
+        crate::ensure_eq!(endpoint.connection_pipe(49152), "control:49152");
+
+        let addr: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 2), 80);
+        let endpoint: Endpoint = Endpoint::Addr(addr);
+        crate::ensure_eq!(endpoint.control_pipe(), super::format_pipe_str(addr.ip(), 80));
+        crate::ensure_eq!(
+            endpoint.connection_pipe(49152),
+            super::format_pipe_str(addr.ip(), 49152)
+        );
+
+        Ok(())
+    }
+}
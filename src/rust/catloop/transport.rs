@@ -3,7 +3,10 @@
 //======================================================================================================================
 
 use crate::{
-    catloop::socket::SharedMemorySocket,
+    catloop::socket::{
+        ConnectRequest,
+        SharedMemorySocket,
+    },
     catmem::SharedCatmemLibOS,
     demikernel::config::Config,
     runtime::{
@@ -13,6 +16,11 @@ use crate::{
             MemoryRuntime,
         },
         network::{
+            ancillary::AncillaryData,
+            socket::{
+                filter::SocketFilter,
+                option::SocketOption,
+            },
             transport::NetworkTransport,
             unwrap_socketaddr,
         },
@@ -22,6 +30,7 @@ use crate::{
 };
 use ::socket2::{
     Domain,
+    Protocol,
     Type,
 };
 use ::std::{
@@ -67,6 +76,43 @@ impl SharedCatloopTransport {
             config: config.clone(),
         }))
     }
+
+    /// Checks whether a passive socket on this host is bound to [addr], so that connections to it go over memory
+    /// queues.
+    pub fn is_listening(&self, addr: SocketAddrV4) -> bool {
+        SharedMemorySocket::is_listening(addr)
+    }
+
+    /// Waits for the next connection request on the passive socket [sd]. This is the first step of [Self::accept], and
+    /// unlike the whole accept, it can be raced against other futures.
+    pub async fn next_connect_request(&mut self, sd: &mut SharedMemorySocket) -> Result<ConnectRequest, Fail> {
+        sd.next_connect_request(self.catmem.clone()).await
+    }
+
+    /// Completes the connection that [request] asks for on the passive socket [sd], over a memory queue that is named
+    /// after a new ephemeral port.
+    pub async fn accept_request(
+        &mut self,
+        sd: &mut SharedMemorySocket,
+        request: ConnectRequest,
+    ) -> Result<(SharedMemorySocket, SocketAddr), Fail> {
+        let new_port: u16 = self.runtime.alloc_ephemeral_port()?;
+        match sd.accept_request(request, new_port, self.catmem.clone()).await {
+            Ok(new_socket) => Ok(new_socket),
+            Err(e) => {
+                self.runtime.free_ephemeral_port(new_port)?;
+                Err(e)
+            },
+        }
+    }
+
+    /// Releases the ephemeral port that [sd] holds, if any, once its connection is closed.
+    fn free_ephemeral_port(&mut self, sd: &mut SharedMemorySocket) -> Result<(), Fail> {
+        match sd.take_ephemeral_port() {
+            Some(port) => self.runtime.free_ephemeral_port(port),
+            None => Ok(()),
+        }
+    }
 }
 
 impl NetworkTransport for SharedCatloopTransport {
@@ -75,7 +121,13 @@ impl NetworkTransport for SharedCatloopTransport {
 
     /// Creates a socket. This function contains the libOS-level functionality needed to create a SharedCatloopQueue
     /// that wraps the underlying Catmem queue.
-    fn socket(&mut self, _: Domain, _: Type) -> Result<Self::SocketDescriptor, Fail> {
+    fn socket(&mut self, _: Domain, typ: Type, _: Protocol) -> Result<Self::SocketDescriptor, Fail> {
+        // Raw sockets have no meaning on top of memory queues.
+        if typ == Type::RAW {
+            let cause: String = format!("socket type not supported (type={:?})", typ);
+            error!("socket(): {}", cause);
+            return Err(Fail::new(libc::ENOTSUP, &cause));
+        }
         // Create fake socket.
         Ok(SharedMemorySocket::new())
     }
@@ -85,53 +137,102 @@ impl NetworkTransport for SharedCatloopTransport {
     fn bind(&mut self, sd: &mut Self::SocketDescriptor, local: SocketAddr) -> Result<(), Fail> {
         // Check if we are binding to a non-local address.
         let local: SocketAddrV4 = unwrap_socketaddr(local)?;
-        if &self.config.local_ipv4_addr() != local.ip() {
+        if &self.config.local_ipv4_addr()? != local.ip() {
             let cause: String = format!("cannot bind to non-local address (sd={:?})", sd);
             error!("bind(): {}", cause);
             return Err(Fail::new(libc::EADDRNOTAVAIL, &cause));
         }
 
         // Check that the socket associated with the queue is not listening.
-        sd.bind(local, &mut self.catmem)
+        sd.bind(local)
+    }
+
+    /// Binds a socket to [name]. The control queue of the socket is then named after [name], which must not be empty.
+    fn bind_name(&mut self, sd: &mut Self::SocketDescriptor, name: &str) -> Result<(), Fail> {
+        if name.is_empty() {
+            let cause: String = format!("cannot bind to an empty name (sd={:?})", sd);
+            error!("bind_name(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        sd.bind_name(name)
     }
 
     /// Sets a SharedCatloopQueue and as a passive one. This function contains the libOS-level
     /// functionality to move the SharedCatloopQueue into a listening state.
     fn listen(&mut self, sd: &mut Self::SocketDescriptor, backlog: usize) -> Result<(), Fail> {
-        sd.listen(backlog)
+        sd.listen(backlog, &mut self.catmem)
     }
 
     /// Asynchronous cross-queue code for accepting a connection. This function returns a coroutine that runs
     /// asynchronously to accept a connection and performs any necessary multi-queue operations at the libOS-level after
     /// the accept succeeds or fails.
     async fn accept(&mut self, sd: &mut Self::SocketDescriptor) -> Result<(Self::SocketDescriptor, SocketAddr), Fail> {
-        let new_port: u16 = self.runtime.alloc_ephemeral_port()?;
-        match sd.accept(new_port, self.catmem.clone()).await {
-            Ok(new_socket) => Ok(new_socket),
-            Err(e) => {
-                self.runtime.free_ephemeral_port(new_port)?;
-                Err(e)
-            },
-        }
+        let request: ConnectRequest = self.next_connect_request(sd).await?;
+        self.accept_request(sd, request).await
     }
 
     /// Asynchronous code to establish a connection to a remote endpoint. This function returns a coroutine that runs
     /// asynchronously to connect a queue and performs any necessary multi-queue operations at the libOS-level after
     /// the connect succeeds or fails.
     async fn connect(&mut self, sd: &mut Self::SocketDescriptor, remote: SocketAddr) -> Result<(), Fail> {
+        // Unbound sockets connect from an ephemeral port, so that the server learns their address.
+        let (local, ephemeral_port): (SocketAddrV4, Option<u16>) = match sd.local() {
+            Some(local) => (local, None),
+            None => {
+                let port: u16 = self.runtime.alloc_ephemeral_port()?;
+                (SocketAddrV4::new(self.config.local_ipv4_addr()?, port), Some(port))
+            },
+        };
+
         // Wait for connect operation to complete.
-        sd.connect(self.catmem.clone(), remote).await
+        match sd.connect(self.catmem.clone(), local, ephemeral_port, remote).await {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                if let Some(port) = ephemeral_port {
+                    self.runtime.free_ephemeral_port(port)?;
+                }
+                Err(e)
+            },
+        }
+    }
+
+    /// Asynchronous code to connect to the socket that is bound to [name].
+    async fn connect_name(&mut self, sd: &mut Self::SocketDescriptor, name: &str) -> Result<(), Fail> {
+        sd.connect_name(self.catmem.clone(), name).await
     }
 
     /// Asynchronous code to close a queue. This function returns a coroutine that runs asynchronously to close a queue
     /// and the underlying Catmem queue and performs any necessary multi-queue operations at the libOS-level after
     /// the close succeeds or fails.
     async fn close(&mut self, sd: &mut Self::SocketDescriptor) -> Result<(), Fail> {
-        sd.close(self.catmem.clone()).await
+        sd.close(self.catmem.clone()).await?;
+        self.free_ephemeral_port(sd)
+    }
+
+    /// Memory queues do not build packet headers, so the only option is whether their pops sleep on a doorbell.
+    fn set_socket_option(&mut self, sd: &mut Self::SocketDescriptor, option: SocketOption) -> Result<(), Fail> {
+        sd.set_socket_option(&mut self.catmem, option)
+    }
+
+    /// Memory queues do not receive packets, so there is nothing to filter.
+    fn set_receive_filter(&mut self, _: &mut Self::SocketDescriptor, _: Option<SocketFilter>) -> Result<(), Fail> {
+        let cause: &str = "receive filters are not supported";
+        error!("set_receive_filter(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, cause))
+    }
+
+    /// Memory queues do not build packet headers, so the only option is whether their pops sleep on a doorbell.
+    fn get_socket_option(
+        &mut self,
+        sd: &mut Self::SocketDescriptor,
+        option: SocketOption,
+    ) -> Result<SocketOption, Fail> {
+        sd.get_socket_option(&mut self.catmem, option)
     }
 
     fn hard_close(&mut self, sd: &mut Self::SocketDescriptor) -> Result<(), Fail> {
-        sd.hard_close(&mut self.catmem)
+        sd.hard_close(&mut self.catmem)?;
+        self.free_ephemeral_port(sd)
     }
 
     /// Asynchronous code to push to a Catloop queue.
@@ -150,8 +251,9 @@ impl NetworkTransport for SharedCatloopTransport {
         &mut self,
         sd: &mut Self::SocketDescriptor,
         size: usize,
-    ) -> Result<(Option<SocketAddr>, DemiBuffer), Fail> {
-        sd.pop(self.catmem.clone(), size).await
+    ) -> Result<(Option<SocketAddr>, DemiBuffer, AncillaryData), Fail> {
+        let (addr, buf) = sd.pop(self.catmem.clone(), size).await?;
+        Ok((addr, buf, AncillaryData::default()))
     }
 
     fn get_runtime(&self) -> &SharedDemiRuntime {
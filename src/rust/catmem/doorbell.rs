@@ -0,0 +1,289 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    pal::linux::shm::SharedMemory,
+    runtime::{
+        fail::Fail,
+        teardown::{
+            self,
+            TeardownGuard,
+        },
+    },
+};
+use ::std::{
+    ffi::CString,
+    mem,
+    os::fd::RawFd,
+    process,
+    sync::atomic::{
+        self,
+        AtomicU32,
+        Ordering,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Number of doorbells: one for the creator and one for the opener of a ring.
+const NUM_ENDPOINTS: usize = 2;
+
+/// Index of the doorbell of the endpoint that created the ring.
+const CREATOR: usize = 0;
+
+/// Index of the doorbell of the endpoint that opened the ring.
+const OPENER: usize = 1;
+
+/// Directory where doorbells are created. This is the same directory that hosts shared memory regions.
+const DOORBELL_DIR: &str = "/dev/shm/demikernel-";
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Doorbells that let an endpoint of a shared memory ring sleep until its peer pushes data.
+///
+/// Each endpoint owns a named FIFO, which it waits on, and an "armed" flag in a small shared memory region. Eventfds
+/// would not do here, because they cannot be opened by name from an unrelated process. A producer only rings the
+/// doorbell of its peer if the peer is armed, so pushing stays cheap when the peer is busy polling.
+pub struct Doorbell {
+    /// Armed flags, one per endpoint.
+    shm: SharedMemory,
+    /// Index of the doorbell owned by this endpoint.
+    local: usize,
+    /// Doorbell on which this endpoint waits.
+    local_fd: RawFd,
+    /// Doorbell of the peer.
+    peer_fd: RawFd,
+    /// Paths to doorbells that were created by this endpoint and should thus be removed.
+    created: Vec<CString>,
+    /// Process that opened this endpoint. Children that inherit the endpoint across fork leave its doorbells in place.
+    pid: u32,
+    /// Removes the doorbells that were created by this endpoint if the process dies without dropping it.
+    teardown: Option<TeardownGuard>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Doorbell {
+    /// Creates the doorbells of a ring.
+    pub fn create(name: &str) -> Result<Self, Fail> {
+        let shm: SharedMemory =
+            SharedMemory::create(&Self::build_shm_name(name), NUM_ENDPOINTS * mem::size_of::<u32>())?;
+        let mut created: Vec<CString> = Vec::with_capacity(NUM_ENDPOINTS);
+        for index in 0..NUM_ENDPOINTS {
+            let path: CString = Self::build_path(name, index)?;
+            if unsafe { libc::mkfifo(path.as_ptr(), libc::S_IRUSR | libc::S_IWUSR) } != 0 {
+                let errno: libc::c_int = unsafe { *libc::__errno_location() };
+                let cause: String = format!("failed to create doorbell (path={:?}, errno={})", path, errno);
+                error!("create(): {}", cause);
+                Self::unlink(&created);
+                return Err(Fail::new(errno, &cause));
+            }
+            created.push(path);
+        }
+        match Self::new(shm, name, CREATOR) {
+            Ok(mut me) => {
+                let pid: u32 = me.pid;
+                let paths: Vec<CString> = created.clone();
+                me.teardown = Some(teardown::register("doorbells", move || {
+                    if process::id() == pid {
+                        Self::unlink(&paths);
+                    }
+                }));
+                me.created = created;
+                Ok(me)
+            },
+            Err(e) => {
+                Self::unlink(&created);
+                Err(e)
+            },
+        }
+    }
+
+    /// Opens the doorbells of an existing ring.
+    pub fn open(name: &str) -> Result<Self, Fail> {
+        let shm: SharedMemory = SharedMemory::open(&Self::build_shm_name(name), NUM_ENDPOINTS * mem::size_of::<u32>())?;
+        Self::new(shm, name, OPENER)
+    }
+
+    /// Opens the doorbells of an existing ring as the endpoint that created it if [creator] is set, or as the one that
+    /// opened it otherwise. The doorbells are left in place when the endpoint goes away.
+    pub fn attach(name: &str, creator: bool) -> Result<Self, Fail> {
+        let shm: SharedMemory = SharedMemory::open(&Self::build_shm_name(name), NUM_ENDPOINTS * mem::size_of::<u32>())?;
+        Self::new(shm, name, if creator { CREATOR } else { OPENER })
+    }
+
+    fn new(shm: SharedMemory, name: &str, local: usize) -> Result<Self, Fail> {
+        let peer: usize = NUM_ENDPOINTS - 1 - local;
+        let local_fd: RawFd = Self::open_fifo(&Self::build_path(name, local)?)?;
+        let peer_fd: RawFd = match Self::open_fifo(&Self::build_path(name, peer)?) {
+            Ok(fd) => fd,
+            Err(e) => {
+                unsafe { libc::close(local_fd) };
+                return Err(e);
+            },
+        };
+        Ok(Self {
+            shm,
+            local,
+            local_fd,
+            peer_fd,
+            created: Vec::new(),
+            pid: process::id(),
+            teardown: None,
+        })
+    }
+
+    /// Removes the doorbells of a ring that was left behind by a process that is gone.
+    pub fn remove(name: &str) {
+        let _ = SharedMemory::remove(&Self::build_shm_name(name));
+        let paths: Vec<CString> = (0..NUM_ENDPOINTS)
+            .filter_map(|index| Self::build_path(name, index).ok())
+            .collect();
+        Self::unlink(&paths);
+    }
+
+    /// Returns the file descriptor that becomes readable when the doorbell of this endpoint rings.
+    pub fn fd(&self) -> RawFd {
+        self.local_fd
+    }
+
+    /// Arms the doorbell of this endpoint, so that the peer rings it on its next push. The caller should check the ring
+    /// once more after arming, to not miss a push that happened concurrently.
+    pub fn arm(&self) {
+        self.flag(self.local).store(1, Ordering::SeqCst);
+        atomic::fence(Ordering::SeqCst);
+    }
+
+    /// Disarms the doorbell of this endpoint and drains any pending rings.
+    pub fn disarm(&self) {
+        self.flag(self.local).store(0, Ordering::SeqCst);
+        let mut buf: [u8; 64] = [0; 64];
+        while unsafe { libc::read(self.local_fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) } > 0 {}
+    }
+
+    /// Rings the doorbell of the peer, if it is armed. This should be called after pushing data.
+    pub fn ring_peer(&self) {
+        atomic::fence(Ordering::SeqCst);
+        let peer: usize = NUM_ENDPOINTS - 1 - self.local;
+        if self.flag(peer).swap(0, Ordering::SeqCst) == 1 {
+            let byte: u8 = 1;
+            // If the FIFO is full the doorbell already rang, thus ignore errors.
+            unsafe { libc::write(self.peer_fd, &byte as *const u8 as *const libc::c_void, 1) };
+        }
+    }
+
+    /// Returns the armed flag at [index].
+    fn flag(&self, index: usize) -> &AtomicU32 {
+        debug_assert!(index < NUM_ENDPOINTS);
+        // Safety: the shared memory region is page-aligned and large enough to hold all flags.
+        unsafe { &*(self.shm.as_ptr() as *const AtomicU32).add(index) }
+    }
+
+    /// Opens a doorbell. Opening for both reading and writing never blocks, even if the other end is not open.
+    fn open_fifo(path: &CString) -> Result<RawFd, Fail> {
+        let fd: RawFd = unsafe { libc::open(path.as_ptr(), libc::O_RDWR | libc::O_NONBLOCK | libc::O_CLOEXEC) };
+        if fd == -1 {
+            let errno: libc::c_int = unsafe { *libc::__errno_location() };
+            let cause: String = format!("failed to open doorbell (path={:?}, errno={})", path, errno);
+            error!("open_fifo(): {}", cause);
+            return Err(Fail::new(errno, &cause));
+        }
+        Ok(fd)
+    }
+
+    /// Removes doorbells.
+    fn unlink(paths: &[CString]) {
+        for path in paths {
+            if unsafe { libc::unlink(path.as_ptr()) } != 0 {
+                warn!("unlink(): failed to remove doorbell (path={:?})", path);
+            }
+        }
+    }
+
+    /// Builds the name of the shared memory region that holds armed flags.
+    fn build_shm_name(name: &str) -> String {
+        format!("{}:db", name)
+    }
+
+    /// Builds the path to the doorbell of endpoint [index].
+    fn build_path(name: &str, index: usize) -> Result<CString, Fail> {
+        match CString::new(format!("{}{}:{}:db", DOORBELL_DIR, name, index)) {
+            Ok(path) => Ok(path),
+            Err(_) => Err(Fail::new(libc::EINVAL, "could not parse name of doorbell")),
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Drop for Doorbell {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.local_fd);
+            libc::close(self.peer_fd);
+        }
+        if self.pid == process::id() {
+            Self::unlink(&self.created);
+        }
+        self.teardown.take();
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::Doorbell;
+    use ::anyhow::Result;
+
+    /// Checks whether the file descriptor of a doorbell is readable.
+    fn is_ringing(doorbell: &Doorbell) -> bool {
+        let mut pollfd: libc::pollfd = libc::pollfd {
+            fd: doorbell.fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        unsafe { libc::poll(&mut pollfd, 1, 0) > 0 }
+    }
+
+    /// Tests that a doorbell only rings when armed.
+    #[test]
+    fn doorbell_rings_when_armed() -> Result<()> {
+        let creator: Doorbell = Doorbell::create("doorbell-test-0")?;
+        let opener: Doorbell = Doorbell::open("doorbell-test-0")?;
+
+        // Not armed, thus should not ring.
+        opener.ring_peer();
+        crate::ensure_eq!(is_ringing(&creator), false);
+
+        // Armed, thus should ring once.
+        creator.arm();
+        opener.ring_peer();
+        opener.ring_peer();
+        crate::ensure_eq!(is_ringing(&creator), true);
+        creator.disarm();
+        crate::ensure_eq!(is_ringing(&creator), false);
+
+        // Works the other way around too.
+        opener.arm();
+        creator.ring_peer();
+        crate::ensure_eq!(is_ringing(&opener), true);
+        crate::ensure_eq!(is_ringing(&creator), false);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,274 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    pal::linux::shm::SharedMemory,
+    runtime::{
+        fail::Fail,
+        yield_with_timeout,
+        SharedDemiRuntime,
+        SharedObject,
+    },
+};
+use ::std::{
+    mem,
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    sync::atomic::{
+        AtomicU64,
+        Ordering,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Number of heartbeat counters in the shared memory region: one for the creator and one for the opener of a ring.
+const NUM_ENDPOINTS: usize = 2;
+
+/// Index of the heartbeat counter of the endpoint that created the ring.
+const CREATOR: usize = 0;
+
+/// Index of the heartbeat counter of the endpoint that opened the ring.
+const OPENER: usize = 1;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Liveness tracking for the two endpoints of a shared memory ring.
+///
+/// Each endpoint periodically bumps its own counter in a small shared memory region and watches the counter of its
+/// peer. If the counter of the peer stops moving for longer than the idle timeout, the peer is declared dead. The peer
+/// is only tracked once it has attached to the ring, so that a creator may wait indefinitely for an opener.
+pub struct Heartbeat {
+    /// Underlying shared memory region. This is released as soon as the ring is dropped.
+    shm: Option<SharedMemory>,
+    /// Index of the counter owned by this endpoint.
+    local: usize,
+    /// Last value observed in the counter of the peer.
+    peer_count: u64,
+    /// When the counter of the peer was last observed to change.
+    peer_last_seen: Option<Instant>,
+    /// Has the peer been declared dead?
+    peer_dead: bool,
+}
+
+#[derive(Clone)]
+pub struct SharedHeartbeat(SharedObject<Heartbeat>);
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Heartbeat {
+    /// Creates the heartbeat region of a ring.
+    fn create(name: &str) -> Result<Self, Fail> {
+        let shm: SharedMemory = SharedMemory::create(&Self::build_name(name), NUM_ENDPOINTS * mem::size_of::<u64>())?;
+        Ok(Self::new(shm, CREATOR))
+    }
+
+    /// Opens the heartbeat region of an existing ring.
+    fn open(name: &str) -> Result<Self, Fail> {
+        let shm: SharedMemory = SharedMemory::open(&Self::build_name(name), NUM_ENDPOINTS * mem::size_of::<u64>())?;
+        Ok(Self::new(shm, OPENER))
+    }
+
+    /// Attaches to the heartbeat region of an existing ring as the endpoint that created it if [creator] is set, or as
+    /// the one that opened it otherwise. This takes over an endpoint that another process handed off.
+    fn attach(name: &str, creator: bool) -> Result<Self, Fail> {
+        let shm: SharedMemory = SharedMemory::open(&Self::build_name(name), NUM_ENDPOINTS * mem::size_of::<u64>())?;
+        Ok(Self::new(shm, if creator { CREATOR } else { OPENER }))
+    }
+
+    fn new(shm: SharedMemory, local: usize) -> Self {
+        let mut me: Self = Self {
+            shm: Some(shm),
+            local,
+            peer_count: 0,
+            peer_last_seen: None,
+            peer_dead: false,
+        };
+        // Beat right away, so that the peer knows that we have attached to the ring.
+        me.beat();
+        me
+    }
+
+    /// Builds the name of the heartbeat region of a ring.
+    fn build_name(name: &str) -> String {
+        format!("{}:hb", name)
+    }
+
+    /// Returns the heartbeat counter at `index`, if the underlying shared memory region was not released.
+    fn counter(&self, index: usize) -> Option<&AtomicU64> {
+        debug_assert!(index < NUM_ENDPOINTS);
+        let shm: &SharedMemory = self.shm.as_ref()?;
+        // Safety: the shared memory region is page-aligned and large enough to hold all counters.
+        Some(unsafe { &*(shm.as_ptr() as *const AtomicU64).add(index) })
+    }
+
+    /// Bumps the heartbeat counter of this endpoint.
+    pub fn beat(&mut self) {
+        if let Some(counter) = self.counter(self.local) {
+            counter.fetch_add(1, Ordering::Release);
+        }
+    }
+
+    /// Checks whether the peer has beaten since the last check. If it has not done so for longer than `idle_timeout`,
+    /// the peer is declared dead.
+    pub fn check_peer(&mut self, now: Instant, idle_timeout: Duration) {
+        let peer: usize = NUM_ENDPOINTS - 1 - self.local;
+        let count: u64 = match self.counter(peer) {
+            Some(counter) => counter.load(Ordering::Acquire),
+            None => return,
+        };
+
+        if count != self.peer_count {
+            self.peer_count = count;
+            self.peer_last_seen = Some(now);
+        } else if let Some(last_seen) = self.peer_last_seen {
+            if !self.peer_dead && now.saturating_duration_since(last_seen) > idle_timeout {
+                warn!(
+                    "check_peer(): peer is idle (idle_time={:?}, idle_timeout={:?})",
+                    now.saturating_duration_since(last_seen),
+                    idle_timeout
+                );
+                self.peer_dead = true;
+            }
+        }
+    }
+
+    /// Returns whether the peer has been declared dead.
+    pub fn is_peer_dead(&self) -> bool {
+        self.peer_dead
+    }
+
+    /// Releases the underlying shared memory region.
+    pub fn release(&mut self) {
+        self.shm = None;
+    }
+}
+
+impl SharedHeartbeat {
+    pub fn create(name: &str) -> Result<Self, Fail> {
+        Ok(Self(SharedObject::new(Heartbeat::create(name)?)))
+    }
+
+    pub fn open(name: &str) -> Result<Self, Fail> {
+        Ok(Self(SharedObject::new(Heartbeat::open(name)?)))
+    }
+
+    pub fn attach(name: &str, creator: bool) -> Result<Self, Fail> {
+        Ok(Self(SharedObject::new(Heartbeat::attach(name, creator)?)))
+    }
+
+    /// Removes the heartbeat region of a ring that was left behind by a process that is gone.
+    pub fn remove(name: &str) {
+        let _ = SharedMemory::remove(&Heartbeat::build_name(name));
+    }
+
+    /// Background coroutine that beats every `interval` and, if an `idle_timeout` is set, watches the peer. This
+    /// coroutine exits once the ring is dropped.
+    pub async fn poll(mut self, runtime: SharedDemiRuntime, interval: Duration, idle_timeout: Option<Duration>) {
+        while self.shm.is_some() {
+            self.beat();
+            if let Some(idle_timeout) = idle_timeout {
+                self.check_peer(runtime.get_now(), idle_timeout);
+            }
+            yield_with_timeout(interval).await;
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Deref for SharedHeartbeat {
+    type Target = Heartbeat;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl DerefMut for SharedHeartbeat {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::SharedHeartbeat;
+    use ::anyhow::Result;
+    use ::std::time::{
+        Duration,
+        Instant,
+    };
+
+    const IDLE_TIMEOUT: Duration = Duration::from_millis(100);
+
+    /// Tests that a creator does not time out while waiting for an opener.
+    #[test]
+    fn heartbeat_waits_for_peer_to_attach() -> Result<()> {
+        let mut creator: SharedHeartbeat = SharedHeartbeat::create("heartbeat-test-0")?;
+        let now: Instant = Instant::now();
+        creator.check_peer(now, IDLE_TIMEOUT);
+        creator.check_peer(now + 10 * IDLE_TIMEOUT, IDLE_TIMEOUT);
+        crate::ensure_eq!(creator.is_peer_dead(), false);
+        Ok(())
+    }
+
+    /// Tests that a peer which keeps beating is not declared dead.
+    #[test]
+    fn heartbeat_peer_alive() -> Result<()> {
+        let mut creator: SharedHeartbeat = SharedHeartbeat::create("heartbeat-test-1")?;
+        let mut opener: SharedHeartbeat = SharedHeartbeat::open("heartbeat-test-1")?;
+        let mut now: Instant = Instant::now();
+        for _ in 0..8 {
+            creator.check_peer(now, IDLE_TIMEOUT);
+            opener.check_peer(now, IDLE_TIMEOUT);
+            creator.beat();
+            opener.beat();
+            now += IDLE_TIMEOUT / 2;
+        }
+        crate::ensure_eq!(creator.is_peer_dead(), false);
+        crate::ensure_eq!(opener.is_peer_dead(), false);
+        Ok(())
+    }
+
+    /// Tests that both endpoints detect when their peer stops beating.
+    #[test]
+    fn heartbeat_peer_dead() -> Result<()> {
+        let mut creator: SharedHeartbeat = SharedHeartbeat::create("heartbeat-test-2")?;
+        let mut opener: SharedHeartbeat = SharedHeartbeat::open("heartbeat-test-2")?;
+        let now: Instant = Instant::now();
+        creator.check_peer(now, IDLE_TIMEOUT);
+        opener.check_peer(now, IDLE_TIMEOUT);
+
+        // Neither endpoint beats anymore.
+        creator.check_peer(now + IDLE_TIMEOUT, IDLE_TIMEOUT);
+        crate::ensure_eq!(creator.is_peer_dead(), false);
+        creator.check_peer(now + 2 * IDLE_TIMEOUT, IDLE_TIMEOUT);
+        opener.check_peer(now + 2 * IDLE_TIMEOUT, IDLE_TIMEOUT);
+        crate::ensure_eq!(creator.is_peer_dead(), true);
+        crate::ensure_eq!(opener.is_peer_dead(), true);
+        Ok(())
+    }
+}
@@ -1,18 +1,27 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
+mod doorbell;
+mod heartbeat;
+mod namespace;
 pub mod queue;
-mod ring;
+pub(crate) mod ring;
 
 //======================================================================================================================
 // Imports
 //======================================================================================================================
 
-use self::queue::SharedCatmemQueue;
+use self::{
+    queue::SharedCatmemQueue,
+    ring::Ring,
+};
 use crate::{
     demikernel::config::Config,
     expect_ok,
-    pal::linux::socketaddrv4_to_sockaddr,
+    pal::linux::{
+        channel,
+        socketaddrv4_to_sockaddr,
+    },
     runtime::{
         fail::Fail,
         limits,
@@ -20,7 +29,15 @@ use crate::{
             DemiBuffer,
             MemoryRuntime,
         },
+        network::{
+            ancillary::AncillaryData,
+            socket::option::SocketOption,
+        },
         queue::downcast_queue,
+        scheduler::{
+            TaskPriority,
+            TaskStats,
+        },
         types::{
             demi_opcode_t,
             demi_qr_value_t,
@@ -34,16 +51,33 @@ use crate::{
     QDesc,
     QToken,
 };
-use ::futures::FutureExt;
+use ::futures::{
+    future::FusedFuture,
+    FutureExt,
+};
 use ::std::{
     mem,
     ops::{
         Deref,
         DerefMut,
     },
+    os::fd::RawFd,
+    pin::Pin,
+    str,
     time::Duration,
 };
 
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Tag that starts the messages which hand a memory queue off to another process. It is followed by a flag that tells
+/// whether the endpoint that created the ring is handed off, and then by the name of the ring.
+const HANDOFF_TAG: &[u8] = b"demikernel:pipe";
+
+/// Maximum length of the name of a memory queue that is handed off.
+const HANDOFF_NAME_MAX: usize = 255;
+
 //======================================================================================================================
 // Structures
 //======================================================================================================================
@@ -51,6 +85,12 @@ use ::std::{
 /// A LibOS that exposes bi-directional memory queues.
 pub struct CatmemLibOS {
     runtime: SharedDemiRuntime,
+    /// How often the liveness of each queue is advertised to its peer.
+    heartbeat_interval: Duration,
+    /// How long a peer may stay silent before its queue is reset. If not set, peers are never timed out.
+    idle_timeout: Option<Duration>,
+    /// Should new queues sleep on a doorbell instead of busy polling?
+    doorbell: bool,
 }
 
 #[derive(Clone)]
@@ -62,39 +102,111 @@ pub struct SharedCatmemLibOS(SharedObject<CatmemLibOS>);
 
 /// Associated functions for Catmem LibOS.
 impl CatmemLibOS {
-    pub fn new(runtime: SharedDemiRuntime) -> Self {
-        Self { runtime }
+    pub fn new(
+        runtime: SharedDemiRuntime,
+        heartbeat_interval: Duration,
+        idle_timeout: Option<Duration>,
+        doorbell: bool,
+    ) -> Self {
+        Self {
+            runtime,
+            heartbeat_interval,
+            idle_timeout,
+            doorbell,
+        }
     }
 }
 
 /// Associate Functions for the shared Catmem LibOS
 impl SharedCatmemLibOS {
     /// Instantiates a shared Catmem LibOS.
-    pub fn new(_config: &Config, runtime: SharedDemiRuntime) -> Self {
-        Self(SharedObject::new(CatmemLibOS::new(runtime)))
+    pub fn new(config: &Config, runtime: SharedDemiRuntime) -> Self {
+        // Rings of crashed processes would otherwise hold on to their names forever.
+        let collected: usize = Ring::collect_dead();
+        if collected > 0 {
+            info!("new(): removed {} rings left behind by dead processes", collected);
+        }
+        Self(SharedObject::new(CatmemLibOS::new(
+            runtime,
+            config.catmem_heartbeat_interval(),
+            config.catmem_idle_timeout(),
+            config.catmem_doorbell(),
+        )))
     }
 
     /// Creates a new memory queue.
     pub fn create_pipe(&mut self, name: &str) -> Result<QDesc, Fail> {
         trace!("create_pipe() name={:?}", name);
-        let qd: QDesc = self
-            .runtime
-            .alloc_queue::<SharedCatmemQueue>(SharedCatmemQueue::create(name)?);
+        let mut queue: SharedCatmemQueue = SharedCatmemQueue::create(name)?;
+        queue.set_doorbell(self.doorbell);
+        self.start_heartbeat(&queue)?;
+        let qd: QDesc = self.runtime.alloc_queue::<SharedCatmemQueue>(queue);
 
         Ok(qd)
     }
 
+    /// Checks whether a memory queue named [name] was created, by any process on this host.
+    pub fn pipe_exists(name: &str) -> bool {
+        Ring::exists(name)
+    }
+
     /// Opens a memory queue.
     pub fn open_pipe(&mut self, name: &str) -> Result<QDesc, Fail> {
         trace!("open_pipe() name={:?}", name);
-
-        let qd: QDesc = self
-            .runtime
-            .alloc_queue::<SharedCatmemQueue>(SharedCatmemQueue::open(name)?);
+        let mut queue: SharedCatmemQueue = SharedCatmemQueue::open(name)?;
+        queue.set_doorbell(self.doorbell);
+        self.start_heartbeat(&queue)?;
+        let qd: QDesc = self.runtime.alloc_queue::<SharedCatmemQueue>(queue);
 
         Ok(qd)
     }
 
+    /// Sets an option on a memory queue. Only [SocketOption::SO_DOORBELL] applies to memory queues, which selects
+    /// whether their pops sleep on a doorbell or busy poll. Sleeping saves CPU when the queue is mostly idle, at the cost
+    /// of a higher latency to wake up.
+    pub fn set_socket_option(&mut self, qd: QDesc, option: SocketOption) -> Result<(), Fail> {
+        trace!("set_socket_option() qd={:?}, option={:?}", qd, option);
+        let mut queue: SharedCatmemQueue = self.get_queue(&qd)?;
+        match option {
+            SocketOption::SO_DOORBELL(enabled) => {
+                queue.set_doorbell(enabled);
+                Ok(())
+            },
+            option => {
+                let cause: String = format!("socket option not supported (option={:?})", option);
+                error!("set_socket_option(): {}", cause);
+                Err(Fail::new(libc::ENOTSUP, &cause))
+            },
+        }
+    }
+
+    /// Gets the current value of an option on a memory queue.
+    pub fn get_socket_option(&mut self, qd: QDesc, option: SocketOption) -> Result<SocketOption, Fail> {
+        trace!("get_socket_option() qd={:?}, option={:?}", qd, option);
+        let queue: SharedCatmemQueue = self.get_queue(&qd)?;
+        match option {
+            SocketOption::SO_DOORBELL(_) => Ok(SocketOption::SO_DOORBELL(queue.doorbell())),
+            option => {
+                let cause: String = format!("socket option not supported (option={:?})", option);
+                error!("get_socket_option(): {}", cause);
+                Err(Fail::new(libc::ENOTSUP, &cause))
+            },
+        }
+    }
+
+    /// Spawns the coroutine that advertises the liveness of `queue` and watches its peer.
+    fn start_heartbeat(&mut self, queue: &SharedCatmemQueue) -> Result<(), Fail> {
+        let coroutine = Box::pin(
+            queue
+                .heartbeat()
+                .poll(self.runtime.clone(), self.heartbeat_interval, self.idle_timeout)
+                .fuse(),
+        );
+        self.runtime
+            .insert_background_coroutine_with_priority("Catmem::heartbeat", TaskPriority::Low, coroutine)?;
+        Ok(())
+    }
+
     /// Shutdown a consumer/pop-only queue. Currently, this is basically a no-op but it does cancel pending operations
     /// and free the queue from the IoQueueTable.
     pub fn shutdown(&mut self, qd: QDesc) -> Result<(), Fail> {
@@ -103,6 +215,70 @@ impl SharedCatmemLibOS {
         queue.shutdown()
     }
 
+    /// Releases a memory queue that this process shares with another one after fork, so that the other process takes
+    /// it over. The queue is not closed, as the other process and the peer keep using its ring. Pending operations on
+    /// the queue should be cancelled beforehand.
+    pub fn release_pipe(&mut self, qd: QDesc) -> Result<(), Fail> {
+        trace!("release_pipe() qd={:?}", qd);
+        let queue: SharedCatmemQueue = self.runtime.free_queue::<SharedCatmemQueue>(&qd)?;
+        // The other process advertises the liveness of the ring from now on.
+        queue.heartbeat().release();
+        // Dropping the queue would remove the names of the regions that back the ring if this process created them, so
+        // the queue is leaked instead, along with its mappings.
+        mem::forget(queue);
+        Ok(())
+    }
+
+    /// Hands a memory queue off to another process through the Unix domain socket [channel]. The queue is then released
+    /// in this process, without closing it. Pending operations on the queue should be cancelled beforehand.
+    pub fn export_pipe(&mut self, qd: QDesc, channel: RawFd) -> Result<(), Fail> {
+        trace!("export_pipe() qd={:?}, channel={:?}", qd, channel);
+        let queue: SharedCatmemQueue = self.get_queue(&qd)?;
+        if queue.name().len() > HANDOFF_NAME_MAX {
+            let cause: String = format!("name of memory queue is too long (qd={:?})", qd);
+            error!("export_pipe(): {}", cause);
+            return Err(Fail::new(libc::ENAMETOOLONG, &cause));
+        }
+        let mut message: Vec<u8> = Vec::with_capacity(HANDOFF_TAG.len() + 1 + queue.name().len());
+        message.extend_from_slice(HANDOFF_TAG);
+        message.push(queue.is_creator() as u8);
+        message.extend_from_slice(queue.name().as_bytes());
+        channel::send_message(channel, &message, None)?;
+        self.release_pipe(qd)
+    }
+
+    /// Takes over a memory queue that another process handed off through the Unix domain socket [channel].
+    pub fn import_pipe(&mut self, channel: RawFd) -> Result<QDesc, Fail> {
+        trace!("import_pipe() channel={:?}", channel);
+        let mut message: Vec<u8> = vec![0; HANDOFF_TAG.len() + 1 + HANDOFF_NAME_MAX];
+        let (len, fd): (usize, Option<RawFd>) = channel::recv_message(channel, &mut message)?;
+        if let Some(fd) = fd {
+            unsafe { libc::close(fd) };
+        }
+        let name: Option<&str> = match fd {
+            None if len > HANDOFF_TAG.len() + 1 && message.starts_with(HANDOFF_TAG) => {
+                str::from_utf8(&message[HANDOFF_TAG.len() + 1..len]).ok()
+            },
+            _ => None,
+        };
+        let name: &str = match name {
+            Some(name) => name,
+            None => {
+                let cause: String = format!("message does not hand a memory queue off (channel={:?})", channel);
+                error!("import_pipe(): {}", cause);
+                return Err(Fail::new(libc::EBADMSG, &cause));
+            },
+        };
+        let creator: bool = message[HANDOFF_TAG.len()] != 0;
+
+        let mut queue: SharedCatmemQueue = SharedCatmemQueue::attach(name, creator)?;
+        queue.set_doorbell(self.doorbell);
+        self.start_heartbeat(&queue)?;
+        let qd: QDesc = self.runtime.alloc_queue::<SharedCatmemQueue>(queue);
+
+        Ok(qd)
+    }
+
     /// Closes a memory queue.
     pub fn close(&mut self, qd: QDesc) -> Result<(), Fail> {
         trace!("close() qd={:?}", qd);
@@ -118,7 +294,7 @@ impl SharedCatmemLibOS {
             let coroutine = Box::pin(self.clone().close_coroutine(qd).fuse());
             self.runtime
                 .clone()
-                .insert_io_coroutine("Catmem::async_close", coroutine)
+                .insert_io_coroutine("Catmem::async_close", qd, coroutine)
         };
 
         queue.async_close(coroutine_constructor)
@@ -151,9 +327,10 @@ impl SharedCatmemLibOS {
         }
     }
 
-    /// Pushes a scatter-gather array to a Push ring. If not a Push ring, then fail.
-    pub fn push(&mut self, qd: QDesc, sga: &demi_sgarray_t) -> Result<QToken, Fail> {
-        trace!("push() qd={:?}", qd);
+    /// Pushes a scatter-gather array to a Push ring. If not a Push ring, then fail. The push fails with ETIMEDOUT if
+    /// it does not complete within [timeout].
+    pub fn push(&mut self, qd: QDesc, sga: &demi_sgarray_t, timeout: Option<Duration>) -> Result<QToken, Fail> {
+        trace!("push() qd={:?}, timeout={:?}", qd, timeout);
 
         let buf: DemiBuffer = self.clone_sgarray(sga)?;
 
@@ -165,7 +342,9 @@ impl SharedCatmemLibOS {
 
         let coroutine = Box::pin(self.clone().push_coroutine(qd, buf).fuse());
 
-        self.runtime.clone().insert_io_coroutine("Catmem::push", coroutine)
+        self.runtime
+            .clone()
+            .insert_io_coroutine_with_timeout("Catmem::push", qd, timeout, coroutine)
     }
 
     pub async fn push_coroutine(self, qd: QDesc, buf: DemiBuffer) -> (QDesc, OperationResult) {
@@ -175,22 +354,26 @@ impl SharedCatmemLibOS {
             Err(e) => return (qd, OperationResult::Failed(e)),
         };
         // Handle result.
+        let nbytes: usize = buf.len();
         match queue.do_push(buf).await {
-            Ok(()) => (qd, OperationResult::Push),
+            Ok(()) => (qd, OperationResult::Push(nbytes)),
             Err(e) => (qd, OperationResult::Failed(e)),
         }
     }
 
-    /// Pops data from a Pop ring. If not a Pop ring, then return an error.
-    pub fn pop(&mut self, qd: QDesc, size: Option<usize>) -> Result<QToken, Fail> {
-        trace!("pop() qd={:?}, size={:?}", qd, size);
+    /// Pops data from a Pop ring. If not a Pop ring, then return an error. The pop fails with ETIMEDOUT if it does not
+    /// complete within [timeout].
+    pub fn pop(&mut self, qd: QDesc, size: Option<usize>, timeout: Option<Duration>) -> Result<QToken, Fail> {
+        trace!("pop() qd={:?}, size={:?}, timeout={:?}", qd, size, timeout);
 
         // We just assert 'size' here, because it was previously checked at PDPIX layer.
         debug_assert!(size.is_none() || ((size.unwrap() > 0) && (size.unwrap() <= limits::POP_SIZE_MAX)));
 
         let coroutine = Box::pin(self.clone().pop_coroutine(qd, size).fuse());
 
-        self.runtime.clone().insert_io_coroutine("Catmem::pop", coroutine)
+        self.runtime
+            .clone()
+            .insert_io_coroutine_with_timeout("Catmem::pop", qd, timeout, coroutine)
     }
 
     pub async fn pop_coroutine(self, qd: QDesc, size: Option<usize>) -> (QDesc, OperationResult) {
@@ -205,7 +388,7 @@ impl SharedCatmemLibOS {
             Ok(result) => result,
             Err(e) => return (qd, OperationResult::Failed(e)),
         };
-        (qd, OperationResult::Pop(None, buf))
+        (qd, OperationResult::Pop(None, buf, AncillaryData::default()))
     }
 
     /// Waits for any of the given pending I/O operations to complete or a timeout to expire.
@@ -232,18 +415,100 @@ impl SharedCatmemLibOS {
         self.runtime.poll()
     }
 
+    /// Shuts the engine down. Every memory queue is closed gracefully, so that its peer gets an end of file, and those
+    /// that are still open once [grace] expires are shut down right away.
+    pub fn shutdown_all(&mut self, grace: Option<Duration>) -> Result<(), Fail> {
+        trace!("shutdown_all() grace={:?}", grace);
+        let mut qts: Vec<QToken> = Vec::new();
+        for qd in self.runtime.get_qtable().get_qds() {
+            match self.async_close(qd) {
+                Ok(qt) => qts.push(qt),
+                Err(e) => warn!("shutdown_all(): failed to close queue (qd={:?}): {:?}", qd, e),
+            }
+        }
+        let pending: Vec<QToken> = self.runtime.wait_all(&qts, grace, |_, qd, result| {
+            if let OperationResult::Failed(e) = result {
+                warn!("shutdown_all(): failed to close queue (qd={:?}): {:?}", qd, e);
+            }
+        })?;
+        for qt in pending {
+            if let Err(e) = self.runtime.cancel(qt) {
+                warn!("shutdown_all(): failed to cancel close (qt={:?}): {:?}", qt, e);
+            }
+        }
+        for qd in self.runtime.get_qtable().get_qds() {
+            if let Err(e) = self.shutdown(qd) {
+                warn!("shutdown_all(): failed to shut down queue (qd={:?}): {:?}", qd, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Cancels the pending I/O operation [qt], which then completes with ECANCELED.
+    pub fn cancel(&mut self, qt: QToken) -> Result<(), Fail> {
+        trace!("cancel(): qt={:?}", qt);
+        self.runtime.cancel(qt)
+    }
+
+    /// Spawns the application coroutine [coroutine] named [name] onto the scheduler.
+    pub fn spawn<F: FusedFuture<Output = Result<(), Fail>> + 'static>(
+        &mut self,
+        name: &'static str,
+        coroutine: Pin<Box<F>>,
+    ) -> Result<QToken, Fail> {
+        trace!("spawn(): name={:?}", name);
+        self.runtime.insert_app_coroutine(name, coroutine)
+    }
+
+    /// Returns a file descriptor that becomes readable when waiting would return.
+    pub fn get_event_fd(&mut self) -> Result<libc::c_int, Fail> {
+        trace!("get_event_fd()");
+        self.runtime.get_event_fd()
+    }
+
+    /// Turns per-coroutine accounting of runtime and poll counts on or off.
+    pub fn set_task_accounting(&mut self, enabled: bool) {
+        trace!("set_task_accounting(): enabled={:?}", enabled);
+        self.runtime.set_task_accounting(enabled)
+    }
+
+    /// Returns the accounting of the [max_tasks] coroutines with the longest single polls.
+    pub fn get_task_stats(&self, max_tasks: usize) -> Vec<TaskStats> {
+        self.runtime.get_task_stats(max_tasks)
+    }
+
+    /// Sets the tunable [name] to [value]. Only tunables under "wait.", which belong to the runtime, are supported.
+    pub fn set_tunable(&mut self, name: &str, value: &str) -> Result<(), Fail> {
+        trace!("set_tunable() name={:?}, value={:?}", name, value);
+        match name.strip_prefix("wait.") {
+            Some(name) => self.runtime.set_wait_tunable(name, value),
+            None => unsupported_tunable(name),
+        }
+    }
+
+    /// Gets the value of the tunable [name]. Only tunables under "wait.", which belong to the runtime, are supported.
+    pub fn get_tunable(&self, name: &str) -> Result<String, Fail> {
+        trace!("get_tunable() name={:?}", name);
+        match name.strip_prefix("wait.") {
+            Some(name) => self.runtime.get_wait_tunable(name),
+            None => unsupported_tunable(name),
+        }
+    }
+
     pub fn create_result(&self, result: OperationResult, qd: QDesc, qt: QToken) -> demi_qresult_t {
         match result {
             OperationResult::Connect => unreachable!("Memory libOSes do not support connect"),
             OperationResult::Accept((_, _)) => unreachable!("Memory libOSes do not support connect"),
-            OperationResult::Push => demi_qresult_t {
+            OperationResult::Splice(_) => unreachable!("Memory libOSes do not support splice"),
+            OperationResult::PopMany(_) => unreachable!("Memory libOSes do not support batched pops"),
+            OperationResult::Push(nbytes) => demi_qresult_t {
                 qr_opcode: demi_opcode_t::DEMI_OPC_PUSH,
                 qr_qd: qd.into(),
                 qr_qt: qt.into(),
-                qr_ret: 0,
+                qr_ret: nbytes as i64,
                 qr_value: unsafe { mem::zeroed() },
             },
-            OperationResult::Pop(addr, bytes) => match self.into_sgarray(bytes) {
+            OperationResult::Pop(addr, bytes, _) => match self.into_sgarray(bytes) {
                 Ok(mut sga) => {
                     if let Some(addr) = addr {
                         sga.sga_addr = socketaddrv4_to_sockaddr(&addr);
@@ -275,6 +540,13 @@ impl SharedCatmemLibOS {
                 qr_ret: 0,
                 qr_value: unsafe { mem::zeroed() },
             },
+            OperationResult::Task => demi_qresult_t {
+                qr_opcode: demi_opcode_t::DEMI_OPC_TASK,
+                qr_qd: qd.into(),
+                qr_qt: qt.into(),
+                qr_ret: 0,
+                qr_value: unsafe { mem::zeroed() },
+            },
             OperationResult::Failed(e) => {
                 warn!("Operation Failed: {:?}", e);
                 demi_qresult_t {
@@ -326,3 +598,14 @@ impl Drop for CatmemLibOS {
 }
 
 impl MemoryRuntime for SharedCatmemLibOS {}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Fails because the tunable [name] does not apply to memory queues.
+fn unsupported_tunable<T>(name: &str) -> Result<T, Fail> {
+    let cause: String = format!("tunable \"{}\" is not supported on memory liboses", name);
+    error!("unsupported_tunable(): {}", cause);
+    Err(Fail::new(libc::ENOTSUP, &cause))
+}
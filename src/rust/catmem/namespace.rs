@@ -0,0 +1,269 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    pal::linux::shm::SharedMemory,
+    runtime::fail::Fail,
+};
+use ::std::{
+    mem,
+    process,
+    sync::atomic::{
+        AtomicU32,
+        Ordering,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Version of the layout of the shared memory regions of a ring. This should be bumped whenever that layout, or the
+/// format of the messages that go through a ring, changes.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Tag that starts the layout header of a ring ("CMEM").
+const MAGIC: u32 = 0x434d_454d;
+
+/// Suffix of the name of the shared memory region that holds the layout header of a ring.
+const HEADER_SUFFIX: &str = ":hdr";
+
+/// Index of the magic tag in the layout header.
+const MAGIC_INDEX: usize = 0;
+
+/// Index of the protocol version in the layout header.
+const VERSION_INDEX: usize = 1;
+
+/// Index of the identifier of the process that owns the ring in the layout header.
+const OWNER_INDEX: usize = 2;
+
+/// Number of fields in the layout header.
+const NUM_FIELDS: usize = 3;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Layout header of a shared memory ring.
+///
+/// The header lives in a shared memory region of its own, which is claimed before any other region of the ring is
+/// created. It records the protocol version of the ring, so that endpoints built from incompatible versions refuse to
+/// attach to each other, and the process that owns the ring, so that rings which were left behind by a crashed process
+/// are recognized and removed instead of being silently reused.
+pub struct Namespace {
+    /// Underlying shared memory region.
+    shm: SharedMemory,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Namespace {
+    /// Claims the name of a new ring for this process. This fails with EEXIST if the name is taken.
+    pub fn create(name: &str) -> Result<Self, Fail> {
+        let shm: SharedMemory = SharedMemory::create(&Self::build_name(name), NUM_FIELDS * mem::size_of::<u32>())?;
+        let me: Self = Self { shm };
+        me.field(VERSION_INDEX).store(PROTOCOL_VERSION, Ordering::Relaxed);
+        me.field(OWNER_INDEX).store(process::id(), Ordering::Relaxed);
+        // Publish the header last, so that no one sees it half written.
+        me.field(MAGIC_INDEX).store(MAGIC, Ordering::Release);
+        Ok(me)
+    }
+
+    /// Opens the layout header of an existing ring and checks that this process may attach to it.
+    pub fn open(name: &str) -> Result<Self, Fail> {
+        let shm: SharedMemory = match SharedMemory::open(&Self::build_name(name), NUM_FIELDS * mem::size_of::<u32>()) {
+            Ok(shm) => shm,
+            // Rings that were created by a version that predates layout headers have none.
+            Err(e) if e.errno == libc::ENOENT && SharedMemory::exists(&format!("{}:tx", name)) => {
+                let cause: String = format!("ring has no layout header (name={:?})", name);
+                error!("open(): {}", cause);
+                return Err(Fail::new(libc::EPROTO, &cause));
+            },
+            Err(e) => return Err(e),
+        };
+        let me: Self = Self { shm };
+        me.check(name)?;
+        Ok(me)
+    }
+
+    /// Opens the layout header of an existing ring and, if [creator] is set, takes over its ownership. This attaches to
+    /// an endpoint that another process handed off, which may have exited since.
+    pub fn attach(name: &str, creator: bool) -> Result<Self, Fail> {
+        let me: Self = Self::open(name)?;
+        if creator {
+            me.field(OWNER_INDEX).store(process::id(), Ordering::Release);
+        }
+        Ok(me)
+    }
+
+    /// Returns the identifier of the process that owns the ring [name], if the ring was left behind by a process that
+    /// is gone. Rings whose layout header is not published yet are never reported, as their owner may still be
+    /// creating them.
+    pub fn dead_owner(name: &str) -> Option<u32> {
+        let shm: SharedMemory = SharedMemory::open(&Self::build_name(name), NUM_FIELDS * mem::size_of::<u32>()).ok()?;
+        let me: Self = Self { shm };
+        if me.field(MAGIC_INDEX).load(Ordering::Acquire) != MAGIC {
+            return None;
+        }
+        let owner: u32 = me.owner();
+        if is_alive(owner) {
+            None
+        } else {
+            Some(owner)
+        }
+    }
+
+    /// Lists the names of the rings that were left behind by processes that are gone.
+    pub fn list_dead() -> Vec<String> {
+        SharedMemory::list()
+            .iter()
+            .filter_map(|region| region.strip_suffix(HEADER_SUFFIX))
+            .filter(|name| Self::dead_owner(name).is_some())
+            .map(String::from)
+            .collect()
+    }
+
+    /// Removes the layout header of a ring that was left behind by a process that is gone.
+    pub fn remove(name: &str) {
+        let _ = SharedMemory::remove(&Self::build_name(name));
+    }
+
+    /// Returns the identifier of the process that owns the ring.
+    pub fn owner(&self) -> u32 {
+        self.field(OWNER_INDEX).load(Ordering::Acquire)
+    }
+
+    /// Checks that the ring [name] was created by a compatible version.
+    fn check(&self, name: &str) -> Result<(), Fail> {
+        let magic: u32 = self.field(MAGIC_INDEX).load(Ordering::Acquire);
+        if magic == 0 {
+            let cause: String = format!("ring is still being created (name={:?})", name);
+            error!("check(): {}", cause);
+            return Err(Fail::new(libc::EAGAIN, &cause));
+        }
+        if magic != MAGIC {
+            let cause: String = format!("bad layout header (name={:?}, magic={:#x})", name, magic);
+            error!("check(): {}", cause);
+            return Err(Fail::new(libc::EPROTO, &cause));
+        }
+        let version: u32 = self.field(VERSION_INDEX).load(Ordering::Relaxed);
+        if version != PROTOCOL_VERSION {
+            let cause: String = format!(
+                "incompatible protocol version (name={:?}, expected={}, found={})",
+                name, PROTOCOL_VERSION, version
+            );
+            error!("check(): {}", cause);
+            return Err(Fail::new(libc::EPROTO, &cause));
+        }
+        Ok(())
+    }
+
+    /// Builds the name of the layout header of a ring.
+    fn build_name(name: &str) -> String {
+        format!("{}{}", name, HEADER_SUFFIX)
+    }
+
+    /// Returns the field at [index] of the layout header.
+    fn field(&self, index: usize) -> &AtomicU32 {
+        debug_assert!(index < NUM_FIELDS);
+        // Safety: the shared memory region is page-aligned and large enough to hold all fields.
+        unsafe { &*(self.shm.as_ptr() as *const AtomicU32).add(index) }
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Checks whether the process [pid] exists. A process that belongs to another user exists too, even though it may not
+/// be signaled.
+fn is_alive(pid: u32) -> bool {
+    let pid: libc::pid_t = match libc::pid_t::try_from(pid) {
+        Ok(pid) if pid > 0 => pid,
+        _ => return false,
+    };
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return true;
+    }
+    unsafe { *libc::__errno_location() != libc::ESRCH }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Namespace,
+        OWNER_INDEX,
+        PROTOCOL_VERSION,
+        VERSION_INDEX,
+    };
+    use crate::catmem::ring::Ring;
+    use ::anyhow::Result;
+    use ::std::{
+        mem,
+        process,
+        sync::atomic::Ordering,
+    };
+
+    /// Identifier of a process that cannot exist, as it is above the largest one that Linux hands out.
+    const DEAD_PID: u32 = i32::MAX as u32;
+
+    /// Tests that a ring cannot be opened by an endpoint built from another protocol version.
+    #[test]
+    fn namespace_rejects_incompatible_version() -> Result<()> {
+        let creator: Namespace = Namespace::create("namespace-test-0")?;
+        crate::ensure_eq!(creator.owner(), process::id());
+        crate::ensure_eq!(Namespace::open("namespace-test-0").is_ok(), true);
+
+        creator
+            .field(VERSION_INDEX)
+            .store(PROTOCOL_VERSION + 1, Ordering::Relaxed);
+        match Namespace::open("namespace-test-0") {
+            Err(e) => crate::ensure_eq!(e.errno, libc::EPROTO),
+            Ok(_) => anyhow::bail!("opening a ring of another protocol version should fail"),
+        }
+
+        Ok(())
+    }
+
+    /// Tests that a ring which was left behind by a process that is gone is removed when its name is reused.
+    #[test]
+    fn namespace_collects_dead_rings() -> Result<()> {
+        // Leave a ring behind, as a crashed process would.
+        let stale: Ring = Ring::create("namespace-test-1")?;
+        mem::forget(stale);
+        crate::ensure_eq!(Namespace::dead_owner("namespace-test-1"), None);
+        match Ring::create("namespace-test-1") {
+            Err(e) => crate::ensure_eq!(e.errno, libc::EEXIST),
+            Ok(_) => anyhow::bail!("creating a ring that is in use should fail"),
+        }
+
+        // Its owner is now gone.
+        Namespace::open("namespace-test-1")?
+            .field(OWNER_INDEX)
+            .store(DEAD_PID, Ordering::Relaxed);
+        crate::ensure_eq!(Namespace::dead_owner("namespace-test-1"), Some(DEAD_PID));
+        crate::ensure_eq!(Namespace::list_dead().contains(&"namespace-test-1".to_string()), true);
+        match Ring::open("namespace-test-1") {
+            Err(e) => crate::ensure_eq!(e.errno, libc::ECONNREFUSED),
+            Ok(_) => anyhow::bail!("opening a ring whose owner is gone should fail"),
+        }
+
+        // The ring was removed, thus its name may be reused.
+        crate::ensure_eq!(Ring::exists("namespace-test-1"), false);
+        let ring: Ring = Ring::create("namespace-test-1")?;
+        crate::ensure_eq!(Namespace::dead_owner("namespace-test-1"), None);
+        drop(ring);
+
+        Ok(())
+    }
+}
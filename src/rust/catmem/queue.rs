@@ -6,17 +6,23 @@
 //======================================================================================================================
 
 use crate::{
-    catmem::ring::{
-        Ring,
-        MAX_RETRIES_PUSH_EOF,
+    catmem::{
+        heartbeat::SharedHeartbeat,
+        ring::{
+            Ring,
+            MAX_RETRIES_PUSH_EOF,
+        },
     },
     expect_ok,
     runtime::{
+        budget::consume_budget,
+        conditional_yield_with_timeout,
         fail::Fail,
         limits,
         memory::DemiBuffer,
         poll_yield,
         queue::IoQueue,
+        wait_readable,
         DemiRuntime,
         QToken,
         QType,
@@ -29,8 +35,17 @@ use ::std::{
         Deref,
         DerefMut,
     },
+    time::Duration,
 };
 
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Maximum time to sleep on a doorbell before checking the ring again. This bounds how long a pop may take to notice
+/// that the peer is gone.
+const DOORBELL_TIMEOUT: Duration = Duration::from_millis(100);
+
 //======================================================================================================================
 // Structures
 //======================================================================================================================
@@ -39,6 +54,12 @@ use ::std::{
 /// the [ring] structure.
 pub struct CatmemQueue {
     ring: Ring,
+    /// Name of the ring.
+    name: String,
+    /// Is this the endpoint that created the ring?
+    creator: bool,
+    /// Should pop operations sleep on the doorbell of the ring instead of busy polling?
+    doorbell: bool,
 }
 
 #[derive(Clone)]
@@ -53,6 +74,9 @@ impl CatmemQueue {
     pub fn create(name: &str) -> Result<Self, Fail> {
         Ok(Self {
             ring: Ring::create(name)?,
+            name: name.to_string(),
+            creator: true,
+            doorbell: false,
         })
     }
 
@@ -60,6 +84,19 @@ impl CatmemQueue {
     pub fn open(name: &str) -> Result<Self, Fail> {
         Ok(Self {
             ring: Ring::open(name)?,
+            name: name.to_string(),
+            creator: false,
+            doorbell: false,
+        })
+    }
+
+    /// Creates a new [CatmemQueue] for the endpoint of an existing ring that another process handed off.
+    pub fn attach(name: &str, creator: bool) -> Result<Self, Fail> {
+        Ok(Self {
+            ring: Ring::attach(name, creator)?,
+            name: name.to_string(),
+            creator,
+            doorbell: false,
         })
     }
 }
@@ -73,6 +110,35 @@ impl SharedCatmemQueue {
         Ok(Self(SharedObject::new(CatmemQueue::open(name)?)))
     }
 
+    pub fn attach(name: &str, creator: bool) -> Result<Self, Fail> {
+        Ok(Self(SharedObject::new(CatmemQueue::attach(name, creator)?)))
+    }
+
+    /// Returns the name of the underlying ring.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Checks whether this is the endpoint that created the underlying ring.
+    pub fn is_creator(&self) -> bool {
+        self.creator
+    }
+
+    /// Selects whether pop operations on this queue sleep on a doorbell or busy poll.
+    pub fn set_doorbell(&mut self, enabled: bool) {
+        self.doorbell = enabled;
+    }
+
+    /// Checks whether pop operations on this queue sleep on a doorbell.
+    pub fn doorbell(&self) -> bool {
+        self.doorbell
+    }
+
+    /// Returns the liveness tracker of the underlying ring.
+    pub fn heartbeat(&self) -> SharedHeartbeat {
+        self.ring.heartbeat()
+    }
+
     pub fn shutdown(&mut self) -> Result<(), Fail> {
         {
             self.ring.prepare_close()?;
@@ -145,8 +211,13 @@ impl SharedCatmemQueue {
     pub async fn do_pop(&mut self, size: Option<usize>) -> Result<(DemiBuffer, bool), Fail> {
         let size: usize = size.unwrap_or(limits::RECVBUF_SIZE_MAX);
         let mut buf: DemiBuffer = DemiBuffer::new(size as u16);
+        let mut armed: bool = false;
         let eof: bool = loop {
-            match self.ring.try_pop(&mut buf) {
+            let result: Result<(usize, bool), Fail> = self.ring.try_pop(&mut buf);
+            if armed && !matches!(&result, Err(e) if DemiRuntime::should_retry(e.errno)) {
+                self.ring.disarm_doorbell();
+            }
+            match result {
                 Ok((len, eof)) => {
                     if eof {
                         self.ring.prepare_close()?;
@@ -158,8 +229,21 @@ impl SharedCatmemQueue {
                     break eof;
                 },
                 Err(e) if DemiRuntime::should_retry(e.errno) => {
-                    // Operation in progress. Check if cancelled.
-                    poll_yield().await;
+                    if !self.doorbell {
+                        // Operation in progress. Check if cancelled.
+                        poll_yield().await;
+                    } else if !armed {
+                        // Arm the doorbell and check the ring once more, so that we do not miss a concurrent push.
+                        self.ring.arm_doorbell();
+                        armed = true;
+                    } else {
+                        // Sleep until the peer rings the doorbell.
+                        let _ =
+                            conditional_yield_with_timeout(wait_readable(self.ring.doorbell_fd()), DOORBELL_TIMEOUT)
+                                .await;
+                        self.ring.disarm_doorbell();
+                        armed = false;
+                    }
                 },
                 Err(e) => return Err(e),
             }
@@ -180,6 +264,8 @@ impl SharedCatmemQueue {
                 },
                 Ok(len) if len < buf.len() => {
                     expect_ok!(buf.adjust(len), "should be able to split remaining bytes");
+                    // Pushing a large buffer may take many rounds, so let other coroutines run in between.
+                    consume_budget(1).await;
                     continue;
                 },
                 Ok(len) => unreachable!(
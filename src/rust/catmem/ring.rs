@@ -6,10 +6,16 @@
 //======================================================================================================================
 
 use crate::{
+    catmem::{
+        doorbell::Doorbell,
+        heartbeat::SharedHeartbeat,
+        namespace::Namespace,
+    },
     collections::{
         concurrent_ring::ConcurrentRingBuffer,
         shared_ring::SharedRingBuffer,
     },
+    pal::linux::shm::SharedMemory,
     runtime::{
         fail::Fail,
         network::ring::{
@@ -18,7 +24,10 @@ use crate::{
         },
     },
 };
-use ::std::ptr::copy;
+use ::std::{
+    os::fd::RawFd,
+    ptr::copy,
+};
 
 //======================================================================================================================
 // Constants
@@ -46,6 +55,9 @@ pub const MAX_RETRIES_PUSH_EOF: u32 = 16;
 //======================================================================================================================
 
 /// An endpoint for a unidirectional queue built on a shared ring buffer
+///
+/// The underlying ring buffers support multiple producers and multiple consumers, thus several processes may open the
+/// same ring and push to it concurrently. Note that an EoF pushed by any of them closes the ring for the consumer.
 pub struct Ring {
     /// Underlying buffer used for sending data.
     push_buf: SharedRingBuffer<ConcurrentRingBuffer>,
@@ -53,6 +65,13 @@ pub struct Ring {
     pop_buf: SharedRingBuffer<ConcurrentRingBuffer>,
     /// Indicates whether the ring is open or closed.
     state_machine: RingStateMachine,
+    /// Liveness tracking for the peer.
+    heartbeat: SharedHeartbeat,
+    /// Doorbells for sleeping until the peer pushes data.
+    doorbell: Doorbell,
+    /// Layout header. This comes last, so that the name of the ring is released after all of its regions.
+    #[allow(unused)]
+    namespace: Namespace,
 }
 
 //======================================================================================================================
@@ -66,10 +85,29 @@ impl Ring {
         if name.is_empty() {
             return Err(Fail::new(libc::EINVAL, "name of shared memory region cannot be empty"));
         }
+        // Claim the name first, taking it over from a process that is gone if needed.
+        let namespace: Namespace = match Namespace::create(name) {
+            Ok(namespace) => namespace,
+            Err(e) if e.errno == libc::EEXIST => match Namespace::dead_owner(name) {
+                Some(pid) => {
+                    warn!(
+                        "create(): removing ring left behind by a dead process (name={:?}, pid={})",
+                        name, pid
+                    );
+                    Self::remove(name);
+                    Namespace::create(name)?
+                },
+                None => return Err(e),
+            },
+            Err(e) => return Err(e),
+        };
         Ok(Self {
             push_buf: SharedRingBuffer::create(&format!("{}:tx", name), RING_BUFFER_CAPACITY)?,
             pop_buf: SharedRingBuffer::create(&format!("{}:rx", name), RING_BUFFER_CAPACITY)?,
             state_machine: RingStateMachine::new(),
+            heartbeat: SharedHeartbeat::create(name)?,
+            doorbell: Doorbell::create(name)?,
+            namespace,
         })
     }
 
@@ -79,21 +117,87 @@ impl Ring {
         if name.is_empty() {
             return Err(Fail::new(libc::EINVAL, "name of shared memory region cannot be empty"));
         }
+        let namespace: Namespace = Namespace::open(name)?;
+        // Do not attach to a ring whose creator is gone, as nobody would ever push to it or drain it.
+        if let Some(pid) = Namespace::dead_owner(name) {
+            drop(namespace);
+            Self::remove(name);
+            let cause: String = format!("ring was left behind by a dead process (name={:?}, pid={})", name, pid);
+            error!("open(): {}", cause);
+            return Err(Fail::new(libc::ECONNREFUSED, &cause));
+        }
         Ok(Self {
             push_buf: SharedRingBuffer::open(&format!("{}:rx", name), RING_BUFFER_CAPACITY)?,
             pop_buf: SharedRingBuffer::open(&format!("{}:tx", name), RING_BUFFER_CAPACITY)?,
             state_machine: RingStateMachine::new(),
+            heartbeat: SharedHeartbeat::open(name)?,
+            doorbell: Doorbell::open(name)?,
+            namespace,
         })
     }
 
+    /// Attaches to an existing shared memory ring as the endpoint that created it if [creator] is set, or as the one that
+    /// opened it otherwise. This takes over an endpoint that another process handed off, so the shared memory regions
+    /// of the ring are left in place when it goes away.
+    pub fn attach(name: &str, creator: bool) -> Result<Self, Fail> {
+        if !creator {
+            return Self::open(name);
+        }
+        // Check if provided name is valid.
+        if name.is_empty() {
+            return Err(Fail::new(libc::EINVAL, "name of shared memory region cannot be empty"));
+        }
+        Ok(Self {
+            push_buf: SharedRingBuffer::open(&format!("{}:tx", name), RING_BUFFER_CAPACITY)?,
+            pop_buf: SharedRingBuffer::open(&format!("{}:rx", name), RING_BUFFER_CAPACITY)?,
+            state_machine: RingStateMachine::new(),
+            heartbeat: SharedHeartbeat::attach(name, creator)?,
+            doorbell: Doorbell::attach(name, creator)?,
+            namespace: Namespace::attach(name, creator)?,
+        })
+    }
+
+    /// Removes the shared memory regions and doorbells of all rings that were left behind by processes that are gone,
+    /// and returns how many of them were removed.
+    pub fn collect_dead() -> usize {
+        let names: Vec<String> = Namespace::list_dead();
+        for name in &names {
+            warn!(
+                "collect_dead(): removing ring left behind by a dead process (name={:?})",
+                name
+            );
+            Self::remove(name);
+        }
+        names.len()
+    }
+
+    /// Removes the names of the shared memory regions and doorbells of a ring that was left behind by a process that is
+    /// gone. Processes that still map the ring keep using it.
+    fn remove(name: &str) {
+        let _ = SharedMemory::remove(&format!("{}:tx", name));
+        let _ = SharedMemory::remove(&format!("{}:rx", name));
+        SharedHeartbeat::remove(name);
+        Doorbell::remove(name);
+        Namespace::remove(name);
+    }
+
+    /// Checks whether a shared memory ring named [name] was created, by any process on this host.
+    pub fn exists(name: &str) -> bool {
+        SharedMemory::exists(&format!("{}:tx", name))
+    }
+
     /// Try to pop a byte from the shared memory ring. If successful, return the byte and whether the eof flag is set,
     /// otherwise return None for a retry.
     pub fn try_pop(&mut self, buf: &mut [u8]) -> Result<(usize, bool), Fail> {
         self.state_machine.may_pop()?;
 
         let mut msg: Vec<u8> = vec![0; buf.len() + HEADER_SIZE];
-        // Read data from the ring buffer.
-        let msg_len: usize = self.pop_buf.try_pop(&mut msg)? - HEADER_SIZE;
+        // Read data from the ring buffer. Data that was pushed before the peer went away is still delivered.
+        let msg_len: usize = match self.pop_buf.try_pop(&mut msg) {
+            Ok(len) => len - HEADER_SIZE,
+            Err(_) if self.heartbeat.is_peer_dead() => return Err(Self::peer_dead("try_pop")),
+            Err(e) => return Err(e),
+        };
 
         // Check how many bytes were read.
         if msg_len > 0 {
@@ -125,12 +229,17 @@ impl Ring {
     /// ring, return [false], otherwise, return [true] if successfully enqueued.
     pub fn try_push(&mut self, buf: &[u8]) -> Result<usize, Fail> {
         self.state_machine.may_push()?;
+        if self.heartbeat.is_peer_dead() {
+            return Err(Self::peer_dead("try_push"));
+        }
         // Write the header.
         let mut msg: Vec<u8> = REGULAR_MESSAGE_HEADER.to_vec();
         msg.append(&mut buf.to_vec());
 
         // Write data to the ring buffer.
-        Ok(self.push_buf.try_push(&msg)? - HEADER_SIZE)
+        let len: usize = self.push_buf.try_push(&msg)? - HEADER_SIZE;
+        self.doorbell.ring_peer();
+        Ok(len)
     }
 
     /// Closes the target ring.
@@ -154,6 +263,7 @@ impl Ring {
         match self.push_buf.try_push(&EOF_MESSAGE_HEADER) {
             Ok(len) => {
                 debug_assert_eq!(len, HEADER_SIZE);
+                self.doorbell.ring_peer();
                 Ok(())
             },
             Err(_) => {
@@ -183,4 +293,42 @@ impl Ring {
     pub fn abort(&mut self) {
         self.state_machine.abort();
     }
+
+    /// Arms the doorbell of the target ring, so that it rings on the next push of the peer.
+    pub fn arm_doorbell(&mut self) {
+        self.doorbell.arm();
+    }
+
+    /// Disarms the doorbell of the target ring.
+    pub fn disarm_doorbell(&mut self) {
+        self.doorbell.disarm();
+    }
+
+    /// Returns the file descriptor that becomes readable when the doorbell of the target ring rings.
+    pub fn doorbell_fd(&self) -> RawFd {
+        self.doorbell.fd()
+    }
+
+    /// Returns the liveness tracker of the target ring.
+    pub fn heartbeat(&self) -> SharedHeartbeat {
+        self.heartbeat.clone()
+    }
+
+    /// Builds the error returned when the peer is gone.
+    fn peer_dead(caller: &str) -> Fail {
+        let cause: &str = "peer stopped responding";
+        error!("{}(): {}", caller, cause);
+        Fail::new(libc::ECONNRESET, cause)
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Drop for Ring {
+    fn drop(&mut self) {
+        // Release the heartbeat region right away, instead of waiting for the heartbeat coroutine to exit.
+        self.heartbeat.release();
+    }
 }
@@ -181,6 +181,38 @@ impl ActiveSocketData {
         Ok((addr, incoming))
     }
 
+    /// Checks whether some data waits to be sent, or a connect waits to complete.
+    pub fn has_pending_send(&self) -> bool {
+        !self.send_queue.is_empty()
+    }
+
+    /// Creates the metadata of an established socket that another process handed off, along with the data that it had
+    /// received but not popped yet and whether the peer had closed the connection.
+    pub fn with_received(socket: Socket, data: &[u8], closed: bool) -> Result<Self, Fail> {
+        let mut me: Self = Self::new(socket);
+        for chunk in data.chunks(limits::POP_SIZE_MAX) {
+            me.recv_queue.push(Ok((None, DemiBuffer::from_slice(chunk)?)));
+        }
+        if closed {
+            me.recv_queue.push(Ok((None, DemiBuffer::new(0))));
+            me.closed = true;
+        }
+        Ok(me)
+    }
+
+    /// Copies out the data that was received but not popped yet, and whether the peer closed the connection, so that
+    /// they can be handed off along with the socket.
+    pub fn get_received(&self) -> Result<(Vec<u8>, bool), Fail> {
+        let mut data: Vec<u8> = Vec::new();
+        for item in self.recv_queue.get_values() {
+            match item {
+                Ok((_, buf)) => data.extend_from_slice(&buf[..]),
+                Err(e) => return Err(Fail::new(e.errno, "socket has a pending receive error")),
+            }
+        }
+        Ok((data, self.closed))
+    }
+
     pub fn get_socket(&self) -> &Socket {
         &self.socket
     }
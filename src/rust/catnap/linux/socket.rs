@@ -62,6 +62,14 @@ impl SharedSocketData {
         )))
     }
 
+    /// Creates new metadata representing an established socket that another process handed off, along with the data
+    /// that it had received.
+    pub fn new_handed_off(socket: Socket, data: &[u8], closed: bool) -> Result<Self, Fail> {
+        Ok(Self(SharedObject::<SocketData>::new(SocketData::Active(
+            ActiveSocketData::with_received(socket, data, closed)?,
+        ))))
+    }
+
     /// Moves an inactive socket to a passive listening socket.
     pub fn move_socket_to_passive(&mut self) {
         let socket: Socket = match self.deref_mut() {
@@ -136,6 +144,23 @@ impl SharedSocketData {
         }
     }
 
+    /// Checks whether some data waits to be sent on an active socket.
+    pub fn has_pending_send(&self) -> bool {
+        match self.deref() {
+            SocketData::Active(data) => data.has_pending_send(),
+            _ => false,
+        }
+    }
+
+    /// Copies out the data that was received on an active socket but not popped yet, and whether the peer closed the
+    /// connection.
+    pub fn get_received(&self) -> Result<(Vec<u8>, bool), Fail> {
+        match self.deref() {
+            SocketData::Active(data) => data.get_received(),
+            _ => Err(Fail::new(libc::ENOTCONN, "socket is not connected")),
+        }
+    }
+
     /// Handle incoming data event.
     pub fn poll_in(&mut self) {
         match self.deref_mut() {
@@ -21,20 +21,38 @@ use crate::{
     demikernel::config::Config,
     expect_ok,
     expect_some,
+    pal::linux::{
+        self,
+        channel,
+    },
     runtime::{
         fail::Fail,
+        limits,
         memory::{
             DemiBuffer,
             MemoryRuntime,
         },
-        network::transport::NetworkTransport,
+        network::{
+            ancillary::AncillaryData,
+            socket::{
+                filter::SocketFilter,
+                option::SocketOption,
+            },
+            transport::NetworkTransport,
+        },
         poll_yield,
+        wait_readable,
         DemiRuntime,
+        SharedConditionVariable,
         SharedDemiRuntime,
         SharedObject,
     },
 };
-use ::futures::FutureExt;
+use ::futures::{
+    pin_mut,
+    select_biased,
+    FutureExt,
+};
 use ::slab::Slab;
 use ::socket2::{
     Domain,
@@ -54,6 +72,7 @@ use ::std::{
     },
     os::fd::{
         AsRawFd,
+        FromRawFd,
         RawFd,
     },
 };
@@ -65,6 +84,13 @@ use ::std::{
 // Set to the max number of file descriptors that can be open without increasing the number on Linux.
 const EPOLL_BATCH_SIZE: usize = 1024;
 
+/// Tag that starts the messages which hand a connection off to another process. It is followed by a flag that tells
+/// whether the peer closed the connection, and then by the data that was received but not popped yet.
+const HANDOFF_TAG: &[u8] = b"demikernel:tcp";
+
+/// Maximum amount of received data that may be handed off along with a connection.
+const HANDOFF_DATA_MAX: usize = 4 * limits::POP_SIZE_MAX;
+
 //======================================================================================================================
 // Structures
 //======================================================================================================================
@@ -72,6 +98,11 @@ const EPOLL_BATCH_SIZE: usize = 1024;
 /// Underlying network transport.
 pub struct CatnapTransport {
     epoll_fd: RawFd,
+    /// Epoll instance that only watches incoming events, on which the background coroutine sleeps while there is
+    /// nothing to send.
+    idle_epoll_fd: RawFd,
+    /// Signaled when data is queued to be sent, which wakes up the background coroutine.
+    send_signal: SharedConditionVariable,
     socket_table: Slab<SharedSocketData>,
     runtime: SharedDemiRuntime,
 }
@@ -90,19 +121,15 @@ type SockDesc = <SharedCatnapTransport as NetworkTransport>::SocketDescriptor;
 impl SharedCatnapTransport {
     /// Create a new Linux-based network transport.
     pub fn new(_config: &Config, runtime: &mut SharedDemiRuntime) -> Self {
-        // Create epoll socket.
-        // Linux ignores the size argument to epoll, it just has to be more than 0.
-        let epoll_fd: RawFd = match unsafe { libc::epoll_create(10) } {
-            fd if fd >= 0 => fd.into(),
-            _ => {
-                let errno: libc::c_int = unsafe { *libc::__errno_location() };
-                panic!("could not create epoll socket: {:?}", errno);
-            },
-        };
+        // Create epoll sockets.
+        let epoll_fd: RawFd = Self::create_epoll();
+        let idle_epoll_fd: RawFd = Self::create_epoll();
 
         // Set up background task for polling epoll API.
         let me: Self = Self(SharedObject::new(CatnapTransport {
             epoll_fd,
+            idle_epoll_fd,
+            send_signal: SharedConditionVariable::default(),
             socket_table: Slab::<SharedSocketData>::new(),
             runtime: runtime.clone(),
         }));
@@ -117,45 +144,60 @@ impl SharedCatnapTransport {
         me
     }
 
+    /// Creates an epoll socket.
+    fn create_epoll() -> RawFd {
+        // The epoll socket is not inherited by programs that a child process executes.
+        match unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) } {
+            fd if fd >= 0 => fd.into(),
+            _ => {
+                let errno: libc::c_int = unsafe { *libc::__errno_location() };
+                panic!("could not create epoll socket: {:?}", errno);
+            },
+        }
+    }
+
     /// This function registers a handler for incoming and outgoing I/O on the socket. There should only be one of
-    /// these per socket.
+    /// these per socket. Incoming I/O is also registered with the idle epoll socket.
     fn register_epoll(&mut self, sd: &SockDesc, events: u32) -> Result<(), Fail> {
         let fd: RawFd = self.raw_fd_from_sd(sd);
-        let mut epoll_event: libc::epoll_event = libc::epoll_event {
-            events,
-            u64: *sd as u64,
-        };
-        match unsafe { libc::epoll_ctl(self.epoll_fd, libc::EPOLL_CTL_ADD, fd, &mut epoll_event) } {
-            0 => Ok(()),
-            _ => {
+        for (epoll_fd, events) in [
+            (self.epoll_fd, events),
+            (self.idle_epoll_fd, (libc::EPOLLIN | libc::EPOLLET) as u32),
+        ] {
+            let mut epoll_event: libc::epoll_event = libc::epoll_event {
+                events,
+                u64: *sd as u64,
+            };
+            if unsafe { libc::epoll_ctl(epoll_fd, libc::EPOLL_CTL_ADD, fd, &mut epoll_event) } != 0 {
                 let errno: libc::c_int = unsafe { *libc::__errno_location() };
                 let cause: String = format!("failed to register epoll (fd={:?}, errno={:?})", fd, errno);
                 error!("register_epoll(): {}", cause);
-                Err(Fail::new(errno, &cause))
-            },
+                return Err(Fail::new(errno, &cause));
+            }
         }
+        Ok(())
     }
 
     /// THis function removes the handlers for incoming and outgoing I/O on the socket.
     fn unregister_epoll(&mut self, sd: &SockDesc, events: u32) -> Result<(), Fail> {
         let fd: RawFd = self.raw_fd_from_sd(sd);
-        let mut epoll_event: libc::epoll_event = libc::epoll_event {
-            events,
-            u64: *sd as u64,
-        };
-        match unsafe { libc::epoll_ctl(self.epoll_fd, libc::EPOLL_CTL_DEL, fd, &mut epoll_event) } {
-            0 => Ok(()),
-            _ => {
+        for epoll_fd in [self.epoll_fd, self.idle_epoll_fd] {
+            let mut epoll_event: libc::epoll_event = libc::epoll_event {
+                events,
+                u64: *sd as u64,
+            };
+            if unsafe { libc::epoll_ctl(epoll_fd, libc::EPOLL_CTL_DEL, fd, &mut epoll_event) } != 0 {
                 let errno: libc::c_int = unsafe { *libc::__errno_location() };
                 if errno == libc::EBADF || errno == libc::ENOENT {
                     warn!("epoll event was already removed or never registered");
-                    return Ok(());
+                    continue;
                 }
                 let cause: String = format!("failed to remove epoll (fd={:?}, errno={:?})", fd, errno);
                 error!("unregister_epoll(): {}", cause);
-                Err(Fail::new(errno, &cause))
-            },
+                return Err(Fail::new(errno, &cause));
+            }
         }
+        Ok(())
     }
 
     /// Background function for checking for epoll events.
@@ -220,11 +262,53 @@ impl SharedCatnapTransport {
                     .poll_out();
                 }
             }
-            // Yield for one iteration.
-            poll_yield().await;
+            if self.has_pending_send() {
+                // Yield for one iteration.
+                poll_yield().await;
+            } else {
+                // Sleep until an incoming event or new data to send, which lets the runtime put the thread to sleep.
+                let mut send_signal: SharedConditionVariable = self.send_signal.clone();
+                let readable = wait_readable(self.idle_epoll_fd).fuse();
+                let queued = send_signal.wait().fuse();
+                pin_mut!(readable);
+                pin_mut!(queued);
+                select_biased! {
+                    _ = readable => (),
+                    _ = queued => (),
+                }
+                // Incoming events are edge-triggered on the idle epoll, so drain them to sleep again on the next idle wait.
+                self.drain_idle_epoll(&mut events);
+                poll_yield().await;
+            }
         }
     }
 
+    /// Consumes the events that are ready on the idle epoll, which are only used to wake up the background coroutine.
+    fn drain_idle_epoll(&self, events: &mut Vec<libc::epoll_event>) {
+        events.clear();
+        while unsafe {
+            libc::epoll_wait(
+                self.idle_epoll_fd,
+                events.as_mut_ptr() as *mut libc::epoll_event,
+                EPOLL_BATCH_SIZE as i32,
+                0,
+            )
+        } == EPOLL_BATCH_SIZE as i32
+        {}
+    }
+
+    /// Checks whether some socket has data waiting to be sent. Outgoing events on sockets are level-triggered, so the
+    /// background coroutine busy-polls until all of it has been sent.
+    fn has_pending_send(&self) -> bool {
+        self.socket_table.iter().any(|(_, data)| data.has_pending_send())
+    }
+
+    /// Queues [buf] to be sent to [addr] on the socket and waits until it has been sent.
+    async fn send(&mut self, sd: &SockDesc, addr: Option<SocketAddr>, buf: DemiBuffer) -> Result<(), Fail> {
+        self.send_signal.signal();
+        self.data_from_sd(sd).push(addr, buf).await
+    }
+
     /// Internal function to get the raw file descriptor from a socket, given the socket descriptor.
     fn raw_fd_from_sd(&self, sd: &SockDesc) -> RawFd {
         expect_some!(self.socket_table.get(*sd), "shoudld have been allocated").as_raw_fd()
@@ -274,7 +358,7 @@ impl NetworkTransport for SharedCatnapTransport {
     type SocketDescriptor = usize;
 
     /// Creates a new socket on the underlying network transport. We only support IPv4 and UDP and TCP sockets for now.
-    fn socket(&mut self, domain: Domain, typ: Type) -> Result<Self::SocketDescriptor, Fail> {
+    fn socket(&mut self, domain: Domain, typ: Type, _: Protocol) -> Result<Self::SocketDescriptor, Fail> {
         // Select protocol.
         let protocol: Protocol = match typ {
             Type::STREAM => Protocol::TCP,
@@ -429,7 +513,7 @@ impl NetworkTransport for SharedCatnapTransport {
                     // Check the return error code.
                     let errno: i32 = get_libc_err(e);
                     if DemiRuntime::should_retry(errno) {
-                        self.data_from_sd(sd).push(None, DemiBuffer::new(0)).await?;
+                        self.send(sd, None, DemiBuffer::new(0)).await?;
                     } else {
                         let cause: String = format!("failed to connect on socket: {:?}", errno);
                         error!("connect(): {}", cause);
@@ -472,6 +556,97 @@ impl NetworkTransport for SharedCatnapTransport {
         Ok(())
     }
 
+    /// Hand the connection off to another process by passing the socket through [channel], along with the data that
+    /// was received but not popped yet. Data that is still being pushed would be lost, so this fails with EBUSY until
+    /// pending pushes complete.
+    fn export(&mut self, sd: &mut Self::SocketDescriptor, channel: RawFd) -> Result<(), Fail> {
+        let data: &mut SharedSocketData = self.data_from_sd(sd);
+        if !matches!(**data, SocketData::Active(_)) {
+            let cause: String = format!("only connections can be handed off (sd={:?})", sd);
+            error!("export(): {}", cause);
+            return Err(Fail::new(libc::ENOTCONN, &cause));
+        }
+        if data.has_pending_send() {
+            let cause: String = format!("connection has data left to send (sd={:?})", sd);
+            error!("export(): {}", cause);
+            return Err(Fail::new(libc::EBUSY, &cause));
+        }
+        let (received, closed): (Vec<u8>, bool) = data.get_received()?;
+        if received.len() > HANDOFF_DATA_MAX {
+            let cause: String = format!(
+                "connection has too much data left to pop (sd={:?}, len={:?})",
+                sd,
+                received.len()
+            );
+            error!("export(): {}", cause);
+            return Err(Fail::new(libc::EBUSY, &cause));
+        }
+
+        let mut message: Vec<u8> = Vec::with_capacity(HANDOFF_TAG.len() + 1 + received.len());
+        message.extend_from_slice(HANDOFF_TAG);
+        message.push(closed as u8);
+        message.extend_from_slice(&received);
+        channel::send_message(channel, &message, Some(data.as_raw_fd()))?;
+
+        // The other process holds a duplicate of the socket, so that dropping ours leaves the connection open. Epoll
+        // registrations follow the socket rather than its descriptor though, so they have to be removed by hand.
+        self.unregister_epoll(sd, (libc::EPOLLIN | libc::EPOLLOUT) as u32)?;
+        self.socket_table.remove(*sd);
+        Ok(())
+    }
+
+    /// Take over a connection that another process handed off through [channel].
+    fn import(&mut self, channel: RawFd) -> Result<(Self::SocketDescriptor, SocketAddr, SocketAddr), Fail> {
+        let mut message: Vec<u8> = vec![0; HANDOFF_TAG.len() + 1 + HANDOFF_DATA_MAX];
+        let (len, fd): (usize, Option<RawFd>) = channel::recv_message(channel, &mut message)?;
+        // Take ownership of the socket right away, so that it is closed if the message turns out to be invalid.
+        let socket: Option<Socket> = fd.map(|fd| unsafe { Socket::from_raw_fd(fd) });
+        let socket: Socket = match socket {
+            Some(socket) if len > HANDOFF_TAG.len() && message.starts_with(HANDOFF_TAG) => socket,
+            _ => {
+                let cause: String = format!("message does not hand a connection off (channel={:?})", channel);
+                error!("import(): {}", cause);
+                return Err(Fail::new(libc::EBADMSG, &cause));
+            },
+        };
+        if !socket.r#type().is_ok_and(|typ| typ == Type::STREAM) {
+            let cause: String = format!("handed off socket is not a TCP socket (channel={:?})", channel);
+            error!("import(): {}", cause);
+            return Err(Fail::new(libc::EBADMSG, &cause));
+        }
+        let closed: bool = message[HANDOFF_TAG.len()] != 0;
+        let received: &[u8] = &message[HANDOFF_TAG.len() + 1..len];
+
+        let (local, remote): (SocketAddr, SocketAddr) = match (socket.local_addr(), socket.peer_addr()) {
+            (Ok(local), Ok(remote)) => match (local.as_socket(), remote.as_socket()) {
+                (Some(local), Some(remote)) => (local, remote),
+                _ => {
+                    let cause: String = format!("handed off socket is not an IP connection (channel={:?})", channel);
+                    error!("import(): {}", cause);
+                    return Err(Fail::new(libc::EBADMSG, &cause));
+                },
+            },
+            (Err(e), _) | (_, Err(e)) => {
+                let cause: String = format!("handed off socket is not connected: {:?}", e);
+                error!("import(): {}", cause);
+                return Err(Fail::new(get_libc_err(e), &cause));
+            },
+        };
+        if let Err(e) = socket.set_nonblocking(true) {
+            let cause: String = format!("cannot set NONBLOCKING option: {:?}", e);
+            error!("import(): {}", cause);
+            return Err(Fail::new(get_libc_err(e), &cause));
+        }
+
+        let data: SharedSocketData = SharedSocketData::new_handed_off(socket, received, closed)?;
+        let sd: usize = self.socket_table.insert(data);
+        if let Err(e) = self.register_epoll(&sd, (libc::EPOLLIN | libc::EPOLLOUT) as u32) {
+            self.socket_table.remove(sd);
+            return Err(e);
+        }
+        Ok((sd, local, remote))
+    }
+
     /// Push [buf] to the underlying transport. This function blocks until the entire buffer has been written to the
     /// socket. Returns Ok if successfully sent and an error if not.
     async fn push(
@@ -481,7 +656,7 @@ impl NetworkTransport for SharedCatnapTransport {
         addr: Option<SocketAddr>,
     ) -> Result<(), Fail> {
         {
-            self.data_from_sd(sd).push(addr, buf.clone()).await?;
+            self.send(sd, addr, buf.clone()).await?;
             // Clear out the original buffer.
             expect_ok!(buf.trim(buf.len()), "Should be able to empty the buffer");
             Ok(())
@@ -495,8 +670,247 @@ impl NetworkTransport for SharedCatnapTransport {
         &mut self,
         sd: &mut Self::SocketDescriptor,
         size: usize,
-    ) -> Result<(Option<SocketAddr>, DemiBuffer), Fail> {
-        self.data_from_sd(sd).pop(size).await
+    ) -> Result<(Option<SocketAddr>, DemiBuffer, AncillaryData), Fail> {
+        let (addr, buf) = self.data_from_sd(sd).pop(size).await?;
+        Ok((addr, buf, AncillaryData::default()))
+    }
+
+    /// Sets [option] on the underlying socket.
+    fn set_socket_option(&mut self, sd: &mut Self::SocketDescriptor, option: SocketOption) -> Result<(), Fail> {
+        let socket: &mut Socket = self.socket_from_sd(sd);
+        let result: io::Result<()> = match option {
+            SocketOption::IP_TOS(tos) => socket.set_tos(tos as u32),
+            SocketOption::IP_TTL(ttl) => socket.set_ttl(ttl as u32),
+            SocketOption::SO_PRIORITY(priority) => {
+                let optval: libc::c_int = priority as libc::c_int;
+                let optval_len: libc::socklen_t = std::mem::size_of_val(&optval) as libc::socklen_t;
+                match unsafe {
+                    libc::setsockopt(
+                        socket.as_raw_fd(),
+                        libc::SOL_SOCKET,
+                        libc::SO_PRIORITY,
+                        &optval as *const _ as *const libc::c_void,
+                        optval_len,
+                    )
+                } {
+                    0 => Ok(()),
+                    _ => Err(io::Error::last_os_error()),
+                }
+            },
+            SocketOption::SO_MAX_PACING_RATE(rate) => {
+                let optval: u64 = rate;
+                let optval_len: libc::socklen_t = std::mem::size_of_val(&optval) as libc::socklen_t;
+                match unsafe {
+                    libc::setsockopt(
+                        socket.as_raw_fd(),
+                        libc::SOL_SOCKET,
+                        libc::SO_MAX_PACING_RATE,
+                        &optval as *const _ as *const libc::c_void,
+                        optval_len,
+                    )
+                } {
+                    0 => Ok(()),
+                    _ => Err(io::Error::last_os_error()),
+                }
+            },
+            SocketOption::SO_NO_CHECK(no_check) => {
+                let optval: libc::c_int = no_check as libc::c_int;
+                let optval_len: libc::socklen_t = std::mem::size_of_val(&optval) as libc::socklen_t;
+                match unsafe {
+                    libc::setsockopt(
+                        socket.as_raw_fd(),
+                        libc::SOL_SOCKET,
+                        libc::SO_NO_CHECK,
+                        &optval as *const _ as *const libc::c_void,
+                        optval_len,
+                    )
+                } {
+                    0 => Ok(()),
+                    _ => Err(io::Error::last_os_error()),
+                }
+            },
+            SocketOption::UDP_SEGMENT(size) => {
+                let optval: libc::c_int = size as libc::c_int;
+                let optval_len: libc::socklen_t = std::mem::size_of_val(&optval) as libc::socklen_t;
+                match unsafe {
+                    libc::setsockopt(
+                        socket.as_raw_fd(),
+                        libc::SOL_UDP,
+                        libc::UDP_SEGMENT,
+                        &optval as *const _ as *const libc::c_void,
+                        optval_len,
+                    )
+                } {
+                    0 => Ok(()),
+                    _ => Err(io::Error::last_os_error()),
+                }
+            },
+            // The kernel aborts its connections on user timeouts as the inetstack does.
+            SocketOption::TCP_USER_TIMEOUT(millis) => {
+                let optval: libc::c_uint = millis;
+                let optval_len: libc::socklen_t = std::mem::size_of_val(&optval) as libc::socklen_t;
+                match unsafe {
+                    libc::setsockopt(
+                        socket.as_raw_fd(),
+                        libc::IPPROTO_TCP,
+                        libc::TCP_USER_TIMEOUT,
+                        &optval as *const _ as *const libc::c_void,
+                        optval_len,
+                    )
+                } {
+                    0 => Ok(()),
+                    _ => Err(io::Error::last_os_error()),
+                }
+            },
+            // Error queues are kept by the inetstack, so they cannot be enabled on kernel sockets.
+            SocketOption::IP_RECVERR(_) => Err(io::Error::from_raw_os_error(libc::ENOTSUP)),
+            // Transmit timestamps are queued by the inetstack, so they cannot be read back from kernel sockets.
+            SocketOption::SO_TIMESTAMPING(_) => Err(io::Error::from_raw_os_error(libc::ENOTSUP)),
+            // Interfaces are numbered by the inetstack, so their indices do not match those of the kernel.
+            SocketOption::SO_BINDTODEVICE(_) => Err(io::Error::from_raw_os_error(libc::ENOTSUP)),
+            // Kernel sockets do not tell when their data is acknowledged, so pushes complete once it is accepted.
+            SocketOption::SO_TXCOMPLETE(false) => Ok(()),
+            SocketOption::SO_TXCOMPLETE(true) => Err(io::Error::from_raw_os_error(libc::ENOTSUP)),
+            SocketOption::SO_DOORBELL(_) => Err(io::Error::from_raw_os_error(libc::ENOPROTOOPT)),
+            SocketOption::TCP_MD5SIG(key) => {
+                linux::set_tcp_md5_key(socket.as_raw_fd(), key.addr(), key.prefix_len(), key.key())
+            },
+            // Authentication keys of kernel sockets are not wired up.
+            SocketOption::TCP_AO_ADD_KEY(_) | SocketOption::TCP_AO_DEL_KEY(_) | SocketOption::TCP_AO_INFO(_) => {
+                Err(io::Error::from_raw_os_error(libc::ENOTSUP))
+            },
+            // Congestion control and retransmission timeouts are tuned in the inetstack, so kernel sockets do not take them.
+            SocketOption::TCP_INIT_CWND(_)
+            | SocketOption::TCP_RTO_INIT_US(_)
+            | SocketOption::TCP_RTO_MIN_US(_)
+            | SocketOption::TCP_RTO_MAX_MS(_) => Err(io::Error::from_raw_os_error(libc::ENOTSUP)),
+        };
+        if let Err(e) = result {
+            let cause: String = format!("failed to set socket option (option={:?}): {:?}", option, e);
+            error!("set_socket_option(): {}", cause);
+            return Err(Fail::new(get_libc_err(e), &cause));
+        }
+        Ok(())
+    }
+
+    /// Receive filters run in the inetstack, so they cannot be attached to kernel sockets.
+    fn set_receive_filter(&mut self, _: &mut Self::SocketDescriptor, _: Option<SocketFilter>) -> Result<(), Fail> {
+        let cause: &str = "receive filters are not supported";
+        error!("set_receive_filter(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, cause))
+    }
+
+    /// Gets the current value of [option] on the underlying socket.
+    fn get_socket_option(
+        &mut self,
+        sd: &mut Self::SocketDescriptor,
+        option: SocketOption,
+    ) -> Result<SocketOption, Fail> {
+        let socket: &mut Socket = self.socket_from_sd(sd);
+        let result: io::Result<SocketOption> = match option {
+            SocketOption::IP_TOS(_) => socket.tos().map(|tos| SocketOption::IP_TOS(tos as u8)),
+            SocketOption::IP_TTL(_) => socket.ttl().map(|ttl| SocketOption::IP_TTL(ttl as u8)),
+            SocketOption::SO_PRIORITY(_) => {
+                let mut optval: libc::c_int = 0;
+                let mut optval_len: libc::socklen_t = std::mem::size_of_val(&optval) as libc::socklen_t;
+                match unsafe {
+                    libc::getsockopt(
+                        socket.as_raw_fd(),
+                        libc::SOL_SOCKET,
+                        libc::SO_PRIORITY,
+                        &mut optval as *mut _ as *mut libc::c_void,
+                        &mut optval_len,
+                    )
+                } {
+                    0 => Ok(SocketOption::SO_PRIORITY(optval as u8)),
+                    _ => Err(io::Error::last_os_error()),
+                }
+            },
+            SocketOption::SO_MAX_PACING_RATE(_) => {
+                let mut optval: u64 = 0;
+                let mut optval_len: libc::socklen_t = std::mem::size_of_val(&optval) as libc::socklen_t;
+                match unsafe {
+                    libc::getsockopt(
+                        socket.as_raw_fd(),
+                        libc::SOL_SOCKET,
+                        libc::SO_MAX_PACING_RATE,
+                        &mut optval as *mut _ as *mut libc::c_void,
+                        &mut optval_len,
+                    )
+                } {
+                    0 => Ok(SocketOption::SO_MAX_PACING_RATE(optval)),
+                    _ => Err(io::Error::last_os_error()),
+                }
+            },
+            SocketOption::IP_RECVERR(_) | SocketOption::SO_TIMESTAMPING(_) | SocketOption::SO_BINDTODEVICE(_) => {
+                Err(io::Error::from_raw_os_error(libc::ENOTSUP))
+            },
+            SocketOption::SO_TXCOMPLETE(_) => Ok(SocketOption::SO_TXCOMPLETE(false)),
+            SocketOption::SO_DOORBELL(_) => Ok(SocketOption::SO_DOORBELL(false)),
+            // Keys cannot be read back, like on Linux.
+            SocketOption::TCP_MD5SIG(_) | SocketOption::TCP_AO_ADD_KEY(_) | SocketOption::TCP_AO_DEL_KEY(_) => {
+                Err(io::Error::from_raw_os_error(libc::ENOPROTOOPT))
+            },
+            SocketOption::TCP_AO_INFO(_) => Err(io::Error::from_raw_os_error(libc::ENOTSUP)),
+            SocketOption::SO_NO_CHECK(_) => {
+                let mut optval: libc::c_int = 0;
+                let mut optval_len: libc::socklen_t = std::mem::size_of_val(&optval) as libc::socklen_t;
+                match unsafe {
+                    libc::getsockopt(
+                        socket.as_raw_fd(),
+                        libc::SOL_SOCKET,
+                        libc::SO_NO_CHECK,
+                        &mut optval as *mut _ as *mut libc::c_void,
+                        &mut optval_len,
+                    )
+                } {
+                    0 => Ok(SocketOption::SO_NO_CHECK(optval != 0)),
+                    _ => Err(io::Error::last_os_error()),
+                }
+            },
+            SocketOption::UDP_SEGMENT(_) => {
+                let mut optval: libc::c_int = 0;
+                let mut optval_len: libc::socklen_t = std::mem::size_of_val(&optval) as libc::socklen_t;
+                match unsafe {
+                    libc::getsockopt(
+                        socket.as_raw_fd(),
+                        libc::SOL_UDP,
+                        libc::UDP_SEGMENT,
+                        &mut optval as *mut _ as *mut libc::c_void,
+                        &mut optval_len,
+                    )
+                } {
+                    0 => Ok(SocketOption::UDP_SEGMENT(optval as u16)),
+                    _ => Err(io::Error::last_os_error()),
+                }
+            },
+            SocketOption::TCP_USER_TIMEOUT(_) => {
+                let mut optval: libc::c_uint = 0;
+                let mut optval_len: libc::socklen_t = std::mem::size_of_val(&optval) as libc::socklen_t;
+                match unsafe {
+                    libc::getsockopt(
+                        socket.as_raw_fd(),
+                        libc::IPPROTO_TCP,
+                        libc::TCP_USER_TIMEOUT,
+                        &mut optval as *mut _ as *mut libc::c_void,
+                        &mut optval_len,
+                    )
+                } {
+                    0 => Ok(SocketOption::TCP_USER_TIMEOUT(optval)),
+                    _ => Err(io::Error::last_os_error()),
+                }
+            },
+            // Congestion control and retransmission timeouts are tuned in the inetstack, so kernel sockets do not take them.
+            SocketOption::TCP_INIT_CWND(_)
+            | SocketOption::TCP_RTO_INIT_US(_)
+            | SocketOption::TCP_RTO_MIN_US(_)
+            | SocketOption::TCP_RTO_MAX_MS(_) => Err(io::Error::from_raw_os_error(libc::ENOTSUP)),
+        };
+        result.map_err(|e| {
+            let cause: String = format!("failed to get socket option (option={:?}): {:?}", option, e);
+            error!("get_socket_option(): {}", cause);
+            Fail::new(get_libc_err(e), &cause)
+        })
     }
 
     /// Close the socket on the underlying transport. Also unregisters the socket with epoll.
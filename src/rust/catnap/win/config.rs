@@ -66,6 +66,11 @@ impl Config {
         Ok(Self::get_bool_option(self.get_libos_section()?, "use_nagle").ok())
     }
 
+    /// Reads the setting to enable or disable Registered I/O. If not set, Registered I/O is disabled.
+    pub fn registered_io(&self) -> Result<bool, Fail> {
+        Ok(Self::get_bool_option(self.get_libos_section()?, "registered_io").unwrap_or(false))
+    }
+
     /// Get the libos subsection, requiring that it exists and is a Hash.
     fn get_libos_section(&self) -> Result<&Yaml, Fail> {
         Self::get_subsection(&self.0, LIBOS)
@@ -0,0 +1,494 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use std::{
+    alloc::{
+        self,
+        Layout,
+    },
+    mem::MaybeUninit,
+    net::SocketAddr,
+    num::NonZeroUsize,
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    ptr::{
+        self,
+        NonNull,
+    },
+};
+
+use ::slab::Slab;
+use windows::{
+    core::PCSTR,
+    Win32::{
+        Foundation::WIN32_ERROR,
+        Networking::WinSock::{
+            AF_INET,
+            AF_INET6,
+            RIORESULT,
+            RIO_BUF,
+            RIO_BUFFERID,
+            RIO_CORRUPT_CQ,
+            RIO_CQ,
+            RIO_EXTENSION_FUNCTION_TABLE,
+            RIO_RQ,
+            SOCKADDR_IN,
+            SOCKADDR_IN6,
+            SOCKADDR_INET,
+        },
+    },
+};
+
+use crate::{
+    catnap::transport::error::expect_last_wsa_error,
+    runtime::{
+        fail::Fail,
+        limits,
+        memory::{
+            BufferPool,
+            DemiBuffer,
+        },
+        SharedConditionVariable,
+        SharedObject,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Size of the completion queue. This bounds the number of outstanding operations across all sockets.
+const COMPLETION_QUEUE_SIZE: u32 = 4096;
+
+/// Maximum number of outstanding receive and send operations on a single socket.
+pub const MAX_OUTSTANDING_OPERATIONS: u32 = 64;
+
+/// Number of completions that are dequeued at once.
+const COMPLETION_BATCH_SIZE: usize = 64;
+
+/// Size of the memory region that backs registered buffers.
+const REGION_SIZE: usize = 32 * 1024 * 1024;
+
+/// Size of registered buffers.
+const BUFFER_SIZE: u16 = limits::RECVBUF_SIZE_MAX as u16;
+
+/// Page size used to pack registered buffers.
+const PAGE_SIZE: usize = 4096;
+
+/// Value returned by RIORegisterBuffer on failure.
+const RIO_INVALID_BUFFERID: RIO_BUFFERID = RIO_BUFFERID(0xFFFFFFFF);
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// A memory region registered with Registered I/O.
+struct RegisteredRegion {
+    base: NonNull<u8>,
+    len: usize,
+    buffer_id: RIO_BUFFERID,
+}
+
+/// An outstanding Registered I/O operation.
+struct Operation {
+    /// Set by the completion processor when the operation completes.
+    result: Option<RIORESULT>,
+    /// Signaled when the operation completes.
+    cv: SharedConditionVariable,
+    /// Buffer that the operation reads from or writes to. This keeps the buffer alive until the operation completes,
+    /// even if the coroutine that started it is cancelled.
+    _buffer: DemiBuffer,
+    /// Set if the coroutine that started the operation is gone, so the completion should be discarded.
+    orphaned: bool,
+}
+
+/// State of Registered I/O (RIO). Data is exchanged through buffers that are registered with the kernel once, and
+/// completions are polled from a completion queue that is shared by all sockets, instead of going through the I/O
+/// completion port.
+pub struct RegisteredIo {
+    /// Registered I/O function table.
+    fns: RIO_EXTENSION_FUNCTION_TABLE,
+    /// Completion queue shared by all sockets.
+    cq: RIO_CQ,
+    /// Region that backs the buffer pool.
+    data: RegisteredRegion,
+    /// Region that holds one socket address per operation, for message-oriented sockets.
+    addresses: RegisteredRegion,
+    /// Pool of registered buffers.
+    pool: BufferPool,
+    /// Outstanding operations, keyed by request context.
+    operations: Slab<Operation>,
+}
+
+#[derive(Clone)]
+pub struct SharedRegisteredIo(SharedObject<RegisteredIo>);
+
+/// Marks an operation as orphaned if the coroutine that waits for it is dropped before it completes.
+struct OperationGuard {
+    rio: SharedRegisteredIo,
+    id: usize,
+    done: bool,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl RegisteredRegion {
+    /// Allocates and registers a memory region of [len] bytes.
+    fn new(fns: &RIO_EXTENSION_FUNCTION_TABLE, len: usize) -> Result<Self, Fail> {
+        let layout: Layout = match Layout::from_size_align(len, PAGE_SIZE) {
+            Ok(layout) => layout,
+            Err(_) => return Err(Fail::new(libc::EINVAL, "invalid layout for registered region")),
+        };
+        // Safety: the layout has a non-zero size.
+        let base: NonNull<u8> = match NonNull::new(unsafe { alloc::alloc_zeroed(layout) }) {
+            Some(base) => base,
+            None => return Err(Fail::new(libc::ENOMEM, "failed to allocate registered region")),
+        };
+
+        let buffer_id: RIO_BUFFERID =
+            unsafe { fns.RIORegisterBuffer.unwrap()(PCSTR::from_raw(base.as_ptr()), len as u32) };
+        if buffer_id == RIO_INVALID_BUFFERID {
+            let e: Fail = expect_last_wsa_error();
+            unsafe { alloc::dealloc(base.as_ptr(), layout) };
+            return Err(e);
+        }
+
+        Ok(Self { base, len, buffer_id })
+    }
+
+    /// Describes the [len] bytes that start at [ptr], or returns None if they do not lie within this region.
+    fn describe(&self, ptr: *const u8, len: usize) -> Option<RIO_BUF> {
+        let start: usize = self.base.as_ptr() as usize;
+        let addr: usize = ptr as usize;
+        if addr < start || addr + len > start + self.len {
+            return None;
+        }
+        Some(RIO_BUF {
+            BufferId: self.buffer_id,
+            Offset: (addr - start) as u32,
+            Length: len as u32,
+        })
+    }
+}
+
+impl SharedRegisteredIo {
+    /// Sets up Registered I/O using the function table [fns].
+    pub fn new(fns: RIO_EXTENSION_FUNCTION_TABLE) -> Result<Self, Fail> {
+        // Completions are polled, so no notifications are requested.
+        let cq: RIO_CQ = unsafe { fns.RIOCreateCompletionQueue.unwrap()(COMPLETION_QUEUE_SIZE, ptr::null()) };
+        if cq.0 == 0 {
+            return Err(expect_last_wsa_error());
+        }
+
+        let data: RegisteredRegion = RegisteredRegion::new(&fns, REGION_SIZE)?;
+        let addresses: RegisteredRegion = RegisteredRegion::new(
+            &fns,
+            COMPLETION_QUEUE_SIZE as usize * std::mem::size_of::<SOCKADDR_INET>(),
+        )?;
+
+        let pool: BufferPool = match BufferPool::new(BUFFER_SIZE) {
+            Ok(pool) => pool,
+            Err(_) => return Err(Fail::new(libc::EINVAL, "invalid layout for registered buffers")),
+        };
+        // Safety: the registered region is never freed, thus it outlives the pool.
+        unsafe {
+            pool.pool().populate(
+                NonNull::from(std::slice::from_raw_parts_mut(
+                    data.base.as_ptr() as *mut MaybeUninit<u8>,
+                    data.len,
+                )),
+                NonZeroUsize::new(PAGE_SIZE).unwrap(),
+            )
+        }?;
+
+        Ok(Self(SharedObject::new(RegisteredIo {
+            fns,
+            cq,
+            data,
+            addresses,
+            pool,
+            operations: Slab::with_capacity(COMPLETION_QUEUE_SIZE as usize),
+        })))
+    }
+
+    /// Returns the completion queue shared by all sockets.
+    pub fn completion_queue(&self) -> RIO_CQ {
+        self.cq
+    }
+
+    /// Allocates a registered buffer of [size] bytes. Returns None if [size] does not fit in a registered buffer or if
+    /// the pool ran out of buffers.
+    pub fn alloc(&self, size: usize) -> Option<DemiBuffer> {
+        if size > BUFFER_SIZE as usize {
+            return None;
+        }
+        let mut buf: DemiBuffer = DemiBuffer::new_in_pool(&self.pool)?;
+        if let Err(e) = buf.trim(buf.len() - size) {
+            warn!("alloc(): failed to trim registered buffer: {:?}", e);
+            return None;
+        }
+        Some(buf)
+    }
+
+    /// Returns a registered buffer that holds the leading bytes of [buf]. If [buf] was allocated from registered
+    /// memory, it is used as is. Otherwise, up to a full registered buffer is copied out of it.
+    pub fn registered(&self, buf: &DemiBuffer) -> Result<DemiBuffer, Fail> {
+        if self.data.describe(buf.as_ptr(), buf.len()).is_some() {
+            return Ok(buf.clone());
+        }
+
+        let len: usize = buf.len().min(BUFFER_SIZE as usize);
+        match self.alloc(len) {
+            Some(mut copy) => {
+                copy.copy_from_slice(&buf[..len]);
+                Ok(copy)
+            },
+            None => Err(Fail::new(libc::ENOBUFS, "out of registered buffers")),
+        }
+    }
+
+    /// Starts receiving into [buf] through the request queue [rq]. If [with_address] is set, the remote address is
+    /// captured as well. Returns an identifier for the operation.
+    pub fn start_receive(&mut self, rq: RIO_RQ, buf: &DemiBuffer, with_address: bool) -> Result<usize, Fail> {
+        let data: RIO_BUF = self.describe(buf)?;
+        let id: usize = self.insert(buf)?;
+        let fns: RIO_EXTENSION_FUNCTION_TABLE = self.fns;
+        let context: *const libc::c_void = id as *const libc::c_void;
+        let success: bool = if with_address {
+            let address: RIO_BUF = self.address(id);
+            unsafe {
+                fns.RIOReceiveEx.unwrap()(
+                    rq,
+                    &data,
+                    1,
+                    ptr::null(),
+                    &address,
+                    ptr::null(),
+                    ptr::null(),
+                    0,
+                    context,
+                ) != 0
+            }
+        } else {
+            unsafe { fns.RIOReceive.unwrap()(rq, &data, 1, 0, context).as_bool() }
+        };
+        self.started(id, success)
+    }
+
+    /// Starts sending [buf] through the request queue [rq], to [addr] if given. Returns an identifier for the
+    /// operation.
+    pub fn start_send(&mut self, rq: RIO_RQ, buf: &DemiBuffer, addr: Option<SocketAddr>) -> Result<usize, Fail> {
+        let data: RIO_BUF = self.describe(buf)?;
+        let id: usize = self.insert(buf)?;
+        let fns: RIO_EXTENSION_FUNCTION_TABLE = self.fns;
+        let context: *const libc::c_void = id as *const libc::c_void;
+        let success: bool = match addr {
+            Some(addr) => {
+                let address: RIO_BUF = self.address(id);
+                let sockaddr: SOCKADDR_INET = match addr {
+                    SocketAddr::V4(addr) => addr.into(),
+                    SocketAddr::V6(addr) => addr.into(),
+                };
+                // Safety: the slot belongs to this operation and is large enough to hold a SOCKADDR_INET.
+                unsafe {
+                    ptr::write(self.address_slot(id), sockaddr);
+                    fns.RIOSendEx.unwrap()(
+                        rq,
+                        &data,
+                        1,
+                        ptr::null(),
+                        &address,
+                        ptr::null(),
+                        ptr::null(),
+                        0,
+                        context,
+                    )
+                    .as_bool()
+                }
+            },
+            None => unsafe { fns.RIOSend.unwrap()(rq, &data, 1, 0, context).as_bool() },
+        };
+        self.started(id, success)
+    }
+
+    /// Waits for the operation [id] to complete. Returns the number of bytes transferred and, for receives that
+    /// captured it, the remote address.
+    pub async fn wait(&mut self, id: usize) -> Result<(usize, Option<SocketAddr>), Fail> {
+        let mut guard: OperationGuard = OperationGuard {
+            rio: self.clone(),
+            id,
+            done: false,
+        };
+        let result: RIORESULT = loop {
+            let operation: &mut Operation = &mut self.operations[id];
+            match operation.result.take() {
+                Some(result) => break result,
+                None => {
+                    let mut cv: SharedConditionVariable = operation.cv.clone();
+                    cv.wait().await;
+                },
+            }
+        };
+        guard.done = true;
+
+        let addr: Option<SocketAddr> = self.read_address(id);
+        self.operations.remove(id);
+
+        if result.Status != 0 {
+            return Err(Fail::from(WIN32_ERROR(result.Status as u32)));
+        }
+        Ok((result.BytesTransferred as usize, addr))
+    }
+
+    /// Dequeues completions and wakes the coroutines that wait for them.
+    pub fn process_completions(&mut self) -> Result<(), Fail> {
+        let mut results: [RIORESULT; COMPLETION_BATCH_SIZE] = [RIORESULT::default(); COMPLETION_BATCH_SIZE];
+        loop {
+            let n: u32 = unsafe {
+                self.fns.RIODequeueCompletion.unwrap()(self.cq, results.as_mut_ptr(), COMPLETION_BATCH_SIZE as u32)
+            };
+            if n == RIO_CORRUPT_CQ {
+                return Err(Fail::new(libc::EFAULT, "completion queue is corrupt"));
+            }
+
+            for result in &results[..n as usize] {
+                let id: usize = result.RequestContext as usize;
+                match self.operations.get_mut(id) {
+                    Some(operation) if operation.orphaned => {
+                        trace!("dropping completion of orphaned operation (id={:?})", id);
+                        self.operations.remove(id);
+                    },
+                    Some(operation) => {
+                        operation.result = Some(*result);
+                        operation.cv.signal();
+                    },
+                    None => warn!("completion for unknown operation (id={:?})", id),
+                }
+            }
+
+            if (n as usize) < COMPLETION_BATCH_SIZE {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Describes a registered buffer.
+    fn describe(&self, buf: &DemiBuffer) -> Result<RIO_BUF, Fail> {
+        match self.data.describe(buf.as_ptr(), buf.len()) {
+            Some(data) => Ok(data),
+            None => Err(Fail::new(libc::EINVAL, "buffer is not registered")),
+        }
+    }
+
+    /// Registers a new operation on [buf].
+    fn insert(&mut self, buf: &DemiBuffer) -> Result<usize, Fail> {
+        // Each operation owns an address slot, thus the number of operations is capped.
+        if self.operations.len() >= COMPLETION_QUEUE_SIZE as usize {
+            return Err(Fail::new(libc::ENOBUFS, "too many outstanding operations"));
+        }
+        Ok(self.operations.insert(Operation {
+            result: None,
+            cv: SharedConditionVariable::default(),
+            _buffer: buf.clone(),
+            orphaned: false,
+        }))
+    }
+
+    /// Checks whether operation [id] was started, and forgets it otherwise.
+    fn started(&mut self, id: usize, success: bool) -> Result<usize, Fail> {
+        if success {
+            Ok(id)
+        } else {
+            self.operations.remove(id);
+            Err(expect_last_wsa_error())
+        }
+    }
+
+    /// Returns a pointer to the address slot of operation [id].
+    fn address_slot(&self, id: usize) -> *mut SOCKADDR_INET {
+        // Safety: operations are capped, so the slot lies within the region.
+        unsafe { (self.addresses.base.as_ptr() as *mut SOCKADDR_INET).add(id) }
+    }
+
+    /// Describes the address slot of operation [id].
+    fn address(&self, id: usize) -> RIO_BUF {
+        RIO_BUF {
+            BufferId: self.addresses.buffer_id,
+            Offset: (id * std::mem::size_of::<SOCKADDR_INET>()) as u32,
+            Length: std::mem::size_of::<SOCKADDR_INET>() as u32,
+        }
+    }
+
+    /// Reads and clears the address slot of operation [id]. Returns None if the slot holds no address.
+    fn read_address(&self, id: usize) -> Option<SocketAddr> {
+        // Safety: the slot belongs to this operation, and the operation completed.
+        let sockaddr: SOCKADDR_INET = unsafe { ptr::replace(self.address_slot(id), SOCKADDR_INET::default()) };
+        let len: i32 = match unsafe { sockaddr.si_family } {
+            AF_INET => std::mem::size_of::<SOCKADDR_IN>(),
+            AF_INET6 => std::mem::size_of::<SOCKADDR_IN6>(),
+            _ => return None,
+        } as i32;
+
+        // NB socket2 uses the windows-sys crate, so type names are qualified here to prevent confusion with Windows
+        // crate.
+        let mut storage: windows_sys::Win32::Networking::WinSock::SOCKADDR_STORAGE = unsafe { std::mem::zeroed() };
+        // Safety: SOCKADDR_STORAGE is large enough to hold any socket address.
+        unsafe {
+            ptr::copy_nonoverlapping(
+                &sockaddr as *const SOCKADDR_INET as *const u8,
+                &mut storage as *mut _ as *mut u8,
+                std::mem::size_of::<SOCKADDR_INET>(),
+            );
+            socket2::SockAddr::new(storage, len)
+        }
+        .as_socket()
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Deref for SharedRegisteredIo {
+    type Target = RegisteredIo;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl DerefMut for SharedRegisteredIo {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        if !self.done {
+            if let Some(operation) = self.rio.operations.get_mut(self.id) {
+                operation.orphaned = true;
+            }
+        }
+    }
+}
+
+/// Closes the completion queue and deregisters memory. Memory itself is never freed, as buffers that were handed out
+/// to the application may outlive Registered I/O.
+impl Drop for RegisteredIo {
+    fn drop(&mut self) {
+        unsafe {
+            self.fns.RIOCloseCompletionQueue.unwrap()(self.cq);
+            self.fns.RIODeregisterBuffer.unwrap()(self.data.buffer_id);
+            self.fns.RIODeregisterBuffer.unwrap()(self.addresses.buffer_id);
+        }
+    }
+}
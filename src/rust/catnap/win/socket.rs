@@ -42,7 +42,10 @@ use windows::{
             FROM_PROTOCOL_INFO,
             INVALID_SOCKET,
             IPPROTO_TCP,
+            IPPROTO_UDP,
             LINGER,
+            RIO_CQ,
+            RIO_RQ,
             SD_BOTH,
             SIO_KEEPALIVE_VALS,
             SOCKADDR,
@@ -63,6 +66,7 @@ use windows::{
             WSAEINVAL,
             WSAPROTOCOL_INFOW,
             WSA_FLAG_OVERLAPPED,
+            WSA_FLAG_REGISTERED_IO,
         },
         System::IO::{
             CancelIoEx,
@@ -81,6 +85,7 @@ use crate::{
             IoCompletionPort,
             OverlappedResult,
         },
+        rio::MAX_OUTSTANDING_OPERATIONS,
         winsock::{
             SocketExtensions,
             WinsockRuntime,
@@ -111,6 +116,12 @@ const ACCEPT_BUFFER_LEN: usize = (SOCKADDR_BUF_SIZE) * 2;
 pub struct Socket {
     s: SOCKET,
     extensions: Rc<SocketExtensions>,
+    /// Whether the socket is message-oriented.
+    datagram: bool,
+    /// Whether the socket supports Registered I/O.
+    registered_io: bool,
+    /// Registered I/O request queue, created on first use.
+    rq: Option<RIO_RQ>,
 }
 
 /// State type used by `Socket::start_accept` and `Socket::finish_accept`.
@@ -170,7 +181,13 @@ impl Socket {
         extensions: Rc<SocketExtensions>,
         iocp: &IoCompletionPort<SocketOpState>,
     ) -> Result<Socket, Fail> {
-        let s: Socket = Socket { s, extensions };
+        let s: Socket = Socket {
+            s,
+            extensions,
+            datagram: protocol == IPPROTO_UDP.0,
+            registered_io: config.registered_io,
+            rq: None,
+        };
         s.setup_socket(protocol, config)?;
         iocp.associate_socket(s.s, 0)?;
         Ok(s)
@@ -237,6 +254,11 @@ impl Socket {
             unsafe { WinsockRuntime::do_getsockopt(template.s, SOL_SOCKET, SO_PROTOCOL_INFOW) }?;
 
         let extensions: Rc<SocketExtensions> = template.extensions.clone();
+        let flags: u32 = if template.registered_io {
+            WSA_FLAG_OVERLAPPED | WSA_FLAG_REGISTERED_IO
+        } else {
+            WSA_FLAG_OVERLAPPED
+        };
 
         // Safety: SOCKET handle is transferred to a Socket instance, which will safely close the handle on drop.
         let s: SOCKET = unsafe {
@@ -245,11 +267,53 @@ impl Socket {
                 FROM_PROTOCOL_INFO,
                 FROM_PROTOCOL_INFO,
                 Some(&protocol),
-                WSA_FLAG_OVERLAPPED,
+                flags,
             )
         }?;
 
-        Ok(Socket { s, extensions })
+        Ok(Socket {
+            s,
+            extensions,
+            datagram: template.datagram,
+            registered_io: template.registered_io,
+            rq: None,
+        })
+    }
+
+    /// Checks whether the socket is message-oriented.
+    pub fn is_datagram(&self) -> bool {
+        self.datagram
+    }
+
+    /// Returns the Registered I/O request queue of the socket, creating it on first use. Completions for all operations
+    /// of the queue are posted to [cq].
+    pub fn request_queue(&mut self, cq: RIO_CQ) -> Result<RIO_RQ, Fail> {
+        if let Some(rq) = self.rq {
+            return Ok(rq);
+        }
+
+        if !self.registered_io {
+            return Err(Fail::new(libc::ENOTSUP, "socket does not support registered I/O"));
+        }
+
+        let rq: RIO_RQ = unsafe {
+            self.extensions.rio_fns.RIOCreateRequestQueue.unwrap()(
+                self.s,
+                MAX_OUTSTANDING_OPERATIONS,
+                1,
+                MAX_OUTSTANDING_OPERATIONS,
+                1,
+                cq,
+                cq,
+                std::ptr::null(),
+            )
+        };
+        if rq.0 == 0 {
+            return Err(expect_last_wsa_error());
+        }
+
+        self.rq = Some(rq);
+        Ok(rq)
     }
 
     /// Begin disconnecting a connection-oriented socket. If called on a non-stream-based socket or an unconnected
@@ -4,6 +4,7 @@
 mod config;
 mod error;
 mod overlapped;
+mod rio;
 mod socket;
 mod winsock;
 
@@ -24,6 +25,8 @@ use windows::Win32::{
         IPPROTO,
         IPPROTO_TCP,
         IPPROTO_UDP,
+        RIO_EXTENSION_FUNCTION_TABLE,
+        RIO_RQ,
     },
     System::IO::OVERLAPPED,
 };
@@ -34,6 +37,7 @@ use crate::{
             IoCompletionPort,
             OverlappedResult,
         },
+        rio::SharedRegisteredIo,
         socket::{
             AcceptState,
             PopState,
@@ -50,8 +54,16 @@ use crate::{
             DemiBuffer,
             MemoryRuntime,
         },
-        network::transport::NetworkTransport,
+        network::{
+            ancillary::AncillaryData,
+            socket::{
+                filter::SocketFilter,
+                option::SocketOption,
+            },
+            transport::NetworkTransport,
+        },
         poll_yield,
+        types::demi_sgarray_t,
         DemiRuntime,
         SharedDemiRuntime,
         SharedObject,
@@ -73,6 +85,9 @@ pub(super) struct WinConfig {
 
     /// Whether Nagle's algorithm is enabled or disabled.
     nagle: Option<bool>,
+
+    /// Whether data is transferred through Registered I/O.
+    registered_io: bool,
 }
 
 /// Underlying network transport.
@@ -83,6 +98,10 @@ pub struct CatnapTransport {
     /// I/O completion port for overlapped I/O.
     iocp: IoCompletionPort<SocketOpState>,
 
+    /// Registered I/O state, if enabled. Pops and pushes go through Registered I/O, while the remaining operations
+    /// still go through the I/O completion port.
+    rio: Option<SharedRegisteredIo>,
+
     /// Configuration values.
     config: WinConfig,
 
@@ -105,11 +124,24 @@ impl SharedCatnapTransport {
             keepalive_params: expect_ok!(config.tcp_keepalive(), "failed to load TCP settings"),
             linger_time: expect_ok!(config.linger_time(), "failed to load linger settings"),
             nagle: expect_ok!(config.nagle(), "failed to load nagle's algorithm settings"),
+            registered_io: expect_ok!(config.registered_io(), "failed to load registered I/O settings"),
+        };
+
+        let mut winsock: WinsockRuntime = expect_ok!(WinsockRuntime::new(), "failed to initialize WinSock");
+        let rio: Option<SharedRegisteredIo> = if config.registered_io {
+            let fns: RIO_EXTENSION_FUNCTION_TABLE = expect_ok!(winsock.rio_fns(), "failed to resolve registered I/O");
+            Some(expect_ok!(
+                SharedRegisteredIo::new(fns),
+                "failed to setup registered I/O"
+            ))
+        } else {
+            None
         };
 
         let me: Self = Self(SharedObject::new(CatnapTransport {
-            winsock: expect_ok!(WinsockRuntime::new(), "failed to initialize WinSock"),
+            winsock,
             iocp: expect_ok!(IoCompletionPort::new(), "failed to setup I/O completion port"),
+            rio,
             config,
             runtime: runtime.clone(),
         }));
@@ -128,6 +160,56 @@ impl SharedCatnapTransport {
         me
     }
 
+    /// Pop data from the socket through Registered I/O. Data is received into a registered buffer, which is handed
+    /// over to the caller.
+    async fn rio_pop(
+        rio: &mut SharedRegisteredIo,
+        socket: &mut Socket,
+        size: usize,
+    ) -> Result<(Option<SocketAddr>, DemiBuffer), Fail> {
+        let rq: RIO_RQ = socket.request_queue(rio.completion_queue())?;
+        let mut buf: DemiBuffer = match rio.alloc(size) {
+            Some(buf) => buf,
+            None => return Err(Fail::new(libc::ENOBUFS, "out of registered buffers")),
+        };
+
+        let id: usize = rio.start_receive(rq, &buf, socket.is_datagram())?;
+        let (nbytes, sockaddr): (usize, Option<SocketAddr>) = rio.wait(id).await?;
+        buf.trim(buf.len() - nbytes)?;
+        if nbytes > 0 {
+            trace!("data received ({:?}/{:?} bytes)", nbytes, size);
+        } else {
+            trace!("not data received");
+        }
+        Ok((sockaddr, buf))
+    }
+
+    /// Push `buf` to the remote endpoint through Registered I/O. Buffers that were not allocated from registered memory
+    /// are copied into registered buffers first.
+    async fn rio_push(
+        rio: &mut SharedRegisteredIo,
+        socket: &mut Socket,
+        buf: &mut DemiBuffer,
+        addr: Option<SocketAddr>,
+    ) -> Result<(), Fail> {
+        let rq: RIO_RQ = socket.request_queue(rio.completion_queue())?;
+        // Message-oriented sockets must send the whole message at once, while connection-oriented sockets ignore the
+        // address.
+        let addr: Option<SocketAddr> = if socket.is_datagram() { addr } else { None };
+        while !buf.is_empty() {
+            let registered: DemiBuffer = rio.registered(buf)?;
+            if socket.is_datagram() && registered.len() < buf.len() {
+                return Err(Fail::new(libc::EMSGSIZE, "message does not fit in a registered buffer"));
+            }
+
+            let id: usize = rio.start_send(rq, &registered, addr)?;
+            let (nbytes, _): (usize, Option<SocketAddr>) = rio.wait(id).await?;
+            trace!("data pushed ({:?}/{:?} bytes)", nbytes, buf.len());
+            buf.adjust(nbytes)?;
+        }
+        Ok(())
+    }
+
     /// Run a coroutine which pulls the I/O completion port for events.
     async fn run_event_processor(&mut self) {
         loop {
@@ -135,6 +217,12 @@ impl SharedCatnapTransport {
                 error!("Completion port error: {}", err);
             }
 
+            if let Some(rio) = self.0.rio.as_mut() {
+                if let Err(err) = rio.process_completions() {
+                    error!("Registered I/O completion queue error: {}", err);
+                }
+            }
+
             poll_yield().await;
         }
     }
@@ -148,7 +236,7 @@ impl NetworkTransport for SharedCatnapTransport {
     type SocketDescriptor = Socket;
 
     /// Create a new socket for the specified domain and type.
-    fn socket(&mut self, domain: socket2::Domain, typ: socket2::Type) -> Result<Socket, Fail> {
+    fn socket(&mut self, domain: socket2::Domain, typ: socket2::Type, _: socket2::Protocol) -> Result<Socket, Fail> {
         // Select protocol.
         let protocol: IPPROTO = match typ {
             socket2::Type::STREAM => IPPROTO_TCP,
@@ -169,6 +257,31 @@ impl NetworkTransport for SharedCatnapTransport {
         Ok(s)
     }
 
+    /// Socket options are not supported on Windows yet.
+    fn set_socket_option(&mut self, _: &mut Self::SocketDescriptor, option: SocketOption) -> Result<(), Fail> {
+        let cause: String = format!("socket option not supported (option={:?})", option);
+        error!("set_socket_option(): {}", &cause);
+        Err(Fail::new(ENOTSUP, &cause))
+    }
+
+    /// Receive filters run in the inetstack, so they cannot be attached to Windows sockets.
+    fn set_receive_filter(&mut self, _: &mut Self::SocketDescriptor, _: Option<SocketFilter>) -> Result<(), Fail> {
+        let cause: &str = "receive filters are not supported";
+        error!("set_receive_filter(): {}", cause);
+        Err(Fail::new(ENOTSUP, cause))
+    }
+
+    /// Socket options are not supported on Windows yet.
+    fn get_socket_option(
+        &mut self,
+        _: &mut Self::SocketDescriptor,
+        option: SocketOption,
+    ) -> Result<SocketOption, Fail> {
+        let cause: String = format!("socket option not supported (option={:?})", option);
+        error!("get_socket_option(): {}", &cause);
+        Err(Fail::new(ENOTSUP, &cause))
+    }
+
     /// Synchronously shut down the specified socket.
     fn hard_close(&mut self, socket: &mut Self::SocketDescriptor) -> Result<(), Fail> {
         socket.shutdown()
@@ -245,7 +358,12 @@ impl NetworkTransport for SharedCatnapTransport {
         &mut self,
         socket: &mut Self::SocketDescriptor,
         size: usize,
-    ) -> Result<(Option<SocketAddr>, DemiBuffer), Fail> {
+    ) -> Result<(Option<SocketAddr>, DemiBuffer, AncillaryData), Fail> {
+        if let Some(mut rio) = self.0.rio.clone() {
+            let (addr, buf) = Self::rio_pop(&mut rio, socket, size).await?;
+            return Ok((addr, buf, AncillaryData::default()));
+        }
+
         let mut buf: DemiBuffer = DemiBuffer::new(size as u16);
         unsafe {
             self.0.iocp.do_io(
@@ -266,7 +384,7 @@ impl NetworkTransport for SharedCatnapTransport {
             } else {
                 trace!("not data received");
             }
-            Ok((sockaddr, buf))
+            Ok((sockaddr, buf, AncillaryData::default()))
         })
     }
 
@@ -279,6 +397,10 @@ impl NetworkTransport for SharedCatnapTransport {
         buf: &mut DemiBuffer,
         addr: Option<SocketAddr>,
     ) -> Result<(), Fail> {
+        if let Some(mut rio) = self.0.rio.clone() {
+            return Self::rio_push(&mut rio, socket, buf, addr).await;
+        }
+
         loop {
             let result: Result<usize, Fail> = unsafe {
                 self.0.iocp.do_io(
@@ -317,4 +439,26 @@ impl NetworkTransport for SharedCatnapTransport {
     }
 }
 
-impl MemoryRuntime for SharedCatnapTransport {}
+impl MemoryRuntime for SharedCatnapTransport {
+    /// Allocates a scatter-gather array. If Registered I/O is enabled, the array is allocated from registered memory
+    /// whenever it fits, so that it can be pushed without copies.
+    fn sgaalloc(&self, size: usize) -> Result<demi_sgarray_t, Fail> {
+        // We can't allocate a zero-sized buffer.
+        if size == 0 {
+            let cause: &str = "cannot allocate a zero-sized buffer";
+            error!("sgaalloc(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, cause));
+        }
+
+        // We can't allocate more than a single buffer.
+        if size > u16::MAX as usize {
+            return Err(Fail::new(libc::EINVAL, "size too large for a single demi_sgaseg_t"));
+        }
+
+        let buf: DemiBuffer = match self.0.rio.as_ref().and_then(|rio: &SharedRegisteredIo| rio.alloc(size)) {
+            Some(buf) => buf,
+            None => DemiBuffer::new(size as u16),
+        };
+        self.into_sgarray(buf)
+    }
+}
@@ -39,7 +39,9 @@ use windows::{
         WSAIoctl,
         WSASocketW,
         WSAStartup,
+        AF_INET,
         INVALID_SOCKET,
+        IPPROTO_UDP,
         LPFN_ACCEPTEX,
         LPFN_CONNECTEX,
         LPFN_DISCONNECTEX,
@@ -48,6 +50,7 @@ use windows::{
         SIO_GET_EXTENSION_FUNCTION_POINTER,
         SIO_GET_MULTIPLE_EXTENSION_FUNCTION_POINTER,
         SOCKET,
+        SOCK_DGRAM,
         SOL_SOCKET,
         SO_PROTOCOL_INFOW,
         WSADATA,
@@ -57,6 +60,7 @@ use windows::{
         WSAID_GETACCEPTEXSOCKADDRS,
         WSAPROTOCOL_INFOW,
         WSA_FLAG_OVERLAPPED,
+        WSA_FLAG_REGISTERED_IO,
     },
 };
 
@@ -88,8 +92,6 @@ pub(super) struct SocketExtensions {
     pub disconnectex: LPFN_DISCONNECTEX,
 
     /// Registered I/O function table.
-    // Note: RIO extensions are preserved for future use.
-    #[allow(unused)]
     pub rio_fns: RIO_EXTENSION_FUNCTION_TABLE,
 }
 
@@ -326,6 +328,26 @@ impl WinsockRuntime {
         }
     }
 
+    /// Resolve the Registered I/O function table. Registered I/O is set up before any socket exists, thus the table is
+    /// resolved through a throwaway socket.
+    pub fn rio_fns(&mut self) -> Result<RIO_EXTENSION_FUNCTION_TABLE, Fail> {
+        // Safety: the socket is closed before returning.
+        let s: SOCKET = unsafe {
+            Self::raw_socket(
+                AF_INET.0 as libc::c_int,
+                SOCK_DGRAM.0,
+                IPPROTO_UDP.0,
+                None,
+                WSA_FLAG_OVERLAPPED | WSA_FLAG_REGISTERED_IO,
+            )
+        }?;
+        let result: Result<RIO_EXTENSION_FUNCTION_TABLE, Fail> = self
+            .get_or_init_extensions(s)
+            .map(|extensions: Rc<SocketExtensions>| extensions.rio_fns);
+        unsafe { closesocket(s) };
+        result
+    }
+
     /// Create a new socket.
     pub fn socket(
         &mut self,
@@ -337,7 +359,12 @@ impl WinsockRuntime {
     ) -> Result<Socket, Fail> {
         // Safety: SOCKET is a loose handle; it must be closed with `closesocket` to clean up resources. Socket struct
         // will take ownership by end of method; failures after this call need to be cause a `closesocket` call.
-        let s: SOCKET = unsafe { Self::raw_socket(domain, typ, protocol, None, WSA_FLAG_OVERLAPPED) }?;
+        let flags: u32 = if config.registered_io {
+            WSA_FLAG_OVERLAPPED | WSA_FLAG_REGISTERED_IO
+        } else {
+            WSA_FLAG_OVERLAPPED
+        };
+        let s: SOCKET = unsafe { Self::raw_socket(domain, typ, protocol, None, flags) }?;
 
         self.get_or_init_extensions(s)
             .and_then(|extensions: Rc<SocketExtensions>| Socket::new(s, protocol, config, extensions, iocp))
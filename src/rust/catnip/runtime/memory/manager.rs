@@ -6,7 +6,10 @@
 //======================================================================================================================
 
 use crate::{
-    catnip::runtime::memory::mempool::MemoryPool,
+    catnip::runtime::{
+        memory::mempool::MemoryPool,
+        numa::NumaPlacer,
+    },
     inetstack::protocols::{
         ethernet2::ETHERNET2_HEADER_SIZE,
         ipv4::IPV4_HEADER_MAX_SIZE,
@@ -67,27 +70,37 @@ pub struct MemoryManager {
 
 /// Associated Functions for Memory Managers
 impl MemoryManager {
-    /// Instantiates a memory manager.
-    pub fn new(max_body_size: usize) -> Result<Self, Error> {
+    /// Instantiates a memory manager for the instance that runs on queue [queue_id]. Memory pools are named after the
+    /// queue, as instances that run in different processes share the same namespace. They are placed on a NUMA node by
+    /// [placer].
+    pub fn new(max_body_size: usize, queue_id: u16, placer: &mut NumaPlacer) -> Result<Self, Error> {
         let config: MemoryConfig = MemoryConfig::new(None, None, Some(max_body_size), None, None);
         let header_size: usize = ETHERNET2_HEADER_SIZE + (IPV4_HEADER_MAX_SIZE as usize) + MAX_TCP_HEADER_SIZE;
         let header_mbuf_size: usize = header_size + config.get_inline_body_size();
 
         // Create memory pool for holding packet headers.
-        let header_pool: MemoryPool = MemoryPool::new(
-            CString::new("header_pool")?,
-            header_mbuf_size,
-            config.get_header_pool_size(),
-            config.get_cache_size(),
-        )?;
+        let header_pool_name: CString = CString::new(format!("header_pool_{}", queue_id))?;
+        let header_pool: MemoryPool = placer.place("header pool", |socket_id| {
+            Ok(MemoryPool::new(
+                header_pool_name.clone(),
+                header_mbuf_size,
+                config.get_header_pool_size(),
+                config.get_cache_size(),
+                socket_id,
+            )?)
+        })?;
 
         // Create memory pool for holding packet bodies.
-        let body_pool: MemoryPool = MemoryPool::new(
-            CString::new("body_pool")?,
-            config.get_max_body_size(),
-            config.get_body_pool_size(),
-            config.get_cache_size(),
-        )?;
+        let body_pool_name: CString = CString::new(format!("body_pool_{}", queue_id))?;
+        let body_pool: MemoryPool = placer.place("body pool", |socket_id| {
+            Ok(MemoryPool::new(
+                body_pool_name.clone(),
+                config.get_max_body_size(),
+                config.get_body_pool_size(),
+                config.get_cache_size(),
+                socket_id,
+            )?)
+        })?;
 
         Ok(Self {
             config,
@@ -111,6 +124,7 @@ impl MemoryManager {
             sga_numsegs: 1,
             sga_segs: [sga_seg],
             sga_addr: unsafe { mem::zeroed() },
+            sga_ancillary: unsafe { mem::zeroed() },
         })
     }
 
@@ -168,6 +182,7 @@ impl MemoryManager {
             sga_numsegs: 1,
             sga_segs: [sga_seg],
             sga_addr: unsafe { mem::zeroed() },
+            sga_ancillary: unsafe { mem::zeroed() },
         })
     }
 
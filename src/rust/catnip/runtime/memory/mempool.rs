@@ -13,7 +13,6 @@ use crate::runtime::{
         rte_pktmbuf_alloc,
         rte_pktmbuf_free,
         rte_pktmbuf_pool_create,
-        rte_socket_id,
     },
 };
 use ::std::ffi::CString;
@@ -35,8 +34,14 @@ pub struct MemoryPool {
 
 /// Associated functions for memory pool.
 impl MemoryPool {
-    /// Creates a new memory pool.
-    pub fn new(name: CString, data_room_size: usize, pool_size: usize, cache_size: usize) -> Result<Self, Fail> {
+    /// Creates a new memory pool on the NUMA node [socket_id].
+    pub fn new(
+        name: CString,
+        data_room_size: usize,
+        pool_size: usize,
+        cache_size: usize,
+        socket_id: i32,
+    ) -> Result<Self, Fail> {
         let pool: *mut rte_mempool = unsafe {
             rte_pktmbuf_pool_create(
                 name.as_ptr(),
@@ -44,7 +49,7 @@ impl MemoryPool {
                 cache_size as u32,
                 0,
                 data_room_size as u16,
-                rte_socket_id() as i32,
+                socket_id,
             )
         };
 
@@ -2,59 +2,112 @@
 // Licensed under the MIT license.
 
 pub mod memory;
+pub mod numa;
+pub mod tls;
 
 //==============================================================================
 // Imports
 //==============================================================================
 
-use self::memory::{
-    consts::DEFAULT_MAX_BODY_SIZE,
-    MemoryManager,
+use self::{
+    memory::{
+        consts::DEFAULT_MAX_BODY_SIZE,
+        MemoryManager,
+    },
+    numa::NumaPlacer,
+    tls::SharedDPDKTlsOffload,
 };
 use crate::{
     demikernel::config::Config,
     expect_some,
-    inetstack::protocols::ethernet2::MIN_PAYLOAD_SIZE,
+    inetstack::protocols::{
+        ethernet2::{
+            EtherType2,
+            Ethernet2Header,
+            VlanTag,
+            VlanTaggedPacket,
+            ETHERNET2_HEADER_SIZE,
+            MIN_PAYLOAD_SIZE,
+            VLAN_TAG_SIZE,
+            VLAN_TPID,
+        },
+        ip::IpProtocol,
+        ipv4::{
+            Ipv4Header,
+            IPV4_HEADER_MIN_SIZE,
+        },
+        tcp::segment::TcpHeader,
+    },
     runtime::{
         fail::Fail,
         libdpdk::{
             rte_delay_us_block,
+            rte_eal_cleanup,
             rte_eal_init,
+            rte_eal_process_type,
             rte_eth_conf,
+            rte_eth_dev_close,
             rte_eth_dev_configure,
             rte_eth_dev_count_avail,
             rte_eth_dev_get_mtu,
+            rte_eth_dev_info,
             rte_eth_dev_info_get,
             rte_eth_dev_is_valid_port,
+            rte_eth_dev_rss_reta_update,
             rte_eth_dev_set_mtu,
             rte_eth_dev_start,
+            rte_eth_dev_stop,
             rte_eth_find_next_owned_by,
             rte_eth_link,
             rte_eth_link_get_nowait,
             rte_eth_macaddr_get,
             rte_eth_promiscuous_enable,
             rte_eth_rss_ip,
+            rte_eth_rss_reta_entry64,
             rte_eth_rx_burst,
             rte_eth_rx_mq_mode_RTE_ETH_MQ_RX_RSS as RTE_ETH_MQ_RX_RSS,
             rte_eth_rx_offload_tcp_cksum,
-            rte_eth_rx_offload_udp_cksum,
+            rte_eth_rx_offload_timestamp,
+            rte_eth_rx_offload_vlan_strip,
             rte_eth_rx_queue_setup,
             rte_eth_rxconf,
+            rte_eth_timesync_enable,
+            rte_eth_timesync_read_tx_timestamp,
             rte_eth_tx_burst,
+            rte_eth_tx_done_cleanup,
             rte_eth_tx_mq_mode_RTE_ETH_MQ_TX_NONE as RTE_ETH_MQ_TX_NONE,
+            rte_eth_tx_offload_ipv4_cksum,
             rte_eth_tx_offload_multi_segs,
+            rte_eth_tx_offload_security,
             rte_eth_tx_offload_tcp_cksum,
+            rte_eth_tx_offload_tcp_tso,
             rte_eth_tx_offload_udp_cksum,
+            rte_eth_tx_offload_vlan_insert,
             rte_eth_tx_queue_setup,
             rte_eth_txconf,
             rte_ether_addr,
             rte_mbuf,
+            rte_mbuf_rx_l4_cksum_bad,
+            rte_mbuf_rx_l4_cksum_good,
+            rte_mbuf_rx_timestamp,
+            rte_mbuf_rx_timestamp_register,
+            rte_mbuf_rx_vlan_tci,
+            rte_mbuf_tx_cksum_offload,
+            rte_mbuf_tx_tcp_seg_offload,
+            rte_mbuf_tx_timestamp,
+            rte_mbuf_tx_tls_offload,
+            rte_mbuf_tx_vlan_offload,
             rte_pktmbuf_chain,
+            rte_pktmbuf_free,
+            rte_proc_type_t_RTE_PROC_PRIMARY as RTE_PROC_PRIMARY,
+            rte_security_tls_tx_capable,
+            RTE_ETHER_CRC_LEN,
             RTE_ETHER_MAX_JUMBO_FRAME_LEN,
             RTE_ETHER_MAX_LEN,
             RTE_ETH_DEV_NO_OWNER,
             RTE_ETH_LINK_FULL_DUPLEX,
             RTE_ETH_LINK_UP,
+            RTE_ETH_RETA_GROUP_SIZE,
             RTE_PKTMBUF_HEADROOM,
         },
         memory::DemiBuffer,
@@ -64,11 +117,24 @@ use crate::{
                 TcpConfig,
                 UdpConfig,
             },
-            consts::RECEIVE_BATCH_SIZE,
+            consts::{
+                MAX_BATCH_SIZE,
+                MAX_GSO_SIZE,
+            },
+            numa::{
+                NumaPlacement,
+                NumaStats,
+            },
+            tls::offload::TlsOffload,
             types::MacAddress,
             NetworkRuntime,
+            PacketBatch,
             PacketBuf,
         },
+        teardown::{
+            self,
+            TeardownGuard,
+        },
         SharedObject,
     },
 };
@@ -81,16 +147,41 @@ use ::anyhow::{
     Error,
 };
 use ::std::{
-    ffi::CString,
+    ffi::{
+        c_void,
+        CString,
+    },
     mem::MaybeUninit,
-    net::Ipv4Addr,
+    net::{
+        Ipv4Addr,
+        SocketAddrV4,
+    },
     ops::{
         Deref,
         DerefMut,
     },
+    process,
+    rc::Rc,
+    sync::atomic::{
+        AtomicU32,
+        Ordering,
+    },
     time::Duration,
 };
 
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Number of times that the transmit queue is checked for packets in flight when shutting down.
+const TX_DRAIN_ATTEMPTS: usize = 1000;
+
+/// Delay between two checks of the transmit queue when shutting down, in microseconds.
+const TX_DRAIN_DELAY_MICROS: u32 = 10;
+
+/// Process that initialized the environment abstraction layer of DPDK, or zero if none did.
+static EAL_PID: AtomicU32 = AtomicU32::new(0);
+
 //==============================================================================
 // Macros
 //==============================================================================
@@ -110,15 +201,74 @@ macro_rules! expect_zero {
 // Structures
 //==============================================================================
 
+/// Hardware queues of a port and how received traffic is spread across them.
+struct QueueConfig {
+    rx_queues: u16,
+    tx_queues: u16,
+    /// Key of the RSS hash function. If not set, the default key of the device is kept.
+    rss_hash_key: Option<Vec<u8>>,
+    /// Receive queues that the entries of the RSS redirection table cycle through. If not set, the default table of
+    /// the device is kept.
+    rss_reta: Option<Vec<u16>>,
+}
+
+/// Checksum offloads that are enabled on a port. These are the ones that were requested and that the device supports.
+/// Checksums that are not offloaded are computed and verified in software by the network stack. UDP checksums are
+/// always verified by the network stack, so that failures are counted.
+#[derive(Clone, Copy, Debug)]
+struct ChecksumOffload {
+    rx_tcp: bool,
+    tx_tcp: bool,
+    tx_udp: bool,
+}
+
+/// VLAN offloads that are enabled on a port. Tags are inserted and stripped in software if the device cannot do so.
+#[derive(Clone, Copy, Debug)]
+struct VlanOffload {
+    /// Does the device strip tags from received frames?
+    strip: bool,
+    /// Does the device insert tags into transmitted frames?
+    insert: bool,
+}
+
+/// Work that the device performs on a frame that is being transmitted.
+#[derive(Clone, Copy, Debug)]
+enum TxOffload {
+    /// Compute the transport checksum, given the lengths of the Ethernet and IPv4 headers and the transport protocol.
+    Checksum(u16, u16, u8),
+    /// Split the TCP segment, given the lengths of the Ethernet, IPv4 and TCP headers and the size of the segments.
+    Segmentation(u16, u16, u16, u16),
+}
+
 /// DPDK Runtime
 pub struct DPDKRuntime {
     mm: MemoryManager,
     port_id: u16,
+    /// Receive queue that is polled by this instance.
+    rx_queue_id: u16,
+    /// Transmit queue that is used by this instance.
+    tx_queue_id: u16,
     link_addr: MacAddress,
     ipv4_addr: Ipv4Addr,
+    checksum_offload: ChecksumOffload,
+    /// Is TCP segmentation offloaded to the device?
+    tso: bool,
+    /// Tag of the VLAN that the port belongs to, if any.
+    vlan: Option<VlanTag>,
+    vlan_offload: VlanOffload,
+    /// Inline encryption of TLS records, if the device takes it over.
+    tls_offload: Option<SharedDPDKTlsOffload>,
+    /// Does the device stamp received frames and, on request, transmitted ones?
+    hw_timestamps: bool,
+    /// Largest number of packets that are received at once.
+    receive_batch_size: usize,
+    /// Where buffer pools and the state of queues were placed, relative to the NUMA node of the device.
+    numa_stats: NumaStats,
     arp_config: ArpConfig,
     tcp_config: TcpConfig,
     udp_config: UdpConfig,
+    /// Releases the port and the hugepages if the process dies without shutting the runtime down.
+    teardown: Option<TeardownGuard>,
 }
 
 #[derive(Clone)]
@@ -131,20 +281,71 @@ pub struct SharedDPDKRuntime(SharedObject<DPDKRuntime>);
 /// Associate Functions for DPDK Runtime
 impl SharedDPDKRuntime {
     pub fn new(config: Config) -> Result<Self, Fail> {
-        let (mm, port_id, link_addr) = Self::initialize_dpdk(
-            &config.eal_init_args(),
-            config.use_jumbo_frames(),
-            config.mtu()?,
-            config.tcp_checksum_offload(),
-            config.udp_checksum_offload(),
-        )
-        .unwrap();
+        let queue_config: QueueConfig = QueueConfig {
+            rx_queues: config.dpdk_rx_queues()?,
+            tx_queues: config.dpdk_tx_queues()?,
+            rss_hash_key: config.dpdk_rss_hash_key()?,
+            rss_reta: config.dpdk_rss_reta()?,
+        };
+        // The environment abstraction layer of DPDK cannot be initialized twice in an address space, and a child process
+        // shares its hugepages and the mappings of the device with its parent. Such a child must exec a new program.
+        match EAL_PID.load(Ordering::Relaxed) {
+            0 => (),
+            pid if pid == process::id() => {
+                let cause: &str = "DPDK cannot be initialized twice in a process";
+                error!("new(): {}", cause);
+                return Err(Fail::new(libc::ENOTSUP, cause));
+            },
+            pid => {
+                let cause: String = format!(
+                    "DPDK was initialized by the parent of this process, which should exec a new program (parent={:?})",
+                    pid
+                );
+                error!("new(): {}", cause);
+                return Err(Fail::new(libc::ENOTSUP, &cause));
+            },
+        }
+        let queue_id: u16 = config.dpdk_queue_id()?;
+        let vlan: Option<VlanTag> = config.vlan_tag()?;
+        let (mm, port_id, link_addr, checksum_offload, tso, vlan_offload, tls_offload, hw_timestamps, numa_stats) =
+            Self::initialize_dpdk(
+                &config.eal_init_args()?,
+                config.use_jumbo_frames(),
+                config.mtu()?,
+                config.dpdk_checksum_offload() || config.tcp_checksum_offload(),
+                config.dpdk_checksum_offload() || config.udp_checksum_offload(),
+                config.dpdk_tso(),
+                vlan.is_some(),
+                config.dpdk_tls_offload(),
+                config.dpdk_hw_timestamps(),
+                config.dpdk_numa_placement()?,
+                config.dpdk_numa_fallback(),
+                &queue_config,
+                queue_id,
+            )
+            .unwrap();
+        EAL_PID.store(process::id(), Ordering::Relaxed);
+
+        // Release the port and the hugepages if the process dies without shutting the runtime down, so that the next
+        // run finds the device usable.
+        let teardown: TeardownGuard = teardown::register("DPDK port and hugepages", move || {
+            if EAL_PID.load(Ordering::Relaxed) != process::id() {
+                return;
+            }
+            unsafe {
+                if rte_eal_process_type() == RTE_PROC_PRIMARY {
+                    rte_eth_dev_stop(port_id);
+                    rte_eth_dev_close(port_id);
+                }
+                rte_eal_cleanup();
+            }
+        });
 
         let arp_config = ArpConfig::new(
             Some(Duration::from_secs(15)),
             Some(Duration::from_secs(20)),
             Some(5),
-            Some(config.arp_table()),
+            Some(config.arp_table()?),
             Some(config.disable_arp()),
         );
 
@@ -155,31 +356,74 @@ impl SharedDPDKRuntime {
             Some(0xffff),
             Some(0),
             None,
-            Some(config.tcp_checksum_offload()),
-            Some(config.udp_checksum_offload()),
+            Some(checksum_offload.rx_tcp),
+            Some(checksum_offload.tx_tcp),
+            if config.dpdk_tso() { Some(MAX_GSO_SIZE) } else { None },
+            Some(tso),
+            Some(config.tcp_gro()),
         );
 
-        let udp_config = UdpConfig::new(Some(config.udp_checksum_offload()), Some(config.udp_checksum_offload()));
+        let udp_config = UdpConfig::new(Some(checksum_offload.tx_udp));
 
         Ok(Self(SharedObject::<DPDKRuntime>::new(DPDKRuntime {
             mm,
             port_id,
+            rx_queue_id: queue_id,
+            tx_queue_id: queue_id % queue_config.tx_queues,
             link_addr,
-            ipv4_addr: config.local_ipv4_addr(),
+            ipv4_addr: config.local_ipv4_addr()?,
+            checksum_offload,
+            tso,
+            vlan,
+            vlan_offload,
+            tls_offload: if tls_offload {
+                Some(SharedDPDKTlsOffload::new(port_id))
+            } else {
+                None
+            },
+            hw_timestamps,
+            receive_batch_size: config.receive_batch_size()?,
+            numa_stats,
             arp_config,
             tcp_config,
             udp_config,
+            teardown: Some(teardown),
         })))
     }
 
-    /// Initializes DPDK.
+    /// Initializes DPDK. The port is only configured by the primary process, so that other processes that run
+    /// instances on the remaining queues can attach to it. Checksum offloads are negotiated the same way by every
+    /// process, so that they all agree on what the port does. The same goes for TCP segmentation offload, VLAN
+    /// offloads, TLS offload and hardware timestamps. Memory pools and the state of queues are placed on a NUMA node
+    /// as told by [numa_placement].
     fn initialize_dpdk(
         eal_init_args: &[CString],
         use_jumbo_frames: bool,
         mtu: u16,
         tcp_checksum_offload: bool,
         udp_checksum_offload: bool,
-    ) -> Result<(MemoryManager, u16, MacAddress), Error> {
+        tso: bool,
+        vlan: bool,
+        tls_offload: bool,
+        hw_timestamps: bool,
+        numa_placement: NumaPlacement,
+        numa_fallback: bool,
+        queue_config: &QueueConfig,
+        queue_id: u16,
+    ) -> Result<
+        (
+            MemoryManager,
+            u16,
+            MacAddress,
+            ChecksumOffload,
+            bool,
+            VlanOffload,
+            bool,
+            bool,
+            NumaStats,
+        ),
+        Error,
+    > {
         std::env::set_var("MLX5_SHUT_UP_BF", "1");
         std::env::set_var("MLX5_SINGLE_THREADED", "1");
         std::env::set_var("MLX4_SINGLE_THREADED", "1");
@@ -196,23 +440,42 @@ impl SharedDPDKRuntime {
         eprintln!("DPDK reports that {} ports (interfaces) are available.", nb_ports);
 
         let max_body_size: usize = if use_jumbo_frames {
-            (RTE_ETHER_MAX_JUMBO_FRAME_LEN + RTE_PKTMBUF_HEADROOM) as usize
+            // Frames are received in a single mbuf, so its data room must fit the largest one.
+            Self::max_frame_size(mtu).max(RTE_ETHER_MAX_JUMBO_FRAME_LEN as usize) + RTE_PKTMBUF_HEADROOM as usize
         } else {
             DEFAULT_MAX_BODY_SIZE
         };
 
-        let memory_manager = MemoryManager::new(max_body_size)?;
-
         let owner: u64 = RTE_ETH_DEV_NO_OWNER as u64;
         let port_id: u16 = unsafe { rte_eth_find_next_owned_by(0, owner) as u16 };
-        Self::initialize_dpdk_port(
-            port_id,
-            &memory_manager,
-            use_jumbo_frames,
-            mtu,
-            tcp_checksum_offload,
-            udp_checksum_offload,
-        )?;
+        let mut placer: NumaPlacer = NumaPlacer::new(port_id, numa_placement, numa_fallback);
+
+        let memory_manager = MemoryManager::new(max_body_size, queue_id, &mut placer)?;
+
+        let dev_info: rte_eth_dev_info = Self::get_dev_info(port_id);
+        let checksum_offload: ChecksumOffload =
+            Self::negotiate_checksum_offload(&dev_info, tcp_checksum_offload, udp_checksum_offload);
+        let tso: bool = Self::negotiate_tso(&dev_info, tso);
+        let vlan_offload: VlanOffload = Self::negotiate_vlan_offload(&dev_info, vlan);
+        let tls_offload: bool = Self::negotiate_tls_offload(port_id, &dev_info, tls_offload);
+        let hw_timestamps: bool = Self::negotiate_hw_timestamps(&dev_info, hw_timestamps)?;
+        if unsafe { rte_eal_process_type() } == RTE_PROC_PRIMARY {
+            Self::initialize_dpdk_port(
+                port_id,
+                &memory_manager,
+                &mut placer,
+                use_jumbo_frames,
+                mtu,
+                &checksum_offload,
+                tso,
+                &vlan_offload,
+                tls_offload,
+                hw_timestamps,
+                queue_config,
+            )?;
+        } else {
+            eprintln!("Attaching to port {} (queue_id={}).", port_id, queue_id);
+        }
 
         // TODO: Where is this function?
         // if unsafe { rte_lcore_count() } > 1 {
@@ -229,20 +492,141 @@ impl SharedDPDKRuntime {
             Err(format_err!("Invalid mac address"))?;
         }
 
-        Ok((memory_manager, port_id, local_link_addr))
+        Ok((
+            memory_manager,
+            port_id,
+            local_link_addr,
+            checksum_offload,
+            tso,
+            vlan_offload,
+            tls_offload,
+            hw_timestamps,
+            placer.get_stats(),
+        ))
+    }
+
+    /// Computes the size of the largest frame that carries [mtu] bytes, including a VLAN tag and the frame check
+    /// sequence.
+    fn max_frame_size(mtu: u16) -> usize {
+        ETHERNET2_HEADER_SIZE + VLAN_TAG_SIZE + mtu as usize + RTE_ETHER_CRC_LEN as usize
+    }
+
+    /// Gets the information of the device behind [port_id].
+    fn get_dev_info(port_id: u16) -> rte_eth_dev_info {
+        unsafe {
+            let mut d: MaybeUninit<rte_eth_dev_info> = MaybeUninit::zeroed();
+            rte_eth_dev_info_get(port_id, d.as_mut_ptr());
+            d.assume_init()
+        }
+    }
+
+    /// Enables the requested checksum offloads that the device described by [dev_info] supports. The others fall
+    /// back to software.
+    fn negotiate_checksum_offload(
+        dev_info: &rte_eth_dev_info,
+        tcp_checksum_offload: bool,
+        udp_checksum_offload: bool,
+    ) -> ChecksumOffload {
+        let checksum_offload: ChecksumOffload = unsafe {
+            ChecksumOffload {
+                rx_tcp: tcp_checksum_offload && dev_info.rx_offload_capa & rte_eth_rx_offload_tcp_cksum() as u64 != 0,
+                tx_tcp: tcp_checksum_offload && dev_info.tx_offload_capa & rte_eth_tx_offload_tcp_cksum() as u64 != 0,
+                tx_udp: udp_checksum_offload && dev_info.tx_offload_capa & rte_eth_tx_offload_udp_cksum() as u64 != 0,
+            }
+        };
+        if (tcp_checksum_offload && !(checksum_offload.rx_tcp && checksum_offload.tx_tcp))
+            || (udp_checksum_offload && !checksum_offload.tx_udp)
+        {
+            eprintln!(
+                "WARNING: Device does not support all requested checksum offloads, falling back to software ({:?}).",
+                checksum_offload
+            );
+        }
+        checksum_offload
+    }
+
+    /// Enables TCP segmentation offload if it was requested and the device described by [dev_info] supports it,
+    /// along with the checksum offloads it relies on. Otherwise, segmentation falls back to software.
+    fn negotiate_tso(dev_info: &rte_eth_dev_info, tso: bool) -> bool {
+        if !tso {
+            return false;
+        }
+        let offloads: u64 = unsafe {
+            (rte_eth_tx_offload_tcp_tso() | rte_eth_tx_offload_ipv4_cksum() | rte_eth_tx_offload_tcp_cksum()) as u64
+        };
+        if dev_info.tx_offload_capa & offloads != offloads {
+            eprintln!("WARNING: Device does not support TCP segmentation offload, falling back to software.");
+            return false;
+        }
+        true
+    }
+
+    /// Enables the VLAN offloads that the device described by [dev_info] supports, if the port belongs to a VLAN.
+    fn negotiate_vlan_offload(dev_info: &rte_eth_dev_info, vlan: bool) -> VlanOffload {
+        let vlan_offload: VlanOffload = unsafe {
+            VlanOffload {
+                strip: vlan && dev_info.rx_offload_capa & rte_eth_rx_offload_vlan_strip() as u64 != 0,
+                insert: vlan && dev_info.tx_offload_capa & rte_eth_tx_offload_vlan_insert() as u64 != 0,
+            }
+        };
+        if vlan && !(vlan_offload.strip && vlan_offload.insert) {
+            eprintln!(
+                "WARNING: Device does not support all VLAN offloads, falling back to software ({:?}).",
+                vlan_offload
+            );
+        }
+        vlan_offload
+    }
+
+    /// Enables inline encryption of TLS records if it was requested and the device behind [port_id], which is described
+    /// by [dev_info], supports it for the cipher suite of TLS sessions. Otherwise, records are encrypted in software.
+    fn negotiate_tls_offload(port_id: u16, dev_info: &rte_eth_dev_info, tls_offload: bool) -> bool {
+        if !tls_offload {
+            return false;
+        }
+        let supported: bool = unsafe {
+            dev_info.tx_offload_capa & rte_eth_tx_offload_security() as u64 != 0
+                && rte_security_tls_tx_capable(port_id) != 0
+        };
+        if !supported {
+            eprintln!("WARNING: Device does not support TLS offload, falling back to software.");
+        }
+        supported
+    }
+
+    /// Enables hardware timestamps if they were requested and the device described by [dev_info] stamps received
+    /// frames. Every process registers the field of mbufs in which the device stores them. Otherwise, packets only get
+    /// software timestamps.
+    fn negotiate_hw_timestamps(dev_info: &rte_eth_dev_info, hw_timestamps: bool) -> Result<bool, Error> {
+        if !hw_timestamps {
+            return Ok(false);
+        }
+        if dev_info.rx_offload_capa & unsafe { rte_eth_rx_offload_timestamp() as u64 } == 0 {
+            eprintln!("WARNING: Device does not support hardware timestamps, falling back to software.");
+            return Ok(false);
+        }
+        unsafe {
+            expect_zero!(rte_mbuf_rx_timestamp_register())?;
+        }
+        Ok(true)
     }
 
-    /// Initializes a DPDK port.
+    /// Initializes a DPDK port. The state of its queues is placed on a NUMA node by [placer].
     fn initialize_dpdk_port(
         port_id: u16,
         memory_manager: &MemoryManager,
+        placer: &mut NumaPlacer,
         use_jumbo_frames: bool,
         mtu: u16,
-        tcp_checksum_offload: bool,
-        udp_checksum_offload: bool,
+        checksum_offload: &ChecksumOffload,
+        tso: bool,
+        vlan_offload: &VlanOffload,
+        tls_offload: bool,
+        hw_timestamps: bool,
+        queue_config: &QueueConfig,
     ) -> Result<(), Error> {
-        let rx_rings: u16 = 1;
-        let tx_rings: u16 = 1;
+        let rx_rings: u16 = queue_config.rx_queues;
+        let tx_rings: u16 = queue_config.tx_queues;
         let rx_ring_size: u16 = 2048;
         let tx_ring_size: u16 = 2048;
         let nb_rxd: u16 = rx_ring_size;
@@ -256,35 +640,75 @@ impl SharedDPDKRuntime {
         let tx_hthresh: u8 = 0;
         let tx_wthresh: u8 = 0;
 
-        let dev_info: dpdk_rs::rte_eth_dev_info = unsafe {
-            let mut d: MaybeUninit<dpdk_rs::rte_eth_dev_info> = MaybeUninit::zeroed();
-            rte_eth_dev_info_get(port_id, d.as_mut_ptr());
-            d.assume_init()
-        };
+        let dev_info: rte_eth_dev_info = Self::get_dev_info(port_id);
 
         println!("dev_info: {:?}", dev_info);
+        if rx_rings > dev_info.max_rx_queues || tx_rings > dev_info.max_tx_queues {
+            bail!(
+                "Too many queues (rx_queues={}, tx_queues={}, max_rx_queues={}, max_tx_queues={})",
+                rx_rings,
+                tx_rings,
+                dev_info.max_rx_queues,
+                dev_info.max_tx_queues
+            );
+        }
+        if mtu < dev_info.min_mtu || mtu > dev_info.max_mtu {
+            bail!(
+                "MTU is not supported by the device (mtu={}, min_mtu={}, max_mtu={})",
+                mtu,
+                dev_info.min_mtu,
+                dev_info.max_mtu
+            );
+        }
         let mut port_conf: rte_eth_conf = unsafe { MaybeUninit::zeroed().assume_init() };
+        port_conf.rxmode.mtu = mtu as u32;
         port_conf.rxmode.max_lro_pkt_size = if use_jumbo_frames {
-            RTE_ETHER_MAX_JUMBO_FRAME_LEN
+            (Self::max_frame_size(mtu) as u32).max(RTE_ETHER_MAX_JUMBO_FRAME_LEN)
         } else {
             RTE_ETHER_MAX_LEN
         };
-        if tcp_checksum_offload {
+        if checksum_offload.rx_tcp {
             port_conf.rxmode.offloads |= unsafe { rte_eth_rx_offload_tcp_cksum() as u64 };
         }
-        if udp_checksum_offload {
-            port_conf.rxmode.offloads |= unsafe { rte_eth_rx_offload_udp_cksum() as u64 };
+        if vlan_offload.strip {
+            port_conf.rxmode.offloads |= unsafe { rte_eth_rx_offload_vlan_strip() as u64 };
+        }
+        if hw_timestamps {
+            port_conf.rxmode.offloads |= unsafe { rte_eth_rx_offload_timestamp() as u64 };
         }
         port_conf.rxmode.mq_mode = RTE_ETH_MQ_RX_RSS;
         port_conf.rx_adv_conf.rss_conf.rss_hf = unsafe { rte_eth_rss_ip() as u64 } | dev_info.flow_type_rss_offloads;
+        if let Some(rss_hash_key) = &queue_config.rss_hash_key {
+            if rss_hash_key.len() != dev_info.hash_key_size as usize {
+                bail!(
+                    "Invalid RSS hash key size (expected={}, got={})",
+                    dev_info.hash_key_size,
+                    rss_hash_key.len()
+                );
+            }
+            // The key is copied by the device when it is configured.
+            port_conf.rx_adv_conf.rss_conf.rss_key = rss_hash_key.as_ptr() as *mut u8;
+            port_conf.rx_adv_conf.rss_conf.rss_key_len = rss_hash_key.len() as u8;
+        }
 
         port_conf.txmode.mq_mode = RTE_ETH_MQ_TX_NONE;
-        if tcp_checksum_offload {
+        if checksum_offload.tx_tcp {
             port_conf.txmode.offloads |= unsafe { rte_eth_tx_offload_tcp_cksum() as u64 };
         }
-        if udp_checksum_offload {
+        if checksum_offload.tx_udp {
             port_conf.txmode.offloads |= unsafe { rte_eth_tx_offload_udp_cksum() as u64 };
         }
+        if tso {
+            port_conf.txmode.offloads |= unsafe {
+                (rte_eth_tx_offload_tcp_tso() | rte_eth_tx_offload_ipv4_cksum() | rte_eth_tx_offload_tcp_cksum()) as u64
+            };
+        }
+        if vlan_offload.insert {
+            port_conf.txmode.offloads |= unsafe { rte_eth_tx_offload_vlan_insert() as u64 };
+        }
+        if tls_offload {
+            port_conf.txmode.offloads |= unsafe { rte_eth_tx_offload_security() as u64 };
+        }
         port_conf.txmode.offloads |= unsafe { rte_eth_tx_offload_multi_segs() as u64 };
 
         let mut rx_conf: rte_eth_rxconf = unsafe { MaybeUninit::zeroed().assume_init() };
@@ -317,32 +741,44 @@ impl SharedDPDKRuntime {
             }
         }
 
-        let socket_id: u32 = 0;
-
-        unsafe {
-            for i in 0..rx_rings {
+        for i in 0..rx_rings {
+            placer.place("receive queue", |socket_id| unsafe {
                 expect_zero!(rte_eth_rx_queue_setup(
                     port_id,
                     i,
                     nb_rxd,
-                    socket_id,
+                    socket_id as u32,
                     &rx_conf as *const _,
                     memory_manager.body_pool(),
-                ))?;
-            }
-            for i in 0..tx_rings {
+                ))
+            })?;
+        }
+        for i in 0..tx_rings {
+            placer.place("transmit queue", |socket_id| unsafe {
                 expect_zero!(rte_eth_tx_queue_setup(
                     port_id,
                     i,
                     nb_txd,
-                    socket_id,
+                    socket_id as u32,
                     &tx_conf as *const _
-                ))?;
-            }
+                ))
+            })?;
+        }
+
+        unsafe {
             expect_zero!(rte_eth_dev_start(port_id))?;
             rte_eth_promiscuous_enable(port_id);
         }
 
+        // Transmitted frames are only stamped once the device synchronizes its clock.
+        if hw_timestamps && unsafe { rte_eth_timesync_enable(port_id) } != 0 {
+            eprintln!("WARNING: Device does not support transmit timestamps, only received frames are stamped.");
+        }
+
+        if let Some(rss_reta) = &queue_config.rss_reta {
+            Self::initialize_rss_reta(port_id, dev_info.reta_size, rss_reta)?;
+        }
+
         if unsafe { rte_eth_dev_is_valid_port(port_id) } == 0 {
             bail!("Invalid port");
         }
@@ -378,6 +814,24 @@ impl SharedDPDKRuntime {
         Ok(())
     }
 
+    /// Programs the RSS redirection table of [port_id], which has [reta_size] entries, so that its entries cycle
+    /// through the receive queues in [rss_reta].
+    fn initialize_rss_reta(port_id: u16, reta_size: u16, rss_reta: &[u16]) -> Result<(), Error> {
+        let group_size: usize = RTE_ETH_RETA_GROUP_SIZE as usize;
+        let mut reta_conf: Vec<rte_eth_rss_reta_entry64> = (0..(reta_size as usize).div_ceil(group_size))
+            .map(|_| unsafe { MaybeUninit::zeroed().assume_init() })
+            .collect();
+        for i in 0..reta_size as usize {
+            let group: &mut rte_eth_rss_reta_entry64 = &mut reta_conf[i / group_size];
+            group.mask |= 1 << (i % group_size);
+            group.reta[i % group_size] = rss_reta[i % rss_reta.len()];
+        }
+        unsafe {
+            expect_zero!(rte_eth_dev_rss_reta_update(port_id, reta_conf.as_mut_ptr(), reta_size))?;
+        }
+        Ok(())
+    }
+
     pub fn get_link_addr(&self) -> MacAddress {
         self.link_addr
     }
@@ -397,33 +851,43 @@ impl SharedDPDKRuntime {
     pub fn get_tcp_config(&self) -> TcpConfig {
         self.tcp_config.clone()
     }
-}
-
-//==============================================================================
-// Trait Implementations
-//==============================================================================
 
-impl Deref for SharedDPDKRuntime {
-    type Target = DPDKRuntime;
-
-    fn deref(&self) -> &Self::Target {
-        self.0.deref()
+    /// Checks if the tag that the device stripped from the frame in [packet], if any, belongs to the VLAN of the port.
+    /// Untagged frames and frames that only carry a priority belong to any VLAN.
+    fn is_local_vlan(&self, packet: *const rte_mbuf) -> bool {
+        // Safety: `packet` is a valid pointer to a properly initialized `rte_mbuf` struct.
+        let tci: libc::c_int = unsafe { rte_mbuf_rx_vlan_tci(packet) };
+        if tci < 0 {
+            return true;
+        }
+        let tag: VlanTag = VlanTag::from_tci(tci as u16);
+        tag.vid() == 0 || Some(tag.vid()) == self.vlan.map(|vlan| vlan.vid())
     }
-}
 
-impl DerefMut for SharedDPDKRuntime {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        self.0.deref_mut()
+    /// Gets the security session that encrypts the TLS records in the TCP segment that starts with [header], if its
+    /// connection is offloaded.
+    fn tls_tx_session(tls_offload: &SharedDPDKTlsOffload, header: &[u8]) -> Option<*mut c_void> {
+        let (l2_len, l3_len, l4_proto): (u16, u16, u8) = TxOffload::parse_header(header)?;
+        let (l2_len, l3_len): (usize, usize) = (l2_len as usize, l3_len as usize);
+        if !matches!(IpProtocol::try_from(l4_proto), Ok(IpProtocol::TCP)) || header.len() < l2_len + l3_len + 8 {
+            return None;
+        }
+        let ipv4_header: &[u8] = &header[l2_len..];
+        let tcp_header: &[u8] = &header[(l2_len + l3_len)..];
+        let local: SocketAddrV4 = SocketAddrV4::new(
+            Ipv4Addr::new(ipv4_header[12], ipv4_header[13], ipv4_header[14], ipv4_header[15]),
+            u16::from_be_bytes([tcp_header[0], tcp_header[1]]),
+        );
+        let remote: SocketAddrV4 = SocketAddrV4::new(
+            Ipv4Addr::new(ipv4_header[16], ipv4_header[17], ipv4_header[18], ipv4_header[19]),
+            u16::from_be_bytes([tcp_header[2], tcp_header[3]]),
+        );
+        let tcp_seq: u32 = u32::from_be_bytes([tcp_header[4], tcp_header[5], tcp_header[6], tcp_header[7]]);
+        tls_offload.tx_session(local, remote, tcp_seq)
     }
-}
 
-//==============================================================================
-// Trait Implementations
-//==============================================================================
-
-/// Network Runtime Trait Implementation for DPDK Runtime
-impl NetworkRuntime for SharedDPDKRuntime {
-    fn transmit(&mut self, buf: Box<dyn PacketBuf>) {
+    /// Turns [buf] into a chain of mbufs that is ready to be handed to the device.
+    fn into_mbuf(&mut self, buf: Box<dyn PacketBuf>) -> *mut rte_mbuf {
         // TODO: Consider an important optimization here: If there is data in this packet (i.e. not just headers), and
         // that data is in a DPDK-owned mbuf, and there is "headroom" in that mbuf to hold the packet headers, just
         // prepend the headers into that mbuf and save the extra header mbuf allocation that we currently always do.
@@ -441,6 +905,15 @@ impl NetworkRuntime for SharedDPDKRuntime {
         //   2) Not managed => alloc body
         // Chain body buffer.
 
+        // Frames are tagged by the device if it is capable of doing so, and in software otherwise.
+        let priority: Option<u8> = buf.priority();
+        let needs_checksum: bool = buf.needs_checksum();
+        let tx_timestamp: bool = self.hw_timestamps && buf.tx_timestamp();
+        let buf: Box<dyn PacketBuf> = match self.vlan {
+            Some(tag) if !self.vlan_offload.insert => Box::new(VlanTaggedPacket::new(buf, tag)),
+            _ => buf,
+        };
+
         // First, allocate a header mbuf and write the header into it.
         let mut header_mbuf: DemiBuffer = match self.mm.alloc_header_mbuf() {
             Ok(mbuf) => mbuf,
@@ -449,8 +922,20 @@ impl NetworkRuntime for SharedDPDKRuntime {
         let header_size = buf.header_size();
         assert!(header_size <= header_mbuf.len());
         buf.write_header(&mut header_mbuf[..header_size]);
+        let tx_offload: Option<TxOffload> = match buf.tso_segment_size() {
+            Some(segment_size) if self.tso => TxOffload::segmentation(&header_mbuf[..header_size], segment_size),
+            _ if !needs_checksum => None,
+            _ => self.checksum_offload.tx_offload(&header_mbuf[..header_size]),
+        };
+        // Segments of connections whose TLS records are encrypted by the device carry the session of the connection.
+        let tls_session: Option<*mut c_void> = match self.tls_offload {
+            Some(ref tls_offload) if buf.body_size() > 0 && !tls_offload.is_empty() => {
+                Self::tls_tx_session(tls_offload, &header_mbuf[..header_size])
+            },
+            _ => None,
+        };
 
-        if let Some(body) = buf.take_body() {
+        let header_mbuf_ptr: *mut rte_mbuf = if let Some(body) = buf.take_body() {
             // Next, see how much space we have remaining and inline the body if we have room.
             let inline_space = header_mbuf.len() - header_size;
 
@@ -466,26 +951,37 @@ impl NetworkRuntime for SharedDPDKRuntime {
                     // The body is already stored in an MBuf, just extract it from the DemiBuffer.
                     expect_some!(body.into_mbuf(), "'body' should be DPDK-allocated")
                 } else {
-                    // The body is not dpdk-allocated, allocate a DPDKBuffer and copy the body into it.
-                    let mut mbuf: DemiBuffer = match self.mm.alloc_body_mbuf() {
-                        Ok(mbuf) => mbuf,
-                        Err(e) => panic!("failed to allocate body mbuf: {:?}", e.cause),
-                    };
-                    assert!(mbuf.len() >= body.len());
-                    mbuf[..body.len()].copy_from_slice(&body[..]);
-                    mbuf.trim(mbuf.len() - body.len()).unwrap();
-                    expect_some!(mbuf.into_mbuf(), "mbuf should not be empty")
+                    // The body is not dpdk-allocated, allocate DPDKBuffers and copy the body into them. Segments that
+                    // are offloaded to the device may not fit in a single one, so these are chained.
+                    let mut body_mbuf: Option<*mut rte_mbuf> = None;
+                    let mut offset: usize = 0;
+                    while offset < body.len() {
+                        let mut mbuf: DemiBuffer = match self.mm.alloc_body_mbuf() {
+                            Ok(mbuf) => mbuf,
+                            Err(e) => panic!("failed to allocate body mbuf: {:?}", e.cause),
+                        };
+                        let len: usize = std::cmp::min(mbuf.len(), body.len() - offset);
+                        mbuf[..len].copy_from_slice(&body[offset..(offset + len)]);
+                        mbuf.trim(mbuf.len() - len).unwrap();
+                        let mbuf_ptr: *mut rte_mbuf = expect_some!(mbuf.into_mbuf(), "mbuf should not be empty");
+                        match body_mbuf {
+                            // Safety: rte_pktmbuf_chain is a FFI that is safe to call as both of its args are valid
+                            // MBuf pointers.
+                            Some(head) => unsafe { assert_eq!(rte_pktmbuf_chain(head, mbuf_ptr), 0) },
+                            None => body_mbuf = Some(mbuf_ptr),
+                        }
+                        offset += len;
+                    }
+                    expect_some!(body_mbuf, "body should not be empty")
                 };
 
-                let mut header_mbuf_ptr: *mut rte_mbuf =
-                    expect_some!(header_mbuf.into_mbuf(), "mbuf should not be empty");
+                let header_mbuf_ptr: *mut rte_mbuf = expect_some!(header_mbuf.into_mbuf(), "mbuf should not be empty");
                 // Safety: rte_pktmbuf_chain is a FFI that is safe to call as both of its args are valid MBuf pointers.
                 unsafe {
                     // Attach the body MBuf onto the header MBuf's buffer chain.
                     assert_eq!(rte_pktmbuf_chain(header_mbuf_ptr, body_mbuf), 0);
                 }
-                let num_sent = unsafe { rte_eth_tx_burst(self.port_id, 0, &mut header_mbuf_ptr, 1) };
-                assert_eq!(num_sent, 1);
+                header_mbuf_ptr
             }
             // Otherwise, write in the inline space.
             else {
@@ -503,9 +999,7 @@ impl NetworkRuntime for SharedDPDKRuntime {
                 let frame_size = std::cmp::max(header_size + body.len(), MIN_PAYLOAD_SIZE);
                 header_mbuf.trim(header_mbuf.len() - frame_size).unwrap();
 
-                let mut header_mbuf_ptr: *mut rte_mbuf = expect_some!(header_mbuf.into_mbuf(), "mbuf cannot be empty");
-                let num_sent = unsafe { rte_eth_tx_burst(self.port_id, 0, &mut header_mbuf_ptr, 1) };
-                assert_eq!(num_sent, 1);
+                expect_some!(header_mbuf.into_mbuf(), "mbuf cannot be empty")
             }
         }
         // No body on our packet, just send the headers.
@@ -519,23 +1013,196 @@ impl NetworkRuntime for SharedDPDKRuntime {
             }
             let frame_size = std::cmp::max(header_size, MIN_PAYLOAD_SIZE);
             header_mbuf.trim(header_mbuf.len() - frame_size).unwrap();
-            let mut header_mbuf_ptr: *mut rte_mbuf = expect_some!(header_mbuf.into_mbuf(), "mbuf cannot be empty");
-            let num_sent = unsafe { rte_eth_tx_burst(self.port_id, 0, &mut header_mbuf_ptr, 1) };
-            assert_eq!(num_sent, 1);
+            expect_some!(header_mbuf.into_mbuf(), "mbuf cannot be empty")
+        };
+
+        // Safety: `header_mbuf_ptr` is a valid pointer to the first segment of the frame.
+        unsafe {
+            if let Some(tx_offload) = tx_offload {
+                tx_offload.apply(header_mbuf_ptr);
+            }
+            if let Some(tag) = self.vlan.filter(|_| self.vlan_offload.insert) {
+                let tag: VlanTag = priority.map_or(tag, |pcp| tag.with_pcp(pcp));
+                rte_mbuf_tx_vlan_offload(header_mbuf_ptr, tag.tci());
+            }
+            if let Some(session) = tls_session {
+                rte_mbuf_tx_tls_offload(header_mbuf_ptr, self.port_id, session);
+            }
+            if tx_timestamp {
+                rte_mbuf_tx_timestamp(header_mbuf_ptr);
+            }
+        }
+        header_mbuf_ptr
+    }
+}
+
+/// Associate Functions for Checksum Offloads
+impl ChecksumOffload {
+    /// Returns the offload that computes the transport checksum of the frame that starts with [header], if the device
+    /// should compute it.
+    fn tx_offload(&self, header: &[u8]) -> Option<TxOffload> {
+        let (l2_len, l3_len, l4_proto): (u16, u16, u8) = TxOffload::parse_header(header)?;
+        let offload: bool = match IpProtocol::try_from(l4_proto) {
+            Ok(IpProtocol::TCP) => self.tx_tcp,
+            Ok(IpProtocol::UDP) => self.tx_udp,
+            _ => false,
+        };
+        if offload {
+            Some(TxOffload::Checksum(l2_len, l3_len, l4_proto))
+        } else {
+            None
+        }
+    }
+
+    /// Checks the transport checksum of the frame in [packet], which is wrapped by [buf]. The network stack does not
+    /// verify checksums that are offloaded, so the verdict of the device is trusted when there is one, and the
+    /// checksum is verified in software otherwise.
+    fn rx_check(&self, packet: *const rte_mbuf, buf: &DemiBuffer) -> bool {
+        // Safety: `packet` is a valid pointer to a properly initialized `rte_mbuf` struct.
+        unsafe {
+            if rte_mbuf_rx_l4_cksum_good(packet) != 0 {
+                return true;
+            }
+            if rte_mbuf_rx_l4_cksum_bad(packet) != 0 {
+                return false;
+            }
+        }
+
+        // Frames that cannot be parsed are left for the network stack to drop.
+        let payload: DemiBuffer = match Ethernet2Header::parse(buf.clone()) {
+            Ok((header, payload)) if header.ether_type() == EtherType2::Ipv4 => payload,
+            _ => return true,
+        };
+        let (ipv4_hdr, payload): (Ipv4Header, DemiBuffer) = match Ipv4Header::parse(payload) {
+            Ok(result) => result,
+            Err(_) => return true,
+        };
+        match ipv4_hdr.get_protocol() {
+            IpProtocol::TCP if self.rx_tcp => TcpHeader::parse(&ipv4_hdr, payload, false).is_ok(),
+            _ => true,
+        }
+    }
+}
+
+/// Associate Functions for Transmit Offloads
+impl TxOffload {
+    /// Returns the offload that splits the TCP segment that starts with [header] into segments of [segment_size]
+    /// bytes.
+    fn segmentation(header: &[u8], segment_size: usize) -> Option<Self> {
+        let (l2_len, l3_len, _): (u16, u16, u8) = Self::parse_header(header)?;
+        let l4_len: u16 = header.len() as u16 - l2_len - l3_len;
+        Some(Self::Segmentation(l2_len, l3_len, l4_len, segment_size as u16))
+    }
+
+    /// Returns the lengths of the Ethernet and IPv4 headers and the transport protocol of the IPv4 frame that starts
+    /// with [header]. The length of the Ethernet header includes the VLAN tag of frames that are tagged in software.
+    fn parse_header(header: &[u8]) -> Option<(u16, u16, u8)> {
+        if header.len() < ETHERNET2_HEADER_SIZE {
+            return None;
+        }
+        let l2_len: usize = if u16::from_be_bytes([header[12], header[13]]) == VLAN_TPID {
+            ETHERNET2_HEADER_SIZE + VLAN_TAG_SIZE
+        } else {
+            ETHERNET2_HEADER_SIZE
+        };
+        if header.len() < l2_len + IPV4_HEADER_MIN_SIZE as usize
+            || u16::from_be_bytes([header[l2_len - 2], header[l2_len - 1]]) != EtherType2::Ipv4 as u16
+        {
+            return None;
+        }
+        let ipv4_header: &[u8] = &header[l2_len..];
+        let l3_len: u16 = (ipv4_header[0] & 0xf) as u16 * 4;
+        let l4_proto: u8 = ipv4_header[9];
+        Some((l2_len as u16, l3_len, l4_proto))
+    }
+
+    /// Requests the device to perform this offload on the frame in [mbuf].
+    ///
+    /// # Safety
+    ///
+    /// [mbuf] must be a valid pointer to the first segment of a properly initialized frame.
+    unsafe fn apply(&self, mbuf: *mut rte_mbuf) {
+        match *self {
+            TxOffload::Checksum(l2_len, l3_len, l4_proto) => rte_mbuf_tx_cksum_offload(mbuf, l2_len, l3_len, l4_proto),
+            TxOffload::Segmentation(l2_len, l3_len, l4_len, segment_size) => {
+                rte_mbuf_tx_tcp_seg_offload(mbuf, l2_len, l3_len, l4_len, segment_size)
+            },
         }
     }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+impl Deref for SharedDPDKRuntime {
+    type Target = DPDKRuntime;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl DerefMut for SharedDPDKRuntime {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
 
-    fn receive(&mut self) -> ArrayVec<DemiBuffer, RECEIVE_BATCH_SIZE> {
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+/// Network Runtime Trait Implementation for DPDK Runtime
+impl NetworkRuntime for SharedDPDKRuntime {
+    fn transmit(&mut self, pkts: PacketBatch) {
+        let mut mbufs: ArrayVec<*mut rte_mbuf, MAX_BATCH_SIZE> =
+            pkts.into_iter().map(|pkt| self.into_mbuf(pkt)).collect();
+        if mbufs.is_empty() {
+            return;
+        }
+
+        // Hand the whole batch to the device at once, so that it is notified only once.
+        let num_sent: u16 =
+            unsafe { rte_eth_tx_burst(self.port_id, self.tx_queue_id, mbufs.as_mut_ptr(), mbufs.len() as u16) };
+        if (num_sent as usize) < mbufs.len() {
+            warn!(
+                "transmit(): dropping {} packets: transmit queue is full",
+                mbufs.len() - num_sent as usize
+            );
+            for &mbuf in &mbufs[num_sent as usize..] {
+                // Safety: the device did not take ownership of this mbuf, so it is still valid.
+                unsafe { rte_pktmbuf_free(mbuf) };
+            }
+        }
+    }
+
+    fn receive(&mut self) -> ArrayVec<DemiBuffer, MAX_BATCH_SIZE> {
         let mut out = ArrayVec::new();
 
-        let mut packets: [*mut rte_mbuf; RECEIVE_BATCH_SIZE] = unsafe { mem::zeroed() };
-        let nb_rx = unsafe { rte_eth_rx_burst(self.port_id, 0, packets.as_mut_ptr(), RECEIVE_BATCH_SIZE as u16) };
-        assert!(nb_rx as usize <= RECEIVE_BATCH_SIZE);
+        let mut packets: [*mut rte_mbuf; MAX_BATCH_SIZE] = unsafe { mem::zeroed() };
+        let nb_rx = unsafe {
+            rte_eth_rx_burst(
+                self.port_id,
+                self.rx_queue_id,
+                packets.as_mut_ptr(),
+                self.receive_batch_size as u16,
+            )
+        };
+        assert!(nb_rx as usize <= self.receive_batch_size);
 
         {
             for &packet in &packets[..nb_rx as usize] {
                 // Safety: `packet` is a valid pointer to a properly initialized `rte_mbuf` struct.
                 let buf: DemiBuffer = unsafe { DemiBuffer::from_mbuf(packet) };
+                if self.vlan_offload.strip && !self.is_local_vlan(packet) {
+                    debug!("receive(): dropping packet: from another VLAN");
+                    continue;
+                }
+                if self.checksum_offload.rx_tcp && !self.checksum_offload.rx_check(packet, &buf) {
+                    warn!("receive(): dropping packet: bad checksum");
+                    continue;
+                }
                 out.push(buf);
             }
         }
@@ -554,4 +1221,77 @@ impl NetworkRuntime for SharedDPDKRuntime {
     fn get_tcp_config(&self) -> TcpConfig {
         self.tcp_config.clone()
     }
+
+    fn tls_offload(&self) -> Option<Rc<dyn TlsOffload>> {
+        self.tls_offload
+            .clone()
+            .map(|tls_offload| Rc::new(tls_offload) as Rc<dyn TlsOffload>)
+    }
+
+    fn rx_hw_timestamp(&self, pkt: &DemiBuffer) -> Option<u64> {
+        if !self.hw_timestamps {
+            return None;
+        }
+        let mbuf: *mut rte_mbuf = pkt.get_mbuf()?;
+        let mut timestamp: u64 = 0;
+        // Safety: `mbuf` is a valid pointer to a properly initialized `rte_mbuf` struct, which is held by `pkt`.
+        match unsafe { rte_mbuf_rx_timestamp(mbuf, &mut timestamp) } {
+            0 => None,
+            _ => Some(timestamp),
+        }
+    }
+
+    fn read_tx_hw_timestamp(&mut self) -> Option<u64> {
+        if !self.hw_timestamps {
+            return None;
+        }
+        let mut timestamp: u64 = 0;
+        match unsafe { rte_eth_timesync_read_tx_timestamp(self.port_id, &mut timestamp) } {
+            0 => Some(timestamp),
+            _ => None,
+        }
+    }
+
+    fn get_numa_stats(&self) -> Result<NumaStats, Fail> {
+        Ok(self.numa_stats)
+    }
+
+    fn shutdown(&mut self) -> Result<(), Fail> {
+        // The port and the hugepages are released right here, whether this succeeds or not.
+        self.teardown.take();
+
+        // Give the device some time to transmit what it was handed, so that the last segments of closed connections
+        // are not lost. The device frees the buffers of the packets that it transmitted, until there are none left.
+        for _ in 0..TX_DRAIN_ATTEMPTS {
+            if unsafe { rte_eth_tx_done_cleanup(self.port_id, self.tx_queue_id, 0) } <= 0 {
+                break;
+            }
+            unsafe { rte_delay_us_block(TX_DRAIN_DELAY_MICROS) };
+        }
+
+        // The port is shared with the processes that run instances on the other queues, so only the primary process,
+        // which configured it, releases it.
+        if unsafe { rte_eal_process_type() } == RTE_PROC_PRIMARY {
+            let ret: i32 = unsafe { rte_eth_dev_stop(self.port_id) };
+            if ret != 0 {
+                let cause: String = format!("failed to stop port (port_id={:?}, ret={:?})", self.port_id, ret);
+                warn!("shutdown(): {}", cause);
+            }
+            let ret: i32 = unsafe { rte_eth_dev_close(self.port_id) };
+            if ret != 0 {
+                let cause: String = format!("failed to close port (port_id={:?}, ret={:?})", self.port_id, ret);
+                error!("shutdown(): {}", cause);
+                return Err(Fail::new(libc::EIO, &cause));
+            }
+        }
+
+        // Release the memory of DPDK, including the memory pools. The application must not hold any buffer by now.
+        let ret: i32 = unsafe { rte_eal_cleanup() };
+        if ret != 0 {
+            let cause: String = format!("failed to clean up the environment abstraction layer (ret={:?})", ret);
+            error!("shutdown(): {}", cause);
+            return Err(Fail::new(-ret, &cause));
+        }
+        Ok(())
+    }
 }
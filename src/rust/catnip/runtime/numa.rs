@@ -0,0 +1,110 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    libdpdk::{
+        rte_eth_dev_socket_id,
+        rte_socket_id,
+    },
+    network::numa::{
+        NumaPlacement,
+        NumaStats,
+    },
+};
+use ::anyhow::Error;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Identifier that DPDK gives to any NUMA node.
+pub const SOCKET_ID_ANY: i32 = -1;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Places the buffer pools and the state of the queues of a port on a NUMA node, and accounts for where they land.
+pub struct NumaPlacer {
+    /// NUMA node on which allocations are placed, or [SOCKET_ID_ANY].
+    node: i32,
+    /// Are allocations that do not fit in [node] retried on any node?
+    fallback: bool,
+    stats: NumaStats,
+}
+
+//======================================================================================================================
+// Associate Functions
+//======================================================================================================================
+
+impl NumaPlacer {
+    /// Creates a placer for [port_id] that follows [placement]. This must be called on the thread that runs the engine,
+    /// once the environment abstraction layer of DPDK is initialized.
+    pub fn new(port_id: u16, placement: NumaPlacement, fallback: bool) -> Self {
+        // DPDK reports unknown nodes as negative numbers.
+        let device_node: Option<u32> = match unsafe { rte_eth_dev_socket_id(port_id) } {
+            node if node >= 0 => Some(node as u32),
+            _ => None,
+        };
+        let core_node: Option<u32> = match unsafe { rte_socket_id() } as i32 {
+            node if node >= 0 => Some(node as u32),
+            _ => None,
+        };
+        let node: Option<u32> = match placement {
+            NumaPlacement::Nic => device_node,
+            NumaPlacement::Core => core_node,
+            NumaPlacement::Any => None,
+        };
+        if device_node.is_some() && core_node.is_some() && device_node != core_node {
+            eprintln!(
+                "WARNING: Engine runs on another NUMA node than the device (device_node={:?}, core_node={:?}).",
+                device_node, core_node
+            );
+        }
+        Self {
+            node: node.map_or(SOCKET_ID_ANY, |node| node as i32),
+            fallback,
+            stats: NumaStats::new(device_node, core_node),
+        }
+    }
+
+    /// Allocates [what] with [alloc], which takes the NUMA node to allocate from. If that fails on the preferred node
+    /// and falling back is enabled, the allocation is retried on any node.
+    pub fn place<T>(&mut self, what: &str, mut alloc: impl FnMut(i32) -> Result<T, Error>) -> Result<T, Error> {
+        let result: Result<T, Error> = alloc(self.node);
+        match result {
+            Ok(value) => {
+                let node: Option<u32> = self.get_node();
+                self.stats.record(node);
+                Ok(value)
+            },
+            Err(e) if self.fallback && self.node != SOCKET_ID_ANY => {
+                eprintln!(
+                    "WARNING: Cannot allocate {} on NUMA node {} ({:?}), falling back to any node.",
+                    what, self.node, e
+                );
+                let value: T = alloc(SOCKET_ID_ANY)?;
+                self.stats.record(None);
+                Ok(value)
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Gets the NUMA node on which allocations are placed, if any.
+    pub fn get_node(&self) -> Option<u32> {
+        match self.node {
+            SOCKET_ID_ANY => None,
+            node => Some(node as u32),
+        }
+    }
+
+    /// Gets where allocations were placed so far.
+    pub fn get_stats(&self) -> NumaStats {
+        self.stats
+    }
+}
@@ -0,0 +1,145 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    libdpdk::{
+        rte_security_tls_tx_session_create,
+        rte_security_tls_tx_session_destroy,
+    },
+    network::tls::{
+        offload::{
+            TlsOffload,
+            TlsOffloadCapabilities,
+            TlsOffloadFlow,
+            TlsOffloadHandle,
+            TlsOffloadKey,
+        },
+        TLS_CHACHA20_POLY1305_SHA256,
+    },
+    SharedObject,
+};
+use ::std::{
+    collections::HashMap,
+    ffi::c_void,
+    net::SocketAddrV4,
+    ops::{
+        Deref,
+        DerefMut,
+    },
+};
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Security session of the device that encrypts the records of a TLS connection from a given TCP sequence number on.
+struct TxSession {
+    flow: TlsOffloadFlow,
+    session: *mut c_void,
+}
+
+/// Inline TLS record encryption of a DPDK port. The security sessions of all connections are kept here, so that
+/// frames are tagged with the session of their connection as they are handed to the device.
+pub struct DPDKTlsOffload {
+    port_id: u16,
+    sessions: HashMap<TlsOffloadHandle, TxSession>,
+    next_handle: u64,
+}
+
+#[derive(Clone)]
+pub struct SharedDPDKTlsOffload(SharedObject<DPDKTlsOffload>);
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+impl SharedDPDKTlsOffload {
+    /// Creates the inline TLS record encryption of [port_id]. The caller must have checked that the device supports it.
+    pub fn new(port_id: u16) -> Self {
+        Self(SharedObject::new(DPDKTlsOffload {
+            port_id,
+            sessions: HashMap::new(),
+            next_handle: 0,
+        }))
+    }
+
+    /// Checks if any connection is offloaded.
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Gets the security session that encrypts the TCP segment that starts at [tcp_seq] on the connection from [local]
+    /// to [remote]. This is the session that was installed last at or before [tcp_seq], so that retransmitted segments
+    /// are encrypted with the key they were first sent with.
+    pub fn tx_session(&self, local: SocketAddrV4, remote: SocketAddrV4, tcp_seq: u32) -> Option<*mut c_void> {
+        self.sessions
+            .values()
+            .filter(|tx| tx.flow.local == local && tx.flow.remote == remote)
+            .map(|tx| (tcp_seq.wrapping_sub(tx.flow.tcp_seq), tx.session))
+            // Sessions that start after the segment are a full sequence space away.
+            .filter(|(distance, _)| *distance < (1 << 31))
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, session)| session)
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+impl TlsOffload for SharedDPDKTlsOffload {
+    fn capabilities(&self) -> TlsOffloadCapabilities {
+        TlsOffloadCapabilities {
+            tx_cipher_suites: vec![TLS_CHACHA20_POLY1305_SHA256],
+        }
+    }
+
+    fn install_tx_key(&self, flow: &TlsOffloadFlow, key: &TlsOffloadKey) -> Result<TlsOffloadHandle, Fail> {
+        if key.cipher_suite != TLS_CHACHA20_POLY1305_SHA256 {
+            let cause: String = format!("unsupported cipher suite (cipher_suite={:#x})", key.cipher_suite);
+            error!("install_tx_key(): {}", cause);
+            return Err(Fail::new(libc::ENOTSUP, &cause));
+        }
+        // Safety: the key and the IV outlive the call, which copies them into the session.
+        let session: *mut c_void = unsafe {
+            rte_security_tls_tx_session_create(self.port_id, key.key.as_ptr(), key.iv.as_ptr(), key.record_seq)
+        };
+        if session.is_null() {
+            let cause: &str = "failed to create security session";
+            error!("install_tx_key(): {}", cause);
+            return Err(Fail::new(libc::ENOMEM, cause));
+        }
+        let mut me: Self = self.clone();
+        let handle: TlsOffloadHandle = TlsOffloadHandle(me.next_handle);
+        me.next_handle += 1;
+        me.sessions.insert(handle, TxSession { flow: *flow, session });
+        Ok(handle)
+    }
+
+    fn remove_key(&self, handle: TlsOffloadHandle) {
+        let mut me: Self = self.clone();
+        if let Some(tx) = me.sessions.remove(&handle) {
+            // Safety: the session was created on this port and is no longer referenced.
+            unsafe { rte_security_tls_tx_session_destroy(me.port_id, tx.session) };
+        }
+    }
+}
+
+impl Deref for SharedDPDKTlsOffload {
+    type Target = DPDKTlsOffload;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl DerefMut for SharedDPDKTlsOffload {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
@@ -0,0 +1,78 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::fail::Fail;
+use ::std::{
+    os::fd::RawFd,
+    ptr,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// A memory mapping.
+pub struct Mmap {
+    addr: *mut libc::c_void,
+    len: usize,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Mmap {
+    /// Maps [len] bytes of [fd] at [offset].
+    pub fn new(fd: RawFd, len: usize, offset: libc::off_t) -> Result<Self, Fail> {
+        Self::map(fd, len, offset, libc::MAP_SHARED | libc::MAP_POPULATE)
+    }
+
+    /// Maps [len] bytes of zeroed, page-aligned memory.
+    pub fn new_anonymous(len: usize) -> Result<Self, Fail> {
+        Self::map(-1, len, 0, libc::MAP_PRIVATE | libc::MAP_ANONYMOUS)
+    }
+
+    fn map(fd: RawFd, len: usize, offset: libc::off_t, flags: libc::c_int) -> Result<Self, Fail> {
+        let addr: *mut libc::c_void = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                flags,
+                fd,
+                offset,
+            )
+        };
+        if addr == libc::MAP_FAILED {
+            let errno: libc::c_int = unsafe { *libc::__errno_location() };
+            let cause: String = format!("failed to map memory (len={:?}, errno={:?})", len, errno);
+            error!("map(): {}", cause);
+            return Err(Fail::new(errno, &cause));
+        }
+        Ok(Self { addr, len })
+    }
+
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.addr as *mut u8
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        if unsafe { libc::munmap(self.addr, self.len) } != 0 {
+            warn!("failed to unmap memory (addr={:?}, len={:?})", self.addr, self.len);
+        }
+    }
+}
@@ -1,20 +1,31 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
+mod mmap;
 mod network;
 mod rawsocket;
+mod tap;
+mod vhost;
+mod xdp;
 
 //==============================================================================
 // Imports
 //==============================================================================
 
-use self::rawsocket::{
-    RawSocket,
-    RawSocketAddr,
+use self::{
+    rawsocket::{
+        RawSocket,
+        RawSocketAddr,
+    },
+    tap::TapDevice,
+    vhost::VhostUserDevice,
+    xdp::XdpSocket,
 };
 use crate::{
     demikernel::config::Config,
     expect_ok,
+    expect_some,
+    inetstack::protocols::ethernet2::VlanTag,
     runtime::{
         memory::MemoryRuntime,
         network::{
@@ -23,6 +34,7 @@ use crate::{
                 TcpConfig,
                 UdpConfig,
             },
+            consts::MAX_GSO_SIZE,
             types::MacAddress,
         },
         Runtime,
@@ -41,6 +53,20 @@ use ::std::{
 // Constants & Structures
 //==============================================================================
 
+/// Socket through which frames are exchanged with the network interface.
+#[derive(Clone)]
+enum LinuxSocket {
+    /// Raw socket bound to the network interface with the given index. Frames go through the kernel.
+    Raw(SharedObject<RawSocket>, i32),
+    /// AF_XDP socket. Frames bypass the kernel.
+    Xdp(SharedObject<XdpSocket>),
+    /// Virtio-net device driven over vhost-user. Frames are exchanged with a backend that runs in userspace, such as
+    /// a virtual switch, and the network interface is not used.
+    VhostUser(SharedObject<VhostUserDevice>),
+    /// TAP device. Frames are exchanged with the kernel stack of the local host.
+    Tap(SharedObject<TapDevice>),
+}
+
 /// Linux Runtime
 #[derive(Clone)]
 pub struct LinuxRuntime {
@@ -49,8 +75,12 @@ pub struct LinuxRuntime {
     arp_config: ArpConfig,
     link_addr: MacAddress,
     ipv4_addr: Ipv4Addr,
-    ifindex: i32,
-    socket: SharedObject<RawSocket>,
+    socket: LinuxSocket,
+    /// Largest number of packets that are received at once.
+    receive_batch_size: usize,
+    /// Tag that is inserted into transmitted frames. None of the sockets can do this, so it is done in software. On
+    /// receive, raw sockets and AF_XDP sockets rely on the network interface to strip tags.
+    vlan: Option<VlanTag>,
 }
 
 //==============================================================================
@@ -71,22 +101,131 @@ impl LinuxRuntime {
 
         // TODO: Make this constructor return a Result and drop expect() calls below.
         let mac_addr: [u8; 6] = [0; 6];
-        let ifindex: i32 = expect_ok!(
-            Self::get_ifindex(&config.local_interface_name()),
-            "could not parse ifindex"
-        );
-        let socket: RawSocket = expect_ok!(RawSocket::new(), "could not create raw socket");
-        let sockaddr: RawSocketAddr = RawSocketAddr::new(ifindex, &mac_addr);
-        expect_ok!(socket.bind(&sockaddr), "could not bind raw socket");
+        let socket: LinuxSocket = if config.catpowder_vhost_user() {
+            let socket_path: String = expect_some!(
+                config.catpowder_vhost_user_socket_path(),
+                "vhost-user socket path should be set"
+            );
+            let device: VhostUserDevice =
+                expect_ok!(VhostUserDevice::new(&socket_path), "could not create vhost-user device");
+            LinuxSocket::VhostUser(SharedObject::<VhostUserDevice>::new(device))
+        } else if config.catpowder_tap() {
+            let device: TapDevice = expect_ok!(
+                TapDevice::new(
+                    &expect_ok!(config.local_interface_name(), "interface name should be set"),
+                    config.mtu().ok()
+                ),
+                "could not create TAP device"
+            );
+            LinuxSocket::Tap(SharedObject::<TapDevice>::new(device))
+        } else if config.catpowder_xdp() {
+            let ifindex: i32 = expect_ok!(
+                Self::get_ifindex(&expect_ok!(
+                    config.local_interface_name(),
+                    "interface name should be set"
+                )),
+                "could not parse ifindex"
+            );
+            let socket: XdpSocket = expect_ok!(
+                XdpSocket::new(
+                    ifindex as u32,
+                    config.catpowder_xdp_queue_id(),
+                    expect_ok!(config.local_ipv4_addr(), "IPv4 address should be set"),
+                    config.catpowder_xdp_zero_copy(),
+                ),
+                "could not create AF_XDP socket"
+            );
+            LinuxSocket::Xdp(SharedObject::<XdpSocket>::new(socket))
+        } else {
+            let ifindex: i32 = expect_ok!(
+                Self::get_ifindex(&expect_ok!(
+                    config.local_interface_name(),
+                    "interface name should be set"
+                )),
+                "could not parse ifindex"
+            );
+            let socket: RawSocket = expect_ok!(RawSocket::new(), "could not create raw socket");
+            let sockaddr: RawSocketAddr = RawSocketAddr::new(ifindex, &mac_addr);
+            expect_ok!(socket.bind(&sockaddr), "could not bind raw socket");
+            LinuxSocket::Raw(SharedObject::<RawSocket>::new(socket), ifindex)
+        };
+
+        // None of the sockets can split segments, so this is always done in software.
+        let gso_max_size: Option<usize> = if config.catpowder_gso() {
+            Some(MAX_GSO_SIZE)
+        } else {
+            None
+        };
+
+        // TAP devices stand in for the network interfaces of catnip, so they honor the same parameters.
+        let (arp_config, tcp_config): (ArpConfig, TcpConfig) = match &socket {
+            LinuxSocket::Tap(device) => (
+                ArpConfig::new(
+                    Some(Duration::from_secs(600)),
+                    Some(Duration::from_secs(1)),
+                    Some(2),
+                    Some(expect_ok!(config.arp_table(), "invalid ARP table")),
+                    Some(config.disable_arp()),
+                ),
+                TcpConfig::new(
+                    Some(config.mss().unwrap_or(TcpConfig::mss_for_mtu(device.mtu()))),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    gso_max_size,
+                    None,
+                    Some(config.tcp_gro()),
+                ),
+            ),
+            // Raw sockets carry frames as large as the MTU of the network interface, so segments may be as large as the
+            // "MTU" parameter allows.
+            LinuxSocket::Raw(..) => (
+                arp_config,
+                TcpConfig::new(
+                    config.mss().ok(),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    gso_max_size,
+                    None,
+                    Some(config.tcp_gro()),
+                ),
+            ),
+            _ => (
+                arp_config,
+                TcpConfig::new(
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    gso_max_size,
+                    None,
+                    Some(config.tcp_gro()),
+                ),
+            ),
+        };
 
         Self {
-            tcp_config: TcpConfig::default(),
+            tcp_config,
             udp_config: UdpConfig::default(),
             arp_config,
-            link_addr: config.local_link_addr(),
-            ipv4_addr: config.local_ipv4_addr(),
-            ifindex,
-            socket: SharedObject::<RawSocket>::new(socket),
+            link_addr: expect_ok!(config.local_link_addr(), "link address should be set"),
+            ipv4_addr: expect_ok!(config.local_ipv4_addr(), "IPv4 address should be set"),
+            socket,
+            receive_batch_size: expect_ok!(config.receive_batch_size(), "invalid receive batch size"),
+            vlan: expect_ok!(config.vlan_tag(), "invalid VLAN tag"),
         }
     }
 
@@ -7,13 +7,18 @@
 
 use crate::{
     catpowder::runtime::{
-        rawsocket::RawSocketAddr,
+        rawsocket::{
+            RawSocket,
+            RawSocketAddr,
+        },
         LinuxRuntime,
+        LinuxSocket,
+    },
+    inetstack::protocols::ethernet2::{
+        Ethernet2Header,
+        VlanTaggedPacket,
     },
-    expect_ok,
-    inetstack::protocols::ethernet2::Ethernet2Header,
     runtime::{
-        limits,
         memory::DemiBuffer,
         network::{
             config::{
@@ -21,17 +26,18 @@ use crate::{
                 TcpConfig,
                 UdpConfig,
             },
-            consts::RECEIVE_BATCH_SIZE,
+            consts::{
+                MAX_BATCH_SIZE,
+                MAX_FRAME_SIZE,
+            },
             NetworkRuntime,
+            PacketBatch,
             PacketBuf,
         },
+        SharedObject,
     },
 };
 use ::arrayvec::ArrayVec;
-use ::std::mem::{
-    self,
-    MaybeUninit,
-};
 
 //======================================================================================================================
 // Trait Implementations
@@ -39,57 +45,72 @@ use ::std::mem::{
 
 /// Network Runtime Trait Implementation for Linux Runtime
 impl NetworkRuntime for LinuxRuntime {
-    /// Transmits a single [PacketBuf].
-    fn transmit(&mut self, pkt: Box<dyn PacketBuf>) {
-        let header_size: usize = pkt.header_size();
-        let body_size: usize = pkt.body_size();
+    /// Transmits a batch of [PacketBuf].
+    fn transmit(&mut self, pkts: PacketBatch) {
+        let pkts: PacketBatch = match self.vlan {
+            Some(tag) => pkts
+                .into_iter()
+                .map(|pkt| Box::new(VlanTaggedPacket::new(pkt, tag)) as Box<dyn PacketBuf>)
+                .collect(),
+            None => pkts,
+        };
+        let (socket, ifindex): (&mut SharedObject<RawSocket>, i32) = match &mut self.socket {
+            LinuxSocket::Raw(socket, ifindex) => (socket, *ifindex),
+            LinuxSocket::Xdp(socket) => return socket.transmit(pkts),
+            LinuxSocket::VhostUser(device) => return device.transmit(pkts),
+            LinuxSocket::Tap(device) => return device.transmit(pkts),
+        };
 
-        assert!(header_size + body_size < u16::MAX as usize);
-        let mut buf: DemiBuffer = DemiBuffer::new((header_size + body_size) as u16);
+        let mut bufs: ArrayVec<DemiBuffer, MAX_BATCH_SIZE> = ArrayVec::new();
+        let mut dest_sockaddrs: ArrayVec<RawSocketAddr, MAX_BATCH_SIZE> = ArrayVec::new();
+        for pkt in pkts {
+            let header_size: usize = pkt.header_size();
+            let body_size: usize = pkt.body_size();
 
-        pkt.write_header(&mut buf[..header_size]);
-        if let Some(body) = pkt.take_body() {
-            buf[header_size..].copy_from_slice(&body[..]);
-        }
+            assert!(header_size + body_size < u16::MAX as usize);
+            let mut buf: DemiBuffer = DemiBuffer::new((header_size + body_size) as u16);
 
-        let (header, _) = Ethernet2Header::parse(buf.clone()).unwrap();
-        let dest_addr_arr: [u8; 6] = header.dst_addr().to_array();
-        let dest_sockaddr: RawSocketAddr = RawSocketAddr::new(self.ifindex, &dest_addr_arr);
+            pkt.write_header(&mut buf[..header_size]);
+            if let Some(body) = pkt.take_body() {
+                buf[header_size..].copy_from_slice(&body[..]);
+            }
+
+            let (header, _) = Ethernet2Header::parse(buf.clone()).unwrap();
+            let dest_addr_arr: [u8; 6] = header.dst_addr().to_array();
+            dest_sockaddrs.push(RawSocketAddr::new(ifindex, &dest_addr_arr));
+            bufs.push(buf);
+        }
 
-        // Send packet.
-        match self.socket.sendto(&buf, &dest_sockaddr) {
+        // Send all packets at once.
+        match socket.sendmmsg(&bufs, &dest_sockaddrs) {
             // Operation succeeded.
-            Ok(_) => (),
-            // Operation failed, drop packet.
-            Err(e) => warn!("dropping packet: {:?}", e),
+            Ok(nsent) if nsent == bufs.len() => (),
+            // Operation partially succeeded, drop remaining packets.
+            Ok(nsent) => warn!("dropping {} packets", bufs.len() - nsent),
+            // Operation failed, drop packets.
+            Err(e) => warn!("dropping packets: {:?}", e),
         };
     }
 
     /// Receives a batch of [DemiBuffer].
-    // TODO: This routine currently only tries to receive a single packet buffer, not a batch of them.
-    fn receive(&mut self) -> ArrayVec<DemiBuffer, RECEIVE_BATCH_SIZE> {
-        // TODO: This routine contains an extra copy of the entire incoming packet that could potentially be removed.
-
-        // TODO: change this function to operate directly on DemiBuffer rather than on MaybeUninit<u8>.
+    fn receive(&mut self) -> ArrayVec<DemiBuffer, MAX_BATCH_SIZE> {
+        let batch_size: usize = self.receive_batch_size;
+        let socket: &mut SharedObject<RawSocket> = match &mut self.socket {
+            LinuxSocket::Raw(socket, _) => socket,
+            LinuxSocket::Xdp(socket) => return socket.receive(batch_size),
+            LinuxSocket::VhostUser(device) => return device.receive(batch_size),
+            LinuxSocket::Tap(device) => return device.receive(batch_size),
+        };
 
-        // This use-case is an example for MaybeUninit in the docs.
-        let mut out: [MaybeUninit<u8>; limits::RECVBUF_SIZE_MAX] =
-            [unsafe { MaybeUninit::uninit().assume_init() }; limits::RECVBUF_SIZE_MAX];
-        if let Ok((nbytes, _origin_addr)) = self.socket.recvfrom(&mut out[..]) {
-            let mut ret: ArrayVec<DemiBuffer, RECEIVE_BATCH_SIZE> = ArrayVec::new();
-            unsafe {
-                let bytes: [u8; limits::RECVBUF_SIZE_MAX] =
-                    mem::transmute::<[MaybeUninit<u8>; limits::RECVBUF_SIZE_MAX], [u8; limits::RECVBUF_SIZE_MAX]>(out);
-                let mut dbuf: DemiBuffer = expect_ok!(DemiBuffer::from_slice(&bytes), "'bytes' should fit");
-                expect_ok!(
-                    dbuf.trim(limits::RECVBUF_SIZE_MAX - nbytes),
-                    "'bytes' <= RECVBUF_SIZE_MAX"
-                );
-                ret.push(dbuf);
-            }
-            ret
-        } else {
-            ArrayVec::new()
+        let mut bufs: ArrayVec<DemiBuffer, MAX_BATCH_SIZE> = (0..batch_size)
+            .map(|_| DemiBuffer::new(MAX_FRAME_SIZE as u16))
+            .collect();
+        match socket.recvmmsg(&mut bufs) {
+            Ok(nrecv) => {
+                bufs.truncate(nrecv);
+                bufs
+            },
+            Err(_) => ArrayVec::new(),
         }
     }
 
@@ -51,15 +51,6 @@ impl RawSocketAddr {
 
         (sockaddr_ptr, sockaddr_len)
     }
-
-    /// Casts the target raw socket address as a mutable raw pointer to a socket address.
-    pub fn as_sockaddr_mut_ptr(&mut self) -> (*mut SockAddr, Socklen) {
-        let sockaddr_ptr: *mut SockAddr =
-            unsafe { mem::transmute::<*mut libc::sockaddr_ll, *mut SockAddr>(&mut self.0) };
-        let sockaddr_len: Socklen = mem::size_of::<libc::sockaddr_ll>() as u32;
-
-        (sockaddr_ptr, sockaddr_len)
-    }
 }
 
 //======================================================================================================================
@@ -7,16 +7,21 @@
 
 use crate::{
     catpowder::runtime::RawSocketAddr,
+    expect_ok,
     pal::data_structures::{
         SockAddr,
-        SockAddrIn,
         Socklen,
     },
-    runtime::fail::Fail,
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        network::consts::MAX_BATCH_SIZE,
+    },
 };
+use ::arrayvec::ArrayVec;
 use ::std::{
     mem,
-    mem::MaybeUninit,
+    ptr,
 };
 
 //======================================================================================================================
@@ -35,7 +40,7 @@ impl RawSocket {
     /// Creates a raw socket.
     pub fn new() -> Result<Self, Fail> {
         let domain: i32 = libc::AF_PACKET; // Do not parse any headers.
-        let ty: i32 = libc::SOCK_RAW | libc::SOCK_NONBLOCK; // Non-blocking, raw socket.
+        let ty: i32 = libc::SOCK_RAW | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC; // Non-blocking, raw socket.
         let protocol: i32 = libc::ETH_P_ALL; // Accept packet from all protocols.
         let sockfd: i32 = unsafe { libc::socket(domain, ty, protocol) };
 
@@ -62,49 +67,95 @@ impl RawSocket {
         Ok(())
     }
 
-    /// Sends data through a raw socket.
-    pub fn sendto(&self, buf: &[u8], rawaddr: &RawSocketAddr) -> Result<usize, Fail> {
-        let buf_len: usize = buf.len();
-        let buf_ptr: *const libc::c_void = buf.as_ptr() as *const libc::c_void;
-        let (addr_ptr, addrlen): (*const SockAddr, Socklen) = rawaddr.as_sockaddr_ptr();
-
-        let nbytes: i32 =
-            unsafe { libc::sendto(self.0, buf_ptr, buf_len, libc::MSG_DONTWAIT, addr_ptr, addrlen) as i32 };
+    /// Sends a batch of frames through a raw socket, each one to the address at the same position in [rawaddrs].
+    /// Returns the number of frames that were sent.
+    pub fn sendmmsg(&self, bufs: &[DemiBuffer], rawaddrs: &[RawSocketAddr]) -> Result<usize, Fail> {
+        assert_eq!(bufs.len(), rawaddrs.len());
+        assert!(bufs.len() <= MAX_BATCH_SIZE);
+        let mut iovecs: ArrayVec<libc::iovec, MAX_BATCH_SIZE> = bufs
+            .iter()
+            .map(|buf| libc::iovec {
+                iov_base: buf.as_ptr() as *mut libc::c_void,
+                iov_len: buf.len(),
+            })
+            .collect();
+        let mut msgs: ArrayVec<libc::mmsghdr, MAX_BATCH_SIZE> = iovecs
+            .iter_mut()
+            .zip(rawaddrs)
+            .map(|(iovec, rawaddr)| {
+                let (addr_ptr, addrlen): (*const SockAddr, Socklen) = rawaddr.as_sockaddr_ptr();
+                Self::mmsghdr(iovec, addr_ptr as *mut libc::c_void, addrlen)
+            })
+            .collect();
+
+        let nsent: libc::c_int = unsafe {
+            libc::sendmmsg(
+                self.0,
+                msgs.as_mut_ptr(),
+                msgs.len() as libc::c_uint,
+                libc::MSG_DONTWAIT,
+            )
+        };
 
         // Check if we failed to send data through raw socket.
-        if nbytes == -1 {
+        if nsent == -1 {
             return Err(Fail::new(libc::EAGAIN, "failed to send data through raw socket"));
         }
 
-        Ok(nbytes as usize)
+        Ok(nsent as usize)
     }
 
-    /// Receives data from a raw socket.
-    pub fn recvfrom(&self, buf: &[MaybeUninit<u8>]) -> Result<(usize, RawSocketAddr), Fail> {
-        let buf_ptr: *mut libc::c_void = buf.as_ptr() as *mut libc::c_void;
-        let buf_len: usize = buf.len();
-        let mut addrlen: Socklen = mem::size_of::<SockAddrIn>() as u32;
-        let mut rawaddr: RawSocketAddr = RawSocketAddr::default();
-        let addrlen_ptr: *mut Socklen = &mut addrlen as *mut Socklen;
-        let (addr_ptr, _): (*mut SockAddr, Socklen) = rawaddr.as_sockaddr_mut_ptr();
-
-        let nbytes: i32 = unsafe {
-            libc::recvfrom(
+    /// Receives a batch of frames from a raw socket, one into each buffer of [bufs]. Buffers that receive a frame are
+    /// trimmed to its length. Returns the number of frames that were received.
+    pub fn recvmmsg(&self, bufs: &mut [DemiBuffer]) -> Result<usize, Fail> {
+        assert!(bufs.len() <= MAX_BATCH_SIZE);
+        let mut iovecs: ArrayVec<libc::iovec, MAX_BATCH_SIZE> = bufs
+            .iter_mut()
+            .map(|buf| libc::iovec {
+                iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+                iov_len: buf.len(),
+            })
+            .collect();
+        let mut msgs: ArrayVec<libc::mmsghdr, MAX_BATCH_SIZE> = iovecs
+            .iter_mut()
+            .map(|iovec| Self::mmsghdr(iovec, ptr::null_mut(), 0))
+            .collect();
+
+        let nrecv: libc::c_int = unsafe {
+            libc::recvmmsg(
                 self.0,
-                buf_ptr,
-                buf_len,
+                msgs.as_mut_ptr(),
+                msgs.len() as libc::c_uint,
                 libc::MSG_DONTWAIT,
-                addr_ptr,
-                addrlen_ptr as *mut u32,
-            ) as i32
+                ptr::null_mut(),
+            )
         };
 
         // Check if we failed to receive data from raw socket.
-        if nbytes == -1 {
+        if nrecv == -1 {
             return Err(Fail::new(libc::EAGAIN, "failed to receive data from raw socket"));
         }
 
-        Ok((nbytes as usize, rawaddr))
+        for (buf, msg) in bufs.iter_mut().zip(&msgs).take(nrecv as usize) {
+            let nbytes: usize = msg.msg_len as usize;
+            expect_ok!(
+                buf.trim(buf.len() - nbytes),
+                "a frame should not be larger than the buffer"
+            );
+        }
+
+        Ok(nrecv as usize)
+    }
+
+    /// Builds the header of a message that is made of the single buffer in [iovec], and is exchanged with the
+    /// address at [addr_ptr].
+    fn mmsghdr(iovec: &mut libc::iovec, addr_ptr: *mut libc::c_void, addrlen: Socklen) -> libc::mmsghdr {
+        let mut msg_hdr: libc::msghdr = unsafe { mem::zeroed() };
+        msg_hdr.msg_name = addr_ptr;
+        msg_hdr.msg_namelen = addrlen;
+        msg_hdr.msg_iov = iovec as *mut libc::iovec;
+        msg_hdr.msg_iovlen = 1;
+        libc::mmsghdr { msg_hdr, msg_len: 0 }
     }
 }
 
@@ -0,0 +1,233 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    expect_ok,
+    inetstack::protocols::ethernet2::ETHERNET2_HEADER_SIZE,
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        network::{
+            consts::MAX_BATCH_SIZE,
+            PacketBatch,
+            PacketBuf,
+        },
+    },
+};
+use ::arrayvec::ArrayVec;
+use ::std::{
+    mem,
+    os::fd::RawFd,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// A TAP device. Frames written to it are delivered to the kernel as if they were received by a network interface,
+/// and frames that the kernel sends through the interface are read back from it. This enables the whole network stack
+/// to be exercised against the kernel stack without any special hardware.
+pub struct TapDevice {
+    fd: RawFd,
+    /// Maximum transmission unit of the device.
+    mtu: u16,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl TapDevice {
+    /// Attaches to the TAP device [ifname], creating it if it does not exist, and brings it up. If [mtu] is set, the
+    /// device is configured to use it, otherwise the MTU of the device is kept.
+    pub fn new(ifname: &str, mtu: Option<u16>) -> Result<Self, Fail> {
+        let fd: RawFd = unsafe {
+            libc::open(
+                c"/dev/net/tun".as_ptr(),
+                libc::O_RDWR | libc::O_NONBLOCK | libc::O_CLOEXEC,
+            )
+        };
+        if fd < 0 {
+            let errno: libc::c_int = unsafe { *libc::__errno_location() };
+            let cause: String = format!("failed to open TUN/TAP driver (errno={:?})", errno);
+            error!("new(): {}", cause);
+            return Err(Fail::new(errno, &cause));
+        }
+
+        match Self::setup(fd, ifname, mtu) {
+            Ok(mtu) => Ok(Self { fd, mtu }),
+            Err(e) => {
+                unsafe { libc::close(fd) };
+                Err(e)
+            },
+        }
+    }
+
+    /// Attaches [fd] to the TAP device [ifname] and configures it. Returns the MTU of the device.
+    fn setup(fd: RawFd, ifname: &str, mtu: Option<u16>) -> Result<u16, Fail> {
+        let mut ifreq: libc::ifreq = ifreq(ifname)?;
+        ifreq.ifr_ifru.ifru_flags = (libc::IFF_TAP | libc::IFF_NO_PI) as libc::c_short;
+        if unsafe { libc::ioctl(fd, libc::TUNSETIFF, &mut ifreq) } != 0 {
+            let errno: libc::c_int = unsafe { *libc::__errno_location() };
+            let cause: String = format!(
+                "failed to attach to TAP device (ifname={:?}, errno={:?})",
+                ifname, errno
+            );
+            error!("setup(): {}", cause);
+            return Err(Fail::new(errno, &cause));
+        }
+
+        // Interface settings are changed through a regular socket.
+        let sockfd: RawFd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+        if sockfd < 0 {
+            let errno: libc::c_int = unsafe { *libc::__errno_location() };
+            let cause: String = format!("failed to create control socket (errno={:?})", errno);
+            error!("setup(): {}", cause);
+            return Err(Fail::new(errno, &cause));
+        }
+        let result: Result<u16, Fail> = Self::configure(sockfd, ifname, mtu);
+        unsafe { libc::close(sockfd) };
+        result
+    }
+
+    /// Sets the MTU of the TAP device [ifname] and brings it up through the control socket [sockfd]. Returns the MTU
+    /// of the device.
+    fn configure(sockfd: RawFd, ifname: &str, mtu: Option<u16>) -> Result<u16, Fail> {
+        let mut ifreq: libc::ifreq = ifreq(ifname)?;
+
+        // Set the MTU and read it back, as the device may not accept it.
+        if let Some(mtu) = mtu {
+            ifreq.ifr_ifru.ifru_mtu = mtu as libc::c_int;
+            ioctl(sockfd, libc::SIOCSIFMTU, &mut ifreq, "set MTU")?;
+        }
+        ioctl(sockfd, libc::SIOCGIFMTU, &mut ifreq, "get MTU")?;
+        let device_mtu: libc::c_int = unsafe { ifreq.ifr_ifru.ifru_mtu };
+        match mtu {
+            Some(mtu) if mtu as libc::c_int != device_mtu => {
+                let cause: String = format!("failed to set MTU (requested={:?}, got={:?})", mtu, device_mtu);
+                error!("configure(): {}", cause);
+                return Err(Fail::new(libc::EINVAL, &cause));
+            },
+            _ => (),
+        }
+
+        // Bring the device up.
+        ioctl(sockfd, libc::SIOCGIFFLAGS, &mut ifreq, "get flags")?;
+        unsafe { ifreq.ifr_ifru.ifru_flags |= libc::IFF_UP as libc::c_short };
+        ioctl(sockfd, libc::SIOCSIFFLAGS, &mut ifreq, "set flags")?;
+
+        Ok(device_mtu.min(u16::MAX as libc::c_int) as u16)
+    }
+
+    /// Returns the maximum transmission unit of the device.
+    pub fn mtu(&self) -> u16 {
+        self.mtu
+    }
+
+    /// Transmits a batch of [PacketBuf]. TAP devices take a single frame per write, so packets are written one by one.
+    pub fn transmit(&mut self, pkts: PacketBatch) {
+        for pkt in pkts {
+            if let Err(e) = self.write(pkt) {
+                warn!("dropping packet: {:?}", e);
+            }
+        }
+    }
+
+    /// Writes a single [PacketBuf] to the device.
+    fn write(&mut self, pkt: Box<dyn PacketBuf>) -> Result<(), Fail> {
+        let header_size: usize = pkt.header_size();
+        let body_size: usize = pkt.body_size();
+        let len: usize = header_size + body_size;
+        if len > self.max_frame_size() {
+            let cause: String = format!("packet exceeds MTU (len={:?}, mtu={:?})", len, self.mtu);
+            warn!("write(): {}", cause);
+            return Err(Fail::new(libc::EMSGSIZE, &cause));
+        }
+
+        let mut buf: DemiBuffer = DemiBuffer::new(len as u16);
+        pkt.write_header(&mut buf[..header_size]);
+        if let Some(body) = pkt.take_body() {
+            buf[header_size..].copy_from_slice(&body[..]);
+        }
+
+        let nbytes: isize = unsafe { libc::write(self.fd, buf.as_ptr() as *const libc::c_void, len) };
+        if nbytes < 0 {
+            let errno: libc::c_int = unsafe { *libc::__errno_location() };
+            let cause: String = format!("failed to write to TAP device (errno={:?})", errno);
+            warn!("write(): {}", cause);
+            return Err(Fail::new(errno, &cause));
+        }
+
+        Ok(())
+    }
+
+    /// Receives a batch of at most [batch_size] [DemiBuffer].
+    pub fn receive(&mut self, batch_size: usize) -> ArrayVec<DemiBuffer, MAX_BATCH_SIZE> {
+        let mut ret: ArrayVec<DemiBuffer, MAX_BATCH_SIZE> = ArrayVec::new();
+        let max_frame_size: usize = self.max_frame_size();
+        while ret.len() < batch_size {
+            let mut buf: DemiBuffer = DemiBuffer::new(max_frame_size as u16);
+            let nbytes: isize = unsafe { libc::read(self.fd, buf.as_mut_ptr() as *mut libc::c_void, max_frame_size) };
+            if nbytes <= 0 {
+                break;
+            }
+            expect_ok!(
+                buf.trim(max_frame_size - nbytes as usize),
+                "a frame should not be larger than the buffer"
+            );
+            ret.push(buf);
+        }
+        ret
+    }
+
+    /// Returns the size of the largest frame that the device can carry.
+    fn max_frame_size(&self) -> usize {
+        (ETHERNET2_HEADER_SIZE + self.mtu as usize).min(u16::MAX as usize)
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Builds an interface request for [ifname].
+fn ifreq(ifname: &str) -> Result<libc::ifreq, Fail> {
+    let mut ifreq: libc::ifreq = unsafe { mem::zeroed() };
+    if ifname.is_empty() || ifname.len() >= ifreq.ifr_name.len() {
+        let cause: String = format!("invalid interface name (ifname={:?})", ifname);
+        error!("ifreq(): {}", cause);
+        return Err(Fail::new(libc::EINVAL, &cause));
+    }
+    for (dst, src) in ifreq.ifr_name.iter_mut().zip(ifname.bytes()) {
+        *dst = src as libc::c_char;
+    }
+    Ok(ifreq)
+}
+
+/// Issues the interface request [request] on [sockfd], which is meant to [what].
+fn ioctl(sockfd: RawFd, request: libc::c_ulong, ifreq: &mut libc::ifreq, what: &str) -> Result<(), Fail> {
+    if unsafe { libc::ioctl(sockfd, request, ifreq as *mut libc::ifreq) } != 0 {
+        let errno: libc::c_int = unsafe { *libc::__errno_location() };
+        let cause: String = format!("failed to {} of TAP device (errno={:?})", what, errno);
+        error!("ioctl(): {}", cause);
+        return Err(Fail::new(errno, &cause));
+    }
+    Ok(())
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+/// Detaches from the TAP device. Unless the device was made persistent, it is destroyed.
+impl Drop for TapDevice {
+    fn drop(&mut self) {
+        if unsafe { libc::close(self.fd) } != 0 {
+            warn!("failed to close TAP device (fd={:?})", self.fd);
+        }
+    }
+}
@@ -0,0 +1,282 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    catpowder::runtime::{
+        mmap::Mmap,
+        vhost::{
+            protocol::VhostUserConnection,
+            virtqueue::{
+                Virtqueue,
+                VIRTQ_DESC_F_WRITE,
+            },
+        },
+    },
+    expect_ok,
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        network::{
+            consts::MAX_BATCH_SIZE,
+            PacketBatch,
+            PacketBuf,
+        },
+    },
+};
+use ::arrayvec::ArrayVec;
+use ::std::{
+    os::fd::{
+        AsRawFd,
+        FromRawFd,
+        OwnedFd,
+        RawFd,
+    },
+    slice,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Number of entries in each virtqueue. Each entry owns a frame.
+const QUEUE_SIZE: u16 = 256;
+
+/// Size of a frame. This fits a virtio-net header and a standard Ethernet frame.
+const FRAME_SIZE: usize = 2048;
+
+/// Indexes of the virtqueues of a virtio-net device.
+const RX_QUEUE: u32 = 0;
+const TX_QUEUE: u32 = 1;
+
+/// Virtio features.
+const VIRTIO_F_VERSION_1: u64 = 1 << 32;
+const VHOST_USER_F_PROTOCOL_FEATURES: u64 = 1 << 30;
+
+/// Size of the virtio-net header that precedes every frame, depending on whether [VIRTIO_F_VERSION_1] is negotiated.
+const VIRTIO_NET_HDR_SIZE: usize = 12;
+const VIRTIO_NET_HDR_SIZE_LEGACY: usize = 10;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// A virtio-net device that is driven over vhost-user. Virtqueues and frames live in a memory region that is shared
+/// with the backend, which makes it possible to exchange frames with a virtual switch or a hypervisor without going
+/// through the kernel.
+pub struct VhostUserDevice {
+    /// Keeping the connection open keeps the device alive.
+    connection: VhostUserConnection,
+    /// File that backs the shared memory.
+    memory_fd: OwnedFd,
+    /// Memory shared with the backend. Virtqueues come first, then receive frames and finally transmit frames.
+    memory: Mmap,
+    rx: Virtqueue,
+    tx: Virtqueue,
+    /// Offset of the first frame in the shared memory.
+    frames_offset: usize,
+    /// Size of the virtio-net header.
+    header_size: usize,
+    /// Transmit descriptors that are currently owned by us.
+    free_tx: Vec<u16>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl VhostUserDevice {
+    /// Connects to the vhost-user backend listening on [socket_path] and sets up a receive and a transmit virtqueue.
+    pub fn new(socket_path: &str) -> Result<Self, Fail> {
+        let mut connection: VhostUserConnection = VhostUserConnection::connect(socket_path)?;
+
+        // Negotiate features. No offload is enabled, so frames always come in a single buffer.
+        let offered: u64 = connection.get_features()?;
+        let features: u64 = offered & (VIRTIO_F_VERSION_1 | VHOST_USER_F_PROTOCOL_FEATURES);
+        if features & VHOST_USER_F_PROTOCOL_FEATURES != 0 {
+            connection.get_protocol_features()?;
+            connection.set_protocol_features(0)?;
+        }
+        connection.set_features(features)?;
+        let header_size: usize = if features & VIRTIO_F_VERSION_1 != 0 {
+            VIRTIO_NET_HDR_SIZE
+        } else {
+            VIRTIO_NET_HDR_SIZE_LEGACY
+        };
+
+        // Allocate memory that can be shared.
+        let queue_len: usize = Virtqueue::layout_size(QUEUE_SIZE);
+        let frames_offset: usize = 2 * queue_len;
+        let len: usize = frames_offset + 2 * QUEUE_SIZE as usize * FRAME_SIZE;
+        let (memory_fd, memory): (OwnedFd, Mmap) = shared_memory(len)?;
+        let rx: Virtqueue = Virtqueue::new(memory.as_ptr(), QUEUE_SIZE)?;
+        let tx: Virtqueue = Virtqueue::new(unsafe { memory.as_ptr().add(queue_len) }, QUEUE_SIZE)?;
+        let mut device: Self = Self {
+            connection,
+            memory_fd,
+            memory,
+            rx,
+            tx,
+            frames_offset,
+            header_size,
+            free_tx: (0..QUEUE_SIZE).collect(),
+        };
+
+        // Lend all receive frames to the backend before starting the device.
+        for id in 0..QUEUE_SIZE {
+            let addr: u64 = device.frame_addr(RX_QUEUE, id);
+            device.rx.push(id, addr, FRAME_SIZE as u32, VIRTQ_DESC_F_WRITE);
+        }
+        device.setup(features & VHOST_USER_F_PROTOCOL_FEATURES != 0)?;
+
+        Ok(device)
+    }
+
+    /// Shares memory and virtqueues with the backend. If [enable] is set, virtqueues start disabled and must be
+    /// explicitly enabled.
+    fn setup(&mut self, enable: bool) -> Result<(), Fail> {
+        let connection: &mut VhostUserConnection = &mut self.connection;
+        connection.set_mem_table(
+            self.memory_fd.as_raw_fd(),
+            self.memory.as_ptr() as u64,
+            self.memory.len() as u64,
+        )?;
+        for (index, queue) in [(RX_QUEUE, &mut self.rx), (TX_QUEUE, &mut self.tx)] {
+            connection.set_vring_num(index, queue.size() as u32)?;
+            connection.set_vring_base(index, 0)?;
+            connection.set_vring_addr(index, queue.desc_addr(), queue.avail_addr(), queue.used_addr())?;
+            connection.set_vring_kick(index, queue.kick_fd())?;
+            connection.set_vring_call(index, queue.call_fd())?;
+            if enable {
+                connection.set_vring_enable(index, true)?;
+            }
+            queue.notify();
+        }
+        Ok(())
+    }
+
+    /// Transmits a batch of [PacketBuf]. The backend is notified once for the whole batch.
+    pub fn transmit(&mut self, pkts: PacketBatch) {
+        for pkt in pkts {
+            if let Err(e) = self.enqueue(pkt) {
+                warn!("dropping packet: {:?}", e);
+            }
+        }
+        self.tx.notify();
+    }
+
+    /// Places a single [PacketBuf] in the transmit queue. The packet is written straight into a frame of the shared
+    /// memory, right after an empty virtio-net header.
+    fn enqueue(&mut self, pkt: Box<dyn PacketBuf>) -> Result<(), Fail> {
+        let header_size: usize = pkt.header_size();
+        let body_size: usize = pkt.body_size();
+        let len: usize = self.header_size + header_size + body_size;
+        if len > FRAME_SIZE {
+            let cause: String = format!("packet does not fit in a frame (len={:?})", len);
+            warn!("enqueue(): {}", cause);
+            return Err(Fail::new(libc::EMSGSIZE, &cause));
+        }
+
+        // Reclaim frames that were already sent.
+        while let Some((id, _)) = self.tx.pop() {
+            self.free_tx.push(id);
+        }
+        let id: u16 = match self.free_tx.pop() {
+            Some(id) => id,
+            None => {
+                let cause: &str = "no frames available for transmission";
+                warn!("enqueue(): {}", cause);
+                return Err(Fail::new(libc::EAGAIN, cause));
+            },
+        };
+
+        let virtio_header_size: usize = self.header_size;
+        let frame: &mut [u8] = self.frame(TX_QUEUE, id, len);
+        frame[..virtio_header_size].fill(0);
+        let frame: &mut [u8] = &mut frame[virtio_header_size..];
+        pkt.write_header(&mut frame[..header_size]);
+        if let Some(body) = pkt.take_body() {
+            frame[header_size..].copy_from_slice(&body[..]);
+        }
+
+        let addr: u64 = self.frame_addr(TX_QUEUE, id);
+        self.tx.push(id, addr, len as u32, 0);
+
+        Ok(())
+    }
+
+    /// Receives a batch of at most [batch_size] [DemiBuffer].
+    pub fn receive(&mut self, batch_size: usize) -> ArrayVec<DemiBuffer, MAX_BATCH_SIZE> {
+        let mut ret: ArrayVec<DemiBuffer, MAX_BATCH_SIZE> = ArrayVec::new();
+        while ret.len() < batch_size {
+            let (id, len): (u16, u32) = match self.rx.pop() {
+                Some(used) => used,
+                None => break,
+            };
+            let len: usize = (len as usize).min(FRAME_SIZE);
+            if len > self.header_size {
+                let header_size: usize = self.header_size;
+                let dbuf: DemiBuffer = expect_ok!(
+                    DemiBuffer::from_slice(&self.frame(RX_QUEUE, id, len)[header_size..]),
+                    "a frame should fit in a buffer"
+                );
+                ret.push(dbuf);
+            }
+
+            // Give the frame back to the backend.
+            let addr: u64 = self.frame_addr(RX_QUEUE, id);
+            self.rx.push(id, addr, FRAME_SIZE as u32, VIRTQ_DESC_F_WRITE);
+        }
+
+        if !ret.is_empty() {
+            self.rx.notify();
+        }
+
+        ret
+    }
+
+    /// Returns the offset in the shared memory of the frame that is attached to descriptor [id] of [queue].
+    fn frame_offset(&self, queue: u32, id: u16) -> usize {
+        self.frames_offset + (queue as usize * QUEUE_SIZE as usize + id as usize) * FRAME_SIZE
+    }
+
+    /// Returns the address of the frame that is attached to descriptor [id] of [queue].
+    fn frame_addr(&self, queue: u32, id: u16) -> u64 {
+        self.memory.as_ptr() as u64 + self.frame_offset(queue, id) as u64
+    }
+
+    /// Returns the first [len] bytes of the frame that is attached to descriptor [id] of [queue].
+    fn frame(&mut self, queue: u32, id: u16, len: usize) -> &mut [u8] {
+        assert!(len <= FRAME_SIZE);
+        let offset: usize = self.frame_offset(queue, id);
+        unsafe { slice::from_raw_parts_mut(self.memory.as_ptr().add(offset), len) }
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Allocates [len] bytes of zeroed memory that are backed by a file, so that they can be shared with the backend.
+fn shared_memory(len: usize) -> Result<(OwnedFd, Mmap), Fail> {
+    let fd: RawFd = unsafe { libc::memfd_create(c"demikernel-vhost-user".as_ptr(), libc::MFD_CLOEXEC) };
+    if fd < 0 {
+        let errno: libc::c_int = unsafe { *libc::__errno_location() };
+        let cause: String = format!("failed to create memory file (errno={:?})", errno);
+        error!("shared_memory(): {}", cause);
+        return Err(Fail::new(errno, &cause));
+    }
+    // Safety: the file descriptor was just created and is not owned by anyone else.
+    let fd: OwnedFd = unsafe { OwnedFd::from_raw_fd(fd) };
+    if unsafe { libc::ftruncate(fd.as_raw_fd(), len as libc::off_t) } != 0 {
+        let errno: libc::c_int = unsafe { *libc::__errno_location() };
+        let cause: String = format!("failed to size memory file (len={:?}, errno={:?})", len, errno);
+        error!("shared_memory(): {}", cause);
+        return Err(Fail::new(errno, &cause));
+    }
+    let memory: Mmap = Mmap::new(fd.as_raw_fd(), len, 0)?;
+    Ok((fd, memory))
+}
@@ -0,0 +1,12 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+mod device;
+mod protocol;
+mod virtqueue;
+
+//======================================================================================================================
+// Exports
+//======================================================================================================================
+
+pub use device::VhostUserDevice;
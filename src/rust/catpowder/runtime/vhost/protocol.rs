@@ -0,0 +1,297 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::fail::Fail;
+use ::std::{
+    io::{
+        Read,
+        Write,
+    },
+    mem,
+    os::{
+        fd::{
+            AsRawFd,
+            RawFd,
+        },
+        unix::net::UnixStream,
+    },
+    ptr,
+    slice,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+const VHOST_USER_GET_FEATURES: u32 = 1;
+const VHOST_USER_SET_FEATURES: u32 = 2;
+const VHOST_USER_SET_OWNER: u32 = 3;
+const VHOST_USER_SET_MEM_TABLE: u32 = 5;
+const VHOST_USER_SET_VRING_NUM: u32 = 8;
+const VHOST_USER_SET_VRING_ADDR: u32 = 9;
+const VHOST_USER_SET_VRING_BASE: u32 = 10;
+const VHOST_USER_SET_VRING_KICK: u32 = 12;
+const VHOST_USER_SET_VRING_CALL: u32 = 13;
+const VHOST_USER_GET_PROTOCOL_FEATURES: u32 = 15;
+const VHOST_USER_SET_PROTOCOL_FEATURES: u32 = 16;
+const VHOST_USER_SET_VRING_ENABLE: u32 = 18;
+
+/// Flags of a message header.
+const VHOST_USER_VERSION: u32 = 0x1;
+const VHOST_USER_REPLY_MASK: u32 = 0x4;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+#[repr(C)]
+#[derive(Default)]
+struct Header {
+    request: u32,
+    flags: u32,
+    size: u32,
+}
+
+#[repr(C)]
+struct VringState {
+    index: u32,
+    num: u32,
+}
+
+#[repr(C)]
+struct VringAddr {
+    index: u32,
+    flags: u32,
+    desc_user_addr: u64,
+    used_user_addr: u64,
+    avail_user_addr: u64,
+    log_guest_addr: u64,
+}
+
+#[repr(C)]
+struct MemoryRegion {
+    guest_phys_addr: u64,
+    memory_size: u64,
+    userspace_addr: u64,
+    mmap_offset: u64,
+}
+
+/// Memory table with a single region.
+#[repr(C)]
+struct Memory {
+    nregions: u32,
+    padding: u32,
+    region: MemoryRegion,
+}
+
+/// Control channel to a vhost-user backend. We play the part of the front-end, that is, the driver of the device.
+pub struct VhostUserConnection {
+    stream: UnixStream,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl VhostUserConnection {
+    /// Connects to the vhost-user backend listening on [socket_path] and becomes its owner.
+    pub fn connect(socket_path: &str) -> Result<Self, Fail> {
+        let stream: UnixStream = match UnixStream::connect(socket_path) {
+            Ok(stream) => stream,
+            Err(e) => {
+                let cause: String = format!(
+                    "failed to connect to vhost-user backend (socket_path={:?}, error={:?})",
+                    socket_path, e
+                );
+                error!("connect(): {}", cause);
+                return Err(Fail::new(e.raw_os_error().unwrap_or(libc::ECONNREFUSED), &cause));
+            },
+        };
+        let mut connection: Self = Self { stream };
+        connection.send(VHOST_USER_SET_OWNER, &[], &[])?;
+        Ok(connection)
+    }
+
+    /// Gets the virtio features that are offered by the backend.
+    pub fn get_features(&mut self) -> Result<u64, Fail> {
+        self.get_u64(VHOST_USER_GET_FEATURES)
+    }
+
+    /// Acknowledges virtio [features].
+    pub fn set_features(&mut self, features: u64) -> Result<(), Fail> {
+        self.send(VHOST_USER_SET_FEATURES, as_bytes(&features), &[])
+    }
+
+    /// Gets the vhost-user protocol features that are offered by the backend.
+    pub fn get_protocol_features(&mut self) -> Result<u64, Fail> {
+        self.get_u64(VHOST_USER_GET_PROTOCOL_FEATURES)
+    }
+
+    /// Acknowledges vhost-user protocol [features].
+    pub fn set_protocol_features(&mut self, features: u64) -> Result<(), Fail> {
+        self.send(VHOST_USER_SET_PROTOCOL_FEATURES, as_bytes(&features), &[])
+    }
+
+    /// Shares the [len] bytes of [fd] with the backend. The memory is mapped at [addr] in our address space, and
+    /// this same address is used as its guest physical address, so that descriptors carry plain pointers.
+    pub fn set_mem_table(&mut self, fd: RawFd, addr: u64, len: u64) -> Result<(), Fail> {
+        let memory: Memory = Memory {
+            nregions: 1,
+            padding: 0,
+            region: MemoryRegion {
+                guest_phys_addr: addr,
+                memory_size: len,
+                userspace_addr: addr,
+                mmap_offset: 0,
+            },
+        };
+        self.send(VHOST_USER_SET_MEM_TABLE, as_bytes(&memory), &[fd])
+    }
+
+    /// Sets the number of entries of virtqueue [index].
+    pub fn set_vring_num(&mut self, index: u32, num: u32) -> Result<(), Fail> {
+        self.send(VHOST_USER_SET_VRING_NUM, as_bytes(&VringState { index, num }), &[])
+    }
+
+    /// Sets the index of the first available entry of virtqueue [index].
+    pub fn set_vring_base(&mut self, index: u32, num: u32) -> Result<(), Fail> {
+        self.send(VHOST_USER_SET_VRING_BASE, as_bytes(&VringState { index, num }), &[])
+    }
+
+    /// Sets the addresses of the descriptor table, available ring and used ring of virtqueue [index].
+    pub fn set_vring_addr(&mut self, index: u32, desc: u64, avail: u64, used: u64) -> Result<(), Fail> {
+        let addr: VringAddr = VringAddr {
+            index,
+            flags: 0,
+            desc_user_addr: desc,
+            used_user_addr: used,
+            avail_user_addr: avail,
+            log_guest_addr: 0,
+        };
+        self.send(VHOST_USER_SET_VRING_ADDR, as_bytes(&addr), &[])
+    }
+
+    /// Sets the eventfd that we signal when buffers are made available in virtqueue [index].
+    pub fn set_vring_kick(&mut self, index: u32, fd: RawFd) -> Result<(), Fail> {
+        self.send(VHOST_USER_SET_VRING_KICK, as_bytes(&(index as u64)), &[fd])
+    }
+
+    /// Sets the eventfd that the backend signals when buffers are used in virtqueue [index].
+    pub fn set_vring_call(&mut self, index: u32, fd: RawFd) -> Result<(), Fail> {
+        self.send(VHOST_USER_SET_VRING_CALL, as_bytes(&(index as u64)), &[fd])
+    }
+
+    /// Enables or disables virtqueue [index].
+    pub fn set_vring_enable(&mut self, index: u32, enable: bool) -> Result<(), Fail> {
+        let state: VringState = VringState {
+            index,
+            num: enable as u32,
+        };
+        self.send(VHOST_USER_SET_VRING_ENABLE, as_bytes(&state), &[])
+    }
+
+    /// Sends [request] and reads back a 64-bit reply.
+    fn get_u64(&mut self, request: u32) -> Result<u64, Fail> {
+        self.send(request, &[], &[])?;
+
+        let mut header: Header = Header::default();
+        let mut value: u64 = 0;
+        if let Err(e) = self.stream.read_exact(as_bytes_mut(&mut header)) {
+            return Err(io_error(request, "read reply header", e));
+        }
+        if header.request != request
+            || header.flags & VHOST_USER_REPLY_MASK == 0
+            || header.size as usize != mem::size_of::<u64>()
+        {
+            let cause: String = format!("unexpected reply from vhost-user backend (request={:?})", request);
+            error!("get_u64(): {}", cause);
+            return Err(Fail::new(libc::EPROTO, &cause));
+        }
+        if let Err(e) = self.stream.read_exact(as_bytes_mut(&mut value)) {
+            return Err(io_error(request, "read reply", e));
+        }
+        Ok(value)
+    }
+
+    /// Sends [request] with [payload], passing along file descriptors [fds].
+    fn send(&mut self, request: u32, payload: &[u8], fds: &[RawFd]) -> Result<(), Fail> {
+        let header: Header = Header {
+            request,
+            flags: VHOST_USER_VERSION,
+            size: payload.len() as u32,
+        };
+        let mut msg: Vec<u8> = Vec::with_capacity(mem::size_of::<Header>() + payload.len());
+        msg.extend_from_slice(as_bytes(&header));
+        msg.extend_from_slice(payload);
+
+        if fds.is_empty() {
+            if let Err(e) = self.stream.write_all(&msg) {
+                return Err(io_error(request, "send message", e));
+            }
+            return Ok(());
+        }
+
+        // File descriptors are passed as ancillary data, so go through sendmsg().
+        let fds_len: usize = mem::size_of_val(fds);
+        let mut control: Vec<u8> = vec![0; unsafe { libc::CMSG_SPACE(fds_len as u32) } as usize];
+        let mut iov: libc::iovec = libc::iovec {
+            iov_base: msg.as_mut_ptr() as *mut libc::c_void,
+            iov_len: msg.len(),
+        };
+        let mut msghdr: libc::msghdr = unsafe { mem::zeroed() };
+        msghdr.msg_iov = &mut iov;
+        msghdr.msg_iovlen = 1;
+        msghdr.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+        msghdr.msg_controllen = control.len();
+        unsafe {
+            let cmsg: *mut libc::cmsghdr = libc::CMSG_FIRSTHDR(&msghdr);
+            (*cmsg).cmsg_level = libc::SOL_SOCKET;
+            (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+            (*cmsg).cmsg_len = libc::CMSG_LEN(fds_len as u32) as usize;
+            ptr::copy_nonoverlapping(fds.as_ptr() as *const u8, libc::CMSG_DATA(cmsg), fds_len);
+        }
+        let nbytes: isize = unsafe { libc::sendmsg(self.stream.as_raw_fd(), &msghdr, 0) };
+        if nbytes != msg.len() as isize {
+            let errno: libc::c_int = if nbytes < 0 {
+                unsafe { *libc::__errno_location() }
+            } else {
+                libc::EIO
+            };
+            let cause: String = format!(
+                "failed to send message to vhost-user backend (request={:?}, errno={:?})",
+                request, errno
+            );
+            error!("send(): {}", cause);
+            return Err(Fail::new(errno, &cause));
+        }
+        Ok(())
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Views [value] as raw bytes.
+fn as_bytes<T>(value: &T) -> &[u8] {
+    unsafe { slice::from_raw_parts(value as *const T as *const u8, mem::size_of::<T>()) }
+}
+
+/// Views [value] as mutable raw bytes.
+fn as_bytes_mut<T>(value: &mut T) -> &mut [u8] {
+    unsafe { slice::from_raw_parts_mut(value as *mut T as *mut u8, mem::size_of::<T>()) }
+}
+
+/// Converts an I/O error that happened while trying to [what] for [request] into a [Fail].
+fn io_error(request: u32, what: &str, e: ::std::io::Error) -> Fail {
+    let cause: String = format!(
+        "failed to {} for vhost-user backend (request={:?}, error={:?})",
+        what, request, e
+    );
+    error!("io_error(): {}", cause);
+    Fail::new(e.raw_os_error().unwrap_or(libc::EIO), &cause)
+}
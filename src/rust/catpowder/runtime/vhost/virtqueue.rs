@@ -0,0 +1,245 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::fail::Fail;
+use ::std::{
+    mem,
+    os::fd::RawFd,
+    ptr,
+    sync::atomic::{
+        AtomicU16,
+        Ordering,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// The buffer of a descriptor is written by the device.
+pub const VIRTQ_DESC_F_WRITE: u16 = 2;
+
+/// We do not want to be interrupted when the device uses a buffer, as we poll.
+const VIRTQ_AVAIL_F_NO_INTERRUPT: u16 = 1;
+
+/// The device does not want to be notified when we make a buffer available, as it polls.
+const VIRTQ_USED_F_NO_NOTIFY: u16 = 1;
+
+/// Alignment of each part of a virtqueue. This is the most restrictive one that legacy devices require.
+const ALIGNMENT: usize = 4096;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct VirtqDesc {
+    addr: u64,
+    len: u32,
+    flags: u16,
+    next: u16,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct VirtqUsedElem {
+    id: u32,
+    len: u32,
+}
+
+/// A split virtqueue that lives in memory shared with the device. We are the driver: we make buffers available to the
+/// device and get them back once the device has used them.
+pub struct Virtqueue {
+    size: u16,
+    desc: *mut VirtqDesc,
+    avail_flags: *mut AtomicU16,
+    avail_idx: *mut AtomicU16,
+    avail_ring: *mut u16,
+    used_flags: *const AtomicU16,
+    used_idx: *const AtomicU16,
+    used_ring: *const VirtqUsedElem,
+    /// Index of the next entry of the available ring that we will fill.
+    next_avail: u16,
+    /// Index of the next entry of the used ring that we will consume.
+    next_used: u16,
+    /// Eventfd that is signaled to notify the device.
+    kick_fd: RawFd,
+    /// Eventfd that the device signals. This is never waited on, but some devices insist on having one.
+    call_fd: RawFd,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Virtqueue {
+    /// Returns the number of bytes that a virtqueue of [size] entries takes.
+    pub fn layout_size(size: u16) -> usize {
+        let (_, _, len) = Self::layout(size);
+        len
+    }
+
+    /// Places a virtqueue of [size] entries at [base], which should point to zeroed memory of at least
+    /// [Self::layout_size] bytes. The size must be a power of two.
+    pub fn new(base: *mut u8, size: u16) -> Result<Self, Fail> {
+        debug_assert!(size.is_power_of_two());
+        let (avail_offset, used_offset, _) = Self::layout(size);
+        let kick_fd: RawFd = eventfd()?;
+        let call_fd: RawFd = match eventfd() {
+            Ok(fd) => fd,
+            Err(e) => {
+                unsafe { libc::close(kick_fd) };
+                return Err(e);
+            },
+        };
+
+        // Safety: the caller guarantees that the memory backs the whole layout.
+        unsafe {
+            let avail: *mut u8 = base.add(avail_offset);
+            let used: *mut u8 = base.add(used_offset);
+            let queue: Self = Self {
+                size,
+                desc: base as *mut VirtqDesc,
+                avail_flags: avail as *mut AtomicU16,
+                avail_idx: avail.add(2) as *mut AtomicU16,
+                avail_ring: avail.add(4) as *mut u16,
+                used_flags: used as *const AtomicU16,
+                used_idx: used.add(2) as *const AtomicU16,
+                used_ring: used.add(4) as *const VirtqUsedElem,
+                next_avail: 0,
+                next_used: 0,
+                kick_fd,
+                call_fd,
+            };
+            (*queue.avail_flags).store(VIRTQ_AVAIL_F_NO_INTERRUPT, Ordering::Relaxed);
+            Ok(queue)
+        }
+    }
+
+    /// Computes the offsets of the available ring and the used ring, and the total length of a virtqueue.
+    fn layout(size: u16) -> (usize, usize, usize) {
+        let size: usize = size as usize;
+        let desc_len: usize = size * mem::size_of::<VirtqDesc>();
+        // Flags, index, ring and used event.
+        let avail_len: usize = 2 + 2 + size * 2 + 2;
+        // Flags, index, ring and available event.
+        let used_len: usize = 2 + 2 + size * mem::size_of::<VirtqUsedElem>() + 2;
+        let avail_offset: usize = align(desc_len);
+        let used_offset: usize = align(avail_offset + avail_len);
+        (avail_offset, used_offset, align(used_offset + used_len))
+    }
+
+    pub fn size(&self) -> u16 {
+        self.size
+    }
+
+    pub fn desc_addr(&self) -> u64 {
+        self.desc as u64
+    }
+
+    pub fn avail_addr(&self) -> u64 {
+        self.avail_flags as u64
+    }
+
+    pub fn used_addr(&self) -> u64 {
+        self.used_flags as u64
+    }
+
+    pub fn kick_fd(&self) -> RawFd {
+        self.kick_fd
+    }
+
+    pub fn call_fd(&self) -> RawFd {
+        self.call_fd
+    }
+
+    /// Makes the buffer of [len] bytes at [addr] available to the device through descriptor [id]. The device only
+    /// sees it once [Self::notify] is called.
+    pub fn push(&mut self, id: u16, addr: u64, len: u32, flags: u16) {
+        debug_assert!(id < self.size);
+        // Safety: [id] and the ring slot are within the table and the ring.
+        unsafe {
+            ptr::write_volatile(
+                self.desc.add(id as usize),
+                VirtqDesc {
+                    addr,
+                    len,
+                    flags,
+                    next: 0,
+                },
+            );
+            ptr::write_volatile(self.avail_ring.add((self.next_avail & (self.size - 1)) as usize), id);
+        }
+        self.next_avail = self.next_avail.wrapping_add(1);
+    }
+
+    /// Publishes the buffers pushed so far and kicks the device, unless it asked not to.
+    pub fn notify(&mut self) {
+        // Safety: the available index is only written by us.
+        unsafe { (*self.avail_idx).store(self.next_avail, Ordering::Release) };
+        if unsafe { (*self.used_flags).load(Ordering::Acquire) } & VIRTQ_USED_F_NO_NOTIFY == 0 {
+            let value: u64 = 1;
+            unsafe {
+                libc::write(
+                    self.kick_fd,
+                    &value as *const u64 as *const libc::c_void,
+                    mem::size_of::<u64>(),
+                )
+            };
+        }
+    }
+
+    /// Gets back a buffer that was used by the device, if any, along with the number of bytes that the device wrote
+    /// into it.
+    pub fn pop(&mut self) -> Option<(u16, u32)> {
+        // Safety: the used index is only written by the device, and entries before it are not touched anymore.
+        unsafe {
+            if (*self.used_idx).load(Ordering::Acquire) == self.next_used {
+                return None;
+            }
+            let elem: VirtqUsedElem =
+                ptr::read_volatile(self.used_ring.add((self.next_used & (self.size - 1)) as usize));
+            self.next_used = self.next_used.wrapping_add(1);
+            Some((elem.id as u16, elem.len))
+        }
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Rounds [len] up to the alignment of the parts of a virtqueue.
+fn align(len: usize) -> usize {
+    (len + ALIGNMENT - 1) & !(ALIGNMENT - 1)
+}
+
+/// Creates a non-blocking eventfd.
+fn eventfd() -> Result<RawFd, Fail> {
+    let fd: RawFd = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) };
+    if fd < 0 {
+        let errno: libc::c_int = unsafe { *libc::__errno_location() };
+        let cause: String = format!("failed to create eventfd (errno={:?})", errno);
+        error!("eventfd(): {}", cause);
+        return Err(Fail::new(errno, &cause));
+    }
+    Ok(fd)
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Drop for Virtqueue {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.kick_fd);
+            libc::close(self.call_fd);
+        }
+    }
+}
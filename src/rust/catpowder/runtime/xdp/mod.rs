@@ -0,0 +1,12 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+mod program;
+mod ring;
+mod socket;
+
+//======================================================================================================================
+// Exports
+//======================================================================================================================
+
+pub use socket::XdpSocket;
@@ -0,0 +1,266 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::fail::Fail;
+use ::std::{
+    mem,
+    net::Ipv4Addr,
+    os::fd::RawFd,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+const BPF_MAP_CREATE: libc::c_int = 0;
+const BPF_MAP_UPDATE_ELEM: libc::c_int = 2;
+const BPF_PROG_LOAD: libc::c_int = 5;
+const BPF_LINK_CREATE: libc::c_int = 28;
+const BPF_MAP_TYPE_XSKMAP: u32 = 17;
+const BPF_PROG_TYPE_XDP: u32 = 6;
+const BPF_XDP: u32 = 37;
+
+/// Verdict for packets that should go up the kernel stack.
+const XDP_PASS: i32 = 2;
+
+/// Ethernet types, as loaded from the wire into a little-endian register.
+const ETH_P_IP_LE: i32 = 0x0008;
+const ETH_P_ARP_LE: i32 = 0x0608;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// A BPF instruction.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct BpfInsn {
+    code: u8,
+    /// Destination register in the lower nibble, source register in the upper nibble.
+    regs: u8,
+    off: i16,
+    imm: i32,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct BpfMapCreateAttr {
+    map_type: u32,
+    key_size: u32,
+    value_size: u32,
+    max_entries: u32,
+    map_flags: u32,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct BpfMapUpdateAttr {
+    map_fd: u32,
+    pad: u32,
+    key: u64,
+    value: u64,
+    flags: u64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct BpfProgLoadAttr {
+    prog_type: u32,
+    insn_cnt: u32,
+    insns: u64,
+    license: u64,
+    log_level: u32,
+    log_size: u32,
+    log_buf: u64,
+    kern_version: u32,
+    prog_flags: u32,
+    prog_name: [u8; 16],
+    prog_ifindex: u32,
+    expected_attach_type: u32,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct BpfLinkCreateAttr {
+    prog_fd: u32,
+    target_ifindex: u32,
+    attach_type: u32,
+    flags: u32,
+}
+
+/// An XDP program that redirects the traffic of the local IPv4 address to AF_XDP sockets, and lets everything else
+/// through to the kernel. This way, the network interface can still be shared with the kernel stack.
+///
+/// The program is detached from the network interface as soon as this structure is dropped.
+pub struct XdpProgram {
+    /// Map from receive queues to AF_XDP sockets.
+    map_fd: RawFd,
+    prog_fd: RawFd,
+    link_fd: RawFd,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl XdpProgram {
+    /// Loads the program and attaches it to the network interface [ifindex]. Frames are redirected if they are ARP
+    /// messages that target [local_ipv4_addr] or IPv4 datagrams destined to it.
+    pub fn new(ifindex: u32, local_ipv4_addr: Ipv4Addr, num_queues: u32) -> Result<Self, Fail> {
+        let map_attr: BpfMapCreateAttr = BpfMapCreateAttr {
+            map_type: BPF_MAP_TYPE_XSKMAP,
+            key_size: mem::size_of::<u32>() as u32,
+            value_size: mem::size_of::<u32>() as u32,
+            max_entries: num_queues,
+            ..Default::default()
+        };
+        let map_fd: RawFd = bpf(BPF_MAP_CREATE, &map_attr, "create XSKMAP")?;
+
+        let insns: Vec<BpfInsn> = Self::build(map_fd, local_ipv4_addr);
+        let license: &[u8] = b"GPL\0";
+        let mut prog_name: [u8; 16] = [0; 16];
+        prog_name[..10].copy_from_slice(b"demikernel");
+        let prog_attr: BpfProgLoadAttr = BpfProgLoadAttr {
+            prog_type: BPF_PROG_TYPE_XDP,
+            insn_cnt: insns.len() as u32,
+            insns: insns.as_ptr() as u64,
+            license: license.as_ptr() as u64,
+            prog_name,
+            expected_attach_type: BPF_XDP,
+            ..Default::default()
+        };
+        let prog_fd: RawFd = match bpf(BPF_PROG_LOAD, &prog_attr, "load XDP program") {
+            Ok(fd) => fd,
+            Err(e) => {
+                unsafe { libc::close(map_fd) };
+                return Err(e);
+            },
+        };
+
+        // Let the kernel pick between native and generic mode.
+        let link_attr: BpfLinkCreateAttr = BpfLinkCreateAttr {
+            prog_fd: prog_fd as u32,
+            target_ifindex: ifindex,
+            attach_type: BPF_XDP,
+            flags: 0,
+        };
+        let link_fd: RawFd = match bpf(BPF_LINK_CREATE, &link_attr, "attach XDP program") {
+            Ok(fd) => fd,
+            Err(e) => {
+                unsafe {
+                    libc::close(prog_fd);
+                    libc::close(map_fd);
+                };
+                return Err(e);
+            },
+        };
+
+        Ok(Self {
+            map_fd,
+            prog_fd,
+            link_fd,
+        })
+    }
+
+    /// Redirects frames received on [queue_id] to the AF_XDP socket [xsk_fd].
+    pub fn register(&self, queue_id: u32, xsk_fd: RawFd) -> Result<(), Fail> {
+        let value: u32 = xsk_fd as u32;
+        let attr: BpfMapUpdateAttr = BpfMapUpdateAttr {
+            map_fd: self.map_fd as u32,
+            key: &queue_id as *const u32 as u64,
+            value: &value as *const u32 as u64,
+            ..Default::default()
+        };
+        bpf(BPF_MAP_UPDATE_ELEM, &attr, "register AF_XDP socket")?;
+        Ok(())
+    }
+
+    /// Builds the program. Registers are used as follows: r6 holds the context, r2 and r3 the bounds of the frame and
+    /// r5 the field of the frame under inspection.
+    fn build(map_fd: RawFd, local_ipv4_addr: Ipv4Addr) -> Vec<BpfInsn> {
+        // The address is compared against a little-endian load of its wire representation.
+        let ipv4_addr: i32 = i32::from_le_bytes(local_ipv4_addr.octets());
+        vec![
+            // r6 = ctx
+            insn(0xbf, 6, 1, 0, 0),
+            // r2 = ctx->data
+            insn(0x61, 2, 6, 0, 0),
+            // r3 = ctx->data_end
+            insn(0x61, 3, 6, 4, 0),
+            // if r2 + 42 > r3 goto pass (Ethernet + ARP headers, which also covers Ethernet + IPv4 addresses).
+            insn(0xbf, 4, 2, 0, 0),
+            insn(0x07, 4, 0, 0, 42),
+            insn(0x2d, 4, 3, 14, 0),
+            // r5 = ether type
+            insn(0x69, 5, 2, 12, 0),
+            // if IPv4 goto ipv4
+            insn(0x15, 5, 0, 2, ETH_P_IP_LE),
+            // if ARP goto arp
+            insn(0x15, 5, 0, 3, ETH_P_ARP_LE),
+            // goto pass
+            insn(0x05, 0, 0, 10, 0),
+            // ipv4: r5 = destination address, goto check
+            insn(0x61, 5, 2, 30, 0),
+            insn(0x05, 0, 0, 1, 0),
+            // arp: r5 = target protocol address
+            insn(0x61, 5, 2, 38, 0),
+            // check: if (u32) r5 != local address goto pass
+            insn(0x56, 5, 0, 6, ipv4_addr),
+            // return bpf_redirect_map(map, ctx->rx_queue_index, XDP_PASS)
+            insn(0x61, 2, 6, 16, 0),
+            insn(0x18, 1, 1, 0, map_fd),
+            insn(0x00, 0, 0, 0, 0),
+            insn(0xb7, 3, 0, 0, XDP_PASS),
+            insn(0x85, 0, 0, 0, 51),
+            insn(0x95, 0, 0, 0, 0),
+            // pass: return XDP_PASS
+            insn(0xb7, 0, 0, 0, XDP_PASS),
+            insn(0x95, 0, 0, 0, 0),
+        ]
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Builds a BPF instruction.
+fn insn(code: u8, dst: u8, src: u8, off: i16, imm: i32) -> BpfInsn {
+    BpfInsn {
+        code,
+        regs: (src << 4) | dst,
+        off,
+        imm,
+    }
+}
+
+/// Issues a bpf() system call and returns its result.
+fn bpf<T>(cmd: libc::c_int, attr: &T, what: &str) -> Result<RawFd, Fail> {
+    let ret: libc::c_long = unsafe { libc::syscall(libc::SYS_bpf, cmd, attr as *const T, mem::size_of::<T>()) };
+    if ret < 0 {
+        let errno: libc::c_int = unsafe { *libc::__errno_location() };
+        let cause: String = format!("failed to {} (errno={:?})", what, errno);
+        error!("bpf(): {}", cause);
+        return Err(Fail::new(errno, &cause));
+    }
+    Ok(ret as RawFd)
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+/// Detaches and unloads the program.
+impl Drop for XdpProgram {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.link_fd);
+            libc::close(self.prog_fd);
+            libc::close(self.map_fd);
+        }
+    }
+}
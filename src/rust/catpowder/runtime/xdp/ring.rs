@@ -0,0 +1,95 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    catpowder::runtime::mmap::Mmap,
+    runtime::fail::Fail,
+};
+use ::std::{
+    mem,
+    os::fd::RawFd,
+    ptr,
+    sync::atomic::{
+        AtomicU32,
+        Ordering,
+    },
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// A single-producer single-consumer ring shared with the kernel. Depending on the ring, either we or the kernel are
+/// the producer.
+pub struct XdpRing<T: Copy> {
+    /// Underlying mapping. This is kept around so that it is unmapped along with the ring.
+    _mmap: Mmap,
+    producer: *const AtomicU32,
+    consumer: *const AtomicU32,
+    flags: *const AtomicU32,
+    descs: *mut T,
+    size: u32,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl<T: Copy> XdpRing<T> {
+    /// Maps a ring of [size] entries of [fd], given the offsets that the kernel reported for it.
+    pub fn new(fd: RawFd, size: u32, offsets: &libc::xdp_ring_offset, pgoff: libc::off_t) -> Result<Self, Fail> {
+        let len: usize = offsets.desc as usize + size as usize * mem::size_of::<T>();
+        let mmap: Mmap = Mmap::new(fd, len, pgoff)?;
+        let base: *mut u8 = mmap.as_ptr();
+        // Safety: offsets were reported by the kernel and lie within the mapping.
+        unsafe {
+            Ok(Self {
+                producer: base.add(offsets.producer as usize) as *const AtomicU32,
+                consumer: base.add(offsets.consumer as usize) as *const AtomicU32,
+                flags: base.add(offsets.flags as usize) as *const AtomicU32,
+                descs: base.add(offsets.desc as usize) as *mut T,
+                size,
+                _mmap: mmap,
+            })
+        }
+    }
+
+    /// Produces an entry. Returns false if the ring is full.
+    pub fn produce(&mut self, desc: T) -> bool {
+        // Safety: we are the only producer, so the producer index only moves here.
+        unsafe {
+            let producer: u32 = (*self.producer).load(Ordering::Relaxed);
+            let consumer: u32 = (*self.consumer).load(Ordering::Acquire);
+            if producer.wrapping_sub(consumer) == self.size {
+                return false;
+            }
+            ptr::write(self.descs.add((producer & (self.size - 1)) as usize), desc);
+            (*self.producer).store(producer.wrapping_add(1), Ordering::Release);
+        }
+        true
+    }
+
+    /// Consumes an entry, if any.
+    pub fn consume(&mut self) -> Option<T> {
+        // Safety: we are the only consumer, so the consumer index only moves here.
+        unsafe {
+            let consumer: u32 = (*self.consumer).load(Ordering::Relaxed);
+            let producer: u32 = (*self.producer).load(Ordering::Acquire);
+            if producer == consumer {
+                return None;
+            }
+            let desc: T = ptr::read(self.descs.add((consumer & (self.size - 1)) as usize));
+            (*self.consumer).store(consumer.wrapping_add(1), Ordering::Release);
+            Some(desc)
+        }
+    }
+
+    /// Checks whether the kernel asked to be woken up to process this ring.
+    pub fn needs_wakeup(&self) -> bool {
+        unsafe { (*self.flags).load(Ordering::Acquire) & libc::XDP_RING_NEED_WAKEUP != 0 }
+    }
+}
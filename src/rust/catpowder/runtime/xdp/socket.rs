@@ -0,0 +1,342 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    catpowder::runtime::{
+        mmap::Mmap,
+        xdp::{
+            program::XdpProgram,
+            ring::XdpRing,
+        },
+    },
+    expect_ok,
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        network::{
+            consts::MAX_BATCH_SIZE,
+            PacketBatch,
+            PacketBuf,
+        },
+    },
+};
+use ::arrayvec::ArrayVec;
+use ::std::{
+    mem,
+    net::Ipv4Addr,
+    os::fd::RawFd,
+    ptr,
+    slice,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Number of frames in the UMEM.
+const NUM_FRAMES: u32 = 4096;
+
+/// Size of a frame in the UMEM. This must be a power of two, so that frames do not cross page boundaries.
+const FRAME_SIZE: u32 = 2048;
+
+/// Number of entries in each ring. Half of the frames are lent to the kernel for reception, the other half is kept
+/// for transmission.
+const RING_SIZE: u32 = NUM_FRAMES / 2;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// An AF_XDP socket bound to a single receive queue of a network interface. Frames are exchanged with the kernel
+/// through a memory region shared by both sides (UMEM) and four rings: the fill ring lends frames to the kernel for
+/// reception, the rx ring returns them filled with data, the tx ring hands frames over for transmission and the
+/// completion ring returns them once they are sent.
+pub struct XdpSocket {
+    fd: RawFd,
+    umem: Mmap,
+    fill: XdpRing<u64>,
+    completion: XdpRing<u64>,
+    rx: XdpRing<libc::xdp_desc>,
+    tx: XdpRing<libc::xdp_desc>,
+    /// Frames that are currently owned by us.
+    free_frames: Vec<u64>,
+    /// Program that steers traffic to this socket. This is dropped after the socket is closed.
+    _program: XdpProgram,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl XdpSocket {
+    /// Creates an AF_XDP socket on receive queue [queue_id] of the network interface [ifindex]. Traffic addressed to
+    /// [ipv4_addr] is redirected to the socket, while the remaining traffic is still delivered to the kernel. If
+    /// [zero_copy] is set, the socket fails to be created if the network interface does not support zero-copy mode.
+    pub fn new(ifindex: u32, queue_id: u32, ipv4_addr: Ipv4Addr, zero_copy: bool) -> Result<Self, Fail> {
+        let fd: RawFd = unsafe { libc::socket(libc::AF_XDP, libc::SOCK_RAW | libc::SOCK_CLOEXEC, 0) };
+        if fd < 0 {
+            let errno: libc::c_int = unsafe { *libc::__errno_location() };
+            let cause: String = format!("failed to create AF_XDP socket (errno={:?})", errno);
+            error!("new(): {}", cause);
+            return Err(Fail::new(errno, &cause));
+        }
+
+        match Self::setup(fd, ifindex, queue_id, ipv4_addr, zero_copy) {
+            Ok(socket) => Ok(socket),
+            Err(e) => {
+                unsafe { libc::close(fd) };
+                Err(e)
+            },
+        }
+    }
+
+    /// Sets up the UMEM and the rings of the AF_XDP socket [fd], binds it and registers it in the XDP program.
+    fn setup(fd: RawFd, ifindex: u32, queue_id: u32, ipv4_addr: Ipv4Addr, zero_copy: bool) -> Result<Self, Fail> {
+        // Register UMEM.
+        let umem: Mmap = Mmap::new_anonymous((NUM_FRAMES * FRAME_SIZE) as usize)?;
+        let umem_reg: libc::xdp_umem_reg_v1 = libc::xdp_umem_reg_v1 {
+            addr: umem.as_ptr() as u64,
+            len: umem.len() as u64,
+            chunk_size: FRAME_SIZE,
+            headroom: 0,
+        };
+        setsockopt(fd, libc::XDP_UMEM_REG, &umem_reg)?;
+
+        // Size rings.
+        setsockopt(fd, libc::XDP_UMEM_FILL_RING, &RING_SIZE)?;
+        setsockopt(fd, libc::XDP_UMEM_COMPLETION_RING, &RING_SIZE)?;
+        setsockopt(fd, libc::XDP_RX_RING, &RING_SIZE)?;
+        setsockopt(fd, libc::XDP_TX_RING, &RING_SIZE)?;
+
+        // Map rings.
+        let mut offsets: libc::xdp_mmap_offsets = unsafe { mem::zeroed() };
+        let mut optlen: libc::socklen_t = mem::size_of::<libc::xdp_mmap_offsets>() as libc::socklen_t;
+        if unsafe {
+            libc::getsockopt(
+                fd,
+                libc::SOL_XDP,
+                libc::XDP_MMAP_OFFSETS,
+                &mut offsets as *mut libc::xdp_mmap_offsets as *mut libc::c_void,
+                &mut optlen,
+            )
+        } != 0
+        {
+            let errno: libc::c_int = unsafe { *libc::__errno_location() };
+            let cause: String = format!("failed to get ring offsets (errno={:?})", errno);
+            error!("setup(): {}", cause);
+            return Err(Fail::new(errno, &cause));
+        }
+        let mut fill: XdpRing<u64> = XdpRing::new(
+            fd,
+            RING_SIZE,
+            &offsets.fr,
+            libc::XDP_UMEM_PGOFF_FILL_RING as libc::off_t,
+        )?;
+        let completion: XdpRing<u64> = XdpRing::new(
+            fd,
+            RING_SIZE,
+            &offsets.cr,
+            libc::XDP_UMEM_PGOFF_COMPLETION_RING as libc::off_t,
+        )?;
+        let rx: XdpRing<libc::xdp_desc> = XdpRing::new(fd, RING_SIZE, &offsets.rx, libc::XDP_PGOFF_RX_RING)?;
+        let tx: XdpRing<libc::xdp_desc> = XdpRing::new(fd, RING_SIZE, &offsets.tx, libc::XDP_PGOFF_TX_RING)?;
+
+        // Lend the first half of the frames to the kernel and keep the other half for transmission.
+        for i in 0..RING_SIZE {
+            let produced: bool = fill.produce((i * FRAME_SIZE) as u64);
+            debug_assert!(produced);
+        }
+        let free_frames: Vec<u64> = (RING_SIZE..NUM_FRAMES).map(|i| (i * FRAME_SIZE) as u64).collect();
+
+        // Bind socket.
+        let mut flags: u16 = libc::XDP_USE_NEED_WAKEUP;
+        if zero_copy {
+            flags |= libc::XDP_ZEROCOPY;
+        }
+        let sockaddr: libc::sockaddr_xdp = libc::sockaddr_xdp {
+            sxdp_family: libc::AF_XDP as u16,
+            sxdp_flags: flags,
+            sxdp_ifindex: ifindex,
+            sxdp_queue_id: queue_id,
+            sxdp_shared_umem_fd: 0,
+        };
+        if unsafe {
+            libc::bind(
+                fd,
+                &sockaddr as *const libc::sockaddr_xdp as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_xdp>() as libc::socklen_t,
+            )
+        } != 0
+        {
+            let errno: libc::c_int = unsafe { *libc::__errno_location() };
+            let cause: String = format!(
+                "failed to bind AF_XDP socket (ifindex={:?}, queue_id={:?}, errno={:?})",
+                ifindex, queue_id, errno
+            );
+            error!("setup(): {}", cause);
+            return Err(Fail::new(errno, &cause));
+        }
+
+        // Steer traffic to this socket.
+        let program: XdpProgram = XdpProgram::new(ifindex, ipv4_addr, queue_id + 1)?;
+        program.register(queue_id, fd)?;
+
+        Ok(Self {
+            fd,
+            umem,
+            fill,
+            completion,
+            rx,
+            tx,
+            free_frames,
+            _program: program,
+        })
+    }
+
+    /// Transmits a batch of [PacketBuf]. The kernel is kicked once for the whole batch.
+    pub fn transmit(&mut self, pkts: PacketBatch) {
+        for pkt in pkts {
+            if let Err(e) = self.enqueue(pkt) {
+                warn!("dropping packet: {:?}", e);
+            }
+        }
+
+        // Kick the kernel.
+        if self.tx.needs_wakeup() {
+            unsafe { libc::sendto(self.fd, ptr::null(), 0, libc::MSG_DONTWAIT, ptr::null(), 0) };
+        }
+    }
+
+    /// Places a single [PacketBuf] in the transmit ring. The packet is written straight into a frame of the UMEM.
+    fn enqueue(&mut self, pkt: Box<dyn PacketBuf>) -> Result<(), Fail> {
+        let header_size: usize = pkt.header_size();
+        let body_size: usize = pkt.body_size();
+        let len: usize = header_size + body_size;
+        if len > FRAME_SIZE as usize {
+            let cause: String = format!("packet does not fit in a frame (len={:?})", len);
+            warn!("enqueue(): {}", cause);
+            return Err(Fail::new(libc::EMSGSIZE, &cause));
+        }
+
+        // Reclaim frames that were already sent.
+        while let Some(addr) = self.completion.consume() {
+            self.free_frames.push(addr);
+        }
+        let addr: u64 = match self.free_frames.pop() {
+            Some(addr) => addr,
+            None => {
+                let cause: &str = "no frames available for transmission";
+                warn!("enqueue(): {}", cause);
+                return Err(Fail::new(libc::EAGAIN, cause));
+            },
+        };
+
+        let frame: &mut [u8] = self.frame(addr, len);
+        pkt.write_header(&mut frame[..header_size]);
+        if let Some(body) = pkt.take_body() {
+            frame[header_size..].copy_from_slice(&body[..]);
+        }
+
+        let desc: libc::xdp_desc = libc::xdp_desc {
+            addr,
+            len: len as u32,
+            options: 0,
+        };
+        if !self.tx.produce(desc) {
+            self.free_frames.push(addr);
+            let cause: &str = "transmit ring is full";
+            warn!("enqueue(): {}", cause);
+            return Err(Fail::new(libc::EAGAIN, cause));
+        }
+
+        Ok(())
+    }
+
+    /// Receives a batch of at most [batch_size] [DemiBuffer].
+    pub fn receive(&mut self, batch_size: usize) -> ArrayVec<DemiBuffer, MAX_BATCH_SIZE> {
+        let mut ret: ArrayVec<DemiBuffer, MAX_BATCH_SIZE> = ArrayVec::new();
+        while ret.len() < batch_size {
+            let desc: libc::xdp_desc = match self.rx.consume() {
+                Some(desc) => desc,
+                None => break,
+            };
+            let dbuf: DemiBuffer = expect_ok!(
+                DemiBuffer::from_slice(self.frame(desc.addr, desc.len as usize)),
+                "a frame should fit in a buffer"
+            );
+            ret.push(dbuf);
+
+            // Give the frame back to the kernel. The kernel may have placed data at an offset within the frame.
+            let frame_addr: u64 = desc.addr & !(FRAME_SIZE as u64 - 1);
+            if !self.fill.produce(frame_addr) {
+                self.free_frames.push(frame_addr);
+            }
+        }
+
+        // Kick the kernel.
+        if self.fill.needs_wakeup() {
+            unsafe {
+                libc::recvfrom(
+                    self.fd,
+                    ptr::null_mut(),
+                    0,
+                    libc::MSG_DONTWAIT,
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                )
+            };
+        }
+
+        ret
+    }
+
+    /// Returns the [len] bytes of the UMEM that start at [addr].
+    fn frame(&mut self, addr: u64, len: usize) -> &mut [u8] {
+        assert!(addr as usize + len <= self.umem.len());
+        unsafe { slice::from_raw_parts_mut(self.umem.as_ptr().add(addr as usize), len) }
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Sets the AF_XDP socket option [name] of [fd] to [value].
+fn setsockopt<T>(fd: RawFd, name: libc::c_int, value: &T) -> Result<(), Fail> {
+    if unsafe {
+        libc::setsockopt(
+            fd,
+            libc::SOL_XDP,
+            name,
+            value as *const T as *const libc::c_void,
+            mem::size_of::<T>() as libc::socklen_t,
+        )
+    } != 0
+    {
+        let errno: libc::c_int = unsafe { *libc::__errno_location() };
+        let cause: String = format!(
+            "failed to set AF_XDP socket option (name={:?}, errno={:?})",
+            name, errno
+        );
+        error!("setsockopt(): {}", cause);
+        return Err(Fail::new(errno, &cause));
+    }
+    Ok(())
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+/// Closes the AF_XDP socket. The UMEM and rings are unmapped afterwards, as fields are dropped.
+impl Drop for XdpSocket {
+    fn drop(&mut self) {
+        if unsafe { libc::close(self.fd) } != 0 {
+            warn!("failed to close AF_XDP socket (fd={:?})", self.fd);
+        }
+    }
+}
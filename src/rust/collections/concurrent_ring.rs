@@ -26,7 +26,10 @@ use ::core::{
 };
 use ::std::{
     alloc,
-    ptr::copy,
+    ptr::{
+        self,
+        copy,
+    },
 };
 
 use crate::timer;
@@ -51,6 +54,10 @@ const HEADER_SIZE: usize = 2;
 /// the header to indicated valid data that is ready to be read; on pop, the reader atomically sets the header to 0 in
 /// order to "claim"/lock the message from other concurrent readers.
 ///
+/// Producers reserve space with a single compare-and-swap on `push_offset`, so a batch of messages may be reserved at
+/// once (see [ConcurrentRingBuffer::try_push_batch]). Consumers zero out the space that they release, so that headers
+/// of messages that are not yet committed always read as zero.
+///
 /// For correctness, the following invariants
 /// must hold:
 /// 0. push_offset and pop_offset must monotonically increase, except when wrapping around, and must always be aligned
@@ -66,6 +73,7 @@ const HEADER_SIZE: usize = 2;
 /// not be modified or read.
 /// 7. If the 16 bytes pointed to by pop_offset are zero, then there is either another ongoing pop or there is no valid
 /// data in the buffer.
+/// 8. Bytes between pop_offset and push_offset (i.e., free space) are zero.
 pub struct ConcurrentRingBuffer {
     // Indexes the first empty byte where buffers can be enqueued.
     push_offset: *mut usize,
@@ -107,8 +115,8 @@ impl ConcurrentRingBuffer {
             is_managed: true,
         };
 
-        // Initialize the first header to 0.
-        me.write_header(0, 0);
+        // Initialize free space to 0.
+        unsafe { me.buffer.get_mut().fill(0) };
 
         Ok(me)
     }
@@ -138,33 +146,8 @@ impl ConcurrentRingBuffer {
             return Err(Fail::new(libc::EINVAL, &cause));
         }
         // reserve_space will allocate space for the header.
-        if let Some(push_offset) = self.reserve_space(len) {
-            debug_assert!(push_offset % HEADER_SIZE == 0);
-            // Push first part of buffer. If longer than the capacity of the ring, wrap around.
-            let first_offset: usize = push_offset + HEADER_SIZE;
-            let first_len: usize = if push_offset + len + HEADER_SIZE > self.capacity() {
-                self.capacity() - first_offset
-            } else {
-                len
-            };
-            let buf_ptr: *const u8 = buf.as_ptr();
-            let ring_ptr: *mut u8 = unsafe { self.buffer.get_mut().as_mut_ptr() };
-            // Copy the data into the ring buffer.
-            unsafe {
-                copy(buf_ptr, ring_ptr.add(first_offset), first_len);
-            }
-            // If there is remaining data in the buffer, wrap around.
-            if len > first_len {
-                // Copy the data into the ring buffer.
-                unsafe {
-                    copy(buf_ptr.add(first_len), ring_ptr, len - first_len);
-                }
-            }
-            // Commit the write by atomically writing the header to release the buffer. The overwritten header MUST be
-            // 0. The header describes just the length of the payload.
-            let old: usize = self.write_header(push_offset, len);
-            debug_assert_eq!(old, 0);
-
+        if let Some(push_offset) = self.reserve_space(align_header(len + HEADER_SIZE)) {
+            self.write_message(push_offset, buf);
             Ok(len)
         } else {
             let cause: String = format!("no space in the ring buffer (len={})", len);
@@ -173,6 +156,41 @@ impl ConcurrentRingBuffer {
         }
     }
 
+    /// Attempts to insert a batch of buffers into the ring buffer. Space for all buffers is reserved at once, thus either
+    /// all buffers are inserted or none is. On success, returns the number of buffers inserted.
+    #[allow(unused)]
+    pub fn try_push_batch(&self, bufs: &[&[u8]]) -> Result<usize, Fail> {
+        timer!("collections::concurrent_ring::try_push_batch");
+        let mut total_len: usize = 0;
+        for buf in bufs {
+            let len: usize = buf.len();
+            if (len == 0) || (len >= (1 << (8 * HEADER_SIZE))) {
+                let cause: String = format!("invalid buffer length (len={})", len);
+                error!("try_push_batch(): {}", &cause);
+                return Err(Fail::new(libc::EINVAL, &cause));
+            }
+            total_len += align_header(len + HEADER_SIZE);
+        }
+        if total_len == 0 {
+            let cause: &str = "empty batch";
+            error!("try_push_batch(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, cause));
+        }
+
+        if let Some(mut push_offset) = self.reserve_space(total_len) {
+            // Messages are committed in order, so consumers observe them in the same order they appear in the batch.
+            for buf in bufs {
+                self.write_message(push_offset, buf);
+                push_offset = (push_offset + align_header(buf.len() + HEADER_SIZE)) % self.capacity();
+            }
+            Ok(bufs.len())
+        } else {
+            let cause: String = format!("no space in the ring buffer (len={})", total_len);
+            error!("try_push_batch(): {}", &cause);
+            Err(Fail::new(libc::EAGAIN, &cause))
+        }
+    }
+
     /// Inserts an item at the enqueue of the target ring buffer. This function may block (spin).
     #[allow(unused)]
     pub fn push(&self, buf: &[u8]) -> Option<usize> {
@@ -195,11 +213,16 @@ impl ConcurrentRingBuffer {
             return Err(Fail::new(libc::EINVAL, "Buffer must be non-zero length"));
         }
 
-        // Is this safe or do we need an atomic indirect read?
         let pop_offset: usize = peek(self.pop_offset);
         // This represents the total length of the incoming message.
         let pop_len: usize = match self.write_header(pop_offset, 0) {
             0 => return Err(Fail::new(libc::EAGAIN, "No messages in the ring buffer")),
+            // Another consumer released this message after we read the pop_offset and the space was reused, thus put
+            // the header back and retry.
+            bytes if peek(self.pop_offset) != pop_offset => {
+                self.write_header(pop_offset, bytes);
+                return Err(Fail::new(libc::EAGAIN, "Concurrent pop in the ring buffer"));
+            },
             bytes if bytes <= buf_len => bytes,
             bytes => {
                 // Buffer is not big enough so put the message back in the queue.
@@ -251,14 +274,46 @@ impl ConcurrentRingBuffer {
         }
     }
 
-    /// Atomically writes a header at the indicated offset and returns the previous one.
+    /// Copies [buf] into space previously reserved at [push_offset] and commits it.
+    fn write_message(&self, push_offset: usize, buf: &[u8]) {
+        timer!("collections::concurrent_ring::write_message");
+        debug_assert!(push_offset % HEADER_SIZE == 0);
+        let len: usize = buf.len();
+        // Push first part of buffer. If longer than the capacity of the ring, wrap around.
+        let first_offset: usize = (push_offset + HEADER_SIZE) % self.capacity();
+        let first_len: usize = if first_offset + len > self.capacity() {
+            self.capacity() - first_offset
+        } else {
+            len
+        };
+        let buf_ptr: *const u8 = buf.as_ptr();
+        let ring_ptr: *mut u8 = unsafe { self.buffer.get_mut().as_mut_ptr() };
+        // Copy the data into the ring buffer.
+        unsafe {
+            copy(buf_ptr, ring_ptr.add(first_offset), first_len);
+        }
+        // If there is remaining data in the buffer, wrap around.
+        if len > first_len {
+            // Copy the data into the ring buffer.
+            unsafe {
+                copy(buf_ptr.add(first_len), ring_ptr, len - first_len);
+            }
+        }
+        // Commit the write by atomically writing the header to release the buffer. The overwritten header MUST be
+        // 0. The header describes just the length of the payload.
+        let old: usize = self.write_header(push_offset, len);
+        debug_assert_eq!(old, 0);
+    }
+
+    /// Atomically writes a header at the indicated offset and returns the previous one. Writing a header publishes
+    /// the payload that follows it, while reading a header acquires that payload.
     fn write_header(&self, offset: usize, val: usize) -> usize {
         timer!("collections::concurrent_ring::write_header");
         assert!(offset % 2 == 0);
         let buffer_ptr: *mut u8 = unsafe { self.buffer.get_mut() }.as_mut_ptr();
         let header_ptr: *mut u16 = unsafe { buffer_ptr.add(offset) } as *mut u16;
         let header: &AtomicU16 = unsafe { &*header_ptr.cast() };
-        header.swap(val as u16, atomic::Ordering::Relaxed) as usize
+        header.swap(val as u16, atomic::Ordering::AcqRel) as usize
     }
 
     /// Given a [push_offset] and [pop_offset] into the ring buffer, return available space for writing data. Always
@@ -271,27 +326,27 @@ impl ConcurrentRingBuffer {
         self.capacity() - used_space - HEADER_SIZE
     }
 
-    /// Reserves [len] + HEADER_SIZE bytes from the ring buffer. If successful, returns the offset of the beginning of
-    /// the buffer, else returns `None`.
+    /// Reserves [len] bytes from the ring buffer, which must already account for headers and alignment. If
+    /// successful, returns the offset of the beginning of the reserved space, else returns `None`. This retries while
+    /// there is enough space, so that contention among producers does not show up as a full ring buffer.
     fn reserve_space(&self, len: usize) -> Option<usize> {
         timer!("collections::concurrent_ring::reserve_space");
-        let len_: usize = align_header(len + HEADER_SIZE);
-        let push_offset: usize = peek(self.push_offset);
-        let pop_offset: usize = peek(self.pop_offset);
+        debug_assert!(len % HEADER_SIZE == 0);
+        loop {
+            let push_offset: usize = peek(self.push_offset);
+            let pop_offset: usize = peek(self.pop_offset);
 
-        if len_ > self.available_space(push_offset, pop_offset) {
-            return None;
-        }
-        let new_offset: usize = (push_offset + len_) % self.capacity();
+            if len > self.available_space(push_offset, pop_offset) {
+                return None;
+            }
+            let new_offset: usize = (push_offset + len) % self.capacity();
 
-        debug_assert_ne!(new_offset, pop_offset);
-        // Queue has space after the enqueue pointer, so try to reserve space.
-        match check_and_set(self.push_offset, push_offset, new_offset) {
-            Ok(start) => {
-                self.write_header(new_offset, 0);
-                Some(start)
-            },
-            Err(_) => None,
+            debug_assert_ne!(new_offset, pop_offset);
+            // Queue has space after the enqueue pointer, so try to reserve space. The header at new_offset is
+            // already zero, because it lies in free space.
+            if let Ok(start) = check_and_set(self.push_offset, push_offset, new_offset) {
+                return Some(start);
+            }
         }
     }
 
@@ -300,8 +355,18 @@ impl ConcurrentRingBuffer {
         timer!("collections::concurrent_ring::release_space");
         let len_: usize = align_header(len + HEADER_SIZE);
         let new_offset: usize = (current_offset + len_) % self.capacity();
-        // Ensure that the old pop_offset was what we expected. Panic if it is not.
-        check_and_set(self.pop_offset, current_offset, new_offset).unwrap();
+        // Zero out the released space, so that it can be reused. The header was already zeroed when the message was
+        // claimed.
+        let ring_ptr: *mut u8 = unsafe { self.buffer.get_mut().as_mut_ptr() };
+        let first_offset: usize = current_offset + HEADER_SIZE;
+        let first_len: usize = (len_ - HEADER_SIZE).min(self.capacity() - first_offset);
+        unsafe {
+            ptr::write_bytes(ring_ptr.add(first_offset), 0, first_len);
+            ptr::write_bytes(ring_ptr, 0, len_ - HEADER_SIZE - first_len);
+        }
+        // Ensure that the old pop_offset was what we expected. Panic if it is not. Only the consumer that claimed the
+        // message at current_offset may move the pop_offset.
+        release(self.pop_offset, current_offset, new_offset).unwrap();
     }
 
     /// Peeks the target ring buffer and checks if it is full.
@@ -373,22 +438,23 @@ impl Ring for ConcurrentRingBuffer {
         let push_offset: *mut usize = buffer_ptr as *mut usize;
         buffer_ptr = unsafe { buffer_ptr.add(SIZE_OF_USIZE) };
 
-        // Initialize enqueue and dequeue pointers only if requested.
+        let me: Self = Self {
+            push_offset,
+            pop_offset,
+            buffer: raw_array::RawArray::<u8>::from_raw_parts(buffer_ptr, capacity - size_of_ring)?,
+            is_managed: false,
+        };
+
+        // Initialize enqueue and dequeue pointers and free space only if requested. Otherwise, we would drop messages
+        // that were pushed before this ring buffer was opened.
         if init {
             unsafe {
                 *push_offset = 0;
                 *pop_offset = 0;
+                me.buffer.get_mut().fill(0);
             }
         }
 
-        let me: Self = Self {
-            push_offset,
-            pop_offset,
-            buffer: raw_array::RawArray::<u8>::from_raw_parts(buffer_ptr, capacity - size_of_ring)?,
-            is_managed: false,
-        };
-        // Intialize the header to 0.
-        me.write_header(0, 0);
         Ok(me)
     }
 }
@@ -399,13 +465,20 @@ impl Ring for ConcurrentRingBuffer {
 /// Peeks at the value at [ptr] to check various constraints.
 fn peek(ptr: *mut usize) -> usize {
     let ptr: &AtomicUsize = unsafe { &*ptr.cast() };
-    ptr.load(atomic::Ordering::Relaxed)
+    ptr.load(atomic::Ordering::Acquire)
 }
 
-/// Compares and increments the value at [ptr] only if it has not changed since the last time we read it.
+/// Compares and increments the value at [ptr] only if it has not changed since the last time we read it. This may
+/// spuriously fail, thus it should be called in a loop.
 fn check_and_set(ptr: *mut usize, current: usize, new: usize) -> Result<usize, usize> {
     let ptr: &AtomicUsize = unsafe { &*ptr.cast() };
-    ptr.compare_exchange_weak(current, new, atomic::Ordering::Acquire, atomic::Ordering::Relaxed)
+    ptr.compare_exchange_weak(current, new, atomic::Ordering::AcqRel, atomic::Ordering::Relaxed)
+}
+
+/// Moves the value at [ptr] forward, publishing all writes made before. This never spuriously fails.
+fn release(ptr: *mut usize, current: usize, new: usize) -> Result<usize, usize> {
+    let ptr: &AtomicUsize = unsafe { &*ptr.cast() };
+    ptr.compare_exchange(current, new, atomic::Ordering::Release, atomic::Ordering::Relaxed)
 }
 
 /// Align to [HEADER_SIZE] for the header offset.
@@ -747,6 +820,94 @@ mod test {
         }
     }
 
+    /// Tests if we succeed to push a batch of buffers that wraps around the ring buffer.
+    #[test]
+    fn try_push_batch() -> Result<()> {
+        let ring: ConcurrentRingBuffer = full_ring_buffer()?;
+        empty_ring_buffer(&ring)?;
+
+        // Push a batch, which wraps around, and pop it back in order.
+        let bufs: [[u8; 3]; 4] = [[1; 3], [2; 3], [3; 3], [4; 3]];
+        let batch: Vec<&[u8]> = bufs.iter().map(|buf| &buf[..]).collect();
+        match ring.try_push_batch(&batch) {
+            Ok(n) => crate::ensure_eq!(n, bufs.len()),
+            Err(e) => anyhow::bail!("should be able to push a batch: {:?}", e),
+        }
+        for expected in bufs.iter() {
+            let mut buf: [u8; 3] = [0; 3];
+            do_success_pop_bytes(&ring, &mut buf, 3)?;
+            crate::ensure_eq!(&buf, expected);
+        }
+        crate::ensure_eq!(ring.is_empty(), true);
+
+        // Fail to push a batch with an empty buffer.
+        let batch: [&[u8]; 2] = [&bufs[0], &[]];
+        if ring.try_push_batch(&batch).is_ok() {
+            anyhow::bail!("should not be able to push an empty buffer");
+        }
+
+        // Fail to push a batch that does not fit, and ensure that nothing was pushed.
+        let big: [u8; RING_BUFFER_CAPACITY / 2] = [0xff; RING_BUFFER_CAPACITY / 2];
+        let batch: [&[u8]; 2] = [&big, &big];
+        if ring.try_push_batch(&batch).is_ok() {
+            anyhow::bail!("should not be able to push a batch larger than the ring buffer");
+        }
+        crate::ensure_eq!(ring.is_empty(), true);
+
+        Ok(())
+    }
+
+    /// Tests if we succeed to push from multiple producers concurrently, without losing or corrupting messages.
+    #[test]
+    fn enqueue_dequeue_multiple_producers() -> Result<()> {
+        const NUMBER_OF_PRODUCERS: u8 = 4;
+        const NUMBER_OF_MESSAGES: usize = 4096;
+        const BUFFER_SIZE: usize = 7;
+        let ring: ConcurrentRingBuffer = do_new()?;
+
+        thread::scope(|s| -> Result<()> {
+            for tid in 0..NUMBER_OF_PRODUCERS {
+                let ring: &ConcurrentRingBuffer = &ring;
+                s.spawn(move || {
+                    for seqnum in 0..NUMBER_OF_MESSAGES {
+                        let mut buf: [u8; BUFFER_SIZE] = [seqnum as u8; BUFFER_SIZE];
+                        buf[0] = tid;
+                        // Alternate between single pushes and batches of two.
+                        if seqnum % 2 == 0 {
+                            while ring.try_push(&buf).is_err() {}
+                        } else {
+                            while ring.try_push_batch(&[&buf[..4], &buf[..]]).is_err() {}
+                        }
+                    }
+                });
+            }
+
+            // Odd messages are pushed twice in a batch, first truncated and then in full.
+            let mut next: [usize; NUMBER_OF_PRODUCERS as usize] = [0; NUMBER_OF_PRODUCERS as usize];
+            let mut npops: usize = 0;
+            while npops < NUMBER_OF_PRODUCERS as usize * NUMBER_OF_MESSAGES * 3 / 2 {
+                let mut buf: [u8; BUFFER_SIZE] = [0; BUFFER_SIZE];
+                let len: usize = match ring.try_pop(&mut buf) {
+                    Ok(len) => len,
+                    Err(_) => continue,
+                };
+                npops += 1;
+                let tid: usize = buf[0] as usize;
+                let seqnum: usize = next[tid];
+                // Check that messages of each producer are received in order and are not corrupted.
+                crate::ensure_eq!(buf[1..len].iter().all(|byte| *byte == seqnum as u8), true);
+                if seqnum % 2 == 0 || len == BUFFER_SIZE {
+                    next[tid] += 1;
+                }
+            }
+            crate::ensure_eq!(next.iter().all(|n| *n == NUMBER_OF_MESSAGES), true);
+            Ok(())
+        })?;
+
+        crate::ensure_eq!(ring.is_empty(), true);
+        Ok(())
+    }
+
     /// Tets if we succeed to sequentially enqueue and dequeue elements to/from a ring buffer.
     #[test]
     fn enqueue_dequeue_sequential() -> Result<()> {
@@ -0,0 +1,265 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! A slab whose entries live in fixed-size chunks and carry a generation counter.
+//!
+//! Growing the slab allocates a new chunk and never moves the entries of existing chunks, so inserting into a large
+//! slab does not stall to copy it. Each entry counts how many times it has been freed, so that holders of a key can
+//! tell whether the entry still stores the value that they inserted. The slab is meant to be owned by a single writer
+//! and needs no locks: lookups are an index computation and a bounds check, and inserts and removals pop from and push
+//! to a free list.
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Number of entries in a chunk.
+const CHUNK_SIZE: usize = 64;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Slab of values of type [T] that tracks the generation of every entry.
+pub struct GenerationalSlab<T> {
+    /// Chunks of entries. Once allocated, a chunk is never moved nor freed until the slab is dropped.
+    chunks: Vec<Box<[Entry<T>]>>,
+    /// Index of the first vacant entry, if any.
+    next_free: Option<usize>,
+    /// Number of occupied entries.
+    len: usize,
+}
+
+/// Entry of a slab.
+struct Entry<T> {
+    /// Number of times that the entry was freed.
+    generation: u32,
+    /// Value of the entry.
+    state: State<T>,
+}
+
+//======================================================================================================================
+// Enumerations
+//======================================================================================================================
+
+/// State of an entry.
+enum State<T> {
+    /// The entry is vacant and points to the next vacant entry, if any.
+    Vacant(Option<usize>),
+    /// The entry stores a value.
+    Occupied(T),
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl<T> GenerationalSlab<T> {
+    /// Inserts [value] and returns the index of its entry.
+    pub fn insert(&mut self, value: T) -> usize {
+        let index: usize = match self.next_free {
+            Some(index) => index,
+            None => self.grow(),
+        };
+        let entry: &mut Entry<T> = self
+            .entry_mut(index)
+            .expect("free list should point to allocated entries");
+        self.next_free = match std::mem::replace(&mut entry.state, State::Occupied(value)) {
+            State::Vacant(next_free) => next_free,
+            State::Occupied(_) => unreachable!("free list should point to vacant entries"),
+        };
+        self.len += 1;
+        index
+    }
+
+    /// Checks whether the entry at [index] stores a value.
+    pub fn contains(&self, index: usize) -> bool {
+        self.get(index).is_some()
+    }
+
+    /// Gets a reference to the value stored at [index].
+    pub fn get(&self, index: usize) -> Option<&T> {
+        match &self.entry(index)?.state {
+            State::Occupied(value) => Some(value),
+            State::Vacant(_) => None,
+        }
+    }
+
+    /// Gets a mutable reference to the value stored at [index].
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        match &mut self.entry_mut(index)?.state {
+            State::Occupied(value) => Some(value),
+            State::Vacant(_) => None,
+        }
+    }
+
+    /// Gets the generation of the entry at [index], which is bumped every time that the entry is freed.
+    pub fn generation(&self, index: usize) -> Option<u32> {
+        Some(self.entry(index)?.generation)
+    }
+
+    /// Removes the value stored at [index] and bumps the generation of its entry.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        let next_free: Option<usize> = self.next_free;
+        let entry: &mut Entry<T> = self.entry_mut(index)?;
+        if let State::Vacant(_) = entry.state {
+            return None;
+        }
+        entry.generation = entry.generation.wrapping_add(1);
+        let value: T = match std::mem::replace(&mut entry.state, State::Vacant(next_free)) {
+            State::Occupied(value) => value,
+            State::Vacant(_) => unreachable!("entry should be occupied"),
+        };
+        self.next_free = Some(index);
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Gets the number of values in the slab.
+    #[allow(unused)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Gets the number of entries that the slab can hold without growing.
+    pub fn capacity(&self) -> usize {
+        self.chunks.len() * CHUNK_SIZE
+    }
+
+    /// Gets an iterator over the indexes and values in the slab.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.chunks
+            .iter()
+            .flat_map(|chunk| chunk.iter())
+            .enumerate()
+            .filter_map(|(index, entry)| match &entry.state {
+                State::Occupied(value) => Some((index, value)),
+                State::Vacant(_) => None,
+            })
+    }
+
+    /// Removes all values from the slab and returns an iterator over them. Entries keep their chunks and have their
+    /// generations bumped.
+    pub fn drain(&mut self) -> impl Iterator<Item = T> + '_ {
+        let indexes: Vec<usize> = self.iter().map(|(index, _)| index).collect();
+        indexes.into_iter().filter_map(move |index| self.remove(index))
+    }
+
+    /// Allocates a new chunk, threads its entries onto the free list and returns the index of the first one.
+    fn grow(&mut self) -> usize {
+        let base: usize = self.capacity();
+        let chunk: Box<[Entry<T>]> = (0..CHUNK_SIZE)
+            .map(|offset| Entry {
+                generation: 0,
+                state: State::Vacant(if offset + 1 < CHUNK_SIZE {
+                    Some(base + offset + 1)
+                } else {
+                    self.next_free
+                }),
+            })
+            .collect();
+        self.chunks.push(chunk);
+        self.next_free = Some(base);
+        base
+    }
+
+    /// Gets a reference to the entry at [index].
+    fn entry(&self, index: usize) -> Option<&Entry<T>> {
+        self.chunks.get(index / CHUNK_SIZE)?.get(index % CHUNK_SIZE)
+    }
+
+    /// Gets a mutable reference to the entry at [index].
+    fn entry_mut(&mut self, index: usize) -> Option<&mut Entry<T>> {
+        self.chunks.get_mut(index / CHUNK_SIZE)?.get_mut(index % CHUNK_SIZE)
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl<T> Default for GenerationalSlab<T> {
+    fn default() -> Self {
+        Self {
+            chunks: Vec::new(),
+            next_free: None,
+            len: 0,
+        }
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::collections::generational_slab::{
+        GenerationalSlab,
+        CHUNK_SIZE,
+    };
+    use ::anyhow::Result;
+
+    #[test]
+    fn insert_get_remove_many() -> Result<()> {
+        let mut slab: GenerationalSlab<u64> = GenerationalSlab::default();
+        let indexes: Vec<usize> = (0..(4 * CHUNK_SIZE as u64)).map(|i| slab.insert(i)).collect();
+        crate::ensure_eq!(slab.len(), 4 * CHUNK_SIZE);
+        crate::ensure_eq!(slab.capacity(), 4 * CHUNK_SIZE);
+
+        for (expected, index) in indexes.iter().copied().enumerate() {
+            crate::ensure_eq!(slab.get(index), Some(&(expected as u64)));
+            crate::ensure_eq!(slab.remove(index), Some(expected as u64));
+            crate::ensure_eq!(slab.get(index), None);
+            crate::ensure_eq!(slab.remove(index), None);
+        }
+        crate::ensure_eq!(slab.len(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn remove_bumps_generation() -> Result<()> {
+        let mut slab: GenerationalSlab<u64> = GenerationalSlab::default();
+        let index: usize = slab.insert(1);
+        crate::ensure_eq!(slab.generation(index), Some(0));
+        slab.remove(index);
+        crate::ensure_eq!(slab.generation(index), Some(1));
+
+        // Freed entries are reused first.
+        crate::ensure_eq!(slab.insert(2), index);
+        crate::ensure_eq!(slab.generation(index), Some(1));
+        crate::ensure_eq!(slab.get(index), Some(&2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn growth_does_not_move_entries() -> Result<()> {
+        let mut slab: GenerationalSlab<u64> = GenerationalSlab::default();
+        let index: usize = slab.insert(0);
+        let before: *const u64 = slab.get(index).unwrap();
+        for i in 1..(8 * CHUNK_SIZE as u64) {
+            slab.insert(i);
+        }
+        crate::ensure_eq!(before, slab.get(index).unwrap() as *const u64);
+
+        Ok(())
+    }
+
+    #[test]
+    fn drain() -> Result<()> {
+        let mut slab: GenerationalSlab<u64> = GenerationalSlab::default();
+        for i in 0..10 {
+            slab.insert(i);
+        }
+        slab.remove(3);
+        let mut drained: Vec<u64> = slab.drain().collect();
+        drained.sort();
+        crate::ensure_eq!(drained, vec![0, 1, 2, 4, 5, 6, 7, 8, 9]);
+        crate::ensure_eq!(slab.len(), 0);
+        crate::ensure_eq!(slab.iter().count(), 0);
+
+        Ok(())
+    }
+}
@@ -23,7 +23,8 @@ use ::std::ops::{
 /// A ring buffer that may be shared across processes.
 ///
 /// This structure resides on a shared memory region and it is lock-free.
-/// This abstraction ensures the correct concurrent access by a single writer and a single reader.
+/// Whether several writers and readers may concurrently access it depends on the underlying ring. A
+/// [crate::collections::concurrent_ring::ConcurrentRingBuffer] supports multiple writers and multiple readers.
 pub struct SharedRingBuffer<T: Ring> {
     #[allow(unused)]
     shm: SharedMemory,
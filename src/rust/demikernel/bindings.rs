@@ -10,10 +10,14 @@ use crate::{
         name::LibOSName,
         LibOS,
     },
+    expect_ok,
     pal::{
         constants::{
             AF_INET,
             AF_INET6,
+            IPPROTO_IP,
+            IP_TOS,
+            IP_TTL,
         },
         data_structures::{
             AddressFamily,
@@ -24,14 +28,65 @@ use crate::{
         },
     },
     runtime::{
+        affinity::BindingConfig,
+        crypto::runtime::AeadKey,
         fail::Fail,
         logging,
+        network::{
+            config::{
+                IpsecDirection,
+                Route,
+                SecurityAssociation,
+            },
+            framing::{
+                length::LengthCodec,
+                line::LineCodec,
+                memcached::MemcachedCodec,
+                resp::RespCodec,
+                FrameCodec,
+            },
+            ptp::{
+                clock::{
+                    nanos_to_system_time,
+                    system_time_to_nanos,
+                },
+                PtpConfig,
+            },
+            quic::QuicConfig,
+            socket::option::SocketOption,
+            tls::TlsConfig,
+        },
         types::{
+            demi_binding_t,
+            demi_codec_t,
+            demi_drop_record_t,
+            demi_drop_stats_t,
+            demi_ipsec_sa_t,
+            demi_numa_stats_t,
+            demi_ptp_config_t,
+            demi_ptp_status_t,
             demi_qresult_t,
             demi_qtoken_t,
+            demi_quic_config_t,
+            demi_route_t,
+            demi_sgarray_batch_t,
             demi_sgarray_t,
             demi_sgaseg_t,
+            demi_tcp_event_t,
+            demi_tcp_info_t,
+            demi_tls_config_t,
+            demi_tx_timestamp_t,
+            DEMI_CODEC_LENGTH,
+            DEMI_CODEC_LINE,
+            DEMI_CODEC_MEMCACHED,
+            DEMI_CODEC_RESP,
+            DEMI_IPSEC_AES_128_GCM,
+            DEMI_IPSEC_CHACHA20_POLY1305,
+            DEMI_IPSEC_INBOUND,
+            DEMI_IPSEC_OUTBOUND,
+            DEMI_PTP_NO_NEGOTIATION,
         },
+        QDesc,
         QToken,
     },
 };
@@ -43,21 +98,89 @@ use ::libc::{
 };
 use ::socket2::SockAddr;
 use ::std::{
-    cell::RefCell,
+    cell::{
+        Cell,
+        RefCell,
+    },
     ffi::CStr,
     mem::{
         self,
         MaybeUninit,
     },
-    net::SocketAddr,
+    net::{
+        Ipv4Addr,
+        SocketAddr,
+    },
+    process,
     ptr,
     slice,
-    time::Duration,
+    time::{
+        Duration,
+        SystemTime,
+    },
+};
+
+#[cfg(target_os = "linux")]
+use crate::{
+    pal::constants::{
+        IPPROTO_TCP,
+        IP_RECVERR,
+        SOL_SOCKET,
+        SOL_UDP,
+        SO_ATTACH_FILTER,
+        SO_BINDTOIFINDEX,
+        SO_DETACH_FILTER,
+        SO_DOORBELL,
+        SO_MAX_PACING_RATE,
+        SO_NO_CHECK,
+        SO_PRIORITY,
+        SO_TIMESTAMPING,
+        SO_TXCOMPLETE,
+        TCP_INIT_CWND,
+        TCP_MD5SIG,
+        TCP_MD5SIG_EXT,
+        TCP_MD5SIG_FLAG_IFINDEX,
+        TCP_MD5SIG_FLAG_PREFIX,
+        TCP_RTO_INIT_US,
+        TCP_RTO_MAX_MS,
+        TCP_RTO_MIN_US,
+        TCP_USER_TIMEOUT,
+        UDP_SEGMENT,
+    },
+    pal::{
+        data_structures::TcpMd5Sig,
+        linux::sockaddr_to_socketaddrv4,
+    },
+    runtime::network::socket::{
+        filter::{
+            BpfInstruction,
+            SocketFilter,
+        },
+        option::TcpMd5Key,
+    },
+};
+
+#[cfg(feature = "librdma")]
+use crate::{
+    demikernel::libos::rdma::RdmaRegion,
+    runtime::types::demi_rdma_region_t,
+};
+
+#[cfg(target_os = "windows")]
+use crate::pal::functions::socketaddrv4_to_sockaddr;
+
+#[cfg(target_os = "linux")]
+use crate::pal::linux::socketaddrv4_to_sockaddr;
+
+#[cfg(target_os = "linux")]
+use ::std::{
+    fs::File,
+    mem::ManuallyDrop,
+    os::fd::FromRawFd,
 };
 
 #[cfg(test)]
 use ::std::net::{
-    Ipv4Addr,
     Ipv6Addr,
     SocketAddrV4,
     SocketAddrV6,
@@ -70,6 +193,8 @@ use ::std::net::{
 thread_local! {
 /// Demikernel state.
     static DEMIKERNEL: RefCell<Option<LibOS>> = RefCell::new(None);
+/// Process that initialized Demikernel, which tells a child created by fork apart from its parent.
+    static DEMIKERNEL_PID: Cell<u32> = Cell::new(0);
 }
 
 //======================================================================================================================
@@ -88,9 +213,18 @@ pub extern "C" fn demi_init(argc: c_int, argv: *mut *mut c_char) -> c_int {
     };
 
     // Check if demikernel has already been initialized and return
-    let ret: i32 = DEMIKERNEL.with(|demikernel| match *demikernel.borrow() {
-        Some(_) => libc::EEXIST,
-        None => 0,
+    let ret: i32 = DEMIKERNEL.with(|demikernel| {
+        let mut demikernel = demikernel.borrow_mut();
+        match demikernel.as_ref() {
+            Some(libos) if is_inherited(libos) => {
+                // The LibOS was inherited from the parent process, which still owns its resources, so it is leaked
+                // instead of being dropped and a new one is initialized for this process.
+                mem::forget(demikernel.take());
+                0
+            },
+            Some(_) => libc::EEXIST,
+            None => 0,
+        }
     });
     if ret != 0 {
         error!("demi_init(): Demikernel is already initialized");
@@ -103,6 +237,7 @@ pub extern "C" fn demi_init(argc: c_int, argv: *mut *mut c_char) -> c_int {
             DEMIKERNEL.with(move |demikernel| {
                 *demikernel.borrow_mut() = Some(libos);
             });
+            DEMIKERNEL_PID.with(|pid| pid.set(process::id()));
         },
         Err(e) => {
             trace!("demi_init() failed: {:?}", e);
@@ -113,6 +248,34 @@ pub extern "C" fn demi_init(argc: c_int, argv: *mut *mut c_char) -> c_int {
     0
 }
 
+//======================================================================================================================
+// shutdown_all
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_shutdown_all(grace: *const libc::timespec) -> c_int {
+    trace!("demi_shutdown_all() grace={:?}", grace);
+    let grace: Option<Duration> = timespec_to_duration(grace);
+
+    // Issue shutdown_all operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.shutdown_all(grace) {
+        Ok(()) => 0,
+        Err(e) => {
+            trace!("demi_shutdown_all() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => {
+            // Release the LibOS, even if it did not shut down cleanly, so that Demikernel can be initialized again.
+            DEMIKERNEL.with(|demikernel| demikernel.borrow_mut().take());
+            ret
+        },
+        Err(e) => e.errno,
+    }
+}
+
 //======================================================================================================================
 // create
 //======================================================================================================================
@@ -201,6 +364,97 @@ pub extern "C" fn demi_open_pipe(memqd_out: *mut c_int, name: *const libc::c_cha
     }
 }
 
+#[no_mangle]
+pub extern "C" fn demi_open_file(qd_out: *mut c_int, path: *const libc::c_char, flags: c_int) -> c_int {
+    trace!(
+        "demi_open_file() qd_out={:?}, path={:?}, flags={:#x}",
+        qd_out,
+        path,
+        flags
+    );
+
+    // Check for invalid storage location.
+    if qd_out.is_null() {
+        warn!("demi_open_file() qd_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Check for invalid path pointer.
+    if path.is_null() {
+        warn!("demi_open_file() path is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Convert C string to a Rust one.
+    let path: &str = match unsafe { CStr::from_ptr(path) }.to_str() {
+        Ok(s) => s,
+        Err(_) => return libc::EINVAL,
+    };
+
+    // Issue open operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.open_file(path, flags) {
+        Ok(qd) => {
+            unsafe { *qd_out = qd.into() };
+            0
+        },
+        Err(e) => {
+            trace!("demi_open_file() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+#[cfg(feature = "libspdk")]
+#[no_mangle]
+pub extern "C" fn demi_open_block(qd_out: *mut c_int, trid: *const libc::c_char, nsid: u32) -> c_int {
+    trace!(
+        "demi_open_block() qd_out={:?}, trid={:?}, nsid={:?}",
+        qd_out,
+        trid,
+        nsid
+    );
+
+    // Check for invalid storage location.
+    if qd_out.is_null() {
+        warn!("demi_open_block() qd_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Check for invalid transport ID pointer.
+    if trid.is_null() {
+        warn!("demi_open_block() trid is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Convert C string to a Rust one.
+    let trid: &str = match unsafe { CStr::from_ptr(trid) }.to_str() {
+        Ok(s) => s,
+        Err(_) => return libc::EINVAL,
+    };
+
+    // Issue open operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.open_block(trid, nsid) {
+        Ok(qd) => {
+            unsafe { *qd_out = qd.into() };
+            0
+        },
+        Err(e) => {
+            trace!("demi_open_block() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
 //======================================================================================================================
 // socket
 //======================================================================================================================
@@ -270,6 +524,37 @@ pub extern "C" fn demi_bind(qd: c_int, saddr: *const sockaddr, size: Socklen) ->
     }
 }
 
+#[no_mangle]
+pub extern "C" fn demi_bind_name(qd: c_int, name: *const libc::c_char) -> c_int {
+    trace!("demi_bind_name() qd={:?}, name={:?}", qd, name);
+
+    // Check for invalid name pointer.
+    if name.is_null() {
+        warn!("demi_bind_name() name is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Convert C string to a Rust one.
+    let name: &str = match unsafe { CStr::from_ptr(name) }.to_str() {
+        Ok(s) => s,
+        Err(_) => return libc::EINVAL,
+    };
+
+    // Issue bind operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.bind_name(qd.into(), name) {
+        Ok(..) => 0,
+        Err(e) => {
+            trace!("demi_bind_name() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
 //======================================================================================================================
 // listen
 //======================================================================================================================
@@ -304,21 +589,33 @@ pub extern "C" fn demi_listen(sockqd: c_int, backlog: c_int) -> c_int {
 
 #[no_mangle]
 pub extern "C" fn demi_accept(qtok_out: *mut demi_qtoken_t, sockqd: c_int) -> c_int {
-    trace!("demi_accept()");
+    demi_timedaccept(qtok_out, sockqd, ptr::null())
+}
+
+#[no_mangle]
+pub extern "C" fn demi_timedaccept(
+    qtok_out: *mut demi_qtoken_t,
+    sockqd: c_int,
+    timeout: *const libc::timespec,
+) -> c_int {
+    trace!("demi_timedaccept()");
 
     // Check for invalid storage location.
     if qtok_out.is_null() {
-        warn!("demi_accept() qtok_out is a null pointer");
+        warn!("demi_timedaccept() qtok_out is a null pointer");
         return libc::EINVAL;
     }
 
+    // Convert timespec to Duration.
+    let duration: Option<Duration> = timespec_to_duration(timeout);
+
     // Issue accept operation.
     let ret: Result<i32, Fail> = do_syscall(|libos| {
         unsafe {
-            *qtok_out = match libos.accept(sockqd.into()) {
+            *qtok_out = match libos.timed_accept(sockqd.into(), duration) {
                 Ok(qt) => qt.into(),
                 Err(e) => {
-                    trace!("demi_accept() failed: {:?}", e);
+                    trace!("demi_timedaccept() failed: {:?}", e);
                     return e.errno;
                 },
             }
@@ -343,11 +640,22 @@ pub extern "C" fn demi_connect(
     saddr: *const sockaddr,
     size: Socklen,
 ) -> c_int {
-    trace!("demi_connect()");
+    demi_timedconnect(qtok_out, sockqd, saddr, size, ptr::null())
+}
+
+#[no_mangle]
+pub extern "C" fn demi_timedconnect(
+    qtok_out: *mut demi_qtoken_t,
+    sockqd: c_int,
+    saddr: *const sockaddr,
+    size: Socklen,
+    timeout: *const libc::timespec,
+) -> c_int {
+    trace!("demi_timedconnect()");
 
     // Check for invalid storage location.
     if qtok_out.is_null() {
-        warn!("demi_connect() qtok_out is a null pointer");
+        warn!("demi_timedconnect() qtok_out is a null pointer");
         return libc::EINVAL;
     }
 
@@ -360,19 +668,22 @@ pub extern "C" fn demi_connect(
     let endpoint: SocketAddr = match sockaddr_to_socketaddr(saddr, size) {
         Ok(endpoint) => endpoint,
         Err(e) => {
-            trace!("demi_connect() failed: {:?}", e);
+            trace!("demi_timedconnect() failed: {:?}", e);
             return e.errno;
         },
     };
 
+    // Convert timespec to Duration.
+    let duration: Option<Duration> = timespec_to_duration(timeout);
+
     // Issue connect operation.
-    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.connect(sockqd.into(), endpoint) {
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.timed_connect(sockqd.into(), endpoint, duration) {
         Ok(qt) => {
             unsafe { *qtok_out = qt.into() };
             0
         },
         Err(e) => {
-            trace!("demi_connect() failed: {:?}", e);
+            trace!("demi_timedconnect() failed: {:?}", e);
             e.errno
         },
     });
@@ -383,19 +694,36 @@ pub extern "C" fn demi_connect(
     }
 }
 
-//======================================================================================================================
-// close
-//======================================================================================================================
-
 #[no_mangle]
-pub extern "C" fn demi_close(qd: c_int) -> c_int {
-    trace!("demi_close()");
+pub extern "C" fn demi_connect_name(qtok_out: *mut demi_qtoken_t, sockqd: c_int, name: *const libc::c_char) -> c_int {
+    trace!("demi_connect_name() sockqd={:?}, name={:?}", sockqd, name);
 
-    // Issue close operation.
-    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.close(qd.into()) {
-        Ok(..) => 0,
+    // Check for invalid storage location.
+    if qtok_out.is_null() {
+        warn!("demi_connect_name() qtok_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Check for invalid name pointer.
+    if name.is_null() {
+        warn!("demi_connect_name() name is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Convert C string to a Rust one.
+    let name: &str = match unsafe { CStr::from_ptr(name) }.to_str() {
+        Ok(s) => s,
+        Err(_) => return libc::EINVAL,
+    };
+
+    // Issue connect operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.connect_name(sockqd.into(), name) {
+        Ok(qt) => {
+            unsafe { *qtok_out = qt.into() };
+            0
+        },
         Err(e) => {
-            trace!("demi_close() failed: {:?}", e);
+            trace!("demi_connect_name() failed: {:?}", e);
             e.errno
         },
     });
@@ -407,53 +735,36 @@ pub extern "C" fn demi_close(qd: c_int) -> c_int {
 }
 
 //======================================================================================================================
-// pushto
+// tls_connect
 //======================================================================================================================
 
 #[no_mangle]
-pub extern "C" fn demi_pushto(
-    qtok_out: *mut demi_qtoken_t,
-    sockqd: c_int,
-    sga: *const demi_sgarray_t,
-    saddr: *const sockaddr,
-    size: Socklen,
-) -> c_int {
-    trace!("demi_pushto()");
+pub extern "C" fn demi_tls_connect(qd_out: *mut c_int, sockqd: c_int, config: *const demi_tls_config_t) -> c_int {
+    trace!("demi_tls_connect()");
 
     // Check for invalid storage location.
-    if qtok_out.is_null() {
-        warn!("demi_pushto() qtok_out is a null pointer");
-        return libc::EINVAL;
-    }
-
-    // Check if scatter-gather array is invalid.
-    if sga.is_null() {
-        return libc::EINVAL;
-    }
-
-    // Check if socket address is invalid.
-    if saddr.is_null() {
+    if qd_out.is_null() {
+        warn!("demi_tls_connect() qd_out is a null pointer");
         return libc::EINVAL;
     }
 
-    let sga: &demi_sgarray_t = unsafe { &*sga };
-
-    // Get socket address.
-    let endpoint: SocketAddr = match sockaddr_to_socketaddr(saddr, size) {
-        Ok(endpoint) => endpoint,
+    // Get TLS configuration.
+    let config: TlsConfig = match tls_config_from_c(config) {
+        Ok(config) => config,
         Err(e) => {
-            trace!("demi_pushto() failed: {:?}", e);
+            trace!("demi_tls_connect() failed: {:?}", e);
             return e.errno;
         },
     };
 
-    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.pushto(sockqd.into(), sga, endpoint) {
-        Ok(qt) => {
-            unsafe { *qtok_out = qt.into() };
+    // Issue tls_connect operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.tls_connect(sockqd.into(), config) {
+        Ok(qd) => {
+            unsafe { *qd_out = qd.into() };
             0
         },
         Err(e) => {
-            trace!("demi_pushto() failed: {:?}", e);
+            trace!("demi_tls_connect() failed: {:?}", e);
             e.errno
         },
     });
@@ -465,34 +776,36 @@ pub extern "C" fn demi_pushto(
 }
 
 //======================================================================================================================
-// push
+// tls_accept
 //======================================================================================================================
 
 #[no_mangle]
-pub extern "C" fn demi_push(qtok_out: *mut demi_qtoken_t, qd: c_int, sga: *const demi_sgarray_t) -> c_int {
-    trace!("demi_push()");
+pub extern "C" fn demi_tls_accept(qd_out: *mut c_int, sockqd: c_int, config: *const demi_tls_config_t) -> c_int {
+    trace!("demi_tls_accept()");
 
     // Check for invalid storage location.
-    if qtok_out.is_null() {
-        warn!("demi_push() qtok_out is a null pointer");
-        return libc::EINVAL;
-    }
-
-    // Check if scatter-gather array is invalid.
-    if sga.is_null() {
+    if qd_out.is_null() {
+        warn!("demi_tls_accept() qd_out is a null pointer");
         return libc::EINVAL;
     }
 
-    let sga: &demi_sgarray_t = unsafe { &*sga };
+    // Get TLS configuration.
+    let config: TlsConfig = match tls_config_from_c(config) {
+        Ok(config) => config,
+        Err(e) => {
+            trace!("demi_tls_accept() failed: {:?}", e);
+            return e.errno;
+        },
+    };
 
-    // Issue push operation.
-    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.push(qd.into(), sga) {
-        Ok(qt) => {
-            unsafe { *qtok_out = qt.into() };
+    // Issue tls_accept operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.tls_accept(sockqd.into(), config) {
+        Ok(qd) => {
+            unsafe { *qd_out = qd.into() };
             0
         },
         Err(e) => {
-            trace!("demi_push() failed: {:?}", e);
+            trace!("demi_tls_accept() failed: {:?}", e);
             e.errno
         },
     });
@@ -504,27 +817,27 @@ pub extern "C" fn demi_push(qtok_out: *mut demi_qtoken_t, qd: c_int, sga: *const
 }
 
 //======================================================================================================================
-// pop
+// http_connect
 //======================================================================================================================
 
 #[no_mangle]
-pub extern "C" fn demi_pop(qtok_out: *mut demi_qtoken_t, qd: c_int) -> c_int {
-    trace!("demi_pop()");
+pub extern "C" fn demi_http_connect(qd_out: *mut c_int, sockqd: c_int) -> c_int {
+    trace!("demi_http_connect()");
 
     // Check for invalid storage location.
-    if qtok_out.is_null() {
-        warn!("demi_pop() qtok_out is a null pointer");
+    if qd_out.is_null() {
+        warn!("demi_http_connect() qd_out is a null pointer");
         return libc::EINVAL;
     }
 
-    // Issue pop operation.
-    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.pop(qd.into(), None) {
-        Ok(qt) => {
-            unsafe { *qtok_out = qt.into() };
+    // Issue http_connect operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.http_connect(sockqd.into()) {
+        Ok(qd) => {
+            unsafe { *qd_out = qd.into() };
             0
         },
         Err(e) => {
-            trace!("demi_pop() failed: {:?}", e);
+            trace!("demi_http_connect() failed: {:?}", e);
             e.errno
         },
     });
@@ -536,27 +849,1652 @@ pub extern "C" fn demi_pop(qtok_out: *mut demi_qtoken_t, qd: c_int) -> c_int {
 }
 
 //======================================================================================================================
-// wait
+// http_accept
 //======================================================================================================================
 
 #[no_mangle]
-pub extern "C" fn demi_wait(qr_out: *mut demi_qresult_t, qt: demi_qtoken_t, timeout: *const libc::timespec) -> c_int {
-    trace!("demi_wait() {:?} {:?} {:?}", qr_out, qt, timeout);
+pub extern "C" fn demi_http_accept(qd_out: *mut c_int, sockqd: c_int) -> c_int {
+    trace!("demi_http_accept()");
 
-    // Check for invalid storage location for queue result.
-    if qr_out.is_null() {
-        warn!("qr_out is a null pointer");
+    // Check for invalid storage location.
+    if qd_out.is_null() {
+        warn!("demi_http_accept() qd_out is a null pointer");
         return libc::EINVAL;
     }
 
-    // Convert timespec to Duration.
-    let duration: Option<Duration> = if timeout.is_null() {
-        None
-    } else {
-        // Safety: We have to trust that our user is providing a valid timeout pointer for us to dereference.
-        Some(unsafe { Duration::new((*timeout).tv_sec as u64, (*timeout).tv_nsec as u32) })
-    };
-
+    // Issue http_accept operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.http_accept(sockqd.into()) {
+        Ok(qd) => {
+            unsafe { *qd_out = qd.into() };
+            0
+        },
+        Err(e) => {
+            trace!("demi_http_accept() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// frame
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_frame(qd_out: *mut c_int, sockqd: c_int, codec: *const demi_codec_t) -> c_int {
+    trace!("demi_frame()");
+
+    // Check for invalid storage location.
+    if qd_out.is_null() {
+        warn!("demi_frame() qd_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Get codec.
+    let codec: Box<dyn FrameCodec> = match codec_from_c(codec) {
+        Ok(codec) => codec,
+        Err(e) => {
+            trace!("demi_frame() failed: {:?}", e);
+            return e.errno;
+        },
+    };
+
+    // Issue frame operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.frame(sockqd.into(), codec) {
+        Ok(qd) => {
+            unsafe { *qd_out = qd.into() };
+            0
+        },
+        Err(e) => {
+            trace!("demi_frame() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// dtls_connect
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_dtls_connect(
+    qd_out: *mut c_int,
+    sockqd: c_int,
+    saddr: *const sockaddr,
+    size: Socklen,
+    config: *const demi_tls_config_t,
+) -> c_int {
+    trace!("demi_dtls_connect()");
+
+    // Check for invalid storage location.
+    if qd_out.is_null() {
+        warn!("demi_dtls_connect() qd_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Check if socket address is invalid.
+    if saddr.is_null() {
+        return libc::EINVAL;
+    }
+
+    // Get socket address.
+    let remote: SocketAddr = match sockaddr_to_socketaddr(saddr, size) {
+        Ok(remote) => remote,
+        Err(e) => {
+            trace!("demi_dtls_connect() failed: {:?}", e);
+            return e.errno;
+        },
+    };
+
+    // Get TLS configuration.
+    let config: TlsConfig = match tls_config_from_c(config) {
+        Ok(config) => config,
+        Err(e) => {
+            trace!("demi_dtls_connect() failed: {:?}", e);
+            return e.errno;
+        },
+    };
+
+    // Issue dtls_connect operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.dtls_connect(sockqd.into(), remote, config) {
+        Ok(qd) => {
+            unsafe { *qd_out = qd.into() };
+            0
+        },
+        Err(e) => {
+            trace!("demi_dtls_connect() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// dtls_accept
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_dtls_accept(qd_out: *mut c_int, sockqd: c_int, config: *const demi_tls_config_t) -> c_int {
+    trace!("demi_dtls_accept()");
+
+    // Check for invalid storage location.
+    if qd_out.is_null() {
+        warn!("demi_dtls_accept() qd_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Get TLS configuration.
+    let config: TlsConfig = match tls_config_from_c(config) {
+        Ok(config) => config,
+        Err(e) => {
+            trace!("demi_dtls_accept() failed: {:?}", e);
+            return e.errno;
+        },
+    };
+
+    // Issue dtls_accept operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.dtls_accept(sockqd.into(), config) {
+        Ok(qd) => {
+            unsafe { *qd_out = qd.into() };
+            0
+        },
+        Err(e) => {
+            trace!("demi_dtls_accept() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// ptp_start
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_ptp_start(config: *const demi_ptp_config_t) -> c_int {
+    trace!("demi_ptp_start()");
+
+    // Get PTP configuration.
+    let config: PtpConfig = match ptp_config_from_c(config) {
+        Ok(config) => config,
+        Err(e) => {
+            trace!("demi_ptp_start() failed: {:?}", e);
+            return e.errno;
+        },
+    };
+
+    // Issue ptp_start operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.ptp_start(config) {
+        Ok(()) => 0,
+        Err(e) => {
+            trace!("demi_ptp_start() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// ptp_stop
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_ptp_stop() -> c_int {
+    trace!("demi_ptp_stop()");
+
+    // Issue ptp_stop operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.ptp_stop() {
+        Ok(()) => 0,
+        Err(e) => {
+            trace!("demi_ptp_stop() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// ptp_gettime
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_ptp_gettime(ts_out: *mut libc::timespec) -> c_int {
+    trace!("demi_ptp_gettime() ts_out={:?}", ts_out);
+
+    // Check for invalid storage location.
+    if ts_out.is_null() {
+        warn!("demi_ptp_gettime() ts_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Issue ptp_clock operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.ptp_clock() {
+        // The clock does not know the time of the master yet.
+        Ok(clock) if !clock.is_synchronized() => libc::EAGAIN,
+        Ok(clock) => {
+            unsafe { *ts_out = system_time_to_timespec(clock.now()) };
+            0
+        },
+        Err(e) => {
+            trace!("demi_ptp_gettime() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// ptp_to_local
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_ptp_to_local(ts: *const libc::timespec, local_out: *mut libc::timespec) -> c_int {
+    trace!("demi_ptp_to_local() ts={:?}, local_out={:?}", ts, local_out);
+
+    // Check for invalid storage locations.
+    if ts.is_null() {
+        warn!("demi_ptp_to_local() ts is a null pointer");
+        return libc::EINVAL;
+    }
+    if local_out.is_null() {
+        warn!("demi_ptp_to_local() local_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Safety: We have to trust that our user is providing a valid pointer.
+    let ts: libc::timespec = unsafe { ptr::read(ts) };
+    let time: SystemTime = nanos_to_system_time((ts.tv_sec as i128) * 1_000_000_000 + (ts.tv_nsec as i128));
+
+    // Issue ptp_clock operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.ptp_clock() {
+        // The clock does not know the time of the master yet.
+        Ok(clock) if !clock.is_synchronized() => libc::EAGAIN,
+        Ok(clock) => {
+            unsafe { *local_out = system_time_to_timespec(clock.to_local(time)) };
+            0
+        },
+        Err(e) => {
+            trace!("demi_ptp_to_local() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// ptp_get_status
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_ptp_get_status(status_out: *mut demi_ptp_status_t) -> c_int {
+    trace!("demi_ptp_get_status() status_out={:?}", status_out);
+
+    // Check for invalid storage location.
+    if status_out.is_null() {
+        warn!("demi_ptp_get_status() status_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Issue ptp_clock operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.ptp_clock() {
+        Ok(clock) => {
+            unsafe { *status_out = demi_ptp_status_t::from(&clock) };
+            0
+        },
+        Err(e) => {
+            trace!("demi_ptp_get_status() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// quic_socket
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_quic_socket(qd_out: *mut c_int, sockqd: c_int, config: *const demi_quic_config_t) -> c_int {
+    trace!("demi_quic_socket()");
+
+    // Check for invalid storage location.
+    if qd_out.is_null() {
+        warn!("demi_quic_socket() qd_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Get QUIC configuration.
+    let config: QuicConfig = match quic_config_from_c(config) {
+        Ok(config) => config,
+        Err(e) => {
+            trace!("demi_quic_socket() failed: {:?}", e);
+            return e.errno;
+        },
+    };
+
+    // Issue quic_socket operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.quic_socket(sockqd.into(), config) {
+        Ok(qd) => {
+            unsafe { *qd_out = qd.into() };
+            0
+        },
+        Err(e) => {
+            trace!("demi_quic_socket() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// quic_open_stream
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_quic_open_stream(qd_out: *mut c_int, qd: c_int) -> c_int {
+    trace!("demi_quic_open_stream()");
+
+    // Check for invalid storage location.
+    if qd_out.is_null() {
+        warn!("demi_quic_open_stream() qd_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Issue quic_open_stream operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.quic_open_stream(qd.into()) {
+        Ok(qd) => {
+            unsafe { *qd_out = qd.into() };
+            0
+        },
+        Err(e) => {
+            trace!("demi_quic_open_stream() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// rdma_socket
+//======================================================================================================================
+
+#[cfg(feature = "librdma")]
+#[no_mangle]
+pub extern "C" fn demi_rdma_socket(qd_out: *mut c_int) -> c_int {
+    trace!("demi_rdma_socket()");
+
+    // Check for invalid storage location.
+    if qd_out.is_null() {
+        warn!("demi_rdma_socket() qd_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Issue rdma_socket operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.rdma_socket() {
+        Ok(qd) => {
+            unsafe { *qd_out = qd.into() };
+            0
+        },
+        Err(e) => {
+            trace!("demi_rdma_socket() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// rdma_expose
+//======================================================================================================================
+
+#[cfg(feature = "librdma")]
+#[no_mangle]
+pub extern "C" fn demi_rdma_expose(
+    region_out: *mut demi_rdma_region_t,
+    qd: c_int,
+    sga: *const demi_sgarray_t,
+) -> c_int {
+    trace!("demi_rdma_expose() qd={:?}", qd);
+
+    // Check for invalid storage location.
+    if region_out.is_null() {
+        warn!("demi_rdma_expose() region_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Check if scatter-gather array is invalid.
+    if sga.is_null() {
+        return libc::EINVAL;
+    }
+
+    let sga: &demi_sgarray_t = unsafe { &*sga };
+
+    // Issue rdma_expose operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.rdma_expose(qd.into(), sga) {
+        Ok(region) => {
+            unsafe {
+                *region_out = demi_rdma_region_t {
+                    addr: region.addr,
+                    len: region.len,
+                    rkey: region.rkey,
+                }
+            };
+            0
+        },
+        Err(e) => {
+            trace!("demi_rdma_expose() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// rdma_withdraw
+//======================================================================================================================
+
+#[cfg(feature = "librdma")]
+#[no_mangle]
+pub extern "C" fn demi_rdma_withdraw(qd: c_int, rkey: u32) -> c_int {
+    trace!("demi_rdma_withdraw() qd={:?}, rkey={:?}", qd, rkey);
+
+    // Issue rdma_withdraw operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.rdma_withdraw(qd.into(), rkey) {
+        Ok(()) => 0,
+        Err(e) => {
+            trace!("demi_rdma_withdraw() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// rdma_write
+//======================================================================================================================
+
+#[cfg(feature = "librdma")]
+#[no_mangle]
+pub extern "C" fn demi_rdma_write(
+    qtok_out: *mut demi_qtoken_t,
+    qd: c_int,
+    sga: *const demi_sgarray_t,
+    region: *const demi_rdma_region_t,
+) -> c_int {
+    trace!("demi_rdma_write() qd={:?}", qd);
+
+    // Check for invalid storage location.
+    if qtok_out.is_null() {
+        warn!("demi_rdma_write() qtok_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Check if scatter-gather array is invalid.
+    if sga.is_null() {
+        return libc::EINVAL;
+    }
+
+    let sga: &demi_sgarray_t = unsafe { &*sga };
+
+    // Get remote region.
+    let region: RdmaRegion = match region_from_c(region) {
+        Ok(region) => region,
+        Err(e) => return e.errno,
+    };
+
+    // Issue rdma_write operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.rdma_write(qd.into(), sga, region) {
+        Ok(qt) => {
+            unsafe { *qtok_out = qt.into() };
+            0
+        },
+        Err(e) => {
+            trace!("demi_rdma_write() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// rdma_read
+//======================================================================================================================
+
+#[cfg(feature = "librdma")]
+#[no_mangle]
+pub extern "C" fn demi_rdma_read(qtok_out: *mut demi_qtoken_t, qd: c_int, region: *const demi_rdma_region_t) -> c_int {
+    trace!("demi_rdma_read() qd={:?}", qd);
+
+    // Check for invalid storage location.
+    if qtok_out.is_null() {
+        warn!("demi_rdma_read() qtok_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Get remote region.
+    let region: RdmaRegion = match region_from_c(region) {
+        Ok(region) => region,
+        Err(e) => return e.errno,
+    };
+
+    // Issue rdma_read operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.rdma_read(qd.into(), region) {
+        Ok(qt) => {
+            unsafe { *qtok_out = qt.into() };
+            0
+        },
+        Err(e) => {
+            trace!("demi_rdma_read() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// ipsec_add_sa
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_ipsec_add_sa(sa: *const demi_ipsec_sa_t) -> c_int {
+    trace!("demi_ipsec_add_sa()");
+
+    // Get security association.
+    let sa: SecurityAssociation = match security_association_from_c(sa) {
+        Ok(sa) => sa,
+        Err(e) => {
+            trace!("demi_ipsec_add_sa() failed: {:?}", e);
+            return e.errno;
+        },
+    };
+
+    // Issue ipsec_add_sa operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.ipsec_add_security_association(sa) {
+        Ok(()) => 0,
+        Err(e) => {
+            trace!("demi_ipsec_add_sa() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// ipsec_remove_sa
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_ipsec_remove_sa(spi: u32, direction: u32) -> c_int {
+    trace!("demi_ipsec_remove_sa() spi={:?}, direction={:?}", spi, direction);
+
+    let direction: IpsecDirection = match ipsec_direction_from_c(direction) {
+        Ok(direction) => direction,
+        Err(e) => return e.errno,
+    };
+
+    // Issue ipsec_remove_sa operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.ipsec_remove_security_association(spi, direction) {
+        Ok(()) => 0,
+        Err(e) => {
+            trace!("demi_ipsec_remove_sa() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// route_add
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_route_add(route: *const demi_route_t) -> c_int {
+    trace!("demi_route_add()");
+
+    // Get route.
+    let route: Route = match route_from_c(route) {
+        Ok(route) => route,
+        Err(e) => {
+            trace!("demi_route_add() failed: {:?}", e);
+            return e.errno;
+        },
+    };
+
+    // Issue route_add operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.add_route(route) {
+        Ok(()) => 0,
+        Err(e) => {
+            trace!("demi_route_add() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// route_remove
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_route_remove(destination: u32, prefix_len: u32) -> c_int {
+    let destination: Ipv4Addr = Ipv4Addr::from(u32::from_be(destination));
+    trace!(
+        "demi_route_remove() destination={:?}, prefix_len={:?}",
+        destination,
+        prefix_len
+    );
+
+    let prefix_len: u8 = match u8::try_from(prefix_len) {
+        Ok(prefix_len) => prefix_len,
+        Err(_) => return libc::EINVAL,
+    };
+
+    // Issue route_remove operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.remove_route(destination, prefix_len) {
+        Ok(()) => 0,
+        Err(e) => {
+            trace!("demi_route_remove() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// set_config
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_set_config(name: *const libc::c_char, value: *const libc::c_char) -> c_int {
+    trace!("demi_set_config() name={:?}, value={:?}", name, value);
+
+    // Check for invalid pointers.
+    if name.is_null() || value.is_null() {
+        warn!("demi_set_config() name or value is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Convert C strings to Rust ones.
+    let (name, value): (&str, &str) = match (
+        unsafe { CStr::from_ptr(name) }.to_str(),
+        unsafe { CStr::from_ptr(value) }.to_str(),
+    ) {
+        (Ok(name), Ok(value)) => (name, value),
+        _ => return libc::EINVAL,
+    };
+
+    // Issue set_config operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.set_config(name, value) {
+        Ok(()) => 0,
+        Err(e) => {
+            trace!("demi_set_config() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// get_config
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_get_config(
+    name: *const libc::c_char,
+    value_out: *mut libc::c_char,
+    size: libc::size_t,
+) -> c_int {
+    trace!(
+        "demi_get_config() name={:?}, value_out={:?}, size={:?}",
+        name,
+        value_out,
+        size
+    );
+
+    // Check for invalid pointers.
+    if name.is_null() || value_out.is_null() {
+        warn!("demi_get_config() name or value_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Convert C string to a Rust one.
+    let name: &str = match unsafe { CStr::from_ptr(name) }.to_str() {
+        Ok(name) => name,
+        Err(_) => return libc::EINVAL,
+    };
+
+    // Issue get_config operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.get_config(name) {
+        // The value is returned as a null-terminated string.
+        Ok(value) if value.len() < size => {
+            unsafe {
+                ptr::copy_nonoverlapping(value.as_ptr() as *const libc::c_char, value_out, value.len());
+                *value_out.add(value.len()) = 0;
+            }
+            0
+        },
+        Ok(_) => {
+            trace!("demi_get_config() failed: value does not fit in the buffer");
+            libc::ERANGE
+        },
+        Err(e) => {
+            trace!("demi_get_config() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// reload_config
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_reload_config() -> c_int {
+    trace!("demi_reload_config()");
+
+    // Issue reload_config operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.reload_config() {
+        Ok(()) => 0,
+        Err(e) => {
+            trace!("demi_reload_config() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// dump_log
+//======================================================================================================================
+
+#[cfg(target_os = "linux")]
+#[no_mangle]
+pub extern "C" fn demi_dump_log(fd: c_int) -> c_int {
+    trace!("demi_dump_log() fd={:?}", fd);
+
+    // Check for invalid file descriptor.
+    if fd < 0 {
+        warn!("demi_dump_log() fd is not a valid file descriptor");
+        return libc::EBADF;
+    }
+
+    // The file descriptor belongs to the caller, so it must not be closed once the records are written.
+    let mut file: ManuallyDrop<File> = ManuallyDrop::new(unsafe { File::from_raw_fd(fd) });
+    match logging::dump(&mut *file) {
+        Ok(_) => 0,
+        Err(e) => {
+            trace!("demi_dump_log() failed: {:?}", e);
+            e.raw_os_error().unwrap_or(libc::EIO)
+        },
+    }
+}
+
+#[cfg(target_os = "windows")]
+#[no_mangle]
+pub extern "C" fn demi_dump_log(fd: c_int) -> c_int {
+    trace!("demi_dump_log() fd={:?}", fd);
+    warn!("demi_dump_log() is not supported on this platform");
+    libc::ENOTSUP
+}
+
+//======================================================================================================================
+// bind_engine
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_bind_engine(binding: *const demi_binding_t) -> c_int {
+    trace!("demi_bind_engine() binding={:?}", binding);
+
+    // Check for invalid binding.
+    if binding.is_null() {
+        warn!("demi_bind_engine() binding is a null pointer");
+        return libc::EINVAL;
+    }
+    let binding: BindingConfig = BindingConfig::from(unsafe { &*binding });
+
+    // Issue bind_engine operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.bind_engine(&binding) {
+        Ok(()) => 0,
+        Err(e) => {
+            trace!("demi_bind_engine() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// get_engine_binding
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_get_engine_binding(binding_out: *mut demi_binding_t) -> c_int {
+    trace!("demi_get_engine_binding() binding_out={:?}", binding_out);
+
+    // Check for invalid storage location.
+    if binding_out.is_null() {
+        warn!("demi_get_engine_binding() binding_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Issue get_engine_binding operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.get_engine_binding() {
+        Ok(binding) => {
+            unsafe { *binding_out = demi_binding_t::from(&binding) };
+            0
+        },
+        Err(e) => {
+            trace!("demi_get_engine_binding() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// get_drop_stats
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_get_drop_stats(qd: c_int, stats_out: *mut demi_drop_stats_t) -> c_int {
+    trace!("demi_get_drop_stats() qd={:?}, stats_out={:?}", qd, stats_out);
+
+    // Check for invalid storage location.
+    if stats_out.is_null() {
+        warn!("demi_get_drop_stats() stats_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Negative queue descriptors select the whole network stack.
+    let qd: Option<QDesc> = if qd < 0 { None } else { Some(qd.into()) };
+
+    // Issue get_drop_stats operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.get_drop_stats(qd) {
+        Ok(stats) => {
+            unsafe {
+                *stats_out = demi_drop_stats_t { drops: *stats.counts() };
+            }
+            0
+        },
+        Err(e) => {
+            trace!("demi_get_drop_stats() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// get_numa_stats
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_get_numa_stats(stats_out: *mut demi_numa_stats_t) -> c_int {
+    trace!("demi_get_numa_stats() stats_out={:?}", stats_out);
+
+    // Check for invalid storage location.
+    if stats_out.is_null() {
+        warn!("demi_get_numa_stats() stats_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Issue get_numa_stats operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.get_numa_stats() {
+        Ok(stats) => {
+            unsafe { *stats_out = demi_numa_stats_t::from(&stats) };
+            0
+        },
+        Err(e) => {
+            trace!("demi_get_numa_stats() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// pop_drop
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_pop_drop(qd: c_int, record_out: *mut demi_drop_record_t) -> c_int {
+    trace!("demi_pop_drop() qd={:?}, record_out={:?}", qd, record_out);
+
+    // Check for invalid storage location.
+    if record_out.is_null() {
+        warn!("demi_pop_drop() record_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Issue pop_drop operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.pop_drop_record(qd.into()) {
+        Ok(Some(record)) => {
+            unsafe {
+                *record_out = demi_drop_record_t {
+                    reason: record.reason as u32,
+                    len: u32::try_from(record.len).unwrap_or(u32::MAX),
+                    addr: match record.remote {
+                        Some(remote) => socketaddrv4_to_sockaddr(&remote),
+                        None => mem::zeroed(),
+                    },
+                };
+            }
+            0
+        },
+        // The error queue is empty.
+        Ok(None) => libc::EAGAIN,
+        Err(e) => {
+            trace!("demi_pop_drop() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// pop_tx_timestamp
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_pop_tx_timestamp(qd: c_int, timestamp_out: *mut demi_tx_timestamp_t) -> c_int {
+    trace!("demi_pop_tx_timestamp() qd={:?}, timestamp_out={:?}", qd, timestamp_out);
+
+    // Check for invalid storage location.
+    if timestamp_out.is_null() {
+        warn!("demi_pop_tx_timestamp() timestamp_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Issue pop_tx_timestamp operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.pop_tx_timestamp(qd.into()) {
+        Ok(Some(timestamp)) => {
+            unsafe {
+                *timestamp_out = demi_tx_timestamp_t::from(timestamp);
+            }
+            0
+        },
+        // No packet was stamped since the last call.
+        Ok(None) => libc::EAGAIN,
+        Err(e) => {
+            trace!("demi_pop_tx_timestamp() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// get_tcp_info
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_get_tcp_info(qd: c_int, info_out: *mut demi_tcp_info_t) -> c_int {
+    trace!("demi_get_tcp_info() qd={:?}, info_out={:?}", qd, info_out);
+
+    // Check for invalid storage location.
+    if info_out.is_null() {
+        warn!("demi_get_tcp_info() info_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Issue get_tcp_info operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.get_tcp_info(qd.into()) {
+        Ok(info) => {
+            unsafe {
+                *info_out = demi_tcp_info_t::from(info);
+            }
+            0
+        },
+        Err(e) => {
+            trace!("demi_get_tcp_info() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// get_tcp_timeline
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_get_tcp_timeline(
+    qd: c_int,
+    events_out: *mut demi_tcp_event_t,
+    capacity: libc::size_t,
+    count_out: *mut libc::size_t,
+) -> c_int {
+    trace!(
+        "demi_get_tcp_timeline() qd={:?}, events_out={:?}, capacity={:?}, count_out={:?}",
+        qd,
+        events_out,
+        capacity,
+        count_out
+    );
+
+    // Check for invalid storage locations.
+    if (events_out.is_null() && capacity > 0) || count_out.is_null() {
+        warn!("demi_get_tcp_timeline() events_out or count_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Issue get_tcp_timeline operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.get_tcp_timeline(qd.into()) {
+        // Only the most recent events are handed over if they do not fit.
+        Ok(events) => {
+            let skipped: usize = events.len().saturating_sub(capacity);
+            let count: usize = events.len() - skipped;
+            unsafe {
+                for (i, event) in events.into_iter().skip(skipped).enumerate() {
+                    *events_out.add(i) = demi_tcp_event_t::from(event);
+                }
+                *count_out = count;
+            }
+            0
+        },
+        Err(e) => {
+            trace!("demi_get_tcp_timeline() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// close
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_close(qd: c_int) -> c_int {
+    trace!("demi_close()");
+
+    // Issue close operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.close(qd.into()) {
+        Ok(..) => 0,
+        Err(e) => {
+            trace!("demi_close() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// release_pipe
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_release_pipe(memqd: c_int) -> c_int {
+    trace!("demi_release_pipe() memqd={:?}", memqd);
+
+    // Issue release_pipe operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.release_pipe(memqd.into()) {
+        Ok(()) => 0,
+        Err(e) => {
+            trace!("demi_release_pipe() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// export_queue
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_export_queue(qd: c_int, channel: c_int) -> c_int {
+    trace!("demi_export_queue() qd={:?}, channel={:?}", qd, channel);
+
+    // Issue export_queue operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.export_queue(qd.into(), channel) {
+        Ok(()) => 0,
+        Err(e) => {
+            trace!("demi_export_queue() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// import_queue
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_import_queue(qd_out: *mut c_int, channel: c_int) -> c_int {
+    trace!("demi_import_queue() qd_out={:?}, channel={:?}", qd_out, channel);
+
+    // Check for invalid storage location.
+    if qd_out.is_null() {
+        warn!("demi_import_queue() qd_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Issue import_queue operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.import_queue(channel) {
+        Ok(qd) => {
+            unsafe { *qd_out = qd.into() };
+            0
+        },
+        Err(e) => {
+            trace!("demi_import_queue() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// freeze_queue
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_freeze_queue(
+    sockqd: c_int,
+    blob_out: *mut *mut libc::c_void,
+    len_out: *mut libc::size_t,
+) -> c_int {
+    trace!(
+        "demi_freeze_queue() sockqd={:?}, blob_out={:?}, len_out={:?}",
+        sockqd,
+        blob_out,
+        len_out
+    );
+
+    // Check for invalid storage locations.
+    if blob_out.is_null() || len_out.is_null() {
+        warn!("demi_freeze_queue() blob_out or len_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Issue freeze_queue operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.freeze_queue(sockqd.into()) {
+        // The blob is handed over in memory that the caller releases with free().
+        Ok(blob) => {
+            let ptr: *mut libc::c_void = unsafe { libc::malloc(blob.len().max(1)) };
+            if ptr.is_null() {
+                warn!("demi_freeze_queue() failed: cannot allocate the blob");
+                return libc::ENOMEM;
+            }
+            unsafe {
+                ptr::copy_nonoverlapping(blob.as_ptr(), ptr as *mut u8, blob.len());
+                *blob_out = ptr;
+                *len_out = blob.len();
+            }
+            0
+        },
+        Err(e) => {
+            trace!("demi_freeze_queue() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// thaw_queue
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_thaw_queue(qd_out: *mut c_int, blob: *const libc::c_void, len: libc::size_t) -> c_int {
+    trace!("demi_thaw_queue() qd_out={:?}, blob={:?}, len={:?}", qd_out, blob, len);
+
+    // Check for invalid pointers.
+    if qd_out.is_null() || blob.is_null() {
+        warn!("demi_thaw_queue() qd_out or blob is a null pointer");
+        return libc::EINVAL;
+    }
+
+    let blob: &[u8] = unsafe { slice::from_raw_parts(blob as *const u8, len) };
+
+    // Issue thaw_queue operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.thaw_queue(blob) {
+        Ok(qd) => {
+            unsafe { *qd_out = qd.into() };
+            0
+        },
+        Err(e) => {
+            trace!("demi_thaw_queue() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// pushto
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_pushto(
+    qtok_out: *mut demi_qtoken_t,
+    sockqd: c_int,
+    sga: *const demi_sgarray_t,
+    saddr: *const sockaddr,
+    size: Socklen,
+) -> c_int {
+    demi_timedpushto(qtok_out, sockqd, sga, saddr, size, ptr::null())
+}
+
+#[no_mangle]
+pub extern "C" fn demi_timedpushto(
+    qtok_out: *mut demi_qtoken_t,
+    sockqd: c_int,
+    sga: *const demi_sgarray_t,
+    saddr: *const sockaddr,
+    size: Socklen,
+    timeout: *const libc::timespec,
+) -> c_int {
+    trace!("demi_timedpushto()");
+
+    // Check for invalid storage location.
+    if qtok_out.is_null() {
+        warn!("demi_timedpushto() qtok_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Check if scatter-gather array is invalid.
+    if sga.is_null() {
+        return libc::EINVAL;
+    }
+
+    // Check if socket address is invalid.
+    if saddr.is_null() {
+        return libc::EINVAL;
+    }
+
+    let sga: &demi_sgarray_t = unsafe { &*sga };
+
+    // Get socket address.
+    let endpoint: SocketAddr = match sockaddr_to_socketaddr(saddr, size) {
+        Ok(endpoint) => endpoint,
+        Err(e) => {
+            trace!("demi_timedpushto() failed: {:?}", e);
+            return e.errno;
+        },
+    };
+
+    // Convert timespec to Duration.
+    let duration: Option<Duration> = timespec_to_duration(timeout);
+
+    let ret: Result<i32, Fail> = do_syscall(
+        |libos| match libos.timed_pushto(sockqd.into(), sga, endpoint, duration) {
+            Ok(qt) => {
+                unsafe { *qtok_out = qt.into() };
+                0
+            },
+            Err(e) => {
+                trace!("demi_timedpushto() failed: {:?}", e);
+                e.errno
+            },
+        },
+    );
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// push
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_push(qtok_out: *mut demi_qtoken_t, qd: c_int, sga: *const demi_sgarray_t) -> c_int {
+    demi_timedpush(qtok_out, qd, sga, ptr::null())
+}
+
+#[no_mangle]
+pub extern "C" fn demi_timedpush(
+    qtok_out: *mut demi_qtoken_t,
+    qd: c_int,
+    sga: *const demi_sgarray_t,
+    timeout: *const libc::timespec,
+) -> c_int {
+    trace!("demi_timedpush()");
+
+    // Check for invalid storage location.
+    if qtok_out.is_null() {
+        warn!("demi_timedpush() qtok_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Check if scatter-gather array is invalid.
+    if sga.is_null() {
+        return libc::EINVAL;
+    }
+
+    let sga: &demi_sgarray_t = unsafe { &*sga };
+
+    // Convert timespec to Duration.
+    let duration: Option<Duration> = timespec_to_duration(timeout);
+
+    // Issue push operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.timed_push(qd.into(), sga, duration) {
+        Ok(qt) => {
+            unsafe { *qtok_out = qt.into() };
+            0
+        },
+        Err(e) => {
+            trace!("demi_timedpush() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// splice
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_splice(qtok_out: *mut demi_qtoken_t, sockqd: c_int, srcqd: c_int, size: libc::size_t) -> c_int {
+    demi_timedsplice(qtok_out, sockqd, srcqd, size, ptr::null())
+}
+
+#[no_mangle]
+pub extern "C" fn demi_timedsplice(
+    qtok_out: *mut demi_qtoken_t,
+    sockqd: c_int,
+    srcqd: c_int,
+    size: libc::size_t,
+    timeout: *const libc::timespec,
+) -> c_int {
+    trace!(
+        "demi_timedsplice() sockqd={:?}, srcqd={:?}, size={:?}",
+        sockqd,
+        srcqd,
+        size
+    );
+
+    // Check for invalid storage location.
+    if qtok_out.is_null() {
+        warn!("demi_timedsplice() qtok_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Convert timespec to Duration.
+    let duration: Option<Duration> = timespec_to_duration(timeout);
+
+    // Issue splice operation.
+    let ret: Result<i32, Fail> =
+        do_syscall(
+            |libos| match libos.timed_splice(sockqd.into(), srcqd.into(), size, duration) {
+                Ok(qt) => {
+                    unsafe { *qtok_out = qt.into() };
+                    0
+                },
+                Err(e) => {
+                    trace!("demi_timedsplice() failed: {:?}", e);
+                    e.errno
+                },
+            },
+        );
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// pop
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_pop(qtok_out: *mut demi_qtoken_t, qd: c_int) -> c_int {
+    demi_timedpop(qtok_out, qd, ptr::null())
+}
+
+#[no_mangle]
+pub extern "C" fn demi_timedpop(qtok_out: *mut demi_qtoken_t, qd: c_int, timeout: *const libc::timespec) -> c_int {
+    trace!("demi_timedpop()");
+
+    // Check for invalid storage location.
+    if qtok_out.is_null() {
+        warn!("demi_timedpop() qtok_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Convert timespec to Duration.
+    let duration: Option<Duration> = timespec_to_duration(timeout);
+
+    // Issue pop operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.timed_pop(qd.into(), None, duration) {
+        Ok(qt) => {
+            unsafe { *qtok_out = qt.into() };
+            0
+        },
+        Err(e) => {
+            trace!("demi_timedpop() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// pop_many
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_pop_many(qtok_out: *mut demi_qtoken_t, qd: c_int, max: u32) -> c_int {
+    demi_timedpop_many(qtok_out, qd, max, ptr::null())
+}
+
+#[no_mangle]
+pub extern "C" fn demi_timedpop_many(
+    qtok_out: *mut demi_qtoken_t,
+    qd: c_int,
+    max: u32,
+    timeout: *const libc::timespec,
+) -> c_int {
+    trace!("demi_timedpop_many()");
+
+    // Check for invalid storage location.
+    if qtok_out.is_null() {
+        warn!("demi_timedpop_many() qtok_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Convert timespec to Duration.
+    let duration: Option<Duration> = timespec_to_duration(timeout);
+
+    // Issue pop operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.timed_pop_many(qd.into(), max as usize, duration) {
+        Ok(qt) => {
+            unsafe { *qtok_out = qt.into() };
+            0
+        },
+        Err(e) => {
+            trace!("demi_timedpop_many() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// wait
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_wait(qr_out: *mut demi_qresult_t, qt: demi_qtoken_t, timeout: *const libc::timespec) -> c_int {
+    trace!("demi_wait() {:?} {:?} {:?}", qr_out, qt, timeout);
+
+    // Check for invalid storage location for queue result.
+    if qr_out.is_null() {
+        warn!("qr_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Convert timespec to Duration.
+    let duration: Option<Duration> = timespec_to_duration(timeout);
+
     // Issue wait operation.
     let ret: Result<i32, Fail> = do_syscall(|libos| match libos.wait(qt.into(), duration) {
         Ok(r) => {
@@ -611,12 +2549,7 @@ pub extern "C" fn demi_wait_any(
     let qts: &[QToken] = unsafe { slice::from_raw_parts(qts as *const QToken, num_qts as usize) };
 
     // Convert timespec to Duration.
-    let duration: Option<Duration> = if timeout.is_null() {
-        None
-    } else {
-        // Safety: We have to trust that our user is providing a valid timeout pointer for us to dereference.
-        Some(unsafe { Duration::new((*timeout).tv_sec as u64, (*timeout).tv_nsec as u32) })
-    };
+    let duration: Option<Duration> = timespec_to_duration(timeout);
 
     // Issue wait_any operation.
     let ret: Result<i32, Fail> = do_syscall(|libos| match libos.wait_any(&qts, duration) {
@@ -669,12 +2602,7 @@ pub extern "C" fn demi_wait_next_n(
     }
 
     // Convert timespec to Duration.
-    let duration: Option<Duration> = if timeout.is_null() {
-        None
-    } else {
-        // Safety: We have to trust that our user is providing a valid timeout pointer for us to dereference.
-        Some(unsafe { Duration::new((*timeout).tv_sec as u64, (*timeout).tv_nsec as u32) })
-    };
+    let duration: Option<Duration> = timespec_to_duration(timeout);
 
     let out_slice: &mut [MaybeUninit<demi_qresult_t>] =
         unsafe { slice::from_raw_parts_mut(qr_out.cast(), qr_out_size as usize) };
@@ -701,6 +2629,61 @@ pub extern "C" fn demi_wait_next_n(
         Err(e) => e.errno,
     }
 }
+
+//======================================================================================================================
+// cancel
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_cancel(qt: demi_qtoken_t) -> c_int {
+    trace!("demi_cancel() {:?}", qt);
+
+    // Issue cancel operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.cancel(qt.into()) {
+        Ok(()) => 0,
+        Err(e) => {
+            trace!("demi_cancel() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
+//======================================================================================================================
+// get_eventfd
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_get_eventfd(fd_out: *mut c_int) -> c_int {
+    trace!("demi_get_eventfd()");
+
+    // Check for invalid storage location.
+    if fd_out.is_null() {
+        warn!("demi_get_eventfd() fd_out is a null pointer");
+        return libc::EINVAL;
+    }
+
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.get_event_fd() {
+        Ok(fd) => {
+            unsafe { *fd_out = fd };
+            0
+        },
+        Err(e) => {
+            trace!("demi_get_eventfd() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
 //======================================================================================================================
 // sgaalloc
 //======================================================================================================================
@@ -718,6 +2701,7 @@ pub extern "C" fn demi_sgaalloc(size: libc::size_t) -> demi_sgarray_t {
                 sgaseg_len: 0,
             }; 1],
             sga_addr: unsafe { mem::zeroed() },
+            sga_ancillary: unsafe { mem::zeroed() },
         }
     };
 
@@ -769,6 +2753,34 @@ pub extern "C" fn demi_sgafree(sga: *mut demi_sgarray_t) -> c_int {
     }
 }
 
+//======================================================================================================================
+// sgafree_batch
+//======================================================================================================================
+
+#[no_mangle]
+pub extern "C" fn demi_sgafree_batch(batch: *mut demi_sgarray_batch_t) -> c_int {
+    trace!("demi_sgafree_batch()");
+
+    // Check if batch is invalid.
+    if batch.is_null() {
+        return libc::EINVAL;
+    }
+
+    // Issue sgafree_batch operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.sgafree_batch(unsafe { *batch }) {
+        Ok(()) => 0,
+        Err(e) => {
+            trace!("demi_sgafree_batch() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
+}
+
 //======================================================================================================================
 // getsockname
 //======================================================================================================================
@@ -784,7 +2796,6 @@ pub extern "C" fn demi_getsockname(qd: c_int, saddr: *mut sockaddr, size: *mut S
 // setsockopt
 //======================================================================================================================
 
-#[allow(unused)]
 #[no_mangle]
 pub extern "C" fn demi_setsockopt(
     qd: c_int,
@@ -793,15 +2804,94 @@ pub extern "C" fn demi_setsockopt(
     optval: *const c_void,
     optlen: Socklen,
 ) -> c_int {
-    // TODO: Implement this system call.
-    libc::ENOSYS
+    trace!(
+        "demi_setsockopt() qd={:?}, level={:?}, optname={:?}",
+        qd,
+        level,
+        optname
+    );
+
+    // Check for invalid option value.
+    if optval.is_null() {
+        warn!("demi_setsockopt() optval is a null pointer");
+        return libc::EINVAL;
+    }
+
+    // Receive filters take a program instead of an integer value.
+    #[cfg(target_os = "linux")]
+    if level == SOL_SOCKET && (optname == SO_ATTACH_FILTER || optname == SO_DETACH_FILTER) {
+        let filter: Option<SocketFilter> = if optname == SO_ATTACH_FILTER {
+            match sockopt_to_socket_filter(optval, optlen) {
+                Ok(filter) => Some(filter),
+                Err(e) => return e.errno,
+            }
+        } else {
+            None
+        };
+        let ret: Result<i32, Fail> = do_syscall(|libos| match libos.set_receive_filter(qd.into(), filter) {
+            Ok(()) => 0,
+            Err(e) => {
+                trace!("demi_setsockopt() failed: {:?}", e);
+                e.errno
+            },
+        });
+        return match ret {
+            Ok(ret) => ret,
+            Err(e) => e.errno,
+        };
+    }
+
+    // Signature keys take a structure instead of an integer value.
+    #[cfg(target_os = "linux")]
+    if level == IPPROTO_TCP && (optname == TCP_MD5SIG || optname == TCP_MD5SIG_EXT) {
+        let option: SocketOption = match sockopt_to_tcp_md5_key(optname, optval, optlen) {
+            Ok(key) => SocketOption::TCP_MD5SIG(key),
+            Err(e) => return e.errno,
+        };
+        let ret: Result<i32, Fail> = do_syscall(|libos| match libos.set_socket_option(qd.into(), option) {
+            Ok(()) => 0,
+            Err(e) => {
+                trace!("demi_setsockopt() failed: {:?}", e);
+                e.errno
+            },
+        });
+        return match ret {
+            Ok(ret) => ret,
+            Err(e) => e.errno,
+        };
+    }
+
+    if (optlen as usize) < mem::size_of::<c_int>() {
+        warn!("demi_setsockopt() optlen is too small (optlen={:?})", optlen);
+        return libc::EINVAL;
+    }
+
+    // Safety: We have to trust that our user is providing a valid pointer to an integer option value.
+    let value: c_int = unsafe { ptr::read_unaligned(optval as *const c_int) };
+    let option: SocketOption = match sockopt_to_socket_option(level, optname, value) {
+        Ok(option) => option,
+        Err(e) => return e.errno,
+    };
+
+    // Issue setsockopt operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.set_socket_option(qd.into(), option) {
+        Ok(()) => 0,
+        Err(e) => {
+            trace!("demi_setsockopt() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
 }
 
 //======================================================================================================================
 // getsockopt
 //======================================================================================================================
 
-#[allow(unused)]
 #[no_mangle]
 pub extern "C" fn demi_getsockopt(
     qd: c_int,
@@ -810,8 +2900,73 @@ pub extern "C" fn demi_getsockopt(
     optval: *mut c_void,
     optlen: *mut Socklen,
 ) -> c_int {
-    // TODO: Implement this system call.
-    libc::ENOSYS
+    trace!(
+        "demi_getsockopt() qd={:?}, level={:?}, optname={:?}",
+        qd,
+        level,
+        optname
+    );
+
+    // Check for invalid storage locations.
+    if optval.is_null() || optlen.is_null() {
+        warn!("demi_getsockopt() optval or optlen is a null pointer");
+        return libc::EINVAL;
+    }
+    // Safety: We have to trust that our user is providing a valid pointer to the length of the option value.
+    if (unsafe { *optlen } as usize) < mem::size_of::<c_int>() {
+        warn!("demi_getsockopt() optlen is too small");
+        return libc::EINVAL;
+    }
+
+    // The value is only used to select the option.
+    let option: SocketOption = match sockopt_to_socket_option(level, optname, 0) {
+        Ok(option) => option,
+        Err(e) => return e.errno,
+    };
+
+    // Issue getsockopt operation.
+    let ret: Result<i32, Fail> = do_syscall(|libos| match libos.get_socket_option(qd.into(), option) {
+        Ok(option) => {
+            let value: c_int = match option {
+                SocketOption::IP_TOS(tos) => tos as c_int,
+                SocketOption::IP_TTL(ttl) => ttl as c_int,
+                SocketOption::SO_PRIORITY(priority) => priority as c_int,
+                // Rates that do not fit are reported as unlimited, like Linux does.
+                SocketOption::SO_MAX_PACING_RATE(rate) => u32::try_from(rate).unwrap_or(u32::MAX) as c_int,
+                SocketOption::IP_RECVERR(recv_err) => recv_err as c_int,
+                SocketOption::SO_NO_CHECK(no_check) => no_check as c_int,
+                SocketOption::SO_TXCOMPLETE(tx_complete) => tx_complete as c_int,
+                SocketOption::SO_DOORBELL(doorbell) => doorbell as c_int,
+                SocketOption::UDP_SEGMENT(size) => size as c_int,
+                SocketOption::TCP_INIT_CWND(value)
+                | SocketOption::TCP_RTO_INIT_US(value)
+                | SocketOption::TCP_RTO_MIN_US(value)
+                | SocketOption::TCP_RTO_MAX_MS(value)
+                | SocketOption::TCP_USER_TIMEOUT(value)
+                | SocketOption::SO_TIMESTAMPING(value)
+                | SocketOption::SO_BINDTODEVICE(value) => value as c_int,
+                // Keys cannot be read back.
+                SocketOption::TCP_MD5SIG(_)
+                | SocketOption::TCP_AO_ADD_KEY(_)
+                | SocketOption::TCP_AO_DEL_KEY(_)
+                | SocketOption::TCP_AO_INFO(_) => return libc::ENOPROTOOPT,
+            };
+            unsafe {
+                ptr::write_unaligned(optval as *mut c_int, value);
+                *optlen = mem::size_of::<c_int>() as Socklen;
+            }
+            0
+        },
+        Err(e) => {
+            trace!("demi_getsockopt() failed: {:?}", e);
+            e.errno
+        },
+    });
+
+    match ret {
+        Ok(ret) => ret,
+        Err(e) => e.errno,
+    }
 }
 
 //======================================================================================================================
@@ -822,6 +2977,10 @@ pub extern "C" fn demi_getsockopt(
 fn do_syscall<T>(f: impl FnOnce(&mut LibOS) -> T) -> Result<T, Fail> {
     DEMIKERNEL.with(|demikernel| match demikernel.try_borrow_mut() {
         Ok(mut libos) => match libos.as_mut() {
+            Some(libos) if is_inherited(libos) => Err(Fail::new(
+                libc::ENOTSUP,
+                "Demikernel was initialized by the parent of this process, call demi_init() to initialize it again",
+            )),
             Some(libos) => Ok(f(libos)),
             None => Err(Fail::new(libc::ENOSYS, "Demikernel is not initialized")),
         },
@@ -829,6 +2988,349 @@ fn do_syscall<T>(f: impl FnOnce(&mut LibOS) -> T) -> Result<T, Fail> {
     })
 }
 
+/// Checks whether [libos] was inherited across fork from the process that initialized it and cannot be used in this one.
+fn is_inherited(libos: &LibOS) -> bool {
+    !libos.survives_fork() && DEMIKERNEL_PID.with(|pid| pid.get()) != process::id()
+}
+
+/// Converts an optional [timeout] into a [Duration]. A null pointer means that there is no timeout.
+fn timespec_to_duration(timeout: *const libc::timespec) -> Option<Duration> {
+    if timeout.is_null() {
+        None
+    } else {
+        // Safety: We have to trust that our user is providing a valid timeout pointer for us to dereference.
+        Some(unsafe { Duration::new((*timeout).tv_sec as u64, (*timeout).tv_nsec as u32) })
+    }
+}
+
+/// Converts a socket option, given by its [level] and [optname], with integer value [value] into a [SocketOption].
+fn sockopt_to_socket_option(level: c_int, optname: c_int, value: c_int) -> Result<SocketOption, Fail> {
+    let option: Option<SocketOption> = match (level, optname) {
+        (IPPROTO_IP, IP_TOS) => u8::try_from(value).ok().map(SocketOption::IP_TOS),
+        (IPPROTO_IP, IP_TTL) => u8::try_from(value).ok().map(SocketOption::IP_TTL),
+        #[cfg(target_os = "linux")]
+        (IPPROTO_IP, IP_RECVERR) => Some(SocketOption::IP_RECVERR(value != 0)),
+        #[cfg(target_os = "linux")]
+        (SOL_SOCKET, SO_NO_CHECK) => Some(SocketOption::SO_NO_CHECK(value != 0)),
+        #[cfg(target_os = "linux")]
+        (SOL_UDP, UDP_SEGMENT) => u16::try_from(value).ok().map(SocketOption::UDP_SEGMENT),
+        #[cfg(target_os = "linux")]
+        (SOL_SOCKET, SO_TIMESTAMPING) => Some(SocketOption::SO_TIMESTAMPING(value as u32)),
+        // Interfaces are given by their index in the stack, which starts from one for the primary interface.
+        #[cfg(target_os = "linux")]
+        (SOL_SOCKET, SO_BINDTOIFINDEX) => u32::try_from(value).ok().map(SocketOption::SO_BINDTODEVICE),
+        #[cfg(target_os = "linux")]
+        (SOL_SOCKET, SO_TXCOMPLETE) => Some(SocketOption::SO_TXCOMPLETE(value != 0)),
+        #[cfg(target_os = "linux")]
+        (SOL_SOCKET, SO_DOORBELL) => Some(SocketOption::SO_DOORBELL(value != 0)),
+        #[cfg(target_os = "linux")]
+        (SOL_SOCKET, SO_PRIORITY) => u8::try_from(value).ok().map(SocketOption::SO_PRIORITY),
+        // The rate is an unsigned integer, in which all ones lifts the limit.
+        #[cfg(target_os = "linux")]
+        (SOL_SOCKET, SO_MAX_PACING_RATE) => Some(SocketOption::SO_MAX_PACING_RATE(match value as u32 {
+            u32::MAX => u64::MAX,
+            rate => rate as u64,
+        })),
+        #[cfg(target_os = "linux")]
+        (IPPROTO_TCP, TCP_INIT_CWND) => u32::try_from(value).ok().map(SocketOption::TCP_INIT_CWND),
+        #[cfg(target_os = "linux")]
+        (IPPROTO_TCP, TCP_RTO_INIT_US) => u32::try_from(value).ok().map(SocketOption::TCP_RTO_INIT_US),
+        #[cfg(target_os = "linux")]
+        (IPPROTO_TCP, TCP_RTO_MIN_US) => u32::try_from(value).ok().map(SocketOption::TCP_RTO_MIN_US),
+        #[cfg(target_os = "linux")]
+        (IPPROTO_TCP, TCP_RTO_MAX_MS) => u32::try_from(value).ok().map(SocketOption::TCP_RTO_MAX_MS),
+        #[cfg(target_os = "linux")]
+        (IPPROTO_TCP, TCP_USER_TIMEOUT) => u32::try_from(value).ok().map(SocketOption::TCP_USER_TIMEOUT),
+        _ => {
+            let cause: String = format!("unsupported socket option (level={:?}, optname={:?})", level, optname);
+            warn!("sockopt_to_socket_option(): {}", cause);
+            return Err(Fail::new(libc::ENOPROTOOPT, &cause));
+        },
+    };
+    match option {
+        Some(option) => Ok(option),
+        None => {
+            let cause: String = format!("invalid socket option value (optname={:?}, value={:?})", optname, value);
+            warn!("sockopt_to_socket_option(): {}", cause);
+            Err(Fail::new(libc::EINVAL, &cause))
+        },
+    }
+}
+
+/// Converts the `struct sock_fprog` that is pointed to by [optval] into a [SocketFilter].
+#[cfg(target_os = "linux")]
+fn sockopt_to_socket_filter(optval: *const c_void, optlen: Socklen) -> Result<SocketFilter, Fail> {
+    if (optlen as usize) < mem::size_of::<libc::sock_fprog>() {
+        let cause: String = format!("optlen is too small for a filter program (optlen={:?})", optlen);
+        warn!("sockopt_to_socket_filter(): {}", cause);
+        return Err(Fail::new(libc::EINVAL, &cause));
+    }
+
+    // Safety: We have to trust that our user is providing a valid pointer to a filter program.
+    let fprog: libc::sock_fprog = unsafe { ptr::read_unaligned(optval as *const libc::sock_fprog) };
+    if fprog.filter.is_null() {
+        let cause: &str = "filter program is a null pointer";
+        warn!("sockopt_to_socket_filter(): {}", cause);
+        return Err(Fail::new(libc::EINVAL, cause));
+    }
+    let program: &[libc::sock_filter] = unsafe { slice::from_raw_parts(fprog.filter, fprog.len as usize) };
+    let program: Vec<BpfInstruction> = program
+        .iter()
+        .map(|insn| BpfInstruction::jump(insn.code, insn.k, insn.jt, insn.jf))
+        .collect();
+    SocketFilter::new(&program)
+}
+
+/// Converts the `struct tcp_md5sig` that is pointed to by [optval] into a [TcpMd5Key]. Keys that are set with
+/// TCP_MD5SIG are shared with a single peer, while those that are set with TCP_MD5SIG_EXT may be shared with a prefix.
+#[cfg(target_os = "linux")]
+fn sockopt_to_tcp_md5_key(optname: c_int, optval: *const c_void, optlen: Socklen) -> Result<TcpMd5Key, Fail> {
+    if (optlen as usize) < mem::size_of::<TcpMd5Sig>() {
+        let cause: String = format!("optlen is too small for a signature key (optlen={:?})", optlen);
+        warn!("sockopt_to_tcp_md5_key(): {}", cause);
+        return Err(Fail::new(libc::EINVAL, &cause));
+    }
+
+    // Safety: We have to trust that our user is providing a valid pointer to a signature key.
+    let sig: TcpMd5Sig = unsafe { ptr::read_unaligned(optval as *const TcpMd5Sig) };
+    let saddr: &libc::sockaddr =
+        unsafe { &*(&sig.tcpm_addr as *const libc::sockaddr_storage as *const libc::sockaddr) };
+    let addr: Ipv4Addr = match sockaddr_to_socketaddrv4(saddr) {
+        Some(addr) => *addr.ip(),
+        None => {
+            let cause: &str = "signature keys are only supported for IPv4 peers";
+            warn!("sockopt_to_tcp_md5_key(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, cause));
+        },
+    };
+    if optname == TCP_MD5SIG_EXT && sig.tcpm_flags & TCP_MD5SIG_FLAG_IFINDEX != 0 {
+        let cause: &str = "signature keys cannot be bound to an interface";
+        warn!("sockopt_to_tcp_md5_key(): {}", cause);
+        return Err(Fail::new(libc::EINVAL, cause));
+    }
+    let prefix_len: u8 = if optname == TCP_MD5SIG_EXT && sig.tcpm_flags & TCP_MD5SIG_FLAG_PREFIX != 0 {
+        sig.tcpm_prefixlen
+    } else {
+        32
+    };
+    let key: &[u8] = match sig.tcpm_key.get(..sig.tcpm_keylen as usize) {
+        Some(key) => key,
+        None => {
+            let cause: String = format!("signature key is too long (len={})", sig.tcpm_keylen);
+            warn!("sockopt_to_tcp_md5_key(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        },
+    };
+    TcpMd5Key::new(addr, prefix_len, key)
+}
+
+/// Converts the [demi_tls_config_t] that is pointed to by [config] into a [TlsConfig].
+fn tls_config_from_c(config: *const demi_tls_config_t) -> Result<TlsConfig, Fail> {
+    if config.is_null() {
+        let cause: &str = "TLS configuration is a null pointer";
+        warn!("tls_config_from_c(): {}", cause);
+        return Err(Fail::new(libc::EINVAL, cause));
+    }
+
+    // Safety: We have to trust that our user is providing valid pointers to the identity and the key.
+    let config: demi_tls_config_t = unsafe { ptr::read(config) };
+    if config.psk_identity.is_null() || config.psk.is_null() {
+        let cause: &str = "pre-shared key or its identity is a null pointer";
+        warn!("tls_config_from_c(): {}", cause);
+        return Err(Fail::new(libc::EINVAL, cause));
+    }
+    let psk_identity: &[u8] = unsafe { slice::from_raw_parts(config.psk_identity, config.psk_identity_len) };
+    let psk: &[u8] = unsafe { slice::from_raw_parts(config.psk, config.psk_len) };
+    TlsConfig::new(psk_identity, psk)
+}
+
+/// Converts the [demi_codec_t] that is pointed to by [codec] into a [FrameCodec].
+fn codec_from_c(codec: *const demi_codec_t) -> Result<Box<dyn FrameCodec>, Fail> {
+    if codec.is_null() {
+        let cause: &str = "codec is a null pointer";
+        warn!("codec_from_c(): {}", cause);
+        return Err(Fail::new(libc::EINVAL, cause));
+    }
+
+    // Safety: We have to trust that our user is providing a valid pointer.
+    let codec: demi_codec_t = unsafe { ptr::read(codec) };
+    match codec.codec_type {
+        DEMI_CODEC_LENGTH => Ok(Box::new(LengthCodec::new(
+            codec.length_size as usize,
+            codec.little_endian != 0,
+        )?)),
+        DEMI_CODEC_LINE => Ok(Box::new(LineCodec::new(codec.delimiter))),
+        DEMI_CODEC_RESP => Ok(Box::new(RespCodec::new())),
+        DEMI_CODEC_MEMCACHED => Ok(Box::new(MemcachedCodec::new())),
+        codec_type => {
+            let cause: String = format!("unknown codec (codec_type={:?})", codec_type);
+            warn!("codec_from_c(): {}", cause);
+            Err(Fail::new(libc::EINVAL, &cause))
+        },
+    }
+}
+
+/// Converts the [demi_rdma_region_t] that is pointed to by [region] into an [RdmaRegion].
+#[cfg(feature = "librdma")]
+fn region_from_c(region: *const demi_rdma_region_t) -> Result<RdmaRegion, Fail> {
+    if region.is_null() {
+        let cause: &str = "region is a null pointer";
+        warn!("region_from_c(): {}", cause);
+        return Err(Fail::new(libc::EINVAL, cause));
+    }
+    let region: &demi_rdma_region_t = unsafe { &*region };
+    Ok(RdmaRegion {
+        addr: region.addr,
+        len: region.len,
+        rkey: region.rkey,
+    })
+}
+
+/// Converts the [demi_quic_config_t] that is pointed to by [config] into a [QuicConfig].
+fn quic_config_from_c(config: *const demi_quic_config_t) -> Result<QuicConfig, Fail> {
+    if config.is_null() {
+        let cause: &str = "QUIC configuration is a null pointer";
+        warn!("quic_config_from_c(): {}", cause);
+        return Err(Fail::new(libc::EINVAL, cause));
+    }
+
+    // Safety: We have to trust that our user is providing a valid pointer.
+    let config: demi_quic_config_t = unsafe { ptr::read(config) };
+    let mut quic_config: QuicConfig = QuicConfig::new(tls_config_from_c(&config.tls)?);
+    if config.idle_timeout_ms != 0 {
+        quic_config = quic_config.with_idle_timeout(Duration::from_millis(config.idle_timeout_ms));
+    }
+    if config.max_data != 0 || config.max_stream_data != 0 {
+        let max_data: u64 = if config.max_data != 0 {
+            config.max_data
+        } else {
+            quic_config.max_data()
+        };
+        let max_stream_data: u64 = if config.max_stream_data != 0 {
+            config.max_stream_data
+        } else {
+            quic_config.max_stream_data()
+        };
+        quic_config = quic_config.with_flow_control(max_data, max_stream_data)?;
+    }
+    if config.max_streams != 0 {
+        quic_config = quic_config.with_max_streams(config.max_streams)?;
+    }
+    Ok(quic_config)
+}
+
+/// Converts the [demi_ptp_config_t] that is pointed to by [config] into a [PtpConfig].
+fn ptp_config_from_c(config: *const demi_ptp_config_t) -> Result<PtpConfig, Fail> {
+    if config.is_null() {
+        let cause: &str = "PTP configuration is a null pointer";
+        warn!("ptp_config_from_c(): {}", cause);
+        return Err(Fail::new(libc::EINVAL, cause));
+    }
+
+    // Safety: We have to trust that our user is providing a valid pointer.
+    let config: demi_ptp_config_t = unsafe { ptr::read(config) };
+    let local: Ipv4Addr = Ipv4Addr::from(u32::from_be(config.local));
+    let master: Ipv4Addr = Ipv4Addr::from(u32::from_be(config.master));
+    let mut ptp_config: PtpConfig = PtpConfig::new(local, master)
+        .with_domain(config.domain)
+        .with_intervals(config.log_sync_interval, config.log_delay_req_interval)?;
+    if config.clock_identity != [0; 8] {
+        ptp_config = ptp_config.with_clock_identity(config.clock_identity);
+    }
+    if config.flags & DEMI_PTP_NO_NEGOTIATION != 0 {
+        ptp_config = ptp_config.with_grant_duration(Duration::ZERO);
+    } else if config.grant_duration_s != 0 {
+        ptp_config = ptp_config.with_grant_duration(Duration::from_secs(config.grant_duration_s as u64));
+    }
+    Ok(ptp_config)
+}
+
+/// Converts [time] into a timespec of the time since the Unix epoch.
+fn system_time_to_timespec(time: SystemTime) -> libc::timespec {
+    let nanos: i128 = system_time_to_nanos(time);
+    libc::timespec {
+        tv_sec: nanos.div_euclid(1_000_000_000) as libc::time_t,
+        tv_nsec: nanos.rem_euclid(1_000_000_000) as libc::c_long,
+    }
+}
+
+/// Converts the [demi_ipsec_sa_t] that is pointed to by [sa] into a [SecurityAssociation].
+fn security_association_from_c(sa: *const demi_ipsec_sa_t) -> Result<SecurityAssociation, Fail> {
+    if sa.is_null() {
+        let cause: &str = "security association is a null pointer";
+        warn!("security_association_from_c(): {}", cause);
+        return Err(Fail::new(libc::EINVAL, cause));
+    }
+
+    // Safety: We have to trust that our user is providing a valid pointer to the key.
+    let sa: demi_ipsec_sa_t = unsafe { ptr::read(sa) };
+    if sa.key.is_null() {
+        let cause: &str = "key is a null pointer";
+        warn!("security_association_from_c(): {}", cause);
+        return Err(Fail::new(libc::EINVAL, cause));
+    }
+    let key: &[u8] = unsafe { slice::from_raw_parts(sa.key, sa.key_len) };
+    let key: AeadKey = match (sa.algorithm, key.len()) {
+        (DEMI_IPSEC_AES_128_GCM, 16) => AeadKey::Aes128Gcm(expect_ok!(key.try_into(), "key size was checked")),
+        (DEMI_IPSEC_CHACHA20_POLY1305, 32) => {
+            AeadKey::ChaCha20Poly1305(expect_ok!(key.try_into(), "key size was checked"))
+        },
+        (DEMI_IPSEC_AES_128_GCM, _) | (DEMI_IPSEC_CHACHA20_POLY1305, _) => {
+            let cause: String = format!("key has the wrong size (key_len={})", sa.key_len);
+            warn!("security_association_from_c(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        },
+        _ => {
+            let cause: String = format!("unsupported algorithm (algorithm={})", sa.algorithm);
+            warn!("security_association_from_c(): {}", cause);
+            return Err(Fail::new(libc::ENOTSUP, &cause));
+        },
+    };
+    let peer: Ipv4Addr = Ipv4Addr::from(u32::from_be(sa.peer));
+    SecurityAssociation::new(sa.spi, ipsec_direction_from_c(sa.direction)?, peer, key, sa.salt)
+}
+
+/// Converts the [demi_route_t] that is pointed to by [route] into a [Route].
+fn route_from_c(route: *const demi_route_t) -> Result<Route, Fail> {
+    if route.is_null() {
+        let cause: &str = "route is a null pointer";
+        warn!("route_from_c(): {}", cause);
+        return Err(Fail::new(libc::EINVAL, cause));
+    }
+
+    // Safety: We have to trust that our user is providing a valid pointer to the route.
+    let route: demi_route_t = unsafe { ptr::read(route) };
+    let prefix_len: u8 = match u8::try_from(route.prefix_len) {
+        Ok(prefix_len) => prefix_len,
+        Err(_) => {
+            let cause: String = format!("prefix is too long (prefix_len={})", route.prefix_len);
+            warn!("route_from_c(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        },
+    };
+    let gateway: Option<Ipv4Addr> = match route.gateway {
+        0 => None,
+        gateway => Some(Ipv4Addr::from(u32::from_be(gateway))),
+    };
+    Ok(
+        Route::new(Ipv4Addr::from(u32::from_be(route.destination)), prefix_len, gateway)?
+            .with_interface(route.interface as usize),
+    )
+}
+
+/// Converts a direction of the C API into an [IpsecDirection].
+fn ipsec_direction_from_c(direction: u32) -> Result<IpsecDirection, Fail> {
+    match direction {
+        DEMI_IPSEC_INBOUND => Ok(IpsecDirection::Inbound),
+        DEMI_IPSEC_OUTBOUND => Ok(IpsecDirection::Outbound),
+        _ => {
+            let cause: String = format!("invalid direction (direction={})", direction);
+            warn!("ipsec_direction_from_c(): {}", cause);
+            Err(Fail::new(libc::EINVAL, &cause))
+        },
+    }
+}
+
 /// Converts a [sockaddr] into a [SocketAddr].
 fn sockaddr_to_socketaddr(saddr: *const sockaddr, size: Socklen) -> Result<SocketAddr, Fail> {
     let check_name_len = |len: usize, exact: bool| {
@@ -934,3 +3436,32 @@ fn test_sockaddr_to_socketaddr_failure() {
         _ => panic!("expected sockaddr_to_socketaddr to fail with ENOTSUP"),
     };
 }
+
+#[test]
+fn test_sockopt_to_socket_option() {
+    match sockopt_to_socket_option(IPPROTO_IP, IP_TOS, 0xb8) {
+        Ok(SocketOption::IP_TOS(tos)) => assert_eq!(tos, 0xb8),
+        _ => panic!("failed to convert"),
+    }
+
+    match sockopt_to_socket_option(IPPROTO_IP, IP_TTL, 64) {
+        Ok(SocketOption::IP_TTL(ttl)) => assert_eq!(ttl, 64),
+        _ => panic!("failed to convert"),
+    }
+
+    // Test out of range option value.
+    match sockopt_to_socket_option(IPPROTO_IP, IP_TTL, 256) {
+        Err(e) if e.errno == libc::EINVAL => (),
+        _ => panic!("expected sockopt_to_socket_option to fail with EINVAL"),
+    };
+    match sockopt_to_socket_option(IPPROTO_IP, IP_TOS, 256) {
+        Err(e) if e.errno == libc::EINVAL => (),
+        _ => panic!("expected sockopt_to_socket_option to fail with EINVAL"),
+    };
+
+    // Test unsupported option.
+    match sockopt_to_socket_option(IPPROTO_IP, -1, 0) {
+        Err(e) if e.errno == libc::ENOPROTOOPT => (),
+        _ => panic!("expected sockopt_to_socket_option to fail with ENOPROTOOPT"),
+    };
+}
@@ -0,0 +1,1120 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+mod profile;
+mod schema;
+
+pub use self::profile::Profile;
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+#[cfg(feature = "catnip-libos")]
+use crate::runtime::network::numa::NumaPlacement;
+#[cfg(any(feature = "catnip-libos", feature = "catpowder-libos"))]
+use crate::runtime::network::{
+    config::TcpConfig,
+    consts::{
+        DEFAULT_MTU,
+        IPV4_TCP_HEADERS_SIZE,
+        MAX_BATCH_SIZE,
+        MAX_MSS,
+        MAX_MTU,
+        MIN_MSS,
+        MIN_MTU,
+        RECEIVE_BATCH_SIZE,
+    },
+};
+use crate::{
+    expect_ok,
+    inetstack::protocols::ethernet2::VlanTag,
+    runtime::{
+        affinity::BindingConfig,
+        crypto::runtime::AeadKey,
+        fail::Fail,
+        network::config::{
+            IpsecDirection,
+            Route,
+            SecurityAssociation,
+            SALT_SIZE,
+        },
+    },
+    MacAddress,
+};
+#[cfg(feature = "catmem-libos")]
+use ::std::time::Duration;
+#[cfg(any(feature = "catnip-libos", feature = "catpowder-libos"))]
+use ::std::{
+    collections::HashMap,
+    ffi::CString,
+};
+use ::std::{
+    env,
+    fs::File,
+    io::Read,
+    net::Ipv4Addr,
+    sync::atomic::{
+        AtomicBool,
+        Ordering,
+    },
+};
+use ::yaml_rust::{
+    yaml::Hash,
+    Yaml,
+    YamlLoader,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Default interval between heartbeats of memory queues.
+#[cfg(feature = "catmem-libos")]
+const DEFAULT_CATMEM_HEARTBEAT_INTERVAL: Duration = Duration::from_millis(100);
+
+//======================================================================================================================
+// Static Variables
+//======================================================================================================================
+
+/// Prefix of environment variables that override parameters of the configuration file.
+const OVERRIDE_PREFIX: &str = "DEMIKERNEL__";
+
+/// Separator of sections in the names of environment variables that override parameters.
+const OVERRIDE_SEPARATOR: &str = "__";
+
+/// Set when the configuration file should be reloaded.
+static RELOAD_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Demikernel configuration.
+#[derive(Clone, Debug)]
+pub struct Config(pub Yaml);
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+/// Common associated functions for Demikernel configuration object.
+impl Config {
+    /// Loads the configuration of Demikernel. Parameters are read from the file at the CONFIG_PATH environment
+    /// variable, and then overridden by environment variables named after their path in upper case, prefixed with
+    /// "DEMIKERNEL__", and with sections separated by "__" (e.g. DEMIKERNEL__CATNIP__MY_IPV4_ADDR overrides
+    /// "catnip.my_ipv4_addr"). The configuration file may be omitted if parameters are only set this way.
+    pub fn load() -> Result<Self, Fail> {
+        let overrides: Vec<(String, String)> = env::vars()
+            .filter(|(name, _)| name.starts_with(OVERRIDE_PREFIX))
+            .collect();
+        let config: Self = match env::var("CONFIG_PATH") {
+            Ok(config_path) => Self::new(&config_path)?,
+            Err(_) if !overrides.is_empty() => Self::default(),
+            Err(_) => {
+                let cause: &str = "missing value for CONFIG_PATH environment variable";
+                error!("load(): {}", cause);
+                return Err(Fail::new(libc::EINVAL, cause));
+            },
+        };
+        config.with_overrides(overrides)
+    }
+
+    /// Reads a configuration file into a [Config] object. All parameters in the file are checked against the schema
+    /// of the configuration file, so this fails if any of them is unknown or has an invalid value.
+    pub fn new(config_path: &str) -> Result<Self, Fail> {
+        let mut config_s: String = String::new();
+        if let Err(e) = File::open(config_path).and_then(|mut file| file.read_to_string(&mut config_s)) {
+            let cause: String = format!("cannot read configuration file (path={:?}, error={:?})", config_path, e);
+            error!("new(): {}", cause);
+            return Err(Fail::new(e.raw_os_error().unwrap_or(libc::EIO), &cause));
+        }
+        Self::parse(&config_s)
+    }
+
+    /// Parses the contents of a configuration file into a [Config] object, checking them against the schema of the
+    /// configuration file.
+    pub fn parse(config_s: &str) -> Result<Self, Fail> {
+        let config: Yaml = match YamlLoader::load_from_str(config_s) {
+            Ok(mut config) if config.len() == 1 => config.remove(0),
+            Ok(_) => {
+                let cause: &str = "configuration file should have exactly one document";
+                error!("parse(): {}", cause);
+                return Err(Fail::new(libc::EINVAL, cause));
+            },
+            Err(e) => {
+                let cause: String = format!("malformed configuration file (error={})", e);
+                error!("parse(): {}", cause);
+                return Err(Fail::new(libc::EINVAL, &cause));
+            },
+        };
+        schema::validate(&config, &schema::SCHEMA, "")?;
+        let config: Self = Self(config);
+        config.validate_environment()?;
+        Ok(config)
+    }
+
+    /// Overrides the parameter [name], which is given by its dotted path (e.g. "catnip.vlan.id"), with [value]. The value
+    /// is parsed as YAML, so numbers, booleans and lists are written as they would be in the configuration file, and
+    /// "null" unsets the parameter. This consumes and returns the configuration, so that calls can be chained to build
+    /// one programmatically.
+    pub fn with(mut self, name: &str, value: &str) -> Result<Self, Fail> {
+        let value: Yaml = match YamlLoader::load_from_str(value) {
+            Ok(mut docs) if docs.len() == 1 => docs.remove(0),
+            // Anything that is not a single YAML document is taken as it is.
+            _ => Yaml::String(value.to_string()),
+        };
+        let path: Vec<&str> = name.split('.').collect();
+        if path.iter().any(|key| key.is_empty()) {
+            let cause: String = format!("invalid parameter name \"{}\"", name);
+            error!("with(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        Self::insert(&mut self.0, &path, value, name)?;
+        schema::validate(&self.0, &schema::SCHEMA, "")?;
+        self.validate_environment()?;
+        Ok(self)
+    }
+
+    /// Overrides parameters with the values of [vars], which are named like the environment variables described in
+    /// [load]. Variables without the prefix are ignored.
+    fn with_overrides<I: IntoIterator<Item = (String, String)>>(mut self, vars: I) -> Result<Self, Fail> {
+        let mut overrides: Vec<(String, String)> = vars
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let path: &str = name.strip_prefix(OVERRIDE_PREFIX)?;
+                Some((path.to_lowercase().replace(OVERRIDE_SEPARATOR, "."), value))
+            })
+            .collect();
+        // Apply overrides in a stable order, so that sections are overridden before the parameters in them.
+        overrides.sort();
+        for (name, value) in overrides {
+            debug!("with_overrides(): {}={}", name, value);
+            self = self.with(&name, &value)?;
+        }
+        Ok(self)
+    }
+
+    /// Sets the parameter at [path] in [yaml] to [value], creating sections along the way.
+    fn insert(yaml: &mut Yaml, path: &[&str], value: Yaml, name: &str) -> Result<(), Fail> {
+        if yaml.is_null() || yaml.is_badvalue() {
+            *yaml = Yaml::Hash(Hash::new());
+        }
+        let entries: &mut Hash = match yaml {
+            Yaml::Hash(entries) => entries,
+            _ => {
+                let cause: String = format!("cannot set \"{}\" because its parent is not a section", name);
+                error!("insert(): {}", cause);
+                return Err(Fail::new(libc::EINVAL, &cause));
+            },
+        };
+        match path {
+            [key] => {
+                entries.insert(Yaml::String(key.to_string()), value);
+                Ok(())
+            },
+            [key, rest @ ..] => {
+                let entry: &mut Yaml = entries.entry(Yaml::String(key.to_string())).or_insert(Yaml::Null);
+                Self::insert(entry, rest, value, name)
+            },
+            [] => unreachable!("parameter names are never empty"),
+        }
+    }
+
+    /// Checks the parameters that are read from environment variables, if they are set.
+    #[cfg(any(feature = "catnip-libos", feature = "catpowder-libos"))]
+    fn validate_environment(&self) -> Result<(), Fail> {
+        if ::std::env::var("MTU").is_ok() {
+            self.mtu()?;
+        }
+        if ::std::env::var("MSS").is_ok() {
+            self.mss()?;
+        }
+        self.receive_batch_size()?;
+        Ok(())
+    }
+
+    #[cfg(not(any(feature = "catnip-libos", feature = "catpowder-libos")))]
+    fn validate_environment(&self) -> Result<(), Fail> {
+        Ok(())
+    }
+
+    /// Reads the "tunables" section from the underlying configuration file. These are the parameters that can also be
+    /// changed at runtime, keyed by their dotted path in the section (e.g. "tcp.window_scale"). Parameters that are
+    /// set to null keep their current value. If a profile is selected, its tunables come first, so that those which
+    /// are set explicitly take precedence.
+    pub fn tunables(&self) -> Result<Vec<(String, String)>, Fail> {
+        let mut tunables: Vec<(String, String)> = match self.profile()? {
+            Some(profile) => profile
+                .tunables()
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            None => Vec::new(),
+        };
+        if let Some(section) = self.0["tunables"].as_hash() {
+            Self::flatten_tunables(section, "", &mut tunables)?;
+        }
+        Ok(tunables)
+    }
+
+    /// Reads the "profile" parameter of the "tunables" section from the underlying configuration file. This selects a
+    /// group of tunables that suits a kind of deployment: "low-latency", "throughput" or "wan". If not set, tunables
+    /// keep their defaults.
+    pub fn profile(&self) -> Result<Option<Profile>, Fail> {
+        match self.0["tunables"]["profile"].as_str() {
+            Some(profile) => match profile.parse() {
+                Ok(profile) => Ok(Some(profile)),
+                Err(()) => {
+                    let cause: String = format!("invalid profile \"{}\"", profile);
+                    error!("profile(): {}", cause);
+                    Err(Fail::new(libc::EINVAL, &cause))
+                },
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Selects the tunables of [profile], as the "profile" parameter of the "tunables" section would. This consumes and
+    /// returns the configuration, like [with].
+    pub fn with_profile(self, profile: Profile) -> Result<Self, Fail> {
+        self.with("tunables.profile", &profile.to_string())
+    }
+
+    /// Reads the "reload on SIGHUP" parameter of the "tunables" section from the underlying configuration file. If
+    /// set, the tunables are reloaded from the configuration file whenever the process receives SIGHUP.
+    pub fn reload_on_sighup(&self) -> bool {
+        self.0["tunables"]["reload_on_sighup"].as_bool().unwrap_or(false)
+    }
+
+    /// Reads the "engine" section from the underlying configuration file. This tells which cores the thread that runs
+    /// the engine is bound to, and how the kernel schedules it. Parameters that are not set are left as they are, so by
+    /// default the engine runs wherever the process was placed, e.g. with taskset.
+    pub fn engine_binding(&self) -> Result<BindingConfig, Fail> {
+        let section: &Yaml = &self.0["engine"];
+        let cores: Option<Vec<usize>> = match section["cores"].as_vec() {
+            Some(cores) => {
+                let mut list: Vec<usize> = Vec::with_capacity(cores.len());
+                for core in cores {
+                    match core.as_i64() {
+                        Some(core) if core >= 0 => list.push(core as usize),
+                        _ => return Err(Fail::new(libc::EINVAL, "parameter \"cores\" should only list cores")),
+                    }
+                }
+                Some(list)
+            },
+            None => None,
+        };
+        Ok(BindingConfig {
+            cores,
+            nice: section["nice"].as_i64().map(|nice| nice as i32),
+            realtime_priority: section["realtime_priority"].as_i64().map(|priority| priority as u32),
+            timer_slack_ns: section["timer_slack_ns"].as_i64().map(|slack| slack as u64),
+        })
+    }
+
+    /// Appends the tunables in [section], whose dotted path is [prefix], to [tunables].
+    fn flatten_tunables(
+        section: &::yaml_rust::yaml::Hash,
+        prefix: &str,
+        tunables: &mut Vec<(String, String)>,
+    ) -> Result<(), Fail> {
+        for (key, value) in section {
+            let key: String = match key.as_str() {
+                Some(key) if prefix.is_empty() => key.to_string(),
+                Some(key) => format!("{}.{}", prefix, key),
+                None => return Err(Fail::new(libc::EINVAL, "tunables should be keyed by strings")),
+            };
+            let value: String = match value {
+                Yaml::Hash(section) => {
+                    Self::flatten_tunables(section, &key, tunables)?;
+                    continue;
+                },
+                Yaml::Null => continue,
+                Yaml::String(value) | Yaml::Real(value) => value.clone(),
+                Yaml::Integer(value) => value.to_string(),
+                Yaml::Boolean(value) => value.to_string(),
+                _ => {
+                    let cause: String = format!("invalid value for tunable \"{}\"", key);
+                    return Err(Fail::new(libc::EINVAL, &cause));
+                },
+            };
+            if key != "reload_on_sighup" && key != "profile" {
+                tunables.push((key, value));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the local IPv4 address parameter from the underlying configuration file.
+    pub fn local_ipv4_addr(&self) -> Result<Ipv4Addr, Fail> {
+        // FIXME: Change the follow key from "catnip" to "demikernel".
+        let local_ipv4_addr: Ipv4Addr = Self::require_str(&self.0["catnip"]["my_ipv4_addr"], "catnip.my_ipv4_addr")?
+            .parse()
+            .map_err(|_| Fail::new(libc::EINVAL, "invalid value for \"catnip.my_ipv4_addr\""))?;
+        if local_ipv4_addr.is_unspecified() || local_ipv4_addr.is_broadcast() {
+            let cause: &str = "parameter \"catnip.my_ipv4_addr\" should be a unicast address";
+            error!("local_ipv4_addr(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, cause));
+        }
+        Ok(local_ipv4_addr)
+    }
+
+    /// Reads the "local interface name" parameter from the underlying configuration file.
+    pub fn local_interface_name(&self) -> Result<String, Fail> {
+        // FIXME: Change the follow key from "catnip" to "catpowder".
+        let local_interface_name: &str =
+            Self::require_str(&self.0["catnip"]["my_interface_name"], "catnip.my_interface_name")?;
+        Ok(local_interface_name.to_string())
+    }
+
+    /// Reads the "local link address" parameter from the underlying configuration file.
+    pub fn local_link_addr(&self) -> Result<MacAddress, Fail> {
+        // FIXME: Change the follow key from "catnip" to "catpowder".
+        MacAddress::parse_str(Self::require_str(
+            &self.0["catnip"]["my_link_addr"],
+            "catnip.my_link_addr",
+        )?)
+    }
+
+    /// Reads the string value [yaml] of parameter [name], which is required.
+    fn require_str<'a>(yaml: &'a Yaml, name: &str) -> Result<&'a str, Fail> {
+        match yaml.as_str() {
+            Some(value) => Ok(value),
+            None => {
+                let cause: String = format!("missing parameter \"{}\"", name);
+                error!("require_str(): {}", cause);
+                Err(Fail::new(libc::EINVAL, &cause))
+            },
+        }
+    }
+
+    /// Reads the "VLAN" parameters from the underlying configuration file. If an ID is set, frames are tagged with it
+    /// and with the given priority, which defaults to zero, and frames that are tagged with other IDs are dropped.
+    pub fn vlan_tag(&self) -> Result<Option<VlanTag>, Fail> {
+        let vlan: &Yaml = &self.0["catnip"]["vlan"];
+        let vid: u16 = match vlan["id"].as_i64() {
+            Some(vid) if (0..=u16::MAX as i64).contains(&vid) => vid as u16,
+            Some(_) => return Err(Fail::new(libc::ERANGE, "parameter \"vlan.id\" is out of range")),
+            None => return Ok(None),
+        };
+        let pcp: u8 = match vlan["pcp"].as_i64() {
+            Some(pcp) if (0..=u8::MAX as i64).contains(&pcp) => pcp as u8,
+            Some(_) => return Err(Fail::new(libc::ERANGE, "parameter \"vlan.pcp\" is out of range")),
+            None => 0,
+        };
+        Ok(Some(VlanTag::new(vid, pcp)?))
+    }
+
+    /// Reads the "routes" parameter from the underlying configuration file. This lists the routes of the routing table,
+    /// each toward the subnet at "destination" with "prefix_len" leading bits, through the "gateway" if the subnet is not
+    /// directly connected, and out of the network "interface" with that index (the primary one, 0, by default). If the
+    /// parameter is missing, every destination is directly connected.
+    pub fn routes(&self) -> Result<Option<Vec<Route>>, Fail> {
+        let entries: &[Yaml] = match &self.0["catnip"]["routes"] {
+            Yaml::Array(entries) => entries,
+            Yaml::BadValue | Yaml::Null => return Ok(None),
+            _ => return Err(Fail::new(libc::EINVAL, "parameter \"routes\" is not a list")),
+        };
+        let mut routes: Vec<Route> = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let destination: Ipv4Addr = match entry["destination"].as_str().and_then(|addr| addr.parse().ok()) {
+                Some(destination) => destination,
+                None => return Err(Fail::new(libc::EINVAL, "invalid value for \"routes.destination\"")),
+            };
+            let prefix_len: u8 = match entry["prefix_len"].as_i64() {
+                Some(prefix_len) if (0..=u8::MAX as i64).contains(&prefix_len) => prefix_len as u8,
+                Some(_) => {
+                    return Err(Fail::new(
+                        libc::ERANGE,
+                        "parameter \"routes.prefix_len\" is out of range",
+                    ))
+                },
+                None => return Err(Fail::new(libc::EINVAL, "missing parameter \"routes.prefix_len\"")),
+            };
+            let gateway: Option<Ipv4Addr> = match &entry["gateway"] {
+                Yaml::BadValue | Yaml::Null => None,
+                gateway => match gateway.as_str().and_then(|addr| addr.parse().ok()) {
+                    Some(gateway) => Some(gateway),
+                    None => return Err(Fail::new(libc::EINVAL, "invalid value for \"routes.gateway\"")),
+                },
+            };
+            let interface: usize = match entry["interface"].as_i64() {
+                Some(interface) if (0..=u32::MAX as i64).contains(&interface) => interface as usize,
+                Some(_) => {
+                    return Err(Fail::new(
+                        libc::ERANGE,
+                        "parameter \"routes.interface\" is out of range",
+                    ))
+                },
+                None => 0,
+            };
+            routes.push(Route::new(destination, prefix_len, gateway)?.with_interface(interface));
+        }
+        Ok(Some(routes))
+    }
+
+    /// Reads the "IPsec" parameters from the underlying configuration file. These list the static security
+    /// associations of transport-mode ESP, each with a security parameter index ("spi"), a "direction" ("inbound" or
+    /// "outbound"), the address of the "peer", an AEAD "algorithm" ("aes-128-gcm" or "chacha20-poly1305"), and a
+    /// hexadecimal "key" and 4-byte "salt". If the section is missing, IPsec is disabled.
+    pub fn ipsec_security_associations(&self) -> Result<Option<Vec<SecurityAssociation>>, Fail> {
+        let ipsec: &Yaml = &self.0["catnip"]["ipsec"];
+        if ipsec.is_badvalue() || ipsec.is_null() {
+            return Ok(None);
+        }
+        let entries: &[Yaml] = match &ipsec["security_associations"] {
+            Yaml::Array(entries) => entries,
+            Yaml::BadValue | Yaml::Null => &[],
+            _ => {
+                return Err(Fail::new(
+                    libc::EINVAL,
+                    "parameter \"ipsec.security_associations\" is not a list",
+                ))
+            },
+        };
+        let mut security_associations: Vec<SecurityAssociation> = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let spi: u32 = match entry["spi"].as_i64() {
+                Some(spi) if (0..=u32::MAX as i64).contains(&spi) => spi as u32,
+                Some(_) => return Err(Fail::new(libc::ERANGE, "parameter \"ipsec.spi\" is out of range")),
+                None => return Err(Fail::new(libc::EINVAL, "missing parameter \"ipsec.spi\"")),
+            };
+            let direction: IpsecDirection = match entry["direction"].as_str() {
+                Some("inbound") => IpsecDirection::Inbound,
+                Some("outbound") => IpsecDirection::Outbound,
+                _ => return Err(Fail::new(libc::EINVAL, "invalid value for \"ipsec.direction\"")),
+            };
+            let peer: Ipv4Addr = match entry["peer"].as_str().and_then(|peer| peer.parse().ok()) {
+                Some(peer) => peer,
+                None => return Err(Fail::new(libc::EINVAL, "invalid value for \"ipsec.peer\"")),
+            };
+            let key: Vec<u8> = Self::read_hex(&entry["key"], "ipsec.key")?;
+            let key: AeadKey = match (entry["algorithm"].as_str(), key.len()) {
+                (Some("aes-128-gcm"), 16) => AeadKey::Aes128Gcm(expect_ok!(key.try_into(), "key size was checked")),
+                (Some("chacha20-poly1305"), 32) => {
+                    AeadKey::ChaCha20Poly1305(expect_ok!(key.try_into(), "key size was checked"))
+                },
+                (Some("aes-128-gcm"), _) | (Some("chacha20-poly1305"), _) => {
+                    return Err(Fail::new(libc::EINVAL, "parameter \"ipsec.key\" has the wrong size"))
+                },
+                _ => return Err(Fail::new(libc::EINVAL, "invalid value for \"ipsec.algorithm\"")),
+            };
+            let salt: [u8; SALT_SIZE] = match Self::read_hex(&entry["salt"], "ipsec.salt")?.try_into() {
+                Ok(salt) => salt,
+                Err(_) => return Err(Fail::new(libc::EINVAL, "parameter \"ipsec.salt\" has the wrong size")),
+            };
+            security_associations.push(SecurityAssociation::new(spi, direction, peer, key, salt)?);
+        }
+        Ok(Some(security_associations))
+    }
+
+    /// Reads the hexadecimal string in [yaml], which is the value of parameter [name], into bytes.
+    fn read_hex(yaml: &Yaml, name: &str) -> Result<Vec<u8>, Fail> {
+        let digits: &str = match yaml.as_str() {
+            Some(digits) => digits,
+            None => {
+                let cause: String = format!("missing parameter \"{}\"", name);
+                return Err(Fail::new(libc::EINVAL, &cause));
+            },
+        };
+        if digits.len() % 2 != 0 {
+            let cause: String = format!("parameter \"{}\" has an odd number of digits", name);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        let mut bytes: Vec<u8> = Vec::with_capacity(digits.len() / 2);
+        for i in (0..digits.len()).step_by(2) {
+            match digits
+                .get(i..i + 2)
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+            {
+                Some(byte) => bytes.push(byte),
+                None => {
+                    let cause: String = format!("parameter \"{}\" is not hexadecimal", name);
+                    return Err(Fail::new(libc::EINVAL, &cause));
+                },
+            }
+        }
+        Ok(bytes)
+    }
+
+    #[cfg(any(feature = "catnip-libos", feature = "catpowder-libos"))]
+    /// Reads the "ARP table" parameter from the underlying configuration file. This maps link addresses to IPv4
+    /// addresses. If not set, the table is empty.
+    pub fn arp_table(&self) -> Result<HashMap<Ipv4Addr, MacAddress>, Fail> {
+        let mut arp_table: HashMap<Ipv4Addr, MacAddress> = HashMap::new();
+        if let Some(arp_table_obj) = self.0["catnip"]["arp_table"].as_hash() {
+            for (k, v) in arp_table_obj {
+                let link_addr: MacAddress = MacAddress::parse_str(Self::require_str(k, "catnip.arp_table")?)?;
+                let ipv4_addr: Ipv4Addr = match Self::require_str(v, "catnip.arp_table")?.parse() {
+                    Ok(ipv4_addr) => ipv4_addr,
+                    Err(_) => return Err(Fail::new(libc::EINVAL, "invalid value for \"catnip.arp_table\"")),
+                };
+                arp_table.insert(ipv4_addr, link_addr);
+            }
+        }
+        Ok(arp_table)
+    }
+
+    #[cfg(any(feature = "catnip-libos", feature = "catpowder-libos"))]
+    /// Reads the "DPDK EAL" parameter from the underlying configuration file.
+    pub fn eal_init_args(&self) -> Result<Vec<CString>, Fail> {
+        let args: &Vec<Yaml> = match self.0["dpdk"]["eal_init"].as_vec() {
+            Some(args) => args,
+            None => return Err(Fail::new(libc::EINVAL, "missing parameter \"dpdk.eal_init\"")),
+        };
+        let mut eal_init_args: Vec<CString> = Vec::with_capacity(args.len());
+        for arg in args {
+            match CString::new(Self::require_str(arg, "dpdk.eal_init")?) {
+                Ok(arg) => eal_init_args.push(arg),
+                Err(_) => return Err(Fail::new(libc::EINVAL, "invalid value for \"dpdk.eal_init\"")),
+            }
+        }
+        Ok(eal_init_args)
+    }
+
+    #[cfg(feature = "catnip-libos")]
+    /// Reads the "RX queues" parameter of DPDK from the underlying configuration file. This is the number of receive
+    /// queues that are set up in the network interface. If not set, a single queue is used.
+    pub fn dpdk_rx_queues(&self) -> Result<u16, Fail> {
+        Self::get_dpdk_queue_count(&self.0["dpdk"]["rx_queues"], "rx_queues")
+    }
+
+    #[cfg(feature = "catnip-libos")]
+    /// Reads the "TX queues" parameter of DPDK from the underlying configuration file. This is the number of transmit
+    /// queues that are set up in the network interface. If not set, a single queue is used.
+    pub fn dpdk_tx_queues(&self) -> Result<u16, Fail> {
+        Self::get_dpdk_queue_count(&self.0["dpdk"]["tx_queues"], "tx_queues")
+    }
+
+    #[cfg(feature = "catnip-libos")]
+    /// Reads the "queue id" parameter of DPDK from the underlying configuration file. This is the receive queue that
+    /// this instance polls, and the transmit queue that it sends on, modulo the number of transmit queues. If not set,
+    /// the first queue is used.
+    pub fn dpdk_queue_id(&self) -> Result<u16, Fail> {
+        let queue_id: u16 = match self.0["dpdk"]["queue_id"].as_i64() {
+            Some(queue_id) if queue_id >= 0 && queue_id <= u16::MAX as i64 => queue_id as u16,
+            Some(_) => return Err(Fail::new(libc::ERANGE, "parameter \"queue_id\" is out of range")),
+            None => 0,
+        };
+        if queue_id >= self.dpdk_rx_queues()? {
+            return Err(Fail::new(
+                libc::EINVAL,
+                "parameter \"queue_id\" exceeds the number of receive queues",
+            ));
+        }
+        Ok(queue_id)
+    }
+
+    #[cfg(feature = "catnip-libos")]
+    /// Reads the "RSS hash key" parameter of DPDK from the underlying configuration file. This is a hexadecimal string
+    /// that must have as many bytes as the hash key of the network interface. If not set, the default key of the
+    /// network interface is kept.
+    pub fn dpdk_rss_hash_key(&self) -> Result<Option<Vec<u8>>, Fail> {
+        let hash_key: &Yaml = &self.0["dpdk"]["rss"]["hash_key"];
+        if hash_key.as_str().is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Self::read_hex(hash_key, "hash_key")?))
+    }
+
+    #[cfg(feature = "catnip-libos")]
+    /// Reads the "RSS redirection table" parameter of DPDK from the underlying configuration file. This is a list of
+    /// receive queues that the entries of the redirection table of the network interface cycle through. If not set,
+    /// the default table of the network interface is kept.
+    pub fn dpdk_rss_reta(&self) -> Result<Option<Vec<u16>>, Fail> {
+        let reta: &Vec<Yaml> = match self.0["dpdk"]["rss"]["reta"].as_vec() {
+            Some(reta) => reta,
+            None => return Ok(None),
+        };
+        let rx_queues: u16 = self.dpdk_rx_queues()?;
+        let mut queues: Vec<u16> = Vec::with_capacity(reta.len());
+        for entry in reta {
+            match entry.as_i64() {
+                Some(queue_id) if queue_id >= 0 && queue_id < rx_queues as i64 => queues.push(queue_id as u16),
+                _ => {
+                    let cause: &str = "parameter \"reta\" should only list existing receive queues";
+                    return Err(Fail::new(libc::EINVAL, cause));
+                },
+            }
+        }
+        if queues.is_empty() {
+            return Err(Fail::new(libc::EINVAL, "parameter \"reta\" is empty"));
+        }
+        Ok(Some(queues))
+    }
+
+    #[cfg(feature = "catnip-libos")]
+    /// Reads the "checksum offload" parameter of DPDK from the underlying configuration file. If set, TCP and UDP
+    /// checksums are computed and verified by the network interface, as long as it is capable of doing so. Otherwise,
+    /// or if not set, checksums are handled in software.
+    pub fn dpdk_checksum_offload(&self) -> bool {
+        self.0["dpdk"]["checksum_offload"].as_bool().unwrap_or(false)
+    }
+
+    #[cfg(feature = "catnip-libos")]
+    /// Reads the "TSO" parameter of DPDK from the underlying configuration file. If set, TCP hands down segments larger
+    /// than the MSS, which are split by the network interface if it is capable of doing so, and in software otherwise.
+    pub fn dpdk_tso(&self) -> bool {
+        self.0["dpdk"]["tso"].as_bool().unwrap_or(false)
+    }
+
+    #[cfg(feature = "catnip-libos")]
+    /// Reads the "TLS offload" parameter of DPDK from the underlying configuration file. If set, outgoing TLS records
+    /// are encrypted by the network interface, as long as it is capable of doing so. Otherwise, or if not set, TLS
+    /// records are encrypted in software.
+    pub fn dpdk_tls_offload(&self) -> bool {
+        self.0["dpdk"]["tls_offload"].as_bool().unwrap_or(false)
+    }
+
+    #[cfg(feature = "catnip-libos")]
+    /// Reads the "hardware timestamps" parameter of DPDK from the underlying configuration file. If set, the network
+    /// interface stamps incoming packets and, on request, outgoing ones, as long as it is capable of doing so.
+    /// Otherwise, or if not set, packets only get software timestamps.
+    pub fn dpdk_hw_timestamps(&self) -> bool {
+        self.0["dpdk"]["hw_timestamps"].as_bool().unwrap_or(false)
+    }
+
+    #[cfg(feature = "catnip-libos")]
+    /// Reads the "NUMA placement" parameter of DPDK from the underlying configuration file. This tells from which NUMA
+    /// node buffer pools and the state of queues are allocated: the node of the network interface ("nic"), the node of
+    /// the core that runs the engine ("core") or any node ("any"). If not set, the node of the network interface is used.
+    pub fn dpdk_numa_placement(&self) -> Result<NumaPlacement, Fail> {
+        match self.0["dpdk"]["numa"]["placement"].as_str() {
+            Some(placement) => placement.parse().map_err(|_| {
+                let cause: String = format!("invalid NUMA placement \"{}\"", placement);
+                Fail::new(libc::EINVAL, &cause)
+            }),
+            None => Ok(NumaPlacement::Nic),
+        }
+    }
+
+    #[cfg(feature = "catnip-libos")]
+    /// Reads the "NUMA fallback" parameter of DPDK from the underlying configuration file. If set, allocations that do
+    /// not fit in the preferred NUMA node are retried on any node, and counted as remote. Otherwise, they fail. If not
+    /// set, allocations fall back.
+    pub fn dpdk_numa_fallback(&self) -> bool {
+        self.0["dpdk"]["numa"]["fallback"].as_bool().unwrap_or(true)
+    }
+
+    #[cfg(feature = "catnip-libos")]
+    /// Parses a number of hardware queues, which defaults to one.
+    fn get_dpdk_queue_count(yaml: &Yaml, index: &str) -> Result<u16, Fail> {
+        match yaml.as_i64() {
+            Some(count) if count > 0 && count <= u16::MAX as i64 => Ok(count as u16),
+            Some(_) => {
+                let cause: String = format!("parameter \"{}\" is out of range", index);
+                Err(Fail::new(libc::ERANGE, &cause))
+            },
+            None => Ok(1),
+        }
+    }
+
+    #[cfg(any(feature = "catnip-libos", feature = "catpowder-libos"))]
+    /// Reads the "ARP Disable" parameter from the underlying configuration file.
+    pub fn disable_arp(&self) -> bool {
+        // TODO: this should be unified with arp_table().
+        self.0["catnip"]["disable_arp"].as_bool().unwrap_or(false)
+    }
+
+    #[cfg(any(feature = "catnip-libos", feature = "catpowder-libos"))]
+    /// Gets the "MTU" parameter from environment variables. It must be between [MIN_MTU] and [MAX_MTU], so values
+    /// beyond the Ethernet MTU enable jumbo frames.
+    pub fn mtu(&self) -> Result<u16, Fail> {
+        match ::std::env::var("MTU") {
+            Ok(var) => match var.parse::<u16>() {
+                Ok(var) if (MIN_MTU..=MAX_MTU).contains(&var) => Ok(var),
+                Ok(_) => Err(Fail::new(libc::ERANGE, "parameter \"MTU\" is out of range")),
+                Err(_) => Err(Fail::new(libc::EINVAL, "invalid value for \"MTU\"")),
+            },
+            Err(_) => Err(Fail::new(libc::EINVAL, "No such environment variable")),
+        }
+    }
+
+    #[cfg(any(feature = "catnip-libos", feature = "catpowder-libos"))]
+    /// Gets the "MSS" parameter from environment variables. Segments of this size must fit in the MTU, along with
+    /// IPv4 and TCP headers without options. If not set, the largest such MSS is derived from the "MTU" parameter.
+    pub fn mss(&self) -> Result<usize, Fail> {
+        match ::std::env::var("MSS") {
+            Ok(var) => match var.parse::<usize>() {
+                Ok(var) => {
+                    let max_mss: usize = match self.mtu() {
+                        Ok(mtu) => mtu as usize - IPV4_TCP_HEADERS_SIZE,
+                        Err(_) => MAX_MSS,
+                    };
+                    if (MIN_MSS..=max_mss).contains(&var) {
+                        Ok(var)
+                    } else {
+                        Err(Fail::new(libc::ERANGE, "parameter \"MSS\" is out of range"))
+                    }
+                },
+                Err(_) => Err(Fail::new(libc::EINVAL, "invalid value for \"MSS\"")),
+            },
+            Err(_) => Ok(TcpConfig::mss_for_mtu(self.mtu()?)),
+        }
+    }
+
+    #[cfg(any(feature = "catnip-libos", feature = "catpowder-libos"))]
+    /// Gets the "TCP_CHECKSUM_OFFLOAD" parameter from environment variables.
+    pub fn tcp_checksum_offload(&self) -> bool {
+        ::std::env::var("TCP_CHECKSUM_OFFLOAD").is_ok()
+    }
+
+    #[cfg(any(feature = "catnip-libos", feature = "catpowder-libos"))]
+    /// Gets the "UDP_CHECKSUM_OFFLOAD" parameter from environment variables.
+    pub fn udp_checksum_offload(&self) -> bool {
+        ::std::env::var("UDP_CHECKSUM_OFFLOAD").is_ok()
+    }
+
+    #[cfg(any(feature = "catnip-libos", feature = "catpowder-libos"))]
+    /// Gets the "TCP_GRO" parameter from environment variables. If set, consecutive in-order TCP segments of the same
+    /// flow that are received in a batch are coalesced before they are processed.
+    pub fn tcp_gro(&self) -> bool {
+        ::std::env::var("TCP_GRO").is_ok()
+    }
+
+    #[cfg(any(feature = "catnip-libos", feature = "catpowder-libos"))]
+    /// Gets the "RECEIVE_BATCH_SIZE" parameter from environment variables. This is the largest number of packets that
+    /// are received at once. If not set, [RECEIVE_BATCH_SIZE] packets are.
+    pub fn receive_batch_size(&self) -> Result<usize, Fail> {
+        match ::std::env::var("RECEIVE_BATCH_SIZE") {
+            Ok(var) => match var.parse::<usize>() {
+                Ok(var) if (1..=MAX_BATCH_SIZE).contains(&var) => Ok(var),
+                Ok(_) => Err(Fail::new(
+                    libc::ERANGE,
+                    "parameter \"RECEIVE_BATCH_SIZE\" is out of range",
+                )),
+                Err(_) => Err(Fail::new(libc::EINVAL, "invalid value for \"RECEIVE_BATCH_SIZE\"")),
+            },
+            Err(_) => Ok(RECEIVE_BATCH_SIZE),
+        }
+    }
+
+    #[cfg(any(feature = "catnip-libos", feature = "catpowder-libos"))]
+    /// Gets the "USE_JUMBO" parameter from environment variables. Jumbo frames are also used if the "MTU" parameter
+    /// exceeds the Ethernet MTU.
+    pub fn use_jumbo_frames(&self) -> bool {
+        ::std::env::var("USE_JUMBO").is_ok() || self.mtu().map_or(false, |mtu| mtu > DEFAULT_MTU)
+    }
+
+    #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+    /// Reads the "local shared memory" parameter of catnap from the underlying configuration file. If set, connections
+    /// to sockets that listen on this host go over memory queues instead of the network.
+    pub fn catnap_local_shared_memory(&self) -> bool {
+        self.0["catnap"]["local_shared_memory"].as_bool().unwrap_or(false)
+    }
+
+    #[cfg(feature = "catmem-libos")]
+    /// Reads the "heartbeat interval" parameter of memory queues from the underlying configuration file. If not set,
+    /// memory queues advertise their liveness every 100 ms.
+    pub fn catmem_heartbeat_interval(&self) -> Duration {
+        match self.0["catmem"]["heartbeat_interval_millis"].as_i64() {
+            Some(millis) if millis > 0 => Duration::from_millis(millis as u64),
+            _ => DEFAULT_CATMEM_HEARTBEAT_INTERVAL,
+        }
+    }
+
+    #[cfg(feature = "catmem-libos")]
+    /// Reads the "idle timeout" parameter of memory queues from the underlying configuration file. If not set, a
+    /// memory queue waits forever for a silent peer.
+    pub fn catmem_idle_timeout(&self) -> Option<Duration> {
+        match self.0["catmem"]["idle_timeout_millis"].as_i64() {
+            Some(millis) if millis > 0 => Some(Duration::from_millis(millis as u64)),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "catmem-libos")]
+    /// Reads the "doorbell" parameter of memory queues from the underlying configuration file. If set, pop operations
+    /// on memory queues sleep until the peer pushes data, instead of busy polling.
+    pub fn catmem_doorbell(&self) -> bool {
+        self.0["catmem"]["doorbell"].as_bool().unwrap_or(false)
+    }
+
+    #[cfg(feature = "catpowder-libos")]
+    /// Reads the "XDP" parameter of catpowder from the underlying configuration file. If set, frames are exchanged
+    /// through an AF_XDP socket instead of a raw socket.
+    pub fn catpowder_xdp(&self) -> bool {
+        self.0["catpowder"]["xdp"]["enabled"].as_bool().unwrap_or(false)
+    }
+
+    #[cfg(feature = "catpowder-libos")]
+    /// Reads the "queue id" parameter of AF_XDP sockets from the underlying configuration file. If not set, the first
+    /// receive queue of the network interface is used.
+    pub fn catpowder_xdp_queue_id(&self) -> u32 {
+        match self.0["catpowder"]["xdp"]["queue_id"].as_i64() {
+            Some(queue_id) if queue_id >= 0 => queue_id as u32,
+            _ => 0,
+        }
+    }
+
+    #[cfg(feature = "catpowder-libos")]
+    /// Reads the "zero copy" parameter of AF_XDP sockets from the underlying configuration file. If set, the network
+    /// interface must support zero-copy mode for the socket to be created.
+    pub fn catpowder_xdp_zero_copy(&self) -> bool {
+        self.0["catpowder"]["xdp"]["zero_copy"].as_bool().unwrap_or(false)
+    }
+
+    #[cfg(feature = "catpowder-libos")]
+    /// Reads the "TAP" parameter of catpowder from the underlying configuration file. If set, frames are exchanged with
+    /// the kernel through a TAP device named after the local interface, which is created if it does not exist.
+    pub fn catpowder_tap(&self) -> bool {
+        self.0["catpowder"]["tap"]["enabled"].as_bool().unwrap_or(false)
+    }
+
+    #[cfg(feature = "catpowder-libos")]
+    /// Reads the "vhost-user" parameter of catpowder from the underlying configuration file. If set, frames are
+    /// exchanged with a vhost-user backend instead of the network interface. This takes precedence over XDP.
+    pub fn catpowder_vhost_user(&self) -> bool {
+        self.0["catpowder"]["vhost_user"]["enabled"].as_bool().unwrap_or(false)
+    }
+
+    #[cfg(feature = "catpowder-libos")]
+    /// Reads the "socket path" parameter of vhost-user from the underlying configuration file. This is the UNIX socket
+    /// on which the backend listens.
+    pub fn catpowder_vhost_user_socket_path(&self) -> Option<String> {
+        self.0["catpowder"]["vhost_user"]["socket_path"]
+            .as_str()
+            .map(|s| s.to_string())
+    }
+
+    #[cfg(feature = "catpowder-libos")]
+    /// Reads the "GSO" parameter of catpowder from the underlying configuration file. If set, TCP hands down segments
+    /// larger than the MSS, which are split in software right before they are transmitted.
+    pub fn catpowder_gso(&self) -> bool {
+        self.0["catpowder"]["gso"].as_bool().unwrap_or(false)
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+/// An empty configuration, where all parameters are unset.
+impl Default for Config {
+    fn default() -> Self {
+        Self(Yaml::Hash(Hash::new()))
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Requests a reload of the configuration file whenever the process receives SIGHUP. The reload itself happens the
+/// next time that Demikernel is polled, outside of the signal handler.
+#[cfg(target_os = "linux")]
+pub fn watch_sighup() {
+    extern "C" fn on_sighup(_: libc::c_int) {
+        RELOAD_REQUESTED.store(true, Ordering::Relaxed);
+    }
+    if unsafe { libc::signal(libc::SIGHUP, on_sighup as *const () as libc::sighandler_t) } == libc::SIG_ERR {
+        warn!("watch_sighup(): failed to install SIGHUP handler");
+    }
+}
+
+/// SIGHUP does not exist on this platform, so configuration reloads have to be requested explicitly.
+#[cfg(not(target_os = "linux"))]
+pub fn watch_sighup() {
+    warn!("watch_sighup(): SIGHUP is not supported on this platform");
+}
+
+/// Returns whether a reload of the configuration file was requested since the last call, and clears the request.
+pub fn take_reload_request() -> bool {
+    RELOAD_REQUESTED.swap(false, Ordering::Relaxed)
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Config,
+        Profile,
+    };
+    use crate::{
+        runtime::network::config::Route,
+        MacAddress,
+    };
+    use ::anyhow::Result;
+    use ::std::net::Ipv4Addr;
+    use ::yaml_rust::YamlLoader;
+
+    /// Tests that the "tunables" section is flattened into dotted names, skipping null values.
+    #[test]
+    fn test_config_tunables() -> Result<()> {
+        let yaml: &str = "
+tunables:
+  reload_on_sighup: true
+  log:
+    level: info
+  arp:
+    cache_ttl_ms: 15000
+    retry_count: null
+  tcp:
+    window_scale: 7
+";
+        let config: Config = Config(YamlLoader::load_from_str(yaml)?.remove(0));
+        crate::ensure_eq!(config.reload_on_sighup(), true);
+        crate::ensure_eq!(
+            config.tunables()?,
+            vec![
+                ("log.level".to_string(), "info".to_string()),
+                ("arp.cache_ttl_ms".to_string(), "15000".to_string()),
+                ("tcp.window_scale".to_string(), "7".to_string()),
+            ]
+        );
+
+        // Configuration files without the section have no tunables.
+        let config: Config = Config(YamlLoader::load_from_str("catnip: {}")?.remove(0));
+        crate::ensure_eq!(config.tunables()?.is_empty(), true);
+        crate::ensure_eq!(config.reload_on_sighup(), false);
+
+        Ok(())
+    }
+
+    /// Tests that a profile expands into its tunables, which are overridden by those that are set explicitly.
+    #[test]
+    fn test_config_profile() -> Result<()> {
+        let yaml: &str = "
+tunables:
+  profile: low-latency
+  wait:
+    policy: hybrid
+";
+        let config: Config = Config::parse(yaml)?;
+        crate::ensure_eq!(config.profile()?, Some(Profile::LowLatency));
+        let tunables: Vec<(String, String)> = config.tunables()?;
+        crate::ensure_eq!(tunables.len(), Profile::LowLatency.tunables().len() + 1);
+        crate::ensure_eq!(
+            tunables.first(),
+            Some(&("wait.policy".to_string(), "busy_poll".to_string()))
+        );
+        crate::ensure_eq!(
+            tunables.last(),
+            Some(&("wait.policy".to_string(), "hybrid".to_string()))
+        );
+
+        // Profiles can be selected programmatically too.
+        let config: Config = Config::default().with_profile(Profile::Wan)?;
+        crate::ensure_eq!(config.profile()?, Some(Profile::Wan));
+        crate::ensure_eq!(config.tunables()?.len(), Profile::Wan.tunables().len());
+
+        crate::ensure_eq!(Config::parse("tunables:\n  profile: fast\n").is_err(), true);
+        crate::ensure_eq!(Config::default().profile()?, None);
+
+        Ok(())
+    }
+
+    /// Tests that routes are read in order, with an optional gateway, and that they are unset by default.
+    #[test]
+    fn test_config_routes() -> Result<()> {
+        let yaml: &str = "
+catnip:
+  routes:
+    - destination: 0.0.0.0
+      prefix_len: 0
+      gateway: 192.168.1.254
+    - destination: 192.168.1.0
+      prefix_len: 24
+    - destination: 10.0.0.0
+      prefix_len: 8
+      interface: 1
+";
+        let config: Config = Config(YamlLoader::load_from_str(yaml)?.remove(0));
+        let routes: Vec<Route> = match config.routes()? {
+            Some(routes) => routes,
+            None => anyhow::bail!("routes should be set"),
+        };
+        crate::ensure_eq!(routes.len(), 3);
+        crate::ensure_eq!(routes[0].gateway(), Some(Ipv4Addr::new(192, 168, 1, 254)));
+        crate::ensure_eq!(routes[1].destination(), Ipv4Addr::new(192, 168, 1, 0));
+        crate::ensure_eq!(routes[1].gateway(), None);
+        crate::ensure_eq!(routes[1].interface(), 0);
+        crate::ensure_eq!(routes[2].interface(), 1);
+
+        // Routes must not have host bits set.
+        let yaml: &str = "catnip:\n  routes:\n    - destination: 192.168.1.1\n      prefix_len: 24\n";
+        let config: Config = Config(YamlLoader::load_from_str(yaml)?.remove(0));
+        crate::ensure_eq!(config.routes().is_err(), true);
+
+        crate::ensure_eq!(Config::default().routes()?, None);
+
+        Ok(())
+    }
+
+    /// Tests that parameters can be set programmatically, creating sections along the way.
+    #[test]
+    fn test_config_with() -> Result<()> {
+        let config: Config = Config::default()
+            .with("catnip.my_ipv4_addr", "192.168.1.1")?
+            .with("catnip.vlan.id", "100")?
+            .with("catmem.doorbell", "true")?
+            .with("tunables.log.level", "debug")?;
+        crate::ensure_eq!(config.local_ipv4_addr()?, Ipv4Addr::new(192, 168, 1, 1));
+        crate::ensure_eq!(config.0["catnip"]["vlan"]["id"].as_i64(), Some(100));
+        crate::ensure_eq!(config.0["catmem"]["doorbell"].as_bool(), Some(true));
+        crate::ensure_eq!(config.tunables()?, vec![("log.level".to_string(), "debug".to_string())]);
+
+        // Parameters that are already set are overridden, and "null" unsets them.
+        let config: Config = config
+            .with("catnip.vlan.id", "200")?
+            .with("catnip.my_ipv4_addr", "null")?;
+        crate::ensure_eq!(config.0["catnip"]["vlan"]["id"].as_i64(), Some(200));
+        crate::ensure_eq!(config.local_ipv4_addr().is_err(), true);
+
+        // Overrides are checked against the schema.
+        crate::ensure_eq!(Config::default().with("catnip.vlan.id", "4095").is_err(), true);
+        crate::ensure_eq!(
+            Config::default().with("catnip.my_ipv4_addr", "localhost").is_err(),
+            true
+        );
+        crate::ensure_eq!(Config::default().with("catnip.unknown", "1").is_err(), true);
+        crate::ensure_eq!(Config::default().with("catnip..vlan", "1").is_err(), true);
+        crate::ensure_eq!(
+            Config::default()
+                .with("catnip.disable_arp", "true")?
+                .with("catnip.disable_arp.enabled", "true")
+                .is_err(),
+            true
+        );
+
+        Ok(())
+    }
+
+    /// Tests that environment variables with the override prefix are mapped to parameters.
+    #[test]
+    fn test_config_overrides() -> Result<()> {
+        let vars: Vec<(String, String)> = vec![
+            (
+                "DEMIKERNEL__CATNIP__MY_LINK_ADDR".to_string(),
+                "12:34:56:78:9a:bc".to_string(),
+            ),
+            (
+                "DEMIKERNEL__DPDK__EAL_INIT".to_string(),
+                "[\"\", \"-c\", \"0xff\"]".to_string(),
+            ),
+            ("DEMIKERNEL__CATNIP__VLAN".to_string(), "{id: 10, pcp: 1}".to_string()),
+            ("DEMIKERNEL__CATNIP__VLAN__PCP".to_string(), "3".to_string()),
+            ("PATH".to_string(), "/usr/bin".to_string()),
+        ];
+        let config: Config = Config::parse("catnip:\n  my_ipv4_addr: 192.168.1.1\n")?.with_overrides(vars)?;
+        crate::ensure_eq!(config.local_ipv4_addr()?, Ipv4Addr::new(192, 168, 1, 1));
+        crate::ensure_eq!(
+            config.local_link_addr()?,
+            MacAddress::new([0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc])
+        );
+        crate::ensure_eq!(config.0["dpdk"]["eal_init"].as_vec().map(|args| args.len()), Some(3));
+        // Sections are overridden before the parameters in them.
+        crate::ensure_eq!(config.0["catnip"]["vlan"]["id"].as_i64(), Some(10));
+        crate::ensure_eq!(config.0["catnip"]["vlan"]["pcp"].as_i64(), Some(3));
+        crate::ensure_eq!(config.0["path"].is_badvalue(), true);
+
+        let vars: Vec<(String, String)> = vec![("DEMIKERNEL__CATNIP__MTU".to_string(), "1500".to_string())];
+        crate::ensure_eq!(Config::default().with_overrides(vars).is_err(), true);
+
+        Ok(())
+    }
+}
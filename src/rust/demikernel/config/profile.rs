@@ -0,0 +1,164 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Profiles of tunables. Each profile sets a group of TCP, UDP and scheduler tunables to values that make sense
+//! together for a kind of deployment, so that applications get sane behavior without tuning each knob. Tunables that
+//! are set explicitly in the configuration file take precedence over those of the profile.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use ::std::{
+    fmt,
+    str::FromStr,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Names of the profiles, as they are written in the configuration file.
+pub const PROFILE_NAMES: &[&str] = &["low-latency", "throughput", "wan"];
+
+/// Tunables of the low-latency profile.
+const LOW_LATENCY: &[(&str, &str)] = &[
+    // Never sleep, so that nothing is added to the time it takes to react to a packet.
+    ("wait.policy", "busy_poll"),
+    // Acknowledge right away and retransmit quickly, as round trips are short.
+    ("tcp.ack_delay_timeout_ms", "0"),
+    ("tcp.rto_initial_us", "10000"),
+    ("tcp.rto_min_us", "1000"),
+    ("tcp.rto_max_us", "1000000"),
+    ("tcp.handshake_timeout_ms", "100"),
+    ("tcp.initial_cwnd", "10"),
+    // Keep queues short, so that data does not wait behind a backlog.
+    ("udp.recv_queue_max_datagrams", "256"),
+];
+
+/// Tunables of the throughput profile.
+const THROUGHPUT: &[(&str, &str)] = &[
+    // Spin for a while before sleeping, so that back-to-back transfers do not pay for wake ups.
+    ("wait.policy", "hybrid"),
+    ("wait.spin_micros", "200"),
+    // Open large windows and buffer enough data to keep them full.
+    ("tcp.window_scale", "7"),
+    ("tcp.initial_cwnd", "10"),
+    ("tcp.send_buffer_size", "8388608"),
+    ("tcp.receive_buffer_autotuning", "true"),
+    ("tcp.receive_buffer_max_size", "16777216"),
+    // Delay acknowledgements, so that fewer of them are sent.
+    ("tcp.ack_delay_timeout_ms", "40"),
+    ("udp.recv_queue_max_datagrams", "8192"),
+    ("udp.recv_queue_max_bytes", "16777216"),
+];
+
+/// Tunables of the WAN profile.
+const WAN: &[(&str, &str)] = &[
+    // Sleep until there is something to do, as round trips dwarf wake ups.
+    ("wait.policy", "block"),
+    // Open windows large enough to fill long paths.
+    ("tcp.window_scale", "7"),
+    ("tcp.send_buffer_size", "4194304"),
+    ("tcp.receive_buffer_autotuning", "true"),
+    ("tcp.receive_buffer_max_size", "16777216"),
+    // Be patient with slow and lossy paths.
+    ("tcp.rto_initial_us", "1000000"),
+    ("tcp.rto_min_us", "200000"),
+    ("tcp.rto_max_us", "60000000"),
+    ("tcp.handshake_retries", "6"),
+    ("tcp.handshake_timeout_ms", "3000"),
+    ("tcp.ack_delay_timeout_ms", "40"),
+];
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// A named group of tunables.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Profile {
+    /// Reacts as fast as possible, at the cost of a whole core. This suits request-response workloads within a
+    /// datacenter.
+    LowLatency,
+    /// Moves as much data as possible, at the cost of memory and latency. This suits bulk transfers.
+    Throughput,
+    /// Copes with long round trips and losses. This suits connections that cross the Internet.
+    Wan,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Profile {
+    /// Returns the tunables of the target profile, keyed by their dotted path in the "tunables" section of the
+    /// configuration file.
+    pub fn tunables(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Profile::LowLatency => LOW_LATENCY,
+            Profile::Throughput => THROUGHPUT,
+            Profile::Wan => WAN,
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl FromStr for Profile {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "low-latency" => Ok(Profile::LowLatency),
+            "throughput" => Ok(Profile::Throughput),
+            "wan" => Ok(Profile::Wan),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Profile::LowLatency => write!(f, "low-latency"),
+            Profile::Throughput => write!(f, "throughput"),
+            Profile::Wan => write!(f, "wan"),
+        }
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Profile,
+        PROFILE_NAMES,
+    };
+    use crate::demikernel::config::Config;
+    use ::anyhow::Result;
+
+    /// Tests that every profile is named consistently and only sets tunables that the configuration file accepts.
+    #[test]
+    fn test_profile_tunables_are_valid() -> Result<()> {
+        for name in PROFILE_NAMES {
+            let profile: Profile = match name.parse() {
+                Ok(profile) => profile,
+                Err(()) => anyhow::bail!("profile {:?} should parse", name),
+            };
+            crate::ensure_eq!(profile.to_string(), *name);
+            for (tunable, value) in profile.tunables() {
+                if let Err(e) = Config::default().with(&format!("tunables.{}", tunable), value) {
+                    anyhow::bail!("profile {:?} sets invalid tunable {:?} (error={:?})", name, tunable, e);
+                }
+            }
+        }
+        crate::ensure_eq!("balanced".parse::<Profile>().is_err(), true);
+        Ok(())
+    }
+}
@@ -0,0 +1,480 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Schema of the configuration file. Every parameter that Demikernel reads is listed here along with the values that
+//! it accepts, so that the whole file is checked when it is loaded and misconfigurations are reported with the path of
+//! the offending parameter (e.g. "catnip.vlan.id"), instead of surfacing as panics deep in the initialization of a
+//! LibOS. Parameters that are set to null are treated as unset. Whether a parameter is required depends on the LibOS,
+//! so that is checked when the parameter is read.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    demikernel::config::profile::PROFILE_NAMES,
+    runtime::{
+        affinity::{
+            MAX_CORES,
+            MAX_REALTIME_PRIORITY,
+        },
+        fail::Fail,
+    },
+    MacAddress,
+};
+use ::std::net::Ipv4Addr;
+use ::yaml_rust::Yaml;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Values that a parameter accepts.
+pub enum Kind {
+    /// Anything. This is used for sections that belong to applications.
+    Any,
+    Bool,
+    String,
+    Ipv4Addr,
+    MacAddress,
+    /// A string of hexadecimal digits, two per byte.
+    Hex,
+    /// An integer between the given bounds, inclusive.
+    Integer(i64, i64),
+    /// One of the given strings.
+    OneOf(&'static [&'static str]),
+    /// A list of values of the given kind.
+    List(&'static Kind),
+    /// A mapping with keys and values of the given kinds.
+    Map(&'static Kind, &'static Kind),
+    /// A mapping with the given parameters, and only those.
+    Section(&'static [(&'static str, Kind)]),
+}
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+const U16: Kind = Kind::Integer(0, u16::MAX as i64);
+const U32: Kind = Kind::Integer(0, u32::MAX as i64);
+const QUEUE_COUNT: Kind = Kind::Integer(1, u16::MAX as i64);
+const MILLIS: Kind = Kind::Integer(0, i64::MAX);
+const POSITIVE_MILLIS: Kind = Kind::Integer(1, i64::MAX);
+
+const SECURITY_ASSOCIATION: &[(&str, Kind)] = &[
+    ("spi", U32),
+    ("direction", Kind::OneOf(&["inbound", "outbound"])),
+    ("peer", Kind::Ipv4Addr),
+    ("algorithm", Kind::OneOf(&["aes-128-gcm", "chacha20-poly1305"])),
+    ("key", Kind::Hex),
+    ("salt", Kind::Hex),
+];
+
+const ROUTE: &[(&str, Kind)] = &[
+    ("destination", Kind::Ipv4Addr),
+    ("prefix_len", Kind::Integer(0, 32)),
+    ("gateway", Kind::Ipv4Addr),
+    ("interface", U32),
+];
+
+const CATNIP: &[(&str, Kind)] = &[
+    ("my_ipv4_addr", Kind::Ipv4Addr),
+    ("my_link_addr", Kind::MacAddress),
+    ("my_interface_name", Kind::String),
+    ("arp_table", Kind::Map(&Kind::MacAddress, &Kind::Ipv4Addr)),
+    ("disable_arp", Kind::Bool),
+    // VLAN IDs 0 and 4095 are reserved.
+    (
+        "vlan",
+        Kind::Section(&[("id", Kind::Integer(1, 4094)), ("pcp", Kind::Integer(0, 7))]),
+    ),
+    (
+        "ipsec",
+        Kind::Section(&[(
+            "security_associations",
+            Kind::List(&Kind::Section(SECURITY_ASSOCIATION)),
+        )]),
+    ),
+    ("routes", Kind::List(&Kind::Section(ROUTE))),
+];
+
+const DPDK: &[(&str, Kind)] = &[
+    ("eal_init", Kind::List(&Kind::String)),
+    ("rx_queues", QUEUE_COUNT),
+    ("tx_queues", QUEUE_COUNT),
+    ("queue_id", U16),
+    (
+        "rss",
+        Kind::Section(&[("hash_key", Kind::Hex), ("reta", Kind::List(&U16))]),
+    ),
+    ("checksum_offload", Kind::Bool),
+    ("tso", Kind::Bool),
+    ("tls_offload", Kind::Bool),
+    ("hw_timestamps", Kind::Bool),
+    (
+        "numa",
+        Kind::Section(&[
+            ("placement", Kind::OneOf(&["nic", "core", "any"])),
+            ("fallback", Kind::Bool),
+        ]),
+    ),
+];
+
+const CATNAP: &[(&str, Kind)] = &[
+    (
+        "tcp_keepalive",
+        Kind::Section(&[("enabled", Kind::Bool), ("time_millis", U32), ("interval", U32)]),
+    ),
+    (
+        "linger",
+        Kind::Section(&[("enabled", Kind::Bool), ("time_seconds", U16)]),
+    ),
+    ("use_nagle", Kind::Bool),
+    ("registered_io", Kind::Bool),
+    ("local_shared_memory", Kind::Bool),
+];
+
+const CATMEM: &[(&str, Kind)] = &[
+    ("heartbeat_interval_millis", MILLIS),
+    ("idle_timeout_millis", MILLIS),
+    ("doorbell", Kind::Bool),
+];
+
+const CATPOWDER: &[(&str, Kind)] = &[
+    (
+        "xdp",
+        Kind::Section(&[("enabled", Kind::Bool), ("queue_id", U32), ("zero_copy", Kind::Bool)]),
+    ),
+    ("tap", Kind::Section(&[("enabled", Kind::Bool)])),
+    (
+        "vhost_user",
+        Kind::Section(&[("enabled", Kind::Bool), ("socket_path", Kind::String)]),
+    ),
+    ("gso", Kind::Bool),
+];
+
+const TUNABLES: &[(&str, Kind)] = &[
+    ("reload_on_sighup", Kind::Bool),
+    ("profile", Kind::OneOf(PROFILE_NAMES)),
+    ("log", Kind::Section(&[("level", Kind::String), ("sink", Kind::String)])),
+    (
+        "arp",
+        Kind::Section(&[
+            ("cache_ttl_ms", POSITIVE_MILLIS),
+            ("request_timeout_ms", POSITIVE_MILLIS),
+            ("retry_count", Kind::Integer(0, u32::MAX as i64)),
+            ("max_pending", Kind::Integer(1, u32::MAX as i64)),
+            ("request_rate", Kind::Integer(0, u32::MAX as i64)),
+        ]),
+    ),
+    (
+        "icmp",
+        Kind::Section(&[
+            ("rate", Kind::Integer(0, u32::MAX as i64)),
+            ("burst", Kind::Integer(0, u32::MAX as i64)),
+            ("echo_reply_rate", Kind::Integer(0, u32::MAX as i64)),
+            ("unreachable_rate", Kind::Integer(0, u32::MAX as i64)),
+            ("time_exceeded_rate", Kind::Integer(0, u32::MAX as i64)),
+            ("disabled", Kind::String),
+        ]),
+    ),
+    (
+        "tcp",
+        Kind::Section(&[
+            ("handshake_retries", Kind::Integer(1, u32::MAX as i64)),
+            ("handshake_timeout_ms", POSITIVE_MILLIS),
+            ("receive_window_size", Kind::Integer(1, u16::MAX as i64)),
+            ("window_scale", Kind::Integer(0, 14)),
+            ("ack_delay_timeout_ms", Kind::Integer(0, 500)),
+            ("initial_cwnd", Kind::Integer(1, u32::MAX as i64)),
+            ("rto_initial_us", Kind::Integer(1, i64::MAX)),
+            ("rto_min_us", Kind::Integer(1, i64::MAX)),
+            ("rto_max_us", Kind::Integer(1, i64::MAX)),
+            ("send_buffer_size", Kind::Integer(1, i64::MAX)),
+            ("receive_buffer_autotuning", Kind::Bool),
+            ("receive_buffer_max_size", Kind::Integer(1, i64::MAX)),
+            ("syn_cookie_threshold", Kind::Integer(0, i64::MAX)),
+        ]),
+    ),
+    (
+        "udp",
+        Kind::Section(&[
+            ("recv_queue_max_datagrams", Kind::Integer(1, i64::MAX)),
+            ("recv_queue_max_bytes", Kind::Integer(1, i64::MAX)),
+            ("checksum_generation", Kind::Bool),
+        ]),
+    ),
+    (
+        "memory",
+        Kind::Section(&[("limit_bytes", Kind::Integer(0, i64::MAX)), ("policy", Kind::String)]),
+    ),
+    (
+        "admission",
+        Kind::Section(&[
+            ("connection_rate", Kind::Integer(0, u32::MAX as i64)),
+            ("connection_burst", Kind::Integer(0, u32::MAX as i64)),
+            ("datagram_rate", Kind::Integer(0, u32::MAX as i64)),
+            ("datagram_burst", Kind::Integer(0, u32::MAX as i64)),
+            ("blocklist", Kind::String),
+        ]),
+    ),
+    (
+        "flows",
+        Kind::Section(&[
+            ("max_entries", Kind::Integer(0, i64::MAX)),
+            ("idle_timeout_ms", Kind::Integer(0, i64::MAX)),
+            ("policy", Kind::OneOf(&["evict_lru", "refuse"])),
+        ]),
+    ),
+    (
+        "wait",
+        Kind::Section(&[
+            ("policy", Kind::OneOf(&["busy_poll", "hybrid", "block"])),
+            ("spin_micros", Kind::Integer(0, i64::MAX)),
+        ]),
+    ),
+];
+
+const ENGINE: &[(&str, Kind)] = &[
+    ("cores", Kind::List(&Kind::Integer(0, MAX_CORES as i64 - 1))),
+    ("nice", Kind::Integer(-20, 19)),
+    ("realtime_priority", Kind::Integer(0, MAX_REALTIME_PRIORITY as i64)),
+    ("timer_slack_ns", Kind::Integer(0, u32::MAX as i64)),
+];
+
+/// Schema of the whole configuration file.
+pub const SCHEMA: Kind = Kind::Section(&[
+    // Sections of the demo applications.
+    ("client", Kind::Any),
+    ("server", Kind::Any),
+    ("catnip", Kind::Section(CATNIP)),
+    ("dpdk", Kind::Section(DPDK)),
+    ("catnap", Kind::Section(CATNAP)),
+    ("catmem", Kind::Section(CATMEM)),
+    ("catpowder", Kind::Section(CATPOWDER)),
+    ("engine", Kind::Section(ENGINE)),
+    ("tunables", Kind::Section(TUNABLES)),
+]);
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Checks that [yaml], which is the value of the parameter at [path], is of the given [kind].
+pub fn validate(yaml: &Yaml, kind: &Kind, path: &str) -> Result<(), Fail> {
+    if yaml.is_null() {
+        return Ok(());
+    }
+    match (kind, yaml) {
+        (Kind::Any, _) => Ok(()),
+        (Kind::Bool, Yaml::Boolean(_)) => Ok(()),
+        (Kind::Bool, _) => invalid(path, "a boolean"),
+        (Kind::String, Yaml::String(_)) => Ok(()),
+        (Kind::String, _) => invalid(path, "a string"),
+        (Kind::Ipv4Addr, Yaml::String(addr)) if addr.parse::<Ipv4Addr>().is_ok() => Ok(()),
+        (Kind::Ipv4Addr, _) => invalid(path, "an IPv4 address"),
+        (Kind::MacAddress, Yaml::String(addr)) if MacAddress::parse_str(addr).is_ok() => Ok(()),
+        (Kind::MacAddress, _) => invalid(path, "a link address"),
+        (Kind::Hex, Yaml::String(digits)) if digits.len() % 2 == 0 && digits.chars().all(|c| c.is_ascii_hexdigit()) => {
+            Ok(())
+        },
+        (Kind::Hex, _) => invalid(path, "an even number of hexadecimal digits"),
+        (Kind::Integer(min, max), Yaml::Integer(value)) if value < min || value > max => {
+            let cause: String = format!(
+                "parameter \"{}\" is out of range (expected an integer between {} and {})",
+                path, min, max
+            );
+            error!("validate(): {}", cause);
+            Err(Fail::new(libc::ERANGE, &cause))
+        },
+        (Kind::Integer(..), Yaml::Integer(_)) => Ok(()),
+        (Kind::Integer(..), _) => invalid(path, "an integer"),
+        (Kind::OneOf(values), Yaml::String(value)) if values.contains(&value.as_str()) => Ok(()),
+        (Kind::OneOf(values), _) => invalid(path, &format!("one of {:?}", values)),
+        (Kind::List(kind), Yaml::Array(items)) => {
+            for (i, item) in items.iter().enumerate() {
+                validate(item, kind, &format!("{}[{}]", path, i))?;
+            }
+            Ok(())
+        },
+        (Kind::List(_), _) => invalid(path, "a list"),
+        (Kind::Map(key_kind, value_kind), Yaml::Hash(entries)) => {
+            for (key, value) in entries {
+                let key_path: String = format!("{}.{}", path, key_name(key));
+                validate(key, key_kind, &key_path)?;
+                validate(value, value_kind, &key_path)?;
+            }
+            Ok(())
+        },
+        (Kind::Map(..), _) => invalid(path, "a mapping"),
+        (Kind::Section(parameters), Yaml::Hash(entries)) => {
+            for (key, value) in entries {
+                let name: String = key_name(key);
+                let key_path: String = if path.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}.{}", path, name)
+                };
+                match parameters.iter().find(|(parameter, _)| *parameter == name) {
+                    Some((_, kind)) => validate(value, kind, &key_path)?,
+                    None => {
+                        let cause: String = format!("unknown parameter \"{}\"", key_path);
+                        error!("validate(): {}", cause);
+                        return Err(Fail::new(libc::EINVAL, &cause));
+                    },
+                }
+            }
+            Ok(())
+        },
+        (Kind::Section(_), _) => invalid(path, "a mapping"),
+    }
+}
+
+/// Fails because the parameter at [path] is not [expected].
+fn invalid(path: &str, expected: &str) -> Result<(), Fail> {
+    let cause: String = format!("invalid value for parameter \"{}\" (expected {})", path, expected);
+    error!("validate(): {}", cause);
+    Err(Fail::new(libc::EINVAL, &cause))
+}
+
+/// Formats a mapping key for error messages.
+fn key_name(key: &Yaml) -> String {
+    match key {
+        Yaml::String(key) => key.clone(),
+        Yaml::Integer(key) => key.to_string(),
+        Yaml::Boolean(key) => key.to_string(),
+        _ => format!("{:?}", key),
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        validate,
+        Kind,
+        SCHEMA,
+        TUNABLES,
+    };
+    use crate::runtime::fail::Fail;
+    use ::anyhow::Result;
+    use ::yaml_rust::{
+        Yaml,
+        YamlLoader,
+    };
+
+    /// Validates [yaml] against the schema of the configuration file.
+    fn check(yaml: &str) -> Result<Result<(), Fail>> {
+        Ok(validate(&YamlLoader::load_from_str(yaml)?.remove(0), &SCHEMA, ""))
+    }
+
+    /// Tests that a complete configuration file is accepted, including parameters that are set to null.
+    #[test]
+    fn test_schema_accepts_valid_config() -> Result<()> {
+        let yaml: &str = "
+client:
+  connect_to:
+    host: 192.168.1.2
+    port: 56789
+catnip:
+  my_ipv4_addr: 192.168.1.1
+  my_link_addr: \"12:34:56:78:9a:bc\"
+  my_interface_name: eth0
+  arp_table:
+    \"12:34:56:78:9a:bd\": 192.168.1.2
+  vlan:
+    id: null
+    pcp: 3
+  routes:
+    - destination: 0.0.0.0
+      prefix_len: 0
+      gateway: 192.168.1.254
+    - destination: 192.168.1.0
+      prefix_len: 24
+dpdk:
+  eal_init: [\"\", \"-c\", \"0xff\"]
+  rx_queues: 4
+  rss:
+    reta: [0, 1, 2, 3]
+catmem:
+  heartbeat_interval_millis: 100
+engine:
+  cores: [2, 3]
+  nice: -5
+  realtime_priority: null
+tunables:
+  tcp:
+    window_scale: 7
+    rto_min_us: null
+    receive_buffer_autotuning: false
+";
+        crate::ensure_eq!(check(yaml)?.is_ok(), true);
+        Ok(())
+    }
+
+    /// Tests that the tunables of the configuration files that ship with the repository are accepted, so that new
+    /// tunables are not added to them without also being added to the schema. Their other sections hold placeholders.
+    #[test]
+    fn test_schema_accepts_shipped_tunables() -> Result<()> {
+        for yaml in [
+            include_str!("../../../../scripts/config/default.yaml"),
+            include_str!("../../../../scripts/config/azure.yaml"),
+        ] {
+            let tunables: Yaml = YamlLoader::load_from_str(yaml)?.remove(0)["tunables"].clone();
+            if let Err(e) = validate(&tunables, &Kind::Section(TUNABLES), "tunables") {
+                anyhow::bail!("shipped tunables should be valid: {:?}", e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Tests that unknown parameters are reported with their full path.
+    #[test]
+    fn test_schema_rejects_unknown_parameter() -> Result<()> {
+        let e: Fail = match check("catnip:\n  vlan:\n    priority: 3\n")? {
+            Ok(()) => anyhow::bail!("unknown parameter should be rejected"),
+            Err(e) => e,
+        };
+        crate::ensure_eq!(e.errno, libc::EINVAL);
+        crate::ensure_eq!(e.cause.contains("\"catnip.vlan.priority\""), true);
+
+        crate::ensure_eq!(check("catnop: {}\n")?.is_err(), true);
+        Ok(())
+    }
+
+    /// Tests that integers outside of their range are rejected.
+    #[test]
+    fn test_schema_rejects_out_of_range() -> Result<()> {
+        let e: Fail = match check("catnip:\n  vlan:\n    id: 4095\n")? {
+            Ok(()) => anyhow::bail!("reserved VLAN ID should be rejected"),
+            Err(e) => e,
+        };
+        crate::ensure_eq!(e.errno, libc::ERANGE);
+        crate::ensure_eq!(e.cause.contains("\"catnip.vlan.id\""), true);
+
+        crate::ensure_eq!(check("dpdk:\n  rx_queues: 0\n")?.is_err(), true);
+        crate::ensure_eq!(check("dpdk:\n  rss:\n    reta: [0, 70000]\n")?.is_err(), true);
+        Ok(())
+    }
+
+    /// Tests that values of the wrong type are rejected.
+    #[test]
+    fn test_schema_rejects_invalid_values() -> Result<()> {
+        let e: Fail = match check("catnip:\n  my_ipv4_addr: 192.168.1\n")? {
+            Ok(()) => anyhow::bail!("malformed IPv4 address should be rejected"),
+            Err(e) => e,
+        };
+        crate::ensure_eq!(e.errno, libc::EINVAL);
+        crate::ensure_eq!(e.cause.contains("\"catnip.my_ipv4_addr\""), true);
+
+        crate::ensure_eq!(check("catnip:\n  my_link_addr: \"12:34\"\n")?.is_err(), true);
+        crate::ensure_eq!(check("catnip:\n  disable_arp: yes\n")?.is_err(), true);
+        crate::ensure_eq!(check("dpdk:\n  eal_init: \"-c 0xff\"\n")?.is_err(), true);
+        crate::ensure_eq!(check("dpdk:\n  rss:\n    hash_key: \"abc\"\n")?.is_err(), true);
+        crate::ensure_eq!(check("catmem: 100\n")?.is_err(), true);
+        Ok(())
+    }
+}
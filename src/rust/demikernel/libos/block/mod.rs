@@ -0,0 +1,140 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+mod nvme;
+
+//======================================================================================================================
+// Exports
+//======================================================================================================================
+
+pub use self::nvme::SharedNvmeDriver;
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use self::nvme::NvmeNamespace;
+use crate::runtime::{
+    fail::Fail,
+    limits,
+    memory::DemiBuffer,
+    queue::{
+        IoQueue,
+        QType,
+    },
+    SharedObject,
+};
+use ::std::{
+    any::Any,
+    ops::{
+        Deref,
+        DerefMut,
+    },
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// A queue of asynchronous reads and writes on an NVMe namespace, which SPDK drives from user space. As with file
+/// queues, the namespace is streamed: pushes write at the position of the queue and pops read from it. Transfers are
+/// made of whole sectors.
+pub struct BlockQueue {
+    namespace: NvmeNamespace,
+    /// Offset of the next read or write, in bytes.
+    position: u64,
+}
+
+#[derive(Clone)]
+pub struct SharedBlockQueue(SharedObject<BlockQueue>);
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl SharedBlockQueue {
+    /// Opens namespace [nsid] of the NVMe controller at the SPDK transport ID [trid].
+    pub fn open(driver: &mut SharedNvmeDriver, trid: &str, nsid: u32) -> Result<Self, Fail> {
+        Ok(Self(SharedObject::new(BlockQueue {
+            namespace: driver.open(trid, nsid)?,
+            position: 0,
+        })))
+    }
+
+    /// Writes [buf] to the namespace. Its length must be a multiple of the sector size.
+    pub async fn push_coroutine(&mut self, buf: DemiBuffer) -> Result<(), Fail> {
+        let (lba, count): (u64, u32) = self.reserve(buf.len())?;
+        if self.namespace.num_sectors() - lba < count as u64 {
+            let cause: String = format!("write past the end of the namespace (lba={:?}, count={:?})", lba, count);
+            warn!("push_coroutine(): {}", cause);
+            return Err(Fail::new(libc::ENOSPC, &cause));
+        }
+        self.position += buf.len() as u64;
+        self.namespace.write(lba, &buf).await
+    }
+
+    /// Reads [size] bytes from the namespace, or as many whole sectors as fit in a receive buffer if [size] is None.
+    /// [size] must be a multiple of the sector size. Reads are cut short at the end of the namespace, where they return
+    /// an empty buffer.
+    pub async fn pop_coroutine(&mut self, size: Option<usize>) -> Result<DemiBuffer, Fail> {
+        let sector_size: usize = self.namespace.sector_size() as usize;
+        let size: usize = size.unwrap_or(limits::RECVBUF_SIZE_MAX / sector_size * sector_size);
+        let (lba, count): (u64, u32) = self.reserve(size)?;
+        let count: u32 = (count as u64).min(self.namespace.num_sectors().saturating_sub(lba)) as u32;
+        if count == 0 {
+            return Ok(DemiBuffer::new(0));
+        }
+        self.position += count as u64 * sector_size as u64;
+        self.namespace.read(lba, count).await
+    }
+
+    /// Translates a transfer of [len] bytes at the position of the queue into sectors.
+    fn reserve(&self, len: usize) -> Result<(u64, u32), Fail> {
+        let sector_size: u64 = self.namespace.sector_size() as u64;
+        if len == 0 || len as u64 % sector_size != 0 {
+            let cause: String = format!(
+                "transfers must be made of whole sectors (len={:?}, sector_size={:?})",
+                len, sector_size
+            );
+            warn!("reserve(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        Ok((self.position / sector_size, (len as u64 / sector_size) as u32))
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl IoQueue for SharedBlockQueue {
+    fn get_qtype(&self) -> QType {
+        QType::BlockQueue
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+impl Deref for SharedBlockQueue {
+    type Target = BlockQueue;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl DerefMut for SharedBlockQueue {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
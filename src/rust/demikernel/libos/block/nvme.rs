@@ -0,0 +1,364 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    memory::DemiBuffer,
+    poll_yield,
+    SharedObject,
+};
+use ::spdk_rs::{
+    spdk_env_dpdk_post_init,
+    spdk_env_init,
+    spdk_env_opts,
+    spdk_env_opts_init,
+    spdk_free,
+    spdk_nvme_connect,
+    spdk_nvme_cpl,
+    spdk_nvme_cpl_is_error,
+    spdk_nvme_ctrlr,
+    spdk_nvme_ctrlr_alloc_io_qpair,
+    spdk_nvme_ctrlr_free_io_qpair,
+    spdk_nvme_ctrlr_get_ns,
+    spdk_nvme_detach,
+    spdk_nvme_ns,
+    spdk_nvme_ns_cmd_read,
+    spdk_nvme_ns_cmd_write,
+    spdk_nvme_ns_get_num_sectors,
+    spdk_nvme_ns_get_sector_size,
+    spdk_nvme_ns_is_active,
+    spdk_nvme_qpair,
+    spdk_nvme_qpair_process_completions,
+    spdk_nvme_transport_id,
+    spdk_nvme_transport_id_parse,
+    spdk_zmalloc,
+    SPDK_ENV_SOCKET_ID_ANY,
+    SPDK_MALLOC_DMA,
+};
+use ::std::{
+    cell::Cell,
+    collections::HashMap,
+    ffi::{
+        c_void,
+        CString,
+    },
+    mem,
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    ptr,
+    rc::Rc,
+    slice,
+    sync::OnceLock,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Name of the SPDK environment.
+const ENV_NAME: &[u8] = b"demikernel\0";
+
+/// Alignment of buffers that devices transfer data to and from.
+const DMA_ALIGNMENT: usize = 4096;
+
+//======================================================================================================================
+// Static Variables
+//======================================================================================================================
+
+/// Outcome of initializing the SPDK environment, which happens once per process.
+static ENV: OnceLock<Result<(), libc::c_int>> = OnceLock::new();
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// An NVMe controller attached to this process. It is detached once the driver and all namespaces on it go away.
+pub struct NvmeController {
+    ctrlr: *mut spdk_nvme_ctrlr,
+}
+
+#[derive(Clone)]
+pub struct SharedNvmeController(SharedObject<NvmeController>);
+
+/// Driver for NVMe devices, which keeps track of the controllers that are attached to this process.
+pub struct NvmeDriver {
+    /// Attached controllers, by transport ID.
+    controllers: HashMap<String, SharedNvmeController>,
+}
+
+#[derive(Clone)]
+pub struct SharedNvmeDriver(SharedObject<NvmeDriver>);
+
+/// An NVMe namespace, along with an I/O queue pair of its controller to submit commands on.
+pub struct NvmeNamespace {
+    ns: *mut spdk_nvme_ns,
+    qpair: *mut spdk_nvme_qpair,
+    sector_size: u32,
+    num_sectors: u64,
+    /// Keeps the controller attached for as long as the namespace is open.
+    _controller: SharedNvmeController,
+}
+
+/// A command submitted to a device. The device transfers data to and from a DMA buffer, thus data is copied between it
+/// and DemiBuffers. Commands in flight are shared with SPDK, which releases its reference once they complete, so that
+/// the buffer outlives the coroutine that submitted the command if it goes away.
+struct Command {
+    /// Did the command succeed? None while it is in flight.
+    status: Cell<Option<bool>>,
+    buf: *mut c_void,
+    len: usize,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl SharedNvmeDriver {
+    /// Creates a driver, initializing the SPDK environment if it was not initialized yet.
+    pub fn new() -> Result<Self, Fail> {
+        init_env()?;
+        Ok(Self(SharedObject::new(NvmeDriver {
+            controllers: HashMap::new(),
+        })))
+    }
+
+    /// Opens namespace [nsid] of the controller at [trid], which is an SPDK transport ID, such as
+    /// "trtype:PCIe traddr:0000:04:00.0". The controller is attached to this process if it was not attached yet.
+    pub fn open(&mut self, trid: &str, nsid: u32) -> Result<NvmeNamespace, Fail> {
+        let controller: SharedNvmeController = match self.controllers.get(trid) {
+            Some(controller) => controller.clone(),
+            None => {
+                let controller: SharedNvmeController = SharedNvmeController::connect(trid)?;
+                self.controllers.insert(trid.to_string(), controller.clone());
+                controller
+            },
+        };
+
+        let ns: *mut spdk_nvme_ns = unsafe { spdk_nvme_ctrlr_get_ns(controller.ctrlr, nsid) };
+        if ns.is_null() || !unsafe { spdk_nvme_ns_is_active(ns) } {
+            let cause: String = format!("no such active namespace (trid={:?}, nsid={:?})", trid, nsid);
+            error!("open(): {}", cause);
+            return Err(Fail::new(libc::ENOENT, &cause));
+        }
+        let qpair: *mut spdk_nvme_qpair = unsafe { spdk_nvme_ctrlr_alloc_io_qpair(controller.ctrlr, ptr::null(), 0) };
+        if qpair.is_null() {
+            let cause: String = format!("failed to allocate I/O queue pair (trid={:?})", trid);
+            error!("open(): {}", cause);
+            return Err(Fail::new(libc::ENOMEM, &cause));
+        }
+
+        Ok(NvmeNamespace {
+            ns,
+            qpair,
+            sector_size: unsafe { spdk_nvme_ns_get_sector_size(ns) },
+            num_sectors: unsafe { spdk_nvme_ns_get_num_sectors(ns) },
+            _controller: controller,
+        })
+    }
+}
+
+impl SharedNvmeController {
+    /// Attaches the controller at [trid] to this process.
+    fn connect(trid: &str) -> Result<Self, Fail> {
+        let trid_c: CString = match CString::new(trid) {
+            Ok(trid_c) => trid_c,
+            Err(_) => return Err(Fail::new(libc::EINVAL, "transport ID has an interior nul byte")),
+        };
+        let mut transport_id: spdk_nvme_transport_id = unsafe { mem::zeroed() };
+        if unsafe { spdk_nvme_transport_id_parse(&mut transport_id, trid_c.as_ptr()) } != 0 {
+            let cause: String = format!("invalid transport ID (trid={:?})", trid);
+            error!("connect(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        let ctrlr: *mut spdk_nvme_ctrlr = unsafe { spdk_nvme_connect(&transport_id, ptr::null(), 0) };
+        if ctrlr.is_null() {
+            let cause: String = format!("failed to attach controller (trid={:?})", trid);
+            error!("connect(): {}", cause);
+            return Err(Fail::new(libc::ENODEV, &cause));
+        }
+        Ok(Self(SharedObject::new(NvmeController { ctrlr })))
+    }
+}
+
+impl NvmeNamespace {
+    /// Size of a sector, in bytes. Transfers are made of whole sectors.
+    pub fn sector_size(&self) -> u32 {
+        self.sector_size
+    }
+
+    /// Number of sectors in the namespace.
+    pub fn num_sectors(&self) -> u64 {
+        self.num_sectors
+    }
+
+    /// Reads [count] sectors starting at [lba].
+    pub async fn read(&mut self, lba: u64, count: u32) -> Result<DemiBuffer, Fail> {
+        let command: Rc<Command> = Command::new(count as usize * self.sector_size as usize)?;
+        let ctx: *mut c_void = Rc::into_raw(command.clone()) as *mut c_void;
+        let ret: libc::c_int =
+            unsafe { spdk_nvme_ns_cmd_read(self.ns, self.qpair, command.buf, lba, count, Some(complete), ctx, 0) };
+        self.wait(&command, ctx, ret).await?;
+        DemiBuffer::from_slice(unsafe { slice::from_raw_parts(command.buf as *const u8, command.len) })
+    }
+
+    /// Writes [buf], which is made of whole sectors, starting at [lba].
+    pub async fn write(&mut self, lba: u64, buf: &DemiBuffer) -> Result<(), Fail> {
+        let command: Rc<Command> = Command::new(buf.len())?;
+        unsafe { slice::from_raw_parts_mut(command.buf as *mut u8, command.len) }.copy_from_slice(&buf[..]);
+        let count: u32 = (buf.len() / self.sector_size as usize) as u32;
+        let ctx: *mut c_void = Rc::into_raw(command.clone()) as *mut c_void;
+        let ret: libc::c_int =
+            unsafe { spdk_nvme_ns_cmd_write(self.ns, self.qpair, command.buf, lba, count, Some(complete), ctx, 0) };
+        self.wait(&command, ctx, ret).await
+    }
+
+    /// Waits for [command] to complete, given the return value [ret] of its submission. Completions are processed by
+    /// whichever coroutine waits on the queue pair, thus no background coroutine is needed.
+    async fn wait(&mut self, command: &Command, ctx: *mut c_void, ret: libc::c_int) -> Result<(), Fail> {
+        if ret != 0 {
+            // The command was not submitted, thus SPDK does not hold a reference to it.
+            drop(unsafe { Rc::from_raw(ctx as *const Command) });
+            let cause: String = format!("failed to submit command (errno={:?})", -ret);
+            warn!("wait(): {}", cause);
+            return Err(Fail::new(-ret, &cause));
+        }
+        loop {
+            let ret: i32 = unsafe { spdk_nvme_qpair_process_completions(self.qpair, 0) };
+            if ret < 0 {
+                let cause: String = format!("failed to process completions (errno={:?})", -ret);
+                error!("wait(): {}", cause);
+                return Err(Fail::new(-ret, &cause));
+            }
+            match command.status.get() {
+                Some(true) => return Ok(()),
+                Some(false) => {
+                    let cause: &str = "command failed";
+                    error!("wait(): {}", cause);
+                    return Err(Fail::new(libc::EIO, cause));
+                },
+                None => poll_yield().await,
+            }
+        }
+    }
+}
+
+impl Command {
+    /// Allocates a command that transfers [len] bytes.
+    fn new(len: usize) -> Result<Rc<Self>, Fail> {
+        let buf: *mut c_void = unsafe {
+            spdk_zmalloc(
+                len,
+                DMA_ALIGNMENT,
+                ptr::null_mut(),
+                SPDK_ENV_SOCKET_ID_ANY,
+                SPDK_MALLOC_DMA,
+            )
+        };
+        if buf.is_null() {
+            let cause: String = format!("failed to allocate DMA buffer (len={:?})", len);
+            error!("new(): {}", cause);
+            return Err(Fail::new(libc::ENOMEM, &cause));
+        }
+        Ok(Rc::new(Self {
+            status: Cell::new(None),
+            buf,
+            len,
+        }))
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Drop for NvmeController {
+    fn drop(&mut self) {
+        if unsafe { spdk_nvme_detach(self.ctrlr) } != 0 {
+            warn!("drop(): failed to detach controller");
+        }
+    }
+}
+
+impl Drop for NvmeNamespace {
+    fn drop(&mut self) {
+        // Commands in flight are aborted, which completes them and releases their buffers.
+        if unsafe { spdk_nvme_ctrlr_free_io_qpair(self.qpair) } != 0 {
+            warn!("drop(): failed to free I/O queue pair");
+        }
+    }
+}
+
+impl Drop for Command {
+    fn drop(&mut self) {
+        unsafe { spdk_free(self.buf) };
+    }
+}
+
+impl Deref for SharedNvmeController {
+    type Target = NvmeController;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl DerefMut for SharedNvmeController {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+impl Deref for SharedNvmeDriver {
+    type Target = NvmeDriver;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl DerefMut for SharedNvmeDriver {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Initializes the SPDK environment. If Catnip already initialized DPDK in this process, SPDK runs on top of it.
+fn init_env() -> Result<(), Fail> {
+    let result: &Result<(), libc::c_int> = ENV.get_or_init(|| unsafe {
+        let mut opts: spdk_env_opts = mem::zeroed();
+        spdk_env_opts_init(&mut opts);
+        opts.name = ENV_NAME.as_ptr() as *const libc::c_char;
+        match spdk_env_init(&opts) {
+            0 => Ok(()),
+            ret if ret == -libc::EALREADY => match spdk_env_dpdk_post_init(false) {
+                0 => Ok(()),
+                ret => Err(-ret),
+            },
+            ret => Err(-ret),
+        }
+    });
+    match result {
+        Ok(()) => Ok(()),
+        Err(errno) => {
+            let cause: String = format!("failed to initialize SPDK environment (errno={:?})", errno);
+            error!("init_env(): {}", cause);
+            Err(Fail::new(*errno, &cause))
+        },
+    }
+}
+
+/// Completion callback of commands, which SPDK calls with the reference to the command that it holds.
+extern "C" fn complete(ctx: *mut c_void, cpl: *const spdk_nvme_cpl) {
+    let command: Rc<Command> = unsafe { Rc::from_raw(ctx as *const Command) };
+    command.status.set(Some(!unsafe { spdk_nvme_cpl_is_error(cpl) }));
+}
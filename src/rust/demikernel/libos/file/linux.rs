@@ -0,0 +1,271 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    expect_some,
+    pal::linux::iouring::{
+        IoUring,
+        IoUringCqe,
+        IoUringSqe,
+        IORING_OP_READ,
+        IORING_OP_WRITE,
+    },
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        poll_yield,
+        SharedDemiRuntime,
+        SharedObject,
+    },
+};
+use ::futures::FutureExt;
+use ::slab::Slab;
+use ::std::{
+    fs::{
+        File,
+        OpenOptions,
+    },
+    future::Future,
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    os::fd::AsRawFd,
+    pin::Pin,
+    rc::Rc,
+    task::{
+        Context,
+        Poll,
+        Waker,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Number of entries in the submission queue.
+const IO_URING_SIZE: u32 = 256;
+
+/// Offset that makes io_uring read or write at the current position of a file, which is its end for files that were
+/// opened with O_APPEND.
+const CURRENT_POSITION: u64 = u64::MAX;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// A read or write submitted to the io_uring.
+struct Operation {
+    /// Result of the operation, once the kernel completed it.
+    result: Option<i32>,
+    /// Coroutine waiting on this operation.
+    waker: Option<Waker>,
+    /// Buffer and file that the kernel accesses while the operation is in flight. They are never read, only kept
+    /// alive, even if the coroutine that issued the operation goes away.
+    _buf: DemiBuffer,
+    _file: Rc<File>,
+    /// Did the coroutine that issued the operation go away? If so, the operation is released once it completes.
+    orphaned: bool,
+}
+
+/// Engine that runs file operations on an io_uring. A background coroutine submits the operations that coroutines
+/// issue and dispatches their completions.
+pub struct FileEngine {
+    io_uring: IoUring,
+    operations: Slab<Operation>,
+}
+
+#[derive(Clone)]
+pub struct SharedFileEngine(SharedObject<FileEngine>);
+
+/// Future that waits for an operation to complete.
+struct CompletionFuture {
+    engine: SharedFileEngine,
+    id: usize,
+    done: bool,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl SharedFileEngine {
+    /// Creates an engine, along with the background coroutine that drives it on [runtime].
+    pub fn new(runtime: &mut SharedDemiRuntime) -> Result<Self, Fail> {
+        let me: Self = Self(SharedObject::new(FileEngine {
+            io_uring: IoUring::new(IO_URING_SIZE)?,
+            operations: Slab::<Operation>::new(),
+        }));
+        let mut me2: Self = me.clone();
+        runtime.insert_background_coroutine(
+            "demikernel::file::io_uring",
+            Box::pin(async move { me2.poll().await }.fuse()),
+        )?;
+        Ok(me)
+    }
+
+    /// Opens the file at [path] with [options].
+    pub fn open(&self, options: &mut OpenOptions, path: &str) -> Result<File, Fail> {
+        match options.open(path) {
+            Ok(file) => Ok(file),
+            Err(e) => {
+                // Flags that do not go together are rejected before reaching the kernel, thus they come without errno.
+                let errno: i32 = e.raw_os_error().unwrap_or(libc::EINVAL);
+                let cause: String = format!("failed to open file (path={:?}, error={:?})", path, e);
+                error!("open(): {}", cause);
+                Err(Fail::new(errno, &cause))
+            },
+        }
+    }
+
+    /// Reads up to [len] bytes from [file] at [offset].
+    pub async fn read(&mut self, file: &Rc<File>, offset: u64, len: usize) -> Result<DemiBuffer, Fail> {
+        let mut buf: DemiBuffer = DemiBuffer::new(len as u16);
+        let sqe: IoUringSqe = IoUringSqe {
+            opcode: IORING_OP_READ,
+            fd: file.as_raw_fd(),
+            off: offset,
+            addr: buf[..].as_mut_ptr() as u64,
+            len: len as u32,
+            ..Default::default()
+        };
+        let nbytes: usize = self.submit_and_wait(sqe, buf.clone(), file.clone()).await?;
+        buf.trim(len - nbytes)?;
+        Ok(buf)
+    }
+
+    /// Writes [buf] to [file] at [offset], or at its current position if [offset] is None. Returns the number of bytes
+    /// that were written.
+    pub async fn write(&mut self, file: &Rc<File>, offset: Option<u64>, buf: DemiBuffer) -> Result<usize, Fail> {
+        let sqe: IoUringSqe = IoUringSqe {
+            opcode: IORING_OP_WRITE,
+            fd: file.as_raw_fd(),
+            off: offset.unwrap_or(CURRENT_POSITION),
+            addr: buf.as_ptr() as u64,
+            len: buf.len() as u32,
+            ..Default::default()
+        };
+        self.submit_and_wait(sqe, buf, file.clone()).await
+    }
+
+    /// Background function for submitting queued operations and dispatching their completions.
+    async fn poll(&mut self) {
+        loop {
+            if let Err(e) = self.io_uring.submit() {
+                warn!("poll(): failed to submit operations: {:?}", e);
+            }
+            while let Some(cqe) = self.io_uring.pop() {
+                self.complete(cqe);
+            }
+            // Yield for one iteration.
+            poll_yield().await;
+        }
+    }
+
+    /// Queues an operation for submission and waits for it to complete. The operation is submitted the next time that
+    /// the background coroutine runs, so that operations issued by different coroutines are submitted in batch.
+    async fn submit_and_wait(&mut self, mut sqe: IoUringSqe, buf: DemiBuffer, file: Rc<File>) -> Result<usize, Fail> {
+        let id: usize = self.operations.insert(Operation {
+            result: None,
+            waker: None,
+            _buf: buf,
+            _file: file,
+            orphaned: false,
+        });
+        sqe.user_data = id as u64;
+        if let Err(e) = self.io_uring.push(sqe) {
+            self.operations.remove(id);
+            return Err(e);
+        }
+
+        let result: i32 = CompletionFuture {
+            engine: self.clone(),
+            id,
+            done: false,
+        }
+        .await;
+        if result < 0 {
+            let cause: String = format!("file operation failed (errno={:?})", -result);
+            warn!("submit_and_wait(): {}", cause);
+            return Err(Fail::new(-result, &cause));
+        }
+        Ok(result as usize)
+    }
+
+    /// Dispatches a completion to the operation that it belongs to.
+    fn complete(&mut self, cqe: IoUringCqe) {
+        let id: usize = cqe.user_data as usize;
+        match self.operations.get_mut(id) {
+            Some(op) if op.orphaned => {
+                self.operations.remove(id);
+            },
+            Some(op) => {
+                op.result = Some(cqe.res);
+                if let Some(waker) = op.waker.take() {
+                    waker.wake();
+                }
+            },
+            None => warn!("complete(): completion for unknown operation (id={:?})", id),
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Deref for SharedFileEngine {
+    type Target = FileEngine;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl DerefMut for SharedFileEngine {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+impl Future for CompletionFuture {
+    type Output = i32;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        let self_: &mut Self = self.get_mut();
+        let mut engine: SharedFileEngine = self_.engine.clone();
+        let op: &mut Operation = expect_some!(engine.operations.get_mut(self_.id), "operation should exist");
+        match op.result {
+            Some(result) => {
+                engine.operations.remove(self_.id);
+                self_.done = true;
+                Poll::Ready(result)
+            },
+            None => {
+                op.waker = Some(context.waker().clone());
+                Poll::Pending
+            },
+        }
+    }
+}
+
+impl Drop for CompletionFuture {
+    fn drop(&mut self) {
+        // If the coroutine that waits on this operation goes away, the kernel may still be accessing the buffer of the
+        // operation, thus we cannot release it right away.
+        if !self.done {
+            let op: &mut Operation = expect_some!(self.engine.operations.get_mut(self.id), "operation should exist");
+            if op.result.is_some() {
+                self.engine.operations.remove(self.id);
+            } else {
+                op.orphaned = true;
+                op.waker = None;
+            }
+        }
+    }
+}
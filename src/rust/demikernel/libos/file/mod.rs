@@ -0,0 +1,255 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+#[cfg_attr(target_os = "linux", path = "linux.rs")]
+#[cfg_attr(target_os = "windows", path = "win.rs")]
+mod engine;
+
+//======================================================================================================================
+// Exports
+//======================================================================================================================
+
+pub use self::engine::SharedFileEngine;
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    limits,
+    memory::DemiBuffer,
+    queue::{
+        IoQueue,
+        QType,
+    },
+    SharedObject,
+};
+use ::std::{
+    any::Any,
+    fs::{
+        File,
+        OpenOptions,
+    },
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    rc::Rc,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Flags that files may be opened with.
+const SUPPORTED_FLAGS: libc::c_int =
+    libc::O_WRONLY | libc::O_RDWR | libc::O_CREAT | libc::O_EXCL | libc::O_TRUNC | libc::O_APPEND;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// A queue of asynchronous reads and writes on a file, so that a file is streamed like a socket: pushes write at the
+/// position of the queue and pops read from it. The position advances as soon as an operation is issued, thus
+/// operations in flight never overlap. Files that were opened with O_APPEND are always written at their end.
+pub struct FileQueue {
+    /// The open file. Operations in flight hold a reference to it, so that it is only closed once they are done.
+    file: Rc<File>,
+    /// Offset of the next read or write.
+    position: u64,
+    /// Do writes go to the end of the file?
+    append: bool,
+    /// Engine that runs the operations.
+    engine: SharedFileEngine,
+}
+
+#[derive(Clone)]
+pub struct SharedFileQueue(SharedObject<FileQueue>);
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl SharedFileQueue {
+    /// Opens the file at [path]. [flags] take O_RDONLY, O_WRONLY or O_RDWR, along with O_CREAT, O_EXCL, O_TRUNC and
+    /// O_APPEND, as in POSIX.
+    pub fn open(engine: SharedFileEngine, path: &str, flags: libc::c_int) -> Result<Self, Fail> {
+        if flags & !SUPPORTED_FLAGS != 0 {
+            let cause: String = format!("unsupported flags (flags={:#x})", flags & !SUPPORTED_FLAGS);
+            error!("open(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        let mut options: OpenOptions = OpenOptions::new();
+        match flags & (libc::O_WRONLY | libc::O_RDWR) {
+            libc::O_WRONLY => options.write(true),
+            libc::O_RDWR => options.read(true).write(true),
+            _ => options.read(true),
+        };
+        options
+            .append(flags & libc::O_APPEND != 0)
+            .truncate(flags & libc::O_TRUNC != 0)
+            .create(flags & libc::O_CREAT != 0)
+            .create_new(flags & libc::O_CREAT != 0 && flags & libc::O_EXCL != 0);
+        let file: File = engine.open(&mut options, path)?;
+
+        Ok(Self(SharedObject::new(FileQueue {
+            file: Rc::new(file),
+            position: 0,
+            append: flags & libc::O_APPEND != 0,
+            engine,
+        })))
+    }
+
+    /// Writes all of [buf] to the file.
+    pub async fn push_coroutine(&mut self, buf: DemiBuffer) -> Result<(), Fail> {
+        let offset: Option<u64> = if self.append {
+            None
+        } else {
+            let offset: u64 = self.position;
+            self.position += buf.len() as u64;
+            Some(offset)
+        };
+
+        let mut engine: SharedFileEngine = self.engine.clone();
+        let mut written: usize = 0;
+        while written < buf.len() {
+            let mut remaining: DemiBuffer = buf.clone();
+            remaining.adjust(written)?;
+            match engine
+                .write(&self.file, offset.map(|offset| offset + written as u64), remaining)
+                .await?
+            {
+                0 => {
+                    let cause: String = format!("file stopped taking data (written={}, len={})", written, buf.len());
+                    error!("push_coroutine(): {}", cause);
+                    return Err(Fail::new(libc::EIO, &cause));
+                },
+                n => written += n,
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads up to [size] bytes from the file. An empty buffer means that the end of the file was reached. If no other
+    /// operation was issued in the meantime, a short read moves the position back to the end of the data, so that
+    /// later reads pick up what is appended to the file.
+    pub async fn pop_coroutine(&mut self, size: Option<usize>) -> Result<DemiBuffer, Fail> {
+        let size: usize = size.unwrap_or(limits::RECVBUF_SIZE_MAX);
+        let offset: u64 = self.position;
+        self.position += size as u64;
+
+        let result: Result<DemiBuffer, Fail> = self.engine.clone().read(&self.file, offset, size).await;
+        if self.position == offset + size as u64 {
+            self.position = offset + result.as_ref().map_or(0, |buf| buf.len() as u64);
+        }
+        result
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl IoQueue for SharedFileQueue {
+    fn get_qtype(&self) -> QType {
+        QType::FileQueue
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+impl Deref for SharedFileQueue {
+    type Target = FileQueue;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl DerefMut for SharedFileQueue {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(all(test, feature = "catnap-libos"))]
+mod tests {
+    use crate::{
+        demikernel::config::Config,
+        runtime::types::{
+            demi_opcode_t,
+            demi_qresult_t,
+            demi_sgarray_t,
+        },
+        LibOS,
+        LibOSName,
+        QDesc,
+        QToken,
+    };
+    use ::anyhow::Result;
+    use ::std::{
+        path::PathBuf,
+        slice,
+    };
+
+    /// Tests that data pushed to a file queue is popped back once the file is reopened, and that the end of the file
+    /// pops an empty buffer.
+    #[test]
+    fn test_file_round_trip() -> Result<()> {
+        let path: PathBuf = std::env::temp_dir().join(format!("demikernel-file-{}", std::process::id()));
+        let path_str: &str = path.to_str().expect("temporary path should be valid unicode");
+        let data: Vec<u8> = (0..1024).map(|i| i as u8).collect();
+        let mut libos: LibOS = LibOS::with_config(LibOSName::Catnap, Config::default())?;
+
+        let qd: QDesc = libos.open_file(path_str, libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC)?;
+        let sga: demi_sgarray_t = libos.sgaalloc(data.len())?;
+        unsafe { slice::from_raw_parts_mut(sga.sga_segs[0].sgaseg_buf as *mut u8, data.len()) }.copy_from_slice(&data);
+        let qt: QToken = libos.push(qd, &sga)?;
+        let qr: demi_qresult_t = libos.wait(qt, None)?;
+        libos.sgafree(sga)?;
+        crate::ensure_eq!(qr.qr_opcode, demi_opcode_t::DEMI_OPC_PUSH);
+        libos.close(qd)?;
+
+        let qd: QDesc = libos.open_file(path_str, libc::O_RDONLY)?;
+        let qt: QToken = libos.pop(qd, None)?;
+        let qr: demi_qresult_t = libos.wait(qt, None)?;
+        crate::ensure_eq!(qr.qr_opcode, demi_opcode_t::DEMI_OPC_POP);
+        let sga: demi_sgarray_t = unsafe { qr.qr_value.sga };
+        let popped: Vec<u8> = unsafe {
+            slice::from_raw_parts(
+                sga.sga_segs[0].sgaseg_buf as *const u8,
+                sga.sga_segs[0].sgaseg_len as usize,
+            )
+        }
+        .to_vec();
+        libos.sgafree(sga)?;
+        crate::ensure_eq!(popped, data);
+
+        let qt: QToken = libos.pop(qd, None)?;
+        let qr: demi_qresult_t = libos.wait(qt, None)?;
+        crate::ensure_eq!(qr.qr_opcode, demi_opcode_t::DEMI_OPC_POP);
+        let sga: demi_sgarray_t = unsafe { qr.qr_value.sga };
+        let len: u32 = sga.sga_segs[0].sgaseg_len;
+        crate::ensure_eq!(len, 0);
+        libos.sgafree(sga)?;
+        libos.close(qd)?;
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
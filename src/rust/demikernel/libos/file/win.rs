@@ -0,0 +1,178 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    memory::DemiBuffer,
+    poll_yield,
+    SharedDemiRuntime,
+};
+use ::std::{
+    fs::{
+        File,
+        OpenOptions,
+    },
+    io,
+    os::windows::{
+        fs::OpenOptionsExt,
+        io::AsRawHandle,
+    },
+    rc::Rc,
+};
+use ::windows::{
+    core::HRESULT,
+    Win32::{
+        Foundation::{
+            ERROR_HANDLE_EOF,
+            ERROR_IO_INCOMPLETE,
+            ERROR_IO_PENDING,
+            FALSE,
+            HANDLE,
+            TRUE,
+        },
+        Storage::FileSystem::{
+            ReadFile,
+            WriteFile,
+            FILE_FLAG_OVERLAPPED,
+        },
+        System::IO::{
+            CancelIoEx,
+            GetOverlappedResult,
+            OVERLAPPED,
+        },
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Offset that makes overlapped writes go to the end of the file.
+const END_OF_FILE: u64 = u64::MAX;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Engine that runs file operations with overlapped I/O. Coroutines poll their own operations for completion, thus the
+/// engine has no state.
+#[derive(Clone)]
+pub struct SharedFileEngine;
+
+/// An overlapped operation. If it is dropped while it is in flight, it cancels the operation and waits for the kernel
+/// to be done with its memory.
+struct OverlappedOperation {
+    handle: HANDLE,
+    overlapped: Box<OVERLAPPED>,
+    /// Buffer and file that the kernel accesses while the operation is in flight.
+    buf: DemiBuffer,
+    _file: Rc<File>,
+    in_flight: bool,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl SharedFileEngine {
+    /// Creates an engine.
+    pub fn new(_runtime: &mut SharedDemiRuntime) -> Result<Self, Fail> {
+        Ok(Self)
+    }
+
+    /// Opens the file at [path] with [options], for overlapped I/O.
+    pub fn open(&self, options: &mut OpenOptions, path: &str) -> Result<File, Fail> {
+        Ok(options.custom_flags(FILE_FLAG_OVERLAPPED.0).open(path)?)
+    }
+
+    /// Reads up to [len] bytes from [file] at [offset].
+    pub async fn read(&mut self, file: &Rc<File>, offset: u64, len: usize) -> Result<DemiBuffer, Fail> {
+        let mut op: OverlappedOperation = OverlappedOperation::new(file, offset, DemiBuffer::new(len as u16));
+        let overlapped: *mut OVERLAPPED = op.overlapped.as_mut();
+        let result: windows::core::Result<()> =
+            unsafe { ReadFile(op.handle, Some(&mut op.buf[..]), None, Some(overlapped)) };
+        let nbytes: usize = op.wait(result).await?;
+        let mut buf: DemiBuffer = op.buf.clone();
+        buf.trim(len - nbytes)?;
+        Ok(buf)
+    }
+
+    /// Writes [buf] to [file] at [offset], or at its end if [offset] is None. Returns the number of bytes that were
+    /// written.
+    pub async fn write(&mut self, file: &Rc<File>, offset: Option<u64>, buf: DemiBuffer) -> Result<usize, Fail> {
+        let mut op: OverlappedOperation = OverlappedOperation::new(file, offset.unwrap_or(END_OF_FILE), buf);
+        let overlapped: *mut OVERLAPPED = op.overlapped.as_mut();
+        let result: windows::core::Result<()> =
+            unsafe { WriteFile(op.handle, Some(&op.buf[..]), None, Some(overlapped)) };
+        op.wait(result).await
+    }
+}
+
+impl OverlappedOperation {
+    fn new(file: &Rc<File>, offset: u64, buf: DemiBuffer) -> Self {
+        let mut overlapped: Box<OVERLAPPED> = Box::new(OVERLAPPED::default());
+        overlapped.Anonymous.Anonymous.Offset = offset as u32;
+        overlapped.Anonymous.Anonymous.OffsetHigh = (offset >> 32) as u32;
+        Self {
+            handle: HANDLE(file.as_raw_handle() as isize),
+            overlapped,
+            buf,
+            _file: file.clone(),
+            in_flight: true,
+        }
+    }
+
+    /// Waits for the operation to complete, given the [result] of the call that issued it. Returns the number of bytes
+    /// that were transferred. Reads at the end of the file transfer nothing.
+    async fn wait(&mut self, result: windows::core::Result<()>) -> Result<usize, Fail> {
+        if let Err(e) = result {
+            if e.code() != HRESULT::from(ERROR_IO_PENDING) {
+                self.in_flight = false;
+                return translate_result(Err(e), 0);
+            }
+        }
+        loop {
+            let mut nbytes: u32 = 0;
+            match unsafe { GetOverlappedResult(self.handle, self.overlapped.as_ref(), &mut nbytes, FALSE) } {
+                Err(e) if e.code() == HRESULT::from(ERROR_IO_INCOMPLETE) => poll_yield().await,
+                result => {
+                    self.in_flight = false;
+                    return translate_result(result, nbytes);
+                },
+            }
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Drop for OverlappedOperation {
+    fn drop(&mut self) {
+        if self.in_flight {
+            let mut nbytes: u32 = 0;
+            unsafe {
+                let _ = CancelIoEx(self.handle, Some(self.overlapped.as_ref()));
+                let _ = GetOverlappedResult(self.handle, self.overlapped.as_ref(), &mut nbytes, TRUE);
+            }
+        }
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Translates the [result] of an overlapped operation that transferred [nbytes].
+fn translate_result(result: windows::core::Result<()>, nbytes: u32) -> Result<usize, Fail> {
+    match result {
+        Ok(()) => Ok(nbytes as usize),
+        Err(e) if e.code() == HRESULT::from(ERROR_HANDLE_EOF) => Ok(0),
+        Err(e) => Err(Fail::from(io::Error::from(e))),
+    }
+}
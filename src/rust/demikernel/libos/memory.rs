@@ -7,6 +7,8 @@
 
 use crate::runtime::{
     fail::Fail,
+    network::socket::option::SocketOption,
+    scheduler::TaskStats,
     types::{
         demi_qresult_t,
         demi_sgarray_t,
@@ -14,7 +16,12 @@ use crate::runtime::{
     QDesc,
     QToken,
 };
-use ::std::time::Duration;
+use ::futures::future::FusedFuture;
+use ::std::{
+    os::fd::RawFd,
+    pin::Pin,
+    time::Duration,
+};
 
 #[cfg(feature = "catmem-libos")]
 use crate::catmem::SharedCatmemLibOS;
@@ -67,22 +74,22 @@ impl MemoryLibOS {
         }
     }
 
-    /// Pushes a scatter-gather array to a memory queue.
+    /// Pushes a scatter-gather array to a memory queue, unless [timeout] passes first.
     #[allow(unreachable_patterns, unused_variables)]
-    pub fn push(&mut self, memqd: QDesc, sga: &demi_sgarray_t) -> Result<QToken, Fail> {
+    pub fn push(&mut self, memqd: QDesc, sga: &demi_sgarray_t, timeout: Option<Duration>) -> Result<QToken, Fail> {
         match self {
             #[cfg(feature = "catmem-libos")]
-            MemoryLibOS::Catmem(libos) => libos.push(memqd, sga),
+            MemoryLibOS::Catmem(libos) => libos.push(memqd, sga, timeout),
             _ => unreachable!("unknown memory libos"),
         }
     }
 
-    /// Pops data from a memory queue.
+    /// Pops data from a memory queue, unless [timeout] passes first.
     #[allow(unreachable_patterns, unused_variables)]
-    pub fn pop(&mut self, memqd: QDesc, size: Option<usize>) -> Result<QToken, Fail> {
+    pub fn pop(&mut self, memqd: QDesc, size: Option<usize>, timeout: Option<Duration>) -> Result<QToken, Fail> {
         match self {
             #[cfg(feature = "catmem-libos")]
-            MemoryLibOS::Catmem(libos) => libos.pop(memqd, size),
+            MemoryLibOS::Catmem(libos) => libos.pop(memqd, size, timeout),
             _ => unreachable!("unknown memory libos"),
         }
     }
@@ -119,9 +126,8 @@ impl MemoryLibOS {
     pub fn wait_next_n<Acceptor: FnMut(demi_qresult_t) -> bool>(
         &mut self,
         acceptor: Acceptor,
-        timeout: Duration
-    ) -> Result<(), Fail>
-    {
+        timeout: Duration,
+    ) -> Result<(), Fail> {
         trace!("wait_next_n(): acceptor, timeout={:?}", timeout);
         match self {
             #[cfg(feature = "catmem-libos")]
@@ -130,7 +136,6 @@ impl MemoryLibOS {
         }
     }
 
-
     /// Allocates a scatter-gather array.
     #[allow(unreachable_patterns, unused_variables)]
     pub fn sgaalloc(&self, size: usize) -> Result<demi_sgarray_t, Fail> {
@@ -160,4 +165,138 @@ impl MemoryLibOS {
             _ => unreachable!("unknown memory libos"),
         }
     }
+
+    /// Shuts the engine down, closing every memory queue within [grace].
+    #[allow(unreachable_patterns, unused_variables)]
+    pub fn shutdown_all(&mut self, grace: Option<Duration>) -> Result<(), Fail> {
+        match self {
+            #[cfg(feature = "catmem-libos")]
+            MemoryLibOS::Catmem(libos) => libos.shutdown_all(grace),
+            _ => unreachable!("unknown memory libos"),
+        }
+    }
+
+    /// Sets an option on a memory queue.
+    #[allow(unreachable_patterns, unused_variables)]
+    pub fn set_socket_option(&mut self, qd: QDesc, option: SocketOption) -> Result<(), Fail> {
+        match self {
+            #[cfg(feature = "catmem-libos")]
+            MemoryLibOS::Catmem(libos) => libos.set_socket_option(qd, option),
+            _ => unreachable!("unknown memory libos"),
+        }
+    }
+
+    /// Gets the current value of an option on a memory queue.
+    #[allow(unreachable_patterns, unused_variables)]
+    pub fn get_socket_option(&mut self, qd: QDesc, option: SocketOption) -> Result<SocketOption, Fail> {
+        match self {
+            #[cfg(feature = "catmem-libos")]
+            MemoryLibOS::Catmem(libos) => libos.get_socket_option(qd, option),
+            _ => unreachable!("unknown memory libos"),
+        }
+    }
+
+    /// Releases a memory queue to another process that shares it after fork.
+    #[allow(unreachable_patterns, unused_variables)]
+    pub fn release_pipe(&mut self, qd: QDesc) -> Result<(), Fail> {
+        match self {
+            #[cfg(feature = "catmem-libos")]
+            MemoryLibOS::Catmem(libos) => libos.release_pipe(qd),
+            _ => unreachable!("unknown memory libos"),
+        }
+    }
+
+    /// Hands a memory queue off to another process through a Unix domain socket.
+    #[allow(unreachable_patterns, unused_variables)]
+    pub fn export_pipe(&mut self, qd: QDesc, channel: RawFd) -> Result<(), Fail> {
+        match self {
+            #[cfg(feature = "catmem-libos")]
+            MemoryLibOS::Catmem(libos) => libos.export_pipe(qd, channel),
+            _ => unreachable!("unknown memory libos"),
+        }
+    }
+
+    /// Takes over a memory queue that another process handed off through a Unix domain socket.
+    #[allow(unreachable_patterns, unused_variables)]
+    pub fn import_pipe(&mut self, channel: RawFd) -> Result<QDesc, Fail> {
+        match self {
+            #[cfg(feature = "catmem-libos")]
+            MemoryLibOS::Catmem(libos) => libos.import_pipe(channel),
+            _ => unreachable!("unknown memory libos"),
+        }
+    }
+
+    /// Cancels a pending I/O operation.
+    #[allow(unreachable_patterns, unused_variables)]
+    pub fn cancel(&mut self, qt: QToken) -> Result<(), Fail> {
+        match self {
+            #[cfg(feature = "catmem-libos")]
+            MemoryLibOS::Catmem(libos) => libos.cancel(qt),
+            _ => unreachable!("unknown memory libos"),
+        }
+    }
+
+    /// Spawns an application coroutine.
+    #[allow(unreachable_patterns, unused_variables)]
+    pub fn spawn<F: FusedFuture<Output = Result<(), Fail>> + 'static>(
+        &mut self,
+        name: &'static str,
+        coroutine: Pin<Box<F>>,
+    ) -> Result<QToken, Fail> {
+        match self {
+            #[cfg(feature = "catmem-libos")]
+            MemoryLibOS::Catmem(libos) => libos.spawn(name, coroutine),
+            _ => unreachable!("unknown memory libos"),
+        }
+    }
+
+    /// Returns a file descriptor that becomes readable when waiting would return.
+    #[allow(unreachable_patterns)]
+    pub fn get_event_fd(&mut self) -> Result<libc::c_int, Fail> {
+        match self {
+            #[cfg(feature = "catmem-libos")]
+            MemoryLibOS::Catmem(libos) => libos.get_event_fd(),
+            _ => unreachable!("unknown memory libos"),
+        }
+    }
+
+    /// Sets the tunable [name] to [value].
+    #[allow(unreachable_patterns, unused_variables)]
+    pub fn set_tunable(&mut self, name: &str, value: &str) -> Result<(), Fail> {
+        match self {
+            #[cfg(feature = "catmem-libos")]
+            MemoryLibOS::Catmem(libos) => libos.set_tunable(name, value),
+            _ => unreachable!("unknown memory libos"),
+        }
+    }
+
+    /// Gets the value of the tunable [name].
+    #[allow(unreachable_patterns, unused_variables)]
+    pub fn get_tunable(&self, name: &str) -> Result<String, Fail> {
+        match self {
+            #[cfg(feature = "catmem-libos")]
+            MemoryLibOS::Catmem(libos) => libos.get_tunable(name),
+            _ => unreachable!("unknown memory libos"),
+        }
+    }
+
+    /// Turns per-coroutine accounting of runtime and poll counts on or off.
+    #[allow(unreachable_patterns, unused_variables)]
+    pub fn set_task_accounting(&mut self, enabled: bool) {
+        match self {
+            #[cfg(feature = "catmem-libos")]
+            MemoryLibOS::Catmem(libos) => libos.set_task_accounting(enabled),
+            _ => unreachable!("unknown memory libos"),
+        }
+    }
+
+    /// Returns the accounting of the [max_tasks] coroutines with the longest single polls.
+    #[allow(unreachable_patterns, unused_variables)]
+    pub fn get_task_stats(&self, max_tasks: usize) -> Vec<TaskStats> {
+        match self {
+            #[cfg(feature = "catmem-libos")]
+            MemoryLibOS::Catmem(libos) => libos.get_task_stats(max_tasks),
+            _ => unreachable!("unknown memory libos"),
+        }
+    }
 }
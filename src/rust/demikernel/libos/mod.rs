@@ -1,16 +1,29 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
+#[cfg(feature = "libspdk")]
+pub mod block;
+#[cfg(any(
+    feature = "catnap-libos",
+    feature = "catcollar-libos",
+    feature = "catnip-libos",
+    feature = "catpowder-libos",
+    feature = "catloop-libos"
+))]
+pub mod file;
 #[cfg(feature = "catmem-libos")]
 pub mod memory;
 pub mod name;
 #[cfg(any(
     feature = "catnap-libos",
+    feature = "catcollar-libos",
     feature = "catnip-libos",
     feature = "catpowder-libos",
     feature = "catloop-libos"
 ))]
 pub mod network;
+#[cfg(feature = "librdma")]
+pub mod rdma;
 
 //======================================================================================================================
 // Imports
@@ -25,6 +38,7 @@ use crate::catnip::runtime::SharedDPDKRuntime;
 use crate::catpowder::runtime::LinuxRuntime;
 #[cfg(any(
     feature = "catnap-libos",
+    feature = "catcollar-libos",
     feature = "catnip-libos",
     feature = "catpowder-libos",
     feature = "catloop-libos"
@@ -33,17 +47,58 @@ use crate::demikernel::libos::network::{
     libos::SharedNetworkLibOS,
     NetworkLibOSWrapper,
 };
+#[cfg(feature = "librdma")]
+use crate::demikernel::libos::rdma::RdmaRegion;
 #[cfg(any(feature = "catpowder-libos", feature = "catnip-libos"))]
 use crate::inetstack::SharedInetStack;
 
 use crate::{
-    demikernel::config::Config,
+    demikernel::config::{
+        self,
+        Config,
+        Profile,
+    },
     runtime::{
+        affinity::{
+            self,
+            BindingConfig,
+            EngineBinding,
+        },
         fail::Fail,
         limits,
         logging,
+        network::{
+            config::{
+                IpsecDirection,
+                Route,
+                SecurityAssociation,
+            },
+            drops::{
+                DropRecord,
+                DropStats,
+            },
+            framing::FrameCodec,
+            numa::NumaStats,
+            ptp::{
+                clock::SharedPtpClock,
+                PtpConfig,
+            },
+            quic::QuicConfig,
+            socket::{
+                filter::SocketFilter,
+                option::SocketOption,
+            },
+            tcp_info::{
+                TcpEvent,
+                TcpInfo,
+            },
+            timestamp::TxTimestamp,
+            tls::TlsConfig,
+        },
+        scheduler::TaskStats,
         types::{
             demi_qresult_t,
+            demi_sgarray_batch_t,
             demi_sgarray_t,
         },
         QDesc,
@@ -52,12 +107,21 @@ use crate::{
     },
     timer,
 };
+use ::futures::future::FusedFuture;
 use ::std::{
-    env,
-    net::SocketAddr,
+    net::{
+        Ipv4Addr,
+        SocketAddr,
+    },
+    os::fd::RawFd,
+    pin::Pin,
     time::Duration,
 };
 
+#[cfg(feature = "catcollar-libos")]
+use crate::catcollar::transport::SharedCatcollarTransport;
+#[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+use crate::catloop::dispatch::SharedDispatchTransport;
 #[cfg(feature = "catloop-libos")]
 use crate::catloop::transport::SharedCatloopTransport;
 #[cfg(feature = "catmem-libos")]
@@ -77,6 +141,7 @@ pub enum LibOS {
     /// Network LibOS
     #[cfg(any(
         feature = "catnap-libos",
+        feature = "catcollar-libos",
         feature = "catnip-libos",
         feature = "catpowder-libos",
         feature = "catloop-libos"
@@ -93,28 +158,47 @@ pub enum LibOS {
 
 /// Associated functions for LibOS.
 impl LibOS {
-    /// Instantiates a new LibOS.
+    /// Instantiates a new LibOS. Its configuration is loaded from the configuration file and environment variables,
+    /// as described in [Config::load].
     pub fn new(libos_name: LibOSName) -> Result<Self, Fail> {
+        Self::with_config(libos_name, Config::load()?)
+    }
+
+    /// Instantiates a new LibOS whose tunables follow [profile]. The rest of its configuration is loaded as in [new],
+    /// and tunables that are set explicitly there take precedence over those of the profile.
+    pub fn with_profile(libos_name: LibOSName, profile: Profile) -> Result<Self, Fail> {
+        Self::with_config(libos_name, Config::load()?.with_profile(profile)?)
+    }
+
+    /// Instantiates a new LibOS with the given [config]. This allows applications and tests to build their
+    /// configuration programmatically, with [Config::default] and [Config::with], instead of writing a configuration
+    /// file. Applications that only want to override some parameters can start from [Config::load].
+    pub fn with_config(libos_name: LibOSName, config: Config) -> Result<Self, Fail> {
         timer!("demikernel::new");
 
         logging::initialize();
 
-        // Read in configuration file.
-        let config_path: String = match env::var("CONFIG_PATH") {
-            Ok(config_path) => config_path,
-            Err(_) => {
-                return Err(Fail::new(
-                    libc::EINVAL,
-                    "missing value for CONFIG_PATH environment variable",
-                ))
-            },
-        };
-        let config: Config = Config::new(config_path);
+        // Bind the engine before it allocates anything, so that its memory is local to the cores that it runs on.
+        let binding: BindingConfig = config.engine_binding()?;
+        if binding != BindingConfig::default() {
+            affinity::bind_engine(&binding)?;
+        }
+
         #[allow(unused_mut)]
         let mut runtime: SharedDemiRuntime = SharedDemiRuntime::default();
         // Instantiate LibOS.
         #[allow(unreachable_patterns)]
-        let libos: LibOS = match libos_name {
+        let mut libos: LibOS = match libos_name {
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            LibOSName::Catnap if config.catnap_local_shared_memory() => {
+                let transport: SharedCatnapTransport = SharedCatnapTransport::new(&config, &mut runtime);
+                Self::NetworkLibOS(NetworkLibOSWrapper::CatnapDispatch(SharedNetworkLibOS::<
+                    SharedDispatchTransport<SharedCatnapTransport>,
+                >::new(
+                    runtime.clone(),
+                    SharedDispatchTransport::new(&config, runtime.clone(), transport),
+                )))
+            },
             #[cfg(all(feature = "catnap-libos"))]
             LibOSName::Catnap => Self::NetworkLibOS(NetworkLibOSWrapper::Catnap(SharedNetworkLibOS::<
                 SharedCatnapTransport,
@@ -122,6 +206,13 @@ impl LibOS {
                 runtime.clone(),
                 SharedCatnapTransport::new(&config, &mut runtime),
             ))),
+            #[cfg(feature = "catcollar-libos")]
+            LibOSName::Catcollar => Self::NetworkLibOS(NetworkLibOSWrapper::Catcollar(SharedNetworkLibOS::<
+                SharedCatcollarTransport,
+            >::new(
+                runtime.clone(),
+                SharedCatcollarTransport::new(&config, &mut runtime),
+            ))),
 
             #[cfg(feature = "catpowder-libos")]
             LibOSName::Catpowder => {
@@ -163,9 +254,121 @@ impl LibOS {
             _ => panic!("unsupported libos"),
         };
 
+        libos.apply_tunables(&config)?;
+        if config.reload_on_sighup() {
+            config::watch_sighup();
+        }
+
         Ok(libos)
     }
 
+    /// Sets the tunable [name] to [value] at runtime, so that long-running services can adjust their behavior without
+    /// restarting. Tunables are named after their dotted path in the "tunables" section of the configuration file:
+    /// "log.level" takes a logging specification like the RUST_LOG environment variable, and "log.sink" tells whether
+    /// log records are written to the standard error ("stderr") or kept in an in-memory flight recorder ("ring"), which
+    /// is dumped on panic or with [logging::dump]. "arp.*" and "tcp.*" tune the network stack of LibOSes that have one.
+    /// Durations are given in milliseconds. TCP changes only apply to connections that are created afterwards.
+    /// "memory.limit_bytes" caps the data that all sockets of the network stack buffer, and "memory.policy" tells
+    /// whether data past the cap is dropped ("drop_newest") or the largest connection is also aborted
+    /// ("abort_largest"). "admission.*" rate limits connection requests and datagrams from each source, and
+    /// "admission.blocklist" takes a comma-separated list of subnets whose hosts are refused. "flows.max_entries" caps
+    /// the flows that TCP connections and UDP peers take up, and "flows.policy" tells whether a new flow past the cap
+    /// evicts the least recently used one ("evict_lru") or is refused ("refuse"), once flows idle for longer than
+    /// "flows.idle_timeout_ms" were evicted. "icmp.*" rate limits the ICMP messages that the network stack generates,
+    /// and "icmp.disabled" takes a comma-separated list of classes that are never sent. "wait.policy" tells whether
+    /// waits busy-poll ("busy_poll"), sleep once nothing has been ready to run for "wait.spin_micros" ("hybrid"), or
+    /// sleep right away ("block") until a backend notification, such as epoll events on catnap or a doorbell on catmem,
+    /// wakes them up. LibOSes that busy-poll a device never sleep.
+    pub fn set_config(&mut self, name: &str, value: &str) -> Result<(), Fail> {
+        timer!("demikernel::set_config");
+        match name {
+            "log.level" => return logging::set_level(value),
+            "log.sink" => return logging::set_sink(value),
+            _ => (),
+        }
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.set_tunable(name, value),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(libos) => libos.set_tunable(name, value),
+        }
+    }
+
+    /// Gets the current value of the tunable [name]. See [set_config] for the available tunables.
+    pub fn get_config(&self, name: &str) -> Result<String, Fail> {
+        timer!("demikernel::get_config");
+        match name {
+            "log.level" => return Ok(logging::get_level()),
+            "log.sink" => return Ok(logging::get_sink().to_string()),
+            _ => (),
+        }
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.get_tunable(name),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(libos) => libos.get_tunable(name),
+        }
+    }
+
+    /// Reloads the tunables from the configuration file and environment variables. The other parameters of the
+    /// configuration file only take effect at startup. This also happens on SIGHUP, if enabled in the configuration file.
+    pub fn reload_config(&mut self) -> Result<(), Fail> {
+        timer!("demikernel::reload_config");
+        let config: Config = Config::load()?;
+        self.apply_tunables(&config)
+    }
+
+    /// Binds the thread that runs the engine to cores and sets how the kernel schedules it, as described by [binding].
+    /// Parameters that are not set are left as they are. The engine runs on the thread that created this LibOS.
+    pub fn bind_engine(&mut self, binding: &BindingConfig) -> Result<(), Fail> {
+        timer!("demikernel::bind_engine");
+        affinity::bind_engine(binding)
+    }
+
+    /// Gets how the thread that runs the engine is bound to cores and scheduled by the kernel.
+    pub fn get_engine_binding(&self) -> Result<EngineBinding, Fail> {
+        timer!("demikernel::get_engine_binding");
+        affinity::get_engine_binding()
+    }
+
+    /// Sets all tunables in [config]. Tunables that are not supported by this LibOS are skipped.
+    fn apply_tunables(&mut self, config: &Config) -> Result<(), Fail> {
+        for (name, value) in config.tunables()? {
+            match self.set_config(&name, &value) {
+                Ok(()) => (),
+                Err(e) if e.errno == libc::ENOTSUP => {
+                    debug!("apply_tunables(): skipping unsupported tunable (name={:?})", name);
+                },
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Reloads the configuration file if a reload was requested through SIGHUP.
+    fn reload_config_if_requested(&mut self) {
+        if config::take_reload_request() {
+            if let Err(e) = self.reload_config() {
+                warn!(
+                    "reload_config_if_requested(): failed to reload configuration file (error={:?})",
+                    e
+                );
+            }
+        }
+    }
+
     /// Creates a new memory queue and connect to consumer end.
     #[allow(unused_variables)]
     pub fn create_pipe(&mut self, name: &str) -> Result<QDesc, Fail> {
@@ -174,6 +377,7 @@ impl LibOS {
             match self {
                 #[cfg(any(
                     feature = "catnap-libos",
+                    feature = "catcollar-libos",
                     feature = "catnip-libos",
                     feature = "catpowder-libos",
                     feature = "catloop-libos"
@@ -200,6 +404,7 @@ impl LibOS {
             match self {
                 #[cfg(any(
                     feature = "catnap-libos",
+                    feature = "catcollar-libos",
                     feature = "catnip-libos",
                     feature = "catpowder-libos",
                     feature = "catloop-libos"
@@ -231,6 +436,7 @@ impl LibOS {
             match self {
                 #[cfg(any(
                     feature = "catnap-libos",
+                    feature = "catcollar-libos",
                     feature = "catnip-libos",
                     feature = "catpowder-libos",
                     feature = "catloop-libos"
@@ -254,6 +460,7 @@ impl LibOS {
             match self {
                 #[cfg(any(
                     feature = "catnap-libos",
+                    feature = "catcollar-libos",
                     feature = "catnip-libos",
                     feature = "catpowder-libos",
                     feature = "catloop-libos"
@@ -269,21 +476,25 @@ impl LibOS {
         result
     }
 
-    /// Marks a socket as a passive one.
+    /// Binds a socket to a name that processes on the same host connect to, instead of an address.
     #[allow(unused_variables)]
-    pub fn listen(&mut self, sockqd: QDesc, backlog: usize) -> Result<(), Fail> {
+    pub fn bind_name(&mut self, sockqd: QDesc, name: &str) -> Result<(), Fail> {
         let result: Result<(), Fail> = {
-            timer!("demikernel::listen");
+            timer!("demikernel::bind_name");
             match self {
                 #[cfg(any(
                     feature = "catnap-libos",
+                    feature = "catcollar-libos",
                     feature = "catnip-libos",
                     feature = "catpowder-libos",
                     feature = "catloop-libos"
                 ))]
-                LibOS::NetworkLibOS(libos) => libos.listen(sockqd, backlog),
+                LibOS::NetworkLibOS(libos) => libos.bind_name(sockqd, name),
                 #[cfg(feature = "catmem-libos")]
-                LibOS::MemoryLibOS(_) => Err(Fail::new(libc::ENOTSUP, "listen() is not supported on memory liboses")),
+                LibOS::MemoryLibOS(_) => Err(Fail::new(
+                    libc::ENOTSUP,
+                    "bind_name() is not supported on memory liboses",
+                )),
             }
         };
 
@@ -292,21 +503,26 @@ impl LibOS {
         result
     }
 
-    /// Accepts an incoming connection on a TCP socket.
+    /// Opens the file at [path] with the POSIX open [flags]. Pushes and pops on the returned queue descriptor write and
+    /// read the file asynchronously, so that disk I/O is waited on along with network I/O.
     #[allow(unused_variables)]
-    pub fn accept(&mut self, sockqd: QDesc) -> Result<QToken, Fail> {
-        let result: Result<QToken, Fail> = {
-            timer!("demikernel::accept");
+    pub fn open_file(&mut self, path: &str, flags: libc::c_int) -> Result<QDesc, Fail> {
+        let result: Result<QDesc, Fail> = {
+            timer!("demikernel::open_file");
             match self {
                 #[cfg(any(
                     feature = "catnap-libos",
+                    feature = "catcollar-libos",
                     feature = "catnip-libos",
                     feature = "catpowder-libos",
                     feature = "catloop-libos"
                 ))]
-                LibOS::NetworkLibOS(libos) => libos.accept(sockqd),
+                LibOS::NetworkLibOS(libos) => libos.open_file(path, flags),
                 #[cfg(feature = "catmem-libos")]
-                LibOS::MemoryLibOS(_) => Err(Fail::new(libc::ENOTSUP, "accept() is not supported on memory liboses")),
+                LibOS::MemoryLibOS(_) => Err(Fail::new(
+                    libc::ENOTSUP,
+                    "open_file() is not supported on memory liboses",
+                )),
             }
         };
 
@@ -315,56 +531,28 @@ impl LibOS {
         result
     }
 
-    /// Initiates a connection with a remote TCP socket.
+    /// Opens namespace [nsid] of the NVMe controller at the SPDK transport ID [trid], such as
+    /// "trtype:PCIe traddr:0000:04:00.0". Pushes and pops on the returned queue descriptor write and read whole sectors
+    /// of the namespace asynchronously, so that storage is waited on along with network I/O.
+    #[cfg(feature = "libspdk")]
     #[allow(unused_variables)]
-    pub fn connect(&mut self, sockqd: QDesc, remote: SocketAddr) -> Result<QToken, Fail> {
-        let result: Result<QToken, Fail> = {
-            timer!("demikernel::connect");
-            match self {
-                #[cfg(any(
-                    feature = "catnap-libos",
-                    feature = "catnip-libos",
-                    feature = "catpowder-libos",
-                    feature = "catloop-libos"
-                ))]
-                LibOS::NetworkLibOS(libos) => libos.connect(sockqd, remote),
-                #[cfg(feature = "catmem-libos")]
-                LibOS::MemoryLibOS(_) => Err(Fail::new(libc::ENOTSUP, "connect() is not supported on memory liboses")),
-            }
-        };
-
-        self.poll();
-
-        result
-    }
-
-    /// Closes an I/O queue.
-    /// async_close() + wait() achieves the same effect as synchronous close.
-    pub fn close(&mut self, qd: QDesc) -> Result<(), Fail> {
-        let result: Result<(), Fail> = {
-            timer!("demikernel::close");
+    pub fn open_block(&mut self, trid: &str, nsid: u32) -> Result<QDesc, Fail> {
+        let result: Result<QDesc, Fail> = {
+            timer!("demikernel::open_block");
             match self {
                 #[cfg(any(
                     feature = "catnap-libos",
+                    feature = "catcollar-libos",
                     feature = "catnip-libos",
                     feature = "catpowder-libos",
                     feature = "catloop-libos"
                 ))]
-                LibOS::NetworkLibOS(libos) => match libos.async_close(qd) {
-                    Ok(qt) => match self.wait(qt, None) {
-                        Ok(_) => Ok(()),
-                        Err(e) => Err(e),
-                    },
-                    Err(e) => Err(e),
-                },
+                LibOS::NetworkLibOS(libos) => libos.open_block(trid, nsid),
                 #[cfg(feature = "catmem-libos")]
-                LibOS::MemoryLibOS(libos) => match libos.async_close(qd) {
-                    Ok(qt) => match self.wait(qt, None) {
-                        Ok(_) => Ok(()),
-                        Err(e) => Err(e),
-                    },
-                    Err(e) => Err(e),
-                },
+                LibOS::MemoryLibOS(_) => Err(Fail::new(
+                    libc::ENOTSUP,
+                    "open_block() is not supported on memory liboses",
+                )),
             }
         };
 
@@ -373,19 +561,27 @@ impl LibOS {
         result
     }
 
-    pub fn async_close(&mut self, qd: QDesc) -> Result<QToken, Fail> {
-        let result: Result<QToken, Fail> = {
-            timer!("demikernel::async_close");
+    /// Creates a queue that runs a reliable connection over RDMA, such as RoCEv2. The queue is bound, listens, accepts
+    /// and connects with IPv4 addresses like a TCP socket, and pushes and pops on it send and receive whole messages.
+    #[cfg(feature = "librdma")]
+    #[allow(unused_variables)]
+    pub fn rdma_socket(&mut self) -> Result<QDesc, Fail> {
+        let result: Result<QDesc, Fail> = {
+            timer!("demikernel::rdma_socket");
             match self {
                 #[cfg(any(
                     feature = "catnap-libos",
+                    feature = "catcollar-libos",
                     feature = "catnip-libos",
                     feature = "catpowder-libos",
                     feature = "catloop-libos"
                 ))]
-                LibOS::NetworkLibOS(libos) => libos.async_close(qd),
+                LibOS::NetworkLibOS(libos) => libos.rdma_socket(),
                 #[cfg(feature = "catmem-libos")]
-                LibOS::MemoryLibOS(libos) => libos.async_close(qd),
+                LibOS::MemoryLibOS(_) => Err(Fail::new(
+                    libc::ENOTSUP,
+                    "rdma_socket() is not supported on memory liboses",
+                )),
             }
         };
 
@@ -394,20 +590,27 @@ impl LibOS {
         result
     }
 
-    /// Pushes a scatter-gather array to an I/O queue.
-    pub fn push(&mut self, qd: QDesc, sga: &demi_sgarray_t) -> Result<QToken, Fail> {
-        let result: Result<QToken, Fail> = {
-            timer!("demikernel::push");
+    /// Lets the peer of the connected RDMA queue [qd] read and write the memory of [sga] with one-sided verbs. The
+    /// returned region is handed to the peer, and stays valid until it is withdrawn or the queue is closed.
+    #[cfg(feature = "librdma")]
+    #[allow(unused_variables)]
+    pub fn rdma_expose(&mut self, qd: QDesc, sga: &demi_sgarray_t) -> Result<RdmaRegion, Fail> {
+        let result: Result<RdmaRegion, Fail> = {
+            timer!("demikernel::rdma_expose");
             match self {
                 #[cfg(any(
                     feature = "catnap-libos",
+                    feature = "catcollar-libos",
                     feature = "catnip-libos",
                     feature = "catpowder-libos",
                     feature = "catloop-libos"
                 ))]
-                LibOS::NetworkLibOS(libos) => libos.push(qd, sga),
+                LibOS::NetworkLibOS(libos) => libos.rdma_expose(qd, sga),
                 #[cfg(feature = "catmem-libos")]
-                LibOS::MemoryLibOS(libos) => libos.push(qd, sga),
+                LibOS::MemoryLibOS(_) => Err(Fail::new(
+                    libc::ENOTSUP,
+                    "rdma_expose() is not supported on memory liboses",
+                )),
             }
         };
 
@@ -416,21 +619,26 @@ impl LibOS {
         result
     }
 
-    /// Pushes a scatter-gather array to a UDP socket.
+    /// Withdraws the region with remote key [rkey] that was exposed to the peer of the RDMA queue [qd].
+    #[cfg(feature = "librdma")]
     #[allow(unused_variables)]
-    pub fn pushto(&mut self, qd: QDesc, sga: &demi_sgarray_t, to: SocketAddr) -> Result<QToken, Fail> {
-        let result: Result<QToken, Fail> = {
-            timer!("demikernel::pushto");
+    pub fn rdma_withdraw(&mut self, qd: QDesc, rkey: u32) -> Result<(), Fail> {
+        let result: Result<(), Fail> = {
+            timer!("demikernel::rdma_withdraw");
             match self {
                 #[cfg(any(
                     feature = "catnap-libos",
+                    feature = "catcollar-libos",
                     feature = "catnip-libos",
                     feature = "catpowder-libos",
                     feature = "catloop-libos"
                 ))]
-                LibOS::NetworkLibOS(libos) => libos.pushto(qd, sga, to),
+                LibOS::NetworkLibOS(libos) => libos.rdma_withdraw(qd, rkey),
                 #[cfg(feature = "catmem-libos")]
-                LibOS::MemoryLibOS(_) => Err(Fail::new(libc::ENOTSUP, "pushto() is not supported on memory liboses")),
+                LibOS::MemoryLibOS(_) => Err(Fail::new(
+                    libc::ENOTSUP,
+                    "rdma_withdraw() is not supported on memory liboses",
+                )),
             }
         };
 
@@ -439,31 +647,22 @@ impl LibOS {
         result
     }
 
-    /// Pops data from a an I/O queue.
-    pub fn pop(&mut self, qd: QDesc, size: Option<usize>) -> Result<QToken, Fail> {
-        let result: Result<QToken, Fail> = {
-            timer!("demikernel::pop");
-
-            // Check if this is a fixed-size pop.
-            if let Some(size) = size {
-                // Check if size is valid.
-                if !((size > 0) && (size <= limits::POP_SIZE_MAX)) {
-                    let cause: String = format!("invalid pop size (size={:?})", size);
-                    error!("pop(): {:?}", &cause);
-                    return Err(Fail::new(libc::EINVAL, &cause));
-                }
-            }
-
+    /// Marks a socket as a passive one.
+    #[allow(unused_variables)]
+    pub fn listen(&mut self, sockqd: QDesc, backlog: usize) -> Result<(), Fail> {
+        let result: Result<(), Fail> = {
+            timer!("demikernel::listen");
             match self {
                 #[cfg(any(
                     feature = "catnap-libos",
+                    feature = "catcollar-libos",
                     feature = "catnip-libos",
                     feature = "catpowder-libos",
                     feature = "catloop-libos"
                 ))]
-                LibOS::NetworkLibOS(libos) => libos.pop(qd, size),
+                LibOS::NetworkLibOS(libos) => libos.listen(sockqd, backlog),
                 #[cfg(feature = "catmem-libos")]
-                LibOS::MemoryLibOS(libos) => libos.pop(qd, size),
+                LibOS::MemoryLibOS(_) => Err(Fail::new(libc::ENOTSUP, "listen() is not supported on memory liboses")),
             }
         };
 
@@ -472,108 +671,1237 @@ impl LibOS {
         result
     }
 
-    /// Waits for a pending I/O operation to complete or a timeout to expire.
-    /// This is just a single-token convenience wrapper for wait_any().
-    pub fn wait(&mut self, qt: QToken, timeout: Option<Duration>) -> Result<demi_qresult_t, Fail> {
-        timer!("demikernel::wait");
+    /// Sets an option on a socket.
+    #[allow(unused_variables)]
+    pub fn set_socket_option(&mut self, sockqd: QDesc, option: SocketOption) -> Result<(), Fail> {
+        timer!("demikernel::set_socket_option");
         match self {
             #[cfg(any(
                 feature = "catnap-libos",
+                feature = "catcollar-libos",
                 feature = "catnip-libos",
                 feature = "catpowder-libos",
                 feature = "catloop-libos"
             ))]
-            LibOS::NetworkLibOS(libos) => libos.wait(qt, timeout.unwrap_or(DEFAULT_TIMEOUT)),
+            LibOS::NetworkLibOS(libos) => libos.set_socket_option(sockqd, option),
             #[cfg(feature = "catmem-libos")]
-            LibOS::MemoryLibOS(libos) => libos.wait(qt, timeout.unwrap_or(DEFAULT_TIMEOUT)),
+            LibOS::MemoryLibOS(libos) => libos.set_socket_option(sockqd, option),
         }
     }
 
-    /// Waits for any of the given pending I/O operations to complete or a timeout to expire.
-    pub fn wait_any(&mut self, qts: &[QToken], timeout: Option<Duration>) -> Result<(usize, demi_qresult_t), Fail> {
-        timer!("demikernel::wait_any");
+    /// Adds an IPsec security association to the network stack.
+    #[allow(unused_variables)]
+    pub fn ipsec_add_security_association(&mut self, sa: SecurityAssociation) -> Result<(), Fail> {
+        timer!("demikernel::ipsec_add_security_association");
         match self {
             #[cfg(any(
                 feature = "catnap-libos",
+                feature = "catcollar-libos",
                 feature = "catnip-libos",
                 feature = "catpowder-libos",
                 feature = "catloop-libos"
             ))]
-            LibOS::NetworkLibOS(libos) => libos.wait_any(qts, timeout.unwrap_or(DEFAULT_TIMEOUT)),
+            LibOS::NetworkLibOS(libos) => libos.ipsec_add_security_association(sa),
             #[cfg(feature = "catmem-libos")]
-            LibOS::MemoryLibOS(libos) => libos.wait_any(qts, timeout.unwrap_or(DEFAULT_TIMEOUT)),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "ipsec_add_security_association() is not supported on memory liboses",
+            )),
         }
     }
 
-    /// Waits in a loop until the next task is complete, passing the result to `acceptor`. This process continues until
-    /// either the acceptor returns false (in which case the method returns Ok), or the timeout has expired (in which
-    /// the method returns an `Err` indicating timeout).
-    #[allow(unreachable_patterns, unused_variables)]
-    pub fn wait_next_n<Acceptor: FnMut(demi_qresult_t) -> bool>(
-        &mut self,
-        acceptor: Acceptor,
-        timeout: Option<Duration>
-    ) -> Result<(), Fail>
-    {
-        timer!("demikernel::wait_next_n");
+    /// Removes the IPsec security association with security parameter index [spi] in [direction] from the network
+    /// stack.
+    #[allow(unused_variables)]
+    pub fn ipsec_remove_security_association(&mut self, spi: u32, direction: IpsecDirection) -> Result<(), Fail> {
+        timer!("demikernel::ipsec_remove_security_association");
         match self {
             #[cfg(any(
                 feature = "catnap-libos",
+                feature = "catcollar-libos",
                 feature = "catnip-libos",
                 feature = "catpowder-libos",
                 feature = "catloop-libos"
             ))]
-            LibOS::NetworkLibOS(libos) => libos.wait_next_n(acceptor, timeout.unwrap_or(DEFAULT_TIMEOUT)),
+            LibOS::NetworkLibOS(libos) => libos.ipsec_remove_security_association(spi, direction),
             #[cfg(feature = "catmem-libos")]
-            LibOS::MemoryLibOS(libos) => libos.wait_next_n(acceptor, timeout.unwrap_or(DEFAULT_TIMEOUT)),
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "ipsec_remove_security_association() is not supported on memory liboses",
+            )),
         }
     }
 
-    /// Allocates a scatter-gather array.
-    pub fn sgaalloc(&mut self, size: usize) -> Result<demi_sgarray_t, Fail> {
-        let result: Result<demi_sgarray_t, Fail> = {
-            timer!("demikernel::sgaalloc");
-            match self {
-                #[cfg(any(
-                    feature = "catnap-libos",
-                    feature = "catnip-libos",
-                    feature = "catpowder-libos",
-                    feature = "catloop-libos"
-                ))]
-                LibOS::NetworkLibOS(libos) => libos.sgaalloc(size),
-                #[cfg(feature = "catmem-libos")]
-                LibOS::MemoryLibOS(libos) => libos.sgaalloc(size),
-            }
-        };
-
-        result
+    /// Adds [route] to the routing table of the network stack.
+    #[allow(unused_variables)]
+    pub fn add_route(&mut self, route: Route) -> Result<(), Fail> {
+        timer!("demikernel::add_route");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.add_route(route),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "add_route() is not supported on memory liboses",
+            )),
+        }
     }
 
-    /// Releases a scatter-gather array.
-    pub fn sgafree(&mut self, sga: demi_sgarray_t) -> Result<(), Fail> {
-        let result: Result<(), Fail> = {
-            timer!("demikernel::sgafree");
-            match self {
-                #[cfg(any(
-                    feature = "catnap-libos",
-                    feature = "catnip-libos",
-                    feature = "catpowder-libos",
-                    feature = "catloop-libos"
-                ))]
-                LibOS::NetworkLibOS(libos) => libos.sgafree(sga),
-                #[cfg(feature = "catmem-libos")]
-                LibOS::MemoryLibOS(libos) => libos.sgafree(sga),
-            }
-        };
-
-        result
+    /// Removes the route toward the subnet [destination]/[prefix_len] from the routing table of the network stack.
+    #[allow(unused_variables)]
+    pub fn remove_route(&mut self, destination: Ipv4Addr, prefix_len: u8) -> Result<(), Fail> {
+        timer!("demikernel::remove_route");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.remove_route(destination, prefix_len),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "remove_route() is not supported on memory liboses",
+            )),
+        }
     }
 
-    pub fn poll(&mut self) {
-        timer!("demikernel::poll");
+    /// Attaches a receive filter to a socket, or detaches the current one if [filter] is `None`.
+    #[allow(unused_variables)]
+    pub fn set_receive_filter(&mut self, sockqd: QDesc, filter: Option<SocketFilter>) -> Result<(), Fail> {
+        timer!("demikernel::set_receive_filter");
         match self {
             #[cfg(any(
                 feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.set_receive_filter(sockqd, filter),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "set_receive_filter() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Gets the number of incoming packets that were dropped on their way to the socket [sockqd] or, if it is `None`,
+    /// anywhere in the network stack, for each reason.
+    #[allow(unused_variables)]
+    pub fn get_drop_stats(&mut self, sockqd: Option<QDesc>) -> Result<DropStats, Fail> {
+        timer!("demikernel::get_drop_stats");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.get_drop_stats(sockqd),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "get_drop_stats() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Gets where the network device placed its buffer pools and the state of its queues, relative to the NUMA node of
+    /// the device. Only catnip places them on purpose, as set in the "dpdk.numa" section of the configuration file.
+    pub fn get_numa_stats(&self) -> Result<NumaStats, Fail> {
+        timer!("demikernel::get_numa_stats");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.get_numa_stats(),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "get_numa_stats() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Pops the oldest record from the error queue of the socket [sockqd]. The error queue is enabled by the
+    /// IP_RECVERR socket option.
+    #[allow(unused_variables)]
+    pub fn pop_drop_record(&mut self, sockqd: QDesc) -> Result<Option<DropRecord>, Fail> {
+        timer!("demikernel::pop_drop_record");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.pop_drop_record(sockqd),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "pop_drop_record() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Pops the oldest transmit timestamp of the socket [sockqd]. Transmit timestamps are enabled by the
+    /// SO_TIMESTAMPING socket option.
+    #[allow(unused_variables)]
+    pub fn pop_tx_timestamp(&mut self, sockqd: QDesc) -> Result<Option<TxTimestamp>, Fail> {
+        timer!("demikernel::pop_tx_timestamp");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.pop_tx_timestamp(sockqd),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "pop_tx_timestamp() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Gets a snapshot of the state of the TCP connection [sockqd]: its congestion control and round-trip time
+    /// estimates, retransmission counts, queue depths and the state of its state machine.
+    #[allow(unused_variables)]
+    pub fn get_tcp_info(&mut self, sockqd: QDesc) -> Result<TcpInfo, Fail> {
+        timer!("demikernel::get_tcp_info");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.get_tcp_info(sockqd),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "get_tcp_info() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Gets the recent retransmission timeouts, fast retransmissions, back offs of the retransmission timer, window
+    /// stalls and zero windows of the TCP connection [sockqd], from the oldest to the newest. The same timeline is
+    /// logged when the connection closes.
+    #[allow(unused_variables)]
+    pub fn get_tcp_timeline(&mut self, sockqd: QDesc) -> Result<Vec<TcpEvent>, Fail> {
+        timer!("demikernel::get_tcp_timeline");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.get_tcp_timeline(sockqd),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "get_tcp_timeline() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Gets the current value of an option on a socket.
+    #[allow(unused_variables)]
+    pub fn get_socket_option(&mut self, sockqd: QDesc, option: SocketOption) -> Result<SocketOption, Fail> {
+        timer!("demikernel::get_socket_option");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.get_socket_option(sockqd, option),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(libos) => libos.get_socket_option(sockqd, option),
+        }
+    }
+
+    /// Accepts an incoming connection on a TCP socket.
+    pub fn accept(&mut self, sockqd: QDesc) -> Result<QToken, Fail> {
+        self.timed_accept(sockqd, None)
+    }
+
+    /// Accepts an incoming connection on a TCP socket, failing with ETIMEDOUT if none arrives within [timeout].
+    #[allow(unused_variables)]
+    pub fn timed_accept(&mut self, sockqd: QDesc, timeout: Option<Duration>) -> Result<QToken, Fail> {
+        let result: Result<QToken, Fail> = {
+            timer!("demikernel::accept");
+            match self {
+                #[cfg(any(
+                    feature = "catnap-libos",
+                    feature = "catcollar-libos",
+                    feature = "catnip-libos",
+                    feature = "catpowder-libos",
+                    feature = "catloop-libos"
+                ))]
+                LibOS::NetworkLibOS(libos) => libos.accept(sockqd, timeout),
+                #[cfg(feature = "catmem-libos")]
+                LibOS::MemoryLibOS(_) => Err(Fail::new(libc::ENOTSUP, "accept() is not supported on memory liboses")),
+            }
+        };
+
+        self.poll();
+
+        result
+    }
+
+    /// Initiates a connection with a remote TCP socket.
+    pub fn connect(&mut self, sockqd: QDesc, remote: SocketAddr) -> Result<QToken, Fail> {
+        self.timed_connect(sockqd, remote, None)
+    }
+
+    /// Initiates a connection with a remote TCP socket, failing with ETIMEDOUT if it is not established within
+    /// [timeout]. The socket should be closed after a timed out connection.
+    #[allow(unused_variables)]
+    pub fn timed_connect(
+        &mut self,
+        sockqd: QDesc,
+        remote: SocketAddr,
+        timeout: Option<Duration>,
+    ) -> Result<QToken, Fail> {
+        let result: Result<QToken, Fail> = {
+            timer!("demikernel::connect");
+            match self {
+                #[cfg(any(
+                    feature = "catnap-libos",
+                    feature = "catcollar-libos",
+                    feature = "catnip-libos",
+                    feature = "catpowder-libos",
+                    feature = "catloop-libos"
+                ))]
+                LibOS::NetworkLibOS(libos) => libos.connect(sockqd, remote, timeout),
+                #[cfg(feature = "catmem-libos")]
+                LibOS::MemoryLibOS(_) => Err(Fail::new(libc::ENOTSUP, "connect() is not supported on memory liboses")),
+            }
+        };
+
+        self.poll();
+
+        result
+    }
+
+    /// Initiates a connection with the socket that is bound to [name].
+    #[allow(unused_variables)]
+    pub fn connect_name(&mut self, sockqd: QDesc, name: &str) -> Result<QToken, Fail> {
+        let result: Result<QToken, Fail> = {
+            timer!("demikernel::connect_name");
+            match self {
+                #[cfg(any(
+                    feature = "catnap-libos",
+                    feature = "catcollar-libos",
+                    feature = "catnip-libos",
+                    feature = "catpowder-libos",
+                    feature = "catloop-libos"
+                ))]
+                LibOS::NetworkLibOS(libos) => libos.connect_name(sockqd, name, None),
+                #[cfg(feature = "catmem-libos")]
+                LibOS::MemoryLibOS(_) => Err(Fail::new(
+                    libc::ENOTSUP,
+                    "connect_name() is not supported on memory liboses",
+                )),
+            }
+        };
+
+        self.poll();
+
+        result
+    }
+
+    /// Starts a TLS session as a client on a connected TCP socket. The returned queue descriptor replaces
+    /// [sockqd].
+    #[allow(unused_variables)]
+    pub fn tls_connect(&mut self, sockqd: QDesc, config: TlsConfig) -> Result<QDesc, Fail> {
+        timer!("demikernel::tls_connect");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.tls_connect(sockqd, config),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "tls_connect() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Starts a TLS session as a server on an accepted TCP socket. The returned queue descriptor replaces
+    /// [sockqd].
+    #[allow(unused_variables)]
+    pub fn tls_accept(&mut self, sockqd: QDesc, config: TlsConfig) -> Result<QDesc, Fail> {
+        timer!("demikernel::tls_accept");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.tls_accept(sockqd, config),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "tls_accept() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Frames the data of a connected TCP socket into HTTP/1.1 messages, as a client that pushes
+    /// requests and pops responses. The returned queue descriptor replaces [sockqd].
+    #[allow(unused_variables)]
+    pub fn http_connect(&mut self, sockqd: QDesc) -> Result<QDesc, Fail> {
+        timer!("demikernel::http_connect");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.http_connect(sockqd),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "http_connect() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Frames the data of an accepted TCP socket into HTTP/1.1 messages, as a server that pops
+    /// requests and pushes responses. The returned queue descriptor replaces [sockqd].
+    #[allow(unused_variables)]
+    pub fn http_accept(&mut self, sockqd: QDesc) -> Result<QDesc, Fail> {
+        timer!("demikernel::http_accept");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.http_accept(sockqd),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "http_accept() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Frames the data of the connected TCP socket [sockqd] with [codec], so that each pop returns a whole frame and
+    /// each push must carry exactly one. The returned queue descriptor replaces [sockqd].
+    #[allow(unused_variables)]
+    pub fn frame(&mut self, sockqd: QDesc, codec: Box<dyn FrameCodec>) -> Result<QDesc, Fail> {
+        timer!("demikernel::frame");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.frame(sockqd, codec),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(_) => Err(Fail::new(libc::ENOTSUP, "frame() is not supported on memory liboses")),
+        }
+    }
+
+    /// Starts a DTLS association with [remote] as a client on the bound UDP socket [sockqd]. The returned queue
+    /// descriptor replaces [sockqd].
+    #[allow(unused_variables)]
+    pub fn dtls_connect(&mut self, sockqd: QDesc, remote: SocketAddr, config: TlsConfig) -> Result<QDesc, Fail> {
+        timer!("demikernel::dtls_connect");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.dtls_connect(sockqd, remote, config),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "dtls_connect() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Waits for a DTLS client that proves its address on the bound UDP socket [sockqd]. The returned queue descriptor
+    /// replaces [sockqd].
+    #[allow(unused_variables)]
+    pub fn dtls_accept(&mut self, sockqd: QDesc, config: TlsConfig) -> Result<QDesc, Fail> {
+        timer!("demikernel::dtls_accept");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.dtls_accept(sockqd, config),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "dtls_accept() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Runs QUIC over a bound UDP socket. The returned queue descriptor replaces [sockqd].
+    /// It connects to a QUIC server or listens for QUIC clients, and accepting on a connection yields the streams that
+    /// the peer opens.
+    #[allow(unused_variables)]
+    pub fn quic_socket(&mut self, sockqd: QDesc, config: QuicConfig) -> Result<QDesc, Fail> {
+        timer!("demikernel::quic_socket");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.quic_socket(sockqd, config),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "quic_socket() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Starts a PTP client with [config], which synchronizes a clock of the network stack to a master. The client
+    /// runs in the background until it is stopped.
+    #[allow(unused_variables)]
+    pub fn ptp_start(&mut self, config: PtpConfig) -> Result<(), Fail> {
+        timer!("demikernel::ptp_start");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.ptp_start(config),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "ptp_start() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Stops the PTP client and closes its sockets.
+    #[allow(unused_variables)]
+    pub fn ptp_stop(&mut self) -> Result<(), Fail> {
+        timer!("demikernel::ptp_stop");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.ptp_stop(),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "ptp_stop() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Gets the clock that the PTP client steers, which gives the time of the master for timestamps and converts its
+    /// deadlines into local time for timers.
+    #[allow(unused_variables)]
+    pub fn ptp_clock(&self) -> Result<SharedPtpClock, Fail> {
+        timer!("demikernel::ptp_clock");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.ptp_clock(),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "ptp_clock() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Opens a stream on an established QUIC connection.
+    #[allow(unused_variables)]
+    pub fn quic_open_stream(&mut self, qd: QDesc) -> Result<QDesc, Fail> {
+        timer!("demikernel::quic_open_stream");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.quic_open_stream(qd),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "quic_open_stream() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Closes an I/O queue.
+    /// async_close() + wait() achieves the same effect as synchronous close.
+    pub fn close(&mut self, qd: QDesc) -> Result<(), Fail> {
+        let result: Result<(), Fail> = {
+            timer!("demikernel::close");
+            match self {
+                #[cfg(any(
+                    feature = "catnap-libos",
+                    feature = "catcollar-libos",
+                    feature = "catnip-libos",
+                    feature = "catpowder-libos",
+                    feature = "catloop-libos"
+                ))]
+                LibOS::NetworkLibOS(libos) => match libos.async_close(qd) {
+                    Ok(qt) => match self.wait(qt, None) {
+                        Ok(_) => Ok(()),
+                        Err(e) => Err(e),
+                    },
+                    Err(e) => Err(e),
+                },
+                #[cfg(feature = "catmem-libos")]
+                LibOS::MemoryLibOS(libos) => match libos.async_close(qd) {
+                    Ok(qt) => match self.wait(qt, None) {
+                        Ok(_) => Ok(()),
+                        Err(e) => Err(e),
+                    },
+                    Err(e) => Err(e),
+                },
+            }
+        };
+
+        self.poll();
+
+        result
+    }
+
+    pub fn async_close(&mut self, qd: QDesc) -> Result<QToken, Fail> {
+        let result: Result<QToken, Fail> = {
+            timer!("demikernel::async_close");
+            match self {
+                #[cfg(any(
+                    feature = "catnap-libos",
+                    feature = "catcollar-libos",
+                    feature = "catnip-libos",
+                    feature = "catpowder-libos",
+                    feature = "catloop-libos"
+                ))]
+                LibOS::NetworkLibOS(libos) => libos.async_close(qd),
+                #[cfg(feature = "catmem-libos")]
+                LibOS::MemoryLibOS(libos) => libos.async_close(qd),
+            }
+        };
+
+        self.poll();
+
+        result
+    }
+
+    /// Shuts the engine down. New connections are refused right away, while established ones are closed gracefully and
+    /// aborted if they are still open once [grace] expires. Pending transmissions are then flushed and the resources
+    /// of the network device are released, so that the LibOS must not be used afterwards. Without a grace period,
+    /// connections are waited on until they close.
+    pub fn shutdown_all(&mut self, grace: Option<Duration>) -> Result<(), Fail> {
+        timer!("demikernel::shutdown_all");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.shutdown_all(grace),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(libos) => libos.shutdown_all(grace),
+        }
+    }
+
+    /// Releases a memory queue that this process shares with another one after fork, leaving it to the other process
+    /// without closing it.
+    #[cfg_attr(not(feature = "catmem-libos"), allow(unused_variables))]
+    pub fn release_pipe(&mut self, qd: QDesc) -> Result<(), Fail> {
+        timer!("demikernel::release_pipe");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "release_pipe() is not supported on network liboses",
+            )),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(libos) => libos.release_pipe(qd),
+        }
+    }
+
+    /// Hands the TCP connection or memory queue [qd] off to another process through the Unix domain socket [channel],
+    /// so that a process may accept connections and dispatch them to workers. The queue descriptor is then released
+    /// without closing the queue.
+    pub fn export_queue(&mut self, qd: QDesc, channel: RawFd) -> Result<(), Fail> {
+        let result: Result<(), Fail> = {
+            timer!("demikernel::export_queue");
+            match self {
+                #[cfg(any(
+                    feature = "catnap-libos",
+                    feature = "catcollar-libos",
+                    feature = "catnip-libos",
+                    feature = "catpowder-libos",
+                    feature = "catloop-libos"
+                ))]
+                LibOS::NetworkLibOS(libos) => libos.export_queue(qd, channel),
+                #[cfg(feature = "catmem-libos")]
+                LibOS::MemoryLibOS(libos) => libos.export_pipe(qd, channel),
+            }
+        };
+
+        self.poll();
+
+        result
+    }
+
+    /// Takes over a TCP connection or memory queue that another process handed off through the Unix domain socket
+    /// [channel].
+    pub fn import_queue(&mut self, channel: RawFd) -> Result<QDesc, Fail> {
+        timer!("demikernel::import_queue");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.import_queue(channel),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(libos) => libos.import_pipe(channel),
+        }
+    }
+
+    /// Freezes the TCP connection [sockqd] into a blob that another instance of the LibOS may thaw, so that the
+    /// connection survives a maintenance restart or moves to another engine. The queue descriptor is then released
+    /// without letting the peer know.
+    #[allow(unused_variables)]
+    pub fn freeze_queue(&mut self, sockqd: QDesc) -> Result<Vec<u8>, Fail> {
+        let result: Result<Vec<u8>, Fail> = {
+            timer!("demikernel::freeze_queue");
+            match self {
+                #[cfg(any(
+                    feature = "catnap-libos",
+                    feature = "catcollar-libos",
+                    feature = "catnip-libos",
+                    feature = "catpowder-libos",
+                    feature = "catloop-libos"
+                ))]
+                LibOS::NetworkLibOS(libos) => libos.freeze_queue(sockqd),
+                #[cfg(feature = "catmem-libos")]
+                LibOS::MemoryLibOS(_) => Err(Fail::new(
+                    libc::ENOTSUP,
+                    "freeze_queue() is not supported on memory liboses",
+                )),
+            }
+        };
+
+        self.poll();
+
+        result
+    }
+
+    /// Thaws a TCP connection that another instance of the LibOS froze into [blob].
+    #[allow(unused_variables)]
+    pub fn thaw_queue(&mut self, blob: &[u8]) -> Result<QDesc, Fail> {
+        timer!("demikernel::thaw_queue");
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.thaw_queue(blob),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(_) => Err(Fail::new(
+                libc::ENOTSUP,
+                "thaw_queue() is not supported on memory liboses",
+            )),
+        }
+    }
+
+    /// Checks whether this LibOS remains usable in a child process after fork. Memory queues live in shared memory and
+    /// are handed off between processes, whereas network devices and sockets are owned by the parent process.
+    pub fn survives_fork(&self) -> bool {
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(_) => false,
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(_) => true,
+        }
+    }
+
+    /// Pushes a scatter-gather array to an I/O queue.
+    pub fn push(&mut self, qd: QDesc, sga: &demi_sgarray_t) -> Result<QToken, Fail> {
+        self.timed_push(qd, sga, None)
+    }
+
+    /// Pushes a scatter-gather array to an I/O queue, failing with ETIMEDOUT if it is not pushed within [timeout].
+    pub fn timed_push(&mut self, qd: QDesc, sga: &demi_sgarray_t, timeout: Option<Duration>) -> Result<QToken, Fail> {
+        let result: Result<QToken, Fail> = {
+            timer!("demikernel::push");
+            match self {
+                #[cfg(any(
+                    feature = "catnap-libos",
+                    feature = "catcollar-libos",
+                    feature = "catnip-libos",
+                    feature = "catpowder-libos",
+                    feature = "catloop-libos"
+                ))]
+                LibOS::NetworkLibOS(libos) => libos.push(qd, sga, timeout),
+                #[cfg(feature = "catmem-libos")]
+                LibOS::MemoryLibOS(libos) => libos.push(qd, sga, timeout),
+            }
+        };
+
+        self.poll();
+
+        result
+    }
+
+    /// Moves up to [size] bytes from the file, block or TCP queue [srcqd] to the TCP socket [sockqd], without going
+    /// through application buffers. The operation completes with the number of bytes that it moved, which is less than
+    /// [size] if the end of the file was reached or the peer of a TCP source closed the connection. It fails with EBADF
+    /// if [srcqd] is closed before it completes.
+    pub fn splice(&mut self, sockqd: QDesc, srcqd: QDesc, size: usize) -> Result<QToken, Fail> {
+        self.timed_splice(sockqd, srcqd, size, None)
+    }
+
+    /// Splices data like [Self::splice], failing with ETIMEDOUT if it is not moved within [timeout].
+    #[allow(unused_variables)]
+    pub fn timed_splice(
+        &mut self,
+        sockqd: QDesc,
+        srcqd: QDesc,
+        size: usize,
+        timeout: Option<Duration>,
+    ) -> Result<QToken, Fail> {
+        let result: Result<QToken, Fail> = {
+            timer!("demikernel::splice");
+            match self {
+                #[cfg(any(
+                    feature = "catnap-libos",
+                    feature = "catcollar-libos",
+                    feature = "catnip-libos",
+                    feature = "catpowder-libos",
+                    feature = "catloop-libos"
+                ))]
+                LibOS::NetworkLibOS(libos) => libos.splice(sockqd, srcqd, size, timeout),
+                #[cfg(feature = "catmem-libos")]
+                LibOS::MemoryLibOS(_) => Err(Fail::new(libc::ENOTSUP, "splice() is not supported on memory liboses")),
+            }
+        };
+
+        self.poll();
+
+        result
+    }
+
+    /// Writes [sga] to the memory of the peer of the RDMA queue [qd] that [region] describes, without involving the CPU
+    /// of the peer.
+    #[cfg(feature = "librdma")]
+    pub fn rdma_write(&mut self, qd: QDesc, sga: &demi_sgarray_t, region: RdmaRegion) -> Result<QToken, Fail> {
+        self.timed_rdma_write(qd, sga, region, None)
+    }
+
+    /// Writes data like [Self::rdma_write], failing with ETIMEDOUT if it is not written within [timeout].
+    #[cfg(feature = "librdma")]
+    #[allow(unused_variables)]
+    pub fn timed_rdma_write(
+        &mut self,
+        qd: QDesc,
+        sga: &demi_sgarray_t,
+        region: RdmaRegion,
+        timeout: Option<Duration>,
+    ) -> Result<QToken, Fail> {
+        let result: Result<QToken, Fail> = {
+            timer!("demikernel::rdma_write");
+            match self {
+                #[cfg(any(
+                    feature = "catnap-libos",
+                    feature = "catcollar-libos",
+                    feature = "catnip-libos",
+                    feature = "catpowder-libos",
+                    feature = "catloop-libos"
+                ))]
+                LibOS::NetworkLibOS(libos) => libos.rdma_write(qd, sga, region, timeout),
+                #[cfg(feature = "catmem-libos")]
+                LibOS::MemoryLibOS(_) => Err(Fail::new(
+                    libc::ENOTSUP,
+                    "rdma_write() is not supported on memory liboses",
+                )),
+            }
+        };
+
+        self.poll();
+
+        result
+    }
+
+    /// Reads the memory of the peer of the RDMA queue [qd] that [region] describes. The data completes the returned
+    /// token like a pop.
+    #[cfg(feature = "librdma")]
+    pub fn rdma_read(&mut self, qd: QDesc, region: RdmaRegion) -> Result<QToken, Fail> {
+        self.timed_rdma_read(qd, region, None)
+    }
+
+    /// Reads data like [Self::rdma_read], failing with ETIMEDOUT if it is not read within [timeout].
+    #[cfg(feature = "librdma")]
+    #[allow(unused_variables)]
+    pub fn timed_rdma_read(
+        &mut self,
+        qd: QDesc,
+        region: RdmaRegion,
+        timeout: Option<Duration>,
+    ) -> Result<QToken, Fail> {
+        let result: Result<QToken, Fail> = {
+            timer!("demikernel::rdma_read");
+            match self {
+                #[cfg(any(
+                    feature = "catnap-libos",
+                    feature = "catcollar-libos",
+                    feature = "catnip-libos",
+                    feature = "catpowder-libos",
+                    feature = "catloop-libos"
+                ))]
+                LibOS::NetworkLibOS(libos) => libos.rdma_read(qd, region, timeout),
+                #[cfg(feature = "catmem-libos")]
+                LibOS::MemoryLibOS(_) => Err(Fail::new(
+                    libc::ENOTSUP,
+                    "rdma_read() is not supported on memory liboses",
+                )),
+            }
+        };
+
+        self.poll();
+
+        result
+    }
+
+    /// Pushes a scatter-gather array to a UDP socket.
+    pub fn pushto(&mut self, qd: QDesc, sga: &demi_sgarray_t, to: SocketAddr) -> Result<QToken, Fail> {
+        self.timed_pushto(qd, sga, to, None)
+    }
+
+    /// Pushes a scatter-gather array to a UDP socket, failing with ETIMEDOUT if it is not pushed within [timeout].
+    #[allow(unused_variables)]
+    pub fn timed_pushto(
+        &mut self,
+        qd: QDesc,
+        sga: &demi_sgarray_t,
+        to: SocketAddr,
+        timeout: Option<Duration>,
+    ) -> Result<QToken, Fail> {
+        let result: Result<QToken, Fail> = {
+            timer!("demikernel::pushto");
+            match self {
+                #[cfg(any(
+                    feature = "catnap-libos",
+                    feature = "catcollar-libos",
+                    feature = "catnip-libos",
+                    feature = "catpowder-libos",
+                    feature = "catloop-libos"
+                ))]
+                LibOS::NetworkLibOS(libos) => libos.pushto(qd, sga, to, timeout),
+                #[cfg(feature = "catmem-libos")]
+                LibOS::MemoryLibOS(_) => Err(Fail::new(libc::ENOTSUP, "pushto() is not supported on memory liboses")),
+            }
+        };
+
+        self.poll();
+
+        result
+    }
+
+    /// Pops data from a an I/O queue.
+    pub fn pop(&mut self, qd: QDesc, size: Option<usize>) -> Result<QToken, Fail> {
+        self.timed_pop(qd, size, None)
+    }
+
+    /// Pops data from an I/O queue, failing with ETIMEDOUT if no data arrives within [timeout].
+    pub fn timed_pop(&mut self, qd: QDesc, size: Option<usize>, timeout: Option<Duration>) -> Result<QToken, Fail> {
+        let result: Result<QToken, Fail> = {
+            timer!("demikernel::pop");
+
+            // Check if this is a fixed-size pop.
+            if let Some(size) = size {
+                // Check if size is valid.
+                if !((size > 0) && (size <= limits::POP_SIZE_MAX)) {
+                    let cause: String = format!("invalid pop size (size={:?})", size);
+                    error!("timed_pop(): {:?}", &cause);
+                    return Err(Fail::new(libc::EINVAL, &cause));
+                }
+            }
+
+            match self {
+                #[cfg(any(
+                    feature = "catnap-libos",
+                    feature = "catcollar-libos",
+                    feature = "catnip-libos",
+                    feature = "catpowder-libos",
+                    feature = "catloop-libos"
+                ))]
+                LibOS::NetworkLibOS(libos) => libos.pop(qd, size, timeout),
+                #[cfg(feature = "catmem-libos")]
+                LibOS::MemoryLibOS(libos) => libos.pop(qd, size, timeout),
+            }
+        };
+
+        self.poll();
+
+        result
+    }
+
+    /// Pops up to [max] datagrams, or a coalesced range of bytes, that a socket already received.
+    pub fn pop_many(&mut self, qd: QDesc, max: usize) -> Result<QToken, Fail> {
+        self.timed_pop_many(qd, max, None)
+    }
+
+    /// Pops like [Self::pop_many], failing with ETIMEDOUT if no data arrives within [timeout].
+    #[allow(unused_variables)]
+    pub fn timed_pop_many(&mut self, qd: QDesc, max: usize, timeout: Option<Duration>) -> Result<QToken, Fail> {
+        let result: Result<QToken, Fail> = {
+            timer!("demikernel::pop_many");
+
+            if max == 0 {
+                let cause: &str = "batched pops take at least one buffer";
+                error!("timed_pop_many(): {}", cause);
+                return Err(Fail::new(libc::EINVAL, cause));
+            }
+
+            match self {
+                #[cfg(any(
+                    feature = "catnap-libos",
+                    feature = "catcollar-libos",
+                    feature = "catnip-libos",
+                    feature = "catpowder-libos",
+                    feature = "catloop-libos"
+                ))]
+                LibOS::NetworkLibOS(libos) => libos.pop_many(qd, max, timeout),
+                #[cfg(feature = "catmem-libos")]
+                LibOS::MemoryLibOS(_) => Err(Fail::new(
+                    libc::ENOTSUP,
+                    "pop_many() is not supported on memory liboses",
+                )),
+            }
+        };
+
+        self.poll();
+
+        result
+    }
+
+    /// Waits for a pending I/O operation to complete or a timeout to expire.
+    /// This is just a single-token convenience wrapper for wait_any().
+    pub fn wait(&mut self, qt: QToken, timeout: Option<Duration>) -> Result<demi_qresult_t, Fail> {
+        timer!("demikernel::wait");
+        self.reload_config_if_requested();
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.wait(qt, timeout.unwrap_or(DEFAULT_TIMEOUT)),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(libos) => libos.wait(qt, timeout.unwrap_or(DEFAULT_TIMEOUT)),
+        }
+    }
+
+    /// Waits for any of the given pending I/O operations to complete or a timeout to expire.
+    pub fn wait_any(&mut self, qts: &[QToken], timeout: Option<Duration>) -> Result<(usize, demi_qresult_t), Fail> {
+        timer!("demikernel::wait_any");
+        self.reload_config_if_requested();
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.wait_any(qts, timeout.unwrap_or(DEFAULT_TIMEOUT)),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(libos) => libos.wait_any(qts, timeout.unwrap_or(DEFAULT_TIMEOUT)),
+        }
+    }
+
+    /// Waits in a loop until the next task is complete, passing the result to `acceptor`. This process continues until
+    /// either the acceptor returns false (in which case the method returns Ok), or the timeout has expired (in which
+    /// the method returns an `Err` indicating timeout).
+    #[allow(unreachable_patterns, unused_variables)]
+    pub fn wait_next_n<Acceptor: FnMut(demi_qresult_t) -> bool>(
+        &mut self,
+        acceptor: Acceptor,
+        timeout: Option<Duration>,
+    ) -> Result<(), Fail> {
+        timer!("demikernel::wait_next_n");
+        self.reload_config_if_requested();
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.wait_next_n(acceptor, timeout.unwrap_or(DEFAULT_TIMEOUT)),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(libos) => libos.wait_next_n(acceptor, timeout.unwrap_or(DEFAULT_TIMEOUT)),
+        }
+    }
+
+    /// Allocates a scatter-gather array.
+    pub fn sgaalloc(&mut self, size: usize) -> Result<demi_sgarray_t, Fail> {
+        let result: Result<demi_sgarray_t, Fail> = {
+            timer!("demikernel::sgaalloc");
+            match self {
+                #[cfg(any(
+                    feature = "catnap-libos",
+                    feature = "catcollar-libos",
+                    feature = "catnip-libos",
+                    feature = "catpowder-libos",
+                    feature = "catloop-libos"
+                ))]
+                LibOS::NetworkLibOS(libos) => libos.sgaalloc(size),
+                #[cfg(feature = "catmem-libos")]
+                LibOS::MemoryLibOS(libos) => libos.sgaalloc(size),
+            }
+        };
+
+        result
+    }
+
+    /// Releases a scatter-gather array.
+    pub fn sgafree(&mut self, sga: demi_sgarray_t) -> Result<(), Fail> {
+        let result: Result<(), Fail> = {
+            timer!("demikernel::sgafree");
+            match self {
+                #[cfg(any(
+                    feature = "catnap-libos",
+                    feature = "catcollar-libos",
+                    feature = "catnip-libos",
+                    feature = "catpowder-libos",
+                    feature = "catloop-libos"
+                ))]
+                LibOS::NetworkLibOS(libos) => libos.sgafree(sga),
+                #[cfg(feature = "catmem-libos")]
+                LibOS::MemoryLibOS(libos) => libos.sgafree(sga),
+            }
+        };
+
+        result
+    }
+
+    /// Releases the scatter-gather arrays of a batched pop.
+    #[allow(unused_variables)]
+    pub fn sgafree_batch(&mut self, batch: demi_sgarray_batch_t) -> Result<(), Fail> {
+        let result: Result<(), Fail> = {
+            timer!("demikernel::sgafree_batch");
+            match self {
+                #[cfg(any(
+                    feature = "catnap-libos",
+                    feature = "catcollar-libos",
+                    feature = "catnip-libos",
+                    feature = "catpowder-libos",
+                    feature = "catloop-libos"
+                ))]
+                LibOS::NetworkLibOS(libos) => libos.sgafree_batch(batch),
+                #[cfg(feature = "catmem-libos")]
+                LibOS::MemoryLibOS(_) => Err(Fail::new(
+                    libc::ENOTSUP,
+                    "sgafree_batch() is not supported on memory liboses",
+                )),
+            }
+        };
+
+        result
+    }
+
+    pub fn poll(&mut self) {
+        timer!("demikernel::poll");
+        self.reload_config_if_requested();
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
                 feature = "catnip-libos",
                 feature = "catpowder-libos",
                 feature = "catloop-libos"
@@ -583,4 +1911,114 @@ impl LibOS {
             LibOS::MemoryLibOS(libos) => libos.poll(),
         }
     }
+
+    /// Cancels a pending I/O operation. The operation then completes with ECANCELED.
+    pub fn cancel(&mut self, qt: QToken) -> Result<(), Fail> {
+        let result: Result<(), Fail> = {
+            timer!("demikernel::cancel");
+            match self {
+                #[cfg(any(
+                    feature = "catnap-libos",
+                    feature = "catcollar-libos",
+                    feature = "catnip-libos",
+                    feature = "catpowder-libos",
+                    feature = "catloop-libos"
+                ))]
+                LibOS::NetworkLibOS(libos) => libos.cancel(qt),
+                #[cfg(feature = "catmem-libos")]
+                LibOS::MemoryLibOS(libos) => libos.cancel(qt),
+            }
+        };
+
+        result
+    }
+
+    /// Spawns the application coroutine [coroutine] named [name] onto the Demikernel scheduler, so that application
+    /// timers and housekeeping run in the same poll loop as I/O. The coroutine makes progress whenever the application
+    /// waits, and it completes through the returned queue token like an I/O operation: with DEMI_OPC_TASK if it
+    /// returns Ok, or with DEMI_OPC_FAILED and its error code otherwise. It may also be cancelled.
+    pub fn spawn<F: FusedFuture<Output = Result<(), Fail>> + 'static>(
+        &mut self,
+        name: &'static str,
+        coroutine: Pin<Box<F>>,
+    ) -> Result<QToken, Fail> {
+        let result: Result<QToken, Fail> = {
+            timer!("demikernel::spawn");
+            match self {
+                #[cfg(any(
+                    feature = "catnap-libos",
+                    feature = "catcollar-libos",
+                    feature = "catnip-libos",
+                    feature = "catpowder-libos",
+                    feature = "catloop-libos"
+                ))]
+                LibOS::NetworkLibOS(libos) => libos.spawn(name, coroutine),
+                #[cfg(feature = "catmem-libos")]
+                LibOS::MemoryLibOS(libos) => libos.spawn(name, coroutine),
+            }
+        };
+
+        result
+    }
+
+    /// Returns a file descriptor that becomes readable when waiting would return, so that Demikernel can be embedded in
+    /// an external event loop (e.g. epoll) instead of dedicating a thread to waiting. The descriptor is readable while
+    /// completed operations are waiting to be collected or while a doorbell that some coroutine waits on has rung.
+    /// It is only brought up to date when Demikernel is polled, so applications should poll after it becomes readable
+    /// and after they issue new operations. It may be readable spuriously. Timers and busy-polled devices do not
+    /// signal it, so event loops should still poll periodically while operations are pending on them. The descriptor
+    /// is owned by Demikernel and must not be closed.
+    pub fn get_event_fd(&mut self) -> Result<libc::c_int, Fail> {
+        let result: Result<libc::c_int, Fail> = {
+            timer!("demikernel::get_event_fd");
+            match self {
+                #[cfg(any(
+                    feature = "catnap-libos",
+                    feature = "catcollar-libos",
+                    feature = "catnip-libos",
+                    feature = "catpowder-libos",
+                    feature = "catloop-libos"
+                ))]
+                LibOS::NetworkLibOS(libos) => libos.get_event_fd(),
+                #[cfg(feature = "catmem-libos")]
+                LibOS::MemoryLibOS(libos) => libos.get_event_fd(),
+            }
+        };
+
+        result
+    }
+
+    /// Turns per-coroutine accounting of runtime and poll counts on or off. Accounting reads the clock around every
+    /// poll of a coroutine, so it is off by default. Turning it off drops the stats collected so far.
+    pub fn set_task_accounting(&mut self, enabled: bool) {
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.set_task_accounting(enabled),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(libos) => libos.set_task_accounting(enabled),
+        }
+    }
+
+    /// Returns the accounting of the [max_tasks] coroutines with the longest single polls, which are the likely causes
+    /// of tail latency spikes. Coroutines that already finished are aggregated by name.
+    pub fn get_task_stats(&self, max_tasks: usize) -> Vec<TaskStats> {
+        match self {
+            #[cfg(any(
+                feature = "catnap-libos",
+                feature = "catcollar-libos",
+                feature = "catnip-libos",
+                feature = "catpowder-libos",
+                feature = "catloop-libos"
+            ))]
+            LibOS::NetworkLibOS(libos) => libos.get_task_stats(max_tasks),
+            #[cfg(feature = "catmem-libos")]
+            LibOS::MemoryLibOS(libos) => libos.get_task_stats(max_tasks),
+        }
+    }
 }
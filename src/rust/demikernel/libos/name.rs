@@ -17,6 +17,7 @@ use ::std::env;
 pub enum LibOSName {
     Catpowder,
     Catnap,
+    Catcollar,
     Catnip,
     Catmem,
     Catloop,
@@ -46,6 +47,7 @@ impl From<String> for LibOSName {
         match str.to_lowercase().as_str() {
             "catpowder" => LibOSName::Catpowder,
             "catnap" => LibOSName::Catnap,
+            "catcollar" => LibOSName::Catcollar,
             "catnip" => LibOSName::Catnip,
             "catmem" => LibOSName::Catmem,
             "catloop" => LibOSName::Catloop,
@@ -0,0 +1,393 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    demikernel::libos::network::queue::SharedNetworkQueue,
+    runtime::{
+        conditional_yield_until,
+        fail::Fail,
+        memory::DemiBuffer,
+        network::{
+            tls::{
+                dtls::{
+                    DtlsAdmission,
+                    DtlsCookieGuard,
+                    DtlsSession,
+                },
+                TlsConfig,
+            },
+            transport::NetworkTransport,
+        },
+        queue::{
+            IoQueue,
+            QType,
+        },
+        SharedConditionVariable,
+        SharedDemiRuntime,
+        SharedObject,
+    },
+};
+use ::futures::{
+    pin_mut,
+    select_biased,
+    FutureExt,
+};
+use ::std::{
+    any::Any,
+    net::SocketAddr,
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    time::Instant,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// A DTLS association over a bound UDP socket. There is no driver coroutine: pushes and pops move the handshake along,
+/// send the datagrams of the session and fire its retransmission timer.
+///
+/// A client starts its association right away. A server waits for the first client whose ClientHello carries a valid
+/// cookie, and then only talks to that client.
+pub struct DtlsQueue<T: NetworkTransport> {
+    runtime: SharedDemiRuntime,
+    /// UDP socket that carries the records of the association.
+    udp: SharedNetworkQueue<T>,
+    config: TlsConfig,
+    /// The association, unless the queue is a server that did not admit a client yet.
+    session: Option<DtlsSession>,
+    /// Guard that admits clients, if the queue is a server.
+    guard: Option<DtlsCookieGuard>,
+    /// Address of the peer, once it is known.
+    remote: Option<SocketAddr>,
+    /// Whether the queue is being closed.
+    closing: bool,
+    /// Wakes coroutines that wait on the association when another coroutine made progress on it.
+    progress_cv: SharedConditionVariable,
+}
+
+#[derive(Clone)]
+pub struct SharedDtlsQueue<T: NetworkTransport>(SharedObject<DtlsQueue<T>>);
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl<T: NetworkTransport> SharedDtlsQueue<T> {
+    /// Starts a DTLS association with [remote] over the bound UDP socket [udp].
+    pub fn new_client(
+        runtime: SharedDemiRuntime,
+        udp: SharedNetworkQueue<T>,
+        remote: SocketAddr,
+        config: TlsConfig,
+    ) -> Result<Self, Fail> {
+        Self::check_socket(&udp)?;
+        let session: DtlsSession = DtlsSession::new_client(config.clone(), runtime.get_now());
+        Ok(Self::create(runtime, udp, config, Some(session), None, Some(remote)))
+    }
+
+    /// Waits for a DTLS client on the bound UDP socket [udp].
+    pub fn new_server(runtime: SharedDemiRuntime, udp: SharedNetworkQueue<T>, config: TlsConfig) -> Result<Self, Fail> {
+        Self::check_socket(&udp)?;
+        let guard: DtlsCookieGuard = DtlsCookieGuard::new(config.crypto_provider());
+        Ok(Self::create(runtime, udp, config, None, Some(guard), None))
+    }
+
+    fn create(
+        runtime: SharedDemiRuntime,
+        udp: SharedNetworkQueue<T>,
+        config: TlsConfig,
+        session: Option<DtlsSession>,
+        guard: Option<DtlsCookieGuard>,
+        remote: Option<SocketAddr>,
+    ) -> Self {
+        Self(SharedObject::new(DtlsQueue::<T> {
+            runtime,
+            udp,
+            config,
+            session,
+            guard,
+            remote,
+            closing: false,
+            progress_cv: SharedConditionVariable::default(),
+        }))
+    }
+
+    /// Checks that DTLS can run over [udp].
+    fn check_socket(udp: &SharedNetworkQueue<T>) -> Result<(), Fail> {
+        if udp.get_qtype() != QType::UdpSocket {
+            let cause: String = format!("DTLS only runs over UDP sockets (qtype={:?})", udp.get_qtype());
+            error!("new(): {}", cause);
+            return Err(Fail::new(libc::ENOTSUP, &cause));
+        }
+        if udp.local().is_none() {
+            let cause: &str = "socket is not bound";
+            error!("new(): {}", cause);
+            return Err(Fail::new(libc::EDESTADDRREQ, cause));
+        }
+        Ok(())
+    }
+
+    /// Encrypts [buf] and sends it to the peer in one datagram, once the handshake completes.
+    pub async fn push_coroutine(&mut self, buf: &mut DemiBuffer) -> Result<(), Fail> {
+        self.handshake().await?;
+        self.ensure_not_closing()?;
+        self.get_session()?.write(&buf[..])?;
+        self.flush().await?;
+        buf.trim(buf.len())?;
+        Ok(())
+    }
+
+    /// Waits for the next datagram of application data from the peer and returns up to [size] bytes of it. An empty
+    /// buffer means that the peer closed the association.
+    pub async fn pop_coroutine(&mut self, size: usize) -> Result<(Option<SocketAddr>, DemiBuffer), Fail> {
+        self.handshake().await?;
+        let remote: Option<SocketAddr> = self.remote;
+        loop {
+            self.ensure_not_closing()?;
+            let session: &mut DtlsSession = self.get_session()?;
+            session.check_error()?;
+            if let Some(data) = session.read() {
+                let len: usize = data.len().min(size);
+                return Ok((remote, DemiBuffer::from_slice(&data[..len])?));
+            }
+            if session.is_peer_closed() {
+                return Ok((remote, DemiBuffer::new(0)));
+            }
+            self.receive().await?;
+        }
+    }
+
+    /// Sets the target queue as closing, so that no further operations start.
+    pub fn close(&mut self) -> Result<(), Fail> {
+        self.ensure_not_closing()?;
+        self.closing = true;
+        self.progress_cv.broadcast();
+        Ok(())
+    }
+
+    /// Lets the peer know that the association is closed and closes the UDP socket.
+    pub async fn close_coroutine(&mut self) -> Result<(), Fail> {
+        if let Some(session) = self.session.as_mut() {
+            session.close();
+        }
+        if let Err(e) = self.flush().await {
+            warn!("close_coroutine(): failed to send close_notify ({:?})", e);
+        }
+        self.udp.hard_close()
+    }
+
+    /// Closes the UDP socket without letting the peer know.
+    pub fn hard_close(&mut self) -> Result<(), Fail> {
+        self.closing = true;
+        self.udp.hard_close()
+    }
+
+    /// Gets the address that the UDP socket is bound to.
+    pub fn local(&self) -> Option<SocketAddr> {
+        self.udp.local()
+    }
+
+    /// Gets the address of the peer, once it is known.
+    pub fn remote(&self) -> Option<SocketAddr> {
+        self.remote
+    }
+
+    /// Waits until the handshake of the association completes.
+    async fn handshake(&mut self) -> Result<(), Fail> {
+        loop {
+            self.ensure_not_closing()?;
+            if let Some(session) = self.session.as_ref() {
+                session.check_error()?;
+                if session.is_handshake_complete() {
+                    return Ok(());
+                }
+            }
+            self.receive().await?;
+        }
+    }
+
+    /// Sends whatever the association has for the peer, waits for the next datagram, the retransmission timer of the
+    /// association, or progress that another coroutine made, and sends whatever the association has for the peer
+    /// afterwards.
+    async fn receive(&mut self) -> Result<(), Fail> {
+        self.flush().await?;
+        let deadline: Option<Instant> = self.session.as_ref().and_then(|session| session.next_timeout());
+        let mut udp: SharedNetworkQueue<T> = self.udp.clone();
+        let mut progress_cv: SharedConditionVariable = self.progress_cv.clone();
+        let operation = udp.pop_coroutine(None).fuse();
+        let progress = progress_cv.wait().fuse();
+        pin_mut!(operation);
+        pin_mut!(progress);
+        let event = async {
+            select_biased! {
+                result = operation => Some(result),
+                _ = progress => None,
+            }
+        };
+        let result: Result<(), Fail> = match conditional_yield_until(event, deadline).await {
+            Ok(Some(Ok((Some(remote), buf, _)))) => self.process_datagram(remote, &buf[..]).await,
+            Ok(Some(Ok((None, _, _)))) => {
+                warn!("receive(): dropping datagram without a source address");
+                return Ok(());
+            },
+            Ok(Some(Err(e))) => return Err(e),
+            // Another coroutine made progress, which it already sent.
+            Ok(None) => return Ok(()),
+            Err(_) => {
+                let now: Instant = self.runtime.get_now();
+                match self.session.as_mut() {
+                    Some(session) => session.on_timeout(now),
+                    None => Ok(()),
+                }
+            },
+        };
+        let flushed: Result<(), Fail> = self.flush().await;
+        self.progress_cv.broadcast();
+        result.and(flushed)
+    }
+
+    /// Hands the [datagram] that arrived from [remote] to the association. A server without a client yet has its guard
+    /// decide whether to admit [remote].
+    async fn process_datagram(&mut self, remote: SocketAddr, datagram: &[u8]) -> Result<(), Fail> {
+        let now: Instant = self.runtime.get_now();
+        let is_peer: bool = self.remote == Some(remote);
+        if let Some(session) = self.session.as_mut() {
+            if !is_peer {
+                trace!(
+                    "process_datagram(): dropping datagram of unknown peer (remote={:?})",
+                    remote
+                );
+                return Ok(());
+            }
+            return session.receive(datagram, now);
+        }
+        let guard: &DtlsCookieGuard = match self.guard.as_ref() {
+            Some(guard) => guard,
+            None => return Ok(()),
+        };
+        match guard.admit(&self.config, &encode_address(remote), datagram, now) {
+            DtlsAdmission::Accept(session) => {
+                self.session = Some(session);
+                self.remote = Some(remote);
+            },
+            DtlsAdmission::Retry(reply) | DtlsAdmission::Refuse(reply) => self.send_to(remote, reply).await,
+            DtlsAdmission::Drop => (),
+        }
+        Ok(())
+    }
+
+    /// Sends the datagrams that the association built.
+    async fn flush(&mut self) -> Result<(), Fail> {
+        let remote: SocketAddr = match self.remote {
+            Some(remote) => remote,
+            None => return Ok(()),
+        };
+        let mut datagrams: Vec<Vec<u8>> = Vec::new();
+        if let Some(session) = self.session.as_mut() {
+            while let Some(datagram) = session.take_output() {
+                datagrams.push(datagram);
+            }
+        }
+        let mut udp: SharedNetworkQueue<T> = self.udp.clone();
+        for datagram in datagrams {
+            let mut buf: DemiBuffer = DemiBuffer::from_slice(&datagram)?;
+            udp.push_coroutine(&mut buf, Some(remote)).await?;
+        }
+        Ok(())
+    }
+
+    /// Sends [datagram] to [remote], which is not the peer of an association.
+    async fn send_to(&mut self, remote: SocketAddr, datagram: Vec<u8>) {
+        let mut buf: DemiBuffer = match DemiBuffer::from_slice(&datagram) {
+            Ok(buf) => buf,
+            Err(e) => {
+                warn!("send_to(): failed to allocate datagram ({:?})", e);
+                return;
+            },
+        };
+        let mut udp: SharedNetworkQueue<T> = self.udp.clone();
+        if let Err(e) = udp.push_coroutine(&mut buf, Some(remote)).await {
+            warn!(
+                "send_to(): failed to send datagram (remote={:?}, error={:?})",
+                remote, e
+            );
+        }
+    }
+
+    fn get_session(&mut self) -> Result<&mut DtlsSession, Fail> {
+        match self.session.as_mut() {
+            Some(session) => Ok(session),
+            None => {
+                let cause: &str = "association is not established";
+                warn!("get_session(): {}", cause);
+                Err(Fail::new(libc::ENOTCONN, cause))
+            },
+        }
+    }
+
+    fn ensure_not_closing(&self) -> Result<(), Fail> {
+        if self.closing {
+            let cause: &str = "socket is closing";
+            warn!("ensure_not_closing(): {}", cause);
+            return Err(Fail::new(libc::EBADF, cause));
+        }
+        Ok(())
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl<T: NetworkTransport> IoQueue for SharedDtlsQueue<T> {
+    fn get_qtype(&self) -> QType {
+        QType::DtlsSocket
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+impl<T: NetworkTransport> Deref for SharedDtlsQueue<T> {
+    type Target = DtlsQueue<T>;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl<T: NetworkTransport> DerefMut for SharedDtlsQueue<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Encodes [addr] for the cookies of a server, which are bound to the address of the client.
+fn encode_address(addr: SocketAddr) -> Vec<u8> {
+    let mut bytes: Vec<u8> = match addr {
+        SocketAddr::V4(addr) => addr.ip().octets().to_vec(),
+        SocketAddr::V6(addr) => addr.ip().octets().to_vec(),
+    };
+    bytes.extend_from_slice(&addr.port().to_be_bytes());
+    bytes
+}
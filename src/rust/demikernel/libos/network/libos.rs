@@ -5,8 +5,33 @@
 // Imports
 //==============================================================================
 
+#[cfg(feature = "libspdk")]
+use crate::demikernel::libos::block::{
+    SharedBlockQueue,
+    SharedNvmeDriver,
+};
+#[cfg(feature = "librdma")]
+use crate::demikernel::libos::rdma::{
+    RdmaRegion,
+    SharedRdmaDriver,
+    SharedRdmaQueue,
+};
 use crate::{
-    demikernel::libos::network::queue::SharedNetworkQueue,
+    demikernel::libos::{
+        file::{
+            SharedFileEngine,
+            SharedFileQueue,
+        },
+        network::{
+            dtls::SharedDtlsQueue,
+            ptp::SharedPtpDriver,
+            queue::SharedNetworkQueue,
+            quic::{
+                SharedQuicQueue,
+                SharedQuicStreamQueue,
+            },
+        },
+    },
     expect_ok,
     expect_some,
     pal::{
@@ -18,7 +43,48 @@ use crate::{
         limits,
         memory::DemiBuffer,
         network::{
-            socket::SocketId,
+            ancillary::AncillaryData,
+            config::{
+                IpsecDirection,
+                Route,
+                SecurityAssociation,
+            },
+            drops::{
+                DropRecord,
+                DropStats,
+            },
+            framing::{
+                http::{
+                    HttpCodec,
+                    HttpRole,
+                },
+                FrameCodec,
+            },
+            numa::NumaStats,
+            ptp::{
+                clock::SharedPtpClock,
+                PtpConfig,
+                PTP_EVENT_PORT,
+                PTP_GENERAL_PORT,
+            },
+            quic::QuicConfig,
+            socket::{
+                filter::SocketFilter,
+                option::SocketOption,
+                SocketId,
+            },
+            tcp_info::{
+                TcpEvent,
+                TcpInfo,
+            },
+            timestamp::{
+                TxTimestamp,
+                SOF_TIMESTAMPING_TX_SOFTWARE,
+            },
+            tls::{
+                TlsConfig,
+                TlsRole,
+            },
             transport::NetworkTransport,
             unwrap_socketaddr,
         },
@@ -27,11 +93,13 @@ use crate::{
             IoQueue,
             OperationResult,
         },
+        scheduler::TaskStats,
         types::{
             demi_accept_result_t,
             demi_opcode_t,
             demi_qr_value_t,
             demi_qresult_t,
+            demi_sgarray_batch_t,
             demi_sgarray_t,
         },
         QDesc,
@@ -41,13 +109,17 @@ use crate::{
     },
     QType,
 };
-use ::futures::FutureExt;
+use ::futures::{
+    future::FusedFuture,
+    FutureExt,
+};
 use ::socket2::{
     Domain,
     Protocol,
     Type,
 };
 use ::std::{
+    collections::HashMap,
     mem,
     net::{
         Ipv4Addr,
@@ -58,6 +130,9 @@ use ::std::{
         Deref,
         DerefMut,
     },
+    os::fd::RawFd,
+    pin::Pin,
+    ptr,
     time::Duration,
 };
 
@@ -80,6 +155,16 @@ pub struct NetworkLibOS<T: NetworkTransport> {
     runtime: SharedDemiRuntime,
     /// Underlying network transport.
     transport: T,
+    /// PTP client, if one was started.
+    ptp: Option<SharedPtpDriver<T>>,
+    /// Engine for file queues, created when the first file is opened.
+    files: Option<SharedFileEngine>,
+    /// Driver for block queues, created when the first block device is opened.
+    #[cfg(feature = "libspdk")]
+    blocks: Option<SharedNvmeDriver>,
+    /// Driver for RDMA queues, created when the first one is.
+    #[cfg(feature = "librdma")]
+    rdma: Option<SharedRdmaDriver>,
 }
 
 #[derive(Clone)]
@@ -96,28 +181,38 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
         Self(SharedObject::new(NetworkLibOS::<T> {
             runtime: runtime.clone(),
             transport,
+            ptp: None,
+            files: None,
+            #[cfg(feature = "libspdk")]
+            blocks: None,
+            #[cfg(feature = "librdma")]
+            rdma: None,
         }))
     }
 
     /// Creates a socket. This function contains the libOS-level functionality needed to create a SharedNetworkQueue that
     /// wraps the underlying POSIX socket.
-    pub fn socket(&mut self, domain: Domain, typ: Type, _protocol: Protocol) -> Result<QDesc, Fail> {
-        trace!("socket() domain={:?}, type={:?}, protocol={:?}", domain, typ, _protocol);
-
-        // Parse communication domain.
-        if domain != Domain::IPV4 {
+    pub fn socket(&mut self, domain: Domain, typ: Type, protocol: Protocol) -> Result<QDesc, Fail> {
+        trace!("socket() domain={:?}, type={:?}, protocol={:?}", domain, typ, protocol);
+
+        // Parse communication domain. Packet sockets are only available on Linux, where they take raw frames.
+        #[cfg(target_os = "linux")]
+        let is_packet_socket: bool = domain == Domain::PACKET && typ == Type::RAW;
+        #[cfg(not(target_os = "linux"))]
+        let is_packet_socket: bool = false;
+        if domain != Domain::IPV4 && !is_packet_socket {
             return Err(Fail::new(libc::ENOTSUP, "communication domain not supported"));
         }
 
         // Parse socket type.
-        if (typ != Type::STREAM) && (typ != Type::DGRAM) {
+        if (typ != Type::STREAM) && (typ != Type::DGRAM) && (typ != Type::RAW) {
             let cause: String = format!("socket type not supported (type={:?})", typ);
             error!("socket(): {}", cause);
             return Err(Fail::new(libc::ENOTSUP, &cause));
         }
 
         // Create underlying queue.
-        let queue: SharedNetworkQueue<T> = SharedNetworkQueue::new(domain, typ, &mut self.transport)?;
+        let queue: SharedNetworkQueue<T> = SharedNetworkQueue::new(domain, typ, protocol, &mut self.transport)?;
         let qd: QDesc = self.runtime.alloc_queue(queue);
         Ok(qd)
     }
@@ -128,6 +223,11 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
         trace!("bind() qd={:?}, local={:?}", qd, local);
 
         let localv4: SocketAddrV4 = unwrap_socketaddr(local)?;
+        // RDMA queues have ports of their own, which the connection manager hands out.
+        #[cfg(feature = "librdma")]
+        if self.runtime.get_queue_type(&qd)? == QType::RdmaQueue {
+            return self.get_rdma_queue(&qd)?.bind(localv4);
+        }
         // Check if we are binding to the wildcard address. We only support this for UDP sockets right now.
         // FIXME: https://github.com/demikernel/demikernel/issues/189
         if localv4.ip() == &Ipv4Addr::UNSPECIFIED && self.get_shared_queue(&qd)?.get_qtype() != QType::UdpSocket {
@@ -180,6 +280,13 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
         }
     }
 
+    /// Binds the socket that is associated with a SharedNetworkQueue to [name], which local processes connect to
+    /// instead of an address.
+    pub fn bind_name(&mut self, qd: QDesc, name: &str) -> Result<(), Fail> {
+        trace!("bind_name() qd={:?}, name={:?}", qd, name);
+        self.get_shared_queue(&qd)?.bind_name(name)
+    }
+
     /// Sets a SharedNetworkQueue and its underlying socket as a passive one. This function contains the libOS-level
     /// functionality to move the SharedNetworkQueue and underlying socket into the listen state.
     pub fn listen(&mut self, qd: QDesc, backlog: usize) -> Result<(), Fail> {
@@ -191,100 +298,1138 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
             warn!("{}", cause);
             return Err(Fail::new(libc::EINVAL, &cause));
         }
-
-        // Issue listen operation.
-        self.get_shared_queue(&qd)?.listen(backlog)
+
+        // Issue listen operation.
+        if self.runtime.get_queue_type(&qd)? == QType::QuicSocket {
+            return self.get_quic_queue(&qd)?.listen(backlog);
+        }
+        #[cfg(feature = "librdma")]
+        if self.runtime.get_queue_type(&qd)? == QType::RdmaQueue {
+            return self.get_rdma_queue(&qd)?.listen(backlog);
+        }
+        self.get_shared_queue(&qd)?.listen(backlog)
+    }
+
+    /// Sets [option] on the socket that is associated with a SharedNetworkQueue.
+    pub fn set_socket_option(&mut self, qd: QDesc, option: SocketOption) -> Result<(), Fail> {
+        trace!("set_socket_option() qd={:?}, option={:?}", qd, option);
+        self.get_shared_queue(&qd)?.set_socket_option(option)
+    }
+
+    /// Attaches a receive filter to the socket that is associated with a SharedNetworkQueue, or detaches the current
+    /// one if [filter] is `None`.
+    pub fn set_receive_filter(&mut self, qd: QDesc, filter: Option<SocketFilter>) -> Result<(), Fail> {
+        trace!("set_receive_filter() qd={:?}", qd);
+        self.get_shared_queue(&qd)?.set_receive_filter(filter)
+    }
+
+    /// Adds an IPsec security association to the network transport.
+    pub fn ipsec_add_security_association(&mut self, sa: SecurityAssociation) -> Result<(), Fail> {
+        trace!(
+            "ipsec_add_security_association() spi={:?}, direction={:?}, peer={:?}",
+            sa.spi(),
+            sa.direction(),
+            sa.peer()
+        );
+        self.transport.ipsec_add_security_association(sa)
+    }
+
+    /// Removes the IPsec security association with security parameter index [spi] in [direction] from the network
+    /// transport.
+    pub fn ipsec_remove_security_association(&mut self, spi: u32, direction: IpsecDirection) -> Result<(), Fail> {
+        trace!(
+            "ipsec_remove_security_association() spi={:?}, direction={:?}",
+            spi,
+            direction
+        );
+        self.transport.ipsec_remove_security_association(spi, direction)
+    }
+
+    /// Adds [route] to the routing table of the network transport.
+    pub fn add_route(&mut self, route: Route) -> Result<(), Fail> {
+        trace!("add_route() route={}", route);
+        self.transport.add_route(route)
+    }
+
+    /// Removes the route toward the subnet [destination]/[prefix_len] from the routing table of the network
+    /// transport.
+    pub fn remove_route(&mut self, destination: Ipv4Addr, prefix_len: u8) -> Result<(), Fail> {
+        trace!(
+            "remove_route() destination={:?}, prefix_len={:?}",
+            destination,
+            prefix_len
+        );
+        self.transport.remove_route(destination, prefix_len)
+    }
+
+    /// Sets the tunable [name] of the network transport to [value]. Tunables under "wait." belong to the runtime.
+    pub fn set_tunable(&mut self, name: &str, value: &str) -> Result<(), Fail> {
+        trace!("set_tunable() name={:?}, value={:?}", name, value);
+        match name.strip_prefix("wait.") {
+            Some(name) => self.runtime.set_wait_tunable(name, value),
+            None => self.transport.set_tunable(name, value),
+        }
+    }
+
+    /// Gets the value of the tunable [name] of the network transport. Tunables under "wait." belong to the runtime.
+    pub fn get_tunable(&self, name: &str) -> Result<String, Fail> {
+        trace!("get_tunable() name={:?}", name);
+        match name.strip_prefix("wait.") {
+            Some(name) => self.runtime.get_wait_tunable(name),
+            None => self.transport.get_tunable(name),
+        }
+    }
+
+    /// Gets the current value of [option] on the socket that is associated with a SharedNetworkQueue.
+    pub fn get_socket_option(&mut self, qd: QDesc, option: SocketOption) -> Result<SocketOption, Fail> {
+        trace!("get_socket_option() qd={:?}, option={:?}", qd, option);
+        self.get_shared_queue(&qd)?.get_socket_option(option)
+    }
+
+    /// Gets the number of incoming packets that were dropped on their way to the socket that is associated with a
+    /// SharedNetworkQueue or, if [qd] is `None`, anywhere in the network transport.
+    pub fn get_drop_stats(&mut self, qd: Option<QDesc>) -> Result<DropStats, Fail> {
+        trace!("get_drop_stats() qd={:?}", qd);
+        match qd {
+            Some(qd) => self.get_shared_queue(&qd)?.get_drop_stats(),
+            None => self.transport.get_drop_stats(),
+        }
+    }
+
+    /// Gets where the network device placed its buffer pools and the state of its queues, relative to its NUMA node.
+    pub fn get_numa_stats(&self) -> Result<NumaStats, Fail> {
+        trace!("get_numa_stats()");
+        self.transport.get_numa_stats()
+    }
+
+    /// Pops the oldest record from the error queue of the socket that is associated with a SharedNetworkQueue.
+    pub fn pop_drop_record(&mut self, qd: QDesc) -> Result<Option<DropRecord>, Fail> {
+        trace!("pop_drop_record() qd={:?}", qd);
+        self.get_shared_queue(&qd)?.pop_drop_record()
+    }
+
+    /// Pops the oldest transmit timestamp of the socket that is associated with a SharedNetworkQueue.
+    pub fn pop_tx_timestamp(&mut self, qd: QDesc) -> Result<Option<TxTimestamp>, Fail> {
+        trace!("pop_tx_timestamp() qd={:?}", qd);
+        self.get_shared_queue(&qd)?.pop_tx_timestamp()
+    }
+
+    /// Gets a snapshot of the state of the TCP connection that is associated with a SharedNetworkQueue.
+    pub fn get_tcp_info(&mut self, qd: QDesc) -> Result<TcpInfo, Fail> {
+        trace!("get_tcp_info() qd={:?}", qd);
+        self.get_shared_queue(&qd)?.get_tcp_info()
+    }
+
+    /// Gets the recent retransmission and flow control events of the TCP connection that is associated with a
+    /// SharedNetworkQueue.
+    pub fn get_tcp_timeline(&mut self, qd: QDesc) -> Result<Vec<TcpEvent>, Fail> {
+        trace!("get_tcp_timeline() qd={:?}", qd);
+        self.get_shared_queue(&qd)?.get_tcp_timeline()
+    }
+
+    /// Hands the TCP connection of [qd] off to another process through the Unix domain socket [channel], so that a
+    /// process that accepts connections may dispatch them to workers. The queue descriptor is released without closing
+    /// the connection. Pending operations on the queue should complete or be cancelled beforehand.
+    pub fn export_queue(&mut self, qd: QDesc, channel: RawFd) -> Result<(), Fail> {
+        trace!("export_queue() qd={:?}, channel={:?}", qd, channel);
+        if self.runtime.get_queue_type(&qd)? != QType::TcpSocket {
+            let cause: String = format!("only TCP connections can be handed off (qd={:?})", qd);
+            error!("export_queue(): {}", cause);
+            return Err(Fail::new(libc::ENOTSUP, &cause));
+        }
+        self.get_shared_queue(&qd)?.export(channel)?;
+        self.runtime.free_queue::<SharedNetworkQueue<T>>(&qd)?;
+        Ok(())
+    }
+
+    /// Takes over a TCP connection that another process handed off through the Unix domain socket [channel].
+    pub fn import_queue(&mut self, channel: RawFd) -> Result<QDesc, Fail> {
+        trace!("import_queue() channel={:?}", channel);
+        let queue: SharedNetworkQueue<T> = SharedNetworkQueue::import(channel, &mut self.transport)?;
+        Ok(self.runtime.alloc_queue(queue))
+    }
+
+    /// Freezes the TCP connection of [qd] into a blob that another instance of the network stack may thaw, so that the
+    /// connection survives a restart or moves to another engine. The queue descriptor is released without letting the
+    /// peer know. Pending operations on the queue should complete or be cancelled beforehand.
+    pub fn freeze_queue(&mut self, qd: QDesc) -> Result<Vec<u8>, Fail> {
+        trace!("freeze_queue() qd={:?}", qd);
+        if self.runtime.get_queue_type(&qd)? != QType::TcpSocket {
+            let cause: String = format!("only TCP connections can be frozen (qd={:?})", qd);
+            error!("freeze_queue(): {}", cause);
+            return Err(Fail::new(libc::ENOTSUP, &cause));
+        }
+        let blob: Vec<u8> = self.get_shared_queue(&qd)?.freeze()?;
+        self.runtime.free_queue::<SharedNetworkQueue<T>>(&qd)?;
+        Ok(blob)
+    }
+
+    /// Thaws a TCP connection that another instance of the network stack froze into [blob].
+    pub fn thaw_queue(&mut self, blob: &[u8]) -> Result<QDesc, Fail> {
+        trace!("thaw_queue() len={:?}", blob.len());
+        let queue: SharedNetworkQueue<T> = SharedNetworkQueue::thaw(blob, &mut self.transport)?;
+        Ok(self.runtime.alloc_queue(queue))
+    }
+
+    /// Synchronous cross-queue code to start accepting a connection. This function schedules the asynchronous
+    /// coroutine and performs any necessary synchronous, multi-queue operations at the libOS-level before beginning
+    /// the accept. The accept fails with ETIMEDOUT if it does not complete within [timeout].
+    pub fn accept(&mut self, qd: QDesc, timeout: Option<Duration>) -> Result<QToken, Fail> {
+        trace!("accept(): qd={:?}, timeout={:?}", qd, timeout);
+
+        if self.runtime.get_queue_type(&qd)? == QType::QuicSocket {
+            let coroutine = Box::pin(self.clone().quic_accept_coroutine(qd).fuse());
+            return self.runtime.clone().insert_io_coroutine_with_timeout(
+                "NetworkLibOS::quic_accept",
+                qd,
+                timeout,
+                coroutine,
+            );
+        }
+        #[cfg(feature = "librdma")]
+        if self.runtime.get_queue_type(&qd)? == QType::RdmaQueue {
+            let coroutine = Box::pin(self.clone().rdma_accept_coroutine(qd).fuse());
+            return self.runtime.clone().insert_io_coroutine_with_timeout(
+                "NetworkLibOS::rdma_accept",
+                qd,
+                timeout,
+                coroutine,
+            );
+        }
+
+        let mut queue: SharedNetworkQueue<T> = self.get_shared_queue(&qd)?;
+        let coroutine_constructor = || -> Result<QToken, Fail> {
+            let coroutine = Box::pin(self.clone().accept_coroutine(qd).fuse());
+            self.runtime
+                .clone()
+                .insert_io_coroutine_with_timeout("NetworkLibOS::accept", qd, timeout, coroutine)
+        };
+
+        queue.accept(coroutine_constructor)
+    }
+
+    /// Asynchronous cross-queue code for accepting a connection. This function returns a coroutine that runs
+    /// asynchronously to accept a connection and performs any necessary multi-queue operations at the libOS-level after
+    /// the accept succeeds or fails.
+    async fn accept_coroutine(mut self, qd: QDesc) -> (QDesc, OperationResult) {
+        // Grab the queue, make sure it hasn't been closed in the meantime.
+        // This will bump the Rc refcount so the coroutine can have it's own reference to the shared queue data
+        // structure and the SharedNetworkQueue will not be freed until this coroutine finishes.
+        let mut queue: SharedNetworkQueue<T> = match self.get_shared_queue(&qd) {
+            Ok(queue) => queue.clone(),
+            Err(e) => return (qd, OperationResult::Failed(e)),
+        };
+        // Wait for the accept operation to complete.
+        match queue.accept_coroutine().await {
+            Ok(new_queue) => {
+                // TODO: Do we need to add this to the socket id to queue descriptor table?
+                // It is safe to call except here because the new queue is connected and it should be connected to a
+                // remote address.
+                let addr: SocketAddr =
+                    expect_some!(new_queue.remote(), "An accepted socket must have a remote address");
+                let new_qd: QDesc = self.runtime.alloc_queue(new_queue);
+                // FIXME: add IPv6 support; https://github.com/microsoft/demikernel/issues/935
+                (
+                    qd,
+                    OperationResult::Accept((new_qd, expect_ok!(unwrap_socketaddr(addr), "we only support IPv4"))),
+                )
+            },
+            Err(e) => {
+                warn!("accept() listening_qd={:?}: {:?}", qd, &e);
+                (qd, OperationResult::Failed(e))
+            },
+        }
+    }
+
+    /// Synchronous code to establish a connection to a remote endpoint. This function schedules the asynchronous
+    /// coroutine and performs any necessary synchronous, multi-queue operations at the libOS-level before beginning
+    /// the connect. The connect fails with ETIMEDOUT if it does not complete within [timeout].
+    pub fn connect(&mut self, qd: QDesc, remote: SocketAddr, timeout: Option<Duration>) -> Result<QToken, Fail> {
+        trace!("connect() qd={:?}, remote={:?}, timeout={:?}", qd, remote, timeout);
+
+        if self.runtime.get_queue_type(&qd)? == QType::QuicSocket {
+            self.get_quic_queue(&qd)?.connect(remote)?;
+            let coroutine = Box::pin(self.clone().quic_connect_coroutine(qd).fuse());
+            return self.runtime.clone().insert_io_coroutine_with_timeout(
+                "NetworkLibOS::quic_connect",
+                qd,
+                timeout,
+                coroutine,
+            );
+        }
+        #[cfg(feature = "librdma")]
+        if self.runtime.get_queue_type(&qd)? == QType::RdmaQueue {
+            let remote: SocketAddrV4 = unwrap_socketaddr(remote)?;
+            let coroutine = Box::pin(self.clone().rdma_connect_coroutine(qd, remote).fuse());
+            return self.runtime.clone().insert_io_coroutine_with_timeout(
+                "NetworkLibOS::rdma_connect",
+                qd,
+                timeout,
+                coroutine,
+            );
+        }
+
+        // FIXME: add IPv6 support; https://github.com/microsoft/demikernel/issues/935
+        let mut queue: SharedNetworkQueue<T> = self.get_shared_queue(&qd)?;
+        let coroutine_constructor = || -> Result<QToken, Fail> {
+            let coroutine = Box::pin(self.clone().connect_coroutine(qd, remote).fuse());
+            self.runtime
+                .clone()
+                .insert_io_coroutine_with_timeout("NetworkLibOS::connect", qd, timeout, coroutine)
+        };
+
+        queue.connect(coroutine_constructor)
+    }
+
+    /// Asynchronous code to establish a connection to a remote endpoint. This function returns a coroutine that runs
+    /// asynchronously to connect a queue and performs any necessary multi-queue operations at the libOS-level after
+    /// the connect succeeds or fails.
+    async fn connect_coroutine(self, qd: QDesc, remote: SocketAddr) -> (QDesc, OperationResult) {
+        // Grab the queue, make sure it hasn't been closed in the meantime.
+        // This will bump the Rc refcount so the coroutine can have it's own reference to the shared queue data
+        // structure and the SharedNetworkQueue will not be freed until this coroutine finishes.
+        let mut queue: SharedNetworkQueue<T> = match self.get_shared_queue(&qd) {
+            Ok(queue) => queue.clone(),
+            Err(e) => return (qd, OperationResult::Failed(e)),
+        };
+        // Wait for connect operation to complete.
+        match queue.connect_coroutine(remote).await {
+            Ok(()) => {
+                // TODO: Do we need to add this to socket id to queue descriptor table?
+                (qd, OperationResult::Connect)
+            },
+            Err(e) => {
+                warn!("connect() failed (qd={:?}, error={:?})", qd, e.cause);
+                (qd, OperationResult::Failed(e))
+            },
+        }
+    }
+
+    /// Connects the socket that is associated with a SharedNetworkQueue to the socket that is bound to [name].
+    pub fn connect_name(&mut self, qd: QDesc, name: &str, timeout: Option<Duration>) -> Result<QToken, Fail> {
+        trace!("connect_name() qd={:?}, name={:?}, timeout={:?}", qd, name, timeout);
+
+        let mut queue: SharedNetworkQueue<T> = self.get_shared_queue(&qd)?;
+        let coroutine_constructor = || -> Result<QToken, Fail> {
+            let coroutine = Box::pin(self.clone().connect_name_coroutine(qd, name.to_string()).fuse());
+            self.runtime
+                .clone()
+                .insert_io_coroutine_with_timeout("NetworkLibOS::connect_name", qd, timeout, coroutine)
+        };
+
+        queue.connect(coroutine_constructor)
+    }
+
+    /// Asynchronous code to connect to the socket that is bound to [name].
+    async fn connect_name_coroutine(self, qd: QDesc, name: String) -> (QDesc, OperationResult) {
+        let mut queue: SharedNetworkQueue<T> = match self.get_shared_queue(&qd) {
+            Ok(queue) => queue.clone(),
+            Err(e) => return (qd, OperationResult::Failed(e)),
+        };
+        match queue.connect_name_coroutine(name).await {
+            Ok(()) => (qd, OperationResult::Connect),
+            Err(e) => {
+                warn!("connect_name() failed (qd={:?}, error={:?})", qd, e.cause);
+                (qd, OperationResult::Failed(e))
+            },
+        }
+    }
+
+    /// Starts a TLS session as a client on the connected TCP socket [qd]. Data that is pushed to or popped from the
+    /// returned queue descriptor is transparently encrypted or decrypted, and [qd] is no longer valid.
+    pub fn tls_connect(&mut self, qd: QDesc, config: TlsConfig) -> Result<QDesc, Fail> {
+        trace!("tls_connect() qd={:?}", qd);
+        self.start_tls(qd, TlsRole::Client, config)
+    }
+
+    /// Starts a TLS session as a server on the accepted TCP socket [qd]. Data that is pushed to or popped from the
+    /// returned queue descriptor is transparently encrypted or decrypted, and [qd] is no longer valid.
+    pub fn tls_accept(&mut self, qd: QDesc, config: TlsConfig) -> Result<QDesc, Fail> {
+        trace!("tls_accept() qd={:?}", qd);
+        self.start_tls(qd, TlsRole::Server, config)
+    }
+
+    /// Starts a TLS session of [role] on [qd] and moves the queue to a new queue descriptor.
+    fn start_tls(&mut self, qd: QDesc, role: TlsRole, config: TlsConfig) -> Result<QDesc, Fail> {
+        let mut queue: SharedNetworkQueue<T> = self.get_shared_queue(&qd)?;
+        queue.start_tls(role, config)?;
+        Ok(self.move_queue(qd, queue))
+    }
+
+    /// Frames the data of the connected TCP socket [qd] into HTTP/1.1 messages, as a client. Each pop on the returned
+    /// queue descriptor returns a whole response and each push must carry a whole request, and [qd] is no longer valid.
+    pub fn http_connect(&mut self, qd: QDesc) -> Result<QDesc, Fail> {
+        trace!("http_connect() qd={:?}", qd);
+        self.frame(qd, Box::new(HttpCodec::new(HttpRole::Client)))
+    }
+
+    /// Frames the data of the accepted TCP socket [qd] into HTTP/1.1 messages, as a server. Each pop on the returned
+    /// queue descriptor returns a whole request and each push must carry a whole response, and [qd] is no longer valid.
+    pub fn http_accept(&mut self, qd: QDesc) -> Result<QDesc, Fail> {
+        trace!("http_accept() qd={:?}", qd);
+        self.frame(qd, Box::new(HttpCodec::new(HttpRole::Server)))
+    }
+
+    /// Frames the data of the connected TCP socket [qd] with [codec]. Each pop on the returned queue descriptor returns
+    /// a whole frame and each push must carry exactly one, and [qd] is no longer valid.
+    pub fn frame(&mut self, qd: QDesc, codec: Box<dyn FrameCodec>) -> Result<QDesc, Fail> {
+        trace!("frame() qd={:?}", qd);
+        let mut queue: SharedNetworkQueue<T> = self.get_shared_queue(&qd)?;
+        queue.start_framing(codec)?;
+        Ok(self.move_queue(qd, queue))
+    }
+
+    /// Moves [queue] from [qd] to a new queue descriptor.
+    fn move_queue(&mut self, qd: QDesc, queue: SharedNetworkQueue<T>) -> QDesc {
+        // Allocate the new queue descriptor before releasing the old one, so that they differ.
+        let local: Option<SocketAddr> = queue.local();
+        let new_qd: QDesc = self.runtime.alloc_queue(queue);
+        expect_ok!(
+            self.runtime.free_queue::<SharedNetworkQueue<T>>(&qd),
+            "queue should exist"
+        );
+        // If the queue was bound, update the socket id to queue descriptor table.
+        if let Some(local) = local {
+            // FIXME: add IPv6 support; https://github.com/microsoft/demikernel/issues/935
+            let local: SocketAddrV4 = expect_ok!(unwrap_socketaddr(local), "we only support IPv4");
+            self.runtime.insert_socket_id_to_qd(SocketId::Passive(local), new_qd);
+        }
+        new_qd
+    }
+
+    /// Runs QUIC with [config] over the bound UDP socket [qd]. The returned queue descriptor connects to or listens
+    /// for QUIC peers with the regular calls, and [qd] is no longer valid.
+    pub fn quic_socket(&mut self, qd: QDesc, config: QuicConfig) -> Result<QDesc, Fail> {
+        trace!("quic_socket() qd={:?}", qd);
+        let udp: SharedNetworkQueue<T> = self.get_shared_queue(&qd)?;
+        let queue: SharedQuicQueue<T> = SharedQuicQueue::new(self.runtime.clone(), udp, config)?;
+        // Allocate the new queue descriptor before releasing the old one, so that they differ.
+        let new_qd: QDesc = self.runtime.alloc_queue(queue.clone());
+        expect_ok!(
+            self.runtime.free_queue::<SharedNetworkQueue<T>>(&qd),
+            "queue should exist"
+        );
+        // FIXME: add IPv6 support; https://github.com/microsoft/demikernel/issues/935
+        let local: SocketAddrV4 = expect_ok!(
+            unwrap_socketaddr(expect_some!(queue.local(), "QUIC runs over bound sockets")),
+            "we only support IPv4"
+        );
+        self.runtime.insert_socket_id_to_qd(SocketId::Passive(local), new_qd);
+        Ok(new_qd)
+    }
+
+    /// Runs a DTLS client with [config] over the bound UDP socket [qd], which starts an association with [remote]. The
+    /// returned queue descriptor pushes and pops datagrams of the association, and [qd] is no longer valid.
+    pub fn dtls_connect(&mut self, qd: QDesc, remote: SocketAddr, config: TlsConfig) -> Result<QDesc, Fail> {
+        trace!("dtls_connect() qd={:?}, remote={:?}", qd, remote);
+        let udp: SharedNetworkQueue<T> = self.get_shared_queue(&qd)?;
+        let queue: SharedDtlsQueue<T> = SharedDtlsQueue::new_client(self.runtime.clone(), udp, remote, config)?;
+        Ok(self.replace_with_dtls_queue(qd, queue))
+    }
+
+    /// Runs a DTLS server with [config] over the bound UDP socket [qd], which waits for the first client that proves
+    /// its address. The returned queue descriptor pushes and pops datagrams of the association, and [qd] is no longer
+    /// valid.
+    pub fn dtls_accept(&mut self, qd: QDesc, config: TlsConfig) -> Result<QDesc, Fail> {
+        trace!("dtls_accept() qd={:?}", qd);
+        let udp: SharedNetworkQueue<T> = self.get_shared_queue(&qd)?;
+        let queue: SharedDtlsQueue<T> = SharedDtlsQueue::new_server(self.runtime.clone(), udp, config)?;
+        Ok(self.replace_with_dtls_queue(qd, queue))
+    }
+
+    /// Replaces the UDP socket [qd] with the DTLS socket [queue] that runs over it.
+    fn replace_with_dtls_queue(&mut self, qd: QDesc, queue: SharedDtlsQueue<T>) -> QDesc {
+        // Allocate the new queue descriptor before releasing the old one, so that they differ.
+        let new_qd: QDesc = self.runtime.alloc_queue(queue.clone());
+        expect_ok!(
+            self.runtime.free_queue::<SharedNetworkQueue<T>>(&qd),
+            "queue should exist"
+        );
+        // FIXME: add IPv6 support; https://github.com/microsoft/demikernel/issues/935
+        let local: SocketAddrV4 = expect_ok!(
+            unwrap_socketaddr(expect_some!(queue.local(), "DTLS runs over bound sockets")),
+            "we only support IPv4"
+        );
+        self.runtime.insert_socket_id_to_qd(SocketId::Passive(local), new_qd);
+        new_qd
+    }
+
+    /// Starts a PTP client with [config], which steers the clock that [ptp_clock] returns.
+    pub fn ptp_start(&mut self, config: PtpConfig) -> Result<(), Fail> {
+        trace!("ptp_start() local={:?}, master={:?}", config.local(), config.master());
+        if self.ptp.is_some() {
+            let cause: &str = "PTP client is already running";
+            error!("ptp_start(): {}", cause);
+            return Err(Fail::new(libc::EALREADY, cause));
+        }
+
+        let event_qd: QDesc = self.ptp_socket(config.local(), PTP_EVENT_PORT)?;
+        let general_qd: QDesc = match self.ptp_socket(config.local(), PTP_GENERAL_PORT) {
+            Ok(qd) => qd,
+            Err(e) => {
+                self.ptp_release_socket(event_qd);
+                return Err(e);
+            },
+        };
+        let mut event: SharedNetworkQueue<T> = self.get_shared_queue(&event_qd)?;
+        let general: SharedNetworkQueue<T> = self.get_shared_queue(&general_qd)?;
+        // Stamp Delay_Req messages in the network stack where the transport supports it, which is closer to the wire.
+        let tx_timestamps: bool = event
+            .set_socket_option(SocketOption::SO_TIMESTAMPING(SOF_TIMESTAMPING_TX_SOFTWARE))
+            .is_ok();
+        match SharedPtpDriver::new(
+            self.runtime.clone(),
+            (event_qd, event),
+            (general_qd, general),
+            tx_timestamps,
+            config,
+        ) {
+            Ok(driver) => {
+                self.ptp = Some(driver);
+                Ok(())
+            },
+            Err(e) => {
+                self.ptp_release_socket(event_qd);
+                self.ptp_release_socket(general_qd);
+                Err(e)
+            },
+        }
+    }
+
+    /// Stops the PTP client and closes its sockets.
+    pub fn ptp_stop(&mut self) -> Result<(), Fail> {
+        trace!("ptp_stop()");
+        let mut driver: SharedPtpDriver<T> = match self.ptp.take() {
+            Some(driver) => driver,
+            None => {
+                let cause: &str = "PTP client is not running";
+                error!("ptp_stop(): {}", cause);
+                return Err(Fail::new(libc::ENOENT, cause));
+            },
+        };
+        driver.stop();
+        let (event_qd, general_qd): (QDesc, QDesc) = driver.qds();
+        self.ptp_release_socket(event_qd);
+        self.ptp_release_socket(general_qd);
+        Ok(())
+    }
+
+    /// Gets the clock that the PTP client steers.
+    pub fn ptp_clock(&self) -> Result<SharedPtpClock, Fail> {
+        match self.ptp.as_ref() {
+            Some(driver) => Ok(driver.clock()),
+            None => {
+                let cause: &str = "PTP client is not running";
+                warn!("ptp_clock(): {}", cause);
+                Err(Fail::new(libc::ENOENT, cause))
+            },
+        }
+    }
+
+    /// Opens a UDP socket of the PTP client that is bound to [port] of [local].
+    fn ptp_socket(&mut self, local: Ipv4Addr, port: u16) -> Result<QDesc, Fail> {
+        let qd: QDesc = self.socket(Domain::IPV4, Type::DGRAM, Protocol::UDP)?;
+        if let Err(e) = self.bind(qd, SocketAddr::V4(SocketAddrV4::new(local, port))) {
+            self.ptp_release_socket(qd);
+            return Err(e);
+        }
+        Ok(qd)
+    }
+
+    /// Closes the UDP socket [qd] of the PTP client and releases its queue descriptor.
+    fn ptp_release_socket(&mut self, qd: QDesc) {
+        let mut queue: SharedNetworkQueue<T> = expect_ok!(self.get_shared_queue(&qd), "queue should exist");
+        if let Err(e) = queue.hard_close() {
+            warn!(
+                "ptp_release_socket(): failed to close socket (qd={:?}, error={:?})",
+                qd, e
+            );
+        }
+        if let Some(local) = queue.local() {
+            // FIXME: add IPv6 support; https://github.com/microsoft/demikernel/issues/935
+            self.runtime.remove_socket_id_to_qd(&SocketId::Passive(expect_ok!(
+                unwrap_socketaddr(local),
+                "we only support IPv4"
+            )));
+        }
+        expect_ok!(
+            self.runtime.free_queue::<SharedNetworkQueue<T>>(&qd),
+            "queue should exist"
+        );
+    }
+
+    /// Opens a stream on the QUIC connection [qd] and returns its queue descriptor.
+    pub fn quic_open_stream(&mut self, qd: QDesc) -> Result<QDesc, Fail> {
+        trace!("quic_open_stream() qd={:?}", qd);
+        let stream: SharedQuicStreamQueue<T> = self.get_quic_queue(&qd)?.open_stream()?;
+        Ok(self.runtime.alloc_queue(stream))
+    }
+
+    /// Asynchronous code to accept on the QUIC socket [qd]. A listening socket accepts connections, while a connection
+    /// accepts the streams that the peer opens.
+    async fn quic_accept_coroutine(mut self, qd: QDesc) -> (QDesc, OperationResult) {
+        let mut queue: SharedQuicQueue<T> = match self.get_quic_queue(&qd) {
+            Ok(queue) => queue,
+            Err(e) => return (qd, OperationResult::Failed(e)),
+        };
+        let result: Result<(QDesc, SocketAddr), Fail> = if queue.is_connection() {
+            match queue.accept_stream_coroutine().await {
+                Ok(stream) => {
+                    let remote: SocketAddr = stream.remote();
+                    Ok((self.runtime.alloc_queue(stream), remote))
+                },
+                Err(e) => Err(e),
+            }
+        } else {
+            match queue.accept_connection_coroutine().await {
+                Ok(connection) => {
+                    let remote: SocketAddr =
+                        expect_some!(connection.remote(), "an accepted connection must have a remote address");
+                    Ok((self.runtime.alloc_queue(connection), remote))
+                },
+                Err(e) => Err(e),
+            }
+        };
+        match result {
+            // FIXME: add IPv6 support; https://github.com/microsoft/demikernel/issues/935
+            Ok((new_qd, remote)) => (
+                qd,
+                OperationResult::Accept((new_qd, expect_ok!(unwrap_socketaddr(remote), "we only support IPv4"))),
+            ),
+            Err(e) => {
+                warn!("quic_accept() qd={:?}: {:?}", qd, &e);
+                (qd, OperationResult::Failed(e))
+            },
+        }
+    }
+
+    /// Asynchronous code to wait for the handshake of the QUIC connection [qd].
+    async fn quic_connect_coroutine(self, qd: QDesc) -> (QDesc, OperationResult) {
+        let mut queue: SharedQuicQueue<T> = match self.get_quic_queue(&qd) {
+            Ok(queue) => queue,
+            Err(e) => return (qd, OperationResult::Failed(e)),
+        };
+        match queue.connect_coroutine().await {
+            Ok(()) => (qd, OperationResult::Connect),
+            Err(e) => {
+                warn!("quic_connect() failed (qd={:?}, error={:?})", qd, e.cause);
+                (qd, OperationResult::Failed(e))
+            },
+        }
+    }
+
+    /// Asynchronous code to close the QUIC socket [qd].
+    async fn quic_close_coroutine(mut self, qd: QDesc) -> (QDesc, OperationResult) {
+        let mut queue: SharedQuicQueue<T> = match self.get_quic_queue(&qd) {
+            Ok(queue) => queue,
+            Err(e) => return (qd, OperationResult::Failed(e)),
+        };
+        match queue.close_coroutine().await {
+            Ok(released) => {
+                // If this was the last queue of the UDP socket, remove the socket from the socket id to queue
+                // descriptor table.
+                if let Some(local) = released {
+                    // FIXME: add IPv6 support; https://github.com/microsoft/demikernel/issues/935
+                    self.runtime.remove_socket_id_to_qd(&SocketId::Passive(expect_ok!(
+                        unwrap_socketaddr(local),
+                        "we only support IPv4"
+                    )));
+                    if SharedDemiRuntime::is_private_ephemeral_port(local.port()) {
+                        if let Err(e) = self.runtime.free_ephemeral_port(local.port()) {
+                            warn!("quic_close(): could not free ephemeral port: {:?}", e);
+                        }
+                    }
+                }
+                expect_ok!(self.runtime.free_queue::<SharedQuicQueue<T>>(&qd), "queue should exist");
+                (qd, OperationResult::Close)
+            },
+            Err(e) => {
+                warn!("quic_close() qd={:?}: {:?}", qd, &e);
+                (qd, OperationResult::Failed(e))
+            },
+        }
+    }
+
+    /// Asynchronous code to release the QUIC stream [qd], which was closed.
+    async fn quic_stream_close_coroutine(mut self, qd: QDesc) -> (QDesc, OperationResult) {
+        match self.runtime.free_queue::<SharedQuicStreamQueue<T>>(&qd) {
+            Ok(_) => (qd, OperationResult::Close),
+            Err(e) => (qd, OperationResult::Failed(e)),
+        }
+    }
+
+    /// Asynchronous code to push [buf] to the QUIC stream [qd].
+    async fn quic_push_coroutine(self, qd: QDesc, mut buf: DemiBuffer) -> (QDesc, OperationResult) {
+        let mut stream: SharedQuicStreamQueue<T> = match self.get_quic_stream(&qd) {
+            Ok(stream) => stream,
+            Err(e) => return (qd, OperationResult::Failed(e)),
+        };
+        let nbytes: usize = buf.len();
+        match stream.push(&mut buf) {
+            Ok(()) => (qd, OperationResult::Push(nbytes)),
+            Err(e) => {
+                warn!("quic_push() qd={:?}: {:?}", qd, &e);
+                (qd, OperationResult::Failed(e))
+            },
+        }
+    }
+
+    /// Asynchronous code to pop up to [size] bytes from the QUIC stream [qd].
+    async fn quic_pop_coroutine(self, qd: QDesc, size: Option<usize>) -> (QDesc, OperationResult) {
+        let mut stream: SharedQuicStreamQueue<T> = match self.get_quic_stream(&qd) {
+            Ok(stream) => stream,
+            Err(e) => return (qd, OperationResult::Failed(e)),
+        };
+        match stream.pop_coroutine(size.unwrap_or(limits::RECVBUF_SIZE_MAX)).await {
+            Ok(buf) => (qd, OperationResult::Pop(None, buf, AncillaryData::default())),
+            Err(e) => {
+                warn!("quic_pop() qd={:?}: {:?}", qd, &e);
+                (qd, OperationResult::Failed(e))
+            },
+        }
+    }
+
+    /// Asynchronous code to close the DTLS socket [qd].
+    async fn dtls_close_coroutine(mut self, qd: QDesc) -> (QDesc, OperationResult) {
+        let mut queue: SharedDtlsQueue<T> = match self.get_dtls_queue(&qd) {
+            Ok(queue) => queue,
+            Err(e) => return (qd, OperationResult::Failed(e)),
+        };
+        match queue.close_coroutine().await {
+            Ok(()) => {
+                if let Some(local) = queue.local() {
+                    // FIXME: add IPv6 support; https://github.com/microsoft/demikernel/issues/935
+                    self.runtime.remove_socket_id_to_qd(&SocketId::Passive(expect_ok!(
+                        unwrap_socketaddr(local),
+                        "we only support IPv4"
+                    )));
+                    if SharedDemiRuntime::is_private_ephemeral_port(local.port()) {
+                        if let Err(e) = self.runtime.free_ephemeral_port(local.port()) {
+                            warn!("dtls_close(): could not free ephemeral port: {:?}", e);
+                        }
+                    }
+                }
+                expect_ok!(self.runtime.free_queue::<SharedDtlsQueue<T>>(&qd), "queue should exist");
+                (qd, OperationResult::Close)
+            },
+            Err(e) => {
+                warn!("dtls_close() qd={:?}: {:?}", qd, &e);
+                (qd, OperationResult::Failed(e))
+            },
+        }
+    }
+
+    /// Asynchronous code to push [buf] to the peer of the DTLS socket [qd].
+    async fn dtls_push_coroutine(self, qd: QDesc, mut buf: DemiBuffer) -> (QDesc, OperationResult) {
+        let mut queue: SharedDtlsQueue<T> = match self.get_dtls_queue(&qd) {
+            Ok(queue) => queue,
+            Err(e) => return (qd, OperationResult::Failed(e)),
+        };
+        let nbytes: usize = buf.len();
+        match queue.push_coroutine(&mut buf).await {
+            Ok(()) => (qd, OperationResult::Push(nbytes)),
+            Err(e) => {
+                warn!("dtls_push() qd={:?}: {:?}", qd, &e);
+                (qd, OperationResult::Failed(e))
+            },
+        }
+    }
+
+    /// Asynchronous code to pop up to [size] bytes of the next datagram from the peer of the DTLS socket [qd].
+    async fn dtls_pop_coroutine(self, qd: QDesc, size: Option<usize>) -> (QDesc, OperationResult) {
+        let mut queue: SharedDtlsQueue<T> = match self.get_dtls_queue(&qd) {
+            Ok(queue) => queue,
+            Err(e) => return (qd, OperationResult::Failed(e)),
+        };
+        match queue.pop_coroutine(size.unwrap_or(limits::RECVBUF_SIZE_MAX)).await {
+            // FIXME: add IPv6 support; https://github.com/microsoft/demikernel/issues/935
+            Ok((Some(addr), buf)) => (
+                qd,
+                OperationResult::Pop(
+                    Some(expect_ok!(unwrap_socketaddr(addr), "we only support IPv4")),
+                    buf,
+                    AncillaryData::default(),
+                ),
+            ),
+            Ok((None, buf)) => (qd, OperationResult::Pop(None, buf, AncillaryData::default())),
+            Err(e) => {
+                warn!("dtls_pop() qd={:?}: {:?}", qd, &e);
+                (qd, OperationResult::Failed(e))
+            },
+        }
+    }
+
+    /// Opens the file at [path] with the POSIX [flags] and returns a queue that reads and writes it asynchronously.
+    pub fn open_file(&mut self, path: &str, flags: libc::c_int) -> Result<QDesc, Fail> {
+        trace!("open_file() path={:?}, flags={:#x}", path, flags);
+        let engine: SharedFileEngine = match self.files {
+            Some(ref engine) => engine.clone(),
+            None => {
+                let engine: SharedFileEngine = SharedFileEngine::new(&mut self.runtime)?;
+                self.files = Some(engine.clone());
+                engine
+            },
+        };
+        let queue: SharedFileQueue = SharedFileQueue::open(engine, path, flags)?;
+        Ok(self.runtime.alloc_queue(queue))
+    }
+
+    /// Asynchronous code to close the file queue [qd].
+    async fn file_close_coroutine(mut self, qd: QDesc) -> (QDesc, OperationResult) {
+        match self.runtime.free_queue::<SharedFileQueue>(&qd) {
+            Ok(_) => (qd, OperationResult::Close),
+            Err(e) => (qd, OperationResult::Failed(e)),
+        }
+    }
+
+    /// Asynchronous code to write [buf] to the file queue [qd].
+    async fn file_push_coroutine(self, qd: QDesc, buf: DemiBuffer) -> (QDesc, OperationResult) {
+        let mut queue: SharedFileQueue = match self.get_file_queue(&qd) {
+            Ok(queue) => queue,
+            Err(e) => return (qd, OperationResult::Failed(e)),
+        };
+        let nbytes: usize = buf.len();
+        match queue.push_coroutine(buf).await {
+            Ok(()) => (qd, OperationResult::Push(nbytes)),
+            Err(e) => {
+                warn!("file_push() qd={:?}: {:?}", qd, &e);
+                (qd, OperationResult::Failed(e))
+            },
+        }
+    }
+
+    /// Asynchronous code to read up to [size] bytes from the file queue [qd].
+    async fn file_pop_coroutine(self, qd: QDesc, size: Option<usize>) -> (QDesc, OperationResult) {
+        let mut queue: SharedFileQueue = match self.get_file_queue(&qd) {
+            Ok(queue) => queue,
+            Err(e) => return (qd, OperationResult::Failed(e)),
+        };
+        match queue.pop_coroutine(size).await {
+            Ok(buf) => (qd, OperationResult::Pop(None, buf, AncillaryData::default())),
+            Err(e) => {
+                warn!("file_pop() qd={:?}: {:?}", qd, &e);
+                (qd, OperationResult::Failed(e))
+            },
+        }
+    }
+
+    /// Opens namespace [nsid] of the NVMe controller at the SPDK transport ID [trid] and returns a queue that reads and
+    /// writes it asynchronously.
+    #[cfg(feature = "libspdk")]
+    pub fn open_block(&mut self, trid: &str, nsid: u32) -> Result<QDesc, Fail> {
+        trace!("open_block() trid={:?}, nsid={:?}", trid, nsid);
+        let mut driver: SharedNvmeDriver = match self.blocks {
+            Some(ref driver) => driver.clone(),
+            None => {
+                let driver: SharedNvmeDriver = SharedNvmeDriver::new()?;
+                self.blocks = Some(driver.clone());
+                driver
+            },
+        };
+        let queue: SharedBlockQueue = SharedBlockQueue::open(&mut driver, trid, nsid)?;
+        Ok(self.runtime.alloc_queue(queue))
+    }
+
+    /// Asynchronous code to close the block queue [qd].
+    #[cfg(feature = "libspdk")]
+    async fn block_close_coroutine(mut self, qd: QDesc) -> (QDesc, OperationResult) {
+        match self.runtime.free_queue::<SharedBlockQueue>(&qd) {
+            Ok(_) => (qd, OperationResult::Close),
+            Err(e) => (qd, OperationResult::Failed(e)),
+        }
+    }
+
+    /// Asynchronous code to write [buf] to the block queue [qd].
+    #[cfg(feature = "libspdk")]
+    async fn block_push_coroutine(self, qd: QDesc, buf: DemiBuffer) -> (QDesc, OperationResult) {
+        let mut queue: SharedBlockQueue = match self.get_block_queue(&qd) {
+            Ok(queue) => queue,
+            Err(e) => return (qd, OperationResult::Failed(e)),
+        };
+        let nbytes: usize = buf.len();
+        match queue.push_coroutine(buf).await {
+            Ok(()) => (qd, OperationResult::Push(nbytes)),
+            Err(e) => {
+                warn!("block_push() qd={:?}: {:?}", qd, &e);
+                (qd, OperationResult::Failed(e))
+            },
+        }
+    }
+
+    /// Asynchronous code to read [size] bytes from the block queue [qd].
+    #[cfg(feature = "libspdk")]
+    async fn block_pop_coroutine(self, qd: QDesc, size: Option<usize>) -> (QDesc, OperationResult) {
+        let mut queue: SharedBlockQueue = match self.get_block_queue(&qd) {
+            Ok(queue) => queue,
+            Err(e) => return (qd, OperationResult::Failed(e)),
+        };
+        match queue.pop_coroutine(size).await {
+            Ok(buf) => (qd, OperationResult::Pop(None, buf, AncillaryData::default())),
+            Err(e) => {
+                warn!("block_pop() qd={:?}: {:?}", qd, &e);
+                (qd, OperationResult::Failed(e))
+            },
+        }
+    }
+
+    /// Creates a queue that runs a reliable connection over RDMA. It is bound, listens, accepts and connects like a TCP
+    /// socket.
+    #[cfg(feature = "librdma")]
+    pub fn rdma_socket(&mut self) -> Result<QDesc, Fail> {
+        trace!("rdma_socket()");
+        let driver: SharedRdmaDriver = self.rdma.get_or_insert_with(SharedRdmaDriver::new).clone();
+        let queue: SharedRdmaQueue = SharedRdmaQueue::new(driver)?;
+        Ok(self.runtime.alloc_queue(queue))
+    }
+
+    /// Writes the data of [sga] to the memory of the peer of the RDMA queue [qd] that [region] describes.
+    #[cfg(feature = "librdma")]
+    pub fn rdma_write(
+        &mut self,
+        qd: QDesc,
+        sga: &demi_sgarray_t,
+        region: RdmaRegion,
+        timeout: Option<Duration>,
+    ) -> Result<QToken, Fail> {
+        trace!("rdma_write() qd={:?}, region={:?}", qd, region);
+        let buf: DemiBuffer = self.transport.clone_sgarray(sga)?;
+        self.get_rdma_queue(&qd)?;
+        let coroutine = Box::pin(self.clone().rdma_write_coroutine(qd, buf, region).fuse());
+        self.runtime
+            .clone()
+            .insert_io_coroutine_with_timeout("NetworkLibOS::rdma_write", qd, timeout, coroutine)
+    }
+
+    /// Reads the memory of the peer of the RDMA queue [qd] that [region] describes.
+    #[cfg(feature = "librdma")]
+    pub fn rdma_read(&mut self, qd: QDesc, region: RdmaRegion, timeout: Option<Duration>) -> Result<QToken, Fail> {
+        trace!("rdma_read() qd={:?}, region={:?}", qd, region);
+        self.get_rdma_queue(&qd)?;
+        let coroutine = Box::pin(self.clone().rdma_read_coroutine(qd, region).fuse());
+        self.runtime
+            .clone()
+            .insert_io_coroutine_with_timeout("NetworkLibOS::rdma_read", qd, timeout, coroutine)
+    }
+
+    /// Lets the peer of the connected RDMA queue [qd] read and write the memory of [sga] with one-sided verbs.
+    #[cfg(feature = "librdma")]
+    pub fn rdma_expose(&mut self, qd: QDesc, sga: &demi_sgarray_t) -> Result<RdmaRegion, Fail> {
+        trace!("rdma_expose() qd={:?}", qd);
+        let buf: DemiBuffer = self.transport.clone_sgarray(sga)?;
+        self.get_rdma_queue(&qd)?.expose(buf)
+    }
+
+    /// Withdraws the region with remote key [rkey] from the peer of the RDMA queue [qd].
+    #[cfg(feature = "librdma")]
+    pub fn rdma_withdraw(&mut self, qd: QDesc, rkey: u32) -> Result<(), Fail> {
+        trace!("rdma_withdraw() qd={:?}, rkey={:?}", qd, rkey);
+        self.get_rdma_queue(&qd)?.withdraw(rkey)
+    }
+
+    /// Asynchronous code to accept a connection on the RDMA queue [qd].
+    #[cfg(feature = "librdma")]
+    async fn rdma_accept_coroutine(mut self, qd: QDesc) -> (QDesc, OperationResult) {
+        let mut queue: SharedRdmaQueue = match self.get_rdma_queue(&qd) {
+            Ok(queue) => queue,
+            Err(e) => return (qd, OperationResult::Failed(e)),
+        };
+        match queue.accept().await {
+            Ok((new_queue, remote)) => {
+                let new_qd: QDesc = self.runtime.alloc_queue(new_queue);
+                (qd, OperationResult::Accept((new_qd, remote)))
+            },
+            Err(e) => {
+                warn!("rdma_accept() qd={:?}: {:?}", qd, &e);
+                (qd, OperationResult::Failed(e))
+            },
+        }
+    }
+
+    /// Asynchronous code to connect the RDMA queue [qd] to [remote].
+    #[cfg(feature = "librdma")]
+    async fn rdma_connect_coroutine(self, qd: QDesc, remote: SocketAddrV4) -> (QDesc, OperationResult) {
+        let mut queue: SharedRdmaQueue = match self.get_rdma_queue(&qd) {
+            Ok(queue) => queue,
+            Err(e) => return (qd, OperationResult::Failed(e)),
+        };
+        match queue.connect(remote).await {
+            Ok(()) => (qd, OperationResult::Connect),
+            Err(e) => {
+                warn!("rdma_connect() qd={:?}: {:?}", qd, &e);
+                (qd, OperationResult::Failed(e))
+            },
+        }
     }
 
-    /// Synchronous cross-queue code to start accepting a connection. This function schedules the asynchronous
-    /// coroutine and performs any necessary synchronous, multi-queue operations at the libOS-level before beginning
-    /// the accept.
-    pub fn accept(&mut self, qd: QDesc) -> Result<QToken, Fail> {
-        trace!("accept(): qd={:?}", qd);
+    /// Asynchronous code to close the RDMA queue [qd].
+    #[cfg(feature = "librdma")]
+    async fn rdma_close_coroutine(mut self, qd: QDesc) -> (QDesc, OperationResult) {
+        match self.runtime.free_queue::<SharedRdmaQueue>(&qd) {
+            Ok(_) => (qd, OperationResult::Close),
+            Err(e) => (qd, OperationResult::Failed(e)),
+        }
+    }
 
-        let mut queue: SharedNetworkQueue<T> = self.get_shared_queue(&qd)?;
-        let coroutine_constructor = || -> Result<QToken, Fail> {
-            let coroutine = Box::pin(self.clone().accept_coroutine(qd).fuse());
-            self.runtime
-                .clone()
-                .insert_io_coroutine("NetworkLibOS::accept", coroutine)
+    /// Asynchronous code to send [buf] on the RDMA queue [qd].
+    #[cfg(feature = "librdma")]
+    async fn rdma_push_coroutine(self, qd: QDesc, buf: DemiBuffer) -> (QDesc, OperationResult) {
+        let mut queue: SharedRdmaQueue = match self.get_rdma_queue(&qd) {
+            Ok(queue) => queue,
+            Err(e) => return (qd, OperationResult::Failed(e)),
         };
+        let nbytes: usize = buf.len();
+        match queue.push(buf).await {
+            Ok(()) => (qd, OperationResult::Push(nbytes)),
+            Err(e) => {
+                warn!("rdma_push() qd={:?}: {:?}", qd, &e);
+                (qd, OperationResult::Failed(e))
+            },
+        }
+    }
 
-        queue.accept(coroutine_constructor)
+    /// Asynchronous code to receive a message on the RDMA queue [qd].
+    #[cfg(feature = "librdma")]
+    async fn rdma_pop_coroutine(self, qd: QDesc) -> (QDesc, OperationResult) {
+        let mut queue: SharedRdmaQueue = match self.get_rdma_queue(&qd) {
+            Ok(queue) => queue,
+            Err(e) => return (qd, OperationResult::Failed(e)),
+        };
+        match queue.pop().await {
+            Ok(buf) => (qd, OperationResult::Pop(None, buf, AncillaryData::default())),
+            Err(e) => {
+                warn!("rdma_pop() qd={:?}: {:?}", qd, &e);
+                (qd, OperationResult::Failed(e))
+            },
+        }
     }
 
-    /// Asynchronous cross-queue code for accepting a connection. This function returns a coroutine that runs
-    /// asynchronously to accept a connection and performs any necessary multi-queue operations at the libOS-level after
-    /// the accept succeeds or fails.
-    async fn accept_coroutine(mut self, qd: QDesc) -> (QDesc, OperationResult) {
-        // Grab the queue, make sure it hasn't been closed in the meantime.
-        // This will bump the Rc refcount so the coroutine can have it's own reference to the shared queue data
-        // structure and the SharedNetworkQueue will not be freed until this coroutine finishes.
-        let mut queue: SharedNetworkQueue<T> = match self.get_shared_queue(&qd) {
-            Ok(queue) => queue.clone(),
+    /// Asynchronous code to write [buf] to the memory of the peer of the RDMA queue [qd].
+    #[cfg(feature = "librdma")]
+    async fn rdma_write_coroutine(self, qd: QDesc, buf: DemiBuffer, region: RdmaRegion) -> (QDesc, OperationResult) {
+        let mut queue: SharedRdmaQueue = match self.get_rdma_queue(&qd) {
+            Ok(queue) => queue,
             Err(e) => return (qd, OperationResult::Failed(e)),
         };
-        // Wait for the accept operation to complete.
-        match queue.accept_coroutine().await {
-            Ok(new_queue) => {
-                // TODO: Do we need to add this to the socket id to queue descriptor table?
-                // It is safe to call except here because the new queue is connected and it should be connected to a
-                // remote address.
-                let addr: SocketAddr =
-                    expect_some!(new_queue.remote(), "An accepted socket must have a remote address");
-                let new_qd: QDesc = self.runtime.alloc_queue(new_queue);
-                // FIXME: add IPv6 support; https://github.com/microsoft/demikernel/issues/935
-                (
-                    qd,
-                    OperationResult::Accept((new_qd, expect_ok!(unwrap_socketaddr(addr), "we only support IPv4"))),
-                )
+        let nbytes: usize = buf.len();
+        match queue.write(buf, region).await {
+            Ok(()) => (qd, OperationResult::Push(nbytes)),
+            Err(e) => {
+                warn!("rdma_write() qd={:?}: {:?}", qd, &e);
+                (qd, OperationResult::Failed(e))
             },
+        }
+    }
+
+    /// Asynchronous code to read the memory of the peer of the RDMA queue [qd].
+    #[cfg(feature = "librdma")]
+    async fn rdma_read_coroutine(self, qd: QDesc, region: RdmaRegion) -> (QDesc, OperationResult) {
+        let mut queue: SharedRdmaQueue = match self.get_rdma_queue(&qd) {
+            Ok(queue) => queue,
+            Err(e) => return (qd, OperationResult::Failed(e)),
+        };
+        match queue.read(region).await {
+            Ok(buf) => (qd, OperationResult::Pop(None, buf, AncillaryData::default())),
             Err(e) => {
-                warn!("accept() listening_qd={:?}: {:?}", qd, &e);
+                warn!("rdma_read() qd={:?}: {:?}", qd, &e);
                 (qd, OperationResult::Failed(e))
             },
         }
     }
 
-    /// Synchronous code to establish a connection to a remote endpoint. This function schedules the asynchronous
-    /// coroutine and performs any necessary synchronous, multi-queue operations at the libOS-level before beginning
-    /// the connect.
-    pub fn connect(&mut self, qd: QDesc, remote: SocketAddr) -> Result<QToken, Fail> {
-        trace!("connect() qd={:?}, remote={:?}", qd, remote);
+    /// Moves up to [size] bytes from the file, block or TCP queue [srcqd] to the TCP socket [sockqd], without going
+    /// through application buffers. The splice stops early at the end of the file or when the peer of a TCP source
+    /// closes its side of the connection, and completes with the number of bytes that it moved. Thus, proxies forward
+    /// a whole connection by splicing up to usize::MAX bytes. Reads of block queues are made of whole sectors, thus
+    /// [size] must be a multiple of the sector size for them. The splice fails with EBADF if [srcqd] is closed before
+    /// it completes, and with ETIMEDOUT if it does not complete within [timeout].
+    pub fn splice(
+        &mut self,
+        sockqd: QDesc,
+        srcqd: QDesc,
+        size: usize,
+        timeout: Option<Duration>,
+    ) -> Result<QToken, Fail> {
+        trace!("splice() sockqd={:?}, srcqd={:?}, size={:?}", sockqd, srcqd, size);
+        if size == 0 {
+            let cause: &str = "zero-length splice";
+            warn!("splice(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, cause));
+        }
+        match self.runtime.get_queue_type(&srcqd)? {
+            QType::FileQueue => (),
+            #[cfg(feature = "libspdk")]
+            QType::BlockQueue => (),
+            QType::TcpSocket if srcqd != sockqd => (),
+            qtype => {
+                let cause: String = format!("cannot splice from this queue (qtype={:?})", qtype);
+                warn!("splice(): {}", cause);
+                return Err(Fail::new(libc::EINVAL, &cause));
+            },
+        }
+        if self.runtime.get_queue_type(&sockqd)? != QType::TcpSocket {
+            let cause: String = format!("can only splice to TCP sockets (qd={:?})", sockqd);
+            warn!("splice(): {}", cause);
+            return Err(Fail::new(libc::ENOTSUP, &cause));
+        }
 
-        // FIXME: add IPv6 support; https://github.com/microsoft/demikernel/issues/935
-        let mut queue: SharedNetworkQueue<T> = self.get_shared_queue(&qd)?;
+        let mut queue: SharedNetworkQueue<T> = self.get_shared_queue(&sockqd)?;
         let coroutine_constructor = || -> Result<QToken, Fail> {
-            let coroutine = Box::pin(self.clone().connect_coroutine(qd, remote).fuse());
+            let coroutine = Box::pin(self.clone().splice_coroutine(sockqd, srcqd, size).fuse());
             self.runtime
                 .clone()
-                .insert_io_coroutine("NetworkLibOS::connect", coroutine)
+                .insert_io_coroutine_with_timeout("NetworkLibOS::splice", sockqd, timeout, coroutine)
         };
 
-        queue.connect(coroutine_constructor)
+        match self.runtime.get_queue_type(&srcqd)? {
+            // Sockets that we splice from must be able to pop as well.
+            QType::TcpSocket => {
+                let mut source: SharedNetworkQueue<T> = self.get_shared_queue(&srcqd)?;
+                queue.push(|| source.pop(coroutine_constructor))
+            },
+            _ => queue.push(coroutine_constructor),
+        }
     }
 
-    /// Asynchronous code to establish a connection to a remote endpoint. This function returns a coroutine that runs
-    /// asynchronously to connect a queue and performs any necessary multi-queue operations at the libOS-level after
-    /// the connect succeeds or fails.
-    async fn connect_coroutine(self, qd: QDesc, remote: SocketAddr) -> (QDesc, OperationResult) {
-        // Grab the queue, make sure it hasn't been closed in the meantime.
-        // This will bump the Rc refcount so the coroutine can have it's own reference to the shared queue data
-        // structure and the SharedNetworkQueue will not be freed until this coroutine finishes.
-        let mut queue: SharedNetworkQueue<T> = match self.get_shared_queue(&qd) {
-            Ok(queue) => queue.clone(),
-            Err(e) => return (qd, OperationResult::Failed(e)),
+    /// Asynchronous code to splice [size] bytes from [srcqd] to the TCP socket [sockqd]. Data is read in chunks of at
+    /// most a receive buffer, each of which is pushed to the socket as it is.
+    async fn splice_coroutine(self, sockqd: QDesc, srcqd: QDesc, size: usize) -> (QDesc, OperationResult) {
+        let mut queue: SharedNetworkQueue<T> = match self.get_shared_queue(&sockqd) {
+            Ok(queue) => queue,
+            Err(e) => return (sockqd, OperationResult::Failed(e)),
         };
-        // Wait for connect operation to complete.
-        match queue.connect_coroutine(remote).await {
-            Ok(()) => {
-                // TODO: Do we need to add this to socket id to queue descriptor table?
-                (qd, OperationResult::Connect)
-            },
-            Err(e) => {
-                warn!("connect() failed (qd={:?}, error={:?})", qd, e.cause);
-                (qd, OperationResult::Failed(e))
+        let mut spliced: usize = 0;
+        while spliced < size {
+            let len: usize = (size - spliced).min(limits::POP_SIZE_MAX);
+            let mut buf: DemiBuffer = match self.splice_read(srcqd, len).await {
+                Ok(buf) if buf.len() == 0 => break,
+                Ok(buf) => buf,
+                Err(e) => {
+                    warn!("splice() srcqd={:?}: {:?}", srcqd, &e);
+                    return (sockqd, OperationResult::Failed(e));
+                },
+            };
+            let nbytes: usize = buf.len();
+            if let Err(e) = queue.push_coroutine(&mut buf, None).await {
+                warn!("splice() sockqd={:?}: {:?}", sockqd, &e);
+                return (sockqd, OperationResult::Failed(e));
+            }
+            spliced += nbytes;
+        }
+        (sockqd, OperationResult::Splice(spliced))
+    }
+
+    /// Reads up to [size] bytes from the file, block or TCP queue [qd], for a splice. The queue is looked up on every
+    /// read, rather than held for the whole splice, so that the splice fails with EBADF once [qd] is closed: closing a
+    /// socket fails the pop that waits on it, and the generation of [qd] tells it apart from queues opened later on.
+    async fn splice_read(&self, qd: QDesc, size: usize) -> Result<DemiBuffer, Fail> {
+        match self.runtime.get_queue_type(&qd)? {
+            #[cfg(feature = "libspdk")]
+            QType::BlockQueue => self.get_block_queue(&qd)?.pop_coroutine(Some(size)).await,
+            QType::TcpSocket => {
+                let (_, buf, _) = self.get_shared_queue(&qd)?.pop_coroutine(Some(size)).await?;
+                Ok(buf)
             },
+            _ => self.get_file_queue(&qd)?.pop_coroutine(Some(size)).await,
         }
     }
 
@@ -293,12 +1438,63 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
     pub fn async_close(&mut self, qd: QDesc) -> Result<QToken, Fail> {
         trace!("async_close() qd={:?}", qd);
 
+        match self.runtime.get_queue_type(&qd)? {
+            QType::QuicSocket => {
+                self.get_quic_queue(&qd)?.close()?;
+                let coroutine = Box::pin(self.clone().quic_close_coroutine(qd).fuse());
+                return self
+                    .runtime
+                    .clone()
+                    .insert_io_coroutine("NetworkLibOS::quic_close", qd, coroutine);
+            },
+            QType::QuicStream => {
+                self.get_quic_stream(&qd)?.close()?;
+                let coroutine = Box::pin(self.clone().quic_stream_close_coroutine(qd).fuse());
+                return self
+                    .runtime
+                    .clone()
+                    .insert_io_coroutine("NetworkLibOS::quic_close", qd, coroutine);
+            },
+            QType::DtlsSocket => {
+                self.get_dtls_queue(&qd)?.close()?;
+                let coroutine = Box::pin(self.clone().dtls_close_coroutine(qd).fuse());
+                return self
+                    .runtime
+                    .clone()
+                    .insert_io_coroutine("NetworkLibOS::dtls_close", qd, coroutine);
+            },
+            QType::FileQueue => {
+                let coroutine = Box::pin(self.clone().file_close_coroutine(qd).fuse());
+                return self
+                    .runtime
+                    .clone()
+                    .insert_io_coroutine("NetworkLibOS::file_close", qd, coroutine);
+            },
+            #[cfg(feature = "libspdk")]
+            QType::BlockQueue => {
+                let coroutine = Box::pin(self.clone().block_close_coroutine(qd).fuse());
+                return self
+                    .runtime
+                    .clone()
+                    .insert_io_coroutine("NetworkLibOS::block_close", qd, coroutine);
+            },
+            #[cfg(feature = "librdma")]
+            QType::RdmaQueue => {
+                let coroutine = Box::pin(self.clone().rdma_close_coroutine(qd).fuse());
+                return self
+                    .runtime
+                    .clone()
+                    .insert_io_coroutine("NetworkLibOS::rdma_close", qd, coroutine);
+            },
+            _ => (),
+        }
+
         let mut queue: SharedNetworkQueue<T> = self.get_shared_queue(&qd)?;
         let coroutine_constructor = || -> Result<QToken, Fail> {
             let coroutine = Box::pin(self.clone().close_coroutine(qd).fuse());
             self.runtime
                 .clone()
-                .insert_io_coroutine("NetworkLibOS::close", coroutine)
+                .insert_io_coroutine("NetworkLibOS::close", qd, coroutine)
         };
 
         queue.close(coroutine_constructor)
@@ -353,8 +1549,8 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
 
     /// Synchronous code to push [buf] to a SharedNetworkQueue and its underlying POSIX socket. This function schedules the
     /// coroutine that asynchronously runs the push and any synchronous multi-queue functionality before the push
-    /// begins.
-    pub fn push(&mut self, qd: QDesc, sga: &demi_sgarray_t) -> Result<QToken, Fail> {
+    /// begins. The push fails with ETIMEDOUT if it does not complete within [timeout].
+    pub fn push(&mut self, qd: QDesc, sga: &demi_sgarray_t, timeout: Option<Duration>) -> Result<QToken, Fail> {
         let buf: DemiBuffer = self.transport.clone_sgarray(sga)?;
         if buf.len() == 0 {
             let cause: String = format!("zero-length buffer");
@@ -362,12 +1558,63 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
             return Err(Fail::new(libc::EINVAL, &cause));
         };
 
+        match self.runtime.get_queue_type(&qd)? {
+            QType::QuicStream => {
+                let coroutine = Box::pin(self.clone().quic_push_coroutine(qd, buf).fuse());
+                return self.runtime.clone().insert_io_coroutine_with_timeout(
+                    "NetworkLibOS::quic_push",
+                    qd,
+                    timeout,
+                    coroutine,
+                );
+            },
+            QType::DtlsSocket => {
+                let coroutine = Box::pin(self.clone().dtls_push_coroutine(qd, buf).fuse());
+                return self.runtime.clone().insert_io_coroutine_with_timeout(
+                    "NetworkLibOS::dtls_push",
+                    qd,
+                    timeout,
+                    coroutine,
+                );
+            },
+            QType::FileQueue => {
+                let coroutine = Box::pin(self.clone().file_push_coroutine(qd, buf).fuse());
+                return self.runtime.clone().insert_io_coroutine_with_timeout(
+                    "NetworkLibOS::file_push",
+                    qd,
+                    timeout,
+                    coroutine,
+                );
+            },
+            #[cfg(feature = "libspdk")]
+            QType::BlockQueue => {
+                let coroutine = Box::pin(self.clone().block_push_coroutine(qd, buf).fuse());
+                return self.runtime.clone().insert_io_coroutine_with_timeout(
+                    "NetworkLibOS::block_push",
+                    qd,
+                    timeout,
+                    coroutine,
+                );
+            },
+            #[cfg(feature = "librdma")]
+            QType::RdmaQueue => {
+                let coroutine = Box::pin(self.clone().rdma_push_coroutine(qd, buf).fuse());
+                return self.runtime.clone().insert_io_coroutine_with_timeout(
+                    "NetworkLibOS::rdma_push",
+                    qd,
+                    timeout,
+                    coroutine,
+                );
+            },
+            _ => (),
+        }
+
         let mut queue: SharedNetworkQueue<T> = self.get_shared_queue(&qd)?;
         let coroutine_constructor = || -> Result<QToken, Fail> {
             let coroutine = Box::pin(self.clone().push_coroutine(qd, buf).fuse());
             self.runtime
                 .clone()
-                .insert_io_coroutine("NetworkLibOS::push", coroutine)
+                .insert_io_coroutine_with_timeout("NetworkLibOS::push", qd, timeout, coroutine)
         };
 
         queue.push(coroutine_constructor)
@@ -384,9 +1631,11 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
             Ok(queue) => queue,
             Err(e) => return (qd, OperationResult::Failed(e)),
         };
+        // The whole buffer is accepted once the push completes.
+        let nbytes: usize = buf.len();
         // Wait for push to complete.
         match queue.push_coroutine(&mut buf, None).await {
-            Ok(()) => (qd, OperationResult::Push),
+            Ok(()) => (qd, OperationResult::Push(nbytes)),
             Err(e) => {
                 warn!("push() qd={:?}: {:?}", qd, &e);
                 (qd, OperationResult::Failed(e))
@@ -396,9 +1645,15 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
 
     /// Synchronous code to pushto [buf] to [remote] on a SharedNetworkQueue and its underlying POSIX socket. This
     /// function schedules the coroutine that asynchronously runs the pushto and any synchronous multi-queue
-    /// functionality after pushto begins.
-    pub fn pushto(&mut self, qd: QDesc, sga: &demi_sgarray_t, remote: SocketAddr) -> Result<QToken, Fail> {
-        trace!("pushto() qd={:?}", qd);
+    /// functionality after pushto begins. The pushto fails with ETIMEDOUT if it does not complete within [timeout].
+    pub fn pushto(
+        &mut self,
+        qd: QDesc,
+        sga: &demi_sgarray_t,
+        remote: SocketAddr,
+        timeout: Option<Duration>,
+    ) -> Result<QToken, Fail> {
+        trace!("pushto() qd={:?}, timeout={:?}", qd, timeout);
 
         let buf: DemiBuffer = self.transport.clone_sgarray(sga)?;
         if buf.len() == 0 {
@@ -410,7 +1665,7 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
             let coroutine = Box::pin(self.clone().pushto_coroutine(qd, buf, remote).fuse());
             self.runtime
                 .clone()
-                .insert_io_coroutine("NetworkLibOS::pushto", coroutine)
+                .insert_io_coroutine_with_timeout("NetworkLibOS::pushto", qd, timeout, coroutine)
         };
 
         queue.push(coroutine_constructor)
@@ -427,9 +1682,11 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
             Ok(queue) => queue,
             Err(e) => return (qd, OperationResult::Failed(e)),
         };
+        // The whole buffer is accepted once the push completes.
+        let nbytes: usize = buf.len();
         // Wait for push to complete.
         match queue.push_coroutine(&mut buf, Some(remote)).await {
-            Ok(()) => (qd, OperationResult::Push),
+            Ok(()) => (qd, OperationResult::Push(nbytes)),
             Err(e) => {
                 warn!("pushto() qd={:?}: {:?}", qd, &e);
                 (qd, OperationResult::Failed(e))
@@ -439,17 +1696,71 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
 
     /// Synchronous code to pop data from a SharedNetworkQueue and its underlying POSIX socket of optional [size]. This
     /// function schedules the asynchronous coroutine and performs any necessary synchronous, multi-queue operations
-    /// at the libOS-level before beginning the pop.
-    pub fn pop(&mut self, qd: QDesc, size: Option<usize>) -> Result<QToken, Fail> {
-        trace!("pop() qd={:?}, size={:?}", qd, size);
+    /// at the libOS-level before beginning the pop. The pop fails with ETIMEDOUT if it does not complete within
+    /// [timeout].
+    pub fn pop(&mut self, qd: QDesc, size: Option<usize>, timeout: Option<Duration>) -> Result<QToken, Fail> {
+        trace!("pop() qd={:?}, size={:?}, timeout={:?}", qd, size, timeout);
 
         // We just assert 'size' here, because it was previously checked at PDPIX layer.
         debug_assert!(size.is_none() || ((size.unwrap() > 0) && (size.unwrap() <= limits::POP_SIZE_MAX)));
 
+        match self.runtime.get_queue_type(&qd)? {
+            QType::QuicStream => {
+                let coroutine = Box::pin(self.clone().quic_pop_coroutine(qd, size).fuse());
+                return self.runtime.clone().insert_io_coroutine_with_timeout(
+                    "NetworkLibOS::quic_pop",
+                    qd,
+                    timeout,
+                    coroutine,
+                );
+            },
+            QType::DtlsSocket => {
+                let coroutine = Box::pin(self.clone().dtls_pop_coroutine(qd, size).fuse());
+                return self.runtime.clone().insert_io_coroutine_with_timeout(
+                    "NetworkLibOS::dtls_pop",
+                    qd,
+                    timeout,
+                    coroutine,
+                );
+            },
+            QType::FileQueue => {
+                let coroutine = Box::pin(self.clone().file_pop_coroutine(qd, size).fuse());
+                return self.runtime.clone().insert_io_coroutine_with_timeout(
+                    "NetworkLibOS::file_pop",
+                    qd,
+                    timeout,
+                    coroutine,
+                );
+            },
+            #[cfg(feature = "libspdk")]
+            QType::BlockQueue => {
+                let coroutine = Box::pin(self.clone().block_pop_coroutine(qd, size).fuse());
+                return self.runtime.clone().insert_io_coroutine_with_timeout(
+                    "NetworkLibOS::block_pop",
+                    qd,
+                    timeout,
+                    coroutine,
+                );
+            },
+            #[cfg(feature = "librdma")]
+            QType::RdmaQueue => {
+                let coroutine = Box::pin(self.clone().rdma_pop_coroutine(qd).fuse());
+                return self.runtime.clone().insert_io_coroutine_with_timeout(
+                    "NetworkLibOS::rdma_pop",
+                    qd,
+                    timeout,
+                    coroutine,
+                );
+            },
+            _ => (),
+        }
+
         let mut queue: SharedNetworkQueue<T> = self.get_shared_queue(&qd)?;
         let coroutine_constructor = || -> Result<QToken, Fail> {
             let coroutine = Box::pin(self.clone().pop_coroutine(qd, size).fuse());
-            self.runtime.clone().insert_io_coroutine("NetworkLibOS::pop", coroutine)
+            self.runtime
+                .clone()
+                .insert_io_coroutine_with_timeout("NetworkLibOS::pop", qd, timeout, coroutine)
         };
 
         queue.pop(coroutine_constructor)
@@ -470,11 +1781,15 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
         // Wait for pop to complete.
         match queue.pop_coroutine(size).await {
             // FIXME: add IPv6 support; https://github.com/microsoft/demikernel/issues/935
-            Ok((Some(addr), buf)) => (
+            Ok((Some(addr), buf, ancillary)) => (
                 qd,
-                OperationResult::Pop(Some(expect_ok!(unwrap_socketaddr(addr), "we only support IPv4")), buf),
+                OperationResult::Pop(
+                    Some(expect_ok!(unwrap_socketaddr(addr), "we only support IPv4")),
+                    buf,
+                    ancillary,
+                ),
             ),
-            Ok((None, buf)) => (qd, OperationResult::Pop(None, buf)),
+            Ok((None, buf, ancillary)) => (qd, OperationResult::Pop(None, buf, ancillary)),
             Err(e) => {
                 warn!("pop() qd={:?}: {:?}", qd, &e);
                 (qd, OperationResult::Failed(e))
@@ -482,6 +1797,64 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
         }
     }
 
+    /// Synchronous code to pop up to [max] datagrams, or a coalesced range of bytes, that a socket already received in
+    /// a single completion. The pop waits only for the first datagram or bytes to arrive and fails with ETIMEDOUT if
+    /// they do not arrive within [timeout].
+    pub fn pop_many(&mut self, qd: QDesc, max: usize, timeout: Option<Duration>) -> Result<QToken, Fail> {
+        trace!("pop_many() qd={:?}, max={:?}, timeout={:?}", qd, max, timeout);
+
+        // We just assert 'max' here, because it was previously checked at PDPIX layer.
+        debug_assert!(max > 0);
+
+        match self.runtime.get_queue_type(&qd)? {
+            QType::TcpSocket | QType::UdpSocket | QType::RawSocket => (),
+            qtype => {
+                let cause: String = format!("batched pops only run on sockets (qtype={:?})", qtype);
+                warn!("pop_many(): {}", cause);
+                return Err(Fail::new(libc::ENOTSUP, &cause));
+            },
+        }
+
+        let mut queue: SharedNetworkQueue<T> = self.get_shared_queue(&qd)?;
+        let coroutine_constructor = || -> Result<QToken, Fail> {
+            let coroutine = Box::pin(self.clone().pop_many_coroutine(qd, max).fuse());
+            self.runtime
+                .clone()
+                .insert_io_coroutine_with_timeout("NetworkLibOS::pop_many", qd, timeout, coroutine)
+        };
+
+        queue.pop(coroutine_constructor)
+    }
+
+    /// Asynchronous code to pop up to [max] datagrams, or a coalesced range of bytes, from a SharedNetworkQueue.
+    async fn pop_many_coroutine(self, qd: QDesc, max: usize) -> (QDesc, OperationResult) {
+        let mut queue: SharedNetworkQueue<T> = match self.get_shared_queue(&qd) {
+            Ok(queue) => queue,
+            Err(e) => return (qd, OperationResult::Failed(e)),
+        };
+
+        match queue.pop_many_coroutine(max).await {
+            // FIXME: add IPv6 support; https://github.com/microsoft/demikernel/issues/935
+            Ok(batch) => (
+                qd,
+                OperationResult::PopMany(
+                    batch
+                        .into_iter()
+                        .map(|(addr, buf, ancillary)| {
+                            let addr: Option<SocketAddrV4> =
+                                addr.map(|addr| expect_ok!(unwrap_socketaddr(addr), "we only support IPv4"));
+                            (addr, buf, ancillary)
+                        })
+                        .collect(),
+                ),
+            ),
+            Err(e) => {
+                warn!("pop_many() qd={:?}: {:?}", qd, &e);
+                (qd, OperationResult::Failed(e))
+            },
+        }
+    }
+
     /// Waits for a pending I/O operation to complete or a timeout to expire.
     /// This is just a single-token convenience wrapper for wait_any().
     pub fn wait(&mut self, qt: QToken, timeout: Duration) -> Result<demi_qresult_t, Fail> {
@@ -508,11 +1881,11 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
     pub fn wait_next_n<Acceptor: FnMut(demi_qresult_t) -> bool>(
         &mut self,
         mut acceptor: Acceptor,
-        timeout: Duration
-    ) -> Result<(), Fail>
-    {
-        self.runtime.clone().wait_next_n(
-            |qt, qd, result| acceptor(self.create_result(result, qd, qt)), timeout)
+        timeout: Duration,
+    ) -> Result<(), Fail> {
+        self.runtime
+            .clone()
+            .wait_next_n(|qt, qd, result| acceptor(self.create_result(result, qd, qt)), timeout)
     }
 
     pub fn create_result(&self, result: OperationResult, qd: QDesc, qt: QToken) -> demi_qresult_t {
@@ -540,18 +1913,19 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
                     qr_value,
                 }
             },
-            OperationResult::Push => demi_qresult_t {
+            OperationResult::Push(nbytes) => demi_qresult_t {
                 qr_opcode: demi_opcode_t::DEMI_OPC_PUSH,
                 qr_qd: qd.into(),
                 qr_qt: qt.into(),
-                qr_ret: 0,
+                qr_ret: nbytes as i64,
                 qr_value: unsafe { mem::zeroed() },
             },
-            OperationResult::Pop(addr, bytes) => match self.transport.into_sgarray(bytes) {
+            OperationResult::Pop(addr, bytes, ancillary) => match self.transport.into_sgarray(bytes) {
                 Ok(mut sga) => {
                     if let Some(addr) = addr {
                         sga.sga_addr = socketaddrv4_to_sockaddr(&addr);
                     }
+                    sga.sga_ancillary = ancillary.into();
                     let qr_value: demi_qr_value_t = demi_qr_value_t { sga };
                     demi_qresult_t {
                         qr_opcode: demi_opcode_t::DEMI_OPC_POP,
@@ -572,6 +1946,46 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
                     }
                 },
             },
+            OperationResult::PopMany(batch) => {
+                let mut sgas: Vec<demi_sgarray_t> = Vec::with_capacity(batch.len());
+                for (addr, bytes, ancillary) in batch {
+                    match self.transport.into_sgarray(bytes) {
+                        Ok(mut sga) => {
+                            if let Some(addr) = addr {
+                                sga.sga_addr = socketaddrv4_to_sockaddr(&addr);
+                            }
+                            sga.sga_ancillary = ancillary.into();
+                            sgas.push(sga);
+                        },
+                        Err(e) => {
+                            warn!("Operation Failed: {:?}", e);
+                            for sga in sgas {
+                                if let Err(e) = self.transport.sgafree(sga) {
+                                    warn!("create_result(): failed to release scatter-gather array: {:?}", e);
+                                }
+                            }
+                            return demi_qresult_t {
+                                qr_opcode: demi_opcode_t::DEMI_OPC_FAILED,
+                                qr_qd: qd.into(),
+                                qr_qt: qt.into(),
+                                qr_ret: e.errno as i64,
+                                qr_value: unsafe { mem::zeroed() },
+                            };
+                        },
+                    }
+                }
+                let batch: demi_sgarray_batch_t = demi_sgarray_batch_t {
+                    count: sgas.len() as u32,
+                    sgas: Box::into_raw(sgas.into_boxed_slice()) as *mut demi_sgarray_t,
+                };
+                demi_qresult_t {
+                    qr_opcode: demi_opcode_t::DEMI_OPC_POP_MANY,
+                    qr_qd: qd.into(),
+                    qr_qt: qt.into(),
+                    qr_ret: 0,
+                    qr_value: demi_qr_value_t { batch },
+                }
+            },
             OperationResult::Close => demi_qresult_t {
                 qr_opcode: demi_opcode_t::DEMI_OPC_CLOSE,
                 qr_qd: qd.into(),
@@ -579,6 +1993,20 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
                 qr_ret: 0,
                 qr_value: unsafe { mem::zeroed() },
             },
+            OperationResult::Task => demi_qresult_t {
+                qr_opcode: demi_opcode_t::DEMI_OPC_TASK,
+                qr_qd: qd.into(),
+                qr_qt: qt.into(),
+                qr_ret: 0,
+                qr_value: unsafe { mem::zeroed() },
+            },
+            OperationResult::Splice(nbytes) => demi_qresult_t {
+                qr_opcode: demi_opcode_t::DEMI_OPC_SPLICE,
+                qr_qd: qd.into(),
+                qr_qt: qt.into(),
+                qr_ret: nbytes as i64,
+                qr_value: unsafe { mem::zeroed() },
+            },
             OperationResult::Failed(e) => {
                 warn!("Operation Failed: {:?}", e);
                 demi_qresult_t {
@@ -602,17 +2030,178 @@ impl<T: NetworkTransport> SharedNetworkLibOS<T> {
         self.runtime.poll()
     }
 
+    /// Shuts the engine down. Listening queues stop accepting connections right away, while every other queue is
+    /// closed gracefully, so that TCP connections send their FIN and wait for their peer to close as well. Queues that
+    /// are still open once [grace] expires are aborted, which resets their TCP connections. Once all queues are gone,
+    /// the transport flushes what is left to transmit and releases the network device, so the LibOS must not be used
+    /// afterwards.
+    pub fn shutdown_all(&mut self, grace: Option<Duration>) -> Result<(), Fail> {
+        trace!("shutdown_all() grace={:?}", grace);
+
+        // Stop accepting connections first, so that no new work comes in while the others are drained.
+        for qd in self.runtime.get_qtable().get_qds() {
+            if let Ok(QType::TcpSocket) = self.runtime.get_queue_type(&qd) {
+                if self.get_shared_queue(&qd).is_ok_and(|queue| queue.is_listening()) {
+                    self.abort(qd);
+                }
+            }
+        }
+
+        // Close the remaining queues gracefully, and abort those that fail to once the others are done. Queues that the
+        // application is closing already are among those, so that their close keeps running in the meantime.
+        let mut qts: Vec<QToken> = Vec::new();
+        let mut qds: HashMap<QToken, QDesc> = HashMap::new();
+        let mut failed: Vec<QDesc> = Vec::new();
+        for qd in self.runtime.get_qtable().get_qds() {
+            match self.async_close(qd) {
+                Ok(qt) => {
+                    qts.push(qt);
+                    qds.insert(qt, qd);
+                },
+                Err(e) => {
+                    warn!("shutdown_all(): failed to close queue (qd={:?}): {:?}", qd, e);
+                    failed.push(qd);
+                },
+            }
+        }
+        let pending: Vec<QToken> = self.runtime.wait_all(&qts, grace, |_, qd, result| {
+            if let OperationResult::Failed(e) = result {
+                warn!("shutdown_all(): failed to close queue (qd={:?}): {:?}", qd, e);
+                failed.push(qd);
+            }
+        })?;
+        for qd in failed {
+            self.abort(qd);
+        }
+
+        // Abort the queues that did not close in time.
+        for qt in pending {
+            if let Err(e) = self.runtime.cancel(qt) {
+                warn!("shutdown_all(): failed to cancel close (qt={:?}): {:?}", qt, e);
+            }
+            self.abort(qds[&qt]);
+        }
+
+        // Release the queues that are left, which are not network queues, before the transport goes away.
+        self.runtime.get_mut_qtable().drain().for_each(drop);
+        self.transport.shutdown()
+    }
+
+    /// Aborts the network queue [qd], which resets its TCP connection, and releases it. Other queues are left as they
+    /// are.
+    fn abort(&mut self, qd: QDesc) {
+        match self.runtime.get_queue_type(&qd) {
+            Ok(QType::TcpSocket | QType::UdpSocket | QType::RawSocket) => (),
+            _ => return,
+        }
+        let mut queue: SharedNetworkQueue<T> = match self.runtime.get_shared_queue(&qd) {
+            Ok(queue) => queue,
+            Err(_) => return,
+        };
+        if let Err(e) = queue.hard_close() {
+            warn!("abort(): failed to abort queue (qd={:?}): {:?}", qd, e);
+        }
+        if let Err(e) = self.runtime.free_queue::<SharedNetworkQueue<T>>(&qd) {
+            warn!("abort(): failed to release queue (qd={:?}): {:?}", qd, e);
+        }
+    }
+
+    /// Cancels the pending I/O operation [qt], which then completes with ECANCELED.
+    pub fn cancel(&mut self, qt: QToken) -> Result<(), Fail> {
+        trace!("cancel(): qt={:?}", qt);
+        self.runtime.cancel(qt)
+    }
+
+    /// Spawns the application coroutine [coroutine] named [name] onto the scheduler.
+    pub fn spawn<F: FusedFuture<Output = Result<(), Fail>> + 'static>(
+        &mut self,
+        name: &'static str,
+        coroutine: Pin<Box<F>>,
+    ) -> Result<QToken, Fail> {
+        trace!("spawn(): name={:?}", name);
+        self.runtime.insert_app_coroutine(name, coroutine)
+    }
+
+    /// Returns a file descriptor that becomes readable when waiting would return.
+    pub fn get_event_fd(&mut self) -> Result<libc::c_int, Fail> {
+        trace!("get_event_fd()");
+        self.runtime.get_event_fd()
+    }
+
+    /// Turns per-coroutine accounting of runtime and poll counts on or off.
+    pub fn set_task_accounting(&mut self, enabled: bool) {
+        trace!("set_task_accounting(): enabled={:?}", enabled);
+        self.runtime.set_task_accounting(enabled)
+    }
+
+    /// Returns the accounting of the [max_tasks] coroutines with the longest single polls.
+    pub fn get_task_stats(&self, max_tasks: usize) -> Vec<TaskStats> {
+        self.runtime.get_task_stats(max_tasks)
+    }
+
     /// Releases a scatter-gather array.
     pub fn sgafree(&self, sga: demi_sgarray_t) -> Result<(), Fail> {
         self.transport.sgafree(sga)
     }
 
+    /// Releases the scatter-gather arrays of a batched pop, and the array that holds them.
+    pub fn sgafree_batch(&self, batch: demi_sgarray_batch_t) -> Result<(), Fail> {
+        let sgas: *mut demi_sgarray_t = batch.sgas;
+        if sgas.is_null() {
+            let cause: &str = "batch holds no scatter-gather arrays";
+            warn!("sgafree_batch(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, cause));
+        }
+        // Safety: the array was allocated as a boxed slice of [count] elements in create_result().
+        let sgas: Box<[demi_sgarray_t]> =
+            unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(sgas, batch.count as usize)) };
+        let mut result: Result<(), Fail> = Ok(());
+        for sga in sgas.iter() {
+            if let Err(e) = self.transport.sgafree(*sga) {
+                result = Err(e);
+            }
+        }
+        result
+    }
+
     /// This function gets a shared queue reference out of the I/O queue table. The type if a ref counted pointer to the
     /// queue itself.
     fn get_shared_queue(&self, qd: &QDesc) -> Result<SharedNetworkQueue<T>, Fail> {
         self.runtime.get_shared_queue::<SharedNetworkQueue<T>>(qd)
     }
 
+    /// This function gets a shared reference to a QUIC socket out of the I/O queue table.
+    fn get_quic_queue(&self, qd: &QDesc) -> Result<SharedQuicQueue<T>, Fail> {
+        self.runtime.get_shared_queue::<SharedQuicQueue<T>>(qd)
+    }
+
+    /// This function gets a shared reference to a QUIC stream out of the I/O queue table.
+    fn get_quic_stream(&self, qd: &QDesc) -> Result<SharedQuicStreamQueue<T>, Fail> {
+        self.runtime.get_shared_queue::<SharedQuicStreamQueue<T>>(qd)
+    }
+
+    /// This function gets a shared reference to a DTLS socket out of the I/O queue table.
+    fn get_dtls_queue(&self, qd: &QDesc) -> Result<SharedDtlsQueue<T>, Fail> {
+        self.runtime.get_shared_queue::<SharedDtlsQueue<T>>(qd)
+    }
+
+    /// This function gets a shared reference to a file queue out of the I/O queue table.
+    fn get_file_queue(&self, qd: &QDesc) -> Result<SharedFileQueue, Fail> {
+        self.runtime.get_shared_queue::<SharedFileQueue>(qd)
+    }
+
+    /// This function gets a shared reference to a block queue out of the I/O queue table.
+    #[cfg(feature = "libspdk")]
+    fn get_block_queue(&self, qd: &QDesc) -> Result<SharedBlockQueue, Fail> {
+        self.runtime.get_shared_queue::<SharedBlockQueue>(qd)
+    }
+
+    /// This function gets a shared reference to an RDMA queue out of the I/O queue table.
+    #[cfg(feature = "librdma")]
+    fn get_rdma_queue(&self, qd: &QDesc) -> Result<SharedRdmaQueue, Fail> {
+        self.runtime.get_shared_queue::<SharedRdmaQueue>(qd)
+    }
+
     /// This exposes the transport for testing purposes.
     pub fn get_transport(&self) -> T {
         self.transport.clone()
@@ -632,6 +2221,39 @@ impl<T: NetworkTransport> Drop for NetworkLibOS<T> {
     // Releases all sockets allocated by Catnap.
     fn drop(&mut self) {
         for boxed_queue in self.runtime.get_mut_qtable().drain() {
+            match boxed_queue.get_qtype() {
+                QType::QuicSocket => {
+                    match downcast_queue::<SharedQuicQueue<T>>(boxed_queue) {
+                        Ok(mut queue) => {
+                            if let Err(e) = queue.hard_close() {
+                                error!("close() failed (error={:?}", e);
+                            }
+                        },
+                        Err(_) => error!("drop(): attempting to drop something that is not a SharedQuicQueue"),
+                    }
+                    continue;
+                },
+                // Streams go away along with their connections.
+                QType::QuicStream => continue,
+                // Files are closed once their last operation completes.
+                QType::FileQueue => continue,
+                // Queue pairs are freed along with their queues, which aborts commands in flight.
+                QType::BlockQueue => continue,
+                // Connections are torn down along with their queues, which flushes requests in flight.
+                QType::RdmaQueue => continue,
+                QType::DtlsSocket => {
+                    match downcast_queue::<SharedDtlsQueue<T>>(boxed_queue) {
+                        Ok(mut queue) => {
+                            if let Err(e) = queue.hard_close() {
+                                error!("close() failed (error={:?}", e);
+                            }
+                        },
+                        Err(_) => error!("drop(): attempting to drop something that is not a SharedDtlsQueue"),
+                    }
+                    continue;
+                },
+                _ => (),
+            }
             match downcast_queue::<SharedNetworkQueue<T>>(boxed_queue) {
                 Ok(mut queue) => {
                     if let Err(e) = queue.hard_close() {
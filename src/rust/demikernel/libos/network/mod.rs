@@ -5,34 +5,79 @@
 // Imports
 //======================================================================================================================
 
+pub mod dtls;
 pub mod libos;
+pub mod ptp;
 pub mod queue;
+pub mod quic;
 
 //======================================================================================================================
 // Imports
 //======================================================================================================================
 
+#[cfg(feature = "librdma")]
+use crate::demikernel::libos::rdma::RdmaRegion;
 use crate::{
     demikernel::libos::network::libos::SharedNetworkLibOS,
     pal::constants::SOMAXCONN,
     runtime::{
         fail::Fail,
+        network::{
+            config::{
+                IpsecDirection,
+                Route,
+                SecurityAssociation,
+            },
+            drops::{
+                DropRecord,
+                DropStats,
+            },
+            framing::FrameCodec,
+            numa::NumaStats,
+            ptp::{
+                clock::SharedPtpClock,
+                PtpConfig,
+            },
+            quic::QuicConfig,
+            socket::{
+                filter::SocketFilter,
+                option::SocketOption,
+            },
+            tcp_info::{
+                TcpEvent,
+                TcpInfo,
+            },
+            timestamp::TxTimestamp,
+            tls::TlsConfig,
+        },
+        scheduler::TaskStats,
         types::{
             demi_qresult_t,
+            demi_sgarray_batch_t,
             demi_sgarray_t,
         },
         QDesc,
         QToken,
     },
 };
+use ::futures::future::FusedFuture;
 use ::std::{
-    net::SocketAddr,
+    net::{
+        Ipv4Addr,
+        SocketAddr,
+    },
+    os::fd::RawFd,
+    pin::Pin,
     time::Duration,
 };
 
 #[cfg(any(feature = "catpowder-libos", feature = "catnip-libos"))]
 use crate::inetstack::SharedInetStack;
 
+#[cfg(feature = "catcollar-libos")]
+use crate::catcollar::transport::SharedCatcollarTransport;
+#[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+use crate::catloop::dispatch::SharedDispatchTransport;
 #[cfg(feature = "catloop-libos")]
 use crate::catloop::transport::SharedCatloopTransport;
 #[cfg(all(feature = "catnap-libos"))]
@@ -52,10 +97,15 @@ pub enum NetworkLibOSWrapper {
     Catpowder(SharedNetworkLibOS<SharedInetStack<LinuxRuntime>>),
     #[cfg(all(feature = "catnap-libos"))]
     Catnap(SharedNetworkLibOS<SharedCatnapTransport>),
+    #[cfg(feature = "catcollar-libos")]
+    Catcollar(SharedNetworkLibOS<SharedCatcollarTransport>),
     #[cfg(feature = "catnip-libos")]
     Catnip(SharedNetworkLibOS<SharedInetStack<SharedDPDKRuntime>>),
     #[cfg(feature = "catloop-libos")]
     Catloop(SharedNetworkLibOS<SharedCatloopTransport>),
+    /// Catnap, with connections to sockets on this host over memory queues.
+    #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+    CatnapDispatch(SharedNetworkLibOS<SharedDispatchTransport<SharedCatnapTransport>>),
 }
 
 //======================================================================================================================
@@ -76,10 +126,16 @@ impl NetworkLibOSWrapper {
             NetworkLibOSWrapper::Catpowder(libos) => libos.socket(domain.into(), socket_type.into(), protocol.into()),
             #[cfg(all(feature = "catnap-libos"))]
             NetworkLibOSWrapper::Catnap(libos) => libos.socket(domain.into(), socket_type.into(), protocol.into()),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.socket(domain.into(), socket_type.into(), protocol.into()),
             #[cfg(feature = "catnip-libos")]
             NetworkLibOSWrapper::Catnip(libos) => libos.socket(domain.into(), socket_type.into(), protocol.into()),
             #[cfg(feature = "catloop-libos")]
             NetworkLibOSWrapper::Catloop(libos) => libos.socket(domain.into(), socket_type.into(), protocol.into()),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => {
+                libos.socket(domain.into(), socket_type.into(), protocol.into())
+            },
         }
     }
 
@@ -90,10 +146,14 @@ impl NetworkLibOSWrapper {
             NetworkLibOSWrapper::Catpowder(libos) => libos.bind(sockqd, local),
             #[cfg(all(feature = "catnap-libos"))]
             NetworkLibOSWrapper::Catnap(libos) => libos.bind(sockqd, local),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.bind(sockqd, local),
             #[cfg(feature = "catnip-libos")]
             NetworkLibOSWrapper::Catnip(libos) => libos.bind(sockqd, local),
             #[cfg(feature = "catloop-libos")]
             NetworkLibOSWrapper::Catloop(libos) => libos.bind(sockqd, local),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.bind(sockqd, local),
         }
     }
 
@@ -119,38 +179,782 @@ impl NetworkLibOSWrapper {
             NetworkLibOSWrapper::Catpowder(libos) => libos.listen(sockqd, backlog),
             #[cfg(all(feature = "catnap-libos"))]
             NetworkLibOSWrapper::Catnap(libos) => libos.listen(sockqd, backlog),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.listen(sockqd, backlog),
             #[cfg(feature = "catnip-libos")]
             NetworkLibOSWrapper::Catnip(libos) => libos.listen(sockqd, backlog),
             #[cfg(feature = "catloop-libos")]
             NetworkLibOSWrapper::Catloop(libos) => libos.listen(sockqd, backlog),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.listen(sockqd, backlog),
+        }
+    }
+
+    /// Binds a socket to a name that local processes connect to.
+    pub fn bind_name(&mut self, sockqd: QDesc, name: &str) -> Result<(), Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.bind_name(sockqd, name),
+            #[cfg(all(feature = "catnap-libos"))]
+            NetworkLibOSWrapper::Catnap(libos) => libos.bind_name(sockqd, name),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.bind_name(sockqd, name),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.bind_name(sockqd, name),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.bind_name(sockqd, name),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.bind_name(sockqd, name),
+        }
+    }
+
+    /// Opens a file for asynchronous reads and writes.
+    pub fn open_file(&mut self, path: &str, flags: libc::c_int) -> Result<QDesc, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.open_file(path, flags),
+            #[cfg(all(feature = "catnap-libos"))]
+            NetworkLibOSWrapper::Catnap(libos) => libos.open_file(path, flags),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.open_file(path, flags),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.open_file(path, flags),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.open_file(path, flags),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.open_file(path, flags),
+        }
+    }
+
+    /// Opens a namespace of an NVMe device for asynchronous reads and writes.
+    #[cfg(feature = "libspdk")]
+    pub fn open_block(&mut self, trid: &str, nsid: u32) -> Result<QDesc, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.open_block(trid, nsid),
+            #[cfg(all(feature = "catnap-libos"))]
+            NetworkLibOSWrapper::Catnap(libos) => libos.open_block(trid, nsid),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.open_block(trid, nsid),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.open_block(trid, nsid),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.open_block(trid, nsid),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.open_block(trid, nsid),
+        }
+    }
+
+    /// Creates a queue that runs a reliable connection over RDMA.
+    #[cfg(feature = "librdma")]
+    pub fn rdma_socket(&mut self) -> Result<QDesc, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.rdma_socket(),
+            #[cfg(all(feature = "catnap-libos"))]
+            NetworkLibOSWrapper::Catnap(libos) => libos.rdma_socket(),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.rdma_socket(),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.rdma_socket(),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.rdma_socket(),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.rdma_socket(),
+        }
+    }
+
+    /// Writes data to the memory of the peer of an RDMA queue.
+    #[cfg(feature = "librdma")]
+    pub fn rdma_write(
+        &mut self,
+        qd: QDesc,
+        sga: &demi_sgarray_t,
+        region: RdmaRegion,
+        timeout: Option<Duration>,
+    ) -> Result<QToken, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.rdma_write(qd, sga, region, timeout),
+            #[cfg(all(feature = "catnap-libos"))]
+            NetworkLibOSWrapper::Catnap(libos) => libos.rdma_write(qd, sga, region, timeout),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.rdma_write(qd, sga, region, timeout),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.rdma_write(qd, sga, region, timeout),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.rdma_write(qd, sga, region, timeout),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.rdma_write(qd, sga, region, timeout),
+        }
+    }
+
+    /// Reads the memory of the peer of an RDMA queue.
+    #[cfg(feature = "librdma")]
+    pub fn rdma_read(&mut self, qd: QDesc, region: RdmaRegion, timeout: Option<Duration>) -> Result<QToken, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.rdma_read(qd, region, timeout),
+            #[cfg(all(feature = "catnap-libos"))]
+            NetworkLibOSWrapper::Catnap(libos) => libos.rdma_read(qd, region, timeout),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.rdma_read(qd, region, timeout),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.rdma_read(qd, region, timeout),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.rdma_read(qd, region, timeout),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.rdma_read(qd, region, timeout),
+        }
+    }
+
+    /// Exposes memory to the peer of an RDMA queue.
+    #[cfg(feature = "librdma")]
+    pub fn rdma_expose(&mut self, qd: QDesc, sga: &demi_sgarray_t) -> Result<RdmaRegion, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.rdma_expose(qd, sga),
+            #[cfg(all(feature = "catnap-libos"))]
+            NetworkLibOSWrapper::Catnap(libos) => libos.rdma_expose(qd, sga),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.rdma_expose(qd, sga),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.rdma_expose(qd, sga),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.rdma_expose(qd, sga),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.rdma_expose(qd, sga),
+        }
+    }
+
+    /// Withdraws memory that was exposed to the peer of an RDMA queue.
+    #[cfg(feature = "librdma")]
+    pub fn rdma_withdraw(&mut self, qd: QDesc, rkey: u32) -> Result<(), Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.rdma_withdraw(qd, rkey),
+            #[cfg(all(feature = "catnap-libos"))]
+            NetworkLibOSWrapper::Catnap(libos) => libos.rdma_withdraw(qd, rkey),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.rdma_withdraw(qd, rkey),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.rdma_withdraw(qd, rkey),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.rdma_withdraw(qd, rkey),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.rdma_withdraw(qd, rkey),
+        }
+    }
+
+    /// Sets an option on a socket.
+    pub fn set_socket_option(&mut self, sockqd: QDesc, option: SocketOption) -> Result<(), Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.set_socket_option(sockqd, option),
+            #[cfg(all(feature = "catnap-libos"))]
+            NetworkLibOSWrapper::Catnap(libos) => libos.set_socket_option(sockqd, option),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.set_socket_option(sockqd, option),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.set_socket_option(sockqd, option),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.set_socket_option(sockqd, option),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.set_socket_option(sockqd, option),
+        }
+    }
+
+    /// Adds an IPsec security association.
+    pub fn ipsec_add_security_association(&mut self, sa: SecurityAssociation) -> Result<(), Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.ipsec_add_security_association(sa),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.ipsec_add_security_association(sa),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.ipsec_add_security_association(sa),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.ipsec_add_security_association(sa),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.ipsec_add_security_association(sa),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.ipsec_add_security_association(sa),
+        }
+    }
+
+    /// Removes an IPsec security association.
+    pub fn ipsec_remove_security_association(&mut self, spi: u32, direction: IpsecDirection) -> Result<(), Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.ipsec_remove_security_association(spi, direction),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.ipsec_remove_security_association(spi, direction),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.ipsec_remove_security_association(spi, direction),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.ipsec_remove_security_association(spi, direction),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.ipsec_remove_security_association(spi, direction),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.ipsec_remove_security_association(spi, direction),
+        }
+    }
+
+    /// Adds a route to the routing table.
+    pub fn add_route(&mut self, route: Route) -> Result<(), Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.add_route(route),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.add_route(route),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.add_route(route),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.add_route(route),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.add_route(route),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.add_route(route),
+        }
+    }
+
+    /// Removes a route from the routing table.
+    pub fn remove_route(&mut self, destination: Ipv4Addr, prefix_len: u8) -> Result<(), Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.remove_route(destination, prefix_len),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.remove_route(destination, prefix_len),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.remove_route(destination, prefix_len),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.remove_route(destination, prefix_len),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.remove_route(destination, prefix_len),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.remove_route(destination, prefix_len),
+        }
+    }
+
+    /// Sets a tunable of the network stack.
+    pub fn set_tunable(&mut self, name: &str, value: &str) -> Result<(), Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.set_tunable(name, value),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.set_tunable(name, value),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.set_tunable(name, value),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.set_tunable(name, value),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.set_tunable(name, value),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.set_tunable(name, value),
+        }
+    }
+
+    /// Gets the value of a tunable of the network stack.
+    pub fn get_tunable(&self, name: &str) -> Result<String, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.get_tunable(name),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.get_tunable(name),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.get_tunable(name),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.get_tunable(name),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.get_tunable(name),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.get_tunable(name),
+        }
+    }
+
+    /// Attaches a receive filter to a socket, or detaches the current one.
+    pub fn set_receive_filter(&mut self, sockqd: QDesc, filter: Option<SocketFilter>) -> Result<(), Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.set_receive_filter(sockqd, filter),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.set_receive_filter(sockqd, filter),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.set_receive_filter(sockqd, filter),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.set_receive_filter(sockqd, filter),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.set_receive_filter(sockqd, filter),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.set_receive_filter(sockqd, filter),
+        }
+    }
+
+    /// Gets the number of incoming packets that were dropped on their way to a socket or anywhere in the network stack.
+    pub fn get_drop_stats(&mut self, sockqd: Option<QDesc>) -> Result<DropStats, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.get_drop_stats(sockqd),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.get_drop_stats(sockqd),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.get_drop_stats(sockqd),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.get_drop_stats(sockqd),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.get_drop_stats(sockqd),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.get_drop_stats(sockqd),
+        }
+    }
+
+    /// Gets where the network device placed its buffer pools and the state of its queues, relative to its NUMA node.
+    pub fn get_numa_stats(&self) -> Result<NumaStats, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.get_numa_stats(),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.get_numa_stats(),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.get_numa_stats(),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.get_numa_stats(),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.get_numa_stats(),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.get_numa_stats(),
+        }
+    }
+
+    /// Pops the oldest record from the error queue of a socket.
+    pub fn pop_drop_record(&mut self, sockqd: QDesc) -> Result<Option<DropRecord>, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.pop_drop_record(sockqd),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.pop_drop_record(sockqd),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.pop_drop_record(sockqd),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.pop_drop_record(sockqd),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.pop_drop_record(sockqd),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.pop_drop_record(sockqd),
+        }
+    }
+
+    /// Pops the oldest transmit timestamp of a socket.
+    pub fn pop_tx_timestamp(&mut self, sockqd: QDesc) -> Result<Option<TxTimestamp>, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.pop_tx_timestamp(sockqd),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.pop_tx_timestamp(sockqd),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.pop_tx_timestamp(sockqd),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.pop_tx_timestamp(sockqd),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.pop_tx_timestamp(sockqd),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.pop_tx_timestamp(sockqd),
+        }
+    }
+
+    /// Hands a TCP connection off to another process through a Unix domain socket.
+    pub fn export_queue(&mut self, sockqd: QDesc, channel: RawFd) -> Result<(), Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.export_queue(sockqd, channel),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.export_queue(sockqd, channel),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.export_queue(sockqd, channel),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.export_queue(sockqd, channel),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.export_queue(sockqd, channel),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.export_queue(sockqd, channel),
+        }
+    }
+
+    /// Takes over a TCP connection that another process handed off through a Unix domain socket.
+    pub fn import_queue(&mut self, channel: RawFd) -> Result<QDesc, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.import_queue(channel),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.import_queue(channel),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.import_queue(channel),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.import_queue(channel),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.import_queue(channel),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.import_queue(channel),
+        }
+    }
+
+    /// Freezes a TCP connection into a blob that another instance of the network stack may thaw.
+    pub fn freeze_queue(&mut self, sockqd: QDesc) -> Result<Vec<u8>, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.freeze_queue(sockqd),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.freeze_queue(sockqd),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.freeze_queue(sockqd),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.freeze_queue(sockqd),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.freeze_queue(sockqd),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.freeze_queue(sockqd),
+        }
+    }
+
+    /// Thaws a TCP connection that another instance of the network stack froze into a blob.
+    pub fn thaw_queue(&mut self, blob: &[u8]) -> Result<QDesc, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.thaw_queue(blob),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.thaw_queue(blob),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.thaw_queue(blob),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.thaw_queue(blob),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.thaw_queue(blob),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.thaw_queue(blob),
+        }
+    }
+
+    /// Gets a snapshot of the state of a TCP connection.
+    pub fn get_tcp_info(&mut self, sockqd: QDesc) -> Result<TcpInfo, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.get_tcp_info(sockqd),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.get_tcp_info(sockqd),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.get_tcp_info(sockqd),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.get_tcp_info(sockqd),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.get_tcp_info(sockqd),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.get_tcp_info(sockqd),
+        }
+    }
+
+    /// Gets the recent retransmission and flow control events of a TCP connection.
+    pub fn get_tcp_timeline(&mut self, sockqd: QDesc) -> Result<Vec<TcpEvent>, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.get_tcp_timeline(sockqd),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.get_tcp_timeline(sockqd),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.get_tcp_timeline(sockqd),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.get_tcp_timeline(sockqd),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.get_tcp_timeline(sockqd),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.get_tcp_timeline(sockqd),
+        }
+    }
+
+    /// Gets the current value of an option on a socket.
+    pub fn get_socket_option(&mut self, sockqd: QDesc, option: SocketOption) -> Result<SocketOption, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.get_socket_option(sockqd, option),
+            #[cfg(all(feature = "catnap-libos"))]
+            NetworkLibOSWrapper::Catnap(libos) => libos.get_socket_option(sockqd, option),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.get_socket_option(sockqd, option),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.get_socket_option(sockqd, option),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.get_socket_option(sockqd, option),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.get_socket_option(sockqd, option),
+        }
+    }
+
+    /// Accepts an incoming connection on a TCP socket, unless [timeout] passes first.
+    pub fn accept(&mut self, sockqd: QDesc, timeout: Option<Duration>) -> Result<QToken, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.accept(sockqd, timeout),
+            #[cfg(all(feature = "catnap-libos"))]
+            NetworkLibOSWrapper::Catnap(libos) => libos.accept(sockqd, timeout),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.accept(sockqd, timeout),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.accept(sockqd, timeout),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.accept(sockqd, timeout),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.accept(sockqd, timeout),
         }
     }
 
-    /// Accepts an incoming connection on a TCP socket.
-    pub fn accept(&mut self, sockqd: QDesc) -> Result<QToken, Fail> {
+    /// Initiates a connection with a remote TCP peer, which fails if it is not established within [timeout].
+    pub fn connect(&mut self, sockqd: QDesc, remote: SocketAddr, timeout: Option<Duration>) -> Result<QToken, Fail> {
         match self {
             #[cfg(feature = "catpowder-libos")]
-            NetworkLibOSWrapper::Catpowder(libos) => libos.accept(sockqd),
+            NetworkLibOSWrapper::Catpowder(libos) => libos.connect(sockqd, remote, timeout),
             #[cfg(all(feature = "catnap-libos"))]
-            NetworkLibOSWrapper::Catnap(libos) => libos.accept(sockqd),
+            NetworkLibOSWrapper::Catnap(libos) => libos.connect(sockqd, remote, timeout),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.connect(sockqd, remote, timeout),
             #[cfg(feature = "catnip-libos")]
-            NetworkLibOSWrapper::Catnip(libos) => libos.accept(sockqd),
+            NetworkLibOSWrapper::Catnip(libos) => libos.connect(sockqd, remote, timeout),
             #[cfg(feature = "catloop-libos")]
-            NetworkLibOSWrapper::Catloop(libos) => libos.accept(sockqd),
+            NetworkLibOSWrapper::Catloop(libos) => libos.connect(sockqd, remote, timeout),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.connect(sockqd, remote, timeout),
         }
     }
 
-    /// Initiates a connection with a remote TCP peer.
-    pub fn connect(&mut self, sockqd: QDesc, remote: SocketAddr) -> Result<QToken, Fail> {
+    /// Connects a socket to the socket that is bound to [name].
+    pub fn connect_name(&mut self, sockqd: QDesc, name: &str, timeout: Option<Duration>) -> Result<QToken, Fail> {
         match self {
             #[cfg(feature = "catpowder-libos")]
-            NetworkLibOSWrapper::Catpowder(libos) => libos.connect(sockqd, remote),
+            NetworkLibOSWrapper::Catpowder(libos) => libos.connect_name(sockqd, name, timeout),
             #[cfg(all(feature = "catnap-libos"))]
-            NetworkLibOSWrapper::Catnap(libos) => libos.connect(sockqd, remote),
+            NetworkLibOSWrapper::Catnap(libos) => libos.connect_name(sockqd, name, timeout),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.connect_name(sockqd, name, timeout),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.connect_name(sockqd, name, timeout),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.connect_name(sockqd, name, timeout),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.connect_name(sockqd, name, timeout),
+        }
+    }
+
+    /// Starts a TLS session as a client on a connected TCP socket.
+    pub fn tls_connect(&mut self, sockqd: QDesc, config: TlsConfig) -> Result<QDesc, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.tls_connect(sockqd, config),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.tls_connect(sockqd, config),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.tls_connect(sockqd, config),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.tls_connect(sockqd, config),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.tls_connect(sockqd, config),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.tls_connect(sockqd, config),
+        }
+    }
+
+    /// Starts a TLS session as a server on an accepted TCP socket.
+    pub fn tls_accept(&mut self, sockqd: QDesc, config: TlsConfig) -> Result<QDesc, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.tls_accept(sockqd, config),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.tls_accept(sockqd, config),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.tls_accept(sockqd, config),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.tls_accept(sockqd, config),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.tls_accept(sockqd, config),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.tls_accept(sockqd, config),
+        }
+    }
+
+    /// Frames HTTP/1.1 messages as a client on a connected TCP socket.
+    pub fn http_connect(&mut self, sockqd: QDesc) -> Result<QDesc, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.http_connect(sockqd),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.http_connect(sockqd),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.http_connect(sockqd),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.http_connect(sockqd),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.http_connect(sockqd),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.http_connect(sockqd),
+        }
+    }
+
+    /// Frames HTTP/1.1 messages as a server on an accepted TCP socket.
+    pub fn http_accept(&mut self, sockqd: QDesc) -> Result<QDesc, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.http_accept(sockqd),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.http_accept(sockqd),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.http_accept(sockqd),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.http_accept(sockqd),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.http_accept(sockqd),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.http_accept(sockqd),
+        }
+    }
+
+    /// Frames the data of a connected TCP socket with a codec.
+    pub fn frame(&mut self, sockqd: QDesc, codec: Box<dyn FrameCodec>) -> Result<QDesc, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.frame(sockqd, codec),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.frame(sockqd, codec),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.frame(sockqd, codec),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.frame(sockqd, codec),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.frame(sockqd, codec),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.frame(sockqd, codec),
+        }
+    }
+
+    /// Starts a DTLS association as a client on a bound UDP socket.
+    pub fn dtls_connect(&mut self, sockqd: QDesc, remote: SocketAddr, config: TlsConfig) -> Result<QDesc, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.dtls_connect(sockqd, remote, config),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.dtls_connect(sockqd, remote, config),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.dtls_connect(sockqd, remote, config),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.dtls_connect(sockqd, remote, config),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.dtls_connect(sockqd, remote, config),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.dtls_connect(sockqd, remote, config),
+        }
+    }
+
+    /// Waits for a DTLS association as a server on a bound UDP socket.
+    pub fn dtls_accept(&mut self, sockqd: QDesc, config: TlsConfig) -> Result<QDesc, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.dtls_accept(sockqd, config),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.dtls_accept(sockqd, config),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.dtls_accept(sockqd, config),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.dtls_accept(sockqd, config),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.dtls_accept(sockqd, config),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.dtls_accept(sockqd, config),
+        }
+    }
+
+    /// Runs QUIC over a bound UDP socket.
+    pub fn quic_socket(&mut self, sockqd: QDesc, config: QuicConfig) -> Result<QDesc, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.quic_socket(sockqd, config),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.quic_socket(sockqd, config),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.quic_socket(sockqd, config),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.quic_socket(sockqd, config),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.quic_socket(sockqd, config),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.quic_socket(sockqd, config),
+        }
+    }
+
+    /// Starts a PTP client.
+    pub fn ptp_start(&mut self, config: PtpConfig) -> Result<(), Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.ptp_start(config),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.ptp_start(config),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.ptp_start(config),
             #[cfg(feature = "catnip-libos")]
-            NetworkLibOSWrapper::Catnip(libos) => libos.connect(sockqd, remote),
+            NetworkLibOSWrapper::Catnip(libos) => libos.ptp_start(config),
             #[cfg(feature = "catloop-libos")]
-            NetworkLibOSWrapper::Catloop(libos) => libos.connect(sockqd, remote),
+            NetworkLibOSWrapper::Catloop(libos) => libos.ptp_start(config),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.ptp_start(config),
+        }
+    }
+
+    /// Stops the PTP client.
+    pub fn ptp_stop(&mut self) -> Result<(), Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.ptp_stop(),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.ptp_stop(),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.ptp_stop(),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.ptp_stop(),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.ptp_stop(),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.ptp_stop(),
+        }
+    }
+
+    /// Gets the clock that the PTP client steers.
+    pub fn ptp_clock(&self) -> Result<SharedPtpClock, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.ptp_clock(),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.ptp_clock(),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.ptp_clock(),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.ptp_clock(),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.ptp_clock(),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.ptp_clock(),
+        }
+    }
+
+    /// Opens a stream on a QUIC connection.
+    pub fn quic_open_stream(&mut self, qd: QDesc) -> Result<QDesc, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.quic_open_stream(qd),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.quic_open_stream(qd),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.quic_open_stream(qd),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.quic_open_stream(qd),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.quic_open_stream(qd),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.quic_open_stream(qd),
         }
     }
 
@@ -160,53 +964,135 @@ impl NetworkLibOSWrapper {
             NetworkLibOSWrapper::Catpowder(libos) => libos.async_close(sockqd),
             #[cfg(all(feature = "catnap-libos"))]
             NetworkLibOSWrapper::Catnap(libos) => libos.async_close(sockqd),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.async_close(sockqd),
             #[cfg(feature = "catnip-libos")]
             NetworkLibOSWrapper::Catnip(libos) => libos.async_close(sockqd),
             #[cfg(feature = "catloop-libos")]
             NetworkLibOSWrapper::Catloop(libos) => libos.async_close(sockqd),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.async_close(sockqd),
+        }
+    }
+
+    /// Shuts the engine down, draining all connections within [grace].
+    pub fn shutdown_all(&mut self, grace: Option<Duration>) -> Result<(), Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.shutdown_all(grace),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.shutdown_all(grace),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.shutdown_all(grace),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.shutdown_all(grace),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.shutdown_all(grace),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.shutdown_all(grace),
         }
     }
 
-    /// Pushes a scatter-gather array to a TCP socket.
-    pub fn push(&mut self, sockqd: QDesc, sga: &demi_sgarray_t) -> Result<QToken, Fail> {
+    /// Moves data from a file, block or TCP queue to a TCP socket.
+    pub fn splice(
+        &mut self,
+        sockqd: QDesc,
+        srcqd: QDesc,
+        size: usize,
+        timeout: Option<Duration>,
+    ) -> Result<QToken, Fail> {
         match self {
             #[cfg(feature = "catpowder-libos")]
-            NetworkLibOSWrapper::Catpowder(libos) => libos.push(sockqd, sga),
+            NetworkLibOSWrapper::Catpowder(libos) => libos.splice(sockqd, srcqd, size, timeout),
             #[cfg(all(feature = "catnap-libos"))]
-            NetworkLibOSWrapper::Catnap(libos) => libos.push(sockqd, sga),
+            NetworkLibOSWrapper::Catnap(libos) => libos.splice(sockqd, srcqd, size, timeout),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.splice(sockqd, srcqd, size, timeout),
             #[cfg(feature = "catnip-libos")]
-            NetworkLibOSWrapper::Catnip(libos) => libos.push(sockqd, sga),
+            NetworkLibOSWrapper::Catnip(libos) => libos.splice(sockqd, srcqd, size, timeout),
             #[cfg(feature = "catloop-libos")]
-            NetworkLibOSWrapper::Catloop(libos) => libos.push(sockqd, sga),
+            NetworkLibOSWrapper::Catloop(libos) => libos.splice(sockqd, srcqd, size, timeout),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.splice(sockqd, srcqd, size, timeout),
         }
     }
 
-    /// Pushes a scatter-gather array to a UDP socket.
+    /// Pushes a scatter-gather array to a TCP socket, unless [timeout] passes first.
+    pub fn push(&mut self, sockqd: QDesc, sga: &demi_sgarray_t, timeout: Option<Duration>) -> Result<QToken, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.push(sockqd, sga, timeout),
+            #[cfg(all(feature = "catnap-libos"))]
+            NetworkLibOSWrapper::Catnap(libos) => libos.push(sockqd, sga, timeout),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.push(sockqd, sga, timeout),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.push(sockqd, sga, timeout),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.push(sockqd, sga, timeout),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.push(sockqd, sga, timeout),
+        }
+    }
+
+    /// Pushes a scatter-gather array to a UDP socket, unless [timeout] passes first.
     #[allow(unused_variables)]
-    pub fn pushto(&mut self, sockqd: QDesc, sga: &demi_sgarray_t, to: SocketAddr) -> Result<QToken, Fail> {
+    pub fn pushto(
+        &mut self,
+        sockqd: QDesc,
+        sga: &demi_sgarray_t,
+        to: SocketAddr,
+        timeout: Option<Duration>,
+    ) -> Result<QToken, Fail> {
         match self {
             #[cfg(feature = "catpowder-libos")]
-            NetworkLibOSWrapper::Catpowder(libos) => libos.pushto(sockqd, sga, to),
+            NetworkLibOSWrapper::Catpowder(libos) => libos.pushto(sockqd, sga, to, timeout),
             #[cfg(all(feature = "catnap-libos"))]
-            NetworkLibOSWrapper::Catnap(libos) => libos.pushto(sockqd, sga, to),
+            NetworkLibOSWrapper::Catnap(libos) => libos.pushto(sockqd, sga, to, timeout),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.pushto(sockqd, sga, to, timeout),
             #[cfg(feature = "catnip-libos")]
-            NetworkLibOSWrapper::Catnip(libos) => libos.pushto(sockqd, sga, to),
+            NetworkLibOSWrapper::Catnip(libos) => libos.pushto(sockqd, sga, to, timeout),
             #[cfg(feature = "catloop-libos")]
             NetworkLibOSWrapper::Catloop(_) => Err(Fail::new(libc::ENOTSUP, "operation not supported")),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.pushto(sockqd, sga, to, timeout),
+        }
+    }
+
+    /// Pops data from a socket, unless [timeout] passes first.
+    pub fn pop(&mut self, sockqd: QDesc, size: Option<usize>, timeout: Option<Duration>) -> Result<QToken, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.pop(sockqd, size, timeout),
+            #[cfg(all(feature = "catnap-libos"))]
+            NetworkLibOSWrapper::Catnap(libos) => libos.pop(sockqd, size, timeout),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.pop(sockqd, size, timeout),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.pop(sockqd, size, timeout),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.pop(sockqd, size, timeout),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.pop(sockqd, size, timeout),
         }
     }
 
-    /// Pops data from a socket.
-    pub fn pop(&mut self, sockqd: QDesc, size: Option<usize>) -> Result<QToken, Fail> {
+    /// Pops up to [max] datagrams, or a coalesced range of bytes, that a socket already received.
+    pub fn pop_many(&mut self, sockqd: QDesc, max: usize, timeout: Option<Duration>) -> Result<QToken, Fail> {
         match self {
             #[cfg(feature = "catpowder-libos")]
-            NetworkLibOSWrapper::Catpowder(libos) => libos.pop(sockqd, size),
+            NetworkLibOSWrapper::Catpowder(libos) => libos.pop_many(sockqd, max, timeout),
             #[cfg(all(feature = "catnap-libos"))]
-            NetworkLibOSWrapper::Catnap(libos) => libos.pop(sockqd, size),
+            NetworkLibOSWrapper::Catnap(libos) => libos.pop_many(sockqd, max, timeout),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.pop_many(sockqd, max, timeout),
             #[cfg(feature = "catnip-libos")]
-            NetworkLibOSWrapper::Catnip(libos) => libos.pop(sockqd, size),
+            NetworkLibOSWrapper::Catnip(libos) => libos.pop_many(sockqd, max, timeout),
             #[cfg(feature = "catloop-libos")]
-            NetworkLibOSWrapper::Catloop(libos) => libos.pop(sockqd, size),
+            NetworkLibOSWrapper::Catloop(libos) => libos.pop_many(sockqd, max, timeout),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.pop_many(sockqd, max, timeout),
         }
     }
 
@@ -231,10 +1117,14 @@ impl NetworkLibOSWrapper {
             NetworkLibOSWrapper::Catpowder(libos) => libos.wait_any(qts, timeout),
             #[cfg(all(feature = "catnap-libos"))]
             NetworkLibOSWrapper::Catnap(libos) => libos.wait_any(qts, timeout),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.wait_any(qts, timeout),
             #[cfg(feature = "catnip-libos")]
             NetworkLibOSWrapper::Catnip(libos) => libos.wait_any(qts, timeout),
             #[cfg(feature = "catloop-libos")]
             NetworkLibOSWrapper::Catloop(libos) => libos.wait_any(qts, timeout),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.wait_any(qts, timeout),
         }
     }
 
@@ -244,22 +1134,24 @@ impl NetworkLibOSWrapper {
     pub fn wait_next_n<Acceptor: FnMut(demi_qresult_t) -> bool>(
         &mut self,
         acceptor: Acceptor,
-        timeout: Duration
-    ) -> Result<(), Fail>
-    {
+        timeout: Duration,
+    ) -> Result<(), Fail> {
         match self {
             #[cfg(feature = "catpowder-libos")]
             NetworkLibOSWrapper::Catpowder(libos) => libos.wait_next_n(acceptor, timeout),
             #[cfg(all(feature = "catnap-libos"))]
             NetworkLibOSWrapper::Catnap(libos) => libos.wait_next_n(acceptor, timeout),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.wait_next_n(acceptor, timeout),
             #[cfg(feature = "catnip-libos")]
             NetworkLibOSWrapper::Catnip(libos) => libos.wait_next_n(acceptor, timeout),
             #[cfg(feature = "catloop-libos")]
             NetworkLibOSWrapper::Catloop(libos) => libos.wait_next_n(acceptor, timeout),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.wait_next_n(acceptor, timeout),
         }
     }
 
-
     /// Waits for any operation in an I/O queue.
     pub fn poll(&mut self) {
         match self {
@@ -267,10 +1159,108 @@ impl NetworkLibOSWrapper {
             NetworkLibOSWrapper::Catpowder(libos) => libos.poll(),
             #[cfg(all(feature = "catnap-libos"))]
             NetworkLibOSWrapper::Catnap(libos) => libos.poll(),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.poll(),
             #[cfg(feature = "catnip-libos")]
             NetworkLibOSWrapper::Catnip(libos) => libos.poll(),
             #[cfg(feature = "catloop-libos")]
             NetworkLibOSWrapper::Catloop(libos) => libos.poll(),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.poll(),
+        }
+    }
+
+    /// Cancels a pending I/O operation.
+    pub fn cancel(&mut self, qt: QToken) -> Result<(), Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.cancel(qt),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.cancel(qt),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.cancel(qt),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.cancel(qt),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.cancel(qt),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.cancel(qt),
+        }
+    }
+
+    /// Spawns an application coroutine.
+    pub fn spawn<F: FusedFuture<Output = Result<(), Fail>> + 'static>(
+        &mut self,
+        name: &'static str,
+        coroutine: Pin<Box<F>>,
+    ) -> Result<QToken, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.spawn(name, coroutine),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.spawn(name, coroutine),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.spawn(name, coroutine),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.spawn(name, coroutine),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.spawn(name, coroutine),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.spawn(name, coroutine),
+        }
+    }
+
+    /// Returns a file descriptor that becomes readable when waiting would return.
+    pub fn get_event_fd(&mut self) -> Result<libc::c_int, Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.get_event_fd(),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.get_event_fd(),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.get_event_fd(),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.get_event_fd(),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.get_event_fd(),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.get_event_fd(),
+        }
+    }
+
+    /// Turns per-coroutine accounting of runtime and poll counts on or off.
+    pub fn set_task_accounting(&mut self, enabled: bool) {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.set_task_accounting(enabled),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.set_task_accounting(enabled),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.set_task_accounting(enabled),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.set_task_accounting(enabled),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.set_task_accounting(enabled),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.set_task_accounting(enabled),
+        }
+    }
+
+    /// Returns the accounting of the [max_tasks] coroutines with the longest single polls.
+    pub fn get_task_stats(&self, max_tasks: usize) -> Vec<TaskStats> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.get_task_stats(max_tasks),
+            #[cfg(feature = "catnap-libos")]
+            NetworkLibOSWrapper::Catnap(libos) => libos.get_task_stats(max_tasks),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.get_task_stats(max_tasks),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.get_task_stats(max_tasks),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.get_task_stats(max_tasks),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.get_task_stats(max_tasks),
         }
     }
 
@@ -283,12 +1273,16 @@ impl NetworkLibOSWrapper {
             NetworkLibOSWrapper::Catpowder(libos) => libos.sgaalloc(size),
             #[cfg(all(feature = "catnap-libos"))]
             NetworkLibOSWrapper::Catnap(libos) => libos.sgaalloc(size),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.sgaalloc(size),
             #[cfg(feature = "catnip-libos")]
             // TODO: Move this over to the transport once we set that up.
             // FIXME: https://github.com/microsoft/demikernel/issues/1057
             NetworkLibOSWrapper::Catnip(libos) => libos.sgaalloc(size),
             #[cfg(feature = "catloop-libos")]
             NetworkLibOSWrapper::Catloop(libos) => libos.sgaalloc(size),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.sgaalloc(size),
         }
     }
 
@@ -299,10 +1293,32 @@ impl NetworkLibOSWrapper {
             NetworkLibOSWrapper::Catpowder(libos) => libos.sgafree(sga),
             #[cfg(all(feature = "catnap-libos"))]
             NetworkLibOSWrapper::Catnap(libos) => libos.sgafree(sga),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.sgafree(sga),
             #[cfg(feature = "catnip-libos")]
             NetworkLibOSWrapper::Catnip(libos) => libos.sgafree(sga),
             #[cfg(feature = "catloop-libos")]
             NetworkLibOSWrapper::Catloop(libos) => libos.sgafree(sga),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.sgafree(sga),
+        }
+    }
+
+    /// Releases the scatter-gather arrays of a batched pop.
+    pub fn sgafree_batch(&self, batch: demi_sgarray_batch_t) -> Result<(), Fail> {
+        match self {
+            #[cfg(feature = "catpowder-libos")]
+            NetworkLibOSWrapper::Catpowder(libos) => libos.sgafree_batch(batch),
+            #[cfg(all(feature = "catnap-libos"))]
+            NetworkLibOSWrapper::Catnap(libos) => libos.sgafree_batch(batch),
+            #[cfg(feature = "catcollar-libos")]
+            NetworkLibOSWrapper::Catcollar(libos) => libos.sgafree_batch(batch),
+            #[cfg(feature = "catnip-libos")]
+            NetworkLibOSWrapper::Catnip(libos) => libos.sgafree_batch(batch),
+            #[cfg(feature = "catloop-libos")]
+            NetworkLibOSWrapper::Catloop(libos) => libos.sgafree_batch(batch),
+            #[cfg(all(feature = "catnap-libos", feature = "catloop-libos"))]
+            NetworkLibOSWrapper::CatnapDispatch(libos) => libos.sgafree_batch(batch),
         }
     }
 }
@@ -0,0 +1,233 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    demikernel::libos::network::queue::SharedNetworkQueue,
+    runtime::{
+        conditional_yield_until,
+        fail::Fail,
+        memory::DemiBuffer,
+        network::{
+            ancillary::AncillaryData,
+            ptp::{
+                client::PtpClient,
+                clock::SharedPtpClock,
+                PtpConfig,
+                PTP_EVENT_PORT,
+            },
+            timestamp::TxTimestamp,
+            transport::NetworkTransport,
+        },
+        QDesc,
+        SharedConditionVariable,
+        SharedDemiRuntime,
+        SharedObject,
+    },
+};
+use ::futures::{
+    pin_mut,
+    select_biased,
+    FutureExt,
+};
+use ::std::{
+    net::{
+        Ipv4Addr,
+        SocketAddr,
+        SocketAddrV4,
+    },
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    time::{
+        Instant,
+        SystemTime,
+    },
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// PTP client that runs over the UDP sockets of the event and general ports. A coroutine feeds it the messages that
+/// arrive, sends the messages that it builds and fires its timers until the client is stopped.
+pub struct PtpDriver<T: NetworkTransport> {
+    runtime: SharedDemiRuntime,
+    /// Queue descriptor and UDP socket of the event port.
+    event: (QDesc, SharedNetworkQueue<T>),
+    /// Queue descriptor and UDP socket of the general port.
+    general: (QDesc, SharedNetworkQueue<T>),
+    client: PtpClient,
+    /// Whether the transport stamps the messages that leave the event port.
+    tx_timestamps: bool,
+    /// Whether the client was stopped.
+    stopped: bool,
+    /// Wakes the driver.
+    driver_cv: SharedConditionVariable,
+}
+
+#[derive(Clone)]
+pub struct SharedPtpDriver<T: NetworkTransport>(SharedObject<PtpDriver<T>>);
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl<T: NetworkTransport> SharedPtpDriver<T> {
+    /// Creates a client with [config] over the bound UDP sockets of the [event] and [general] ports and starts its
+    /// driver. If [tx_timestamps] is set, the event socket was asked for software transmit timestamps.
+    pub fn new(
+        mut runtime: SharedDemiRuntime,
+        event: (QDesc, SharedNetworkQueue<T>),
+        general: (QDesc, SharedNetworkQueue<T>),
+        tx_timestamps: bool,
+        config: PtpConfig,
+    ) -> Result<Self, Fail> {
+        let now: Instant = runtime.get_now();
+        let driver: Self = Self(SharedObject::new(PtpDriver::<T> {
+            runtime: runtime.clone(),
+            event,
+            general,
+            client: PtpClient::new(config, SharedPtpClock::new(), now),
+            tx_timestamps,
+            stopped: false,
+            driver_cv: SharedConditionVariable::default(),
+        }));
+        let coroutine = Box::pin(driver.clone().drive().fuse());
+        runtime.insert_background_coroutine("PtpDriver::drive", coroutine)?;
+        Ok(driver)
+    }
+
+    /// Stops the driver. The caller closes the sockets.
+    pub fn stop(&mut self) {
+        self.stopped = true;
+        self.driver_cv.broadcast();
+    }
+
+    /// Gets the queue descriptors of the sockets of the event and general ports.
+    pub fn qds(&self) -> (QDesc, QDesc) {
+        (self.event.0, self.general.0)
+    }
+
+    pub fn clock(&self) -> SharedPtpClock {
+        self.client.clock().clone()
+    }
+
+    /// Drives the client until it is stopped.
+    async fn drive(mut self) {
+        let mut event: SharedNetworkQueue<T> = self.event.1.clone();
+        let mut general: SharedNetworkQueue<T> = self.general.1.clone();
+        while !self.stopped {
+            let now: Instant = self.runtime.get_now();
+            if self.client.timeout().map_or(false, |timeout| now >= timeout) {
+                self.client.on_timeout(now);
+            }
+            self.transmit().await;
+            if self.stopped {
+                break;
+            }
+
+            // Wait for a message on either port, the stop of the client, or the next timer.
+            let deadline: Option<Instant> = self.client.timeout();
+            let mut driver_cv: SharedConditionVariable = self.driver_cv.clone();
+            let event_operation = event.pop_coroutine(None).fuse();
+            let general_operation = general.pop_coroutine(None).fuse();
+            let kicked = driver_cv.wait().fuse();
+            pin_mut!(event_operation);
+            pin_mut!(general_operation);
+            pin_mut!(kicked);
+            let message = async {
+                select_biased! {
+                    _ = kicked => None,
+                    result = event_operation => Some(result),
+                    result = general_operation => Some(result),
+                }
+            };
+            match conditional_yield_until(message, deadline).await {
+                Ok(Some(Ok((Some(SocketAddr::V4(remote)), buf, ancillary)))) => self.receive(remote, buf, ancillary),
+                Ok(Some(Ok((remote, _, _)))) => {
+                    warn!(
+                        "drive(): dropping message without an IPv4 source address (remote={:?})",
+                        remote
+                    )
+                },
+                Ok(Some(Err(e))) if !self.stopped => warn!("drive(): failed to receive a message ({:?})", e),
+                // Either the client was stopped or a timer expired.
+                _ => (),
+            }
+        }
+    }
+
+    /// Feeds the client with a message from [remote], which is stamped with the local time at which it arrived.
+    fn receive(&mut self, remote: SocketAddrV4, buf: DemiBuffer, ancillary: AncillaryData) {
+        let now: Instant = self.runtime.get_now();
+        let receive: SystemTime = ancillary.timestamp.unwrap_or_else(SystemTime::now);
+        self.client.receive(now, *remote.ip(), &buf[..], receive);
+    }
+
+    /// Sends the messages that the client built and reports when messages to the event port left.
+    async fn transmit(&mut self) {
+        let master: Ipv4Addr = self.client.config().master();
+        while let Some((port, message)) = self.client.poll_transmit() {
+            let mut buf: DemiBuffer = match DemiBuffer::from_slice(&message) {
+                Ok(buf) => buf,
+                Err(e) => {
+                    warn!("transmit(): failed to allocate message ({:?})", e);
+                    continue;
+                },
+            };
+            let mut udp: SharedNetworkQueue<T> = if port == PTP_EVENT_PORT {
+                self.event.1.clone()
+            } else {
+                self.general.1.clone()
+            };
+            let remote: SocketAddr = SocketAddr::V4(SocketAddrV4::new(master, port));
+            let sent: SystemTime = SystemTime::now();
+            if let Err(e) = udp.push_coroutine(&mut buf, Some(remote)).await {
+                warn!(
+                    "transmit(): failed to send message (remote={:?}, error={:?})",
+                    remote, e
+                );
+                continue;
+            }
+            if port == PTP_EVENT_PORT {
+                let sent: SystemTime = self.tx_timestamp(&mut udp).unwrap_or(sent);
+                self.client.on_delay_req_sent(sent);
+            }
+        }
+    }
+
+    /// Pops the software transmit timestamp of the last message that left [udp], if the transport stamps them.
+    fn tx_timestamp(&self, udp: &mut SharedNetworkQueue<T>) -> Option<SystemTime> {
+        if !self.tx_timestamps {
+            return None;
+        }
+        let mut last: Option<TxTimestamp> = None;
+        while let Ok(Some(timestamp)) = udp.pop_tx_timestamp() {
+            last = Some(timestamp);
+        }
+        last.and_then(|timestamp| timestamp.software)
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl<T: NetworkTransport> Deref for SharedPtpDriver<T> {
+    type Target = PtpDriver<T>;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl<T: NetworkTransport> DerefMut for SharedPtpDriver<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
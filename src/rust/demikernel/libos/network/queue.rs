@@ -5,23 +5,48 @@
 // Imports
 //======================================================================================================================
 
-use crate::runtime::{
-    fail::Fail,
-    limits,
-    memory::DemiBuffer,
-    network::{
-        socket::{
-            operation::SocketOp,
-            state::SocketStateMachine,
+use crate::{
+    expect_some,
+    runtime::{
+        fail::Fail,
+        limits,
+        memory::DemiBuffer,
+        network::{
+            ancillary::AncillaryData,
+            drops::{
+                DropRecord,
+                DropStats,
+            },
+            framing::{
+                FrameCodec,
+                Framer,
+            },
+            socket::{
+                filter::SocketFilter,
+                operation::SocketOp,
+                option::SocketOption,
+                state::SocketStateMachine,
+            },
+            tcp_info::{
+                TcpEvent,
+                TcpInfo,
+            },
+            timestamp::TxTimestamp,
+            tls::{
+                session::TlsSession,
+                TlsConfig,
+                TlsRole,
+            },
+            transport::NetworkTransport,
         },
-        transport::NetworkTransport,
-    },
-    queue::{
-        IoQueue,
-        QType,
+        queue::{
+            IoQueue,
+            QType,
+        },
+        QToken,
+        SharedConditionVariable,
+        SharedObject,
     },
-    QToken,
-    SharedObject,
 };
 use ::futures::{
     pin_mut,
@@ -30,6 +55,7 @@ use ::futures::{
 };
 use ::socket2::{
     Domain,
+    Protocol,
     Type,
 };
 use ::std::{
@@ -39,6 +65,7 @@ use ::std::{
         Deref,
         DerefMut,
     },
+    os::fd::RawFd,
 };
 
 //======================================================================================================================
@@ -60,29 +87,47 @@ pub struct NetworkQueue<T: NetworkTransport> {
     remote: Option<SocketAddr>,
     /// Underlying network transport.
     transport: T,
+    /// TLS session that protects the data of the queue, if any.
+    tls: Option<TlsSession>,
+    /// Whether a coroutine is sending TLS records, which must go out in the order in which they were sealed.
+    tls_sending: bool,
+    /// Whether a coroutine is receiving TLS records, which must be processed in the order in which they arrive.
+    tls_receiving: bool,
+    /// Wakes coroutines that wait to send or receive TLS records.
+    tls_cv: SharedConditionVariable,
+    /// Frames the data of the queue into the messages of an application protocol, if it carries one.
+    framer: Option<Framer>,
 }
 
 #[derive(Clone)]
 pub struct SharedNetworkQueue<T: NetworkTransport>(SharedObject<NetworkQueue<T>>);
 
+/// Holds a TLS lock of a queue and releases it when dropped, so that a cancelled coroutine does not keep the lock.
+struct TlsLockGuard<T: NetworkTransport> {
+    queue: SharedNetworkQueue<T>,
+    /// Whether this is the sending lock, rather than the receiving one.
+    sending: bool,
+}
+
 //======================================================================================================================
 // Associated Functions
 //======================================================================================================================
 
 /// Associate Functions for Catnap LibOS
 impl<T: NetworkTransport> SharedNetworkQueue<T> {
-    pub fn new(domain: Domain, typ: Type, transport: &mut T) -> Result<Self, Fail> {
+    pub fn new(domain: Domain, typ: Type, protocol: Protocol, transport: &mut T) -> Result<Self, Fail> {
         // This was previously checked in the LibOS layer.
-        debug_assert!(typ == Type::STREAM || typ == Type::DGRAM);
+        debug_assert!(typ == Type::STREAM || typ == Type::DGRAM || typ == Type::RAW);
 
         let qtype: QType = match typ {
             Type::STREAM => QType::TcpSocket,
             Type::DGRAM => QType::UdpSocket,
+            Type::RAW => QType::RawSocket,
             // The following statement is unreachable because we have checked this on the libOS layer.
             _ => unreachable!("Invalid socket type (typ={:?})", typ),
         };
 
-        let socket: T::SocketDescriptor = transport.socket(domain, typ)?;
+        let socket: T::SocketDescriptor = transport.socket(domain, typ, protocol)?;
         Ok(Self(SharedObject::new(NetworkQueue::<T> {
             qtype,
             state_machine: SocketStateMachine::new_unbound(typ),
@@ -90,9 +135,67 @@ impl<T: NetworkTransport> SharedNetworkQueue<T> {
             local: None,
             remote: None,
             transport: transport.clone(),
+            tls: None,
+            tls_sending: false,
+            tls_receiving: false,
+            tls_cv: SharedConditionVariable::default(),
+            framer: None,
         })))
     }
 
+    /// Creates a queue for a connection that another process handed off through the Unix domain socket [channel].
+    pub fn import(channel: RawFd, transport: &mut T) -> Result<Self, Fail> {
+        let (socket, local, remote): (T::SocketDescriptor, SocketAddr, SocketAddr) = transport.import(channel)?;
+        Ok(Self::new_established(socket, local, remote, transport))
+    }
+
+    /// Creates a queue for a connection that another instance of the network stack froze into [blob].
+    pub fn thaw(blob: &[u8], transport: &mut T) -> Result<Self, Fail> {
+        let (socket, local, remote): (T::SocketDescriptor, SocketAddr, SocketAddr) = transport.thaw(blob)?;
+        Ok(Self::new_established(socket, local, remote, transport))
+    }
+
+    /// Creates a queue for a TCP connection that was established elsewhere.
+    fn new_established(socket: T::SocketDescriptor, local: SocketAddr, remote: SocketAddr, transport: &T) -> Self {
+        Self(SharedObject::new(NetworkQueue::<T> {
+            qtype: QType::TcpSocket,
+            state_machine: SocketStateMachine::new_established(),
+            socket,
+            local: Some(local),
+            remote: Some(remote),
+            transport: transport.clone(),
+            tls: None,
+            tls_sending: false,
+            tls_receiving: false,
+            tls_cv: SharedConditionVariable::default(),
+            framer: None,
+        }))
+    }
+
+    /// Hands the connection of this queue off to another process through the Unix domain socket [channel]. TLS
+    /// sessions and framers keep their state in this process, so connections that use them cannot be handed off.
+    pub fn export(&mut self, channel: RawFd) -> Result<(), Fail> {
+        self.state_machine.may_push()?;
+        if self.tls.is_some() || self.framer.is_some() {
+            let cause: &str = "connections that use TLS or framing cannot be handed off";
+            error!("export(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, cause));
+        }
+        self.transport.clone().export(&mut self.socket, channel)
+    }
+
+    /// Freezes the connection of this queue into a blob that another instance of the network stack may thaw. TLS
+    /// sessions and framers keep their state in this queue, so connections that use them cannot be frozen.
+    pub fn freeze(&mut self) -> Result<Vec<u8>, Fail> {
+        self.state_machine.may_push()?;
+        if self.tls.is_some() || self.framer.is_some() {
+            let cause: &str = "connections that use TLS or framing cannot be frozen";
+            error!("freeze(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, cause));
+        }
+        self.transport.clone().freeze(&mut self.socket)
+    }
+
     /// Binds the target queue to `local` address.
     pub fn bind(&mut self, local: SocketAddr) -> Result<(), Fail> {
         self.state_machine.prepare(SocketOp::Bind)?;
@@ -110,6 +213,22 @@ impl<T: NetworkTransport> SharedNetworkQueue<T> {
         }
     }
 
+    /// Binds the target queue to `name`, which local processes connect to instead of an address.
+    pub fn bind_name(&mut self, name: &str) -> Result<(), Fail> {
+        self.state_machine.prepare(SocketOp::Bind)?;
+        // Bind underlying socket.
+        match self.transport.clone().bind_name(&mut self.socket, name) {
+            Ok(_) => {
+                self.state_machine.commit();
+                Ok(())
+            },
+            Err(e) => {
+                self.state_machine.abort();
+                Err(e)
+            },
+        }
+    }
+
     /// Sets the target queue to listen for incoming connections.
     pub fn listen(&mut self, backlog: usize) -> Result<(), Fail> {
         // Begins the listen operation.
@@ -170,6 +289,11 @@ impl<T: NetworkTransport> SharedNetworkQueue<T> {
             local: None,
             remote: Some(saddr),
             transport: self.transport.clone(),
+            tls: None,
+            tls_sending: false,
+            tls_receiving: false,
+            tls_cv: SharedConditionVariable::default(),
+            framer: None,
         })))
     }
 
@@ -223,6 +347,43 @@ impl<T: NetworkTransport> SharedNetworkQueue<T> {
         }
     }
 
+    /// Asynchronously connects the target queue to the queue that is bound to `name`. Named endpoints have no address,
+    /// so the queue has no remote address once connected.
+    pub async fn connect_name_coroutine(&mut self, name: String) -> Result<(), Fail> {
+        // 1. Check whether we can still connect.
+        self.state_machine.may_connect()?;
+
+        // 2. Wait until either the connect completes or the socket state changes.
+        let result: Result<(), Fail> = {
+            let mut state_machine: SocketStateMachine = self.state_machine.clone();
+            let mut transport: T = self.transport.clone();
+            let state_tracker = state_machine.while_may_connect().fuse();
+            let operation = transport.connect_name(&mut self.socket, &name).fuse();
+            pin_mut!(state_tracker);
+            pin_mut!(operation);
+
+            select_biased! {
+                // If the state changed, then immediately return.
+                fail = state_tracker => Err(fail),
+                // If the operation completed, continue with the result.
+                result = operation => result,
+            }
+        };
+        match result {
+            Ok(()) => {
+                self.state_machine.prepare(SocketOp::Established)?;
+                self.state_machine.commit();
+                Ok(())
+            },
+            Err(e) => {
+                // If connect does not succeed, we close the socket.
+                self.state_machine.prepare(SocketOp::Closed)?;
+                self.state_machine.commit();
+                Err(e)
+            },
+        }
+    }
+
     /// Start an asynchronous coroutine to close this queue.
     pub fn close<F>(&mut self, coroutine_constructor: F) -> Result<QToken, Fail>
     where
@@ -249,6 +410,16 @@ impl<T: NetworkTransport> SharedNetworkQueue<T> {
     /// Asynchronously closes this queue. This function contains all of the single-queue, asynchronous code necessary
     /// to close a queue and any single-queue functionality after the close completes.
     pub async fn close_coroutine(&mut self) -> Result<(), Fail> {
+        // Let the peer know that no more data follows, so that it can tell a close from a truncation attack.
+        if let Some(session) = self.tls.as_mut() {
+            if session.is_handshake_complete() {
+                session.close();
+                let _sending: TlsLockGuard<T> = self.lock_tls_sending().await;
+                if let Err(e) = self.send_tls_records().await {
+                    warn!("close_coroutine(): failed to send close_notify ({:?})", e);
+                }
+            }
+        }
         match self.transport.clone().close(&mut self.socket).await {
             Ok(()) => {
                 self.state_machine.prepare(SocketOp::Closed)?;
@@ -273,6 +444,12 @@ impl<T: NetworkTransport> SharedNetworkQueue<T> {
     /// necessary to push to the queue and any single-queue functionality after the push completes.
     pub async fn push_coroutine(&mut self, buf: &mut DemiBuffer, addr: Option<SocketAddr>) -> Result<(), Fail> {
         self.state_machine.may_push()?;
+        if let Some(framer) = self.framer.as_mut() {
+            framer.send(&buf[..])?;
+        }
+        if self.tls.is_some() {
+            return self.tls_push_coroutine(buf).await;
+        }
 
         let result = {
             let mut state_machine: SocketStateMachine = self.state_machine.clone();
@@ -306,9 +483,26 @@ impl<T: NetworkTransport> SharedNetworkQueue<T> {
 
     /// Asynchronously pops data from the queue. This function contains all of the single-queue, asynchronous code
     /// necessary to pop from a queue and any single-queue functionality after the pop completes.
-    pub async fn pop_coroutine(&mut self, size: Option<usize>) -> Result<(Option<SocketAddr>, DemiBuffer), Fail> {
+    pub async fn pop_coroutine(
+        &mut self,
+        size: Option<usize>,
+    ) -> Result<(Option<SocketAddr>, DemiBuffer, AncillaryData), Fail> {
         self.state_machine.may_pop()?;
-        let size: usize = size.unwrap_or(limits::RECVBUF_SIZE_MAX);
+        if self.framer.is_some() {
+            return Ok((None, self.frame_pop_coroutine().await?, AncillaryData::default()));
+        }
+        self.stream_pop_coroutine(size.unwrap_or(limits::RECVBUF_SIZE_MAX))
+            .await
+    }
+
+    /// Pops up to [size] bytes of the data of the queue, decrypting them if the queue runs a TLS session.
+    async fn stream_pop_coroutine(
+        &mut self,
+        size: usize,
+    ) -> Result<(Option<SocketAddr>, DemiBuffer, AncillaryData), Fail> {
+        if self.tls.is_some() {
+            return Ok((None, self.tls_pop_coroutine(size).await?, AncillaryData::default()));
+        }
 
         let mut state_machine: SocketStateMachine = self.state_machine.clone();
         let mut transport: T = self.transport.clone();
@@ -323,6 +517,286 @@ impl<T: NetworkTransport> SharedNetworkQueue<T> {
         }
     }
 
+    /// Asynchronously pops what the queue already received, waiting only for the first datagram or bytes to arrive.
+    /// Datagrams come one per entry, up to [max] of them, while up to [max] pops of a stream are coalesced into one
+    /// buffer, followed by an empty one if the peer closed the connection.
+    pub async fn pop_many_coroutine(
+        &mut self,
+        max: usize,
+    ) -> Result<Vec<(Option<SocketAddr>, DemiBuffer, AncillaryData)>, Fail> {
+        let mut batch: Vec<(Option<SocketAddr>, DemiBuffer, AncillaryData)> = vec![self.pop_coroutine(None).await?];
+        // TLS sessions and framers hold partial records across pops, so only their first pop is taken.
+        if self.tls.is_some() || self.framer.is_some() || batch[0].1.is_empty() {
+            return Ok(batch);
+        }
+
+        let stream: bool = self.qtype == QType::TcpSocket;
+        let mut len: usize = batch[0].1.len();
+        while batch.len() < max {
+            if stream && len + limits::RECVBUF_SIZE_MAX > u16::MAX as usize {
+                break;
+            }
+            // A pop that would wait is dropped before it takes anything, so errors surface on the next pop instead.
+            let entry: (Option<SocketAddr>, DemiBuffer, AncillaryData) =
+                match self.stream_pop_coroutine(limits::RECVBUF_SIZE_MAX).now_or_never() {
+                    Some(Ok(entry)) => entry,
+                    _ => break,
+                };
+            len += entry.1.len();
+            let closed: bool = stream && entry.1.is_empty();
+            batch.push(entry);
+            if closed {
+                break;
+            }
+        }
+
+        if stream {
+            Self::coalesce(&mut batch);
+        }
+        Ok(batch)
+    }
+
+    /// Copies the buffers of a stream [batch] into one, keeping apart the empty buffer that closes the stream.
+    fn coalesce(batch: &mut Vec<(Option<SocketAddr>, DemiBuffer, AncillaryData)>) {
+        let closed: Option<(Option<SocketAddr>, DemiBuffer, AncillaryData)> = match batch.last() {
+            Some((_, buf, _)) if buf.is_empty() => batch.pop(),
+            _ => None,
+        };
+        if batch.len() > 1 {
+            let len: usize = batch.iter().map(|(_, buf, _)| buf.len()).sum();
+            let mut coalesced: DemiBuffer = DemiBuffer::new(len as u16);
+            let mut offset: usize = 0;
+            for (_, buf, _) in batch.iter() {
+                coalesced[offset..offset + buf.len()].copy_from_slice(&buf[..]);
+                offset += buf.len();
+            }
+            let (addr, _, ancillary) = batch.swap_remove(0);
+            *batch = vec![(addr, coalesced, ancillary)];
+        }
+        batch.extend(closed);
+    }
+
+    /// Runs a TLS session of [role] with [config] on this queue, which must be an established TCP connection. The
+    /// handshake runs along with the first push or pop.
+    pub fn start_tls(&mut self, role: TlsRole, config: TlsConfig) -> Result<(), Fail> {
+        if self.qtype != QType::TcpSocket {
+            let cause: String = format!("TLS only runs over TCP sockets (qtype={:?})", self.qtype);
+            error!("start_tls(): {}", cause);
+            return Err(Fail::new(libc::ENOTSUP, &cause));
+        }
+        if self.tls.is_some() {
+            let cause: &str = "socket already runs a TLS session";
+            error!("start_tls(): {}", cause);
+            return Err(Fail::new(libc::EISCONN, cause));
+        }
+        if self.state_machine.may_push().is_err() {
+            let cause: &str = "socket is not connected";
+            error!("start_tls(): {}", cause);
+            return Err(Fail::new(libc::ENOTCONN, cause));
+        }
+        self.tls = Some(TlsSession::new(role, config));
+        Ok(())
+    }
+
+    /// Frames the data of this queue, which must be an established TCP connection, with [codec]. Each pop returns a
+    /// whole frame and each push must carry exactly one. Framing runs over TLS if the queue runs a TLS session.
+    pub fn start_framing(&mut self, codec: Box<dyn FrameCodec>) -> Result<(), Fail> {
+        if self.qtype != QType::TcpSocket {
+            let cause: String = format!("framing only runs over TCP sockets (qtype={:?})", self.qtype);
+            error!("start_framing(): {}", cause);
+            return Err(Fail::new(libc::ENOTSUP, &cause));
+        }
+        if self.framer.is_some() {
+            let cause: &str = "socket is already framed";
+            error!("start_framing(): {}", cause);
+            return Err(Fail::new(libc::EISCONN, cause));
+        }
+        if self.state_machine.may_push().is_err() {
+            let cause: &str = "socket is not connected";
+            error!("start_framing(): {}", cause);
+            return Err(Fail::new(libc::ENOTCONN, cause));
+        }
+        self.framer = Some(Framer::new(codec));
+        Ok(())
+    }
+
+    /// Pops the next frame. An empty buffer means that the peer closed the connection.
+    async fn frame_pop_coroutine(&mut self) -> Result<DemiBuffer, Fail> {
+        loop {
+            if let Some(frame) = self.framer().next_frame()? {
+                return Ok(frame);
+            }
+            let (_, buf, _): (Option<SocketAddr>, DemiBuffer, AncillaryData) =
+                self.stream_pop_coroutine(limits::RECVBUF_SIZE_MAX).await?;
+            if buf.len() == 0 {
+                return Ok(self.framer().finish()?.unwrap_or(buf));
+            }
+            self.framer().receive(buf);
+        }
+    }
+
+    fn framer(&mut self) -> &mut Framer {
+        expect_some!(self.framer.as_mut(), "queue should be framed")
+    }
+
+    /// Encrypts [buf] and pushes it to the peer, once the TLS handshake completes.
+    async fn tls_push_coroutine(&mut self, buf: &mut DemiBuffer) -> Result<(), Fail> {
+        self.tls_handshake().await?;
+        let sending: TlsLockGuard<T> = self.lock_tls_sending().await;
+        let result: Result<(), Fail> = match self.tls_session().write(&buf[..]) {
+            Ok(()) => self.send_tls_records().await,
+            Err(e) => Err(e),
+        };
+        drop(sending);
+        result?;
+        buf.trim(buf.len())
+    }
+
+    /// Pops up to [size] bytes of decrypted data, once the TLS handshake completes. An empty buffer means that the peer
+    /// closed the session.
+    async fn tls_pop_coroutine(&mut self, size: usize) -> Result<DemiBuffer, Fail> {
+        self.tls_handshake().await?;
+        let _receiving: TlsLockGuard<T> = self.lock_tls_receiving().await;
+        let result: Result<DemiBuffer, Fail> = loop {
+            if let Some(data) = self.tls_session().read(size) {
+                break DemiBuffer::from_slice(&data);
+            }
+            if self.tls_session().is_peer_closed() {
+                break Ok(DemiBuffer::new(0));
+            }
+            // Records from the peer may call for an answer, such as an alert or a key update.
+            let received: Result<(), Fail> = self.receive_tls_records().await;
+            let sending: TlsLockGuard<T> = self.lock_tls_sending().await;
+            let sent: Result<(), Fail> = self.send_tls_records().await;
+            drop(sending);
+            if let Err(e) = received.and(sent) {
+                break Err(e);
+            }
+        };
+        result
+    }
+
+    /// Runs the TLS handshake, unless it already completed. Only one coroutine drives the handshake; the others wait
+    /// for it.
+    async fn tls_handshake(&mut self) -> Result<(), Fail> {
+        if self.tls_session().is_handshake_complete() {
+            return Ok(());
+        }
+        let _receiving: TlsLockGuard<T> = self.lock_tls_receiving().await;
+        let _sending: TlsLockGuard<T> = self.lock_tls_sending().await;
+        let result: Result<(), Fail> = loop {
+            let sent: Result<(), Fail> = self.send_tls_records().await;
+            if sent.is_err() || self.tls_session().is_handshake_complete() {
+                break sent;
+            }
+            if let Err(e) = self.receive_tls_records().await {
+                // Let the peer know why the handshake failed.
+                if let Err(e) = self.send_tls_records().await {
+                    warn!("tls_handshake(): failed to send alert ({:?})", e);
+                }
+                break Err(e);
+            }
+        };
+        if result.is_ok() {
+            self.offload_tls_transmit();
+        }
+        result
+    }
+
+    /// Leaves the encryption of outgoing TLS records to the device, if the transport offers one. Records stay in
+    /// software otherwise. The caller must hold the sending lock and have sent all pending records, so that the flow
+    /// starts on a record boundary.
+    fn offload_tls_transmit(&mut self) {
+        debug_assert!(self.tls_sending);
+        let mut transport: T = self.transport.clone();
+        if let Some((device, flow)) = transport.tls_offload(&mut self.socket) {
+            match self.tls_session().offload_transmit(device, flow) {
+                Ok(()) => debug!("offload_tls_transmit(): offloaded TLS records (flow={:?})", flow),
+                Err(e) if e.errno == libc::ENOTSUP => debug!("offload_tls_transmit(): {}", e.cause),
+                Err(e) => warn!("offload_tls_transmit(): falling back to software crypto ({:?})", e),
+            }
+        }
+    }
+
+    /// Sends the pending records of the TLS session. The caller must hold the sending lock.
+    async fn send_tls_records(&mut self) -> Result<(), Fail> {
+        debug_assert!(self.tls_sending);
+        if let Some(records) = self.tls_session().take_output() {
+            let mut transport: T = self.transport.clone();
+            for chunk in records.chunks(u16::MAX as usize) {
+                let mut buf: DemiBuffer = DemiBuffer::from_slice(chunk)?;
+                transport.push(&mut self.socket, &mut buf, None).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Feeds the next bytes that arrive from the peer to the TLS session. The caller must hold the receiving lock.
+    async fn receive_tls_records(&mut self) -> Result<(), Fail> {
+        debug_assert!(self.tls_receiving);
+        let mut state_machine: SocketStateMachine = self.state_machine.clone();
+        let mut transport: T = self.transport.clone();
+        let buf: DemiBuffer = {
+            let state_tracker = state_machine.while_may_pop().fuse();
+            let operation = transport.pop(&mut self.socket, limits::RECVBUF_SIZE_MAX).fuse();
+            pin_mut!(state_tracker);
+            pin_mut!(operation);
+
+            select_biased! {
+                fail = state_tracker => return Err(fail),
+                result = operation => result?.1,
+            }
+        };
+        if buf.len() == 0 {
+            let cause: &str = "connection closed without a TLS close_notify";
+            warn!("receive_tls_records(): {}", cause);
+            return Err(Fail::new(libc::ECONNABORTED, cause));
+        }
+        self.tls_session().receive(&buf[..])
+    }
+
+    fn tls_session(&mut self) -> &mut TlsSession {
+        expect_some!(self.tls.as_mut(), "queue must run a TLS session")
+    }
+
+    async fn lock_tls_sending(&mut self) -> TlsLockGuard<T> {
+        loop {
+            if !self.tls_sending {
+                break;
+            }
+            self.tls_cv.clone().wait().await;
+        }
+        self.tls_sending = true;
+        TlsLockGuard {
+            queue: self.clone(),
+            sending: true,
+        }
+    }
+
+    fn unlock_tls_sending(&mut self) {
+        self.tls_sending = false;
+        self.tls_cv.broadcast();
+    }
+
+    async fn lock_tls_receiving(&mut self) -> TlsLockGuard<T> {
+        loop {
+            if !self.tls_receiving {
+                break;
+            }
+            self.tls_cv.clone().wait().await;
+        }
+        self.tls_receiving = true;
+        TlsLockGuard {
+            queue: self.clone(),
+            sending: false,
+        }
+    }
+
+    fn unlock_tls_receiving(&mut self) {
+        self.tls_receiving = false;
+        self.tls_cv.broadcast();
+    }
+
     /// Generic function for spawning a control-path coroutine on [self].
     fn do_generic_sync_control_path_call<F>(&mut self, coroutine_constructor: F) -> Result<QToken, Fail>
     where
@@ -345,6 +819,46 @@ impl<T: NetworkTransport> SharedNetworkQueue<T> {
         }
     }
 
+    /// Sets [option] on the underlying socket.
+    pub fn set_socket_option(&mut self, option: SocketOption) -> Result<(), Fail> {
+        self.transport.clone().set_socket_option(&mut self.socket, option)
+    }
+
+    /// Attaches [filter] to the underlying socket, or detaches the current one if [filter] is `None`.
+    pub fn set_receive_filter(&mut self, filter: Option<SocketFilter>) -> Result<(), Fail> {
+        self.transport.clone().set_receive_filter(&mut self.socket, filter)
+    }
+
+    /// Gets the current value of [option] on the underlying socket.
+    pub fn get_socket_option(&mut self, option: SocketOption) -> Result<SocketOption, Fail> {
+        self.transport.clone().get_socket_option(&mut self.socket, option)
+    }
+
+    /// Gets the number of incoming packets that were dropped on their way to the underlying socket, for each reason.
+    pub fn get_drop_stats(&mut self) -> Result<DropStats, Fail> {
+        self.transport.clone().get_socket_drop_stats(&mut self.socket)
+    }
+
+    /// Pops the oldest record from the error queue of the underlying socket.
+    pub fn pop_drop_record(&mut self) -> Result<Option<DropRecord>, Fail> {
+        self.transport.clone().pop_drop_record(&mut self.socket)
+    }
+
+    /// Pops the oldest transmit timestamp of the underlying socket.
+    pub fn pop_tx_timestamp(&mut self) -> Result<Option<TxTimestamp>, Fail> {
+        self.transport.clone().pop_tx_timestamp(&mut self.socket)
+    }
+
+    /// Gets a snapshot of the state of the underlying TCP connection.
+    pub fn get_tcp_info(&mut self) -> Result<TcpInfo, Fail> {
+        self.transport.clone().get_tcp_info(&mut self.socket)
+    }
+
+    /// Gets the recent retransmission and flow control events of the underlying TCP connection.
+    pub fn get_tcp_timeline(&mut self) -> Result<Vec<TcpEvent>, Fail> {
+        self.transport.clone().get_tcp_timeline(&mut self.socket)
+    }
+
     pub fn local(&self) -> Option<SocketAddr> {
         self.local
     }
@@ -352,6 +866,11 @@ impl<T: NetworkTransport> SharedNetworkQueue<T> {
     pub fn remote(&self) -> Option<SocketAddr> {
         self.remote
     }
+
+    /// Is this queue listening for connections?
+    pub fn is_listening(&self) -> bool {
+        self.state_machine.may_accept().is_ok()
+    }
 }
 
 //======================================================================================================================
@@ -376,6 +895,16 @@ impl<T: NetworkTransport> IoQueue for SharedNetworkQueue<T> {
     }
 }
 
+impl<T: NetworkTransport> Drop for TlsLockGuard<T> {
+    fn drop(&mut self) {
+        if self.sending {
+            self.queue.unlock_tls_sending();
+        } else {
+            self.queue.unlock_tls_receiving();
+        }
+    }
+}
+
 impl<T: NetworkTransport> Deref for SharedNetworkQueue<T> {
     type Target = NetworkQueue<T>;
 
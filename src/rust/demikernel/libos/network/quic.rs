@@ -0,0 +1,635 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    demikernel::libos::network::queue::SharedNetworkQueue,
+    expect_some,
+    runtime::{
+        conditional_yield_until,
+        fail::Fail,
+        memory::DemiBuffer,
+        network::{
+            quic::{
+                connection::QuicConnection,
+                QuicConfig,
+                NO_ERROR,
+            },
+            transport::NetworkTransport,
+        },
+        queue::{
+            IoQueue,
+            QType,
+        },
+        SharedConditionVariable,
+        SharedDemiRuntime,
+        SharedObject,
+    },
+};
+use ::futures::{
+    pin_mut,
+    select_biased,
+    FutureExt,
+};
+use ::std::{
+    any::Any,
+    collections::{
+        HashMap,
+        VecDeque,
+    },
+    net::SocketAddr,
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    time::Instant,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// QUIC connections that share a UDP socket. A single coroutine drives all of them: it feeds them the datagrams that
+/// arrive, sends the datagrams that they build and fires their timers. Connections are told apart by the address of
+/// the peer, so peers may not migrate.
+pub struct QuicEndpoint<T: NetworkTransport> {
+    runtime: SharedDemiRuntime,
+    /// UDP socket that carries the packets of all connections.
+    udp: SharedNetworkQueue<T>,
+    /// Configuration of the connections.
+    config: QuicConfig,
+    /// Connections by the address of the peer.
+    connections: HashMap<SocketAddr, SharedObject<QuicConnection>>,
+    /// Number of connections from clients that may wait to be accepted, if the endpoint listens.
+    backlog: Option<usize>,
+    /// Connections from clients that were not accepted yet.
+    incoming: VecDeque<(SocketAddr, SharedObject<QuicConnection>)>,
+    /// Number of QUIC socket queues that use the endpoint. The endpoint shuts down once all of them are closed.
+    num_queues: usize,
+    /// Whether a queue changed a connection since the driver last looked at them.
+    dirty: bool,
+    /// Whether the driver stopped and closed the UDP socket.
+    shut_down: bool,
+    /// Wakes the driver.
+    driver_cv: SharedConditionVariable,
+    /// Wakes coroutines that wait for progress on any connection.
+    progress_cv: SharedConditionVariable,
+}
+
+#[derive(Clone)]
+pub struct SharedQuicEndpoint<T: NetworkTransport>(SharedObject<QuicEndpoint<T>>);
+
+/// A QUIC socket, which either listens for connections or is a single connection.
+pub struct QuicQueue<T: NetworkTransport> {
+    endpoint: SharedQuicEndpoint<T>,
+    /// The connection of the queue, along with the address of the peer, unless the queue listens or is not connected.
+    connection: Option<(SocketAddr, SharedObject<QuicConnection>)>,
+    /// Whether the queue is being closed.
+    closing: bool,
+}
+
+#[derive(Clone)]
+pub struct SharedQuicQueue<T: NetworkTransport>(SharedObject<QuicQueue<T>>);
+
+/// A bidirectional stream of a QUIC connection.
+pub struct QuicStreamQueue<T: NetworkTransport> {
+    endpoint: SharedQuicEndpoint<T>,
+    /// Address of the peer of the connection.
+    remote: SocketAddr,
+    connection: SharedObject<QuicConnection>,
+    stream_id: u64,
+    /// Whether the queue is being closed.
+    closing: bool,
+}
+
+#[derive(Clone)]
+pub struct SharedQuicStreamQueue<T: NetworkTransport>(SharedObject<QuicStreamQueue<T>>);
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl<T: NetworkTransport> SharedQuicEndpoint<T> {
+    /// Creates an endpoint over the bound UDP socket [udp] and starts its driver.
+    fn new(mut runtime: SharedDemiRuntime, udp: SharedNetworkQueue<T>, config: QuicConfig) -> Result<Self, Fail> {
+        let endpoint: Self = Self(SharedObject::new(QuicEndpoint::<T> {
+            runtime: runtime.clone(),
+            udp,
+            config,
+            connections: HashMap::new(),
+            backlog: None,
+            incoming: VecDeque::new(),
+            num_queues: 1,
+            dirty: false,
+            shut_down: false,
+            driver_cv: SharedConditionVariable::default(),
+            progress_cv: SharedConditionVariable::default(),
+        }));
+        let coroutine = Box::pin(endpoint.clone().drive().fuse());
+        runtime.insert_background_coroutine("QuicEndpoint::drive", coroutine)?;
+        Ok(endpoint)
+    }
+
+    /// Drives the connections of the endpoint until all QUIC socket queues are closed, then closes the UDP socket.
+    async fn drive(mut self) {
+        let mut udp: SharedNetworkQueue<T> = self.udp.clone();
+        loop {
+            self.dirty = false;
+            let now: Instant = self.runtime.get_now();
+            for connection in self.connections.values_mut() {
+                if connection.timeout().map_or(false, |timeout| now >= timeout) {
+                    connection.on_timeout(now);
+                }
+            }
+            self.transmit(now).await;
+            self.connections.retain(|_, connection| !connection.is_closed());
+            self.incoming.retain(|(_, connection)| !connection.is_closed());
+            self.progress_cv.broadcast();
+            if self.num_queues == 0 {
+                break;
+            }
+            if self.dirty {
+                continue;
+            }
+
+            // Wait for a datagram, a change that a queue made, or the next timer.
+            let deadline: Option<Instant> = self.connections.values().filter_map(|c| c.timeout()).min();
+            let mut driver_cv: SharedConditionVariable = self.driver_cv.clone();
+            let operation = udp.pop_coroutine(None).fuse();
+            let kicked = driver_cv.wait().fuse();
+            pin_mut!(operation);
+            pin_mut!(kicked);
+            let event = async {
+                select_biased! {
+                    result = operation => Some(result),
+                    _ = kicked => None,
+                }
+            };
+            match conditional_yield_until(event, deadline).await {
+                Ok(Some(Ok((Some(remote), buf, _)))) => self.receive(remote, &buf[..]),
+                Ok(Some(Ok((None, _, _)))) => warn!("drive(): dropping datagram without a source address"),
+                Ok(Some(Err(e))) => warn!("drive(): failed to receive a datagram ({:?})", e),
+                // Either a queue changed a connection or a timer expired.
+                Ok(None) | Err(_) => (),
+            }
+        }
+
+        if let Err(e) = udp.hard_close() {
+            warn!("drive(): failed to close UDP socket ({:?})", e);
+        }
+        self.shut_down = true;
+        self.progress_cv.broadcast();
+    }
+
+    /// Sends the datagrams that the connections built.
+    async fn transmit(&mut self, now: Instant) {
+        let mut datagrams: Vec<(SocketAddr, Vec<u8>)> = Vec::new();
+        for (remote, connection) in self.connections.iter_mut() {
+            while let Some(datagram) = connection.poll_transmit(now) {
+                datagrams.push((*remote, datagram));
+            }
+        }
+        let mut udp: SharedNetworkQueue<T> = self.udp.clone();
+        for (remote, datagram) in datagrams {
+            let mut buf: DemiBuffer = match DemiBuffer::from_slice(&datagram) {
+                Ok(buf) => buf,
+                Err(e) => {
+                    warn!("transmit(): failed to allocate datagram ({:?})", e);
+                    continue;
+                },
+            };
+            if let Err(e) = udp.push_coroutine(&mut buf, Some(remote)).await {
+                warn!(
+                    "transmit(): failed to send datagram (remote={:?}, error={:?})",
+                    remote, e
+                );
+            }
+        }
+    }
+
+    /// Hands the [datagram] that arrived from [remote] to its connection. If the endpoint listens and there is room in
+    /// the backlog, a new connection is accepted.
+    fn receive(&mut self, remote: SocketAddr, datagram: &[u8]) {
+        let now: Instant = self.runtime.get_now();
+        if let Some(connection) = self.connections.get_mut(&remote) {
+            connection.receive(datagram, now);
+            return;
+        }
+        match self.backlog {
+            Some(backlog) if self.incoming.len() < backlog => (),
+            _ => {
+                trace!("receive(): dropping datagram of unknown peer (remote={:?})", remote);
+                return;
+            },
+        }
+        if let Ok(connection) = QuicConnection::accept(self.config.clone(), datagram, now) {
+            let connection: SharedObject<QuicConnection> = SharedObject::new(connection);
+            self.connections.insert(remote, connection.clone());
+            self.incoming.push_back((remote, connection));
+        }
+    }
+
+    /// Starts a connection to [remote].
+    fn connect(&mut self, remote: SocketAddr) -> Result<SharedObject<QuicConnection>, Fail> {
+        if self.connections.contains_key(&remote) {
+            let cause: String = format!("endpoint is already connected to peer (remote={:?})", remote);
+            error!("connect(): {}", cause);
+            return Err(Fail::new(libc::EADDRINUSE, &cause));
+        }
+        let now: Instant = self.runtime.get_now();
+        let connection: SharedObject<QuicConnection> =
+            SharedObject::new(QuicConnection::connect(self.config.clone(), now));
+        self.connections.insert(remote, connection.clone());
+        self.kick();
+        Ok(connection)
+    }
+
+    /// Wakes the driver to look at the connections, which a queue changed.
+    fn kick(&mut self) {
+        self.dirty = true;
+        self.driver_cv.signal();
+    }
+
+    /// Waits until the driver looks at the connections again.
+    async fn wait_for_progress(&mut self) {
+        self.progress_cv.clone().wait().await
+    }
+
+    /// Lets go of the endpoint on behalf of a QUIC socket queue and returns whether it was the last one.
+    fn release(&mut self) -> bool {
+        debug_assert!(self.num_queues > 0);
+        self.num_queues -= 1;
+        if self.num_queues > 0 {
+            return false;
+        }
+        // Let peers of connections that were never accepted know that they are gone.
+        let now: Instant = self.runtime.get_now();
+        for connection in self.connections.values_mut() {
+            connection.close(NO_ERROR, now);
+        }
+        self.kick();
+        true
+    }
+}
+
+impl<T: NetworkTransport> SharedQuicQueue<T> {
+    /// Creates a QUIC socket over the bound UDP socket [udp].
+    pub fn new(runtime: SharedDemiRuntime, udp: SharedNetworkQueue<T>, config: QuicConfig) -> Result<Self, Fail> {
+        if udp.get_qtype() != QType::UdpSocket {
+            let cause: String = format!("QUIC only runs over UDP sockets (qtype={:?})", udp.get_qtype());
+            error!("new(): {}", cause);
+            return Err(Fail::new(libc::ENOTSUP, &cause));
+        }
+        if udp.local().is_none() {
+            let cause: &str = "socket is not bound";
+            error!("new(): {}", cause);
+            return Err(Fail::new(libc::EDESTADDRREQ, cause));
+        }
+        Ok(Self(SharedObject::new(QuicQueue::<T> {
+            endpoint: SharedQuicEndpoint::new(runtime, udp, config)?,
+            connection: None,
+            closing: false,
+        })))
+    }
+
+    /// Sets the target queue to accept up to [backlog] pending connections.
+    pub fn listen(&mut self, backlog: usize) -> Result<(), Fail> {
+        self.ensure_not_closing()?;
+        if self.connection.is_some() || self.endpoint.backlog.is_some() {
+            let cause: &str = "socket is already connected or listening";
+            error!("listen(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, cause));
+        }
+        self.endpoint.backlog = Some(backlog);
+        Ok(())
+    }
+
+    /// Starts a connection to [remote] on the target queue, which completes in [connect_coroutine].
+    pub fn connect(&mut self, remote: SocketAddr) -> Result<(), Fail> {
+        self.ensure_not_closing()?;
+        if self.connection.is_some() || self.endpoint.backlog.is_some() {
+            let cause: &str = "socket is already connected or listening";
+            error!("connect(): {}", cause);
+            return Err(Fail::new(libc::EISCONN, cause));
+        }
+        let connection: SharedObject<QuicConnection> = self.endpoint.connect(remote)?;
+        self.connection = Some((remote, connection));
+        Ok(())
+    }
+
+    /// Waits until the handshake of the connection completes.
+    pub async fn connect_coroutine(&mut self) -> Result<(), Fail> {
+        let (_, connection): (SocketAddr, SharedObject<QuicConnection>) = self.get_connection("connect_coroutine")?;
+        loop {
+            if connection.is_established() {
+                return Ok(());
+            }
+            if connection.is_closed() || connection.error().is_some() {
+                return Err(connection_error(&connection));
+            }
+            self.endpoint.wait_for_progress().await;
+        }
+    }
+
+    /// Waits for a client to connect to the target queue, which must listen, and returns the connection.
+    pub async fn accept_connection_coroutine(&mut self) -> Result<Self, Fail> {
+        loop {
+            self.ensure_not_closing()?;
+            let position: Option<usize> = self
+                .endpoint
+                .incoming
+                .iter()
+                .position(|(_, connection)| connection.is_established());
+            if let Some(position) = position {
+                let connection = expect_some!(self.endpoint.incoming.remove(position), "connection should exist");
+                self.endpoint.num_queues += 1;
+                return Ok(Self(SharedObject::new(QuicQueue::<T> {
+                    endpoint: self.endpoint.clone(),
+                    connection: Some(connection),
+                    closing: false,
+                })));
+            }
+            self.endpoint.wait_for_progress().await;
+        }
+    }
+
+    /// Waits for the peer to open a stream on the connection of the target queue and returns the stream.
+    pub async fn accept_stream_coroutine(&mut self) -> Result<SharedQuicStreamQueue<T>, Fail> {
+        let (remote, mut connection): (SocketAddr, SharedObject<QuicConnection>) =
+            self.get_connection("accept_stream_coroutine")?;
+        loop {
+            self.ensure_not_closing()?;
+            if let Some(stream_id) = connection.accept_stream() {
+                return Ok(SharedQuicStreamQueue::new(
+                    self.endpoint.clone(),
+                    remote,
+                    connection,
+                    stream_id,
+                ));
+            }
+            if connection.is_closed() || connection.error().is_some() {
+                return Err(connection_error(&connection));
+            }
+            self.endpoint.wait_for_progress().await;
+        }
+    }
+
+    /// Opens a stream on the connection of the target queue.
+    pub fn open_stream(&mut self) -> Result<SharedQuicStreamQueue<T>, Fail> {
+        self.ensure_not_closing()?;
+        let (remote, mut connection): (SocketAddr, SharedObject<QuicConnection>) =
+            self.get_connection("open_stream")?;
+        let stream_id: u64 = connection.open_stream()?;
+        Ok(SharedQuicStreamQueue::new(
+            self.endpoint.clone(),
+            remote,
+            connection,
+            stream_id,
+        ))
+    }
+
+    /// Starts closing the target queue.
+    pub fn close(&mut self) -> Result<(), Fail> {
+        self.ensure_not_closing()?;
+        self.closing = true;
+        // Wake coroutines that wait to accept on the queue.
+        self.endpoint.progress_cv.broadcast();
+        Ok(())
+    }
+
+    /// Closes the connection of the target queue, if any, and lets go of the endpoint. Returns the address of the UDP
+    /// socket if this was the last queue of the endpoint, which then closed the socket.
+    pub async fn close_coroutine(&mut self) -> Result<Option<SocketAddr>, Fail> {
+        let now: Instant = self.endpoint.runtime.get_now();
+        if let Some((_, connection)) = self.connection.as_mut() {
+            connection.close(NO_ERROR, now);
+            // Wait for the driver to send the CONNECTION_CLOSE frame.
+            self.endpoint.kick();
+            self.endpoint.wait_for_progress().await;
+        }
+        if !self.endpoint.release() {
+            return Ok(None);
+        }
+        loop {
+            if self.endpoint.shut_down {
+                break;
+            }
+            self.endpoint.wait_for_progress().await;
+        }
+        Ok(self.endpoint.udp.local())
+    }
+
+    /// Closes the target queue right away.
+    pub fn hard_close(&mut self) -> Result<(), Fail> {
+        if !self.closing {
+            self.closing = true;
+            if self.endpoint.release() {
+                return self.endpoint.udp.hard_close();
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks whether the target queue is a connection, rather than a listening or unconnected socket.
+    pub fn is_connection(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    /// Gets the address of the peer, if the target queue is a connection.
+    pub fn remote(&self) -> Option<SocketAddr> {
+        self.connection.as_ref().map(|(remote, _)| *remote)
+    }
+
+    /// Gets the address of the UDP socket that the target queue runs over.
+    pub fn local(&self) -> Option<SocketAddr> {
+        self.endpoint.udp.local()
+    }
+
+    fn get_connection(&self, caller: &str) -> Result<(SocketAddr, SharedObject<QuicConnection>), Fail> {
+        match self.connection.as_ref() {
+            Some((remote, connection)) => Ok((*remote, connection.clone())),
+            None => {
+                let cause: &str = "socket is not connected";
+                error!("{}(): {}", caller, cause);
+                Err(Fail::new(libc::ENOTCONN, cause))
+            },
+        }
+    }
+
+    fn ensure_not_closing(&self) -> Result<(), Fail> {
+        if self.closing {
+            let cause: &str = "socket is closing";
+            error!("ensure_not_closing(): {}", cause);
+            return Err(Fail::new(libc::EBADF, cause));
+        }
+        Ok(())
+    }
+}
+
+impl<T: NetworkTransport> SharedQuicStreamQueue<T> {
+    fn new(
+        endpoint: SharedQuicEndpoint<T>,
+        remote: SocketAddr,
+        connection: SharedObject<QuicConnection>,
+        stream_id: u64,
+    ) -> Self {
+        Self(SharedObject::new(QuicStreamQueue::<T> {
+            endpoint,
+            remote,
+            connection,
+            stream_id,
+            closing: false,
+        }))
+    }
+
+    /// Queues the data in [buf] on the stream.
+    pub fn push(&mut self, buf: &mut DemiBuffer) -> Result<(), Fail> {
+        self.ensure_not_closing()?;
+        let stream_id: u64 = self.stream_id;
+        self.connection.stream_write(stream_id, &buf[..])?;
+        self.endpoint.kick();
+        buf.trim(buf.len())
+    }
+
+    /// Waits for up to [size] bytes to arrive on the stream. An empty buffer means that the peer ended the stream.
+    pub async fn pop_coroutine(&mut self, size: usize) -> Result<DemiBuffer, Fail> {
+        let stream_id: u64 = self.stream_id;
+        loop {
+            self.ensure_not_closing()?;
+            if let Some(data) = self.connection.stream_read(stream_id, size)? {
+                // Reading may open the flow control window of the peer.
+                self.endpoint.kick();
+                return DemiBuffer::from_slice(&data);
+            }
+            if self.connection.is_closed() {
+                return Err(connection_error(&self.connection));
+            }
+            self.endpoint.wait_for_progress().await;
+        }
+    }
+
+    /// Closes the stream. Data that was pushed is still delivered.
+    pub fn close(&mut self) -> Result<(), Fail> {
+        self.ensure_not_closing()?;
+        self.closing = true;
+        let stream_id: u64 = self.stream_id;
+        if let Err(e) = self.connection.stream_close(stream_id) {
+            // The stream is gone along with its connection.
+            debug!("close(): {:?}", e);
+        }
+        self.endpoint.kick();
+        // Wake coroutines that wait to pop from the stream.
+        self.endpoint.progress_cv.broadcast();
+        Ok(())
+    }
+
+    pub fn remote(&self) -> SocketAddr {
+        self.remote
+    }
+
+    fn ensure_not_closing(&self) -> Result<(), Fail> {
+        if self.closing {
+            let cause: &str = "stream is closing";
+            error!("ensure_not_closing(): {}", cause);
+            return Err(Fail::new(libc::EBADF, cause));
+        }
+        Ok(())
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Gets the error that closed [connection].
+fn connection_error(connection: &QuicConnection) -> Fail {
+    match connection.error() {
+        Some(e) => e,
+        None => Fail::new(libc::ECONNABORTED, "connection was closed"),
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl<T: NetworkTransport> IoQueue for SharedQuicQueue<T> {
+    fn get_qtype(&self) -> QType {
+        QType::QuicSocket
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+impl<T: NetworkTransport> IoQueue for SharedQuicStreamQueue<T> {
+    fn get_qtype(&self) -> QType {
+        QType::QuicStream
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+impl<T: NetworkTransport> Deref for SharedQuicEndpoint<T> {
+    type Target = QuicEndpoint<T>;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl<T: NetworkTransport> DerefMut for SharedQuicEndpoint<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+impl<T: NetworkTransport> Deref for SharedQuicQueue<T> {
+    type Target = QuicQueue<T>;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl<T: NetworkTransport> DerefMut for SharedQuicQueue<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+impl<T: NetworkTransport> Deref for SharedQuicStreamQueue<T> {
+    type Target = QuicStreamQueue<T>;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl<T: NetworkTransport> DerefMut for SharedQuicStreamQueue<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
@@ -0,0 +1,210 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+mod verbs;
+
+//======================================================================================================================
+// Exports
+//======================================================================================================================
+
+pub use self::verbs::{
+    RdmaRegion,
+    SharedRdmaDriver,
+};
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use self::verbs::{
+    CmId,
+    SharedRdmaConnection,
+};
+use crate::runtime::{
+    fail::Fail,
+    memory::DemiBuffer,
+    queue::{
+        IoQueue,
+        QType,
+    },
+    SharedObject,
+};
+use ::std::{
+    any::Any,
+    mem,
+    net::SocketAddrV4,
+    ops::{
+        Deref,
+        DerefMut,
+    },
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Where an RDMA queue stands in its lifetime.
+enum RdmaState {
+    /// Not connected yet, and possibly bound.
+    Unconnected(CmId),
+    /// Listening for connection requests.
+    Listening(CmId),
+    /// Connecting, or failed to.
+    Connecting,
+    Connected(SharedRdmaConnection),
+}
+
+/// A queue that runs a reliable connection over RDMA, such as RoCEv2, which the RDMA connection manager sets up from IP
+/// addresses. Like TCP sockets, queues are bound, listen, accept and connect. Pushes and pops send and receive whole
+/// messages, while one-sided reads and writes access memory that the peer exposed, without involving its CPU.
+pub struct RdmaQueue {
+    driver: SharedRdmaDriver,
+    state: RdmaState,
+}
+
+#[derive(Clone)]
+pub struct SharedRdmaQueue(SharedObject<RdmaQueue>);
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl SharedRdmaQueue {
+    pub fn new(driver: SharedRdmaDriver) -> Result<Self, Fail> {
+        Ok(Self(SharedObject::new(RdmaQueue {
+            driver,
+            state: RdmaState::Unconnected(CmId::new()?),
+        })))
+    }
+
+    /// Binds the queue to [local], which selects the RDMA device that it runs on.
+    pub fn bind(&mut self, local: SocketAddrV4) -> Result<(), Fail> {
+        match self.state {
+            RdmaState::Unconnected(ref mut cm) => cm.bind(local),
+            _ => Err(Fail::new(libc::EINVAL, "queue is already in use")),
+        }
+    }
+
+    /// Starts listening for connection requests on the bound queue.
+    pub fn listen(&mut self, backlog: usize) -> Result<(), Fail> {
+        match mem::replace(&mut self.state, RdmaState::Connecting) {
+            RdmaState::Unconnected(mut cm) => {
+                let result: Result<(), Fail> = cm.listen(backlog);
+                self.state = match result {
+                    Ok(()) => RdmaState::Listening(cm),
+                    Err(_) => RdmaState::Unconnected(cm),
+                };
+                result
+            },
+            state => {
+                self.state = state;
+                Err(Fail::new(libc::EINVAL, "queue is already in use"))
+            },
+        }
+    }
+
+    /// Waits for a connection request and accepts it. Returns the queue of the new connection.
+    pub async fn accept(&mut self) -> Result<(Self, SocketAddrV4), Fail> {
+        let mut driver: SharedRdmaDriver = self.driver.clone();
+        let cm: CmId = match self.state {
+            RdmaState::Listening(ref mut cm) => cm.next_request().await?,
+            _ => return Err(Fail::new(libc::EINVAL, "queue is not listening")),
+        };
+        let connection: SharedRdmaConnection = SharedRdmaConnection::accept(cm, &mut driver).await?;
+        let remote: SocketAddrV4 = connection.remote();
+        let queue: Self = Self(SharedObject::new(RdmaQueue {
+            driver,
+            state: RdmaState::Connected(connection),
+        }));
+        Ok((queue, remote))
+    }
+
+    /// Connects the queue to [remote]. A queue that fails to connect cannot be used any more.
+    pub async fn connect(&mut self, remote: SocketAddrV4) -> Result<(), Fail> {
+        let mut driver: SharedRdmaDriver = self.driver.clone();
+        let cm: CmId = match mem::replace(&mut self.state, RdmaState::Connecting) {
+            RdmaState::Unconnected(cm) => cm,
+            state => {
+                self.state = state;
+                return Err(Fail::new(libc::EISCONN, "queue is already in use"));
+            },
+        };
+        let connection: SharedRdmaConnection = SharedRdmaConnection::connect(cm, &mut driver, remote).await?;
+        self.state = RdmaState::Connected(connection);
+        Ok(())
+    }
+
+    /// Sends [buf] as a message.
+    pub async fn push(&mut self, buf: DemiBuffer) -> Result<(), Fail> {
+        self.connection()?.send(&buf).await
+    }
+
+    /// Waits for the next message. Returns an empty buffer once the peer disconnected.
+    pub async fn pop(&mut self) -> Result<DemiBuffer, Fail> {
+        self.connection()?.receive().await
+    }
+
+    /// Writes [buf] to the memory of the peer that [region] describes.
+    pub async fn write(&mut self, buf: DemiBuffer, region: RdmaRegion) -> Result<(), Fail> {
+        self.connection()?.write(&buf, region).await
+    }
+
+    /// Reads the memory of the peer that [region] describes.
+    pub async fn read(&mut self, region: RdmaRegion) -> Result<DemiBuffer, Fail> {
+        self.connection()?.read(region).await
+    }
+
+    /// Lets the peer read and write [buf] with one-sided verbs, until the returned region is withdrawn.
+    pub fn expose(&mut self, buf: DemiBuffer) -> Result<RdmaRegion, Fail> {
+        self.connection()?.expose(buf)
+    }
+
+    /// Withdraws the region with remote key [rkey].
+    pub fn withdraw(&mut self, rkey: u32) -> Result<(), Fail> {
+        self.connection()?.withdraw(rkey)
+    }
+
+    /// Returns the connection of the queue, if it is connected.
+    fn connection(&self) -> Result<SharedRdmaConnection, Fail> {
+        match self.state {
+            RdmaState::Connected(ref connection) => Ok(connection.clone()),
+            _ => Err(Fail::new(libc::ENOTCONN, "queue is not connected")),
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl IoQueue for SharedRdmaQueue {
+    fn get_qtype(&self) -> QType {
+        QType::RdmaQueue
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+impl Deref for SharedRdmaQueue {
+    type Target = RdmaQueue;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl DerefMut for SharedRdmaQueue {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
@@ -0,0 +1,1011 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    pal::linux::{
+        sockaddr_to_socketaddrv4,
+        socketaddrv4_to_sockaddr,
+    },
+    runtime::{
+        fail::Fail,
+        limits,
+        memory::{
+            BufferPool,
+            DemiBuffer,
+        },
+        poll_yield,
+        SharedObject,
+    },
+};
+use ::rdma_rs::{
+    ibv_alloc_pd,
+    ibv_context,
+    ibv_cq,
+    ibv_create_cq,
+    ibv_dealloc_pd,
+    ibv_dereg_mr,
+    ibv_destroy_cq,
+    ibv_mr,
+    ibv_pd,
+    ibv_poll_cq,
+    ibv_post_recv,
+    ibv_post_send,
+    ibv_qp_init_attr,
+    ibv_recv_wr,
+    ibv_reg_mr,
+    ibv_send_wr,
+    ibv_sge,
+    ibv_wc,
+    ibv_wr_opcode,
+    rdma_accept,
+    rdma_ack_cm_event,
+    rdma_bind_addr,
+    rdma_cm_event,
+    rdma_cm_event_type,
+    rdma_cm_id,
+    rdma_conn_param,
+    rdma_connect,
+    rdma_create_event_channel,
+    rdma_create_id,
+    rdma_create_qp,
+    rdma_destroy_event_channel,
+    rdma_destroy_id,
+    rdma_destroy_qp,
+    rdma_disconnect,
+    rdma_event_channel,
+    rdma_get_cm_event,
+    rdma_get_peer_addr,
+    rdma_listen,
+    rdma_migrate_id,
+    rdma_reject,
+    rdma_resolve_addr,
+    rdma_resolve_route,
+    IBV_ACCESS_LOCAL_WRITE,
+    IBV_ACCESS_REMOTE_READ,
+    IBV_ACCESS_REMOTE_WRITE,
+    IBV_QPT_RC,
+    IBV_SEND_SIGNALED,
+    IBV_WC_SUCCESS,
+    IBV_WC_WR_FLUSH_ERR,
+    IBV_WR_RDMA_READ,
+    IBV_WR_RDMA_WRITE,
+    IBV_WR_SEND,
+    RDMA_CM_EVENT_ADDR_ERROR,
+    RDMA_CM_EVENT_ADDR_RESOLVED,
+    RDMA_CM_EVENT_CONNECT_REQUEST,
+    RDMA_CM_EVENT_DISCONNECTED,
+    RDMA_CM_EVENT_ESTABLISHED,
+    RDMA_CM_EVENT_REJECTED,
+    RDMA_CM_EVENT_ROUTE_ERROR,
+    RDMA_CM_EVENT_ROUTE_RESOLVED,
+    RDMA_CM_EVENT_UNREACHABLE,
+    RDMA_PS_TCP,
+};
+use ::slab::Slab;
+use ::std::{
+    alloc::{
+        self,
+        Layout,
+    },
+    collections::{
+        HashMap,
+        VecDeque,
+    },
+    io,
+    mem::{
+        self,
+        MaybeUninit,
+    },
+    net::SocketAddrV4,
+    num::NonZeroUsize,
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    ptr::{
+        self,
+        NonNull,
+    },
+    slice,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Number of receives that are kept posted on each connection, which is also the number of sends, reads and writes that
+/// may be in flight on it.
+const QUEUE_DEPTH: u32 = 64;
+
+/// Number of one-sided reads that may be in flight in each direction of a connection.
+const READ_DEPTH: u8 = 4;
+
+/// Number of completions that are polled at once.
+const COMPLETION_BATCH_SIZE: usize = 16;
+
+/// Size of the memory region that backs the registered buffers of a device.
+const REGION_SIZE: usize = 32 * 1024 * 1024;
+
+/// Size of registered buffers, which bounds the size of messages and of one-sided reads.
+const BUFFER_SIZE: u16 = limits::RECVBUF_SIZE_MAX as u16;
+
+/// Page size used to pack registered buffers.
+const PAGE_SIZE: usize = 4096;
+
+/// Time allowed to resolve the address and the route of a peer, in milliseconds.
+const RESOLVE_TIMEOUT_MS: i32 = 2000;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// A protection domain on an RDMA device, along with a pool of buffers that are registered with it. Messages are sent
+/// from and received into these buffers, thus buffers that are popped from a connection are pushed again without a
+/// copy.
+pub struct RdmaDevice {
+    pd: *mut ibv_pd,
+    /// Region that backs the buffer pool.
+    base: NonNull<u8>,
+    mr: *mut ibv_mr,
+    pool: BufferPool,
+}
+
+#[derive(Clone)]
+pub struct SharedRdmaDevice(SharedObject<RdmaDevice>);
+
+/// Driver for RDMA devices, which keeps track of the devices that connections were established on.
+pub struct RdmaDriver {
+    /// Devices, by the address of their verbs context.
+    devices: HashMap<usize, SharedRdmaDevice>,
+}
+
+#[derive(Clone)]
+pub struct SharedRdmaDriver(SharedObject<RdmaDriver>);
+
+/// An identifier of the RDMA connection manager, which plays the role of a socket. Each one has its own event channel,
+/// which is polled without blocking.
+pub struct CmId {
+    channel: *mut rdma_event_channel,
+    id: *mut rdma_cm_id,
+}
+
+/// An event of the connection manager, which is copied out of the channel so that it can be acknowledged right away.
+struct CmEvent {
+    kind: rdma_cm_event_type,
+    status: i32,
+    /// Identifier that the event is about, which is a new one for connection requests.
+    id: *mut rdma_cm_id,
+}
+
+/// A memory region that the peer of a connection may read and write with one-sided verbs.
+#[derive(Clone, Copy, Debug)]
+pub struct RdmaRegion {
+    pub addr: u64,
+    pub len: u32,
+    pub rkey: u32,
+}
+
+/// A region that was exposed to the peer. It keeps the memory that it covers alive for as long as it is registered.
+struct ExposedRegion {
+    mr: *mut ibv_mr,
+    _buf: DemiBuffer,
+}
+
+/// A work request that was posted and did not complete yet. It keeps its buffer alive until the device is done with it,
+/// even if the coroutine that posted it is gone.
+struct Request {
+    /// Receives are queued once they complete, while other requests are waited on.
+    receive: bool,
+    buf: DemiBuffer,
+    /// Number of bytes transferred, once the request completes.
+    result: Option<Result<usize, Fail>>,
+    /// Set if the coroutine that posted the request is gone, so that its completion is discarded.
+    orphaned: bool,
+}
+
+/// A reliable connection over RDMA. It sends and receives messages, and it reads and writes memory that the peer
+/// exposed without involving its CPU. Completions are processed by whichever coroutine waits on the connection, thus no
+/// background coroutine is needed.
+pub struct RdmaConnection {
+    cm: CmId,
+    cq: *mut ibv_cq,
+    device: SharedRdmaDevice,
+    remote: SocketAddrV4,
+    /// Work requests in flight, by work request ID.
+    requests: Slab<Request>,
+    /// Messages that were received and not popped yet.
+    received: VecDeque<DemiBuffer>,
+    /// Regions that the peer may access, by remote key.
+    exposed: HashMap<u32, ExposedRegion>,
+    /// Set once either peer disconnected.
+    disconnected: bool,
+}
+
+#[derive(Clone)]
+pub struct SharedRdmaConnection(SharedObject<RdmaConnection>);
+
+/// Marks a request as orphaned if the coroutine that waits for it is dropped before it completes.
+struct RequestGuard {
+    connection: SharedRdmaConnection,
+    id: usize,
+    done: bool,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl SharedRdmaDevice {
+    /// Allocates a protection domain on the device of [verbs] and registers a region of buffers with it.
+    fn new(verbs: *mut ibv_context) -> Result<Self, Fail> {
+        let pd: *mut ibv_pd = unsafe { ibv_alloc_pd(verbs) };
+        if pd.is_null() {
+            let cause: &str = "failed to allocate protection domain";
+            error!("new(): {}", cause);
+            return Err(Fail::new(libc::ENOMEM, cause));
+        }
+
+        let layout: Layout = match Layout::from_size_align(REGION_SIZE, PAGE_SIZE) {
+            Ok(layout) => layout,
+            Err(_) => return Err(Fail::new(libc::EINVAL, "invalid layout for registered region")),
+        };
+        // Safety: the layout has a non-zero size.
+        let base: NonNull<u8> = match NonNull::new(unsafe { alloc::alloc_zeroed(layout) }) {
+            Some(base) => base,
+            None => {
+                unsafe { ibv_dealloc_pd(pd) };
+                return Err(Fail::new(libc::ENOMEM, "failed to allocate registered region"));
+            },
+        };
+        let mr: *mut ibv_mr = unsafe {
+            ibv_reg_mr(
+                pd,
+                base.as_ptr() as *mut libc::c_void,
+                REGION_SIZE,
+                IBV_ACCESS_LOCAL_WRITE as libc::c_int,
+            )
+        };
+        if mr.is_null() {
+            let e: Fail = Fail::from(io::Error::last_os_error());
+            error!("new(): failed to register region: {:?}", e);
+            unsafe {
+                alloc::dealloc(base.as_ptr(), layout);
+                ibv_dealloc_pd(pd);
+            }
+            return Err(e);
+        }
+
+        let pool: BufferPool = match BufferPool::new(BUFFER_SIZE) {
+            Ok(pool) => pool,
+            Err(_) => return Err(Fail::new(libc::EINVAL, "invalid layout for registered buffers")),
+        };
+        // Safety: the registered region is never freed, thus it outlives the pool.
+        unsafe {
+            pool.pool().populate(
+                NonNull::from(slice::from_raw_parts_mut(
+                    base.as_ptr() as *mut MaybeUninit<u8>,
+                    REGION_SIZE,
+                )),
+                NonZeroUsize::new(PAGE_SIZE).unwrap(),
+            )
+        }?;
+
+        Ok(Self(SharedObject::new(RdmaDevice { pd, base, mr, pool })))
+    }
+
+    /// Allocates a whole registered buffer.
+    fn alloc(&self) -> Result<DemiBuffer, Fail> {
+        match DemiBuffer::new_in_pool(&self.pool) {
+            Some(buf) => Ok(buf),
+            None => Err(Fail::new(libc::ENOBUFS, "out of registered buffers")),
+        }
+    }
+
+    /// Returns a registered buffer that holds the data of [buf]. If [buf] was allocated from registered memory, it is
+    /// used as is. Otherwise, its data is copied into a registered buffer.
+    fn registered(&self, buf: &DemiBuffer) -> Result<DemiBuffer, Fail> {
+        if self.contains(buf) {
+            return Ok(buf.clone());
+        }
+        if buf.len() > BUFFER_SIZE as usize {
+            let cause: String = format!(
+                "buffer does not fit in a registered buffer (len={:?}, max={:?})",
+                buf.len(),
+                BUFFER_SIZE
+            );
+            warn!("registered(): {}", cause);
+            return Err(Fail::new(libc::EMSGSIZE, &cause));
+        }
+        let mut copy: DemiBuffer = self.alloc()?;
+        copy.trim(copy.len() - buf.len())?;
+        copy.copy_from_slice(&buf[..]);
+        Ok(copy)
+    }
+
+    /// Checks whether [buf] lies within the registered region.
+    fn contains(&self, buf: &DemiBuffer) -> bool {
+        let start: usize = self.base.as_ptr() as usize;
+        let addr: usize = buf.as_ptr() as usize;
+        addr >= start && addr + buf.len() <= start + REGION_SIZE
+    }
+
+    /// Describes [buf], which lies within the registered region.
+    fn sge(&self, buf: &DemiBuffer) -> ibv_sge {
+        debug_assert!(self.contains(buf));
+        ibv_sge {
+            addr: buf.as_ptr() as u64,
+            length: buf.len() as u32,
+            lkey: unsafe { (*self.mr).lkey },
+        }
+    }
+}
+
+impl SharedRdmaDriver {
+    pub fn new() -> Self {
+        Self(SharedObject::new(RdmaDriver {
+            devices: HashMap::new(),
+        }))
+    }
+
+    /// Returns the device of [verbs], setting it up if no connection was established on it yet.
+    fn device(&mut self, verbs: *mut ibv_context) -> Result<SharedRdmaDevice, Fail> {
+        if let Some(device) = self.devices.get(&(verbs as usize)) {
+            return Ok(device.clone());
+        }
+        let device: SharedRdmaDevice = SharedRdmaDevice::new(verbs)?;
+        self.devices.insert(verbs as usize, device.clone());
+        Ok(device)
+    }
+}
+
+impl CmId {
+    /// Creates an identifier for reliable connections.
+    pub fn new() -> Result<Self, Fail> {
+        let channel: *mut rdma_event_channel = create_event_channel()?;
+        let mut id: *mut rdma_cm_id = ptr::null_mut();
+        if unsafe { rdma_create_id(channel, &mut id, ptr::null_mut(), RDMA_PS_TCP) } != 0 {
+            let e: Fail = Fail::from(io::Error::last_os_error());
+            error!("new(): failed to create identifier: {:?}", e);
+            unsafe { rdma_destroy_event_channel(channel) };
+            return Err(e);
+        }
+        Ok(Self { channel, id })
+    }
+
+    /// Takes over [id], which came with a connection request, by moving it to an event channel of its own. The request
+    /// is rejected on failure.
+    fn from_request(id: *mut rdma_cm_id) -> Result<Self, Fail> {
+        let channel: *mut rdma_event_channel = match create_event_channel() {
+            Ok(channel) => channel,
+            Err(e) => {
+                reject(id);
+                return Err(e);
+            },
+        };
+        if unsafe { rdma_migrate_id(id, channel) } != 0 {
+            let e: Fail = Fail::from(io::Error::last_os_error());
+            warn!("from_request(): failed to migrate identifier: {:?}", e);
+            reject(id);
+            unsafe { rdma_destroy_event_channel(channel) };
+            return Err(e);
+        }
+        Ok(Self { channel, id })
+    }
+
+    /// Binds the identifier to [local], which selects the device that it runs on.
+    pub fn bind(&mut self, local: SocketAddrV4) -> Result<(), Fail> {
+        let mut saddr: libc::sockaddr = socketaddrv4_to_sockaddr(&local);
+        if unsafe { rdma_bind_addr(self.id, &mut saddr as *mut libc::sockaddr as *mut _) } != 0 {
+            let e: Fail = Fail::from(io::Error::last_os_error());
+            warn!("bind(): failed to bind to {:?}: {:?}", local, e);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Starts listening for connection requests, keeping up to [backlog] of them pending.
+    pub fn listen(&mut self, backlog: usize) -> Result<(), Fail> {
+        if unsafe { rdma_listen(self.id, backlog as libc::c_int) } != 0 {
+            let e: Fail = Fail::from(io::Error::last_os_error());
+            warn!("listen(): {:?}", e);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Waits for the next connection request and takes over its identifier.
+    pub async fn next_request(&mut self) -> Result<CmId, Fail> {
+        loop {
+            let event: CmEvent = self.next_event().await?;
+            if event.kind == RDMA_CM_EVENT_CONNECT_REQUEST {
+                return CmId::from_request(event.id);
+            }
+            trace!("next_request(): ignoring event (kind={:?})", event.kind);
+        }
+    }
+
+    /// Resolves the address and the route of [remote], which selects the device that the identifier runs on.
+    async fn resolve(&mut self, remote: SocketAddrV4) -> Result<(), Fail> {
+        let mut saddr: libc::sockaddr = socketaddrv4_to_sockaddr(&remote);
+        let ret: libc::c_int = unsafe {
+            rdma_resolve_addr(
+                self.id,
+                ptr::null_mut(),
+                &mut saddr as *mut libc::sockaddr as *mut _,
+                RESOLVE_TIMEOUT_MS,
+            )
+        };
+        if ret != 0 {
+            return Err(Fail::from(io::Error::last_os_error()));
+        }
+        expect_event(self.next_event().await?, RDMA_CM_EVENT_ADDR_RESOLVED)?;
+
+        if unsafe { rdma_resolve_route(self.id, RESOLVE_TIMEOUT_MS) } != 0 {
+            return Err(Fail::from(io::Error::last_os_error()));
+        }
+        expect_event(self.next_event().await?, RDMA_CM_EVENT_ROUTE_RESOLVED)
+    }
+
+    /// Returns the address of the peer, once the identifier is connected or was handed over with a request.
+    fn peer_addr(&self) -> Option<SocketAddrV4> {
+        let saddr: *const libc::sockaddr = unsafe { rdma_get_peer_addr(self.id) } as *const libc::sockaddr;
+        if saddr.is_null() {
+            return None;
+        }
+        sockaddr_to_socketaddrv4(unsafe { &*saddr })
+    }
+
+    /// Polls the event channel for an event, without waiting for one.
+    fn try_event(&mut self) -> Result<Option<CmEvent>, Fail> {
+        let mut event: *mut rdma_cm_event = ptr::null_mut();
+        if unsafe { rdma_get_cm_event(self.channel, &mut event) } != 0 {
+            let e: io::Error = io::Error::last_os_error();
+            return match e.raw_os_error() {
+                Some(libc::EAGAIN) => Ok(None),
+                _ => Err(Fail::from(e)),
+            };
+        }
+        let copy: CmEvent = unsafe {
+            CmEvent {
+                kind: (*event).event,
+                status: (*event).status,
+                id: (*event).id,
+            }
+        };
+        unsafe { rdma_ack_cm_event(event) };
+        Ok(Some(copy))
+    }
+
+    /// Waits for the next event.
+    async fn next_event(&mut self) -> Result<CmEvent, Fail> {
+        loop {
+            match self.try_event()? {
+                Some(event) => return Ok(event),
+                None => poll_yield().await,
+            }
+        }
+    }
+}
+
+impl SharedRdmaConnection {
+    /// Connects [cm] to [remote].
+    pub async fn connect(mut cm: CmId, driver: &mut SharedRdmaDriver, remote: SocketAddrV4) -> Result<Self, Fail> {
+        cm.resolve(remote).await?;
+        let mut connection: Self = Self::new(cm, driver, remote)?;
+        let mut param: rdma_conn_param = conn_param();
+        if unsafe { rdma_connect(connection.cm.id, &mut param) } != 0 {
+            return Err(Fail::from(io::Error::last_os_error()));
+        }
+        connection.established().await?;
+        Ok(connection)
+    }
+
+    /// Accepts the connection request that [cm] was handed over with.
+    pub async fn accept(cm: CmId, driver: &mut SharedRdmaDriver) -> Result<Self, Fail> {
+        let remote: SocketAddrV4 = match cm.peer_addr() {
+            Some(remote) => remote,
+            None => {
+                reject(cm.id);
+                return Err(Fail::new(libc::EAFNOSUPPORT, "we only support IPv4"));
+            },
+        };
+        let mut connection: Self = Self::new(cm, driver, remote)?;
+        let mut param: rdma_conn_param = conn_param();
+        if unsafe { rdma_accept(connection.cm.id, &mut param) } != 0 {
+            return Err(Fail::from(io::Error::last_os_error()));
+        }
+        connection.established().await?;
+        Ok(connection)
+    }
+
+    /// Sets up a reliable queue pair on [cm], which resolved its device, and posts receives on it.
+    fn new(cm: CmId, driver: &mut SharedRdmaDriver, remote: SocketAddrV4) -> Result<Self, Fail> {
+        let verbs: *mut ibv_context = unsafe { (*cm.id).verbs };
+        if verbs.is_null() {
+            let cause: &str = "identifier is not bound to a device";
+            warn!("new(): {}", cause);
+            return Err(Fail::new(libc::ENODEV, cause));
+        }
+        let device: SharedRdmaDevice = driver.device(verbs)?;
+
+        let cq: *mut ibv_cq = unsafe {
+            ibv_create_cq(
+                verbs,
+                2 * QUEUE_DEPTH as libc::c_int,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                0,
+            )
+        };
+        if cq.is_null() {
+            let e: Fail = Fail::from(io::Error::last_os_error());
+            error!("new(): failed to create completion queue: {:?}", e);
+            return Err(e);
+        }
+        let mut attr: ibv_qp_init_attr = unsafe { mem::zeroed() };
+        attr.send_cq = cq;
+        attr.recv_cq = cq;
+        attr.cap.max_send_wr = QUEUE_DEPTH;
+        attr.cap.max_recv_wr = QUEUE_DEPTH;
+        attr.cap.max_send_sge = 1;
+        attr.cap.max_recv_sge = 1;
+        attr.qp_type = IBV_QPT_RC;
+        if unsafe { rdma_create_qp(cm.id, device.pd, &mut attr) } != 0 {
+            let e: Fail = Fail::from(io::Error::last_os_error());
+            error!("new(): failed to create queue pair: {:?}", e);
+            unsafe { ibv_destroy_cq(cq) };
+            return Err(e);
+        }
+
+        let mut connection: Self = Self(SharedObject::new(RdmaConnection {
+            cm,
+            cq,
+            device,
+            remote,
+            requests: Slab::with_capacity(2 * QUEUE_DEPTH as usize),
+            received: VecDeque::new(),
+            exposed: HashMap::new(),
+            disconnected: false,
+        }));
+        for _ in 0..QUEUE_DEPTH {
+            connection.post_receive()?;
+        }
+        Ok(connection)
+    }
+
+    /// Address of the peer.
+    pub fn remote(&self) -> SocketAddrV4 {
+        self.remote
+    }
+
+    /// Sends [buf] as a message.
+    pub async fn send(&mut self, buf: &DemiBuffer) -> Result<(), Fail> {
+        let buf: DemiBuffer = self.device.registered(buf)?;
+        let id: usize = self.post_send(IBV_WR_SEND, buf, None)?;
+        self.wait(id).await?;
+        Ok(())
+    }
+
+    /// Waits for the next message. Returns an empty buffer once the peer disconnected.
+    pub async fn receive(&mut self) -> Result<DemiBuffer, Fail> {
+        loop {
+            if let Some(buf) = self.received.pop_front() {
+                return Ok(buf);
+            }
+            if self.disconnected {
+                return Ok(DemiBuffer::new(0));
+            }
+            self.poll()?;
+            if self.received.is_empty() && !self.disconnected {
+                poll_yield().await;
+            }
+        }
+    }
+
+    /// Writes [buf] at the start of [region], which the peer exposed.
+    pub async fn write(&mut self, buf: &DemiBuffer, region: RdmaRegion) -> Result<(), Fail> {
+        if buf.len() > region.len as usize {
+            let cause: String = format!(
+                "buffer does not fit in the region (len={:?}, region_len={:?})",
+                buf.len(),
+                region.len
+            );
+            warn!("write(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        let buf: DemiBuffer = self.device.registered(buf)?;
+        let id: usize = self.post_send(IBV_WR_RDMA_WRITE, buf, Some(region))?;
+        self.wait(id).await?;
+        Ok(())
+    }
+
+    /// Reads the whole of [region], which the peer exposed.
+    pub async fn read(&mut self, region: RdmaRegion) -> Result<DemiBuffer, Fail> {
+        if region.len == 0 || region.len > BUFFER_SIZE as u32 {
+            let cause: String = format!(
+                "region does not fit in a registered buffer (len={:?}, max={:?})",
+                region.len, BUFFER_SIZE
+            );
+            warn!("read(): {}", cause);
+            return Err(Fail::new(libc::EMSGSIZE, &cause));
+        }
+        let mut buf: DemiBuffer = self.device.alloc()?;
+        buf.trim(buf.len() - region.len as usize)?;
+        let id: usize = self.post_send(IBV_WR_RDMA_READ, buf.clone(), Some(region))?;
+        self.wait(id).await?;
+        Ok(buf)
+    }
+
+    /// Lets the peer read and write [buf] with one-sided verbs, until the returned region is withdrawn.
+    pub fn expose(&mut self, buf: DemiBuffer) -> Result<RdmaRegion, Fail> {
+        let access: libc::c_int =
+            (IBV_ACCESS_LOCAL_WRITE | IBV_ACCESS_REMOTE_READ | IBV_ACCESS_REMOTE_WRITE) as libc::c_int;
+        let mr: *mut ibv_mr =
+            unsafe { ibv_reg_mr(self.device.pd, buf.as_ptr() as *mut libc::c_void, buf.len(), access) };
+        if mr.is_null() {
+            let e: Fail = Fail::from(io::Error::last_os_error());
+            warn!("expose(): failed to register buffer: {:?}", e);
+            return Err(e);
+        }
+        let region: RdmaRegion = RdmaRegion {
+            addr: buf.as_ptr() as u64,
+            len: buf.len() as u32,
+            rkey: unsafe { (*mr).rkey },
+        };
+        self.exposed.insert(region.rkey, ExposedRegion { mr, _buf: buf });
+        Ok(region)
+    }
+
+    /// Withdraws the region with remote key [rkey], which the peer can no longer access.
+    pub fn withdraw(&mut self, rkey: u32) -> Result<(), Fail> {
+        match self.exposed.remove(&rkey) {
+            Some(region) => {
+                unsafe { ibv_dereg_mr(region.mr) };
+                Ok(())
+            },
+            None => {
+                let cause: String = format!("no such exposed region (rkey={:?})", rkey);
+                warn!("withdraw(): {}", cause);
+                Err(Fail::new(libc::ENOENT, &cause))
+            },
+        }
+    }
+
+    /// Posts a receive into a fresh registered buffer.
+    fn post_receive(&mut self) -> Result<(), Fail> {
+        let buf: DemiBuffer = self.device.alloc()?;
+        let mut sge: ibv_sge = self.device.sge(&buf);
+        let id: usize = self.requests.insert(Request {
+            receive: true,
+            buf,
+            result: None,
+            orphaned: false,
+        });
+        let mut wr: ibv_recv_wr = unsafe { mem::zeroed() };
+        wr.wr_id = id as u64;
+        wr.sg_list = &mut sge;
+        wr.num_sge = 1;
+        let mut bad_wr: *mut ibv_recv_wr = ptr::null_mut();
+        let ret: libc::c_int = unsafe { ibv_post_recv((*self.cm.id).qp, &mut wr, &mut bad_wr) };
+        if ret != 0 {
+            self.requests.remove(id);
+            let cause: String = format!("failed to post receive (errno={:?})", ret);
+            warn!("post_receive(): {}", cause);
+            return Err(Fail::new(ret, &cause));
+        }
+        Ok(())
+    }
+
+    /// Posts a send, a read or a write of [buf], which is registered. Reads and writes target [region].
+    fn post_send(&mut self, opcode: ibv_wr_opcode, buf: DemiBuffer, region: Option<RdmaRegion>) -> Result<usize, Fail> {
+        if self.disconnected {
+            return Err(Fail::new(libc::ECONNRESET, "connection was closed"));
+        }
+        if self.requests.iter().filter(|(_, request)| !request.receive).count() >= QUEUE_DEPTH as usize {
+            return Err(Fail::new(libc::ENOBUFS, "too many requests in flight"));
+        }
+        let mut sge: ibv_sge = self.device.sge(&buf);
+        let id: usize = self.requests.insert(Request {
+            receive: false,
+            buf,
+            result: None,
+            orphaned: false,
+        });
+        let mut wr: ibv_send_wr = unsafe { mem::zeroed() };
+        wr.wr_id = id as u64;
+        wr.sg_list = &mut sge;
+        wr.num_sge = 1;
+        wr.opcode = opcode;
+        wr.send_flags = IBV_SEND_SIGNALED;
+        if let Some(region) = region {
+            wr.wr.rdma.remote_addr = region.addr;
+            wr.wr.rdma.rkey = region.rkey;
+        }
+        let mut bad_wr: *mut ibv_send_wr = ptr::null_mut();
+        let ret: libc::c_int = unsafe { ibv_post_send((*self.cm.id).qp, &mut wr, &mut bad_wr) };
+        if ret != 0 {
+            self.requests.remove(id);
+            let cause: String = format!("failed to post work request (errno={:?})", ret);
+            warn!("post_send(): {}", cause);
+            return Err(Fail::new(ret, &cause));
+        }
+        Ok(id)
+    }
+
+    /// Waits for request [id] to complete. Returns the number of bytes transferred.
+    async fn wait(&mut self, id: usize) -> Result<usize, Fail> {
+        let mut guard: RequestGuard = RequestGuard {
+            connection: self.clone(),
+            id,
+            done: false,
+        };
+        loop {
+            if let Some(result) = self.requests[id].result.take() {
+                guard.done = true;
+                self.requests.remove(id);
+                return result;
+            }
+            self.poll()?;
+            if self.requests[id].result.is_none() {
+                poll_yield().await;
+            }
+        }
+    }
+
+    /// Processes completions and events of the connection manager.
+    fn poll(&mut self) -> Result<(), Fail> {
+        let mut wcs: [ibv_wc; COMPLETION_BATCH_SIZE] = unsafe { mem::zeroed() };
+        loop {
+            let n: libc::c_int =
+                unsafe { ibv_poll_cq(self.cq, COMPLETION_BATCH_SIZE as libc::c_int, wcs.as_mut_ptr()) };
+            if n < 0 {
+                let cause: &str = "failed to poll completion queue";
+                error!("poll(): {}", cause);
+                return Err(Fail::new(libc::EIO, cause));
+            }
+            for wc in &wcs[..n as usize] {
+                self.complete(wc)?;
+            }
+            if (n as usize) < COMPLETION_BATCH_SIZE {
+                break;
+            }
+        }
+
+        while let Some(event) = self.cm.try_event()? {
+            if event.kind == RDMA_CM_EVENT_DISCONNECTED {
+                self.disconnect();
+            } else {
+                trace!("poll(): ignoring event (kind={:?})", event.kind);
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles the completion [wc].
+    fn complete(&mut self, wc: &ibv_wc) -> Result<(), Fail> {
+        let id: usize = wc.wr_id as usize;
+        let request: &mut Request = match self.requests.get_mut(id) {
+            Some(request) => request,
+            None => {
+                warn!("complete(): completion for unknown request (id={:?})", id);
+                return Ok(());
+            },
+        };
+
+        if request.receive {
+            let mut request: Request = self.requests.remove(id);
+            match wc.status {
+                IBV_WC_SUCCESS => {
+                    request.buf.trim(request.buf.len() - wc.byte_len as usize)?;
+                    self.received.push_back(request.buf);
+                    if let Err(e) = self.post_receive() {
+                        warn!("complete(): failed to replenish receives: {:?}", e);
+                    }
+                },
+                // Receives are flushed once the connection goes down.
+                IBV_WC_WR_FLUSH_ERR => self.disconnect(),
+                status => {
+                    warn!("complete(): receive failed (status={:?})", status);
+                    self.disconnect();
+                },
+            }
+            return Ok(());
+        }
+
+        if request.orphaned {
+            trace!("complete(): dropping completion of orphaned request (id={:?})", id);
+            self.requests.remove(id);
+            return Ok(());
+        }
+        request.result = Some(match wc.status {
+            IBV_WC_SUCCESS => Ok(wc.byte_len as usize),
+            IBV_WC_WR_FLUSH_ERR => Err(Fail::new(libc::ECONNRESET, "connection was closed")),
+            status => {
+                let cause: String = format!("work request failed (status={:?})", status);
+                warn!("complete(): {}", cause);
+                Err(Fail::new(libc::EIO, &cause))
+            },
+        });
+        Ok(())
+    }
+
+    /// Disconnects, which flushes the requests in flight.
+    fn disconnect(&mut self) {
+        if !self.disconnected {
+            self.disconnected = true;
+            unsafe { rdma_disconnect(self.cm.id) };
+        }
+    }
+
+    /// Waits until the connection is established.
+    async fn established(&mut self) -> Result<(), Fail> {
+        expect_event(self.cm.next_event().await?, RDMA_CM_EVENT_ESTABLISHED)
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Default for SharedRdmaDriver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Deregisters memory and frees the protection domain. Memory itself is never freed, as buffers that were handed out to
+/// the application may outlive the device.
+impl Drop for RdmaDevice {
+    fn drop(&mut self) {
+        unsafe {
+            ibv_dereg_mr(self.mr);
+            ibv_dealloc_pd(self.pd);
+        }
+    }
+}
+
+impl Drop for CmId {
+    fn drop(&mut self) {
+        unsafe {
+            rdma_destroy_id(self.id);
+            rdma_destroy_event_channel(self.channel);
+        }
+    }
+}
+
+/// Tears down the queue pair, which stops the device from accessing the buffers of requests in flight, before they are
+/// released.
+impl Drop for RdmaConnection {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.disconnected {
+                rdma_disconnect(self.cm.id);
+            }
+            rdma_destroy_qp(self.cm.id);
+            if ibv_destroy_cq(self.cq) != 0 {
+                warn!("drop(): failed to destroy completion queue");
+            }
+            for region in self.exposed.values() {
+                ibv_dereg_mr(region.mr);
+            }
+        }
+    }
+}
+
+impl Drop for RequestGuard {
+    fn drop(&mut self) {
+        if !self.done {
+            if let Some(request) = self.connection.requests.get_mut(self.id) {
+                request.orphaned = true;
+            }
+        }
+    }
+}
+
+impl Deref for SharedRdmaDevice {
+    type Target = RdmaDevice;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl DerefMut for SharedRdmaDevice {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+impl Deref for SharedRdmaDriver {
+    type Target = RdmaDriver;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl DerefMut for SharedRdmaDriver {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+impl Deref for SharedRdmaConnection {
+    type Target = RdmaConnection;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl DerefMut for SharedRdmaConnection {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Creates an event channel that is polled without blocking.
+fn create_event_channel() -> Result<*mut rdma_event_channel, Fail> {
+    let channel: *mut rdma_event_channel = unsafe { rdma_create_event_channel() };
+    if channel.is_null() {
+        let e: Fail = Fail::from(io::Error::last_os_error());
+        error!("create_event_channel(): {:?}", e);
+        return Err(e);
+    }
+    let fd: libc::c_int = unsafe { (*channel).fd };
+    let flags: libc::c_int = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+    if flags < 0 || unsafe { libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) } < 0 {
+        let e: Fail = Fail::from(io::Error::last_os_error());
+        error!("create_event_channel(): failed to make channel non-blocking: {:?}", e);
+        unsafe { rdma_destroy_event_channel(channel) };
+        return Err(e);
+    }
+    Ok(channel)
+}
+
+/// Rejects the connection request that came with [id] and destroys it.
+fn reject(id: *mut rdma_cm_id) {
+    unsafe {
+        rdma_reject(id, ptr::null(), 0);
+        rdma_destroy_id(id);
+    }
+}
+
+/// Parameters of connections, which are the same on both ends.
+fn conn_param() -> rdma_conn_param {
+    let mut param: rdma_conn_param = unsafe { mem::zeroed() };
+    param.responder_resources = READ_DEPTH;
+    param.initiator_depth = READ_DEPTH;
+    param.retry_count = 7;
+    // Retry forever if the peer is out of receives, as it replenishes them once messages are popped.
+    param.rnr_retry_count = 7;
+    param
+}
+
+/// Checks that [event] is of [kind], mapping failures of the connection manager to errors.
+fn expect_event(event: CmEvent, kind: rdma_cm_event_type) -> Result<(), Fail> {
+    if event.kind == kind {
+        return Ok(());
+    }
+    let errno: libc::c_int = match event.kind {
+        RDMA_CM_EVENT_ADDR_ERROR | RDMA_CM_EVENT_ROUTE_ERROR => libc::EHOSTUNREACH,
+        RDMA_CM_EVENT_UNREACHABLE => libc::ENETUNREACH,
+        RDMA_CM_EVENT_REJECTED => libc::ECONNREFUSED,
+        RDMA_CM_EVENT_DISCONNECTED => libc::ECONNRESET,
+        _ => libc::EPROTO,
+    };
+    let cause: String = format!(
+        "unexpected event (kind={:?}, status={:?}, expected={:?})",
+        event.kind, event.status, kind
+    );
+    warn!("expect_event(): {}", cause);
+    Err(Fail::new(errno, &cause))
+}
@@ -70,6 +70,14 @@ where
         }
     }
 
+    /// Changes the default expiration of entries that are inserted afterwards.
+    pub fn set_default_ttl(&mut self, default_ttl: Option<Duration>) {
+        if let Some(ttl) = default_ttl {
+            assert!(ttl > Duration::new(0, 0));
+        };
+        self.default_ttl = default_ttl;
+    }
+
     // Cleanups the cache.
     pub fn clear(&mut self) {
         self.graveyard.clear();
@@ -10,22 +10,66 @@ use crate::{
     demikernel::config::Config,
     expect_some,
     inetstack::protocols::{
-        arp::SharedArpPeer,
+        arp::{
+            NextHopStats,
+            SharedArpPeer,
+        },
         ethernet2::{
             EtherType2,
             Ethernet2Header,
+            VlanTag,
+        },
+        interface::SharedInterfaces,
+        ip::IpProtocol,
+        ipsec::SharedIpsecNetwork,
+        packet::{
+            socket::SharedPacketSocket,
+            SharedPacketPeer,
+        },
+        raw::socket::SharedRawSocket,
+        tcp::{
+            gro::GroStats,
+            socket::SharedTcpSocket,
         },
-        tcp::socket::SharedTcpSocket,
         udp::socket::SharedUdpSocket,
         Peer,
     },
     runtime::{
+        budget::consume_budget,
         fail::Fail,
         memory::{
             DemiBuffer,
             MemoryRuntime,
         },
         network::{
+            ancillary::AncillaryData,
+            config::{
+                IpsecDirection,
+                Route,
+                SecurityAssociation,
+            },
+            drops::{
+                DropReason,
+                DropRecord,
+                DropStats,
+            },
+            numa::NumaStats,
+            socket::{
+                filter::SocketFilter,
+                option::SocketOption,
+            },
+            tcp_info::{
+                TcpEvent,
+                TcpInfo,
+            },
+            timestamp::{
+                RxTimestamp,
+                TxTimestamp,
+            },
+            tls::offload::{
+                TlsOffload,
+                TlsOffloadFlow,
+            },
             transport::NetworkTransport,
             types::MacAddress,
             unwrap_socketaddr,
@@ -35,11 +79,14 @@ use crate::{
         SharedDemiRuntime,
         SharedObject,
     },
+    trace_event,
 };
 use ::socket2::{
     Domain,
+    Protocol,
     Type,
 };
+use ::std::time::SystemTime;
 #[cfg(test)]
 use ::std::{
     collections::HashMap,
@@ -59,6 +106,7 @@ use ::std::{
         Deref,
         DerefMut,
     },
+    rc::Rc,
 };
 
 use crate::timer;
@@ -71,6 +119,8 @@ use crate::timer;
 pub mod test_helpers;
 
 pub mod collections;
+#[cfg(any(test, feature = "fuzz"))]
+pub mod fuzz;
 pub mod options;
 pub mod protocols;
 
@@ -84,20 +134,33 @@ const MAX_RECV_ITERS: usize = 2;
 // Structures
 //======================================================================================================================
 
+/// Network runtime of the IPv4 protocols, which spreads their traffic over the interfaces of the stack and protects it
+/// with IPsec.
+type Ipv4Network<N> = SharedIpsecNetwork<SharedInterfaces<N>>;
+
 /// Socket Representation.
 #[derive(Clone)]
 pub enum Socket<N: NetworkRuntime> {
-    Tcp(SharedTcpSocket<N>),
-    Udp(SharedUdpSocket<N>),
+    Tcp(SharedTcpSocket<Ipv4Network<N>>),
+    Udp(SharedUdpSocket<Ipv4Network<N>>),
+    Raw(SharedRawSocket<Ipv4Network<N>>),
+    Packet(SharedPacketSocket<N>),
 }
 
 /// Representation of a network stack designed for a network interface that expects raw ethernet frames.
 pub struct InetStack<N: NetworkRuntime> {
-    arp: SharedArpPeer<N>,
-    ipv4: Peer<N>,
+    arp: SharedArpPeer<Ipv4Network<N>>,
+    ipv4: Peer<Ipv4Network<N>>,
+    /// Packet sockets take and send whole frames, so their traffic bypasses IPsec.
+    packet: SharedPacketPeer<N>,
+    /// Network runtime of the IPv4 protocols, which protects their traffic with IPsec.
+    ipsec: Ipv4Network<N>,
+    /// Network interfaces of the stack. Packet sockets only use the primary one, [network].
+    interfaces: SharedInterfaces<N>,
     runtime: SharedDemiRuntime,
     network: N,
-    local_link_addr: MacAddress,
+    /// Tag of the VLAN that the network interface belongs to, if any.
+    vlan: Option<VlanTag>,
 }
 
 #[derive(Clone)]
@@ -109,39 +172,71 @@ pub struct SharedInetStack<N: NetworkRuntime>(SharedObject<InetStack<N>>);
 
 impl<N: NetworkRuntime> SharedInetStack<N> {
     pub fn new(config: Config, runtime: SharedDemiRuntime, network: N) -> Result<Self, Fail> {
-        SharedInetStack::<N>::new_test(runtime, network, config.local_link_addr(), config.local_ipv4_addr())
+        let vlan: Option<VlanTag> = config.vlan_tag()?;
+        let security_associations: Option<Vec<SecurityAssociation>> = config.ipsec_security_associations()?;
+        let routes: Option<Vec<Route>> = config.routes()?;
+        let mut me: Self = SharedInetStack::<N>::new_with_ipsec(
+            runtime,
+            network,
+            config.local_link_addr()?,
+            config.local_ipv4_addr()?,
+            security_associations,
+        )?;
+        me.vlan = vlan;
+        if let Some(routes) = routes {
+            me.arp.set_routes(routes)?;
+        }
+        Ok(me)
     }
 
     pub fn new_test(
+        runtime: SharedDemiRuntime,
+        network: N,
+        local_link_addr: MacAddress,
+        local_ipv4_addr: Ipv4Addr,
+    ) -> Result<Self, Fail> {
+        Self::new_with_ipsec(runtime, network, local_link_addr, local_ipv4_addr, None)
+    }
+
+    /// Creates a stack whose IPv4 traffic is protected with IPsec if [security_associations] is set, even if it is
+    /// empty.
+    pub fn new_with_ipsec(
         mut runtime: SharedDemiRuntime,
         network: N,
         local_link_addr: MacAddress,
         local_ipv4_addr: Ipv4Addr,
+        security_associations: Option<Vec<SecurityAssociation>>,
     ) -> Result<Self, Fail> {
         let rng_seed: [u8; 32] = [0; 32];
-        let arp: SharedArpPeer<N> = SharedArpPeer::new(
+        let interfaces: SharedInterfaces<N> = SharedInterfaces::new(network.clone(), local_link_addr, local_ipv4_addr);
+        let ipsec: Ipv4Network<N> = SharedIpsecNetwork::new(interfaces.clone(), security_associations)?;
+        let arp: SharedArpPeer<Ipv4Network<N>> = SharedArpPeer::new(
             runtime.clone(),
-            network.clone(),
+            ipsec.clone(),
             local_link_addr,
             local_ipv4_addr,
-            network.get_arp_config(),
+            ipsec.get_arp_config(),
         )?;
-        let ipv4: Peer<N> = Peer::new(
+        let ipv4: Peer<Ipv4Network<N>> = Peer::new(
             runtime.clone(),
-            network.clone(),
+            ipsec.clone(),
             local_link_addr,
             local_ipv4_addr,
-            network.get_udp_config(),
-            network.get_tcp_config(),
+            ipsec.get_udp_config(),
+            ipsec.get_tcp_config(),
             arp.clone(),
             rng_seed,
         )?;
+        let packet: SharedPacketPeer<N> = SharedPacketPeer::new(network.clone());
         let me: Self = Self(SharedObject::<InetStack<N>>::new(InetStack::<N> {
             arp,
             ipv4,
+            packet,
+            ipsec,
+            interfaces,
             runtime: runtime.clone(),
             network,
-            local_link_addr: local_link_addr,
+            vlan: None,
         }));
         runtime.insert_background_coroutine("inetstack::poll_recv", Box::pin(me.clone().poll().fuse()))?;
         Ok(me)
@@ -157,7 +252,7 @@ impl<N: NetworkRuntime> SharedInetStack<N> {
                 let batch = {
                     timer!("inetstack::poll_bg_work::for::receive");
 
-                    self.network.receive()
+                    self.interfaces.receive()
                 };
 
                 {
@@ -167,21 +262,50 @@ impl<N: NetworkRuntime> SharedInetStack<N> {
                         break;
                     }
 
+                    let batch_len: usize = batch.len();
+                    // Packets are stamped with the time at which their batch was received.
+                    let now: SystemTime = SystemTime::now();
                     for pkt in batch {
-                        if let Err(e) = self.receive(pkt) {
-                            warn!("incorrectly formatted packet: {:?}", e);
+                        let timestamp: RxTimestamp = RxTimestamp {
+                            software: now,
+                            hardware: self.network.rx_hw_timestamp(&pkt),
+                        };
+                        if let Err(e) = self.receive_at(pkt, timestamp) {
+                            self.runtime.record_drop(DropReason::from_parse_error(&e));
+                            trace_event!("drop", "malformed_frame", cause = e.cause);
                         }
                     }
+                    self.ipv4.flush();
+                    // Bound the number of packets processed in a single poll.
+                    consume_budget(batch_len).await;
                 }
             }
             poll_yield().await;
         }
     }
 
+    /// Attaches a secondary network interface with its own device, [network], and addresses. Returns the index of the
+    /// interface, which routes refer to (see [Route::with_interface]).
+    pub fn attach_interface(&mut self, network: N, link_addr: MacAddress, ipv4_addr: Ipv4Addr) -> Result<usize, Fail> {
+        let index: usize = self.interfaces.attach(network, link_addr, ipv4_addr)?;
+        self.arp.add_interface(link_addr, ipv4_addr);
+        Ok(index)
+    }
+
+    /// Gets the receive coalescing statistics of TCP.
+    pub fn get_gro_stats(&self) -> GroStats {
+        self.ipv4.tcp.get_gro_stats()
+    }
+
+    /// Gets the statistics of the next-hop caches of TCP connections.
+    pub fn get_next_hop_stats(&self) -> NextHopStats {
+        self.arp.get_next_hop_stats()
+    }
+
     /// Generally these functions are for testing.
     #[cfg(test)]
     pub fn get_link_addr(&self) -> MacAddress {
-        self.local_link_addr
+        self.interfaces.get_link_addr(protocols::interface::PRIMARY_INTERFACE)
     }
 
     #[cfg(test)]
@@ -210,19 +334,44 @@ impl<N: NetworkRuntime> SharedInetStack<N> {
         self.arp.export_cache()
     }
 
+    #[cfg(test)]
+    pub fn get_arp(&self) -> SharedArpPeer<Ipv4Network<N>> {
+        self.arp.clone()
+    }
+
     pub fn receive(&mut self, pkt: DemiBuffer) -> Result<(), Fail> {
+        self.receive_at(pkt, RxTimestamp::now())
+    }
+
+    /// Receives [pkt], which arrived at [timestamp].
+    pub fn receive_at(&mut self, pkt: DemiBuffer, timestamp: RxTimestamp) -> Result<(), Fail> {
+        // Packet sockets see every frame, including those that the stack drops.
+        self.packet.receive(&pkt);
         let (header, payload) = Ethernet2Header::parse(pkt)?;
         debug!("Engine received {:?}", header);
-        if self.local_link_addr != header.dst_addr()
+        if !self.interfaces.is_local_link_addr(header.dst_addr())
             && !header.dst_addr().is_broadcast()
             && !header.dst_addr().is_multicast()
         {
-            warn!("dropping packet");
+            self.runtime.record_drop(DropReason::AddressMismatch);
+            trace_event!("drop", "link_addr_mismatch", dst = header.dst_addr());
             return Ok(());
         }
+        // Untagged frames and frames that only carry a priority belong to any VLAN, since devices may strip tags.
+        if let Some(tag) = header.vlan() {
+            if tag.vid() != 0 && Some(tag.vid()) != self.vlan.map(|vlan| vlan.vid()) {
+                self.runtime.record_drop(DropReason::VlanMismatch);
+                trace_event!("drop", "vlan_mismatch", vid = tag.vid());
+                return Ok(());
+            }
+        }
         match header.ether_type() {
             EtherType2::Arp => self.arp.receive(payload),
-            EtherType2::Ipv4 => self.ipv4.receive(payload),
+            EtherType2::Ipv4 => {
+                if let Some(payload) = self.ipsec.receive(payload)? {
+                    self.ipv4.receive(payload, timestamp)
+                }
+            },
             EtherType2::Ipv6 => (), // Ignore for now.
         };
         Ok(())
@@ -264,13 +413,26 @@ impl<N: NetworkRuntime> NetworkTransport for SharedInetStack<N> {
     /// defined in the libc crate. Currently, the following families are supported:
     ///
     /// - AF_INET Internet Protocol Version 4 (IPv4)
+    /// - AF_PACKET Raw Ethernet frames, on Linux and with type `SOCK_RAW` only
+    ///
+    /// The protocol argument is only used by raw sockets, where it selects the protocol of the IPv4 datagrams that
+    /// are sent and received through the socket, and by packet sockets, where it is the EtherType of the frames that
+    /// are received through the socket, in network byte order.
     ///
     /// **Return Vale**
     ///
     /// Upon successful completion, a file descriptor for the newly created
     /// socket is returned. Upon failure, `Fail` is returned instead.
     ///
-    fn socket(&mut self, domain: Domain, typ: Type) -> Result<Self::SocketDescriptor, Fail> {
+    fn socket(&mut self, domain: Domain, typ: Type, protocol: Protocol) -> Result<Self::SocketDescriptor, Fail> {
+        #[cfg(target_os = "linux")]
+        if domain == Domain::PACKET && typ == Type::RAW {
+            let protocol: u16 = match u16::try_from(libc::c_int::from(protocol)) {
+                Ok(protocol) => protocol,
+                Err(_) => return Err(Fail::new(libc::ENOTSUP, "protocol not supported")),
+            };
+            return Ok(Socket::Packet(self.packet.socket(protocol)));
+        }
         // TODO: Remove this once we support Ipv6.
         if domain != Domain::IPV4 {
             return Err(Fail::new(libc::ENOTSUP, "address family not supported"));
@@ -278,6 +440,13 @@ impl<N: NetworkRuntime> NetworkTransport for SharedInetStack<N> {
         match typ {
             Type::STREAM => Ok(Socket::Tcp(self.ipv4.tcp.socket()?)),
             Type::DGRAM => Ok(Socket::Udp(self.ipv4.udp.socket()?)),
+            Type::RAW => {
+                let protocol: IpProtocol = match u8::try_from(libc::c_int::from(protocol)) {
+                    Ok(protocol) => IpProtocol::try_from(protocol)?,
+                    Err(_) => return Err(Fail::new(libc::ENOTSUP, "protocol not supported")),
+                };
+                Ok(Socket::Raw(self.ipv4.raw.socket(protocol)?))
+            },
             _ => Err(Fail::new(libc::ENOTSUP, "socket type not supported")),
         }
     }
@@ -300,6 +469,8 @@ impl<N: NetworkRuntime> NetworkTransport for SharedInetStack<N> {
         match sd {
             Socket::Tcp(socket) => self.ipv4.tcp.bind(socket, local),
             Socket::Udp(socket) => self.ipv4.udp.bind(socket, local),
+            Socket::Raw(_) => Err(Fail::new(libc::ENOTSUP, "cannot bind raw socket")),
+            Socket::Packet(_) => Err(Fail::new(libc::ENOTSUP, "cannot bind packet socket")),
         }
     }
 
@@ -398,6 +569,121 @@ impl<N: NetworkRuntime> NetworkTransport for SharedInetStack<N> {
         match sd {
             Socket::Tcp(socket) => self.ipv4.tcp.close(socket).await,
             Socket::Udp(socket) => self.ipv4.udp.close(socket).await,
+            Socket::Raw(socket) => self.ipv4.raw.close(socket).await,
+            Socket::Packet(socket) => self.packet.close(socket).await,
+        }
+    }
+
+    /// Sets [option] on a socket. The new value applies to the packets that are sent from now on.
+    fn set_socket_option(&mut self, sd: &mut Self::SocketDescriptor, option: SocketOption) -> Result<(), Fail> {
+        if let (SocketOption::IP_RECVERR(_), Socket::Raw(_) | Socket::Packet(_)) = (option, &sd) {
+            let cause: &str = "error queues are only supported on TCP and UDP sockets";
+            error!("set_socket_option(): {}", cause);
+            return Err(Fail::new(libc::ENOTSUP, cause));
+        }
+        if let (
+            SocketOption::TCP_INIT_CWND(_)
+            | SocketOption::TCP_RTO_INIT_US(_)
+            | SocketOption::TCP_RTO_MIN_US(_)
+            | SocketOption::TCP_RTO_MAX_MS(_)
+            | SocketOption::TCP_USER_TIMEOUT(_),
+            Socket::Udp(_) | Socket::Raw(_) | Socket::Packet(_),
+        ) = (option, &sd)
+        {
+            let cause: &str = "congestion control and retransmission options are only supported on TCP sockets";
+            error!("set_socket_option(): {}", cause);
+            return Err(Fail::new(libc::ENOPROTOOPT, cause));
+        }
+        if let (SocketOption::SO_NO_CHECK(_), Socket::Tcp(_) | Socket::Raw(_) | Socket::Packet(_)) = (option, &sd) {
+            let cause: &str = "checksum generation can only be disabled on UDP sockets";
+            error!("set_socket_option(): {}", cause);
+            return Err(Fail::new(libc::ENOPROTOOPT, cause));
+        }
+        if let (SocketOption::UDP_SEGMENT(_), Socket::Tcp(_) | Socket::Raw(_) | Socket::Packet(_)) = (option, &sd) {
+            let cause: &str = "segmentation of payloads is only supported on UDP sockets";
+            error!("set_socket_option(): {}", cause);
+            return Err(Fail::new(libc::ENOPROTOOPT, cause));
+        }
+        if let (SocketOption::SO_TIMESTAMPING(_), Socket::Tcp(_) | Socket::Raw(_) | Socket::Packet(_)) = (option, &sd) {
+            let cause: &str = "transmit timestamps are only supported on UDP sockets";
+            error!("set_socket_option(): {}", cause);
+            return Err(Fail::new(libc::ENOPROTOOPT, cause));
+        }
+        if let (
+            SocketOption::TCP_MD5SIG(_)
+            | SocketOption::TCP_AO_ADD_KEY(_)
+            | SocketOption::TCP_AO_DEL_KEY(_)
+            | SocketOption::TCP_AO_INFO(_),
+            Socket::Udp(_) | Socket::Raw(_) | Socket::Packet(_),
+        ) = (option, &sd)
+        {
+            let cause: &str = "signature keys are only supported on TCP sockets";
+            error!("set_socket_option(): {}", cause);
+            return Err(Fail::new(libc::ENOPROTOOPT, cause));
+        }
+        if let SocketOption::SO_BINDTODEVICE(index) = option {
+            if let Socket::Packet(_) = sd {
+                let cause: &str = "packet sockets always use the primary interface";
+                error!("set_socket_option(): {}", cause);
+                return Err(Fail::new(libc::ENOPROTOOPT, cause));
+            }
+            if index as usize > self.interfaces.num_interfaces() {
+                let cause: String = format!("no such interface (index={})", index);
+                error!("set_socket_option(): {}", cause);
+                return Err(Fail::new(libc::ENODEV, &cause));
+            }
+        }
+        match sd {
+            Socket::Tcp(socket) => socket.set_socket_option(option),
+            Socket::Udp(socket) => socket.set_socket_option(option),
+            Socket::Raw(socket) => socket.set_socket_option(option),
+            Socket::Packet(socket) => socket.set_socket_option(option),
+        }
+    }
+
+    /// Attaches a receive filter to a socket, or detaches the current one if [filter] is `None`. Filters run on the
+    /// data that would be queued on the socket: the payload of UDP and raw datagrams, and whole frames on packet
+    /// sockets.
+    fn set_receive_filter(
+        &mut self,
+        sd: &mut Self::SocketDescriptor,
+        filter: Option<SocketFilter>,
+    ) -> Result<(), Fail> {
+        match sd {
+            Socket::Tcp(_) => {
+                let cause: &str = "receive filters are not supported on TCP sockets";
+                error!("set_receive_filter(): {}", cause);
+                return Err(Fail::new(libc::ENOTSUP, cause));
+            },
+            Socket::Udp(socket) => socket.set_receive_filter(filter),
+            Socket::Raw(socket) => socket.set_receive_filter(filter),
+            Socket::Packet(socket) => socket.set_receive_filter(filter),
+        }
+        Ok(())
+    }
+
+    /// Gets the current value of [option] on a socket.
+    fn get_socket_option(
+        &mut self,
+        sd: &mut Self::SocketDescriptor,
+        option: SocketOption,
+    ) -> Result<SocketOption, Fail> {
+        if let SocketOption::TCP_MD5SIG(_) | SocketOption::TCP_AO_ADD_KEY(_) | SocketOption::TCP_AO_DEL_KEY(_) = option
+        {
+            let cause: &str = "signature keys cannot be read back";
+            error!("get_socket_option(): {}", cause);
+            return Err(Fail::new(libc::ENOPROTOOPT, cause));
+        }
+        if let (SocketOption::TCP_AO_INFO(_), Socket::Udp(_) | Socket::Raw(_) | Socket::Packet(_)) = (option, &sd) {
+            let cause: &str = "authentication keys are only supported on TCP sockets";
+            error!("get_socket_option(): {}", cause);
+            return Err(Fail::new(libc::ENOPROTOOPT, cause));
+        }
+        match sd {
+            Socket::Tcp(socket) => socket.get_socket_option(option),
+            Socket::Udp(socket) => Ok(socket.get_socket_option(option)),
+            Socket::Raw(socket) => Ok(socket.get_socket_option(option)),
+            Socket::Packet(socket) => Ok(socket.get_socket_option(option)),
         }
     }
 
@@ -406,9 +692,30 @@ impl<N: NetworkRuntime> NetworkTransport for SharedInetStack<N> {
         match sd {
             Socket::Tcp(socket) => self.ipv4.tcp.hard_close(socket),
             Socket::Udp(socket) => self.ipv4.udp.hard_close(socket),
+            Socket::Raw(socket) => self.ipv4.raw.hard_close(socket),
+            Socket::Packet(socket) => self.packet.hard_close(socket),
+        }
+    }
+
+    /// Freezes a TCP connection into a blob that another instance of the stack may thaw.
+    fn freeze(&mut self, sd: &mut Self::SocketDescriptor) -> Result<Vec<u8>, Fail> {
+        match sd {
+            Socket::Tcp(socket) => self.ipv4.tcp.freeze(socket),
+            Socket::Udp(_) | Socket::Raw(_) | Socket::Packet(_) => {
+                let cause: &str = "only TCP connections can be frozen";
+                error!("freeze(): {}", cause);
+                Err(Fail::new(libc::ENOTSUP, cause))
+            },
         }
     }
 
+    /// Thaws a TCP connection that another instance of the stack froze into [blob].
+    fn thaw(&mut self, blob: &[u8]) -> Result<(Self::SocketDescriptor, SocketAddr, SocketAddr), Fail> {
+        let socket: SharedTcpSocket<Ipv4Network<N>> = self.ipv4.tcp.thaw(blob)?;
+        let (local, remote): (SocketAddrV4, SocketAddrV4) = socket.endpoints()?;
+        Ok((Socket::Tcp(socket), SocketAddr::V4(local), SocketAddr::V4(remote)))
+    }
+
     /// Pushes a buffer to a TCP socket.
     async fn push(
         &mut self,
@@ -419,6 +726,8 @@ impl<N: NetworkRuntime> NetworkTransport for SharedInetStack<N> {
         match sd {
             Socket::Tcp(socket) => self.ipv4.tcp.push(socket, buf).await,
             Socket::Udp(socket) => self.ipv4.udp.push(socket, buf, addr).await,
+            Socket::Raw(socket) => self.ipv4.raw.push(socket, buf, addr).await,
+            Socket::Packet(socket) => self.packet.push(socket, buf).await,
         }
     }
 
@@ -428,10 +737,167 @@ impl<N: NetworkRuntime> NetworkTransport for SharedInetStack<N> {
         &mut self,
         sd: &mut Self::SocketDescriptor,
         size: usize,
-    ) -> Result<(Option<SocketAddr>, DemiBuffer), Fail> {
+    ) -> Result<(Option<SocketAddr>, DemiBuffer, AncillaryData), Fail> {
+        let (addr, buf) = match sd {
+            Socket::Tcp(socket) => self.ipv4.tcp.pop(socket, size).await?,
+            Socket::Udp(socket) => return self.ipv4.udp.pop(socket, size).await,
+            Socket::Raw(socket) => self.ipv4.raw.pop(socket, size).await?,
+            Socket::Packet(socket) => self.packet.pop(socket, size).await?,
+        };
+        Ok((addr, buf, AncillaryData::default()))
+    }
+
+    /// Gets the device that encrypts the outgoing TLS records of an established TCP connection, if the network
+    /// runtime has one.
+    fn tls_offload(&mut self, sd: &mut Self::SocketDescriptor) -> Option<(Rc<dyn TlsOffload>, TlsOffloadFlow)> {
+        let socket: &SharedTcpSocket<Ipv4Network<N>> = match sd {
+            Socket::Tcp(socket) => socket,
+            _ => return None,
+        };
+        let device: Rc<dyn TlsOffload> = self.ipsec.tls_offload()?;
+        let (local, remote): (SocketAddrV4, SocketAddrV4) = socket.endpoints().ok()?;
+        // Looped back records never reach the device.
+        if self.interfaces.is_local_addr(*remote.ip()) {
+            return None;
+        }
+        let tcp_seq: u32 = socket.unsent_seq_no().ok()?;
+        Some((device, TlsOffloadFlow { local, remote, tcp_seq }))
+    }
+
+    /// Adds a security association, if IPsec was enabled when the stack was created.
+    fn ipsec_add_security_association(&mut self, sa: SecurityAssociation) -> Result<(), Fail> {
+        self.ipsec.add_security_association(sa)
+    }
+
+    /// Removes the security association with security parameter index [spi] in [direction].
+    fn ipsec_remove_security_association(&mut self, spi: u32, direction: IpsecDirection) -> Result<(), Fail> {
+        self.ipsec.remove_security_association(spi, direction)
+    }
+
+    /// Adds [route] to the routing table. Connections pick it up the next time that they transmit.
+    fn add_route(&mut self, route: Route) -> Result<(), Fail> {
+        self.arp.add_route(route)
+    }
+
+    /// Removes the route toward the subnet [destination]/[prefix_len] from the routing table.
+    fn remove_route(&mut self, destination: Ipv4Addr, prefix_len: u8) -> Result<(), Fail> {
+        self.arp.remove_route(destination, prefix_len)
+    }
+
+    /// Sets the tunable [name] of the ARP ("arp.*"), ICMP ("icmp.*"), TCP ("tcp.*") or UDP ("udp.*") protocol, of the
+    /// memory that sockets buffer ("memory.*"), of the admission control of inbound traffic ("admission.*"), or of the
+    /// flow table ("flows.*"), to [value].
+    fn set_tunable(&mut self, name: &str, value: &str) -> Result<(), Fail> {
+        match name.split_once('.') {
+            Some(("arp", name)) => self.arp.set_tunable(name, value),
+            Some(("icmp", name)) => self.ipv4.icmpv4.set_tunable(name, value),
+            Some(("tcp", name)) => self.ipv4.tcp.set_tunable(name, value),
+            Some(("udp", name)) => self.ipv4.udp.set_tunable(name, value),
+            Some(("memory", name)) => self.runtime.get_memory_pool().set_tunable(name, value),
+            Some(("admission", name)) => self.runtime.get_admission_control().set_tunable(name, value),
+            Some(("flows", name)) => self.runtime.get_flow_table().set_tunable(name, value),
+            _ => {
+                let cause: String = format!("unknown tunable \"{}\"", name);
+                error!("set_tunable(): {}", cause);
+                Err(Fail::new(libc::ENOENT, &cause))
+            },
+        }
+    }
+
+    /// Gets the value of the tunable [name] of the ARP ("arp.*"), ICMP ("icmp.*"), TCP ("tcp.*") or UDP ("udp.*")
+    /// protocol, of the memory that sockets buffer ("memory.*"), of the admission control of inbound traffic
+    /// ("admission.*"), or of the flow table ("flows.*").
+    fn get_tunable(&self, name: &str) -> Result<String, Fail> {
+        match name.split_once('.') {
+            Some(("arp", name)) => self.arp.get_tunable(name),
+            Some(("icmp", name)) => self.ipv4.icmpv4.get_tunable(name),
+            Some(("tcp", name)) => self.ipv4.tcp.get_tunable(name),
+            Some(("udp", name)) => self.ipv4.udp.get_tunable(name),
+            Some(("memory", name)) => self.runtime.get_memory_pool().get_tunable(name),
+            Some(("admission", name)) => self.runtime.get_admission_control().get_tunable(name),
+            Some(("flows", name)) => self.runtime.get_flow_table().get_tunable(name),
+            _ => {
+                let cause: String = format!("unknown tunable \"{}\"", name);
+                error!("get_tunable(): {}", cause);
+                Err(Fail::new(libc::ENOENT, &cause))
+            },
+        }
+    }
+
+    /// Gets the number of incoming packets that the stack dropped, for each reason.
+    fn get_drop_stats(&self) -> Result<DropStats, Fail> {
+        Ok(self.runtime.get_drop_stats())
+    }
+
+    /// Gets where the network device placed its buffer pools and the state of its queues, relative to its NUMA node.
+    fn get_numa_stats(&self) -> Result<NumaStats, Fail> {
+        self.network.get_numa_stats()
+    }
+
+    /// Releases the devices of all network interfaces.
+    fn shutdown(&mut self) -> Result<(), Fail> {
+        self.interfaces.shutdown()
+    }
+
+    /// Gets the number of incoming packets that were dropped on their way to a TCP or UDP socket, for each reason.
+    fn get_socket_drop_stats(&mut self, sd: &mut Self::SocketDescriptor) -> Result<DropStats, Fail> {
+        match sd {
+            Socket::Tcp(socket) => Ok(socket.get_drop_stats()),
+            Socket::Udp(socket) => Ok(socket.get_drop_stats()),
+            Socket::Raw(_) | Socket::Packet(_) => {
+                let cause: &str = "drop statistics are only supported on TCP and UDP sockets";
+                error!("get_socket_drop_stats(): {}", cause);
+                Err(Fail::new(libc::ENOTSUP, cause))
+            },
+        }
+    }
+
+    /// Pops the oldest record from the error queue of a TCP or UDP socket.
+    fn pop_drop_record(&mut self, sd: &mut Self::SocketDescriptor) -> Result<Option<DropRecord>, Fail> {
         match sd {
-            Socket::Tcp(socket) => self.ipv4.tcp.pop(socket, size).await,
-            Socket::Udp(socket) => self.ipv4.udp.pop(socket, size).await,
+            Socket::Tcp(socket) => Ok(socket.pop_drop_record()),
+            Socket::Udp(socket) => Ok(socket.pop_drop_record()),
+            Socket::Raw(_) | Socket::Packet(_) => {
+                let cause: &str = "error queues are only supported on TCP and UDP sockets";
+                error!("pop_drop_record(): {}", cause);
+                Err(Fail::new(libc::ENOTSUP, cause))
+            },
+        }
+    }
+
+    /// Pops the oldest transmit timestamp of a UDP socket.
+    fn pop_tx_timestamp(&mut self, sd: &mut Self::SocketDescriptor) -> Result<Option<TxTimestamp>, Fail> {
+        match sd {
+            Socket::Udp(socket) => Ok(socket.pop_tx_timestamp()),
+            Socket::Tcp(_) | Socket::Raw(_) | Socket::Packet(_) => {
+                let cause: &str = "transmit timestamps are only supported on UDP sockets";
+                error!("pop_tx_timestamp(): {}", cause);
+                Err(Fail::new(libc::ENOTSUP, cause))
+            },
+        }
+    }
+
+    /// Gets a snapshot of the state of a TCP socket.
+    fn get_tcp_info(&mut self, sd: &mut Self::SocketDescriptor) -> Result<TcpInfo, Fail> {
+        match sd {
+            Socket::Tcp(socket) => Ok(socket.get_tcp_info()),
+            Socket::Udp(_) | Socket::Raw(_) | Socket::Packet(_) => {
+                let cause: &str = "connection introspection is only supported on TCP sockets";
+                error!("get_tcp_info(): {}", cause);
+                Err(Fail::new(libc::ENOTSUP, cause))
+            },
+        }
+    }
+
+    /// Gets the recent retransmission and flow control events of a TCP socket.
+    fn get_tcp_timeline(&mut self, sd: &mut Self::SocketDescriptor) -> Result<Vec<TcpEvent>, Fail> {
+        match sd {
+            Socket::Tcp(socket) => Ok(socket.get_tcp_timeline()),
+            Socket::Udp(_) | Socket::Raw(_) | Socket::Packet(_) => {
+                let cause: &str = "connection introspection is only supported on TCP sockets";
+                error!("get_tcp_timeline(): {}", cause);
+                Err(Fail::new(libc::ENOTSUP, cause))
+            },
         }
     }
 
@@ -465,6 +931,8 @@ impl<N: NetworkRuntime> Debug for Socket<N> {
         match self {
             Socket::Tcp(socket) => socket.fmt(f),
             Socket::Udp(socket) => socket.fmt(f),
+            Socket::Raw(socket) => socket.fmt(f),
+            Socket::Packet(socket) => socket.fmt(f),
         }
     }
 }
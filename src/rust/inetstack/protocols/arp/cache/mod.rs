@@ -48,6 +48,9 @@ pub struct ArpCache {
 
     /// Disable ARP?
     disable: bool,
+
+    /// Number of times that a cached address resolution changed or was removed.
+    generation: u64,
 }
 
 //======================================================================================================================
@@ -65,6 +68,7 @@ impl ArpCache {
         let mut peer = ArpCache {
             cache: HashTtlCache::new(now, default_ttl),
             disable,
+            generation: 0,
         };
 
         // Populate cache.
@@ -80,7 +84,11 @@ impl ArpCache {
     /// Caches an address resolution.
     pub fn insert(&mut self, ipv4_addr: Ipv4Addr, link_addr: MacAddress) -> Option<MacAddress> {
         let record = Record { link_addr };
-        self.cache.insert(ipv4_addr, record).map(|r| r.link_addr)
+        let old: Option<MacAddress> = self.cache.insert(ipv4_addr, record).map(|r| r.link_addr);
+        if old.is_some_and(|old| old != link_addr) {
+            self.generation += 1;
+        }
+        old
     }
 
     /// Gets the MAC address of given IPv4 address.
@@ -92,10 +100,22 @@ impl ArpCache {
         }
     }
 
+    /// Gets the generation of the cache, which changes whenever a cached address resolution changes or is removed.
+    /// Resolutions that were looked up in the same generation are still valid.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Changes the time to live of address resolutions that are cached afterwards.
+    pub fn set_ttl(&mut self, ttl: Duration) {
+        self.cache.set_default_ttl(Some(ttl))
+    }
+
     /// Clears the ARP cache.
     #[allow(unused)]
     pub fn clear(&mut self) {
         self.cache.clear();
+        self.generation += 1;
     }
 
     // Exports address resolutions that are stored in the ARP cache.
@@ -80,3 +80,25 @@ fn export() -> Result<()> {
 
     Ok(())
 }
+
+/// Tests that the generation of the ARP Cache changes only when a cached address resolution changes or is removed.
+#[test]
+fn generation() -> Result<()> {
+    let now = Instant::now();
+    let ttl = Duration::from_secs(1);
+    let mut cache = ArpCache::new(now, Some(ttl), None, false);
+    crate::ensure_eq!(cache.generation(), 0);
+
+    // New and refreshed address resolutions do not change the generation.
+    cache.insert(test_helpers::ALICE_IPV4, test_helpers::ALICE_MAC);
+    cache.insert(test_helpers::ALICE_IPV4, test_helpers::ALICE_MAC);
+    crate::ensure_eq!(cache.generation(), 0);
+
+    // Changed and removed address resolutions do.
+    cache.insert(test_helpers::ALICE_IPV4, test_helpers::BOB_MAC);
+    crate::ensure_eq!(cache.generation(), 1);
+    cache.clear();
+    crate::ensure_eq!(cache.generation(), 2);
+
+    Ok(())
+}
@@ -2,10 +2,18 @@
 // Licensed under the MIT license.
 
 mod cache;
+mod next_hop;
 mod packet;
 mod peer;
 
 // Disable for now due to incorrect use of scheduler.
 #[cfg(test)]
 mod tests;
-pub use peer::SharedArpPeer;
+pub use self::{
+    next_hop::{
+        NextHop,
+        NextHopStats,
+    },
+    packet::ArpHeader,
+    peer::SharedArpPeer,
+};
@@ -0,0 +1,74 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::protocols::arp::SharedArpPeer,
+    runtime::network::{
+        types::MacAddress,
+        NetworkRuntime,
+    },
+};
+use ::std::net::Ipv4Addr;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Statistics of the next-hop caches of connections.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NextHopStats {
+    /// Lookups that were answered by the cache of a connection.
+    pub hits: u64,
+    /// Lookups that went to the ARP cache, because the connection had nothing cached or the ARP cache changed since.
+    pub misses: u64,
+}
+
+/// Next hop toward the remote address of a connection, cached so that transmitting a segment does not look up the
+/// routing table and ARP cache every time. The cached address is dropped as soon as any route or address resolution of
+/// the ARP cache changes.
+pub struct NextHop {
+    /// Remote address of the connection.
+    remote: Ipv4Addr,
+    /// Interface that the traffic of the connection leaves through, if the connection is bound to one.
+    device: Option<usize>,
+    /// Link address of the next hop, and the generation of the ARP cache when it was looked up.
+    cached: Option<(MacAddress, u64)>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl NextHop {
+    /// Creates an empty next-hop cache for a connection to [remote] whose traffic leaves through [device], if set.
+    pub fn new(remote: Ipv4Addr, device: Option<usize>) -> Self {
+        Self {
+            remote,
+            device,
+            cached: None,
+        }
+    }
+
+    /// Gets the link address of the next hop, from the cache if the ARP cache did not change since it was looked up,
+    /// or else from [arp]. Returns None if the address is not resolved.
+    pub fn get<N: NetworkRuntime>(&mut self, arp: &mut SharedArpPeer<N>) -> Option<MacAddress> {
+        let generation: u64 = arp.get_generation();
+        match self.cached {
+            Some((link_addr, cached_generation)) if cached_generation == generation => {
+                arp.record_next_hop(true);
+                Some(link_addr)
+            },
+            _ => {
+                arp.record_next_hop(false);
+                self.cached = arp
+                    .try_query_via(self.remote, self.device)
+                    .map(|link_addr| (link_addr, generation));
+                self.cached.map(|(link_addr, _)| link_addr)
+            },
+        }
+    }
+}
@@ -7,6 +7,7 @@ use crate::{
     inetstack::protocols::{
         arp::{
             cache::ArpCache,
+            next_hop::NextHopStats,
             packet::{
                 ArpHeader,
                 ArpMessage,
@@ -17,16 +18,21 @@ use crate::{
             EtherType2,
             Ethernet2Header,
         },
+        ipv4::RoutingTable,
     },
     runtime::{
         conditional_yield_with_timeout,
         fail::Fail,
         memory::DemiBuffer,
         network::{
-            config::ArpConfig,
+            config::{
+                ArpConfig,
+                Route,
+            },
             types::MacAddress,
             NetworkRuntime,
         },
+        scheduler::TaskPriority,
         SharedDemiRuntime,
         SharedObject,
     },
@@ -50,7 +56,10 @@ use ::std::{
         Deref,
         DerefMut,
     },
-    time::Duration,
+    time::{
+        Duration,
+        Instant,
+    },
 };
 
 //==============================================================================
@@ -65,18 +74,39 @@ use ::std::{
 /// Arp Peer
 ///
 pub struct ArpPeer<N: NetworkRuntime> {
+    runtime: SharedDemiRuntime,
     network: N,
-    local_link_addr: MacAddress,
-    local_ipv4_addr: Ipv4Addr,
+    /// Link and IPv4 addresses of the network interfaces, the primary one first. Interfaces are on distinct subnets, so
+    /// they share the cache.
+    interfaces: Vec<(MacAddress, Ipv4Addr)>,
     cache: ArpCache,
     waiters: HashMap<Ipv4Addr, LinkedList<Sender<MacAddress>>>,
+    /// Number of packets that wait on the resolution of each address.
+    pending: HashMap<Ipv4Addr, usize>,
+    /// Time at which the last request was sent to each address that is being resolved.
+    last_requests: HashMap<Ipv4Addr, Instant>,
+    /// Number of packets that were dropped because too many were waiting on the resolution of their address, and of
+    /// requests that were not sent because of the request rate.
+    pending_drops: u64,
+    suppressed_requests: u64,
     arp_config: ArpConfig,
     recv_queue: AsyncQueue<DemiBuffer>,
+    next_hop_stats: NextHopStats,
+    routes: RoutingTable,
+    /// Number of times that the routing table changed.
+    routes_generation: u64,
 }
 
 #[derive(Clone)]
 pub struct SharedArpPeer<N: NetworkRuntime>(SharedObject<ArpPeer<N>>);
 
+/// Packet that waits on the resolution of an address. It stops counting as pending when dropped, even if its query is
+/// cancelled.
+struct PendingQuery<N: NetworkRuntime> {
+    peer: SharedArpPeer<N>,
+    ipv4_addr: Ipv4Addr,
+}
+
 //==============================================================================
 // Associate Functions
 //==============================================================================
@@ -100,16 +130,28 @@ impl<N: NetworkRuntime> SharedArpPeer<N> {
         );
 
         let peer: SharedArpPeer<N> = Self(SharedObject::<ArpPeer<N>>::new(ArpPeer {
+            runtime: runtime.clone(),
             network,
-            local_link_addr,
-            local_ipv4_addr,
+            interfaces: vec![(local_link_addr, local_ipv4_addr)],
             cache,
             waiters: HashMap::default(),
+            pending: HashMap::default(),
+            last_requests: HashMap::default(),
+            pending_drops: 0,
+            suppressed_requests: 0,
             arp_config,
             recv_queue: AsyncQueue::<DemiBuffer>::default(),
+            next_hop_stats: NextHopStats::default(),
+            routes: RoutingTable::default(),
+            routes_generation: 0,
         }));
         // This is a future returned by the async function.
-        runtime.insert_background_coroutine("Inetstack::arp::background", Box::pin(peer.clone().poll().fuse()))?;
+        // Cache refreshes and replies to peers are not on the datapath, so let packet processing run first.
+        runtime.insert_background_coroutine_with_priority(
+            "Inetstack::arp::background",
+            TaskPriority::Low,
+            Box::pin(peer.clone().poll().fuse()),
+        )?;
         Ok(peer.clone())
     }
 
@@ -124,6 +166,7 @@ impl<N: NetworkRuntime> SharedArpPeer<N> {
     }
 
     fn do_insert(&mut self, ipv4_addr: Ipv4Addr, link_addr: MacAddress) -> Option<MacAddress> {
+        self.last_requests.remove(&ipv4_addr);
         if let Some(wait_queue) = self.waiters.remove(&ipv4_addr) {
             for sender in wait_queue {
                 let _ = sender.send(link_addr);
@@ -196,19 +239,27 @@ impl<N: NetworkRuntime> SharedArpPeer<N> {
                 }
             };
             // from RFC 826: ?Am I the target protocol address?
-            if header.get_destination_protocol_addr() != self.local_ipv4_addr {
-                if !merge_flag {
-                    // we didn't do something.
-                    let cause: String = format!("unrecognized IP address");
-                    warn!("arp_cache::poll(): {}", &cause);
-                }
-                trace!(
-                    "poll(): dropping arp packet (link_addr={:?}, ipv4_addr={:?})",
-                    header.get_sender_hardware_addr(),
-                    header.get_sender_protocol_addr()
-                );
-                continue;
-            }
+            let target: Option<(MacAddress, Ipv4Addr)> = self
+                .interfaces
+                .iter()
+                .find(|(_, ipv4_addr)| *ipv4_addr == header.get_destination_protocol_addr())
+                .copied();
+            let (local_link_addr, local_ipv4_addr): (MacAddress, Ipv4Addr) = match target {
+                Some(target) => target,
+                None => {
+                    if !merge_flag {
+                        // we didn't do something.
+                        let cause: String = format!("unrecognized IP address");
+                        warn!("arp_cache::poll(): {}", &cause);
+                    }
+                    trace!(
+                        "poll(): dropping arp packet (link_addr={:?}, ipv4_addr={:?})",
+                        header.get_sender_hardware_addr(),
+                        header.get_sender_protocol_addr()
+                    );
+                    continue;
+                },
+            };
             // from RFC 826:
             // > If Merge_flag is false, add the triplet <protocol type,
             // > sender protocol address, sender hardware address> to
@@ -228,17 +279,17 @@ impl<N: NetworkRuntime> SharedArpPeer<N> {
                     // > Swap hardware and protocol fields, putting the local
                     // > hardware and protocol addresses in the sender fields.
                     let reply = ArpMessage::new(
-                        Ethernet2Header::new(header.get_sender_hardware_addr(), self.local_link_addr, EtherType2::Arp),
+                        Ethernet2Header::new(header.get_sender_hardware_addr(), local_link_addr, EtherType2::Arp),
                         ArpHeader::new(
                             ArpOperation::Reply,
-                            self.local_link_addr,
-                            self.local_ipv4_addr,
+                            local_link_addr,
+                            local_ipv4_addr,
                             header.get_sender_hardware_addr(),
                             header.get_sender_protocol_addr(),
                         ),
                     );
                     debug!("Responding {:?}", reply);
-                    self.network.transmit(Box::new(reply));
+                    self.network.transmit_one(Box::new(reply));
                 },
                 ArpOperation::Reply => {
                     debug!(
@@ -253,31 +304,189 @@ impl<N: NetworkRuntime> SharedArpPeer<N> {
         }
     }
 
+    /// Sets the ARP tunable [name] to [value]. Changes apply to queries and cached resolutions from now on.
+    pub fn set_tunable(&mut self, name: &str, value: &str) -> Result<(), Fail> {
+        self.arp_config.set_tunable(name, value)?;
+        let cache_ttl: Duration = self.arp_config.get_cache_ttl();
+        self.cache.set_ttl(cache_ttl);
+        Ok(())
+    }
+
+    /// Gets the value of the ARP tunable [name]. Besides those of [ArpConfig], "pending_drops" is the number of packets
+    /// that were dropped because too many were waiting on the resolution of their address, and "requests_suppressed"
+    /// the number of requests that were not sent because of the request rate.
+    pub fn get_tunable(&self, name: &str) -> Result<String, Fail> {
+        match name {
+            "pending_drops" => Ok(self.pending_drops.to_string()),
+            "requests_suppressed" => Ok(self.suppressed_requests.to_string()),
+            _ => self.arp_config.get_tunable(name),
+        }
+    }
+
+    /// Gets the generation of the ARP cache and routing table, which changes whenever a cached address resolution or a
+    /// route changes or is removed.
+    pub fn get_generation(&self) -> u64 {
+        self.cache.generation() + self.routes_generation
+    }
+
+    /// Replaces the routing table with [routes].
+    pub fn set_routes(&mut self, routes: Vec<Route>) -> Result<(), Fail> {
+        self.routes = RoutingTable::new(routes)?;
+        self.routes_generation += 1;
+        Ok(())
+    }
+
+    /// Adds [route] to the routing table.
+    pub fn add_route(&mut self, route: Route) -> Result<(), Fail> {
+        self.routes.add(route)?;
+        self.routes_generation += 1;
+        Ok(())
+    }
+
+    /// Removes the route toward [destination]/[prefix_len] from the routing table.
+    pub fn remove_route(&mut self, destination: Ipv4Addr, prefix_len: u8) -> Result<(), Fail> {
+        self.routes.remove(destination, prefix_len)?;
+        self.routes_generation += 1;
+        Ok(())
+    }
+
+    /// Adds the addresses of a secondary network interface, which answers address resolutions of [ipv4_addr] and
+    /// resolves the next hops of the routes through it.
+    pub fn add_interface(&mut self, link_addr: MacAddress, ipv4_addr: Ipv4Addr) {
+        self.interfaces.push((link_addr, ipv4_addr));
+        self.routes_generation += 1;
+    }
+
+    /// Checks whether [ipv4_addr] is the address of a network interface or a loopback address.
+    pub fn is_local_addr(&self, ipv4_addr: Ipv4Addr) -> bool {
+        self.local_link_addr(ipv4_addr).is_some()
+    }
+
+    /// Gets the source address of traffic toward [ipv4_addr]: [ipv4_addr] itself if it is the address of an interface,
+    /// the loopback address if it is a loopback one, or else the address of the interface that the route toward it
+    /// leaves through, or the address of the primary interface if there is no such route.
+    pub fn source_addr(&self, ipv4_addr: Ipv4Addr) -> Ipv4Addr {
+        self.source_addr_via(ipv4_addr, None)
+    }
+
+    /// Gets the source address of traffic toward [ipv4_addr] like [source_addr], but for traffic that leaves through
+    /// [device] if set.
+    pub fn source_addr_via(&self, ipv4_addr: Ipv4Addr, device: Option<usize>) -> Ipv4Addr {
+        if ipv4_addr.is_loopback() {
+            return Ipv4Addr::LOCALHOST;
+        }
+        if self.is_local_addr(ipv4_addr) {
+            return ipv4_addr;
+        }
+        match self.egress(ipv4_addr, device) {
+            Ok((interface, _)) => self.interfaces[interface].1,
+            Err(_) => self.interfaces[0].1,
+        }
+    }
+
+    /// Gets the link address that local traffic toward [ipv4_addr] is looped back to: the one of the interface that owns
+    /// [ipv4_addr], or the one of the primary interface for loopback addresses. Returns None for remote addresses.
+    fn local_link_addr(&self, ipv4_addr: Ipv4Addr) -> Option<MacAddress> {
+        if ipv4_addr.is_loopback() {
+            return Some(self.interfaces[0].0);
+        }
+        self.interfaces
+            .iter()
+            .find(|(_, local_ipv4_addr)| *local_ipv4_addr == ipv4_addr)
+            .map(|(link_addr, _)| *link_addr)
+    }
+
+    /// Gets the index of the interface that traffic toward [ipv4_addr] leaves through, and its next hop. Traffic that
+    /// is bound to a [device] leaves through it whatever the routes say: toward the gateway of the route if that route
+    /// goes through the device, or else straight to [ipv4_addr], as if it were directly connected.
+    fn egress(&self, ipv4_addr: Ipv4Addr, device: Option<usize>) -> Result<(usize, Ipv4Addr), Fail> {
+        if let Some(device) = device {
+            if device >= self.interfaces.len() {
+                let cause: String = format!("socket is bound to a missing interface (device={})", device);
+                warn!("egress(): {}", cause);
+                return Err(Fail::new(libc::ENODEV, &cause));
+            }
+            return match self.routes.lookup_via(ipv4_addr, device) {
+                Some(route) => Ok((device, route.next_hop(ipv4_addr))),
+                None => Ok((device, ipv4_addr)),
+            };
+        }
+        let route: &Route = self.routes.route(ipv4_addr)?;
+        if route.interface() >= self.interfaces.len() {
+            let cause: String = format!("route is through a missing interface (route={})", route);
+            warn!("egress(): {}", cause);
+            return Err(Fail::new(libc::ENETUNREACH, &cause));
+        }
+        Ok((route.interface(), route.next_hop(ipv4_addr)))
+    }
+
+    /// Counts a lookup in the next-hop cache of a connection, which was a [hit] or a miss.
+    pub fn record_next_hop(&mut self, hit: bool) {
+        match hit {
+            true => self.next_hop_stats.hits += 1,
+            false => self.next_hop_stats.misses += 1,
+        }
+    }
+
+    /// Gets the statistics of the next-hop caches of connections.
+    pub fn get_next_hop_stats(&self) -> NextHopStats {
+        self.next_hop_stats
+    }
+
+    /// Gets the link address of the next hop toward [ipv4_addr] from the ARP cache, if it is resolved and there is a
+    /// route toward [ipv4_addr]. Local addresses resolve to the link address of the stack.
     pub fn try_query(&self, ipv4_addr: Ipv4Addr) -> Option<MacAddress> {
-        self.cache.get(ipv4_addr).cloned()
+        self.try_query_via(ipv4_addr, None)
     }
 
+    /// Gets the link address of the next hop toward [ipv4_addr] like [try_query], but for traffic that leaves through
+    /// [device] if set.
+    pub fn try_query_via(&self, ipv4_addr: Ipv4Addr, device: Option<usize>) -> Option<MacAddress> {
+        if let Some(link_addr) = self.local_link_addr(ipv4_addr) {
+            return Some(link_addr);
+        }
+        let (_, next_hop): (usize, Ipv4Addr) = self.egress(ipv4_addr, device).ok()?;
+        self.cache.get(next_hop).cloned()
+    }
+
+    /// Resolves the link address of the next hop toward [ipv4_addr], which is [ipv4_addr] itself if it is directly
+    /// connected or else the gateway of the route toward it. Requests go out on the interface of that route. Local
+    /// addresses resolve to the link address of the stack, without any request.
     pub async fn query(&mut self, ipv4_addr: Ipv4Addr) -> Result<MacAddress, Fail> {
+        self.query_via(ipv4_addr, None).await
+    }
+
+    /// Resolves the link address of the next hop toward [ipv4_addr] like [query], but for traffic that leaves through
+    /// [device] if set.
+    pub async fn query_via(&mut self, ipv4_addr: Ipv4Addr, device: Option<usize>) -> Result<MacAddress, Fail> {
+        if let Some(link_addr) = self.local_link_addr(ipv4_addr) {
+            return Ok(link_addr);
+        }
+        let (interface, ipv4_addr): (usize, Ipv4Addr) = self.egress(ipv4_addr, device)?;
         if let Some(&link_addr) = self.cache.get(ipv4_addr) {
             return Ok(link_addr);
         }
+        let (local_link_addr, local_ipv4_addr): (MacAddress, Ipv4Addr) = self.interfaces[interface];
         let msg = ArpMessage::new(
-            Ethernet2Header::new(MacAddress::broadcast(), self.local_link_addr, EtherType2::Arp),
+            Ethernet2Header::new(MacAddress::broadcast(), local_link_addr, EtherType2::Arp),
             ArpHeader::new(
                 ArpOperation::Request,
-                self.local_link_addr,
-                self.local_ipv4_addr,
+                local_link_addr,
+                local_ipv4_addr,
                 MacAddress::broadcast(),
                 ipv4_addr,
             ),
         );
+        let _pending: PendingQuery<N> = self.wait_pending(ipv4_addr)?;
         let mut peer: SharedArpPeer<N> = self.clone();
         // from TCP/IP illustrated, chapter 4:
         // > The frequency of the ARP request is very close to one per
         // > second, the maximum suggested by [RFC1122].
         let result = {
             for i in 0..self.arp_config.get_retry_count() + 1 {
-                self.network.transmit(Box::new(msg.clone()));
+                if self.may_request(ipv4_addr) {
+                    self.network.transmit_one(Box::new(msg.clone()));
+                }
                 let arp_response = peer.do_wait_link_addr(ipv4_addr);
 
                 match conditional_yield_with_timeout(arp_response, self.arp_config.get_request_timeout()).await {
@@ -300,6 +509,47 @@ impl<N: NetworkRuntime> SharedArpPeer<N> {
         result
     }
 
+    /// Counts one more packet that waits on the resolution of [ipv4_addr]. Fails with ENOBUFS if too many packets are
+    /// waiting already, so that a burst of sends to a dead host cannot take up all transmit buffers.
+    fn wait_pending(&mut self, ipv4_addr: Ipv4Addr) -> Result<PendingQuery<N>, Fail> {
+        let max_pending: usize = self.arp_config.get_max_pending();
+        let pending: &mut usize = self.pending.entry(ipv4_addr).or_insert(0);
+        if *pending >= max_pending {
+            self.pending_drops += 1;
+            let cause: String = format!(
+                "too many packets waiting on address resolution (ipv4_addr={:?}, max_pending={})",
+                ipv4_addr, max_pending
+            );
+            warn!("wait_pending(): {}", cause);
+            return Err(Fail::new(libc::ENOBUFS, &cause));
+        }
+        *pending += 1;
+        Ok(PendingQuery {
+            peer: self.clone(),
+            ipv4_addr,
+        })
+    }
+
+    /// Checks whether a request may be sent to [ipv4_addr] without exceeding the request rate. If so, the time of the
+    /// request is recorded.
+    fn may_request(&mut self, ipv4_addr: Ipv4Addr) -> bool {
+        let interval: Duration = match self.arp_config.get_request_rate() {
+            Some(rate) => Duration::from_secs(1) / rate,
+            None => return true,
+        };
+        let now: Instant = self.runtime.get_now();
+        // Forget about the addresses that may be requested again.
+        self.last_requests
+            .retain(|_, last_request| now.saturating_duration_since(*last_request) < interval);
+        if self.last_requests.contains_key(&ipv4_addr) {
+            self.suppressed_requests += 1;
+            trace!("may_request(): suppressing request (ipv4_addr={:?})", ipv4_addr);
+            return false;
+        }
+        self.last_requests.insert(ipv4_addr, now);
+        true
+    }
+
     #[cfg(test)]
     pub fn export_cache(&self) -> HashMap<Ipv4Addr, MacAddress> {
         self.cache.export()
@@ -310,6 +560,17 @@ impl<N: NetworkRuntime> SharedArpPeer<N> {
 // Trait Implementations
 //======================================================================================================================
 
+impl<N: NetworkRuntime> Drop for PendingQuery<N> {
+    fn drop(&mut self) {
+        if let Some(pending) = self.peer.pending.get_mut(&self.ipv4_addr) {
+            *pending -= 1;
+            if *pending == 0 {
+                self.peer.pending.remove(&self.ipv4_addr);
+            }
+        }
+    }
+}
+
 impl<N: NetworkRuntime> Deref for SharedArpPeer<N> {
     type Target = ArpPeer<N>;
 
@@ -8,10 +8,14 @@
 use crate::{
     inetstack::{
         protocols::{
-            arp::packet::{
-                ArpHeader,
-                ArpMessage,
-                ArpOperation,
+            arp::{
+                packet::{
+                    ArpHeader,
+                    ArpMessage,
+                    ArpOperation,
+                },
+                NextHop,
+                NextHopStats,
             },
             ethernet2::{
                 EtherType2,
@@ -29,6 +33,7 @@ use crate::{
         network::{
             config::{
                 ArpConfig,
+                Route,
                 TcpConfig,
                 UdpConfig,
             },
@@ -230,6 +235,126 @@ fn arp_cache_timeout() -> Result<()> {
     Ok(())
 }
 
+/// Tests that the next hop of a connection is cached until the ARP cache changes.
+#[test]
+fn arp_next_hop_cache() -> Result<()> {
+    let mut now: Instant = Instant::now();
+    let local_mac: MacAddress = test_helpers::ALICE_MAC;
+    let local_ipv4: Ipv4Addr = test_helpers::ALICE_IPV4;
+    let remote_mac: MacAddress = test_helpers::BOB_MAC;
+    let remote_ipv4: Ipv4Addr = test_helpers::BOB_IPV4;
+    let mut engine: SharedEngine = new_engine(now, &local_mac, &local_ipv4, &remote_mac, &remote_ipv4)?;
+    let mut next_hop: NextHop = NextHop::new(remote_ipv4, None);
+
+    // The first lookup misses, and the next ones hit.
+    let mut arp = engine.get_transport().get_arp();
+    crate::ensure_eq!(next_hop.get(&mut arp), Some(remote_mac));
+    crate::ensure_eq!(next_hop.get(&mut arp), Some(remote_mac));
+
+    // The remote host moves to another link address, which it announces in an ARP request.
+    let moved_mac: MacAddress = MacAddress::new([0xab, 0x89, 0x67, 0x45, 0x23, 0x13]);
+    let pkt: ArpMessage = build_arp_query(&moved_mac, &remote_ipv4, &local_ipv4);
+    engine.receive(serialize_arp_message(&pkt))?;
+    now += Duration::from_micros(1);
+    engine.advance_clock(now);
+    engine.poll();
+
+    // The change of the ARP cache invalidates the cached next hop.
+    crate::ensure_eq!(next_hop.get(&mut arp), Some(moved_mac));
+    crate::ensure_eq!(
+        engine.get_transport().get_next_hop_stats(),
+        NextHopStats { hits: 1, misses: 2 }
+    );
+
+    Ok(())
+}
+
+/// Tests that traffic toward a subnet behind a gateway is sent to the link address of the gateway, and that routing
+/// changes invalidate cached next hops.
+#[test]
+fn arp_route_through_gateway() -> Result<()> {
+    let now: Instant = Instant::now();
+    let local_mac: MacAddress = test_helpers::ALICE_MAC;
+    let local_ipv4: Ipv4Addr = test_helpers::ALICE_IPV4;
+    let gateway_mac: MacAddress = test_helpers::BOB_MAC;
+    let gateway_ipv4: Ipv4Addr = test_helpers::BOB_IPV4;
+    let engine: SharedEngine = new_engine(now, &local_mac, &local_ipv4, &gateway_mac, &gateway_ipv4)?;
+    let mut arp = engine.get_transport().get_arp();
+    let remote_ipv4: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 5);
+    let mut next_hop: NextHop = NextHop::new(remote_ipv4, None);
+
+    // Without routes, the remote host is directly connected, so its address is not resolved.
+    crate::ensure_eq!(next_hop.get(&mut arp), None);
+
+    arp.add_route(Route::new(Ipv4Addr::new(10, 0, 0, 0), 8, Some(gateway_ipv4))?)?;
+    crate::ensure_eq!(arp.try_query(remote_ipv4), Some(gateway_mac));
+    crate::ensure_eq!(next_hop.get(&mut arp), Some(gateway_mac));
+    crate::ensure_eq!(next_hop.get(&mut arp), Some(gateway_mac));
+
+    // Once the default route is removed, hosts outside of the routed subnet are unreachable.
+    arp.remove_route(Ipv4Addr::UNSPECIFIED, 0)?;
+    crate::ensure_eq!(arp.try_query(gateway_ipv4), None);
+    crate::ensure_eq!(
+        arp.query(gateway_ipv4)
+            .now_or_never()
+            .map(|result| result.unwrap_err().errno),
+        Some(libc::ENETUNREACH)
+    );
+
+    // Removing the route toward the subnet invalidates the cached next hop.
+    arp.remove_route(Ipv4Addr::new(10, 0, 0, 0), 8)?;
+    crate::ensure_eq!(next_hop.get(&mut arp), None);
+    crate::ensure_eq!(
+        engine.get_transport().get_next_hop_stats(),
+        NextHopStats { hits: 1, misses: 3 }
+    );
+
+    Ok(())
+}
+
+/// Tests that the packets waiting on the resolution of an address are bounded, and that requests to each destination are
+/// rate limited.
+#[test]
+fn arp_pending_and_request_rate() -> Result<()> {
+    let mut now: Instant = Instant::now();
+    let local_mac: MacAddress = test_helpers::ALICE_MAC;
+    let local_ipv4: Ipv4Addr = test_helpers::ALICE_IPV4;
+    let remote_mac: MacAddress = test_helpers::BOB_MAC;
+    let remote_ipv4: Ipv4Addr = test_helpers::BOB_IPV4;
+    let mut engine: SharedEngine = new_engine(now, &local_mac, &local_ipv4, &remote_mac, &remote_ipv4)?;
+    engine.set_tunable("arp.max_pending", "2")?;
+
+    // Resolve an address that does not answer. The third packet does not fit, and only the first request is sent.
+    let mut queries = Vec::new();
+    for _ in 0..2 {
+        let mut arp = engine.get_transport().get_arp();
+        let mut query = Box::pin(async move { arp.query(test_helpers::CARRIE_IPV4).await });
+        crate::ensure_eq!(query.as_mut().now_or_never().is_none(), true);
+        queries.push(query);
+    }
+    let mut arp = engine.get_transport().get_arp();
+    crate::ensure_eq!(
+        arp.query(test_helpers::CARRIE_IPV4)
+            .now_or_never()
+            .map(|result| result.unwrap_err().errno),
+        Some(libc::ENOBUFS)
+    );
+    crate::ensure_eq!(engine.pop_all_frames().len(), 1);
+    crate::ensure_eq!(engine.get_tunable("arp.pending_drops")?, "1".to_string());
+    crate::ensure_eq!(engine.get_tunable("arp.requests_suppressed")?, "1".to_string());
+
+    // Packets that stop waiting make room for others, and requests go out again once the rate allows.
+    drop(queries);
+    now += Duration::from_secs(1);
+    engine.advance_clock(now);
+    let mut arp = engine.get_transport().get_arp();
+    let mut query = Box::pin(async move { arp.query(test_helpers::CARRIE_IPV4).await });
+    crate::ensure_eq!(query.as_mut().now_or_never().is_none(), true);
+    crate::ensure_eq!(engine.pop_all_frames().len(), 1);
+
+    Ok(())
+}
+
 //======================================================================================================================
 // Test Helpers
 //======================================================================================================================
@@ -0,0 +1,203 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Vectorized computation of the ones-complement sum that underlies the Internet checksum (RFC 1071).
+//!
+//! The sum of big-endian 16-bit words equals 256 times the sum of the bytes at even offsets plus the sum of the bytes at
+//! odd offsets, so vector units may add bytes up without byte swapping and without carries between lanes. The widest
+//! instruction set that the CPU supports is detected at runtime, and the scalar loop is the fallback.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use ::std::slice::ChunksExact;
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Sums the big-endian 16-bit words of [buf], padding the last byte with zero if [buf] has an odd length. The result
+/// is not folded, and equals that of [sum_scalar] for every buffer.
+pub fn sum(buf: &[u8]) -> u32 {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if ::std::is_x86_feature_detected!("avx2") {
+            // Safety: the CPU supports AVX2.
+            return unsafe { x86_64::sum_avx2(buf) };
+        }
+        // Safety: SSE2 is part of the x86_64 baseline.
+        return unsafe { x86_64::sum_sse2(buf) };
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        if ::std::arch::is_aarch64_feature_detected!("neon") {
+            // Safety: the CPU supports NEON.
+            return unsafe { aarch64::sum_neon(buf) };
+        }
+    }
+
+    #[allow(unreachable_code)]
+    sum_scalar(buf)
+}
+
+/// Sums the big-endian 16-bit words of [buf] one at a time.
+pub fn sum_scalar(buf: &[u8]) -> u32 {
+    let mut state: u32 = 0;
+    let mut chunks_iter: ChunksExact<u8> = buf.chunks_exact(2);
+    while let Some(chunk) = chunks_iter.next() {
+        state = state.wrapping_add(u16::from_be_bytes([chunk[0], chunk[1]]) as u32);
+    }
+    // Pad with zero if the buffer has an odd number of bytes.
+    if let Some(&b) = chunks_iter.remainder().get(0) {
+        state = state.wrapping_add(u16::from_be_bytes([b, 0]) as u32);
+    }
+    state
+}
+
+/// Combines the sums of the bytes at even and odd offsets of the vectorized part of a buffer with the scalar sum of
+/// its [tail]. Vectorized parts always have an even length, so the tail starts at an even offset.
+#[allow(dead_code)]
+fn combine(even: u64, odd: u64, tail: &[u8]) -> u32 {
+    (((even << 8) + odd) as u32).wrapping_add(sum_scalar(tail))
+}
+
+//======================================================================================================================
+// x86_64
+//======================================================================================================================
+
+#[cfg(target_arch = "x86_64")]
+mod x86_64 {
+    use ::std::arch::x86_64::*;
+
+    /// Sums [buf] 32 bytes at a time with AVX2.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn sum_avx2(buf: &[u8]) -> u32 {
+        let zero: __m256i = _mm256_setzero_si256();
+        let low_bytes: __m256i = _mm256_set1_epi16(0x00ff);
+        let mut even: __m256i = zero;
+        let mut odd: __m256i = zero;
+        let mut chunks = buf.chunks_exact(32);
+        for chunk in &mut chunks {
+            let v: __m256i = _mm256_loadu_si256(chunk.as_ptr() as *const __m256i);
+            // Within each little-endian 16-bit lane, the low byte is at an even offset and the high byte at an odd one.
+            // Sum of absolute differences against zero adds up the bytes of each 64-bit lane.
+            even = _mm256_add_epi64(even, _mm256_sad_epu8(_mm256_and_si256(v, low_bytes), zero));
+            odd = _mm256_add_epi64(odd, _mm256_sad_epu8(_mm256_srli_epi16(v, 8), zero));
+        }
+        let mut lanes: [u64; 4] = [0; 4];
+        _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, even);
+        let even: u64 = lanes.iter().sum();
+        _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, odd);
+        let odd: u64 = lanes.iter().sum();
+        super::combine(even, odd, chunks.remainder())
+    }
+
+    /// Sums [buf] 16 bytes at a time with SSE2.
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn sum_sse2(buf: &[u8]) -> u32 {
+        let zero: __m128i = _mm_setzero_si128();
+        let low_bytes: __m128i = _mm_set1_epi16(0x00ff);
+        let mut even: __m128i = zero;
+        let mut odd: __m128i = zero;
+        let mut chunks = buf.chunks_exact(16);
+        for chunk in &mut chunks {
+            let v: __m128i = _mm_loadu_si128(chunk.as_ptr() as *const __m128i);
+            even = _mm_add_epi64(even, _mm_sad_epu8(_mm_and_si128(v, low_bytes), zero));
+            odd = _mm_add_epi64(odd, _mm_sad_epu8(_mm_srli_epi16(v, 8), zero));
+        }
+        let mut lanes: [u64; 2] = [0; 2];
+        _mm_storeu_si128(lanes.as_mut_ptr() as *mut __m128i, even);
+        let even: u64 = lanes.iter().sum();
+        _mm_storeu_si128(lanes.as_mut_ptr() as *mut __m128i, odd);
+        let odd: u64 = lanes.iter().sum();
+        super::combine(even, odd, chunks.remainder())
+    }
+}
+
+//======================================================================================================================
+// aarch64
+//======================================================================================================================
+
+#[cfg(target_arch = "aarch64")]
+mod aarch64 {
+    use ::std::arch::aarch64::*;
+
+    /// Sums [buf] 32 bytes at a time with NEON.
+    #[target_feature(enable = "neon")]
+    pub unsafe fn sum_neon(buf: &[u8]) -> u32 {
+        let mut even: uint64x2_t = vdupq_n_u64(0);
+        let mut odd: uint64x2_t = vdupq_n_u64(0);
+        let mut chunks = buf.chunks_exact(32);
+        for chunk in &mut chunks {
+            // Deinterleave the bytes at even and odd offsets, then widen them pairwise up to 64 bits.
+            let v: uint8x16x2_t = vld2q_u8(chunk.as_ptr());
+            even = vpadalq_u32(even, vpaddlq_u16(vpaddlq_u8(v.0)));
+            odd = vpadalq_u32(odd, vpaddlq_u16(vpaddlq_u8(v.1)));
+        }
+        super::combine(vaddvq_u64(even), vaddvq_u64(odd), chunks.remainder())
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Builds a buffer of [len] pseudo-random bytes.
+    fn buffer(len: usize) -> Vec<u8> {
+        let mut state: u32 = 0x12345678;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                (state >> 24) as u8
+            })
+            .collect()
+    }
+
+    /// Tests that vectorized sums match scalar sums for lengths around the vector widths.
+    #[test]
+    fn sum_matches_scalar() {
+        for len in (0..=130).chain([1459, 1460, 1472, 8960, 9001]) {
+            let buf: Vec<u8> = buffer(len);
+            assert_eq!(sum(&buf), sum_scalar(&buf), "len={}", len);
+            // Also check unaligned starts.
+            if len > 0 {
+                assert_eq!(sum(&buf[1..]), sum_scalar(&buf[1..]), "len={}", len - 1);
+            }
+        }
+    }
+
+    /// Tests that all-ones buffers, which have the largest sums, do not lose carries.
+    #[test]
+    fn sum_of_ones() {
+        let buf: Vec<u8> = vec![0xff; 65535];
+        assert_eq!(sum(&buf), sum_scalar(&buf));
+        assert_eq!(sum_scalar(&buf), 32767 * 0xffff + 0xff00);
+    }
+
+    /// Tests that every available implementation matches the scalar one.
+    #[test]
+    fn implementations_match_scalar() {
+        let buf: Vec<u8> = buffer(4099);
+        let expected: u32 = sum_scalar(&buf);
+        #[cfg(target_arch = "x86_64")]
+        {
+            assert_eq!(unsafe { x86_64::sum_sse2(&buf) }, expected);
+            if ::std::is_x86_feature_detected!("avx2") {
+                assert_eq!(unsafe { x86_64::sum_avx2(&buf) }, expected);
+            }
+        }
+        #[cfg(target_arch = "aarch64")]
+        {
+            if ::std::arch::is_aarch64_feature_detected!("neon") {
+                assert_eq!(unsafe { aarch64::sum_neon(&buf) }, expected);
+            }
+        }
+        assert_eq!(sum(&buf), expected);
+    }
+}
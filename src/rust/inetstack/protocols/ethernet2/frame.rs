@@ -2,7 +2,14 @@
 // Licensed under the MIT license.
 
 use crate::{
-    inetstack::protocols::ethernet2::EtherType2,
+    inetstack::protocols::ethernet2::{
+        vlan::{
+            VlanTag,
+            VLAN_TAG_SIZE,
+            VLAN_TPID,
+        },
+        EtherType2,
+    },
     runtime::{
         fail::Fail,
         memory::DemiBuffer,
@@ -22,6 +29,12 @@ pub struct Ethernet2Header {
     src_addr: MacAddress,
     // Bytes 12..14
     ether_type: EtherType2,
+    /// IEEE 802.1Q tag of a received frame, which goes right before the EtherType. Frames that are built by the
+    /// network stack are untagged, and are tagged by the network runtime if it is configured to.
+    vlan: Option<VlanTag>,
+    /// Priority of a frame that is built by the network stack. It becomes the priority code point of the frame if the
+    /// network runtime tags it.
+    priority: Option<u8>,
 }
 
 impl Ethernet2Header {
@@ -31,9 +44,17 @@ impl Ethernet2Header {
             dst_addr,
             src_addr,
             ether_type,
+            vlan: None,
+            priority: None,
         }
     }
 
+    /// Sets the priority of the frame.
+    pub fn with_priority(mut self, priority: Option<u8>) -> Self {
+        self.priority = priority;
+        self
+    }
+
     pub fn compute_size(&self) -> usize {
         ETHERNET2_HEADER_SIZE
     }
@@ -42,17 +63,28 @@ impl Ethernet2Header {
         if buf.len() < ETHERNET2_HEADER_SIZE {
             return Err(Fail::new(EBADMSG, "frame too small"));
         }
-        let hdr_buf = &buf[..ETHERNET2_HEADER_SIZE];
+        let mut hdr_size: usize = ETHERNET2_HEADER_SIZE;
+        let mut vlan: Option<VlanTag> = None;
+        if u16::from_be_bytes([buf[12], buf[13]]) == VLAN_TPID {
+            hdr_size += VLAN_TAG_SIZE;
+            if buf.len() < hdr_size {
+                return Err(Fail::new(EBADMSG, "frame too small"));
+            }
+            vlan = Some(VlanTag::from_tci(u16::from_be_bytes([buf[14], buf[15]])));
+        }
+        let hdr_buf = &buf[..hdr_size];
         let dst_addr = MacAddress::from_bytes(&hdr_buf[0..6]);
         let src_addr = MacAddress::from_bytes(&hdr_buf[6..12]);
-        let ether_type = EtherType2::try_from(u16::from_be_bytes([hdr_buf[12], hdr_buf[13]]))?;
+        let ether_type = EtherType2::try_from(u16::from_be_bytes([hdr_buf[hdr_size - 2], hdr_buf[hdr_size - 1]]))?;
         let hdr = Self {
             dst_addr,
             src_addr,
             ether_type,
+            vlan,
+            priority: vlan.map(|tag| tag.pcp()),
         };
 
-        buf.adjust(ETHERNET2_HEADER_SIZE)?;
+        buf.adjust(hdr_size)?;
         Ok((hdr, buf))
     }
 
@@ -74,4 +106,12 @@ impl Ethernet2Header {
     pub fn ether_type(&self) -> EtherType2 {
         self.ether_type
     }
+
+    pub fn vlan(&self) -> Option<VlanTag> {
+        self.vlan
+    }
+
+    pub fn priority(&self) -> Option<u8> {
+        self.priority
+    }
 }
@@ -3,6 +3,7 @@
 
 mod frame;
 mod protocol;
+mod vlan;
 
 pub use self::{
     frame::{
@@ -11,4 +12,10 @@ pub use self::{
         MIN_PAYLOAD_SIZE,
     },
     protocol::EtherType2,
+    vlan::{
+        VlanTag,
+        VlanTaggedPacket,
+        VLAN_TAG_SIZE,
+        VLAN_TPID,
+    },
 };
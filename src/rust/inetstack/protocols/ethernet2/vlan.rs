@@ -0,0 +1,265 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    memory::DemiBuffer,
+    network::PacketBuf,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Tag protocol identifier of IEEE 802.1Q, which takes the place of the EtherType in tagged frames.
+pub const VLAN_TPID: u16 = 0x8100;
+
+/// Size of an IEEE 802.1Q tag.
+pub const VLAN_TAG_SIZE: usize = 4;
+
+/// Largest VLAN ID that may be assigned to a network. IDs 0 and 0xfff are reserved.
+const MAX_VLAN_ID: u16 = 0xffe;
+
+/// Largest priority code point.
+const MAX_VLAN_PCP: u8 = 7;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// IEEE 802.1Q tag.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VlanTag {
+    /// VLAN identifier. Frames with an ID of zero only carry a priority.
+    vid: u16,
+    /// Priority code point.
+    pcp: u8,
+}
+
+/// A packet that is tagged right before it is handed to the device, for devices that do not insert tags themselves.
+pub struct VlanTaggedPacket {
+    pkt: Box<dyn PacketBuf>,
+    tag: VlanTag,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl VlanTag {
+    /// Creates a tag for the VLAN with ID [vid], with priority [pcp].
+    pub fn new(vid: u16, pcp: u8) -> Result<Self, Fail> {
+        if !(1..=MAX_VLAN_ID).contains(&vid) {
+            let cause: String = format!("invalid VLAN ID (vid={})", vid);
+            error!("new(): {}", cause);
+            return Err(Fail::new(libc::ERANGE, &cause));
+        }
+        if pcp > MAX_VLAN_PCP {
+            let cause: String = format!("invalid VLAN priority (pcp={})", pcp);
+            error!("new(): {}", cause);
+            return Err(Fail::new(libc::ERANGE, &cause));
+        }
+        Ok(Self { vid, pcp })
+    }
+
+    /// Creates a tag from a tag control information field. The drop eligible indicator is ignored.
+    pub fn from_tci(tci: u16) -> Self {
+        Self {
+            vid: tci & 0xfff,
+            pcp: (tci >> 13) as u8,
+        }
+    }
+
+    /// Gets the tag control information field of this tag.
+    pub fn tci(&self) -> u16 {
+        ((self.pcp as u16) << 13) | self.vid
+    }
+
+    pub fn vid(&self) -> u16 {
+        self.vid
+    }
+
+    pub fn pcp(&self) -> u8 {
+        self.pcp
+    }
+
+    /// Gets a copy of this tag with priority [pcp]. Priorities are masked to the width of the field.
+    pub fn with_pcp(&self, pcp: u8) -> Self {
+        Self {
+            vid: self.vid,
+            pcp: pcp & MAX_VLAN_PCP,
+        }
+    }
+
+    /// Writes this tag, starting with the tag protocol identifier, into [buf].
+    pub fn serialize(&self, buf: &mut [u8]) {
+        let buf: &mut [u8; VLAN_TAG_SIZE] = buf.try_into().unwrap();
+        buf[0..2].copy_from_slice(&VLAN_TPID.to_be_bytes());
+        buf[2..4].copy_from_slice(&self.tci().to_be_bytes());
+    }
+}
+
+impl VlanTaggedPacket {
+    /// Tags the untagged Ethernet frame [pkt] with [tag].
+    pub fn new(pkt: Box<dyn PacketBuf>, tag: VlanTag) -> Self {
+        Self { pkt, tag }
+    }
+
+    /// Gets the tag of this packet. The priority of the packet, if it has one, overrides the configured one.
+    fn tag(&self) -> VlanTag {
+        match self.pkt.priority() {
+            Some(pcp) => self.tag.with_pcp(pcp),
+            None => self.tag,
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl PacketBuf for VlanTaggedPacket {
+    fn header_size(&self) -> usize {
+        self.pkt.header_size() + VLAN_TAG_SIZE
+    }
+
+    fn write_header(&self, buf: &mut [u8]) {
+        // The tag goes between the source address and the EtherType, so the addresses are moved ahead of it.
+        self.pkt.write_header(&mut buf[VLAN_TAG_SIZE..]);
+        buf.copy_within(VLAN_TAG_SIZE..(VLAN_TAG_SIZE + 12), 0);
+        self.tag().serialize(&mut buf[12..(12 + VLAN_TAG_SIZE)]);
+    }
+
+    fn body_size(&self) -> usize {
+        self.pkt.body_size()
+    }
+
+    fn take_body(&self) -> Option<DemiBuffer> {
+        self.pkt.take_body()
+    }
+
+    fn tso_segment_size(&self) -> Option<usize> {
+        self.pkt.tso_segment_size()
+    }
+
+    fn priority(&self) -> Option<u8> {
+        self.pkt.priority()
+    }
+
+    fn needs_checksum(&self) -> bool {
+        self.pkt.needs_checksum()
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        VlanTag,
+        VlanTaggedPacket,
+        VLAN_TAG_SIZE,
+    };
+    use crate::{
+        inetstack::protocols::ethernet2::{
+            EtherType2,
+            Ethernet2Header,
+            ETHERNET2_HEADER_SIZE,
+        },
+        runtime::{
+            memory::DemiBuffer,
+            network::{
+                types::MacAddress,
+                PacketBuf,
+            },
+        },
+    };
+    use ::anyhow::Result;
+
+    /// Untagged frame without a body.
+    struct Frame(Ethernet2Header);
+
+    impl PacketBuf for Frame {
+        fn header_size(&self) -> usize {
+            self.0.compute_size()
+        }
+
+        fn write_header(&self, buf: &mut [u8]) {
+            self.0.serialize(buf)
+        }
+
+        fn body_size(&self) -> usize {
+            0
+        }
+
+        fn take_body(&self) -> Option<DemiBuffer> {
+            None
+        }
+
+        fn priority(&self) -> Option<u8> {
+            self.0.priority()
+        }
+    }
+
+    /// Tests that out of range VLAN IDs and priorities are rejected.
+    #[test]
+    fn test_vlan_tag_bounds() -> Result<()> {
+        crate::ensure_eq!(VlanTag::new(0, 0).is_err(), true);
+        crate::ensure_eq!(VlanTag::new(4095, 0).is_err(), true);
+        crate::ensure_eq!(VlanTag::new(100, 8).is_err(), true);
+        let tag: VlanTag = VlanTag::new(4094, 7)?;
+        crate::ensure_eq!(VlanTag::from_tci(tag.tci()), tag);
+
+        Ok(())
+    }
+
+    /// Tests that a tagged frame is parsed back into the original header and tag.
+    #[test]
+    fn test_vlan_tagged_packet() -> Result<()> {
+        let dst_addr: MacAddress = MacAddress::new([0x02, 0, 0, 0, 0, 1]);
+        let src_addr: MacAddress = MacAddress::new([0x02, 0, 0, 0, 0, 2]);
+        let tag: VlanTag = VlanTag::new(100, 5)?;
+        let pkt: VlanTaggedPacket = VlanTaggedPacket::new(
+            Box::new(Frame(Ethernet2Header::new(dst_addr, src_addr, EtherType2::Ipv4))),
+            tag,
+        );
+        crate::ensure_eq!(pkt.header_size(), ETHERNET2_HEADER_SIZE + VLAN_TAG_SIZE);
+
+        let mut buf: DemiBuffer = DemiBuffer::new(pkt.header_size() as u16);
+        pkt.write_header(&mut buf[..]);
+        crate::ensure_eq!(&buf[12..14], &[0x81, 0x00]);
+
+        let (header, payload): (Ethernet2Header, DemiBuffer) = Ethernet2Header::parse(buf)?;
+        crate::ensure_eq!(header.dst_addr(), dst_addr);
+        crate::ensure_eq!(header.src_addr(), src_addr);
+        crate::ensure_eq!(header.ether_type(), EtherType2::Ipv4);
+        crate::ensure_eq!(header.vlan(), Some(tag));
+        crate::ensure_eq!(payload.len(), 0);
+
+        Ok(())
+    }
+
+    /// Tests that the priority of a frame overrides the configured priority code point.
+    #[test]
+    fn test_vlan_tagged_packet_priority() -> Result<()> {
+        let dst_addr: MacAddress = MacAddress::new([0x02, 0, 0, 0, 0, 1]);
+        let src_addr: MacAddress = MacAddress::new([0x02, 0, 0, 0, 0, 2]);
+        let tag: VlanTag = VlanTag::new(100, 1)?;
+        let header: Ethernet2Header = Ethernet2Header::new(dst_addr, src_addr, EtherType2::Ipv4).with_priority(Some(6));
+        let pkt: VlanTaggedPacket = VlanTaggedPacket::new(Box::new(Frame(header)), tag);
+
+        let mut buf: DemiBuffer = DemiBuffer::new(pkt.header_size() as u16);
+        pkt.write_header(&mut buf[..]);
+        let (header, _): (Ethernet2Header, DemiBuffer) = Ethernet2Header::parse(buf)?;
+        crate::ensure_eq!(header.vlan(), Some(VlanTag::new(100, 6)?));
+        crate::ensure_eq!(header.priority(), Some(6));
+
+        Ok(())
+    }
+}
@@ -4,8 +4,10 @@
 mod datagram;
 mod peer;
 
-// Disable for now due to incorrect use of scheduler.
-// #[cfg(test)]
-// mod tests;
+#[cfg(test)]
+mod tests;
 
-pub use peer::SharedIcmpv4Peer;
+pub use self::{
+    datagram::Icmpv4Header,
+    peer::SharedIcmpv4Peer,
+};
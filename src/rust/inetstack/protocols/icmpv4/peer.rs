@@ -23,6 +23,11 @@ use crate::{
         fail::Fail,
         memory::DemiBuffer,
         network::{
+            admission::Bucket,
+            config::{
+                IcmpClass,
+                IcmpConfig,
+            },
             types::MacAddress,
             NetworkRuntime,
         },
@@ -96,6 +101,15 @@ pub struct Icmpv4Peer<N: NetworkRuntime> {
 
     /// Inflight ping requests.
     inflight: HashMap<(u16, u16), InflightRequest>,
+
+    /// Rate limits and classes of the ICMP messages that we generate.
+    icmp_config: IcmpConfig,
+    /// Token buckets of all generated messages, and of each class of them.
+    bucket: Bucket,
+    class_buckets: HashMap<IcmpClass, Bucket>,
+    /// Number of generated messages that were not sent because of the rate limits, or because their class is disabled.
+    rate_limited: u64,
+    suppressed: u64,
 }
 
 #[derive(Clone)]
@@ -111,6 +125,8 @@ impl<N: NetworkRuntime> SharedIcmpv4Peer<N> {
         rng_seed: [u8; 32],
     ) -> Result<Self, Fail> {
         let rng: SmallRng = SmallRng::from_seed(rng_seed);
+        let icmp_config: IcmpConfig = IcmpConfig::default();
+        let bucket: Bucket = Bucket::new(runtime.get_now(), icmp_config.get_burst());
         let peer: SharedIcmpv4Peer<N> = Self(SharedObject::new(Icmpv4Peer {
             runtime: runtime.clone(),
             transport: transport.clone(),
@@ -121,6 +137,11 @@ impl<N: NetworkRuntime> SharedIcmpv4Peer<N> {
             seq: Wrapping(0),
             rng,
             inflight: HashMap::<(u16, u16), InflightRequest>::new(),
+            icmp_config,
+            bucket,
+            class_buckets: HashMap::new(),
+            rate_limited: 0,
+            suppressed: 0,
         }));
         runtime.insert_background_coroutine("Inetstack::ICMP::background", Box::pin(peer.clone().poll().fuse()))?;
         Ok(peer)
@@ -157,6 +178,11 @@ impl<N: NetworkRuntime> SharedIcmpv4Peer<N> {
                     continue;
                 },
             };
+            // Do not let the stack be used to reflect a flood of echo requests.
+            if !self.admit(IcmpClass::EchoReply) {
+                trace!("poll(): not replying to ping ({}, {}, {})", dst_ipv4_addr, id, seq_num);
+                continue;
+            }
             debug!("initiating ARP query");
             let dst_link_addr: MacAddress = match self.arp.query(dst_ipv4_addr).await {
                 Ok(dst_link_addr) => dst_link_addr,
@@ -169,8 +195,8 @@ impl<N: NetworkRuntime> SharedIcmpv4Peer<N> {
             debug!("reply ping ({}, {}, {})", dst_ipv4_addr, id, seq_num);
             // Send reply message.
             let local_link_addr: MacAddress = self.local_link_addr;
-            let local_ipv4_addr: Ipv4Addr = self.local_ipv4_addr;
-            self.transport.transmit(Box::new(Icmpv4Message::new(
+            let local_ipv4_addr: Ipv4Addr = self.arp.source_addr(dst_ipv4_addr);
+            self.transport.transmit_one(Box::new(Icmpv4Message::new(
                 Ethernet2Header::new(dst_link_addr, local_link_addr, EtherType2::Ipv4),
                 Ipv4Header::new(local_ipv4_addr, dst_ipv4_addr, IpProtocol::ICMPv4),
                 Icmpv4Header::new(Icmpv4Type2::EchoReply { id, seq_num }, 0),
@@ -184,6 +210,56 @@ impl<N: NetworkRuntime> SharedIcmpv4Peer<N> {
         self.recv_queue.push((ipv4_hdr, buf));
     }
 
+    /// Checks whether a generated ICMP message of [class] may be sent now. Messages of disabled classes are never sent,
+    /// and the others are rate limited for their class and for all classes. Refusals are counted.
+    fn admit(&mut self, class: IcmpClass) -> bool {
+        if self.icmp_config.is_disabled(class) {
+            self.suppressed += 1;
+            return false;
+        }
+        let now: Instant = self.runtime.get_now();
+        let peer: &mut Icmpv4Peer<N> = &mut self.0;
+        if let Some(rate) = peer.icmp_config.get_class_rate(class) {
+            let bucket: &mut Bucket = peer
+                .class_buckets
+                .entry(class)
+                .or_insert_with(|| Bucket::new(now, rate));
+            if !bucket.take(now, rate, rate) {
+                peer.rate_limited += 1;
+                return false;
+            }
+        }
+        if let Some(rate) = peer.icmp_config.get_rate() {
+            if !peer.bucket.take(now, rate, peer.icmp_config.get_burst()) {
+                peer.rate_limited += 1;
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Sets the ICMP tunable [name] to [value]. Changing a rate or a burst refills the buckets.
+    pub fn set_tunable(&mut self, name: &str, value: &str) -> Result<(), Fail> {
+        self.icmp_config.set_tunable(name, value)?;
+        if name != "disabled" {
+            let now: Instant = self.runtime.get_now();
+            self.bucket = Bucket::new(now, self.icmp_config.get_burst());
+            self.class_buckets.clear();
+        }
+        Ok(())
+    }
+
+    /// Gets the value of the ICMP tunable [name]. Besides those of [IcmpConfig], "rate_limited" is the number of
+    /// generated messages that were not sent because of the rate limits, and "suppressed" the number of those that were
+    /// not sent because their class is disabled.
+    pub fn get_tunable(&self, name: &str) -> Result<String, Fail> {
+        match name {
+            "rate_limited" => Ok(self.rate_limited.to_string()),
+            "suppressed" => Ok(self.suppressed.to_string()),
+            _ => self.icmp_config.get_tunable(name),
+        }
+    }
+
     /// Computes the identifier for an ICMP message.
     fn make_id(&mut self) -> u16 {
         let mut state: u32 = 0xFFFF;
@@ -227,11 +303,11 @@ impl<N: NetworkRuntime> SharedIcmpv4Peer<N> {
 
         let msg: Icmpv4Message = Icmpv4Message::new(
             Ethernet2Header::new(dst_link_addr, self.local_link_addr, EtherType2::Ipv4),
-            Ipv4Header::new(self.local_ipv4_addr, dst_ipv4_addr, IpProtocol::ICMPv4),
+            Ipv4Header::new(self.arp.source_addr(dst_ipv4_addr), dst_ipv4_addr, IpProtocol::ICMPv4),
             Icmpv4Header::new(echo_request, 0),
             data,
         );
-        self.transport.transmit(Box::new(msg));
+        self.transport.transmit_one(Box::new(msg));
         let condition_variable: SharedConditionVariable = SharedConditionVariable::default();
         self.inflight
             .insert((id, seq_num), InflightRequest::Inflight(condition_variable));
@@ -116,3 +116,45 @@ fn ipv4_ping_loop() -> Result<()> {
 
     Ok(())
 }
+
+/// Tests that echo replies are rate limited, and that they are not sent at all once they are disabled.
+#[test]
+fn ipv4_ping_rate_limit() -> Result<()> {
+    let mut ctx = Context::from_waker(noop_waker_ref());
+    let mut now = Instant::now();
+
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+
+    let mut bob: SharedEngine = test_helpers::new_bob2(now);
+    bob.set_tunable("icmp.echo_reply_rate", "1")?;
+
+    for i in 0..4 {
+        if i == 3 {
+            // Let the rate limit refill, then disable echo replies.
+            now += Duration::from_secs(1);
+            alice.advance_clock(now);
+            bob.advance_clock(now);
+            bob.set_tunable("icmp.disabled", "echo_reply")?;
+        }
+
+        // Alice pings Bob.
+        let mut alice_transport = alice.get_transport();
+        let ping_fut = alice_transport.ping(test_helpers::BOB_IPV4, None);
+        pin_mut!(ping_fut);
+        match Future::poll(Pin::new(&mut ping_fut), &mut ctx) {
+            Poll::Pending => {},
+            _ => anyhow::bail!("Ping should not have completed"),
+        };
+
+        // Bob receives ping request from Alice.
+        bob.receive(alice.pop_frame())?;
+        bob.poll();
+    }
+
+    // Bob only replied to the first ping.
+    crate::ensure_eq!(bob.pop_all_frames().len(), 1);
+    crate::ensure_eq!(bob.get_tunable("icmp.rate_limited")?, "2".to_string());
+    crate::ensure_eq!(bob.get_tunable("icmp.suppressed")?, "1".to_string());
+
+    Ok(())
+}
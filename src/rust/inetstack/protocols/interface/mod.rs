@@ -0,0 +1,422 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Network interfaces of a stack. Besides the primary interface that it is created with, a stack may attach secondary
+//! interfaces, each with its own device and its own link and IPv4 addresses, so that one process can bridge several
+//! networks (e.g. a front-end and a back-end network).
+//!
+//! All interfaces share the IPv4 protocols and their sockets. Routes pick the interface that traffic toward a
+//! destination leaves through (see [crate::runtime::network::config::Route::with_interface]), and with it the source
+//! address of sockets that are not bound to one. Frames then leave through the interface that owns their source
+//! address, with the link address of that interface.
+//!
+//! Datagrams toward an address of the stack, or toward the loopback subnet (127.0.0.0/8), never reach a device: they
+//! are looped back to the receive path of the stack, so that sockets of the same process can talk to each other. Their
+//! checksums are left as they are, so devices that compute them on transmission must check them on reception too.
+
+#[cfg(test)]
+mod tests;
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    demi_sgarray_t,
+    inetstack::protocols::ethernet2::{
+        EtherType2,
+        ETHERNET2_HEADER_SIZE,
+        VLAN_TAG_SIZE,
+        VLAN_TPID,
+    },
+    runtime::{
+        fail::Fail,
+        memory::{
+            DemiBuffer,
+            MemoryRuntime,
+        },
+        network::{
+            config::{
+                ArpConfig,
+                TcpConfig,
+                UdpConfig,
+            },
+            consts::MAX_BATCH_SIZE,
+            tls::offload::TlsOffload,
+            types::MacAddress,
+            NetworkRuntime,
+            PacketBatch,
+            PacketBuf,
+        },
+        SharedObject,
+    },
+};
+use ::arrayvec::ArrayVec;
+use ::std::{
+    collections::VecDeque,
+    net::Ipv4Addr,
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    rc::Rc,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Index of the primary interface.
+pub const PRIMARY_INTERFACE: usize = 0;
+
+/// Offset of the source address in IPv4 headers.
+const IPV4_SRC_ADDR_OFFSET: usize = 12;
+
+/// Offset of the destination address in IPv4 headers.
+const IPV4_DST_ADDR_OFFSET: usize = 16;
+
+/// Offset of the sender protocol address in ARP messages.
+const ARP_SENDER_PROTOCOL_ADDR_OFFSET: usize = 14;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Network interface, with its device and addresses.
+struct Interface<N: NetworkRuntime> {
+    network: N,
+    link_addr: MacAddress,
+    ipv4_addr: Ipv4Addr,
+}
+
+/// Network runtime that spreads the traffic of the stack over its interfaces.
+pub struct Interfaces<N: NetworkRuntime> {
+    /// Interfaces, the primary one first.
+    interfaces: Vec<Interface<N>>,
+    /// Frames that were looped back, waiting to be received.
+    loopback: VecDeque<DemiBuffer>,
+    /// Interface that is polled first for incoming frames. The loopback queue takes its turn after the last interface.
+    next_rx: usize,
+}
+
+#[derive(Clone)]
+pub struct SharedInterfaces<N: NetworkRuntime>(SharedObject<Interfaces<N>>);
+
+/// Where a frame leaves through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Egress {
+    /// Interface with this index.
+    Interface(usize),
+    /// Loopback queue of the stack.
+    Loopback,
+}
+
+/// Frame that leaves through a secondary interface, and thus carries the link address of that interface.
+struct EgressFrame {
+    pkt: Box<dyn PacketBuf>,
+    link_addr: MacAddress,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl<N: NetworkRuntime> SharedInterfaces<N> {
+    /// Creates the interfaces of a stack, starting with the primary one.
+    pub fn new(network: N, link_addr: MacAddress, ipv4_addr: Ipv4Addr) -> Self {
+        Self(SharedObject::new(Interfaces {
+            interfaces: vec![Interface {
+                network,
+                link_addr,
+                ipv4_addr,
+            }],
+            loopback: VecDeque::new(),
+            next_rx: PRIMARY_INTERFACE,
+        }))
+    }
+
+    /// Attaches a secondary interface and returns its index. Interfaces must have distinct addresses.
+    pub fn attach(&mut self, network: N, link_addr: MacAddress, ipv4_addr: Ipv4Addr) -> Result<usize, Fail> {
+        if !link_addr.is_unicast() || ipv4_addr.is_unspecified() || ipv4_addr.is_broadcast() || ipv4_addr.is_multicast()
+        {
+            let cause: String = format!(
+                "interfaces must have unicast addresses (link_addr={:?}, ipv4_addr={})",
+                link_addr, ipv4_addr
+            );
+            error!("attach(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        if self
+            .interfaces
+            .iter()
+            .any(|interface| interface.link_addr == link_addr || interface.ipv4_addr == ipv4_addr)
+        {
+            let cause: String = format!(
+                "there is an interface with the same address already (link_addr={:?}, ipv4_addr={})",
+                link_addr, ipv4_addr
+            );
+            error!("attach(): {}", cause);
+            return Err(Fail::new(libc::EEXIST, &cause));
+        }
+        self.interfaces.push(Interface {
+            network,
+            link_addr,
+            ipv4_addr,
+        });
+        Ok(self.interfaces.len() - 1)
+    }
+
+    /// Checks whether [link_addr] is the link address of an interface.
+    pub fn is_local_link_addr(&self, link_addr: MacAddress) -> bool {
+        self.interfaces.iter().any(|interface| interface.link_addr == link_addr)
+    }
+
+    /// Checks whether [ipv4_addr] is the address of an interface or a loopback address.
+    pub fn is_local_addr(&self, ipv4_addr: Ipv4Addr) -> bool {
+        ipv4_addr.is_loopback() || self.interfaces.iter().any(|interface| interface.ipv4_addr == ipv4_addr)
+    }
+
+    /// Gets the link address of the interface with index [interface].
+    pub fn get_link_addr(&self, interface: usize) -> MacAddress {
+        self.interfaces[interface].link_addr
+    }
+
+    /// Gets the number of interfaces, including the primary one.
+    pub fn num_interfaces(&self) -> usize {
+        self.interfaces.len()
+    }
+
+    /// Gets the device of the primary interface.
+    fn primary(&self) -> &N {
+        &self.interfaces[PRIMARY_INTERFACE].network
+    }
+
+    /// Gets where [pkt] leaves through: the loopback queue if it carries a datagram toward a local address, or else the
+    /// interface that owns the source IPv4 address of the datagram or ARP message that it carries, or else the primary
+    /// interface.
+    fn egress(&self, pkt: &dyn PacketBuf) -> Egress {
+        let mut header: Vec<u8> = vec![0; pkt.header_size()];
+        pkt.write_header(&mut header);
+        let src_addr: Option<Ipv4Addr> = match parse_ether_type(&header) {
+            Some((EtherType2::Ipv4, offset)) => {
+                if let Some(dst_addr) = read_ipv4_addr(&header, offset + IPV4_DST_ADDR_OFFSET) {
+                    if self.is_local_addr(dst_addr) {
+                        return Egress::Loopback;
+                    }
+                }
+                read_ipv4_addr(&header, offset + IPV4_SRC_ADDR_OFFSET)
+            },
+            Some((EtherType2::Arp, offset)) => read_ipv4_addr(&header, offset + ARP_SENDER_PROTOCOL_ADDR_OFFSET),
+            _ => None,
+        };
+        let index: Option<usize> = src_addr.and_then(|src_addr| {
+            self.interfaces
+                .iter()
+                .position(|interface| interface.ipv4_addr == src_addr)
+        });
+        Egress::Interface(index.unwrap_or(PRIMARY_INTERFACE))
+    }
+
+    /// Serializes [pkt] into the loopback queue.
+    fn loop_back(&mut self, pkt: Box<dyn PacketBuf>) {
+        let header_size: usize = pkt.header_size();
+        let body_size: usize = pkt.body_size();
+        let size: u16 = match u16::try_from(header_size + body_size) {
+            Ok(size) => size,
+            Err(_) => {
+                warn!(
+                    "loop_back(): dropping oversized frame (size={})",
+                    header_size + body_size
+                );
+                return;
+            },
+        };
+        let mut buf: DemiBuffer = DemiBuffer::new(size);
+        pkt.write_header(&mut buf[..header_size]);
+        if let Some(body) = pkt.take_body() {
+            buf[header_size..].copy_from_slice(&body[..]);
+        }
+        self.loopback.push_back(buf);
+    }
+
+    /// Takes a batch of frames from the loopback queue.
+    fn receive_loopback(&mut self) -> ArrayVec<DemiBuffer, MAX_BATCH_SIZE> {
+        let count: usize = self.loopback.len().min(MAX_BATCH_SIZE);
+        self.loopback.drain(..count).collect()
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl<N: NetworkRuntime> Deref for SharedInterfaces<N> {
+    type Target = Interfaces<N>;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl<N: NetworkRuntime> DerefMut for SharedInterfaces<N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+/// Buffers are handed out by the device of the primary interface.
+impl<N: NetworkRuntime> MemoryRuntime for SharedInterfaces<N> {
+    fn into_sgarray(&self, buf: DemiBuffer) -> Result<demi_sgarray_t, Fail> {
+        self.primary().into_sgarray(buf)
+    }
+
+    fn sgaalloc(&self, size: usize) -> Result<demi_sgarray_t, Fail> {
+        self.primary().sgaalloc(size)
+    }
+
+    fn sgafree(&self, sga: demi_sgarray_t) -> Result<(), Fail> {
+        self.primary().sgafree(sga)
+    }
+
+    fn clone_sgarray(&self, sga: &demi_sgarray_t) -> Result<DemiBuffer, Fail> {
+        self.primary().clone_sgarray(sga)
+    }
+}
+
+/// Configuration and offloads are those of the primary interface.
+impl<N: NetworkRuntime> NetworkRuntime for SharedInterfaces<N> {
+    fn transmit(&mut self, pkts: PacketBatch) {
+        let mut batches: Vec<PacketBatch> = (0..self.interfaces.len()).map(|_| PacketBatch::new()).collect();
+        for pkt in pkts {
+            match self.egress(pkt.as_ref()) {
+                Egress::Loopback => self.loop_back(pkt),
+                Egress::Interface(PRIMARY_INTERFACE) => batches[PRIMARY_INTERFACE].push(pkt),
+                Egress::Interface(index) => batches[index].push(Box::new(EgressFrame {
+                    pkt,
+                    link_addr: self.interfaces[index].link_addr,
+                })),
+            }
+        }
+        for (interface, batch) in self.interfaces.iter_mut().zip(batches) {
+            if !batch.is_empty() {
+                interface.network.transmit(batch);
+            }
+        }
+    }
+
+    fn receive(&mut self) -> ArrayVec<DemiBuffer, MAX_BATCH_SIZE> {
+        // Batches come from a single interface, taking turns, so that a busy interface does not starve the others.
+        let count: usize = self.interfaces.len() + 1;
+        for i in 0..count {
+            let index: usize = (self.next_rx + i) % count;
+            let batch: ArrayVec<DemiBuffer, MAX_BATCH_SIZE> = match self.interfaces.get_mut(index) {
+                Some(interface) => interface.network.receive(),
+                None => self.receive_loopback(),
+            };
+            if !batch.is_empty() {
+                self.next_rx = (index + 1) % count;
+                return batch;
+            }
+        }
+        ArrayVec::new()
+    }
+
+    fn get_udp_config(&self) -> UdpConfig {
+        self.primary().get_udp_config()
+    }
+
+    fn get_tcp_config(&self) -> TcpConfig {
+        self.primary().get_tcp_config()
+    }
+
+    fn get_arp_config(&self) -> ArpConfig {
+        self.primary().get_arp_config()
+    }
+
+    fn tls_offload(&self) -> Option<Rc<dyn TlsOffload>> {
+        // The device of the primary interface cannot encrypt records that leave through another one.
+        match self.interfaces.len() {
+            1 => self.primary().tls_offload(),
+            _ => None,
+        }
+    }
+
+    fn rx_hw_timestamp(&self, pkt: &DemiBuffer) -> Option<u64> {
+        self.primary().rx_hw_timestamp(pkt)
+    }
+
+    fn read_tx_hw_timestamp(&mut self) -> Option<u64> {
+        self.interfaces[PRIMARY_INTERFACE].network.read_tx_hw_timestamp()
+    }
+
+    fn shutdown(&mut self) -> Result<(), Fail> {
+        // Frames that were looped back have nobody left to receive them.
+        self.loopback.clear();
+        // Every device is released, even if one of them fails to.
+        let mut result: Result<(), Fail> = Ok(());
+        for interface in self.interfaces.iter_mut() {
+            if let Err(e) = interface.network.shutdown() {
+                warn!("shutdown(): failed to release a network device ({:?})", e);
+                result = Err(e);
+            }
+        }
+        result
+    }
+}
+
+impl PacketBuf for EgressFrame {
+    fn header_size(&self) -> usize {
+        self.pkt.header_size()
+    }
+
+    fn write_header(&self, buf: &mut [u8]) {
+        self.pkt.write_header(buf);
+        buf[6..12].copy_from_slice(&self.link_addr.octets());
+    }
+
+    fn body_size(&self) -> usize {
+        self.pkt.body_size()
+    }
+
+    fn take_body(&self) -> Option<DemiBuffer> {
+        self.pkt.take_body()
+    }
+
+    fn tso_segment_size(&self) -> Option<usize> {
+        self.pkt.tso_segment_size()
+    }
+
+    fn priority(&self) -> Option<u8> {
+        self.pkt.priority()
+    }
+
+    fn needs_checksum(&self) -> bool {
+        self.pkt.needs_checksum()
+    }
+
+    fn tx_timestamp(&self) -> bool {
+        self.pkt.tx_timestamp()
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Parses the EtherType of the Ethernet [header] of a frame, skipping its VLAN tag if any, and gets the offset of the
+/// payload.
+fn parse_ether_type(header: &[u8]) -> Option<(EtherType2, usize)> {
+    let mut offset: usize = ETHERNET2_HEADER_SIZE;
+    let mut ether_type: u16 = u16::from_be_bytes([*header.get(12)?, *header.get(13)?]);
+    if ether_type == VLAN_TPID {
+        ether_type = u16::from_be_bytes([*header.get(16)?, *header.get(17)?]);
+        offset += VLAN_TAG_SIZE;
+    }
+    Some((EtherType2::try_from(ether_type).ok()?, offset))
+}
+
+/// Reads the IPv4 address at [offset] of [header].
+fn read_ipv4_addr(header: &[u8], offset: usize) -> Option<Ipv4Addr> {
+    let octets: &[u8] = header.get(offset..offset + 4)?;
+    Some(Ipv4Addr::new(octets[0], octets[1], octets[2], octets[3]))
+}
@@ -0,0 +1,323 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::{
+        protocols::{
+            arp::ArpHeader,
+            ethernet2::{
+                EtherType2,
+                Ethernet2Header,
+            },
+            ipv4::Ipv4Header,
+        },
+        test_helpers::{
+            self,
+            engine::{
+                SharedEngine,
+                DEFAULT_TIMEOUT,
+            },
+            runtime::SharedTestRuntime,
+        },
+    },
+    runtime::{
+        memory::DemiBuffer,
+        network::{
+            config::{
+                ArpConfig,
+                Route,
+                TcpConfig,
+                UdpConfig,
+            },
+            socket::option::SocketOption,
+            types::MacAddress,
+        },
+        queue::{
+            OperationResult,
+            QDesc,
+            QToken,
+        },
+    },
+};
+use ::anyhow::Result;
+use ::std::{
+    collections::HashMap,
+    net::{
+        Ipv4Addr,
+        SocketAddrV4,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+const SECONDARY_MAC: MacAddress = MacAddress::new([0x12, 0x23, 0x45, 0x67, 0x89, 0xac]);
+const SECONDARY_IPV4: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 1);
+const BACKEND_MAC: MacAddress = MacAddress::new([0xab, 0x89, 0x67, 0x45, 0x23, 0x13]);
+const BACKEND_IPV4: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 2);
+const PORT: u16 = 80;
+
+//======================================================================================================================
+// Tests
+//======================================================================================================================
+
+/// Tests that traffic toward a subnet that is routed through a secondary interface leaves through that interface, with
+/// its addresses, and that replies come back to sockets that are bound to every address.
+#[test]
+fn interface_route_egress() -> Result<()> {
+    let now: Instant = Instant::now();
+
+    // Setup Alice with a secondary interface on the back-end network.
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let mut secondary: SharedTestRuntime = new_runtime(now, SECONDARY_MAC, SECONDARY_IPV4);
+    let interface: usize = alice
+        .get_transport()
+        .attach_interface(secondary.clone(), SECONDARY_MAC, SECONDARY_IPV4)?;
+    crate::ensure_eq!(interface, 1);
+    alice
+        .get_transport()
+        .get_arp()
+        .add_route(Route::new(Ipv4Addr::new(10, 0, 0, 0), 24, None)?.with_interface(interface))?;
+    let alice_fd: QDesc = alice.udp_socket()?;
+    alice.udp_bind(alice_fd, SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, PORT))?;
+
+    // Setup a host on the back-end network.
+    let mut backend: SharedEngine = SharedEngine::new(new_runtime(now, BACKEND_MAC, BACKEND_IPV4), now)?;
+    let backend_fd: QDesc = backend.udp_socket()?;
+    backend.udp_bind(backend_fd, SocketAddrV4::new(BACKEND_IPV4, PORT))?;
+
+    // The address of the back-end host is resolved through the secondary interface.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&[0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    let alice_qt: QToken = alice.udp_pushto(alice_fd, buf, SocketAddrV4::new(BACKEND_IPV4, PORT))?;
+    alice.poll();
+    let request: DemiBuffer = secondary.pop_frame();
+    let (header, payload): (Ethernet2Header, DemiBuffer) = Ethernet2Header::parse(request.clone())?;
+    crate::ensure_eq!(header.ether_type(), EtherType2::Arp);
+    crate::ensure_eq!(header.src_addr(), SECONDARY_MAC);
+    crate::ensure_eq!(ArpHeader::parse(payload)?.get_sender_protocol_addr(), SECONDARY_IPV4);
+    backend.receive(request)?;
+    alice.receive(backend.pop_frame())?;
+
+    // The datagram leaves through the secondary interface, from its addresses.
+    match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(_)) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+    let datagram: DemiBuffer = secondary.pop_frame();
+    let (header, payload): (Ethernet2Header, DemiBuffer) = Ethernet2Header::parse(datagram.clone())?;
+    crate::ensure_eq!(header.src_addr(), SECONDARY_MAC);
+    crate::ensure_eq!(header.dst_addr(), BACKEND_MAC);
+    let (header, _): (Ipv4Header, DemiBuffer) = Ipv4Header::parse(payload)?;
+    crate::ensure_eq!(header.get_src_addr(), SECONDARY_IPV4);
+    crate::ensure_eq!(alice.pop_all_frames().is_empty(), true);
+
+    backend.receive(datagram)?;
+    let backend_qt: QToken = backend.udp_pop(backend_fd)?;
+    match backend.wait(backend_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Pop(Some(addr), _, _)) if addr == SocketAddrV4::new(SECONDARY_IPV4, PORT) => {},
+        _ => anyhow::bail!("Pop failed"),
+    };
+
+    // Replies are sent to the link address of the secondary interface, which the stack accepts.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&[0xa5; 32][..]).expect("slice should fit in DemiBuffer");
+    let backend_qt: QToken = backend.udp_pushto(backend_fd, buf, SocketAddrV4::new(SECONDARY_IPV4, PORT))?;
+    match backend.wait(backend_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(_)) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+    alice.receive(backend.pop_frame())?;
+    let alice_qt: QToken = alice.udp_pop(alice_fd)?;
+    match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Pop(Some(addr), _, _)) if addr == SocketAddrV4::new(BACKEND_IPV4, PORT) => {},
+        _ => anyhow::bail!("Pop failed"),
+    };
+
+    // Close peers.
+    alice.udp_close(alice_fd)?;
+    backend.udp_close(backend_fd)?;
+
+    Ok(())
+}
+
+/// Tests that interfaces must have distinct unicast addresses.
+#[test]
+fn interface_attach_invalid() -> Result<()> {
+    let now: Instant = Instant::now();
+    let alice: SharedEngine = test_helpers::new_alice2(now);
+    let secondary: SharedTestRuntime = new_runtime(now, SECONDARY_MAC, SECONDARY_IPV4);
+
+    let attach = |link_addr: MacAddress, ipv4_addr: Ipv4Addr| {
+        alice
+            .get_transport()
+            .attach_interface(secondary.clone(), link_addr, ipv4_addr)
+            .map_err(|e| e.errno)
+    };
+    crate::ensure_eq!(attach(test_helpers::ALICE_MAC, SECONDARY_IPV4), Err(libc::EEXIST));
+    crate::ensure_eq!(attach(SECONDARY_MAC, test_helpers::ALICE_IPV4), Err(libc::EEXIST));
+    crate::ensure_eq!(attach(MacAddress::broadcast(), SECONDARY_IPV4), Err(libc::EINVAL));
+    crate::ensure_eq!(attach(SECONDARY_MAC, Ipv4Addr::UNSPECIFIED), Err(libc::EINVAL));
+    crate::ensure_eq!(attach(SECONDARY_MAC, SECONDARY_IPV4), Ok(1));
+
+    Ok(())
+}
+
+/// Tests that traffic of a socket that is bound to an interface leaves through that interface, although no route
+/// points there.
+#[test]
+fn interface_bind_to_device() -> Result<()> {
+    let now: Instant = Instant::now();
+
+    // Setup Alice with a secondary interface, and a socket that is bound to it.
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let mut secondary: SharedTestRuntime = new_runtime(now, SECONDARY_MAC, SECONDARY_IPV4);
+    alice
+        .get_transport()
+        .attach_interface(secondary.clone(), SECONDARY_MAC, SECONDARY_IPV4)?;
+    let alice_fd: QDesc = alice.udp_socket()?;
+    alice.udp_bind(alice_fd, SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, PORT))?;
+    match alice.set_socket_option(alice_fd, SocketOption::SO_BINDTODEVICE(3)) {
+        Err(e) => crate::ensure_eq!(e.errno, libc::ENODEV),
+        Ok(()) => anyhow::bail!("binding to a missing interface should fail"),
+    }
+    alice.set_socket_option(alice_fd, SocketOption::SO_BINDTODEVICE(2))?;
+
+    // Setup a host on the back-end network.
+    let mut backend: SharedEngine = SharedEngine::new(new_runtime(now, BACKEND_MAC, BACKEND_IPV4), now)?;
+    let backend_fd: QDesc = backend.udp_socket()?;
+    backend.udp_bind(backend_fd, SocketAddrV4::new(BACKEND_IPV4, PORT))?;
+
+    // The address of the back-end host is resolved through the secondary interface.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&[0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    let alice_qt: QToken = alice.udp_pushto(alice_fd, buf, SocketAddrV4::new(BACKEND_IPV4, PORT))?;
+    alice.poll();
+    let request: DemiBuffer = secondary.pop_frame();
+    let (header, _): (Ethernet2Header, DemiBuffer) = Ethernet2Header::parse(request.clone())?;
+    crate::ensure_eq!(header.ether_type(), EtherType2::Arp);
+    crate::ensure_eq!(header.src_addr(), SECONDARY_MAC);
+    backend.receive(request)?;
+    alice.receive(backend.pop_frame())?;
+
+    // The datagram leaves through the secondary interface, from its addresses.
+    match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(_)) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+    let datagram: DemiBuffer = secondary.pop_frame();
+    let (header, payload): (Ethernet2Header, DemiBuffer) = Ethernet2Header::parse(datagram)?;
+    crate::ensure_eq!(header.src_addr(), SECONDARY_MAC);
+    crate::ensure_eq!(header.dst_addr(), BACKEND_MAC);
+    let (header, _): (Ipv4Header, DemiBuffer) = Ipv4Header::parse(payload)?;
+    crate::ensure_eq!(header.get_src_addr(), SECONDARY_IPV4);
+    crate::ensure_eq!(alice.pop_all_frames().is_empty(), true);
+
+    // Close peers.
+    alice.udp_close(alice_fd)?;
+    backend.udp_close(backend_fd)?;
+
+    Ok(())
+}
+
+/// Tests that a TCP connection to the address of the stack is looped back, without any frame reaching the device.
+#[test]
+fn loopback_tcp() -> Result<()> {
+    let now: Instant = Instant::now();
+    let mut alice: SharedEngine = test_helpers::new_alice(now);
+    let server_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, PORT);
+
+    // Setup the server.
+    let server_fd: QDesc = alice.tcp_socket()?;
+    alice.tcp_bind(server_fd, server_addr)?;
+    alice.tcp_listen(server_fd, 1)?;
+    let accept_qt: QToken = alice.tcp_accept(server_fd)?;
+
+    // Connect to it from the same stack.
+    let client_fd: QDesc = alice.tcp_socket()?;
+    let connect_qt: QToken = alice.tcp_connect(client_fd, server_addr)?;
+    match alice.wait(connect_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Connect) => {},
+        _ => anyhow::bail!("Connect failed"),
+    };
+    let accepted_fd: QDesc = match alice.wait(accept_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Accept((fd, addr))) if *addr.ip() == test_helpers::ALICE_IPV4 => fd,
+        _ => anyhow::bail!("Accept failed"),
+    };
+
+    // Exchange data.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&[0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    let push_qt: QToken = alice.tcp_push(client_fd, buf)?;
+    let pop_qt: QToken = alice.tcp_pop(accepted_fd)?;
+    match alice.wait(push_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(_)) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+    match alice.wait(pop_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Pop(_, buf, _)) => crate::ensure_eq!(&buf[..], &[0x5a; 32][..]),
+        _ => anyhow::bail!("Pop failed"),
+    };
+    crate::ensure_eq!(alice.pop_all_frames().is_empty(), true);
+
+    Ok(())
+}
+
+/// Tests that datagrams toward the loopback address come back from it, without any frame reaching the device.
+#[test]
+fn loopback_udp() -> Result<()> {
+    let now: Instant = Instant::now();
+    let mut alice: SharedEngine = test_helpers::new_alice(now);
+    let server_addr: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::LOCALHOST, PORT);
+    let server_fd: QDesc = alice.udp_socket()?;
+    alice.udp_bind(server_fd, server_addr)?;
+    let client_fd: QDesc = alice.udp_socket()?;
+    alice.udp_bind(client_fd, SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, PORT + 1))?;
+
+    let buf: DemiBuffer = DemiBuffer::from_slice(&[0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    let push_qt: QToken = alice.udp_pushto(client_fd, buf, server_addr)?;
+    match alice.wait(push_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(_)) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+    let pop_qt: QToken = alice.udp_pop(server_fd)?;
+    match alice.wait(pop_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Pop(Some(addr), _, _)) if addr == SocketAddrV4::new(Ipv4Addr::LOCALHOST, PORT + 1) => {},
+        _ => anyhow::bail!("Pop failed"),
+    };
+    crate::ensure_eq!(alice.pop_all_frames().is_empty(), true);
+
+    // Close sockets.
+    alice.udp_close(server_fd)?;
+    alice.udp_close(client_fd)?;
+
+    Ok(())
+}
+
+//======================================================================================================================
+// Test Helpers
+//======================================================================================================================
+
+/// Creates the device of a host on the back-end network.
+fn new_runtime(now: Instant, link_addr: MacAddress, ipv4_addr: Ipv4Addr) -> SharedTestRuntime {
+    let arp_config: ArpConfig = ArpConfig::new(
+        Some(Duration::from_secs(600)),
+        Some(Duration::from_secs(1)),
+        Some(2),
+        Some(HashMap::new()),
+        Some(false),
+    );
+    SharedTestRuntime::new(
+        now,
+        arp_config,
+        UdpConfig::default(),
+        TcpConfig::default(),
+        link_addr,
+        ipv4_addr,
+    )
+}
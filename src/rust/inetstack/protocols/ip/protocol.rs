@@ -12,22 +12,24 @@ use crate::runtime::fail::Fail;
 //======================================================================================================================
 
 /// Ipv4 Protocol
-#[repr(u8)]
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum IpProtocol {
     /// Internet Control Message Protocol
-    ICMPv4 = 0x01,
+    ICMPv4,
     /// Transmission Control Protocol
-    TCP = 0x06,
+    TCP,
     /// User Datagram Protocol
-    UDP = 0x11,
+    UDP,
+    /// Any other protocol. The stack does not handle these itself, but hands them over to raw sockets.
+    Other(u8),
 }
 
 //======================================================================================================================
 // Trait Implementations
 //======================================================================================================================
 
-/// TryFrom trait implementation.
+/// TryFrom trait implementation. Protocol numbers that are not assigned by IANA are rejected, while those that are
+/// reserved for experimentation are accepted.
 impl TryFrom<u8> for IpProtocol {
     type Error = Fail;
 
@@ -36,7 +38,20 @@ impl TryFrom<u8> for IpProtocol {
             0x01 => Ok(IpProtocol::ICMPv4),
             0x06 => Ok(IpProtocol::TCP),
             0x11 => Ok(IpProtocol::UDP),
-            _ => Err(Fail::new(libc::ENOTSUP, "unsupported IPv4 protocol")),
+            0x90..=0xfc | 0xff => Err(Fail::new(libc::ENOTSUP, "unsupported IPv4 protocol")),
+            _ => Ok(IpProtocol::Other(value)),
+        }
+    }
+}
+
+/// From trait implementation.
+impl From<IpProtocol> for u8 {
+    fn from(value: IpProtocol) -> Self {
+        match value {
+            IpProtocol::ICMPv4 => 0x01,
+            IpProtocol::TCP => 0x06,
+            IpProtocol::UDP => 0x11,
+            IpProtocol::Other(value) => value,
         }
     }
 }
@@ -0,0 +1,26 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! # IPsec
+//!
+//! Transport-mode ESP with static security associations, which encrypts the IPv4 traffic of the stack on the wire
+//! without changes to the applications. Security associations are read from the configuration file and may be added
+//! or removed at runtime.
+
+mod esp;
+mod network;
+
+#[cfg(test)]
+mod tests;
+
+//==============================================================================
+// Exports
+//==============================================================================
+
+pub use self::{
+    esp::{
+        ESP_MAX_OVERHEAD,
+        ESP_PROTOCOL,
+    },
+    network::SharedIpsecNetwork,
+};
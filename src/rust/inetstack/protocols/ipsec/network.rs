@@ -0,0 +1,394 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    demi_sgarray_t,
+    inetstack::protocols::{
+        ethernet2::{
+            EtherType2,
+            ETHERNET2_HEADER_SIZE,
+        },
+        ip::IpProtocol,
+        ipsec::esp::{
+            InboundSa,
+            OutboundSa,
+            ESP_HEADER_SIZE,
+            ESP_MAX_OVERHEAD,
+            ESP_PROTOCOL,
+        },
+        ipv4::{
+            Ipv4Header,
+            IPV4_HEADER_MIN_SIZE,
+        },
+    },
+    runtime::{
+        crypto::runtime::{
+            CryptoRuntime,
+            SoftwareCryptoRuntime,
+        },
+        fail::Fail,
+        memory::{
+            DemiBuffer,
+            MemoryRuntime,
+        },
+        network::{
+            config::{
+                ArpConfig,
+                IpsecDirection,
+                SecurityAssociation,
+                TcpConfig,
+                UdpConfig,
+            },
+            consts::MAX_BATCH_SIZE,
+            tls::offload::TlsOffload,
+            NetworkRuntime,
+            PacketBatch,
+            PacketBuf,
+        },
+        SharedObject,
+    },
+};
+use ::arrayvec::ArrayVec;
+use ::std::{
+    collections::HashMap,
+    net::Ipv4Addr,
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    rc::Rc,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Network runtime that protects the IPv4 traffic of the stack with transport-mode ESP (see RFC 4303) before it is
+/// handed to the underlying runtime. Datagrams to peers that have an outbound security association are encrypted,
+/// while the rest are passed through untouched.
+pub struct IpsecNetwork<N: NetworkRuntime> {
+    network: N,
+    crypto: Rc<dyn CryptoRuntime>,
+    /// Whether IPsec was enabled when the stack was created. Devices cannot compute checksums or split segments that
+    /// are encrypted, so these offloads are turned off for the whole stack.
+    enabled: bool,
+    /// Outbound security associations, by peer.
+    outbound: HashMap<Ipv4Addr, OutboundSa>,
+    /// Inbound security associations, by security parameter index.
+    inbound: HashMap<u32, InboundSa>,
+}
+
+#[derive(Clone)]
+pub struct SharedIpsecNetwork<N: NetworkRuntime>(SharedObject<IpsecNetwork<N>>);
+
+/// Ethernet frame that carries an ESP packet.
+struct EspFrame {
+    /// Ethernet and IPv4 headers.
+    header: Vec<u8>,
+    /// ESP packet.
+    body: DemiBuffer,
+    priority: Option<u8>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl<N: NetworkRuntime> SharedIpsecNetwork<N> {
+    /// Wraps [network]. IPsec is enabled if [security_associations] is set, even if it is empty, in which case more
+    /// security associations may be added later on.
+    pub fn new(network: N, security_associations: Option<Vec<SecurityAssociation>>) -> Result<Self, Fail> {
+        let mut me: Self = Self(SharedObject::new(IpsecNetwork {
+            network,
+            crypto: Rc::new(SoftwareCryptoRuntime),
+            enabled: security_associations.is_some(),
+            outbound: HashMap::new(),
+            inbound: HashMap::new(),
+        }));
+        for sa in security_associations.unwrap_or_default() {
+            me.add_security_association(sa)?;
+        }
+        Ok(me)
+    }
+
+    /// Adds a security association. Peers have at most one outbound security association, and inbound ones must have
+    /// distinct security parameter indexes.
+    pub fn add_security_association(&mut self, sa: SecurityAssociation) -> Result<(), Fail> {
+        if !self.enabled {
+            let cause: &str = "IPsec is not enabled in the configuration";
+            error!("add_security_association(): {}", cause);
+            return Err(Fail::new(libc::ENOTSUP, cause));
+        }
+        match sa.direction() {
+            IpsecDirection::Outbound => {
+                if self.outbound.contains_key(&sa.peer()) {
+                    let cause: String = format!("peer has an outbound security association (peer={})", sa.peer());
+                    error!("add_security_association(): {}", cause);
+                    return Err(Fail::new(libc::EEXIST, &cause));
+                }
+                self.outbound.insert(sa.peer(), OutboundSa::new(sa));
+            },
+            IpsecDirection::Inbound => {
+                if self.inbound.contains_key(&sa.spi()) {
+                    let cause: String = format!("security parameter index is in use (spi={})", sa.spi());
+                    error!("add_security_association(): {}", cause);
+                    return Err(Fail::new(libc::EEXIST, &cause));
+                }
+                self.inbound.insert(sa.spi(), InboundSa::new(sa));
+            },
+        }
+        Ok(())
+    }
+
+    /// Removes the security association with security parameter index [spi] in [direction].
+    pub fn remove_security_association(&mut self, spi: u32, direction: IpsecDirection) -> Result<(), Fail> {
+        let removed: bool = match direction {
+            IpsecDirection::Outbound => {
+                match self
+                    .outbound
+                    .iter()
+                    .find(|(_, sa)| sa.sa().spi() == spi)
+                    .map(|(peer, _)| *peer)
+                {
+                    Some(peer) => self.outbound.remove(&peer).is_some(),
+                    None => false,
+                }
+            },
+            IpsecDirection::Inbound => self.inbound.remove(&spi).is_some(),
+        };
+        if !removed {
+            let cause: String = format!("no such security association (spi={}, direction={:?})", spi, direction);
+            error!("remove_security_association(): {}", cause);
+            return Err(Fail::new(libc::ENOENT, &cause));
+        }
+        Ok(())
+    }
+
+    /// Removes the ESP encapsulation of the received IPv4 [datagram], if it has one that this stack knows the security
+    /// association of. Datagrams in the clear from peers that have an inbound security association are dropped, in
+    /// which case `None` is returned.
+    pub fn receive(&mut self, datagram: DemiBuffer) -> Result<Option<DemiBuffer>, Fail> {
+        // Malformed datagrams are left to the IPv4 layer.
+        if datagram.len() < IPV4_HEADER_MIN_SIZE as usize {
+            return Ok(Some(datagram));
+        }
+        let src_addr: Ipv4Addr = Ipv4Addr::new(datagram[12], datagram[13], datagram[14], datagram[15]);
+        if datagram[9] != ESP_PROTOCOL {
+            if self.inbound.values().any(|sa| sa.sa().peer() == src_addr) {
+                debug!(
+                    "receive(): dropping datagram in the clear from a protected peer (src={})",
+                    src_addr
+                );
+                return Ok(None);
+            }
+            return Ok(Some(datagram));
+        }
+
+        let (header, payload): (Ipv4Header, DemiBuffer) = Ipv4Header::parse(datagram.clone())?;
+        if payload.len() < ESP_HEADER_SIZE {
+            let cause: &str = "ESP packet is too short";
+            warn!("receive(): {}", cause);
+            return Err(Fail::new(libc::EBADMSG, cause));
+        }
+        // Packets of unknown security associations go on to raw sockets, since applications may handle ESP themselves.
+        let spi: u32 = u32::from_be_bytes([payload[0], payload[1], payload[2], payload[3]]);
+        let crypto: Rc<dyn CryptoRuntime> = self.crypto.clone();
+        let sa: &mut InboundSa = match self.inbound.get_mut(&spi) {
+            Some(sa) => sa,
+            None => return Ok(Some(datagram)),
+        };
+        if sa.sa().peer() != src_addr {
+            let cause: String = format!("ESP packet from another peer (spi={}, src={})", spi, src_addr);
+            warn!("receive(): {}", cause);
+            return Err(Fail::new(libc::EPERM, &cause));
+        }
+        let (next_header, plaintext): (u8, Vec<u8>) = sa.decapsulate(crypto.as_ref(), &payload[..])?;
+
+        // Rebuild the datagram as if it had been received in the clear.
+        let inner: Ipv4Header = Ipv4Header::new(src_addr, header.get_dest_addr(), IpProtocol::try_from(next_header)?)
+            .with_tos(header.get_tos())
+            .with_ttl(header.get_ttl());
+        let header_size: usize = inner.compute_size();
+        let mut buf: DemiBuffer = DemiBuffer::new((header_size + plaintext.len()) as u16);
+        inner.serialize(&mut buf[..header_size], plaintext.len());
+        buf[header_size..].copy_from_slice(&plaintext);
+        Ok(Some(buf))
+    }
+
+    /// Encapsulates [pkt] if it is an IPv4 datagram to a peer that has an outbound security association. Packets that
+    /// cannot be protected are dropped rather than sent in the clear, in which case `None` is returned.
+    fn protect(&mut self, pkt: Box<dyn PacketBuf>) -> Option<Box<dyn PacketBuf>> {
+        if self.outbound.is_empty() {
+            return Some(pkt);
+        }
+        let ip_offset: usize = ETHERNET2_HEADER_SIZE;
+        let mut header: Vec<u8> = vec![0; pkt.header_size()];
+        pkt.write_header(&mut header);
+        if header.len() < ip_offset + IPV4_HEADER_MIN_SIZE as usize
+            || EtherType2::try_from(u16::from_be_bytes([header[12], header[13]])).ok() != Some(EtherType2::Ipv4)
+        {
+            return Some(pkt);
+        }
+        let protocol: u8 = header[ip_offset + 9];
+        let dst_addr: Ipv4Addr = Ipv4Addr::new(
+            header[ip_offset + 16],
+            header[ip_offset + 17],
+            header[ip_offset + 18],
+            header[ip_offset + 19],
+        );
+        let crypto: Rc<dyn CryptoRuntime> = self.crypto.clone();
+        let sa: &mut OutboundSa = match self.outbound.get_mut(&dst_addr) {
+            Some(sa) if protocol != ESP_PROTOCOL => sa,
+            _ => return Some(pkt),
+        };
+        // The stack does not emit IPv4 options, and segments are not handed down for segmentation while IPsec is on.
+        if (header[ip_offset] & 0xf) as usize * 4 != IPV4_HEADER_MIN_SIZE as usize || pkt.tso_segment_size().is_some() {
+            warn!(
+                "protect(): dropping datagram that cannot be encapsulated (dst={})",
+                dst_addr
+            );
+            return None;
+        }
+
+        let ip_end: usize = ip_offset + IPV4_HEADER_MIN_SIZE as usize;
+        let mut payload: Vec<u8> = header[ip_end..].to_vec();
+        if let Some(body) = pkt.take_body() {
+            payload.extend_from_slice(&body[..]);
+        }
+        let esp: Vec<u8> = sa.encapsulate(crypto.as_ref(), protocol, &payload).ok()?;
+        let body: DemiBuffer = match DemiBuffer::from_slice(&esp) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!(
+                    "protect(): dropping datagram that is too large (dst={}, error={:?})",
+                    dst_addr, e
+                );
+                return None;
+            },
+        };
+
+        // Rewrite the IPv4 header for the encapsulated datagram.
+        header.truncate(ip_end);
+        let total_length: u16 = (IPV4_HEADER_MIN_SIZE as usize + esp.len()) as u16;
+        header[(ip_offset + 2)..(ip_offset + 4)].copy_from_slice(&total_length.to_be_bytes());
+        header[ip_offset + 9] = ESP_PROTOCOL;
+        let checksum: u16 = Ipv4Header::compute_checksum(&header[ip_offset..]);
+        header[(ip_offset + 10)..(ip_offset + 12)].copy_from_slice(&checksum.to_be_bytes());
+
+        Some(Box::new(EspFrame {
+            header,
+            body,
+            priority: pkt.priority(),
+        }))
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl<N: NetworkRuntime> Deref for SharedIpsecNetwork<N> {
+    type Target = IpsecNetwork<N>;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl<N: NetworkRuntime> DerefMut for SharedIpsecNetwork<N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+impl<N: NetworkRuntime> MemoryRuntime for SharedIpsecNetwork<N> {
+    fn into_sgarray(&self, buf: DemiBuffer) -> Result<demi_sgarray_t, Fail> {
+        self.network.into_sgarray(buf)
+    }
+
+    fn sgaalloc(&self, size: usize) -> Result<demi_sgarray_t, Fail> {
+        self.network.sgaalloc(size)
+    }
+
+    fn sgafree(&self, sga: demi_sgarray_t) -> Result<(), Fail> {
+        self.network.sgafree(sga)
+    }
+
+    fn clone_sgarray(&self, sga: &demi_sgarray_t) -> Result<DemiBuffer, Fail> {
+        self.network.clone_sgarray(sga)
+    }
+}
+
+impl<N: NetworkRuntime> NetworkRuntime for SharedIpsecNetwork<N> {
+    fn transmit(&mut self, pkts: PacketBatch) {
+        if self.outbound.is_empty() {
+            return self.network.transmit(pkts);
+        }
+        let pkts: PacketBatch = pkts.into_iter().filter_map(|pkt| self.protect(pkt)).collect();
+        if !pkts.is_empty() {
+            self.network.transmit(pkts);
+        }
+    }
+
+    fn transmit_one(&mut self, pkt: Box<dyn PacketBuf>) {
+        if let Some(pkt) = self.protect(pkt) {
+            self.network.transmit_one(pkt);
+        }
+    }
+
+    fn receive(&mut self) -> ArrayVec<DemiBuffer, MAX_BATCH_SIZE> {
+        self.network.receive()
+    }
+
+    fn get_udp_config(&self) -> UdpConfig {
+        match self.enabled {
+            true => self.network.get_udp_config().for_encapsulation(),
+            false => self.network.get_udp_config(),
+        }
+    }
+
+    fn get_tcp_config(&self) -> TcpConfig {
+        match self.enabled {
+            true => self.network.get_tcp_config().for_encapsulation(ESP_MAX_OVERHEAD),
+            false => self.network.get_tcp_config(),
+        }
+    }
+
+    fn get_arp_config(&self) -> ArpConfig {
+        self.network.get_arp_config()
+    }
+
+    fn tls_offload(&self) -> Option<Rc<dyn TlsOffload>> {
+        // Devices cannot find TLS records inside encrypted datagrams.
+        match self.enabled {
+            true => None,
+            false => self.network.tls_offload(),
+        }
+    }
+}
+
+impl PacketBuf for EspFrame {
+    fn header_size(&self) -> usize {
+        self.header.len()
+    }
+
+    fn write_header(&self, buf: &mut [u8]) {
+        buf.copy_from_slice(&self.header)
+    }
+
+    fn body_size(&self) -> usize {
+        self.body.len()
+    }
+
+    fn take_body(&self) -> Option<DemiBuffer> {
+        Some(self.body.clone())
+    }
+
+    fn priority(&self) -> Option<u8> {
+        self.priority
+    }
+}
@@ -0,0 +1,257 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+use crate::{
+    inetstack::{
+        protocols::ipsec::ESP_PROTOCOL,
+        test_helpers::{
+            self,
+            engine::{
+                SharedEngine,
+                DEFAULT_TIMEOUT,
+            },
+        },
+    },
+    runtime::{
+        crypto::runtime::AeadKey,
+        memory::DemiBuffer,
+        network::config::{
+            IpsecDirection,
+            SecurityAssociation,
+        },
+        queue::{
+            OperationResult,
+            QDesc,
+            QToken,
+        },
+    },
+};
+use ::anyhow::Result;
+use ::std::{
+    net::{
+        Ipv4Addr,
+        SocketAddrV4,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Offset of the protocol field of IPv4 headers in Ethernet frames.
+const IPV4_PROTOCOL_OFFSET: usize = 14 + 9;
+
+/// Security parameter index of the traffic from Alice to Bob.
+const ALICE_TO_BOB_SPI: u32 = 0x1001;
+
+/// Security parameter index of the traffic from Bob to Alice.
+const BOB_TO_ALICE_SPI: u32 = 0x2002;
+
+/// Time to wait for datagrams that are expected to be dropped.
+const DROP_TIMEOUT: Duration = Duration::from_millis(10);
+
+//==============================================================================
+// Push & Pop
+//==============================================================================
+
+/// Tests that UDP datagrams are encrypted on the wire and decrypted back in both directions.
+#[test]
+fn ipsec_udp_push_pop() -> Result<()> {
+    let now: Instant = Instant::now();
+    let (mut alice, mut bob): (SharedEngine, SharedEngine) = new_protected_pair(now)?;
+    let (alice_fd, alice_addr): (QDesc, SocketAddrV4) = udp_bind(&mut alice, test_helpers::ALICE_IPV4)?;
+    let (bob_fd, bob_addr): (QDesc, SocketAddrV4) = udp_bind(&mut bob, test_helpers::BOB_IPV4)?;
+
+    // Send data to Bob, which must not show up in the clear.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&[0x5a; 32]).expect("slice should fit in DemiBuffer");
+    let frame: DemiBuffer = udp_push(&mut alice, alice_fd, buf.clone(), bob_addr)?;
+    crate::ensure_eq!(frame[IPV4_PROTOCOL_OFFSET], ESP_PROTOCOL);
+    crate::ensure_eq!(frame.windows(buf.len()).any(|window| window == &buf[..]), false);
+    bob.receive(frame)?;
+    crate::ensure_eq!(udp_pop(&mut bob, bob_fd, DEFAULT_TIMEOUT)?, (alice_addr, buf.to_vec()));
+
+    // Reply to Alice.
+    let buf: DemiBuffer = DemiBuffer::from_slice(b"pong").expect("slice should fit in DemiBuffer");
+    let frame: DemiBuffer = udp_push(&mut bob, bob_fd, buf.clone(), alice_addr)?;
+    crate::ensure_eq!(frame[IPV4_PROTOCOL_OFFSET], ESP_PROTOCOL);
+    alice.receive(frame)?;
+    crate::ensure_eq!(
+        udp_pop(&mut alice, alice_fd, DEFAULT_TIMEOUT)?,
+        (bob_addr, buf.to_vec())
+    );
+
+    Ok(())
+}
+
+/// Tests that replayed datagrams and datagrams in the clear from a protected peer are dropped.
+#[test]
+fn ipsec_udp_drop_replay_and_cleartext() -> Result<()> {
+    let now: Instant = Instant::now();
+    let (mut alice, mut bob): (SharedEngine, SharedEngine) = new_protected_pair(now)?;
+    let (alice_fd, _): (QDesc, SocketAddrV4) = udp_bind(&mut alice, test_helpers::ALICE_IPV4)?;
+    let (bob_fd, bob_addr): (QDesc, SocketAddrV4) = udp_bind(&mut bob, test_helpers::BOB_IPV4)?;
+
+    // Deliver a datagram twice.
+    let buf: DemiBuffer = DemiBuffer::from_slice(b"once").expect("slice should fit in DemiBuffer");
+    let frame: DemiBuffer = udp_push(&mut alice, alice_fd, buf.clone(), bob_addr)?;
+    bob.receive(frame.clone())?;
+    udp_pop(&mut bob, bob_fd, DEFAULT_TIMEOUT)?;
+    crate::ensure_eq!(bob.receive(frame).is_err(), true);
+    crate::ensure_eq!(udp_pop(&mut bob, bob_fd, DROP_TIMEOUT).is_err(), true);
+
+    // Send a datagram from a stack of Alice that does not protect its traffic.
+    let mut cleartext_alice: SharedEngine = test_helpers::new_alice2(now);
+    let (cleartext_fd, _): (QDesc, SocketAddrV4) = udp_bind(&mut cleartext_alice, test_helpers::ALICE_IPV4)?;
+    let frame: DemiBuffer = udp_push(&mut cleartext_alice, cleartext_fd, buf, bob_addr)?;
+    crate::ensure_eq!(frame[IPV4_PROTOCOL_OFFSET], 0x11);
+    bob.receive(frame)?;
+    crate::ensure_eq!(udp_pop(&mut bob, bob_fd, DROP_TIMEOUT).is_err(), true);
+
+    Ok(())
+}
+
+/// Tests that a TCP connection is established and carries data over IPsec.
+#[test]
+fn ipsec_tcp_connect_push_pop() -> Result<()> {
+    let now: Instant = Instant::now();
+    let (mut alice, mut bob): (SharedEngine, SharedEngine) = new_protected_pair(now)?;
+
+    // Set up Bob to accept a connection from Alice.
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
+    let listen_fd: QDesc = bob.tcp_socket()?;
+    bob.tcp_bind(listen_fd, bob_addr)?;
+    bob.tcp_listen(listen_fd, 1)?;
+    let accept_qt: QToken = bob.tcp_accept(listen_fd)?;
+    let alice_fd: QDesc = alice.tcp_socket()?;
+    let connect_qt: QToken = alice.tcp_connect(alice_fd, bob_addr)?;
+    alice.exchange_frames_with(&mut bob, check_protected)?;
+    let bob_fd: QDesc = match bob.wait(accept_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Accept((qd, _))) => qd,
+        _ => anyhow::bail!("accept failed"),
+    };
+    match alice.wait(connect_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Connect) => {},
+        _ => anyhow::bail!("connect failed"),
+    };
+
+    // Send data to Bob.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&[0xa5; 64]).expect("slice should fit in DemiBuffer");
+    let push_qt: QToken = alice.tcp_push(alice_fd, buf.clone())?;
+    let pop_qt: QToken = bob.tcp_pop(bob_fd)?;
+    alice.exchange_frames_with(&mut bob, check_protected)?;
+    match alice.wait(push_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(_)) => {},
+        _ => anyhow::bail!("push failed"),
+    };
+    match bob.wait(pop_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Pop(_, received, _)) => crate::ensure_eq!(&received[..], &buf[..]),
+        _ => anyhow::bail!("pop failed"),
+    };
+
+    Ok(())
+}
+
+//==============================================================================
+// Security Associations
+//==============================================================================
+
+/// Tests that security associations are only added to stacks that enabled IPsec, and only once.
+#[test]
+fn ipsec_add_remove_security_association() -> Result<()> {
+    let now: Instant = Instant::now();
+    let sa: SecurityAssociation = new_sa(0x3003, IpsecDirection::Inbound, test_helpers::BOB_IPV4)?;
+
+    // Stacks that did not enable IPsec keep offloads on, so they cannot protect traffic.
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    crate::ensure_eq!(
+        alice.ipsec_add_security_association(sa.clone()).unwrap_err().errno,
+        libc::ENOTSUP
+    );
+
+    let mut alice: SharedEngine = test_helpers::new_alice2_with_ipsec(now, vec![]);
+    alice.ipsec_add_security_association(sa.clone())?;
+    crate::ensure_eq!(
+        alice.ipsec_add_security_association(sa).unwrap_err().errno,
+        libc::EEXIST
+    );
+    alice.ipsec_remove_security_association(0x3003, IpsecDirection::Inbound)?;
+    crate::ensure_eq!(
+        alice
+            .ipsec_remove_security_association(0x3003, IpsecDirection::Inbound)
+            .unwrap_err()
+            .errno,
+        libc::ENOENT
+    );
+
+    Ok(())
+}
+
+//==============================================================================
+// Standalone Functions
+//==============================================================================
+
+/// Creates a security association with [spi] and a fixed key.
+fn new_sa(spi: u32, direction: IpsecDirection, peer: Ipv4Addr) -> Result<SecurityAssociation> {
+    Ok(SecurityAssociation::new(
+        spi,
+        direction,
+        peer,
+        AeadKey::Aes128Gcm([spi as u8; 16]),
+        [0x11; 4],
+    )?)
+}
+
+/// Creates Alice and Bob, with security associations that protect their traffic in both directions.
+fn new_protected_pair(now: Instant) -> Result<(SharedEngine, SharedEngine)> {
+    let alice: SharedEngine = test_helpers::new_alice2_with_ipsec(
+        now,
+        vec![
+            new_sa(ALICE_TO_BOB_SPI, IpsecDirection::Outbound, test_helpers::BOB_IPV4)?,
+            new_sa(BOB_TO_ALICE_SPI, IpsecDirection::Inbound, test_helpers::BOB_IPV4)?,
+        ],
+    );
+    let bob: SharedEngine = test_helpers::new_bob2_with_ipsec(
+        now,
+        vec![
+            new_sa(BOB_TO_ALICE_SPI, IpsecDirection::Outbound, test_helpers::ALICE_IPV4)?,
+            new_sa(ALICE_TO_BOB_SPI, IpsecDirection::Inbound, test_helpers::ALICE_IPV4)?,
+        ],
+    );
+    Ok((alice, bob))
+}
+
+/// Creates a UDP socket of [engine] that is bound to port 80 of [addr].
+fn udp_bind(engine: &mut SharedEngine, addr: Ipv4Addr) -> Result<(QDesc, SocketAddrV4)> {
+    let addr: SocketAddrV4 = SocketAddrV4::new(addr, 80);
+    let qd: QDesc = engine.udp_socket()?;
+    engine.udp_bind(qd, addr)?;
+    Ok((qd, addr))
+}
+
+/// Pushes [buf] to [to] and returns the frame that carries it.
+fn udp_push(engine: &mut SharedEngine, qd: QDesc, buf: DemiBuffer, to: SocketAddrV4) -> Result<DemiBuffer> {
+    let qt: QToken = engine.udp_pushto(qd, buf, to)?;
+    match engine.wait(qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(_)) => Ok(engine.pop_frame()),
+        _ => anyhow::bail!("push failed"),
+    }
+}
+
+/// Pops a datagram and returns its sender along with its data.
+fn udp_pop(engine: &mut SharedEngine, qd: QDesc, timeout: Duration) -> Result<(SocketAddrV4, Vec<u8>)> {
+    let qt: QToken = engine.udp_pop(qd)?;
+    match engine.wait(qt, timeout)? {
+        (_, OperationResult::Pop(Some(addr), buf, _)) => Ok((addr, buf.to_vec())),
+        _ => anyhow::bail!("pop failed"),
+    }
+}
+
+/// Checks that [frame] is protected with ESP.
+fn check_protected(frame: &DemiBuffer) -> Result<()> {
+    crate::ensure_eq!(frame[IPV4_PROTOCOL_OFFSET], ESP_PROTOCOL);
+    Ok(())
+}
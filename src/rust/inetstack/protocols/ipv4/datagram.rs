@@ -11,6 +11,7 @@ use crate::{
     runtime::{
         fail::Fail,
         memory::DemiBuffer,
+        network::consts::DEFAULT_IPV4_TTL,
     },
 };
 use ::libc::{
@@ -35,9 +36,6 @@ const IPV4_DATAGRAM_MIN_SIZE: u16 = 20;
 /// IPv4 header length when no options are present (in 32-bit words).
 const IPV4_IHL_NO_OPTIONS: u8 = (IPV4_HEADER_MIN_SIZE as u8) / 4;
 
-/// Default time to live value.
-const DEFAULT_IPV4_TTL: u8 = 255;
-
 /// Version number for IPv4.
 const IPV4_VERSION: u8 = 4;
 
@@ -112,6 +110,25 @@ impl Ipv4Header {
         }
     }
 
+    /// Sets the type of service byte, that is, both the DSCP and ECN fields, of the target IPv4 header.
+    pub fn with_tos(mut self, tos: u8) -> Self {
+        self.dscp = tos >> 2;
+        self.ecn = tos & 3;
+        self
+    }
+
+    /// Sets the DSCP field of the target IPv4 header, leaving the ECN field untouched.
+    pub fn with_dscp(mut self, dscp: u8) -> Self {
+        self.dscp = dscp & 0x3f;
+        self
+    }
+
+    /// Sets the time to live field of the target IPv4 header.
+    pub fn with_ttl(mut self, ttl: u8) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
     /// Computes the size of the target IPv4 header.
     pub fn compute_size(&self) -> usize {
         (self.ihl as usize) << 2
@@ -143,9 +160,6 @@ impl Ipv4Header {
 
         // Differentiated services code point.
         let dscp: u8 = hdr_buf[1] >> 2;
-        if dscp != 0 {
-            warn!("ignoring dscp field (dscp={:?})", dscp);
-        }
 
         // Explicit congestion notification.
         let ecn: u8 = hdr_buf[1] & 3;
@@ -269,7 +283,7 @@ impl Ipv4Header {
         buf[8] = self.ttl;
 
         // Protocol.
-        buf[9] = self.protocol as u8;
+        buf[9] = u8::from(self.protocol);
 
         // Skip the checksum (bytes 10..12) until we finish writing the header.
 
@@ -299,6 +313,16 @@ impl Ipv4Header {
         self.protocol
     }
 
+    /// Returns the time to live field stored in the target IPv4 header.
+    pub fn get_ttl(&self) -> u8 {
+        self.ttl
+    }
+
+    /// Returns the type of service byte stored in the target IPv4 header.
+    pub fn get_tos(&self) -> u8 {
+        (self.dscp << 2) | (self.ecn & 3)
+    }
+
     /// Computes the checksum of the target IPv4 header.
     pub fn compute_checksum(buf: &[u8]) -> u16 {
         let mut state: u32 = 0xffff;
@@ -2,6 +2,7 @@
 // Licensed under the MIT license.
 
 mod datagram;
+mod routing;
 
 #[cfg(test)]
 mod tests;
@@ -15,3 +16,4 @@ pub use self::datagram::{
     IPV4_HEADER_MIN_SIZE,
     IPV4_HEADER_MAX_SIZE,
 };
+pub use self::routing::RoutingTable;
@@ -0,0 +1,188 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    expect_ok,
+    runtime::{
+        fail::Fail,
+        network::config::Route,
+    },
+};
+use ::std::net::Ipv4Addr;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Routing table of IPv4 destinations. The route with the longest prefix that contains a destination wins.
+///
+/// Tables hold a handful of routes, so lookups scan them in order of decreasing prefix length. Connections cache the
+/// result of lookups along with their next hop (see [crate::inetstack::protocols::arp::NextHop]).
+pub struct RoutingTable {
+    /// Routes, sorted by decreasing prefix length.
+    routes: Vec<Route>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl RoutingTable {
+    /// Creates a routing table with [routes].
+    pub fn new(routes: Vec<Route>) -> Result<Self, Fail> {
+        let mut table: RoutingTable = Self { routes: Vec::new() };
+        for route in routes {
+            table.add(route)?;
+        }
+        Ok(table)
+    }
+
+    /// Adds [route]. Fails if there is a route toward the same subnet already.
+    pub fn add(&mut self, route: Route) -> Result<(), Fail> {
+        if self.position(route.destination(), route.prefix_len()).is_some() {
+            let cause: String = format!("there is a route toward the subnet already (route={})", route);
+            error!("add(): {}", cause);
+            return Err(Fail::new(libc::EEXIST, &cause));
+        }
+        let index: usize = self
+            .routes
+            .partition_point(|other| other.prefix_len() >= route.prefix_len());
+        self.routes.insert(index, route);
+        Ok(())
+    }
+
+    /// Removes the route toward [destination]/[prefix_len].
+    pub fn remove(&mut self, destination: Ipv4Addr, prefix_len: u8) -> Result<Route, Fail> {
+        match self.position(destination, prefix_len) {
+            Some(index) => Ok(self.routes.remove(index)),
+            None => {
+                let cause: String = format!("no route toward subnet {}/{}", destination, prefix_len);
+                error!("remove(): {}", cause);
+                Err(Fail::new(libc::ESRCH, &cause))
+            },
+        }
+    }
+
+    /// Gets the route with the longest prefix that contains [addr], if any.
+    pub fn lookup(&self, addr: Ipv4Addr) -> Option<&Route> {
+        self.routes.iter().find(|route| route.contains(addr))
+    }
+
+    /// Gets the route with the longest prefix that contains [addr] and goes through [interface], if any.
+    pub fn lookup_via(&self, addr: Ipv4Addr, interface: usize) -> Option<&Route> {
+        self.routes
+            .iter()
+            .find(|route| route.contains(addr) && route.interface() == interface)
+    }
+
+    /// Gets the route that traffic toward [addr] takes. Fails with ENETUNREACH if no route contains [addr].
+    pub fn route(&self, addr: Ipv4Addr) -> Result<&Route, Fail> {
+        match self.lookup(addr) {
+            Some(route) => Ok(route),
+            None => {
+                let cause: String = format!("no route toward {}", addr);
+                warn!("route(): {}", cause);
+                Err(Fail::new(libc::ENETUNREACH, &cause))
+            },
+        }
+    }
+
+    /// Gets the routes of the table, from the longest prefix to the shortest.
+    pub fn routes(&self) -> &[Route] {
+        &self.routes
+    }
+
+    /// Gets the index of the route toward [destination]/[prefix_len].
+    fn position(&self, destination: Ipv4Addr, prefix_len: u8) -> Option<usize> {
+        self.routes
+            .iter()
+            .position(|route| route.destination() == destination && route.prefix_len() == prefix_len)
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+/// Without any configuration, every destination is directly connected.
+impl Default for RoutingTable {
+    fn default() -> Self {
+        Self {
+            routes: vec![expect_ok!(
+                Route::new(Ipv4Addr::UNSPECIFIED, 0, None),
+                "default route should be valid"
+            )],
+        }
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::RoutingTable;
+    use crate::runtime::network::config::Route;
+    use ::anyhow::Result;
+    use ::std::net::Ipv4Addr;
+
+    const GATEWAY: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 254);
+    const OTHER_GATEWAY: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 1);
+
+    /// Gets the address that traffic toward [addr] is sent to on the link.
+    fn next_hop(table: &RoutingTable, addr: Ipv4Addr) -> Result<Ipv4Addr> {
+        Ok(table.route(addr)?.next_hop(addr))
+    }
+
+    /// Tests that the route with the longest prefix wins, whatever the order in which routes were added.
+    #[test]
+    fn test_longest_prefix_match() -> Result<()> {
+        let mut table: RoutingTable = RoutingTable::new(vec![
+            Route::new(Ipv4Addr::UNSPECIFIED, 0, Some(GATEWAY))?,
+            Route::new(Ipv4Addr::new(10, 0, 0, 0), 8, None)?,
+        ])?;
+        table.add(Route::new(Ipv4Addr::new(10, 2, 0, 0), 16, Some(OTHER_GATEWAY))?)?;
+
+        crate::ensure_eq!(next_hop(&table, Ipv4Addr::new(8, 8, 8, 8))?, GATEWAY);
+        crate::ensure_eq!(
+            next_hop(&table, Ipv4Addr::new(10, 1, 2, 3))?,
+            Ipv4Addr::new(10, 1, 2, 3)
+        );
+        crate::ensure_eq!(next_hop(&table, Ipv4Addr::new(10, 2, 2, 3))?, OTHER_GATEWAY);
+        let prefixes: Vec<u8> = table.routes().iter().map(|route| route.prefix_len()).collect();
+        crate::ensure_eq!(prefixes, vec![16, 8, 0]);
+
+        Ok(())
+    }
+
+    /// Tests that routes are added and removed by subnet, and that destinations without a route are unreachable.
+    #[test]
+    fn test_add_remove() -> Result<()> {
+        let mut table: RoutingTable = RoutingTable::default();
+        crate::ensure_eq!(next_hop(&table, Ipv4Addr::new(8, 8, 8, 8))?, Ipv4Addr::new(8, 8, 8, 8));
+
+        // Routes toward the same subnet cannot be added twice, even through another gateway.
+        crate::ensure_eq!(
+            table
+                .add(Route::new(Ipv4Addr::UNSPECIFIED, 0, Some(GATEWAY))?)
+                .unwrap_err()
+                .errno,
+            libc::EEXIST
+        );
+
+        let route: Route = table.remove(Ipv4Addr::UNSPECIFIED, 0)?;
+        crate::ensure_eq!(route.gateway(), None);
+        crate::ensure_eq!(
+            table.route(Ipv4Addr::new(8, 8, 8, 8)).unwrap_err().errno,
+            libc::ENETUNREACH
+        );
+        crate::ensure_eq!(table.remove(Ipv4Addr::UNSPECIFIED, 0).unwrap_err().errno, libc::ESRCH);
+
+        Ok(())
+    }
+}
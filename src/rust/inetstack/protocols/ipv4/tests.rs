@@ -106,7 +106,7 @@ fn test_ipv4_header_parse_good() -> Result<()> {
             0x2,
             0,
             1,
-            IpProtocol::UDP as u8,
+            u8::from(IpProtocol::UDP),
             &ALICE_IPV4.octets(),
             &BOB_IPV4.octets(),
             None,
@@ -135,6 +135,49 @@ fn test_ipv4_header_parse_good() -> Result<()> {
     Ok(())
 }
 
+/// Serializes IPv4 headers with a type of service byte and parses them back.
+#[test]
+fn test_ipv4_header_serialize_tos() -> Result<()> {
+    const HEADER_SIZE: usize = 20;
+    let mut buf: [u8; HEADER_SIZE] = [0; HEADER_SIZE];
+
+    // Expedited forwarding, with the ECN field set by the application.
+    let ipv4_hdr: Ipv4Header = Ipv4Header::new(ALICE_IPV4, BOB_IPV4, IpProtocol::UDP).with_tos(0xb9);
+    ipv4_hdr.serialize(&mut buf, 0);
+    crate::ensure_eq!(buf[1], 0xb9);
+
+    // Overriding the DSCP leaves the ECN field untouched.
+    let ipv4_hdr: Ipv4Header = ipv4_hdr.with_dscp(10);
+    ipv4_hdr.serialize(&mut buf, 0);
+    let buf_bytes: DemiBuffer = match DemiBuffer::from_slice(&buf) {
+        Ok(buf_bytes) => buf_bytes,
+        Err(e) => anyhow::bail!("'buf' should fit: {:?}", e),
+    };
+    let (ipv4_hdr, _): (Ipv4Header, DemiBuffer) = Ipv4Header::parse(buf_bytes)?;
+    crate::ensure_eq!(ipv4_hdr.get_tos(), (10 << 2) | 1);
+
+    Ok(())
+}
+
+/// Serializes an IPv4 header with a time to live and parses it back.
+#[test]
+fn test_ipv4_header_serialize_ttl() -> Result<()> {
+    const HEADER_SIZE: usize = 20;
+    let mut buf: [u8; HEADER_SIZE] = [0; HEADER_SIZE];
+
+    let ipv4_hdr: Ipv4Header = Ipv4Header::new(ALICE_IPV4, BOB_IPV4, IpProtocol::UDP).with_ttl(1);
+    ipv4_hdr.serialize(&mut buf, 0);
+    crate::ensure_eq!(buf[8], 1);
+    let buf_bytes: DemiBuffer = match DemiBuffer::from_slice(&buf) {
+        Ok(buf_bytes) => buf_bytes,
+        Err(e) => anyhow::bail!("'buf' should fit: {:?}", e),
+    };
+    let (ipv4_hdr, _): (Ipv4Header, DemiBuffer) = Ipv4Header::parse(buf_bytes)?;
+    crate::ensure_eq!(ipv4_hdr.get_ttl(), 1);
+
+    Ok(())
+}
+
 //==============================================================================
 // Unit-Tests for Invalid Path
 //==============================================================================
@@ -160,7 +203,7 @@ fn test_ipv4_header_parse_invalid_version() -> Result<()> {
             0x2,
             0,
             1,
-            IpProtocol::UDP as u8,
+            u8::from(IpProtocol::UDP),
             &ALICE_IPV4.octets(),
             &BOB_IPV4.octets(),
             None,
@@ -201,7 +244,7 @@ fn test_ipv4_header_parse_invalid_ihl() -> Result<()> {
             0x2,
             0,
             1,
-            IpProtocol::UDP as u8,
+            u8::from(IpProtocol::UDP),
             &ALICE_IPV4.octets(),
             &BOB_IPV4.octets(),
             None,
@@ -243,7 +286,7 @@ fn test_ipv4_header_parse_invalid_total_length() -> Result<()> {
             0x2,
             0,
             1,
-            IpProtocol::UDP as u8,
+            u8::from(IpProtocol::UDP),
             &ALICE_IPV4.octets(),
             &BOB_IPV4.octets(),
             None,
@@ -285,7 +328,7 @@ fn test_ipv4_header_parse_invalid_flags() -> Result<()> {
         flags,
         0,
         1,
-        IpProtocol::UDP as u8,
+        u8::from(IpProtocol::UDP),
         &ALICE_IPV4.octets(),
         &BOB_IPV4.octets(),
         None,
@@ -324,7 +367,7 @@ fn test_ipv4_header_parse_invalid_ttl() -> Result<()> {
         0x2,
         0,
         ttl,
-        IpProtocol::UDP as u8,
+        u8::from(IpProtocol::UDP),
         &ALICE_IPV4.octets(),
         &BOB_IPV4.octets(),
         None,
@@ -405,7 +448,7 @@ fn test_ipv4_header_parse_invalid_header_checksum() -> Result<()> {
         0x2,
         0,
         1,
-        IpProtocol::UDP as u8,
+        u8::from(IpProtocol::UDP),
         &ALICE_IPV4.octets(),
         &BOB_IPV4.octets(),
         Some(hdr_checksum),
@@ -448,7 +491,7 @@ fn test_ipv4_header_parse_unsupported_dscp() -> Result<()> {
             0x2,
             0,
             1,
-            IpProtocol::UDP as u8,
+            u8::from(IpProtocol::UDP),
             &ALICE_IPV4.octets(),
             &BOB_IPV4.octets(),
             None,
@@ -490,7 +533,7 @@ fn test_ipv4_header_parse_unsupported_ecn() -> Result<()> {
             0x2,
             0,
             1,
-            IpProtocol::UDP as u8,
+            u8::from(IpProtocol::UDP),
             &ALICE_IPV4.octets(),
             &BOB_IPV4.octets(),
             None,
@@ -535,7 +578,7 @@ fn test_ipv4_header_parse_unsupported_fragmentation() -> Result<()> {
         flags,
         0,
         1,
-        IpProtocol::UDP as u8,
+        u8::from(IpProtocol::UDP),
         &ALICE_IPV4.octets(),
         &BOB_IPV4.octets(),
         None,
@@ -566,7 +609,7 @@ fn test_ipv4_header_parse_unsupported_fragmentation() -> Result<()> {
         0x2,
         fragment_offset,
         1,
-        IpProtocol::UDP as u8,
+        u8::from(IpProtocol::UDP),
         &ALICE_IPV4.octets(),
         &BOB_IPV4.octets(),
         None,
@@ -587,17 +630,15 @@ fn test_ipv4_header_parse_unsupported_fragmentation() -> Result<()> {
     }
 }
 
-/// Parses a malformed IPv4 header with unsupported protocol field.
-///
-/// TODO: Drop this test once we support them.
+/// Parses IPv4 headers with protocols that the stack does not handle itself.
 #[test]
-fn test_ipv4_header_parse_unsupported_protocol() -> Result<()> {
+fn test_ipv4_header_parse_other_protocol() -> Result<()> {
     const HEADER_SIZE: usize = 20;
     const PAYLOAD_SIZE: usize = 0;
     const DATAGRAM_SIZE: usize = HEADER_SIZE + PAYLOAD_SIZE;
     let mut buf: [u8; DATAGRAM_SIZE] = [0; DATAGRAM_SIZE];
 
-    // Iterate over unsupported values for fragment flags.
+    // Iterate over protocols that are not handled by the stack.
     for protocol in 0..143 {
         match protocol {
             // Skip supported protocols.
@@ -626,10 +667,8 @@ fn test_ipv4_header_parse_unsupported_protocol() -> Result<()> {
                     Err(e) => anyhow::bail!("'buf' should fit: {:?}", e),
                 };
 
-                match Ipv4Header::parse(buf_bytes) {
-                    Ok(_) => anyhow::bail!("parsed ipv4 header with protocol={:?}. Do we support it now?", protocol,),
-                    Err(_) => {},
-                };
+                let (header, _): (Ipv4Header, DemiBuffer) = Ipv4Header::parse(buf_bytes)?;
+                crate::ensure_eq!(header.get_protocol(), IpProtocol::Other(protocol));
             },
         };
     }
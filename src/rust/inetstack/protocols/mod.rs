@@ -2,18 +2,21 @@
 // Licensed under the MIT license.
 
 pub mod arp;
+pub mod checksum;
 pub mod ethernet2;
 pub mod icmpv4;
+pub mod interface;
 pub mod ip;
+pub mod ipsec;
 pub mod ipv4;
+pub mod packet;
 mod peer;
+pub mod raw;
 pub mod tcp;
 pub mod udp;
 
 pub use peer::Peer;
 
-use ::std::slice::ChunksExact;
-
 pub enum Protocol {
     Tcp,
     Udp,
@@ -31,14 +34,7 @@ pub fn compute_generic_checksum(buf: &[u8], start: Option<u32>) -> u32 {
         None => 0xFFFF,
     };
 
-    let mut chunks_iter: ChunksExact<u8> = buf.chunks_exact(2);
-    while let Some(chunk) = chunks_iter.next() {
-        state += u16::from_be_bytes([chunk[0], chunk[1]]) as u32;
-    }
-
-    if let Some(&b) = chunks_iter.remainder().get(0) {
-        state += u16::from_be_bytes([b, 0]) as u32;
-    }
+    state += checksum::sum(buf);
 
     state
 }
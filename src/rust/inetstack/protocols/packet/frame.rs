@@ -0,0 +1,75 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    expect_ok,
+    inetstack::protocols::ethernet2::ETHERNET2_HEADER_SIZE,
+    runtime::{
+        memory::DemiBuffer,
+        network::PacketBuf,
+    },
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Ethernet frame that is built by the application. The Ethernet header is handed to the network runtime as the
+/// header of the packet, so that the runtime may still tag it.
+#[derive(Debug)]
+pub struct PacketFrame {
+    /// Whole frame, starting with the Ethernet header.
+    frame: DemiBuffer,
+    /// Priority of the frame, if the socket was assigned one.
+    priority: Option<u8>,
+}
+
+//======================================================================================================================
+// Associate Functions
+//======================================================================================================================
+
+impl PacketFrame {
+    /// Wraps [frame], which should be at least as large as an Ethernet header.
+    pub fn new(frame: DemiBuffer, priority: Option<u8>) -> Self {
+        debug_assert!(frame.len() >= ETHERNET2_HEADER_SIZE);
+        Self { frame, priority }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl PacketBuf for PacketFrame {
+    fn header_size(&self) -> usize {
+        ETHERNET2_HEADER_SIZE
+    }
+
+    fn write_header(&self, buf: &mut [u8]) {
+        buf.copy_from_slice(&self.frame[..ETHERNET2_HEADER_SIZE]);
+    }
+
+    fn body_size(&self) -> usize {
+        self.frame.len() - ETHERNET2_HEADER_SIZE
+    }
+
+    fn take_body(&self) -> Option<DemiBuffer> {
+        if self.body_size() == 0 {
+            return None;
+        }
+        let mut body: DemiBuffer = self.frame.clone();
+        expect_ok!(
+            body.adjust(ETHERNET2_HEADER_SIZE),
+            "frame should hold an Ethernet header"
+        );
+        Some(body)
+    }
+
+    fn priority(&self) -> Option<u8> {
+        self.priority
+    }
+}
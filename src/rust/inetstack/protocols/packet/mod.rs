@@ -0,0 +1,23 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! # Packet Sockets
+//!
+//! Packet sockets send and receive whole Ethernet frames, so that applications may implement their own protocols on
+//! top of the datapath. The stack keeps handling the frames that packet sockets receive.
+
+mod frame;
+pub mod peer;
+pub mod socket;
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests;
+
+//==============================================================================
+// Exports
+//==============================================================================
+
+pub use self::{
+    peer::SharedPacketPeer,
+    socket::PacketFilter,
+};
@@ -0,0 +1,151 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::protocols::{
+        ethernet2::{
+            ETHERNET2_HEADER_SIZE,
+            VLAN_TAG_SIZE,
+            VLAN_TPID,
+        },
+        packet::socket::{
+            PacketFilter,
+            SharedPacketSocket,
+        },
+    },
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        network::NetworkRuntime,
+        SharedObject,
+    },
+    timer,
+};
+use ::std::{
+    collections::HashMap,
+    net::SocketAddr,
+    ops::{
+        Deref,
+        DerefMut,
+    },
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Packet Peer
+pub struct PacketPeer<N: NetworkRuntime> {
+    /// Underlying transport.
+    transport: N,
+    /// Open packet sockets, indexed by their identifier.
+    sockets: HashMap<usize, SharedPacketSocket<N>>,
+    /// Identifier of the next socket that is opened.
+    next_id: usize,
+}
+
+#[derive(Clone)]
+pub struct SharedPacketPeer<N: NetworkRuntime>(SharedObject<PacketPeer<N>>);
+
+//======================================================================================================================
+// Associate Functions
+//======================================================================================================================
+
+impl<N: NetworkRuntime> SharedPacketPeer<N> {
+    pub fn new(transport: N) -> Self {
+        Self(SharedObject::<PacketPeer<N>>::new(PacketPeer {
+            transport,
+            sockets: HashMap::<usize, SharedPacketSocket<N>>::new(),
+            next_id: 0,
+        }))
+    }
+
+    /// Opens a packet socket for [protocol], which is an EtherType in network byte order.
+    pub fn socket(&mut self, protocol: u16) -> SharedPacketSocket<N> {
+        let filter: PacketFilter = PacketFilter::from_protocol(protocol);
+        let id: usize = self.next_id;
+        self.next_id += 1;
+        let socket: SharedPacketSocket<N> = SharedPacketSocket::new(id, filter, self.transport.clone());
+        self.sockets.insert(id, socket.clone());
+        socket
+    }
+
+    /// Closes a packet socket.
+    pub fn hard_close(&mut self, socket: &mut SharedPacketSocket<N>) -> Result<(), Fail> {
+        self.sockets.remove(&socket.id());
+        Ok(())
+    }
+
+    /// Closes a packet socket asynchronously.
+    pub async fn close(&mut self, socket: &mut SharedPacketSocket<N>) -> Result<(), Fail> {
+        self.hard_close(socket)
+    }
+
+    /// Pushes a frame to the network.
+    pub async fn push(&mut self, socket: &mut SharedPacketSocket<N>, buf: &mut DemiBuffer) -> Result<(), Fail> {
+        socket.push(buf.clone()).await?;
+        buf.trim(buf.len())
+    }
+
+    /// Pops a frame from a socket. Frames carry their own addresses, so no address is returned.
+    pub async fn pop(
+        &mut self,
+        socket: &mut SharedPacketSocket<N>,
+        size: usize,
+    ) -> Result<(Option<SocketAddr>, DemiBuffer), Fail> {
+        let frame: DemiBuffer = socket.pop(size).await?;
+        Ok((None, frame))
+    }
+
+    /// Hands a copy of an incoming frame to the packet sockets whose filter it passes.
+    pub fn receive(&mut self, frame: &DemiBuffer) {
+        if self.sockets.is_empty() {
+            return;
+        }
+        timer!("packet::receive");
+        let ether_type: u16 = match Self::ether_type(frame) {
+            Some(ether_type) => ether_type,
+            None => return,
+        };
+        for socket in self.sockets.values_mut() {
+            socket.receive(ether_type, frame);
+        }
+    }
+
+    /// Gets the EtherType of [frame], skipping the IEEE 802.1Q tag of tagged frames.
+    fn ether_type(frame: &DemiBuffer) -> Option<u16> {
+        if frame.len() < ETHERNET2_HEADER_SIZE {
+            return None;
+        }
+        let ether_type: u16 = u16::from_be_bytes([frame[12], frame[13]]);
+        if ether_type != VLAN_TPID {
+            return Some(ether_type);
+        }
+        if frame.len() < ETHERNET2_HEADER_SIZE + VLAN_TAG_SIZE {
+            return None;
+        }
+        Some(u16::from_be_bytes([frame[16], frame[17]]))
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl<N: NetworkRuntime> Deref for SharedPacketPeer<N> {
+    type Target = PacketPeer<N>;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl<N: NetworkRuntime> DerefMut for SharedPacketPeer<N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
@@ -0,0 +1,205 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    collections::async_queue::AsyncQueue,
+    inetstack::protocols::{
+        ethernet2::ETHERNET2_HEADER_SIZE,
+        packet::frame::PacketFrame,
+    },
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        network::{
+            socket::{
+                filter::SocketFilter,
+                option::{
+                    SocketOption,
+                    SocketOptions,
+                },
+                shaper::Shaper,
+            },
+            NetworkRuntime,
+        },
+        SharedObject,
+    },
+};
+use ::std::{
+    fmt::Debug,
+    ops::{
+        Deref,
+        DerefMut,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Protocol of packet sockets that receive all frames, regardless of their EtherType.
+pub const ETH_P_ALL: u16 = 0x0003;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Frames that are received by a packet socket.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PacketFilter {
+    /// No frames. The socket may only send.
+    Nothing,
+    /// All frames.
+    All,
+    /// Frames with the given EtherType. The EtherType of tagged frames is the one that follows the tag.
+    EtherType(u16),
+}
+
+/// Per-queue metadata for a packet socket.
+pub struct PacketSocket<N: NetworkRuntime> {
+    /// Identifier of this socket in the packet peer.
+    id: usize,
+    filter: PacketFilter,
+    network: N,
+    // A queue of incoming frames.
+    recv_queue: AsyncQueue<DemiBuffer>,
+    options: SocketOptions,
+    receive_filter: Option<SocketFilter>,
+    shaper: Shaper,
+}
+
+#[derive(Clone)]
+pub struct SharedPacketSocket<N: NetworkRuntime>(SharedObject<PacketSocket<N>>);
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl PacketFilter {
+    /// Creates the filter of a packet socket that was opened for [protocol], which is an EtherType in network byte
+    /// order, just like the protocol of Linux packet sockets.
+    pub fn from_protocol(protocol: u16) -> Self {
+        match u16::from_be(protocol) {
+            0 => PacketFilter::Nothing,
+            ETH_P_ALL => PacketFilter::All,
+            ether_type => PacketFilter::EtherType(ether_type),
+        }
+    }
+
+    /// Checks if a frame with EtherType [ether_type] passes this filter.
+    pub fn matches(&self, ether_type: u16) -> bool {
+        match self {
+            PacketFilter::Nothing => false,
+            PacketFilter::All => true,
+            PacketFilter::EtherType(expected) => *expected == ether_type,
+        }
+    }
+}
+
+impl<N: NetworkRuntime> SharedPacketSocket<N> {
+    pub fn new(id: usize, filter: PacketFilter, network: N) -> Self {
+        Self(SharedObject::new(PacketSocket::<N> {
+            id,
+            filter,
+            network,
+            recv_queue: AsyncQueue::<DemiBuffer>::default(),
+            options: SocketOptions::default(),
+            receive_filter: None,
+            shaper: Shaper::default(),
+        }))
+    }
+
+    /// Sets [option] on this socket. Only the priority applies to the frames that are sent from now on.
+    pub fn set_socket_option(&mut self, option: SocketOption) -> Result<(), Fail> {
+        self.options.set(option)?;
+        let rate: Option<u64> = self.options.max_pacing_rate();
+        self.shaper.set_rate(rate);
+        Ok(())
+    }
+
+    /// Gets the current value of [option] on this socket.
+    pub fn get_socket_option(&self, option: SocketOption) -> SocketOption {
+        self.options.get(option)
+    }
+
+    /// Attaches [filter] to this socket, replacing the previous one. The filter runs after the EtherType filter of the
+    /// socket. Frames that are already queued are kept.
+    pub fn set_receive_filter(&mut self, filter: Option<SocketFilter>) {
+        self.receive_filter = filter;
+    }
+
+    /// Sends [frame] as is. The frame should start with an Ethernet header.
+    pub async fn push(&mut self, frame: DemiBuffer) -> Result<(), Fail> {
+        if frame.len() < ETHERNET2_HEADER_SIZE {
+            let cause: String = format!("frame is too small (len={:?})", frame.len());
+            error!("push(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        let len: usize = frame.len();
+        let packet: PacketFrame = PacketFrame::new(frame, self.options.priority());
+        self.shaper.wait(len).await;
+        self.network.transmit_one(Box::new(packet));
+        Ok(())
+    }
+
+    /// Pops the next incoming frame. Frames that are larger than [size] are truncated.
+    pub async fn pop(&mut self, size: usize) -> Result<DemiBuffer, Fail> {
+        let mut frame: DemiBuffer = self.recv_queue.pop(None).await?;
+        if size < frame.len() {
+            frame.trim(frame.len() - size)?;
+        }
+        Ok(frame)
+    }
+
+    /// Delivers [frame], whose EtherType is [ether_type], if it passes the filters of this socket.
+    pub fn receive(&mut self, ether_type: u16, frame: &DemiBuffer) {
+        if !self.filter.matches(ether_type) {
+            return;
+        }
+        let mut frame: DemiBuffer = frame.clone();
+        if let Some(filter) = self.receive_filter.as_ref() {
+            if !filter.apply(&mut frame) {
+                debug!("receive(): frame dropped by filter (ether_type={:#06x})", ether_type);
+                return;
+            }
+        }
+        self.recv_queue.push(frame);
+    }
+
+    /// Returns the identifier of this socket in the packet peer.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Returns the filter of this socket.
+    pub fn filter(&self) -> PacketFilter {
+        self.filter
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl<N: NetworkRuntime> Deref for SharedPacketSocket<N> {
+    type Target = PacketSocket<N>;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl<N: NetworkRuntime> DerefMut for SharedPacketSocket<N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+impl<N: NetworkRuntime> Debug for SharedPacketSocket<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Packet socket filter={:?}", self.filter)
+    }
+}
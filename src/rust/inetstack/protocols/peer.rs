@@ -7,6 +7,7 @@ use crate::{
         icmpv4::SharedIcmpv4Peer,
         ip::IpProtocol,
         ipv4::Ipv4Header,
+        raw::SharedRawPeer,
         tcp::SharedTcpPeer,
         udp::SharedUdpPeer,
     },
@@ -18,11 +19,14 @@ use crate::{
                 TcpConfig,
                 UdpConfig,
             },
+            drops::DropReason,
+            timestamp::RxTimestamp,
             types::MacAddress,
             NetworkRuntime,
         },
         SharedDemiRuntime,
     },
+    trace_event,
 };
 use ::std::{
     net::Ipv4Addr,
@@ -33,10 +37,13 @@ use ::std::{
 use crate::inetstack::protocols::tcp::socket::SharedTcpSocket;
 
 pub struct Peer<N: NetworkRuntime> {
+    runtime: SharedDemiRuntime,
     local_ipv4_addr: Ipv4Addr,
-    icmpv4: SharedIcmpv4Peer<N>,
+    arp: SharedArpPeer<N>,
+    pub icmpv4: SharedIcmpv4Peer<N>,
     pub tcp: SharedTcpPeer<N>,
     pub udp: SharedUdpPeer<N>,
+    pub raw: SharedRawPeer<N>,
 }
 
 impl<N: NetworkRuntime> Peer<N> {
@@ -50,13 +57,11 @@ impl<N: NetworkRuntime> Peer<N> {
         arp: SharedArpPeer<N>,
         rng_seed: [u8; 32],
     ) -> Result<Self, Fail> {
-        let udp_offload_checksum: bool = udp_config.get_tx_checksum_offload();
         let udp: SharedUdpPeer<N> = SharedUdpPeer::<N>::new(
             runtime.clone(),
             transport.clone(),
             local_link_addr,
-            local_ipv4_addr,
-            udp_offload_checksum,
+            udp_config,
             arp.clone(),
         )?;
         let icmpv4: SharedIcmpv4Peer<N> = SharedIcmpv4Peer::<N>::new(
@@ -71,42 +76,51 @@ impl<N: NetworkRuntime> Peer<N> {
             runtime.clone(),
             transport.clone(),
             local_link_addr,
-            local_ipv4_addr,
             tcp_config,
-            arp,
+            arp.clone(),
             rng_seed,
         )?;
+        let raw: SharedRawPeer<N> = SharedRawPeer::<N>::new(transport, local_link_addr, arp.clone());
 
         Ok(Peer {
+            runtime,
             local_ipv4_addr,
+            arp,
             icmpv4,
             tcp,
             udp,
+            raw,
         })
     }
 
-    pub fn receive(&mut self, buf: DemiBuffer) {
+    pub fn receive(&mut self, buf: DemiBuffer, timestamp: RxTimestamp) {
         let (header, payload) = match Ipv4Header::parse(buf) {
             Ok(result) => result,
             Err(e) => {
-                let cause: String = format!("Invalid destination address: {:?}", e);
-                warn!("dropping packet: {}", cause);
+                self.runtime.record_drop(DropReason::from_parse_error(&e));
+                trace_event!("drop", "malformed_ipv4", cause = e.cause);
                 return;
             },
         };
         debug!("Ipv4 received {:?}", header);
-        if header.get_dest_addr() != self.local_ipv4_addr && !header.get_dest_addr().is_broadcast() {
-            let cause: String = format!("Invalid destination address");
-            warn!("dropping packet: {}", cause);
+        if !self.arp.is_local_addr(header.get_dest_addr()) && !header.get_dest_addr().is_broadcast() {
+            self.runtime.record_drop(DropReason::AddressMismatch);
+            trace_event!("drop", "ipv4_addr_mismatch", dst = header.get_dest_addr());
             return;
         }
         match header.get_protocol() {
             IpProtocol::ICMPv4 => self.icmpv4.receive(header, payload),
             IpProtocol::TCP => self.tcp.receive(header, payload),
-            IpProtocol::UDP => self.udp.receive(header, payload),
+            IpProtocol::UDP => self.udp.receive(header, payload, timestamp),
+            IpProtocol::Other(_) => self.raw.receive(header, payload),
         }
     }
 
+    /// Ends the current batch of received packets.
+    pub fn flush(&mut self) {
+        self.tcp.flush_coalesced();
+    }
+
     pub async fn ping(&mut self, dest_ipv4_addr: Ipv4Addr, timeout: Option<Duration>) -> Result<Duration, Fail> {
         self.icmpv4.ping(dest_ipv4_addr, timeout).await
     }
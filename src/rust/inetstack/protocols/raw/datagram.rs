@@ -0,0 +1,78 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::protocols::{
+        ethernet2::Ethernet2Header,
+        ipv4::Ipv4Header,
+    },
+    runtime::{
+        memory::DemiBuffer,
+        network::PacketBuf,
+    },
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// IPv4 datagram whose payload is written by the application.
+#[derive(Debug)]
+pub struct RawDatagram {
+    /// Ethernet header.
+    ethernet2_hdr: Ethernet2Header,
+    /// IPv4 header.
+    ipv4_hdr: Ipv4Header,
+    /// Payload, starting with the header of the protocol that the datagram carries.
+    data: DemiBuffer,
+}
+
+//======================================================================================================================
+// Associate Functions
+//======================================================================================================================
+
+impl RawDatagram {
+    /// Creates a raw IPv4 datagram.
+    pub fn new(ethernet2_hdr: Ethernet2Header, ipv4_hdr: Ipv4Header, data: DemiBuffer) -> Self {
+        Self {
+            ethernet2_hdr,
+            ipv4_hdr,
+            data,
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl PacketBuf for RawDatagram {
+    fn header_size(&self) -> usize {
+        self.ethernet2_hdr.compute_size() + self.ipv4_hdr.compute_size()
+    }
+
+    fn body_size(&self) -> usize {
+        self.data.len()
+    }
+
+    fn write_header(&self, buf: &mut [u8]) {
+        let eth_hdr_size: usize = self.ethernet2_hdr.compute_size();
+        let ipv4_hdr_size: usize = self.ipv4_hdr.compute_size();
+
+        self.ethernet2_hdr.serialize(&mut buf[..eth_hdr_size]);
+        self.ipv4_hdr
+            .serialize(&mut buf[eth_hdr_size..(eth_hdr_size + ipv4_hdr_size)], self.data.len());
+    }
+
+    fn take_body(&self) -> Option<DemiBuffer> {
+        Some(self.data.clone())
+    }
+
+    fn priority(&self) -> Option<u8> {
+        self.ethernet2_hdr.priority()
+    }
+}
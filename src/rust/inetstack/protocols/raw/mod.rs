@@ -0,0 +1,20 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! # Raw IPv4 Sockets
+//!
+//! Raw sockets send and receive IPv4 datagrams that carry a protocol chosen by the application, for protocols that
+//! are not handled by the stack itself (e.g. OSPF or custom encapsulations).
+
+mod datagram;
+pub mod peer;
+pub mod socket;
+
+#[cfg(test)]
+mod tests;
+
+//==============================================================================
+// Exports
+//==============================================================================
+
+pub use self::peer::SharedRawPeer;
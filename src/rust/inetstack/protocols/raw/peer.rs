@@ -0,0 +1,157 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::protocols::{
+        arp::SharedArpPeer,
+        ip::IpProtocol,
+        ipv4::Ipv4Header,
+        raw::socket::SharedRawSocket,
+    },
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        network::{
+            types::MacAddress,
+            NetworkRuntime,
+        },
+        SharedObject,
+    },
+    timer,
+};
+use ::std::{
+    collections::HashMap,
+    net::{
+        Ipv4Addr,
+        SocketAddr,
+        SocketAddrV4,
+    },
+    ops::{
+        Deref,
+        DerefMut,
+    },
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Raw IPv4 Peer
+pub struct RawPeer<N: NetworkRuntime> {
+    /// Underlying transport.
+    transport: N,
+    /// Underlying ARP peer.
+    arp: SharedArpPeer<N>,
+    /// Local link address.
+    local_link_addr: MacAddress,
+    /// Incoming demultiplexing table, indexed by protocol number.
+    protocols: HashMap<u8, SharedRawSocket<N>>,
+}
+
+#[derive(Clone)]
+pub struct SharedRawPeer<N: NetworkRuntime>(SharedObject<RawPeer<N>>);
+
+//======================================================================================================================
+// Associate Functions
+//======================================================================================================================
+
+impl<N: NetworkRuntime> SharedRawPeer<N> {
+    pub fn new(transport: N, local_link_addr: MacAddress, arp: SharedArpPeer<N>) -> Self {
+        Self(SharedObject::<RawPeer<N>>::new(RawPeer {
+            transport,
+            arp,
+            local_link_addr,
+            protocols: HashMap::<u8, SharedRawSocket<N>>::new(),
+        }))
+    }
+
+    /// Opens a raw socket for [protocol]. Protocols that are handled by the stack itself cannot be opened, and each
+    /// protocol may only be opened by one socket at a time.
+    pub fn socket(&mut self, protocol: IpProtocol) -> Result<SharedRawSocket<N>, Fail> {
+        let protocol_number: u8 = match protocol {
+            IpProtocol::Other(protocol_number) if protocol_number != 0 => protocol_number,
+            _ => {
+                let cause: String = format!("protocol not supported on raw sockets (protocol={:?})", protocol);
+                error!("socket(): {}", cause);
+                return Err(Fail::new(libc::ENOTSUP, &cause));
+            },
+        };
+        if self.protocols.contains_key(&protocol_number) {
+            let cause: String = format!(
+                "protocol is already open on another raw socket (protocol={:?})",
+                protocol
+            );
+            error!("socket(): {}", cause);
+            return Err(Fail::new(libc::EADDRINUSE, &cause));
+        }
+
+        let socket: SharedRawSocket<N> =
+            SharedRawSocket::new(protocol, self.local_link_addr, self.transport.clone(), self.arp.clone());
+        self.protocols.insert(protocol_number, socket.clone());
+        Ok(socket)
+    }
+
+    /// Closes a raw socket.
+    pub fn hard_close(&mut self, socket: &mut SharedRawSocket<N>) -> Result<(), Fail> {
+        self.protocols.remove(&u8::from(socket.protocol()));
+        Ok(())
+    }
+
+    /// Closes a raw socket asynchronously.
+    pub async fn close(&mut self, socket: &mut SharedRawSocket<N>) -> Result<(), Fail> {
+        self.hard_close(socket)
+    }
+
+    /// Pushes a datagram to a remote host.
+    pub async fn push(
+        &mut self,
+        socket: &mut SharedRawSocket<N>,
+        buf: &mut DemiBuffer,
+        remote: Option<SocketAddr>,
+    ) -> Result<(), Fail> {
+        socket.push(remote, buf.clone()).await?;
+        buf.trim(buf.len())
+    }
+
+    /// Pops a datagram from a socket. The port of the returned address is always zero.
+    pub async fn pop(
+        &mut self,
+        socket: &mut SharedRawSocket<N>,
+        size: usize,
+    ) -> Result<(Option<SocketAddr>, DemiBuffer), Fail> {
+        let (addr, buf): (Ipv4Addr, DemiBuffer) = socket.pop(size).await?;
+        Ok((Some(SocketAddrV4::new(addr, 0).into()), buf))
+    }
+
+    /// Hands the payload of an incoming datagram to the raw socket that is open for its protocol.
+    pub fn receive(&mut self, ipv4_hdr: Ipv4Header, buf: DemiBuffer) {
+        timer!("raw::receive");
+        let protocol_number: u8 = u8::from(ipv4_hdr.get_protocol());
+        match self.protocols.get_mut(&protocol_number) {
+            Some(socket) => socket.receive(ipv4_hdr.get_src_addr(), buf),
+            None => warn!("dropping packet: protocol not open: {:?}", ipv4_hdr.get_protocol()),
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl<N: NetworkRuntime> Deref for SharedRawPeer<N> {
+    type Target = RawPeer<N>;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl<N: NetworkRuntime> DerefMut for SharedRawPeer<N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
@@ -0,0 +1,188 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    collections::async_queue::AsyncQueue,
+    inetstack::protocols::{
+        arp::SharedArpPeer,
+        ethernet2::{
+            EtherType2,
+            Ethernet2Header,
+        },
+        ip::IpProtocol,
+        ipv4::Ipv4Header,
+        raw::datagram::RawDatagram,
+    },
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+        network::{
+            socket::{
+                filter::SocketFilter,
+                option::{
+                    SocketOption,
+                    SocketOptions,
+                },
+                shaper::Shaper,
+            },
+            types::MacAddress,
+            unwrap_socketaddr,
+            NetworkRuntime,
+            PacketBuf,
+        },
+        SharedObject,
+    },
+};
+use ::std::{
+    fmt::Debug,
+    net::{
+        Ipv4Addr,
+        SocketAddr,
+        SocketAddrV4,
+    },
+    ops::{
+        Deref,
+        DerefMut,
+    },
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Per-queue metadata for a raw IPv4 socket.
+pub struct RawSocket<N: NetworkRuntime> {
+    /// Protocol of the datagrams that are sent and received through this socket.
+    protocol: IpProtocol,
+    local_link_addr: MacAddress,
+    network: N,
+    // A queue of incoming datagrams as source address and payload pairs.
+    recv_queue: AsyncQueue<(Ipv4Addr, DemiBuffer)>,
+    arp: SharedArpPeer<N>,
+    options: SocketOptions,
+    receive_filter: Option<SocketFilter>,
+    shaper: Shaper,
+}
+
+#[derive(Clone)]
+pub struct SharedRawSocket<N: NetworkRuntime>(SharedObject<RawSocket<N>>);
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl<N: NetworkRuntime> SharedRawSocket<N> {
+    pub fn new(protocol: IpProtocol, local_link_addr: MacAddress, network: N, arp: SharedArpPeer<N>) -> Self {
+        Self(SharedObject::new(RawSocket::<N> {
+            protocol,
+            local_link_addr,
+            network,
+            recv_queue: AsyncQueue::<(Ipv4Addr, DemiBuffer)>::default(),
+            arp,
+            options: SocketOptions::default(),
+            receive_filter: None,
+            shaper: Shaper::default(),
+        }))
+    }
+
+    /// Sets [option] on this socket. The new value applies to the datagrams that are sent from now on.
+    pub fn set_socket_option(&mut self, option: SocketOption) -> Result<(), Fail> {
+        self.options.set(option)?;
+        let rate: Option<u64> = self.options.max_pacing_rate();
+        self.shaper.set_rate(rate);
+        Ok(())
+    }
+
+    /// Gets the current value of [option] on this socket.
+    pub fn get_socket_option(&self, option: SocketOption) -> SocketOption {
+        self.options.get(option)
+    }
+
+    /// Attaches [filter] to this socket, replacing the previous one. Datagrams that are already queued are kept.
+    pub fn set_receive_filter(&mut self, filter: Option<SocketFilter>) {
+        self.receive_filter = filter;
+    }
+
+    /// Sends [buf] to [remote] in a single IPv4 datagram. The port of [remote] is ignored.
+    pub async fn push(&mut self, remote: Option<SocketAddr>, buf: DemiBuffer) -> Result<(), Fail> {
+        let remote: SocketAddrV4 = if let Some(remote) = remote {
+            unwrap_socketaddr(remote)?
+        } else {
+            let cause: &str = "raw socket requires a remote address";
+            error!("pushto(): {}", cause);
+            return Err(Fail::new(libc::EDESTADDRREQ, cause));
+        };
+        // Options are taken before resolving the remote, so that changes made in the meantime apply to later datagrams.
+        let options: SocketOptions = self.options;
+        let remote_link_addr: MacAddress = self.arp.query_via(*remote.ip(), options.device()).await?;
+        let datagram: RawDatagram = RawDatagram::new(
+            Ethernet2Header::new(remote_link_addr, self.local_link_addr, EtherType2::Ipv4)
+                .with_priority(options.priority()),
+            Ipv4Header::new(
+                self.arp.source_addr_via(*remote.ip(), options.device()),
+                *remote.ip(),
+                self.protocol,
+            )
+            .with_tos(options.tos())
+            .with_ttl(options.ttl()),
+            buf,
+        );
+        self.shaper.wait(datagram.header_size() + datagram.body_size()).await;
+        self.network.transmit_one(Box::new(datagram));
+        Ok(())
+    }
+
+    /// Pops the payload of the next incoming datagram, along with the address of its sender. Payloads that are
+    /// larger than [size] are truncated.
+    pub async fn pop(&mut self, size: usize) -> Result<(Ipv4Addr, DemiBuffer), Fail> {
+        let (remote, mut buf): (Ipv4Addr, DemiBuffer) = self.recv_queue.pop(None).await?;
+        if size < buf.len() {
+            buf.trim(buf.len() - size)?;
+        }
+        Ok((remote, buf))
+    }
+
+    /// Delivers the payload of a datagram that was sent by [remote], unless the receive filter drops it.
+    pub fn receive(&mut self, remote: Ipv4Addr, mut buf: DemiBuffer) {
+        if let Some(filter) = self.receive_filter.as_ref() {
+            if !filter.apply(&mut buf) {
+                debug!("receive(): datagram dropped by filter (remote={:?})", remote);
+                return;
+            }
+        }
+        self.recv_queue.push((remote, buf));
+    }
+
+    /// Returns the protocol of the datagrams that are sent and received through this socket.
+    pub fn protocol(&self) -> IpProtocol {
+        self.protocol
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl<N: NetworkRuntime> Deref for SharedRawSocket<N> {
+    type Target = RawSocket<N>;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl<N: NetworkRuntime> DerefMut for SharedRawSocket<N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+impl<N: NetworkRuntime> Debug for SharedRawSocket<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Raw socket protocol={:?}", self.protocol)
+    }
+}
@@ -0,0 +1,99 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+use crate::{
+    inetstack::test_helpers::{
+        self,
+        engine::{
+            SharedEngine,
+            DEFAULT_TIMEOUT,
+        },
+    },
+    runtime::{
+        memory::DemiBuffer,
+        queue::{
+            OperationResult,
+            QDesc,
+            QToken,
+        },
+    },
+};
+use ::anyhow::Result;
+use ::libc::{
+    EADDRINUSE,
+    ENOTSUP,
+};
+use ::std::{
+    net::SocketAddrV4,
+    time::Instant,
+};
+
+/// Protocol number of OSPF.
+const OSPF: u8 = 89;
+
+//==============================================================================
+// Socket
+//==============================================================================
+
+/// Tests that raw sockets cannot be opened for protocols that the stack handles itself, nor twice for a protocol.
+#[test]
+fn raw_socket_protocols() -> Result<()> {
+    let now: Instant = Instant::now();
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+
+    for protocol in [0, 1, 6, 17, 200] {
+        match alice.raw_socket(protocol) {
+            Err(e) if e.errno == ENOTSUP => {},
+            _ => anyhow::bail!("opened raw socket for protocol={:?}", protocol),
+        }
+    }
+
+    let qd: QDesc = alice.raw_socket(OSPF)?;
+    match alice.raw_socket(OSPF) {
+        Err(e) if e.errno == EADDRINUSE => {},
+        _ => anyhow::bail!("opened a second raw socket for the same protocol"),
+    }
+    alice.udp_close(qd)?;
+    let qd: QDesc = alice.raw_socket(OSPF)?;
+    alice.udp_close(qd)?;
+
+    Ok(())
+}
+
+//==============================================================================
+// Push & Pop
+//==============================================================================
+
+/// Tests that a datagram pushed to a raw socket is delivered to the raw socket of the same protocol on the remote.
+#[test]
+fn raw_push_pop() -> Result<()> {
+    let now: Instant = Instant::now();
+
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let alice_fd: QDesc = alice.raw_socket(OSPF)?;
+    let mut bob: SharedEngine = test_helpers::new_bob2(now);
+    let bob_fd: QDesc = bob.raw_socket(OSPF)?;
+
+    // Send data to Bob.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    let alice_qt: QToken = alice.udp_pushto(alice_fd, buf.clone(), SocketAddrV4::new(test_helpers::BOB_IPV4, 0))?;
+    match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(_)) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+
+    // Receive data from Alice.
+    bob.receive(alice.pop_frame())?;
+    let bob_qt: QToken = bob.udp_pop(bob_fd)?;
+    let (remote_addr, received_buf): (Option<SocketAddrV4>, DemiBuffer) = match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Pop(addr, buf, _)) => (addr, buf),
+        _ => anyhow::bail!("Pop failed"),
+    };
+    crate::ensure_eq!(remote_addr, Some(SocketAddrV4::new(test_helpers::ALICE_IPV4, 0)));
+    crate::ensure_eq!(received_buf[..], buf[..]);
+
+    alice.udp_close(alice_fd)?;
+    bob.udp_close(bob_fd)?;
+
+    Ok(())
+}
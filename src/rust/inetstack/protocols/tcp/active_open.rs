@@ -18,6 +18,7 @@ use crate::{
         ip::IpProtocol,
         ipv4::Ipv4Header,
         tcp::{
+            authentication::check_unauthenticated,
             constants::{
                 FALLBACK_MSS,
                 MAX_WINDOW_SCALE,
@@ -34,6 +35,10 @@ use crate::{
                 TcpOptions2,
                 TcpSegment,
             },
+            signature::{
+                TcpSigner,
+                TcpSigning,
+            },
             SeqNumber,
         },
     },
@@ -42,6 +47,11 @@ use crate::{
         memory::DemiBuffer,
         network::{
             config::TcpConfig,
+            socket::option::{
+                SocketOptions,
+                TcpAoKey,
+                TcpMd5Key,
+            },
             types::MacAddress,
             NetworkRuntime,
         },
@@ -49,6 +59,7 @@ use crate::{
         SharedDemiRuntime,
         SharedObject,
     },
+    trace_event,
 };
 use ::futures::channel::mpsc;
 use ::std::{
@@ -57,6 +68,10 @@ use ::std::{
         Deref,
         DerefMut,
     },
+    time::{
+        Duration,
+        Instant,
+    },
 };
 
 //======================================================================================================================
@@ -73,8 +88,13 @@ pub struct ActiveOpenSocket<N: NetworkRuntime> {
     ack_queue: SharedAsyncQueue<usize>,
     local_link_addr: MacAddress,
     tcp_config: TcpConfig,
+    options: SocketOptions,
+    /// How the segments of the connection are protected, if our peer shares keys with us.
+    signing: Option<TcpSigning>,
     arp: SharedArpPeer<N>,
     dead_socket_tx: mpsc::UnboundedSender<QDesc>,
+    /// SYN segment that our peer sent while our own SYN was outstanding (simultaneous open).
+    syn_received: Option<(Ipv4Header, TcpHeader, DemiBuffer)>,
 }
 
 #[derive(Clone)]
@@ -94,6 +114,8 @@ impl<N: NetworkRuntime> SharedActiveOpenSocket<N> {
         recv_queue: SharedAsyncQueue<(Ipv4Header, TcpHeader, DemiBuffer)>,
         ack_queue: SharedAsyncQueue<usize>,
         tcp_config: TcpConfig,
+        options: SocketOptions,
+        signing: Option<TcpSigning>,
         local_link_addr: MacAddress,
         arp: SharedArpPeer<N>,
         dead_socket_tx: mpsc::UnboundedSender<QDesc>,
@@ -110,66 +132,142 @@ impl<N: NetworkRuntime> SharedActiveOpenSocket<N> {
             ack_queue,
             local_link_addr,
             tcp_config,
+            options,
+            signing,
             arp,
             dead_socket_tx,
+            syn_received: None,
         })))
     }
 
-    fn process_ack(&mut self, header: TcpHeader) -> Result<EstablishedSocket<N>, Fail> {
+    /// Sets the options that are used to build the headers of the segments that are sent from now on.
+    pub fn set_socket_options(&mut self, options: SocketOptions) {
+        self.options = options;
+    }
+
+    /// Sets the key that signs the segments that are sent from now on. Connections that use the authentication option
+    /// keep it.
+    pub fn set_md5_key(&mut self, md5_key: Option<TcpMd5Key>) {
+        if !matches!(self.signing, Some(TcpSigning::Ao(_))) {
+            self.signing = md5_key.map(TcpSigning::Md5);
+        }
+    }
+
+    /// Sets the authentication keys that are shared with our peer, if the connection uses the authentication option.
+    pub fn set_ao_keys(&mut self, keys: Vec<TcpAoKey>) {
+        if let Some(TcpSigning::Ao(ref mut connection)) = self.signing {
+            connection.set_keys(keys);
+        }
+    }
+
+    /// Checks whether [key] signs the segments of the connection.
+    pub fn uses_ao_key(&self, key: &TcpAoKey) -> bool {
+        match self.signing {
+            Some(TcpSigning::Ao(ref connection)) => connection.is_current(key),
+            _ => false,
+        }
+    }
+
+    /// Checks the authentication option of a segment with header [header] and payload [data] that arrives while the
+    /// handshake is in progress. Segments of connections that do not use it must not carry it.
+    pub fn check_authentication(&mut self, header: &TcpHeader, data: &[u8]) -> Result<(), Fail> {
+        match self.signing {
+            Some(TcpSigning::Ao(ref mut connection)) => connection.check(header, data),
+            _ => check_unauthenticated(header),
+        }
+    }
+
+    /// Processes a segment that arrives while the handshake is in progress. Returns the new connection once the
+    /// handshake completes, and fails with `EAGAIN` if the segment does not complete it.
+    fn process_segment(
+        &mut self,
+        ipv4_hdr: Ipv4Header,
+        header: TcpHeader,
+        data: DemiBuffer,
+    ) -> Result<EstablishedSocket<N>, Fail> {
         let expected_seq: SeqNumber = self.local_isn + SeqNumber::from(1);
 
-        // Bail if we didn't receive a ACK packet with the right sequence number.
+        // A SYN without an ACK means that our peer is opening this connection at the same time as us. Answer it with a
+        // SYN+ACK, and complete the handshake once our SYN gets acknowledged.
+        // Reference: https://datatracker.ietf.org/doc/html/rfc9293#section-3.5
+        if header.syn && !header.ack && !header.rst {
+            debug!("Received SYN: {:?}", header);
+            trace_event!("tcp", "simultaneous_open", local = self.local, remote = self.remote);
+            self.syn_received = Some((ipv4_hdr, header, data));
+            match self.arp.try_query_via(self.remote.ip().clone(), self.options.device()) {
+                Some(remote_link_addr) => self.send_syn(remote_link_addr),
+                None => warn!("process_segment(): missing ARP entry (remote={})", self.remote.ip()),
+            }
+            let cause: String = format!("waiting for ack on syn+ack");
+            return Err(Fail::new(libc::EAGAIN, &cause));
+        }
+
+        // A reset without an ACK refuses our connection only if it follows the SYN of our peer.
+        if header.rst && !header.ack {
+            if let Some((_, syn_hdr, _)) = self.syn_received.as_ref() {
+                if header.seq_num == syn_hdr.seq_num + SeqNumber::from(1) {
+                    let cause: String = format!("connection refused");
+                    error!("process_segment(): {}", cause);
+                    return Err(Fail::new(libc::ECONNREFUSED, &cause));
+                }
+            }
+        }
+
+        // Bail if we didn't receive a ACK packet with the right sequence number. If the segment acknowledges something
+        // else, answer it with a reset (unless it is a reset itself).
         if !(header.ack && header.ack_num == expected_seq) {
             let cause: String = format!(
                 "expected ack_num: {}, received ack_num: {}",
                 expected_seq, header.ack_num
             );
-            error!("process_ack(): {}", cause);
+            error!("process_segment(): {}", cause);
+            if header.ack {
+                self.send_rst(&header);
+            }
             return Err(Fail::new(libc::EAGAIN, &cause));
         }
 
         // Check if our peer is refusing our connection request.
         if header.rst {
             let cause: String = format!("connection refused");
-            error!("process_ack(): {}", cause);
+            error!("process_segment(): {}", cause);
             return Err(Fail::new(libc::ECONNREFUSED, &cause));
         }
 
-        // Bail if we didn't receive a SYN packet.
-        if !header.syn {
-            let cause: String = format!("is not a syn packet");
-            error!("process_ack(): {}", cause);
-            return Err(Fail::new(libc::EAGAIN, &cause));
-        }
-
-        debug!("Received SYN+ACK: {:?}", header);
-
-        // Acknowledge the SYN+ACK segment.
-        let remote_link_addr = match self.arp.try_query(self.remote.ip().clone()) {
-            Some(r) => r,
-            None => panic!("TODO: Clean up ARP query control flow"),
+        // Figure out which SYN segment of our peer completes the handshake. Either it comes along with the ACK on our
+        // SYN, or we have received it earlier and this segment acknowledges our SYN+ACK.
+        let (syn_ipv4_hdr, syn_hdr, syn_data) = if header.syn {
+            debug!("Received SYN+ACK: {:?}", header);
+            self.send_ack(&header);
+            (ipv4_hdr, header.clone(), data)
+        } else {
+            match self.syn_received.take() {
+                Some(syn) => {
+                    debug!("Received ACK: {:?}", header);
+                    // If there is data with the ACK, deliver it ahead of any segment that arrived after it.
+                    if !data.is_empty() || header.fin {
+                        self.recv_queue.push_front((ipv4_hdr, header.clone(), data));
+                    }
+                    syn
+                },
+                None => {
+                    let cause: String = format!("is not a syn packet");
+                    error!("process_segment(): {}", cause);
+                    return Err(Fail::new(libc::EAGAIN, &cause));
+                },
+            }
         };
-        let remote_seq_num = header.seq_num + SeqNumber::from(1);
+        let remote_seq_num = syn_hdr.seq_num + SeqNumber::from(1);
 
-        let mut tcp_hdr = TcpHeader::new(self.local.port(), self.remote.port());
-        tcp_hdr.ack = true;
-        tcp_hdr.ack_num = remote_seq_num;
-        tcp_hdr.window_size = self.tcp_config.get_receive_window_size();
-        tcp_hdr.seq_num = self.local_isn + SeqNumber::from(1);
-        debug!("Sending ACK: {:?}", tcp_hdr);
-
-        let segment = TcpSegment {
-            ethernet2_hdr: Ethernet2Header::new(remote_link_addr, self.local_link_addr, EtherType2::Ipv4),
-            ipv4_hdr: Ipv4Header::new(self.local.ip().clone(), self.remote.ip().clone(), IpProtocol::TCP),
-            tcp_hdr,
-            data: None,
-            tx_checksum_offload: self.tcp_config.get_rx_checksum_offload(),
-        };
-        self.transport.transmit(Box::new(segment));
+        // Deliver the data that came with the SYN first.
+        if !syn_data.is_empty() || syn_hdr.fin {
+            self.recv_queue
+                .push_front((syn_ipv4_hdr, syn_hdr.syn_data_header(expected_seq), syn_data));
+        }
 
         let mut remote_window_scale = None;
         let mut mss = FALLBACK_MSS;
-        for option in header.iter_options() {
+        for option in syn_hdr.iter_options() {
             match option {
                 TcpOptions2::WindowScale(w) => {
                     info!("Received window scale: {}", w);
@@ -220,10 +318,15 @@ impl<N: NetworkRuntime> SharedActiveOpenSocket<N> {
             "TODO: Window size overflow"
         );
 
-        info!("Window sizes: local {}, remote {}", rx_window_size, tx_window_size);
-        info!(
-            "Window scale: local {}, remote {}",
-            local_window_scale, remote_window_scale
+        trace_event!(
+            "tcp",
+            "established",
+            local = self.local,
+            remote = self.remote,
+            local_window = rx_window_size,
+            remote_window = tx_window_size,
+            local_window_scale = local_window_scale,
+            remote_window_scale = remote_window_scale,
         );
         Ok(EstablishedSocket::new(
             self.local,
@@ -234,6 +337,8 @@ impl<N: NetworkRuntime> SharedActiveOpenSocket<N> {
             self.ack_queue.clone(),
             self.local_link_addr,
             self.tcp_config.clone(),
+            self.options,
+            self.signing.clone(),
             self.arp.clone(),
             remote_seq_num,
             self.tcp_config.get_ack_delay_timeout(),
@@ -256,7 +361,12 @@ impl<N: NetworkRuntime> SharedActiveOpenSocket<N> {
         for _ in 0..handshake_retries {
             // Look up remote MAC address.
             // TODO: Do we need to do this every iteration?
-            let remote_link_addr = match self.clone().arp.query(self.remote.ip().clone()).await {
+            let remote_link_addr = match self
+                .clone()
+                .arp
+                .query_via(self.remote.ip().clone(), self.options.device())
+                .await
+            {
                 Ok(r) => r,
                 Err(e) => {
                     warn!("ARP query failed: {:?}", e);
@@ -264,43 +374,27 @@ impl<N: NetworkRuntime> SharedActiveOpenSocket<N> {
                 },
             };
 
-            // Set up SYN packet.
-            let mut tcp_hdr = TcpHeader::new(self.local.port(), self.remote.port());
-            tcp_hdr.syn = true;
-            tcp_hdr.seq_num = self.local_isn;
-            tcp_hdr.window_size = self.tcp_config.get_receive_window_size();
-
-            let mss = self.tcp_config.get_advertised_mss() as u16;
-            tcp_hdr.push_option(TcpOptions2::MaximumSegmentSize(mss));
-            info!("Advertising MSS: {}", mss);
-
-            tcp_hdr.push_option(TcpOptions2::WindowScale(self.tcp_config.get_window_scale()));
-            info!("Advertising window scale: {}", self.tcp_config.get_window_scale());
-
-            debug!("Sending SYN {:?}", tcp_hdr);
-            let segment = TcpSegment {
-                ethernet2_hdr: Ethernet2Header::new(remote_link_addr, self.local_link_addr, EtherType2::Ipv4),
-                ipv4_hdr: Ipv4Header::new(self.local.ip().clone(), self.remote.ip().clone(), IpProtocol::TCP),
-                tcp_hdr,
-                data: None,
-                tx_checksum_offload: self.tcp_config.get_rx_checksum_offload(),
-            };
-            // Send SYN.
-            self.transport.transmit(Box::new(segment));
-
-            // Wait for either a response or timeout.
-            match self.recv_queue.pop(Some(handshake_timeout)).await {
-                Ok((_, header, _)) => match self.process_ack(header) {
-                    Ok(socket) => return Ok(socket),
-                    Err(Fail { errno, cause: _ }) if errno == libc::EAGAIN => continue,
-                    Err(e) => return Err(e),
-                },
-                Err(Fail { errno, cause: _ }) if errno == libc::ETIMEDOUT => continue,
-                Err(_) => {
-                    unreachable!(
-                        "either the ack deadline changed or the deadline passed, no other errors are possible!"
-                    )
-                },
+            // Send SYN, or SYN+ACK if our peer has already sent its SYN.
+            self.send_syn(remote_link_addr);
+
+            // Wait for either a response or timeout. Segments that do not complete the handshake do not restart the
+            // timer.
+            let deadline: Instant = self.runtime.get_now() + handshake_timeout;
+            loop {
+                let timeout: Duration = deadline.saturating_duration_since(self.runtime.get_now());
+                match self.recv_queue.pop(Some(timeout)).await {
+                    Ok((ipv4_hdr, header, data)) => match self.process_segment(ipv4_hdr, header, data) {
+                        Ok(socket) => return Ok(socket),
+                        Err(Fail { errno, cause: _ }) if errno == libc::EAGAIN => continue,
+                        Err(e) => return Err(e),
+                    },
+                    Err(Fail { errno, cause: _ }) if errno == libc::ETIMEDOUT => break,
+                    Err(_) => {
+                        unreachable!(
+                            "either the ack deadline changed or the deadline passed, no other errors are possible!"
+                        )
+                    },
+                }
             }
         }
 
@@ -309,6 +403,104 @@ impl<N: NetworkRuntime> SharedActiveOpenSocket<N> {
         Err(Fail::new(libc::ETIMEDOUT, &cause))
     }
 
+    /// Sends our SYN segment. Once our peer has sent its own SYN, the segment also acknowledges it.
+    fn send_syn(&mut self, remote_link_addr: MacAddress) {
+        let mut tcp_hdr = TcpHeader::new(self.local.port(), self.remote.port());
+        tcp_hdr.syn = true;
+        tcp_hdr.seq_num = self.local_isn;
+        tcp_hdr.window_size = self.tcp_config.get_receive_window_size();
+        if let Some((_, syn_hdr, _)) = self.syn_received.as_ref() {
+            tcp_hdr.ack = true;
+            tcp_hdr.ack_num = syn_hdr.seq_num + SeqNumber::from(1);
+        }
+
+        let mss = self.tcp_config.get_advertised_mss() as u16;
+        tcp_hdr.push_option(TcpOptions2::MaximumSegmentSize(mss));
+        info!("Advertising MSS: {}", mss);
+
+        tcp_hdr.push_option(TcpOptions2::WindowScale(self.tcp_config.get_window_scale()));
+        info!("Advertising window scale: {}", self.tcp_config.get_window_scale());
+
+        let signer: Option<TcpSigner> = self.signing.as_mut().map(|signing| signing.sign(&mut tcp_hdr));
+
+        debug!("Sending SYN {:?}", tcp_hdr);
+        let segment = TcpSegment {
+            ethernet2_hdr: Ethernet2Header::new(remote_link_addr, self.local_link_addr, EtherType2::Ipv4)
+                .with_priority(self.options.priority()),
+            ipv4_hdr: Ipv4Header::new(self.local.ip().clone(), self.remote.ip().clone(), IpProtocol::TCP)
+                .with_dscp(self.options.dscp())
+                .with_ttl(self.options.ttl()),
+            tcp_hdr,
+            data: None,
+            tx_checksum_offload: self.tcp_config.get_tx_checksum_offload(),
+            tso_segment_size: None,
+            signer,
+        };
+        self.transport.transmit_one(Box::new(segment));
+    }
+
+    /// Acknowledges the SYN+ACK segment with header [header].
+    fn send_ack(&mut self, header: &TcpHeader) {
+        let remote_link_addr = match self.arp.try_query_via(self.remote.ip().clone(), self.options.device()) {
+            Some(r) => r,
+            None => panic!("TODO: Clean up ARP query control flow"),
+        };
+
+        let mut tcp_hdr = TcpHeader::new(self.local.port(), self.remote.port());
+        tcp_hdr.ack = true;
+        tcp_hdr.ack_num = header.seq_num + SeqNumber::from(1);
+        tcp_hdr.window_size = self.tcp_config.get_receive_window_size();
+        tcp_hdr.seq_num = self.local_isn + SeqNumber::from(1);
+        let signer: Option<TcpSigner> = self.signing.as_mut().map(|signing| signing.sign(&mut tcp_hdr));
+        debug!("Sending ACK: {:?}", tcp_hdr);
+
+        let segment = TcpSegment {
+            ethernet2_hdr: Ethernet2Header::new(remote_link_addr, self.local_link_addr, EtherType2::Ipv4)
+                .with_priority(self.options.priority()),
+            ipv4_hdr: Ipv4Header::new(self.local.ip().clone(), self.remote.ip().clone(), IpProtocol::TCP)
+                .with_dscp(self.options.dscp())
+                .with_ttl(self.options.ttl()),
+            tcp_hdr,
+            data: None,
+            tx_checksum_offload: self.tcp_config.get_tx_checksum_offload(),
+            tso_segment_size: None,
+            signer,
+        };
+        self.transport.transmit_one(Box::new(segment));
+    }
+
+    /// Sends a RST segment in answer to the segment with header [header].
+    fn send_rst(&mut self, header: &TcpHeader) {
+        let mut tcp_hdr: TcpHeader = match header.reset_reply(0) {
+            Some(tcp_hdr) => tcp_hdr,
+            None => return,
+        };
+        let remote_link_addr: MacAddress = match self.arp.try_query_via(self.remote.ip().clone(), self.options.device())
+        {
+            Some(link_addr) => link_addr,
+            None => {
+                warn!("send_rst(): missing ARP entry (remote={})", self.remote.ip());
+                return;
+            },
+        };
+        let signer: Option<TcpSigner> = self.signing.as_mut().map(|signing| signing.sign(&mut tcp_hdr));
+        debug!("Sending RST: {:?}", tcp_hdr);
+
+        let segment = TcpSegment {
+            ethernet2_hdr: Ethernet2Header::new(remote_link_addr, self.local_link_addr, EtherType2::Ipv4)
+                .with_priority(self.options.priority()),
+            ipv4_hdr: Ipv4Header::new(self.local.ip().clone(), self.remote.ip().clone(), IpProtocol::TCP)
+                .with_dscp(self.options.dscp())
+                .with_ttl(self.options.ttl()),
+            tcp_hdr,
+            data: None,
+            tx_checksum_offload: self.tcp_config.get_tx_checksum_offload(),
+            tso_segment_size: None,
+            signer,
+        };
+        self.transport.transmit_one(Box::new(segment));
+    }
+
     /// Returns the addresses of the two ends of this connection.
     pub fn endpoints(&self) -> (SocketAddrV4, SocketAddrV4) {
         (self.local, self.remote)
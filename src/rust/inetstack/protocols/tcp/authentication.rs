@@ -0,0 +1,700 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! TCP authentication option (RFC 5925), which supersedes the MD5 signature option. Segments are authenticated with
+//! traffic keys that each connection derives from the master keys that it shares with its peer, and peers that share
+//! several master keys may rotate from one to another without disrupting their connections.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    expect_some,
+    inetstack::protocols::{
+        ip::IpProtocol,
+        tcp::{
+            segment::{
+                TcpHeader,
+                TcpOptions2,
+                MIN_TCP_HEADER_SIZE,
+            },
+            signature::TcpSigner,
+            SeqNumber,
+        },
+    },
+    runtime::{
+        crypto::{
+            aes::AES128_KEY_SIZE,
+            runtime::{
+                CryptoRuntime,
+                SoftwareCryptoRuntime,
+            },
+            sha256::SHA256_DIGEST_SIZE,
+        },
+        fail::Fail,
+        network::socket::option::{
+            TcpAoAlgorithm,
+            TcpAoInfo,
+            TcpAoKey,
+        },
+    },
+    trace_event,
+};
+use ::std::{
+    collections::HashMap,
+    net::{
+        Ipv4Addr,
+        SocketAddrV4,
+    },
+    rc::Rc,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Size of the MACs of the authentication option, which are truncated to 96 bits.
+pub const TCP_AO_MAC_SIZE: usize = 12;
+
+/// Space that the authentication option takes in the header of a segment. Segments of authenticated connections carry
+/// this much less data.
+pub const AO_OPTION_SPACE: usize = 4 + TCP_AO_MAC_SIZE;
+
+/// Label of the key derivation function (RFC 5926, section 3.1.1).
+const KDF_LABEL: &[u8] = b"TCP-AO";
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Master key tuples of a socket, each of which is shared with a group of peers.
+#[derive(Clone)]
+pub struct TcpAoKeys {
+    crypto: Rc<dyn CryptoRuntime>,
+    keys: Vec<TcpAoKey>,
+}
+
+/// State of the authentication option on a connection.
+#[derive(Clone)]
+pub struct TcpAoConnection {
+    crypto: Rc<dyn CryptoRuntime>,
+    local: SocketAddrV4,
+    remote: SocketAddrV4,
+    /// Master key tuples that are shared with our peer.
+    keys: Vec<TcpAoKey>,
+    /// Send identifier of the key that outgoing segments are signed with.
+    current_key: u8,
+    /// Receive identifier of the key that our peer is asked to sign its segments with.
+    rnext_key: u8,
+    /// Send identifier of the key that our peer last asked us to sign our segments with.
+    peer_rnext_key: u8,
+    local_isn: SeqNumber,
+    /// Initial sequence number of our peer, once it is known.
+    remote_isn: Option<SeqNumber>,
+    send_sne: Sne,
+    recv_sne: Sne,
+    /// Traffic keys of the segments that carry no SYN, by identifiers of their master key and direction.
+    traffic_keys: HashMap<(u8, u8, Direction), TrafficKey>,
+}
+
+/// Signs an outgoing segment with the authentication option once it is serialized.
+#[derive(Clone)]
+pub struct TcpAoSigner {
+    crypto: Rc<dyn CryptoRuntime>,
+    traffic_key: TrafficKey,
+    sne: u32,
+}
+
+/// Key that authenticates the segments that flow in one direction of a connection, which is derived from a master key.
+#[derive(Clone, Copy)]
+enum TrafficKey {
+    Aes128Cmac([u8; AES128_KEY_SIZE]),
+    HmacSha256([u8; SHA256_DIGEST_SIZE]),
+}
+
+/// Direction in which segments flow.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Direction {
+    Outgoing,
+    Incoming,
+}
+
+/// Sequence number extension of one direction of a connection, that is, the number of times that its sequence numbers
+/// wrapped around (RFC 5925, section 6.2).
+#[derive(Clone, Copy)]
+struct Sne {
+    sne: u32,
+    prev_seq: u32,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl TcpAoKeys {
+    /// Adds [key]. Keys of peers that overlap must have distinct identifiers.
+    pub fn add(&mut self, key: TcpAoKey) -> Result<(), Fail> {
+        let clash: bool = self.keys.iter().any(|other| {
+            (other.matches(key.addr()) || key.matches(other.addr()))
+                && (other.send_id() == key.send_id() || other.recv_id() == key.recv_id())
+        });
+        if clash {
+            let cause: String = format!(
+                "authentication key identifiers are in use (addr={}, send_id={}, recv_id={})",
+                key.addr(),
+                key.send_id(),
+                key.recv_id()
+            );
+            error!("add(): {}", cause);
+            return Err(Fail::new(libc::EEXIST, &cause));
+        }
+        self.keys.push(key);
+        Ok(())
+    }
+
+    /// Removes the key with the same peers and identifiers as [key].
+    pub fn remove(&mut self, key: &TcpAoKey) -> Result<(), Fail> {
+        match self.keys.iter().position(|other| other.same_key(key)) {
+            Some(i) => {
+                self.keys.remove(i);
+                Ok(())
+            },
+            None => {
+                let cause: String = format!(
+                    "no such authentication key (addr={}, send_id={}, recv_id={})",
+                    key.addr(),
+                    key.send_id(),
+                    key.recv_id()
+                );
+                error!("remove(): {}", cause);
+                Err(Fail::new(libc::ENOENT, &cause))
+            },
+        }
+    }
+
+    /// Gets the keys that are shared with the peer [addr], in the order in which they were added.
+    pub fn lookup(&self, addr: Ipv4Addr) -> Vec<TcpAoKey> {
+        self.keys.iter().filter(|key| key.matches(addr)).copied().collect()
+    }
+
+    /// Starts the authentication option on the connection from [local] to [remote], if they share keys. The first key
+    /// that was added signs our segments until our peer asks for another one.
+    pub fn connection(
+        &self,
+        local: SocketAddrV4,
+        remote: SocketAddrV4,
+        local_isn: SeqNumber,
+        remote_isn: Option<SeqNumber>,
+    ) -> Option<TcpAoConnection> {
+        let keys: Vec<TcpAoKey> = self.lookup(*remote.ip());
+        let first: TcpAoKey = *keys.first()?;
+        Some(TcpAoConnection {
+            crypto: self.crypto.clone(),
+            local,
+            remote,
+            keys,
+            current_key: first.send_id(),
+            rnext_key: first.recv_id(),
+            peer_rnext_key: first.send_id(),
+            local_isn,
+            remote_isn,
+            send_sne: Sne::new(local_isn),
+            recv_sne: Sne::new(remote_isn.unwrap_or(SeqNumber::from(0))),
+            traffic_keys: HashMap::new(),
+        })
+    }
+
+    /// Checks the authentication option of the segment with header [header] and payload [data] from [remote] to
+    /// [local], which belongs to the connection with initial sequence numbers [isns], if any. Segments from peers that
+    /// share keys must be authenticated with one of them, and other peers must not authenticate their segments.
+    pub fn check(
+        &self,
+        local: SocketAddrV4,
+        remote: SocketAddrV4,
+        isns: Option<(SeqNumber, SeqNumber)>,
+        header: &TcpHeader,
+        data: &[u8],
+    ) -> Result<(), Fail> {
+        let (local_isn, remote_isn): (SeqNumber, Option<SeqNumber>) = match isns {
+            Some((local_isn, remote_isn)) => (local_isn, Some(remote_isn)),
+            None => handshake_isns(header),
+        };
+        match self.connection(local, remote, local_isn, remote_isn) {
+            Some(mut connection) => connection.check(header, data),
+            None => check_unauthenticated(header),
+        }
+    }
+}
+
+impl TcpAoConnection {
+    /// Replaces the keys that are shared with our peer. The current key must be among them. If the key that our peer
+    /// is asked for is gone, our peer is asked for the first key instead.
+    pub fn set_keys(&mut self, keys: Vec<TcpAoKey>) {
+        debug_assert!(keys.iter().any(|key| key.send_id() == self.current_key));
+        if !keys.iter().any(|key| key.recv_id() == self.rnext_key) {
+            if let Some(first) = keys.first() {
+                self.rnext_key = first.recv_id();
+            }
+        }
+        self.keys = keys;
+        self.traffic_keys.clear();
+    }
+
+    /// Checks whether [key] signs our segments.
+    pub fn is_current(&self, key: &TcpAoKey) -> bool {
+        self.keys
+            .iter()
+            .any(|other| other.same_key(key) && other.send_id() == self.current_key)
+    }
+
+    /// Gets the keys that this connection uses.
+    pub fn info(&self) -> TcpAoInfo {
+        TcpAoInfo {
+            current_key: self.current_key,
+            rnext_key: self.rnext_key,
+            peer_rnext_key: self.peer_rnext_key,
+        }
+    }
+
+    /// Switches to the keys of [info]. Both keys must be shared with our peer.
+    pub fn set_info(&mut self, info: TcpAoInfo) -> Result<(), Fail> {
+        if !self.keys.iter().any(|key| key.send_id() == info.current_key)
+            || !self.keys.iter().any(|key| key.recv_id() == info.rnext_key)
+        {
+            let cause: String = format!(
+                "no such authentication key (current_key={}, rnext_key={})",
+                info.current_key, info.rnext_key
+            );
+            error!("set_info(): {}", cause);
+            return Err(Fail::new(libc::ENOENT, &cause));
+        }
+        self.current_key = info.current_key;
+        self.rnext_key = info.rnext_key;
+        Ok(())
+    }
+
+    /// Adds the authentication option to the header [header] of an outgoing segment, and returns the signer that fills
+    /// it in.
+    pub fn sign(&mut self, header: &mut TcpHeader) -> TcpSigner {
+        let key: TcpAoKey = expect_some!(
+            self.keys.iter().find(|key| key.send_id() == self.current_key).copied(),
+            "the current key should be shared with our peer"
+        );
+        header.push_option(TcpOptions2::Authentication {
+            key_id: key.send_id(),
+            rnext_key_id: self.rnext_key,
+            mac: [0; TCP_AO_MAC_SIZE],
+        });
+
+        let sne: u32 = match header.syn {
+            true => 0,
+            false => {
+                let (sne, send_sne): (u32, Sne) = self.send_sne.extend(header.seq_num);
+                self.send_sne = send_sne;
+                sne
+            },
+        };
+        let remote_isn: SeqNumber = match header.syn && !header.ack {
+            true => SeqNumber::from(0),
+            false => self.remote_isn.unwrap_or(SeqNumber::from(0)),
+        };
+        let traffic_key: TrafficKey =
+            self.traffic_key(&key, Direction::Outgoing, header.syn, self.local_isn, remote_isn);
+        TcpSigner::Ao(TcpAoSigner {
+            crypto: self.crypto.clone(),
+            traffic_key,
+            sne,
+        })
+    }
+
+    /// Checks the authentication option of the incoming segment with header [header] and payload [data]. Once it
+    /// checks out, this follows our peer onto the key that it asks for.
+    pub fn check(&mut self, header: &TcpHeader, data: &[u8]) -> Result<(), Fail> {
+        check_unsigned(header)?;
+        let (key_id, rnext_key_id, mac): (u8, u8, [u8; TCP_AO_MAC_SIZE]) = header
+            .iter_options()
+            .find_map(|option| match option {
+                TcpOptions2::Authentication {
+                    key_id,
+                    rnext_key_id,
+                    mac,
+                } => Some((*key_id, *rnext_key_id, *mac)),
+                _ => None,
+            })
+            .ok_or_else(|| Fail::new(libc::EBADMSG, "TCP authentication option missing"))?;
+        let key: TcpAoKey = self
+            .keys
+            .iter()
+            .find(|key| key.recv_id() == key_id)
+            .copied()
+            .ok_or_else(|| Fail::new(libc::EBADMSG, "unknown TCP authentication key"))?;
+        let signed_header: &[u8] = header
+            .signed_header
+            .as_deref()
+            .ok_or_else(|| Fail::new(libc::EBADMSG, "TCP authentication option missing"))?;
+
+        // SYN segments start their direction of the connection over.
+        let (remote_isn, sne, recv_sne): (SeqNumber, u32, Sne) = match header.syn {
+            true => (header.seq_num, 0, Sne::new(header.seq_num)),
+            false => {
+                let remote_isn: SeqNumber = self
+                    .remote_isn
+                    .ok_or_else(|| Fail::new(libc::EBADMSG, "TCP authentication option before SYN"))?;
+                let (sne, recv_sne): (u32, Sne) = self.recv_sne.extend(header.seq_num);
+                (remote_isn, sne, recv_sne)
+            },
+        };
+        let local_isn: SeqNumber = match header.syn && !header.ack {
+            true => SeqNumber::from(0),
+            false => self.local_isn,
+        };
+        let traffic_key: TrafficKey = self.traffic_key(&key, Direction::Incoming, header.syn, remote_isn, local_isn);
+        let expected: [u8; TCP_AO_MAC_SIZE] = compute_mac(
+            self.crypto.as_ref(),
+            &traffic_key,
+            sne,
+            *self.remote.ip(),
+            *self.local.ip(),
+            signed_header,
+            data,
+        );
+        if expected != mac {
+            return Err(Fail::new(libc::EBADMSG, "TCP authentication option mismatch"));
+        }
+
+        // Traffic keys of the peer are derived anew once its initial sequence number is known.
+        if self.remote_isn != Some(remote_isn) {
+            self.remote_isn = Some(remote_isn);
+            self.traffic_keys.clear();
+        }
+        self.recv_sne = recv_sne;
+        self.peer_rnext_key = rnext_key_id;
+        if self.current_key != rnext_key_id && self.keys.iter().any(|key| key.send_id() == rnext_key_id) {
+            trace_event!(
+                "tcp",
+                "ao_key_rotated",
+                remote = self.remote,
+                from = self.current_key,
+                to = rnext_key_id,
+            );
+            self.current_key = rnext_key_id;
+        }
+        Ok(())
+    }
+
+    /// Gets the traffic key of [key] for segments that flow in [direction] on this connection, where [src_isn] and
+    /// [dst_isn] are the initial sequence numbers of their sender and receiver. Traffic keys of SYN segments are not
+    /// kept, as they are seldom used.
+    fn traffic_key(
+        &mut self,
+        key: &TcpAoKey,
+        direction: Direction,
+        syn: bool,
+        src_isn: SeqNumber,
+        dst_isn: SeqNumber,
+    ) -> TrafficKey {
+        let (src, dst): (SocketAddrV4, SocketAddrV4) = match direction {
+            Direction::Outgoing => (self.local, self.remote),
+            Direction::Incoming => (self.remote, self.local),
+        };
+        if syn {
+            return derive_traffic_key(self.crypto.as_ref(), key, src, dst, src_isn, dst_isn);
+        }
+        let crypto: &dyn CryptoRuntime = self.crypto.as_ref();
+        *self
+            .traffic_keys
+            .entry((key.send_id(), key.recv_id(), direction))
+            .or_insert_with(|| derive_traffic_key(crypto, key, src, dst, src_isn, dst_isn))
+    }
+}
+
+impl TcpAoSigner {
+    /// Fills in the MAC at [pos] in the serialized TCP header [header] of a segment from [src] to [dst] with payload
+    /// [data]. The MAC must be zeroed.
+    pub fn sign(&self, src: Ipv4Addr, dst: Ipv4Addr, header: &mut [u8], pos: usize, data: &[u8]) {
+        let mac: [u8; TCP_AO_MAC_SIZE] = compute_mac(
+            self.crypto.as_ref(),
+            &self.traffic_key,
+            self.sne,
+            src,
+            dst,
+            header,
+            data,
+        );
+        header[pos..(pos + TCP_AO_MAC_SIZE)].copy_from_slice(&mac);
+    }
+}
+
+impl Sne {
+    /// Tracks a direction of a connection whose initial sequence number is [isn].
+    fn new(isn: SeqNumber) -> Self {
+        Self {
+            sne: 0,
+            prev_seq: u32::from(isn),
+        }
+    }
+
+    /// Gets the sequence number extension of the segment with sequence number [seq], along with the state to keep
+    /// once the segment is accepted. Segments that were sent right before sequence numbers wrapped around belong to
+    /// the previous extension.
+    fn extend(&self, seq: SeqNumber) -> (u32, Sne) {
+        let seq: u32 = u32::from(seq);
+        if seq < self.prev_seq && self.prev_seq - seq > (1 << 31) {
+            let sne: u32 = self.sne.wrapping_add(1);
+            (sne, Sne { sne, prev_seq: seq })
+        } else if seq > self.prev_seq && seq - self.prev_seq > (1 << 31) {
+            (self.sne.wrapping_sub(1), *self)
+        } else {
+            (
+                self.sne,
+                Sne {
+                    sne: self.sne,
+                    prev_seq: self.prev_seq.max(seq),
+                },
+            )
+        }
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Guesses the initial sequence numbers of our side and of our peer on the connection that the incoming segment with
+/// header [header] belongs to, as if it were part of a handshake. Listening sockets check and answer the segments of
+/// connections that they keep no state for with them.
+pub fn handshake_isns(header: &TcpHeader) -> (SeqNumber, Option<SeqNumber>) {
+    match header.syn {
+        true => (SeqNumber::from(0), Some(header.seq_num)),
+        false => (
+            header.ack_num - SeqNumber::from(1),
+            Some(header.seq_num - SeqNumber::from(1)),
+        ),
+    }
+}
+
+/// Checks that the segment with header [header], which belongs to a connection that is not authenticated, carries no
+/// authentication option.
+pub fn check_unauthenticated(header: &TcpHeader) -> Result<(), Fail> {
+    if header
+        .iter_options()
+        .any(|option| matches!(option, TcpOptions2::Authentication { .. }))
+    {
+        return Err(Fail::new(libc::EBADMSG, "unexpected TCP authentication option"));
+    }
+    Ok(())
+}
+
+/// Checks that the segment with header [header] carries no MD5 signature, which authenticated connections never use.
+fn check_unsigned(header: &TcpHeader) -> Result<(), Fail> {
+    if header
+        .iter_options()
+        .any(|option| matches!(option, TcpOptions2::Md5Signature(..)))
+    {
+        return Err(Fail::new(libc::EBADMSG, "unexpected TCP signature"));
+    }
+    Ok(())
+}
+
+/// Derives the traffic key of [key] for the segments from [src] to [dst], whose initial sequence numbers are [src_isn]
+/// and [dst_isn] (RFC 5926, section 3.1).
+fn derive_traffic_key(
+    crypto: &dyn CryptoRuntime,
+    key: &TcpAoKey,
+    src: SocketAddrV4,
+    dst: SocketAddrV4,
+    src_isn: SeqNumber,
+    dst_isn: SeqNumber,
+) -> TrafficKey {
+    let mut context: [u8; 20] = [0; 20];
+    context[0..4].copy_from_slice(&src.ip().octets());
+    context[4..8].copy_from_slice(&dst.ip().octets());
+    context[8..10].copy_from_slice(&src.port().to_be_bytes());
+    context[10..12].copy_from_slice(&dst.port().to_be_bytes());
+    context[12..16].copy_from_slice(&u32::from(src_isn).to_be_bytes());
+    context[16..20].copy_from_slice(&u32::from(dst_isn).to_be_bytes());
+
+    match key.algorithm() {
+        TcpAoAlgorithm::Aes128Cmac => {
+            // Master keys of other sizes are first compressed into an AES key (RFC 5926, section 3.1.1.2).
+            let master_key: [u8; AES128_KEY_SIZE] = match key.key().try_into() {
+                Ok(master_key) => master_key,
+                Err(_) => crypto.aes128_cmac(&[0; AES128_KEY_SIZE], &[key.key()]),
+            };
+            let output_len: u16 = (AES128_KEY_SIZE * 8) as u16;
+            TrafficKey::Aes128Cmac(
+                crypto.aes128_cmac(&master_key, &[&[1], KDF_LABEL, &context, &output_len.to_be_bytes()]),
+            )
+        },
+        TcpAoAlgorithm::HmacSha256 => {
+            let output_len: u16 = (SHA256_DIGEST_SIZE * 8) as u16;
+            TrafficKey::HmacSha256(
+                crypto.hmac_sha256(key.key(), &[&[1], KDF_LABEL, &context, &output_len.to_be_bytes()]),
+            )
+        },
+    }
+}
+
+/// Computes the MAC of a segment from [src] to [dst] with serialized TCP header [header] and payload [data] under
+/// [traffic_key], where [sne] extends its sequence number. The MAC covers the extension, a pseudo-header, the header
+/// with a zero checksum and a zero MAC, and the payload, in this order (RFC 5925, section 5.1).
+fn compute_mac(
+    crypto: &dyn CryptoRuntime,
+    traffic_key: &TrafficKey,
+    sne: u32,
+    src: Ipv4Addr,
+    dst: Ipv4Addr,
+    header: &[u8],
+    data: &[u8],
+) -> [u8; TCP_AO_MAC_SIZE] {
+    let segment_len: u16 = (header.len() + data.len()) as u16;
+    let mut pseudo_header: [u8; 12] = [0; 12];
+    pseudo_header[0..4].copy_from_slice(&src.octets());
+    pseudo_header[4..8].copy_from_slice(&dst.octets());
+    pseudo_header[9] = u8::from(IpProtocol::TCP);
+    pseudo_header[10..12].copy_from_slice(&segment_len.to_be_bytes());
+    let parts: [&[u8]; 6] = [
+        &sne.to_be_bytes(),
+        &pseudo_header,
+        &header[..16],
+        &[0, 0],
+        &header[18..],
+        data,
+    ];
+    debug_assert!(header.len() >= MIN_TCP_HEADER_SIZE);
+
+    let mut mac: [u8; TCP_AO_MAC_SIZE] = [0; TCP_AO_MAC_SIZE];
+    match traffic_key {
+        TrafficKey::Aes128Cmac(key) => mac.copy_from_slice(&crypto.aes128_cmac(key, &parts)[..TCP_AO_MAC_SIZE]),
+        TrafficKey::HmacSha256(key) => mac.copy_from_slice(&crypto.hmac_sha256(key, &parts)[..TCP_AO_MAC_SIZE]),
+    }
+    mac
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+/// Keys are computed in software unless the socket is given another runtime.
+impl Default for TcpAoKeys {
+    fn default() -> Self {
+        Self {
+            crypto: Rc::new(SoftwareCryptoRuntime),
+            keys: Vec::new(),
+        }
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        derive_traffic_key,
+        Sne,
+        TcpAoKeys,
+        TrafficKey,
+    };
+    use crate::{
+        inetstack::protocols::tcp::SeqNumber,
+        runtime::{
+            crypto::runtime::SoftwareCryptoRuntime,
+            network::socket::option::{
+                TcpAoAlgorithm,
+                TcpAoKey,
+            },
+        },
+    };
+    use ::anyhow::Result;
+    use ::std::net::{
+        Ipv4Addr,
+        SocketAddrV4,
+    };
+
+    /// Tests that keys of overlapping peers must have distinct identifiers, and that keys are removed by identifiers.
+    #[test]
+    fn test_ao_keys() -> Result<()> {
+        let peer: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 2);
+        let mut keys: TcpAoKeys = TcpAoKeys::default();
+        keys.add(TcpAoKey::new(peer, 32, 1, 1, TcpAoAlgorithm::Aes128Cmac, b"first")?)?;
+        keys.add(TcpAoKey::new(peer, 32, 2, 2, TcpAoAlgorithm::HmacSha256, b"second")?)?;
+        crate::ensure_eq!(
+            keys.add(TcpAoKey::new(
+                Ipv4Addr::new(10, 0, 0, 0),
+                8,
+                1,
+                3,
+                TcpAoAlgorithm::Aes128Cmac,
+                b"x"
+            )?)
+            .is_err(),
+            true
+        );
+        crate::ensure_eq!(keys.lookup(peer).len(), 2);
+        crate::ensure_eq!(keys.lookup(Ipv4Addr::new(10, 0, 0, 3)).len(), 0);
+
+        keys.remove(&TcpAoKey::new(
+            peer,
+            32,
+            1,
+            1,
+            TcpAoAlgorithm::Aes128Cmac,
+            b"other bytes",
+        )?)?;
+        crate::ensure_eq!(keys.lookup(peer).len(), 1);
+        crate::ensure_eq!(
+            keys.remove(&TcpAoKey::new(peer, 32, 1, 1, TcpAoAlgorithm::Aes128Cmac, b"first")?)
+                .is_err(),
+            true
+        );
+        Ok(())
+    }
+
+    /// Tests that traffic keys depend on the direction of the connection, and that master keys of any size are
+    /// accepted.
+    #[test]
+    fn test_ao_traffic_keys() -> Result<()> {
+        let alice: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 179);
+        let bob: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 2), 49152);
+        for (algorithm, master_key) in [
+            (TcpAoAlgorithm::Aes128Cmac, &b"sixteen byte key"[..]),
+            (TcpAoAlgorithm::Aes128Cmac, &b"a longer master key"[..]),
+            (TcpAoAlgorithm::HmacSha256, &b"shared secret"[..]),
+        ] {
+            let key: TcpAoKey = TcpAoKey::new(*bob.ip(), 32, 1, 1, algorithm, master_key)?;
+            let derive = |src, dst| match derive_traffic_key(
+                &SoftwareCryptoRuntime,
+                &key,
+                src,
+                dst,
+                SeqNumber::from(1),
+                SeqNumber::from(2),
+            ) {
+                TrafficKey::Aes128Cmac(key) => key.to_vec(),
+                TrafficKey::HmacSha256(key) => key.to_vec(),
+            };
+            crate::ensure_eq!(derive(alice, bob) == derive(alice, bob), true);
+            crate::ensure_eq!(derive(alice, bob) == derive(bob, alice), false);
+        }
+        Ok(())
+    }
+
+    /// Tests that sequence number extensions count wraparounds, including for segments that were sent right before
+    /// one.
+    #[test]
+    fn test_ao_sne() -> Result<()> {
+        let sne: Sne = Sne::new(SeqNumber::from(0xfff0_0000));
+        let (value, sne): (u32, Sne) = sne.extend(SeqNumber::from(0xffff_0000));
+        crate::ensure_eq!(value, 0);
+        let (value, sne): (u32, Sne) = sne.extend(SeqNumber::from(0x0000_1000));
+        crate::ensure_eq!(value, 1);
+        let (value, sne): (u32, Sne) = sne.extend(SeqNumber::from(0xffff_8000));
+        crate::ensure_eq!(value, 0);
+        let (value, _): (u32, Sne) = sne.extend(SeqNumber::from(0x0000_2000));
+        crate::ensure_eq!(value, 1);
+        Ok(())
+    }
+}
@@ -14,6 +14,7 @@ use crate::{
     async_timer,
     inetstack::protocols::tcp::established::ctrlblk::SharedControlBlock,
     runtime::{
+        fail::Fail,
         network::NetworkRuntime,
         QDesc,
     },
@@ -38,11 +39,52 @@ pub async fn background<N: NetworkRuntime>(cb: SharedControlBlock<N>, _dead_sock
     let receiver = async_timer!("tcp::established::background::receiver", cb2.poll()).fuse();
     pin_mut!(receiver);
 
+    // Stop everything once the connection is reset, as there is nothing left to send or acknowledge.
+    let mut reset = cb.watch_reset();
+    let reset = async move {
+        while !reset.get() {
+            let _ = reset.wait_for_change_until(None).await;
+        }
+        Err(Fail::new(libc::ECONNRESET, "connection reset"))
+    }
+    .fuse();
+    pin_mut!(reset);
+
+    // Abort the connection if the memory pool of the stack asks to reclaim its buffers.
+    let mut cb3: SharedControlBlock<N> = cb.clone();
+    let mut memory_abort = cb.watch_memory_abort();
+    let memory_abort = async move {
+        while !memory_abort.get() {
+            let _ = memory_abort.wait_for_change_until(None).await;
+        }
+        cb3.abort();
+        Err(Fail::new(libc::ENOBUFS, "connection aborted to reclaim memory"))
+    }
+    .fuse();
+    pin_mut!(memory_abort);
+
+    // Abort the connection if the flow table of the stack evicts it to make room for another flow.
+    let mut cb4: SharedControlBlock<N> = cb.clone();
+    let mut flow_evict = cb.watch_flow_evict();
+    let flow_evict = async move {
+        while !flow_evict.get() {
+            let _ = flow_evict.wait_for_change_until(None).await;
+        }
+        cb4.abort();
+        Err(Fail::new(libc::ECONNABORTED, "connection evicted from the flow table"))
+    }
+    .fuse();
+    pin_mut!(flow_evict);
+
     let r = futures::select_biased! {
+        r = reset => r,
+        r = memory_abort => r,
+        r = flow_evict => r,
         r = receiver => r,
         r = acknowledger => r,
         r = retransmitter => r,
         r = sender => r,
     };
     error!("Connection terminated: {:?}", r);
+    cb.log_tcp_timeline();
 }
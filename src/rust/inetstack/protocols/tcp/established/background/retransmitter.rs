@@ -9,6 +9,7 @@ use crate::{
         fail::Fail,
         network::NetworkRuntime,
     },
+    trace_event,
 };
 use ::futures::{
     never::Never,
@@ -31,6 +32,7 @@ pub async fn retransmitter<N: NetworkRuntime>(mut cb: SharedControlBlock<N>) ->
         let rtx_fast_retransmit: bool = rtx_fast_retransmit_watched.get();
         if rtx_fast_retransmit {
             // Notify congestion control about fast retransmit.
+            trace_event!("tcp", "fast_retransmit");
             cb.congestion_control_on_fast_retransmit();
 
             // Retransmit earliest unacknowledged segment.
@@ -46,9 +48,18 @@ pub async fn retransmitter<N: NetworkRuntime>(mut cb: SharedControlBlock<N>) ->
             )
         };
         pin_mut!(something_changed);
-        match conditional_yield_until(something_changed, rtx_deadline).await {
+        // Also wake up when the oldest data in flight runs out of its user timeout, whatever the retransmission timer.
+        let user_deadline: Option<Instant> = cb.get_user_timeout_deadline();
+        let deadline: Option<Instant> = [rtx_deadline, user_deadline].into_iter().flatten().min();
+        match conditional_yield_until(something_changed, deadline).await {
             Ok(()) => continue,
             Err(Fail { errno, cause: _ }) if errno == libc::ETIMEDOUT => {
+                // Give up on the connection if our peer did not acknowledge the oldest data in flight in time.
+                cb.check_user_timeout()?;
+                if rtx_deadline.map_or(true, |rtx_deadline| rtx_deadline > cb.get_now()) {
+                    continue;
+                }
+
                 // Retransmit timeout.
 
                 // Notify congestion control about RTO.
@@ -65,6 +76,7 @@ pub async fn retransmitter<N: NetworkRuntime>(mut cb: SharedControlBlock<N>) ->
 
                 // RFC 6298 Section 5.6: Restart the retransmission timer with the new RTO.
                 let rto: Duration = cb.rto();
+                trace_event!("tcp", "retransmit_timeout", rto = rto);
                 let deadline: Instant = cb.get_now() + rto;
                 cb.set_retransmit_deadline(Some(deadline));
             },
@@ -17,7 +17,9 @@ use crate::{
         fail::Fail,
         memory::DemiBuffer,
         network::NetworkRuntime,
+        yield_until,
     },
+    trace_event,
 };
 use ::futures::{
     never::Never,
@@ -28,9 +30,19 @@ use ::futures::{
 use ::std::{
     cmp,
     pin::pin,
-    time::Duration,
+    time::{
+        Duration,
+        Instant,
+    },
 };
 
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Upper bound of the persist timer, which matches the one of the retransmission timer.
+const MAX_PERSIST_TIMEOUT: Duration = Duration::from_secs(60);
+
 pub async fn sender<N: NetworkRuntime>(mut cb: SharedControlBlock<N>) -> Result<Never, Fail> {
     'top: loop {
         // First, check to see if there's any unsent data.
@@ -64,40 +76,10 @@ pub async fn sender<N: NetworkRuntime>(mut cb: SharedControlBlock<N>) -> Result<
 
         // If we don't have any window size at all, we need to transition to PERSIST mode and
         // repeatedly send window probes until window opens up.
-        if win_sz == 0 {
-            // Send a window probe (this is a one-byte packet designed to elicit a window update from our peer).
-            let remote_link_addr = cb.arp().query(cb.get_remote().ip().clone()).await?;
-            let buf: DemiBuffer = cb
-                .pop_one_unsent_byte()
-                .unwrap_or_else(|| panic!("No unsent data? {}, {}", send_next, unsent_seq));
-
-            // Update SND.NXT.
-            cb.modify_send_next(|s| s + SeqNumber::from(1));
-
-            // Add the probe byte (as a new separate buffer) to our unacknowledged queue.
-            let unacked_segment = UnackedSegment {
-                bytes: buf.clone(),
-                initial_tx: Some(cb.get_now()),
-            };
-            cb.push_unacked_segment(unacked_segment);
-
-            // Note that we loop here *forever*, exponentially backing off.
-            // TODO: Use the correct PERSIST mode timer here.
-            let mut timeout: Duration = Duration::from_secs(1);
-            loop {
-                // Create packet.
-                let mut header: TcpHeader = cb.tcp_header();
-                header.seq_num = send_next;
-                cb.emit(header, Some(buf.clone()), remote_link_addr);
-
-                match win_sz_watched.wait_for_change(Some(timeout)).await {
-                    Ok(_) => continue 'top,
-                    Err(Fail { errno, cause: _ }) if errno == libc::ETIMEDOUT => timeout *= 2,
-                    Err(_) => unreachable!(
-                        "either the ack deadline changed or the deadline passed, no other errors are possible!"
-                    ),
-                }
-            }
+        let next_buf_size: usize = expect_some!(cb.unsent_top_size(), "no buffer in unsent queue");
+        if win_sz == 0 && next_buf_size > 0 {
+            persist(&mut cb, &mut win_sz_watched, send_next).await?;
+            continue 'top;
         }
 
         // The remote window is nonzero, but there still may not be room.
@@ -114,10 +96,16 @@ pub async fn sender<N: NetworkRuntime>(mut cb: SharedControlBlock<N>) -> Result<
         let ltci: u32 = ltci_watched.get();
 
         let effective_cwnd: u32 = cwnd + ltci;
-        let next_buf_size: usize = expect_some!(cb.unsent_top_size(), "no buffer in unsent queue");
 
+        // When nothing is in flight, no acknowledgement will come to open the window any further, so send whatever
+        // fits in it instead of waiting for room for the whole buffer.
         let sent_data: u32 = (send_next - send_unacked).into();
-        if win_sz <= (sent_data + next_buf_size as u32)
+        let window_blocked: bool = match sent_data {
+            0 => win_sz == 0,
+            _ => win_sz <= (sent_data + next_buf_size as u32),
+        };
+        cb.set_window_stalled(window_blocked, win_sz);
+        if window_blocked
             || effective_cwnd <= sent_data
             || (effective_cwnd - sent_data) <= cb.get_mss() as u32
         {
@@ -130,17 +118,23 @@ pub async fn sender<N: NetworkRuntime>(mut cb: SharedControlBlock<N>) -> Result<
             };
         }
 
+        // Hold the data back while the connection is over its pacing rate.
+        if let Some(deadline) = cb.get_pacing_deadline() {
+            yield_until(deadline).await;
+            continue 'top;
+        }
+
         // Past this point we have data to send and it's valid to send it!
 
         // TODO: Nagle's algorithm - We need to coalese small buffers together to send MSS sized packets.
         // TODO: Silly window syndrome - See RFC 1122's discussion of the SWS avoidance algorithm.
 
         // TODO: Link-level concerns don't belong here, we should call an IP-level send routine below.
-        let remote_link_addr = cb.arp().query(cb.get_remote().ip().clone()).await?;
+        let remote_link_addr = cb.next_hop().await?;
 
         // Form an outgoing packet.
         let max_size: usize = cmp::min(
-            cmp::min((win_sz - sent_data) as usize, cb.get_mss()),
+            cmp::min((win_sz - sent_data) as usize, cb.get_max_segment_size()),
             (effective_cwnd - sent_data) as usize,
         );
         let (segment_data, do_push): (DemiBuffer, bool) = expect_some!(
@@ -170,9 +164,11 @@ pub async fn sender<N: NetworkRuntime>(mut cb: SharedControlBlock<N>) -> Result<
         cb.modify_send_next(|s| s + SeqNumber::from(segment_data_len));
 
         // Put this segment on the unacknowledged list.
+        let now: Instant = cb.get_now();
         let unacked_segment = UnackedSegment {
             bytes: segment_data,
-            initial_tx: Some(cb.get_now()),
+            initial_tx: Some(now),
+            first_tx: now,
         };
         cb.push_unacked_segment(unacked_segment);
 
@@ -185,3 +181,77 @@ pub async fn sender<N: NetworkRuntime>(mut cb: SharedControlBlock<N>) -> Result<
         }
     }
 }
+
+/// Runs the persist timer while our peer advertises a zero window (RFC 9293 Section 3.8.6.1). Every time that the
+/// timer expires, a probe with the first unsent byte is sent to elicit a window update, and the timer backs off
+/// exponentially as the retransmission timer does. Returns once the window opens.
+async fn persist<N: NetworkRuntime>(
+    cb: &mut SharedControlBlock<N>,
+    win_sz_watched: &mut SharedAsyncValue<u32>,
+    send_next: SeqNumber,
+) -> Result<(), Fail> {
+    trace_event!("tcp", "persist", seq = send_next);
+    cb.set_flow_control_stalled(true);
+
+    // The probe byte is taken from the unsent queue the first time that the timer expires, and then sent again on
+    // every following expiration, so that probes never go past the window.
+    let mut probe: Option<DemiBuffer> = None;
+    let mut timeout: Duration = cb.rto();
+    let mut deadline: Instant = cb.get_now() + timeout;
+    loop {
+        // The probe byte stays unacknowledged while the window is closed, so the user timeout bounds how long we probe.
+        let user_deadline: Option<Instant> = cb.get_user_timeout_deadline();
+        let wait_deadline: Instant = user_deadline.map_or(deadline, |user_deadline| user_deadline.min(deadline));
+        match win_sz_watched.wait_for_change_until(Some(wait_deadline)).await {
+            // Acknowledgements of the probes keep advertising a zero window, so keep the timer running.
+            Ok(0) => continue,
+            Ok(_) => break,
+            Err(Fail { errno, cause: _ }) if errno == libc::ETIMEDOUT => {
+                cb.check_user_timeout()?;
+                if deadline > cb.get_now() {
+                    continue;
+                }
+                let remote_link_addr = cb.next_hop().await?;
+                let buf: DemiBuffer = match probe {
+                    Some(ref buf) => buf.clone(),
+                    None => {
+                        let buf: DemiBuffer = expect_some!(cb.pop_one_unsent_byte(), "no buffer in unsent queue");
+
+                        // Update SND.NXT.
+                        cb.modify_send_next(|s| s + SeqNumber::from(1));
+
+                        // Add the probe byte (as a new separate buffer) to our unacknowledged queue.
+                        let now: Instant = cb.get_now();
+                        let unacked_segment = UnackedSegment {
+                            bytes: buf.clone(),
+                            initial_tx: Some(now),
+                            first_tx: now,
+                        };
+                        cb.push_unacked_segment(unacked_segment);
+                        probe = Some(buf.clone());
+                        buf
+                    },
+                };
+
+                // Send a window probe (this is a one-byte packet designed to elicit a window update from our peer).
+                trace_event!("tcp", "window_probe", seq = send_next, timeout = timeout);
+                cb.count_window_probe();
+                let mut header: TcpHeader = cb.tcp_header();
+                header.seq_num = send_next;
+                cb.emit(header, Some(buf), remote_link_addr);
+
+                timeout = cmp::min(timeout * 2, MAX_PERSIST_TIMEOUT);
+                deadline = cb.get_now() + timeout;
+            },
+            Err(_) => unreachable!("either the window changed or the deadline passed, no other errors are possible!"),
+        }
+    }
+    cb.set_flow_control_stalled(false);
+
+    // The probe byte is now regular data in flight, so make sure that it is retransmitted if it was not accepted.
+    if probe.is_some() && cb.get_retransmit_deadline().is_none() {
+        let rto: Duration = cb.rto();
+        cb.set_retransmit_deadline(Some(cb.get_now() + rto));
+    }
+    Ok(())
+}
@@ -75,14 +75,18 @@ pub struct Cubic {
 impl CongestionControl for Cubic {
     fn new(mss: usize, seq_no: SeqNumber, options: Option<Options>) -> Box<dyn CongestionControl> {
         let mss: u32 = mss.try_into().unwrap();
-        // The initial value of cwnd is set according to RFC5681, section 3.1, page 7.
-        let initial_cwnd: u32 = match mss {
-            0..=1095 => 4 * mss,
-            1096..=2190 => 3 * mss,
-            _ => 2 * mss,
+        let options: Options = options.unwrap_or_default();
+
+        // The initial value of cwnd is either given in segments, or set according to RFC5681, section 3.1, page 7.
+        let initial_cwnd: u32 = match options.get_int("initial_cwnd") {
+            Some(segments) => (segments as u32).saturating_mul(mss),
+            None => match mss {
+                0..=1095 => 4 * mss,
+                1096..=2190 => 3 * mss,
+                _ => 2 * mss,
+            },
         };
 
-        let options: Options = options.unwrap_or_default();
         let fast_convergence: bool = options.get_bool("fast_convergence").unwrap_or(true);
 
         Box::new(Self {
@@ -297,6 +301,10 @@ impl SlowStartCongestionAvoidance for Cubic {
         self.cwnd.clone()
     }
 
+    fn get_ssthresh(&self) -> u32 {
+        self.ssthresh.get()
+    }
+
     fn on_cwnd_check_before_send(&mut self) {
         let long_time_since_send: bool =
             Instant::now().duration_since(self.last_send_time.get()) > self.rtt_at_last_send.get();
@@ -366,3 +374,34 @@ impl LimitedTransmit for Cubic {
         self.limited_transmit_cwnd_increase.clone()
     }
 }
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::Cubic;
+    use crate::inetstack::protocols::tcp::{
+        established::congestion_control::{
+            CongestionControl,
+            Options,
+        },
+        SeqNumber,
+    };
+    use ::anyhow::Result;
+
+    /// Tests that the initial congestion window follows RFC 5681, unless it is given in segments.
+    #[test]
+    fn test_cubic_initial_cwnd() -> Result<()> {
+        let cc: Box<dyn CongestionControl> = Cubic::new(1460, SeqNumber::from(0), None);
+        crate::ensure_eq!(cc.get_cwnd().get(), 3 * 1460);
+
+        let mut options: Options = Options::default();
+        options.insert_int("initial_cwnd".to_string(), 10);
+        let cc: Box<dyn CongestionControl> = Cubic::new(1460, SeqNumber::from(0), Some(options));
+        crate::ensure_eq!(cc.get_cwnd().get(), 10 * 1460);
+
+        Ok(())
+    }
+}
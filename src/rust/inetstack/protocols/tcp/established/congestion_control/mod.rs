@@ -26,6 +26,11 @@ pub use self::{
 pub trait SlowStartCongestionAvoidance {
     fn get_cwnd(&self) -> SharedAsyncValue<u32>;
 
+    // Gets the slow start threshold, which is arbitrarily high for algorithms that do not use one.
+    fn get_ssthresh(&self) -> u32 {
+        u32::MAX
+    }
+
     // Called immediately before the cwnd check is performed before data is sent.
     fn on_cwnd_check_before_send(&mut self) {}
 
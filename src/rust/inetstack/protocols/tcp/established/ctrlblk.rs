@@ -15,7 +15,10 @@ use crate::{
     },
     expect_ok,
     inetstack::protocols::{
-        arp::SharedArpPeer,
+        arp::{
+            NextHop,
+            SharedArpPeer,
+        },
         ethernet2::{
             EtherType2,
             Ethernet2Header,
@@ -23,11 +26,13 @@ use crate::{
         ip::IpProtocol,
         ipv4::Ipv4Header,
         tcp::{
+            authentication::check_unauthenticated,
             established::{
                 congestion_control::{
                     self,
                     CongestionControlConstructor,
                 },
+                frozen::FrozenConnection,
                 rto::RtoCalculator,
                 sender::{
                     Sender,
@@ -38,29 +43,69 @@ use crate::{
                 TcpHeader,
                 TcpSegment,
             },
+            signature::{
+                TcpSigner,
+                TcpSigning,
+            },
             SeqNumber,
         },
     },
     runtime::{
         fail::Fail,
+        limits,
         memory::DemiBuffer,
         network::{
             config::TcpConfig,
+            drops::{
+                DropQueue,
+                DropReason,
+                DropRecord,
+                DropStats,
+            },
+            flows::FlowEntry,
+            memory::{
+                MemoryAccount,
+                MemoryKind,
+            },
+            socket::{
+                option::{
+                    SocketOptions,
+                    TcpAoInfo,
+                    TcpAoKey,
+                    TcpMd5Key,
+                },
+                shaper::Shaper,
+            },
+            tcp_info::{
+                TcpEvent,
+                TcpEventKind,
+                TcpInfo,
+                TcpState,
+                TcpTimeline,
+            },
             types::MacAddress,
             NetworkRuntime,
+            PacketBatch,
+            PacketBuf,
         },
         SharedDemiRuntime,
         SharedObject,
     },
+    trace_event,
+};
+use ::futures::{
+    never::Never,
+    FutureExt,
 };
-use ::futures::never::Never;
 use ::std::{
     collections::VecDeque,
+    mem,
     net::SocketAddrV4,
     ops::{
         Deref,
         DerefMut,
     },
+    pin::pin,
     time::{
         Duration,
         Instant,
@@ -129,6 +174,9 @@ struct Receiver {
 
     // Receive queue.  Contains in-order received (and acknowledged) data ready for the application to read.
     recv_queue: AsyncQueue<DemiBuffer>,
+
+    // Number of bytes in the receive queue.
+    recv_queue_bytes: usize,
 }
 
 impl Receiver {
@@ -137,12 +185,14 @@ impl Receiver {
             reader_next,
             receive_next,
             recv_queue: AsyncQueue::with_capacity(RECV_QUEUE_SZ),
+            recv_queue_bytes: 0,
         }
     }
 
     pub async fn pop(&mut self, size: Option<usize>) -> Result<DemiBuffer, Fail> {
         let buf: DemiBuffer = if let Some(size) = size {
             let mut buf: DemiBuffer = self.recv_queue.pop(None).await?;
+            self.recv_queue_bytes -= buf.len();
             // Split the buffer if it's too big.
             if buf.len() > size {
                 buf.split_front(size)?
@@ -150,7 +200,9 @@ impl Receiver {
                 buf
             }
         } else {
-            self.recv_queue.pop(None).await?
+            let buf: DemiBuffer = self.recv_queue.pop(None).await?;
+            self.recv_queue_bytes -= buf.len();
+            buf
         };
 
         self.reader_next = self.reader_next + SeqNumber::from(buf.len() as u32);
@@ -161,6 +213,7 @@ impl Receiver {
     pub fn push(&mut self, buf: DemiBuffer) {
         let buf_len: u32 = buf.len() as u32;
         self.recv_queue.push(buf);
+        self.recv_queue_bytes += buf_len as usize;
         self.receive_next = self.receive_next + SeqNumber::from(buf_len as u32);
     }
 }
@@ -180,10 +233,23 @@ pub struct ControlBlock<N: NetworkRuntime> {
     runtime: SharedDemiRuntime,
     local_link_addr: MacAddress,
     tcp_config: TcpConfig,
+    options: SocketOptions,
+    // How the segments of this connection are protected, if our peer shares keys with us.
+    signing: Option<TcpSigning>,
+    // Egress shaper that enforces the pacing rate of the socket.
+    shaper: Shaper,
+    // Incoming segments that were dropped on this connection.
+    drops: DropQueue,
+    // Memory that this connection buffers, accounted against the limit of the stack.
+    memory: MemoryAccount,
+    // Flow that this connection takes up in the flow table of the stack, until it is reset.
+    flow: Option<FlowEntry>,
 
     // TODO: We shouldn't be keeping anything datalink-layer specific at this level.  The IP layer should be holding
     // this along with other remote IP information (such as routing, path MTU, etc).
     arp: SharedArpPeer<N>,
+    // Link address of the next hop toward our peer, cached until the ARP cache changes.
+    next_hop: NextHop,
 
     // Send-side state information.  TODO: Consider incorporating this directly into ControlBlock.
     sender: Sender,
@@ -191,6 +257,20 @@ pub struct ControlBlock<N: NetworkRuntime> {
     // TCP Connection State.
     state: State,
 
+    // Whether the connection was reset, either by our peer or by an abortive close. Pending operations wait on this to
+    // fail with ECONNRESET.
+    reset: SharedAsyncValue<bool>,
+    // Whether the connection was aborted because sent data stayed unacknowledged for longer than the user timeout, in
+    // which case pending operations fail with ETIMEDOUT instead.
+    timed_out: bool,
+
+    // Tickets of the pushes that wait for room in the send buffer, in the order that they were issued. Only the push at
+    // the front may take room, so that a later push never overtakes an earlier one. The counter is bumped whenever a
+    // push leaves the queue, to wake up the pushes behind it.
+    send_waiters: VecDeque<u64>,
+    next_send_ticket: u64,
+    send_waiters_changed: SharedAsyncValue<u64>,
+
     ack_delay_timeout: Duration,
 
     ack_deadline: SharedAsyncValue<Option<Instant>>,
@@ -199,6 +279,14 @@ pub struct ControlBlock<N: NetworkRuntime> {
     // Note: The maximum possible advertised window is 1 GiB with window scaling and 64 KiB without.
     receive_buffer_size: u32,
 
+    // Receive buffer auto-tuning (dynamic right-sizing). Our round-trip time is estimated as the time that it takes to
+    // receive a full window, since a receiver may never have its own data acknowledged. Once per round-trip time, the
+    // receive buffer grows to twice the bytes that the application popped, so that the sender is never limited by how
+    // fast the application drains our receive queue.
+    receive_rtt: Option<Duration>,
+    receive_rtt_measurement: Option<(SeqNumber, Instant)>,
+    receive_space_start: (SeqNumber, Instant),
+
     // TODO: Review how this is used.  We could have separate window scale factors, so there should be one for the
     // receiver and one for the sender.
     // This is the receive-side window scale factor.
@@ -230,6 +318,20 @@ pub struct ControlBlock<N: NetworkRuntime> {
     // Retransmission Timeout (RTO) calculator.
     rto_calculator: RtoCalculator,
 
+    // Number of segments that were retransmitted, retransmission timeouts and fast retransmissions on this connection.
+    retransmits: u64,
+    timeouts: u64,
+    fast_retransmits: u64,
+
+    // Whether our peer advertises a zero window and the persist timer is running, and the number of window probes.
+    flow_control_stalled: bool,
+    window_probes: u32,
+
+    // Whether data that is waiting to be sent does not fit in the window of our peer, and the timeline of recent
+    // retransmission and flow control events, which is logged on close.
+    window_stalled: bool,
+    timeline: TcpTimeline,
+
     // Incoming packets for this connection.
     recv_queue: SharedAsyncQueue<(Ipv4Header, TcpHeader, DemiBuffer)>,
 
@@ -238,6 +340,12 @@ pub struct ControlBlock<N: NetworkRuntime> {
 
 #[derive(Clone)]
 pub struct SharedControlBlock<N: NetworkRuntime>(SharedObject<ControlBlock<N>>);
+
+/// Turn of a push to take room in the send buffer. Dropping it lets the next push in line take its turn.
+pub struct SendBufferTurn<N: NetworkRuntime> {
+    cb: SharedControlBlock<N>,
+    ticket: u64,
+}
 //==============================================================================
 
 impl<N: NetworkRuntime> SharedControlBlock<N> {
@@ -248,6 +356,8 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
         transport: N,
         local_link_addr: MacAddress,
         tcp_config: TcpConfig,
+        options: SocketOptions,
+        signing: Option<TcpSigning>,
         arp: SharedArpPeer<N>,
         receiver_seq_no: SeqNumber,
         ack_delay_timeout: Duration,
@@ -261,8 +371,30 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
         congestion_control_options: Option<congestion_control::Options>,
         recv_queue: SharedAsyncQueue<(Ipv4Header, TcpHeader, DemiBuffer)>,
         ack_queue: SharedAsyncQueue<usize>,
+        flow: FlowEntry,
     ) -> Self {
         let sender: Sender = Sender::new(sender_seq_no, sender_window_size, sender_window_scale, sender_mss);
+        let mut shaper: Shaper = Shaper::default();
+        shaper.set_rate(options.max_pacing_rate());
+        let mut drops: DropQueue = DropQueue::default();
+        drops.set_enabled(options.recv_err());
+        // The initial congestion window of the socket, or else that of the stack, is handed to congestion control.
+        let congestion_control_options: Option<congestion_control::Options> =
+            match options.init_cwnd().or(tcp_config.get_initial_cwnd()) {
+                Some(segments) => {
+                    let mut cc_options: congestion_control::Options = congestion_control_options.unwrap_or_default();
+                    cc_options.insert_int("initial_cwnd".to_string(), segments as i64);
+                    Some(cc_options)
+                },
+                None => congestion_control_options,
+            };
+        let memory: MemoryAccount = runtime.get_memory_pool().open_account(true);
+        let now: Instant = runtime.get_now();
+        let rto_calculator: RtoCalculator = RtoCalculator::new(
+            options.rto_initial().unwrap_or(tcp_config.get_rto_initial()),
+            options.rto_min().unwrap_or(tcp_config.get_rto_min()),
+            options.rto_max().unwrap_or(tcp_config.get_rto_max()),
+        );
         Self(SharedObject::<ControlBlock<N>>::new(ControlBlock {
             local,
             remote,
@@ -270,19 +402,41 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
             transport,
             local_link_addr,
             tcp_config,
+            options,
+            signing,
+            shaper,
+            drops,
+            memory,
+            flow: Some(flow),
             arp,
+            next_hop: NextHop::new(*remote.ip(), options.device()),
             sender,
             state: State::Established,
+            reset: SharedAsyncValue::new(false),
+            timed_out: false,
+            send_waiters: VecDeque::new(),
+            next_send_ticket: 0,
+            send_waiters_changed: SharedAsyncValue::new(0),
             ack_delay_timeout,
             ack_deadline: SharedAsyncValue::new(None),
             receive_buffer_size: receiver_window_size,
+            receive_rtt: None,
+            receive_rtt_measurement: None,
+            receive_space_start: (receiver_seq_no, now),
             window_scale: receiver_window_scale,
             out_of_order: VecDeque::new(),
             out_of_order_fin: Option::None,
             receiver: Receiver::new(receiver_seq_no, receiver_seq_no),
             cc: cc_constructor(sender_mss, sender_seq_no, congestion_control_options),
             retransmit_deadline: SharedAsyncValue::new(None),
-            rto_calculator: RtoCalculator::new(),
+            rto_calculator,
+            retransmits: 0,
+            timeouts: 0,
+            fast_retransmits: 0,
+            flow_control_stalled: false,
+            window_probes: 0,
+            window_stalled: false,
+            timeline: TcpTimeline::new(now),
             recv_queue,
             ack_queue,
         }))
@@ -301,12 +455,88 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
         self.arp.clone()
     }
 
+    /// Gets the link address of the next hop toward our peer, if it is resolved.
+    pub fn try_next_hop(&mut self) -> Option<MacAddress> {
+        let cb: &mut ControlBlock<N> = &mut self.0;
+        cb.next_hop.get(&mut cb.arp)
+    }
+
+    /// Gets the link address of the next hop toward our peer, resolving it if needed.
+    pub async fn next_hop(&mut self) -> Result<MacAddress, Fail> {
+        match self.try_next_hop() {
+            Some(link_addr) => Ok(link_addr),
+            None => self.arp().query_via(*self.remote.ip(), self.options.device()).await,
+        }
+    }
+
     pub fn send(&mut self, buf: DemiBuffer) -> Result<(), Fail> {
+        self.check_not_reset()?;
+        if !self.memory.admit(buf.len()) {
+            let cause: String = format!(
+                "memory limit reached (local={:?}, remote={:?})",
+                self.local, self.remote
+            );
+            warn!("send(): {}", cause);
+            return Err(Fail::new(libc::ENOBUFS, &cause));
+        }
+        let now: Instant = self.runtime.get_now();
+        self.touch_flow(now);
         let self_: Self = self.clone();
-        self.sender.send(buf, self_)
+        let result: Result<(), Fail> = self.sender.send(buf, self_);
+        self.update_memory_usage();
+        result
     }
 
-    pub fn retransmit(&self) {
+    /// Waits until the send buffer has room for [len] more bytes and every push that was issued before this one took
+    /// its room. A push that is larger than the send buffer is accepted once everything that was pushed before it is
+    /// acknowledged. The returned turn must be held until the data is sent, so that the next push waits for it.
+    pub async fn wait_for_send_buffer(&mut self, len: usize) -> Result<SendBufferTurn<N>, Fail> {
+        let ticket: u64 = self.next_send_ticket;
+        self.next_send_ticket = self.next_send_ticket.wrapping_add(1);
+        self.send_waiters.push_back(ticket);
+        // Leave the queue on every exit, including when the push fails or is cancelled.
+        let turn: SendBufferTurn<N> = SendBufferTurn {
+            cb: self.clone(),
+            ticket,
+        };
+        let mut reset: SharedAsyncValue<bool> = self.reset.clone();
+        let mut send_unacked: SharedAsyncValue<SeqNumber> = self.sender.get_send_unacked();
+        let mut send_waiters_changed: SharedAsyncValue<u64> = self.send_waiters_changed.clone();
+        loop {
+            self.check_not_reset()?;
+            let buffered: usize = self.sender.buffered_bytes();
+            if self.send_waiters.front() == Some(&ticket)
+                && (buffered == 0 || buffered + len <= self.tcp_config.get_send_buffer_size())
+            {
+                return Ok(turn);
+            }
+            trace_event!("tcp", "send_buffer_full", buffered = buffered, len = len);
+            futures::select_biased! {
+                _ = pin!(reset.wait_for_change_until(None).fuse()) => continue,
+                _ = pin!(send_unacked.wait_for_change_until(None).fuse()) => continue,
+                _ = pin!(send_waiters_changed.wait_for_change_until(None).fuse()) => continue,
+            }
+        }
+    }
+
+    /// Waits until our peer acknowledges everything before [seq_no].
+    pub async fn wait_for_acknowledgement(&mut self, seq_no: SeqNumber) -> Result<(), Fail> {
+        let mut reset: SharedAsyncValue<bool> = self.reset.clone();
+        let mut send_unacked: SharedAsyncValue<SeqNumber> = self.sender.get_send_unacked();
+        loop {
+            self.check_not_reset()?;
+            if send_unacked.get() >= seq_no {
+                return Ok(());
+            }
+            futures::select_biased! {
+                _ = pin!(reset.wait_for_change_until(None).fuse()) => continue,
+                _ = pin!(send_unacked.wait_for_change_until(None).fuse()) => continue,
+            }
+        }
+    }
+
+    pub fn retransmit(&mut self) {
+        self.retransmits += 1;
         self.sender.retransmit(self.clone())
     }
 
@@ -315,10 +545,16 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
     }
 
     pub fn congestion_control_on_fast_retransmit(&mut self) {
+        self.fast_retransmits += 1;
+        let bytes_in_flight: u64 = self.sender.unacked_bytes() as u64;
+        self.record_event(TcpEventKind::FastRetransmit, bytes_in_flight);
         self.cc.on_fast_retransmit()
     }
 
     pub fn congestion_control_on_rto(&mut self, send_unacknowledged: SeqNumber) {
+        self.timeouts += 1;
+        let bytes_in_flight: u64 = self.sender.unacked_bytes() as u64;
+        self.record_event(TcpEventKind::RetransmitTimeout, bytes_in_flight);
         self.cc.on_rto(send_unacknowledged)
     }
 
@@ -338,8 +574,192 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
         self.cc.get_limited_transmit_cwnd_increase()
     }
 
+    /// Gets the largest payload of the segments that we send, which leaves room for the signature or authentication
+    /// option of protected connections.
     pub fn get_mss(&self) -> usize {
-        self.sender.get_mss()
+        match self.signing {
+            Some(ref signing) => self.sender.get_mss().saturating_sub(signing.space()).max(1),
+            None => self.sender.get_mss(),
+        }
+    }
+
+    /// Sets the options that are used to build the headers of the segments that are sent from now on. New bounds for
+    /// the retransmission timeout and new user timeouts apply right away.
+    pub fn set_socket_options(&mut self, options: SocketOptions) {
+        let user_timeout_changed: bool = options.user_timeout() != self.options.user_timeout();
+        self.options = options;
+        if user_timeout_changed {
+            // Wake up the retransmitter, so that it watches the new deadline.
+            self.retransmit_deadline.modify(|deadline| deadline);
+        }
+        self.shaper.set_rate(options.max_pacing_rate());
+        self.drops.set_enabled(options.recv_err());
+        let rto_min: Duration = options.rto_min().unwrap_or(self.tcp_config.get_rto_min());
+        let rto_max: Duration = options.rto_max().unwrap_or(self.tcp_config.get_rto_max());
+        self.rto_calculator.set_bounds(rto_min, rto_max);
+    }
+
+    /// Checks if pushes complete only once our peer acknowledges their data.
+    pub fn tx_complete(&self) -> bool {
+        self.options.tx_complete()
+    }
+
+    /// Sets the key that signs the segments that are sent from now on, and that the segments of our peer must be signed
+    /// with. Connections that use the authentication option keep it.
+    pub fn set_md5_key(&mut self, md5_key: Option<TcpMd5Key>) {
+        if !matches!(self.signing, Some(TcpSigning::Ao(_))) {
+            self.signing = md5_key.map(TcpSigning::Md5);
+        }
+    }
+
+    /// Sets the authentication keys that are shared with our peer, on connections that use the authentication option.
+    pub fn set_ao_keys(&mut self, keys: Vec<TcpAoKey>) {
+        if let Some(TcpSigning::Ao(ref mut connection)) = self.signing {
+            connection.set_keys(keys);
+        }
+    }
+
+    /// Gets the authentication keys that this connection uses, if it uses the authentication option.
+    pub fn get_ao_info(&self) -> Option<TcpAoInfo> {
+        match self.signing {
+            Some(TcpSigning::Ao(ref connection)) => Some(connection.info()),
+            _ => None,
+        }
+    }
+
+    /// Switches to the authentication keys of [info].
+    pub fn set_ao_info(&mut self, info: TcpAoInfo) -> Result<(), Fail> {
+        match self.signing {
+            Some(TcpSigning::Ao(ref mut connection)) => connection.set_info(info),
+            _ => {
+                let cause: &str = "connection does not use the authentication option";
+                error!("set_ao_info(): {}", cause);
+                Err(Fail::new(libc::ENOENT, cause))
+            },
+        }
+    }
+
+    /// Checks whether [key] signs the segments of this connection.
+    pub fn uses_ao_key(&self, key: &TcpAoKey) -> bool {
+        match self.signing {
+            Some(TcpSigning::Ao(ref connection)) => connection.is_current(key),
+            _ => false,
+        }
+    }
+
+    /// Checks the authentication option of an incoming segment with header [header] and payload [data]. Segments of
+    /// connections that do not use it must not carry it.
+    pub fn check_authentication(&mut self, header: &TcpHeader, data: &[u8]) -> Result<(), Fail> {
+        match self.signing {
+            Some(TcpSigning::Ao(ref mut connection)) => connection.check(header, data),
+            _ => check_unauthenticated(header),
+        }
+    }
+
+    /// Gets the number of incoming segments that were dropped on this connection, for each reason.
+    pub fn get_drop_stats(&self) -> DropStats {
+        self.drops.stats()
+    }
+
+    /// Pops the oldest record from the error queue of this connection.
+    pub fn pop_drop_record(&mut self) -> Option<DropRecord> {
+        self.drops.pop()
+    }
+
+    /// Records a segment from our peer with [len] bytes of payload that was dropped for [reason].
+    pub fn record_drop(&mut self, reason: DropReason, len: usize) {
+        let remote: SocketAddrV4 = self.remote;
+        self.runtime.record_drop(reason);
+        self.drops.record(reason, Some(remote), len);
+    }
+
+    /// Gets a snapshot of the state of this connection.
+    pub fn get_tcp_info(&self) -> TcpInfo {
+        let state: TcpState = match self.state {
+            State::Established => TcpState::Established,
+            State::FinWait1 => TcpState::FinWait1,
+            State::FinWait2 => TcpState::FinWait2,
+            State::Closing => TcpState::Closing,
+            State::TimeWait => TcpState::TimeWait,
+            State::CloseWait => TcpState::CloseWait,
+            State::LastAck => TcpState::LastAck,
+            State::Closed => TcpState::Closed,
+        };
+        let recv_queue: usize = self.receiver.recv_queue.get_values().map(|buf| buf.len()).sum();
+        TcpInfo {
+            state,
+            mss: self.sender.get_mss() as u32,
+            cwnd: self.cc.get_cwnd().get(),
+            ssthresh: self.cc.get_ssthresh(),
+            srtt: self.rto_calculator.srtt(),
+            rttvar: self.rto_calculator.rttvar(),
+            rto: self.rto_calculator.rto(),
+            retransmits: self.retransmits,
+            timeouts: self.timeouts,
+            fast_retransmits: self.fast_retransmits,
+            flow_control_stalled: self.flow_control_stalled,
+            window_probes: self.window_probes,
+            bytes_in_flight: self.sender.unacked_bytes() as u32,
+            send_queue: self.sender.unsent_bytes() as u32,
+            recv_queue: recv_queue as u32,
+            recv_space: self.receive_buffer_size,
+        }
+    }
+
+    pub fn set_flow_control_stalled(&mut self, stalled: bool) {
+        if stalled && !self.flow_control_stalled {
+            let send_queue: u64 = self.sender.unsent_bytes() as u64;
+            self.record_event(TcpEventKind::ZeroWindow, send_queue);
+        }
+        self.flow_control_stalled = stalled;
+    }
+
+    /// Sets whether data that is waiting to be sent does not fit in the send window [win_sz] of our peer. Only the
+    /// start of a stall is recorded in the timeline.
+    pub fn set_window_stalled(&mut self, stalled: bool, win_sz: u32) {
+        if stalled && !self.window_stalled {
+            self.record_event(TcpEventKind::WindowStall, win_sz as u64);
+        }
+        self.window_stalled = stalled;
+    }
+
+    /// Gets the recent retransmission and flow control events of this connection, from the oldest to the newest.
+    pub fn get_tcp_timeline(&self) -> Vec<TcpEvent> {
+        self.timeline.events()
+    }
+
+    /// Logs the timeline of this connection, if anything was recorded, so that it can be looked at after the fact.
+    pub fn log_tcp_timeline(&self) {
+        for event in self.timeline.events() {
+            info!(
+                "tcp timeline {} -> {}: {:?} at {:?} seq={} value={}",
+                self.local, self.remote, event.kind, event.at, event.seq_no, event.value
+            );
+        }
+    }
+
+    fn record_event(&mut self, kind: TcpEventKind, value: u64) {
+        let now: Instant = self.get_now();
+        let seq_no: u32 = self.sender.get_send_unacked().get().into();
+        self.timeline.record(now, kind, seq_no, value);
+    }
+
+    pub fn count_window_probe(&mut self) {
+        self.window_probes += 1;
+    }
+
+    /// Gets the time at which the next segment with data may be sent, if the connection is over its pacing rate.
+    pub fn get_pacing_deadline(&mut self) -> Option<Instant> {
+        self.shaper.next_send_time()
+    }
+
+    /// Gets the largest segment that may be handed down at once. This is larger than the MSS when segmentation is
+    /// offloaded.
+    pub fn get_max_segment_size(&self) -> usize {
+        let mss: usize = self.get_mss();
+        self.tcp_config
+            .get_gso_max_size()
+            .map_or(mss, |gso_max_size| gso_max_size.max(mss))
     }
 
     pub fn get_send_window(&self) -> SharedAsyncValue<u32> {
@@ -386,8 +806,37 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
         self.rto_calculator.rto()
     }
 
+    /// Gets the time at which the connection is aborted unless our peer acknowledges the oldest data in flight, if the
+    /// socket has a user timeout.
+    pub fn get_user_timeout_deadline(&self) -> Option<Instant> {
+        let user_timeout: Duration = self.options.user_timeout()?;
+        self.sender.oldest_unacked_tx().map(|first_tx| first_tx + user_timeout)
+    }
+
+    /// Aborts the connection if the oldest data in flight stayed unacknowledged for longer than the user timeout, so
+    /// that pending operations fail with ETIMEDOUT.
+    pub fn check_user_timeout(&mut self) -> Result<(), Fail> {
+        match self.get_user_timeout_deadline() {
+            Some(deadline) if deadline <= self.get_now() => {
+                let bytes_in_flight: u64 = self.sender.unacked_bytes() as u64;
+                self.record_event(TcpEventKind::UserTimeout, bytes_in_flight);
+                let cause: String = format!(
+                    "sent data was not acknowledged in time (local={:?}, remote={:?})",
+                    self.local, self.remote
+                );
+                warn!("check_user_timeout(): {}", cause);
+                self.timed_out = true;
+                self.abort();
+                Err(Fail::new(libc::ETIMEDOUT, &cause))
+            },
+            _ => Ok(()),
+        }
+    }
+
     pub fn rto_back_off(&mut self) {
-        self.rto_calculator.back_off()
+        self.rto_calculator.back_off();
+        let rto_us: u64 = self.rto_calculator.rto().as_micros() as u64;
+        self.record_event(TcpEventKind::RtoBackoff, rto_us);
     }
 
     pub fn unsent_top_size(&self) -> Option<usize> {
@@ -442,10 +891,18 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
                 header
             );
 
-            match self.process_packet(header, data) {
+            let now: Instant = self.runtime.get_now();
+            self.touch_flow(now);
+            let len: usize = data.len();
+            let result: Result<(), Fail> = self.process_packet(header, data);
+            self.update_memory_usage();
+            match result {
                 Ok(()) => (),
                 Err(e) if e.errno == libc::ECONNRESET => {
-                    self.state = State::CloseWait;
+                    // A reset closes the connection right away, while a FIN still lets us send until we close.
+                    if self.state != State::Closed {
+                        self.set_state(State::CloseWait);
+                    }
                     let cause: String = format!(
                         "remote closed connection, stopping processing (local={:?}, remote={:?})",
                         self.local, self.remote
@@ -453,15 +910,63 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
                     error!("poll(): {}", cause);
                     return Err(Fail::new(libc::ECANCELED, &cause));
                 },
-                Err(e) => debug!("Dropped packet: {:?}", e),
+                Err(e) if e.errno == libc::ENOBUFS => {
+                    trace_event!("drop", "tcp_segment", cause = e.cause);
+                    let remote: SocketAddrV4 = self.remote;
+                    self.runtime.record_drop(DropReason::MemoryLimit);
+                    self.drops.record(DropReason::MemoryLimit, Some(remote), len);
+                },
+                Err(e) => {
+                    trace_event!("drop", "tcp_segment", cause = e.cause);
+                    let remote: SocketAddrV4 = self.remote;
+                    self.runtime.record_drop(DropReason::InvalidSegment);
+                    self.drops.record(DropReason::InvalidSegment, Some(remote), len);
+                },
             }
         }
     }
 
     /// This is the main function for processing an incoming packet during the Established state when the connection is
-    /// active. Each step in this function return Ok if there is further processing to be done and EBADMSG if the
-    /// packet should be dropped after the step.
-    fn process_packet(&mut self, mut header: TcpHeader, mut data: DemiBuffer) -> Result<(), Fail> {
+    /// active. The common case of in-order data or ACKs is handled by [process_packet_fast], and everything else by
+    /// [process_packet_slow].
+    fn process_packet(&mut self, header: TcpHeader, data: DemiBuffer) -> Result<(), Fail> {
+        if self.is_fast_path(&header, &data) {
+            self.process_packet_fast(&header, data)
+        } else {
+            self.process_packet_slow(header, data)
+        }
+    }
+
+    /// Checks whether a segment with [header] and [data] takes the fast path (header prediction): the connection is
+    /// established with nothing waiting to be reassembled, and the segment is a plain ACK without options whose data
+    /// starts at RCV.NXT and fits within the receive window.
+    #[inline]
+    fn is_fast_path(&self, header: &TcpHeader, data: &DemiBuffer) -> bool {
+        self.state == State::Established
+            && header.ack
+            && !(header.syn || header.fin || header.rst || header.urg || header.cwr || header.ece)
+            && header.num_options == 0
+            && header.seq_num == self.receiver.receive_next
+            && self.out_of_order.is_empty()
+            && data.len() <= self.get_receive_window_size() as usize
+    }
+
+    /// Processes a segment that [is_fast_path] accepted. Such a segment needs neither trimming nor reassembly, so its
+    /// data goes straight to the receive queue.
+    fn process_packet_fast(&mut self, header: &TcpHeader, data: DemiBuffer) -> Result<(), Fail> {
+        self.process_ack(header)?;
+        if !data.is_empty() {
+            self.admit_data(data.len())?;
+            self.receiver.push(data);
+            self.update_receive_rtt();
+        }
+        self.schedule_ack();
+        Ok(())
+    }
+
+    /// Processes any segment. Each step in this function return Ok if there is further processing to be done and
+    /// EBADMSG if the packet should be dropped after the step.
+    fn process_packet_slow(&mut self, mut header: TcpHeader, mut data: DemiBuffer) -> Result<(), Fail> {
         let mut seg_start: SeqNumber = header.seq_num;
 
         let mut seg_end: SeqNumber = seg_start;
@@ -482,6 +987,13 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
             self.process_data(&mut header, data, seg_start, seg_end, seg_len)?;
         }
         self.process_remote_close(&header)?;
+        self.schedule_ack();
+
+        Ok(())
+    }
+
+    /// Schedules an ACK for a segment that we accepted.
+    fn schedule_ack(&mut self) {
         // We should ACK this segment, preferably via piggybacking on a response.
         // TODO: Consider replacing the delayed ACK timer with a simple flag.
         if self.ack_deadline.get().is_none() {
@@ -493,11 +1005,9 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
         } else {
             // We already owe our peer an ACK (the timer was already running), so cancel the timer and ACK now.
             self.ack_deadline.set(None);
-            trace!("process_packet(): sending ack on deadline expiration");
+            trace!("schedule_ack(): sending ack on deadline expiration");
             self.send_ack();
         }
-
-        Ok(())
     }
 
     // Check to see if the segment is acceptable sequence-wise (i.e. contains some data that fits within the receive
@@ -630,12 +1140,20 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
     // Check the RST bit.
     fn check_rst(&mut self, header: &TcpHeader) -> Result<(), Fail> {
         if header.rst {
-            // TODO: RFC 5961 "Blind Reset Attack Using the RST Bit" prevention would have us ACK and drop if the new
-            // segment doesn't start precisely on RCV.NXT.
+            // RFC 5961 "Blind Reset Attack Using the RST Bit" prevention has us only accept a reset that starts
+            // precisely on RCV.NXT. Any other reset gets a challenge ACK, so that a legitimate peer that lost track of
+            // our state answers with a reset that we accept.
+            if header.seq_num != self.receiver.receive_next {
+                trace_event!("tcp", "challenge_ack", seq = header.seq_num, rcv_nxt = self.receiver.receive_next);
+                self.send_ack();
+                let cause: String = format!("reset does not start on RCV.NXT (seq={:?})", header.seq_num);
+                warn!("check_rst(): {}", cause);
+                return Err(Fail::new(libc::EBADMSG, &cause));
+            }
 
             // Our peer has given up.  Shut the connection down hard.
             info!("Received RST");
-            // TODO: Schedule a close coroutine.
+            self.set_reset();
             let cause: String = format!("remote reset connection");
             info!("check_rst(): {}", cause);
             return Err(Fail::new(libc::ECONNRESET, &cause));
@@ -649,16 +1167,12 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
 
         // Check the SYN bit.
         if header.syn {
-            // TODO: RFC 5961 "Blind Reset Attack Using the SYN Bit" prevention would have us always ACK and drop here.
-
-            // Receiving a SYN here is an error.
+            // RFC 5961 "Blind Reset Attack Using the SYN Bit" prevention has us always send a challenge ACK and drop
+            // here. If our peer restarted, it answers with a reset that matches RCV.NXT.
             let cause: String = format!("Received in-window SYN on established connection.");
             error!("{}", cause);
-            // TODO: Send Reset.
-            // TODO: Return all outstanding Receive and Send requests with "reset" responses.
-            // TODO: Flush all segment queues.
-
-            // TODO: Start the close coroutine
+            trace_event!("tcp", "challenge_ack", seq = header.seq_num, rcv_nxt = self.receiver.receive_next);
+            self.send_ack();
             return Err(Fail::new(libc::EBADMSG, &cause));
         }
         Ok(())
@@ -748,6 +1262,8 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
         mut seg_end: SeqNumber,
         mut seg_len: u32,
     ) -> Result<(), Fail> {
+        self.admit_data(data.len())?;
+
         // We can only process in-order data (or FIN).  Check for out-of-order segment.
         if seg_start != self.receiver.receive_next {
             debug!("Received out-of-order segment");
@@ -783,6 +1299,20 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
         Ok(())
     }
 
+    /// Drops [len] bytes of incoming data if buffering them would exceed the memory limit of the stack, so that our peer
+    /// retransmits them later.
+    fn admit_data(&mut self, len: usize) -> Result<(), Fail> {
+        if len > 0 && !self.memory.admit(len) {
+            let cause: String = format!(
+                "memory limit reached (local={:?}, remote={:?})",
+                self.local, self.remote
+            );
+            warn!("admit_data(): {}", cause);
+            return Err(Fail::new(libc::ENOBUFS, &cause));
+        }
+        Ok(())
+    }
+
     /// Fetch a TCP header filling out various values based on our current state.
     /// TODO: Fix the "filling out various values based on our current state" part to actually do that correctly.
     pub fn tcp_header(&self) -> TcpHeader {
@@ -807,7 +1337,7 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
 
         // TODO: Remove this if clause once emit() is fixed to not require the remote hardware addr (this should be
         // left to the ARP layer and not exposed to TCP).
-        if let Some(remote_link_addr) = self.arp().try_query(self.remote.ip().clone()) {
+        if let Some(remote_link_addr) = self.try_next_hop() {
             self.emit(header, None, remote_link_addr);
         }
     }
@@ -828,18 +1358,22 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
 
         let sent_fin: bool = header.fin;
 
-        // Prepare description of TCP segment to send.
-        // TODO: Change this to call lower levels to fill in their header information, handle routing, ARPing, etc.
-        let segment = TcpSegment {
-            ethernet2_hdr: Ethernet2Header::new(remote_link_addr, self.local_link_addr, EtherType2::Ipv4),
-            ipv4_hdr: Ipv4Header::new(self.local.ip().clone(), self.remote.ip().clone(), IpProtocol::TCP),
-            tcp_hdr: header,
-            data: body,
-            tx_checksum_offload: self.tcp_config.get_tx_checksum_offload(),
-        };
-
-        // Call the runtime to send the segment.
-        self.transport.transmit(Box::new(segment));
+        // Segments larger than the MSS are split by the runtime if it supports segmentation offload, and in software
+        // otherwise. Signed segments are always split in software, as each of them needs a signature of its own.
+        let mss: usize = self.get_mss();
+        match body {
+            Some(body) if body.len() > mss => {
+                if self.tcp_config.get_tso()
+                    && self.signing.is_none()
+                    && body.len() <= self.tcp_config.get_gso_max_size().unwrap_or(0)
+                {
+                    self.transmit_segment(header, Some(body), Some(mss), remote_link_addr);
+                } else {
+                    self.transmit_segments(header, body, mss, remote_link_addr);
+                }
+            },
+            body => self.transmit_segment(header, body, None, remote_link_addr),
+        }
 
         // Post-send operations follow.
         // Review: We perform these after the send, in order to keep send latency as low as possible.
@@ -851,9 +1385,9 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
         if sent_fin {
             match self.state {
                 // Active close.
-                State::Established => self.state = State::FinWait1,
+                State::Established => self.set_state(State::FinWait1),
                 // Passive close.
-                State::CloseWait => self.state = State::LastAck,
+                State::CloseWait => self.set_state(State::LastAck),
                 // We can legitimately retransmit the FIN in these states.  And we stay there until the FIN is ACK'd.
                 State::FinWait1 | State::LastAck => {},
                 // We shouldn't be sending a FIN from any other state.
@@ -862,6 +1396,81 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
         }
     }
 
+    /// Hands a single segment to the runtime. If [tso_segment_size] is set, the runtime splits the segment into
+    /// segments of that size.
+    fn transmit_segment(
+        &mut self,
+        header: TcpHeader,
+        body: Option<DemiBuffer>,
+        tso_segment_size: Option<usize>,
+        remote_link_addr: MacAddress,
+    ) {
+        let segment: TcpSegment = self.build_segment(header, body, tso_segment_size, remote_link_addr);
+        self.shaper.consume(segment.header_size() + segment.body_size());
+
+        // Call the runtime to send the segment.
+        self.transport.transmit_one(Box::new(segment));
+    }
+
+    /// Prepares the description of a TCP segment to send.
+    fn build_segment(
+        &mut self,
+        mut header: TcpHeader,
+        body: Option<DemiBuffer>,
+        tso_segment_size: Option<usize>,
+        remote_link_addr: MacAddress,
+    ) -> TcpSegment {
+        let signer: Option<TcpSigner> = self.signing.as_mut().map(|signing| signing.sign(&mut header));
+        // TODO: Change this to call lower levels to fill in their header information, handle routing, ARPing, etc.
+        TcpSegment {
+            ethernet2_hdr: Ethernet2Header::new(remote_link_addr, self.local_link_addr, EtherType2::Ipv4)
+                .with_priority(self.options.priority()),
+            ipv4_hdr: Ipv4Header::new(self.local.ip().clone(), self.remote.ip().clone(), IpProtocol::TCP)
+                .with_dscp(self.options.dscp())
+                .with_ttl(self.options.ttl()),
+            tcp_hdr: header,
+            data: body,
+            tx_checksum_offload: self.tcp_config.get_tx_checksum_offload(),
+            tso_segment_size,
+            signer,
+        }
+    }
+
+    /// Splits [body] into segments of at most [mss] bytes and hands them to the runtime in batches. Each segment gets
+    /// a copy of [header] with its own sequence number, and only the last one keeps the PSH and FIN flags.
+    fn transmit_segments(&mut self, header: TcpHeader, body: DemiBuffer, mss: usize, remote_link_addr: MacAddress) {
+        let len: usize = body.len();
+        let mut offset: usize = 0;
+        let mut batch: PacketBatch = PacketBatch::new();
+        while offset < len {
+            let size: usize = mss.min(len - offset);
+            let mut data: DemiBuffer = body.clone();
+            expect_ok!(data.adjust(offset), "'data' should contain at least 'offset' bytes");
+            expect_ok!(
+                data.trim(len - offset - size),
+                "'data' should contain at least 'size' bytes past 'offset'"
+            );
+
+            let mut segment_header: TcpHeader = header.clone();
+            segment_header.seq_num = header.seq_num + SeqNumber::from(offset as u32);
+            if offset + size < len {
+                segment_header.psh = false;
+                segment_header.fin = false;
+            }
+            let segment: TcpSegment = self.build_segment(segment_header, Some(data), None, remote_link_addr);
+            self.shaper.consume(segment.header_size() + segment.body_size());
+            batch.push(Box::new(segment));
+            if batch.is_full() {
+                self.transport.transmit(mem::take(&mut batch));
+            }
+
+            offset += size;
+        }
+        if !batch.is_empty() {
+            self.transport.transmit(batch);
+        }
+    }
+
     pub fn remote_mss(&self) -> usize {
         self.sender.remote_mss()
     }
@@ -879,6 +1488,64 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
         self.receive_buffer_size - bytes_unread
     }
 
+    /// Takes a sample of our round-trip time once the peer sent all the data that our window allowed when the
+    /// measurement started.
+    fn update_receive_rtt(&mut self) {
+        let now: Instant = self.get_now();
+        match self.receive_rtt_measurement {
+            Some((end, start)) if self.receiver.receive_next >= end => {
+                let sample: Duration = now - start;
+                // Keep the smallest recent sample, since a sender that is not limited by our window sends a full
+                // window faster than one that is.
+                self.receive_rtt = Some(match self.receive_rtt {
+                    Some(rtt) if rtt < sample => (rtt * 7 + sample) / 8,
+                    _ => sample,
+                });
+                self.receive_rtt_measurement = None;
+            },
+            Some(_) => (),
+            None => {
+                let end: SeqNumber = self.receiver.receive_next + SeqNumber::from(self.get_receive_window_size());
+                self.receive_rtt_measurement = Some((end, now));
+            },
+        }
+    }
+
+    /// Grows the receive buffer to twice the bytes that the application popped in the last round-trip time, up to the
+    /// limit of the stack and the largest window that we can advertise. The receive buffer never shrinks, because
+    /// that would shrink a window that we already advertised.
+    fn adjust_receive_buffer(&mut self) {
+        if !self.tcp_config.get_receive_buffer_autotuning() {
+            return;
+        }
+        let rtt: Duration = match self.receive_rtt {
+            Some(rtt) => rtt,
+            None => return,
+        };
+        let now: Instant = self.get_now();
+        let (start_seq_no, start_time): (SeqNumber, Instant) = self.receive_space_start;
+        if now - start_time < rtt {
+            return;
+        }
+
+        let popped: u32 = (self.receiver.reader_next - start_seq_no).into();
+        let max_size: u32 = (self.tcp_config.get_receive_buffer_max_size() as u64)
+            .min((u16::MAX as u64) << self.window_scale)
+            .min(u32::MAX as u64) as u32;
+        let size: u32 = popped.saturating_mul(2).min(max_size);
+        if size > self.receive_buffer_size {
+            trace_event!(
+                "tcp",
+                "receive_buffer_grow",
+                from = self.receive_buffer_size,
+                to = size,
+                rtt = rtt
+            );
+            self.receive_buffer_size = size;
+        }
+        self.receive_space_start = (self.receiver.reader_next, now);
+    }
+
     fn hdr_window_size(&self) -> u16 {
         let window_size: u32 = self.get_receive_window_size();
         let hdr_window_size: u16 = expect_ok!((window_size >> self.window_scale).try_into(), "Window size overflow");
@@ -892,8 +1559,13 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
     }
 
     pub async fn push(&mut self, mut nbytes: usize) -> Result<(), Fail> {
+        let mut reset: SharedAsyncValue<bool> = self.reset.clone();
         loop {
-            let n: usize = self.ack_queue.pop(None).await?;
+            self.check_not_reset()?;
+            let n: usize = futures::select_biased! {
+                n = pin!(self.ack_queue.pop(None).fuse()) => n?,
+                _ = pin!(reset.wait_for_change_until(None).fuse()) => continue,
+            };
 
             if n > nbytes {
                 self.ack_queue.push_front(n - nbytes);
@@ -916,7 +1588,164 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
         //  if self.receiver.reader_next.get() == self.receiver.receive_next.get() {
         // But that will think data is available to be read once we've received a FIN, because FINs consume sequence
         // number space.  Now we call is_empty() on the receive queue instead.
-        self.receiver.pop(size).await
+        let mut reset: SharedAsyncValue<bool> = self.reset.clone();
+        let buf: DemiBuffer = loop {
+            self.check_not_reset()?;
+            futures::select_biased! {
+                buf = pin!(self.receiver.pop(size).fuse()) => break buf?,
+                _ = pin!(reset.wait_for_change_until(None).fuse()) => continue,
+            }
+        };
+        self.update_memory_usage();
+        self.adjust_receive_buffer();
+        Ok(buf)
+    }
+
+    /// Fails with ECONNRESET if the connection was reset, or with ETIMEDOUT if it was aborted by the user timeout.
+    fn check_not_reset(&self) -> Result<(), Fail> {
+        if self.timed_out {
+            let cause: String = format!(
+                "connection timed out (local={:?}, remote={:?})",
+                self.local, self.remote
+            );
+            warn!("check_not_reset(): {}", cause);
+            return Err(Fail::new(libc::ETIMEDOUT, &cause));
+        }
+        if self.reset.get() {
+            let cause: String = format!("connection reset (local={:?}, remote={:?})", self.local, self.remote);
+            warn!("check_not_reset(): {}", cause);
+            return Err(Fail::new(libc::ECONNRESET, &cause));
+        }
+        Ok(())
+    }
+
+    /// Moves the connection to the CLOSED state after a reset, waking up all pending operations.
+    fn set_reset(&mut self) {
+        self.set_state(State::Closed);
+        self.reset.set(true);
+        // Nothing can be sent nor received anymore, so give back the memory of the connection.
+        self.out_of_order.clear();
+        while self.receiver.recv_queue.try_pop().is_some() {}
+        self.receiver.recv_queue_bytes = 0;
+        self.update_memory_usage();
+        // The connection no longer takes up a flow.
+        self.flow = None;
+    }
+
+    /// Gets whether the connection was reset, so that the background coroutines can stop.
+    pub fn watch_reset(&self) -> SharedAsyncValue<bool> {
+        self.reset.clone()
+    }
+
+    /// Gets whether the memory pool of the stack asked to abort the connection, to reclaim its buffers.
+    pub fn watch_memory_abort(&self) -> SharedAsyncValue<bool> {
+        self.memory.watch_abort()
+    }
+
+    /// Gets whether the flow table of the stack evicted the connection, to make room for another flow.
+    pub fn watch_flow_evict(&self) -> SharedAsyncValue<bool> {
+        match self.flow.as_ref() {
+            Some(flow) => flow.watch_evict(),
+            None => SharedAsyncValue::new(false),
+        }
+    }
+
+    /// Marks the flow of the connection as used at [now], so that it is not the least recently used one.
+    fn touch_flow(&mut self, now: Instant) {
+        if let Some(flow) = self.flow.as_mut() {
+            flow.touch(now);
+        }
+    }
+
+    /// Tells the memory pool of the stack how many bytes the connection buffers. Once reset, the send buffer is no
+    /// longer retransmitted and does not count.
+    fn update_memory_usage(&mut self) {
+        let send_buffer: usize = match self.reset.get() {
+            true => 0,
+            false => self.sender.buffered_bytes(),
+        };
+        let recv_queue: usize = self.receiver.recv_queue_bytes;
+        let reassembly: usize = self.out_of_order.iter().map(|(_, buf)| buf.len()).sum();
+        self.memory.set(MemoryKind::SendBuffer, send_buffer);
+        self.memory.set(MemoryKind::RecvQueue, recv_queue);
+        self.memory.set(MemoryKind::Reassembly, reassembly);
+    }
+
+    /// Aborts the connection, sending a RST to our peer and discarding all data that was not sent or received yet.
+    /// See: https://datatracker.ietf.org/doc/html/rfc9293#section-3.10.4
+    pub fn abort(&mut self) {
+        if self.state == State::Closed {
+            return;
+        }
+        let mut header: TcpHeader = self.tcp_header();
+        header.seq_num = self.get_send_next().get();
+        header.rst = true;
+        if let Some(remote_link_addr) = self.try_next_hop() {
+            trace_event!("tcp", "abort", seq = header.seq_num);
+            self.emit(header, None, remote_link_addr);
+        }
+        self.set_reset();
+    }
+
+    /// Freezes the connection, so that it can be thawed in another instance of the stack. The connection then stops
+    /// here without letting our peer know, as it carries on elsewhere. Only connections that we did not start closing
+    /// can be frozen.
+    pub fn freeze(&mut self) -> Result<FrozenConnection, Fail> {
+        self.check_not_reset()?;
+        let remote_closed: bool = match self.state {
+            State::Established => false,
+            State::CloseWait => true,
+            state => {
+                let cause: String = format!(
+                    "cannot freeze a connection that is closing (local={:?}, remote={:?}, state={:?})",
+                    self.local, self.remote, state
+                );
+                error!("freeze(): {}", cause);
+                return Err(Fail::new(libc::EBUSY, &cause));
+            },
+        };
+        let mut received: Vec<u8> = Vec::with_capacity(self.receiver.recv_queue_bytes);
+        for buf in self.receiver.recv_queue.get_values() {
+            received.extend_from_slice(&buf[..]);
+        }
+        let frozen: FrozenConnection = FrozenConnection {
+            local: self.local,
+            remote: self.remote,
+            remote_closed,
+            reader_next: self.receiver.reader_next,
+            receive_buffer_size: self.receive_buffer_size,
+            receive_window_scale: self.window_scale as u8,
+            received,
+            send_unacked: self.sender.get_send_unacked().get(),
+            send_window: self.sender.get_send_window().get(),
+            send_window_scale: self.sender.get_window_scale(),
+            mss: self.sender.get_mss() as u32,
+            unacked: self.sender.copy_buffered_data(),
+        };
+        trace_event!("tcp", "freeze", local = self.local, remote = self.remote);
+        // Stop the background coroutines and give back the memory of the connection, but do not send a RST.
+        self.set_reset();
+        Ok(frozen)
+    }
+
+    /// Restores the data that a frozen connection buffered. Data that our peer did not acknowledge yet is pushed
+    /// again, so that it is sent anew.
+    pub fn thaw(&mut self, frozen: &FrozenConnection) -> Result<(), Fail> {
+        for chunk in frozen.received.chunks(limits::POP_SIZE_MAX) {
+            self.receiver.push(DemiBuffer::from_slice(chunk)?);
+        }
+        if frozen.remote_closed {
+            // Push empty buffer to indicate EOF, and advance RCV.NXT over the FIN.
+            self.receiver.push(DemiBuffer::new(0));
+            self.receiver.receive_next = self.receiver.receive_next + SeqNumber::from(1);
+            self.set_state(State::CloseWait);
+        }
+        self.update_memory_usage();
+        for chunk in frozen.unacked.chunks(limits::POP_SIZE_MAX) {
+            self.send(DemiBuffer::from_slice(chunk)?)?;
+        }
+        trace_event!("tcp", "thaw", local = self.local, remote = self.remote);
+        Ok(())
     }
 
     // This routine remembers that we have received an out-of-order FIN.
@@ -1046,6 +1875,7 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
         let mut recv_next: SeqNumber = recv_next + SeqNumber::from(buf.len() as u32);
         // This inserts the segment and wakes a waiting pop coroutine.
         self.receiver.push(buf);
+        self.update_receive_rtt();
 
         // Okay, we've successfully received some new data.  Check if any of the formerly out-of-order data waiting in
         // the out-of-order queue is now in-order.  If so, we can move it to the receive queue.
@@ -1124,12 +1954,22 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
         }
     }
 
+    /// Moves the connection to [state].
+    fn set_state(&mut self, state: State) {
+        if self.state != state {
+            trace_event!("tcp", "state", from = self.state, to = state);
+            self.state = state;
+        }
+    }
+
     // This coroutine runs the close protocol.
     pub async fn close(&mut self) -> Result<(), Fail> {
         // Assert we are in a valid state and move to new state.
         match self.state {
             State::Established => self.local_close().await,
             State::CloseWait => self.remote_already_closed().await,
+            // The connection was reset, so there is nothing left to close.
+            State::Closed if self.reset.get() => Ok(()),
             _ => {
                 let cause: String = format!("socket is already closing");
                 error!("close(): {}", cause);
@@ -1140,7 +1980,7 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
 
     async fn local_close(&mut self) -> Result<(), Fail> {
         // 0. Set state.
-        self.state = State::FinWait1;
+        self.set_state(State::FinWait1);
         // 1. Send FIN.
         self.send_fin();
 
@@ -1148,8 +1988,16 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
             // Wait for next packet.
             let (_, header, _) = self.recv_queue.pop(None).await?;
 
+            // An acceptable reset ends the close protocol right away, while any other reset is dropped.
+            if header.rst {
+                match self.check_rst(&header) {
+                    Err(e) if e.errno == libc::ECONNRESET => return Ok(()),
+                    _ => continue,
+                }
+            }
+
             // Check ACK.
-            self.state = match self.process_ack(&header) {
+            let state: State = match self.process_ack(&header) {
                 // Got ACK to our FIN.
                 Ok(()) => match self.state {
                     State::FinWait1 => State::FinWait2,
@@ -1160,11 +2008,12 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
                 // Don't do anything if this is an unexpected message.
                 Err(_) => self.state,
             };
+            self.set_state(state);
 
             // TODO: Receive data in the FINWAIT-1 and FINWAIT-2 states.
 
             // Check FIN.
-            self.state = match self.process_remote_close(&header) {
+            let state: State = match self.process_remote_close(&header) {
                 // No FIN, keep waiting.
                 Ok(()) => self.state,
                 // Found FIN, move to next state.
@@ -1175,7 +2024,8 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
                 },
                 // Some other error, stop close protocol.
                 Err(e) => return Err(e),
-            }
+            };
+            self.set_state(state);
         }
 
         // TODO: Get 2MSL value or linger option if set.
@@ -1186,13 +2036,13 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
         //     .wait(Duration::from_secs(1), &timeout_yielder)
         //     .await?;
 
-        self.state = State::Closed;
+        self.set_state(State::Closed);
         Ok(())
     }
 
     async fn remote_already_closed(&mut self) -> Result<(), Fail> {
         // 0. Set state.
-        self.state = State::LastAck;
+        self.set_state(State::LastAck);
         // 1. Send FIN.
         self.send_fin();
         // Wait for ACK of FIN.
@@ -1200,6 +2050,14 @@ impl<N: NetworkRuntime> SharedControlBlock<N> {
             // Wait for next packet.
             let (_, header, _) = self.recv_queue.pop(None).await?;
 
+            // An acceptable reset ends the close protocol right away, while any other reset is dropped.
+            if header.rst {
+                match self.check_rst(&header) {
+                    Err(e) if e.errno == libc::ECONNRESET => return Ok(()),
+                    _ => continue,
+                }
+            }
+
             // Check ACK.
             match self.process_ack(&header) {
                 Ok(()) => break,
@@ -1227,3 +2085,262 @@ impl<N: NetworkRuntime> DerefMut for SharedControlBlock<N> {
         self.0.deref_mut()
     }
 }
+
+impl<N: NetworkRuntime> Drop for SendBufferTurn<N> {
+    fn drop(&mut self) {
+        let ticket: u64 = self.ticket;
+        self.cb.send_waiters.retain(|waiter| *waiter != ticket);
+        self.cb.send_waiters_changed.modify(|changes| changes.wrapping_add(1));
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        collections::async_queue::SharedAsyncQueue,
+        inetstack::{
+            protocols::{
+                arp::SharedArpPeer,
+                tcp::{
+                    established::{
+                        congestion_control::{
+                            self,
+                            CongestionControl,
+                        },
+                        ctrlblk::SharedControlBlock,
+                    },
+                    segment::TcpHeader,
+                    SeqNumber,
+                },
+            },
+            test_helpers::{
+                self,
+                SharedTestRuntime,
+            },
+        },
+        runtime::{
+            memory::DemiBuffer,
+            network::{
+                config::{
+                    ArpConfig,
+                    TcpConfig,
+                    UdpConfig,
+                },
+                flows::{
+                    FlowEntry,
+                    FlowKey,
+                    FlowProtocol,
+                    SharedFlowTable,
+                },
+                socket::option::SocketOptions,
+                types::MacAddress,
+            },
+        },
+    };
+    use ::anyhow::Result;
+    use ::std::{
+        collections::HashMap,
+        net::{
+            Ipv4Addr,
+            SocketAddrV4,
+        },
+        time::{
+            Duration,
+            Instant,
+        },
+    };
+
+    /// Initial sequence numbers of Alice, who owns the control block, and of Bob, who sends the segments.
+    const ALICE_ISN: u32 = 1000;
+    const BOB_ISN: u32 = 5000;
+
+    const ALICE_PORT: u16 = 49152;
+    const BOB_PORT: u16 = 80;
+
+    /// Creates the control block of an established connection from Alice to Bob, on a runtime of its own.
+    fn new_cb(now: Instant) -> Result<(SharedTestRuntime, SharedControlBlock<SharedTestRuntime>)> {
+        let mut arp: HashMap<Ipv4Addr, MacAddress> = HashMap::<Ipv4Addr, MacAddress>::new();
+        arp.insert(test_helpers::BOB_IPV4, test_helpers::BOB_MAC);
+        let arp_config: ArpConfig = ArpConfig::new(
+            Some(Duration::from_secs(600)),
+            Some(Duration::from_secs(1)),
+            Some(2),
+            Some(arp),
+            Some(false),
+        );
+        let tcp_config: TcpConfig = TcpConfig::default();
+        let transport: SharedTestRuntime = SharedTestRuntime::new(
+            now,
+            arp_config.clone(),
+            UdpConfig::default(),
+            tcp_config.clone(),
+            test_helpers::ALICE_MAC,
+            test_helpers::ALICE_IPV4,
+        );
+        let local: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, ALICE_PORT);
+        let remote: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, BOB_PORT);
+        let arp: SharedArpPeer<SharedTestRuntime> = SharedArpPeer::new(
+            transport.get_runtime(),
+            transport.clone(),
+            test_helpers::ALICE_MAC,
+            test_helpers::ALICE_IPV4,
+            arp_config,
+        )?;
+        let flow: FlowEntry = SharedFlowTable::default().open(now, FlowKey::new(FlowProtocol::Tcp, local, remote))?;
+        let cb: SharedControlBlock<SharedTestRuntime> = SharedControlBlock::new(
+            local,
+            remote,
+            transport.get_runtime(),
+            transport.clone(),
+            test_helpers::ALICE_MAC,
+            tcp_config.clone(),
+            SocketOptions::default(),
+            None,
+            arp,
+            SeqNumber::from(BOB_ISN),
+            tcp_config.get_ack_delay_timeout(),
+            65535,
+            0,
+            SeqNumber::from(ALICE_ISN),
+            65535,
+            0,
+            1450,
+            congestion_control::None::new,
+            None,
+            SharedAsyncQueue::default(),
+            SharedAsyncQueue::default(),
+            flow,
+        );
+        Ok((transport, cb))
+    }
+
+    /// Builds a segment from Bob that acknowledges the first [acked] bytes that Alice sent, advertises [window] and
+    /// carries [len] bytes starting [offset] bytes after the first byte that Bob sends.
+    fn segment(offset: u32, len: usize, acked: u32, window: u16) -> Result<(TcpHeader, DemiBuffer)> {
+        let mut header: TcpHeader = TcpHeader::new(BOB_PORT, ALICE_PORT);
+        header.seq_num = SeqNumber::from(BOB_ISN + offset);
+        header.ack = true;
+        header.ack_num = SeqNumber::from(ALICE_ISN + acked);
+        header.window_size = window;
+        let data: DemiBuffer = match len {
+            0 => DemiBuffer::new(0),
+            _ => DemiBuffer::from_slice(&(0..len).map(|i| (offset as usize + i) as u8).collect::<Vec<u8>>()[..])?,
+        };
+        Ok((header, data))
+    }
+
+    /// Runs a segment through the fast path of [fast] where it qualifies, and always through the slow path of [slow].
+    /// Returns whether the segment qualified for the fast path.
+    fn process(
+        fast: &mut SharedControlBlock<SharedTestRuntime>,
+        slow: &mut SharedControlBlock<SharedTestRuntime>,
+        header: TcpHeader,
+        data: DemiBuffer,
+    ) -> Result<bool> {
+        let is_fast_path: bool = fast.is_fast_path(&header, &data);
+        let fast_result: Option<i32> = fast.process_packet(header.clone(), data.clone()).err().map(|e| e.errno);
+        let slow_result: Option<i32> = slow.process_packet_slow(header, data).err().map(|e| e.errno);
+        crate::ensure_eq!(fast_result, slow_result);
+        Ok(is_fast_path)
+    }
+
+    /// Checks that both control blocks delivered the same data, hold the same out-of-order data, owe the same ACK, see
+    /// the same send window and sent the same frames. Returns the number of frames that each one sent.
+    fn ensure_same_state(
+        fast: &mut (SharedTestRuntime, SharedControlBlock<SharedTestRuntime>),
+        slow: &mut (SharedTestRuntime, SharedControlBlock<SharedTestRuntime>),
+    ) -> Result<usize> {
+        let received = |cb: &SharedControlBlock<SharedTestRuntime>| -> Vec<u8> {
+            cb.receiver
+                .recv_queue
+                .get_values()
+                .flat_map(|buf| buf[..].to_vec())
+                .collect()
+        };
+        let out_of_order = |cb: &SharedControlBlock<SharedTestRuntime>| -> Vec<(SeqNumber, Vec<u8>)> {
+            cb.out_of_order
+                .iter()
+                .map(|(seq_no, buf)| (*seq_no, buf[..].to_vec()))
+                .collect()
+        };
+        crate::ensure_eq!(fast.1.receiver.receive_next, slow.1.receiver.receive_next);
+        crate::ensure_eq!(fast.1.receiver.recv_queue_bytes, slow.1.receiver.recv_queue_bytes);
+        crate::ensure_eq!(received(&fast.1), received(&slow.1));
+        crate::ensure_eq!(out_of_order(&fast.1), out_of_order(&slow.1));
+        crate::ensure_eq!(fast.1.out_of_order_fin, slow.1.out_of_order_fin);
+        crate::ensure_eq!(fast.1.state, slow.1.state);
+        crate::ensure_eq!(fast.1.ack_deadline.get(), slow.1.ack_deadline.get());
+        crate::ensure_eq!(fast.1.receive_rtt_measurement, slow.1.receive_rtt_measurement);
+        crate::ensure_eq!(
+            fast.1.sender.get_send_window().get(),
+            slow.1.sender.get_send_window().get()
+        );
+        crate::ensure_eq!(
+            fast.1.sender.get_send_unacked().get(),
+            slow.1.sender.get_send_unacked().get()
+        );
+        let fast_frames: Vec<Vec<u8>> = fast.0.pop_all_frames().iter().map(|frame| frame[..].to_vec()).collect();
+        let slow_frames: Vec<Vec<u8>> = slow.0.pop_all_frames().iter().map(|frame| frame[..].to_vec()).collect();
+        crate::ensure_eq!(fast_frames, slow_frames);
+        Ok(fast_frames.len())
+    }
+
+    /// Tests that segments that take the fast path leave the control block in the same state as the slow path does, and
+    /// that out-of-order and flagged segments fall back to the slow path.
+    #[test]
+    fn tcp_fast_path_matches_slow_path() -> Result<()> {
+        let now: Instant = Instant::now();
+        let mut fast: (SharedTestRuntime, SharedControlBlock<SharedTestRuntime>) = new_cb(now)?;
+        let mut slow: (SharedTestRuntime, SharedControlBlock<SharedTestRuntime>) = new_cb(now)?;
+
+        // Alice has data in flight.
+        for cb in [&mut fast, &mut slow] {
+            cb.1.send(DemiBuffer::from_slice(&[0x5a; 100][..])?)?;
+        }
+        crate::ensure_eq!(ensure_same_state(&mut fast, &mut slow)?, 1);
+
+        // In-order data starts the delayed ACK timer, and the next segment is acknowledged right away.
+        let (header, data) = segment(0, 100, 0, 65535)?;
+        crate::ensure_eq!(process(&mut fast.1, &mut slow.1, header, data)?, true);
+        crate::ensure_eq!(ensure_same_state(&mut fast, &mut slow)?, 0);
+        crate::ensure_eq!(fast.1.ack_deadline.get().is_some(), true);
+        let (header, data) = segment(100, 100, 0, 65535)?;
+        crate::ensure_eq!(process(&mut fast.1, &mut slow.1, header, data)?, true);
+        crate::ensure_eq!(ensure_same_state(&mut fast, &mut slow)?, 1);
+        crate::ensure_eq!(fast.1.ack_deadline.get(), None);
+
+        // A plain ACK of the data in flight updates the send window.
+        let (header, data) = segment(200, 0, 100, 4096)?;
+        crate::ensure_eq!(process(&mut fast.1, &mut slow.1, header, data)?, true);
+        ensure_same_state(&mut fast, &mut slow)?;
+        crate::ensure_eq!(fast.1.sender.get_send_window().get(), 4096);
+
+        // Data past RCV.NXT waits to be reassembled, and so does the data after the hole is filled.
+        let (header, data) = segment(300, 100, 100, 4096)?;
+        crate::ensure_eq!(process(&mut fast.1, &mut slow.1, header, data)?, false);
+        ensure_same_state(&mut fast, &mut slow)?;
+        crate::ensure_eq!(fast.1.out_of_order.len(), 1);
+        let (header, data) = segment(200, 100, 100, 4096)?;
+        crate::ensure_eq!(process(&mut fast.1, &mut slow.1, header, data)?, false);
+        ensure_same_state(&mut fast, &mut slow)?;
+        crate::ensure_eq!(fast.1.out_of_order.len(), 0);
+        crate::ensure_eq!(fast.1.receiver.receive_next, SeqNumber::from(BOB_ISN + 400));
+
+        // Segments with flags other than ACK and PSH take the slow path.
+        let (mut header, data) = segment(400, 100, 100, 4096)?;
+        header.psh = true;
+        crate::ensure_eq!(process(&mut fast.1, &mut slow.1, header, data)?, true);
+        ensure_same_state(&mut fast, &mut slow)?;
+        let (mut header, data) = segment(500, 0, 100, 4096)?;
+        header.fin = true;
+        crate::ensure_eq!(process(&mut fast.1, &mut slow.1, header, data)?, false);
+        ensure_same_state(&mut fast, &mut slow)?;
+        crate::ensure_eq!(fast.1.receiver.receive_next, SeqNumber::from(BOB_ISN + 501));
+
+        Ok(())
+    }
+}
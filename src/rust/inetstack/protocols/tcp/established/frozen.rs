@@ -0,0 +1,239 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::protocols::tcp::SeqNumber,
+    runtime::fail::Fail,
+};
+use ::std::net::{
+    Ipv4Addr,
+    SocketAddrV4,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Tag that starts the blob of a frozen connection.
+const FROZEN_TAG: &[u8] = b"demikernel:tcp:frozen";
+
+/// Version of the layout of the blob, which is bumped whenever the layout changes.
+const FROZEN_VERSION: u8 = 1;
+
+/// Flag that tells that our peer had closed its side of the connection.
+const FLAG_REMOTE_CLOSED: u8 = 0x1;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// State of a TCP connection that was frozen in one instance of the stack, so that it can be thawed in another.
+/// Segments that were sent but not acknowledged yet are kept along with those that were not sent at all, and are all
+/// sent again once the connection is thawed. Out-of-order segments are left for our peer to retransmit.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FrozenConnection {
+    pub local: SocketAddrV4,
+    pub remote: SocketAddrV4,
+    /// Whether our peer had closed its side of the connection, that is, whether we had received its FIN.
+    pub remote_closed: bool,
+    /// Sequence number of the first byte of data that was received but not popped yet.
+    pub reader_next: SeqNumber,
+    /// Size of our receive buffer, and its window scale factor.
+    pub receive_buffer_size: u32,
+    pub receive_window_scale: u8,
+    /// Data that was received but not popped yet.
+    pub received: Vec<u8>,
+    /// Sequence number of the oldest byte of data that was not acknowledged yet.
+    pub send_unacked: SeqNumber,
+    /// Window that our peer last advertised, and its scale factor.
+    pub send_window: u32,
+    pub send_window_scale: u8,
+    /// Maximum segment size of our peer.
+    pub mss: u32,
+    /// Data that was pushed but not acknowledged yet, whether it was sent or not.
+    pub unacked: Vec<u8>,
+}
+
+/// Reads the fields of a blob in order.
+struct Reader<'a> {
+    blob: &'a [u8],
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl FrozenConnection {
+    /// Lays this connection out as a blob that does not depend on the instance of the stack, nor on the host.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut blob: Vec<u8> = Vec::with_capacity(64 + self.received.len() + self.unacked.len());
+        blob.extend_from_slice(FROZEN_TAG);
+        blob.push(FROZEN_VERSION);
+        for addr in [self.local, self.remote] {
+            blob.extend_from_slice(&addr.ip().octets());
+            blob.extend_from_slice(&addr.port().to_be_bytes());
+        }
+        blob.push(if self.remote_closed { FLAG_REMOTE_CLOSED } else { 0 });
+        blob.extend_from_slice(&u32::from(self.reader_next).to_be_bytes());
+        blob.extend_from_slice(&self.receive_buffer_size.to_be_bytes());
+        blob.push(self.receive_window_scale);
+        blob.extend_from_slice(&u32::from(self.send_unacked).to_be_bytes());
+        blob.extend_from_slice(&self.send_window.to_be_bytes());
+        blob.push(self.send_window_scale);
+        blob.extend_from_slice(&self.mss.to_be_bytes());
+        for data in [&self.received, &self.unacked] {
+            blob.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            blob.extend_from_slice(data);
+        }
+        blob
+    }
+
+    /// Reads a connection back from a blob that [serialize] laid out.
+    pub fn deserialize(blob: &[u8]) -> Result<Self, Fail> {
+        if !blob.starts_with(FROZEN_TAG) {
+            let cause: &str = "blob does not hold a frozen connection";
+            error!("deserialize(): {}", cause);
+            return Err(Fail::new(libc::EBADMSG, cause));
+        }
+        let mut reader: Reader = Reader {
+            blob: &blob[FROZEN_TAG.len()..],
+        };
+        let version: u8 = reader.read_u8()?;
+        if version != FROZEN_VERSION {
+            let cause: String = format!("unsupported version of frozen connection (version={:?})", version);
+            error!("deserialize(): {}", cause);
+            return Err(Fail::new(libc::ENOTSUP, &cause));
+        }
+        let local: SocketAddrV4 = reader.read_addr()?;
+        let remote: SocketAddrV4 = reader.read_addr()?;
+        let remote_closed: bool = reader.read_u8()? & FLAG_REMOTE_CLOSED != 0;
+        let reader_next: SeqNumber = SeqNumber::from(reader.read_u32()?);
+        let receive_buffer_size: u32 = reader.read_u32()?;
+        let receive_window_scale: u8 = reader.read_u8()?;
+        let send_unacked: SeqNumber = SeqNumber::from(reader.read_u32()?);
+        let send_window: u32 = reader.read_u32()?;
+        let send_window_scale: u8 = reader.read_u8()?;
+        let mss: u32 = reader.read_u32()?;
+        let received: Vec<u8> = reader.read_data()?;
+        let unacked: Vec<u8> = reader.read_data()?;
+        if !reader.blob.is_empty() {
+            let cause: String = format!("blob has trailing bytes (len={:?})", reader.blob.len());
+            error!("deserialize(): {}", cause);
+            return Err(Fail::new(libc::EBADMSG, &cause));
+        }
+        Ok(Self {
+            local,
+            remote,
+            remote_closed,
+            reader_next,
+            receive_buffer_size,
+            receive_window_scale,
+            received,
+            send_unacked,
+            send_window,
+            send_window_scale,
+            mss,
+            unacked,
+        })
+    }
+}
+
+impl<'a> Reader<'a> {
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], Fail> {
+        if self.blob.len() < len {
+            let cause: &str = "blob of frozen connection is truncated";
+            error!("read_bytes(): {}", cause);
+            return Err(Fail::new(libc::EBADMSG, cause));
+        }
+        let (bytes, rest): (&[u8], &[u8]) = self.blob.split_at(len);
+        self.blob = rest;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Fail> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16, Fail> {
+        let bytes: &[u8] = self.read_bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Fail> {
+        let bytes: &[u8] = self.read_bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_addr(&mut self) -> Result<SocketAddrV4, Fail> {
+        let bytes: &[u8] = self.read_bytes(4)?;
+        let ip: Ipv4Addr = Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]);
+        Ok(SocketAddrV4::new(ip, self.read_u16()?))
+    }
+
+    fn read_data(&mut self) -> Result<Vec<u8>, Fail> {
+        let len: usize = self.read_u32()? as usize;
+        Ok(self.read_bytes(len)?.to_vec())
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::FrozenConnection;
+    use crate::inetstack::protocols::tcp::SeqNumber;
+    use ::anyhow::Result;
+    use ::std::net::{
+        Ipv4Addr,
+        SocketAddrV4,
+    };
+
+    fn frozen() -> FrozenConnection {
+        FrozenConnection {
+            local: SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 80),
+            remote: SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 2), 49152),
+            remote_closed: true,
+            reader_next: SeqNumber::from(0xfffffff0),
+            receive_buffer_size: 65535,
+            receive_window_scale: 7,
+            received: b"received".to_vec(),
+            send_unacked: SeqNumber::from(1000),
+            send_window: 32768,
+            send_window_scale: 2,
+            mss: 1460,
+            unacked: b"not acknowledged yet".to_vec(),
+        }
+    }
+
+    /// Tests that a frozen connection reads back from its blob.
+    #[test]
+    fn frozen_connection_roundtrip() -> Result<()> {
+        let connection: FrozenConnection = frozen();
+        match FrozenConnection::deserialize(&connection.serialize()) {
+            Ok(thawed) => crate::ensure_eq!(thawed, connection),
+            Err(e) => anyhow::bail!("deserialize() should succeed: {:?}", e),
+        }
+
+        Ok(())
+    }
+
+    /// Tests that blobs which are truncated, or which do not hold a frozen connection, are rejected.
+    #[test]
+    fn frozen_connection_rejects_bad_blob() -> Result<()> {
+        let blob: Vec<u8> = frozen().serialize();
+        for bad in [&blob[..blob.len() - 1], &blob[1..], &[]] {
+            match FrozenConnection::deserialize(bad) {
+                Err(e) if e.errno == libc::EBADMSG => (),
+                result => anyhow::bail!("deserialize() should fail with EBADMSG: {:?}", result),
+            }
+        }
+
+        Ok(())
+    }
+}
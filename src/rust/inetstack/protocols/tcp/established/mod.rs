@@ -4,6 +4,7 @@
 mod background;
 pub mod congestion_control;
 mod ctrlblk;
+pub mod frozen;
 mod rto;
 mod sender;
 
@@ -14,8 +15,15 @@ use crate::{
             ipv4::Ipv4Header,
             tcp::{
                 congestion_control::CongestionControlConstructor,
-                established::ctrlblk::SharedControlBlock,
+                established::{
+                    ctrlblk::{
+                        SendBufferTurn,
+                        SharedControlBlock,
+                    },
+                    frozen::FrozenConnection,
+                },
                 segment::TcpHeader,
+                signature::TcpSigning,
                 SeqNumber,
             },
         },
@@ -27,11 +35,32 @@ use crate::{
         memory::DemiBuffer,
         network::{
             config::TcpConfig,
+            drops::{
+                DropReason,
+                DropRecord,
+                DropStats,
+            },
+            flows::{
+                FlowEntry,
+                FlowKey,
+                FlowProtocol,
+            },
+            socket::option::{
+                SocketOptions,
+                TcpAoInfo,
+                TcpAoKey,
+                TcpMd5Key,
+            },
+            tcp_info::{
+                TcpEvent,
+                TcpInfo,
+            },
             NetworkRuntime,
         },
         QDesc,
         SharedDemiRuntime,
     },
+    trace_instrument,
     QToken,
 };
 use ::futures::{
@@ -66,6 +95,8 @@ impl<N: NetworkRuntime> EstablishedSocket<N> {
         ack_queue: SharedAsyncQueue<usize>,
         local_link_addr: MacAddress,
         tcp_config: TcpConfig,
+        options: SocketOptions,
+        signing: Option<TcpSigning>,
         arp: SharedArpPeer<N>,
         receiver_seq_no: SeqNumber,
         ack_delay_timeout: Duration,
@@ -79,6 +110,9 @@ impl<N: NetworkRuntime> EstablishedSocket<N> {
         congestion_control_options: Option<congestion_control::Options>,
         dead_socket_tx: mpsc::UnboundedSender<QDesc>,
     ) -> Result<Self, Fail> {
+        let flow: FlowEntry = runtime
+            .get_flow_table()
+            .open(runtime.get_now(), FlowKey::new(FlowProtocol::Tcp, local, remote))?;
         // TODO: Maybe add the queue descriptor here.
         let cb = SharedControlBlock::new(
             local,
@@ -87,6 +121,8 @@ impl<N: NetworkRuntime> EstablishedSocket<N> {
             transport,
             local_link_addr,
             tcp_config,
+            options,
+            signing,
             arp,
             receiver_seq_no,
             ack_delay_timeout,
@@ -100,10 +136,16 @@ impl<N: NetworkRuntime> EstablishedSocket<N> {
             congestion_control_options,
             recv_queue.clone(),
             ack_queue.clone(),
+            flow,
         );
         let qt: QToken = runtime.insert_background_coroutine(
             "Inetstack::TCP::established::background",
-            Box::pin(background::background(cb.clone(), dead_socket_tx).fuse()),
+            trace_instrument!(
+                Box::pin(background::background(cb.clone(), dead_socket_tx).fuse()),
+                "tcp",
+                local = local,
+                remote = remote
+            ),
         )?;
         Ok(Self {
             cb,
@@ -117,8 +159,18 @@ impl<N: NetworkRuntime> EstablishedSocket<N> {
         self.recv_queue.clone()
     }
 
-    pub fn send(&mut self, buf: DemiBuffer) -> Result<(), Fail> {
-        self.cb.send(buf)
+    pub async fn send(&mut self, buf: DemiBuffer) -> Result<(), Fail> {
+        // Accept the data once the send buffer has room for it, instead of once it is transmitted. Pushes take room in
+        // the order that they were issued, so that their data goes out in that order.
+        let turn: SendBufferTurn<N> = self.cb.wait_for_send_buffer(buf.len()).await?;
+        self.cb.send(buf)?;
+        drop(turn);
+        if self.cb.tx_complete() {
+            // Complete the push once our peer acknowledges everything up to its last byte.
+            let seq_no: SeqNumber = self.cb.get_unsent_seq_no().get();
+            self.cb.wait_for_acknowledgement(seq_no).await?;
+        }
+        Ok(())
     }
 
     pub async fn push(&mut self, nbytes: usize) -> Result<(), Fail> {
@@ -130,13 +182,68 @@ impl<N: NetworkRuntime> EstablishedSocket<N> {
     }
 
     pub async fn close(&mut self) -> Result<(), Fail> {
-        self.cb.close().await
+        let result: Result<(), Fail> = self.cb.close().await;
+        self.cb.log_tcp_timeline();
+        result
+    }
+
+    /// Aborts the connection, sending a RST to our peer.
+    pub fn abort(&mut self) {
+        self.cb.abort()
     }
 
     pub fn remote_mss(&self) -> usize {
         self.cb.remote_mss()
     }
 
+    pub fn set_socket_options(&mut self, options: SocketOptions) {
+        self.cb.set_socket_options(options)
+    }
+
+    pub fn set_md5_key(&mut self, md5_key: Option<TcpMd5Key>) {
+        self.cb.set_md5_key(md5_key)
+    }
+
+    pub fn set_ao_keys(&mut self, keys: Vec<TcpAoKey>) {
+        self.cb.set_ao_keys(keys)
+    }
+
+    pub fn get_ao_info(&self) -> Option<TcpAoInfo> {
+        self.cb.get_ao_info()
+    }
+
+    pub fn set_ao_info(&mut self, info: TcpAoInfo) -> Result<(), Fail> {
+        self.cb.set_ao_info(info)
+    }
+
+    pub fn uses_ao_key(&self, key: &TcpAoKey) -> bool {
+        self.cb.uses_ao_key(key)
+    }
+
+    pub fn check_authentication(&mut self, header: &TcpHeader, data: &[u8]) -> Result<(), Fail> {
+        self.cb.check_authentication(header, data)
+    }
+
+    pub fn get_drop_stats(&self) -> DropStats {
+        self.cb.get_drop_stats()
+    }
+
+    pub fn record_drop(&mut self, reason: DropReason, len: usize) {
+        self.cb.record_drop(reason, len)
+    }
+
+    pub fn pop_drop_record(&mut self) -> Option<DropRecord> {
+        self.cb.pop_drop_record()
+    }
+
+    pub fn get_tcp_info(&self) -> TcpInfo {
+        self.cb.get_tcp_info()
+    }
+
+    pub fn get_tcp_timeline(&self) -> Vec<TcpEvent> {
+        self.cb.get_tcp_timeline()
+    }
+
     pub fn current_rto(&self) -> Duration {
         self.cb.rto()
     }
@@ -144,6 +251,22 @@ impl<N: NetworkRuntime> EstablishedSocket<N> {
     pub fn endpoints(&self) -> (SocketAddrV4, SocketAddrV4) {
         (self.cb.get_local(), self.cb.get_remote())
     }
+
+    /// Freezes the connection, so that it can be thawed in another instance of the stack. The connection then stops
+    /// here without letting our peer know.
+    pub fn freeze(&mut self) -> Result<FrozenConnection, Fail> {
+        self.cb.freeze()
+    }
+
+    /// Restores the data that a frozen connection buffered into this one, which was created with its state.
+    pub fn thaw(&mut self, frozen: &FrozenConnection) -> Result<(), Fail> {
+        self.cb.thaw(frozen)
+    }
+
+    /// Gets the sequence number of the next byte that is pushed to the connection.
+    pub fn unsent_seq_no(&self) -> SeqNumber {
+        self.cb.get_unsent_seq_no().get()
+    }
 }
 
 //======================================================================================================================
@@ -21,19 +21,36 @@ pub struct RtoCalculator {
 
     // Whether a RTT (round-trip-time) sample has been received yet.
     received_sample: bool,
+
+    // Bounds for the retransmission timeout.
+    lower_bound: f64,
+    upper_bound: f64,
 }
 
 impl RtoCalculator {
-    /// Initializes an RTO Calculator.
-    pub fn new() -> Self {
+    /// Initializes an RTO Calculator that starts at [initial] and keeps the RTO between [lower_bound] and
+    /// [upper_bound].
+    pub fn new(initial: Duration, lower_bound: Duration, upper_bound: Duration) -> Self {
         // RFC 6298 recommends an initial value of 1 second for RTO (See also RFC 6298 Appendix A).  The initial values
         // for SRTT and RTTVAR are arbitrary as they aren't used until after the first sample has been received.
-        Self {
+        let mut calculator: Self = Self {
             srtt: 1.0,
             rttvar: 0.0,
             rto: 1.0,
             received_sample: false,
-        }
+            lower_bound: 0.0,
+            upper_bound: 0.0,
+        };
+        calculator.set_bounds(lower_bound, upper_bound);
+        calculator.update_rto(initial.as_secs_f64());
+        calculator
+    }
+
+    /// Sets the bounds for the RTO. The upper bound is raised to the lower bound if it is smaller.
+    pub fn set_bounds(&mut self, lower_bound: Duration, upper_bound: Duration) {
+        self.lower_bound = lower_bound.as_secs_f64();
+        self.upper_bound = upper_bound.max(lower_bound).as_secs_f64();
+        self.update_rto(self.rto);
     }
 
     /// Adds an RTT sample to the calculator.
@@ -42,8 +59,9 @@ impl RtoCalculator {
         const ALPHA: f64 = 0.125;
         // RFC 6298's suggested value for beta is 1/4.
         const BETA: f64 = 0.25;
-        // Clock granularity in seconds.
-        const GRANULARITY: f64 = 0.001f64;
+        // Clock granularity in seconds. Lower bounds below it are meant for microsecond round-trip times, so the
+        // granularity shrinks with them.
+        let granularity: f64 = 0.001f64.min(self.lower_bound);
 
         let rtt: f64 = rtt.as_secs_f64();
 
@@ -59,7 +77,7 @@ impl RtoCalculator {
         }
 
         // The new RTO value is the smoothed RTT plus the maximum of the clock granularity and 4 times the RTT variance.
-        let rto: f64 = self.srtt + GRANULARITY.max(4.0 * self.rttvar);
+        let rto: f64 = self.srtt + granularity.max(4.0 * self.rttvar);
 
         // Store the updated RTT value.
         self.update_rto(rto);
@@ -67,15 +85,10 @@ impl RtoCalculator {
 
     /// Updates the stored RTO value while keeping it within the prescribed bounds (RFC 6298 Section 2.4)
     fn update_rto(&mut self, new_rto: f64) {
-        // RFC 6298's suggested value for the lower bound is 1 second.  Note this currently uses 1/10 of a second.
-        const LOWER_BOUND_SEC: f64 = 0.100f64;
-        // RFC 6298's suggested value for the upper bound is >= 60 seconds.
-        const UPPER_BOUND_SEC: f64 = 60.0f64;
-
         // Note: We use clamp() below as it is clearer in intent than a min/max combination.  However, if we were
         // concerned that new_rto could be NaN here (we're not) we wouldn't want to use clamp() as it would pass NaN
-        // through.  We'd use "self.rto = f64::min(new_rto.max(LOWER_BOUND_SEC), UPPER_BOUND_SEC);" below instead.
-        self.rto = new_rto.clamp(LOWER_BOUND_SEC, UPPER_BOUND_SEC);
+        // through.  We'd use "self.rto = f64::min(new_rto.max(self.lower_bound), self.upper_bound);" below instead.
+        self.rto = new_rto.clamp(self.lower_bound, self.upper_bound);
     }
 
     /// Performs an exponential "back off" of the RTO (doubles the current timeout).
@@ -87,4 +100,75 @@ impl RtoCalculator {
     pub fn rto(&self) -> Duration {
         Duration::from_secs_f64(self.rto)
     }
+
+    /// Gets the smoothed RTT, or zero if no sample has been received yet.
+    pub fn srtt(&self) -> Duration {
+        match self.received_sample {
+            true => Duration::from_secs_f64(self.srtt),
+            false => Duration::ZERO,
+        }
+    }
+
+    /// Gets the RTT variation, or zero if no sample has been received yet.
+    pub fn rttvar(&self) -> Duration {
+        Duration::from_secs_f64(self.rttvar)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RtoCalculator;
+    use crate::runtime::network::consts::{
+        TCP_RTO_INITIAL,
+        TCP_RTO_MAX,
+        TCP_RTO_MIN,
+    };
+    use ::anyhow::Result;
+    use ::std::time::Duration;
+
+    /// Tests that the round-trip time estimates follow the samples, as in RFC 6298 Section 2.
+    #[test]
+    fn test_rtt_estimates() -> Result<()> {
+        let mut calculator: RtoCalculator = RtoCalculator::new(TCP_RTO_INITIAL, TCP_RTO_MIN, TCP_RTO_MAX);
+        crate::ensure_eq!(calculator.srtt(), Duration::ZERO);
+        crate::ensure_eq!(calculator.rto(), Duration::from_secs(1));
+
+        // The first sample sets SRTT and half of it sets RTTVAR.
+        calculator.add_sample(Duration::from_millis(200));
+        crate::ensure_eq!(calculator.srtt(), Duration::from_millis(200));
+        crate::ensure_eq!(calculator.rttvar(), Duration::from_millis(100));
+        crate::ensure_eq!(calculator.rto(), Duration::from_millis(600));
+
+        // Subsequent samples are smoothed.
+        calculator.add_sample(Duration::from_millis(200));
+        crate::ensure_eq!(calculator.srtt(), Duration::from_millis(200));
+        crate::ensure_eq!(calculator.rttvar(), Duration::from_millis(75));
+        Ok(())
+    }
+
+    /// Tests that the RTO stays within configured bounds that suit microsecond round-trip times.
+    #[test]
+    fn test_rto_bounds() -> Result<()> {
+        let mut calculator: RtoCalculator =
+            RtoCalculator::new(Duration::from_millis(1), Duration::from_micros(200), Duration::from_millis(10));
+        crate::ensure_eq!(calculator.rto(), Duration::from_millis(1));
+
+        // Steady samples drive the RTO down to the smoothed RTT plus the clock granularity, which is no larger than the
+        // lower bound.
+        for _ in 0..32 {
+            calculator.add_sample(Duration::from_micros(20));
+        }
+        crate::ensure_eq!(calculator.rto(), Duration::from_micros(220));
+
+        // Backing off stops at the upper bound.
+        for _ in 0..8 {
+            calculator.back_off();
+        }
+        crate::ensure_eq!(calculator.rto(), Duration::from_millis(10));
+
+        // Changing the bounds clamps the current RTO.
+        calculator.set_bounds(Duration::from_micros(200), Duration::from_millis(5));
+        crate::ensure_eq!(calculator.rto(), Duration::from_millis(5));
+        Ok(())
+    }
 }
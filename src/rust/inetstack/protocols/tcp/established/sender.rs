@@ -14,11 +14,9 @@ use crate::{
         memory::DemiBuffer,
         network::NetworkRuntime,
     },
+    trace_event,
 };
-use ::libc::{
-    EBUSY,
-    EINVAL,
-};
+use ::libc::EINVAL;
 use ::std::{
     cell::{
         Cell,
@@ -40,13 +38,10 @@ pub struct UnackedSegment {
     pub bytes: DemiBuffer,
     // Set to `None` on retransmission to implement Karn's algorithm.
     pub initial_tx: Option<Instant>,
+    // Kept across retransmissions, to bound how long the segment may stay unacknowledged.
+    pub first_tx: Instant,
 }
 
-/// Hard limit for unsent queue.
-/// TODO: Remove this.  We should limit the unsent queue by either having a (configurable) send buffer size (in bytes,
-/// not segments) and rejecting send requests that exceed that, or by limiting the user's send buffer allocations.
-const UNSENT_QUEUE_CUTOFF: usize = 1024;
-
 // TODO: Consider moving retransmit timer and congestion control fields out of this structure.
 // TODO: Make all public fields in this structure private.
 pub struct Sender {
@@ -134,6 +129,10 @@ impl Sender {
         self.send_unacked.clone()
     }
 
+    pub fn get_window_scale(&self) -> u8 {
+        self.window_scale
+    }
+
     pub fn get_send_next(&self) -> SharedAsyncValue<SeqNumber> {
         self.send_next.clone()
     }
@@ -146,10 +145,46 @@ impl Sender {
         self.unsent_seq_no.clone()
     }
 
+    /// Gets the number of bytes that were sent but not acknowledged yet.
+    pub fn unacked_bytes(&self) -> usize {
+        self.unacked_queue
+            .borrow()
+            .iter()
+            .map(|segment| segment.bytes.len())
+            .sum()
+    }
+
+    /// Gets the number of bytes that were pushed but not sent yet.
+    pub fn unsent_bytes(&self) -> usize {
+        self.unsent_queue.borrow().iter().map(|buf| buf.len()).sum()
+    }
+
+    /// Gets the number of bytes that were pushed but not acknowledged yet, whether they were sent or not.
+    pub fn buffered_bytes(&self) -> usize {
+        Into::<u32>::into(self.unsent_seq_no.get() - self.send_unacked.get()) as usize
+    }
+
+    /// Copies out the data that was pushed but not acknowledged yet, in sequence order, whether it was sent or not.
+    pub fn copy_buffered_data(&self) -> Vec<u8> {
+        let mut data: Vec<u8> = Vec::with_capacity(self.buffered_bytes());
+        for segment in self.unacked_queue.borrow().iter() {
+            data.extend_from_slice(&segment.bytes[..]);
+        }
+        for buf in self.unsent_queue.borrow().iter() {
+            data.extend_from_slice(&buf[..]);
+        }
+        data
+    }
+
     pub fn push_unacked_segment(&self, segment: UnackedSegment) {
         self.unacked_queue.borrow_mut().push_back(segment)
     }
 
+    /// Gets the time at which the oldest data that is not acknowledged yet was first sent, if any.
+    pub fn oldest_unacked_tx(&self) -> Option<Instant> {
+        self.unacked_queue.borrow().front().map(|segment| segment.first_tx)
+    }
+
     // This is the main TCP send routine.
     //
     pub fn send<N: NetworkRuntime>(&mut self, buf: DemiBuffer, mut cb: SharedControlBlock<N>) -> Result<(), Fail> {
@@ -204,8 +239,13 @@ impl Sender {
 
             let win_sz: u32 = self.send_window.get();
 
-            if win_sz > 0 && win_sz >= in_flight_after_send && effective_cwnd >= in_flight_after_send {
-                if let Some(remote_link_addr) = cb.arp().try_query(cb.get_remote().ip().clone()) {
+            // Connections that are over their pacing rate leave the data to the background sender.
+            if win_sz > 0
+                && win_sz >= in_flight_after_send
+                && effective_cwnd >= in_flight_after_send
+                && cb.get_pacing_deadline().is_none()
+            {
+                if let Some(remote_link_addr) = cb.try_next_hop() {
                     // This hook is primarily intended to record the last time we sent data, so we can later tell if
                     // the connection has been idle.
                     let rto: Duration = cb.rto();
@@ -232,9 +272,11 @@ impl Sender {
                     self.unsent_seq_no.modify(|s| s + SeqNumber::from(buf_len));
 
                     // Put the segment we just sent on the retransmission queue.
+                    let now: Instant = cb.get_now();
                     let unacked_segment = UnackedSegment {
                         bytes: buf,
-                        initial_tx: Some(cb.get_now()),
+                        initial_tx: Some(now),
+                        first_tx: now,
                     };
                     self.unacked_queue.borrow_mut().push_back(unacked_segment);
 
@@ -251,12 +293,6 @@ impl Sender {
             }
         }
 
-        // Too fast.
-        // TODO: We need to fix this the correct way: limit our send buffer size to the amount we're willing to buffer.
-        if self.unsent_queue.borrow().len() > UNSENT_QUEUE_CUTOFF {
-            return Err(Fail::new(EBUSY, "too many packets to send"));
-        }
-
         // Slow path: Delegating sending the data to background processing.
         trace!("Queueing Send for background processing");
         self.unsent_queue.borrow_mut().push_back(buf);
@@ -279,7 +315,7 @@ impl Sender {
             // TODO: Issue #198 Repacketization - we should send a full MSS (and set the FIN flag if applicable).
 
             // Prepare and send the segment.
-            if let Some(first_hop_link_addr) = cb.arp().try_query(cb.get_remote().ip().clone()) {
+            if let Some(first_hop_link_addr) = cb.try_next_hop() {
                 let mut header: TcpHeader = cb.tcp_header();
                 header.seq_num = self.send_unacked.get();
                 if data.len() == 0 {
@@ -288,6 +324,7 @@ impl Sender {
                 } else {
                     header.psh = true;
                 }
+                trace_event!("tcp", "retransmit", seq = header.seq_num, len = data.len());
                 cb.emit(header, Some(data), first_hop_link_addr);
             }
         } else {
@@ -354,6 +391,11 @@ impl Sender {
         let mut cloned_buf = buf.clone();
         let buf_len: usize = buf.len();
 
+        // Do not leave an empty buffer behind, as it would be taken for the end-of-send marker.
+        if buf_len == 1 {
+            return queue.pop_front();
+        }
+
         // Pop one byte off the buf still in the queue and all but one of the bytes on our clone.
         expect_ok!(buf.adjust(1), "'buf' should contain at least one byte");
         expect_ok!(
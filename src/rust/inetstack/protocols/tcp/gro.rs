@@ -0,0 +1,280 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::protocols::{
+        ipv4::Ipv4Header,
+        tcp::{
+            segment::TcpHeader,
+            SeqNumber,
+        },
+    },
+    runtime::memory::DemiBuffer,
+};
+use ::arrayvec::ArrayVec;
+use ::std::mem;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Largest payload of a coalesced segment.
+const MAX_COALESCED_SIZE: usize = u16::MAX as usize;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Receive coalescing statistics.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GroStats {
+    /// Segments that went through the coalescer.
+    pub segments: u64,
+    /// Segments that were merged into a previous segment of the same flow.
+    pub coalesced: u64,
+    /// Segments that were delivered to the receiver. A coalesced segment is delivered once.
+    pub delivered: u64,
+}
+
+/// A segment that is waiting for more in-order segments of the same flow.
+struct PendingSegment {
+    ip_hdr: Ipv4Header,
+    tcp_hdr: TcpHeader,
+    /// Payloads of the segments that were merged so far, in order.
+    data: Vec<DemiBuffer>,
+    /// Total length of the payloads.
+    len: usize,
+}
+
+/// Generic receive offload: merges consecutive in-order TCP segments of the same flow within a batch of received
+/// packets, so that the receiver processes them at once.
+#[derive(Default)]
+pub struct Coalescer {
+    pending: Vec<PendingSegment>,
+    stats: GroStats,
+}
+
+/// A segment that is ready to be delivered to the receiver.
+pub type Segment = (Ipv4Header, TcpHeader, DemiBuffer);
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Coalescer {
+    /// Feeds a segment to the coalescer. Returns the segments that are ready to be delivered, in order. Segments
+    /// that may be merged with later ones are held until the next call to [flush](Self::flush).
+    pub fn push(&mut self, ip_hdr: Ipv4Header, tcp_hdr: TcpHeader, data: DemiBuffer) -> ArrayVec<Segment, 2> {
+        let mut ready: ArrayVec<Segment, 2> = ArrayVec::new();
+        self.stats.segments += 1;
+
+        if let Some(index) = self
+            .pending
+            .iter()
+            .position(|pending| pending.is_same_flow(&ip_hdr, &tcp_hdr))
+        {
+            if self.pending[index].can_merge(&tcp_hdr, data.len()) {
+                self.pending[index].merge(tcp_hdr, data);
+                self.stats.coalesced += 1;
+                return ready;
+            }
+            // This segment cannot be merged, so everything that came before it in the same flow goes first.
+            let pending: PendingSegment = self.pending.remove(index);
+            ready.push(self.deliver(pending));
+        }
+
+        if Self::is_mergeable(&tcp_hdr, data.len()) {
+            let len: usize = data.len();
+            self.pending.push(PendingSegment {
+                ip_hdr,
+                tcp_hdr,
+                data: vec![data],
+                len,
+            });
+        } else {
+            self.stats.delivered += 1;
+            ready.push((ip_hdr, tcp_hdr, data));
+        }
+
+        ready
+    }
+
+    /// Ends the current batch. Returns all segments that are held by the coalescer.
+    pub fn flush(&mut self) -> Vec<Segment> {
+        let pending: Vec<PendingSegment> = mem::take(&mut self.pending);
+        pending.into_iter().map(|pending| self.deliver(pending)).collect()
+    }
+
+    /// Gets the coalescing statistics.
+    pub fn get_stats(&self) -> GroStats {
+        self.stats
+    }
+
+    /// Checks if a segment with header [tcp_hdr] and [len] bytes of payload may be merged with other segments. Only
+    /// plain data segments are, since any other segment may change the state of the connection.
+    fn is_mergeable(tcp_hdr: &TcpHeader, len: usize) -> bool {
+        len > 0
+            && tcp_hdr.ack
+            && !(tcp_hdr.syn || tcp_hdr.fin || tcp_hdr.rst || tcp_hdr.urg || tcp_hdr.cwr || tcp_hdr.ece)
+            && tcp_hdr.num_options == 0
+    }
+
+    /// Turns a pending segment into one that is ready to be delivered.
+    fn deliver(&mut self, pending: PendingSegment) -> Segment {
+        self.stats.delivered += 1;
+        let PendingSegment {
+            ip_hdr,
+            tcp_hdr,
+            mut data,
+            len,
+        } = pending;
+        if data.len() == 1 {
+            return (ip_hdr, tcp_hdr, data.remove(0));
+        }
+        let mut buf: DemiBuffer = DemiBuffer::new(len as u16);
+        let mut offset: usize = 0;
+        for chunk in data {
+            buf[offset..(offset + chunk.len())].copy_from_slice(&chunk[..]);
+            offset += chunk.len();
+        }
+        (ip_hdr, tcp_hdr, buf)
+    }
+}
+
+impl PendingSegment {
+    /// Checks if a segment with headers [ip_hdr] and [tcp_hdr] belongs to the same flow as this one.
+    fn is_same_flow(&self, ip_hdr: &Ipv4Header, tcp_hdr: &TcpHeader) -> bool {
+        self.ip_hdr.get_src_addr() == ip_hdr.get_src_addr()
+            && self.ip_hdr.get_dest_addr() == ip_hdr.get_dest_addr()
+            && self.tcp_hdr.src_port == tcp_hdr.src_port
+            && self.tcp_hdr.dst_port == tcp_hdr.dst_port
+    }
+
+    /// Checks if a segment of the same flow with header [tcp_hdr] and [len] bytes of payload directly follows this
+    /// one and carries the same control information.
+    fn can_merge(&self, tcp_hdr: &TcpHeader, len: usize) -> bool {
+        !self.tcp_hdr.psh
+            && Coalescer::is_mergeable(tcp_hdr, len)
+            && tcp_hdr.seq_num == self.tcp_hdr.seq_num + SeqNumber::from(self.len as u32)
+            && tcp_hdr.ack_num == self.tcp_hdr.ack_num
+            && tcp_hdr.window_size == self.tcp_hdr.window_size
+            && self.len + len <= MAX_COALESCED_SIZE
+    }
+
+    /// Appends a segment with header [tcp_hdr] and payload [data] to this one.
+    fn merge(&mut self, tcp_hdr: TcpHeader, data: DemiBuffer) {
+        self.tcp_hdr.psh |= tcp_hdr.psh;
+        self.len += data.len();
+        self.data.push(data);
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Coalescer,
+        GroStats,
+        Segment,
+    };
+    use crate::{
+        inetstack::protocols::{
+            ip::IpProtocol,
+            ipv4::Ipv4Header,
+            tcp::{
+                segment::TcpHeader,
+                SeqNumber,
+            },
+        },
+        runtime::memory::DemiBuffer,
+    };
+    use ::anyhow::Result;
+    use ::arrayvec::ArrayVec;
+    use ::std::net::Ipv4Addr;
+
+    fn segment(src_port: u16, seq_num: u32, payload: &[u8]) -> (Ipv4Header, TcpHeader, DemiBuffer) {
+        let ip_hdr: Ipv4Header =
+            Ipv4Header::new(Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2), IpProtocol::TCP);
+        let mut tcp_hdr: TcpHeader = TcpHeader::new(src_port, 80);
+        tcp_hdr.seq_num = SeqNumber::from(seq_num);
+        tcp_hdr.ack = true;
+        let data: DemiBuffer = DemiBuffer::from_slice(payload).expect("slice should fit in a buffer");
+        (ip_hdr, tcp_hdr, data)
+    }
+
+    /// Tests that consecutive in-order segments of a flow are merged into one.
+    #[test]
+    fn test_coalesce_in_order_segments() -> Result<()> {
+        let mut coalescer: Coalescer = Coalescer::default();
+        for (seq_num, payload) in [(1, b"abc"), (4, b"def"), (7, b"ghi")] {
+            let (ip_hdr, tcp_hdr, data) = segment(1024, seq_num, payload);
+            let ready: ArrayVec<Segment, 2> = coalescer.push(ip_hdr, tcp_hdr, data);
+            crate::ensure_eq!(ready.len(), 0);
+        }
+        let flushed: Vec<Segment> = coalescer.flush();
+        crate::ensure_eq!(flushed.len(), 1);
+        crate::ensure_eq!(flushed[0].1.seq_num, SeqNumber::from(1));
+        crate::ensure_eq!(&flushed[0].2[..], b"abcdefghi");
+        crate::ensure_eq!(
+            coalescer.get_stats(),
+            GroStats {
+                segments: 3,
+                coalesced: 2,
+                delivered: 1,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Tests that out-of-order segments and segments with control flags are not merged.
+    #[test]
+    fn test_do_not_coalesce_out_of_order_or_control_segments() -> Result<()> {
+        let mut coalescer: Coalescer = Coalescer::default();
+        let (ip_hdr, tcp_hdr, data) = segment(1024, 1, b"abc");
+        crate::ensure_eq!(coalescer.push(ip_hdr, tcp_hdr, data).len(), 0);
+
+        // A gap in the sequence space flushes the pending segment.
+        let (ip_hdr, tcp_hdr, data) = segment(1024, 10, b"def");
+        let ready: ArrayVec<Segment, 2> = coalescer.push(ip_hdr, tcp_hdr, data);
+        crate::ensure_eq!(ready.len(), 1);
+        crate::ensure_eq!(&ready[0].2[..], b"abc");
+
+        // A FIN is delivered right away, after the pending segment.
+        let (ip_hdr, mut tcp_hdr, data) = segment(1024, 13, b"ghi");
+        tcp_hdr.fin = true;
+        let ready: ArrayVec<Segment, 2> = coalescer.push(ip_hdr, tcp_hdr, data);
+        crate::ensure_eq!(ready.len(), 2);
+        crate::ensure_eq!(&ready[0].2[..], b"def");
+        crate::ensure_eq!(ready[1].1.fin, true);
+
+        crate::ensure_eq!(coalescer.flush().len(), 0);
+        crate::ensure_eq!(coalescer.get_stats().coalesced, 0);
+        crate::ensure_eq!(coalescer.get_stats().delivered, 3);
+
+        Ok(())
+    }
+
+    /// Tests that segments of different flows are coalesced separately.
+    #[test]
+    fn test_coalesce_interleaved_flows() -> Result<()> {
+        let mut coalescer: Coalescer = Coalescer::default();
+        for (src_port, seq_num, payload) in [(1024, 1, b"abc"), (2048, 100, b"xyz"), (1024, 4, b"def")] {
+            let (ip_hdr, tcp_hdr, data) = segment(src_port, seq_num, payload);
+            crate::ensure_eq!(coalescer.push(ip_hdr, tcp_hdr, data).len(), 0);
+        }
+        let flushed: Vec<Segment> = coalescer.flush();
+        crate::ensure_eq!(flushed.len(), 2);
+        crate::ensure_eq!(&flushed[0].2[..], b"abcdef");
+        crate::ensure_eq!(&flushed[1].2[..], b"xyz");
+
+        Ok(())
+    }
+}
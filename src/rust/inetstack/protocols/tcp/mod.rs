@@ -2,14 +2,18 @@
 // Licensed under the MIT license.
 
 mod active_open;
+mod authentication;
 pub mod constants;
 mod established;
+pub mod gro;
 mod isn_generator;
 mod passive_open;
 pub mod peer;
 pub mod segment;
 mod sequence_number;
+mod signature;
 pub mod socket;
+mod syn_cookie;
 
 #[cfg(test)]
 mod tests;
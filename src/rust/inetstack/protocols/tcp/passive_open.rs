@@ -21,6 +21,10 @@ use crate::{
         ip::IpProtocol,
         ipv4::Ipv4Header,
         tcp::{
+            authentication::{
+                handshake_isns,
+                TcpAoKeys,
+            },
             constants::FALLBACK_MSS,
             established::{
                 congestion_control,
@@ -33,6 +37,15 @@ use crate::{
                 TcpOptions2,
                 TcpSegment,
             },
+            signature::{
+                TcpMd5Keys,
+                TcpSigner,
+                TcpSigning,
+            },
+            syn_cookie::{
+                SynCookie,
+                SynCookieGenerator,
+            },
             SeqNumber,
         },
     },
@@ -41,7 +54,15 @@ use crate::{
         fail::Fail,
         memory::DemiBuffer,
         network::{
+            admission::AdmissionKind,
             config::TcpConfig,
+            drops::{
+                DropQueue,
+                DropReason,
+                DropRecord,
+                DropStats,
+            },
+            socket::option::SocketOptions,
             types::MacAddress,
             NetworkRuntime,
         },
@@ -49,6 +70,8 @@ use crate::{
         SharedDemiRuntime,
         SharedObject,
     },
+    trace_event,
+    trace_instrument,
     QToken,
 };
 use ::futures::{
@@ -56,7 +79,7 @@ use ::futures::{
     FutureExt,
 };
 use ::libc::{
-    EBADMSG,
+    ECONNRESET,
     ETIMEDOUT,
 };
 use ::std::{
@@ -66,26 +89,48 @@ use ::std::{
         Deref,
         DerefMut,
     },
-    time::Duration,
+    time::{
+        Duration,
+        Instant,
+    },
 };
 
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Upper bound for the time we wait for the handshake to complete before retransmitting a SYN+ACK.
+const MAX_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(60);
+
 //======================================================================================================================
 // Structures
 //======================================================================================================================
 
 pub struct PassiveSocket<N: NetworkRuntime> {
     connections: HashMap<SocketAddrV4, SharedAsyncQueue<(Ipv4Header, TcpHeader, DemiBuffer)>>,
+    /// Initial sequence numbers of our side and of our peer on the connections that we keep state for, which the
+    /// authentication option covers.
+    isns: HashMap<SocketAddrV4, (SeqNumber, SeqNumber)>,
     recv_queue: SharedAsyncQueue<(Ipv4Header, TcpHeader, DemiBuffer)>,
     ready: AsyncQueue<Result<EstablishedSocket<N>, Fail>>,
     max_backlog: usize,
     isn_generator: IsnGenerator,
+    syn_cookies: SynCookieGenerator,
+    /// Number of handshakes that are in flight, that is, connection requests that we keep state for.
+    handshakes: usize,
     local: SocketAddrV4,
     runtime: SharedDemiRuntime,
     transport: N,
     tcp_config: TcpConfig,
+    options: SocketOptions,
+    /// Keys that sign the segments of connections with the peers that share them.
+    md5_keys: TcpMd5Keys,
+    /// Keys that authenticate the segments of connections with the peers that share them.
+    ao_keys: TcpAoKeys,
     local_link_addr: MacAddress,
     arp: SharedArpPeer<N>,
     dead_socket_tx: mpsc::UnboundedSender<QDesc>,
+    drops: DropQueue,
 
     background_task_qt: Option<QToken>,
 }
@@ -105,24 +150,36 @@ impl<N: NetworkRuntime> SharedPassiveSocket<N> {
         recv_queue: SharedAsyncQueue<(Ipv4Header, TcpHeader, DemiBuffer)>,
         transport: N,
         tcp_config: TcpConfig,
+        options: SocketOptions,
+        md5_keys: TcpMd5Keys,
+        ao_keys: TcpAoKeys,
         local_link_addr: MacAddress,
         arp: SharedArpPeer<N>,
         dead_socket_tx: mpsc::UnboundedSender<QDesc>,
         nonce: u32,
     ) -> Result<Self, Fail> {
+        let mut drops: DropQueue = DropQueue::default();
+        drops.set_enabled(options.recv_err());
         let mut me: Self = Self(SharedObject::<PassiveSocket<N>>::new(PassiveSocket {
             connections: HashMap::<SocketAddrV4, SharedAsyncQueue<(Ipv4Header, TcpHeader, DemiBuffer)>>::new(),
+            isns: HashMap::<SocketAddrV4, (SeqNumber, SeqNumber)>::new(),
             recv_queue,
             ready: AsyncQueue::<Result<EstablishedSocket<N>, Fail>>::default(),
             max_backlog,
             isn_generator: IsnGenerator::new(nonce),
+            syn_cookies: SynCookieGenerator::new(nonce, runtime.get_now()),
+            handshakes: 0,
             local,
             local_link_addr,
             runtime: runtime.clone(),
             transport,
             tcp_config,
+            options,
+            md5_keys,
+            ao_keys,
             arp,
             dead_socket_tx,
+            drops,
             background_task_qt: None,
         }));
         let qt: QToken =
@@ -136,6 +193,35 @@ impl<N: NetworkRuntime> SharedPassiveSocket<N> {
         self.local
     }
 
+    /// Sets the options that are used to build the headers of the segments that are sent from now on. Connections
+    /// that are accepted afterwards inherit them.
+    pub fn set_socket_options(&mut self, options: SocketOptions) {
+        self.options = options;
+        self.drops.set_enabled(options.recv_err());
+    }
+
+    /// Sets the keys that sign the segments of connection requests from now on. Connections that are accepted
+    /// afterwards inherit them.
+    pub fn set_md5_keys(&mut self, md5_keys: TcpMd5Keys) {
+        self.md5_keys = md5_keys;
+    }
+
+    /// Sets the keys that authenticate the segments of connection requests from now on. Connections that are accepted
+    /// afterwards inherit them.
+    pub fn set_ao_keys(&mut self, ao_keys: TcpAoKeys) {
+        self.ao_keys = ao_keys;
+    }
+
+    /// Gets the number of incoming segments that were dropped on their way to this socket, for each reason.
+    pub fn get_drop_stats(&self) -> DropStats {
+        self.drops.stats()
+    }
+
+    /// Pops the oldest record from the error queue of this socket.
+    pub fn pop_drop_record(&mut self) -> Option<DropRecord> {
+        self.drops.pop()
+    }
+
     /// Accept a new connection by fetching one from the queue of requests, blocking if there are no new requests.
     pub async fn do_accept(&mut self) -> Result<EstablishedSocket<N>, Fail> {
         self.ready.pop(None).await?
@@ -148,50 +234,88 @@ impl<N: NetworkRuntime> SharedPassiveSocket<N> {
                 Err(_) => break,
             };
             let remote: SocketAddrV4 = SocketAddrV4::new(ipv4_hdr.get_src_addr(), tcp_hdr.src_port);
+
+            // Segments whose authentication option does not check out never reach a connection. Connections that were
+            // not accepted yet are checked here, as they have no socket of their own.
+            let local: SocketAddrV4 = SocketAddrV4::new(ipv4_hdr.get_dest_addr(), self.local.port());
+            let isns: Option<(SeqNumber, SeqNumber)> = self.isns.get(&remote).copied();
+            if let Err(e) = self.ao_keys.check(local, remote, isns, &tcp_hdr, &buf) {
+                trace_event!("drop", "bad_signature", remote = remote, cause = e.cause);
+                self.record_drop(DropReason::BadSignature, remote, buf.len());
+                continue;
+            }
+
             if let Some(recv_queue) = self.connections.get_mut(&remote) {
                 // Packet is either for an inflight request or established connection.
                 recv_queue.push((ipv4_hdr, tcp_hdr, buf));
                 continue;
             }
 
+            // An ACK from a remote that we keep no state for may complete a handshake that we answered with a SYN
+            // cookie.
+            if tcp_hdr.ack && !tcp_hdr.syn && !tcp_hdr.rst && self.tcp_config.get_syn_cookie_threshold().is_some() {
+                if let Some(cookie) = self.validate_syn_cookie(&remote, &tcp_hdr) {
+                    self.handle_syn_cookie_ack(ipv4_hdr, remote, tcp_hdr, buf, cookie);
+                    continue;
+                }
+            }
+
             // If not a SYN, then this packet is not for a new connection and we throw it away.
             if !tcp_hdr.syn || tcp_hdr.ack || tcp_hdr.rst {
                 let cause: String = format!(
                     "invalid TCP flags (syn={}, ack={}, rst={})",
                     tcp_hdr.syn, tcp_hdr.ack, tcp_hdr.rst
                 );
-                warn!("poll(): {}", cause);
-                self.send_rst(&remote, tcp_hdr);
+                trace_event!("drop", "unexpected_segment", remote = remote, cause = cause);
+                self.record_drop(DropReason::InvalidSegment, remote, buf.len());
+                self.send_rst(&remote, tcp_hdr, buf.len());
                 continue;
             }
 
-            // Check if this SYN segment carries any data.
-            if !buf.is_empty() {
-                // RFC 793 allows connections to be established with data-carrying segments, but we do not support this.
-                // We simply drop the data and and proceed with the three-way handshake protocol, on the hope that the
-                // remote will retransmit the data after the connection is established.
-                // See: https://datatracker.ietf.org/doc/html/rfc793#section-3.4 fo more details.
-                warn!("Received SYN with data (len={})", buf.len());
-                // TODO: https://github.com/microsoft/demikernel/issues/1115
+            // Refuse connection requests from sources that the admission control of the stack blocks or rate limits.
+            // No reset is sent, so that refused sources learn nothing.
+            let now: Instant = self.runtime.get_now();
+            if !self
+                .runtime
+                .get_admission_control()
+                .admit(now, AdmissionKind::Connection, *remote.ip())
+            {
+                trace_event!("drop", "admission", remote = remote);
+                self.record_drop(DropReason::Admission, remote, buf.len());
+                continue;
             }
 
-            // Start a new connection.
-            self.handle_new_syn(remote, tcp_hdr);
+            // Start a new connection. RFC 793 allows connections to be established with data-carrying segments, so
+            // any data in this SYN segment is queued until the connection is established.
+            // See: https://datatracker.ietf.org/doc/html/rfc793#section-3.4 fo more details.
+            self.handle_new_syn(ipv4_hdr, remote, tcp_hdr, buf);
         }
     }
 
-    fn handle_new_syn(&mut self, remote: SocketAddrV4, tcp_hdr: TcpHeader) {
+    fn handle_new_syn(&mut self, ipv4_hdr: Ipv4Header, remote: SocketAddrV4, tcp_hdr: TcpHeader, buf: DemiBuffer) {
         debug!("Received SYN: {:?}", tcp_hdr);
         let inflight_len: usize = self.connections.len();
-        if inflight_len + self.ready.len() >= self.max_backlog {
+        let backlog_full: bool = inflight_len + self.ready.len() >= self.max_backlog;
+
+        // Once too many handshakes are in flight, or the backlog fills up, answer with a SYN cookie instead of keeping
+        // state for this connection request, as long as there is room for it in the accept queue.
+        if let Some(threshold) = self.tcp_config.get_syn_cookie_threshold() {
+            if (self.handshakes >= threshold || backlog_full) && self.ready.len() < self.max_backlog {
+                self.send_syn_cookie(&remote, &tcp_hdr);
+                return;
+            }
+        }
+
+        if backlog_full {
             let cause: String = format!(
                 "backlog full (inflight={}, ready={}, backlog={})",
                 inflight_len,
                 self.ready.len(),
                 self.max_backlog
             );
-            warn!("handle_new_syn(): {}", cause);
-            self.send_rst(&remote, tcp_hdr);
+            trace_event!("drop", "backlog_full", remote = remote, cause = cause);
+            self.record_drop(DropReason::BacklogFull, remote, 0);
+            self.send_rst(&remote, tcp_hdr, buf.len());
             return;
         }
 
@@ -204,13 +328,25 @@ impl<N: NetworkRuntime> SharedPassiveSocket<N> {
         let recv_queue: SharedAsyncQueue<(Ipv4Header, TcpHeader, DemiBuffer)> =
             SharedAsyncQueue::<(Ipv4Header, TcpHeader, DemiBuffer)>::default();
         let ack_queue: SharedAsyncQueue<usize> = SharedAsyncQueue::<usize>::default();
-        let future = self
-            .clone()
-            .send_syn_ack_and_wait_for_ack(remote, remote_isn, local_isn, tcp_hdr, recv_queue.clone(), ack_queue)
-            .fuse();
+        let mut me: Self = self.clone();
+        let handshake = me.clone().send_syn_ack_and_wait_for_ack(
+            remote,
+            remote_isn,
+            local_isn,
+            (ipv4_hdr, tcp_hdr, buf),
+            recv_queue.clone(),
+            ack_queue,
+        );
+        let future = async move {
+            handshake.await;
+            // The handshake is over, whether the connection made it into the accept queue or not.
+            me.handshakes -= 1;
+        }
+        .fuse();
+        let future = trace_instrument!(Box::pin(future), "tcp", local = local, remote = remote);
         match self
             .runtime
-            .insert_background_coroutine("Inetstack::TCP::passiveopen::background", Box::pin(future))
+            .insert_background_coroutine("Inetstack::TCP::passiveopen::background", future)
         {
             Ok(qt) => qt,
             Err(e) => {
@@ -221,27 +357,121 @@ impl<N: NetworkRuntime> SharedPassiveSocket<N> {
         };
         // TODO: Clean up the connections table once we have merged all of the routing tables into one.
         self.connections.insert(remote, recv_queue);
+        self.isns.insert(remote, (local_isn, remote_isn));
+        self.handshakes += 1;
     }
 
-    /// Sends a RST segment to `remote`.
-    fn send_rst(&mut self, remote: &SocketAddrV4, tcp_hdr: TcpHeader) {
-        debug!("send_rst(): sending RST to {:?}", remote);
+    /// Answers the SYN segment with header [tcp_hdr] from [remote] with a SYN+ACK whose initial sequence number is a
+    /// SYN cookie, without keeping any state for the connection request. Any data in the SYN segment is dropped, and
+    /// our peer sends it again once the handshake completes.
+    fn send_syn_cookie(&mut self, remote: &SocketAddrV4, tcp_hdr: &TcpHeader) {
+        let remote_link_addr: MacAddress = match self.arp.try_query(remote.ip().clone()) {
+            Some(link_addr) => link_addr,
+            None => {
+                // Resolving the link address would take state, so drop the SYN segment and let our peer retry.
+                let cause: String = format!("missing ARP entry (remote={})", remote.ip());
+                warn!("send_syn_cookie(): {}", &cause);
+                return;
+            },
+        };
+        let (remote_window_scale, mss) = parse_syn_options(tcp_hdr);
+        let remote_isn: SeqNumber = tcp_hdr.seq_num;
+        let local_isn: SeqNumber = self.syn_cookies.generate(
+            self.runtime.get_now(),
+            &self.local,
+            remote,
+            remote_isn,
+            mss,
+            remote_window_scale,
+        );
+        trace_event!(
+            "tcp",
+            "syn_cookie_sent",
+            local = self.local,
+            remote = remote,
+            handshakes = self.handshakes,
+        );
+        self.transmit_syn_ack(remote_link_addr, local_isn, remote_isn, *remote);
+    }
 
-        // If this is an inactive socket, then generate a RST segment.
-        // Generate the RST segment according to the ACK field.
-        // If the incoming segment has an ACK field, the reset takes its
-        // sequence number from the ACK field of the segment, otherwise the
-        // reset has sequence number zero and the ACK field is set to the sum
-        // of the sequence number and segment length of the incoming segment.
-        // Reference: https://datatracker.ietf.org/doc/html/rfc793#section-3.4
-        let (seq_num, ack_num): (SeqNumber, Option<SeqNumber>) = if tcp_hdr.ack {
-            (tcp_hdr.ack_num, Some(tcp_hdr.ack_num + SeqNumber::from(1)))
-        } else {
-            (
-                SeqNumber::from(0),
-                Some(tcp_hdr.seq_num + SeqNumber::from(tcp_hdr.compute_size() as u32)),
-            )
+    /// Checks whether the ACK segment with header [tcp_hdr] from [remote] echoes a SYN cookie that we sent.
+    fn validate_syn_cookie(&self, remote: &SocketAddrV4, tcp_hdr: &TcpHeader) -> Option<SynCookie> {
+        self.syn_cookies.validate(
+            self.runtime.get_now(),
+            &self.local,
+            remote,
+            tcp_hdr.seq_num - SeqNumber::from(1),
+            tcp_hdr.ack_num - SeqNumber::from(1),
+        )
+    }
+
+    /// Establishes the connection with [remote] whose handshake the ACK segment with header [tcp_hdr] completed, with
+    /// the options that were recovered from the SYN [cookie].
+    fn handle_syn_cookie_ack(
+        &mut self,
+        ipv4_hdr: Ipv4Header,
+        remote: SocketAddrV4,
+        tcp_hdr: TcpHeader,
+        buf: DemiBuffer,
+        cookie: SynCookie,
+    ) {
+        if self.ready.len() >= self.max_backlog {
+            let cause: String = format!(
+                "accept queue full (ready={}, backlog={})",
+                self.ready.len(),
+                self.max_backlog
+            );
+            trace_event!("drop", "backlog_full", remote = remote, cause = cause);
+            self.record_drop(DropReason::BacklogFull, remote, buf.len());
+            self.send_rst(&remote, tcp_hdr, buf.len());
+            return;
+        }
+        trace_event!("tcp", "syn_cookie_accepted", local = self.local, remote = remote);
+
+        let local_isn: SeqNumber = tcp_hdr.ack_num - SeqNumber::from(1);
+        let remote_isn: SeqNumber = tcp_hdr.seq_num - SeqNumber::from(1);
+        let header_window_size: u16 = tcp_hdr.window_size;
+        let mut recv_queue: SharedAsyncQueue<(Ipv4Header, TcpHeader, DemiBuffer)> =
+            SharedAsyncQueue::<(Ipv4Header, TcpHeader, DemiBuffer)>::default();
+        let ack_queue: SharedAsyncQueue<usize> = SharedAsyncQueue::<usize>::default();
+        if !buf.is_empty() || tcp_hdr.fin {
+            recv_queue.push((ipv4_hdr, tcp_hdr, buf));
+        }
+        let result: Result<EstablishedSocket<N>, Fail> = self.establish(
+            recv_queue.clone(),
+            ack_queue,
+            remote,
+            local_isn,
+            remote_isn,
+            header_window_size,
+            cookie.window_scale,
+            cookie.mss,
+        );
+        self.connections.insert(remote, recv_queue);
+        self.isns.insert(remote, (local_isn, remote_isn));
+        self.ready.push(result);
+    }
+
+    /// Records a segment from [remote] with [len] bytes of payload that was dropped for [reason].
+    pub fn record_drop(&mut self, reason: DropReason, remote: SocketAddrV4, len: usize) {
+        self.runtime.record_drop(reason);
+        self.drops.record(reason, Some(remote), len);
+    }
+
+    /// Sends a RST segment to `remote` in answer to the segment with header [tcp_hdr] and [data_len] bytes of data.
+    fn send_rst(&mut self, remote: &SocketAddrV4, tcp_hdr: TcpHeader, data_len: usize) {
+        let (local_isn, remote_isn): (SeqNumber, Option<SeqNumber>) = match self.isns.get(remote) {
+            Some((local_isn, remote_isn)) => (*local_isn, Some(*remote_isn)),
+            None => handshake_isns(&tcp_hdr),
+        };
+        let mut tcp_hdr: TcpHeader = match tcp_hdr.reset_reply(data_len) {
+            Some(tcp_hdr) => tcp_hdr,
+            None => return,
         };
+        debug!("send_rst(): sending RST to {:?}", remote);
+        let signer: Option<TcpSigner> = self
+            .signing(*remote, local_isn, remote_isn)
+            .map(|mut signing| signing.sign(&mut tcp_hdr));
 
         // Query link address for destination.
         let dst_link_addr: MacAddress = match self.arp.try_query(remote.ip().clone()) {
@@ -256,26 +486,22 @@ impl<N: NetworkRuntime> SharedPassiveSocket<N> {
         };
 
         // Create a RST segment.
-        let segment: TcpSegment = {
-            let mut tcp_hdr: TcpHeader = TcpHeader::new(self.local.port(), remote.port());
-            tcp_hdr.rst = true;
-            tcp_hdr.seq_num = seq_num;
-            if let Some(ack_num) = ack_num {
-                tcp_hdr.ack = true;
-                tcp_hdr.ack_num = ack_num;
-            }
-            TcpSegment {
-                ethernet2_hdr: Ethernet2Header::new(dst_link_addr, self.local_link_addr, EtherType2::Ipv4),
-                ipv4_hdr: Ipv4Header::new(self.local.ip().clone(), remote.ip().clone(), IpProtocol::TCP),
-                tcp_hdr,
-                data: None,
-                tx_checksum_offload: self.tcp_config.get_rx_checksum_offload(),
-            }
+        let segment: TcpSegment = TcpSegment {
+            ethernet2_hdr: Ethernet2Header::new(dst_link_addr, self.local_link_addr, EtherType2::Ipv4)
+                .with_priority(self.options.priority()),
+            ipv4_hdr: Ipv4Header::new(self.local.ip().clone(), remote.ip().clone(), IpProtocol::TCP)
+                .with_dscp(self.options.dscp())
+                .with_ttl(self.options.ttl()),
+            tcp_hdr,
+            data: None,
+            tx_checksum_offload: self.tcp_config.get_tx_checksum_offload(),
+            tso_segment_size: None,
+            signer,
         };
 
         // Send it.
         let pkt: Box<TcpSegment> = Box::new(segment);
-        self.transport.transmit(pkt);
+        self.transport.transmit_one(pkt);
     }
 
     async fn send_syn_ack_and_wait_for_ack(
@@ -283,29 +509,16 @@ impl<N: NetworkRuntime> SharedPassiveSocket<N> {
         remote: SocketAddrV4,
         remote_isn: SeqNumber,
         local_isn: SeqNumber,
-        tcp_hdr: TcpHeader,
+        syn: (Ipv4Header, TcpHeader, DemiBuffer),
         recv_queue: SharedAsyncQueue<(Ipv4Header, TcpHeader, DemiBuffer)>,
         ack_queue: SharedAsyncQueue<usize>,
     ) {
+        let tcp_hdr: &TcpHeader = &syn.1;
         // Set up new inflight accept connection.
-        let mut remote_window_scale = None;
-        let mut mss = FALLBACK_MSS;
-        for option in tcp_hdr.iter_options() {
-            match option {
-                TcpOptions2::WindowScale(w) => {
-                    info!("Received window scale: {:?}", w);
-                    remote_window_scale = Some(*w);
-                },
-                TcpOptions2::MaximumSegmentSize(m) => {
-                    info!("Received advertised MSS: {}", m);
-                    mss = *m as usize;
-                },
-                _ => continue,
-            }
-        }
+        let (remote_window_scale, mss) = parse_syn_options(tcp_hdr);
 
         let mut handshake_retries: usize = self.tcp_config.get_handshake_retries();
-        let handshake_timeout: Duration = self.tcp_config.get_handshake_timeout();
+        let mut handshake_timeout: Duration = self.tcp_config.get_handshake_timeout();
 
         loop {
             // Send the SYN + ACK.
@@ -326,21 +539,33 @@ impl<N: NetworkRuntime> SharedPassiveSocket<N> {
                 tcp_hdr.window_size,
                 remote_window_scale,
                 mss,
+                syn.clone(),
             );
 
             // Either we get an ack or a timeout.
             match conditional_yield_with_timeout(ack, handshake_timeout).await {
+                // The connection request was reset, so forget about it.
+                Ok(Err(Fail { errno, cause: _ })) if errno == ECONNRESET => {
+                    self.connections.remove(&remote);
+                    self.isns.remove(&remote);
+                    return;
+                },
                 // Got an ack
                 Ok(result) => {
                     self.ready.push(result);
                     return;
                 },
+                // Retransmit the SYN+ACK, backing off exponentially, until we run out of retries. Then forget about
+                // this connection request, as it never made it into the accept queue.
                 Err(Fail { errno, cause: _ }) if errno == ETIMEDOUT => {
                     if handshake_retries > 0 {
                         handshake_retries = handshake_retries - 1;
+                        handshake_timeout = (handshake_timeout * 2).min(MAX_HANDSHAKE_TIMEOUT);
                         continue;
                     } else {
-                        self.ready.push(Err(Fail::new(ETIMEDOUT, "handshake timeout")));
+                        trace_event!("tcp", "handshake_timeout", local = self.local, remote = remote);
+                        self.connections.remove(&remote);
+                        self.isns.remove(&remote);
                         return;
                     }
                 },
@@ -359,6 +584,17 @@ impl<N: NetworkRuntime> SharedPassiveSocket<N> {
         remote: SocketAddrV4,
     ) -> Result<(), Fail> {
         let remote_link_addr = self.arp.query(remote.ip().clone()).await?;
+        self.transmit_syn_ack(remote_link_addr, local_isn, remote_isn, remote);
+        Ok(())
+    }
+
+    fn transmit_syn_ack(
+        &mut self,
+        remote_link_addr: MacAddress,
+        local_isn: SeqNumber,
+        remote_isn: SeqNumber,
+        remote: SocketAddrV4,
+    ) {
         let mut tcp_hdr = TcpHeader::new(self.local.port(), remote.port());
         tcp_hdr.syn = true;
         tcp_hdr.seq_num = local_isn;
@@ -373,20 +609,28 @@ impl<N: NetworkRuntime> SharedPassiveSocket<N> {
         tcp_hdr.push_option(TcpOptions2::WindowScale(self.tcp_config.get_window_scale()));
         info!("Advertising window scale: {}", self.tcp_config.get_window_scale());
 
+        let signer: Option<TcpSigner> = self
+            .signing(remote, local_isn, Some(remote_isn))
+            .map(|mut signing| signing.sign(&mut tcp_hdr));
+
         debug!("Sending SYN+ACK: {:?}", tcp_hdr);
         let segment = TcpSegment {
-            ethernet2_hdr: Ethernet2Header::new(remote_link_addr, self.local_link_addr, EtherType2::Ipv4),
-            ipv4_hdr: Ipv4Header::new(self.local.ip().clone(), remote.ip().clone(), IpProtocol::TCP),
+            ethernet2_hdr: Ethernet2Header::new(remote_link_addr, self.local_link_addr, EtherType2::Ipv4)
+                .with_priority(self.options.priority()),
+            ipv4_hdr: Ipv4Header::new(self.local.ip().clone(), remote.ip().clone(), IpProtocol::TCP)
+                .with_dscp(self.options.dscp())
+                .with_ttl(self.options.ttl()),
             tcp_hdr,
             data: None,
-            tx_checksum_offload: self.tcp_config.get_rx_checksum_offload(),
+            tx_checksum_offload: self.tcp_config.get_tx_checksum_offload(),
+            tso_segment_size: None,
+            signer,
         };
-        self.transport.transmit(Box::new(segment));
-        Ok(())
+        self.transport.transmit_one(Box::new(segment));
     }
 
     async fn wait_for_ack(
-        self,
+        mut self,
         mut recv_queue: SharedAsyncQueue<(Ipv4Header, TcpHeader, DemiBuffer)>,
         ack_queue: SharedAsyncQueue<usize>,
         remote: SocketAddrV4,
@@ -395,15 +639,74 @@ impl<N: NetworkRuntime> SharedPassiveSocket<N> {
         header_window_size: u16,
         remote_window_scale: Option<u8>,
         mss: usize,
+        syn: (Ipv4Header, TcpHeader, DemiBuffer),
     ) -> Result<EstablishedSocket<N>, Fail> {
-        let (ipv4_hdr, tcp_hdr, buf) = recv_queue.pop(None).await?;
-        debug!("Received ACK: {:?}", tcp_hdr);
+        let (ipv4_hdr, tcp_hdr, buf) = loop {
+            let (ipv4_hdr, tcp_hdr, buf) = recv_queue.pop(None).await?;
+            debug!("Received ACK: {:?}", tcp_hdr);
+
+            // A reset that starts right after the SYN aborts this connection request, while any other reset is
+            // dropped. See: https://datatracker.ietf.org/doc/html/rfc9293#section-3.10.7.4
+            if tcp_hdr.rst {
+                if tcp_hdr.seq_num == remote_isn + SeqNumber::from(1) {
+                    return Err(Fail::new(ECONNRESET, "connection request reset"));
+                }
+                continue;
+            }
 
-        // Check the ack sequence number.
-        if tcp_hdr.ack_num != local_isn + SeqNumber::from(1) {
-            return Err(Fail::new(EBADMSG, "invalid SYN+ACK seq num"));
+            // A retransmitted SYN means that our SYN+ACK was lost, so send it again right away.
+            if !tcp_hdr.ack {
+                if tcp_hdr.syn && tcp_hdr.seq_num == remote_isn {
+                    self.send_syn_ack(local_isn, remote_isn, remote).await?;
+                }
+                continue;
+            }
+
+            // Check the ack sequence number. Segments that acknowledge something else are answered with a reset, and
+            // the handshake goes on.
+            if tcp_hdr.ack_num != local_isn + SeqNumber::from(1) {
+                warn!("wait_for_ack(): invalid SYN+ACK seq num (ack_num={:?})", tcp_hdr.ack_num);
+                self.send_rst(&remote, tcp_hdr, buf.len());
+                continue;
+            }
+            break (ipv4_hdr, tcp_hdr, buf);
+        };
+
+        // If there is data with the ACK, deliver it ahead of any segment that arrived after it. The data that came
+        // with the SYN goes first.
+        if !buf.is_empty() || tcp_hdr.fin {
+            recv_queue.push_front((ipv4_hdr, tcp_hdr, buf));
+        }
+        let (syn_ipv4_hdr, syn_tcp_hdr, syn_buf) = syn;
+        if !syn_buf.is_empty() || syn_tcp_hdr.fin {
+            let header: TcpHeader = syn_tcp_hdr.syn_data_header(local_isn + SeqNumber::from(1));
+            recv_queue.push_front((syn_ipv4_hdr, header, syn_buf));
         }
 
+        self.establish(
+            recv_queue,
+            ack_queue,
+            remote,
+            local_isn,
+            remote_isn,
+            header_window_size,
+            remote_window_scale,
+            mss,
+        )
+    }
+
+    /// Builds the socket of the connection with [remote] once its handshake completes.
+    fn establish(
+        &self,
+        recv_queue: SharedAsyncQueue<(Ipv4Header, TcpHeader, DemiBuffer)>,
+        ack_queue: SharedAsyncQueue<usize>,
+        remote: SocketAddrV4,
+        local_isn: SeqNumber,
+        remote_isn: SeqNumber,
+        header_window_size: u16,
+        remote_window_scale: Option<u8>,
+        mss: usize,
+    ) -> Result<EstablishedSocket<N>, Fail> {
         let (local_window_scale, remote_window_scale) = match remote_window_scale {
             Some(w) => (self.tcp_config.get_window_scale() as u32, w),
             None => (0, 0),
@@ -420,29 +723,30 @@ impl<N: NetworkRuntime> SharedPassiveSocket<N> {
             (self.tcp_config.get_receive_window_size() as u32).checked_shl(local_window_scale as u32),
             "TODO: Window size overflow"
         );
-        info!(
-            "Window sizes: local {}, remote {}",
-            local_window_size, remote_window_size
-        );
-        info!(
-            "Window scale: local {}, remote {}",
-            local_window_scale, remote_window_scale
+        trace_event!(
+            "tcp",
+            "established",
+            local = self.local,
+            remote = remote,
+            local_window = local_window_size,
+            remote_window = remote_window_size,
+            local_window_scale = local_window_scale,
+            remote_window_scale = remote_window_scale,
         );
 
-        // If there is data with the SYN+ACK, deliver it.
-        if !buf.is_empty() {
-            recv_queue.push((ipv4_hdr, tcp_hdr, buf));
-        }
-
+        // Accepted connections answer from the address that their peer reached, whatever interface the listener is bound
+        // to.
         let new_socket: EstablishedSocket<N> = EstablishedSocket::<N>::new(
             self.local,
             remote,
             self.runtime.clone(),
             self.transport.clone(),
-            recv_queue.clone(),
+            recv_queue,
             ack_queue,
             self.local_link_addr,
             self.tcp_config.clone(),
+            self.options.without_device(),
+            self.signing(remote, local_isn, Some(remote_isn)),
             self.arp.clone(),
             remote_isn + SeqNumber::from(1),
             self.tcp_config.get_ack_delay_timeout(),
@@ -459,6 +763,40 @@ impl<N: NetworkRuntime> SharedPassiveSocket<N> {
 
         Ok(new_socket)
     }
+
+    /// Gets how the segments of the connection with [remote] are protected, given the initial sequence numbers of our
+    /// side and of our peer. The authentication option wins over the signature option.
+    fn signing(&self, remote: SocketAddrV4, local_isn: SeqNumber, remote_isn: Option<SeqNumber>) -> Option<TcpSigning> {
+        match self.ao_keys.connection(self.local, remote, local_isn, remote_isn) {
+            Some(connection) => Some(TcpSigning::Ao(connection)),
+            None => self.md5_keys.lookup(*remote.ip()).map(TcpSigning::Md5),
+        }
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Parses the window scale and the maximum segment size that our peer advertised in the SYN segment with header
+/// [tcp_hdr].
+fn parse_syn_options(tcp_hdr: &TcpHeader) -> (Option<u8>, usize) {
+    let mut remote_window_scale = None;
+    let mut mss = FALLBACK_MSS;
+    for option in tcp_hdr.iter_options() {
+        match option {
+            TcpOptions2::WindowScale(w) => {
+                info!("Received window scale: {:?}", w);
+                remote_window_scale = Some(*w);
+            },
+            TcpOptions2::MaximumSegmentSize(m) => {
+                info!("Received advertised MSS: {}", m);
+                mss = *m as usize;
+            },
+            _ => continue,
+        }
+    }
+    (remote_window_scale, mss)
 }
 
 //======================================================================================================================
@@ -478,3 +816,174 @@ impl<N: NetworkRuntime> DerefMut for SharedPassiveSocket<N> {
         self.0.deref_mut()
     }
 }
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        inetstack::test_helpers::{
+            self,
+            engine::{
+                SharedEngine,
+                DEFAULT_TIMEOUT,
+            },
+        },
+        runtime::{
+            memory::DemiBuffer,
+            network::drops::DropReason,
+            queue::{
+                OperationResult,
+                QDesc,
+                QToken,
+            },
+        },
+    };
+    use ::anyhow::Result;
+    use ::std::{
+        net::SocketAddrV4,
+        time::{
+            Duration,
+            Instant,
+        },
+    };
+
+    /// Tests that a connection request which arrives once the backlog is full is answered with a SYN cookie, and that
+    /// the connection that the cookie establishes carries data.
+    #[test]
+    fn syn_cookie_backlog_full() -> Result<()> {
+        let now: Instant = Instant::now();
+        let mut alice: SharedEngine = test_helpers::new_alice(now);
+        let server_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, 80);
+        let server_fd: QDesc = alice.tcp_socket()?;
+        alice.tcp_bind(server_fd, server_addr)?;
+        alice.tcp_listen(server_fd, 1)?;
+
+        // The first connection request takes the only slot of the backlog, so the second one gets a SYN cookie.
+        let mut accepted: Vec<(QDesc, QDesc)> = Vec::new();
+        for _ in 0..2 {
+            let accept_qt: QToken = alice.tcp_accept(server_fd)?;
+            let client_fd: QDesc = alice.tcp_socket()?;
+            let connect_qt: QToken = alice.tcp_connect(client_fd, server_addr)?;
+            match alice.wait(connect_qt, DEFAULT_TIMEOUT)? {
+                (_, OperationResult::Connect) => {},
+                result => anyhow::bail!("connect() should succeed: {:?}", result),
+            };
+            match alice.wait(accept_qt, DEFAULT_TIMEOUT)? {
+                (_, OperationResult::Accept((fd, _))) => accepted.push((client_fd, fd)),
+                result => anyhow::bail!("accept() should succeed: {:?}", result),
+            };
+        }
+
+        let (client_fd, accepted_fd): (QDesc, QDesc) = accepted[1];
+        let buf: DemiBuffer = DemiBuffer::from_slice(&[0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+        let push_qt: QToken = alice.tcp_push(client_fd, buf)?;
+        let pop_qt: QToken = alice.tcp_pop(accepted_fd)?;
+        match alice.wait(push_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Push(_)) => {},
+            result => anyhow::bail!("push() should succeed: {:?}", result),
+        };
+        match alice.wait(pop_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Pop(_, buf, _)) => crate::ensure_eq!(&buf[..], &[0x5a; 32][..]),
+            result => anyhow::bail!("pop() should succeed: {:?}", result),
+        };
+
+        Ok(())
+    }
+
+    /// Tests that connection requests from a source that exceeds its rate are dropped without an answer, and counted.
+    #[test]
+    fn admission_connection_rate() -> Result<()> {
+        let now: Instant = Instant::now();
+        let mut alice: SharedEngine = test_helpers::new_alice(now);
+        alice.set_tunable("admission.connection_rate", "1")?;
+        let server_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, 80);
+        let server_fd: QDesc = alice.tcp_socket()?;
+        alice.tcp_bind(server_fd, server_addr)?;
+        alice.tcp_listen(server_fd, 8)?;
+
+        // The first connection request is admitted, while the second one is dropped and left to retry.
+        let accept_qt: QToken = alice.tcp_accept(server_fd)?;
+        let client_fd: QDesc = alice.tcp_socket()?;
+        let connect_qt: QToken = alice.tcp_connect(client_fd, server_addr)?;
+        match alice.wait(connect_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Connect) => {},
+            result => anyhow::bail!("connect() should succeed: {:?}", result),
+        };
+        match alice.wait(accept_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Accept(_)) => {},
+            result => anyhow::bail!("accept() should succeed: {:?}", result),
+        };
+        let client_fd: QDesc = alice.tcp_socket()?;
+        let connect_qt: QToken = alice.tcp_connect(client_fd, server_addr)?;
+        match alice.wait(connect_qt, Duration::from_millis(10)) {
+            Err(e) if e.errno == libc::ETIMEDOUT => {},
+            result => anyhow::bail!("connect() should not complete: {:?}", result),
+        };
+        crate::ensure_eq!(alice.get_drop_stats(None)?.get(DropReason::Admission), 1);
+        crate::ensure_eq!(alice.get_tunable("admission.rate_limited")?, "1".to_string());
+
+        Ok(())
+    }
+
+    /// Tests that connections which do not fit in the flow table are refused, or evict the least recently used one.
+    #[test]
+    fn flow_table_limit() -> Result<()> {
+        let now: Instant = Instant::now();
+        let mut alice: SharedEngine = test_helpers::new_alice(now);
+        alice.set_tunable("flows.max_entries", "2")?;
+        alice.set_tunable("flows.policy", "refuse")?;
+        let server_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, 80);
+        let server_fd: QDesc = alice.tcp_socket()?;
+        alice.tcp_bind(server_fd, server_addr)?;
+        alice.tcp_listen(server_fd, 8)?;
+
+        // Both ends of the first connection take up the table.
+        let accept_qt: QToken = alice.tcp_accept(server_fd)?;
+        let first_fd: QDesc = alice.tcp_socket()?;
+        let connect_qt: QToken = alice.tcp_connect(first_fd, server_addr)?;
+        match alice.wait(connect_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Connect) => {},
+            result => anyhow::bail!("connect() should succeed: {:?}", result),
+        };
+        match alice.wait(accept_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Accept(_)) => {},
+            result => anyhow::bail!("accept() should succeed: {:?}", result),
+        };
+        crate::ensure_eq!(alice.get_tunable("flows.entries")?, "2".to_string());
+
+        // The second connection is refused.
+        let client_fd: QDesc = alice.tcp_socket()?;
+        let connect_qt: QToken = alice.tcp_connect(client_fd, server_addr)?;
+        match alice.wait(connect_qt, DEFAULT_TIMEOUT) {
+            Ok((_, OperationResult::Failed(e))) if e.errno == libc::ENOBUFS => {},
+            result => anyhow::bail!("connect() should fail: {:?}", result),
+        };
+
+        // The third connection evicts the first one, which is aborted.
+        alice.set_tunable("flows.policy", "evict_lru")?;
+        alice.advance_clock(now + Duration::from_secs(1));
+        let accept_qt: QToken = alice.tcp_accept(server_fd)?;
+        let client_fd: QDesc = alice.tcp_socket()?;
+        let connect_qt: QToken = alice.tcp_connect(client_fd, server_addr)?;
+        match alice.wait(connect_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Connect) => {},
+            result => anyhow::bail!("connect() should succeed: {:?}", result),
+        };
+        match alice.wait(accept_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Accept(_)) => {},
+            result => anyhow::bail!("accept() should succeed: {:?}", result),
+        };
+        let pop_qt: QToken = alice.tcp_pop(first_fd)?;
+        match alice.wait(pop_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Failed(_)) => {},
+            result => anyhow::bail!("pop() should fail: {:?}", result),
+        };
+        crate::ensure_eq!(alice.get_tunable("flows.entries")?, "2".to_string());
+        crate::ensure_eq!(alice.get_tunable("flows.refusals")?, "1".to_string());
+
+        Ok(())
+    }
+}
@@ -8,10 +8,24 @@
 use crate::{
     inetstack::protocols::{
         arp::SharedArpPeer,
+        ethernet2::{
+            EtherType2,
+            Ethernet2Header,
+        },
+        ip::IpProtocol,
         ipv4::Ipv4Header,
         tcp::{
+            established::frozen::FrozenConnection,
+            gro::{
+                Coalescer,
+                GroStats,
+                Segment,
+            },
             isn_generator::IsnGenerator,
-            segment::TcpHeader,
+            segment::{
+                TcpHeader,
+                TcpSegment,
+            },
             socket::SharedTcpSocket,
             SeqNumber,
         },
@@ -21,6 +35,7 @@ use crate::{
         memory::DemiBuffer,
         network::{
             config::TcpConfig,
+            drops::DropReason,
             socket::SocketId,
             types::MacAddress,
             NetworkRuntime,
@@ -29,6 +44,7 @@ use crate::{
         SharedDemiRuntime,
         SharedObject,
     },
+    trace_event,
 };
 use ::futures::channel::mpsc;
 use ::rand::{
@@ -59,12 +75,13 @@ pub struct TcpPeer<N: NetworkRuntime> {
     isn_generator: IsnGenerator,
     transport: N,
     local_link_addr: MacAddress,
-    local_ipv4_addr: Ipv4Addr,
     tcp_config: TcpConfig,
     arp: SharedArpPeer<N>,
     rng: SmallRng,
     dead_socket_tx: mpsc::UnboundedSender<QDesc>,
     addresses: HashMap<SocketId, SharedTcpSocket<N>>,
+    /// Coalesces received segments, if enabled in [tcp_config].
+    coalescer: Coalescer,
 }
 
 #[derive(Clone)]
@@ -79,7 +96,6 @@ impl<N: NetworkRuntime> SharedTcpPeer<N> {
         runtime: SharedDemiRuntime,
         transport: N,
         local_link_addr: MacAddress,
-        local_ipv4_addr: Ipv4Addr,
         tcp_config: TcpConfig,
         arp: SharedArpPeer<N>,
         rng_seed: [u8; 32],
@@ -92,12 +108,12 @@ impl<N: NetworkRuntime> SharedTcpPeer<N> {
             runtime,
             transport,
             local_link_addr,
-            local_ipv4_addr,
             tcp_config,
             arp,
             rng,
             dead_socket_tx: tx,
             addresses: HashMap::<SocketId, SharedTcpSocket<N>>::new(),
+            coalescer: Coalescer::default(),
         })))
     }
 
@@ -139,9 +155,12 @@ impl<N: NetworkRuntime> SharedTcpPeer<N> {
         // Wait for accept to complete.
         match socket.accept().await {
             Ok(socket) => {
-                self.addresses.insert(SocketId::Active(socket.local().unwrap(), socket.remote().unwrap()), socket.clone());
+                self.addresses.insert(
+                    SocketId::Active(socket.local().unwrap(), socket.remote().unwrap()),
+                    socket.clone(),
+                );
                 Ok(socket)
-            }
+            },
             Err(e) => Err(e),
         }
     }
@@ -153,7 +172,8 @@ impl<N: NetworkRuntime> SharedTcpPeer<N> {
             Some(addr) => addr,
             None => {
                 let local_port: u16 = self.runtime.alloc_ephemeral_port()?;
-                SocketAddrV4::new(self.local_ipv4_addr, local_port)
+                let device: Option<usize> = socket.options().device();
+                SocketAddrV4::new(self.arp.source_addr_via(*remote.ip(), device), local_port)
             },
         };
         // Insert the connection to receive incoming packets for this address pair.
@@ -237,42 +257,147 @@ impl<N: NetworkRuntime> SharedTcpPeer<N> {
         Ok(())
     }
 
+    /// Freezes the connection of a TCP socket into a blob, so that it can be thawed in another instance of the stack.
+    /// The connection stops here without letting our peer know, and its address pair is released.
+    pub fn freeze(&mut self, socket: &mut SharedTcpSocket<N>) -> Result<Vec<u8>, Fail> {
+        let (frozen, socket_id): (FrozenConnection, SocketId) = socket.freeze()?;
+        self.addresses.remove(&socket_id);
+        self.free_ephemeral_port(&socket_id);
+        Ok(frozen.serialize())
+    }
+
+    /// Thaws a connection that was frozen into [blob] in another instance of the stack.
+    pub fn thaw(&mut self, blob: &[u8]) -> Result<SharedTcpSocket<N>, Fail> {
+        let frozen: FrozenConnection = FrozenConnection::deserialize(blob)?;
+        let socket_id: SocketId = SocketId::Active(frozen.local, frozen.remote);
+        if self.addresses.contains_key(&socket_id) {
+            let cause: String = format!(
+                "there is already a connection on this address pair (local={:?}, remote={:?})",
+                frozen.local, frozen.remote
+            );
+            error!("thaw(): {}", cause);
+            return Err(Fail::new(libc::EADDRINUSE, &cause));
+        }
+        // Keep the ephemeral port of the connection from being allocated to another one.
+        let port: u16 = frozen.local.port();
+        if SharedDemiRuntime::is_private_ephemeral_port(port) {
+            self.runtime.reserve_ephemeral_port(port)?;
+        }
+
+        let mut socket: SharedTcpSocket<N> = self.socket()?;
+        if let Err(e) = socket.thaw(&frozen) {
+            self.free_ephemeral_port(&socket_id);
+            return Err(e);
+        }
+        self.addresses.insert(socket_id, socket.clone());
+        Ok(socket)
+    }
+
     /// Processes an incoming TCP segment.
     pub fn receive(&mut self, ip_hdr: Ipv4Header, buf: DemiBuffer) {
         let (tcp_hdr, data): (TcpHeader, DemiBuffer) =
             match TcpHeader::parse(&ip_hdr, buf, self.tcp_config.get_rx_checksum_offload()) {
                 Ok(result) => result,
                 Err(e) => {
-                    let cause: String = format!("invalid tcp header: {:?}", e);
-                    error!("receive(): {}", &cause);
+                    self.runtime.record_drop(DropReason::from_parse_error(&e));
+                    trace_event!("drop", "malformed_tcp", cause = e.cause);
                     return;
                 },
             };
         debug!("TCP received {:?}", tcp_hdr);
+
+        if self.tcp_config.get_gro() {
+            for (ip_hdr, tcp_hdr, data) in self.coalescer.push(ip_hdr, tcp_hdr, data) {
+                self.deliver(ip_hdr, tcp_hdr, data);
+            }
+        } else {
+            self.deliver(ip_hdr, tcp_hdr, data);
+        }
+    }
+
+    /// Delivers the segments that are held for coalescing. This should be called at the end of every batch of
+    /// received packets.
+    pub fn flush_coalesced(&mut self) {
+        if !self.tcp_config.get_gro() {
+            return;
+        }
+        let segments: Vec<Segment> = self.coalescer.flush();
+        for (ip_hdr, tcp_hdr, data) in segments {
+            self.deliver(ip_hdr, tcp_hdr, data);
+        }
+    }
+
+    /// Sets the TCP tunable [name] to [value]. Changes apply to connections that are created from now on.
+    pub fn set_tunable(&mut self, name: &str, value: &str) -> Result<(), Fail> {
+        self.tcp_config.set_tunable(name, value)
+    }
+
+    /// Gets the value of the TCP tunable [name].
+    pub fn get_tunable(&self, name: &str) -> Result<String, Fail> {
+        self.tcp_config.get_tunable(name)
+    }
+
+    /// Gets the receive coalescing statistics.
+    pub fn get_gro_stats(&self) -> GroStats {
+        self.coalescer.get_stats()
+    }
+
+    /// Dispatches an incoming TCP segment to its socket.
+    fn deliver(&mut self, ip_hdr: Ipv4Header, tcp_hdr: TcpHeader, data: DemiBuffer) {
         let local: SocketAddrV4 = SocketAddrV4::new(ip_hdr.get_dest_addr(), tcp_hdr.dst_port);
         let remote: SocketAddrV4 = SocketAddrV4::new(ip_hdr.get_src_addr(), tcp_hdr.src_port);
 
         if remote.ip().is_broadcast() || remote.ip().is_multicast() || remote.ip().is_unspecified() {
-            let cause: String = format!("invalid remote address (remote={})", remote.ip());
-            error!("receive(): {}", &cause);
+            self.runtime.record_drop(DropReason::InvalidAddress);
+            trace_event!("drop", "invalid_remote", remote = remote);
             return;
         }
 
         // Retrieve the queue descriptor based on the incoming segment.
-        let socket: &mut SharedTcpSocket<N> = match self.addresses.get_mut(&SocketId::Active(local, remote)) {
-            Some(socket) => socket,
-            None => match self.addresses.get_mut(&SocketId::Passive(local)) {
-                Some(socket) => socket,
-                None => {
-                    let cause: String = format!("no queue descriptor for remote address (remote={})", remote.ip());
-                    error!("receive(): {}", &cause);
-                    return;
-                },
-            },
+        let socket: Option<&mut SharedTcpSocket<N>> = match self.addresses.get_mut(&SocketId::Active(local, remote)) {
+            Some(socket) => Some(socket),
+            None => self.addresses.get_mut(&SocketId::Passive(local)),
         };
 
         // Dispatch to further processing depending on the socket state.
-        socket.receive(ip_hdr, tcp_hdr, data)
+        match socket {
+            Some(socket) if socket.is_receiving() => socket.receive(ip_hdr, tcp_hdr, data),
+            // No connection takes this segment, so answer it with a reset.
+            _ => {
+                self.runtime.record_drop(DropReason::NoSocket);
+                trace_event!("drop", "no_socket", local = local, remote = remote);
+                self.send_rst(&ip_hdr, &tcp_hdr, data.len());
+            },
+        }
+    }
+
+    /// Sends a RST segment in answer to the segment with headers [ip_hdr] and [tcp_hdr] and [data_len] bytes of data,
+    /// which arrived at a connection that does not exist.
+    /// See: https://datatracker.ietf.org/doc/html/rfc9293#section-3.10.7.1
+    fn send_rst(&mut self, ip_hdr: &Ipv4Header, tcp_hdr: &TcpHeader, data_len: usize) {
+        let reset_hdr: TcpHeader = match tcp_hdr.reset_reply(data_len) {
+            Some(reset_hdr) => reset_hdr,
+            None => return,
+        };
+        let remote_link_addr: MacAddress = match self.arp.try_query(ip_hdr.get_src_addr()) {
+            Some(link_addr) => link_addr,
+            None => {
+                warn!("send_rst(): missing ARP entry (remote={})", ip_hdr.get_src_addr());
+                return;
+            },
+        };
+        debug!("send_rst(): sending RST {:?}", reset_hdr);
+
+        let segment: TcpSegment = TcpSegment {
+            ethernet2_hdr: Ethernet2Header::new(remote_link_addr, self.local_link_addr, EtherType2::Ipv4),
+            ipv4_hdr: Ipv4Header::new(ip_hdr.get_dest_addr(), ip_hdr.get_src_addr(), IpProtocol::TCP),
+            tcp_hdr: reset_hdr,
+            data: None,
+            tx_checksum_offload: self.tcp_config.get_tx_checksum_offload(),
+            tso_segment_size: None,
+            signer: None,
+        };
+        self.transport.transmit_one(Box::new(segment));
     }
 }
 
@@ -4,24 +4,28 @@
 use crate::{
     expect_ok,
     inetstack::protocols::{
+        checksum,
         ethernet2::Ethernet2Header,
         ip::IpProtocol,
         ipv4::Ipv4Header,
-        tcp::SeqNumber,
+        tcp::{
+            authentication::TCP_AO_MAC_SIZE,
+            signature::TcpSigner,
+            SeqNumber,
+        },
     },
     runtime::{
+        crypto::md5::MD5_DIGEST_SIZE,
         fail::Fail,
         memory::DemiBuffer,
         network::PacketBuf,
     },
 };
+use ::arrayvec::ArrayVec;
 use ::libc::EBADMSG;
-use ::std::{
-    io::{
-        Cursor,
-        Read,
-    },
-    slice::ChunksExact,
+use ::std::io::{
+    Cursor,
+    Read,
 };
 
 pub const MIN_TCP_HEADER_SIZE: usize = 20;
@@ -34,6 +38,10 @@ pub struct TcpSegment {
     pub tcp_hdr: TcpHeader,
     pub data: Option<DemiBuffer>,
     pub tx_checksum_offload: bool,
+    /// Size of the segments that the network runtime should split the data into, if any.
+    pub tso_segment_size: Option<usize>,
+    /// Signs the segment, if any. The header must then carry the option that the signer fills in.
+    pub signer: Option<TcpSigner>,
 }
 
 impl PacketBuf for TcpSegment {
@@ -72,6 +80,7 @@ impl PacketBuf for TcpSegment {
             &self.ipv4_hdr,
             payload,
             self.tx_checksum_offload,
+            self.signer.as_ref(),
         );
     }
 
@@ -81,6 +90,14 @@ impl PacketBuf for TcpSegment {
             None => None,
         }
     }
+
+    fn tso_segment_size(&self) -> Option<usize> {
+        self.tso_segment_size
+    }
+
+    fn priority(&self) -> Option<u8> {
+        self.ethernet2_hdr.priority()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -104,6 +121,14 @@ pub enum TcpOptions2 {
         sender_timestamp: u32,
         echo_timestamp: u32,
     },
+    /// Reference: https://datatracker.ietf.org/doc/html/rfc2385#section-3.0
+    Md5Signature([u8; MD5_DIGEST_SIZE]),
+    /// Reference: https://datatracker.ietf.org/doc/html/rfc5925#section-2.2
+    Authentication {
+        key_id: u8,
+        rnext_key_id: u8,
+        mac: [u8; TCP_AO_MAC_SIZE],
+    },
 }
 
 impl TcpOptions2 {
@@ -117,6 +142,8 @@ impl TcpOptions2 {
             SelectiveAcknowlegementPermitted => 2,
             SelectiveAcknowlegement { num_sacks, .. } => 2 + 8 * num_sacks,
             Timestamp { .. } => 10,
+            Md5Signature(..) => 2 + MD5_DIGEST_SIZE,
+            Authentication { .. } => 4 + TCP_AO_MAC_SIZE,
         }
     }
 
@@ -164,11 +191,29 @@ impl TcpOptions2 {
                 buf[6..10].copy_from_slice(&echo_timestamp.to_be_bytes());
                 10
             },
+            Md5Signature(signature) => {
+                buf[0] = 19;
+                buf[1] = 2 + MD5_DIGEST_SIZE as u8;
+                buf[2..(2 + MD5_DIGEST_SIZE)].copy_from_slice(signature);
+                2 + MD5_DIGEST_SIZE
+            },
+            Authentication {
+                key_id,
+                rnext_key_id,
+                mac,
+            } => {
+                buf[0] = 29;
+                buf[1] = 4 + TCP_AO_MAC_SIZE as u8;
+                buf[2] = *key_id;
+                buf[3] = *rnext_key_id;
+                buf[4..(4 + TCP_AO_MAC_SIZE)].copy_from_slice(mac);
+                4 + TCP_AO_MAC_SIZE
+            },
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct TcpHeader {
     pub src_port: u16,
     pub dst_port: u16,
@@ -198,6 +243,10 @@ pub struct TcpHeader {
 
     pub num_options: usize,
     pub option_list: [TcpOptions2; MAX_TCP_OPTIONS],
+
+    // Raw bytes of the header, which are only kept for signed segments, as the signature is checked against them. The
+    // MAC of the authentication option is zeroed, as it does not cover itself.
+    pub signed_header: Option<ArrayVec<u8, MAX_TCP_HEADER_SIZE>>,
 }
 
 impl TcpHeader {
@@ -222,6 +271,7 @@ impl TcpHeader {
             urgent_pointer: 0,
             num_options: 0,
             option_list: [TcpOptions2::NoOperation; MAX_TCP_OPTIONS],
+            signed_header: None,
         }
     }
 
@@ -276,6 +326,7 @@ impl TcpHeader {
 
         let mut num_options: usize = 0;
         let mut option_list: [TcpOptions2; MAX_TCP_OPTIONS] = [TcpOptions2::NoOperation; MAX_TCP_OPTIONS];
+        let mut mac_pos: Option<usize> = None;
 
         if data_offset > MIN_TCP_HEADER_SIZE {
             let mut option_rdr: Cursor<&[u8]> = Cursor::new(&hdr_buf[MIN_TCP_HEADER_SIZE..data_offset]);
@@ -356,6 +407,32 @@ impl TcpHeader {
                             echo_timestamp,
                         }
                     },
+                    19 => {
+                        let mut temp: [u8; 1] = [0; 1];
+                        option_rdr.read_exact(&mut temp)?;
+                        let option_length: u8 = temp[0];
+                        if option_length as usize != 2 + MD5_DIGEST_SIZE {
+                            return Err(Fail::new(EBADMSG, "TCP MD5 signature size was not 18"));
+                        }
+                        let mut signature: [u8; MD5_DIGEST_SIZE] = [0; MD5_DIGEST_SIZE];
+                        option_rdr.read_exact(&mut signature)?;
+                        TcpOptions2::Md5Signature(signature)
+                    },
+                    29 => {
+                        let mut temp: [u8; 3] = [0; 3];
+                        option_rdr.read_exact(&mut temp)?;
+                        if temp[0] as usize != 4 + TCP_AO_MAC_SIZE {
+                            return Err(Fail::new(EBADMSG, "TCP authentication option size was not 16"));
+                        }
+                        mac_pos = Some(MIN_TCP_HEADER_SIZE + option_rdr.position() as usize);
+                        let mut mac: [u8; TCP_AO_MAC_SIZE] = [0; TCP_AO_MAC_SIZE];
+                        option_rdr.read_exact(&mut mac)?;
+                        TcpOptions2::Authentication {
+                            key_id: temp[1],
+                            rnext_key_id: temp[2],
+                            mac,
+                        }
+                    },
                     _ => return Err(Fail::new(EBADMSG, "invalid TCP option")),
                 };
                 if num_options >= option_list.len() {
@@ -366,6 +443,20 @@ impl TcpHeader {
             }
         }
 
+        let signed: bool = option_list[..num_options].iter().any(|option| {
+            matches!(
+                option,
+                TcpOptions2::Md5Signature(..) | TcpOptions2::Authentication { .. }
+            )
+        });
+        let signed_header: Option<ArrayVec<u8, MAX_TCP_HEADER_SIZE>> = signed.then(|| {
+            let mut signed_header: ArrayVec<u8, MAX_TCP_HEADER_SIZE> = hdr_buf.iter().copied().collect();
+            if let Some(pos) = mac_pos {
+                signed_header[pos..(pos + TCP_AO_MAC_SIZE)].fill(0);
+            }
+            signed_header
+        });
+
         let header: TcpHeader = Self {
             src_port,
             dst_port,
@@ -385,6 +476,7 @@ impl TcpHeader {
 
             num_options,
             option_list,
+            signed_header,
         };
         expect_ok!(
             buf.adjust(data_offset),
@@ -393,7 +485,16 @@ impl TcpHeader {
         Ok((header, buf))
     }
 
-    pub fn serialize(&self, buf: &mut [u8], ipv4_hdr: &Ipv4Header, data: &[u8], tx_checksum_offload: bool) {
+    /// Serializes the header into [buf]. If [signer] is set, the signature or authentication option of the header, which
+    /// must have one, is filled in by it.
+    pub fn serialize(
+        &self,
+        buf: &mut [u8],
+        ipv4_hdr: &Ipv4Header,
+        data: &[u8],
+        tx_checksum_offload: bool,
+        signer: Option<&TcpSigner>,
+    ) {
         let fixed_buf: &mut [u8; MIN_TCP_HEADER_SIZE] = (&mut buf[..MIN_TCP_HEADER_SIZE]).try_into().unwrap();
         fixed_buf[0..2].copy_from_slice(&self.src_port.to_be_bytes());
         fixed_buf[2..4].copy_from_slice(&self.dst_port.to_be_bytes());
@@ -436,7 +537,13 @@ impl TcpHeader {
         fixed_buf[18..20].copy_from_slice(&self.urgent_pointer.to_be_bytes());
 
         let mut cur_pos: usize = MIN_TCP_HEADER_SIZE;
+        let mut signature_pos: Option<usize> = None;
         for i in 0..self.num_options {
+            match self.option_list[i] {
+                TcpOptions2::Md5Signature(..) => signature_pos = Some(cur_pos + 2),
+                TcpOptions2::Authentication { .. } => signature_pos = Some(cur_pos + 4),
+                _ => {},
+            }
             let bytes_written = self.option_list[i].serialize(&mut buf[cur_pos..]);
             cur_pos += bytes_written;
         }
@@ -450,6 +557,11 @@ impl TcpHeader {
             *byte = 0;
         }
 
+        // Sign the segment before the checksum, as the checksum covers the signature.
+        if let (Some(signer), Some(pos)) = (signer, signature_pos) {
+            signer.sign(ipv4_hdr.get_src_addr(), ipv4_hdr.get_dest_addr(), buf, pos, data);
+        }
+
         // Alright, we've fully filled out the header, time to compute the checksum.
         if !tx_checksum_offload {
             let checksum: u16 = tcp_checksum(ipv4_hdr, &buf[..], data);
@@ -484,6 +596,48 @@ impl TcpHeader {
         self.option_list[self.num_options] = option;
         self.num_options += 1;
     }
+
+    /// Makes room for the signature of the segment, which is filled in when the segment is serialized with a key.
+    pub fn push_md5_signature(&mut self) {
+        self.push_option(TcpOptions2::Md5Signature([0; MD5_DIGEST_SIZE]));
+    }
+
+    /// Builds the header of the RST segment that answers this segment, which carries [data_len] bytes of data, when
+    /// no connection can take it. If this segment has an ACK, the reset takes its sequence number from the ACK field.
+    /// Otherwise, the reset has sequence number zero and acknowledges everything that this segment occupies. Returns
+    /// `None` if this segment is a reset itself, as resets are never answered.
+    /// Reference: https://datatracker.ietf.org/doc/html/rfc9293#section-3.5.2
+    pub fn reset_reply(&self, data_len: usize) -> Option<TcpHeader> {
+        if self.rst {
+            return None;
+        }
+
+        let mut header: TcpHeader = TcpHeader::new(self.dst_port, self.src_port);
+        header.rst = true;
+        if self.ack {
+            header.seq_num = self.ack_num;
+        } else {
+            let seg_len: u32 = data_len as u32 + self.syn as u32 + self.fin as u32;
+            header.ack = true;
+            header.ack_num = self.seq_num + SeqNumber::from(seg_len);
+        }
+        Some(header)
+    }
+
+    /// Builds the header of a segment that carries the data of this SYN segment on its own, right after the SYN, and
+    /// acknowledges [ack_num]. A connection holds on to the data of a SYN segment until the handshake completes and
+    /// then hands it over to the established socket as if it arrived in such a segment.
+    /// Reference: https://datatracker.ietf.org/doc/html/rfc9293#section-3.10.7.3
+    pub fn syn_data_header(&self, ack_num: SeqNumber) -> TcpHeader {
+        let mut header: TcpHeader = self.clone();
+        header.syn = false;
+        header.seq_num = self.seq_num + SeqNumber::from(1);
+        header.ack = true;
+        header.ack_num = ack_num;
+        header.num_options = 0;
+        header.signed_header = None;
+        header
+    }
 }
 
 fn tcp_checksum(ipv4_header: &Ipv4Header, header: &[u8], data: &[u8]) -> u16 {
@@ -501,7 +655,7 @@ fn tcp_checksum(ipv4_header: &Ipv4Header, header: &[u8], data: &[u8]) -> u16 {
     state += u16::from_be_bytes([dst_octets[2], dst_octets[3]]) as u32;
 
     // 3) 1 byte of zeros and TCP protocol number (1 byte)
-    state += u16::from_be_bytes([0, IpProtocol::TCP as u8]) as u32;
+    state += u16::from_be_bytes([0, u8::from(IpProtocol::TCP)]) as u32;
 
     // 4) TCP segment length (2 bytes)
     state += (header.len() + data.len()) as u32;
@@ -544,15 +698,9 @@ fn tcp_checksum(ipv4_header: &Ipv4Header, header: &[u8], data: &[u8]) -> u16 {
         }
     }
 
-    // Finally, checksum the data itself.
-    let mut chunks_iter: ChunksExact<u8> = data.chunks_exact(2);
-    while let Some(chunk) = chunks_iter.next() {
-        state += u16::from_be_bytes([chunk[0], chunk[1]]) as u32;
-    }
-    // Since the data may have an odd number of bytes, pad the last byte with zero if necessary.
-    if let Some(&b) = chunks_iter.remainder().get(0) {
-        state += u16::from_be_bytes([b, 0]) as u32;
-    }
+    // Finally, checksum the data itself. Since the data may have an odd number of bytes, the last byte is padded with
+    // zero if necessary.
+    state += checksum::sum(data);
 
     // NB: We don't need to subtract out 0xFFFF as we accumulate the sum. Since we use a u32 for
     // intermediate state, we would need 2^16 additions to overflow. This is well beyond the reach
@@ -0,0 +1,213 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! TCP MD5 signature option (RFC 2385), which protects the segments of a connection with a key that is shared with
+//! the peer. It is mostly used between BGP speakers. Connections that use the authentication option instead are signed
+//! through the same hooks.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::protocols::{
+        ip::IpProtocol,
+        ipv4::Ipv4Header,
+        tcp::{
+            authentication::{
+                TcpAoConnection,
+                TcpAoSigner,
+                AO_OPTION_SPACE,
+            },
+            segment::{
+                TcpHeader,
+                TcpOptions2,
+                MIN_TCP_HEADER_SIZE,
+            },
+        },
+    },
+    runtime::{
+        crypto::md5::{
+            Md5,
+            MD5_DIGEST_SIZE,
+        },
+        fail::Fail,
+        network::socket::option::TcpMd5Key,
+    },
+};
+use ::std::net::Ipv4Addr;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Space that the signature option takes in the header of a segment, once the header is padded. Segments of signed
+/// connections carry this much less data.
+pub const MD5_SIGNATURE_SPACE: usize = 20;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Signature keys of a socket, each of which is shared with a group of peers.
+#[derive(Clone, Debug, Default)]
+pub struct TcpMd5Keys {
+    keys: Vec<TcpMd5Key>,
+}
+
+/// How a connection protects its segments.
+#[derive(Clone)]
+pub enum TcpSigning {
+    Md5(TcpMd5Key),
+    Ao(TcpAoConnection),
+}
+
+/// Signs an outgoing segment once it is serialized.
+#[derive(Clone)]
+pub enum TcpSigner {
+    Md5(TcpMd5Key),
+    Ao(TcpAoSigner),
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl TcpMd5Keys {
+    /// Adds [key], which replaces the key of the same peers. Empty keys remove the key of their peers.
+    pub fn set(&mut self, key: TcpMd5Key) {
+        self.keys.retain(|other| !other.same_peers(&key));
+        if !key.key().is_empty() {
+            self.keys.push(key);
+        }
+    }
+
+    /// Gets the key of the peer [addr], if any. The key of the smallest group of peers wins.
+    pub fn lookup(&self, addr: Ipv4Addr) -> Option<TcpMd5Key> {
+        self.keys
+            .iter()
+            .filter(|key| key.matches(addr))
+            .max_by_key(|key| key.prefix_len())
+            .copied()
+    }
+
+    /// Checks the signature of an incoming segment with headers [ip_hdr] and [tcp_hdr] and payload [data]. Segments
+    /// from peers that share a key must be signed with it, and other peers must not sign their segments.
+    pub fn check(&self, ip_hdr: &Ipv4Header, tcp_hdr: &TcpHeader, data: &[u8]) -> Result<(), Fail> {
+        let signature: Option<[u8; MD5_DIGEST_SIZE]> = tcp_hdr.iter_options().find_map(|option| match option {
+            TcpOptions2::Md5Signature(signature) => Some(*signature),
+            _ => None,
+        });
+        let key: Option<TcpMd5Key> = self.lookup(ip_hdr.get_src_addr());
+        match (key, signature, tcp_hdr.signed_header.as_deref()) {
+            (None, None, _) => Ok(()),
+            (Some(key), Some(signature), Some(header))
+                if md5_signature(ip_hdr.get_src_addr(), ip_hdr.get_dest_addr(), header, data, key.key())
+                    == signature =>
+            {
+                Ok(())
+            },
+            (Some(_), Some(_), _) => Err(Fail::new(libc::EBADMSG, "TCP signature mismatch")),
+            (Some(_), None, _) => Err(Fail::new(libc::EBADMSG, "TCP signature missing")),
+            (None, Some(_), _) => Err(Fail::new(libc::EBADMSG, "unexpected TCP signature")),
+        }
+    }
+}
+
+impl TcpSigning {
+    /// Gets the space that the option that protects segments takes in their header.
+    pub fn space(&self) -> usize {
+        match self {
+            TcpSigning::Md5(_) => MD5_SIGNATURE_SPACE,
+            TcpSigning::Ao(_) => AO_OPTION_SPACE,
+        }
+    }
+
+    /// Adds the option that protects segments to the header [header] of an outgoing segment, and returns the signer
+    /// that fills it in.
+    pub fn sign(&mut self, header: &mut TcpHeader) -> TcpSigner {
+        match self {
+            TcpSigning::Md5(key) => {
+                header.push_md5_signature();
+                TcpSigner::Md5(*key)
+            },
+            TcpSigning::Ao(connection) => connection.sign(header),
+        }
+    }
+}
+
+impl TcpSigner {
+    /// Fills in the signature or MAC at [pos] in the serialized TCP header [header] of a segment from [src_addr] to
+    /// [dst_addr] with payload [data].
+    pub fn sign(&self, src_addr: Ipv4Addr, dst_addr: Ipv4Addr, header: &mut [u8], pos: usize, data: &[u8]) {
+        match self {
+            TcpSigner::Md5(key) => {
+                let signature: [u8; MD5_DIGEST_SIZE] = md5_signature(src_addr, dst_addr, header, data, key.key());
+                header[pos..(pos + MD5_DIGEST_SIZE)].copy_from_slice(&signature);
+            },
+            TcpSigner::Ao(signer) => signer.sign(src_addr, dst_addr, header, pos, data),
+        }
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Computes the signature of a segment from [src_addr] to [dst_addr] with header [header], options included, and
+/// payload [data] under [key]. The signature covers a pseudo-header, the fixed part of the TCP header with a zero
+/// checksum, the payload and the key, in this order.
+pub fn md5_signature(
+    src_addr: Ipv4Addr,
+    dst_addr: Ipv4Addr,
+    header: &[u8],
+    data: &[u8],
+    key: &[u8],
+) -> [u8; MD5_DIGEST_SIZE] {
+    let segment_len: u16 = (header.len() + data.len()) as u16;
+    let mut hash: Md5 = Md5::new();
+    hash.update(&src_addr.octets());
+    hash.update(&dst_addr.octets());
+    hash.update(&[0, u8::from(IpProtocol::TCP)]);
+    hash.update(&segment_len.to_be_bytes());
+    hash.update(&header[..16]);
+    hash.update(&[0, 0]);
+    hash.update(&header[18..MIN_TCP_HEADER_SIZE]);
+    hash.update(data);
+    hash.update(key);
+    hash.finalize()
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::TcpMd5Keys;
+    use crate::runtime::network::socket::option::TcpMd5Key;
+    use ::anyhow::Result;
+    use ::std::net::Ipv4Addr;
+
+    /// Tests that the key of the smallest group of peers wins, and that empty keys remove the key of their peers.
+    #[test]
+    fn test_md5_keys_lookup() -> Result<()> {
+        let mut keys: TcpMd5Keys = TcpMd5Keys::default();
+        keys.set(TcpMd5Key::new(Ipv4Addr::new(10, 0, 0, 0), 8, b"subnet")?);
+        keys.set(TcpMd5Key::new(Ipv4Addr::new(10, 0, 0, 2), 32, b"host")?);
+        crate::ensure_eq!(TcpMd5Key::new(Ipv4Addr::new(10, 0, 0, 2), 33, b"host").is_err(), true);
+        crate::ensure_eq!(TcpMd5Key::new(Ipv4Addr::new(10, 0, 0, 2), 32, &[0; 81]).is_err(), true);
+
+        let lookup = |keys: &TcpMd5Keys, addr: Ipv4Addr| keys.lookup(addr).map(|key| key.key().to_vec());
+        crate::ensure_eq!(lookup(&keys, Ipv4Addr::new(10, 0, 0, 2)), Some(b"host".to_vec()));
+        crate::ensure_eq!(lookup(&keys, Ipv4Addr::new(10, 1, 0, 3)), Some(b"subnet".to_vec()));
+        crate::ensure_eq!(lookup(&keys, Ipv4Addr::new(192, 168, 0, 2)), None);
+
+        keys.set(TcpMd5Key::new(Ipv4Addr::new(10, 0, 0, 2), 32, b"rotated")?);
+        crate::ensure_eq!(lookup(&keys, Ipv4Addr::new(10, 0, 0, 2)), Some(b"rotated".to_vec()));
+        keys.set(TcpMd5Key::new(Ipv4Addr::new(10, 0, 0, 2), 32, b"")?);
+        crate::ensure_eq!(lookup(&keys, Ipv4Addr::new(10, 0, 0, 2)), Some(b"subnet".to_vec()));
+
+        Ok(())
+    }
+}
@@ -13,9 +13,21 @@ use crate::{
             ipv4::Ipv4Header,
             tcp::{
                 active_open::SharedActiveOpenSocket,
-                established::EstablishedSocket,
+                authentication::TcpAoKeys,
+                congestion_control::{
+                    self,
+                    CongestionControl,
+                },
+                established::{
+                    frozen::FrozenConnection,
+                    EstablishedSocket,
+                },
                 passive_open::SharedPassiveSocket,
                 segment::TcpHeader,
+                signature::{
+                    TcpMd5Keys,
+                    TcpSigning,
+                },
                 SeqNumber,
             },
         },
@@ -27,13 +39,31 @@ use crate::{
         memory::DemiBuffer,
         network::{
             config::TcpConfig,
-            socket::SocketId,
+            drops::{
+                DropReason,
+                DropRecord,
+                DropStats,
+            },
+            socket::{
+                option::{
+                    SocketOption,
+                    SocketOptions,
+                    TcpAoKey,
+                },
+                SocketId,
+            },
+            tcp_info::{
+                TcpEvent,
+                TcpInfo,
+                TcpState,
+            },
             NetworkRuntime,
         },
         QDesc,
         SharedDemiRuntime,
         SharedObject,
     },
+    trace_event,
 };
 use ::futures::channel::mpsc;
 use ::std::{
@@ -71,6 +101,11 @@ pub struct TcpSocket<N: NetworkRuntime> {
     network: N,
     local_link_addr: MacAddress,
     tcp_config: TcpConfig,
+    options: SocketOptions,
+    // Keys that sign the segments of connections with the peers that share them.
+    md5_keys: TcpMd5Keys,
+    // Keys that authenticate the segments of connections with the peers that share them.
+    ao_keys: TcpAoKeys,
     arp: SharedArpPeer<N>,
     dead_socket_tx: mpsc::UnboundedSender<QDesc>,
 }
@@ -98,6 +133,9 @@ impl<N: NetworkRuntime> SharedTcpSocket<N> {
             network,
             local_link_addr,
             tcp_config,
+            options: SocketOptions::default(),
+            md5_keys: TcpMd5Keys::default(),
+            ao_keys: TcpAoKeys::default(),
             arp,
             dead_socket_tx,
         }))
@@ -109,6 +147,9 @@ impl<N: NetworkRuntime> SharedTcpSocket<N> {
         network: N,
         local_link_addr: MacAddress,
         tcp_config: TcpConfig,
+        options: SocketOptions,
+        md5_keys: TcpMd5Keys,
+        ao_keys: TcpAoKeys,
         arp: SharedArpPeer<N>,
         dead_socket_tx: mpsc::UnboundedSender<QDesc>,
     ) -> Self {
@@ -120,6 +161,9 @@ impl<N: NetworkRuntime> SharedTcpSocket<N> {
             network,
             local_link_addr,
             tcp_config,
+            options,
+            md5_keys,
+            ao_keys,
             arp,
             dead_socket_tx,
         }))
@@ -145,6 +189,9 @@ impl<N: NetworkRuntime> SharedTcpSocket<N> {
             recv_queue.clone(),
             self.network.clone(),
             self.tcp_config.clone(),
+            self.options,
+            self.md5_keys.clone(),
+            self.ao_keys.clone(),
             self.local_link_addr,
             self.arp.clone(),
             self.dead_socket_tx.clone(),
@@ -168,6 +215,9 @@ impl<N: NetworkRuntime> SharedTcpSocket<N> {
             self.network.clone(),
             self.local_link_addr,
             self.tcp_config.clone(),
+            self.options,
+            self.md5_keys.clone(),
+            self.ao_keys.clone(),
             self.arp.clone(),
             self.dead_socket_tx.clone(),
         );
@@ -183,6 +233,11 @@ impl<N: NetworkRuntime> SharedTcpSocket<N> {
         let recv_queue: SharedAsyncQueue<(Ipv4Header, TcpHeader, DemiBuffer)> =
             SharedAsyncQueue::<(Ipv4Header, TcpHeader, DemiBuffer)>::default();
         let ack_queue: SharedAsyncQueue<usize> = SharedAsyncQueue::<usize>::default();
+        // The authentication option wins over the signature option.
+        let signing: Option<TcpSigning> = match self.ao_keys.connection(local, remote, local_isn, None) {
+            Some(connection) => Some(TcpSigning::Ao(connection)),
+            None => self.md5_keys.lookup(*remote.ip()).map(TcpSigning::Md5),
+        };
         // Create active socket.
         let socket: SharedActiveOpenSocket<N> = SharedActiveOpenSocket::new(
             local_isn,
@@ -193,6 +248,8 @@ impl<N: NetworkRuntime> SharedTcpSocket<N> {
             recv_queue.clone(),
             ack_queue,
             self.tcp_config.clone(),
+            self.options,
+            signing,
             self.local_link_addr,
             self.arp.clone(),
             self.dead_socket_tx.clone(),
@@ -205,9 +262,8 @@ impl<N: NetworkRuntime> SharedTcpSocket<N> {
     }
 
     pub async fn push(&mut self, buf: DemiBuffer) -> Result<(), Fail> {
-        // Send synchronously.
         match self.state {
-            SocketState::Established(ref mut socket) => socket.send(buf),
+            SocketState::Established(ref mut socket) => socket.send(buf).await,
             _ => unreachable!("State machine check should ensure that this socket is connected"),
         }
     }
@@ -253,15 +309,12 @@ impl<N: NetworkRuntime> SharedTcpSocket<N> {
         match self.state {
             // Closing an active socket.
             SocketState::Established(ref mut socket) => {
-                // TODO: Send a RST or something?
+                // Abortive close, so let our peer know that the connection is gone.
+                socket.abort();
                 Ok(Some(SocketId::Active(socket.endpoints().0, socket.endpoints().1)))
             },
-            // Closing a listening socket.
-            SocketState::Listening(_) => {
-                let cause: String = format!("cannot close a listening socket");
-                error!("do_close(): {}", &cause);
-                Err(Fail::new(libc::ENOTSUP, &cause))
-            },
+            // Closing a listening socket stops accepting connections, and drops those that were not accepted yet.
+            SocketState::Listening(ref socket) => Ok(Some(SocketId::Passive(socket.endpoint()))),
             // Closing a connecting socket.
             SocketState::Connecting(_) => {
                 let cause: String = format!("cannot close a connecting socket");
@@ -279,6 +332,253 @@ impl<N: NetworkRuntime> SharedTcpSocket<N> {
         }
     }
 
+    /// Freezes the connection of this socket, so that it can be thawed in another instance of the stack. This returns
+    /// the frozen connection along with the address pair that it used here.
+    pub fn freeze(&mut self) -> Result<(FrozenConnection, SocketId), Fail> {
+        match self.state {
+            // Traffic keys and sequence number extensions of the authentication option do not carry over.
+            SocketState::Established(ref socket) if socket.get_ao_info().is_some() => {
+                let cause: &str = "authenticated connections cannot be frozen";
+                error!("freeze(): {}", cause);
+                Err(Fail::new(libc::ENOTSUP, cause))
+            },
+            SocketState::Established(ref mut socket) => {
+                let frozen: FrozenConnection = socket.freeze()?;
+                Ok((frozen, SocketId::Active(socket.endpoints().0, socket.endpoints().1)))
+            },
+            _ => {
+                let cause: &str = "only established connections can be frozen";
+                error!("freeze(): {}", cause);
+                Err(Fail::new(libc::ENOTCONN, cause))
+            },
+        }
+    }
+
+    /// Turns this unbound socket into a connection that was frozen in another instance of the stack. Congestion
+    /// control and retransmission timers start over, as they do not carry over to another path.
+    pub fn thaw(&mut self, frozen: &FrozenConnection) -> Result<(), Fail> {
+        debug_assert!(matches!(self.state, SocketState::Unbound));
+        if !self.ao_keys.lookup(*frozen.remote.ip()).is_empty() {
+            let cause: String = format!("authenticated connections cannot be thawed (remote={})", frozen.remote);
+            error!("thaw(): {}", cause);
+            return Err(Fail::new(libc::ENOTSUP, &cause));
+        }
+        let recv_queue: SharedAsyncQueue<(Ipv4Header, TcpHeader, DemiBuffer)> =
+            SharedAsyncQueue::<(Ipv4Header, TcpHeader, DemiBuffer)>::default();
+        let mut socket: EstablishedSocket<N> = EstablishedSocket::new(
+            frozen.local,
+            frozen.remote,
+            self.runtime.clone(),
+            self.network.clone(),
+            recv_queue.clone(),
+            SharedAsyncQueue::<usize>::default(),
+            self.local_link_addr,
+            self.tcp_config.clone(),
+            self.options,
+            self.md5_keys.lookup(*frozen.remote.ip()).map(TcpSigning::Md5),
+            self.arp.clone(),
+            frozen.reader_next,
+            self.tcp_config.get_ack_delay_timeout(),
+            frozen.receive_buffer_size,
+            frozen.receive_window_scale as u32,
+            frozen.send_unacked,
+            frozen.send_window,
+            frozen.send_window_scale,
+            frozen.mss as usize,
+            congestion_control::None::new,
+            None,
+            self.dead_socket_tx.clone(),
+        )?;
+        if let Err(e) = socket.thaw(frozen) {
+            // Stop the connection without letting our peer know, so that it may still be thawed elsewhere.
+            let _ = socket.freeze();
+            return Err(e);
+        }
+        self.recv_queue = Some(recv_queue);
+        self.state = SocketState::Established(socket);
+        Ok(())
+    }
+
+    /// Sets [option] on this socket. The new value applies to the segments that are sent from now on.
+    pub fn set_socket_option(&mut self, option: SocketOption) -> Result<(), Fail> {
+        // Signature keys are kept apart from the other options, as there is one for each group of peers. Peers use
+        // either the signature option or the authentication option, but not both.
+        if let SocketOption::TCP_MD5SIG(key) = option {
+            if !key.key().is_empty()
+                && self
+                    .ao_keys
+                    .lookup(key.addr())
+                    .iter()
+                    .any(|other| key.matches(other.addr()))
+            {
+                let cause: String = format!("peers already share authentication keys (addr={})", key.addr());
+                error!("set_socket_option(): {}", cause);
+                return Err(Fail::new(libc::EKEYREJECTED, &cause));
+            }
+            self.md5_keys.set(key);
+            let md5_keys: TcpMd5Keys = self.md5_keys.clone();
+            match self.state {
+                SocketState::Listening(ref mut socket) => socket.set_md5_keys(md5_keys),
+                SocketState::Connecting(ref mut socket) => {
+                    socket.set_md5_key(md5_keys.lookup(*socket.endpoints().1.ip()))
+                },
+                SocketState::Established(ref mut socket) | SocketState::Closing(ref mut socket) => {
+                    socket.set_md5_key(md5_keys.lookup(*socket.endpoints().1.ip()))
+                },
+                SocketState::Unbound | SocketState::Bound(_) => (),
+            }
+            return Ok(());
+        }
+        match option {
+            SocketOption::TCP_AO_ADD_KEY(key) => return self.add_ao_key(key),
+            SocketOption::TCP_AO_DEL_KEY(key) => return self.remove_ao_key(key),
+            SocketOption::TCP_AO_INFO(info) => {
+                return match self.state {
+                    SocketState::Established(ref mut socket) | SocketState::Closing(ref mut socket) => {
+                        socket.set_ao_info(info)
+                    },
+                    _ => {
+                        let cause: &str = "socket has no authenticated connection";
+                        error!("set_socket_option(): {}", cause);
+                        Err(Fail::new(libc::ENOENT, cause))
+                    },
+                };
+            },
+            _ => (),
+        }
+        self.options.set(option)?;
+        let options: SocketOptions = self.options;
+        match self.state {
+            SocketState::Listening(ref mut socket) => socket.set_socket_options(options),
+            SocketState::Connecting(ref mut socket) => socket.set_socket_options(options),
+            SocketState::Established(ref mut socket) | SocketState::Closing(ref mut socket) => {
+                socket.set_socket_options(options)
+            },
+            SocketState::Unbound | SocketState::Bound(_) => (),
+        }
+        Ok(())
+    }
+
+    /// Adds the authentication key [key], which is shared with peers that do not use the signature option. Connections
+    /// with those peers that use the authentication option may switch to it.
+    fn add_ao_key(&mut self, key: TcpAoKey) -> Result<(), Fail> {
+        if self.md5_keys.lookup(key.addr()).is_some() {
+            let cause: String = format!("peers already share a signature key (addr={})", key.addr());
+            error!("add_ao_key(): {}", cause);
+            return Err(Fail::new(libc::EKEYREJECTED, &cause));
+        }
+        self.ao_keys.add(key)?;
+        self.update_ao_keys();
+        Ok(())
+    }
+
+    /// Removes the authentication key [key], unless a connection of this socket signs its segments with it.
+    fn remove_ao_key(&mut self, key: TcpAoKey) -> Result<(), Fail> {
+        let in_use: bool = match self.state {
+            SocketState::Connecting(ref socket) => socket.uses_ao_key(&key),
+            SocketState::Established(ref socket) | SocketState::Closing(ref socket) => socket.uses_ao_key(&key),
+            _ => false,
+        };
+        if in_use {
+            let cause: String = format!(
+                "authentication key is in use (send_id={}, recv_id={})",
+                key.send_id(),
+                key.recv_id()
+            );
+            error!("remove_ao_key(): {}", cause);
+            return Err(Fail::new(libc::EBUSY, &cause));
+        }
+        self.ao_keys.remove(&key)?;
+        self.update_ao_keys();
+        Ok(())
+    }
+
+    /// Hands the authentication keys of this socket over to its listening socket or connection.
+    fn update_ao_keys(&mut self) {
+        let ao_keys: TcpAoKeys = self.ao_keys.clone();
+        match self.state {
+            SocketState::Listening(ref mut socket) => socket.set_ao_keys(ao_keys),
+            SocketState::Connecting(ref mut socket) => socket.set_ao_keys(ao_keys.lookup(*socket.endpoints().1.ip())),
+            SocketState::Established(ref mut socket) | SocketState::Closing(ref mut socket) => {
+                socket.set_ao_keys(ao_keys.lookup(*socket.endpoints().1.ip()))
+            },
+            SocketState::Unbound | SocketState::Bound(_) => (),
+        }
+    }
+
+    /// Gets the current value of [option] on this socket. Congestion control and retransmission options that the
+    /// application did not set report the configuration of the stack.
+    pub fn get_socket_option(&self, option: SocketOption) -> Result<SocketOption, Fail> {
+        if let SocketOption::TCP_AO_INFO(_) = option {
+            return match self.state {
+                SocketState::Established(ref socket) | SocketState::Closing(ref socket) => match socket.get_ao_info() {
+                    Some(info) => Ok(SocketOption::TCP_AO_INFO(info)),
+                    None => Err(Fail::new(libc::ENOENT, "connection is not authenticated")),
+                },
+                _ => Err(Fail::new(libc::ENOENT, "socket has no authenticated connection")),
+            };
+        }
+        Ok(match self.options.get(option) {
+            SocketOption::TCP_INIT_CWND(0) => {
+                SocketOption::TCP_INIT_CWND(self.tcp_config.get_initial_cwnd().unwrap_or(0))
+            },
+            SocketOption::TCP_RTO_INIT_US(0) => {
+                SocketOption::TCP_RTO_INIT_US(self.tcp_config.get_rto_initial().as_micros() as u32)
+            },
+            SocketOption::TCP_RTO_MIN_US(0) => {
+                SocketOption::TCP_RTO_MIN_US(self.tcp_config.get_rto_min().as_micros() as u32)
+            },
+            SocketOption::TCP_RTO_MAX_MS(0) => {
+                SocketOption::TCP_RTO_MAX_MS(self.tcp_config.get_rto_max().as_millis() as u32)
+            },
+            option => option,
+        })
+    }
+
+    /// Gets the options that were set on this socket.
+    pub fn options(&self) -> SocketOptions {
+        self.options
+    }
+
+    /// Gets the number of incoming segments that were dropped on their way to this socket, for each reason. Only
+    /// listening sockets and connections count drops.
+    pub fn get_drop_stats(&self) -> DropStats {
+        match self.state {
+            SocketState::Listening(ref socket) => socket.get_drop_stats(),
+            SocketState::Established(ref socket) | SocketState::Closing(ref socket) => socket.get_drop_stats(),
+            SocketState::Unbound | SocketState::Bound(_) | SocketState::Connecting(_) => DropStats::default(),
+        }
+    }
+
+    /// Pops the oldest record from the error queue of this socket.
+    pub fn pop_drop_record(&mut self) -> Option<DropRecord> {
+        match self.state {
+            SocketState::Listening(ref mut socket) => socket.pop_drop_record(),
+            SocketState::Established(ref mut socket) | SocketState::Closing(ref mut socket) => socket.pop_drop_record(),
+            SocketState::Unbound | SocketState::Bound(_) | SocketState::Connecting(_) => None,
+        }
+    }
+
+    /// Gets a snapshot of the state of this socket.
+    pub fn get_tcp_info(&self) -> TcpInfo {
+        match self.state {
+            SocketState::Unbound | SocketState::Bound(_) => TcpInfo::with_state(TcpState::Closed),
+            SocketState::Listening(_) => TcpInfo::with_state(TcpState::Listen),
+            SocketState::Connecting(_) => TcpInfo::with_state(TcpState::SynSent),
+            SocketState::Established(ref socket) | SocketState::Closing(ref socket) => socket.get_tcp_info(),
+        }
+    }
+
+    /// Gets the recent retransmission and flow control events of this socket, which only connections have.
+    pub fn get_tcp_timeline(&self) -> Vec<TcpEvent> {
+        match self.state {
+            SocketState::Established(ref socket) | SocketState::Closing(ref socket) => socket.get_tcp_timeline(),
+            SocketState::Unbound | SocketState::Bound(_) | SocketState::Listening(_) | SocketState::Connecting(_) => {
+                Vec::new()
+            },
+        }
+    }
+
     pub fn remote_mss(&self) -> Result<usize, Fail> {
         match self.state {
             SocketState::Established(ref socket) => Ok(socket.remote_mss()),
@@ -293,6 +593,14 @@ impl<N: NetworkRuntime> SharedTcpSocket<N> {
         }
     }
 
+    /// Gets the sequence number of the next byte that is pushed to an established connection.
+    pub fn unsent_seq_no(&self) -> Result<u32, Fail> {
+        match self.state {
+            SocketState::Established(ref socket) => Ok(socket.unsent_seq_no().into()),
+            _ => Err(Fail::new(libc::ENOTCONN, "connection not established")),
+        }
+    }
+
     pub fn endpoints(&self) -> Result<(SocketAddrV4, SocketAddrV4), Fail> {
         match self.state {
             SocketState::Established(ref socket) => Ok(socket.endpoints()),
@@ -301,7 +609,36 @@ impl<N: NetworkRuntime> SharedTcpSocket<N> {
         }
     }
 
+    /// Returns whether this socket takes incoming segments, that is, whether it is listening or connected.
+    pub fn is_receiving(&self) -> bool {
+        self.recv_queue.is_some()
+    }
+
     pub fn receive(&mut self, ip_hdr: Ipv4Header, tcp_hdr: TcpHeader, buf: DemiBuffer) {
+        // Segments whose signature does not check out never reach the connection.
+        if let Err(e) = self.md5_keys.check(&ip_hdr, &tcp_hdr, &buf) {
+            let remote: SocketAddrV4 = SocketAddrV4::new(ip_hdr.get_src_addr(), tcp_hdr.src_port);
+            trace_event!("drop", "bad_signature", remote = remote, cause = e.cause);
+            self.record_drop(DropReason::BadSignature, remote, buf.len());
+            return;
+        }
+
+        // Listening sockets check the authentication option of connection requests themselves, as they know the
+        // initial sequence numbers of the handshakes that are in flight.
+        let authenticated: Result<(), Fail> = match self.state {
+            SocketState::Connecting(ref mut socket) => socket.check_authentication(&tcp_hdr, &buf),
+            SocketState::Established(ref mut socket) | SocketState::Closing(ref mut socket) => {
+                socket.check_authentication(&tcp_hdr, &buf)
+            },
+            SocketState::Unbound | SocketState::Bound(_) | SocketState::Listening(_) => Ok(()),
+        };
+        if let Err(e) = authenticated {
+            let remote: SocketAddrV4 = SocketAddrV4::new(ip_hdr.get_src_addr(), tcp_hdr.src_port);
+            trace_event!("drop", "bad_signature", remote = remote, cause = e.cause);
+            self.record_drop(DropReason::BadSignature, remote, buf.len());
+            return;
+        }
+
         // If this queue has an allocated receive queue, then direct the packet there.
         if let Some(recv_queue) = self.recv_queue.as_mut() {
             recv_queue.push((ip_hdr, tcp_hdr, buf));
@@ -309,6 +646,20 @@ impl<N: NetworkRuntime> SharedTcpSocket<N> {
         }
     }
 
+    /// Records a segment from [remote] with [len] bytes of payload that was dropped for [reason]. Only listening
+    /// sockets and connections count drops of their own.
+    fn record_drop(&mut self, reason: DropReason, remote: SocketAddrV4, len: usize) {
+        match self.state {
+            SocketState::Listening(ref mut socket) => socket.record_drop(reason, remote, len),
+            SocketState::Established(ref mut socket) | SocketState::Closing(ref mut socket) => {
+                socket.record_drop(reason, len)
+            },
+            SocketState::Unbound | SocketState::Bound(_) | SocketState::Connecting(_) => {
+                self.runtime.record_drop(reason)
+            },
+        }
+    }
+
     /// Returns the local address to which the target queue is bound.
     pub fn local(&self) -> Option<SocketAddrV4> {
         match self.state {
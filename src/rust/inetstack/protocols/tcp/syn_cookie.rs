@@ -0,0 +1,256 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::protocols::tcp::SeqNumber,
+    runtime::network::consts::MAX_WINDOW_SCALE,
+};
+use ::std::{
+    net::SocketAddrV4,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Period of the counter that stamps SYN cookies with the time at which they were sent.
+const COUNTER_PERIOD: Duration = Duration::from_secs(64);
+
+/// Number of periods of the counter that a SYN cookie stays valid for, besides the one in which it was sent.
+const MAX_COUNTER_AGE: u32 = 1;
+
+/// Approximations of the maximum segment size of our peer that fit in a SYN cookie. These are the values that show
+/// up the most in the wild.
+const MSS_TABLE: [usize; 4] = [536, 1220, 1440, 1460];
+
+/// Value of the window scale field of a SYN cookie when our peer does not scale its window.
+const NO_WINDOW_SCALE: u32 = 0xf;
+
+/// Layout of a SYN cookie, from the most significant bit: a 5-bit counter, a 2-bit index into [MSS_TABLE], a 4-bit
+/// window scale and a 21-bit hash.
+const COUNTER_SHIFT: u32 = 27;
+const COUNTER_MASK: u32 = 0x1f;
+const MSS_SHIFT: u32 = 25;
+const MSS_MASK: u32 = 0x3;
+const WINDOW_SCALE_SHIFT: u32 = 21;
+const WINDOW_SCALE_MASK: u32 = 0xf;
+const HASH_MASK: u32 = 0x1fffff;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Generates and validates SYN cookies, which let a listening socket answer a SYN segment without keeping any state
+/// until the handshake completes. The initial sequence number that goes in the SYN+ACK encodes approximations of the
+/// options in the SYN, and our peer echoes it back in the ACK that completes the handshake.
+pub struct SynCookieGenerator {
+    nonce: u32,
+    epoch: Instant,
+}
+
+/// Options of a connection request that were recovered from a valid SYN cookie.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SynCookie {
+    pub mss: usize,
+    pub window_scale: Option<u8>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl SynCookieGenerator {
+    pub fn new(nonce: u32, now: Instant) -> Self {
+        Self { nonce, epoch: now }
+    }
+
+    /// Generates the initial sequence number for the SYN+ACK that answers a SYN segment from [remote], which carried
+    /// [remote_isn] and the [mss] and [window_scale] options. The MSS is rounded down to the closest approximation.
+    pub fn generate(
+        &self,
+        now: Instant,
+        local: &SocketAddrV4,
+        remote: &SocketAddrV4,
+        remote_isn: SeqNumber,
+        mss: usize,
+        window_scale: Option<u8>,
+    ) -> SeqNumber {
+        let counter: u32 = self.counter(now);
+        let mss_index: u32 = MSS_TABLE.iter().rposition(|m| *m <= mss).unwrap_or(0) as u32;
+        let window_scale: u32 = match window_scale {
+            Some(scale) => scale.min(MAX_WINDOW_SCALE as u8) as u32,
+            None => NO_WINDOW_SCALE,
+        };
+        let hash: u32 = self.hash(local, remote, remote_isn, counter, mss_index, window_scale);
+        SeqNumber::from(
+            (counter & COUNTER_MASK) << COUNTER_SHIFT
+                | mss_index << MSS_SHIFT
+                | window_scale << WINDOW_SCALE_SHIFT
+                | hash,
+        )
+    }
+
+    /// Validates the [cookie] that [remote] echoed back for a SYN segment that carried [remote_isn]. Returns the options
+    /// of the connection request, if the cookie was generated by us recently enough.
+    pub fn validate(
+        &self,
+        now: Instant,
+        local: &SocketAddrV4,
+        remote: &SocketAddrV4,
+        remote_isn: SeqNumber,
+        cookie: SeqNumber,
+    ) -> Option<SynCookie> {
+        let cookie: u32 = u32::from(cookie);
+        let current: u32 = self.counter(now);
+        let age: u32 = current.wrapping_sub(cookie >> COUNTER_SHIFT) & COUNTER_MASK;
+        if age > MAX_COUNTER_AGE {
+            return None;
+        }
+        let counter: u32 = current.wrapping_sub(age);
+        let mss_index: u32 = (cookie >> MSS_SHIFT) & MSS_MASK;
+        let window_scale: u32 = (cookie >> WINDOW_SCALE_SHIFT) & WINDOW_SCALE_MASK;
+        if cookie & HASH_MASK != self.hash(local, remote, remote_isn, counter, mss_index, window_scale) {
+            return None;
+        }
+        Some(SynCookie {
+            mss: MSS_TABLE[mss_index as usize],
+            window_scale: match window_scale {
+                NO_WINDOW_SCALE => None,
+                scale => Some(scale as u8),
+            },
+        })
+    }
+
+    /// Computes the number of periods that went by since the target generator was created.
+    fn counter(&self, now: Instant) -> u32 {
+        (now.saturating_duration_since(self.epoch).as_secs() / COUNTER_PERIOD.as_secs()) as u32
+    }
+
+    fn hash(
+        &self,
+        local: &SocketAddrV4,
+        remote: &SocketAddrV4,
+        remote_isn: SeqNumber,
+        counter: u32,
+        mss_index: u32,
+        window_scale: u32,
+    ) -> u32 {
+        let crc: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_CKSUM);
+        let mut digest = crc.digest();
+        digest.update(&self.nonce.to_be_bytes());
+        digest.update(&remote.ip().octets());
+        digest.update(&remote.port().to_be_bytes());
+        digest.update(&local.ip().octets());
+        digest.update(&local.port().to_be_bytes());
+        digest.update(&u32::from(remote_isn).to_be_bytes());
+        digest.update(&counter.to_be_bytes());
+        digest.update(&[mss_index as u8, window_scale as u8]);
+        digest.finalize() & HASH_MASK
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        SynCookie,
+        SynCookieGenerator,
+        COUNTER_PERIOD,
+    };
+    use crate::inetstack::protocols::tcp::SeqNumber;
+    use ::anyhow::Result;
+    use ::std::{
+        net::{
+            Ipv4Addr,
+            SocketAddrV4,
+        },
+        time::Instant,
+    };
+
+    const LOCAL: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 80);
+    const REMOTE: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 2), 49152);
+
+    /// Tests that a SYN cookie gives back approximations of the options of the connection request.
+    #[test]
+    fn syn_cookie_roundtrip() -> Result<()> {
+        let now: Instant = Instant::now();
+        let generator: SynCookieGenerator = SynCookieGenerator::new(0xdeadbeef, now);
+        let remote_isn: SeqNumber = SeqNumber::from(0xfffffff0);
+        for (mss, window_scale, expected) in [
+            (
+                1460,
+                Some(7),
+                SynCookie {
+                    mss: 1460,
+                    window_scale: Some(7),
+                },
+            ),
+            (
+                1400,
+                None,
+                SynCookie {
+                    mss: 1220,
+                    window_scale: None,
+                },
+            ),
+            (
+                100,
+                Some(0),
+                SynCookie {
+                    mss: 536,
+                    window_scale: Some(0),
+                },
+            ),
+            (
+                9000,
+                Some(20),
+                SynCookie {
+                    mss: 1460,
+                    window_scale: Some(14),
+                },
+            ),
+        ] {
+            let cookie: SeqNumber = generator.generate(now, &LOCAL, &REMOTE, remote_isn, mss, window_scale);
+            let later: Instant = now + COUNTER_PERIOD;
+            crate::ensure_eq!(
+                generator.validate(later, &LOCAL, &REMOTE, remote_isn, cookie),
+                Some(expected)
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Tests that SYN cookies which were tampered with, were sent to someone else, or expired are rejected.
+    #[test]
+    fn syn_cookie_rejects_bad_cookie() -> Result<()> {
+        let now: Instant = Instant::now();
+        let generator: SynCookieGenerator = SynCookieGenerator::new(0xdeadbeef, now);
+        let remote_isn: SeqNumber = SeqNumber::from(1000);
+        let cookie: SeqNumber = generator.generate(now, &LOCAL, &REMOTE, remote_isn, 1460, Some(7));
+
+        let tampered: SeqNumber = SeqNumber::from(u32::from(cookie) ^ (1 << 25));
+        crate::ensure_eq!(generator.validate(now, &LOCAL, &REMOTE, remote_isn, tampered), None);
+        let other: SocketAddrV4 = SocketAddrV4::new(*REMOTE.ip(), REMOTE.port() + 1);
+        crate::ensure_eq!(generator.validate(now, &LOCAL, &other, remote_isn, cookie), None);
+        crate::ensure_eq!(
+            generator.validate(now, &LOCAL, &REMOTE, remote_isn + SeqNumber::from(1), cookie),
+            None
+        );
+        let expired: Instant = now + COUNTER_PERIOD * 2;
+        crate::ensure_eq!(generator.validate(expired, &LOCAL, &REMOTE, remote_isn, cookie), None);
+
+        Ok(())
+    }
+}
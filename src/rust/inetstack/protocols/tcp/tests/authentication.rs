@@ -0,0 +1,222 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::test_helpers::{
+        self,
+        ao_key,
+        engine::{
+            SharedEngine,
+            DEFAULT_TIMEOUT,
+        },
+        TcpKeyKind,
+    },
+    runtime::{
+        memory::DemiBuffer,
+        network::{
+            drops::DropReason,
+            socket::option::{
+                SocketOption,
+                TcpAoAlgorithm,
+                TcpAoInfo,
+                TcpAoKey,
+                TcpMd5Key,
+            },
+        },
+        OperationResult,
+        QDesc,
+        QToken,
+    },
+};
+use ::anyhow::Result;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+const PORT: u16 = 179;
+
+/// Identifier and MAC algorithm of the key that Alice and Bob first share.
+const KIND: TcpKeyKind = TcpKeyKind::Ao(1, TcpAoAlgorithm::Aes128Cmac);
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Pushes [len] bytes from [sender_fd] and pops them from [receiver_fd].
+fn transfer(
+    sender: &mut SharedEngine,
+    sender_fd: QDesc,
+    receiver: &mut SharedEngine,
+    receiver_fd: QDesc,
+    len: usize,
+) -> Result<()> {
+    let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; len][..]).expect("slice should fit in DemiBuffer");
+    let push_qt: QToken = sender.tcp_push(sender_fd, buf)?;
+    sender.exchange_frames(receiver)?;
+    match sender.wait(push_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(_)) => {},
+        result => anyhow::bail!("push() should succeed: {:?}", result),
+    };
+    let mut received: usize = 0;
+    while received < len {
+        let pop_qt: QToken = receiver.tcp_pop(receiver_fd)?;
+        match receiver.wait(pop_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Pop(_, buf, _)) => received += buf.len(),
+            result => anyhow::bail!("pop() should succeed: {:?}", result),
+        };
+    }
+    crate::ensure_eq!(received, len);
+    Ok(())
+}
+
+/// Gets the keys that the connection of [qd] uses.
+fn ao_info(engine: &mut SharedEngine, qd: QDesc) -> Result<TcpAoInfo> {
+    match engine.get_socket_option(qd, SocketOption::TCP_AO_INFO(TcpAoInfo::default()))? {
+        SocketOption::TCP_AO_INFO(info) => Ok(info),
+        option => anyhow::bail!("get_socket_option() should return TCP_AO_INFO: {:?}", option),
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+/// Tests that peers which share a key connect and exchange data over authenticated segments.
+#[test]
+fn tcp_ao_matching_keys() -> Result<()> {
+    let (mut alice, mut bob, client_fd, accepted_fd) = test_helpers::establish_signed(KIND, PORT)?;
+
+    // Segments carry the authentication option, which leaves less room for data.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&[0x5a; 4096][..]).expect("slice should fit in DemiBuffer");
+    let push_qt: QToken = alice.tcp_push(client_fd, buf)?;
+    alice.poll();
+    let frames = alice.pop_all_frames();
+    crate::ensure_eq!(frames.len() > 1, true);
+    for frame in frames {
+        // Option kind 29 with length 16 follows the fixed part of the TCP header, along with the key identifiers.
+        crate::ensure_eq!(&frame[54..58], &[29, 16, 1, 1][..]);
+        bob.receive(frame)?;
+    }
+    match alice.wait(push_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(_)) => {},
+        result => anyhow::bail!("push() should succeed: {:?}", result),
+    };
+    alice.exchange_frames(&mut bob)?;
+    transfer(&mut bob, accepted_fd, &mut alice, client_fd, 1024)?;
+    crate::ensure_eq!(bob.get_drop_stats(Some(accepted_fd))?.get(DropReason::BadSignature), 0);
+    crate::ensure_eq!(alice.get_drop_stats(None)?.get(DropReason::BadSignature), 0);
+
+    crate::ensure_eq!(
+        ao_info(&mut alice, client_fd)?,
+        TcpAoInfo {
+            current_key: 1,
+            rnext_key: 1,
+            peer_rnext_key: 1,
+        }
+    );
+
+    // Peers use either the signature option or the authentication option.
+    crate::ensure_eq!(
+        alice
+            .set_socket_option(
+                client_fd,
+                SocketOption::TCP_MD5SIG(TcpMd5Key::new(test_helpers::BOB_IPV4, 32, b"shared secret")?)
+            )
+            .unwrap_err()
+            .errno,
+        libc::EKEYREJECTED
+    );
+
+    Ok(())
+}
+
+/// Tests that connection requests that are authenticated with another key, or not authenticated at all, are dropped
+/// without an answer.
+#[test]
+fn tcp_ao_mismatched_keys() -> Result<()> {
+    for alice_key in [Some(&b"wrong secret"[..]), None] {
+        let (_, mut bob, server_fd, _, _) = test_helpers::connect_signed(KIND, PORT, alice_key)?;
+        crate::ensure_eq!(bob.get_drop_stats(Some(server_fd))?.get(DropReason::BadSignature), 1);
+    }
+    Ok(())
+}
+
+/// Tests that peers rotate to a new key without disrupting their connection, and that the key that signs the segments
+/// of a connection cannot be removed.
+#[test]
+fn tcp_ao_key_rotation() -> Result<()> {
+    let (mut alice, mut bob, client_fd, accepted_fd) = test_helpers::establish_signed(KIND, PORT)?;
+    // Keys are removed by their identifiers, whatever their bytes.
+    let old_alice_key: TcpAoKey = ao_key(test_helpers::BOB_IPV4, 1, TcpAoAlgorithm::Aes128Cmac, b"x")?;
+    let old_bob_key: TcpAoKey = ao_key(test_helpers::ALICE_IPV4, 1, TcpAoAlgorithm::Aes128Cmac, b"x")?;
+
+    // Both ends add the new key, and Alice asks Bob to switch to it.
+    let new_key: &[u8] = b"next shared secret";
+    let alice_key: TcpAoKey = ao_key(test_helpers::BOB_IPV4, 2, TcpAoAlgorithm::HmacSha256, new_key)?;
+    let bob_key: TcpAoKey = ao_key(test_helpers::ALICE_IPV4, 2, TcpAoAlgorithm::HmacSha256, new_key)?;
+    alice.set_socket_option(client_fd, SocketOption::TCP_AO_ADD_KEY(alice_key))?;
+    bob.set_socket_option(accepted_fd, SocketOption::TCP_AO_ADD_KEY(bob_key))?;
+    crate::ensure_eq!(
+        bob.set_socket_option(accepted_fd, SocketOption::TCP_AO_ADD_KEY(bob_key))
+            .unwrap_err()
+            .errno,
+        libc::EEXIST
+    );
+    alice.set_socket_option(
+        client_fd,
+        SocketOption::TCP_AO_INFO(TcpAoInfo {
+            current_key: 1,
+            rnext_key: 2,
+            peer_rnext_key: 0,
+        }),
+    )?;
+    transfer(&mut alice, client_fd, &mut bob, accepted_fd, 1024)?;
+    crate::ensure_eq!(ao_info(&mut bob, accepted_fd)?.current_key, 2);
+    crate::ensure_eq!(ao_info(&mut bob, accepted_fd)?.peer_rnext_key, 2);
+
+    // Keys that sign the segments of a connection are in use.
+    crate::ensure_eq!(
+        bob.set_socket_option(accepted_fd, SocketOption::TCP_AO_DEL_KEY(bob_key))
+            .unwrap_err()
+            .errno,
+        libc::EBUSY
+    );
+    crate::ensure_eq!(
+        alice
+            .set_socket_option(client_fd, SocketOption::TCP_AO_DEL_KEY(old_alice_key))
+            .unwrap_err()
+            .errno,
+        libc::EBUSY
+    );
+
+    // Alice switches too, after which both ends drop the old key.
+    alice.set_socket_option(
+        client_fd,
+        SocketOption::TCP_AO_INFO(TcpAoInfo {
+            current_key: 2,
+            rnext_key: 2,
+            peer_rnext_key: 0,
+        }),
+    )?;
+    alice.set_socket_option(client_fd, SocketOption::TCP_AO_DEL_KEY(old_alice_key))?;
+    bob.set_socket_option(accepted_fd, SocketOption::TCP_AO_DEL_KEY(old_bob_key))?;
+    transfer(&mut bob, accepted_fd, &mut alice, client_fd, 1024)?;
+    transfer(&mut alice, client_fd, &mut bob, accepted_fd, 1024)?;
+    crate::ensure_eq!(
+        ao_info(&mut alice, client_fd)?,
+        TcpAoInfo {
+            current_key: 2,
+            rnext_key: 2,
+            peer_rnext_key: 2,
+        }
+    );
+    crate::ensure_eq!(bob.get_drop_stats(Some(accepted_fd))?.get(DropReason::BadSignature), 0);
+    crate::ensure_eq!(alice.get_drop_stats(None)?.get(DropReason::BadSignature), 0);
+
+    Ok(())
+}
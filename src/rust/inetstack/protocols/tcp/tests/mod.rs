@@ -5,5 +5,13 @@
 // Exports
 //======================================================================================================================
 
+mod authentication;
+mod receive_buffer;
+mod send_buffer;
+mod signature;
 #[cfg(debug_assertions)]
 mod simulator;
+mod splice;
+mod timeline;
+mod tx_complete;
+mod user_timeout;
@@ -0,0 +1,113 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::{
+        protocols::{
+            ethernet2::Ethernet2Header,
+            ipv4::Ipv4Header,
+            tcp::segment::TcpHeader,
+        },
+        test_helpers::{
+            self,
+            engine::{
+                SharedEngine,
+                DEFAULT_TIMEOUT,
+            },
+        },
+    },
+    runtime::{
+        memory::DemiBuffer,
+        network::{
+            config::TcpConfig,
+            tcp_info::TcpInfo,
+        },
+        types::demi_tcp_info_t,
+        OperationResult,
+        QToken,
+    },
+};
+use ::anyhow::Result;
+use ::std::time::{
+    Duration,
+    Instant,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Window scale and initial receive window of Bob, which add up to a receive buffer of 16 KiB.
+const WINDOW_SCALE: u32 = 4;
+const RECEIVE_WINDOW_SIZE: u32 = 1024;
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+/// Tests that the receive buffer grows, and so does the window that is advertised, while the application pops data as
+/// fast as it arrives, and that the receive buffer is reported through the C API.
+#[test]
+fn tcp_receive_buffer_autotuning() -> Result<()> {
+    let mut now: Instant = Instant::now();
+    let mut tcp_config: TcpConfig = TcpConfig::default();
+    tcp_config.set_tunable("window_scale", &WINDOW_SCALE.to_string())?;
+    tcp_config.set_tunable("receive_window_size", &RECEIVE_WINDOW_SIZE.to_string())?;
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let mut bob: SharedEngine = test_helpers::new_bob2_with_tcp_config(now, tcp_config);
+
+    // Establish a connection from Alice to Bob.
+    let (_, accepted_fd, client_fd) = alice.establish(&mut bob, 80, &[], &[])?;
+    let initial_space: u32 = RECEIVE_WINDOW_SIZE << WINDOW_SCALE;
+    crate::ensure_eq!(bob.get_tcp_info(accepted_fd)?.recv_space, initial_space);
+
+    // Alice keeps data waiting to be sent, and Bob pops it as soon as it arrives.
+    let mut max_window: u32 = 0;
+    let mut record_window = |frame: &DemiBuffer| -> Result<()> {
+        let (_, ipv4_payload) = Ethernet2Header::parse(frame.clone())?;
+        let (ipv4_header, tcp_payload) = Ipv4Header::parse(ipv4_payload)?;
+        if ipv4_header.get_src_addr() == test_helpers::BOB_IPV4 {
+            let (tcp_header, _) = TcpHeader::parse(&ipv4_header, tcp_payload, true)?;
+            max_window = max_window.max((tcp_header.window_size as u32) << WINDOW_SCALE);
+        }
+        Ok(())
+    };
+    let mut pop_qt: QToken = bob.tcp_pop(accepted_fd)?;
+    let mut received: usize = 0;
+    for _ in 0..8 {
+        for _ in 0..4 {
+            let buf: DemiBuffer = DemiBuffer::from_slice(&[0x5a; 8192][..]).expect("slice should fit in DemiBuffer");
+            let push_qt: QToken = alice.tcp_push(client_fd, buf)?;
+            match alice.wait(push_qt, DEFAULT_TIMEOUT)? {
+                (_, OperationResult::Push(_)) => {},
+                result => anyhow::bail!("push() should succeed: {:?}", result),
+            };
+        }
+        alice.exchange_frames_with(&mut bob, &mut record_window)?;
+        loop {
+            match bob.wait(pop_qt, Duration::ZERO) {
+                Ok((_, OperationResult::Pop(_, buf, _))) => received += buf.len(),
+                Err(e) if e.errno == libc::ETIMEDOUT => break,
+                result => anyhow::bail!("pop() should succeed: {:?}", result),
+            };
+            pop_qt = bob.tcp_pop(accepted_fd)?;
+        }
+        now += Duration::from_millis(100);
+        bob.advance_clock(now);
+        alice.advance_clock(now);
+        alice.exchange_frames_with(&mut bob, &mut record_window)?;
+    }
+    crate::ensure_eq!(received, 8 * 4 * 8192);
+
+    // The receive buffer grew, and Bob advertised a larger window than it started with.
+    let info: TcpInfo = bob.get_tcp_info(accepted_fd)?;
+    crate::ensure_eq!(info.recv_space > initial_space, true);
+    crate::ensure_eq!(max_window > initial_space, true);
+    crate::ensure_eq!(demi_tcp_info_t::from(info).recv_space, info.recv_space);
+
+    Ok(())
+}
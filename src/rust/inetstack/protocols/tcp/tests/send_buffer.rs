@@ -0,0 +1,104 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::test_helpers::{
+        self,
+        engine::{
+            SharedEngine,
+            DEFAULT_TIMEOUT,
+        },
+    },
+    runtime::{
+        memory::DemiBuffer,
+        network::config::TcpConfig,
+        OperationResult,
+        QToken,
+    },
+};
+use ::anyhow::Result;
+use ::std::time::{
+    Duration,
+    Instant,
+};
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+/// Tests that pushes that wait for room in a full send buffer take it in the order that they were issued, so that a
+/// small push does not overtake a large push that was issued before it.
+#[test]
+fn tcp_push_send_buffer_fifo() -> Result<()> {
+    let mut now: Instant = Instant::now();
+    let mut tcp_config: TcpConfig = TcpConfig::default();
+    tcp_config.set_tunable("send_buffer_size", "4096")?;
+    let mut alice: SharedEngine = test_helpers::new_alice2_with_tcp_config(now, tcp_config);
+    let mut bob: SharedEngine = test_helpers::new_bob2(now);
+
+    // Establish a connection from Alice to Bob.
+    let (_, accepted_fd, client_fd) = alice.establish(&mut bob, 80, &[], &[])?;
+
+    // Fill half of the send buffer with data that has not reached Bob yet.
+    let first: DemiBuffer = DemiBuffer::from_slice(&[0x01; 2048][..]).expect("slice should fit in DemiBuffer");
+    let push_qt: QToken = alice.tcp_push(client_fd, first)?;
+    match alice.wait(push_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(nbytes)) => crate::ensure_eq!(nbytes, 2048),
+        result => anyhow::bail!("push() should succeed: {:?}", result),
+    };
+
+    // A large push does not fit, and a small push that would fit must wait behind it.
+    let large: DemiBuffer = DemiBuffer::from_slice(&[0x02; 3000][..]).expect("slice should fit in DemiBuffer");
+    let large_qt: QToken = alice.tcp_push(client_fd, large)?;
+    match alice.wait(large_qt, Duration::ZERO) {
+        Err(e) if e.errno == libc::ETIMEDOUT => {},
+        result => anyhow::bail!("push() should still be pending: {:?}", result),
+    };
+    let small: DemiBuffer = DemiBuffer::from_slice(&[0x03; 1000][..]).expect("slice should fit in DemiBuffer");
+    let small_qt: QToken = alice.tcp_push(client_fd, small)?;
+    for qt in [small_qt, large_qt] {
+        match alice.wait(qt, Duration::ZERO) {
+            Err(e) if e.errno == libc::ETIMEDOUT => {},
+            result => anyhow::bail!("push() should still be pending: {:?}", result),
+        };
+    }
+
+    // Once Bob acknowledges the first push, both pushes take room and complete.
+    alice.exchange_frames(&mut bob)?;
+    now += Duration::from_millis(500);
+    bob.advance_clock(now);
+    alice.advance_clock(now);
+    alice.exchange_frames(&mut bob)?;
+    match alice.wait(large_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(nbytes)) => crate::ensure_eq!(nbytes, 3000),
+        result => anyhow::bail!("push() should succeed: {:?}", result),
+    };
+    match alice.wait(small_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(nbytes)) => crate::ensure_eq!(nbytes, 1000),
+        result => anyhow::bail!("push() should succeed: {:?}", result),
+    };
+
+    // Bob receives the data of every push in the order that they were issued.
+    let mut received: Vec<u8> = Vec::new();
+    while received.len() < 6048 {
+        alice.exchange_frames(&mut bob)?;
+        now += Duration::from_millis(500);
+        bob.advance_clock(now);
+        alice.advance_clock(now);
+        let pop_qt: QToken = bob.tcp_pop(accepted_fd)?;
+        match bob.wait(pop_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Pop(_, buf, _)) => received.extend_from_slice(&buf[..]),
+            result => anyhow::bail!("pop() should succeed: {:?}", result),
+        };
+    }
+    let mut expected: Vec<u8> = vec![0x01; 2048];
+    expected.extend_from_slice(&[0x02; 3000]);
+    expected.extend_from_slice(&[0x03; 1000]);
+    crate::ensure_eq!(received, expected);
+
+    Ok(())
+}
@@ -0,0 +1,108 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::test_helpers::{
+        self,
+        engine::DEFAULT_TIMEOUT,
+        host_key,
+        TcpKeyKind,
+        SHARED_KEY,
+    },
+    runtime::{
+        memory::DemiBuffer,
+        network::drops::DropReason,
+        OperationResult,
+        QDesc,
+        QToken,
+    },
+};
+use ::anyhow::Result;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+const PORT: u16 = 179;
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+/// Tests that peers which share a key connect and exchange data over signed segments.
+#[test]
+fn tcp_md5_signature_matching_keys() -> Result<()> {
+    let (mut alice, mut bob, server_fd, accept_qt, connect_qt) =
+        test_helpers::connect_signed(TcpKeyKind::Md5, PORT, Some(SHARED_KEY))?;
+    let client_fd: QDesc = match alice.wait(connect_qt, DEFAULT_TIMEOUT)? {
+        (qd, OperationResult::Connect) => qd,
+        result => anyhow::bail!("connect() should succeed: {:?}", result),
+    };
+    let accepted_fd: QDesc = match bob.wait(accept_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Accept((qd, _))) => qd,
+        result => anyhow::bail!("accept() should succeed: {:?}", result),
+    };
+
+    // Segments carry a signature, which leaves less room for data.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&[0x5a; 4096][..]).expect("slice should fit in DemiBuffer");
+    let push_qt: QToken = alice.tcp_push(client_fd, buf)?;
+    alice.poll();
+    let frames = alice.pop_all_frames();
+    crate::ensure_eq!(frames.len() > 1, true);
+    for frame in frames {
+        // Option kind 19 with length 18 follows the fixed part of the TCP header.
+        crate::ensure_eq!(&frame[54..56], &[19, 18][..]);
+        bob.receive(frame)?;
+    }
+    match alice.wait(push_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(_)) => {},
+        result => anyhow::bail!("push() should succeed: {:?}", result),
+    };
+
+    let mut received: usize = 0;
+    while received < 4096 {
+        let pop_qt: QToken = bob.tcp_pop(accepted_fd)?;
+        match bob.wait(pop_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Pop(_, buf, _)) => received += buf.len(),
+            result => anyhow::bail!("pop() should succeed: {:?}", result),
+        };
+    }
+    crate::ensure_eq!(received, 4096);
+    crate::ensure_eq!(bob.get_drop_stats(Some(server_fd))?.get(DropReason::BadSignature), 0);
+    crate::ensure_eq!(bob.get_drop_stats(Some(accepted_fd))?.get(DropReason::BadSignature), 0);
+
+    // Keys cannot be read back, and other protocols do not take them.
+    crate::ensure_eq!(
+        alice
+            .get_socket_option(client_fd, host_key(TcpKeyKind::Md5, test_helpers::BOB_IPV4, b"")?)
+            .unwrap_err()
+            .errno,
+        libc::ENOPROTOOPT
+    );
+    let udp_fd: QDesc = alice.udp_socket()?;
+    crate::ensure_eq!(
+        alice
+            .set_socket_option(udp_fd, host_key(TcpKeyKind::Md5, test_helpers::BOB_IPV4, SHARED_KEY)?)
+            .unwrap_err()
+            .errno,
+        libc::ENOPROTOOPT
+    );
+
+    Ok(())
+}
+
+/// Tests that connection requests that are signed with another key, or not signed at all, are dropped without an
+/// answer.
+#[test]
+fn tcp_md5_signature_mismatched_keys() -> Result<()> {
+    for alice_key in [Some(&b"wrong secret"[..]), None] {
+        let (_, mut bob, server_fd, _, _) = test_helpers::connect_signed(TcpKeyKind::Md5, PORT, alice_key)?;
+        crate::ensure_eq!(bob.get_drop_stats(Some(server_fd))?.get(DropReason::BadSignature), 1);
+        crate::ensure_eq!(bob.get_drop_stats(None)?.get(DropReason::BadSignature), 1);
+    }
+    Ok(())
+}
@@ -46,7 +46,10 @@ use crate::{
     QDesc,
     QToken,
 };
-use anyhow::Result;
+use anyhow::{
+    Context,
+    Result,
+};
 use nettest::glue::{
     AcceptArgs,
     BindArgs,
@@ -107,13 +110,10 @@ fn test_simulation() -> Result<()> {
     let remote_ephemeral_port: u16 = 49152;
     let remote_ipv4: Ipv4Addr = test_helpers::BOB_IPV4;
 
+    // Fallback to the conformance suite that ships with the source tree, if no input path is informed.
     let input_path: String = match env::var("INPUT") {
         Ok(config_path) => config_path,
-        Err(e) => {
-            let cause: String = format!("missing INPUT environment variable (err={:?})", e);
-            warn!("test_simulation(): {:?}", cause);
-            anyhow::bail!(cause);
-        },
+        Err(_) => format!("{}/nettest/input", env!("CARGO_MANIFEST_DIR")),
     };
 
     let tests: Vec<String> = collect_tests(&input_path)?;
@@ -138,7 +138,9 @@ fn test_simulation() -> Result<()> {
             remote_ephemeral_port,
             &remote_ipv4,
         )?;
-        simulation.run(verbose)?;
+        simulation
+            .run(verbose)
+            .with_context(|| format!("test case failed: {:?}", test))?;
     }
 
     Ok(())
@@ -599,13 +601,8 @@ impl Simulation {
             },
         };
 
-        // Extract remote queue descriptor.
-        let remote_qd: QDesc = match self.remote_qd {
-            Some((_, qd)) => qd.unwrap(),
-            None => {
-                anyhow::bail!("remote queue descriptor musth have been previously assigned");
-            },
-        };
+        // Extract queue descriptor of the connected socket.
+        let remote_qd: QDesc = self.connected_qd()?;
 
         let buf: DemiBuffer = Self::cook_buffer(buf_len as usize, None);
         match self.engine.tcp_push(remote_qd, buf) {
@@ -614,6 +611,8 @@ impl Simulation {
                 // We need an extra poll because we now perform all work for the push inside the asynchronous coroutine.
                 // TODO: Remove this once we separate the poll and advance clock functions.
                 self.engine.poll();
+                // The push coroutine hands the data over to the sender coroutine, which needs another poll to run.
+                self.engine.poll();
 
                 Ok(())
             },
@@ -628,13 +627,8 @@ impl Simulation {
 
     /// Runs a pop system call.
     fn run_pop_syscall(&mut self, ret: i32) -> Result<()> {
-        // Extract remote queue descriptor.
-        let remote_qd: QDesc = match self.remote_qd {
-            Some((_, qd)) => qd.unwrap(),
-            None => {
-                anyhow::bail!("remote queue descriptor musth have been previously assigned");
-            },
-        };
+        // Extract queue descriptor of the connected socket.
+        let remote_qd: QDesc = self.connected_qd()?;
 
         match self.engine.tcp_pop(remote_qd) {
             Ok(pop_qt) => {
@@ -673,11 +667,11 @@ impl Simulation {
                     info!("connection established as expected (qd={:?})", qd);
                     Ok(())
                 },
-                crate::OperationResult::Pop(_sockaddr, _data) => {
+                crate::OperationResult::Pop(_sockaddr, _data, _) => {
                     info!("pop completed as expected (qd={:?})", qd);
                     Ok(())
                 },
-                crate::OperationResult::Push => {
+                crate::OperationResult::Push(_) => {
                     info!("push completed as expected (qd={:?})", qd);
                     Ok(())
                 },
@@ -698,6 +692,16 @@ impl Simulation {
         }
     }
 
+    /// Returns the queue descriptor of the connected socket. This is either the socket that was returned by accept, or
+    /// the local socket if the connection was actively opened.
+    fn connected_qd(&self) -> Result<QDesc> {
+        match (self.remote_qd, self.local_qd) {
+            (Some((_, Some(qd))), _) => Ok(qd),
+            (None, Some((_, qd))) => Ok(qd),
+            _ => anyhow::bail!("connected queue descriptor must have been previously assigned"),
+        }
+    }
+
     fn run_close_syscall(&mut self, args: &CloseArgs, ret: i32) -> Result<()> {
         // Extract queue descriptor.
         let args_qd: QDesc = args.qd.into();
@@ -795,6 +799,7 @@ impl Simulation {
             urgent_pointer: 0,
             num_options,
             option_list,
+            signed_header: None,
         }
     }
 
@@ -816,6 +821,8 @@ impl Simulation {
             tcp_hdr,
             data,
             tx_checksum_offload: false,
+            tso_segment_size: None,
+            signer: None,
         }
     }
 
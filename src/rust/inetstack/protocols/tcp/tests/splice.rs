@@ -0,0 +1,244 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::test_helpers::{
+        self,
+        engine::{
+            SharedEngine,
+            DEFAULT_TIMEOUT,
+        },
+    },
+    runtime::{
+        memory::DemiBuffer,
+        OperationResult,
+        QDesc,
+        QToken,
+    },
+};
+use ::anyhow::Result;
+use ::std::{
+    path::PathBuf,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Writes [len] bytes to a new temporary file named after [name] and returns its path along with its contents.
+fn write_file(name: &str, len: usize) -> Result<(PathBuf, Vec<u8>)> {
+    let path: PathBuf = std::env::temp_dir().join(format!("demikernel-splice-{}-{}", name, std::process::id()));
+    let data: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+    std::fs::write(&path, &data)?;
+    Ok((path, data))
+}
+
+/// Opens the file at [path] for reading on [engine].
+fn open_file(engine: &mut SharedEngine, path: &PathBuf) -> Result<QDesc> {
+    let path: &str = path.to_str().expect("temporary path should be valid unicode");
+    Ok(engine.open_file(path, libc::O_RDONLY)?)
+}
+
+/// Moves frames between [alice] and [bob], letting delayed acknowledgements go out, until [qt] on Alice completes.
+fn run_until_complete(
+    alice: &mut SharedEngine,
+    bob: &mut SharedEngine,
+    now: &mut Instant,
+    qt: QToken,
+) -> Result<OperationResult> {
+    loop {
+        alice.exchange_frames(bob)?;
+        match alice.wait(qt, Duration::ZERO) {
+            Ok((_, result)) => return Ok(result),
+            Err(e) if e.errno == libc::ETIMEDOUT => {},
+            Err(e) => anyhow::bail!("wait() should succeed: {:?}", e),
+        }
+        *now += Duration::from_millis(500);
+        alice.advance_clock(*now);
+        bob.advance_clock(*now);
+    }
+}
+
+/// Pops [len] bytes from [qd] on [bob].
+fn pop_exactly(
+    alice: &mut SharedEngine,
+    bob: &mut SharedEngine,
+    now: &mut Instant,
+    qd: QDesc,
+    len: usize,
+) -> Result<Vec<u8>> {
+    let mut received: Vec<u8> = Vec::new();
+    while received.len() < len {
+        let pop_qt: QToken = bob.tcp_pop(qd)?;
+        match run_until_complete(bob, alice, now, pop_qt)? {
+            OperationResult::Pop(_, buf, _) => received.extend_from_slice(&buf[..]),
+            result => anyhow::bail!("pop() should succeed: {:?}", result),
+        }
+    }
+    Ok(received)
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+/// Tests that a splice from a file moves the whole file to a TCP connection.
+#[test]
+fn tcp_splice_file() -> Result<()> {
+    let mut now: Instant = Instant::now();
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let mut bob: SharedEngine = test_helpers::new_bob2(now);
+    let (_, accepted_fd, client_fd) = alice.establish(&mut bob, 80, &[], &[])?;
+    let (path, data) = write_file("full", 10000)?;
+    let file_qd: QDesc = open_file(&mut alice, &path)?;
+
+    let splice_qt: QToken = alice.splice(client_fd, file_qd, data.len(), None)?;
+    match run_until_complete(&mut alice, &mut bob, &mut now, splice_qt)? {
+        OperationResult::Splice(nbytes) => crate::ensure_eq!(nbytes, data.len()),
+        result => anyhow::bail!("splice() should succeed: {:?}", result),
+    };
+    crate::ensure_eq!(
+        pop_exactly(&mut alice, &mut bob, &mut now, accepted_fd, data.len())?,
+        data
+    );
+
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+/// Tests that a splice from a file that ends before the requested size completes with the bytes that it moved.
+#[test]
+fn tcp_splice_file_short() -> Result<()> {
+    let mut now: Instant = Instant::now();
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let mut bob: SharedEngine = test_helpers::new_bob2(now);
+    let (_, accepted_fd, client_fd) = alice.establish(&mut bob, 80, &[], &[])?;
+    let (path, data) = write_file("short", 3000)?;
+    let file_qd: QDesc = open_file(&mut alice, &path)?;
+
+    let splice_qt: QToken = alice.splice(client_fd, file_qd, 2 * data.len(), None)?;
+    match run_until_complete(&mut alice, &mut bob, &mut now, splice_qt)? {
+        OperationResult::Splice(nbytes) => crate::ensure_eq!(nbytes, data.len()),
+        result => anyhow::bail!("splice() should stop at the end of the file: {:?}", result),
+    };
+    crate::ensure_eq!(
+        pop_exactly(&mut alice, &mut bob, &mut now, accepted_fd, data.len())?,
+        data
+    );
+
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+/// Tests that a splice fails right away from a queue descriptor that does not exist or to a socket that is not
+/// connected.
+#[test]
+fn tcp_splice_bad_queues() -> Result<()> {
+    let now: Instant = Instant::now();
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let mut bob: SharedEngine = test_helpers::new_bob2(now);
+    let (_, _, client_fd) = alice.establish(&mut bob, 80, &[], &[])?;
+    let (path, _) = write_file("bad", 100)?;
+    let file_qd: QDesc = open_file(&mut alice, &path)?;
+
+    let unknown_qd: QDesc = QDesc::from(1000);
+    crate::ensure_eq!(
+        alice.splice(client_fd, unknown_qd, 100, None).unwrap_err().errno,
+        libc::EBADF
+    );
+    let unconnected_fd: QDesc = alice.tcp_socket()?;
+    crate::ensure_eq!(
+        alice.splice(unconnected_fd, file_qd, 100, None).unwrap_err().errno,
+        libc::ENOTCONN
+    );
+
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+/// Tests that a splice between two TCP connections forwards everything until the peer of the source closes its side.
+#[test]
+fn tcp_splice_tcp_until_eof() -> Result<()> {
+    let mut now: Instant = Instant::now();
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let mut bob: SharedEngine = test_helpers::new_bob2(now);
+    // Alice forwards what Bob sends on the first connection back to Bob on the second one.
+    let (_, source_fd, alice_source_fd) = alice.establish(&mut bob, 80, &[], &[])?;
+    let (_, sink_fd, alice_sink_fd) = alice.establish(&mut bob, 81, &[], &[])?;
+
+    let splice_qt: QToken = alice.splice(alice_sink_fd, alice_source_fd, usize::MAX, None)?;
+    let mut data: Vec<u8> = Vec::new();
+    for i in 0..4u8 {
+        let chunk: Vec<u8> = vec![i; 1000];
+        let push_qt: QToken = bob.tcp_push(source_fd, DemiBuffer::from_slice(&chunk[..])?)?;
+        match run_until_complete(&mut bob, &mut alice, &mut now, push_qt)? {
+            OperationResult::Push(_) => {},
+            result => anyhow::bail!("push() should succeed: {:?}", result),
+        };
+        data.extend_from_slice(&chunk);
+    }
+    let close_qt: QToken = bob.tcp_async_close(source_fd)?;
+    match run_until_complete(&mut alice, &mut bob, &mut now, splice_qt)? {
+        OperationResult::Splice(nbytes) => crate::ensure_eq!(nbytes, data.len()),
+        result => anyhow::bail!("splice() should stop when Bob closes: {:?}", result),
+    };
+    crate::ensure_eq!(pop_exactly(&mut alice, &mut bob, &mut now, sink_fd, data.len())?, data);
+
+    // Closing the source on Alice as well ends the connection.
+    let alice_close_qt: QToken = alice.tcp_async_close(alice_source_fd)?;
+    match run_until_complete(&mut alice, &mut bob, &mut now, alice_close_qt)? {
+        OperationResult::Close => {},
+        result => anyhow::bail!("close() should succeed: {:?}", result),
+    };
+    match bob.wait(close_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Close) => {},
+        result => anyhow::bail!("close() should succeed: {:?}", result),
+    };
+
+    Ok(())
+}
+
+/// Tests that a splice from a TCP connection fails with EBADF once the source is closed while the splice waits on it.
+#[test]
+fn tcp_splice_tcp_source_closed() -> Result<()> {
+    let mut now: Instant = Instant::now();
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let mut bob: SharedEngine = test_helpers::new_bob2(now);
+    let (_, source_fd, alice_source_fd) = alice.establish(&mut bob, 80, &[], &[])?;
+    let (_, sink_fd, alice_sink_fd) = alice.establish(&mut bob, 81, &[], &[])?;
+
+    let splice_qt: QToken = alice.splice(alice_sink_fd, alice_source_fd, usize::MAX, None)?;
+    let data: Vec<u8> = vec![0x5a; 1000];
+    let push_qt: QToken = bob.tcp_push(source_fd, DemiBuffer::from_slice(&data[..])?)?;
+    match run_until_complete(&mut bob, &mut alice, &mut now, push_qt)? {
+        OperationResult::Push(_) => {},
+        result => anyhow::bail!("push() should succeed: {:?}", result),
+    };
+    crate::ensure_eq!(pop_exactly(&mut alice, &mut bob, &mut now, sink_fd, data.len())?, data);
+
+    // Alice closes the source while the splice waits for more data.
+    let close_qt: QToken = alice.tcp_async_close(alice_source_fd)?;
+    match run_until_complete(&mut alice, &mut bob, &mut now, splice_qt)? {
+        OperationResult::Failed(e) => crate::ensure_eq!(e.errno, libc::EBADF),
+        result => anyhow::bail!("splice() should fail once its source is closed: {:?}", result),
+    };
+    let bob_close_qt: QToken = bob.tcp_async_close(source_fd)?;
+    match run_until_complete(&mut alice, &mut bob, &mut now, close_qt)? {
+        OperationResult::Close => {},
+        result => anyhow::bail!("close() should succeed: {:?}", result),
+    };
+    match bob.wait(bob_close_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Close) => {},
+        result => anyhow::bail!("close() should succeed: {:?}", result),
+    };
+
+    Ok(())
+}
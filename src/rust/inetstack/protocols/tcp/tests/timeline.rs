@@ -0,0 +1,75 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::test_helpers::{
+        self,
+        engine::{
+            SharedEngine,
+            DEFAULT_TIMEOUT,
+        },
+    },
+    runtime::{
+        memory::DemiBuffer,
+        network::tcp_info::{
+            TcpEvent,
+            TcpEventKind,
+        },
+        OperationResult,
+        QDesc,
+        QToken,
+    },
+};
+use ::anyhow::Result;
+use ::std::time::{
+    Duration,
+    Instant,
+};
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+/// Tests that a retransmission timeout and the back off of the retransmission timer show up in the timeline of the
+/// connection, stamped with the time since the connection was established.
+#[test]
+fn tcp_timeline_retransmit_timeout() -> Result<()> {
+    let mut now: Instant = Instant::now();
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let mut bob: SharedEngine = test_helpers::new_bob2(now);
+
+    // Establish a connection from Alice to Bob.
+    let (server_fd, _, client_fd) = alice.establish(&mut bob, 80, &[], &[])?;
+    crate::ensure_eq!(alice.get_tcp_timeline(client_fd)?.is_empty(), true);
+
+    // The segment that Alice sends is lost, so the retransmission timer expires.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&[0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    let push_qt: QToken = alice.tcp_push(client_fd, buf)?;
+    match alice.wait(push_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(_)) => {},
+        result => anyhow::bail!("push() should succeed: {:?}", result),
+    };
+    alice.poll();
+    alice.pop_all_frames();
+    now += Duration::from_secs(5);
+    alice.advance_clock(now);
+    alice.poll();
+
+    let events: Vec<TcpEvent> = alice.get_tcp_timeline(client_fd)?;
+    let kinds: Vec<TcpEventKind> = events.iter().map(|event| event.kind).collect();
+    crate::ensure_eq!(kinds, vec![TcpEventKind::RetransmitTimeout, TcpEventKind::RtoBackoff]);
+    crate::ensure_eq!(events[0].value, 32);
+    crate::ensure_eq!(events[0].at, Duration::from_secs(5));
+    crate::ensure_eq!(Duration::from_micros(events[1].value) > Duration::from_secs(1), true);
+
+    // Listening sockets have no timeline, and other protocols have none either.
+    crate::ensure_eq!(bob.get_tcp_timeline(server_fd)?.is_empty(), true);
+    let udp_fd: QDesc = alice.udp_socket()?;
+    crate::ensure_eq!(alice.get_tcp_timeline(udp_fd).unwrap_err().errno, libc::ENOTSUP);
+
+    Ok(())
+}
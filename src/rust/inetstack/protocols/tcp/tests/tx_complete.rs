@@ -0,0 +1,80 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::test_helpers::{
+        self,
+        engine::{
+            SharedEngine,
+            DEFAULT_TIMEOUT,
+        },
+    },
+    runtime::{
+        memory::DemiBuffer,
+        network::socket::option::SocketOption,
+        OperationResult,
+        QToken,
+    },
+};
+use ::anyhow::Result;
+use ::std::time::{
+    Duration,
+    Instant,
+};
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+/// Tests that pushes report the bytes that they accepted, and that pushes to sockets with the transmit-complete option
+/// complete only once our peer acknowledges their data.
+#[test]
+fn tcp_push_tx_complete() -> Result<()> {
+    let mut now: Instant = Instant::now();
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let mut bob: SharedEngine = test_helpers::new_bob2(now);
+
+    // Establish a connection from Alice to Bob.
+    let (_, _, client_fd) = alice.establish(&mut bob, 80, &[], &[])?;
+
+    // By default, a push completes once its data is accepted, before anything reaches Bob.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&[0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    let push_qt: QToken = alice.tcp_push(client_fd, buf)?;
+    match alice.wait(push_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(nbytes)) => crate::ensure_eq!(nbytes, 32),
+        result => anyhow::bail!("push() should succeed: {:?}", result),
+    };
+    alice.exchange_frames(&mut bob)?;
+    now += Duration::from_millis(500);
+    bob.advance_clock(now);
+    alice.advance_clock(now);
+    alice.exchange_frames(&mut bob)?;
+
+    // With the option set, the push waits until Bob acknowledges its data, and Bob delays acknowledgements.
+    crate::ensure_eq!(
+        alice.get_socket_option(client_fd, SocketOption::SO_TXCOMPLETE(true))?,
+        SocketOption::SO_TXCOMPLETE(false)
+    );
+    alice.set_socket_option(client_fd, SocketOption::SO_TXCOMPLETE(true))?;
+    let buf: DemiBuffer = DemiBuffer::from_slice(&[0x5a; 64][..]).expect("slice should fit in DemiBuffer");
+    let push_qt: QToken = alice.tcp_push(client_fd, buf)?;
+    alice.exchange_frames(&mut bob)?;
+    match alice.wait(push_qt, Duration::ZERO) {
+        Err(e) if e.errno == libc::ETIMEDOUT => {},
+        result => anyhow::bail!("push() should still be pending: {:?}", result),
+    };
+    now += Duration::from_millis(500);
+    bob.advance_clock(now);
+    alice.advance_clock(now);
+    alice.exchange_frames(&mut bob)?;
+    match alice.wait(push_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(nbytes)) => crate::ensure_eq!(nbytes, 64),
+        result => anyhow::bail!("push() should succeed: {:?}", result),
+    };
+
+    Ok(())
+}
@@ -0,0 +1,132 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    inetstack::test_helpers::{
+        self,
+        engine::{
+            SharedEngine,
+            DEFAULT_TIMEOUT,
+        },
+    },
+    runtime::{
+        memory::DemiBuffer,
+        network::{
+            socket::option::SocketOption,
+            tcp_info::{
+                TcpEvent,
+                TcpEventKind,
+            },
+        },
+        OperationResult,
+        QDesc,
+        QToken,
+    },
+};
+use ::anyhow::Result;
+use ::std::time::{
+    Duration,
+    Instant,
+};
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Pushes [len] bytes on [qd] and waits for the push to complete.
+fn push(engine: &mut SharedEngine, qd: QDesc, len: usize) -> Result<()> {
+    let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; len][..]).expect("slice should fit in DemiBuffer");
+    let push_qt: QToken = engine.tcp_push(qd, buf)?;
+    match engine.wait(push_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(_)) => Ok(()),
+        result => anyhow::bail!("push() should succeed: {:?}", result),
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+/// Tests that a connection is aborted with ETIMEDOUT once sent data stays unacknowledged for longer than the user
+/// timeout, between two expirations of the retransmission timer, while data that our peer acknowledges keeps the
+/// connection alive however long it is idle.
+#[test]
+fn tcp_user_timeout() -> Result<()> {
+    let mut now: Instant = Instant::now();
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let mut bob: SharedEngine = test_helpers::new_bob2(now);
+
+    // Establish a connection from Alice to Bob.
+    let (_, _, client_fd) = alice.establish(&mut bob, 80, &[], &[])?;
+
+    // The retransmission timer expires after one second, and then after two more.
+    alice.set_socket_option(client_fd, SocketOption::TCP_RTO_MIN_US(1_000_000))?;
+    alice.set_socket_option(client_fd, SocketOption::TCP_USER_TIMEOUT(2500))?;
+    crate::ensure_eq!(
+        alice.get_socket_option(client_fd, SocketOption::TCP_USER_TIMEOUT(0))?,
+        SocketOption::TCP_USER_TIMEOUT(2500)
+    );
+    let udp_fd: QDesc = alice.udp_socket()?;
+    crate::ensure_eq!(
+        alice
+            .set_socket_option(udp_fd, SocketOption::TCP_USER_TIMEOUT(2500))
+            .unwrap_err()
+            .errno,
+        libc::ENOPROTOOPT
+    );
+
+    // Data that Bob acknowledges does not count, however long the connection is idle afterwards.
+    push(&mut alice, client_fd, 32)?;
+    alice.exchange_frames(&mut bob)?;
+    now += Duration::from_millis(500);
+    bob.advance_clock(now);
+    alice.advance_clock(now);
+    alice.exchange_frames(&mut bob)?;
+    now += Duration::from_secs(10);
+    alice.advance_clock(now);
+    alice.poll();
+    crate::ensure_eq!(alice.get_tcp_timeline(client_fd)?.is_empty(), true);
+
+    // The next segment that Alice sends is lost, and so are its retransmissions.
+    let pop_qt: QToken = alice.tcp_pop(client_fd)?;
+    push(&mut alice, client_fd, 32)?;
+    alice.poll();
+    alice.pop_all_frames();
+    now += Duration::from_millis(1500);
+    alice.advance_clock(now);
+    alice.poll();
+    alice.pop_all_frames();
+    let kinds: Vec<TcpEventKind> = alice
+        .get_tcp_timeline(client_fd)?
+        .iter()
+        .map(|event| event.kind)
+        .collect();
+    crate::ensure_eq!(kinds, vec![TcpEventKind::RetransmitTimeout, TcpEventKind::RtoBackoff]);
+
+    // The user timeout expires before the retransmission timer does again.
+    now += Duration::from_millis(1100);
+    alice.advance_clock(now);
+    alice.poll();
+    let events: Vec<TcpEvent> = alice.get_tcp_timeline(client_fd)?;
+    crate::ensure_eq!(events.len(), 3);
+    crate::ensure_eq!(events[2].kind, TcpEventKind::UserTimeout);
+    crate::ensure_eq!(events[2].value, 32);
+
+    // The pending pop fails with ETIMEDOUT, and so do new operations.
+    match alice.wait(pop_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Failed(e)) => crate::ensure_eq!(e.errno, libc::ETIMEDOUT),
+        result => anyhow::bail!("pop() should time out: {:?}", result),
+    };
+    let buf: DemiBuffer = DemiBuffer::from_slice(&[0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    let push_qt: QToken = alice.tcp_push(client_fd, buf)?;
+    match alice.wait(push_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Failed(e)) => crate::ensure_eq!(e.errno, libc::ETIMEDOUT),
+        result => anyhow::bail!("push() should time out: {:?}", result),
+    };
+
+    Ok(())
+}
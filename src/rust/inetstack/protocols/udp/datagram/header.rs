@@ -7,6 +7,7 @@
 
 use crate::{
     inetstack::protocols::{
+        checksum,
         ip::IpProtocol,
         ipv4::Ipv4Header,
     },
@@ -16,7 +17,6 @@ use crate::{
     },
 };
 use ::libc::EBADMSG;
-use std::slice::ChunksExact;
 
 //======================================================================================================================
 // Constants
@@ -122,11 +122,14 @@ impl UdpHeader {
         // Write payload length.
         fixed_buf[4..6].copy_from_slice(&((UDP_HEADER_SIZE + data.len()) as u16).to_be_bytes());
 
-        // Write checksum.
+        // Write checksum. A computed checksum of zero is sent as all ones, since zero means that there is no checksum.
         let checksum: u16 = if checksum_offload {
             0
         } else {
-            Self::checksum(ipv4_hdr, &fixed_buf[..], data)
+            match Self::checksum(ipv4_hdr, &fixed_buf[..], data) {
+                0 => 0xffff,
+                checksum => checksum,
+            }
         };
         fixed_buf[6..8].copy_from_slice(&checksum.to_be_bytes());
     }
@@ -153,7 +156,7 @@ impl UdpHeader {
         state += u16::from_be_bytes([dst_octets[2], dst_octets[3]]) as u32;
 
         // Padding zeros (1 byte) and UDP protocol number (1 byte)
-        state += u16::from_be_bytes([0, IpProtocol::UDP as u8]) as u32;
+        state += u16::from_be_bytes([0, u8::from(IpProtocol::UDP)]) as u32;
 
         // UDP segment length (2 bytes)
         state += (udp_hdr.len() + data.len()) as u32;
@@ -173,15 +176,8 @@ impl UdpHeader {
         // Checksum (2 bytes, all zeros)
         state += 0;
 
-        // Payload.
-        let mut chunks_iter: ChunksExact<u8> = data.chunks_exact(2);
-        while let Some(chunk) = chunks_iter.next() {
-            state += u16::from_be_bytes([chunk[0], chunk[1]]) as u32;
-        }
-        // Pad with zeros with payload has an odd number of bytes.
-        if let Some(&b) = chunks_iter.remainder().get(0) {
-            state += u16::from_be_bytes([b, 0]) as u32;
-        }
+        // Payload, padded with zeros if it has an odd number of bytes.
+        state += checksum::sum(data);
 
         // NOTE: We don't need to subtract out 0xFFFF as we accumulate the sum.
         // Since we use a u32 for intermediate state, we would need 2^16
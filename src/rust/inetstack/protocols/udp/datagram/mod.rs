@@ -41,6 +41,10 @@ pub struct UdpDatagram {
     data: DemiBuffer,
     /// Offload checksum to hardware?
     checksum_offload: bool,
+    /// Send with a checksum?
+    checksum: bool,
+    /// Ask the device for a transmit timestamp?
+    tx_timestamp: bool,
 }
 
 //======================================================================================================================
@@ -63,8 +67,22 @@ impl UdpDatagram {
             udp_hdr,
             data,
             checksum_offload,
+            checksum: true,
+            tx_timestamp: false,
         }
     }
+
+    /// Sends the target UDP datagram without a checksum.
+    pub fn without_checksum(mut self) -> Self {
+        self.checksum = false;
+        self
+    }
+
+    /// Asks the device to stamp the target UDP datagram when it transmits it.
+    pub fn with_tx_timestamp(mut self) -> Self {
+        self.tx_timestamp = true;
+        self
+    }
 }
 
 //======================================================================================================================
@@ -106,7 +124,7 @@ impl PacketBuf for UdpDatagram {
             &mut buf[cur_pos..(cur_pos + udp_hdr_size)],
             &self.ipv4_hdr,
             &self.data[..],
-            self.checksum_offload,
+            self.checksum_offload || !self.checksum,
         );
     }
 
@@ -114,6 +132,21 @@ impl PacketBuf for UdpDatagram {
     fn take_body(&self) -> Option<DemiBuffer> {
         Some(self.data.clone())
     }
+
+    /// Returns the priority of the target UDP datagram.
+    fn priority(&self) -> Option<u8> {
+        self.ethernet2_hdr.priority()
+    }
+
+    /// Returns whether the checksum of the target UDP datagram must be computed.
+    fn needs_checksum(&self) -> bool {
+        self.checksum
+    }
+
+    /// Returns whether the device should stamp the target UDP datagram when it transmits it.
+    fn tx_timestamp(&self) -> bool {
+        self.tx_timestamp
+    }
 }
 
 //======================================================================================================================
@@ -18,6 +18,19 @@ use crate::{
         fail::Fail,
         memory::DemiBuffer,
         network::{
+            admission::{
+                AdmissionKind,
+                SharedAdmissionControl,
+            },
+            ancillary::AncillaryData,
+            config::UdpConfig,
+            drops::DropReason,
+            flows::{
+                FlowKey,
+                FlowProtocol,
+                SharedFlowTable,
+            },
+            timestamp::RxTimestamp,
             types::MacAddress,
             NetworkRuntime,
         },
@@ -25,6 +38,7 @@ use crate::{
         SharedObject,
     },
     timer,
+    trace_event,
 };
 
 use ::std::{
@@ -38,6 +52,7 @@ use ::std::{
         Deref,
         DerefMut,
     },
+    time::Instant,
 };
 
 //======================================================================================================================
@@ -47,16 +62,16 @@ use ::std::{
 
 /// UDP Peer
 pub struct UdpPeer<N: NetworkRuntime> {
+    /// Shared runtime, which counts dropped datagrams.
+    runtime: SharedDemiRuntime,
     /// Underlying transport.
     transport: N,
     /// Underlying ARP peer.
     arp: SharedArpPeer<N>,
     /// Local link address.
     local_link_addr: MacAddress,
-    /// Local IPv4 address.
-    local_ipv4_addr: Ipv4Addr,
-    /// Offload checksum to hardware?
-    checksum_offload: bool,
+    /// UDP configuration, which also holds the tunables.
+    udp_config: UdpConfig,
     /// Incoming routing table.
     addresses: HashMap<SocketAddrV4, SharedUdpSocket<N>>,
 }
@@ -72,19 +87,18 @@ pub struct SharedUdpPeer<N: NetworkRuntime>(SharedObject<UdpPeer<N>>);
 
 impl<N: NetworkRuntime> SharedUdpPeer<N> {
     pub fn new(
-        _runtime: SharedDemiRuntime,
+        runtime: SharedDemiRuntime,
         transport: N,
         local_link_addr: MacAddress,
-        local_ipv4_addr: Ipv4Addr,
-        offload_checksum: bool,
+        udp_config: UdpConfig,
         arp: SharedArpPeer<N>,
     ) -> Result<Self, Fail> {
         Ok(Self(SharedObject::<UdpPeer<N>>::new(UdpPeer {
+            runtime,
             transport,
             arp,
             local_link_addr,
-            local_ipv4_addr,
-            checksum_offload: offload_checksum,
+            udp_config,
             addresses: HashMap::<SocketAddrV4, SharedUdpSocket<N>>::new(),
         })))
     }
@@ -92,11 +106,11 @@ impl<N: NetworkRuntime> SharedUdpPeer<N> {
     /// Opens a UDP socket.
     pub fn socket(&mut self) -> Result<SharedUdpSocket<N>, Fail> {
         SharedUdpSocket::<N>::new(
-            self.local_ipv4_addr,
             self.local_link_addr,
             self.transport.clone(),
             self.arp.clone(),
-            self.checksum_offload,
+            &self.udp_config,
+            self.runtime.get_memory_pool().open_account(false),
         )
     }
 
@@ -150,20 +164,20 @@ impl<N: NetworkRuntime> SharedUdpPeer<N> {
         &mut self,
         socket: &mut SharedUdpSocket<N>,
         size: usize,
-    ) -> Result<(Option<SocketAddr>, DemiBuffer), Fail> {
-        let (addr, buf) = socket.pop(size).await?;
-        Ok((Some(addr.into()), buf))
+    ) -> Result<(Option<SocketAddr>, DemiBuffer, AncillaryData), Fail> {
+        let (addr, buf, ancillary) = socket.pop(size).await?;
+        Ok((Some(addr.into()), buf, ancillary))
     }
 
     /// Consumes the payload from a buffer.
-    pub fn receive(&mut self, ipv4_hdr: Ipv4Header, buf: DemiBuffer) {
+    pub fn receive(&mut self, ipv4_hdr: Ipv4Header, buf: DemiBuffer, timestamp: RxTimestamp) {
         timer!("udp::receive");
-        // Parse datagram.
-        let (hdr, data): (UdpHeader, DemiBuffer) = match UdpHeader::parse(&ipv4_hdr, buf, self.checksum_offload) {
+        // Parse datagram. Checksums are always verified in software, so that failures are counted on every backend.
+        let (hdr, data): (UdpHeader, DemiBuffer) = match UdpHeader::parse(&ipv4_hdr, buf, false) {
             Ok(result) => result,
             Err(e) => {
-                let cause: String = format!("dropping packet: unable to parse UDP header");
-                warn!("{}: {:?}", cause, e);
+                self.runtime.record_drop(DropReason::from_parse_error(&e));
+                trace_event!("drop", "malformed_udp", cause = e.cause);
                 return;
             },
         };
@@ -172,6 +186,9 @@ impl<N: NetworkRuntime> SharedUdpPeer<N> {
         let local: SocketAddrV4 = SocketAddrV4::new(ipv4_hdr.get_dest_addr(), hdr.dest_port());
         let remote: SocketAddrV4 = SocketAddrV4::new(ipv4_hdr.get_src_addr(), hdr.src_port());
 
+        let now: Instant = self.runtime.get_now();
+        let mut admission: SharedAdmissionControl = self.runtime.get_admission_control();
+        let mut flows: SharedFlowTable = self.runtime.get_flow_table();
         let socket: &mut SharedUdpSocket<N> = match self.get_socket_from_addr(&local) {
             Some(queue) => queue,
             None => {
@@ -184,15 +201,48 @@ impl<N: NetworkRuntime> SharedUdpPeer<N> {
                         // port. However, we simply drop the datagram as this could be a port-scan attack, and not
                         // sending an ICMP message is a valid action. See https://www.rfc-editor.org/rfc/rfc792 for more
                         // details.
-                        let cause: String = format!("dropping packet: port not bound");
-                        warn!("{}: {:?}", cause, local);
+                        self.runtime.record_drop(DropReason::NoSocket);
+                        trace_event!("drop", "no_socket", local = local, remote = remote);
                         return;
                     },
                 }
             },
         };
+        // Drop the datagram if the admission control of the stack blocks or rate limits its source.
+        if !admission.admit(now, AdmissionKind::Datagram, *remote.ip()) {
+            trace_event!("drop", "admission", remote = remote);
+            socket.record_drop(DropReason::Admission, remote, data.len());
+            self.runtime.record_drop(DropReason::Admission);
+            return;
+        }
+        // Drop the datagram if the flow table of the stack has no room for its flow.
+        if !flows.track(now, FlowKey::new(FlowProtocol::Udp, local, remote)) {
+            trace_event!("drop", "flow_limit", local = local, remote = remote);
+            socket.record_drop(DropReason::FlowLimit, remote, data.len());
+            self.runtime.record_drop(DropReason::FlowLimit);
+            return;
+        }
+        let ancillary: AncillaryData = AncillaryData {
+            local: Some(ipv4_hdr.get_dest_addr()),
+            ttl: Some(ipv4_hdr.get_ttl()),
+            tos: Some(ipv4_hdr.get_tos()),
+            timestamp: Some(timestamp.software),
+            hw_timestamp: timestamp.hardware,
+        };
         // TODO: Drop this packet if local address/port pair is not bound.
-        socket.receive(remote, data)
+        if let Err(reason) = socket.receive(remote, data, ancillary) {
+            self.runtime.record_drop(reason);
+        }
+    }
+
+    /// Sets the UDP tunable [name] to [value].
+    pub fn set_tunable(&mut self, name: &str, value: &str) -> Result<(), Fail> {
+        self.udp_config.set_tunable(name, value)
+    }
+
+    /// Gets the value of the UDP tunable [name].
+    pub fn get_tunable(&self, name: &str) -> Result<String, Fail> {
+        self.udp_config.get_tunable(name)
     }
 
     fn get_socket_from_addr(&mut self, local: &SocketAddrV4) -> Option<&mut SharedUdpSocket<N>> {
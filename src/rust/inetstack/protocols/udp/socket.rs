@@ -24,12 +24,38 @@ use crate::{
         fail::Fail,
         memory::DemiBuffer,
         network::{
+            ancillary::AncillaryData,
+            config::UdpConfig,
+            drops::{
+                DropQueue,
+                DropReason,
+                DropRecord,
+                DropStats,
+            },
+            memory::{
+                MemoryAccount,
+                MemoryKind,
+            },
+            socket::{
+                filter::SocketFilter,
+                option::{
+                    SocketOption,
+                    SocketOptions,
+                },
+                shaper::Shaper,
+            },
+            timestamp::{
+                TxTimestamp,
+                TxTimestampQueue,
+            },
             types::MacAddress,
             unwrap_socketaddr,
             NetworkRuntime,
+            PacketBuf,
         },
         SharedObject,
     },
+    trace_event,
 };
 use ::std::{
     fmt::Debug,
@@ -48,30 +74,40 @@ use ::std::{
 // Constants
 //======================================================================================================================
 
-// Maximum size for receive queues (in messages).
-// TODO: Support max size on async queues.
-#[allow(dead_code)]
-const RECV_QUEUE_MAX_SIZE: usize = 1024;
-
 // Maximum size for send queues (in messages).
 // TODO: Support max size on async queues.
 #[allow(dead_code)]
 const SEND_QUEUE_MAX_SIZE: usize = 1024;
 
+/// Largest number of datagrams that one push is split into, which is the limit of Linux for UDP_SEGMENT.
+const MAX_SEGMENTS: usize = 64;
+
 //======================================================================================================================
 // Structures
 //======================================================================================================================
 
 /// Per-queue metadata for a UDP socket.
 pub struct UdpSocket<N: NetworkRuntime> {
-    local_ipv4_addr: Ipv4Addr,
     bound: Option<SocketAddrV4>,
     local_link_addr: MacAddress,
     network: N,
     // A queue of incoming packets as remote address and data buffer pairs.
-    recv_queue: AsyncQueue<(SocketAddrV4, DemiBuffer)>,
+    recv_queue: AsyncQueue<(SocketAddrV4, DemiBuffer, AncillaryData)>,
+    // Number of payload bytes in the receive queue, and the limits past which incoming datagrams are dropped.
+    recv_queue_bytes: usize,
+    recv_queue_max_datagrams: usize,
+    recv_queue_max_bytes: usize,
     arp: SharedArpPeer<N>,
     checksum_offload: bool,
+    // Whether outgoing datagrams carry a checksum, unless the application disables it on this socket.
+    checksum_generation: bool,
+    options: SocketOptions,
+    receive_filter: Option<SocketFilter>,
+    shaper: Shaper,
+    drops: DropQueue,
+    tx_timestamps: TxTimestampQueue,
+    // Memory that the receive queue buffers, accounted against the limit of the stack.
+    memory: MemoryAccount,
 }
 #[derive(Clone)]
 pub struct SharedUdpSocket<N: NetworkRuntime>(SharedObject<UdpSocket<N>>);
@@ -82,20 +118,29 @@ pub struct SharedUdpSocket<N: NetworkRuntime>(SharedObject<UdpSocket<N>>);
 
 impl<N: NetworkRuntime> SharedUdpSocket<N> {
     pub fn new(
-        local_ipv4_addr: Ipv4Addr,
         local_link_addr: MacAddress,
         network: N,
         arp: SharedArpPeer<N>,
-        checksum_offload: bool,
+        udp_config: &UdpConfig,
+        memory: MemoryAccount,
     ) -> Result<Self, Fail> {
         Ok(Self(SharedObject::new(UdpSocket::<N> {
-            local_ipv4_addr,
             bound: None,
             local_link_addr,
             network,
-            recv_queue: AsyncQueue::<(SocketAddrV4, DemiBuffer)>::default(),
+            recv_queue: AsyncQueue::<(SocketAddrV4, DemiBuffer, AncillaryData)>::default(),
+            recv_queue_bytes: 0,
+            recv_queue_max_datagrams: udp_config.get_recv_queue_max_datagrams(),
+            recv_queue_max_bytes: udp_config.get_recv_queue_max_bytes(),
             arp,
-            checksum_offload,
+            checksum_offload: udp_config.get_tx_checksum_offload(),
+            checksum_generation: udp_config.get_checksum_generation(),
+            options: SocketOptions::default(),
+            receive_filter: None,
+            shaper: Shaper::default(),
+            drops: DropQueue::default(),
+            tx_timestamps: TxTimestampQueue::default(),
+            memory,
         })))
     }
 
@@ -104,6 +149,31 @@ impl<N: NetworkRuntime> SharedUdpSocket<N> {
         Ok(())
     }
 
+    /// Sets [option] on this socket. The new value applies to the datagrams that are sent from now on.
+    pub fn set_socket_option(&mut self, option: SocketOption) -> Result<(), Fail> {
+        self.options.set(option)?;
+        let rate: Option<u64> = self.options.max_pacing_rate();
+        self.shaper.set_rate(rate);
+        let recv_err: bool = self.options.recv_err();
+        self.drops.set_enabled(recv_err);
+        let timestamping: u32 = self.options.timestamping();
+        self.tx_timestamps.set_flags(timestamping);
+        Ok(())
+    }
+
+    /// Gets the current value of [option] on this socket.
+    pub fn get_socket_option(&self, option: SocketOption) -> SocketOption {
+        match option {
+            SocketOption::SO_NO_CHECK(_) => SocketOption::SO_NO_CHECK(!self.checksum(&self.options)),
+            _ => self.options.get(option),
+        }
+    }
+
+    /// Attaches [filter] to this socket, replacing the previous one. Datagrams that are already queued are kept.
+    pub fn set_receive_filter(&mut self, filter: Option<SocketFilter>) {
+        self.receive_filter = filter;
+    }
+
     pub async fn push(&mut self, remote: Option<SocketAddr>, buf: DemiBuffer) -> Result<(), Fail> {
         let remote: SocketAddrV4 = if let Some(remote) = remote {
             unwrap_socketaddr(remote)?
@@ -112,49 +182,146 @@ impl<N: NetworkRuntime> SharedUdpSocket<N> {
             error!("pushto(): {}", &cause);
             return Err(Fail::new(libc::ENOTSUP, &cause));
         };
-        // Check that the socket is bound.
-        let port: u16 = if let Some(addr) = self.local() {
-            addr.port()
+        // Options are taken before resolving the remote, so that changes made in the meantime apply to later datagrams.
+        let options: SocketOptions = self.options;
+        // Check that the socket is bound. Sockets that are bound to every address send from the one of the interface
+        // that the datagram leaves through.
+        let (src_ipv4_addr, port): (Ipv4Addr, u16) = if let Some(addr) = self.local() {
+            match addr.ip().is_unspecified() {
+                true => (self.arp.source_addr_via(*remote.ip(), options.device()), addr.port()),
+                false => (*addr.ip(), addr.port()),
+            }
         } else {
             let cause: String = format!("queue is not bound");
             error!("pushto(): {}", &cause);
             return Err(Fail::new(libc::ENOTSUP, &cause));
         };
-        let remote_link_addr: MacAddress = self.arp.query(remote.ip().clone()).await?;
-        let udp_header: UdpHeader = UdpHeader::new(port, remote.port());
-        debug!("UDP send {:?}", udp_header);
-        let datagram = UdpDatagram::new(
-            Ethernet2Header::new(remote_link_addr, self.local_link_addr, EtherType2::Ipv4),
-            Ipv4Header::new(self.local_ipv4_addr, remote.ip().clone(), IpProtocol::UDP),
-            udp_header,
-            buf,
-            self.checksum_offload,
-        );
-        self.network.transmit(Box::new(datagram));
+        let payloads: Vec<DemiBuffer> = segment(buf, options.segment_size())?;
+        let remote_link_addr: MacAddress = self.arp.query_via(remote.ip().clone(), options.device()).await?;
+        for payload in payloads {
+            let udp_header: UdpHeader = UdpHeader::new(port, remote.port());
+            debug!("UDP send {:?}", udp_header);
+            let datagram: UdpDatagram = UdpDatagram::new(
+                Ethernet2Header::new(remote_link_addr, self.local_link_addr, EtherType2::Ipv4)
+                    .with_priority(options.priority()),
+                Ipv4Header::new(src_ipv4_addr, remote.ip().clone(), IpProtocol::UDP)
+                    .with_tos(options.tos())
+                    .with_ttl(options.ttl()),
+                udp_header,
+                payload,
+                self.checksum_offload,
+            );
+            let datagram: UdpDatagram = match self.checksum(&options) {
+                true => datagram,
+                false => datagram.without_checksum(),
+            };
+            let datagram: UdpDatagram = match self.tx_timestamps.wants_hardware() {
+                true => datagram.with_tx_timestamp(),
+                false => datagram,
+            };
+            self.shaper.wait(datagram.header_size() + datagram.body_size()).await;
+            self.network.transmit_one(Box::new(datagram));
+            self.tx_timestamps.record();
+        }
         Ok(())
     }
 
-    pub async fn pop(&mut self, size: usize) -> Result<(SocketAddrV4, DemiBuffer), Fail> {
+    pub async fn pop(&mut self, size: usize) -> Result<(SocketAddrV4, DemiBuffer, AncillaryData), Fail> {
         loop {
             match self.recv_queue.pop(None).await {
                 Ok(msg) => {
                     let remote: SocketAddrV4 = msg.0;
                     let mut buf: DemiBuffer = msg.1;
+                    let ancillary: AncillaryData = msg.2;
+                    self.recv_queue_bytes -= buf.len();
+                    let recv_queue_bytes: usize = self.recv_queue_bytes;
+                    self.memory.set(MemoryKind::RecvQueue, recv_queue_bytes);
                     // We got more bytes than expected, so we trim the buffer.
                     if size < buf.len() {
                         buf.trim(size - buf.len())?;
                     };
-                    return Ok((remote, buf));
+                    return Ok((remote, buf, ancillary));
                 },
                 Err(e) => return Err(e),
             }
         }
     }
 
-    pub fn receive(&mut self, remote: SocketAddrV4, buf: DemiBuffer) {
+    /// Checks if outgoing datagrams carry a checksum with [options], which override the configuration of the stack.
+    fn checksum(&self, options: &SocketOptions) -> bool {
+        match options.no_check() {
+            Some(no_check) => !no_check,
+            None => self.checksum_generation,
+        }
+    }
+
+    /// Queues a datagram from [remote] along with its [ancillary] data. If the datagram is dropped instead, this returns
+    /// the reason, which has already been recorded on this socket.
+    pub fn receive(
+        &mut self,
+        remote: SocketAddrV4,
+        mut buf: DemiBuffer,
+        ancillary: AncillaryData,
+    ) -> Result<(), DropReason> {
+        // Run the receive filter on the payload before it takes up space in the queue.
+        if let Some(filter) = self.receive_filter.as_ref() {
+            let len: usize = buf.len();
+            if !filter.apply(&mut buf) {
+                trace_event!("drop", "filtered", remote = remote);
+                self.drops.record(DropReason::Filtered, Some(remote), len);
+                return Err(DropReason::Filtered);
+            }
+        }
+        // Drop the datagram if the application is not popping fast enough to keep the queue within its limits.
+        let len: usize = buf.len();
+        if self.recv_queue.len() >= self.recv_queue_max_datagrams
+            || self.recv_queue_bytes + len > self.recv_queue_max_bytes
+        {
+            trace_event!(
+                "drop",
+                "queue_full",
+                remote = remote,
+                datagrams = self.recv_queue.len(),
+                bytes = self.recv_queue_bytes
+            );
+            self.drops.record(DropReason::QueueFull, Some(remote), len);
+            return Err(DropReason::QueueFull);
+        }
+        // Drop the datagram if buffering it would exceed the memory limit of the stack.
+        if !self.memory.admit(len) {
+            trace_event!("drop", "memory_limit", remote = remote, bytes = len);
+            self.drops.record(DropReason::MemoryLimit, Some(remote), len);
+            return Err(DropReason::MemoryLimit);
+        }
         // Push data to the receiver-side shared queue. This will cause the
         // associated pool operation to be ready.
-        self.recv_queue.push((remote, buf));
+        self.recv_queue_bytes += len;
+        let recv_queue_bytes: usize = self.recv_queue_bytes;
+        self.memory.set(MemoryKind::RecvQueue, recv_queue_bytes);
+        self.recv_queue.push((remote, buf, ancillary));
+        Ok(())
+    }
+
+    /// Records a datagram from [remote] with [len] bytes of payload that was dropped for [reason] before it reached
+    /// this socket.
+    pub fn record_drop(&mut self, reason: DropReason, remote: SocketAddrV4, len: usize) {
+        self.drops.record(reason, Some(remote), len);
+    }
+
+    /// Gets the number of incoming datagrams that were dropped on their way to this socket, for each reason.
+    pub fn get_drop_stats(&self) -> DropStats {
+        self.drops.stats()
+    }
+
+    /// Pops the oldest record from the error queue of this socket.
+    pub fn pop_drop_record(&mut self) -> Option<DropRecord> {
+        self.drops.pop()
+    }
+
+    /// Pops the oldest transmit timestamp of this socket. Hardware timestamps are read from the device on the way out.
+    pub fn pop_tx_timestamp(&mut self) -> Option<TxTimestamp> {
+        let mut network: N = self.network.clone();
+        self.tx_timestamps.pop(|| network.read_tx_hw_timestamp())
     }
 
     pub fn is_bound(&self) -> bool {
@@ -196,3 +363,33 @@ impl<N: NetworkRuntime> Debug for SharedUdpSocket<N> {
         write!(f, "UDP socket local={:?} remote={:?}", self.local(), self.remote())
     }
 }
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Splits [buf] into payloads of [segment_size] bytes, the last of which may be shorter. Payloads that fit in one
+/// segment, or that are pushed without a segment size, are sent as they are.
+fn segment(mut buf: DemiBuffer, segment_size: Option<u16>) -> Result<Vec<DemiBuffer>, Fail> {
+    let segment_size: usize = match segment_size {
+        Some(size) if buf.len() > size as usize => size as usize,
+        _ => return Ok(vec![buf]),
+    };
+    let count: usize = buf.len().div_ceil(segment_size);
+    if count > MAX_SEGMENTS {
+        let cause: String = format!(
+            "payload does not fit in {:?} datagrams (len={:?}, segment_size={:?})",
+            MAX_SEGMENTS,
+            buf.len(),
+            segment_size
+        );
+        error!("pushto(): {}", &cause);
+        return Err(Fail::new(libc::EINVAL, &cause));
+    }
+    let mut payloads: Vec<DemiBuffer> = Vec::with_capacity(count);
+    while buf.len() > segment_size {
+        payloads.push(buf.split_front(segment_size)?);
+    }
+    payloads.push(buf);
+    Ok(payloads)
+}
@@ -11,6 +11,25 @@ use crate::{
     },
     runtime::{
         memory::DemiBuffer,
+        network::{
+            ancillary::AncillaryData,
+            drops::{
+                DropReason,
+                DropRecord,
+                DropStats,
+            },
+            socket::{
+                filter::{
+                    BpfInstruction,
+                    SocketFilter,
+                },
+                option::SocketOption,
+            },
+            timestamp::{
+                TxTimestamp,
+                SOF_TIMESTAMPING_TX_SOFTWARE,
+            },
+        },
         queue::{
             OperationResult,
             QDesc,
@@ -31,6 +50,7 @@ use ::std::{
     time::{
         Duration,
         Instant,
+        SystemTime,
     },
 };
 
@@ -97,7 +117,7 @@ fn udp_push_pop() -> Result<()> {
     let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
     let alice_qt: QToken = alice.udp_pushto(alice_fd, buf.clone(), bob_addr)?;
     match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
-        (_, OperationResult::Push) => {},
+        (_, OperationResult::Push(_)) => {},
         _ => anyhow::bail!("Push failed"),
     };
     now += Duration::from_micros(1);
@@ -107,7 +127,7 @@ fn udp_push_pop() -> Result<()> {
     let bob_qt: QToken = bob.udp_pop(bob_fd)?;
 
     let (remote_addr, received_buf): (Option<SocketAddrV4>, DemiBuffer) = match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
-        (_, OperationResult::Pop(addr, buf)) => (addr, buf),
+        (_, OperationResult::Pop(addr, buf, _)) => (addr, buf),
         _ => anyhow::bail!("Pop failed"),
     };
     assert_eq!(remote_addr.unwrap(), alice_addr);
@@ -120,6 +140,461 @@ fn udp_push_pop() -> Result<()> {
     Ok(())
 }
 
+/// Tests that popped datagrams carry their destination address, TTL, TOS, and receive timestamp, so that a socket that
+/// is bound to the wildcard address can tell which local address they were sent to.
+#[test]
+fn udp_pop_ancillary() -> Result<()> {
+    let now: Instant = Instant::now();
+
+    // Setup Alice.
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let alice_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, 80);
+    let alice_fd: QDesc = alice.udp_socket()?;
+    alice.udp_bind(alice_fd, alice_addr)?;
+    alice.set_socket_option(alice_fd, SocketOption::IP_TTL(17))?;
+    alice.set_socket_option(alice_fd, SocketOption::IP_TOS(0x28))?;
+
+    // Setup Bob on the wildcard address.
+    let mut bob: SharedEngine = test_helpers::new_bob2(now);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_bind(bob_fd, SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 80))?;
+
+    // Send data to Bob.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&[0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    let alice_qt: QToken = alice.udp_pushto(alice_fd, buf, SocketAddrV4::new(test_helpers::BOB_IPV4, 80))?;
+    match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(_)) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+    let before: SystemTime = SystemTime::now();
+    bob.receive(alice.pop_frame())?;
+
+    // Receive data from Alice, along with its ancillary data.
+    let bob_qt: QToken = bob.udp_pop(bob_fd)?;
+    let ancillary: AncillaryData = match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Pop(Some(addr), _, ancillary)) if addr == alice_addr => ancillary,
+        _ => anyhow::bail!("Pop failed"),
+    };
+    crate::ensure_eq!(ancillary.local, Some(test_helpers::BOB_IPV4));
+    crate::ensure_eq!(ancillary.ttl, Some(17));
+    crate::ensure_eq!(ancillary.tos, Some(0x28));
+    crate::ensure_eq!(ancillary.timestamp.map_or(false, |timestamp| timestamp >= before), true);
+
+    // Close peers.
+    alice.udp_close(alice_fd)?;
+    bob.udp_close(bob_fd)?;
+
+    Ok(())
+}
+
+/// Tests that datagrams are stamped on their way out while transmit timestamps are enabled.
+#[test]
+fn udp_tx_timestamps() -> Result<()> {
+    let now: Instant = Instant::now();
+
+    // Setup Alice, with software transmit timestamps.
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let alice_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, 80);
+    let alice_fd: QDesc = alice.udp_socket()?;
+    alice.udp_bind(alice_fd, alice_addr)?;
+    alice.set_socket_option(alice_fd, SocketOption::SO_TIMESTAMPING(SOF_TIMESTAMPING_TX_SOFTWARE))?;
+
+    // Send two datagrams to Bob.
+    let before: SystemTime = SystemTime::now();
+    for _ in 0..2 {
+        let buf: DemiBuffer = DemiBuffer::from_slice(&[0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+        let alice_qt: QToken = alice.udp_pushto(alice_fd, buf, SocketAddrV4::new(test_helpers::BOB_IPV4, 80))?;
+        match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Push(_)) => {},
+            _ => anyhow::bail!("Push failed"),
+        };
+    }
+
+    // Each datagram got a software timestamp, and none from the device.
+    for id in 0..2 {
+        let timestamp: TxTimestamp = match alice.pop_tx_timestamp(alice_fd)? {
+            Some(timestamp) => timestamp,
+            None => anyhow::bail!("missing transmit timestamp"),
+        };
+        crate::ensure_eq!(timestamp.id, id);
+        crate::ensure_eq!(timestamp.software.map_or(false, |software| software >= before), true);
+        crate::ensure_eq!(timestamp.hardware, None);
+    }
+    crate::ensure_eq!(alice.pop_tx_timestamp(alice_fd)?, None);
+
+    // Close peers.
+    alice.udp_close(alice_fd)?;
+
+    Ok(())
+}
+
+/// Tests that a receive filter drops datagrams before they are queued and truncates the ones it keeps.
+#[test]
+fn udp_push_pop_filtered() -> Result<()> {
+    let now: Instant = Instant::now();
+
+    // Setup Alice.
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let alice_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, 80);
+    let alice_fd: QDesc = alice.udp_socket()?;
+    alice.udp_bind(alice_fd, alice_addr)?;
+
+    // Setup Bob, with a filter that keeps the first four bytes of datagrams whose payload starts with 0x01.
+    let mut bob: SharedEngine = test_helpers::new_bob2(now);
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_bind(bob_fd, bob_addr)?;
+    let filter: SocketFilter = SocketFilter::new(&[
+        BpfInstruction::stmt(0x30, 0),
+        BpfInstruction::jump(0x15, 0x01, 0, 1),
+        BpfInstruction::stmt(0x06, 4),
+        BpfInstruction::stmt(0x06, 0),
+    ])?;
+    bob.set_receive_filter(bob_fd, Some(filter))?;
+
+    // Send a datagram that is dropped and then one that is kept.
+    for byte in [0x02, 0x01] {
+        let buf: DemiBuffer = DemiBuffer::from_slice(&vec![byte; 32][..]).expect("slice should fit in DemiBuffer");
+        let alice_qt: QToken = alice.udp_pushto(alice_fd, buf, bob_addr)?;
+        match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Push(_)) => {},
+            _ => anyhow::bail!("Push failed"),
+        };
+        bob.receive(alice.pop_frame())?;
+    }
+
+    // Only the second datagram was queued.
+    let bob_qt: QToken = bob.udp_pop(bob_fd)?;
+    let received_buf: DemiBuffer = match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Pop(_, buf, _)) => buf,
+        _ => anyhow::bail!("Pop failed"),
+    };
+    crate::ensure_eq!(&received_buf[..], &[0x01; 4]);
+
+    // Close peers.
+    alice.udp_close(alice_fd)?;
+    bob.udp_close(bob_fd)?;
+
+    Ok(())
+}
+
+/// Tests that dropped datagrams are counted on the socket and the stack, and that they are queued on the error queue of
+/// the socket once it is enabled.
+#[test]
+fn udp_drop_stats() -> Result<()> {
+    let now: Instant = Instant::now();
+
+    // Setup Alice.
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let alice_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, 80);
+    let alice_fd: QDesc = alice.udp_socket()?;
+    alice.udp_bind(alice_fd, alice_addr)?;
+
+    // Setup Bob, with a filter that drops every datagram and an error queue.
+    let mut bob: SharedEngine = test_helpers::new_bob2(now);
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_bind(bob_fd, bob_addr)?;
+    let filter: SocketFilter = SocketFilter::new(&[BpfInstruction::stmt(0x06, 0)])?;
+    bob.set_receive_filter(bob_fd, Some(filter))?;
+    bob.set_socket_option(bob_fd, SocketOption::IP_RECVERR(true))?;
+
+    // Send a datagram that is filtered and one to a port where nobody listens.
+    for remote in [bob_addr, SocketAddrV4::new(test_helpers::BOB_IPV4, 81)] {
+        let buf: DemiBuffer = DemiBuffer::from_slice(&[0x01; 32][..]).expect("slice should fit in DemiBuffer");
+        let alice_qt: QToken = alice.udp_pushto(alice_fd, buf, remote)?;
+        match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Push(_)) => {},
+            _ => anyhow::bail!("Push failed"),
+        };
+        bob.receive(alice.pop_frame())?;
+    }
+
+    // Only the filtered datagram is attributed to the socket.
+    let socket_stats: DropStats = bob.get_drop_stats(Some(bob_fd))?;
+    crate::ensure_eq!(socket_stats.get(DropReason::Filtered), 1);
+    crate::ensure_eq!(socket_stats.total(), 1);
+    let stack_stats: DropStats = bob.get_drop_stats(None)?;
+    crate::ensure_eq!(stack_stats.get(DropReason::Filtered), 1);
+    crate::ensure_eq!(stack_stats.get(DropReason::NoSocket), 1);
+    crate::ensure_eq!(
+        bob.pop_drop_record(bob_fd)?,
+        Some(DropRecord {
+            reason: DropReason::Filtered,
+            remote: Some(alice_addr),
+            len: 32,
+        })
+    );
+    crate::ensure_eq!(bob.pop_drop_record(bob_fd)?, None);
+
+    // Close peers.
+    alice.udp_close(alice_fd)?;
+    bob.udp_close(bob_fd)?;
+
+    Ok(())
+}
+
+/// Tests that datagrams are dropped and counted once the receive queue of a socket is full, and that popping makes room
+/// again.
+#[test]
+fn udp_recv_queue_limits() -> Result<()> {
+    let now: Instant = Instant::now();
+
+    // Setup Alice.
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let alice_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, 80);
+    let alice_fd: QDesc = alice.udp_socket()?;
+    alice.udp_bind(alice_fd, alice_addr)?;
+
+    // Setup Bob, with room for two datagrams or 48 bytes.
+    let mut bob: SharedEngine = test_helpers::new_bob2(now);
+    bob.set_tunable("udp.recv_queue_max_datagrams", "2")?;
+    bob.set_tunable("udp.recv_queue_max_bytes", "48")?;
+    crate::ensure_eq!(bob.get_tunable("udp.recv_queue_max_datagrams")?, "2".to_string());
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_bind(bob_fd, bob_addr)?;
+
+    // The third datagram goes over the byte limit, and the fourth over the datagram limit.
+    for len in [16, 16, 32, 8] {
+        let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; len][..]).expect("slice should fit in DemiBuffer");
+        let alice_qt: QToken = alice.udp_pushto(alice_fd, buf, bob_addr)?;
+        match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Push(_)) => {},
+            _ => anyhow::bail!("Push failed"),
+        };
+        bob.receive(alice.pop_frame())?;
+    }
+    let socket_stats: DropStats = bob.get_drop_stats(Some(bob_fd))?;
+    crate::ensure_eq!(socket_stats.get(DropReason::QueueFull), 2);
+    crate::ensure_eq!(bob.get_drop_stats(None)?.get(DropReason::QueueFull), 2);
+
+    // Popping a datagram makes room for another one.
+    let bob_qt: QToken = bob.udp_pop(bob_fd)?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Pop(_, buf, _)) => crate::ensure_eq!(buf.len(), 16),
+        _ => anyhow::bail!("Pop failed"),
+    };
+    let buf: DemiBuffer = DemiBuffer::from_slice(&[0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    let alice_qt: QToken = alice.udp_pushto(alice_fd, buf, bob_addr)?;
+    match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(_)) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+    bob.receive(alice.pop_frame())?;
+    crate::ensure_eq!(bob.get_drop_stats(Some(bob_fd))?.get(DropReason::QueueFull), 2);
+
+    // Close peers.
+    alice.udp_close(alice_fd)?;
+    bob.udp_close(bob_fd)?;
+
+    Ok(())
+}
+
+/// Tests that datagrams are dropped and counted once the sockets of the stack buffer as much as its memory limit, and
+/// that the memory is given back when datagrams are popped.
+#[test]
+fn udp_memory_limit() -> Result<()> {
+    let now: Instant = Instant::now();
+
+    // Setup Alice.
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let alice_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, 80);
+    let alice_fd: QDesc = alice.udp_socket()?;
+    alice.udp_bind(alice_fd, alice_addr)?;
+
+    // Setup Bob, with room for 40 bytes across all sockets.
+    let mut bob: SharedEngine = test_helpers::new_bob2(now);
+    bob.set_tunable("memory.limit_bytes", "40")?;
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_bind(bob_fd, bob_addr)?;
+
+    // The third datagram goes over the limit.
+    for len in [16, 16, 16] {
+        let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; len][..]).expect("slice should fit in DemiBuffer");
+        let alice_qt: QToken = alice.udp_pushto(alice_fd, buf, bob_addr)?;
+        match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Push(_)) => {},
+            _ => anyhow::bail!("Push failed"),
+        };
+        bob.receive(alice.pop_frame())?;
+    }
+    crate::ensure_eq!(bob.get_drop_stats(Some(bob_fd))?.get(DropReason::MemoryLimit), 1);
+    crate::ensure_eq!(bob.get_drop_stats(None)?.get(DropReason::MemoryLimit), 1);
+    crate::ensure_eq!(bob.get_tunable("memory.used_bytes")?, "32".to_string());
+
+    // Popping a datagram gives its memory back.
+    let bob_qt: QToken = bob.udp_pop(bob_fd)?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Pop(_, buf, _)) => crate::ensure_eq!(buf.len(), 16),
+        _ => anyhow::bail!("Pop failed"),
+    };
+    crate::ensure_eq!(bob.get_tunable("memory.used_bytes")?, "16".to_string());
+
+    // Closing the socket gives back the rest.
+    alice.udp_close(alice_fd)?;
+    bob.udp_close(bob_fd)?;
+    crate::ensure_eq!(bob.get_tunable("memory.used_bytes")?, "0".to_string());
+
+    Ok(())
+}
+
+/// Tests that datagrams from sources that are blocked, or that exceed their rate, are dropped and counted.
+#[test]
+fn udp_admission() -> Result<()> {
+    let now: Instant = Instant::now();
+
+    // Setup Alice.
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let alice_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, 80);
+    let alice_fd: QDesc = alice.udp_socket()?;
+    alice.udp_bind(alice_fd, alice_addr)?;
+
+    // Setup Bob, which admits two datagrams per second from each source.
+    let mut bob: SharedEngine = test_helpers::new_bob2(now);
+    bob.set_tunable("admission.datagram_rate", "2")?;
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_bind(bob_fd, bob_addr)?;
+
+    // The third datagram exceeds the rate, and the fourth one comes once Alice is blocked.
+    for i in 0..4 {
+        if i == 3 {
+            bob.set_tunable("admission.blocklist", &test_helpers::ALICE_IPV4.to_string())?;
+        }
+        let buf: DemiBuffer = DemiBuffer::from_slice(&[0x5a; 16][..]).expect("slice should fit in DemiBuffer");
+        let alice_qt: QToken = alice.udp_pushto(alice_fd, buf, bob_addr)?;
+        match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Push(_)) => {},
+            _ => anyhow::bail!("Push failed"),
+        };
+        bob.receive(alice.pop_frame())?;
+    }
+    crate::ensure_eq!(bob.get_drop_stats(Some(bob_fd))?.get(DropReason::Admission), 2);
+    crate::ensure_eq!(bob.get_drop_stats(None)?.get(DropReason::Admission), 2);
+    crate::ensure_eq!(bob.get_tunable("admission.rate_limited")?, "1".to_string());
+    crate::ensure_eq!(bob.get_tunable("admission.blocked")?, "1".to_string());
+
+    // Only the admitted datagrams were queued.
+    for _ in 0..2 {
+        let bob_qt: QToken = bob.udp_pop(bob_fd)?;
+        match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Pop(_, buf, _)) => crate::ensure_eq!(buf.len(), 16),
+            _ => anyhow::bail!("Pop failed"),
+        };
+    }
+    crate::ensure_eq!(bob.get_tunable("memory.used_bytes")?, "0".to_string());
+
+    // Close peers.
+    alice.udp_close(alice_fd)?;
+    bob.udp_close(bob_fd)?;
+
+    Ok(())
+}
+
+/// Tests that datagrams are sent without a checksum once a socket disables it, and that datagrams with a bad checksum
+/// are dropped and counted.
+#[test]
+fn udp_checksum_generation() -> Result<()> {
+    // Offset of the UDP checksum in a frame without VLAN tag or IPv4 options.
+    const CHECKSUM_OFFSET: usize = 14 + 20 + 6;
+    let now: Instant = Instant::now();
+
+    // Setup Alice.
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let alice_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, 80);
+    let alice_fd: QDesc = alice.udp_socket()?;
+    alice.udp_bind(alice_fd, alice_addr)?;
+
+    // Setup Bob.
+    let mut bob: SharedEngine = test_helpers::new_bob2(now);
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_bind(bob_fd, bob_addr)?;
+
+    // Send one datagram with a checksum and one without.
+    let mut frames: Vec<DemiBuffer> = Vec::new();
+    for no_check in [false, true] {
+        alice.set_socket_option(alice_fd, SocketOption::SO_NO_CHECK(no_check))?;
+        let buf: DemiBuffer = DemiBuffer::from_slice(&[0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+        let alice_qt: QToken = alice.udp_pushto(alice_fd, buf, bob_addr)?;
+        match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Push(_)) => {},
+            _ => anyhow::bail!("Push failed"),
+        };
+        frames.push(alice.pop_frame());
+    }
+    crate::ensure_neq!(frames[0][CHECKSUM_OFFSET..CHECKSUM_OFFSET + 2], [0, 0]);
+    crate::ensure_eq!(frames[1][CHECKSUM_OFFSET..CHECKSUM_OFFSET + 2], [0, 0]);
+    crate::ensure_eq!(
+        alice.get_socket_option(alice_fd, SocketOption::SO_NO_CHECK(false))?,
+        SocketOption::SO_NO_CHECK(true)
+    );
+
+    // Corrupt the checksum of a copy of the first datagram.
+    let mut corrupted: DemiBuffer = DemiBuffer::from_slice(&frames[0][..]).expect("slice should fit in DemiBuffer");
+    corrupted[CHECKSUM_OFFSET] ^= 0xff;
+    bob.receive(corrupted)?;
+    crate::ensure_eq!(bob.get_drop_stats(None)?.get(DropReason::BadChecksum), 1);
+
+    // Both intact datagrams are delivered.
+    for frame in frames {
+        bob.receive(frame)?;
+        let bob_qt: QToken = bob.udp_pop(bob_fd)?;
+        match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Pop(_, buf, _)) => crate::ensure_eq!(buf.len(), 32),
+            _ => anyhow::bail!("Pop failed"),
+        };
+    }
+
+    // Close peers.
+    alice.udp_close(alice_fd)?;
+    bob.udp_close(bob_fd)?;
+
+    Ok(())
+}
+
+/// Tests that datagrams are held back once a socket goes over its pacing rate, until enough time passes.
+#[test]
+fn udp_push_paced() -> Result<()> {
+    let now: Instant = Instant::now();
+
+    // Setup Alice, limited to 1000 bytes per second.
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let alice_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, 80);
+    let alice_fd: QDesc = alice.udp_socket()?;
+    alice.udp_bind(alice_fd, alice_addr)?;
+    alice.set_socket_option(alice_fd, SocketOption::SO_MAX_PACING_RATE(1000))?;
+
+    // The first two datagrams go out in a burst, which leaves the socket in debt for about 15 seconds.
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
+    let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 40000][..]).expect("slice should fit in DemiBuffer");
+    for _ in 0..2 {
+        let alice_qt: QToken = alice.udp_pushto(alice_fd, buf.clone(), bob_addr)?;
+        match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Push(_)) => {},
+            _ => anyhow::bail!("Push failed"),
+        };
+    }
+    crate::ensure_eq!(alice.pop_all_frames().len(), 2);
+
+    // The third datagram goes out once the debt is paid.
+    let alice_qt: QToken = alice.udp_pushto(alice_fd, buf, bob_addr)?;
+    alice.poll();
+    crate::ensure_eq!(alice.pop_all_frames().len(), 0);
+    alice.advance_clock(now + Duration::from_secs(16));
+    match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(_)) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+    crate::ensure_eq!(alice.pop_all_frames().len(), 1);
+
+    alice.udp_close(alice_fd)?;
+
+    Ok(())
+}
+
 //==============================================================================
 // Push & Pop
 //==============================================================================
@@ -146,7 +621,7 @@ fn udp_push_pop_wildcard_address() -> Result<()> {
     let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
     let qt: QToken = alice.udp_pushto(alice_fd, buf.clone(), bob_addr)?;
     match alice.wait(qt, DEFAULT_TIMEOUT)? {
-        (_, OperationResult::Push) => {},
+        (_, OperationResult::Push(_)) => {},
         _ => anyhow::bail!("Push failed"),
     };
 
@@ -156,7 +631,7 @@ fn udp_push_pop_wildcard_address() -> Result<()> {
     bob.receive(alice.pop_frame()).unwrap();
     let bob_qt: QToken = bob.udp_pop(bob_fd)?;
     let (remote_addr, received_buf): (Option<SocketAddrV4>, DemiBuffer) = match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
-        (_, OperationResult::Pop(addr, buf)) => (addr, buf),
+        (_, OperationResult::Pop(addr, buf, _)) => (addr, buf),
         _ => anyhow::bail!("Pop failed"),
     };
     assert_eq!(remote_addr.unwrap(), alice_addr);
@@ -168,6 +643,180 @@ fn udp_push_pop_wildcard_address() -> Result<()> {
     Ok(())
 }
 
+/// Tests that payloads larger than the segment size of a socket are split into datagrams of that size.
+#[test]
+fn udp_push_segmented() -> Result<()> {
+    let now: Instant = Instant::now();
+
+    // Setup Alice, who splits payloads into datagrams of 1000 bytes.
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let alice_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, 80);
+    let alice_fd: QDesc = alice.udp_socket()?;
+    alice.udp_bind(alice_fd, alice_addr)?;
+    alice.set_socket_option(alice_fd, SocketOption::UDP_SEGMENT(1000))?;
+
+    // Setup Bob.
+    let mut bob: SharedEngine = test_helpers::new_bob2(now);
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_bind(bob_fd, bob_addr)?;
+
+    // One push of 2500 bytes goes out as three datagrams.
+    let data: Vec<u8> = (0..2500).map(|i| i as u8).collect();
+    let buf: DemiBuffer = DemiBuffer::from_slice(&data[..]).expect("slice should fit in DemiBuffer");
+    let alice_qt: QToken = alice.udp_pushto(alice_fd, buf, bob_addr)?;
+    match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(_)) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+    let frames: Vec<DemiBuffer> = alice.pop_all_frames().into();
+    crate::ensure_eq!(frames.len(), 3);
+
+    // Bob receives the payload in order, one segment per datagram.
+    for frame in frames {
+        bob.receive(frame)?;
+    }
+    for chunk in data.chunks(1000) {
+        let bob_qt: QToken = bob.udp_pop(bob_fd)?;
+        match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Pop(_, buf, _)) => crate::ensure_eq!(&buf[..], chunk),
+            _ => anyhow::bail!("Pop failed"),
+        };
+    }
+
+    // Payloads that take too many datagrams are rejected.
+    let buf: DemiBuffer =
+        DemiBuffer::from_slice(&vec![0x5a; 64 * 1000 + 1][..]).expect("slice should fit in DemiBuffer");
+    let alice_qt: QToken = alice.udp_pushto(alice_fd, buf, bob_addr)?;
+    match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Failed(e)) => crate::ensure_eq!(e.errno, libc::EINVAL),
+        _ => anyhow::bail!("Push should have failed"),
+    };
+    crate::ensure_eq!(alice.pop_all_frames().len(), 0);
+
+    // Close peers.
+    alice.udp_close(alice_fd)?;
+    bob.udp_close(bob_fd)?;
+
+    Ok(())
+}
+
+//==============================================================================
+// Cancel
+//==============================================================================
+
+/// Tests that a cancelled pop completes with ECANCELED and does not take data away from the next pop.
+#[test]
+fn udp_cancel_pop() -> Result<()> {
+    let now: Instant = Instant::now();
+
+    // Setup Alice.
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let alice_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, 80);
+    let alice_fd: QDesc = alice.udp_socket()?;
+    alice.udp_bind(alice_fd, alice_addr)?;
+
+    // Setup Bob.
+    let mut bob: SharedEngine = test_helpers::new_bob2(now);
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_bind(bob_fd, bob_addr)?;
+
+    // Cancel a pop that has nothing to receive.
+    let cancelled_qt: QToken = bob.udp_pop(bob_fd)?;
+    bob.poll();
+    bob.cancel(cancelled_qt)?;
+    match bob.wait(cancelled_qt, DEFAULT_TIMEOUT)? {
+        (qd, OperationResult::Failed(e)) if qd == bob_fd && e.errno == libc::ECANCELED => {},
+        _ => anyhow::bail!("cancelled pop should fail"),
+    };
+
+    // Send data to Bob.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    let alice_qt: QToken = alice.udp_pushto(alice_fd, buf.clone(), bob_addr)?;
+    match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(_)) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+    bob.receive(alice.pop_frame())?;
+
+    // The next pop receives the data.
+    let bob_qt: QToken = bob.udp_pop(bob_fd)?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Pop(addr, received, _)) => {
+            assert_eq!(addr, Some(alice_addr));
+            assert_eq!(received[..], buf[..]);
+        },
+        _ => anyhow::bail!("Pop failed"),
+    };
+
+    // Operations that already completed cannot be cancelled.
+    crate::ensure_eq!(bob.cancel(bob_qt).unwrap_err().errno, libc::EINVAL);
+
+    // Close peers.
+    alice.udp_close(alice_fd)?;
+    bob.udp_close(bob_fd)?;
+
+    Ok(())
+}
+
+//==============================================================================
+// Timeout
+//==============================================================================
+
+/// Tests that a pop that receives nothing before its timeout fails with ETIMEDOUT and does not take data away from
+/// the next pop.
+#[test]
+fn udp_pop_timeout() -> Result<()> {
+    let now: Instant = Instant::now();
+
+    // Setup Alice.
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let alice_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::ALICE_IPV4, 80);
+    let alice_fd: QDesc = alice.udp_socket()?;
+    alice.udp_bind(alice_fd, alice_addr)?;
+
+    // Setup Bob.
+    let mut bob: SharedEngine = test_helpers::new_bob2(now);
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_bind(bob_fd, bob_addr)?;
+
+    // Let a pop that has nothing to receive time out.
+    let timed_out_qt: QToken = bob.pop(bob_fd, None, Some(Duration::from_secs(1)))?;
+    bob.poll();
+    bob.advance_clock(now + Duration::from_secs(2));
+    match bob.wait(timed_out_qt, DEFAULT_TIMEOUT)? {
+        (qd, OperationResult::Failed(e)) if qd == bob_fd && e.errno == libc::ETIMEDOUT => {},
+        _ => anyhow::bail!("pop should time out"),
+    };
+
+    // Send data to Bob.
+    let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
+    let alice_qt: QToken = alice.udp_pushto(alice_fd, buf.clone(), bob_addr)?;
+    match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Push(_)) => {},
+        _ => anyhow::bail!("Push failed"),
+    };
+    bob.receive(alice.pop_frame())?;
+
+    // A pop whose timeout has not passed receives the data.
+    let bob_qt: QToken = bob.pop(bob_fd, None, Some(Duration::from_secs(1)))?;
+    match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+        (_, OperationResult::Pop(addr, received, _)) => {
+            assert_eq!(addr, Some(alice_addr));
+            assert_eq!(received[..], buf[..]);
+        },
+        _ => anyhow::bail!("Pop failed"),
+    };
+
+    // Close peers.
+    alice.udp_close(alice_fd)?;
+    bob.udp_close(bob_fd)?;
+
+    Ok(())
+}
+
 //==============================================================================
 // Ping Pong
 //==============================================================================
@@ -194,7 +843,7 @@ fn udp_ping_pong() -> Result<()> {
     let buf_a: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
     let alice_qt: QToken = alice.udp_pushto(alice_fd, buf_a.clone(), bob_addr)?;
     match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
-        (_, OperationResult::Push) => {},
+        (_, OperationResult::Push(_)) => {},
         _ => anyhow::bail!("Push failed"),
     };
     now += Duration::from_micros(1);
@@ -205,7 +854,7 @@ fn udp_ping_pong() -> Result<()> {
     bob.poll();
 
     let (remote_addr, received_buf_a): (Option<SocketAddrV4>, DemiBuffer) = match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
-        (_, OperationResult::Pop(addr, buf)) => (addr, buf),
+        (_, OperationResult::Pop(addr, buf, _)) => (addr, buf),
         _ => anyhow::bail!("Pop failed"),
     };
     assert_eq!(remote_addr.unwrap(), alice_addr);
@@ -217,7 +866,7 @@ fn udp_ping_pong() -> Result<()> {
     let buf_b: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
     let bob_qt2: QToken = bob.udp_pushto(bob_fd, buf_b.clone(), alice_addr)?;
     match bob.wait(bob_qt2, DEFAULT_TIMEOUT)? {
-        (_, OperationResult::Push) => {},
+        (_, OperationResult::Push(_)) => {},
         _ => anyhow::bail!("Push failed"),
     };
     bob.poll();
@@ -228,7 +877,7 @@ fn udp_ping_pong() -> Result<()> {
     let alice_qt: QToken = alice.udp_pop(alice_fd)?;
     let (remote_addr, received_buf_b): (Option<SocketAddrV4>, DemiBuffer) =
         match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
-            (_, OperationResult::Pop(addr, buf)) => (addr, buf),
+            (_, OperationResult::Pop(addr, buf, _)) => (addr, buf),
             _ => anyhow::bail!("Pop failed"),
         };
     assert_eq!(remote_addr.unwrap(), bob_addr);
@@ -326,7 +975,7 @@ fn udp_loop2_push_pop() -> Result<()> {
         let buf: DemiBuffer = DemiBuffer::from_slice(&vec![(b % 256) as u8; 32][..]).expect("slice should fit");
         let alice_qt: QToken = alice.udp_pushto(alice_fd, buf.clone(), bob_addr)?;
         match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
-            (_, OperationResult::Push) => {},
+            (_, OperationResult::Push(_)) => {},
             _ => anyhow::bail!("Push failed"),
         };
         alice.poll();
@@ -337,7 +986,7 @@ fn udp_loop2_push_pop() -> Result<()> {
         bob.receive(alice.pop_frame()).unwrap();
         let bob_qt: QToken = bob.udp_pop(bob_fd)?;
         let (remote_addr, received_buf): (Option<SocketAddrV4>, DemiBuffer) = match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
-            (_, OperationResult::Pop(addr, buf)) => (addr, buf),
+            (_, OperationResult::Pop(addr, buf, _)) => (addr, buf),
             _ => anyhow::bail!("Pop failed"),
         };
         assert_eq!(remote_addr.unwrap(), alice_addr);
@@ -389,7 +1038,7 @@ fn udp_loop2_ping_pong() -> Result<()> {
         let buf_a: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
         let alice_qt: QToken = alice.udp_pushto(alice_fd, buf_a.clone(), bob_addr)?;
         match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
-            (_, OperationResult::Push) => {},
+            (_, OperationResult::Push(_)) => {},
             _ => anyhow::bail!("Push failed"),
         };
         alice.poll();
@@ -401,7 +1050,7 @@ fn udp_loop2_ping_pong() -> Result<()> {
         let bob_qt: QToken = bob.udp_pop(bob_fd)?;
         let (remote_addr, received_buf_a): (Option<SocketAddrV4>, DemiBuffer) =
             match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
-                (_, OperationResult::Pop(addr, buf)) => (addr, buf),
+                (_, OperationResult::Pop(addr, buf, _)) => (addr, buf),
                 _ => anyhow::bail!("Pop failed"),
             };
         assert_eq!(remote_addr.unwrap(), alice_addr);
@@ -413,7 +1062,7 @@ fn udp_loop2_ping_pong() -> Result<()> {
         let buf_b: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
         let bob_qt: QToken = bob.udp_pushto(bob_fd, buf_b.clone(), alice_addr)?;
         match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
-            (_, OperationResult::Push) => {},
+            (_, OperationResult::Push(_)) => {},
             _ => anyhow::bail!("Push failed"),
         };
 
@@ -424,7 +1073,7 @@ fn udp_loop2_ping_pong() -> Result<()> {
         let alice_qt: QToken = alice.udp_pop(alice_fd)?;
         let (remote_addr, received_buf_b): (Option<SocketAddrV4>, DemiBuffer) =
             match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
-                (_, OperationResult::Pop(addr, buf)) => (addr, buf),
+                (_, OperationResult::Pop(addr, buf, _)) => (addr, buf),
                 _ => anyhow::bail!("Pop failed"),
             };
         assert_eq!(remote_addr.unwrap(), bob_addr);
@@ -540,7 +1189,7 @@ fn udp_pop_not_bound() -> Result<()> {
     let buf: DemiBuffer = DemiBuffer::from_slice(&vec![0x5a; 32][..]).expect("slice should fit in DemiBuffer");
     let alice_qt: QToken = alice.udp_pushto(alice_fd, buf, bob_addr)?;
     match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
-        (_, OperationResult::Push) => {},
+        (_, OperationResult::Push(_)) => {},
         _ => anyhow::bail!("Push failed"),
     };
     alice.poll();
@@ -595,3 +1244,57 @@ fn udp_push_bad_file_descriptor() -> Result<()> {
 
     Ok(())
 }
+
+/// Tests that datagrams of new flows are dropped once the flow table is full and refuses new flows.
+#[test]
+fn udp_flow_limit() -> Result<()> {
+    let now: Instant = Instant::now();
+
+    // Setup Alice, with a socket for each flow.
+    let mut alice: SharedEngine = test_helpers::new_alice2(now);
+    let mut alice_fds: Vec<QDesc> = Vec::new();
+    for port in [80, 81] {
+        let alice_fd: QDesc = alice.udp_socket()?;
+        alice.udp_bind(alice_fd, SocketAddrV4::new(test_helpers::ALICE_IPV4, port))?;
+        alice_fds.push(alice_fd);
+    }
+
+    // Setup Bob, which tracks a single flow.
+    let mut bob: SharedEngine = test_helpers::new_bob2(now);
+    bob.set_tunable("flows.max_entries", "1")?;
+    bob.set_tunable("flows.policy", "refuse")?;
+    let bob_addr: SocketAddrV4 = SocketAddrV4::new(test_helpers::BOB_IPV4, 80);
+    let bob_fd: QDesc = bob.udp_socket()?;
+    bob.udp_bind(bob_fd, bob_addr)?;
+
+    // The second flow does not fit, while the first one keeps going.
+    for alice_fd in [alice_fds[0], alice_fds[1], alice_fds[0]] {
+        let buf: DemiBuffer = DemiBuffer::from_slice(&[0x5a; 16][..]).expect("slice should fit in DemiBuffer");
+        let alice_qt: QToken = alice.udp_pushto(alice_fd, buf, bob_addr)?;
+        match alice.wait(alice_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Push(_)) => {},
+            _ => anyhow::bail!("Push failed"),
+        };
+        bob.receive(alice.pop_frame())?;
+    }
+    crate::ensure_eq!(bob.get_drop_stats(Some(bob_fd))?.get(DropReason::FlowLimit), 1);
+    crate::ensure_eq!(bob.get_tunable("flows.entries")?, "1".to_string());
+    crate::ensure_eq!(bob.get_tunable("flows.refusals")?, "1".to_string());
+
+    // Only the datagrams of the first flow were queued.
+    for _ in 0..2 {
+        let bob_qt: QToken = bob.udp_pop(bob_fd)?;
+        match bob.wait(bob_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Pop(Some(addr), _, _)) => crate::ensure_eq!(addr.port(), 80),
+            _ => anyhow::bail!("Pop failed"),
+        };
+    }
+
+    // Close peers.
+    for alice_fd in alice_fds {
+        alice.udp_close(alice_fd)?;
+    }
+    bob.udp_close(bob_fd)?;
+
+    Ok(())
+}
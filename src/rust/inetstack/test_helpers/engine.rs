@@ -14,13 +14,18 @@ use crate::{
             DemiBuffer,
             MemoryRuntime,
         },
-        network::types::MacAddress,
+        network::{
+            config::SecurityAssociation,
+            socket::option::SocketOption,
+            types::MacAddress,
+        },
         OperationResult,
         QDesc,
         QToken,
         SharedDemiRuntime,
     },
 };
+use ::anyhow::Result;
 use ::socket2::{
     Domain,
     Protocol,
@@ -48,19 +53,32 @@ use ::std::{
 /// A default amount of time to wait on an operation to complete. This was chosen arbitrarily.
 pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(120);
 
+/// Largest number of rounds in which two engines exchange frames before they are expected to be quiet.
+const MAX_EXCHANGE_ROUNDS: usize = 16;
+
 #[derive(Clone)]
 pub struct SharedEngine(SharedNetworkLibOS<SharedInetStack<SharedTestRuntime>>);
 
 impl SharedEngine {
     pub fn new(test_rig: SharedTestRuntime, now: Instant) -> Result<Self, Fail> {
+        Self::new_with_ipsec(test_rig, now, None)
+    }
+
+    /// Creates an engine whose IPv4 traffic is protected with IPsec if [security_associations] is set.
+    pub fn new_with_ipsec(
+        test_rig: SharedTestRuntime,
+        now: Instant,
+        security_associations: Option<Vec<SecurityAssociation>>,
+    ) -> Result<Self, Fail> {
         // Instantiate all of the layers.
         // Shared Demikernel runtime.
         let runtime: SharedDemiRuntime = SharedDemiRuntime::new(now);
-        let transport: SharedInetStack<SharedTestRuntime> = SharedInetStack::new_test(
+        let transport: SharedInetStack<SharedTestRuntime> = SharedInetStack::new_with_ipsec(
             runtime.clone(),
             test_rig.clone(),
             test_rig.get_link_addr(),
             test_rig.get_ip_addr(),
+            security_associations,
         )?;
 
         Ok(Self(SharedNetworkLibOS::<SharedInetStack<SharedTestRuntime>>::new(
@@ -76,6 +94,89 @@ impl SharedEngine {
         self.get_transport().get_network().pop_all_frames()
     }
 
+    /// Makes [peer] listen on [port] and [self] connect to it, after setting [listener_options] on the listening socket
+    /// and [client_options] on the connecting one. Returns the listening socket along with the pending accept and
+    /// connect, once the frames of the handshake are exchanged.
+    pub fn start_connect(
+        &mut self,
+        peer: &mut SharedEngine,
+        port: u16,
+        listener_options: &[SocketOption],
+        client_options: &[SocketOption],
+    ) -> Result<(QDesc, QToken, QToken)> {
+        let peer_addr: SocketAddrV4 = SocketAddrV4::new(peer.get_transport().get_ip_addr(), port);
+        let server_fd: QDesc = peer.tcp_socket()?;
+        for option in listener_options {
+            peer.set_socket_option(server_fd, *option)?;
+        }
+        peer.tcp_bind(server_fd, peer_addr)?;
+        peer.tcp_listen(server_fd, 8)?;
+        let accept_qt: QToken = peer.tcp_accept(server_fd)?;
+        let client_fd: QDesc = self.tcp_socket()?;
+        for option in client_options {
+            self.set_socket_option(client_fd, *option)?;
+        }
+        let connect_qt: QToken = self.tcp_connect(client_fd, peer_addr)?;
+        self.exchange_frames(peer)?;
+        Ok((server_fd, accept_qt, connect_qt))
+    }
+
+    /// Connects [self] to [peer] on [port] like [Self::start_connect] does, and waits for the connection to be
+    /// established. Returns the listening and the accepted socket of [peer], and the connected socket of [self].
+    pub fn establish(
+        &mut self,
+        peer: &mut SharedEngine,
+        port: u16,
+        listener_options: &[SocketOption],
+        client_options: &[SocketOption],
+    ) -> Result<(QDesc, QDesc, QDesc)> {
+        let (server_fd, accept_qt, connect_qt) = self.start_connect(peer, port, listener_options, client_options)?;
+        let client_fd: QDesc = match self.wait(connect_qt, DEFAULT_TIMEOUT)? {
+            (qd, OperationResult::Connect) => qd,
+            result => anyhow::bail!("connect() should succeed: {:?}", result),
+        };
+        let accepted_fd: QDesc = match peer.wait(accept_qt, DEFAULT_TIMEOUT)? {
+            (_, OperationResult::Accept((qd, _))) => qd,
+            result => anyhow::bail!("accept() should succeed: {:?}", result),
+        };
+        Ok((server_fd, accepted_fd, client_fd))
+    }
+
+    /// Hands the frames that [self] and [peer] sent over to each other, until neither has anything left to send.
+    pub fn exchange_frames(&mut self, peer: &mut SharedEngine) -> Result<()> {
+        self.exchange_frames_with(peer, |_| Ok(()))
+    }
+
+    /// Hands the frames that [self] and [peer] sent over to each other, until neither has anything left to send,
+    /// running [check] on each frame before it is delivered.
+    pub fn exchange_frames_with(
+        &mut self,
+        peer: &mut SharedEngine,
+        mut check: impl FnMut(&DemiBuffer) -> Result<()>,
+    ) -> Result<()> {
+        for _ in 0..MAX_EXCHANGE_ROUNDS {
+            // Newly scheduled coroutines only run on the second poll.
+            self.poll();
+            self.poll();
+            peer.poll();
+            peer.poll();
+            let frames: VecDeque<DemiBuffer> = self.pop_all_frames();
+            let peer_frames: VecDeque<DemiBuffer> = peer.pop_all_frames();
+            if frames.is_empty() && peer_frames.is_empty() {
+                return Ok(());
+            }
+            for frame in frames {
+                check(&frame)?;
+                peer.receive(frame)?;
+            }
+            for frame in peer_frames {
+                check(&frame)?;
+                self.receive(frame)?;
+            }
+        }
+        anyhow::bail!("frames kept flowing")
+    }
+
     pub fn advance_clock(&mut self, now: Instant) {
         self.get_runtime().advance_clock(now)
     }
@@ -96,11 +197,11 @@ impl SharedEngine {
 
     pub fn udp_pushto(&mut self, qd: QDesc, buf: DemiBuffer, to: SocketAddrV4) -> Result<QToken, Fail> {
         let data: demi_sgarray_t = self.get_transport().into_sgarray(buf)?;
-        self.pushto(qd, &data, to.into())
+        self.pushto(qd, &data, to.into(), None)
     }
 
     pub fn udp_pop(&mut self, qd: QDesc) -> Result<QToken, Fail> {
-        self.pop(qd, None)
+        self.pop(qd, None, None)
     }
 
     pub fn udp_socket(&mut self) -> Result<QDesc, Fail> {
@@ -119,12 +220,30 @@ impl SharedEngine {
         }
     }
 
+    pub fn raw_socket(&mut self, protocol: u8) -> Result<QDesc, Fail> {
+        self.socket(Domain::IPV4, Type::RAW, Protocol::from(protocol as libc::c_int))
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn packet_socket(&mut self, ether_type: u16) -> Result<QDesc, Fail> {
+        self.socket(
+            Domain::PACKET,
+            Type::RAW,
+            Protocol::from(ether_type.to_be() as libc::c_int),
+        )
+    }
+
+    pub fn packet_push(&mut self, qd: QDesc, frame: DemiBuffer) -> Result<QToken, Fail> {
+        let data: demi_sgarray_t = self.get_transport().into_sgarray(frame)?;
+        self.push(qd, &data, None)
+    }
+
     pub fn tcp_socket(&mut self) -> Result<QDesc, Fail> {
         self.socket(Domain::IPV4, Type::STREAM, Protocol::TCP)
     }
 
     pub fn tcp_connect(&mut self, socket_fd: QDesc, remote_endpoint: SocketAddrV4) -> Result<QToken, Fail> {
-        self.connect(socket_fd, remote_endpoint.into())
+        self.connect(socket_fd, remote_endpoint.into(), None)
     }
 
     pub fn tcp_bind(&mut self, socket_fd: QDesc, endpoint: SocketAddrV4) -> Result<(), Fail> {
@@ -132,16 +251,16 @@ impl SharedEngine {
     }
 
     pub fn tcp_accept(&mut self, fd: QDesc) -> Result<QToken, Fail> {
-        self.accept(fd)
+        self.accept(fd, None)
     }
 
     pub fn tcp_push(&mut self, socket_fd: QDesc, buf: DemiBuffer) -> Result<QToken, Fail> {
         let data: demi_sgarray_t = self.get_transport().into_sgarray(buf)?;
-        self.push(socket_fd, &data)
+        self.push(socket_fd, &data, None)
     }
 
     pub fn tcp_pop(&mut self, socket_fd: QDesc) -> Result<QToken, Fail> {
-        self.pop(socket_fd, None)
+        self.pop(socket_fd, None, None)
     }
 
     pub fn tcp_async_close(&mut self, socket_fd: QDesc) -> Result<QToken, Fail> {
@@ -3,14 +3,26 @@
 
 pub mod engine;
 pub mod runtime;
-use crate::runtime::network::{
-    config::{
-        ArpConfig,
-        TcpConfig,
-        UdpConfig,
+use crate::runtime::{
+    network::{
+        config::{
+            ArpConfig,
+            SecurityAssociation,
+            TcpConfig,
+            UdpConfig,
+        },
+        socket::option::{
+            SocketOption,
+            TcpAoAlgorithm,
+            TcpAoKey,
+            TcpMd5Key,
+        },
+        types::MacAddress,
     },
-    types::MacAddress,
+    QDesc,
+    QToken,
 };
+use ::anyhow::Result;
 use ::std::{
     collections::HashMap,
     net::Ipv4Addr,
@@ -33,6 +45,21 @@ pub const BOB_MAC: MacAddress = MacAddress::new([0xab, 0x89, 0x67, 0x45, 0x23, 0
 pub const BOB_IPV4: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 2);
 pub const CARRIE_MAC: MacAddress = MacAddress::new([0xef, 0xcd, 0xab, 0x89, 0x67, 0x45]);
 pub const CARRIE_IPV4: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 3);
+/// Key that Alice and Bob share to sign their segments.
+pub const SHARED_KEY: &[u8] = b"shared secret";
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// How the segments of a test connection are signed.
+#[derive(Clone, Copy, Debug)]
+pub enum TcpKeyKind {
+    /// TCP MD5 signature option.
+    Md5,
+    /// TCP authentication option, with the same send and receive identifier.
+    Ao(u8, TcpAoAlgorithm),
+}
 
 //==============================================================================
 // Standalone Functions
@@ -68,6 +95,23 @@ pub fn new_bob(now: Instant) -> SharedEngine {
 }
 
 pub fn new_alice2(now: Instant) -> SharedEngine {
+    SharedEngine::new(new_alice2_runtime(now, TcpConfig::default()), now).unwrap()
+}
+
+pub fn new_alice2_with_tcp_config(now: Instant, tcp_config: TcpConfig) -> SharedEngine {
+    SharedEngine::new(new_alice2_runtime(now, tcp_config), now).unwrap()
+}
+
+pub fn new_alice2_with_ipsec(now: Instant, security_associations: Vec<SecurityAssociation>) -> SharedEngine {
+    SharedEngine::new_with_ipsec(
+        new_alice2_runtime(now, TcpConfig::default()),
+        now,
+        Some(security_associations),
+    )
+    .unwrap()
+}
+
+fn new_alice2_runtime(now: Instant, tcp_config: TcpConfig) -> SharedTestRuntime {
     let mut arp: HashMap<Ipv4Addr, MacAddress> = HashMap::<Ipv4Addr, MacAddress>::new();
     arp.insert(ALICE_IPV4, ALICE_MAC);
     arp.insert(BOB_IPV4, BOB_MAC);
@@ -79,12 +123,27 @@ pub fn new_alice2(now: Instant) -> SharedEngine {
         Some(false),
     );
     let udp_config = UdpConfig::default();
-    let tcp_config = TcpConfig::default();
-    let network = SharedTestRuntime::new(now, arp_config, udp_config, tcp_config, ALICE_MAC, ALICE_IPV4);
-    SharedEngine::new(network, now).unwrap()
+    SharedTestRuntime::new(now, arp_config, udp_config, tcp_config, ALICE_MAC, ALICE_IPV4)
 }
 
 pub fn new_bob2(now: Instant) -> SharedEngine {
+    SharedEngine::new(new_bob2_runtime(now, TcpConfig::default()), now).unwrap()
+}
+
+pub fn new_bob2_with_tcp_config(now: Instant, tcp_config: TcpConfig) -> SharedEngine {
+    SharedEngine::new(new_bob2_runtime(now, tcp_config), now).unwrap()
+}
+
+pub fn new_bob2_with_ipsec(now: Instant, security_associations: Vec<SecurityAssociation>) -> SharedEngine {
+    SharedEngine::new_with_ipsec(
+        new_bob2_runtime(now, TcpConfig::default()),
+        now,
+        Some(security_associations),
+    )
+    .unwrap()
+}
+
+fn new_bob2_runtime(now: Instant, tcp_config: TcpConfig) -> SharedTestRuntime {
     let mut arp: HashMap<Ipv4Addr, MacAddress> = HashMap::<Ipv4Addr, MacAddress>::new();
     arp.insert(BOB_IPV4, BOB_MAC);
     arp.insert(ALICE_IPV4, ALICE_MAC);
@@ -96,9 +155,7 @@ pub fn new_bob2(now: Instant) -> SharedEngine {
         Some(false),
     );
     let udp_config = UdpConfig::default();
-    let tcp_config = TcpConfig::default();
-    let network = SharedTestRuntime::new(now, arp_config, udp_config, tcp_config, BOB_MAC, BOB_IPV4);
-    SharedEngine::new(network, now).unwrap()
+    SharedTestRuntime::new(now, arp_config, udp_config, tcp_config, BOB_MAC, BOB_IPV4)
 }
 
 pub fn new_carrie(now: Instant) -> SharedEngine {
@@ -115,3 +172,48 @@ pub fn new_carrie(now: Instant) -> SharedEngine {
     let network = SharedTestRuntime::new(now, arp_config, udp_config, tcp_config, CARRIE_MAC, CARRIE_IPV4);
     SharedEngine::new(network, now).unwrap()
 }
+
+/// Builds a key of the TCP authentication option that is shared with the single peer [addr], with the same send and
+/// receive identifier [id].
+pub fn ao_key(addr: Ipv4Addr, id: u8, algorithm: TcpAoAlgorithm, key: &[u8]) -> Result<TcpAoKey> {
+    Ok(TcpAoKey::new(addr, 32, id, id, algorithm, key)?)
+}
+
+/// Builds the socket option that adds a [kind] key that is shared with the single peer [addr].
+pub fn host_key(kind: TcpKeyKind, addr: Ipv4Addr, key: &[u8]) -> Result<SocketOption> {
+    match kind {
+        TcpKeyKind::Md5 => Ok(SocketOption::TCP_MD5SIG(TcpMd5Key::new(addr, 32, key)?)),
+        TcpKeyKind::Ao(id, algorithm) => Ok(SocketOption::TCP_AO_ADD_KEY(ao_key(addr, id, algorithm, key)?)),
+    }
+}
+
+/// Makes Bob listen on [port] with a [kind] key that Bob shares with Alice, and makes Alice connect with a [kind] key
+/// [alice_key], if any. Returns the engines, the listening socket of Bob and the pending accept and connect.
+pub fn connect_signed(
+    kind: TcpKeyKind,
+    port: u16,
+    alice_key: Option<&[u8]>,
+) -> Result<(SharedEngine, SharedEngine, QDesc, QToken, QToken)> {
+    let now: Instant = Instant::now();
+    let mut alice: SharedEngine = new_alice2(now);
+    let mut bob: SharedEngine = new_bob2(now);
+    let bob_key: SocketOption = host_key(kind, ALICE_IPV4, SHARED_KEY)?;
+    let alice_key: Vec<SocketOption> = match alice_key {
+        Some(key) => vec![host_key(kind, BOB_IPV4, key)?],
+        None => Vec::new(),
+    };
+    let (server_fd, accept_qt, connect_qt) = alice.start_connect(&mut bob, port, &[bob_key], &alice_key)?;
+    Ok((alice, bob, server_fd, accept_qt, connect_qt))
+}
+
+/// Connects Alice to Bob on [port] with a [kind] key that they share, and returns the engines along with the socket of
+/// each end.
+pub fn establish_signed(kind: TcpKeyKind, port: u16) -> Result<(SharedEngine, SharedEngine, QDesc, QDesc)> {
+    let now: Instant = Instant::now();
+    let mut alice: SharedEngine = new_alice2(now);
+    let mut bob: SharedEngine = new_bob2(now);
+    let bob_key: SocketOption = host_key(kind, ALICE_IPV4, SHARED_KEY)?;
+    let alice_key: SocketOption = host_key(kind, BOB_IPV4, SHARED_KEY)?;
+    let (_, accepted_fd, client_fd) = alice.establish(&mut bob, port, &[bob_key], &[alice_key])?;
+    Ok((alice, bob, client_fd, accepted_fd))
+}
@@ -17,10 +17,10 @@ use crate::runtime::{
             TcpConfig,
             UdpConfig,
         },
-        consts::RECEIVE_BATCH_SIZE,
+        consts::MAX_BATCH_SIZE,
         types::MacAddress,
         NetworkRuntime,
-        PacketBuf,
+        PacketBatch,
     },
     SharedDemiRuntime,
     SharedObject,
@@ -116,24 +116,26 @@ impl SharedTestRuntime {
 //==============================================================================
 
 impl NetworkRuntime for SharedTestRuntime {
-    fn transmit(&mut self, pkt: Box<dyn PacketBuf>) {
-        let header_size: usize = pkt.header_size();
-        let body_size: usize = pkt.body_size();
-        debug!("transmit frame: {:?} body: {:?}", self.outgoing.len(), body_size);
-
-        // The packet header and body must fit into whatever physical media we're transmitting over.
-        // For this test harness, we 2^16 bytes (u16::MAX) as our limit.
-        assert!(header_size + body_size < u16::MAX as usize);
-
-        let mut buf: DemiBuffer = DemiBuffer::new((header_size + body_size) as u16);
-        pkt.write_header(&mut buf[..header_size]);
-        if let Some(body) = pkt.take_body() {
-            buf[header_size..].copy_from_slice(&body[..]);
+    fn transmit(&mut self, pkts: PacketBatch) {
+        for pkt in pkts {
+            let header_size: usize = pkt.header_size();
+            let body_size: usize = pkt.body_size();
+            debug!("transmit frame: {:?} body: {:?}", self.outgoing.len(), body_size);
+
+            // The packet header and body must fit into whatever physical media we're transmitting over.
+            // For this test harness, we 2^16 bytes (u16::MAX) as our limit.
+            assert!(header_size + body_size < u16::MAX as usize);
+
+            let mut buf: DemiBuffer = DemiBuffer::new((header_size + body_size) as u16);
+            pkt.write_header(&mut buf[..header_size]);
+            if let Some(body) = pkt.take_body() {
+                buf[header_size..].copy_from_slice(&body[..]);
+            }
+            self.outgoing.push_back(buf);
         }
-        self.outgoing.push_back(buf);
     }
 
-    fn receive(&mut self) -> ArrayVec<DemiBuffer, RECEIVE_BATCH_SIZE> {
+    fn receive(&mut self) -> ArrayVec<DemiBuffer, MAX_BATCH_SIZE> {
         let mut out = ArrayVec::new();
         if let Some(buf) = self.incoming.pop_front() {
             out.push(buf);
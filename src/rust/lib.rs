@@ -43,9 +43,16 @@ mod catpowder;
 #[cfg(all(feature = "catnap-libos"))]
 mod catnap;
 
+#[cfg(feature = "catcollar-libos")]
+mod catcollar;
+
 #[cfg(feature = "catmem-libos")]
 mod catmem;
 
+/// Shared memory ring of Catmem, which is not part of the API but is exported for the benchmarks.
+#[cfg(all(feature = "catmem-libos", feature = "bench"))]
+pub use crate::catmem::ring::Ring as CatmemRing;
+
 #[cfg(feature = "catloop-libos")]
 mod catloop;
 
@@ -68,6 +75,7 @@ pub use crate::runtime::{
     QType,
 };
 
+pub mod asyncio;
 pub mod demikernel;
 
 use mimalloc::MiMalloc;
@@ -236,6 +244,94 @@ macro_rules! timer {
     };
 }
 
+/// Emits the tracing event [event] of [category] with the given fields, which are formatted with [Debug]. Events are
+/// prefixed with the spans that are open, and they are only compiled in with the "tracing" feature. See
+/// [runtime::tracing] for how they are filtered at runtime.
+///
+/// # Example
+///
+/// ```ignore
+/// trace_event!("tcp", "retransmit", seq = seq_no, len = data.len());
+/// ```
+#[cfg(feature = "tracing")]
+#[macro_export]
+macro_rules! trace_event {
+    ($category:literal, $event:literal $(, $key:ident = $value:expr)* $(,)?) => {
+        if ::log::log_enabled!(target: concat!("demikernel::trace::", $category), ::log::Level::Debug) {
+            #[allow(unused_mut)]
+            let mut fields: String = String::new();
+            $(
+                let _ = ::std::fmt::Write::write_fmt(
+                    &mut fields,
+                    format_args!(" {}={:?}", stringify!($key), $value),
+                );
+            )*
+            ::log::debug!(
+                target: concat!("demikernel::trace::", $category),
+                "[{}] {}{}",
+                $crate::runtime::tracing::current_spans(),
+                $event,
+                fields
+            );
+        }
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+#[macro_export]
+macro_rules! trace_event {
+    ($category:literal, $event:literal $(, $key:ident = $value:expr)* $(,)?) => {
+        if false {
+            let _ = ($(&$value,)*);
+        }
+    };
+}
+
+/// Opens the tracing span [name] with the given fields until the end of the current scope. This is only compiled in
+/// with the "tracing" feature.
+#[cfg(feature = "tracing")]
+#[macro_export]
+macro_rules! trace_scope {
+    ($name:literal $(, $key:ident = $value:expr)* $(,)?) => {
+        let span: $crate::runtime::tracing::Span = $crate::runtime::tracing::Span::new($name);
+        $( span.record(stringify!($key), &$value); )*
+        let _entered: $crate::runtime::tracing::Entered = span.enter();
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+#[macro_export]
+macro_rules! trace_scope {
+    ($name:literal $(, $key:ident = $value:expr)* $(,)?) => {
+        if false {
+            let _ = ($(&$value,)*);
+        }
+    };
+}
+
+/// Runs the pinned future [future] in the tracing span [name] with the given fields, so that the span is open whenever
+/// the future is polled. This evaluates to [future] unless the "tracing" feature is enabled.
+#[cfg(feature = "tracing")]
+#[macro_export]
+macro_rules! trace_instrument {
+    ($future:expr, $name:literal $(, $key:ident = $value:expr)* $(,)?) => {{
+        let span: $crate::runtime::tracing::Span = $crate::runtime::tracing::Span::new($name);
+        $( span.record(stringify!($key), &$value); )*
+        Box::pin($crate::runtime::tracing::Instrumented::new($future, span))
+    }};
+}
+
+#[cfg(not(feature = "tracing"))]
+#[macro_export]
+macro_rules! trace_instrument {
+    ($future:expr, $name:literal $(, $key:ident = $value:expr)* $(,)?) => {{
+        if false {
+            let _ = ($(&$value,)*);
+        }
+        $future
+    }};
+}
+
 #[cfg(feature = "profiler")]
 #[macro_export]
 macro_rules! async_timer {
@@ -17,6 +17,15 @@ pub const AF_INET6: WinSock::ADDRESS_FAMILY = WinSock::AF_INET6;
 #[cfg(target_os = "windows")]
 pub const SOMAXCONN: i32 = WinSock::SOMAXCONN as i32;
 
+#[cfg(target_os = "windows")]
+pub const IPPROTO_IP: i32 = WinSock::IPPROTO_IP.0;
+
+#[cfg(target_os = "windows")]
+pub const IP_TOS: i32 = WinSock::IP_TOS;
+
+#[cfg(target_os = "windows")]
+pub const IP_TTL: i32 = WinSock::IP_TTL;
+
 //==============================================================================
 // Linux constants
 //==============================================================================
@@ -29,3 +38,93 @@ pub const AF_INET6: libc::sa_family_t = libc::AF_INET6 as u16;
 
 #[cfg(target_os = "linux")]
 pub const SOMAXCONN: i32 = libc::SOMAXCONN;
+
+#[cfg(target_os = "linux")]
+pub const SOL_SOCKET: i32 = libc::SOL_SOCKET;
+
+#[cfg(target_os = "linux")]
+pub const IPPROTO_IP: i32 = libc::IPPROTO_IP;
+
+#[cfg(target_os = "linux")]
+pub const IP_TOS: i32 = libc::IP_TOS;
+
+#[cfg(target_os = "linux")]
+pub const IP_TTL: i32 = libc::IP_TTL;
+
+#[cfg(target_os = "linux")]
+pub const IP_RECVERR: i32 = libc::IP_RECVERR;
+
+#[cfg(target_os = "linux")]
+pub const SO_PRIORITY: i32 = libc::SO_PRIORITY;
+
+#[cfg(target_os = "linux")]
+pub const SO_MAX_PACING_RATE: i32 = libc::SO_MAX_PACING_RATE;
+
+#[cfg(target_os = "linux")]
+pub const SO_NO_CHECK: i32 = libc::SO_NO_CHECK;
+
+#[cfg(target_os = "linux")]
+pub const SO_TIMESTAMPING: i32 = libc::SO_TIMESTAMPING;
+
+#[cfg(target_os = "linux")]
+pub const SO_BINDTOIFINDEX: i32 = libc::SO_BINDTOIFINDEX;
+
+#[cfg(target_os = "linux")]
+pub const SO_ATTACH_FILTER: i32 = libc::SO_ATTACH_FILTER;
+
+#[cfg(target_os = "linux")]
+pub const SO_DETACH_FILTER: i32 = libc::SO_DETACH_FILTER;
+
+#[cfg(target_os = "linux")]
+pub const IPPROTO_TCP: i32 = libc::IPPROTO_TCP;
+
+#[cfg(target_os = "linux")]
+pub const TCP_USER_TIMEOUT: i32 = libc::TCP_USER_TIMEOUT;
+
+#[cfg(target_os = "linux")]
+pub const TCP_MD5SIG: i32 = libc::TCP_MD5SIG;
+
+#[cfg(target_os = "linux")]
+pub const TCP_MD5SIG_EXT: i32 = libc::TCP_MD5SIG_EXT;
+
+/// Flag of the TCP_MD5SIG_EXT socket option that makes a key apply to a whole prefix of peers.
+#[cfg(target_os = "linux")]
+pub const TCP_MD5SIG_FLAG_PREFIX: u8 = 1;
+
+/// Flag of the TCP_MD5SIG_EXT socket option that makes a key apply to a single interface.
+#[cfg(target_os = "linux")]
+pub const TCP_MD5SIG_FLAG_IFINDEX: u8 = 2;
+
+#[cfg(target_os = "linux")]
+pub const SOL_UDP: i32 = libc::SOL_UDP;
+
+#[cfg(target_os = "linux")]
+pub const UDP_SEGMENT: i32 = libc::UDP_SEGMENT;
+
+/// Upper bound for the retransmission timeout of a TCP socket, in milliseconds. Newer than the libc crate.
+#[cfg(target_os = "linux")]
+pub const TCP_RTO_MAX_MS: i32 = 44;
+
+/// Lower bound for the retransmission timeout of a TCP socket, in microseconds. Newer than the libc crate.
+#[cfg(target_os = "linux")]
+pub const TCP_RTO_MIN_US: i32 = 45;
+
+/// Initial congestion window of a TCP socket, in segments. Linux has no such option, so this is numbered well beyond
+/// the options of Linux.
+#[cfg(target_os = "linux")]
+pub const TCP_INIT_CWND: i32 = 0x1000;
+
+/// Retransmission timeout of a TCP socket before any round-trip time is measured, in microseconds. Linux has no such
+/// option, so this is numbered well beyond the options of Linux.
+#[cfg(target_os = "linux")]
+pub const TCP_RTO_INIT_US: i32 = 0x1001;
+
+/// Whether pushes to a socket complete only once their data is delivered. Linux has no such option, so this is
+/// numbered well beyond the options of Linux.
+#[cfg(target_os = "linux")]
+pub const SO_TXCOMPLETE: i32 = 0x1002;
+
+/// Whether pops on a memory queue sleep on a doorbell. Linux has no such option, so this is numbered well beyond the
+/// options of Linux.
+#[cfg(target_os = "linux")]
+pub const SO_DOORBELL: i32 = 0x1003;
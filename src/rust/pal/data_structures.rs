@@ -47,3 +47,16 @@ pub type SockAddrStorage = libc::sockaddr_storage;
 
 #[cfg(target_os = "linux")]
 pub type AddressFamily = libc::sa_family_t;
+
+/// Argument of the TCP_MD5SIG and TCP_MD5SIG_EXT socket options, which is missing from the libc crate.
+#[cfg(target_os = "linux")]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct TcpMd5Sig {
+    pub tcpm_addr: libc::sockaddr_storage,
+    pub tcpm_flags: u8,
+    pub tcpm_prefixlen: u8,
+    pub tcpm_keylen: u16,
+    pub tcpm_ifindex: libc::c_int,
+    pub tcpm_key: [u8; libc::TCP_MD5SIG_MAXKEYLEN],
+}
@@ -2,7 +2,10 @@
 // Licensed under the MIT license.
 
 #[cfg(target_os = "windows")]
-use std::net::SocketAddrV4;
+use std::net::{
+    Ipv4Addr,
+    SocketAddrV4,
+};
 
 #[cfg(target_os = "windows")]
 use windows::Win32::Networking::WinSock::SOCKADDR;
@@ -22,3 +25,17 @@ pub fn socketaddrv4_to_sockaddr(addr: &SocketAddrV4) -> SOCKADDR {
     let sockaddr: SOCKADDR = unsafe { std::mem::transmute(sockaddr_in) };
     sockaddr
 }
+
+/// Converts a [SOCKADDR] to a [SocketAddrV4]. Returns None if it is not an IPv4 address.
+#[cfg(target_os = "windows")]
+pub fn sockaddr_to_socketaddrv4(saddr: &SOCKADDR) -> Option<SocketAddrV4> {
+    if saddr.sa_family != AF_INET {
+        return None;
+    }
+    let sockaddr_in: SOCKADDR_IN = unsafe { std::mem::transmute(*saddr) };
+    let addr: u32 = unsafe { sockaddr_in.sin_addr.S_un.S_addr };
+    Some(SocketAddrV4::new(
+        Ipv4Addr::from(addr),
+        u16::from_be(sockaddr_in.sin_port),
+    ))
+}
@@ -0,0 +1,224 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::fail::Fail;
+use ::std::{
+    mem,
+    os::fd::RawFd,
+    ptr,
+};
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Sends [payload] as a single message on the Unix domain socket [channel], along with a duplicate of the file
+/// descriptor [fd], if any. The message either goes out whole or not at all.
+pub fn send_message(channel: RawFd, payload: &[u8], fd: Option<RawFd>) -> Result<(), Fail> {
+    let mut iov: libc::iovec = libc::iovec {
+        iov_base: payload.as_ptr() as *mut libc::c_void,
+        iov_len: payload.len(),
+    };
+    let mut control: [u8; 64] = [0; 64];
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    if let Some(fd) = fd {
+        let space: usize = unsafe { libc::CMSG_SPACE(mem::size_of::<RawFd>() as u32) } as usize;
+        debug_assert!(space <= control.len());
+        msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+        msg.msg_controllen = space as _;
+        // Safety: the control buffer is large enough to hold a single file descriptor.
+        unsafe {
+            let cmsg: *mut libc::cmsghdr = libc::CMSG_FIRSTHDR(&msg);
+            (*cmsg).cmsg_level = libc::SOL_SOCKET;
+            (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+            (*cmsg).cmsg_len = libc::CMSG_LEN(mem::size_of::<RawFd>() as u32) as _;
+            ptr::write_unaligned(libc::CMSG_DATA(cmsg) as *mut RawFd, fd);
+        }
+    }
+
+    match unsafe { libc::sendmsg(channel, &msg, libc::MSG_NOSIGNAL) } {
+        nbytes if nbytes as usize == payload.len() => Ok(()),
+        nbytes if nbytes >= 0 => {
+            let cause: String = format!("message was truncated (channel={:?}, nbytes={:?})", channel, nbytes);
+            error!("send_message(): {}", cause);
+            Err(Fail::new(libc::EMSGSIZE, &cause))
+        },
+        _ => {
+            let errno: libc::c_int = unsafe { *libc::__errno_location() };
+            let cause: String = format!("failed to send message (channel={:?}, errno={:?})", channel, errno);
+            error!("send_message(): {}", cause);
+            Err(Fail::new(errno, &cause))
+        },
+    }
+}
+
+/// Receives a single message from the Unix domain socket [channel] into [buf], returning its length along with the
+/// file descriptor that came with it, if any. Received file descriptors are closed on exec.
+pub fn recv_message(channel: RawFd, buf: &mut [u8]) -> Result<(usize, Option<RawFd>), Fail> {
+    let mut iov: libc::iovec = libc::iovec {
+        iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+        iov_len: buf.len(),
+    };
+    let mut control: [u8; 64] = [0; 64];
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+    msg.msg_controllen = control.len() as _;
+
+    let nbytes: isize = unsafe { libc::recvmsg(channel, &mut msg, libc::MSG_CMSG_CLOEXEC) };
+    if nbytes < 0 {
+        let errno: libc::c_int = unsafe { *libc::__errno_location() };
+        let cause: String = format!("failed to receive message (channel={:?}, errno={:?})", channel, errno);
+        error!("recv_message(): {}", cause);
+        return Err(Fail::new(errno, &cause));
+    }
+
+    // Collect the file descriptors that came along, so that none leaks if the message is rejected.
+    let mut fds: Vec<RawFd> = Vec::new();
+    // Safety: the kernel filled in the control buffer, which outlives this loop.
+    unsafe {
+        let mut cmsg: *mut libc::cmsghdr = libc::CMSG_FIRSTHDR(&msg);
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+                let data: *const RawFd = libc::CMSG_DATA(cmsg) as *const RawFd;
+                let len: usize = (*cmsg).cmsg_len as usize - libc::CMSG_LEN(0) as usize;
+                for i in 0..(len / mem::size_of::<RawFd>()) {
+                    fds.push(ptr::read_unaligned(data.add(i)));
+                }
+            }
+            cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+        }
+    }
+
+    let cause: Option<String> = if nbytes == 0 {
+        Some(format!("channel was closed (channel={:?})", channel))
+    } else if msg.msg_flags & (libc::MSG_TRUNC | libc::MSG_CTRUNC) != 0 {
+        Some(format!("message was truncated (channel={:?})", channel))
+    } else if fds.len() > 1 {
+        Some(format!(
+            "message carries too many file descriptors (nfds={:?})",
+            fds.len()
+        ))
+    } else {
+        None
+    };
+    match cause {
+        Some(cause) => {
+            for fd in fds {
+                unsafe { libc::close(fd) };
+            }
+            error!("recv_message(): {}", cause);
+            Err(Fail::new(libc::EBADMSG, &cause))
+        },
+        None => Ok((nbytes as usize, fds.pop())),
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::pal::linux::channel::{
+        recv_message,
+        send_message,
+    };
+    use ::anyhow::Result;
+    use ::std::{
+        fs::File,
+        io::{
+            Read,
+            Seek,
+            Write,
+        },
+        os::{
+            fd::{
+                AsRawFd,
+                FromRawFd,
+                RawFd,
+            },
+            unix::net::UnixDatagram,
+        },
+    };
+
+    /// Tests that a message and the file descriptor that comes with it go through a channel.
+    #[test]
+    fn channel_passes_file_descriptor() -> Result<()> {
+        let (alice, bob): (UnixDatagram, UnixDatagram) = UnixDatagram::pair()?;
+        let mut file: File = tempfile()?;
+        file.write_all(b"hello")?;
+
+        if let Err(e) = send_message(alice.as_raw_fd(), b"message", Some(file.as_raw_fd())) {
+            anyhow::bail!("send_message() should succeed: {:?}", e);
+        }
+        let mut buf: [u8; 16] = [0; 16];
+        let (len, fd): (usize, Option<RawFd>) = match recv_message(bob.as_raw_fd(), &mut buf) {
+            Ok(result) => result,
+            Err(e) => anyhow::bail!("recv_message() should succeed: {:?}", e),
+        };
+        crate::ensure_eq!(&buf[..len], b"message");
+
+        // The received descriptor refers to the same file.
+        let fd: RawFd = match fd {
+            Some(fd) => fd,
+            None => anyhow::bail!("recv_message() should return a file descriptor"),
+        };
+        crate::ensure_neq!(fd, file.as_raw_fd());
+        let mut received: File = unsafe { File::from_raw_fd(fd) };
+        received.rewind()?;
+        let mut contents: String = String::new();
+        received.read_to_string(&mut contents)?;
+        crate::ensure_eq!(contents, "hello");
+
+        Ok(())
+    }
+
+    /// Tests that a message without a file descriptor goes through a channel.
+    #[test]
+    fn channel_passes_message() -> Result<()> {
+        let (alice, bob): (UnixDatagram, UnixDatagram) = UnixDatagram::pair()?;
+
+        if let Err(e) = send_message(alice.as_raw_fd(), b"message", None) {
+            anyhow::bail!("send_message() should succeed: {:?}", e);
+        }
+        let mut buf: [u8; 16] = [0; 16];
+        match recv_message(bob.as_raw_fd(), &mut buf) {
+            Ok((len, None)) => crate::ensure_eq!(&buf[..len], b"message"),
+            Ok((_, Some(fd))) => anyhow::bail!("recv_message() should not return a file descriptor (fd={:?})", fd),
+            Err(e) => anyhow::bail!("recv_message() should succeed: {:?}", e),
+        }
+
+        Ok(())
+    }
+
+    /// Tests that a message that does not fit in the receive buffer is rejected.
+    #[test]
+    fn channel_rejects_truncated_message() -> Result<()> {
+        let (alice, bob): (UnixDatagram, UnixDatagram) = UnixDatagram::pair()?;
+
+        if let Err(e) = send_message(alice.as_raw_fd(), b"a long message", None) {
+            anyhow::bail!("send_message() should succeed: {:?}", e);
+        }
+        let mut buf: [u8; 4] = [0; 4];
+        match recv_message(bob.as_raw_fd(), &mut buf) {
+            Err(e) if e.errno == libc::EBADMSG => Ok(()),
+            result => anyhow::bail!("recv_message() should fail with EBADMSG: {:?}", result),
+        }
+    }
+
+    /// Creates an anonymous temporary file.
+    fn tempfile() -> Result<File> {
+        match unsafe { libc::memfd_create(b"channel-test\0".as_ptr() as *const libc::c_char, 0) } {
+            fd if fd >= 0 => Ok(unsafe { File::from_raw_fd(fd) }),
+            _ => anyhow::bail!("memfd_create() should succeed"),
+        }
+    }
+}
@@ -0,0 +1,590 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Thin wrapper around the io_uring system call interface. Structures and constants in this module mirror the ones in
+//! `linux/io_uring.h`, so that we do not depend on liburing.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::fail::Fail;
+use ::std::{
+    mem,
+    os::fd::RawFd,
+    ptr,
+    sync::atomic::{
+        AtomicU16,
+        AtomicU32,
+        Ordering,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+pub const IORING_OP_SENDMSG: u8 = 9;
+pub const IORING_OP_RECVMSG: u8 = 10;
+pub const IORING_OP_ACCEPT: u8 = 13;
+pub const IORING_OP_ASYNC_CANCEL: u8 = 14;
+pub const IORING_OP_CONNECT: u8 = 16;
+pub const IORING_OP_READ: u8 = 22;
+pub const IORING_OP_WRITE: u8 = 23;
+pub const IORING_OP_SEND: u8 = 26;
+pub const IORING_OP_RECV: u8 = 27;
+
+/// Select a buffer from the provided buffer group in [IoUringSqe::buf_group].
+pub const IOSQE_BUFFER_SELECT: u8 = 1 << 5;
+
+/// Keep accepting connections with a single submission.
+pub const IORING_ACCEPT_MULTISHOT: u16 = 1 << 0;
+
+/// Cancel all requests that match the given key.
+pub const IORING_ASYNC_CANCEL_ALL: u32 = 1 << 0;
+/// Match requests on their file descriptor, rather than on their user data.
+pub const IORING_ASYNC_CANCEL_FD: u32 = 1 << 1;
+
+/// The upper 16 bits of the completion flags hold the identifier of a provided buffer.
+pub const IORING_CQE_F_BUFFER: u32 = 1 << 0;
+/// The submission will generate more completions.
+pub const IORING_CQE_F_MORE: u32 = 1 << 1;
+const IORING_CQE_BUFFER_SHIFT: u32 = 16;
+
+const IORING_OFF_SQ_RING: libc::off_t = 0;
+const IORING_OFF_CQ_RING: libc::off_t = 0x8000000;
+const IORING_OFF_SQES: libc::off_t = 0x10000000;
+const IORING_FEAT_SINGLE_MMAP: u32 = 1 << 0;
+const IORING_REGISTER_PBUF_RING: libc::c_uint = 22;
+const IORING_UNREGISTER_PBUF_RING: libc::c_uint = 23;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Submission queue entry. Unions in the kernel definition are flattened to the member that we use.
+#[repr(C)]
+#[derive(Default)]
+pub struct IoUringSqe {
+    pub opcode: u8,
+    pub flags: u8,
+    pub ioprio: u16,
+    pub fd: i32,
+    pub off: u64,
+    pub addr: u64,
+    pub len: u32,
+    pub op_flags: u32,
+    pub user_data: u64,
+    pub buf_group: u16,
+    pub personality: u16,
+    pub file_index: u32,
+    pub addr3: u64,
+    pub pad: u64,
+}
+
+/// Completion queue entry.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct IoUringCqe {
+    pub user_data: u64,
+    pub res: i32,
+    pub flags: u32,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct IoSqringOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    flags: u32,
+    dropped: u32,
+    array: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct IoCqringOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    overflow: u32,
+    cqes: u32,
+    flags: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct IoUringParams {
+    sq_entries: u32,
+    cq_entries: u32,
+    flags: u32,
+    sq_thread_cpu: u32,
+    sq_thread_idle: u32,
+    features: u32,
+    wq_fd: u32,
+    resv: [u32; 3],
+    sq_off: IoSqringOffsets,
+    cq_off: IoCqringOffsets,
+}
+
+#[repr(C)]
+struct IoUringBuf {
+    addr: u64,
+    len: u32,
+    bid: u16,
+    resv: u16,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct IoUringBufReg {
+    ring_addr: u64,
+    ring_entries: u32,
+    bgid: u16,
+    pad: u16,
+    resv: [u64; 3],
+}
+
+/// A memory mapping shared with the kernel.
+struct Mmap {
+    addr: *mut libc::c_void,
+    len: usize,
+}
+
+/// An io_uring instance.
+pub struct IoUring {
+    fd: RawFd,
+    /// Submission queue ring. This is kept around so that it is unmapped along with the io_uring.
+    _sq_ring: Mmap,
+    /// Completion queue ring, if it is not mapped along with the submission queue ring.
+    _cq_ring: Option<Mmap>,
+    sqes: Mmap,
+    sq_head: *const AtomicU32,
+    sq_tail: *const AtomicU32,
+    sq_mask: u32,
+    sq_entries: u32,
+    sq_array: *mut u32,
+    cq_head: *const AtomicU32,
+    cq_tail: *const AtomicU32,
+    cq_mask: u32,
+    cqes: *const IoUringCqe,
+    /// Tail of the submission queue, including entries that were not yet handed over to the kernel.
+    local_sq_tail: u32,
+    /// Number of entries that were handed over to the kernel, but not yet submitted.
+    pending: u32,
+}
+
+/// A ring of provided buffers. The kernel picks a buffer from this ring when a receive operation completes, instead of
+/// having each operation pin a buffer while it waits for data.
+pub struct BufferRing {
+    /// Ring shared with the kernel.
+    ring: Mmap,
+    /// Backing memory for all buffers.
+    buffers: Vec<u8>,
+    buffer_size: usize,
+    entries: u16,
+    tail: u16,
+    bgid: u16,
+    /// The io_uring instance with which this ring is registered.
+    io_uring_fd: RawFd,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl IoUring {
+    /// Creates an io_uring instance with (at least) [entries] submission queue entries.
+    pub fn new(entries: u32) -> Result<Self, Fail> {
+        let mut params: IoUringParams = IoUringParams::default();
+        let fd: RawFd =
+            unsafe { libc::syscall(libc::SYS_io_uring_setup, entries, &mut params as *mut IoUringParams) } as RawFd;
+        if fd < 0 {
+            return Err(last_os_fail("io_uring_setup"));
+        }
+
+        // Map submission and completion queues.
+        let sq_len: usize = params.sq_off.array as usize + params.sq_entries as usize * mem::size_of::<u32>();
+        let cq_len: usize = params.cq_off.cqes as usize + params.cq_entries as usize * mem::size_of::<IoUringCqe>();
+        let single_mmap: bool = params.features & IORING_FEAT_SINGLE_MMAP != 0;
+        let sq_ring: Mmap = match Mmap::new(
+            fd,
+            if single_mmap { sq_len.max(cq_len) } else { sq_len },
+            IORING_OFF_SQ_RING,
+        ) {
+            Ok(mmap) => mmap,
+            Err(e) => {
+                unsafe { libc::close(fd) };
+                return Err(e);
+            },
+        };
+        let cq_ring: Option<Mmap> = if single_mmap {
+            None
+        } else {
+            match Mmap::new(fd, cq_len, IORING_OFF_CQ_RING) {
+                Ok(mmap) => Some(mmap),
+                Err(e) => {
+                    unsafe { libc::close(fd) };
+                    return Err(e);
+                },
+            }
+        };
+        let sqes: Mmap = match Mmap::new(
+            fd,
+            params.sq_entries as usize * mem::size_of::<IoUringSqe>(),
+            IORING_OFF_SQES,
+        ) {
+            Ok(mmap) => mmap,
+            Err(e) => {
+                unsafe { libc::close(fd) };
+                return Err(e);
+            },
+        };
+
+        let sq_base: *mut u8 = sq_ring.addr as *mut u8;
+        let cq_base: *mut u8 = match cq_ring {
+            Some(ref mmap) => mmap.addr as *mut u8,
+            None => sq_base,
+        };
+        // Safety: offsets were filled in by the kernel and point within the mapped regions.
+        unsafe {
+            let sq_tail: *const AtomicU32 = sq_base.add(params.sq_off.tail as usize) as *const AtomicU32;
+            let local_sq_tail: u32 = (*sq_tail).load(Ordering::Acquire);
+            Ok(Self {
+                fd,
+                sq_head: sq_base.add(params.sq_off.head as usize) as *const AtomicU32,
+                sq_tail,
+                sq_mask: *(sq_base.add(params.sq_off.ring_mask as usize) as *const u32),
+                sq_entries: params.sq_entries,
+                sq_array: sq_base.add(params.sq_off.array as usize) as *mut u32,
+                cq_head: cq_base.add(params.cq_off.head as usize) as *const AtomicU32,
+                cq_tail: cq_base.add(params.cq_off.tail as usize) as *const AtomicU32,
+                cq_mask: *(cq_base.add(params.cq_off.ring_mask as usize) as *const u32),
+                cqes: cq_base.add(params.cq_off.cqes as usize) as *const IoUringCqe,
+                _sq_ring: sq_ring,
+                _cq_ring: cq_ring,
+                sqes,
+                local_sq_tail,
+                pending: 0,
+            })
+        }
+    }
+
+    /// Returns the underlying file descriptor.
+    #[allow(unused)]
+    pub fn fd(&self) -> RawFd {
+        self.fd
+    }
+
+    /// Queues [sqe] for submission. If the submission queue is full, queued entries are submitted first.
+    pub fn push(&mut self, sqe: IoUringSqe) -> Result<(), Fail> {
+        let head: u32 = unsafe { (*self.sq_head).load(Ordering::Acquire) };
+        if self.local_sq_tail.wrapping_sub(head) == self.sq_entries {
+            self.submit()?;
+            let head: u32 = unsafe { (*self.sq_head).load(Ordering::Acquire) };
+            if self.local_sq_tail.wrapping_sub(head) == self.sq_entries {
+                return Err(Fail::new(libc::EBUSY, "submission queue is full"));
+            }
+        }
+        let index: u32 = self.local_sq_tail & self.sq_mask;
+        // Safety: index is within the bounds of the submission queue and the kernel does not access this entry until
+        // we bump the tail.
+        unsafe {
+            ptr::write((self.sqes.addr as *mut IoUringSqe).add(index as usize), sqe);
+            ptr::write(self.sq_array.add(index as usize), index);
+        }
+        self.local_sq_tail = self.local_sq_tail.wrapping_add(1);
+        unsafe { (*self.sq_tail).store(self.local_sq_tail, Ordering::Release) };
+        self.pending += 1;
+        Ok(())
+    }
+
+    /// Submits all queued entries to the kernel, without waiting for any completion.
+    pub fn submit(&mut self) -> Result<(), Fail> {
+        while self.pending > 0 {
+            let ret: libc::c_long = unsafe {
+                libc::syscall(
+                    libc::SYS_io_uring_enter,
+                    self.fd,
+                    self.pending,
+                    0,
+                    0,
+                    ptr::null::<libc::sigset_t>(),
+                    0,
+                )
+            };
+            if ret < 0 {
+                let errno: libc::c_int = unsafe { *libc::__errno_location() };
+                if errno == libc::EINTR {
+                    continue;
+                }
+                // The kernel is out of resources, thus try again later.
+                if errno == libc::EAGAIN || errno == libc::EBUSY {
+                    return Ok(());
+                }
+                return Err(last_os_fail("io_uring_enter"));
+            }
+            self.pending -= ret as u32;
+        }
+        Ok(())
+    }
+
+    /// Takes the next completion queue entry, if any.
+    pub fn pop(&mut self) -> Option<IoUringCqe> {
+        // Safety: head and tail point into the mapped completion queue.
+        unsafe {
+            let head: u32 = (*self.cq_head).load(Ordering::Relaxed);
+            let tail: u32 = (*self.cq_tail).load(Ordering::Acquire);
+            if head == tail {
+                return None;
+            }
+            let cqe: IoUringCqe = ptr::read(self.cqes.add((head & self.cq_mask) as usize));
+            (*self.cq_head).store(head.wrapping_add(1), Ordering::Release);
+            Some(cqe)
+        }
+    }
+}
+
+impl BufferRing {
+    /// Registers a ring of [entries] buffers of [buffer_size] bytes each as buffer group [bgid] of [io_uring].
+    pub fn new(io_uring: &IoUring, bgid: u16, entries: u16, buffer_size: usize) -> Result<Self, Fail> {
+        if !entries.is_power_of_two() {
+            return Err(Fail::new(
+                libc::EINVAL,
+                "number of provided buffers should be a power of two",
+            ));
+        }
+        let ring: Mmap = Mmap::new_anonymous(entries as usize * mem::size_of::<IoUringBuf>())?;
+        let reg: IoUringBufReg = IoUringBufReg {
+            ring_addr: ring.addr as u64,
+            ring_entries: entries as u32,
+            bgid,
+            ..Default::default()
+        };
+        if unsafe {
+            libc::syscall(
+                libc::SYS_io_uring_register,
+                io_uring.fd,
+                IORING_REGISTER_PBUF_RING,
+                &reg as *const IoUringBufReg,
+                1,
+            )
+        } < 0
+        {
+            return Err(last_os_fail("io_uring_register"));
+        }
+
+        let mut me: Self = Self {
+            ring,
+            buffers: vec![0; entries as usize * buffer_size],
+            buffer_size,
+            entries,
+            tail: 0,
+            bgid,
+            io_uring_fd: io_uring.fd,
+        };
+        for bid in 0..entries {
+            me.recycle(bid);
+        }
+        Ok(me)
+    }
+
+    /// Returns the identifier of this buffer group.
+    pub fn bgid(&self) -> u16 {
+        self.bgid
+    }
+
+    /// Returns the size of each buffer.
+    pub fn buffer_size(&self) -> usize {
+        self.buffer_size
+    }
+
+    /// Returns the first [len] bytes of the buffer that the kernel picked for completion [flags], if any.
+    pub fn get(&self, flags: u32, len: usize) -> Option<(u16, &[u8])> {
+        if flags & IORING_CQE_F_BUFFER == 0 {
+            return None;
+        }
+        let bid: u16 = (flags >> IORING_CQE_BUFFER_SHIFT) as u16;
+        let offset: usize = bid as usize * self.buffer_size;
+        Some((bid, &self.buffers[offset..offset + len.min(self.buffer_size)]))
+    }
+
+    /// Gives buffer [bid] back to the kernel.
+    pub fn recycle(&mut self, bid: u16) {
+        debug_assert!(bid < self.entries);
+        let mask: u16 = self.entries - 1;
+        // Safety: the ring holds [entries] buffer descriptors and the kernel does not read the descriptor at [tail] until
+        // the tail is bumped.
+        unsafe {
+            let bufs: *mut IoUringBuf = self.ring.addr as *mut IoUringBuf;
+            let buf: &mut IoUringBuf = &mut *bufs.add((self.tail & mask) as usize);
+            buf.addr = self.buffers.as_ptr().add(bid as usize * self.buffer_size) as u64;
+            buf.len = self.buffer_size as u32;
+            buf.bid = bid;
+            self.tail = self.tail.wrapping_add(1);
+            // The tail of the ring overlays the reserved field of the first descriptor.
+            let tail: *const AtomicU16 = ptr::addr_of!((*bufs).resv) as *const AtomicU16;
+            (*tail).store(self.tail, Ordering::Release);
+        }
+    }
+}
+
+impl Mmap {
+    /// Maps [len] bytes of [fd] at [offset].
+    fn new(fd: RawFd, len: usize, offset: libc::off_t) -> Result<Self, Fail> {
+        let addr: *mut libc::c_void = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED | libc::MAP_POPULATE,
+                fd,
+                offset,
+            )
+        };
+        if addr == libc::MAP_FAILED {
+            return Err(last_os_fail("mmap"));
+        }
+        Ok(Self { addr, len })
+    }
+
+    /// Maps [len] bytes of zeroed, page-aligned memory.
+    fn new_anonymous(len: usize) -> Result<Self, Fail> {
+        let addr: *mut libc::c_void = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if addr == libc::MAP_FAILED {
+            return Err(last_os_fail("mmap"));
+        }
+        Ok(Self { addr, len })
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Builds a [Fail] out of the last OS error.
+fn last_os_fail(syscall: &str) -> Fail {
+    let errno: libc::c_int = unsafe { *libc::__errno_location() };
+    let cause: String = format!("{}() failed (errno={:?})", syscall, errno);
+    error!("{}", cause);
+    Fail::new(errno, &cause)
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Drop for IoUring {
+    fn drop(&mut self) {
+        if unsafe { libc::close(self.fd) } != 0 {
+            warn!("failed to close io_uring (fd={:?})", self.fd);
+        }
+    }
+}
+
+impl Drop for BufferRing {
+    fn drop(&mut self) {
+        let reg: IoUringBufReg = IoUringBufReg {
+            bgid: self.bgid,
+            ..Default::default()
+        };
+        // This fails if the io_uring was already closed, in which case the ring was released along with it.
+        unsafe {
+            libc::syscall(
+                libc::SYS_io_uring_register,
+                self.io_uring_fd,
+                IORING_UNREGISTER_PBUF_RING,
+                &reg as *const IoUringBufReg,
+                1,
+            )
+        };
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        if unsafe { libc::munmap(self.addr, self.len) } != 0 {
+            warn!("failed to unmap memory (addr={:?}, len={:?})", self.addr, self.len);
+        }
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::anyhow::Result;
+
+    /// Tests that data received on a socket lands in a provided buffer.
+    #[test]
+    fn recv_provided_buffer() -> Result<()> {
+        let mut io_uring: IoUring = IoUring::new(8)?;
+        let mut buffers: BufferRing = BufferRing::new(&io_uring, 0, 4, 64)?;
+        let mut fds: [libc::c_int; 2] = [0; 2];
+        crate::ensure_eq!(
+            unsafe { libc::socketpair(libc::AF_UNIX, libc::SOCK_STREAM, 0, fds.as_mut_ptr()) },
+            0
+        );
+
+        // Send data on one end and receive it on the other end.
+        let data: [u8; 4] = [1, 2, 3, 4];
+        io_uring.push(IoUringSqe {
+            opcode: IORING_OP_SEND,
+            fd: fds[0],
+            addr: data.as_ptr() as u64,
+            len: data.len() as u32,
+            user_data: 1,
+            ..Default::default()
+        })?;
+        io_uring.push(IoUringSqe {
+            opcode: IORING_OP_RECV,
+            flags: IOSQE_BUFFER_SELECT,
+            fd: fds[1],
+            buf_group: buffers.bgid(),
+            user_data: 2,
+            ..Default::default()
+        })?;
+        io_uring.submit()?;
+
+        let mut completions: Vec<IoUringCqe> = Vec::new();
+        while completions.len() < 2 {
+            if let Some(cqe) = io_uring.pop() {
+                completions.push(cqe);
+            }
+        }
+        completions.sort_by_key(|cqe| cqe.user_data);
+        crate::ensure_eq!(completions[0].res, data.len() as i32);
+        crate::ensure_eq!(completions[1].res, data.len() as i32);
+        let (bid, received): (u16, &[u8]) = match buffers.get(completions[1].flags, completions[1].res as usize) {
+            Some(buf) => buf,
+            None => anyhow::bail!("receive should have picked a provided buffer"),
+        };
+        crate::ensure_eq!(received, &data[..]);
+        buffers.recycle(bid);
+
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+        Ok(())
+    }
+}
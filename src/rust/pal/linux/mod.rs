@@ -5,6 +5,18 @@
 // Exports
 //======================================================================================================================
 
+// Catcollar uses most of the io_uring interface, file queues only a small part of it.
+#[cfg(any(feature = "catnap-libos", feature = "catmem-libos"))]
+pub mod channel;
+#[cfg(any(
+    feature = "catnap-libos",
+    feature = "catcollar-libos",
+    feature = "catnip-libos",
+    feature = "catpowder-libos",
+    feature = "catloop-libos"
+))]
+#[allow(dead_code)]
+pub mod iouring;
 #[cfg(feature = "catmem-libos")]
 pub mod shm;
 
@@ -12,9 +24,23 @@ pub mod shm;
 // Imports
 //======================================================================================================================
 
+#[cfg(any(feature = "catnap-libos", feature = "catcollar-libos"))]
+use crate::pal::{
+    constants::TCP_MD5SIG_FLAG_PREFIX,
+    data_structures::TcpMd5Sig,
+};
+#[cfg(any(feature = "catnap-libos", feature = "catcollar-libos"))]
+use ::std::{
+    io,
+    os::fd::RawFd,
+    ptr,
+};
 use ::std::{
     mem,
-    net::SocketAddrV4,
+    net::{
+        Ipv4Addr,
+        SocketAddrV4,
+    },
 };
 
 //======================================================================================================================
@@ -43,3 +69,47 @@ pub fn socketaddrv4_to_sockaddr(addr: &SocketAddrV4) -> libc::sockaddr {
     let sin: libc::sockaddr_in = socketaddrv4_to_sockaddr_in(addr);
     unsafe { mem::transmute::<libc::sockaddr_in, libc::sockaddr>(sin) }
 }
+
+/// Converts a [libc::sockaddr] to a [std::net::SocketAddrV4]. Returns None if it is not an IPv4 address.
+pub fn sockaddr_to_socketaddrv4(saddr: &libc::sockaddr) -> Option<SocketAddrV4> {
+    if saddr.sa_family != libc::AF_INET as libc::sa_family_t {
+        return None;
+    }
+    let sin: libc::sockaddr_in = unsafe { mem::transmute::<libc::sockaddr, libc::sockaddr_in>(*saddr) };
+    Some(SocketAddrV4::new(
+        Ipv4Addr::from(sin.sin_addr.s_addr.to_ne_bytes()),
+        u16::from_be(sin.sin_port),
+    ))
+}
+
+/// Sets the TCP MD5 signature key that the socket [fd] shares with the peers in [addr]/[prefix_len]. An empty [key]
+/// removes the key of those peers.
+#[cfg(any(feature = "catnap-libos", feature = "catcollar-libos"))]
+pub fn set_tcp_md5_key(fd: RawFd, addr: Ipv4Addr, prefix_len: u8, key: &[u8]) -> io::Result<()> {
+    // Safety: the argument of the option is plain old data, for which all zeros is a valid value.
+    let mut sig: TcpMd5Sig = unsafe { mem::zeroed() };
+    let sin: libc::sockaddr_in = socketaddrv4_to_sockaddr_in(&SocketAddrV4::new(addr, 0));
+    // Safety: a socket address storage is large enough and aligned enough to hold any socket address.
+    unsafe {
+        ptr::write(
+            &mut sig.tcpm_addr as *mut libc::sockaddr_storage as *mut libc::sockaddr_in,
+            sin,
+        )
+    };
+    sig.tcpm_flags = TCP_MD5SIG_FLAG_PREFIX;
+    sig.tcpm_prefixlen = prefix_len;
+    sig.tcpm_keylen = key.len() as u16;
+    sig.tcpm_key[..key.len()].copy_from_slice(key);
+    match unsafe {
+        libc::setsockopt(
+            fd,
+            libc::IPPROTO_TCP,
+            libc::TCP_MD5SIG_EXT,
+            &sig as *const TcpMd5Sig as *const libc::c_void,
+            mem::size_of::<TcpMd5Sig>() as libc::socklen_t,
+        )
+    } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
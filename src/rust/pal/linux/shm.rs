@@ -7,7 +7,13 @@
 // Imports
 //======================================================================================================================
 
-use crate::runtime::fail::Fail;
+use crate::runtime::{
+    fail::Fail,
+    teardown::{
+        self,
+        TeardownGuard,
+    },
+};
 use ::core::{
     mem,
     ops::{
@@ -17,7 +23,11 @@ use ::core::{
     ptr,
     slice,
 };
-use ::std::ffi;
+use ::std::{
+    ffi,
+    fs,
+    process,
+};
 
 //======================================================================================================================
 // Structures
@@ -27,6 +37,9 @@ use ::std::ffi;
 pub struct SharedMemory {
     /// Was this region created or opened?
     was_created: bool,
+    /// Process that created or opened this region. A child that inherits the region across fork leaves its name in
+    /// place, as the region still belongs to its parent.
+    pid: u32,
     /// Name.
     name: ffi::CString,
     /// Underlying file descriptor.
@@ -35,6 +48,8 @@ pub struct SharedMemory {
     size: libc::size_t,
     /// Base address.
     addr: *mut libc::c_void,
+    /// Removes the name of a region that this process created if it dies without dropping the region.
+    teardown: Option<TeardownGuard>,
 }
 
 //======================================================================================================================
@@ -45,6 +60,8 @@ pub struct SharedMemory {
 impl SharedMemory {
     /// Prefix for shared memory region names.
     const SHM_NAME_PREFIX: &'static str = "demikernel-";
+    /// Directory where the names of shared memory regions live.
+    const SHM_DIR: &'static str = "/dev/shm";
 
     /// Opens an existing named shared memory region.
     pub fn open(name: &str, len: usize) -> Result<SharedMemory, Fail> {
@@ -69,10 +86,12 @@ impl SharedMemory {
 
         let mut shm: SharedMemory = SharedMemory {
             was_created: false,
+            pid: process::id(),
             fd,
             name,
             size: 0,
             addr: ptr::null_mut(),
+            teardown: None,
         };
 
         shm.map(len)?;
@@ -80,6 +99,52 @@ impl SharedMemory {
         Ok(shm)
     }
 
+    /// Checks whether a shared memory region named [name] exists.
+    pub fn exists(name: &str) -> bool {
+        let name: ffi::CString = match Self::build_name(name) {
+            Ok(name) => name,
+            Err(_) => return false,
+        };
+        // Forward request to underlying POSIX OS.
+        unsafe {
+            let fd: libc::c_int = libc::shm_open(name.as_ptr(), libc::O_RDONLY, 0);
+            if fd == -1 {
+                return false;
+            }
+            libc::close(fd);
+        }
+        true
+    }
+
+    /// Removes the name of the shared memory region [name], which was left behind by a process that is gone. Processes
+    /// that still map the region keep using it.
+    pub fn remove(name: &str) -> Result<(), Fail> {
+        let name: ffi::CString = Self::build_name(name)?;
+        // Forward request to underlying POSIX OS.
+        if unsafe { libc::shm_unlink(name.as_ptr()) } == -1 {
+            let errno: libc::c_int = unsafe { *libc::__errno_location() };
+            let cause: String = format!("failed to remove shared memory region (name={:?}, errno={})", name, errno);
+            warn!("remove(): {}", cause);
+            return Err(Fail::new(errno, &cause));
+        }
+        Ok(())
+    }
+
+    /// Lists the names of the shared memory regions that exist on this host, without their prefix.
+    pub fn list() -> Vec<String> {
+        let entries: fs::ReadDir = match fs::read_dir(Self::SHM_DIR) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("list(): failed to list shared memory regions (error={:?})", e);
+                return Vec::new();
+            },
+        };
+        entries
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter_map(|name| name.strip_prefix(Self::SHM_NAME_PREFIX).map(String::from))
+            .collect()
+    }
+
     /// Creates a named shared memory region.
     pub fn create(name: &str, size: usize) -> Result<SharedMemory, Fail> {
         let name: ffi::CString = Self::build_name(name)?;
@@ -104,12 +169,21 @@ impl SharedMemory {
             ret
         };
 
+        let pid: u32 = process::id();
+        let unlink_name: ffi::CString = name.clone();
+        let guard: TeardownGuard = teardown::register("shared memory region", move || {
+            if process::id() == pid {
+                unsafe { libc::shm_unlink(unlink_name.as_ptr()) };
+            }
+        });
         let mut shm: SharedMemory = SharedMemory {
             was_created: true,
+            pid,
             fd,
             name,
             size: 0,
             addr: ptr::null_mut(),
+            teardown: Some(guard),
         };
 
         shm.truncate(size)?;
@@ -296,12 +370,14 @@ impl Drop for SharedMemory {
             Err(e) => eprintln!("{}", e),
         }
         // 3) Remove the underlying shared memory region name link.
-        if self.was_created {
+        if self.was_created && self.pid == process::id() {
             match self.unlink() {
                 Ok(_) => {},
                 Err(e) => eprintln!("{}", e),
             }
         }
+        // 4) Withdraw the teardown action, as there is nothing left to release.
+        self.teardown.take();
     }
 }
 
@@ -0,0 +1,267 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! This module binds the thread that runs the engine of a LibOS to cores, and sets how the kernel schedules it. A
+//! LibOS lives on the thread that created it and is polled from there, so binding the calling thread binds the engine.
+//! Each engine is expected to have its own thread, so binding one engine does not affect the others.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::fail::Fail;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Number of cores that a binding can refer to. This is the size of the CPU sets of the kernel.
+pub const MAX_CORES: usize = 1024;
+
+/// Highest real-time priority of the engine.
+pub const MAX_REALTIME_PRIORITY: u32 = 99;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// How to bind the engine thread. Parameters that are not set are left as they are.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BindingConfig {
+    /// Cores that the engine may run on.
+    pub cores: Option<Vec<usize>>,
+    /// Niceness of the engine, from -20 (highest priority) to 19 (lowest priority).
+    pub nice: Option<i32>,
+    /// Real-time priority of the engine, under the FIFO policy. Zero brings the engine back to the default policy.
+    pub realtime_priority: Option<u32>,
+    /// Slack that the kernel may add to the timers of the engine, in nanoseconds. A slack of one nanosecond keeps the
+    /// kernel from batching the wakeups of the engine with other timers, which suits cores that run tickless.
+    pub timer_slack_ns: Option<u64>,
+}
+
+/// How the engine thread is bound.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EngineBinding {
+    /// Cores that the engine may run on, sorted.
+    pub cores: Vec<usize>,
+    /// Core that the engine runs on right now.
+    pub current_core: usize,
+    /// Niceness of the engine.
+    pub nice: i32,
+    /// Real-time priority of the engine, if it is scheduled under the FIFO policy.
+    pub realtime_priority: Option<u32>,
+    /// Slack that the kernel may add to the timers of the engine, in nanoseconds.
+    pub timer_slack_ns: u64,
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Binds the calling thread, which runs the engine, as described by [config].
+#[cfg(target_os = "linux")]
+pub fn bind_engine(config: &BindingConfig) -> Result<(), Fail> {
+    trace!("bind_engine(): config={:?}", config);
+    if let Some(cores) = &config.cores {
+        if cores.is_empty() {
+            let cause: &str = "engine should be bound to at least one core";
+            error!("bind_engine(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, cause));
+        }
+        let mut cpuset: libc::cpu_set_t = unsafe { ::std::mem::zeroed() };
+        for core in cores {
+            if *core >= MAX_CORES {
+                let cause: String = format!("core is out of range (core={:?})", core);
+                error!("bind_engine(): {}", cause);
+                return Err(Fail::new(libc::EINVAL, &cause));
+            }
+            unsafe { libc::CPU_SET(*core, &mut cpuset) };
+        }
+        if unsafe { libc::sched_setaffinity(0, ::std::mem::size_of::<libc::cpu_set_t>(), &cpuset) } != 0 {
+            return Err(last_error("bind_engine", "failed to set the cores of the engine"));
+        }
+    }
+    if let Some(nice) = config.nice {
+        if !(-20..=19).contains(&nice) {
+            let cause: String = format!("niceness is out of range (nice={:?})", nice);
+            error!("bind_engine(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        // On Linux, the niceness of a process only applies to the thread that is given.
+        if unsafe { libc::setpriority(libc::PRIO_PROCESS, libc::gettid() as libc::id_t, nice) } != 0 {
+            return Err(last_error("bind_engine", "failed to set the niceness of the engine"));
+        }
+    }
+    if let Some(priority) = config.realtime_priority {
+        if priority > MAX_REALTIME_PRIORITY {
+            let cause: String = format!("real-time priority is out of range (priority={:?})", priority);
+            error!("bind_engine(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        let policy: libc::c_int = if priority == 0 {
+            libc::SCHED_OTHER
+        } else {
+            libc::SCHED_FIFO
+        };
+        let param: libc::sched_param = libc::sched_param {
+            sched_priority: priority as libc::c_int,
+        };
+        if unsafe { libc::sched_setscheduler(0, policy, &param) } != 0 {
+            return Err(last_error(
+                "bind_engine",
+                "failed to set the scheduling policy of the engine",
+            ));
+        }
+    }
+    if let Some(slack) = config.timer_slack_ns {
+        if unsafe { libc::prctl(libc::PR_SET_TIMERSLACK, slack as libc::c_ulong) } != 0 {
+            return Err(last_error("bind_engine", "failed to set the timer slack of the engine"));
+        }
+    }
+    Ok(())
+}
+
+/// Gets how the calling thread, which runs the engine, is bound.
+#[cfg(target_os = "linux")]
+pub fn get_engine_binding() -> Result<EngineBinding, Fail> {
+    let mut cpuset: libc::cpu_set_t = unsafe { ::std::mem::zeroed() };
+    if unsafe { libc::sched_getaffinity(0, ::std::mem::size_of::<libc::cpu_set_t>(), &mut cpuset) } != 0 {
+        return Err(last_error(
+            "get_engine_binding",
+            "failed to get the cores of the engine",
+        ));
+    }
+    let cores: Vec<usize> = (0..MAX_CORES)
+        .filter(|core| unsafe { libc::CPU_ISSET(*core, &cpuset) })
+        .collect();
+    let current_core: libc::c_int = unsafe { libc::sched_getcpu() };
+    if current_core < 0 {
+        return Err(last_error(
+            "get_engine_binding",
+            "failed to get the current core of the engine",
+        ));
+    }
+    // The niceness may legitimately be -1, so errors are told apart through errno.
+    unsafe { *libc::__errno_location() = 0 };
+    let nice: libc::c_int = unsafe { libc::getpriority(libc::PRIO_PROCESS, libc::gettid() as libc::id_t) };
+    if nice == -1 && unsafe { *libc::__errno_location() } != 0 {
+        return Err(last_error(
+            "get_engine_binding",
+            "failed to get the niceness of the engine",
+        ));
+    }
+    let realtime_priority: Option<u32> = match unsafe { libc::sched_getscheduler(0) } {
+        policy if policy < 0 => {
+            return Err(last_error(
+                "get_engine_binding",
+                "failed to get the scheduling policy of the engine",
+            ))
+        },
+        libc::SCHED_FIFO | libc::SCHED_RR => {
+            let mut param: libc::sched_param = libc::sched_param { sched_priority: 0 };
+            if unsafe { libc::sched_getparam(0, &mut param) } != 0 {
+                return Err(last_error(
+                    "get_engine_binding",
+                    "failed to get the real-time priority of the engine",
+                ));
+            }
+            Some(param.sched_priority as u32)
+        },
+        _ => None,
+    };
+    let timer_slack_ns: libc::c_int = unsafe { libc::prctl(libc::PR_GET_TIMERSLACK) };
+    if timer_slack_ns < 0 {
+        return Err(last_error(
+            "get_engine_binding",
+            "failed to get the timer slack of the engine",
+        ));
+    }
+    Ok(EngineBinding {
+        cores,
+        current_core: current_core as usize,
+        nice,
+        realtime_priority,
+        timer_slack_ns: timer_slack_ns as u64,
+    })
+}
+
+/// Threads cannot be bound to cores on this platform.
+#[cfg(not(target_os = "linux"))]
+pub fn bind_engine(_config: &BindingConfig) -> Result<(), Fail> {
+    Err(Fail::new(
+        libc::ENOTSUP,
+        "binding the engine is not supported on this platform",
+    ))
+}
+
+/// Threads cannot be bound to cores on this platform.
+#[cfg(not(target_os = "linux"))]
+pub fn get_engine_binding() -> Result<EngineBinding, Fail> {
+    Err(Fail::new(
+        libc::ENOTSUP,
+        "binding the engine is not supported on this platform",
+    ))
+}
+
+/// Builds the error of a system call that just failed in [function].
+#[cfg(target_os = "linux")]
+fn last_error(function: &str, cause: &str) -> Fail {
+    let errno: libc::c_int = unsafe { *libc::__errno_location() };
+    let cause: String = format!("{} (errno={:?})", cause, errno);
+    error!("{}(): {}", function, cause);
+    Fail::new(errno, &cause)
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::affinity::{
+        bind_engine,
+        get_engine_binding,
+        BindingConfig,
+        EngineBinding,
+    };
+    use ::anyhow::Result;
+    use ::std::thread;
+
+    /// Tests that the engine thread can be bound to a core and that the binding is reported.
+    #[test]
+    fn test_bind_engine() -> Result<()> {
+        // Bind a thread of its own, so that the binding does not leak into other tests.
+        thread::spawn(|| -> Result<()> {
+            let before: EngineBinding = get_engine_binding()?;
+            let core: usize = before.current_core;
+            let config: BindingConfig = BindingConfig {
+                cores: Some(vec![core]),
+                nice: Some(before.nice.max(1)),
+                realtime_priority: None,
+                timer_slack_ns: Some(1),
+            };
+            bind_engine(&config)?;
+
+            let after: EngineBinding = get_engine_binding()?;
+            crate::ensure_eq!(after.cores, vec![core]);
+            crate::ensure_eq!(after.current_core, core);
+            crate::ensure_eq!(after.nice, before.nice.max(1));
+            crate::ensure_eq!(after.timer_slack_ns, 1);
+
+            // Out of range parameters are rejected.
+            let config: BindingConfig = BindingConfig {
+                cores: Some(vec![]),
+                ..Default::default()
+            };
+            crate::ensure_eq!(bind_engine(&config).unwrap_err().errno, libc::EINVAL);
+            let config: BindingConfig = BindingConfig {
+                nice: Some(20),
+                ..Default::default()
+            };
+            crate::ensure_eq!(bind_engine(&config).unwrap_err().errno, libc::EINVAL);
+            Ok(())
+        })
+        .join()
+        .expect("binding thread should not panic")
+    }
+}
@@ -0,0 +1,159 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Cooperative yield budget. Every time that the scheduler polls a task, the task gets a fresh budget of work units.
+//! Long-running coroutines (e.g. a loop that drains a large receive queue) call [consume_budget] at their yield points
+//! and, once the budget runs out, give the processor back to the scheduler and are re-queued. This bounds the time
+//! that a single poll can take and, thus, the worst-case scheduling latency of the other tasks.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::poll::PollFuture;
+use ::std::cell::Cell;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Number of work units that a task may consume in a single poll before it is forced to yield.
+pub const DEFAULT_YIELD_BUDGET: usize = 128;
+
+//======================================================================================================================
+// Thread local variable
+//======================================================================================================================
+
+thread_local! {
+/// Work units left to the task that is currently being polled. The scheduler is single-threaded, so there is one
+/// budget per thread.
+static BUDGET: Cell<usize> = const { Cell::new(DEFAULT_YIELD_BUDGET) };
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Refills the budget. The scheduler calls this before polling each task.
+pub fn reset_budget() {
+    BUDGET.with(|budget| budget.set(DEFAULT_YIELD_BUDGET));
+}
+
+/// Returns the number of work units left to the task that is currently being polled.
+pub fn remaining_budget() -> usize {
+    BUDGET.with(|budget| budget.get())
+}
+
+/// Consumes [units] of work from the budget of the running task. This is cheap when there is budget left: it does not
+/// yield and completes immediately. Once the budget is exhausted, the task yields for one quanta and is re-queued.
+pub async fn consume_budget(units: usize) {
+    let exhausted: bool = BUDGET.with(|budget| {
+        let remaining: usize = budget.get().saturating_sub(units);
+        budget.set(remaining);
+        remaining == 0
+    });
+    if exhausted {
+        // The budget is refilled when the scheduler polls us again, but do it here as well in case this future is
+        // being driven outside of the scheduler.
+        reset_budget();
+        PollFuture::default().await;
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        consume_budget,
+        remaining_budget,
+        reset_budget,
+        DEFAULT_YIELD_BUDGET,
+    };
+    use crate::runtime::{
+        poll_yield,
+        OperationResult,
+        QDesc,
+        QToken,
+        SharedDemiRuntime,
+    };
+    use ::anyhow::Result;
+    use ::futures::FutureExt;
+    use ::std::{
+        cell::Cell,
+        rc::Rc,
+        time::Duration,
+    };
+
+    /// Tests that a coroutine that keeps consuming its budget is re-queued, so that other coroutines get to run before
+    /// it completes.
+    #[test]
+    fn test_consume_budget_yields() -> Result<()> {
+        let mut runtime: SharedDemiRuntime = SharedDemiRuntime::default();
+        let polls: Rc<Cell<usize>> = Rc::new(Cell::new(0));
+        let polls_: Rc<Cell<usize>> = polls.clone();
+        let long_running_qt: QToken = runtime.insert_io_coroutine(
+            "long-running coroutine",
+            QDesc::from(0),
+            Box::pin(
+                async move {
+                    for _ in 0..(4 * DEFAULT_YIELD_BUDGET) {
+                        polls_.set(polls_.get() + 1);
+                        consume_budget(1).await;
+                    }
+                    (QDesc::from(0), OperationResult::Close)
+                }
+                .fuse(),
+            ),
+        )?;
+
+        // A short coroutine that starts after the long-running one must not wait for it to complete.
+        let observed: Rc<Cell<Option<usize>>> = Rc::new(Cell::new(None));
+        let observed_: Rc<Cell<Option<usize>>> = observed.clone();
+        let polls_: Rc<Cell<usize>> = polls.clone();
+        let short_qt: QToken = runtime.insert_io_coroutine(
+            "short coroutine",
+            QDesc::from(1),
+            Box::pin(
+                async move {
+                    poll_yield().await;
+                    observed_.set(Some(polls_.get()));
+                    (QDesc::from(1), OperationResult::Close)
+                }
+                .fuse(),
+            ),
+        )?;
+
+        match runtime.wait(short_qt, Duration::from_secs(1))? {
+            (0, _, _, OperationResult::Close) => (),
+            _ => anyhow::bail!("operation should complete"),
+        }
+        let observed: usize = observed.get().unwrap_or_default();
+        crate::ensure_eq!(observed < 4 * DEFAULT_YIELD_BUDGET, true);
+        // The long-running coroutine only yields at the end of its budget.
+        crate::ensure_eq!(observed % DEFAULT_YIELD_BUDGET, 0);
+
+        match runtime.wait(long_running_qt, Duration::from_secs(1))? {
+            (0, _, _, OperationResult::Close) => (),
+            _ => anyhow::bail!("operation should complete"),
+        }
+        crate::ensure_eq!(polls.get(), 4 * DEFAULT_YIELD_BUDGET);
+
+        Ok(())
+    }
+
+    /// Tests that consuming the budget does not underflow and that resetting it refills it.
+    #[test]
+    fn test_reset_budget() -> Result<()> {
+        reset_budget();
+        crate::ensure_eq!(consume_budget(DEFAULT_YIELD_BUDGET - 1).now_or_never().is_some(), true);
+        crate::ensure_eq!(remaining_budget(), 1);
+        // Going over the budget yields once and then refills it.
+        crate::ensure_eq!(consume_budget(2 * DEFAULT_YIELD_BUDGET).now_or_never().is_none(), true);
+        crate::ensure_eq!(remaining_budget(), DEFAULT_YIELD_BUDGET);
+
+        Ok(())
+    }
+}
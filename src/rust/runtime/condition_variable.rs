@@ -30,6 +30,7 @@ use ::std::{
 enum YieldState {
     Running,
     Yielded,
+    Completed,
 }
 
 #[derive(Eq, PartialEq, Clone, Copy)]
@@ -40,7 +41,9 @@ struct YieldPointId(u64);
 /// ready, the last coroutine to call get is woken.
 pub struct ConditionVariable {
     waiters: VecDeque<(YieldPointId, Waker)>,
-    num_ready: usize,
+    // Waiters that were woken up but did not run yet. Each wake-up belongs to the waiter that it was meant for, so that
+    // another coroutine that starts waiting in the meantime cannot take it.
+    woken: Vec<YieldPointId>,
     last_id: u64,
 }
 
@@ -63,8 +66,8 @@ struct YieldPoint {
 impl SharedConditionVariable {
     /// Wake the next waiting coroutine.
     pub fn signal(&mut self) {
-        if let Some((_, waiter)) = self.waiters.pop_front() {
-            self.num_ready += 1;
+        if let Some((id, waiter)) = self.waiters.pop_front() {
+            self.woken.push(id);
             waiter.wake_by_ref();
         }
     }
@@ -72,8 +75,8 @@ impl SharedConditionVariable {
     #[allow(unused)]
     /// Wake all waiting coroutines.
     pub fn broadcast(&mut self) {
-        while let Some((task_id, waiter)) = self.waiters.pop_front() {
-            self.num_ready += 1;
+        while let Some((id, waiter)) = self.waiters.pop_front() {
+            self.woken.push(id);
             waiter.wake_by_ref();
         }
     }
@@ -81,7 +84,7 @@ impl SharedConditionVariable {
     /// Cancel all waiting coroutines. This function should be used CAREFULLY as the waiting coroutines will never wake.
     pub fn cancel(&mut self) {
         self.waiters.clear();
-        self.num_ready = 0;
+        self.woken.clear();
     }
 
     /// Wait until signal.
@@ -99,8 +102,22 @@ impl SharedConditionVariable {
         self.waiters.push_back((id, waker));
     }
 
-    fn remove_waiter(&mut self, id: YieldPointId) {
+    /// Consumes the wake-up of the waiter [id]. Returns false if the waiter was not woken up.
+    fn take_wake_up(&mut self, id: YieldPointId) -> bool {
+        match self.woken.iter().position(|i| *i == id) {
+            Some(index) => {
+                self.woken.swap_remove(index);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Removes the waiter [id]. Returns false if the waiter was already woken up.
+    fn remove_waiter(&mut self, id: YieldPointId) -> bool {
+        let num_waiters: usize = self.waiters.len();
         self.waiters.retain(|(i, _)| *i != id);
+        self.waiters.len() != num_waiters
     }
 }
 
@@ -112,7 +129,7 @@ impl Default for SharedConditionVariable {
     fn default() -> Self {
         Self(SharedObject::new(ConditionVariable {
             waiters: VecDeque::default(),
-            num_ready: 0,
+            woken: Vec::default(),
             last_id: 0,
         }))
     }
@@ -139,8 +156,8 @@ impl Future for YieldPoint {
     /// ready.
     fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
         let self_: &mut Self = self.get_mut();
-        if self_.cond_var.num_ready > 0 {
-            self_.cond_var.num_ready -= 1;
+        if self_.state == YieldState::Yielded && self_.cond_var.take_wake_up(self_.id) {
+            self_.state = YieldState::Completed;
             Poll::Ready(())
         } else {
             if self_.state == YieldState::Running {
@@ -154,7 +171,11 @@ impl Future for YieldPoint {
 
 impl Drop for YieldPoint {
     fn drop(&mut self) {
-        self.cond_var.remove_waiter(self.id)
+        // If a coroutine is cancelled after a signal woke it up, but before it ran, pass the signal on to the next
+        // waiting coroutine.
+        if !self.cond_var.remove_waiter(self.id) && self.cond_var.take_wake_up(self.id) {
+            self.cond_var.signal();
+        }
     }
 }
 
@@ -0,0 +1,207 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    crypto::aes::{
+        Aes128,
+        AES128_KEY_SIZE,
+        AES_BLOCK_SIZE,
+    },
+    fail::Fail,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Size of an AES-GCM nonce.
+pub const AES_GCM_NONCE_SIZE: usize = 12;
+
+/// Size of an AES-GCM authentication tag.
+pub const AES_GCM_TAG_SIZE: usize = 16;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// GHASH universal hash function (NIST SP 800-38D, section 6.4).
+struct Ghash {
+    h: u128,
+    y: u128,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Ghash {
+    fn new(h: u128) -> Self {
+        Self { h, y: 0 }
+    }
+
+    /// Adds [data] to the hash, zero padded to a multiple of the block size.
+    fn update_padded(&mut self, data: &[u8]) {
+        for chunk in data.chunks(AES_BLOCK_SIZE) {
+            let mut block: [u8; AES_BLOCK_SIZE] = [0; AES_BLOCK_SIZE];
+            block[..chunk.len()].copy_from_slice(chunk);
+            self.y = gf128_mul(self.y ^ u128::from_be_bytes(block), self.h);
+        }
+    }
+
+    fn finalize(mut self, aad_len: usize, text_len: usize) -> u128 {
+        let lengths: u128 = ((aad_len as u128 * 8) << 64) | (text_len as u128 * 8);
+        self.y = gf128_mul(self.y ^ lengths, self.h);
+        self.y
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Multiplies [x] and [y] in GF(2^128) with the bit order of GCM.
+fn gf128_mul(x: u128, y: u128) -> u128 {
+    const R: u128 = 0xe1 << 120;
+    let mut z: u128 = 0;
+    let mut v: u128 = y;
+    for i in 0..128 {
+        // Constant-time selection of whether bit i of x (from the most significant one) is set.
+        z ^= v & 0u128.wrapping_sub((x >> (127 - i)) & 1);
+        v = (v >> 1) ^ (R & 0u128.wrapping_sub(v & 1));
+    }
+    z
+}
+
+/// Encrypts or decrypts [buf] in place in counter mode, starting at counter block [counter].
+fn aes_ctr(aes: &Aes128, counter: &[u8; AES_BLOCK_SIZE], buf: &mut [u8]) {
+    let mut counter: u128 = u128::from_be_bytes(*counter);
+    for chunk in buf.chunks_mut(AES_BLOCK_SIZE) {
+        let mut block: [u8; AES_BLOCK_SIZE] = counter.to_be_bytes();
+        aes.encrypt_block(&mut block);
+        for (byte, key_byte) in chunk.iter_mut().zip(block.iter()) {
+            *byte ^= key_byte;
+        }
+        // Only the last 32 bits of the counter block are incremented.
+        counter = (counter & !0xffffffff) | ((counter as u32).wrapping_add(1) as u128);
+    }
+}
+
+/// Computes the tag of AES-GCM over [aad] and [ciphertext].
+fn compute_tag(aes: &Aes128, j0: &[u8; AES_BLOCK_SIZE], aad: &[u8], ciphertext: &[u8]) -> [u8; AES_GCM_TAG_SIZE] {
+    let mut h: [u8; AES_BLOCK_SIZE] = [0; AES_BLOCK_SIZE];
+    aes.encrypt_block(&mut h);
+    let mut ghash: Ghash = Ghash::new(u128::from_be_bytes(h));
+    ghash.update_padded(aad);
+    ghash.update_padded(ciphertext);
+    let mut tag: [u8; AES_GCM_TAG_SIZE] = ghash.finalize(aad.len(), ciphertext.len()).to_be_bytes();
+    aes_ctr(aes, j0, &mut tag);
+    tag
+}
+
+/// Builds the first counter block of a 96-bit [nonce].
+fn initial_counter(nonce: &[u8; AES_GCM_NONCE_SIZE]) -> [u8; AES_BLOCK_SIZE] {
+    let mut j0: [u8; AES_BLOCK_SIZE] = [0; AES_BLOCK_SIZE];
+    j0[..AES_GCM_NONCE_SIZE].copy_from_slice(nonce);
+    j0[AES_BLOCK_SIZE - 1] = 1;
+    j0
+}
+
+/// Encrypts [buf] in place with AES-128-GCM and appends the authentication tag, which also covers [aad].
+pub fn aes128_gcm_seal(key: &[u8; AES128_KEY_SIZE], nonce: &[u8; AES_GCM_NONCE_SIZE], aad: &[u8], buf: &mut Vec<u8>) {
+    let aes: Aes128 = Aes128::new(key);
+    let j0: [u8; AES_BLOCK_SIZE] = initial_counter(nonce);
+    let mut counter: [u8; AES_BLOCK_SIZE] = j0;
+    counter[AES_BLOCK_SIZE - 1] = 2;
+    aes_ctr(&aes, &counter, buf);
+    let tag: [u8; AES_GCM_TAG_SIZE] = compute_tag(&aes, &j0, aad, buf);
+    buf.extend_from_slice(&tag);
+}
+
+/// Checks the authentication tag at the end of [buf] and decrypts the rest of it in place with AES-128-GCM. The tag is
+/// removed from [buf]. On failure, [buf] is left untouched.
+pub fn aes128_gcm_open(
+    key: &[u8; AES128_KEY_SIZE],
+    nonce: &[u8; AES_GCM_NONCE_SIZE],
+    aad: &[u8],
+    buf: &mut Vec<u8>,
+) -> Result<(), Fail> {
+    if buf.len() < AES_GCM_TAG_SIZE {
+        let cause: String = format!("ciphertext is too short (len={:?})", buf.len());
+        warn!("aes128_gcm_open(): {}", cause);
+        return Err(Fail::new(libc::EBADMSG, &cause));
+    }
+    let aes: Aes128 = Aes128::new(key);
+    let j0: [u8; AES_BLOCK_SIZE] = initial_counter(nonce);
+    let len: usize = buf.len() - AES_GCM_TAG_SIZE;
+    let expected: [u8; AES_GCM_TAG_SIZE] = compute_tag(&aes, &j0, aad, &buf[..len]);
+    // Compare the tags in constant time.
+    let diff: u8 = expected
+        .iter()
+        .zip(buf[len..].iter())
+        .fold(0, |diff, (a, b)| diff | (a ^ b));
+    if diff != 0 {
+        let cause: &str = "authentication tag mismatch";
+        warn!("aes128_gcm_open(): {}", cause);
+        return Err(Fail::new(libc::EBADMSG, cause));
+    }
+    buf.truncate(len);
+    let mut counter: [u8; AES_BLOCK_SIZE] = j0;
+    counter[AES_BLOCK_SIZE - 1] = 2;
+    aes_ctr(&aes, &counter, buf);
+    Ok(())
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        aes128_gcm_open,
+        aes128_gcm_seal,
+    };
+    use crate::runtime::crypto::unhex;
+    use ::anyhow::Result;
+
+    /// Test cases 2 and 4 of the original GCM specification.
+    #[test]
+    fn test_aes128_gcm() -> Result<()> {
+        let mut buf: Vec<u8> = vec![0; 16];
+        aes128_gcm_seal(&[0; 16], &[0; 12], &[], &mut buf);
+        crate::ensure_eq!(
+            buf,
+            unhex("0388dace60b6a392f328c2b971b2fe78ab6e47d42cec13bdf53a67b21257bddf")
+        );
+
+        let key: [u8; 16] = unhex("feffe9928665731c6d6a8f9467308308").try_into().unwrap();
+        let nonce: [u8; 12] = unhex("cafebabefacedbaddecaf888").try_into().unwrap();
+        let aad: Vec<u8> = unhex("feedfacedeadbeeffeedfacedeadbeefabaddad2");
+        let plaintext: Vec<u8> = unhex(concat!(
+            "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72",
+            "1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39"
+        ));
+        let mut buf: Vec<u8> = plaintext.clone();
+        aes128_gcm_seal(&key, &nonce, &aad, &mut buf);
+        crate::ensure_eq!(
+            buf,
+            unhex(concat!(
+                "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e",
+                "21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091",
+                "5bc94fbc3221a5db94fae95ae7121a47"
+            ))
+        );
+        aes128_gcm_open(&key, &nonce, &aad, &mut buf)?;
+        crate::ensure_eq!(buf, plaintext);
+
+        // Any change to the additional data is detected.
+        let mut buf: Vec<u8> = plaintext.clone();
+        aes128_gcm_seal(&key, &nonce, &aad, &mut buf);
+        crate::ensure_eq!(aes128_gcm_open(&key, &nonce, &aad[1..], &mut buf).is_err(), true);
+        Ok(())
+    }
+}
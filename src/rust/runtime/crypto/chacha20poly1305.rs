@@ -0,0 +1,381 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::fail::Fail;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Size of a ChaCha20-Poly1305 key.
+pub const CHACHA20_POLY1305_KEY_SIZE: usize = 32;
+
+/// Size of a ChaCha20-Poly1305 nonce.
+pub const CHACHA20_POLY1305_NONCE_SIZE: usize = 12;
+
+/// Size of a ChaCha20-Poly1305 authentication tag.
+pub const CHACHA20_POLY1305_TAG_SIZE: usize = 16;
+
+/// Size of a ChaCha20 block.
+const CHACHA20_BLOCK_SIZE: usize = 64;
+
+/// Mask of a 26-bit limb of the Poly1305 accumulator.
+const POLY1305_LIMB_MASK: u32 = 0x3ffffff;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Poly1305 one-time authenticator (RFC 8439, section 2.5). The accumulator is kept in five 26-bit limbs.
+struct Poly1305 {
+    r: [u32; 5],
+    h: [u32; 5],
+    pad: [u32; 4],
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Poly1305 {
+    fn new(key: &[u8; 32]) -> Self {
+        Self {
+            r: [
+                load32(&key[0..]) & 0x3ffffff,
+                (load32(&key[3..]) >> 2) & 0x3ffff03,
+                (load32(&key[6..]) >> 4) & 0x3ffc0ff,
+                (load32(&key[9..]) >> 6) & 0x3f03fff,
+                (load32(&key[12..]) >> 8) & 0x00fffff,
+            ],
+            h: [0; 5],
+            pad: [
+                load32(&key[16..]),
+                load32(&key[20..]),
+                load32(&key[24..]),
+                load32(&key[28..]),
+            ],
+        }
+    }
+
+    /// Adds [data] to the authenticator, zero padded to a multiple of 16 bytes.
+    fn update_padded(&mut self, data: &[u8]) {
+        for chunk in data.chunks(16) {
+            let mut block: [u8; 16] = [0; 16];
+            block[..chunk.len()].copy_from_slice(chunk);
+            self.block(&block, 1 << 24);
+        }
+    }
+
+    /// Adds a 16-byte [block] to the authenticator. [hibit] is the bit that is set past the end of the block, which is
+    /// only cleared for a final block that was padded by the caller.
+    fn block(&mut self, block: &[u8; 16], hibit: u32) {
+        let [r0, r1, r2, r3, r4] = self.r.map(|r| r as u64);
+        let [s1, s2, s3, s4] = [r1 * 5, r2 * 5, r3 * 5, r4 * 5];
+
+        let h0: u64 = (self.h[0] + (load32(&block[0..]) & POLY1305_LIMB_MASK)) as u64;
+        let h1: u64 = (self.h[1] + ((load32(&block[3..]) >> 2) & POLY1305_LIMB_MASK)) as u64;
+        let h2: u64 = (self.h[2] + ((load32(&block[6..]) >> 4) & POLY1305_LIMB_MASK)) as u64;
+        let h3: u64 = (self.h[3] + ((load32(&block[9..]) >> 6) & POLY1305_LIMB_MASK)) as u64;
+        let h4: u64 = (self.h[4] + ((load32(&block[12..]) >> 8) | hibit)) as u64;
+
+        let d0: u64 = h0 * r0 + h1 * s4 + h2 * s3 + h3 * s2 + h4 * s1;
+        let mut d1: u64 = h0 * r1 + h1 * r0 + h2 * s4 + h3 * s3 + h4 * s2;
+        let mut d2: u64 = h0 * r2 + h1 * r1 + h2 * r0 + h3 * s4 + h4 * s3;
+        let mut d3: u64 = h0 * r3 + h1 * r2 + h2 * r1 + h3 * r0 + h4 * s4;
+        let mut d4: u64 = h0 * r4 + h1 * r3 + h2 * r2 + h3 * r1 + h4 * r0;
+
+        // Carry the products back into 26-bit limbs, reducing modulo 2^130 - 5.
+        d1 += d0 >> 26;
+        d2 += d1 >> 26;
+        d3 += d2 >> 26;
+        d4 += d3 >> 26;
+        let h0: u64 = (d0 & POLY1305_LIMB_MASK as u64) + (d4 >> 26) * 5;
+        self.h = [
+            (h0 & POLY1305_LIMB_MASK as u64) as u32,
+            (d1 & POLY1305_LIMB_MASK as u64) as u32 + (h0 >> 26) as u32,
+            (d2 & POLY1305_LIMB_MASK as u64) as u32,
+            (d3 & POLY1305_LIMB_MASK as u64) as u32,
+            (d4 & POLY1305_LIMB_MASK as u64) as u32,
+        ];
+    }
+
+    /// Gets the authentication tag.
+    fn finalize(self) -> [u8; CHACHA20_POLY1305_TAG_SIZE] {
+        let [mut h0, mut h1, mut h2, mut h3, mut h4] = self.h;
+
+        // Fully carry the accumulator.
+        h2 += h1 >> 26;
+        h1 &= POLY1305_LIMB_MASK;
+        h3 += h2 >> 26;
+        h2 &= POLY1305_LIMB_MASK;
+        h4 += h3 >> 26;
+        h3 &= POLY1305_LIMB_MASK;
+        h0 += (h4 >> 26) * 5;
+        h4 &= POLY1305_LIMB_MASK;
+        h1 += h0 >> 26;
+        h0 &= POLY1305_LIMB_MASK;
+
+        // Compute h - p and keep it if it does not underflow.
+        let mut g0: u32 = h0 + 5;
+        let mut g1: u32 = h1 + (g0 >> 26);
+        g0 &= POLY1305_LIMB_MASK;
+        let mut g2: u32 = h2 + (g1 >> 26);
+        g1 &= POLY1305_LIMB_MASK;
+        let mut g3: u32 = h3 + (g2 >> 26);
+        g2 &= POLY1305_LIMB_MASK;
+        let g4: u32 = (h4 + (g3 >> 26)).wrapping_sub(1 << 26);
+        g3 &= POLY1305_LIMB_MASK;
+        let mask: u32 = (g4 >> 31).wrapping_sub(1);
+        h0 = (h0 & !mask) | (g0 & mask);
+        h1 = (h1 & !mask) | (g1 & mask);
+        h2 = (h2 & !mask) | (g2 & mask);
+        h3 = (h3 & !mask) | (g3 & mask);
+        h4 = (h4 & !mask) | (g4 & mask);
+
+        // Add the pad modulo 2^128.
+        let words: [u32; 4] = [
+            h0 | (h1 << 26),
+            (h1 >> 6) | (h2 << 20),
+            (h2 >> 12) | (h3 << 14),
+            (h3 >> 18) | (h4 << 8),
+        ];
+        let mut tag: [u8; CHACHA20_POLY1305_TAG_SIZE] = [0; CHACHA20_POLY1305_TAG_SIZE];
+        let mut carry: u64 = 0;
+        for i in 0..4 {
+            let f: u64 = words[i] as u64 + self.pad[i] as u64 + carry;
+            tag[(4 * i)..(4 * i + 4)].copy_from_slice(&(f as u32).to_le_bytes());
+            carry = f >> 32;
+        }
+        tag
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Loads a little-endian 32-bit word from the start of [buf].
+fn load32(buf: &[u8]) -> u32 {
+    u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]])
+}
+
+/// Runs the ChaCha20 quarter round on words [a], [b], [c] and [d] of [state].
+fn quarter_round(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(12);
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(7);
+}
+
+/// Computes the ChaCha20 block for [counter] (RFC 8439, section 2.3).
+fn chacha20_block(
+    key: &[u8; CHACHA20_POLY1305_KEY_SIZE],
+    counter: u32,
+    nonce: &[u8; CHACHA20_POLY1305_NONCE_SIZE],
+) -> [u8; CHACHA20_BLOCK_SIZE] {
+    let mut initial: [u32; 16] = [
+        0x61707865, 0x3320646e, 0x79622d32, 0x6b206574, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    for i in 0..8 {
+        initial[4 + i] = load32(&key[(4 * i)..]);
+    }
+    initial[12] = counter;
+    for i in 0..3 {
+        initial[13 + i] = load32(&nonce[(4 * i)..]);
+    }
+
+    let mut state: [u32; 16] = initial;
+    for _ in 0..10 {
+        quarter_round(&mut state, 0, 4, 8, 12);
+        quarter_round(&mut state, 1, 5, 9, 13);
+        quarter_round(&mut state, 2, 6, 10, 14);
+        quarter_round(&mut state, 3, 7, 11, 15);
+        quarter_round(&mut state, 0, 5, 10, 15);
+        quarter_round(&mut state, 1, 6, 11, 12);
+        quarter_round(&mut state, 2, 7, 8, 13);
+        quarter_round(&mut state, 3, 4, 9, 14);
+    }
+
+    let mut block: [u8; CHACHA20_BLOCK_SIZE] = [0; CHACHA20_BLOCK_SIZE];
+    for i in 0..16 {
+        block[(4 * i)..(4 * i + 4)].copy_from_slice(&state[i].wrapping_add(initial[i]).to_le_bytes());
+    }
+    block
+}
+
+/// Encrypts or decrypts [buf] in place with the ChaCha20 key stream, starting at block [counter].
+pub fn chacha20_xor(
+    key: &[u8; CHACHA20_POLY1305_KEY_SIZE],
+    counter: u32,
+    nonce: &[u8; CHACHA20_POLY1305_NONCE_SIZE],
+    buf: &mut [u8],
+) {
+    for (i, chunk) in buf.chunks_mut(CHACHA20_BLOCK_SIZE).enumerate() {
+        let block: [u8; CHACHA20_BLOCK_SIZE] = chacha20_block(key, counter.wrapping_add(i as u32), nonce);
+        for (byte, key_byte) in chunk.iter_mut().zip(block.iter()) {
+            *byte ^= key_byte;
+        }
+    }
+}
+
+/// Computes the tag of the AEAD construction (RFC 8439, section 2.8) over [aad] and [ciphertext].
+fn compute_tag(
+    key: &[u8; CHACHA20_POLY1305_KEY_SIZE],
+    nonce: &[u8; CHACHA20_POLY1305_NONCE_SIZE],
+    aad: &[u8],
+    ciphertext: &[u8],
+) -> [u8; CHACHA20_POLY1305_TAG_SIZE] {
+    let block: [u8; CHACHA20_BLOCK_SIZE] = chacha20_block(key, 0, nonce);
+    let mut poly1305: Poly1305 = Poly1305::new(block[..32].try_into().unwrap());
+    poly1305.update_padded(aad);
+    poly1305.update_padded(ciphertext);
+    let mut lengths: [u8; 16] = [0; 16];
+    lengths[..8].copy_from_slice(&(aad.len() as u64).to_le_bytes());
+    lengths[8..].copy_from_slice(&(ciphertext.len() as u64).to_le_bytes());
+    poly1305.block(&lengths, 1 << 24);
+    poly1305.finalize()
+}
+
+/// Encrypts [buf] in place and appends the authentication tag, which also covers [aad].
+pub fn chacha20_poly1305_seal(
+    key: &[u8; CHACHA20_POLY1305_KEY_SIZE],
+    nonce: &[u8; CHACHA20_POLY1305_NONCE_SIZE],
+    aad: &[u8],
+    buf: &mut Vec<u8>,
+) {
+    chacha20_xor(key, 1, nonce, buf);
+    let tag: [u8; CHACHA20_POLY1305_TAG_SIZE] = compute_tag(key, nonce, aad, buf);
+    buf.extend_from_slice(&tag);
+}
+
+/// Checks the authentication tag at the end of [buf] and decrypts the rest of it in place. The tag is removed from
+/// [buf]. On failure, [buf] is left untouched.
+pub fn chacha20_poly1305_open(
+    key: &[u8; CHACHA20_POLY1305_KEY_SIZE],
+    nonce: &[u8; CHACHA20_POLY1305_NONCE_SIZE],
+    aad: &[u8],
+    buf: &mut Vec<u8>,
+) -> Result<(), Fail> {
+    if buf.len() < CHACHA20_POLY1305_TAG_SIZE {
+        let cause: String = format!("ciphertext is too short (len={:?})", buf.len());
+        warn!("chacha20_poly1305_open(): {}", cause);
+        return Err(Fail::new(libc::EBADMSG, &cause));
+    }
+    let len: usize = buf.len() - CHACHA20_POLY1305_TAG_SIZE;
+    let expected: [u8; CHACHA20_POLY1305_TAG_SIZE] = compute_tag(key, nonce, aad, &buf[..len]);
+    // Compare the tags in constant time.
+    let diff: u8 = expected
+        .iter()
+        .zip(buf[len..].iter())
+        .fold(0, |diff, (a, b)| diff | (a ^ b));
+    if diff != 0 {
+        let cause: &str = "authentication tag mismatch";
+        warn!("chacha20_poly1305_open(): {}", cause);
+        return Err(Fail::new(libc::EBADMSG, cause));
+    }
+    buf.truncate(len);
+    chacha20_xor(key, 1, nonce, buf);
+    Ok(())
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        chacha20_poly1305_open,
+        chacha20_poly1305_seal,
+        chacha20_xor,
+        Poly1305,
+    };
+    use crate::runtime::crypto::unhex;
+    use ::anyhow::Result;
+
+    /// Tests ChaCha20 against the example of RFC 8439, section 2.4.2.
+    #[test]
+    fn test_chacha20() -> Result<()> {
+        let key: [u8; 32] = unhex("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f")
+            .try_into()
+            .unwrap();
+        let nonce: [u8; 12] = unhex("000000000000004a00000000").try_into().unwrap();
+        let mut buf: Vec<u8> = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the \
+            future, sunscreen would be it."
+            .to_vec();
+        chacha20_xor(&key, 1, &nonce, &mut buf);
+        crate::ensure_eq!(
+            buf,
+            unhex(
+                "6e2e359a2568f98041ba0728dd0d6981e97e7aec1d4360c20a27afccfd9fae0bf91b65c5524733ab8f593dabcd62b3571639d6\
+                 24e65152ab8f530c359f0861d807ca0dbf500d6a6156a38e088a22b65e52bc514d16ccf806818ce91ab77937365af90bbf74a3\
+                 5be6b40b8eedf2785e42874d"
+            )
+        );
+
+        Ok(())
+    }
+
+    /// Tests Poly1305 against the example of RFC 8439, section 2.5.2.
+    #[test]
+    fn test_poly1305() -> Result<()> {
+        let key: [u8; 32] = unhex("85d6be7857556d337f4452fe42d506a80103808afb0db2fd4abff6af4149f51b")
+            .try_into()
+            .unwrap();
+        let message: &[u8] = b"Cryptographic Forum Research Group";
+        let mut poly1305: Poly1305 = Poly1305::new(&key);
+        poly1305.block(message[0..16].try_into().unwrap(), 1 << 24);
+        poly1305.block(message[16..32].try_into().unwrap(), 1 << 24);
+        // The last block is short, so it is padded with a one and zeros instead of getting the high bit.
+        let mut last: [u8; 16] = [0; 16];
+        last[..2].copy_from_slice(&message[32..]);
+        last[2] = 1;
+        poly1305.block(&last, 0);
+        crate::ensure_eq!(poly1305.finalize().to_vec(), unhex("a8061dc1305136c6c22b8baf0c0127a9"));
+
+        Ok(())
+    }
+
+    /// Tests the AEAD construction against the example of RFC 8439, section 2.8.2.
+    #[test]
+    fn test_chacha20_poly1305() -> Result<()> {
+        let key: [u8; 32] = unhex("808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f")
+            .try_into()
+            .unwrap();
+        let nonce: [u8; 12] = unhex("070000004041424344454647").try_into().unwrap();
+        let aad: Vec<u8> = unhex("50515253c0c1c2c3c4c5c6c7");
+        let plaintext: &[u8] = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the \
+            future, sunscreen would be it.";
+
+        let mut buf: Vec<u8> = plaintext.to_vec();
+        chacha20_poly1305_seal(&key, &nonce, &aad, &mut buf);
+        crate::ensure_eq!(
+            buf,
+            unhex(
+                "d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d63dbea45e8ca9671282fafb69da92728b1a71de\
+                 0a9e060b2905d6a5b67ecd3b3692ddbd7f2d778b8c9803aee328091b58fab324e4fad675945585808b4831d7bc3ff4def08e4b\
+                 7a9de576d26586cec64b61161ae10b594f09e26a7e902ecbd0600691"
+            )
+        );
+
+        chacha20_poly1305_open(&key, &nonce, &aad, &mut buf)?;
+        crate::ensure_eq!(buf.as_slice(), plaintext);
+
+        // Tampering with the ciphertext or the additional data is detected.
+        chacha20_poly1305_seal(&key, &nonce, &aad, &mut buf);
+        buf[0] ^= 1;
+        crate::ensure_eq!(chacha20_poly1305_open(&key, &nonce, &aad, &mut buf).is_err(), true);
+        buf[0] ^= 1;
+        crate::ensure_eq!(chacha20_poly1305_open(&key, &nonce, &[], &mut buf).is_err(), true);
+
+        Ok(())
+    }
+}
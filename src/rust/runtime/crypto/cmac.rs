@@ -0,0 +1,132 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! AES-CMAC (RFC 4493), which the TCP authentication option computes its traffic keys and MACs with (RFC 5926).
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::crypto::aes::{
+    Aes128,
+    AES128_KEY_SIZE,
+    AES_BLOCK_SIZE,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Constant that is folded into subkeys whose most significant bit overflows (RFC 4493, section 2.3).
+const RB: u8 = 0x87;
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Computes the AES-128-CMAC of the concatenation of [data] under [key].
+pub fn aes128_cmac(key: &[u8; AES128_KEY_SIZE], data: &[&[u8]]) -> [u8; AES_BLOCK_SIZE] {
+    let cipher: Aes128 = Aes128::new(key);
+    let mut subkey: [u8; AES_BLOCK_SIZE] = [0; AES_BLOCK_SIZE];
+    cipher.encrypt_block(&mut subkey);
+    let k1: [u8; AES_BLOCK_SIZE] = double(&subkey);
+    let k2: [u8; AES_BLOCK_SIZE] = double(&k1);
+
+    // The last block is held back, as it is masked with a subkey before it is encrypted.
+    let mut state: [u8; AES_BLOCK_SIZE] = [0; AES_BLOCK_SIZE];
+    let mut block: [u8; AES_BLOCK_SIZE] = [0; AES_BLOCK_SIZE];
+    let mut block_len: usize = 0;
+    for byte in data.iter().flat_map(|chunk| chunk.iter()) {
+        if block_len == AES_BLOCK_SIZE {
+            xor(&mut state, &block);
+            cipher.encrypt_block(&mut state);
+            block_len = 0;
+        }
+        block[block_len] = *byte;
+        block_len += 1;
+    }
+
+    if block_len == AES_BLOCK_SIZE {
+        xor(&mut block, &k1);
+    } else {
+        block[block_len] = 0x80;
+        block[(block_len + 1)..].fill(0);
+        xor(&mut block, &k2);
+    }
+    xor(&mut state, &block);
+    cipher.encrypt_block(&mut state);
+    state
+}
+
+/// Multiplies [block] by two in GF(2^128), which derives the subkeys.
+fn double(block: &[u8; AES_BLOCK_SIZE]) -> [u8; AES_BLOCK_SIZE] {
+    let mut doubled: [u8; AES_BLOCK_SIZE] = [0; AES_BLOCK_SIZE];
+    for i in 0..AES_BLOCK_SIZE {
+        let carry: u8 = block.get(i + 1).map_or(0, |next| next >> 7);
+        doubled[i] = (block[i] << 1) | carry;
+    }
+    if block[0] & 0x80 != 0 {
+        doubled[AES_BLOCK_SIZE - 1] ^= RB;
+    }
+    doubled
+}
+
+/// XORs [other] into [block].
+fn xor(block: &mut [u8; AES_BLOCK_SIZE], other: &[u8; AES_BLOCK_SIZE]) {
+    for (byte, other) in block.iter_mut().zip(other.iter()) {
+        *byte ^= other;
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        aes128_cmac,
+        double,
+    };
+    use crate::runtime::crypto::{
+        aes::Aes128,
+        unhex,
+    };
+    use ::anyhow::Result;
+
+    /// Tests the subkeys and the MACs of the examples of RFC 4493, section 4, including messages that are split
+    /// across several slices.
+    #[test]
+    fn test_aes128_cmac() -> Result<()> {
+        let key: [u8; 16] = unhex("2b7e151628aed2a6abf7158809cf4f3c").try_into().unwrap();
+        let mut l: [u8; 16] = [0; 16];
+        Aes128::new(&key).encrypt_block(&mut l);
+        crate::ensure_eq!(l.to_vec(), unhex("7df76b0c1ab899b33e42f047b91b546f"));
+        crate::ensure_eq!(double(&l).to_vec(), unhex("fbeed618357133667c85e08f7236a8de"));
+        crate::ensure_eq!(double(&double(&l)).to_vec(), unhex("f7ddac306ae266ccf90bc11ee46d513b"));
+
+        let message: Vec<u8> = unhex(concat!(
+            "6bc1bee22e409f96e93d7e117393172a",
+            "ae2d8a571e03ac9c9eb76fac45af8e51",
+            "30c81c46a35ce411e5fbc1191a0a52ef",
+            "f69f2445df4f9b17ad2b417be66c3710"
+        ));
+        crate::ensure_eq!(
+            aes128_cmac(&key, &[]).to_vec(),
+            unhex("bb1d6929e95937287fa37d129b756746")
+        );
+        crate::ensure_eq!(
+            aes128_cmac(&key, &[&message[..16]]).to_vec(),
+            unhex("070a16b46b4d4144f79bdd9dd04a287c")
+        );
+        crate::ensure_eq!(
+            aes128_cmac(&key, &[&message[..7], &message[7..40]]).to_vec(),
+            unhex("dfa66747de9ae63030ca32611497c827")
+        );
+        crate::ensure_eq!(
+            aes128_cmac(&key, &[&message[..32], &[], &message[32..]]).to_vec(),
+            unhex("51f0bebf7e3b9d92fc49741779363cfe")
+        );
+        Ok(())
+    }
+}
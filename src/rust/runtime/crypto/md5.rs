@@ -0,0 +1,177 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! MD5 (RFC 1321). MD5 is broken as a general purpose hash, and it is only here because the TCP MD5 signature option
+//! (RFC 2385) is built on it.
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Size of an MD5 digest.
+pub const MD5_DIGEST_SIZE: usize = 16;
+
+/// Size of an MD5 block.
+const MD5_BLOCK_SIZE: usize = 64;
+
+/// Initial state (RFC 1321, section 3.3).
+const H0: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+/// Shift amounts of each round (RFC 1321, section 3.4).
+const S: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20,
+    4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15,
+    21,
+];
+
+/// Round constants, that is, the integer part of 2^32 times the absolute value of the sine of 1 to 64 (RFC 1321,
+/// section 3.4).
+const K: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501, 0x698098d8,
+    0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821, 0xf61e2562, 0xc040b340,
+    0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8, 0x21e1cde6, 0xc33707d6, 0xf4d50d87,
+    0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a, 0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c,
+    0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70, 0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039,
+    0xe6db99e5, 0x1fa27cf8, 0xc4ac5665, 0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92,
+    0xffeff47d, 0x85845dd1, 0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb,
+    0xeb86d391,
+];
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Incremental MD5 hash.
+#[derive(Clone)]
+pub struct Md5 {
+    state: [u32; 4],
+    block: [u8; MD5_BLOCK_SIZE],
+    block_len: usize,
+    total_len: u64,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Md5 {
+    pub fn new() -> Self {
+        Self {
+            state: H0,
+            block: [0; MD5_BLOCK_SIZE],
+            block_len: 0,
+            total_len: 0,
+        }
+    }
+
+    /// Hashes [data] in one go.
+    pub fn digest(data: &[u8]) -> [u8; MD5_DIGEST_SIZE] {
+        let mut hash: Md5 = Md5::new();
+        hash.update(data);
+        hash.finalize()
+    }
+
+    /// Adds [data] to the hash.
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.total_len += data.len() as u64;
+        while !data.is_empty() {
+            let len: usize = (MD5_BLOCK_SIZE - self.block_len).min(data.len());
+            self.block[self.block_len..(self.block_len + len)].copy_from_slice(&data[..len]);
+            self.block_len += len;
+            data = &data[len..];
+            if self.block_len == MD5_BLOCK_SIZE {
+                let block: [u8; MD5_BLOCK_SIZE] = self.block;
+                self.compress(&block);
+                self.block_len = 0;
+            }
+        }
+    }
+
+    /// Gets the digest of the data that was hashed so far.
+    pub fn finalize(mut self) -> [u8; MD5_DIGEST_SIZE] {
+        let bit_len: u64 = self.total_len * 8;
+        self.update(&[0x80]);
+        while self.block_len != MD5_BLOCK_SIZE - 8 {
+            self.update(&[0]);
+        }
+        self.update(&bit_len.to_le_bytes());
+
+        let mut digest: [u8; MD5_DIGEST_SIZE] = [0; MD5_DIGEST_SIZE];
+        for (chunk, word) in digest.chunks_exact_mut(4).zip(self.state.iter()) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        digest
+    }
+
+    /// Runs the compression function over [block].
+    fn compress(&mut self, block: &[u8; MD5_BLOCK_SIZE]) {
+        let mut m: [u32; 16] = [0; 16];
+        for (i, chunk) in block.chunks_exact(4).enumerate() {
+            m[i] = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+
+        let [mut a, mut b, mut c, mut d] = self.state;
+        for i in 0..64 {
+            let (f, g): (u32, usize) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let rotated: u32 = a.wrapping_add(f).wrapping_add(K[i]).wrapping_add(m[g]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(rotated.rotate_left(S[i]));
+        }
+
+        for (word, value) in self.state.iter_mut().zip([a, b, c, d]) {
+            *word = word.wrapping_add(value);
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Default for Md5 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::Md5;
+    use crate::runtime::crypto::unhex;
+    use ::anyhow::Result;
+
+    /// Tests MD5 against the test suite of RFC 1321, including inputs that span two blocks.
+    #[test]
+    fn test_md5() -> Result<()> {
+        crate::ensure_eq!(Md5::digest(b"").to_vec(), unhex("d41d8cd98f00b204e9800998ecf8427e"));
+        crate::ensure_eq!(Md5::digest(b"abc").to_vec(), unhex("900150983cd24fb0d6963f7d28e17f72"));
+        crate::ensure_eq!(
+            Md5::digest(b"abcdefghijklmnopqrstuvwxyz").to_vec(),
+            unhex("c3fcd3d76192e4007dfb496cca67e13b")
+        );
+        crate::ensure_eq!(
+            Md5::digest(b"12345678901234567890123456789012345678901234567890123456789012345678901234567890").to_vec(),
+            unhex("57edf4a22be3c955ac49da2e2107b67a")
+        );
+
+        // Hashing in pieces gives the same digest.
+        let mut hash: Md5 = Md5::new();
+        for byte in b"12345678901234567890123456789012345678901234567890123456789012345678901234567890" {
+            hash.update(&[*byte]);
+        }
+        crate::ensure_eq!(hash.finalize().to_vec(), unhex("57edf4a22be3c955ac49da2e2107b67a"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,30 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Software implementations of the cryptographic primitives that the protocols of the network stack are built on.
+
+//======================================================================================================================
+// Exports
+//======================================================================================================================
+
+pub mod aes;
+pub mod aes_gcm;
+pub mod chacha20poly1305;
+pub mod cmac;
+pub mod md5;
+pub mod runtime;
+pub mod sha256;
+pub mod x25519;
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Decodes a hexadecimal string of a test vector.
+#[cfg(test)]
+pub fn unhex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..(i + 2)], 16).unwrap())
+        .collect()
+}
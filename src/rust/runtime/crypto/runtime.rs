@@ -0,0 +1,221 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    crypto::{
+        aes::{
+            AES128_KEY_SIZE,
+            AES_BLOCK_SIZE,
+        },
+        aes_gcm::{
+            aes128_gcm_open,
+            aes128_gcm_seal,
+        },
+        chacha20poly1305::{
+            chacha20_poly1305_open,
+            chacha20_poly1305_seal,
+            CHACHA20_POLY1305_KEY_SIZE,
+        },
+        cmac::aes128_cmac,
+        sha256::{
+            hmac_sha256,
+            SHA256_DIGEST_SIZE,
+        },
+    },
+    fail::Fail,
+};
+use ::rand::{
+    rngs::OsRng,
+    RngCore,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Size of the nonce of all AEAD algorithms of the runtime.
+pub const AEAD_NONCE_SIZE: usize = 12;
+
+/// Size of the authentication tag of all AEAD algorithms of the runtime.
+pub const AEAD_TAG_SIZE: usize = 16;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// AEAD algorithms of the runtime.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum AeadAlgorithm {
+    Aes128Gcm,
+    ChaCha20Poly1305,
+}
+
+/// Key of an AEAD algorithm.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AeadKey {
+    Aes128Gcm([u8; AES128_KEY_SIZE]),
+    ChaCha20Poly1305([u8; CHACHA20_POLY1305_KEY_SIZE]),
+}
+
+/// Crypto runtime that runs on the CPU and draws random bytes from the operating system.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SoftwareCryptoRuntime;
+
+//======================================================================================================================
+// Traits
+//======================================================================================================================
+
+/// Cryptographic operations of the runtime, which the protocols of the network stack carry out through this trait
+/// instead of calling a specific implementation. This lets a LibOS hand them to a lookaside accelerator, such as a
+/// DPDK cryptodev, or to a third-party library.
+///
+/// Operations may not fail for reasons other than bad input, so implementations that queue requests to a device fall
+/// back to software when the device cannot take them.
+pub trait CryptoRuntime {
+    /// Fills [buf] with cryptographically secure random bytes.
+    fn fill_random(&self, buf: &mut [u8]);
+
+    /// Encrypts [buf] in place under [key] and appends the authentication tag, which also covers [aad].
+    fn aead_seal(&self, key: &AeadKey, nonce: &[u8; AEAD_NONCE_SIZE], aad: &[u8], buf: &mut Vec<u8>);
+
+    /// Checks the authentication tag at the end of [buf] and decrypts the rest of it in place under [key]. The tag is
+    /// removed from [buf].
+    fn aead_open(
+        &self,
+        key: &AeadKey,
+        nonce: &[u8; AEAD_NONCE_SIZE],
+        aad: &[u8],
+        buf: &mut Vec<u8>,
+    ) -> Result<(), Fail>;
+
+    /// Computes the HMAC-SHA256 of the concatenation of [data] under [key].
+    fn hmac_sha256(&self, key: &[u8], data: &[&[u8]]) -> [u8; SHA256_DIGEST_SIZE];
+
+    /// Computes the AES-128-CMAC of the concatenation of [data] under [key].
+    fn aes128_cmac(&self, key: &[u8; AES128_KEY_SIZE], data: &[&[u8]]) -> [u8; AES_BLOCK_SIZE];
+
+    /// Checks whether [algorithm] runs on an accelerator rather than on the CPU, so that callers can decide whether
+    /// batching requests pays off.
+    fn offloads(&self, _algorithm: AeadAlgorithm) -> bool {
+        false
+    }
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl AeadKey {
+    /// Gets the algorithm of the key.
+    pub fn algorithm(&self) -> AeadAlgorithm {
+        match self {
+            AeadKey::Aes128Gcm(_) => AeadAlgorithm::Aes128Gcm,
+            AeadKey::ChaCha20Poly1305(_) => AeadAlgorithm::ChaCha20Poly1305,
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl CryptoRuntime for SoftwareCryptoRuntime {
+    fn fill_random(&self, buf: &mut [u8]) {
+        OsRng.fill_bytes(buf)
+    }
+
+    fn aead_seal(&self, key: &AeadKey, nonce: &[u8; AEAD_NONCE_SIZE], aad: &[u8], buf: &mut Vec<u8>) {
+        match key {
+            AeadKey::Aes128Gcm(key) => aes128_gcm_seal(key, nonce, aad, buf),
+            AeadKey::ChaCha20Poly1305(key) => chacha20_poly1305_seal(key, nonce, aad, buf),
+        }
+    }
+
+    fn aead_open(
+        &self,
+        key: &AeadKey,
+        nonce: &[u8; AEAD_NONCE_SIZE],
+        aad: &[u8],
+        buf: &mut Vec<u8>,
+    ) -> Result<(), Fail> {
+        match key {
+            AeadKey::Aes128Gcm(key) => aes128_gcm_open(key, nonce, aad, buf),
+            AeadKey::ChaCha20Poly1305(key) => chacha20_poly1305_open(key, nonce, aad, buf),
+        }
+    }
+
+    fn hmac_sha256(&self, key: &[u8], data: &[&[u8]]) -> [u8; SHA256_DIGEST_SIZE] {
+        hmac_sha256(key, data)
+    }
+
+    fn aes128_cmac(&self, key: &[u8; AES128_KEY_SIZE], data: &[&[u8]]) -> [u8; AES_BLOCK_SIZE] {
+        aes128_cmac(key, data)
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        AeadAlgorithm,
+        AeadKey,
+        CryptoRuntime,
+        SoftwareCryptoRuntime,
+        AEAD_TAG_SIZE,
+    };
+    use crate::runtime::crypto::unhex;
+    use ::anyhow::Result;
+
+    /// Tests that the software runtime seals and opens with both AEAD algorithms and rejects tampered data.
+    #[test]
+    fn test_software_crypto_runtime_aead() -> Result<()> {
+        let runtime: SoftwareCryptoRuntime = SoftwareCryptoRuntime;
+        for key in [AeadKey::Aes128Gcm([3; 16]), AeadKey::ChaCha20Poly1305([3; 32])] {
+            crate::ensure_eq!(runtime.offloads(key.algorithm()), false);
+            let mut buf: Vec<u8> = b"demikernel".to_vec();
+            runtime.aead_seal(&key, &[5; 12], b"header", &mut buf);
+            crate::ensure_eq!(buf.len(), 10 + AEAD_TAG_SIZE);
+            let sealed: Vec<u8> = buf.clone();
+            runtime.aead_open(&key, &[5; 12], b"header", &mut buf)?;
+            crate::ensure_eq!(&buf[..], b"demikernel");
+
+            let mut buf: Vec<u8> = sealed;
+            buf[0] ^= 1;
+            crate::ensure_eq!(runtime.aead_open(&key, &[5; 12], b"header", &mut buf).is_err(), true);
+        }
+
+        // Test case 2 of the original GCM specification.
+        let mut buf: Vec<u8> = vec![0; 16];
+        runtime.aead_seal(&AeadKey::Aes128Gcm([0; 16]), &[0; 12], &[], &mut buf);
+        crate::ensure_eq!(
+            buf,
+            unhex("0388dace60b6a392f328c2b971b2fe78ab6e47d42cec13bdf53a67b21257bddf")
+        );
+        crate::ensure_eq!(
+            AeadKey::ChaCha20Poly1305([0; 32]).algorithm(),
+            AeadAlgorithm::ChaCha20Poly1305
+        );
+        Ok(())
+    }
+
+    /// Tests the MACs of the software runtime against test case 2 of RFC 4231 and example 2 of RFC 4493.
+    #[test]
+    fn test_software_crypto_runtime_hmac() -> Result<()> {
+        let mac: [u8; 32] = SoftwareCryptoRuntime.hmac_sha256(b"Jefe", &[b"what do ya want ", b"for nothing?"]);
+        crate::ensure_eq!(
+            mac.to_vec(),
+            unhex("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")
+        );
+        let key: [u8; 16] = unhex("2b7e151628aed2a6abf7158809cf4f3c").try_into().unwrap();
+        let mac: [u8; 16] = SoftwareCryptoRuntime.aes128_cmac(&key, &[&unhex("6bc1bee22e409f96e93d7e117393172a")]);
+        crate::ensure_eq!(mac.to_vec(), unhex("070a16b46b4d4144f79bdd9dd04a287c"));
+        Ok(())
+    }
+}
@@ -0,0 +1,262 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Size of a SHA-256 digest.
+pub const SHA256_DIGEST_SIZE: usize = 32;
+
+/// Size of a SHA-256 block.
+const SHA256_BLOCK_SIZE: usize = 64;
+
+/// Initial hash value (FIPS 180-4, section 5.3.3).
+const H0: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// Round constants (FIPS 180-4, section 4.2.2).
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5, 0xd807aa98,
+    0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174, 0xe49b69c1, 0xefbe4786,
+    0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da, 0x983e5152, 0xa831c66d, 0xb00327c8,
+    0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967, 0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13,
+    0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85, 0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819,
+    0xd6990624, 0xf40e3585, 0x106aa070, 0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a,
+    0x5b9cca4f, 0x682e6ff3, 0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7,
+    0xc67178f2,
+];
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Incremental SHA-256 hash. Cloning the hash takes a snapshot of the data that was hashed so far.
+#[derive(Clone)]
+pub struct Sha256 {
+    state: [u32; 8],
+    block: [u8; SHA256_BLOCK_SIZE],
+    block_len: usize,
+    total_len: u64,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Sha256 {
+    pub fn new() -> Self {
+        Self {
+            state: H0,
+            block: [0; SHA256_BLOCK_SIZE],
+            block_len: 0,
+            total_len: 0,
+        }
+    }
+
+    /// Hashes [data] in one go.
+    pub fn digest(data: &[u8]) -> [u8; SHA256_DIGEST_SIZE] {
+        let mut hash: Sha256 = Sha256::new();
+        hash.update(data);
+        hash.finalize()
+    }
+
+    /// Adds [data] to the hash.
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.total_len += data.len() as u64;
+        while !data.is_empty() {
+            let len: usize = (SHA256_BLOCK_SIZE - self.block_len).min(data.len());
+            self.block[self.block_len..(self.block_len + len)].copy_from_slice(&data[..len]);
+            self.block_len += len;
+            data = &data[len..];
+            if self.block_len == SHA256_BLOCK_SIZE {
+                let block: [u8; SHA256_BLOCK_SIZE] = self.block;
+                self.compress(&block);
+                self.block_len = 0;
+            }
+        }
+    }
+
+    /// Gets the digest of the data that was hashed so far.
+    pub fn finalize(mut self) -> [u8; SHA256_DIGEST_SIZE] {
+        let bit_len: u64 = self.total_len * 8;
+        self.update(&[0x80]);
+        while self.block_len != SHA256_BLOCK_SIZE - 8 {
+            self.update(&[0]);
+        }
+        self.update(&bit_len.to_be_bytes());
+
+        let mut digest: [u8; SHA256_DIGEST_SIZE] = [0; SHA256_DIGEST_SIZE];
+        for (chunk, word) in digest.chunks_exact_mut(4).zip(self.state.iter()) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+
+    /// Runs the compression function over [block].
+    fn compress(&mut self, block: &[u8; SHA256_BLOCK_SIZE]) {
+        let mut w: [u32; 64] = [0; 64];
+        for (i, chunk) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        for i in 16..64 {
+            let s0: u32 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1: u32 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+        for i in 0..64 {
+            let s1: u32 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch: u32 = (e & f) ^ (!e & g);
+            let t1: u32 = h
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(K[i])
+                .wrapping_add(w[i]);
+            let s0: u32 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj: u32 = (a & b) ^ (a & c) ^ (b & c);
+            let t2: u32 = s0.wrapping_add(maj);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+
+        for (word, value) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *word = word.wrapping_add(value);
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Default for Sha256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Computes the HMAC-SHA256 (RFC 2104) of the concatenation of [data] under [key].
+pub fn hmac_sha256(key: &[u8], data: &[&[u8]]) -> [u8; SHA256_DIGEST_SIZE] {
+    let mut block: [u8; SHA256_BLOCK_SIZE] = [0; SHA256_BLOCK_SIZE];
+    if key.len() > SHA256_BLOCK_SIZE {
+        block[..SHA256_DIGEST_SIZE].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner: Sha256 = Sha256::new();
+    inner.update(&block.map(|byte| byte ^ 0x36));
+    for chunk in data {
+        inner.update(chunk);
+    }
+    let mut outer: Sha256 = Sha256::new();
+    outer.update(&block.map(|byte| byte ^ 0x5c));
+    outer.update(&inner.finalize());
+    outer.finalize()
+}
+
+/// Extracts a pseudorandom key from [ikm] and [salt] (RFC 5869, section 2.2).
+pub fn hkdf_extract(salt: &[u8], ikm: &[u8]) -> [u8; SHA256_DIGEST_SIZE] {
+    hmac_sha256(salt, &[ikm])
+}
+
+/// Expands the pseudorandom key [prk] into [out], bound to [info] (RFC 5869, section 2.3). [out] must not be longer
+/// than 255 digests.
+pub fn hkdf_expand(prk: &[u8], info: &[u8], out: &mut [u8]) {
+    debug_assert!(out.len() <= 255 * SHA256_DIGEST_SIZE);
+    let mut previous: Option<[u8; SHA256_DIGEST_SIZE]> = None;
+    for (i, chunk) in out.chunks_mut(SHA256_DIGEST_SIZE).enumerate() {
+        let counter: [u8; 1] = [i as u8 + 1];
+        let block: [u8; SHA256_DIGEST_SIZE] = match previous {
+            Some(previous) => hmac_sha256(prk, &[&previous, info, &counter]),
+            None => hmac_sha256(prk, &[info, &counter]),
+        };
+        chunk.copy_from_slice(&block[..chunk.len()]);
+        previous = Some(block);
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        hkdf_expand,
+        hkdf_extract,
+        hmac_sha256,
+        Sha256,
+    };
+    use crate::runtime::crypto::unhex;
+    use ::anyhow::Result;
+
+    /// Tests SHA-256 against the examples of FIPS 180-4, including one that spans two blocks.
+    #[test]
+    fn test_sha256() -> Result<()> {
+        crate::ensure_eq!(
+            Sha256::digest(b"abc").to_vec(),
+            unhex("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        crate::ensure_eq!(
+            Sha256::digest(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq").to_vec(),
+            unhex("248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1")
+        );
+
+        // Hashing in pieces gives the same digest.
+        let mut hash: Sha256 = Sha256::new();
+        for byte in b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq" {
+            hash.update(&[*byte]);
+        }
+        crate::ensure_eq!(
+            hash.finalize().to_vec(),
+            unhex("248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1")
+        );
+
+        Ok(())
+    }
+
+    /// Tests HMAC-SHA256 against RFC 4231, test cases 2 and 6, and HKDF against RFC 5869, test case 1.
+    #[test]
+    fn test_hmac_hkdf() -> Result<()> {
+        crate::ensure_eq!(
+            hmac_sha256(b"Jefe", &[b"what do ya want ", b"for nothing?"]).to_vec(),
+            unhex("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")
+        );
+        crate::ensure_eq!(
+            hmac_sha256(
+                &[0xaa; 131],
+                &[b"Test Using Larger Than Block-Size Key - Hash Key First"]
+            )
+            .to_vec(),
+            unhex("60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54")
+        );
+
+        let prk: [u8; 32] = hkdf_extract(&unhex("000102030405060708090a0b0c"), &[0x0b; 22]);
+        crate::ensure_eq!(
+            prk.to_vec(),
+            unhex("077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5")
+        );
+        let mut okm: [u8; 42] = [0; 42];
+        hkdf_expand(&prk, &unhex("f0f1f2f3f4f5f6f7f8f9"), &mut okm);
+        crate::ensure_eq!(
+            okm.to_vec(),
+            unhex("3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865")
+        );
+
+        Ok(())
+    }
+}
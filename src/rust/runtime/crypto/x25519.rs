@@ -0,0 +1,258 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Size of an X25519 scalar, point or shared secret.
+pub const X25519_KEY_SIZE: usize = 32;
+
+/// The base point of Curve25519.
+pub const X25519_BASE_POINT: [u8; X25519_KEY_SIZE] = {
+    let mut point: [u8; X25519_KEY_SIZE] = [0; X25519_KEY_SIZE];
+    point[0] = 9;
+    point
+};
+
+/// Mask of a 51-bit limb of a field element.
+const LIMB_MASK: u64 = (1 << 51) - 1;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Element of the field of integers modulo 2^255 - 19, kept in five 51-bit limbs. Limbs may grow a few bits past 51
+/// between reductions.
+#[derive(Clone, Copy)]
+struct FieldElement([u64; 5]);
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl FieldElement {
+    const ONE: FieldElement = FieldElement([1, 0, 0, 0, 0]);
+    const ZERO: FieldElement = FieldElement([0; 5]);
+
+    /// Decodes a little-endian field element. The most significant bit is ignored (RFC 7748, section 5).
+    fn from_bytes(bytes: &[u8; X25519_KEY_SIZE]) -> Self {
+        let load = |offset: usize| -> u64 { u64::from_le_bytes(bytes[offset..(offset + 8)].try_into().unwrap()) };
+        Self([
+            load(0) & LIMB_MASK,
+            (load(6) >> 3) & LIMB_MASK,
+            (load(12) >> 6) & LIMB_MASK,
+            (load(19) >> 1) & LIMB_MASK,
+            (load(24) >> 12) & LIMB_MASK,
+        ])
+    }
+
+    /// Encodes the canonical form of this field element.
+    fn to_bytes(self) -> [u8; X25519_KEY_SIZE] {
+        // Carry twice, so that every limb is below 2^51 and the element is below 2^255.
+        let mut limbs: [u64; 5] = self.0;
+        for _ in 0..2 {
+            for i in 0..4 {
+                limbs[i + 1] += limbs[i] >> 51;
+                limbs[i] &= LIMB_MASK;
+            }
+            limbs[0] += (limbs[4] >> 51) * 19;
+            limbs[4] &= LIMB_MASK;
+        }
+
+        // Subtract p if the element is not smaller than it. Here q is either 0 or 1.
+        let mut q: u64 = (limbs[0] + 19) >> 51;
+        for limb in &limbs[1..] {
+            q = (limb + q) >> 51;
+        }
+        limbs[0] += 19 * q;
+        for i in 0..4 {
+            limbs[i + 1] += limbs[i] >> 51;
+            limbs[i] &= LIMB_MASK;
+        }
+        limbs[4] &= LIMB_MASK;
+
+        let words: [u64; 4] = [
+            limbs[0] | (limbs[1] << 51),
+            (limbs[1] >> 13) | (limbs[2] << 38),
+            (limbs[2] >> 26) | (limbs[3] << 25),
+            (limbs[3] >> 39) | (limbs[4] << 12),
+        ];
+        let mut bytes: [u8; X25519_KEY_SIZE] = [0; X25519_KEY_SIZE];
+        for (chunk, word) in bytes.chunks_exact_mut(8).zip(words.iter()) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Carries wide limbs back into 51-bit limbs.
+    fn carry(mut limbs: [u128; 5]) -> Self {
+        for i in 0..4 {
+            limbs[i + 1] += limbs[i] >> 51;
+            limbs[i] &= LIMB_MASK as u128;
+        }
+        limbs[0] += (limbs[4] >> 51) * 19;
+        limbs[4] &= LIMB_MASK as u128;
+        limbs[1] += limbs[0] >> 51;
+        limbs[0] &= LIMB_MASK as u128;
+        Self(limbs.map(|limb| limb as u64))
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        let [a, b] = [self.0, other.0];
+        Self([a[0] + b[0], a[1] + b[1], a[2] + b[2], a[3] + b[3], a[4] + b[4]])
+    }
+
+    /// Subtracts [other], which must be reduced, by adding 2p first so that limbs do not underflow.
+    fn sub(&self, other: &Self) -> Self {
+        let [a, b] = [self.0, other.0];
+        Self::carry([
+            (a[0] + 0xfffffffffffda - b[0]) as u128,
+            (a[1] + 0xffffffffffffe - b[1]) as u128,
+            (a[2] + 0xffffffffffffe - b[2]) as u128,
+            (a[3] + 0xffffffffffffe - b[3]) as u128,
+            (a[4] + 0xffffffffffffe - b[4]) as u128,
+        ])
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        let a: [u128; 5] = self.0.map(|limb| limb as u128);
+        let b: [u128; 5] = other.0.map(|limb| limb as u128);
+        // Products that overflow 2^255 wrap around multiplied by 19.
+        let b19: [u128; 5] = b.map(|limb| limb * 19);
+        Self::carry([
+            a[0] * b[0] + a[1] * b19[4] + a[2] * b19[3] + a[3] * b19[2] + a[4] * b19[1],
+            a[0] * b[1] + a[1] * b[0] + a[2] * b19[4] + a[3] * b19[3] + a[4] * b19[2],
+            a[0] * b[2] + a[1] * b[1] + a[2] * b[0] + a[3] * b19[4] + a[4] * b19[3],
+            a[0] * b[3] + a[1] * b[2] + a[2] * b[1] + a[3] * b[0] + a[4] * b19[4],
+            a[0] * b[4] + a[1] * b[3] + a[2] * b[2] + a[3] * b[1] + a[4] * b[0],
+        ])
+    }
+
+    fn square(&self) -> Self {
+        self.mul(self)
+    }
+
+    fn mul_small(&self, factor: u64) -> Self {
+        Self::carry(self.0.map(|limb| limb as u128 * factor as u128))
+    }
+
+    /// Computes the multiplicative inverse as this element raised to p - 2.
+    fn invert(&self) -> Self {
+        // p - 2 = 2^255 - 21, whose bits are all set except for bits 2 and 4.
+        let mut result: Self = Self::ONE;
+        for bit in (0..255).rev() {
+            result = result.square();
+            if bit != 2 && bit != 4 {
+                result = result.mul(self);
+            }
+        }
+        result
+    }
+
+    /// Swaps [a] and [b] if [swap] is one, in constant time.
+    fn conditional_swap(a: &mut Self, b: &mut Self, swap: u64) {
+        let mask: u64 = 0u64.wrapping_sub(swap);
+        for i in 0..5 {
+            let t: u64 = mask & (a.0[i] ^ b.0[i]);
+            a.0[i] ^= t;
+            b.0[i] ^= t;
+        }
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Computes the X25519 function (RFC 7748, section 5) of [scalar] and the u-coordinate [point].
+pub fn x25519(scalar: &[u8; X25519_KEY_SIZE], point: &[u8; X25519_KEY_SIZE]) -> [u8; X25519_KEY_SIZE] {
+    let mut k: [u8; X25519_KEY_SIZE] = *scalar;
+    k[0] &= 248;
+    k[31] &= 127;
+    k[31] |= 64;
+
+    let x1: FieldElement = FieldElement::from_bytes(point);
+    let mut x2: FieldElement = FieldElement::ONE;
+    let mut z2: FieldElement = FieldElement::ZERO;
+    let mut x3: FieldElement = x1;
+    let mut z3: FieldElement = FieldElement::ONE;
+    let mut swap: u64 = 0;
+
+    // Montgomery ladder.
+    for t in (0..255).rev() {
+        let bit: u64 = ((k[t / 8] >> (t % 8)) & 1) as u64;
+        swap ^= bit;
+        FieldElement::conditional_swap(&mut x2, &mut x3, swap);
+        FieldElement::conditional_swap(&mut z2, &mut z3, swap);
+        swap = bit;
+
+        let a: FieldElement = x2.add(&z2);
+        let aa: FieldElement = a.square();
+        let b: FieldElement = x2.sub(&z2);
+        let bb: FieldElement = b.square();
+        let e: FieldElement = aa.sub(&bb);
+        let c: FieldElement = x3.add(&z3);
+        let d: FieldElement = x3.sub(&z3);
+        let da: FieldElement = d.mul(&a);
+        let cb: FieldElement = c.mul(&b);
+        x3 = da.add(&cb).square();
+        z3 = x1.mul(&da.sub(&cb).square());
+        x2 = aa.mul(&bb);
+        z2 = e.mul(&aa.add(&e.mul_small(121665)));
+    }
+    FieldElement::conditional_swap(&mut x2, &mut x3, swap);
+    FieldElement::conditional_swap(&mut z2, &mut z3, swap);
+
+    x2.mul(&z2.invert()).to_bytes()
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        x25519,
+        X25519_BASE_POINT,
+    };
+    use crate::runtime::crypto::unhex;
+    use ::anyhow::Result;
+
+    /// Tests X25519 against the examples of RFC 7748, sections 5.2 and 6.1.
+    #[test]
+    fn test_x25519() -> Result<()> {
+        let scalar: [u8; 32] = unhex("a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4")
+            .try_into()
+            .unwrap();
+        let point: [u8; 32] = unhex("e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c")
+            .try_into()
+            .unwrap();
+        crate::ensure_eq!(
+            x25519(&scalar, &point).to_vec(),
+            unhex("c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552")
+        );
+
+        // Both sides of a Diffie-Hellman exchange agree on the shared secret.
+        let alice: [u8; 32] = unhex("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a")
+            .try_into()
+            .unwrap();
+        let bob: [u8; 32] = unhex("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb")
+            .try_into()
+            .unwrap();
+        let alice_public: [u8; 32] = x25519(&alice, &X25519_BASE_POINT);
+        crate::ensure_eq!(
+            alice_public.to_vec(),
+            unhex("8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a")
+        );
+        let bob_public: [u8; 32] = x25519(&bob, &X25519_BASE_POINT);
+        crate::ensure_eq!(x25519(&alice, &bob_public), x25519(&bob, &alice_public));
+        crate::ensure_eq!(
+            x25519(&alice, &bob_public).to_vec(),
+            unhex("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742")
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,249 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! This module lets coroutines sleep until a file descriptor becomes readable. This is used for doorbells, so that a
+//! blocked wait may put the calling thread to sleep instead of busy-polling when there is nothing else to do. Much like
+//! the global timer, file descriptors are only checked when the runtime explicitly polls them.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::SharedObject;
+use ::std::{
+    future::Future,
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    os::fd::RawFd,
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+        Waker,
+    },
+    time::Duration,
+};
+
+//======================================================================================================================
+// Thread local variable
+//======================================================================================================================
+
+thread_local! {
+/// File descriptors that coroutines are currently waiting on.
+static THREAD_FDS: SharedFdWaiters = SharedFdWaiters::default();
+}
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+struct FdWaiter {
+    id: u64,
+    fd: RawFd,
+    waker: Waker,
+}
+
+/// Coroutines waiting for file descriptors to become readable.
+#[derive(Default)]
+pub struct FdWaiters {
+    waiters: Vec<FdWaiter>,
+    // Monotonically increasing identifier for waiters.
+    last_id: u64,
+}
+
+#[derive(Clone)]
+pub struct SharedFdWaiters(SharedObject<FdWaiters>);
+
+/// A future that completes once a file descriptor becomes readable.
+pub struct ReadableFuture {
+    fd: RawFd,
+    id: Option<u64>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl SharedFdWaiters {
+    fn add_waiter(&mut self, fd: RawFd, waker: Waker) -> u64 {
+        let id: u64 = self.last_id;
+        self.last_id += 1;
+        self.waiters.push(FdWaiter { id, fd, waker });
+        id
+    }
+
+    fn remove_waiter(&mut self, id: u64) {
+        self.waiters.retain(|waiter| waiter.id != id);
+    }
+
+    fn poll(&mut self, timeout: Duration) {
+        if self.waiters.is_empty() {
+            return;
+        }
+
+        let mut pollfds: Vec<libc::pollfd> = self
+            .waiters
+            .iter()
+            .map(|waiter| libc::pollfd {
+                fd: waiter.fd,
+                events: libc::POLLIN,
+                revents: 0,
+            })
+            .collect();
+        let timeout_ms: libc::c_int = timeout.as_millis().min(libc::c_int::MAX as u128) as libc::c_int;
+        let ret: libc::c_int = unsafe { libc::poll(pollfds.as_mut_ptr(), pollfds.len() as libc::nfds_t, timeout_ms) };
+        if ret <= 0 {
+            // Either nothing is readable or we were interrupted. Either way, waiters will be polled again.
+            return;
+        }
+
+        // Wake up waiters whose file descriptors are readable. They register themselves again if needed.
+        let mut i: usize = 0;
+        self.waiters.retain(|waiter| {
+            let ready: bool = pollfds[i].revents != 0;
+            i += 1;
+            if ready {
+                waiter.waker.wake_by_ref();
+            }
+            !ready
+        });
+    }
+}
+
+/// Checks whether any coroutine is waiting on a file descriptor.
+pub fn global_has_waiters() -> bool {
+    THREAD_FDS.with(|s| !s.waiters.is_empty())
+}
+
+/// Returns the file descriptors that coroutines are waiting on.
+pub fn global_waiter_fds() -> Vec<RawFd> {
+    THREAD_FDS.with(|s| s.waiters.iter().map(|waiter| waiter.fd).collect())
+}
+
+/// Wakes up coroutines whose file descriptors are readable, sleeping up to [timeout] if none is.
+pub fn global_poll(timeout: Duration) {
+    THREAD_FDS.with(|s| s.clone().poll(timeout))
+}
+
+/// Blocks until [fd] becomes readable. This does not consume any data from [fd].
+pub async fn wait_readable(fd: RawFd) {
+    ReadableFuture { fd, id: None }.await
+}
+
+/// Checks whether [fd] is readable, without blocking.
+fn is_readable(fd: RawFd) -> bool {
+    let mut pollfd: libc::pollfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    unsafe { libc::poll(&mut pollfd, 1, 0) > 0 }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Default for SharedFdWaiters {
+    fn default() -> Self {
+        Self(SharedObject::<FdWaiters>::new(FdWaiters::default()))
+    }
+}
+
+impl Deref for SharedFdWaiters {
+    type Target = FdWaiters;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for SharedFdWaiters {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+impl Future for ReadableFuture {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        let self_: &mut Self = self.get_mut();
+        if let Some(id) = self_.id.take() {
+            THREAD_FDS.with(|s| s.clone().remove_waiter(id));
+        }
+        if is_readable(self_.fd) {
+            Poll::Ready(())
+        } else {
+            self_.id = Some(THREAD_FDS.with(|s| s.clone().add_waiter(self_.fd, context.waker().clone())));
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for ReadableFuture {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            THREAD_FDS.with(|s| s.clone().remove_waiter(id));
+        }
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        global_has_waiters,
+        global_poll,
+        wait_readable,
+    };
+    use ::anyhow::Result;
+    use ::futures::{
+        task::noop_waker_ref,
+        FutureExt,
+    };
+    use ::std::{
+        future::Future,
+        pin::Pin,
+        task::{
+            Context,
+            Poll,
+        },
+        time::Duration,
+    };
+
+    #[test]
+    fn wait_readable_pipe() -> Result<()> {
+        let mut fds: [libc::c_int; 2] = [0; 2];
+        crate::ensure_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let mut ctx: Context = Context::from_waker(noop_waker_ref());
+        let mut fut: Pin<Box<dyn Future<Output = ()>>> = Box::pin(wait_readable(fds[0]).fuse());
+
+        // Nothing to read, thus the future should register itself and block.
+        crate::ensure_eq!(Future::poll(fut.as_mut(), &mut ctx), Poll::Pending);
+        crate::ensure_eq!(global_has_waiters(), true);
+        global_poll(Duration::ZERO);
+        crate::ensure_eq!(global_has_waiters(), true);
+
+        // Write to the pipe and check that the waiter is woken up.
+        let byte: u8 = 1;
+        crate::ensure_eq!(
+            unsafe { libc::write(fds[1], &byte as *const u8 as *const libc::c_void, 1) },
+            1
+        );
+        global_poll(Duration::from_secs(1));
+        crate::ensure_eq!(global_has_waiters(), false);
+        crate::ensure_eq!(Future::poll(fut.as_mut(), &mut ctx), Poll::Ready(()));
+
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+        Ok(())
+    }
+}
@@ -5,8 +5,68 @@
 // Imports
 //==============================================================================
 
-use ::flexi_logger::Logger;
-use ::std::sync::Once;
+use crate::runtime::fail::Fail;
+use ::flexi_logger::{
+    writers::LogWriter,
+    DeferredNow,
+    LogSpecification,
+    Logger,
+    LoggerHandle,
+};
+use ::log::Record;
+use ::std::{
+    fmt,
+    io::{
+        self,
+        Write,
+    },
+    panic,
+    ptr,
+    str::FromStr,
+    sync::{
+        atomic::{
+            AtomicPtr,
+            AtomicU8,
+            AtomicUsize,
+            Ordering,
+        },
+        Mutex,
+        Once,
+        OnceLock,
+    },
+};
+
+//==============================================================================
+// Constants
+//==============================================================================
+
+/// Number of log records that the flight recorder keeps. Older records are overwritten.
+const LOG_RING_CAPACITY: usize = 4096;
+
+//==============================================================================
+// Structures
+//==============================================================================
+
+/// Where log records go.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum LogSink {
+    /// Records are written to the standard error right away.
+    Stderr = 0,
+    /// Records are kept in an in-memory flight recorder, which is dumped on panic or with [dump], so that logging does
+    /// not perturb the latency of the data path.
+    Ring = 1,
+}
+
+/// Ring of the most recent log records. Writers claim a slot and swap their record into it without taking a lock, and
+/// whoever swaps a record out of a slot owns it.
+struct LogRing {
+    slots: Box<[AtomicPtr<String>]>,
+    next: AtomicUsize,
+}
+
+/// Writer that hands log records over to the current sink.
+struct SinkWriter;
 
 //==============================================================================
 // Static Variables
@@ -15,6 +75,18 @@ use ::std::sync::Once;
 /// Guardian to the logging initialize function.
 static INIT_LOG: Once = Once::new();
 
+/// Handle to the logger, which lets us change the logging level at runtime.
+static LOGGER: OnceLock<LoggerHandle> = OnceLock::new();
+
+/// Current logging level, as it was last set.
+static LOG_LEVEL: Mutex<String> = Mutex::new(String::new());
+
+/// Current sink of log records.
+static LOG_SINK: AtomicU8 = AtomicU8::new(LogSink::Stderr as u8);
+
+/// Flight recorder of log records.
+static LOG_RING: OnceLock<LogRing> = OnceLock::new();
+
 //==============================================================================
 // Standalone Functions
 //==============================================================================
@@ -22,6 +94,213 @@ static INIT_LOG: Once = Once::new();
 /// Initializes logging features.
 pub fn initialize() {
     INIT_LOG.call_once(|| {
-        Logger::try_with_env().unwrap().start().unwrap();
+        let spec: LogSpecification = LogSpecification::env().unwrap();
+        set_current_level(spec.to_string());
+        let handle: LoggerHandle = Logger::with(spec).log_to_writer(Box::new(SinkWriter)).start().unwrap();
+        let _ = LOGGER.set(handle);
+
+        // Dump the flight recorder before the panic message, so that the records that led to the panic are not lost.
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if get_sink() == LogSink::Ring {
+                let _ = dump(&mut io::stderr());
+            }
+            hook(info)
+        }));
     });
 }
+
+/// Changes where log records go. [sink] is either "stderr" or "ring".
+pub fn set_sink(sink: &str) -> Result<(), Fail> {
+    let sink: LogSink = match LogSink::from_str(sink) {
+        Ok(sink) => sink,
+        Err(()) => {
+            let cause: String = format!("invalid logging sink (sink={:?})", sink);
+            error!("set_sink(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        },
+    };
+    LOG_SINK.store(sink as u8, Ordering::Release);
+    Ok(())
+}
+
+/// Returns where log records go.
+pub fn get_sink() -> LogSink {
+    match LOG_SINK.load(Ordering::Acquire) {
+        0 => LogSink::Stderr,
+        _ => LogSink::Ring,
+    }
+}
+
+/// Writes the records of the flight recorder to [out], from the oldest to the newest, and empties it. Returns the
+/// number of records that were written.
+pub fn dump(out: &mut dyn Write) -> io::Result<usize> {
+    let records: Vec<String> = log_ring().drain();
+    for record in records.iter() {
+        out.write_all(record.as_bytes())?;
+    }
+    out.flush()?;
+    Ok(records.len())
+}
+
+/// Changes the logging level. [spec] follows the syntax of the RUST_LOG environment variable (e.g. "info" or
+/// "warn,demikernel::inetstack=trace").
+pub fn set_level(spec: &str) -> Result<(), Fail> {
+    let spec: LogSpecification = match LogSpecification::parse(spec) {
+        Ok(spec) => spec,
+        Err(e) => {
+            let cause: String = format!("invalid logging level (error={:?})", e);
+            error!("set_level(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        },
+    };
+    match LOGGER.get() {
+        Some(handle) => {
+            set_current_level(spec.to_string());
+            handle.set_new_spec(spec);
+            Ok(())
+        },
+        None => {
+            let cause: &str = "logging is not initialized";
+            error!("set_level(): {}", cause);
+            Err(Fail::new(libc::ENOSYS, cause))
+        },
+    }
+}
+
+/// Returns the current logging level.
+pub fn get_level() -> String {
+    match LOG_LEVEL.lock() {
+        Ok(level) => level.clone(),
+        Err(e) => e.into_inner().clone(),
+    }
+}
+
+fn set_current_level(level: String) {
+    match LOG_LEVEL.lock() {
+        Ok(mut current) => *current = level,
+        Err(e) => *e.into_inner() = level,
+    }
+}
+
+fn log_ring() -> &'static LogRing {
+    LOG_RING.get_or_init(|| LogRing::new(LOG_RING_CAPACITY))
+}
+
+//==============================================================================
+// Associate Functions
+//==============================================================================
+
+impl LogRing {
+    fn new(capacity: usize) -> Self {
+        Self {
+            slots: (0..capacity).map(|_| AtomicPtr::new(ptr::null_mut())).collect(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Stores [record], overwriting the oldest one if the ring is full.
+    fn push(&self, record: String) {
+        let index: usize = self.next.fetch_add(1, Ordering::Relaxed) % self.slots.len();
+        let old: *mut String = self.slots[index].swap(Box::into_raw(Box::new(record)), Ordering::AcqRel);
+        if !old.is_null() {
+            // Safety: the record was swapped out of the ring, so nobody else owns it.
+            drop(unsafe { Box::from_raw(old) });
+        }
+    }
+
+    /// Takes the records out of the ring, from the oldest to the newest.
+    fn drain(&self) -> Vec<String> {
+        let start: usize = self.next.load(Ordering::Relaxed);
+        let mut records: Vec<String> = Vec::new();
+        for i in 0..self.slots.len() {
+            let index: usize = (start + i) % self.slots.len();
+            let record: *mut String = self.slots[index].swap(ptr::null_mut(), Ordering::AcqRel);
+            if !record.is_null() {
+                // Safety: the record was swapped out of the ring, so nobody else owns it.
+                records.push(*unsafe { Box::from_raw(record) });
+            }
+        }
+        records
+    }
+}
+
+//==============================================================================
+// Trait Implementations
+//==============================================================================
+
+impl Drop for LogRing {
+    fn drop(&mut self) {
+        self.drain();
+    }
+}
+
+impl LogWriter for SinkWriter {
+    fn write(&self, now: &mut DeferredNow, record: &Record) -> io::Result<()> {
+        match get_sink() {
+            LogSink::Stderr => {
+                let mut stderr = io::stderr().lock();
+                flexi_logger::default_format(&mut stderr, now, record)?;
+                writeln!(stderr)
+            },
+            LogSink::Ring => {
+                let mut line: Vec<u8> = Vec::new();
+                flexi_logger::default_format(&mut line, now, record)?;
+                writeln!(line)?;
+                log_ring().push(String::from_utf8_lossy(&line).into_owned());
+                Ok(())
+            },
+        }
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+impl FromStr for LogSink {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stderr" => Ok(LogSink::Stderr),
+            "ring" => Ok(LogSink::Ring),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for LogSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogSink::Stderr => write!(f, "stderr"),
+            LogSink::Ring => write!(f, "ring"),
+        }
+    }
+}
+
+//==============================================================================
+// Unit Tests
+//==============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::LogRing;
+    use ::anyhow::Result;
+
+    /// Tests that the flight recorder keeps the most recent records, in order, and is emptied once it is drained.
+    #[test]
+    fn test_log_ring_wraps_around() -> Result<()> {
+        let ring: LogRing = LogRing::new(4);
+        for i in 0..6 {
+            ring.push(format!("record {}", i));
+        }
+        crate::ensure_eq!(ring.drain(), vec!["record 2", "record 3", "record 4", "record 5"]);
+        crate::ensure_eq!(ring.drain().is_empty(), true);
+
+        ring.push("record 6".to_string());
+        crate::ensure_eq!(ring.drain(), vec!["record 6"]);
+
+        Ok(())
+    }
+}
@@ -682,6 +682,17 @@ impl DemiBuffer {
         }
     }
 
+    /// Returns the contained MBuf pointer, without consuming the `DemiBuffer`.
+    // The reference count is not affected. The pointer is valid for as long as the DemiBuffer exists.
+    #[cfg(feature = "libdpdk")]
+    pub fn get_mbuf(&self) -> Option<*mut rte_mbuf> {
+        if self.get_tag() == Tag::Dpdk {
+            Some(self.as_mbuf())
+        } else {
+            None
+        }
+    }
+
     // ------------------
     // Internal Functions
     // ------------------
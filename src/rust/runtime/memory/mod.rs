@@ -58,6 +58,7 @@ pub trait MemoryRuntime {
             sga_numsegs: 1,
             sga_segs: [sga_seg],
             sga_addr: unsafe { mem::zeroed() },
+            sga_ancillary: unsafe { mem::zeroed() },
         })
     }
 
@@ -93,6 +94,7 @@ pub trait MemoryRuntime {
             sga_numsegs: 1,
             sga_segs: [sga_seg],
             sga_addr: unsafe { mem::zeroed() },
+            sga_ancillary: unsafe { mem::zeroed() },
         })
     }
 
@@ -5,7 +5,10 @@
 // Exports
 //======================================================================================================================
 
+pub mod affinity;
+pub mod budget;
 pub mod condition_variable;
+pub mod crypto;
 pub mod fail;
 pub mod limits;
 pub mod logging;
@@ -13,8 +16,15 @@ pub mod memory;
 pub mod network;
 pub mod queue;
 pub mod scheduler;
+pub mod teardown;
+pub mod tracing;
 pub mod types;
+pub mod wait;
 pub use condition_variable::SharedConditionVariable;
+#[cfg(target_os = "linux")]
+mod fdwait;
+#[cfg(target_os = "linux")]
+mod notifier;
 mod poll;
 mod timer;
 pub use queue::{
@@ -36,13 +46,28 @@ pub use dpdk_rs as libdpdk;
 //======================================================================================================================
 
 use crate::runtime::network::{
+    admission::SharedAdmissionControl,
+    drops::{
+        DropReason,
+        DropStats,
+    },
     ephemeral::EphemeralPorts,
+    flows::SharedFlowTable,
+    memory::SharedMemoryPool,
     socket::SocketId,
     NetworkQueueTable,
 };
 
 #[cfg(feature = "profiler")]
 use crate::coroutine_timer;
+#[cfg(feature = "tracing")]
+use crate::runtime::tracing::{
+    Instrumented,
+    Span,
+};
+
+#[cfg(target_os = "linux")]
+use crate::runtime::notifier::Notifier;
 
 use crate::{
     expect_some,
@@ -55,8 +80,12 @@ use crate::{
         },
         scheduler::{
             SharedScheduler,
+            Task,
+            TaskPriority,
+            TaskStats,
             TaskWithResult,
         },
+        wait::WaitConfig,
     },
 };
 use ::futures::{
@@ -93,6 +122,9 @@ use std::pin::Pin;
 const TIMER_RESOLUTION: usize = 1024;
 const TIMER_FINER_RESOLUTION: usize = 16;
 
+/// Queue descriptor reported by application coroutines, which are not bound to any queue. This is -1 in C.
+pub const APP_TASK_QDESC: u32 = QDesc::MAX;
+
 //======================================================================================================================
 // Structures
 //======================================================================================================================
@@ -107,10 +139,26 @@ pub struct DemiRuntime {
     ephemeral_ports: EphemeralPorts,
     /// Shared table for mapping from underlying transport identifiers to queue descriptors.
     network_table: NetworkQueueTable,
+    /// Number of incoming packets that the network stack dropped, for each reason.
+    drop_stats: DropStats,
+    /// Memory that the sockets of the network stack buffer.
+    memory_pool: SharedMemoryPool,
+    /// Admission control of inbound connection requests and datagrams.
+    admission: SharedAdmissionControl,
+    flows: SharedFlowTable,
     /// Number of iterations that we have polled since advancing the clock.
     ts_iters: usize,
     /// Tasks that have been completed and removed from the
     completed_tasks: HashMap<QToken, (QDesc, OperationResult)>,
+    /// Queue descriptors of the I/O operations that are still running, so that they can be cancelled.
+    pending_operations: HashMap<QToken, QDesc>,
+    /// Pollable file descriptor for external event loops. Only created if the application asks for it.
+    #[cfg(target_os = "linux")]
+    notifier: Option<Notifier>,
+    /// What waits do once no coroutine is ready to run.
+    wait_config: WaitConfig,
+    /// Since when no coroutine has been ready to run, if that is the case.
+    idle_since: Option<Instant>,
 }
 
 #[derive(Clone)]
@@ -145,18 +193,94 @@ impl SharedDemiRuntime {
             scheduler: SharedScheduler::default(),
             ephemeral_ports: EphemeralPorts::default(),
             network_table: NetworkQueueTable::default(),
+            drop_stats: DropStats::default(),
+            memory_pool: SharedMemoryPool::default(),
+            admission: SharedAdmissionControl::default(),
+            flows: SharedFlowTable::default(),
             ts_iters: 0,
             completed_tasks: HashMap::<QToken, (QDesc, OperationResult)>::new(),
+            pending_operations: HashMap::<QToken, QDesc>::new(),
+            #[cfg(target_os = "linux")]
+            notifier: None,
+            wait_config: WaitConfig::default(),
+            idle_since: None,
         }))
     }
 
-    /// Inserts the `coroutine` named `task_name` into the scheduler.
+    /// Inserts the `coroutine` named `task_name`, which runs an I/O operation on `qd`, into the scheduler.
     pub fn insert_io_coroutine<F: FusedFuture<Output = (QDesc, OperationResult)> + 'static>(
         &mut self,
         task_name: &'static str,
+        qd: QDesc,
         coroutine: Pin<Box<F>>,
     ) -> Result<QToken, Fail> {
-        self.insert_coroutine(task_name, coroutine)
+        // Trace the operation under its queue descriptor and queue token, which is only known once it is inserted.
+        #[cfg(feature = "tracing")]
+        let span: Span = Span::new("op");
+        #[cfg(feature = "tracing")]
+        let coroutine = {
+            span.record("task", &task_name);
+            span.record("qd", &qd);
+            Box::pin(Instrumented::new(coroutine, span.clone()))
+        };
+        let qt: QToken = self.insert_coroutine(task_name, coroutine)?;
+        #[cfg(feature = "tracing")]
+        span.record("qt", &qt);
+        self.pending_operations.insert(qt, qd);
+        Ok(qt)
+    }
+
+    /// Inserts the application `coroutine` named `task_name` into the scheduler. Application coroutines are not bound to
+    /// any queue, but they complete through their queue token like I/O operations do, so they can be waited on and
+    /// cancelled. Their results carry [APP_TASK_QDESC] as queue descriptor.
+    pub fn insert_app_coroutine<F: FusedFuture<Output = Result<(), Fail>> + 'static>(
+        &mut self,
+        task_name: &'static str,
+        coroutine: Pin<Box<F>>,
+    ) -> Result<QToken, Fail> {
+        let qd: QDesc = QDesc::from(APP_TASK_QDESC);
+        let coroutine = Box::pin(
+            async move {
+                match coroutine.await {
+                    Ok(()) => (qd, OperationResult::Task),
+                    Err(e) => (qd, OperationResult::Failed(e)),
+                }
+            }
+            .fuse(),
+        );
+        self.insert_io_coroutine(task_name, qd, coroutine)
+    }
+
+    /// Inserts the `coroutine` named `task_name`, which runs an I/O operation on `qd`, into the scheduler. If the
+    /// operation does not complete within `timeout`, the coroutine is dropped, which releases whatever it held on its
+    /// queue, and the operation fails with ETIMEDOUT.
+    pub fn insert_io_coroutine_with_timeout<F: FusedFuture<Output = (QDesc, OperationResult)> + 'static>(
+        &mut self,
+        task_name: &'static str,
+        qd: QDesc,
+        timeout: Option<Duration>,
+        coroutine: Pin<Box<F>>,
+    ) -> Result<QToken, Fail> {
+        let timeout: Duration = match timeout {
+            Some(timeout) => timeout,
+            None => return self.insert_io_coroutine(task_name, qd, coroutine),
+        };
+        // The deadline starts when the operation is submitted, rather than when the coroutine first runs.
+        let expiry: Instant = self.get_now() + timeout;
+        let coroutine = Box::pin(
+            async move {
+                match conditional_yield_until(coroutine, Some(expiry)).await {
+                    Ok(result) => result,
+                    Err(_) => {
+                        let cause: String = format!("operation timed out (qd={:?}, timeout={:?})", qd, timeout);
+                        debug!("{}: {}", task_name, cause);
+                        (qd, OperationResult::Failed(Fail::new(libc::ETIMEDOUT, &cause)))
+                    },
+                }
+            }
+            .fuse(),
+        );
+        self.insert_io_coroutine(task_name, qd, coroutine)
     }
 
     /// Inserts the background `coroutine` named `task_name` into the scheduler
@@ -168,6 +292,18 @@ impl SharedDemiRuntime {
         self.insert_coroutine(task_name, coroutine)
     }
 
+    /// Inserts the background `coroutine` named `task_name` into the scheduler with `priority`. Background coroutines
+    /// that are not on the datapath, such as those that refresh caches, should run with [TaskPriority::Low] so they do
+    /// not add latency to packet processing.
+    pub fn insert_background_coroutine_with_priority<F: FusedFuture<Output = ()> + 'static>(
+        &mut self,
+        task_name: &'static str,
+        priority: TaskPriority,
+        coroutine: Pin<Box<F>>,
+    ) -> Result<QToken, Fail> {
+        self.insert_coroutine_with_priority(task_name, priority, coroutine)
+    }
+
     /// Inserts a coroutine of type T and task
     pub fn insert_coroutine<F: FusedFuture + 'static>(
         &mut self,
@@ -177,11 +313,24 @@ impl SharedDemiRuntime {
     where
         F::Output: Unpin + Clone + Any,
     {
-        trace!("Inserting coroutine: {:?}", task_name);
+        self.insert_coroutine_with_priority(task_name, TaskPriority::High, coroutine)
+    }
+
+    /// Inserts a coroutine of type T and task with `priority`.
+    fn insert_coroutine_with_priority<F: FusedFuture + 'static>(
+        &mut self,
+        task_name: &'static str,
+        priority: TaskPriority,
+        coroutine: Pin<Box<F>>,
+    ) -> Result<QToken, Fail>
+    where
+        F::Output: Unpin + Clone + Any,
+    {
+        trace!("Inserting coroutine: {:?}, priority={:?}", task_name, priority);
         #[cfg(feature = "profiler")]
         let coroutine = coroutine_timer!(task_name, coroutine);
         let task: TaskWithResult<F::Output> = TaskWithResult::<F::Output>::new(task_name, coroutine);
-        match self.scheduler.insert_task(task) {
+        match self.scheduler.insert_task_with_priority(priority, task) {
             Some(task_id) => Ok(task_id.into()),
             None => {
                 let cause: String = format!("cannot schedule coroutine (task_name={:?})", &task_name);
@@ -224,6 +373,7 @@ impl SharedDemiRuntime {
                 if let Ok(mut operation_task) = OperationTask::try_from(boxed_task.as_any()) {
                     let (qd, result): (QDesc, OperationResult) =
                         expect_some!(operation_task.get_result(), "coroutine not finished");
+                    self.pending_operations.remove(&completed_qt);
 
                     // Check whether it matches any of the queue tokens that we are waiting on.
                     if completed_qt == qt {
@@ -235,11 +385,14 @@ impl SharedDemiRuntime {
                 }
             }
             // Check the timeout.
-            if let Some(abstime) = abstime {
-                if SystemTime::now() >= abstime {
-                    return Err(Fail::new(libc::ETIMEDOUT, "wait timed out"));
-                }
-            }
+            let remaining_time: Duration = match abstime {
+                Some(abstime) => match abstime.duration_since(SystemTime::now()) {
+                    Ok(remaining_time) if !remaining_time.is_zero() => remaining_time,
+                    _ => return Err(Fail::new(libc::ETIMEDOUT, "wait timed out")),
+                },
+                None => Duration::MAX,
+            };
+            self.sleep_if_idle(remaining_time);
 
             // Advance the clock and continue running tasks.
             self.advance_clock_to_now();
@@ -277,6 +430,7 @@ impl SharedDemiRuntime {
             if let Some((i, qd, result)) = self.run_any(qts, remaining_time) {
                 return Ok((i, qts[i], qd, result));
             }
+            self.sleep_if_idle(remaining_time);
             // Otherwise, move time forward.
             self.advance_clock_to_now();
             let now: Instant = self.get_now();
@@ -291,6 +445,34 @@ impl SharedDemiRuntime {
         }
     }
 
+    /// Waits until all the tasks in [qts] have completed or [timeout] expires, passing the result of each task that
+    /// completes to [acceptor]. Returns the tokens of the tasks that are still pending.
+    pub fn wait_all<Acceptor: FnMut(QToken, QDesc, OperationResult)>(
+        &mut self,
+        qts: &[QToken],
+        timeout: Option<Duration>,
+        mut acceptor: Acceptor,
+    ) -> Result<Vec<QToken>, Fail> {
+        self.advance_clock_to_now();
+        let deadline: Option<Instant> = timeout.and_then(|timeout| self.get_now().checked_add(timeout));
+        let mut pending: Vec<QToken> = qts.to_vec();
+        while !pending.is_empty() {
+            let remaining_time: Duration = match deadline {
+                Some(deadline) => deadline.saturating_duration_since(self.get_now()),
+                None => Duration::MAX,
+            };
+            match self.wait_any(&pending, remaining_time) {
+                Ok((i, qt, qd, result)) => {
+                    pending.swap_remove(i);
+                    acceptor(qt, qd, result);
+                },
+                Err(e) if e.errno == libc::ETIMEDOUT => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(pending)
+    }
+
     pub fn get_completed_task(&mut self, qt: &QToken) -> Option<(QDesc, OperationResult)> {
         self.completed_tasks.remove(qt)
     }
@@ -323,6 +505,7 @@ impl SharedDemiRuntime {
                     return Ok(());
                 }
             }
+            self.sleep_if_idle(remaining_time);
             // Otherwise, move time forward.
             self.advance_clock_to_now();
             let now: Instant = self.get_now();
@@ -371,16 +554,24 @@ impl SharedDemiRuntime {
             if let Ok(mut operation_task) = OperationTask::try_from(boxed_task.as_any()) {
                 let (qd, result): (QDesc, OperationResult) =
                     expect_some!(operation_task.get_result(), "coroutine not finished");
+                self.pending_operations.remove(&qt);
 
+                self.update_notifier();
                 return Some((qt, qd, result));
             }
         }
 
+        self.update_notifier();
         None
     }
 
     /// Performs a single pool on the underlying scheduler.
     pub fn poll(&mut self) {
+        // Wake up coroutines whose doorbells rang, since nothing else checks them when the application only polls.
+        #[cfg(target_os = "linux")]
+        if fdwait::global_has_waiters() {
+            fdwait::global_poll(Duration::ZERO);
+        }
         // For all ready tasks that were removed from the scheduler, add to our completed task list.
         for boxed_task in self.scheduler.poll_all() {
             trace!("Completed while polling coroutine: {:?}", boxed_task.get_name());
@@ -389,9 +580,97 @@ impl SharedDemiRuntime {
             if let Ok(mut operation_task) = OperationTask::try_from(boxed_task.as_any()) {
                 let (qd, result): (QDesc, OperationResult) =
                     expect_some!(operation_task.get_result(), "coroutine not finished");
+                self.pending_operations.remove(&qt);
                 self.completed_tasks.insert(qt, (qd, result));
             }
         }
+        self.update_notifier();
+    }
+
+    /// Returns a file descriptor that becomes readable when waiting would return, so that the application can embed
+    /// the runtime in an external event loop. The descriptor is readable while results of completed operations are
+    /// waiting to be collected or while a doorbell that some coroutine waits on has rung. It is only brought up to
+    /// date when the runtime is polled, so the application should poll after it becomes readable and after it issues
+    /// new operations. Timers and busy-polled devices do not signal it.
+    #[cfg(target_os = "linux")]
+    pub fn get_event_fd(&mut self) -> Result<libc::c_int, Fail> {
+        if self.notifier.is_none() {
+            self.notifier = Some(Notifier::new()?);
+            self.update_notifier();
+        }
+        Ok(expect_some!(self.notifier.as_ref(), "notifier should exist").get_fd())
+    }
+
+    /// Pollable file descriptors are not supported on this platform.
+    #[cfg(not(target_os = "linux"))]
+    pub fn get_event_fd(&mut self) -> Result<libc::c_int, Fail> {
+        let cause: &str = "pollable file descriptors are not supported on this platform";
+        warn!("get_event_fd(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, cause))
+    }
+
+    /// Brings the pollable file descriptor up to date, if the application asked for one.
+    #[cfg(target_os = "linux")]
+    fn update_notifier(&mut self) {
+        let has_completions: bool = !self.completed_tasks.is_empty();
+        if let Some(notifier) = self.notifier.as_mut() {
+            notifier.update(has_completions, fdwait::global_waiter_fds());
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn update_notifier(&mut self) {}
+
+    /// Sets the wait tunable [name] ("policy" or "spin_micros") to [value].
+    pub fn set_wait_tunable(&mut self, name: &str, value: &str) -> Result<(), Fail> {
+        self.wait_config.set_tunable(name, value)
+    }
+
+    /// Gets the value of the wait tunable [name].
+    pub fn get_wait_tunable(&self, name: &str) -> Result<String, Fail> {
+        self.wait_config.get_tunable(name)
+    }
+
+    /// Turns per-coroutine accounting of runtime and poll counts on or off. Turning it off drops the stats collected so
+    /// far.
+    pub fn set_task_accounting(&mut self, enabled: bool) {
+        self.scheduler.set_accounting(enabled)
+    }
+
+    /// Returns the accounting of the [max_tasks] coroutines with the longest single polls.
+    pub fn get_task_stats(&self, max_tasks: usize) -> Vec<TaskStats> {
+        self.scheduler.get_task_stats(max_tasks)
+    }
+
+    /// Cancels the I/O operation of [qt]. The coroutine that runs the operation is removed from the scheduler and
+    /// dropped, which releases whatever it held on its queue, and [qt] completes with ECANCELED.
+    pub fn cancel(&mut self, qt: QToken) -> Result<(), Fail> {
+        trace!("cancel(): qt={:?}", qt);
+        if self.completed_tasks.contains_key(&qt) {
+            let cause: String = format!("operation already completed (qt={:?})", qt);
+            warn!("cancel(): {}", cause);
+            return Err(Fail::new(libc::EALREADY, &cause));
+        }
+        // Background coroutines are not I/O operations, so they cannot be cancelled.
+        let qd: QDesc = match self.pending_operations.remove(&qt) {
+            Some(qd) => qd,
+            None => {
+                let cause: String = format!("{:?} is not a valid queue token", qt);
+                warn!("cancel(): {}", cause);
+                return Err(Fail::new(libc::EINVAL, &cause));
+            },
+        };
+        let task: Box<dyn Task> = expect_some!(
+            self.scheduler.remove_task(TaskId::from(qt)),
+            "pending operation should have a coroutine"
+        );
+        trace!("Cancelling coroutine: {:?}", task.get_name());
+        drop(task);
+
+        let cause: String = format!("operation cancelled (qt={:?})", qt);
+        self.completed_tasks
+            .insert(qt, (qd, OperationResult::Failed(Fail::new(libc::ECANCELED, &cause))));
+        Ok(())
     }
 
     /// Allocates a queue of type `T` and returns the associated queue descriptor.
@@ -481,6 +760,43 @@ impl SharedDemiRuntime {
         timer::global_advance_clock(now)
     }
 
+    /// If no coroutine is ready to run and the wait policy allows it, puts the calling thread to sleep until either a
+    /// doorbell rings, the next timer expires, or [timeout] passes. Otherwise, this just checks doorbells without
+    /// blocking. Coroutines that busy-poll a device are always ready to run, so they keep the thread from sleeping.
+    #[cfg(target_os = "linux")]
+    fn sleep_if_idle(&mut self, timeout: Duration) {
+        let now: Instant = Instant::now();
+        let timeout: Duration = if self.scheduler.has_ready_tasks() {
+            self.idle_since = None;
+            Duration::ZERO
+        } else {
+            let idle_since: Instant = *self.idle_since.get_or_insert(now);
+            if !self.wait_config.may_sleep(now - idle_since) {
+                Duration::ZERO
+            } else {
+                match timer::global_next_expiry() {
+                    Some(expiry) => timeout.min(expiry.saturating_duration_since(now)),
+                    None => timeout,
+                }
+            }
+        };
+        if fdwait::global_has_waiters() {
+            fdwait::global_poll(timeout);
+        } else if !timeout.is_zero() {
+            // Nothing but timers can make progress, so there is no doorbell to wake us up early.
+            ::std::thread::sleep(timeout);
+        }
+        // We may have slept, thus move time forward right away.
+        if !timeout.is_zero() {
+            self.advance_clock(Instant::now());
+            self.ts_iters = 0;
+        }
+    }
+
+    /// Doorbells are not supported on this platform, so there is never anything to sleep on.
+    #[cfg(not(target_os = "linux"))]
+    fn sleep_if_idle(&mut self, _timeout: Duration) {}
+
     /// Moves time forward to the current real time.
     fn advance_clock_to_now(&mut self) {
         if self.ts_iters == 0 {
@@ -494,6 +810,31 @@ impl SharedDemiRuntime {
         timer::global_get_time()
     }
 
+    /// Counts an incoming packet that the network stack dropped for [reason].
+    pub fn record_drop(&mut self, reason: DropReason) {
+        self.drop_stats.record(reason);
+    }
+
+    /// Gets the number of incoming packets that the network stack dropped, for each reason.
+    pub fn get_drop_stats(&self) -> DropStats {
+        self.drop_stats
+    }
+
+    /// Gets the pool that accounts for the memory that the sockets of the network stack buffer.
+    pub fn get_memory_pool(&self) -> SharedMemoryPool {
+        self.memory_pool.clone()
+    }
+
+    /// Gets the admission control of inbound connection requests and datagrams.
+    pub fn get_admission_control(&self) -> SharedAdmissionControl {
+        self.admission.clone()
+    }
+
+    /// Gets the table of the flows that TCP connections and UDP peers take up.
+    pub fn get_flow_table(&self) -> SharedFlowTable {
+        self.flows.clone()
+    }
+
     /// Checks if an identifier is in use and returns the queue descriptor if it is.
     pub fn get_qd_from_socket_id(&self, id: &SocketId) -> Option<QDesc> {
         match self.network_table.get_qd(id) {
@@ -557,6 +898,11 @@ pub async fn yield_with_timeout(timeout: Duration) {
     timer::wait(timeout).await
 }
 
+/// Yield until the [expiry] time passes.
+pub async fn yield_until(expiry: Instant) {
+    timer::wait_until(expiry).await
+}
+
 /// Yield until either the condition completes or we time out. If the timeout is 0, then run
 pub async fn conditional_yield_with_timeout<F: Future>(condition: F, timeout: Duration) -> Result<F::Output, Fail> {
     select_biased! {
@@ -578,6 +924,13 @@ pub async fn conditional_yield_until<F: Future>(condition: F, expiry: Option<Ins
     }
 }
 
+/// Yield until [fd] becomes readable. While waiting, the runtime may put the calling thread to sleep if there is
+/// nothing else to do.
+#[cfg(target_os = "linux")]
+pub async fn wait_readable(fd: ::std::os::fd::RawFd) {
+    fdwait::wait_readable(fd).await
+}
+
 /// Yield for one quanta.
 pub async fn poll_yield() {
     let poll: PollFuture = PollFuture::default();
@@ -596,8 +949,17 @@ impl Default for SharedDemiRuntime {
             scheduler: SharedScheduler::default(),
             ephemeral_ports: EphemeralPorts::default(),
             network_table: NetworkQueueTable::default(),
+            drop_stats: DropStats::default(),
+            memory_pool: SharedMemoryPool::default(),
+            admission: SharedAdmissionControl::default(),
+            flows: SharedFlowTable::default(),
             ts_iters: 0,
             completed_tasks: HashMap::<QToken, (QDesc, OperationResult)>::new(),
+            pending_operations: HashMap::<QToken, QDesc>::new(),
+            #[cfg(target_os = "linux")]
+            notifier: None,
+            wait_config: WaitConfig::default(),
+            idle_since: None,
         }))
     }
 }
@@ -696,13 +1058,22 @@ pub trait Runtime: Clone + Unpin + 'static {}
 #[cfg(test)]
 mod tests {
     use crate::runtime::{
+        fail::Fail,
         poll_yield,
         OperationResult,
         QDesc,
         QToken,
         SharedDemiRuntime,
+        APP_TASK_QDESC,
+    };
+    use ::anyhow::Result;
+    use ::std::{
+        thread,
+        time::{
+            Duration,
+            Instant,
+        },
     };
-    use ::std::time::Duration;
     use futures::FutureExt;
     use test::Bencher;
 
@@ -719,11 +1090,166 @@ mod tests {
         }
     }
 
+    /// Tests that cancelled operations complete with ECANCELED while the other ones keep running.
+    #[test]
+    fn test_cancel() -> Result<()> {
+        let mut runtime: SharedDemiRuntime = SharedDemiRuntime::default();
+        let qd: QDesc = QDesc::from(7);
+        let cancelled_qt: QToken =
+            runtime.insert_io_coroutine("dummy coroutine", qd, Box::pin(dummy_coroutine(usize::MAX).fuse()))?;
+        let qt: QToken = runtime.insert_io_coroutine("dummy coroutine", qd, Box::pin(dummy_coroutine(4).fuse()))?;
+        let background_qt: QToken = runtime.insert_background_coroutine(
+            "dummy background coroutine",
+            Box::pin(dummy_background_coroutine().fuse()),
+        )?;
+        crate::ensure_eq!(runtime.run_any(&[cancelled_qt], Duration::ZERO).is_none(), true);
+
+        // Only pending I/O operations can be cancelled.
+        crate::ensure_eq!(runtime.cancel(background_qt).unwrap_err().errno, libc::EINVAL);
+        runtime.cancel(cancelled_qt)?;
+        crate::ensure_eq!(runtime.cancel(cancelled_qt).unwrap_err().errno, libc::EALREADY);
+        match runtime.wait(cancelled_qt, Duration::ZERO)? {
+            (0, _, cancelled_qd, OperationResult::Failed(e)) => {
+                crate::ensure_eq!(cancelled_qd, qd);
+                crate::ensure_eq!(e.errno, libc::ECANCELED);
+            },
+            _ => anyhow::bail!("cancelled operation should fail"),
+        }
+        crate::ensure_eq!(runtime.cancel(cancelled_qt).unwrap_err().errno, libc::EINVAL);
+
+        // The other operations are not affected.
+        match runtime.wait(qt, Duration::from_secs(1))? {
+            (0, _, _, OperationResult::Close) => Ok(()),
+            _ => anyhow::bail!("operation should complete"),
+        }
+    }
+
+    /// Tests that waiting for all operations returns those that did not complete in time.
+    #[test]
+    fn test_wait_all() -> Result<()> {
+        let mut runtime: SharedDemiRuntime = SharedDemiRuntime::default();
+        let qd: QDesc = QDesc::from(5);
+        let stuck_qt: QToken =
+            runtime.insert_io_coroutine("dummy coroutine", qd, Box::pin(dummy_coroutine(usize::MAX).fuse()))?;
+        let qts: Vec<QToken> = (0..3)
+            .map(|i| runtime.insert_io_coroutine("dummy coroutine", qd, Box::pin(dummy_coroutine(i).fuse())))
+            .collect::<Result<Vec<QToken>, Fail>>()?;
+
+        let mut completed: Vec<QToken> = Vec::new();
+        let pending: Vec<QToken> = runtime.wait_all(&qts, None, |qt, _, _| completed.push(qt))?;
+        crate::ensure_eq!(pending.is_empty(), true);
+        let mut expected: Vec<QToken> = qts.clone();
+        expected.sort_by_key(|qt| u64::from(*qt));
+        completed.sort_by_key(|qt| u64::from(*qt));
+        crate::ensure_eq!(completed, expected);
+
+        let pending: Vec<QToken> = runtime.wait_all(&[stuck_qt], Some(Duration::from_millis(1)), |_, _, _| ())?;
+        crate::ensure_eq!(pending, vec![stuck_qt]);
+        Ok(())
+    }
+
+    /// Tests that an operation that does not complete before its timeout fails with ETIMEDOUT, while an operation that
+    /// completes in time is not affected.
+    #[test]
+    fn test_insert_io_coroutine_with_timeout() -> Result<()> {
+        let mut runtime: SharedDemiRuntime = SharedDemiRuntime::default();
+        let qd: QDesc = QDesc::from(3);
+        let timed_out_qt: QToken = runtime.insert_io_coroutine_with_timeout(
+            "dummy coroutine",
+            qd,
+            Some(Duration::from_millis(1)),
+            Box::pin(dummy_coroutine(usize::MAX).fuse()),
+        )?;
+        let qt: QToken = runtime.insert_io_coroutine_with_timeout(
+            "dummy coroutine",
+            qd,
+            Some(Duration::from_secs(60)),
+            Box::pin(dummy_coroutine(4).fuse()),
+        )?;
+
+        // Move the clock past the first deadline.
+        thread::sleep(Duration::from_millis(2));
+        runtime.advance_clock(Instant::now());
+
+        match runtime.wait(timed_out_qt, Duration::from_secs(1))? {
+            (0, _, timed_out_qd, OperationResult::Failed(e)) => {
+                crate::ensure_eq!(timed_out_qd, qd);
+                crate::ensure_eq!(e.errno, libc::ETIMEDOUT);
+            },
+            _ => anyhow::bail!("operation should time out"),
+        }
+        match runtime.wait(qt, Duration::from_secs(1))? {
+            (0, _, _, OperationResult::Close) => Ok(()),
+            _ => anyhow::bail!("operation should complete"),
+        }
+    }
+
+    /// Tests that application coroutines complete through their queue token, both on success and on failure.
+    #[test]
+    fn test_insert_app_coroutine() -> Result<()> {
+        let mut runtime: SharedDemiRuntime = SharedDemiRuntime::default();
+        let qt: QToken = runtime.insert_app_coroutine(
+            "app coroutine",
+            Box::pin(
+                async {
+                    dummy_coroutine(4).await;
+                    Ok(())
+                }
+                .fuse(),
+            ),
+        )?;
+        let failed_qt: QToken = runtime.insert_app_coroutine(
+            "app coroutine",
+            Box::pin(async { Err(Fail::new(libc::EIO, "app coroutine failed")) }.fuse()),
+        )?;
+
+        match runtime.wait(qt, Duration::from_secs(1))? {
+            (0, _, qd, OperationResult::Task) => crate::ensure_eq!(qd, QDesc::from(APP_TASK_QDESC)),
+            _ => anyhow::bail!("app coroutine should complete"),
+        }
+        match runtime.wait(failed_qt, Duration::from_secs(1))? {
+            (0, _, _, OperationResult::Failed(e)) => crate::ensure_eq!(e.errno, libc::EIO),
+            _ => anyhow::bail!("app coroutine should fail"),
+        }
+
+        Ok(())
+    }
+
+    /// Tests that the pollable file descriptor is readable exactly while there are results to collect.
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_event_fd() -> Result<()> {
+        let is_readable = |fd: libc::c_int| -> bool {
+            let mut pollfd: libc::pollfd = libc::pollfd {
+                fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            unsafe { libc::poll(&mut pollfd, 1, 0) > 0 }
+        };
+        let mut runtime: SharedDemiRuntime = SharedDemiRuntime::default();
+        let fd: libc::c_int = runtime.get_event_fd()?;
+        crate::ensure_eq!(runtime.get_event_fd()?, fd);
+        crate::ensure_eq!(is_readable(fd), false);
+
+        let qt: QToken =
+            runtime.insert_io_coroutine("dummy coroutine", QDesc::from(0), Box::pin(dummy_coroutine(0).fuse()))?;
+        runtime.poll();
+        crate::ensure_eq!(is_readable(fd), true);
+
+        // Collecting the result clears the file descriptor on the next poll.
+        runtime.wait(qt, Duration::ZERO)?;
+        runtime.poll();
+        crate::ensure_eq!(is_readable(fd), false);
+
+        Ok(())
+    }
+
     #[bench]
     fn benchmark_insert_io_coroutine(b: &mut Bencher) {
         let mut runtime: SharedDemiRuntime = SharedDemiRuntime::default();
 
-        b.iter(|| runtime.insert_io_coroutine("dummy coroutine", Box::pin(dummy_coroutine(10).fuse())));
+        b.iter(|| runtime.insert_io_coroutine("dummy coroutine", QDesc::from(0), Box::pin(dummy_coroutine(10).fuse())));
     }
 
     #[bench]
@@ -747,7 +1273,11 @@ mod tests {
         for i in 0..NUM_TASKS {
             // Make the arg big enough that the coroutine doesn't exit.
             qts[i] = runtime
-                .insert_io_coroutine("dummy coroutine", Box::pin(dummy_coroutine(1000000000).fuse()))
+                .insert_io_coroutine(
+                    "dummy coroutine",
+                    QDesc::from(0),
+                    Box::pin(dummy_coroutine(1000000000).fuse()),
+                )
                 .expect("should be able to insert tasks");
         }
 
@@ -764,7 +1294,11 @@ mod tests {
         for i in 0..NUM_TASKS {
             // Make the arg big enough that the coroutine doesn't exit.
             qts[i] = runtime
-                .insert_io_coroutine("dummy coroutine", Box::pin(dummy_coroutine(1000000000).fuse()))
+                .insert_io_coroutine(
+                    "dummy coroutine",
+                    QDesc::from(0),
+                    Box::pin(dummy_coroutine(1000000000).fuse()),
+                )
                 .expect("should be able to insert tasks");
         }
 
@@ -781,7 +1315,11 @@ mod tests {
         for i in 0..NUM_TASKS {
             // Make the arg big enough that the coroutine doesn't exit.
             qts[i] = runtime
-                .insert_io_coroutine("dummy coroutine", Box::pin(dummy_coroutine(1000000000).fuse()))
+                .insert_io_coroutine(
+                    "dummy coroutine",
+                    QDesc::from(0),
+                    Box::pin(dummy_coroutine(1000000000).fuse()),
+                )
                 .expect("should be able to insert tasks");
         }
 
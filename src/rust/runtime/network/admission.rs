@@ -0,0 +1,268 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Admission control of inbound connection requests and datagrams, so that a public-facing service can shed sources
+//! that misbehave before they take up any state.
+//!
+//! Sources whose address is in the blocklist are always refused. Otherwise, each source gets a token bucket for
+//! connection requests and another one for datagrams, which refill at the configured rates. The buckets of sources that
+//! stay idle long enough to refill are forgotten, so that the table of sources stays within [MAX_TRACKED_SOURCES].
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    network::config::AdmissionConfig,
+    SharedObject,
+};
+use ::std::{
+    collections::HashMap,
+    net::Ipv4Addr,
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    time::Instant,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Largest number of sources that are rate limited at once, for each kind of traffic. New sources are refused while the
+/// table is full of sources that are busy.
+pub const MAX_TRACKED_SOURCES: usize = 65536;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Kinds of inbound traffic that are admitted separately.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AdmissionKind {
+    /// Requests for new TCP connections.
+    Connection,
+    /// UDP datagrams.
+    Datagram,
+}
+
+/// Token bucket, which lets through traffic at a rate and in bursts of up to its capacity.
+pub struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Admission control of a network stack.
+pub struct AdmissionControl {
+    config: AdmissionConfig,
+    connections: HashMap<Ipv4Addr, Bucket>,
+    datagrams: HashMap<Ipv4Addr, Bucket>,
+    /// Number of times that traffic was refused because its source was blocked, or because it exceeded its rate.
+    blocked: u64,
+    rate_limited: u64,
+}
+
+#[derive(Clone)]
+pub struct SharedAdmissionControl(SharedObject<AdmissionControl>);
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Bucket {
+    /// Creates a bucket that is full with [burst] tokens at [now].
+    pub fn new(now: Instant, burst: u32) -> Self {
+        Self {
+            tokens: burst as f64,
+            updated: now,
+        }
+    }
+
+    /// Takes a token from the target bucket at [now], after refilling it at [rate] tokens per second and up to [burst].
+    /// Returns false if there is none left, in which case the traffic should be refused.
+    pub fn take(&mut self, now: Instant, rate: u32, burst: u32) -> bool {
+        self.refill(now, rate, burst);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Refills the target bucket for the time that went by until [now], at [rate] tokens per second and up to [burst].
+    fn refill(&mut self, now: Instant, rate: u32, burst: u32) {
+        let elapsed: f64 = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate as f64).min(burst as f64);
+        self.updated = now;
+    }
+}
+
+impl SharedAdmissionControl {
+    /// Checks whether traffic of [kind] from [source] is admitted at [now]. Refusals are counted, and the traffic should
+    /// be dropped.
+    pub fn admit(&mut self, now: Instant, kind: AdmissionKind, source: Ipv4Addr) -> bool {
+        if self.config.is_blocked(source) {
+            self.blocked += 1;
+            return false;
+        }
+        let admission: &mut AdmissionControl = &mut self.0;
+        let (rate, burst, buckets) = match kind {
+            AdmissionKind::Connection => (
+                admission.config.get_connection_rate(),
+                admission.config.get_connection_burst(),
+                &mut admission.connections,
+            ),
+            AdmissionKind::Datagram => (
+                admission.config.get_datagram_rate(),
+                admission.config.get_datagram_burst(),
+                &mut admission.datagrams,
+            ),
+        };
+        let rate: u32 = match rate {
+            Some(rate) => rate,
+            None => return true,
+        };
+
+        // Forget about the sources that were idle long enough to refill before tracking a new one.
+        if !buckets.contains_key(&source) && buckets.len() >= MAX_TRACKED_SOURCES {
+            buckets.retain(|_, bucket| {
+                bucket.refill(now, rate, burst);
+                bucket.tokens < burst as f64
+            });
+        }
+        let admitted: bool = if buckets.len() < MAX_TRACKED_SOURCES || buckets.contains_key(&source) {
+            buckets
+                .entry(source)
+                .or_insert_with(|| Bucket::new(now, burst))
+                .take(now, rate, burst)
+        } else {
+            false
+        };
+        if !admitted {
+            admission.rate_limited += 1;
+        }
+        admitted
+    }
+
+    /// Sets the tunable [name] to [value]. Changing a rate or a burst resets the buckets of all sources.
+    pub fn set_tunable(&mut self, name: &str, value: &str) -> Result<(), Fail> {
+        self.config.set_tunable(name, value)?;
+        match name {
+            "connection_rate" | "connection_burst" => self.connections.clear(),
+            "datagram_rate" | "datagram_burst" => self.datagrams.clear(),
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Gets the value of the tunable [name]. Besides those of [AdmissionConfig], "blocked" is the number of times that
+    /// traffic was refused because its source was blocked, and "rate_limited" the number of times that it was refused
+    /// because its source exceeded its rate.
+    pub fn get_tunable(&self, name: &str) -> Result<String, Fail> {
+        match name {
+            "blocked" => Ok(self.blocked.to_string()),
+            "rate_limited" => Ok(self.rate_limited.to_string()),
+            _ => self.config.get_tunable(name),
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Default for SharedAdmissionControl {
+    fn default() -> Self {
+        Self(SharedObject::new(AdmissionControl {
+            config: AdmissionConfig::default(),
+            connections: HashMap::new(),
+            datagrams: HashMap::new(),
+            blocked: 0,
+            rate_limited: 0,
+        }))
+    }
+}
+
+impl Deref for SharedAdmissionControl {
+    type Target = AdmissionControl;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl DerefMut for SharedAdmissionControl {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::network::admission::{
+        AdmissionKind,
+        SharedAdmissionControl,
+    };
+    use ::anyhow::Result;
+    use ::std::{
+        net::Ipv4Addr,
+        time::{
+            Duration,
+            Instant,
+        },
+    };
+
+    const ALICE: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 1);
+    const BOB: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 2);
+
+    #[test]
+    fn blocklist() -> Result<()> {
+        let now: Instant = Instant::now();
+        let mut admission: SharedAdmissionControl = SharedAdmissionControl::default();
+        crate::ensure_eq!(admission.admit(now, AdmissionKind::Connection, ALICE), true);
+
+        admission.set_tunable("blocklist", "10.0.0.1")?;
+        crate::ensure_eq!(admission.admit(now, AdmissionKind::Connection, ALICE), false);
+        crate::ensure_eq!(admission.admit(now, AdmissionKind::Datagram, ALICE), false);
+        crate::ensure_eq!(admission.admit(now, AdmissionKind::Connection, BOB), true);
+        crate::ensure_eq!(admission.get_tunable("blocked")?, "2".to_string());
+
+        // Sources are admitted again once they are taken off the blocklist.
+        admission.set_tunable("blocklist", "")?;
+        crate::ensure_eq!(admission.admit(now, AdmissionKind::Connection, ALICE), true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn rate_limit() -> Result<()> {
+        let now: Instant = Instant::now();
+        let mut admission: SharedAdmissionControl = SharedAdmissionControl::default();
+        admission.set_tunable("connection_rate", "2")?;
+
+        // Each source gets its own burst.
+        for _ in 0..2 {
+            crate::ensure_eq!(admission.admit(now, AdmissionKind::Connection, ALICE), true);
+        }
+        crate::ensure_eq!(admission.admit(now, AdmissionKind::Connection, ALICE), false);
+        crate::ensure_eq!(admission.admit(now, AdmissionKind::Connection, BOB), true);
+
+        // Datagrams are not limited.
+        crate::ensure_eq!(admission.admit(now, AdmissionKind::Datagram, ALICE), true);
+
+        // Buckets refill with time.
+        let later: Instant = now + Duration::from_millis(500);
+        crate::ensure_eq!(admission.admit(later, AdmissionKind::Connection, ALICE), true);
+        crate::ensure_eq!(admission.admit(later, AdmissionKind::Connection, ALICE), false);
+        crate::ensure_eq!(admission.get_tunable("rate_limited")?, "2".to_string());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use ::std::{
+    net::Ipv4Addr,
+    time::SystemTime,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Metadata that comes along with a popped datagram, like the ancillary data of recvmsg(). Fields are `None` when the
+/// transport does not know them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AncillaryData {
+    /// Destination address of the datagram, which tells a socket that is bound to the wildcard address which of the
+    /// local addresses the peer sent it to (e.g. to reply from that address).
+    pub local: Option<Ipv4Addr>,
+    /// Time to live of the datagram when it arrived.
+    pub ttl: Option<u8>,
+    /// Type of service of the datagram.
+    pub tos: Option<u8>,
+    /// Time at which the network stack received the datagram.
+    pub timestamp: Option<SystemTime>,
+    /// Time at which the device received the datagram, in nanoseconds of the clock of the device.
+    pub hw_timestamp: Option<u64>,
+}
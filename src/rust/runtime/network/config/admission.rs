@@ -0,0 +1,213 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    network::config::{
+        parse_tunable,
+        route::MAX_PREFIX_LEN,
+        unknown_tunable,
+    },
+};
+use ::std::{
+    fmt,
+    net::Ipv4Addr,
+    str::FromStr,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// IPv4 subnet whose hosts the admission control refuses. A bare address stands for a subnet with a single host.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockedSubnet {
+    addr: Ipv4Addr,
+    prefix_len: u8,
+}
+
+/// Admission Control Configuration Descriptor
+#[derive(Clone, Debug, Default)]
+pub struct AdmissionConfig {
+    /// Connection Requests Admitted per Second from Each Source (Unlimited if not Set)
+    connection_rate: Option<u32>,
+    /// Connection Requests Admitted in a Burst from Each Source (One Second Worth of the Rate if not Set)
+    connection_burst: Option<u32>,
+    /// Datagrams Admitted per Second from Each Source (Unlimited if not Set)
+    datagram_rate: Option<u32>,
+    /// Datagrams Admitted in a Burst from Each Source (One Second Worth of the Rate if not Set)
+    datagram_burst: Option<u32>,
+    /// Subnets Whose Hosts are Refused
+    blocklist: Vec<BlockedSubnet>,
+}
+
+//======================================================================================================================
+// Associate Functions
+//======================================================================================================================
+
+impl BlockedSubnet {
+    /// Checks whether [addr] is a host of the target subnet.
+    pub fn contains(&self, addr: Ipv4Addr) -> bool {
+        let mask: u32 = match self.prefix_len {
+            0 => 0,
+            prefix_len => u32::MAX << (MAX_PREFIX_LEN - prefix_len),
+        };
+        u32::from(addr) & mask == u32::from(self.addr)
+    }
+}
+
+/// Associate functions for Admission Control Configuration Descriptor
+impl AdmissionConfig {
+    /// Gets the number of connection requests that are admitted per second from each source in the target
+    /// [AdmissionConfig], if they are limited.
+    pub fn get_connection_rate(&self) -> Option<u32> {
+        self.connection_rate
+    }
+
+    /// Gets the number of connection requests that are admitted in a burst from each source in the target
+    /// [AdmissionConfig].
+    pub fn get_connection_burst(&self) -> u32 {
+        self.connection_burst.or(self.connection_rate).unwrap_or(0)
+    }
+
+    /// Gets the number of datagrams that are admitted per second from each source in the target [AdmissionConfig], if
+    /// they are limited.
+    pub fn get_datagram_rate(&self) -> Option<u32> {
+        self.datagram_rate
+    }
+
+    /// Gets the number of datagrams that are admitted in a burst from each source in the target [AdmissionConfig].
+    pub fn get_datagram_burst(&self) -> u32 {
+        self.datagram_burst.or(self.datagram_rate).unwrap_or(0)
+    }
+
+    /// Checks whether [addr] is a host of a subnet in the blocklist of the target [AdmissionConfig].
+    pub fn is_blocked(&self, addr: Ipv4Addr) -> bool {
+        self.blocklist.iter().any(|subnet| subnet.contains(addr))
+    }
+
+    /// Sets the tunable [name] to [value] in the target [AdmissionConfig]. A rate or burst of zero means that there is
+    /// none. The blocklist is given as a comma-separated list of subnets, and replaces the previous one. Changes apply
+    /// right away, to all sockets.
+    pub fn set_tunable(&mut self, name: &str, value: &str) -> Result<(), Fail> {
+        let limit = |value: u32| match value {
+            0 => None,
+            value => Some(value),
+        };
+        match name {
+            "connection_rate" => self.connection_rate = limit(parse_tunable::<u32>(name, value)?),
+            "connection_burst" => self.connection_burst = limit(parse_tunable::<u32>(name, value)?),
+            "datagram_rate" => self.datagram_rate = limit(parse_tunable::<u32>(name, value)?),
+            "datagram_burst" => self.datagram_burst = limit(parse_tunable::<u32>(name, value)?),
+            "blocklist" => {
+                let mut blocklist: Vec<BlockedSubnet> = Vec::new();
+                for subnet in value.split(',').map(str::trim).filter(|subnet| !subnet.is_empty()) {
+                    blocklist.push(parse_tunable::<BlockedSubnet>(name, subnet)?);
+                }
+                self.blocklist = blocklist;
+            },
+            _ => return unknown_tunable(name),
+        }
+        Ok(())
+    }
+
+    /// Gets the value of the tunable [name] in the target [AdmissionConfig].
+    pub fn get_tunable(&self, name: &str) -> Result<String, Fail> {
+        match name {
+            "connection_rate" => Ok(self.connection_rate.unwrap_or(0).to_string()),
+            "connection_burst" => Ok(self.connection_burst.unwrap_or(0).to_string()),
+            "datagram_rate" => Ok(self.datagram_rate.unwrap_or(0).to_string()),
+            "datagram_burst" => Ok(self.datagram_burst.unwrap_or(0).to_string()),
+            "blocklist" => Ok(self
+                .blocklist
+                .iter()
+                .map(|subnet| subnet.to_string())
+                .collect::<Vec<String>>()
+                .join(",")),
+            _ => unknown_tunable(name),
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl FromStr for BlockedSubnet {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix_len): (&str, u8) = match s.split_once('/') {
+            Some((addr, prefix_len)) => (addr, prefix_len.parse::<u8>().map_err(|_| ())?),
+            None => (s, MAX_PREFIX_LEN),
+        };
+        let addr: Ipv4Addr = addr.parse::<Ipv4Addr>().map_err(|_| ())?;
+        let subnet: BlockedSubnet = BlockedSubnet { addr, prefix_len };
+        // Addresses with host bits set are refused, as in routes.
+        match prefix_len <= MAX_PREFIX_LEN && subnet.contains(addr) {
+            true => Ok(subnet),
+            false => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for BlockedSubnet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.prefix_len {
+            MAX_PREFIX_LEN => write!(f, "{}", self.addr),
+            prefix_len => write!(f, "{}/{}", self.addr, prefix_len),
+        }
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::network::config::AdmissionConfig;
+    use ::anyhow::Result;
+    use ::std::net::Ipv4Addr;
+
+    /// Tests that tunables of [AdmissionConfig] can be changed at runtime and are validated.
+    #[test]
+    fn test_admission_config_tunables() -> Result<()> {
+        let mut config: AdmissionConfig = AdmissionConfig::default();
+        crate::ensure_eq!(config.get_connection_rate(), None);
+        crate::ensure_eq!(config.is_blocked(Ipv4Addr::new(10, 0, 0, 1)), false);
+
+        config.set_tunable("connection_rate", "10")?;
+        crate::ensure_eq!(config.get_connection_rate(), Some(10));
+        crate::ensure_eq!(config.get_connection_burst(), 10);
+        config.set_tunable("connection_burst", "3")?;
+        crate::ensure_eq!(config.get_connection_burst(), 3);
+        config.set_tunable("datagram_rate", "0")?;
+        crate::ensure_eq!(config.get_datagram_rate(), None);
+
+        config.set_tunable("blocklist", "10.0.0.0/8, 192.168.1.7")?;
+        crate::ensure_eq!(config.is_blocked(Ipv4Addr::new(10, 1, 2, 3)), true);
+        crate::ensure_eq!(config.is_blocked(Ipv4Addr::new(192, 168, 1, 7)), true);
+        crate::ensure_eq!(config.is_blocked(Ipv4Addr::new(192, 168, 1, 8)), false);
+        crate::ensure_eq!(config.get_tunable("blocklist")?, "10.0.0.0/8,192.168.1.7".to_string());
+        config.set_tunable("blocklist", "")?;
+        crate::ensure_eq!(config.is_blocked(Ipv4Addr::new(10, 1, 2, 3)), false);
+
+        // Invalid subnets leave the blocklist as it was.
+        config.set_tunable("blocklist", "0.0.0.0/0")?;
+        for invalid in ["10.0.0.1/8", "10.0.0.0/33", "10.0.0"] {
+            crate::ensure_eq!(
+                config.set_tunable("blocklist", invalid).unwrap_err().errno,
+                libc::EINVAL
+            );
+        }
+        crate::ensure_eq!(config.is_blocked(Ipv4Addr::new(1, 2, 3, 4)), true);
+        crate::ensure_eq!(config.set_tunable("rate", "1").unwrap_err().errno, libc::ENOENT);
+
+        Ok(())
+    }
+}
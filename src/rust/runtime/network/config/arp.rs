@@ -5,7 +5,17 @@
 // Imports
 //==============================================================================
 
-use crate::runtime::network::types::MacAddress;
+use crate::runtime::{
+    fail::Fail,
+    network::{
+        config::{
+            parse_tunable,
+            tunable_out_of_range,
+            unknown_tunable,
+        },
+        types::MacAddress,
+    },
+};
 use ::std::{
     collections::HashMap,
     net::Ipv4Addr,
@@ -25,6 +35,10 @@ pub struct ArpConfig {
     request_timeout: Duration,
     /// Retry Count for ARP Requests
     retry_count: usize,
+    /// Largest Number of Packets Waiting on the Resolution of an Address
+    max_pending: usize,
+    /// ARP Requests Sent per Second to Each Destination (Unlimited if not Set)
+    request_rate: Option<u32>,
     /// Initial Values for ARP Cache
     initial_values: HashMap<Ipv4Addr, MacAddress>,
     /// Disable ARP?
@@ -81,6 +95,17 @@ impl ArpConfig {
         self.retry_count
     }
 
+    /// Gets the largest number of packets that wait on the resolution of an address in the target [ArpConfig].
+    pub fn get_max_pending(&self) -> usize {
+        self.max_pending
+    }
+
+    /// Gets the number of ARP requests that are sent per second to each destination in the target [ArpConfig], if they
+    /// are limited.
+    pub fn get_request_rate(&self) -> Option<u32> {
+        self.request_rate
+    }
+
     /// Gets the initial values for the ARP Cache in the target [ArpConfig].
     pub fn get_initial_values(&self) -> &HashMap<Ipv4Addr, MacAddress> {
         &self.initial_values
@@ -91,6 +116,58 @@ impl ArpConfig {
         self.disable_arp
     }
 
+    /// Sets the tunable [name] to [value] in the target [ArpConfig]. Durations are given in milliseconds. A request rate
+    /// of zero means that there is no limit.
+    pub fn set_tunable(&mut self, name: &str, value: &str) -> Result<(), Fail> {
+        match name {
+            "cache_ttl_ms" => match parse_tunable::<u64>(name, value)? {
+                0 => tunable_out_of_range(name),
+                millis => {
+                    self.set_cache_ttl(Duration::from_millis(millis));
+                    Ok(())
+                },
+            },
+            "request_timeout_ms" => match parse_tunable::<u64>(name, value)? {
+                0 => tunable_out_of_range(name),
+                millis => {
+                    self.set_request_timeout(Duration::from_millis(millis));
+                    Ok(())
+                },
+            },
+            "retry_count" => {
+                self.set_retry_count(parse_tunable::<usize>(name, value)?);
+                Ok(())
+            },
+            "max_pending" => match parse_tunable::<usize>(name, value)? {
+                0 => tunable_out_of_range(name),
+                max_pending => {
+                    self.max_pending = max_pending;
+                    Ok(())
+                },
+            },
+            "request_rate" => {
+                self.request_rate = match parse_tunable::<u32>(name, value)? {
+                    0 => None,
+                    rate => Some(rate),
+                };
+                Ok(())
+            },
+            _ => unknown_tunable(name),
+        }
+    }
+
+    /// Gets the value of the tunable [name] in the target [ArpConfig].
+    pub fn get_tunable(&self, name: &str) -> Result<String, Fail> {
+        match name {
+            "cache_ttl_ms" => Ok(self.cache_ttl.as_millis().to_string()),
+            "request_timeout_ms" => Ok(self.request_timeout.as_millis().to_string()),
+            "retry_count" => Ok(self.retry_count.to_string()),
+            "max_pending" => Ok(self.max_pending.to_string()),
+            "request_rate" => Ok(self.request_rate.unwrap_or(0).to_string()),
+            _ => unknown_tunable(name),
+        }
+    }
+
     /// Sets the time to live for entries of the ARP Cache in the target [ArpConfig].
     fn set_cache_ttl(&mut self, cache_ttl: Duration) {
         self.cache_ttl = cache_ttl
@@ -129,6 +206,8 @@ impl Default for ArpConfig {
             cache_ttl: Duration::from_secs(15),
             request_timeout: Duration::from_secs(20),
             retry_count: 5,
+            max_pending: 64,
+            request_rate: Some(1),
             initial_values: HashMap::new(),
             disable_arp: false,
         }
@@ -155,9 +234,42 @@ mod tests {
         crate::ensure_eq!(config.get_cache_ttl(), Duration::from_secs(15));
         crate::ensure_eq!(config.get_request_timeout(), Duration::from_secs(20));
         crate::ensure_eq!(config.get_retry_count(), 5);
+        crate::ensure_eq!(config.get_max_pending(), 64);
+        crate::ensure_eq!(config.get_request_rate(), Some(1));
         crate::ensure_eq!(config.get_initial_values(), &HashMap::new());
         crate::ensure_eq!(config.get_disable_arp(), false);
 
         Ok(())
     }
+
+    /// Tests that tunables of [ArpConfig] can be changed at runtime and are validated.
+    #[test]
+    fn test_arp_config_tunables() -> Result<()> {
+        let mut config: ArpConfig = ArpConfig::default();
+        config.set_tunable("cache_ttl_ms", "2500")?;
+        crate::ensure_eq!(config.get_cache_ttl(), Duration::from_millis(2500));
+        crate::ensure_eq!(config.get_tunable("cache_ttl_ms")?, "2500".to_string());
+        config.set_tunable("retry_count", " 3 ")?;
+        crate::ensure_eq!(config.get_retry_count(), 3);
+        config.set_tunable("request_rate", "0")?;
+        crate::ensure_eq!(config.get_request_rate(), None);
+
+        crate::ensure_eq!(
+            config.set_tunable("request_timeout_ms", "0").unwrap_err().errno,
+            libc::ERANGE
+        );
+        crate::ensure_eq!(config.set_tunable("max_pending", "0").unwrap_err().errno, libc::ERANGE);
+        crate::ensure_eq!(
+            config.set_tunable("retry_count", "many").unwrap_err().errno,
+            libc::EINVAL
+        );
+        crate::ensure_eq!(
+            config.set_tunable("disable_arp", "true").unwrap_err().errno,
+            libc::ENOENT
+        );
+        crate::ensure_eq!(config.get_tunable("initial_values").unwrap_err().errno, libc::ENOENT);
+        crate::ensure_eq!(config.get_request_timeout(), Duration::from_secs(20));
+
+        Ok(())
+    }
 }
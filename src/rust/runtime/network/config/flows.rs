@@ -0,0 +1,175 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    network::config::{
+        parse_tunable,
+        unknown_tunable,
+    },
+};
+use ::std::{
+    fmt,
+    str::FromStr,
+    time::Duration,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// What the network stack does when a new flow does not fit in the flow table, once idle flows were evicted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EvictionPolicy {
+    /// The flow that was used the least recently is evicted to make room. Evicted TCP connections are aborted.
+    EvictLru,
+    /// The new flow is refused: connections fail with ENOBUFS and datagrams are dropped.
+    Refuse,
+}
+
+/// Flow Table Configuration Descriptor
+#[derive(Clone, Debug)]
+pub struct FlowConfig {
+    /// Largest Number of Flows in the Table (Unlimited if not Set)
+    max_entries: Option<usize>,
+    /// Time After Which an Unused Flow is Evicted First (Never if not Set)
+    idle_timeout: Option<Duration>,
+    /// What to do When the Table is Full
+    policy: EvictionPolicy,
+}
+
+//======================================================================================================================
+// Associate Functions
+//======================================================================================================================
+
+/// Associate functions for Flow Table Configuration Descriptor
+impl FlowConfig {
+    /// Gets the largest number of flows in the target [FlowConfig], if any.
+    pub fn get_max_entries(&self) -> Option<usize> {
+        self.max_entries
+    }
+
+    /// Gets the time after which an unused flow is evicted first in the target [FlowConfig], if any.
+    pub fn get_idle_timeout(&self) -> Option<Duration> {
+        self.idle_timeout
+    }
+
+    /// Gets what to do when the table is full in the target [FlowConfig].
+    pub fn get_policy(&self) -> EvictionPolicy {
+        self.policy
+    }
+
+    /// Sets the tunable [name] to [value] in the target [FlowConfig]. A limit or a timeout of zero means that there is
+    /// none. Changes apply to the flows that are created afterwards.
+    pub fn set_tunable(&mut self, name: &str, value: &str) -> Result<(), Fail> {
+        match name {
+            "max_entries" => {
+                self.max_entries = match parse_tunable::<usize>(name, value)? {
+                    0 => None,
+                    entries => Some(entries),
+                };
+                Ok(())
+            },
+            "idle_timeout_ms" => {
+                self.idle_timeout = match parse_tunable::<u64>(name, value)? {
+                    0 => None,
+                    millis => Some(Duration::from_millis(millis)),
+                };
+                Ok(())
+            },
+            "policy" => {
+                self.policy = parse_tunable::<EvictionPolicy>(name, value)?;
+                Ok(())
+            },
+            _ => unknown_tunable(name),
+        }
+    }
+
+    /// Gets the value of the tunable [name] in the target [FlowConfig].
+    pub fn get_tunable(&self, name: &str) -> Result<String, Fail> {
+        match name {
+            "max_entries" => Ok(self.max_entries.unwrap_or(0).to_string()),
+            "idle_timeout_ms" => Ok(self.idle_timeout.unwrap_or_default().as_millis().to_string()),
+            "policy" => Ok(self.policy.to_string()),
+            _ => unknown_tunable(name),
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+/// Default Trait Implementation for Flow Table Configuration Descriptor
+impl Default for FlowConfig {
+    /// Creates a Flow Table Configuration Descriptor with the default values.
+    fn default() -> Self {
+        FlowConfig {
+            max_entries: None,
+            idle_timeout: None,
+            policy: EvictionPolicy::EvictLru,
+        }
+    }
+}
+
+impl FromStr for EvictionPolicy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "evict_lru" => Ok(EvictionPolicy::EvictLru),
+            "refuse" => Ok(EvictionPolicy::Refuse),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for EvictionPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvictionPolicy::EvictLru => write!(f, "evict_lru"),
+            EvictionPolicy::Refuse => write!(f, "refuse"),
+        }
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::network::config::{
+        EvictionPolicy,
+        FlowConfig,
+    };
+    use ::anyhow::Result;
+    use ::std::time::Duration;
+
+    /// Tests that tunables of [FlowConfig] can be changed at runtime and are validated.
+    #[test]
+    fn test_flow_config_tunables() -> Result<()> {
+        let mut config: FlowConfig = FlowConfig::default();
+        crate::ensure_eq!(config.get_max_entries(), None);
+        crate::ensure_eq!(config.get_idle_timeout(), None);
+        crate::ensure_eq!(config.get_policy(), EvictionPolicy::EvictLru);
+
+        config.set_tunable("max_entries", "1024")?;
+        crate::ensure_eq!(config.get_max_entries(), Some(1024));
+        config.set_tunable("idle_timeout_ms", "30000")?;
+        crate::ensure_eq!(config.get_idle_timeout(), Some(Duration::from_secs(30)));
+        config.set_tunable("policy", "refuse")?;
+        crate::ensure_eq!(config.get_tunable("policy")?, "refuse".to_string());
+        config.set_tunable("max_entries", "0")?;
+        crate::ensure_eq!(config.get_max_entries(), None);
+
+        crate::ensure_eq!(config.set_tunable("policy", "random").unwrap_err().errno, libc::EINVAL);
+        crate::ensure_eq!(config.set_tunable("entries", "1").unwrap_err().errno, libc::ENOENT);
+
+        Ok(())
+    }
+}
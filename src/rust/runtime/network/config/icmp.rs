@@ -0,0 +1,209 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    network::config::{
+        parse_tunable,
+        unknown_tunable,
+    },
+};
+use ::std::{
+    fmt,
+    str::FromStr,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Classes of ICMP messages that the network stack generates, which are rate limited and disabled separately.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum IcmpClass {
+    /// Replies to echo requests.
+    EchoReply,
+    /// Destination unreachable messages.
+    Unreachable,
+    /// Time exceeded messages.
+    TimeExceeded,
+}
+
+/// ICMP Configuration Descriptor
+#[derive(Clone, Debug)]
+pub struct IcmpConfig {
+    /// ICMP Messages Sent per Second, for All Classes (Unlimited if not Set)
+    rate: Option<u32>,
+    /// ICMP Messages Sent in a Burst, for All Classes (One Second Worth of the Rate if not Set)
+    burst: Option<u32>,
+    /// ICMP Messages Sent per Second, for Each Class (Unlimited if not Set)
+    echo_reply_rate: Option<u32>,
+    unreachable_rate: Option<u32>,
+    time_exceeded_rate: Option<u32>,
+    /// Classes of ICMP Messages that are Never Sent
+    disabled: Vec<IcmpClass>,
+}
+
+//======================================================================================================================
+// Associate Functions
+//======================================================================================================================
+
+/// Associate functions for ICMP Configuration Descriptor
+impl IcmpConfig {
+    /// Gets the number of ICMP messages that are sent per second, for all classes, in the target [IcmpConfig], if they
+    /// are limited.
+    pub fn get_rate(&self) -> Option<u32> {
+        self.rate
+    }
+
+    /// Gets the number of ICMP messages that are sent in a burst, for all classes, in the target [IcmpConfig].
+    pub fn get_burst(&self) -> u32 {
+        self.burst.or(self.rate).unwrap_or(0)
+    }
+
+    /// Gets the number of ICMP messages of [class] that are sent per second in the target [IcmpConfig], if they are
+    /// limited. Messages of a class are sent in bursts of one second worth of its rate.
+    pub fn get_class_rate(&self, class: IcmpClass) -> Option<u32> {
+        match class {
+            IcmpClass::EchoReply => self.echo_reply_rate,
+            IcmpClass::Unreachable => self.unreachable_rate,
+            IcmpClass::TimeExceeded => self.time_exceeded_rate,
+        }
+    }
+
+    /// Checks whether ICMP messages of [class] are never sent in the target [IcmpConfig].
+    pub fn is_disabled(&self, class: IcmpClass) -> bool {
+        self.disabled.contains(&class)
+    }
+
+    /// Sets the tunable [name] to [value] in the target [IcmpConfig]. A rate or burst of zero means that there is none.
+    /// The disabled classes are given as a comma-separated list, and replace the previous ones.
+    pub fn set_tunable(&mut self, name: &str, value: &str) -> Result<(), Fail> {
+        let limit = |value: u32| match value {
+            0 => None,
+            value => Some(value),
+        };
+        match name {
+            "rate" => self.rate = limit(parse_tunable::<u32>(name, value)?),
+            "burst" => self.burst = limit(parse_tunable::<u32>(name, value)?),
+            "echo_reply_rate" => self.echo_reply_rate = limit(parse_tunable::<u32>(name, value)?),
+            "unreachable_rate" => self.unreachable_rate = limit(parse_tunable::<u32>(name, value)?),
+            "time_exceeded_rate" => self.time_exceeded_rate = limit(parse_tunable::<u32>(name, value)?),
+            "disabled" => {
+                let mut disabled: Vec<IcmpClass> = Vec::new();
+                for class in value.split(',').map(str::trim).filter(|class| !class.is_empty()) {
+                    disabled.push(parse_tunable::<IcmpClass>(name, class)?);
+                }
+                self.disabled = disabled;
+            },
+            _ => return unknown_tunable(name),
+        }
+        Ok(())
+    }
+
+    /// Gets the value of the tunable [name] in the target [IcmpConfig].
+    pub fn get_tunable(&self, name: &str) -> Result<String, Fail> {
+        match name {
+            "rate" => Ok(self.rate.unwrap_or(0).to_string()),
+            "burst" => Ok(self.burst.unwrap_or(0).to_string()),
+            "echo_reply_rate" => Ok(self.echo_reply_rate.unwrap_or(0).to_string()),
+            "unreachable_rate" => Ok(self.unreachable_rate.unwrap_or(0).to_string()),
+            "time_exceeded_rate" => Ok(self.time_exceeded_rate.unwrap_or(0).to_string()),
+            "disabled" => Ok(self
+                .disabled
+                .iter()
+                .map(|class| class.to_string())
+                .collect::<Vec<String>>()
+                .join(",")),
+            _ => unknown_tunable(name),
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+/// Default Trait Implementation for ICMP Configuration Descriptor
+impl Default for IcmpConfig {
+    /// Creates an ICMP Configuration Descriptor with the default values, which let through 1000 messages per second in
+    /// bursts of 50, as Linux does.
+    fn default() -> Self {
+        IcmpConfig {
+            rate: Some(1000),
+            burst: Some(50),
+            echo_reply_rate: None,
+            unreachable_rate: None,
+            time_exceeded_rate: None,
+            disabled: Vec::new(),
+        }
+    }
+}
+
+impl FromStr for IcmpClass {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "echo_reply" => Ok(IcmpClass::EchoReply),
+            "unreachable" => Ok(IcmpClass::Unreachable),
+            "time_exceeded" => Ok(IcmpClass::TimeExceeded),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for IcmpClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IcmpClass::EchoReply => write!(f, "echo_reply"),
+            IcmpClass::Unreachable => write!(f, "unreachable"),
+            IcmpClass::TimeExceeded => write!(f, "time_exceeded"),
+        }
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::network::config::{
+        IcmpClass,
+        IcmpConfig,
+    };
+    use ::anyhow::Result;
+
+    /// Tests that tunables of [IcmpConfig] can be changed at runtime and are validated.
+    #[test]
+    fn test_icmp_config_tunables() -> Result<()> {
+        let mut config: IcmpConfig = IcmpConfig::default();
+        crate::ensure_eq!(config.get_rate(), Some(1000));
+        crate::ensure_eq!(config.get_burst(), 50);
+        crate::ensure_eq!(config.get_class_rate(IcmpClass::EchoReply), None);
+
+        config.set_tunable("burst", "0")?;
+        crate::ensure_eq!(config.get_burst(), 1000);
+        config.set_tunable("unreachable_rate", "1")?;
+        crate::ensure_eq!(config.get_class_rate(IcmpClass::Unreachable), Some(1));
+
+        config.set_tunable("disabled", "echo_reply, time_exceeded")?;
+        crate::ensure_eq!(config.is_disabled(IcmpClass::EchoReply), true);
+        crate::ensure_eq!(config.is_disabled(IcmpClass::Unreachable), false);
+        crate::ensure_eq!(config.get_tunable("disabled")?, "echo_reply,time_exceeded".to_string());
+
+        // Invalid classes leave the disabled classes as they were.
+        crate::ensure_eq!(
+            config.set_tunable("disabled", "redirect").unwrap_err().errno,
+            libc::EINVAL
+        );
+        crate::ensure_eq!(config.is_disabled(IcmpClass::TimeExceeded), true);
+        crate::ensure_eq!(config.set_tunable("echo_rate", "1").unwrap_err().errno, libc::ENOENT);
+
+        Ok(())
+    }
+}
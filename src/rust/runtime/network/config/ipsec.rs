@@ -0,0 +1,143 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    crypto::runtime::AeadKey,
+    fail::Fail,
+};
+use ::std::net::Ipv4Addr;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Smallest security parameter index that may be assigned to a security association. Lower values are reserved.
+pub const MIN_SPI: u32 = 256;
+
+/// Size of the salt that is combined with the explicit IV of every packet into the AEAD nonce.
+pub const SALT_SIZE: usize = 4;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Direction of the traffic that a security association protects.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum IpsecDirection {
+    /// Traffic that is received from the peer.
+    Inbound,
+    /// Traffic that is sent to the peer.
+    Outbound,
+}
+
+/// Static security association of transport-mode ESP, which protects the traffic between this host and a single peer in
+/// one direction.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SecurityAssociation {
+    /// Security parameter index, which the receiver uses to find the security association of a packet.
+    spi: u32,
+    /// Direction of the protected traffic.
+    direction: IpsecDirection,
+    /// Address of the peer.
+    peer: Ipv4Addr,
+    /// Key of the AEAD algorithm that protects the traffic.
+    key: AeadKey,
+    /// Salt of the AEAD nonce, which is keying material that is not part of the key.
+    salt: [u8; SALT_SIZE],
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl SecurityAssociation {
+    /// Creates a security association that protects the traffic to or from [peer] in [direction] with [key] and [salt].
+    pub fn new(
+        spi: u32,
+        direction: IpsecDirection,
+        peer: Ipv4Addr,
+        key: AeadKey,
+        salt: [u8; SALT_SIZE],
+    ) -> Result<Self, Fail> {
+        if spi < MIN_SPI {
+            let cause: String = format!("reserved security parameter index (spi={})", spi);
+            error!("new(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        if peer.is_unspecified() || peer.is_broadcast() || peer.is_multicast() {
+            let cause: String = format!("security associations only protect unicast traffic (peer={})", peer);
+            error!("new(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        Ok(Self {
+            spi,
+            direction,
+            peer,
+            key,
+            salt,
+        })
+    }
+
+    pub fn spi(&self) -> u32 {
+        self.spi
+    }
+
+    pub fn direction(&self) -> IpsecDirection {
+        self.direction
+    }
+
+    pub fn peer(&self) -> Ipv4Addr {
+        self.peer
+    }
+
+    pub fn key(&self) -> &AeadKey {
+        &self.key
+    }
+
+    pub fn salt(&self) -> [u8; SALT_SIZE] {
+        self.salt
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        IpsecDirection,
+        SecurityAssociation,
+        MIN_SPI,
+    };
+    use crate::runtime::crypto::runtime::AeadKey;
+    use ::anyhow::Result;
+    use ::std::net::Ipv4Addr;
+
+    /// Tests that reserved security parameter indexes and non-unicast peers are rejected.
+    #[test]
+    fn test_security_association_bounds() -> Result<()> {
+        let key: AeadKey = AeadKey::Aes128Gcm([1; 16]);
+        let peer: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 2);
+        crate::ensure_eq!(
+            SecurityAssociation::new(MIN_SPI - 1, IpsecDirection::Inbound, peer, key, [0; 4]).is_err(),
+            true
+        );
+        crate::ensure_eq!(
+            SecurityAssociation::new(MIN_SPI, IpsecDirection::Outbound, Ipv4Addr::BROADCAST, key, [0; 4]).is_err(),
+            true
+        );
+        let sa: SecurityAssociation = SecurityAssociation::new(MIN_SPI, IpsecDirection::Outbound, peer, key, [7; 4])?;
+        crate::ensure_eq!(sa.spi(), MIN_SPI);
+        crate::ensure_eq!(sa.direction(), IpsecDirection::Outbound);
+        crate::ensure_eq!(sa.peer(), peer);
+        crate::ensure_eq!(sa.key(), &key);
+        crate::ensure_eq!(sa.salt(), [7; 4]);
+
+        Ok(())
+    }
+}
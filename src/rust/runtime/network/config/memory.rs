@@ -0,0 +1,159 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    network::config::{
+        parse_tunable,
+        unknown_tunable,
+    },
+};
+use ::std::{
+    fmt,
+    str::FromStr,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// What the network stack does when buffering incoming or outgoing data would exceed the global memory limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemoryPolicy {
+    /// The data is dropped (incoming data) or refused with ENOBUFS (outgoing data).
+    DropNewest,
+    /// The data is dropped or refused as well, and the TCP connection that buffers the most data is aborted, so that
+    /// its memory is reclaimed.
+    AbortLargest,
+}
+
+/// Memory Configuration Descriptor
+#[derive(Clone, Debug)]
+pub struct MemoryConfig {
+    /// Largest Number of Bytes Buffered by All Sockets (Unlimited if not Set)
+    limit: Option<usize>,
+    /// What to do When the Limit is Reached
+    policy: MemoryPolicy,
+}
+
+//======================================================================================================================
+// Associate Functions
+//======================================================================================================================
+
+/// Associate functions for Memory Configuration Descriptor
+impl MemoryConfig {
+    /// Gets the largest number of bytes that all sockets buffer in the target [MemoryConfig], if any.
+    pub fn get_limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// Gets what to do when the limit is reached in the target [MemoryConfig].
+    pub fn get_policy(&self) -> MemoryPolicy {
+        self.policy
+    }
+
+    /// Sets the tunable [name] to [value] in the target [MemoryConfig]. A limit of zero means that there is none. Changes
+    /// apply right away, to all sockets.
+    pub fn set_tunable(&mut self, name: &str, value: &str) -> Result<(), Fail> {
+        match name {
+            "limit_bytes" => {
+                self.limit = match parse_tunable::<usize>(name, value)? {
+                    0 => None,
+                    bytes => Some(bytes),
+                };
+                Ok(())
+            },
+            "policy" => {
+                self.policy = parse_tunable::<MemoryPolicy>(name, value)?;
+                Ok(())
+            },
+            _ => unknown_tunable(name),
+        }
+    }
+
+    /// Gets the value of the tunable [name] in the target [MemoryConfig].
+    pub fn get_tunable(&self, name: &str) -> Result<String, Fail> {
+        match name {
+            "limit_bytes" => Ok(self.limit.unwrap_or(0).to_string()),
+            "policy" => Ok(self.policy.to_string()),
+            _ => unknown_tunable(name),
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+/// Default Trait Implementation for Memory Configuration Descriptor
+impl Default for MemoryConfig {
+    /// Creates a Memory Configuration Descriptor with the default values.
+    fn default() -> Self {
+        MemoryConfig {
+            limit: None,
+            policy: MemoryPolicy::DropNewest,
+        }
+    }
+}
+
+impl FromStr for MemoryPolicy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "drop_newest" => Ok(MemoryPolicy::DropNewest),
+            "abort_largest" => Ok(MemoryPolicy::AbortLargest),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for MemoryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemoryPolicy::DropNewest => write!(f, "drop_newest"),
+            MemoryPolicy::AbortLargest => write!(f, "abort_largest"),
+        }
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::network::config::{
+        MemoryConfig,
+        MemoryPolicy,
+    };
+    use ::anyhow::Result;
+
+    /// Tests that tunables of [MemoryConfig] can be changed at runtime and are validated.
+    #[test]
+    fn test_memory_config_tunables() -> Result<()> {
+        let mut config: MemoryConfig = MemoryConfig::default();
+        crate::ensure_eq!(config.get_limit(), None);
+        crate::ensure_eq!(config.get_policy(), MemoryPolicy::DropNewest);
+
+        config.set_tunable("limit_bytes", "65536")?;
+        crate::ensure_eq!(config.get_limit(), Some(65536));
+        config.set_tunable("policy", "abort_largest")?;
+        crate::ensure_eq!(config.get_tunable("policy")?, "abort_largest".to_string());
+        config.set_tunable("limit_bytes", "0")?;
+        crate::ensure_eq!(config.get_limit(), None);
+
+        crate::ensure_eq!(
+            config.set_tunable("policy", "drop_oldest").unwrap_err().errno,
+            libc::EINVAL
+        );
+        crate::ensure_eq!(config.set_tunable("limit", "1").unwrap_err().errno, libc::ENOENT);
+        crate::ensure_eq!(config.get_policy(), MemoryPolicy::AbortLargest);
+
+        Ok(())
+    }
+}
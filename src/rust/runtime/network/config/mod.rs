@@ -1,16 +1,85 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
+mod admission;
 mod arp;
+mod flows;
+mod icmp;
+mod ipsec;
+mod memory;
+mod route;
 mod tcp;
 mod udp;
 
+//==============================================================================
+// Imports
+//==============================================================================
+
+use crate::runtime::fail::Fail;
+use ::std::str::FromStr;
+
 //==============================================================================
 // Exports
 //==============================================================================
 
 pub use self::{
+    admission::{
+        AdmissionConfig,
+        BlockedSubnet,
+    },
     arp::ArpConfig,
+    flows::{
+        EvictionPolicy,
+        FlowConfig,
+    },
+    icmp::{
+        IcmpClass,
+        IcmpConfig,
+    },
+    ipsec::{
+        IpsecDirection,
+        SecurityAssociation,
+        MIN_SPI,
+        SALT_SIZE,
+    },
+    memory::{
+        MemoryConfig,
+        MemoryPolicy,
+    },
+    route::{
+        Route,
+        MAX_PREFIX_LEN,
+    },
     tcp::TcpConfig,
     udp::UdpConfig,
 };
+
+//==============================================================================
+// Standalone Functions
+//==============================================================================
+
+/// Parses [value], which is the new value of the tunable [name].
+pub(crate) fn parse_tunable<T: FromStr>(name: &str, value: &str) -> Result<T, Fail> {
+    match value.trim().parse::<T>() {
+        Ok(value) => Ok(value),
+        Err(_) => {
+            let cause: String = format!("invalid value for \"{}\" (value={:?})", name, value);
+            error!("parse_tunable(): {}", cause);
+            Err(Fail::new(libc::EINVAL, &cause))
+        },
+    }
+}
+
+/// Fails because [name] is not a known tunable.
+pub(crate) fn unknown_tunable<T>(name: &str) -> Result<T, Fail> {
+    let cause: String = format!("unknown tunable \"{}\"", name);
+    error!("unknown_tunable(): {}", cause);
+    Err(Fail::new(libc::ENOENT, &cause))
+}
+
+/// Fails because the new value of the tunable [name] is out of range.
+fn tunable_out_of_range<T>(name: &str) -> Result<T, Fail> {
+    let cause: String = format!("value for \"{}\" is out of range", name);
+    error!("tunable_out_of_range(): {}", cause);
+    Err(Fail::new(libc::ERANGE, &cause))
+}
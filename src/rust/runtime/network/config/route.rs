@@ -0,0 +1,171 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::fail::Fail;
+use ::std::{
+    fmt,
+    net::Ipv4Addr,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Longest prefix of an IPv4 route.
+pub const MAX_PREFIX_LEN: u8 = 32;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Route toward the IPv4 subnet [destination]/[prefix_len], which is either directly connected to a network interface or
+/// reached through a gateway on the link of that interface.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Route {
+    /// Address of the subnet, without host bits.
+    destination: Ipv4Addr,
+    /// Number of leading bits of the subnet address that addresses must share with it.
+    prefix_len: u8,
+    /// Address of the gateway that forwards the traffic to the subnet, if the subnet is not directly connected.
+    gateway: Option<Ipv4Addr>,
+    /// Index of the network interface that the traffic leaves through.
+    interface: usize,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Route {
+    /// Creates a route toward [destination]/[prefix_len] through [gateway], or to a directly connected subnet if
+    /// [gateway] is None. A prefix length of zero makes a default route. Traffic leaves through the primary interface,
+    /// unless another one is set with [Self::with_interface].
+    pub fn new(destination: Ipv4Addr, prefix_len: u8, gateway: Option<Ipv4Addr>) -> Result<Self, Fail> {
+        if prefix_len > MAX_PREFIX_LEN {
+            let cause: String = format!("prefix is too long (prefix_len={})", prefix_len);
+            error!("new(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        if u32::from(destination) & !Self::mask(prefix_len) != 0 {
+            let cause: String = format!("destination has host bits set ({}/{})", destination, prefix_len);
+            error!("new(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        if let Some(gateway) = gateway {
+            if gateway.is_unspecified() || gateway.is_broadcast() || gateway.is_multicast() {
+                let cause: String = format!("gateways must have a unicast address (gateway={})", gateway);
+                error!("new(): {}", cause);
+                return Err(Fail::new(libc::EINVAL, &cause));
+            }
+        }
+        Ok(Self {
+            destination,
+            prefix_len,
+            gateway,
+            interface: 0,
+        })
+    }
+
+    /// Makes the traffic of the target [Route] leave through the network interface with index [interface]. Routes
+    /// through interfaces that are not attached make their destinations unreachable.
+    pub fn with_interface(mut self, interface: usize) -> Self {
+        self.interface = interface;
+        self
+    }
+
+    pub fn destination(&self) -> Ipv4Addr {
+        self.destination
+    }
+
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    pub fn gateway(&self) -> Option<Ipv4Addr> {
+        self.gateway
+    }
+
+    pub fn interface(&self) -> usize {
+        self.interface
+    }
+
+    /// Checks whether [addr] belongs to the destination subnet of the route.
+    pub fn contains(&self, addr: Ipv4Addr) -> bool {
+        u32::from(addr) & Self::mask(self.prefix_len) == u32::from(self.destination)
+    }
+
+    /// Gets the address that traffic toward [addr] is sent to on the link: the gateway of the route, or else [addr]
+    /// itself.
+    pub fn next_hop(&self, addr: Ipv4Addr) -> Ipv4Addr {
+        self.gateway.unwrap_or(addr)
+    }
+
+    /// Gets the network mask of a prefix of [prefix_len] bits.
+    fn mask(prefix_len: u8) -> u32 {
+        match prefix_len {
+            0 => 0,
+            _ => u32::MAX << (MAX_PREFIX_LEN - prefix_len),
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl fmt::Display for Route {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.gateway {
+            Some(gateway) => write!(f, "{}/{} via {}", self.destination, self.prefix_len, gateway)?,
+            None => write!(f, "{}/{} (directly connected)", self.destination, self.prefix_len)?,
+        }
+        match self.interface {
+            0 => Ok(()),
+            interface => write!(f, " on interface {}", interface),
+        }
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::Route;
+    use ::anyhow::Result;
+    use ::std::net::Ipv4Addr;
+
+    /// Tests that routes are validated and match the addresses of their subnet.
+    #[test]
+    fn test_route() -> Result<()> {
+        let route: Route = Route::new(Ipv4Addr::new(10, 1, 0, 0), 16, Some(Ipv4Addr::new(192, 168, 1, 254)))?;
+        crate::ensure_eq!(route.contains(Ipv4Addr::new(10, 1, 200, 3)), true);
+        crate::ensure_eq!(route.contains(Ipv4Addr::new(10, 2, 0, 1)), false);
+        crate::ensure_eq!(
+            route.next_hop(Ipv4Addr::new(10, 1, 200, 3)),
+            Ipv4Addr::new(192, 168, 1, 254)
+        );
+
+        // Default routes match every address, and directly connected subnets are reached without a gateway.
+        let route: Route = Route::new(Ipv4Addr::UNSPECIFIED, 0, None)?;
+        crate::ensure_eq!(route.contains(Ipv4Addr::new(8, 8, 8, 8)), true);
+        crate::ensure_eq!(route.next_hop(Ipv4Addr::new(8, 8, 8, 8)), Ipv4Addr::new(8, 8, 8, 8));
+        let route: Route = Route::new(Ipv4Addr::new(192, 168, 1, 7), 32, None)?;
+        crate::ensure_eq!(route.contains(Ipv4Addr::new(192, 168, 1, 7)), true);
+        crate::ensure_eq!(route.contains(Ipv4Addr::new(192, 168, 1, 6)), false);
+
+        crate::ensure_eq!(Route::new(Ipv4Addr::new(10, 0, 0, 0), 33, None).is_err(), true);
+        crate::ensure_eq!(Route::new(Ipv4Addr::new(10, 0, 0, 1), 24, None).is_err(), true);
+        crate::ensure_eq!(
+            Route::new(Ipv4Addr::new(10, 0, 0, 0), 24, Some(Ipv4Addr::BROADCAST)).is_err(),
+            true
+        );
+
+        Ok(())
+    }
+}
@@ -5,12 +5,31 @@
 // Imports
 //==============================================================================
 
-use crate::runtime::network::consts::{
-    DEFAULT_MSS,
-    MAX_MSS,
-    MIN_MSS,
-    TCP_ACK_DELAY_TIMEOUT,
-    TCP_HANDSHAKE_TIMEOUT,
+use crate::runtime::{
+    fail::Fail,
+    network::{
+        config::{
+            parse_tunable,
+            tunable_out_of_range,
+            unknown_tunable,
+        },
+        consts::{
+            DEFAULT_MSS,
+            IPV4_TCP_HEADERS_SIZE,
+            MAX_GSO_SIZE,
+            MAX_MSS,
+            MAX_WINDOW_SCALE,
+            MIN_MSS,
+            TCP_ACK_DELAY_TIMEOUT,
+            TCP_HANDSHAKE_TIMEOUT,
+            TCP_RECEIVE_BUFFER_MAX_SIZE,
+            TCP_RTO_INITIAL,
+            TCP_RTO_MAX,
+            TCP_RTO_MIN,
+            TCP_SEND_BUFFER_SIZE,
+            TCP_SYN_COOKIE_THRESHOLD,
+        },
+    },
 };
 use ::std::time::Duration;
 
@@ -37,6 +56,28 @@ pub struct TcpConfig {
     rx_checksum_offload: bool,
     /// Offload Checksum to Hardware When Sending?
     tx_checksum_offload: bool,
+    /// Largest Segment Handed Down by the Sender (Segments Larger than the MSS are Split Before Transmission)
+    gso_max_size: Option<usize>,
+    /// Offload Segmentation to Hardware?
+    tso: bool,
+    /// Coalesce Received Segments?
+    gro: bool,
+    /// Initial Congestion Window in Segments (Derived from the MSS as in RFC 5681 if not Set)
+    initial_cwnd: Option<u32>,
+    /// Retransmission Timeout Before Any Round-Trip Time is Measured
+    rto_initial: Duration,
+    /// Lower Bound for the Retransmission Timeout
+    rto_min: Duration,
+    /// Upper Bound for the Retransmission Timeout
+    rto_max: Duration,
+    /// Bytes Accepted from the Application that may be Unsent or Unacknowledged
+    send_buffer_size: usize,
+    /// Grow the Receive Buffer with the Rate at which the Application Pops Data?
+    receive_buffer_autotuning: bool,
+    /// Largest Receive Buffer that Auto-Tuning Grows to
+    receive_buffer_max_size: usize,
+    /// Half-Open Connections a Listening Socket Keeps State for Before it Answers with SYN Cookies (Zero Disables Them)
+    syn_cookie_threshold: usize,
 }
 
 //==============================================================================
@@ -45,6 +86,13 @@ pub struct TcpConfig {
 
 /// Associate Functions for TCP Configuration Descriptor
 impl TcpConfig {
+    /// Computes the largest MSS that fits in [mtu], along with IPv4 and TCP headers without options.
+    pub fn mss_for_mtu(mtu: u16) -> usize {
+        (mtu as usize)
+            .saturating_sub(IPV4_TCP_HEADERS_SIZE)
+            .clamp(MIN_MSS, MAX_MSS)
+    }
+
     /// Creates a TCP Configuration Descriptor.
     pub fn new(
         advertised_mss: Option<usize>,
@@ -55,6 +103,9 @@ impl TcpConfig {
         ack_delay_timeout: Option<Duration>,
         rx_checksum_offload: Option<bool>,
         tx_checksum_offload: Option<bool>,
+        gso_max_size: Option<usize>,
+        tso: Option<bool>,
+        gro: Option<bool>,
     ) -> Self {
         let mut options = Self::default();
 
@@ -82,6 +133,15 @@ impl TcpConfig {
         if let Some(value) = tx_checksum_offload {
             options.tx_checksum_offload = value;
         }
+        if let Some(value) = gso_max_size {
+            options = options.set_gso_max_size(value);
+        }
+        if let Some(value) = tso {
+            options.tso = value;
+        }
+        if let Some(value) = gro {
+            options.gro = value;
+        }
 
         options
     }
@@ -126,6 +186,199 @@ impl TcpConfig {
         self.rx_checksum_offload
     }
 
+    /// Gets the largest segment that the sender hands down in the target [TcpConfig]. If not set, segments are never
+    /// larger than the MSS.
+    pub fn get_gso_max_size(&self) -> Option<usize> {
+        self.gso_max_size
+    }
+
+    /// Gets the hardware segmentation offload option in the target [TcpConfig]. If not set, segments larger than the
+    /// MSS are split in software right before they are transmitted.
+    pub fn get_tso(&self) -> bool {
+        self.tso
+    }
+
+    /// Gets the receive coalescing option in the target [TcpConfig]. If set, consecutive in-order segments of the same
+    /// flow that are received in a batch are merged before they reach the receiver.
+    pub fn get_gro(&self) -> bool {
+        self.gro
+    }
+
+    /// Gets the initial congestion window, in segments, in the target [TcpConfig]. If not set, it is derived from the
+    /// MSS as in RFC 5681.
+    pub fn get_initial_cwnd(&self) -> Option<u32> {
+        self.initial_cwnd
+    }
+
+    /// Gets the retransmission timeout that is used before any round-trip time is measured in the target [TcpConfig].
+    pub fn get_rto_initial(&self) -> Duration {
+        self.rto_initial
+    }
+
+    /// Gets the lower bound for the retransmission timeout in the target [TcpConfig].
+    pub fn get_rto_min(&self) -> Duration {
+        self.rto_min
+    }
+
+    /// Gets the upper bound for the retransmission timeout in the target [TcpConfig]. Connections raise it to the lower
+    /// bound if it is smaller.
+    pub fn get_rto_max(&self) -> Duration {
+        self.rto_max
+    }
+
+    /// Gets the size of the send buffer in the target [TcpConfig]. Pushes wait while the bytes that are not yet
+    /// acknowledged by the peer would exceed it.
+    pub fn get_send_buffer_size(&self) -> usize {
+        self.send_buffer_size
+    }
+
+    /// Gets the receive buffer auto-tuning option in the target [TcpConfig]. If set, the receive buffer of a connection
+    /// grows from the receive window size to twice the bytes that the application pops in a round-trip time.
+    pub fn get_receive_buffer_autotuning(&self) -> bool {
+        self.receive_buffer_autotuning
+    }
+
+    /// Gets the largest receive buffer that auto-tuning grows to in the target [TcpConfig]. Connections are further
+    /// limited to the largest window that they can advertise with the negotiated window scale.
+    pub fn get_receive_buffer_max_size(&self) -> usize {
+        self.receive_buffer_max_size
+    }
+
+    /// Gets the number of half-open connections that a listening socket keeps state for before it answers with SYN
+    /// cookies, if they are enabled in the target [TcpConfig].
+    pub fn get_syn_cookie_threshold(&self) -> Option<usize> {
+        match self.syn_cookie_threshold {
+            0 => None,
+            threshold => Some(threshold),
+        }
+    }
+
+    /// Gets a copy of the target [TcpConfig] for segments that are encapsulated in up to [overhead] bytes before they
+    /// reach the device. The device cannot compute checksums or split such segments, and the advertised MSS leaves
+    /// room for the encapsulation.
+    pub fn for_encapsulation(&self, overhead: usize) -> Self {
+        let mut config: Self = self.clone();
+        config.advertised_mss = self.advertised_mss.saturating_sub(overhead).max(MIN_MSS);
+        config.tx_checksum_offload = false;
+        config.tso = false;
+        config
+    }
+
+    /// Sets the tunable [name] to [value] in the target [TcpConfig]. Durations are given in the unit that ends the name
+    /// of the tunable. Changes only apply to connections that are created afterwards.
+    pub fn set_tunable(&mut self, name: &str, value: &str) -> Result<(), Fail> {
+        match name {
+            "handshake_retries" => match parse_tunable::<usize>(name, value)? {
+                0 => tunable_out_of_range(name),
+                retries => {
+                    self.handshake_retries = retries;
+                    Ok(())
+                },
+            },
+            "handshake_timeout_ms" => match parse_tunable::<u64>(name, value)? {
+                0 => tunable_out_of_range(name),
+                millis => {
+                    self.handshake_timeout = Duration::from_millis(millis);
+                    Ok(())
+                },
+            },
+            "receive_window_size" => match parse_tunable::<u16>(name, value)? {
+                0 => tunable_out_of_range(name),
+                size => {
+                    self.receive_window_size = size;
+                    Ok(())
+                },
+            },
+            "window_scale" => match parse_tunable::<u8>(name, value)? {
+                scale if scale as usize > MAX_WINDOW_SCALE => tunable_out_of_range(name),
+                scale => {
+                    self.window_scale = scale;
+                    Ok(())
+                },
+            },
+            "ack_delay_timeout_ms" => match parse_tunable::<u64>(name, value)? {
+                millis if millis > 500 => tunable_out_of_range(name),
+                millis => {
+                    self.ack_delay_timeout = Duration::from_millis(millis);
+                    Ok(())
+                },
+            },
+            "initial_cwnd" => match parse_tunable::<u32>(name, value)? {
+                0 => tunable_out_of_range(name),
+                segments => {
+                    self.initial_cwnd = Some(segments);
+                    Ok(())
+                },
+            },
+            "rto_initial_us" => match parse_tunable::<u64>(name, value)? {
+                0 => tunable_out_of_range(name),
+                micros => {
+                    self.rto_initial = Duration::from_micros(micros);
+                    Ok(())
+                },
+            },
+            "rto_min_us" => match parse_tunable::<u64>(name, value)? {
+                0 => tunable_out_of_range(name),
+                micros => {
+                    self.rto_min = Duration::from_micros(micros);
+                    Ok(())
+                },
+            },
+            "rto_max_us" => match parse_tunable::<u64>(name, value)? {
+                0 => tunable_out_of_range(name),
+                micros => {
+                    self.rto_max = Duration::from_micros(micros);
+                    Ok(())
+                },
+            },
+            "send_buffer_size" => match parse_tunable::<usize>(name, value)? {
+                0 => tunable_out_of_range(name),
+                size => {
+                    self.send_buffer_size = size;
+                    Ok(())
+                },
+            },
+            "receive_buffer_autotuning" => {
+                self.receive_buffer_autotuning = parse_tunable::<bool>(name, value)?;
+                Ok(())
+            },
+            "receive_buffer_max_size" => match parse_tunable::<usize>(name, value)? {
+                0 => tunable_out_of_range(name),
+                size => {
+                    self.receive_buffer_max_size = size;
+                    Ok(())
+                },
+            },
+            "syn_cookie_threshold" => {
+                self.syn_cookie_threshold = parse_tunable::<usize>(name, value)?;
+                Ok(())
+            },
+            _ => unknown_tunable(name),
+        }
+    }
+
+    /// Gets the value of the tunable [name] in the target [TcpConfig].
+    pub fn get_tunable(&self, name: &str) -> Result<String, Fail> {
+        match name {
+            "handshake_retries" => Ok(self.handshake_retries.to_string()),
+            "handshake_timeout_ms" => Ok(self.handshake_timeout.as_millis().to_string()),
+            "receive_window_size" => Ok(self.receive_window_size.to_string()),
+            "window_scale" => Ok(self.window_scale.to_string()),
+            "ack_delay_timeout_ms" => Ok(self.ack_delay_timeout.as_millis().to_string()),
+            // Zero stands for an initial congestion window that is derived from the MSS.
+            "initial_cwnd" => Ok(self.initial_cwnd.unwrap_or(0).to_string()),
+            "rto_initial_us" => Ok(self.rto_initial.as_micros().to_string()),
+            "rto_min_us" => Ok(self.rto_min.as_micros().to_string()),
+            "rto_max_us" => Ok(self.rto_max.as_micros().to_string()),
+            "send_buffer_size" => Ok(self.send_buffer_size.to_string()),
+            "receive_buffer_autotuning" => Ok(self.receive_buffer_autotuning.to_string()),
+            "receive_buffer_max_size" => Ok(self.receive_buffer_max_size.to_string()),
+            // Zero stands for SYN cookies being disabled.
+            "syn_cookie_threshold" => Ok(self.syn_cookie_threshold.to_string()),
+            _ => unknown_tunable(name),
+        }
+    }
+
     /// Sets the advertised maximum segment size in the target [TcpConfig].
     fn set_advertised_mss(mut self, value: usize) -> Self {
         assert!(value >= MIN_MSS);
@@ -167,6 +420,14 @@ impl TcpConfig {
         self.ack_delay_timeout = value;
         self
     }
+
+    /// Sets the largest segment that the sender hands down in the target [TcpConfig].
+    fn set_gso_max_size(mut self, value: usize) -> Self {
+        assert!(value >= MIN_MSS);
+        assert!(value <= MAX_GSO_SIZE);
+        self.gso_max_size = Some(value);
+        self
+    }
 }
 
 //==============================================================================
@@ -186,6 +447,17 @@ impl Default for TcpConfig {
             window_scale: 0,
             rx_checksum_offload: false,
             tx_checksum_offload: false,
+            gso_max_size: None,
+            tso: false,
+            gro: false,
+            initial_cwnd: None,
+            rto_initial: TCP_RTO_INITIAL,
+            rto_min: TCP_RTO_MIN,
+            rto_max: TCP_RTO_MAX,
+            send_buffer_size: TCP_SEND_BUFFER_SIZE,
+            receive_buffer_autotuning: true,
+            receive_buffer_max_size: TCP_RECEIVE_BUFFER_MAX_SIZE,
+            syn_cookie_threshold: TCP_SYN_COOKIE_THRESHOLD,
         }
     }
 }
@@ -198,7 +470,11 @@ impl Default for TcpConfig {
 mod tests {
     use crate::runtime::network::{
         config::TcpConfig,
-        consts::DEFAULT_MSS,
+        consts::{
+            DEFAULT_MSS,
+            MAX_MTU,
+            MIN_MSS,
+        },
     };
     use ::anyhow::Result;
     use ::std::time::Duration;
@@ -214,6 +490,109 @@ mod tests {
         crate::ensure_eq!(config.get_window_scale(), 0);
         crate::ensure_eq!(config.get_rx_checksum_offload(), false);
         crate::ensure_eq!(config.get_tx_checksum_offload(), false);
+        crate::ensure_eq!(config.get_gso_max_size(), None);
+        crate::ensure_eq!(config.get_tso(), false);
+        crate::ensure_eq!(config.get_gro(), false);
+        crate::ensure_eq!(config.get_initial_cwnd(), None);
+        crate::ensure_eq!(config.get_rto_initial(), Duration::from_secs(1));
+        crate::ensure_eq!(config.get_rto_min(), Duration::from_millis(100));
+        crate::ensure_eq!(config.get_rto_max(), Duration::from_secs(60));
+        crate::ensure_eq!(config.get_send_buffer_size(), 1024 * 1024);
+        crate::ensure_eq!(config.get_receive_buffer_autotuning(), true);
+        crate::ensure_eq!(config.get_receive_buffer_max_size(), 6 * 1024 * 1024);
+        crate::ensure_eq!(config.get_syn_cookie_threshold(), Some(128));
+
+        Ok(())
+    }
+
+    /// Tests that tunables of [TcpConfig] can be changed at runtime and are validated.
+    #[test]
+    fn test_tcp_config_tunables() -> Result<()> {
+        let mut config: TcpConfig = TcpConfig::default();
+        config.set_tunable("window_scale", "7")?;
+        crate::ensure_eq!(config.get_window_scale(), 7);
+        config.set_tunable("ack_delay_timeout_ms", "40")?;
+        crate::ensure_eq!(config.get_ack_delay_timeout(), Duration::from_millis(40));
+        crate::ensure_eq!(config.get_tunable("handshake_timeout_ms")?, "3000".to_string());
+
+        crate::ensure_eq!(
+            config.set_tunable("window_scale", "15").unwrap_err().errno,
+            libc::ERANGE
+        );
+        crate::ensure_eq!(
+            config.set_tunable("ack_delay_timeout_ms", "501").unwrap_err().errno,
+            libc::ERANGE
+        );
+        crate::ensure_eq!(
+            config.set_tunable("receive_window_size", "70000").unwrap_err().errno,
+            libc::EINVAL
+        );
+        crate::ensure_eq!(
+            config.set_tunable("advertised_mss", "1000").unwrap_err().errno,
+            libc::ENOENT
+        );
+        crate::ensure_eq!(config.get_window_scale(), 7);
+
+        config.set_tunable("initial_cwnd", "10")?;
+        crate::ensure_eq!(config.get_initial_cwnd(), Some(10));
+        config.set_tunable("rto_min_us", "200")?;
+        config.set_tunable("rto_max_us", "10000")?;
+        crate::ensure_eq!(config.get_rto_min(), Duration::from_micros(200));
+        crate::ensure_eq!(config.get_tunable("rto_max_us")?, "10000".to_string());
+        crate::ensure_eq!(config.set_tunable("rto_min_us", "0").unwrap_err().errno, libc::ERANGE);
+        crate::ensure_eq!(config.set_tunable("initial_cwnd", "0").unwrap_err().errno, libc::ERANGE);
+        config.set_tunable("send_buffer_size", "65536")?;
+        crate::ensure_eq!(config.get_send_buffer_size(), 65536);
+        crate::ensure_eq!(
+            config.set_tunable("send_buffer_size", "0").unwrap_err().errno,
+            libc::ERANGE
+        );
+        config.set_tunable("receive_buffer_autotuning", "false")?;
+        crate::ensure_eq!(config.get_receive_buffer_autotuning(), false);
+        crate::ensure_eq!(
+            config.set_tunable("receive_buffer_autotuning", "1").unwrap_err().errno,
+            libc::EINVAL
+        );
+        config.set_tunable("syn_cookie_threshold", "0")?;
+        crate::ensure_eq!(config.get_syn_cookie_threshold(), None);
+        crate::ensure_eq!(config.get_tunable("syn_cookie_threshold")?, "0".to_string());
+
+        Ok(())
+    }
+
+    /// Tests that the MSS derived from an MTU leaves room for the headers and stays within bounds.
+    #[test]
+    fn test_tcp_config_mss_for_mtu() -> Result<()> {
+        crate::ensure_eq!(TcpConfig::mss_for_mtu(1500), 1460);
+        crate::ensure_eq!(TcpConfig::mss_for_mtu(9000), 8960);
+        crate::ensure_eq!(TcpConfig::mss_for_mtu(0), MIN_MSS);
+        crate::ensure_eq!(TcpConfig::mss_for_mtu(MAX_MTU), MAX_MTU as usize - 40);
+
+        Ok(())
+    }
+
+    /// Tests that encapsulated segments are not offloaded and leave room for the encapsulation.
+    #[test]
+    fn test_tcp_config_for_encapsulation() -> Result<()> {
+        let config: TcpConfig = TcpConfig::new(
+            Some(1460),
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(true),
+            Some(true),
+            None,
+            Some(true),
+            None,
+        );
+        let encapsulated: TcpConfig = config.for_encapsulation(37);
+        crate::ensure_eq!(encapsulated.get_advertised_mss(), 1423);
+        crate::ensure_eq!(encapsulated.get_rx_checksum_offload(), true);
+        crate::ensure_eq!(encapsulated.get_tx_checksum_offload(), false);
+        crate::ensure_eq!(encapsulated.get_tso(), false);
+        crate::ensure_eq!(config.for_encapsulation(DEFAULT_MSS).get_advertised_mss(), MIN_MSS);
 
         Ok(())
     }
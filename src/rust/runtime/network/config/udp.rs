@@ -1,6 +1,25 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    network::{
+        config::{
+            parse_tunable,
+            tunable_out_of_range,
+            unknown_tunable,
+        },
+        consts::{
+            UDP_RECV_QUEUE_MAX_BYTES,
+            UDP_RECV_QUEUE_MAX_DATAGRAMS,
+        },
+    },
+};
+
 //======================================================================================================================
 // Structures
 //======================================================================================================================
@@ -8,10 +27,14 @@
 /// UDP Configuration Descriptor
 #[derive(Clone, Debug)]
 pub struct UdpConfig {
-    /// Offload Checksum to Hardware When Receiving?
-    rx_checksum: bool,
     /// Offload Checksum to Hardware When Sending?
     tx_checksum: bool,
+    /// Generate Checksums for Outgoing Datagrams? (Checksums of Incoming Datagrams are Always Verified in Software)
+    checksum_generation: bool,
+    /// Largest Number of Datagrams Queued on a Socket Before Incoming Ones are Dropped
+    recv_queue_max_datagrams: usize,
+    /// Largest Number of Payload Bytes Queued on a Socket Before Incoming Datagrams are Dropped
+    recv_queue_max_bytes: usize,
 }
 
 //======================================================================================================================
@@ -21,30 +44,77 @@ pub struct UdpConfig {
 /// Associate functions for UDP Configuration Descriptor
 impl UdpConfig {
     /// Creates a UDP Configuration Descriptor.
-    pub fn new(rx_checksum: Option<bool>, tx_checksum: Option<bool>) -> Self {
+    pub fn new(tx_checksum: Option<bool>) -> Self {
         let mut config = Self::default();
-        if let Some(rx_checksum) = rx_checksum {
-            config.set_rx_checksum_offload(rx_checksum);
-        }
         if let Some(tx_checksum) = tx_checksum {
             config.set_tx_checksum_offload(tx_checksum);
         }
         config
     }
 
-    /// Gets the RX hardware checksum offload option in the target [UdpConfig].
-    pub fn get_rx_checksum_offload(&self) -> bool {
-        self.rx_checksum
-    }
-
-    /// Gets the XX hardware checksum offload option in the target [UdpConfig].
+    /// Gets the TX hardware checksum offload option in the target [UdpConfig].
     pub fn get_tx_checksum_offload(&self) -> bool {
         self.tx_checksum
     }
 
-    /// Sets the RX hardware checksum offload option in the target [UdpConfig].
-    fn set_rx_checksum_offload(&mut self, rx_checksum: bool) {
-        self.rx_checksum = rx_checksum;
+    /// Gets the checksum generation option in the target [UdpConfig]. If not set, outgoing datagrams carry no checksum,
+    /// which RFC 768 allows over IPv4, unless their socket asks for one.
+    pub fn get_checksum_generation(&self) -> bool {
+        self.checksum_generation
+    }
+
+    /// Gets the largest number of datagrams that a socket queues in the target [UdpConfig].
+    pub fn get_recv_queue_max_datagrams(&self) -> usize {
+        self.recv_queue_max_datagrams
+    }
+
+    /// Gets the largest number of payload bytes that a socket queues in the target [UdpConfig].
+    pub fn get_recv_queue_max_bytes(&self) -> usize {
+        self.recv_queue_max_bytes
+    }
+
+    /// Sets the tunable [name] to [value] in the target [UdpConfig]. Changes only apply to sockets that are created
+    /// afterwards.
+    pub fn set_tunable(&mut self, name: &str, value: &str) -> Result<(), Fail> {
+        match name {
+            "recv_queue_max_datagrams" => match parse_tunable::<usize>(name, value)? {
+                0 => tunable_out_of_range(name),
+                datagrams => {
+                    self.recv_queue_max_datagrams = datagrams;
+                    Ok(())
+                },
+            },
+            "recv_queue_max_bytes" => match parse_tunable::<usize>(name, value)? {
+                0 => tunable_out_of_range(name),
+                bytes => {
+                    self.recv_queue_max_bytes = bytes;
+                    Ok(())
+                },
+            },
+            "checksum_generation" => {
+                self.checksum_generation = parse_tunable::<bool>(name, value)?;
+                Ok(())
+            },
+            _ => unknown_tunable(name),
+        }
+    }
+
+    /// Gets the value of the tunable [name] in the target [UdpConfig].
+    pub fn get_tunable(&self, name: &str) -> Result<String, Fail> {
+        match name {
+            "recv_queue_max_datagrams" => Ok(self.recv_queue_max_datagrams.to_string()),
+            "recv_queue_max_bytes" => Ok(self.recv_queue_max_bytes.to_string()),
+            "checksum_generation" => Ok(self.checksum_generation.to_string()),
+            _ => unknown_tunable(name),
+        }
+    }
+
+    /// Gets a copy of the target [UdpConfig] for datagrams that are encapsulated before they reach the device, which
+    /// then cannot compute their checksums.
+    pub fn for_encapsulation(&self) -> Self {
+        let mut config: Self = self.clone();
+        config.set_tx_checksum_offload(false);
+        config
     }
 
     /// Sets the TX hardware checksum offload option in the target [UdpConfig].
@@ -62,8 +132,10 @@ impl Default for UdpConfig {
     /// Creates a UDP Configuration Descriptor with the default values.
     fn default() -> Self {
         UdpConfig {
-            rx_checksum: false,
             tx_checksum: false,
+            checksum_generation: true,
+            recv_queue_max_datagrams: UDP_RECV_QUEUE_MAX_DATAGRAMS,
+            recv_queue_max_bytes: UDP_RECV_QUEUE_MAX_BYTES,
         }
     }
 }
@@ -81,8 +153,10 @@ mod tests {
     #[test]
     fn test_udp_config_default() -> Result<()> {
         let config: UdpConfig = UdpConfig::default();
-        crate::ensure_eq!(config.get_rx_checksum_offload(), false);
         crate::ensure_eq!(config.get_tx_checksum_offload(), false);
+        crate::ensure_eq!(config.get_checksum_generation(), true);
+        crate::ensure_eq!(config.get_recv_queue_max_datagrams(), 1024);
+        crate::ensure_eq!(config.get_recv_queue_max_bytes(), 1024 * 1024);
 
         Ok(())
     }
@@ -90,10 +164,33 @@ mod tests {
     /// Tests custom instantiation for [UdpConfig].
     #[test]
     fn test_udp_config_custom() -> Result<()> {
-        let config: UdpConfig = UdpConfig::new(Some(true), Some(true));
-        crate::ensure_eq!(config.get_rx_checksum_offload(), true);
+        let config: UdpConfig = UdpConfig::new(Some(true));
         crate::ensure_eq!(config.get_tx_checksum_offload(), true);
 
         Ok(())
     }
+
+    /// Tests that tunables of [UdpConfig] can be changed at runtime and are validated.
+    #[test]
+    fn test_udp_config_tunables() -> Result<()> {
+        let mut config: UdpConfig = UdpConfig::default();
+        config.set_tunable("recv_queue_max_datagrams", "16")?;
+        crate::ensure_eq!(config.get_recv_queue_max_datagrams(), 16);
+        config.set_tunable("recv_queue_max_bytes", "65536")?;
+        crate::ensure_eq!(config.get_tunable("recv_queue_max_bytes")?, "65536".to_string());
+        config.set_tunable("checksum_generation", "false")?;
+        crate::ensure_eq!(config.get_checksum_generation(), false);
+
+        crate::ensure_eq!(
+            config.set_tunable("recv_queue_max_datagrams", "0").unwrap_err().errno,
+            libc::ERANGE
+        );
+        crate::ensure_eq!(
+            config.set_tunable("tx_checksum", "true").unwrap_err().errno,
+            libc::ENOENT
+        );
+        crate::ensure_eq!(config.get_recv_queue_max_datagrams(), 16);
+
+        Ok(())
+    }
 }
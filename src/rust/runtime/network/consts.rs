@@ -20,6 +20,29 @@ pub const MIN_MSS: usize = FALLBACK_MSS;
 /// Maximum MSS Parameter for TCP
 pub const MAX_MSS: usize = u16::max_value() as usize;
 
+/// Size of IPv4 and TCP headers without options.
+pub const IPV4_TCP_HEADERS_SIZE: usize = 40;
+
+/// Default MTU, which is the one of Ethernet.
+pub const DEFAULT_MTU: u16 = 1500;
+
+/// Minimum MTU. This fits a TCP segment of [MIN_MSS] bytes, along with IPv4 and TCP headers without options.
+pub const MIN_MTU: u16 = (MIN_MSS + IPV4_TCP_HEADERS_SIZE) as u16;
+
+/// Maximum MTU, which is the one of jumbo frames.
+pub const MAX_MTU: u16 = 9216;
+
+/// Largest Ethernet frame that is exchanged with a network runtime, with an IEEE 802.1Q tag but without the frame
+/// check sequence.
+pub const MAX_FRAME_SIZE: usize = 14 + 4 + MAX_MTU as usize;
+
+/// Largest TCP segment that is handed to a network runtime to be split into segments of MSS size. With the largest
+/// IPv4 and TCP headers, this keeps the total length of the IPv4 datagram within 16 bits.
+pub const MAX_GSO_SIZE: usize = u16::MAX as usize - 60 - 60;
+
+/// Default time to live of IPv4 datagrams.
+pub const DEFAULT_IPV4_TTL: u8 = 255;
+
 /// Delay timeout for TCP ACKs.
 /// See: https://www.rfc-editor.org/rfc/rfc5681#section-4.2
 pub const TCP_ACK_DELAY_TIMEOUT: Duration = Duration::from_millis(500);
@@ -27,16 +50,45 @@ pub const TCP_ACK_DELAY_TIMEOUT: Duration = Duration::from_millis(500);
 /// Handshake timeout for tcp.
 pub const TCP_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(3);
 
+/// Initial retransmission timeout for TCP, before any round-trip time is measured.
+/// See: https://www.rfc-editor.org/rfc/rfc6298#section-2.1
+pub const TCP_RTO_INITIAL: Duration = Duration::from_secs(1);
+
+/// Lower bound for the retransmission timeout of TCP. RFC 6298 suggests 1 second, but this is 1/10 of a second.
+/// See: https://www.rfc-editor.org/rfc/rfc6298#section-2.4
+pub const TCP_RTO_MIN: Duration = Duration::from_millis(100);
+
+/// Upper bound for the retransmission timeout of TCP.
+/// See: https://www.rfc-editor.org/rfc/rfc6298#section-2.5
+pub const TCP_RTO_MAX: Duration = Duration::from_secs(60);
+
+/// Number of bytes that a TCP connection accepts from the application before they are acknowledged by the peer.
+pub const TCP_SEND_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Largest receive buffer that auto-tuning grows a TCP connection to. This matches the default of Linux.
+pub const TCP_RECEIVE_BUFFER_MAX_SIZE: usize = 6 * 1024 * 1024;
+
+/// Number of half-open connections that a listening TCP socket keeps state for before answering SYN segments with
+/// SYN cookies.
+pub const TCP_SYN_COOKIE_THRESHOLD: usize = 128;
+
+/// Largest number of datagrams that a UDP socket queues before it drops incoming ones.
+pub const UDP_RECV_QUEUE_MAX_DATAGRAMS: usize = 1024;
+
+/// Largest number of payload bytes that a UDP socket queues before it drops incoming datagrams.
+pub const UDP_RECV_QUEUE_MAX_BYTES: usize = 1024 * 1024;
+
 /// Default MSS Parameter for TCP
 ///
 /// TODO: Auto-Discovery MTU Size
 pub const DEFAULT_MSS: usize = 1450;
 
-/// Length of a [crate::memory::DemiBuffer] batch.
-///
-/// TODO: This Should be Generic
+/// Default length of a [crate::memory::DemiBuffer] batch that is received at once.
 pub const RECEIVE_BATCH_SIZE: usize = 4;
 
+/// Maximum length of a batch of packets that is exchanged with a network runtime at once.
+pub const MAX_BATCH_SIZE: usize = 32;
+
 /// Maximum local and remote window scaling factor.
 /// See: RFC 1323, Section 2.3.
 pub const MAX_WINDOW_SCALE: usize = 14;
@@ -0,0 +1,266 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::fail::Fail;
+use ::std::{
+    collections::VecDeque,
+    net::SocketAddrV4,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Largest number of records that the error queue of a socket holds. Older records are discarded first.
+pub const ERROR_QUEUE_CAPACITY: usize = 64;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Reasons for which the network stack drops incoming packets. The discriminants are part of the C API.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum DropReason {
+    /// The packet could not be parsed.
+    Malformed = 0,
+    /// The checksum of the packet does not match its contents.
+    BadChecksum = 1,
+    /// The time to live of the packet expired.
+    TtlExpired = 2,
+    /// The packet uses a feature that the network stack does not support (e.g. IPv4 fragmentation).
+    Unsupported = 3,
+    /// The fragments of the packet did not arrive in time to be reassembled.
+    ReassemblyTimeout = 4,
+    /// The packet is addressed to another host.
+    AddressMismatch = 5,
+    /// The frame belongs to another VLAN.
+    VlanMismatch = 6,
+    /// The source address of the packet is not a valid unicast address.
+    InvalidAddress = 7,
+    /// No socket is bound to the destination of the packet.
+    NoSocket = 8,
+    /// The segment does not fit in the state of its connection (e.g. it is outside of the receive window).
+    InvalidSegment = 9,
+    /// The listening socket has as many pending connections as its backlog allows.
+    BacklogFull = 10,
+    /// The receive queue of the socket is full.
+    QueueFull = 11,
+    /// The receive filter of the socket rejected the packet.
+    Filtered = 12,
+    /// Buffering the packet would exceed the memory limit of the network stack.
+    MemoryLimit = 13,
+    /// The admission control of the network stack refused the source of the packet, which is blocked or exceeded its
+    /// rate.
+    Admission = 14,
+    /// The flow table of the network stack is full and has no room for the flow of the packet.
+    FlowLimit = 15,
+    /// The signature of the segment does not match the key that is shared with its sender, or the segment is missing
+    /// or carries an unexpected signature.
+    BadSignature = 16,
+}
+
+/// Number of packets that were dropped for each reason.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DropStats {
+    counts: [u64; DropReason::COUNT],
+}
+
+/// A packet that was dropped on its way to a socket.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DropRecord {
+    /// Why the packet was dropped.
+    pub reason: DropReason,
+    /// Address of the sender of the packet, if it is known.
+    pub remote: Option<SocketAddrV4>,
+    /// Length of the payload of the packet, in bytes.
+    pub len: usize,
+}
+
+/// Drops that are attributed to a socket: their counts and, if the application enabled it, an error queue with the
+/// most recent ones.
+#[derive(Debug, Default)]
+pub struct DropQueue {
+    stats: DropStats,
+    records: Option<VecDeque<DropRecord>>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl DropReason {
+    /// All reasons, in the order of their discriminants.
+    pub const ALL: [DropReason; Self::COUNT] = [
+        DropReason::Malformed,
+        DropReason::BadChecksum,
+        DropReason::TtlExpired,
+        DropReason::Unsupported,
+        DropReason::ReassemblyTimeout,
+        DropReason::AddressMismatch,
+        DropReason::VlanMismatch,
+        DropReason::InvalidAddress,
+        DropReason::NoSocket,
+        DropReason::InvalidSegment,
+        DropReason::BacklogFull,
+        DropReason::QueueFull,
+        DropReason::Filtered,
+        DropReason::MemoryLimit,
+        DropReason::Admission,
+        DropReason::FlowLimit,
+        DropReason::BadSignature,
+    ];
+
+    /// Number of reasons.
+    pub const COUNT: usize = 17;
+
+    /// Classifies the error that a parser returned for a packet. Parsers fail with ENOTSUP on features that are not
+    /// supported and with EBADMSG otherwise, in which case checksum and time to live errors are told apart by cause.
+    pub fn from_parse_error(e: &Fail) -> Self {
+        if e.errno == libc::ENOTSUP {
+            DropReason::Unsupported
+        } else if e.cause.contains("checksum") {
+            DropReason::BadChecksum
+        } else if e.cause.contains("too old") {
+            DropReason::TtlExpired
+        } else {
+            DropReason::Malformed
+        }
+    }
+}
+
+impl DropStats {
+    /// Counts a packet that was dropped for [reason].
+    pub fn record(&mut self, reason: DropReason) {
+        self.counts[reason as usize] += 1;
+    }
+
+    /// Gets the number of packets that were dropped for [reason].
+    pub fn get(&self, reason: DropReason) -> u64 {
+        self.counts[reason as usize]
+    }
+
+    /// Gets the number of packets that were dropped for any reason.
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Gets the counts, indexed by the discriminants of the reasons.
+    pub fn counts(&self) -> &[u64; DropReason::COUNT] {
+        &self.counts
+    }
+}
+
+impl DropQueue {
+    /// Enables or disables the error queue. Disabling it discards the records that it holds.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        match (enabled, self.records.is_some()) {
+            (true, false) => self.records = Some(VecDeque::with_capacity(ERROR_QUEUE_CAPACITY)),
+            (false, true) => self.records = None,
+            _ => (),
+        }
+    }
+
+    /// Counts a packet from [remote] with [len] bytes of payload that was dropped for [reason], and queues a record of
+    /// it if the error queue is enabled.
+    pub fn record(&mut self, reason: DropReason, remote: Option<SocketAddrV4>, len: usize) {
+        self.stats.record(reason);
+        if let Some(records) = self.records.as_mut() {
+            if records.len() == ERROR_QUEUE_CAPACITY {
+                records.pop_front();
+            }
+            records.push_back(DropRecord { reason, remote, len });
+        }
+    }
+
+    /// Pops the oldest record from the error queue.
+    pub fn pop(&mut self) -> Option<DropRecord> {
+        self.records.as_mut()?.pop_front()
+    }
+
+    /// Gets the number of packets that were dropped for each reason.
+    pub fn stats(&self) -> DropStats {
+        self.stats
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        DropQueue,
+        DropReason,
+        DropRecord,
+        ERROR_QUEUE_CAPACITY,
+    };
+    use crate::runtime::fail::Fail;
+    use ::anyhow::Result;
+    use ::std::net::{
+        Ipv4Addr,
+        SocketAddrV4,
+    };
+
+    /// Tests that reasons are listed in the order of their discriminants and that parse errors are classified.
+    #[test]
+    fn test_drop_reasons() -> Result<()> {
+        for (i, reason) in DropReason::ALL.iter().enumerate() {
+            crate::ensure_eq!(*reason as usize, i);
+        }
+        let classify = |errno: i32, cause: &str| DropReason::from_parse_error(&Fail::new(errno, cause));
+        crate::ensure_eq!(
+            classify(libc::EBADMSG, "UDP checksum mismatch"),
+            DropReason::BadChecksum
+        );
+        crate::ensure_eq!(classify(libc::EBADMSG, "ipv4 datagram too old"), DropReason::TtlExpired);
+        crate::ensure_eq!(
+            classify(libc::ENOTSUP, "ipv4 fragmentation is not supported"),
+            DropReason::Unsupported
+        );
+        crate::ensure_eq!(classify(libc::EBADMSG, "TCP segment too small"), DropReason::Malformed);
+        Ok(())
+    }
+
+    /// Tests that drops are always counted, but only queued while the error queue is enabled.
+    #[test]
+    fn test_drop_queue() -> Result<()> {
+        let remote: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 80);
+        let mut queue: DropQueue = DropQueue::default();
+        queue.record(DropReason::Filtered, Some(remote), 10);
+        crate::ensure_eq!(queue.pop(), None);
+
+        queue.set_enabled(true);
+        queue.record(DropReason::QueueFull, Some(remote), 20);
+        crate::ensure_eq!(
+            queue.pop(),
+            Some(DropRecord {
+                reason: DropReason::QueueFull,
+                remote: Some(remote),
+                len: 20,
+            })
+        );
+        crate::ensure_eq!(queue.pop(), None);
+
+        // The oldest records are discarded when the queue overflows.
+        for len in 0..(ERROR_QUEUE_CAPACITY + 1) {
+            queue.record(DropReason::InvalidSegment, None, len);
+        }
+        crate::ensure_eq!(queue.pop().map(|record| record.len), Some(1));
+
+        queue.set_enabled(false);
+        crate::ensure_eq!(queue.pop(), None);
+        crate::ensure_eq!(queue.stats().get(DropReason::Filtered), 1);
+        crate::ensure_eq!(
+            queue.stats().get(DropReason::InvalidSegment),
+            ERROR_QUEUE_CAPACITY as u64 + 1
+        );
+        crate::ensure_eq!(queue.stats().total(), ERROR_QUEUE_CAPACITY as u64 + 3);
+        Ok(())
+    }
+}
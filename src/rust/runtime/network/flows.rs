@@ -0,0 +1,407 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Table of the flows that a network stack tracks, so that the state that connections and peers take up is bounded and
+//! observable.
+//!
+//! TCP connections open a [FlowEntry] when they are established, which keeps their flow in the table until it is
+//! dropped. UDP flows have no owner: each datagram that is received tracks the flow of its source and destination, and
+//! the flow stays in the table until it is evicted. For this reason, UDP flows are only tracked when the table has a
+//! limit.
+//!
+//! Once the table is full, flows that were idle for longer than the idle timeout are evicted first. If there is still
+//! no room, [EvictionPolicy::EvictLru] evicts the flow that was used the least recently, and [EvictionPolicy::Refuse]
+//! refuses the new flow. Evicted TCP connections are asked to abort through [FlowEntry::watch_evict].
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    collections::async_value::SharedAsyncValue,
+    runtime::{
+        fail::Fail,
+        network::config::{
+            EvictionPolicy,
+            FlowConfig,
+        },
+        SharedObject,
+    },
+};
+use ::std::{
+    collections::HashMap,
+    net::SocketAddrV4,
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    time::Instant,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Protocols whose flows are tracked.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum FlowProtocol {
+    Tcp,
+    Udp,
+}
+
+/// Identifies a flow.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct FlowKey {
+    pub protocol: FlowProtocol,
+    pub local: SocketAddrV4,
+    pub remote: SocketAddrV4,
+}
+
+/// State of a flow in the table.
+struct Flow {
+    id: u64,
+    last_used: Instant,
+    /// Set to ask the owner of the flow to abort, if it has one.
+    evict: Option<SharedAsyncValue<bool>>,
+}
+
+/// Flows that a network stack tracks.
+pub struct FlowTable {
+    config: FlowConfig,
+    flows: HashMap<FlowKey, Flow>,
+    next_id: u64,
+    /// Number of flows that were evicted, and of new flows that were refused because the table was full.
+    evictions: u64,
+    refusals: u64,
+}
+
+#[derive(Clone)]
+pub struct SharedFlowTable(SharedObject<FlowTable>);
+
+/// Flow that a TCP connection owns. The flow leaves the table when the entry is dropped.
+pub struct FlowEntry {
+    key: FlowKey,
+    id: u64,
+    table: SharedFlowTable,
+    evict: SharedAsyncValue<bool>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl FlowKey {
+    pub fn new(protocol: FlowProtocol, local: SocketAddrV4, remote: SocketAddrV4) -> Self {
+        Self {
+            protocol,
+            local,
+            remote,
+        }
+    }
+}
+
+impl SharedFlowTable {
+    /// Opens an entry for the flow [key] of a new connection at [now]. A flow that is already in the table with the same
+    /// key is taken over. Fails with ENOBUFS if the table is full and its policy refuses new flows.
+    pub fn open(&mut self, now: Instant, key: FlowKey) -> Result<FlowEntry, Fail> {
+        if !self.flows.contains_key(&key) && !self.make_room(now) {
+            let cause: String = format!("flow table is full (key={:?})", key);
+            error!("open(): {}", cause);
+            return Err(Fail::new(libc::ENOBUFS, &cause));
+        }
+        let id: u64 = self.next_id;
+        self.next_id += 1;
+        let evict: SharedAsyncValue<bool> = SharedAsyncValue::new(false);
+        self.flows.insert(
+            key,
+            Flow {
+                id,
+                last_used: now,
+                evict: Some(evict.clone()),
+            },
+        );
+        Ok(FlowEntry {
+            key,
+            id,
+            table: self.clone(),
+            evict,
+        })
+    }
+
+    /// Tracks the flow [key] of a datagram that was received at [now]. Returns false if the flow is new and the table
+    /// has no room for it, in which case the datagram should be dropped. Flows are only tracked when the table has a
+    /// limit.
+    pub fn track(&mut self, now: Instant, key: FlowKey) -> bool {
+        if self.config.get_max_entries().is_none() {
+            return true;
+        }
+        if let Some(flow) = self.flows.get_mut(&key) {
+            flow.last_used = now;
+            return true;
+        }
+        if !self.make_room(now) {
+            return false;
+        }
+        let id: u64 = self.next_id;
+        self.next_id += 1;
+        self.flows.insert(
+            key,
+            Flow {
+                id,
+                last_used: now,
+                evict: None,
+            },
+        );
+        true
+    }
+
+    /// Gets the number of flows in the table.
+    pub fn get_entries(&self) -> usize {
+        self.flows.len()
+    }
+
+    /// Sets the tunable [name] ("max_entries", "idle_timeout_ms" or "policy") to [value].
+    pub fn set_tunable(&mut self, name: &str, value: &str) -> Result<(), Fail> {
+        self.config.set_tunable(name, value)
+    }
+
+    /// Gets the value of the tunable [name]. Besides those of [FlowConfig], "entries" is the number of flows in the
+    /// table, "evictions" the number of flows that were evicted, and "refusals" the number of new flows that were
+    /// refused because the table was full.
+    pub fn get_tunable(&self, name: &str) -> Result<String, Fail> {
+        match name {
+            "entries" => Ok(self.flows.len().to_string()),
+            "evictions" => Ok(self.evictions.to_string()),
+            "refusals" => Ok(self.refusals.to_string()),
+            _ => self.config.get_tunable(name),
+        }
+    }
+
+    /// Makes room for a new flow at [now], evicting flows as the policy allows. Returns false, and counts a refusal, if
+    /// there is still no room.
+    fn make_room(&mut self, now: Instant) -> bool {
+        let max_entries: usize = match self.config.get_max_entries() {
+            Some(max_entries) => max_entries,
+            None => return true,
+        };
+        if self.flows.len() < max_entries {
+            return true;
+        }
+
+        // Evict the flows that were idle for too long first.
+        if let Some(idle_timeout) = self.config.get_idle_timeout() {
+            let idle: Vec<FlowKey> = self
+                .flows
+                .iter()
+                .filter(|(_, flow)| now.saturating_duration_since(flow.last_used) >= idle_timeout)
+                .map(|(key, _)| *key)
+                .collect();
+            for key in idle {
+                self.evict(key);
+            }
+            if self.flows.len() < max_entries {
+                return true;
+            }
+        }
+
+        if self.config.get_policy() == EvictionPolicy::EvictLru {
+            let lru: Option<FlowKey> = self
+                .flows
+                .iter()
+                .min_by_key(|(_, flow)| flow.last_used)
+                .map(|(key, _)| *key);
+            if let Some(key) = lru {
+                self.evict(key);
+                return true;
+            }
+        }
+        self.refusals += 1;
+        false
+    }
+
+    /// Removes the flow [key] from the table, asking its owner to abort.
+    fn evict(&mut self, key: FlowKey) {
+        if let Some(flow) = self.flows.remove(&key) {
+            debug!("evict(): evicting flow (key={:?})", key);
+            self.evictions += 1;
+            if let Some(mut evict) = flow.evict {
+                evict.set(true);
+            }
+        }
+    }
+
+    /// Marks the flow [key] as used at [now], if entry [id] still owns it.
+    fn touch(&mut self, key: &FlowKey, id: u64, now: Instant) {
+        if let Some(flow) = self.flows.get_mut(key) {
+            if flow.id == id {
+                flow.last_used = now;
+            }
+        }
+    }
+
+    /// Removes the flow [key] from the table, if entry [id] still owns it.
+    fn close(&mut self, key: &FlowKey, id: u64) {
+        if self.flows.get(key).map(|flow| flow.id) == Some(id) {
+            self.flows.remove(key);
+        }
+    }
+}
+
+impl FlowEntry {
+    /// Marks the flow as used at [now].
+    pub fn touch(&mut self, now: Instant) {
+        self.table.touch(&self.key, self.id, now)
+    }
+
+    /// Gets the flag that is set when the table evicts the flow, and the connection should abort.
+    pub fn watch_evict(&self) -> SharedAsyncValue<bool> {
+        self.evict.clone()
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Default for SharedFlowTable {
+    fn default() -> Self {
+        Self(SharedObject::new(FlowTable {
+            config: FlowConfig::default(),
+            flows: HashMap::new(),
+            next_id: 0,
+            evictions: 0,
+            refusals: 0,
+        }))
+    }
+}
+
+impl Deref for SharedFlowTable {
+    type Target = FlowTable;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl DerefMut for SharedFlowTable {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+impl Drop for FlowEntry {
+    fn drop(&mut self) {
+        self.table.close(&self.key, self.id)
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::network::flows::{
+        FlowEntry,
+        FlowKey,
+        FlowProtocol,
+        SharedFlowTable,
+    };
+    use ::anyhow::Result;
+    use ::std::{
+        net::{
+            Ipv4Addr,
+            SocketAddrV4,
+        },
+        time::{
+            Duration,
+            Instant,
+        },
+    };
+
+    const LOCAL: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 80);
+
+    fn key(protocol: FlowProtocol, port: u16) -> FlowKey {
+        FlowKey::new(protocol, LOCAL, SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 2), port))
+    }
+
+    #[test]
+    fn entries() -> Result<()> {
+        let now: Instant = Instant::now();
+        let mut table: SharedFlowTable = SharedFlowTable::default();
+        let first: FlowEntry = table.open(now, key(FlowProtocol::Tcp, 1))?;
+        let second: FlowEntry = table.open(now, key(FlowProtocol::Tcp, 2))?;
+        crate::ensure_eq!(table.get_entries(), 2);
+
+        // UDP flows are not tracked without a limit.
+        crate::ensure_eq!(table.track(now, key(FlowProtocol::Udp, 1)), true);
+        crate::ensure_eq!(table.get_tunable("entries")?, "2".to_string());
+
+        // Dropping an entry removes its flow, unless a newer entry took it over.
+        let third: FlowEntry = table.open(now, key(FlowProtocol::Tcp, 2))?;
+        drop(second);
+        drop(first);
+        crate::ensure_eq!(table.get_entries(), 1);
+        drop(third);
+        crate::ensure_eq!(table.get_entries(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn evict_lru() -> Result<()> {
+        let now: Instant = Instant::now();
+        let mut table: SharedFlowTable = SharedFlowTable::default();
+        table.set_tunable("max_entries", "2")?;
+        let mut first: FlowEntry = table.open(now, key(FlowProtocol::Tcp, 1))?;
+        crate::ensure_eq!(
+            table.track(now + Duration::from_secs(1), key(FlowProtocol::Udp, 1)),
+            true
+        );
+        first.touch(now + Duration::from_secs(2));
+
+        // The UDP flow was used the least recently.
+        let second: FlowEntry = table.open(now + Duration::from_secs(3), key(FlowProtocol::Tcp, 2))?;
+        crate::ensure_eq!(table.get_entries(), 2);
+        crate::ensure_eq!(first.watch_evict().get(), false);
+
+        // Then, the first connection is.
+        crate::ensure_eq!(
+            table.track(now + Duration::from_secs(4), key(FlowProtocol::Udp, 2)),
+            true
+        );
+        crate::ensure_eq!(first.watch_evict().get(), true);
+        crate::ensure_eq!(second.watch_evict().get(), false);
+        crate::ensure_eq!(table.get_tunable("evictions")?, "2".to_string());
+
+        // Dropping an evicted entry leaves the table as it is.
+        drop(first);
+        crate::ensure_eq!(table.get_entries(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn refuse() -> Result<()> {
+        let now: Instant = Instant::now();
+        let mut table: SharedFlowTable = SharedFlowTable::default();
+        table.set_tunable("max_entries", "1")?;
+        table.set_tunable("policy", "refuse")?;
+        table.set_tunable("idle_timeout_ms", "1000")?;
+        crate::ensure_eq!(table.track(now, key(FlowProtocol::Udp, 1)), true);
+        crate::ensure_eq!(table.track(now, key(FlowProtocol::Udp, 1)), true);
+        crate::ensure_eq!(table.track(now, key(FlowProtocol::Udp, 2)), false);
+        crate::ensure_eq!(
+            table.open(now, key(FlowProtocol::Tcp, 1)).err().map(|e| e.errno),
+            Some(libc::ENOBUFS)
+        );
+        crate::ensure_eq!(table.get_tunable("refusals")?, "2".to_string());
+
+        // Idle flows are evicted even when new flows are refused.
+        let entry: FlowEntry = table.open(now + Duration::from_secs(1), key(FlowProtocol::Tcp, 1))?;
+        crate::ensure_eq!(table.get_entries(), 1);
+        crate::ensure_eq!(entry.watch_evict().get(), false);
+
+        Ok(())
+    }
+}
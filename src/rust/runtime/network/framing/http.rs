@@ -0,0 +1,631 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    memory::DemiBuffer,
+    network::framing::{
+        bad_frame,
+        invalid_frame,
+        too_large,
+        Decoded,
+        FrameCodec,
+        MAX_FRAME_SIZE,
+    },
+};
+use ::std::collections::VecDeque;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Largest number of header fields in the head of a message.
+pub const MAX_HEADERS: usize = 100;
+
+/// Delimiter of the head of a message.
+const END_OF_HEAD: &[u8] = b"\r\n\r\n";
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Side of a connection that HTTP framing runs on. Clients push requests and pop responses, while servers pop requests
+/// and push responses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HttpRole {
+    Client,
+    Server,
+}
+
+/// First line of the head of a message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HttpStartLine {
+    Request { method: String, target: String },
+    Response { status: u16, reason: String },
+}
+
+/// Head of an HTTP/1.1 message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpHead {
+    pub start: HttpStartLine,
+    /// Minor version of the protocol (i.e. 1 for HTTP/1.1).
+    pub minor_version: u8,
+    /// Header fields, in the order that they were received. Names keep their case.
+    pub headers: Vec<(String, String)>,
+}
+
+/// An HTTP/1.1 message, whose body was stripped of any transfer coding.
+pub struct HttpMessage {
+    pub head: HttpHead,
+    pub body: DemiBuffer,
+}
+
+/// Start and end offsets of the data of the chunks of a chunked body.
+type ChunkOffsets = Vec<(usize, usize)>;
+
+/// How the end of the body of a message is found (RFC 9112, section 6.3).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BodyLength {
+    Fixed(usize),
+    Chunked,
+    /// The body goes on until the connection is closed. Only responses are delimited this way.
+    UntilClose,
+}
+
+/// Frames the byte stream of a connection into HTTP/1.1 messages. Messages are handed out whole, as their head followed
+/// by their body. Chunked bodies are decoded, and the head of their message is rewritten to carry a Content-Length
+/// instead.
+pub struct HttpCodec {
+    role: HttpRole,
+    /// Whether each request that is waiting for a response is a HEAD request, whose response has no body.
+    head_requests: VecDeque<bool>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl HttpHead {
+    /// Parses the head at the start of [buf]. Returns the head and its length, delimiter included, or None if [buf] does
+    /// not hold a whole head yet.
+    pub fn parse(buf: &[u8]) -> Result<Option<(Self, usize)>, Fail> {
+        let len: usize = match buf.windows(END_OF_HEAD.len()).position(|w| w == END_OF_HEAD) {
+            Some(offset) => offset + END_OF_HEAD.len(),
+            None => return Ok(None),
+        };
+        let text: &str = match std::str::from_utf8(&buf[..len - END_OF_HEAD.len()]) {
+            Ok(text) => text,
+            Err(_) => return Err(bad_frame("head is not valid text")),
+        };
+        let mut lines = text.split("\r\n");
+        let (start, minor_version): (HttpStartLine, u8) = parse_start_line(lines.next().unwrap_or(""))?;
+        let mut headers: Vec<(String, String)> = Vec::new();
+        for line in lines {
+            if headers.len() == MAX_HEADERS {
+                return Err(bad_frame("too many header fields"));
+            }
+            headers.push(parse_header(line)?);
+        }
+        Ok(Some((
+            Self {
+                start,
+                minor_version,
+                headers,
+            },
+            len,
+        )))
+    }
+
+    /// Gets the value of the first header field named [name], which is matched regardless of case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn is_request(&self) -> bool {
+        matches!(self.start, HttpStartLine::Request { .. })
+    }
+
+    /// Encodes the head, delimiter included.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out: String = match &self.start {
+            HttpStartLine::Request { method, target } => {
+                format!("{} {} HTTP/1.{}\r\n", method, target, self.minor_version)
+            },
+            HttpStartLine::Response { status, reason } => {
+                format!("HTTP/1.{} {:03} {}\r\n", self.minor_version, status, reason)
+            },
+        };
+        for (name, value) in &self.headers {
+            out.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        out.push_str("\r\n");
+        out.into_bytes()
+    }
+
+    /// Tells how the end of the body of this message is found. [head_request] tells whether this is the response to a
+    /// HEAD request.
+    fn body_length(&self, head_request: bool) -> Result<BodyLength, Fail> {
+        if let HttpStartLine::Response { status, .. } = self.start {
+            if head_request || status < 200 || status == 204 || status == 304 {
+                return Ok(BodyLength::Fixed(0));
+            }
+        }
+        if let Some(coding) = self.header("transfer-encoding") {
+            if self.header("content-length").is_some() {
+                return Err(bad_frame("both Transfer-Encoding and Content-Length are set"));
+            }
+            return match coding.rsplit(',').next().map(str::trim) {
+                Some(last) if last.eq_ignore_ascii_case("chunked") => Ok(BodyLength::Chunked),
+                _ if !self.is_request() => Ok(BodyLength::UntilClose),
+                _ => {
+                    let cause: String = format!("unsupported transfer coding (coding={:?})", coding);
+                    warn!("body_length(): {}", cause);
+                    Err(Fail::new(libc::ENOTSUP, &cause))
+                },
+            };
+        }
+        let mut length: Option<usize> = None;
+        for (_, value) in self
+            .headers
+            .iter()
+            .filter(|(n, _)| n.eq_ignore_ascii_case("content-length"))
+        {
+            let value: usize = match parse_digits(value).and_then(|value| value.parse::<usize>().ok()) {
+                Some(value) => value,
+                None => return Err(bad_frame("invalid Content-Length")),
+            };
+            if length.is_some_and(|length| length != value) {
+                return Err(bad_frame("conflicting Content-Length fields"));
+            }
+            length = Some(value);
+        }
+        match length {
+            Some(length) => Ok(BodyLength::Fixed(length)),
+            None if self.is_request() => Ok(BodyLength::Fixed(0)),
+            None => Ok(BodyLength::UntilClose),
+        }
+    }
+
+    /// Tells whether this is an interim response, which is followed by another response to the same request.
+    fn is_interim(&self) -> bool {
+        matches!(self.start, HttpStartLine::Response { status, .. } if (100..200).contains(&status) && status != 101)
+    }
+}
+
+impl HttpMessage {
+    /// Splits a message that was popped from an HTTP queue into its head and its body, without copying the body.
+    pub fn parse(mut buf: DemiBuffer) -> Result<Self, Fail> {
+        match HttpHead::parse(&buf[..])? {
+            Some((head, len)) => {
+                buf.adjust(len)?;
+                Ok(Self { head, body: buf })
+            },
+            None => Err(bad_frame("message has no head")),
+        }
+    }
+}
+
+impl HttpCodec {
+    pub fn new(role: HttpRole) -> Self {
+        Self {
+            role,
+            head_requests: VecDeque::new(),
+        }
+    }
+
+    /// Checks that [head] goes in the direction that the role allows.
+    fn check_direction(&self, head: &HttpHead, outgoing: bool) -> Result<(), Fail> {
+        let expect_request: bool = (self.role == HttpRole::Client) == outgoing;
+        if head.is_request() != expect_request {
+            let cause: String = format!(
+                "unexpected message (role={:?}, outgoing={:?}, request={:?})",
+                self.role,
+                outgoing,
+                head.is_request()
+            );
+            warn!("check_direction(): {}", cause);
+            let errno: i32 = if outgoing { libc::EINVAL } else { libc::EBADMSG };
+            return Err(Fail::new(errno, &cause));
+        }
+        Ok(())
+    }
+
+    /// Tells whether the next response answers a HEAD request.
+    fn answers_head_request(&self) -> bool {
+        self.head_requests.front().copied().unwrap_or(false)
+    }
+
+    /// Keeps track of the requests that wait for a response once a message with [head] was received.
+    fn message_received(&mut self, head: &HttpHead) {
+        match self.role {
+            HttpRole::Client => {
+                if !head.is_interim() {
+                    self.head_requests.pop_front();
+                }
+            },
+            HttpRole::Server => self.head_requests.push_back(head_method_is(head, "HEAD")),
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl FrameCodec for HttpCodec {
+    fn decode(&mut self, buf: &[u8]) -> Result<Decoded, Fail> {
+        let (head, head_len): (HttpHead, usize) = match HttpHead::parse(buf)? {
+            Some(parsed) => parsed,
+            None => return Ok(Decoded::Incomplete(None)),
+        };
+        self.check_direction(&head, false)?;
+        let decoded: Decoded = match head.body_length(self.answers_head_request())? {
+            BodyLength::Fixed(len) if head_len + len <= buf.len() => Decoded::Frame(head_len + len),
+            BodyLength::Fixed(len) => return Ok(Decoded::Incomplete(Some(head_len + len))),
+            BodyLength::Chunked => match parse_chunked(&buf[head_len..])? {
+                Some((len, chunks)) => {
+                    let body: Vec<&[u8]> = chunks
+                        .iter()
+                        .map(|(start, end)| &buf[head_len + start..head_len + end])
+                        .collect();
+                    Decoded::Rewritten(head_len + len, dechunk(&head, &body)?)
+                },
+                None => return Ok(Decoded::Incomplete(None)),
+            },
+            // The message ends along with the connection.
+            BodyLength::UntilClose => return Ok(Decoded::Incomplete(None)),
+        };
+        self.message_received(&head);
+        Ok(decoded)
+    }
+
+    fn decode_eof(&mut self, buf: &[u8]) -> Result<Option<Decoded>, Fail> {
+        if buf.is_empty() {
+            return Ok(None);
+        }
+        match HttpHead::parse(buf)? {
+            Some((head, _)) if head.body_length(self.answers_head_request())? == BodyLength::UntilClose => {
+                self.message_received(&head);
+                Ok(Some(Decoded::Frame(buf.len())))
+            },
+            _ => Err(bad_frame("connection was closed in the middle of a message")),
+        }
+    }
+
+    fn encode(&mut self, buf: &[u8]) -> Result<(), Fail> {
+        let (head, head_len): (HttpHead, usize) = match HttpHead::parse(buf)? {
+            Some(parsed) => parsed,
+            None => return Err(invalid_frame("message has no head")),
+        };
+        self.check_direction(&head, true)?;
+        let body: &[u8] = &buf[head_len..];
+        let complete: bool = match head.body_length(self.answers_head_request())? {
+            BodyLength::Fixed(len) => body.len() == len,
+            BodyLength::Chunked => parse_chunked(body)?.is_some_and(|(len, _)| len == body.len()),
+            BodyLength::UntilClose => true,
+        };
+        if !complete {
+            return Err(invalid_frame("body does not match the framing of the message"));
+        }
+        match self.role {
+            HttpRole::Client => self.head_requests.push_back(head_method_is(&head, "HEAD")),
+            HttpRole::Server => {
+                if !head.is_interim() {
+                    self.head_requests.pop_front();
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Parses the start line of a head. Returns the line and the minor version of the protocol.
+fn parse_start_line(line: &str) -> Result<(HttpStartLine, u8), Fail> {
+    if let Some(rest) = line.strip_prefix("HTTP/1.") {
+        let (version, rest): (&str, &str) = rest.split_once(' ').ok_or(bad_frame("malformed status line"))?;
+        let (status, reason): (&str, &str) = rest.split_once(' ').unwrap_or((rest, ""));
+        let status: Option<u16> = parse_digits(status)
+            .filter(|status| status.len() == 3)
+            .and_then(|status| status.parse::<u16>().ok());
+        match (parse_minor_version(version), status) {
+            (Some(minor_version), Some(status)) => Ok((
+                HttpStartLine::Response {
+                    status,
+                    reason: reason.to_string(),
+                },
+                minor_version,
+            )),
+            _ => Err(bad_frame("malformed status line")),
+        }
+    } else {
+        let mut fields = line.split(' ');
+        match (fields.next(), fields.next(), fields.next(), fields.next()) {
+            (Some(method), Some(target), Some(version), None) if is_token(method) && !target.is_empty() => {
+                match version.strip_prefix("HTTP/1.").and_then(parse_minor_version) {
+                    Some(minor_version) => Ok((
+                        HttpStartLine::Request {
+                            method: method.to_string(),
+                            target: target.to_string(),
+                        },
+                        minor_version,
+                    )),
+                    None => Err(bad_frame("malformed request line")),
+                }
+            },
+            _ => Err(bad_frame("malformed request line")),
+        }
+    }
+}
+
+/// Parses a header field line into its name and its value, without surrounding whitespace.
+fn parse_header(line: &str) -> Result<(String, String), Fail> {
+    match line.split_once(':') {
+        Some((name, value)) if is_token(name) => Ok((name.to_string(), value.trim_matches([' ', '\t']).to_string())),
+        _ => Err(bad_frame("malformed header field")),
+    }
+}
+
+/// Parses the single-digit minor version of the protocol.
+fn parse_minor_version(version: &str) -> Option<u8> {
+    parse_digits(version)
+        .filter(|version| version.len() == 1)?
+        .parse::<u8>()
+        .ok()
+}
+
+/// Returns [text] if it is a non-empty run of decimal digits.
+fn parse_digits(text: &str) -> Option<&str> {
+    if !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit()) {
+        Some(text)
+    } else {
+        None
+    }
+}
+
+/// Tells whether [text] is a token, which methods and field names are made of (RFC 9110, section 5.6.2).
+fn is_token(text: &str) -> bool {
+    !text.is_empty()
+        && text
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+/// Tells whether [head] is a request with [method].
+fn head_method_is(head: &HttpHead, method: &str) -> bool {
+    matches!(&head.start, HttpStartLine::Request { method: m, .. } if m == method)
+}
+
+/// Parses a chunked body at the start of [buf]. Returns its length, trailer included, and the offsets of the data of
+/// its chunks, or None if [buf] does not hold a whole body yet.
+fn parse_chunked(buf: &[u8]) -> Result<Option<(usize, ChunkOffsets)>, Fail> {
+    let mut chunks: ChunkOffsets = Vec::new();
+    let mut offset: usize = 0;
+    loop {
+        let line: &[u8] = match read_line(&buf[offset..]) {
+            Some(line) => line,
+            None => return Ok(None),
+        };
+        offset += line.len() + 2;
+        // Chunk extensions are ignored.
+        let size: &[u8] = line.split(|b| *b == b';').next().unwrap_or(&[]);
+        let size: usize = match std::str::from_utf8(size)
+            .ok()
+            .and_then(|size| usize::from_str_radix(size.trim_matches([' ', '\t']), 16).ok())
+        {
+            Some(size) if size <= MAX_FRAME_SIZE => size,
+            _ => return Err(bad_frame("invalid chunk size")),
+        };
+        if size == 0 {
+            break;
+        }
+        if buf.len() < offset + size + 2 {
+            return Ok(None);
+        }
+        if &buf[offset + size..offset + size + 2] != b"\r\n" {
+            return Err(bad_frame("chunk is not terminated"));
+        }
+        chunks.push((offset, offset + size));
+        offset += size + 2;
+    }
+    // Trailer fields are dropped.
+    loop {
+        match read_line(&buf[offset..]) {
+            Some(line) => {
+                offset += line.len() + 2;
+                if line.is_empty() {
+                    return Ok(Some((offset, chunks)));
+                }
+            },
+            None => return Ok(None),
+        }
+    }
+}
+
+/// Reads the line at the start of [buf], without its delimiter.
+fn read_line(buf: &[u8]) -> Option<&[u8]> {
+    buf.windows(2).position(|w| w == b"\r\n").map(|len| &buf[..len])
+}
+
+/// Builds a message out of [head] and the data of the chunks of its body, which is sent with a Content-Length instead.
+fn dechunk(head: &HttpHead, chunks: &[&[u8]]) -> Result<DemiBuffer, Fail> {
+    let body_len: usize = chunks.iter().map(|chunk| chunk.len()).sum();
+    let mut head: HttpHead = head.clone();
+    head.headers
+        .retain(|(name, _)| !name.eq_ignore_ascii_case("transfer-encoding"));
+    head.headers.push(("Content-Length".to_string(), body_len.to_string()));
+    let encoded: Vec<u8> = head.encode();
+    let len: usize = encoded.len() + body_len;
+    if len > MAX_FRAME_SIZE {
+        return Err(too_large(len));
+    }
+    let mut buf: DemiBuffer = DemiBuffer::new(len as u16);
+    buf[..encoded.len()].copy_from_slice(&encoded);
+    let mut offset: usize = encoded.len();
+    for chunk in chunks {
+        buf[offset..offset + chunk.len()].copy_from_slice(chunk);
+        offset += chunk.len();
+    }
+    Ok(buf)
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        HttpCodec,
+        HttpHead,
+        HttpMessage,
+        HttpRole,
+        HttpStartLine,
+    };
+    use crate::runtime::{
+        memory::DemiBuffer,
+        network::framing::Framer,
+    };
+    use ::anyhow::Result;
+
+    /// Creates a framer of HTTP messages for [role].
+    fn http_framer(role: HttpRole) -> Framer {
+        Framer::new(Box::new(HttpCodec::new(role)))
+    }
+
+    /// Frames all messages that [framer] has, as text.
+    fn frame_all(framer: &mut Framer) -> Result<Vec<String>> {
+        let mut messages: Vec<String> = Vec::new();
+        while let Some(buf) = framer.next_frame()? {
+            messages.push(String::from_utf8(buf[..].to_vec())?);
+        }
+        Ok(messages)
+    }
+
+    /// Tests that heads are parsed into their fields.
+    #[test]
+    fn test_http_head_parse() -> Result<()> {
+        let text: &[u8] = b"GET /index.html HTTP/1.1\r\nHost: example.com\r\nAccept:  */* \r\n\r\nbody";
+        let (head, len): (HttpHead, usize) = match HttpHead::parse(text)? {
+            Some(parsed) => parsed,
+            None => anyhow::bail!("head should be complete"),
+        };
+        crate::ensure_eq!(len, text.len() - 4);
+        crate::ensure_eq!(
+            head.start,
+            HttpStartLine::Request {
+                method: "GET".to_string(),
+                target: "/index.html".to_string()
+            }
+        );
+        crate::ensure_eq!(head.minor_version, 1);
+        crate::ensure_eq!(head.header("host"), Some("example.com"));
+        crate::ensure_eq!(head.header("ACCEPT"), Some("*/*"));
+        crate::ensure_eq!(HttpHead::parse(&head.encode())?.map(|(h, _)| h), Some(head));
+
+        crate::ensure_eq!(HttpHead::parse(b"GET / HTTP/1.1\r\nHost: x\r\n")?.is_none(), true);
+        crate::ensure_eq!(HttpHead::parse(b"GET / HTTP/2\r\n\r\n").is_err(), true);
+        crate::ensure_eq!(HttpHead::parse(b"HTTP/1.1 20 OK\r\n\r\n").is_err(), true);
+        crate::ensure_eq!(HttpHead::parse(b"GET / HTTP/1.1\r\nBad Name: x\r\n\r\n").is_err(), true);
+        Ok(())
+    }
+
+    /// Tests that pipelined requests are framed one by one, and that a request that is split across buffers is framed
+    /// once it arrives whole.
+    #[test]
+    fn test_http_framer_pipelined_requests() -> Result<()> {
+        let first: &str = "POST /a HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello";
+        let second: &str = "GET /b HTTP/1.1\r\n\r\n";
+        let third: &str = "PUT /c HTTP/1.1\r\nContent-Length: 3\r\n\r\nabc";
+        let mut framer: Framer = http_framer(HttpRole::Server);
+
+        framer.receive(DemiBuffer::from_slice(
+            format!("{}{}{}", first, second, &third[..10]).as_bytes(),
+        )?);
+        crate::ensure_eq!(frame_all(&mut framer)?, vec![first.to_string(), second.to_string()]);
+        framer.receive(DemiBuffer::from_slice(third[10..].as_bytes())?);
+        crate::ensure_eq!(frame_all(&mut framer)?, vec![third.to_string()]);
+        crate::ensure_eq!(framer.finish()?.is_none(), true);
+
+        let message: HttpMessage = HttpMessage::parse(DemiBuffer::from_slice(first.as_bytes())?)?;
+        crate::ensure_eq!(&message.body[..], b"hello");
+        Ok(())
+    }
+
+    /// Tests that chunked bodies are decoded and that their message is handed out with a Content-Length.
+    #[test]
+    fn test_http_framer_chunked() -> Result<()> {
+        let mut framer: Framer = http_framer(HttpRole::Server);
+        let wire: &str = "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5;ext=1\r\nhello\r\n6\r\n \
+                          world\r\n0\r\nX-Trailer: 1\r\n\r\n";
+        framer.receive(DemiBuffer::from_slice(&wire.as_bytes()[..50])?);
+        crate::ensure_eq!(framer.next_frame()?.is_none(), true);
+        framer.receive(DemiBuffer::from_slice(&wire.as_bytes()[50..])?);
+        let message: HttpMessage = match framer.next_frame()? {
+            Some(buf) => HttpMessage::parse(buf)?,
+            None => anyhow::bail!("message should be complete"),
+        };
+        crate::ensure_eq!(&message.body[..], b"hello world");
+        crate::ensure_eq!(message.head.header("content-length"), Some("11"));
+        crate::ensure_eq!(message.head.header("transfer-encoding"), None);
+        crate::ensure_eq!(framer.finish()?.is_none(), true);
+        Ok(())
+    }
+
+    /// Tests that clients frame responses by the request that they answer, and that responses without a length end
+    /// with the connection.
+    #[test]
+    fn test_http_framer_responses() -> Result<()> {
+        let mut framer: Framer = http_framer(HttpRole::Client);
+        framer.send(b"HEAD / HTTP/1.1\r\n\r\n")?;
+        framer.send(b"GET / HTTP/1.1\r\n\r\n")?;
+        crate::ensure_eq!(framer.send(b"HTTP/1.1 200 OK\r\n\r\n").is_err(), true);
+        crate::ensure_eq!(
+            framer.send(b"POST / HTTP/1.1\r\nContent-Length: 4\r\n\r\nab").is_err(),
+            true
+        );
+
+        let interim: &str = "HTTP/1.1 100 Continue\r\n\r\n";
+        let head: &str = "HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\n";
+        let get: &str = "HTTP/1.1 200 OK\r\n\r\nuntil the end";
+        framer.receive(DemiBuffer::from_slice(
+            format!("{}{}{}", interim, head, get).as_bytes(),
+        )?);
+        crate::ensure_eq!(frame_all(&mut framer)?, vec![interim.to_string(), head.to_string()]);
+        let last: Option<DemiBuffer> = framer.finish()?;
+        crate::ensure_eq!(last.map(|buf| buf[..].to_vec()), Some(get.as_bytes().to_vec()));
+        Ok(())
+    }
+
+    /// Tests that messages that are cut short by the end of the connection or that are too large fail.
+    #[test]
+    fn test_http_framer_errors() -> Result<()> {
+        let mut framer: Framer = http_framer(HttpRole::Server);
+        framer.receive(DemiBuffer::from_slice(
+            b"POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort",
+        )?);
+        crate::ensure_eq!(framer.next_frame()?.is_none(), true);
+        crate::ensure_eq!(framer.finish().is_err(), true);
+
+        let mut framer: Framer = http_framer(HttpRole::Server);
+        framer.receive(DemiBuffer::from_slice(
+            b"POST / HTTP/1.1\r\nContent-Length: 100000\r\n\r\n",
+        )?);
+        crate::ensure_eq!(framer.next_frame().map_err(|e| e.errno).err(), Some(libc::EMSGSIZE));
+
+        let mut framer: Framer = http_framer(HttpRole::Server);
+        framer.receive(DemiBuffer::from_slice(b"HTTP/1.1 200 OK\r\n\r\n")?);
+        crate::ensure_eq!(framer.next_frame().map_err(|e| e.errno).err(), Some(libc::EBADMSG));
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    network::framing::{
+        check_whole_frame,
+        Decoded,
+        FrameCodec,
+    },
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Frames that start with the length of their payload, as an unsigned integer. Frames keep their prefix.
+pub struct LengthCodec {
+    /// Size of prefixes in bytes.
+    size: usize,
+    little_endian: bool,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl LengthCodec {
+    /// Creates a codec whose prefixes take [size] bytes (1, 2, 4 or 8), in network byte order unless [little_endian]
+    /// is set.
+    pub fn new(size: usize, little_endian: bool) -> Result<Self, Fail> {
+        match size {
+            1 | 2 | 4 | 8 => Ok(Self { size, little_endian }),
+            _ => {
+                let cause: String = format!("length prefixes take 1, 2, 4 or 8 bytes (size={:?})", size);
+                warn!("new(): {}", cause);
+                Err(Fail::new(libc::EINVAL, &cause))
+            },
+        }
+    }
+
+    /// Reads the length of the frame that starts [buf], once its prefix arrived.
+    fn frame_len(&self, buf: &[u8]) -> Option<usize> {
+        if buf.len() < self.size {
+            return None;
+        }
+        let mut bytes: [u8; 8] = [0; 8];
+        let payload_len: u64 = if self.little_endian {
+            bytes[..self.size].copy_from_slice(&buf[..self.size]);
+            u64::from_le_bytes(bytes)
+        } else {
+            bytes[8 - self.size..].copy_from_slice(&buf[..self.size]);
+            u64::from_be_bytes(bytes)
+        };
+        Some(usize::try_from(payload_len).map_or(usize::MAX, |len| len.saturating_add(self.size)))
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl FrameCodec for LengthCodec {
+    fn decode(&mut self, buf: &[u8]) -> Result<Decoded, Fail> {
+        Ok(match self.frame_len(buf) {
+            Some(len) if len <= buf.len() => Decoded::Frame(len),
+            len => Decoded::Incomplete(len),
+        })
+    }
+
+    fn encode(&mut self, buf: &[u8]) -> Result<(), Fail> {
+        check_whole_frame(buf, self.decode(buf)?)
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::LengthCodec;
+    use crate::runtime::network::framing::{
+        Decoded,
+        FrameCodec,
+    };
+    use ::anyhow::Result;
+
+    /// Tests that prefixes are read in either byte order and that frames are only handed out once they are whole.
+    #[test]
+    fn test_length_codec() -> Result<()> {
+        let mut codec: LengthCodec = LengthCodec::new(4, true)?;
+        crate::ensure_eq!(matches!(codec.decode(b"\x03\x00")?, Decoded::Incomplete(None)), true);
+        crate::ensure_eq!(
+            matches!(codec.decode(b"\x03\x00\x00\x00ab")?, Decoded::Incomplete(Some(7))),
+            true
+        );
+        crate::ensure_eq!(
+            matches!(codec.decode(b"\x03\x00\x00\x00abcd")?, Decoded::Frame(7)),
+            true
+        );
+
+        let mut codec: LengthCodec = LengthCodec::new(2, false)?;
+        crate::ensure_eq!(
+            matches!(codec.decode(b"\x01\x00")?, Decoded::Incomplete(Some(258))),
+            true
+        );
+        crate::ensure_eq!(codec.encode(b"\x00\x01a").is_ok(), true);
+        crate::ensure_eq!(codec.encode(b"\x00\x01ab").is_err(), true);
+        crate::ensure_eq!(LengthCodec::new(3, false).is_err(), true);
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    network::framing::{
+        invalid_frame,
+        Decoded,
+        FrameCodec,
+    },
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Frames that end with a delimiter byte, like the lines of text protocols. Frames keep their delimiter.
+pub struct LineCodec {
+    delimiter: u8,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl LineCodec {
+    pub fn new(delimiter: u8) -> Self {
+        Self { delimiter }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl FrameCodec for LineCodec {
+    fn decode(&mut self, buf: &[u8]) -> Result<Decoded, Fail> {
+        Ok(match buf.iter().position(|byte| *byte == self.delimiter) {
+            Some(pos) => Decoded::Frame(pos + 1),
+            None => Decoded::Incomplete(None),
+        })
+    }
+
+    /// The last line of a stream may lack its delimiter.
+    fn decode_eof(&mut self, buf: &[u8]) -> Result<Option<Decoded>, Fail> {
+        if buf.is_empty() {
+            return Ok(None);
+        }
+        self.decode(buf).map(|decoded| match decoded {
+            Decoded::Incomplete(_) => Some(Decoded::Frame(buf.len())),
+            decoded => Some(decoded),
+        })
+    }
+
+    fn encode(&mut self, buf: &[u8]) -> Result<(), Fail> {
+        match buf.iter().position(|byte| *byte == self.delimiter) {
+            Some(pos) if pos + 1 == buf.len() => Ok(()),
+            Some(_) => Err(invalid_frame("buffer holds more than one line")),
+            None => Err(invalid_frame("buffer does not end with the delimiter")),
+        }
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::LineCodec;
+    use crate::runtime::network::framing::{
+        Decoded,
+        FrameCodec,
+    };
+    use ::anyhow::Result;
+
+    /// Tests that lines keep their delimiter and that the last line of a stream may lack it.
+    #[test]
+    fn test_line_codec() -> Result<()> {
+        let mut codec: LineCodec = LineCodec::new(b'\n');
+        crate::ensure_eq!(matches!(codec.decode(b"get a\nget b\n")?, Decoded::Frame(6)), true);
+        crate::ensure_eq!(matches!(codec.decode(b"get")?, Decoded::Incomplete(None)), true);
+        crate::ensure_eq!(matches!(codec.decode_eof(b"get")?, Some(Decoded::Frame(3))), true);
+        crate::ensure_eq!(codec.encode(b"get a\n").is_ok(), true);
+        crate::ensure_eq!(codec.encode(b"get a\nget b\n").is_err(), true);
+        Ok(())
+    }
+}
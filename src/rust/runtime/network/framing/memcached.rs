@@ -0,0 +1,121 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    network::framing::{
+        bad_frame,
+        check_whole_frame,
+        Decoded,
+        FrameCodec,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Size of the header of packets.
+const HEADER_SIZE: usize = 24;
+
+/// Magic byte of request packets.
+const MAGIC_REQUEST: u8 = 0x80;
+
+/// Magic byte of response packets.
+const MAGIC_RESPONSE: u8 = 0x81;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Frames the packets of the binary protocol of memcached, which are a fixed header that tells the length of the body
+/// that follows. Both requests and responses are framed.
+#[derive(Default)]
+pub struct MemcachedCodec {}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl MemcachedCodec {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl FrameCodec for MemcachedCodec {
+    fn decode(&mut self, buf: &[u8]) -> Result<Decoded, Fail> {
+        if buf.is_empty() {
+            return Ok(Decoded::Incomplete(None));
+        }
+        if buf[0] != MAGIC_REQUEST && buf[0] != MAGIC_RESPONSE {
+            return Err(bad_frame("packet does not start with a magic byte"));
+        }
+        if buf.len() < HEADER_SIZE {
+            return Ok(Decoded::Incomplete(Some(HEADER_SIZE)));
+        }
+        let key_len: usize = u16::from_be_bytes([buf[2], buf[3]]) as usize;
+        let extras_len: usize = buf[4] as usize;
+        let body_len: usize = u32::from_be_bytes([buf[8], buf[9], buf[10], buf[11]]) as usize;
+        if key_len + extras_len > body_len {
+            return Err(bad_frame("key and extras do not fit in the body"));
+        }
+        let len: usize = HEADER_SIZE + body_len;
+        Ok(if len <= buf.len() {
+            Decoded::Frame(len)
+        } else {
+            Decoded::Incomplete(Some(len))
+        })
+    }
+
+    fn encode(&mut self, buf: &[u8]) -> Result<(), Fail> {
+        check_whole_frame(buf, self.decode(buf)?)
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::MemcachedCodec;
+    use crate::runtime::network::framing::{
+        Decoded,
+        FrameCodec,
+    };
+    use ::anyhow::Result;
+
+    /// Builds the header of a packet with [magic], a key of [key_len] bytes and a body of [body_len] bytes.
+    fn header(magic: u8, key_len: u16, body_len: u32) -> Vec<u8> {
+        let mut header: Vec<u8> = vec![0; 24];
+        header[0] = magic;
+        header[2..4].copy_from_slice(&key_len.to_be_bytes());
+        header[8..12].copy_from_slice(&body_len.to_be_bytes());
+        header
+    }
+
+    /// Tests that packets are framed by the length of their body and that malformed headers are rejected.
+    #[test]
+    fn test_memcached_codec() -> Result<()> {
+        let mut codec: MemcachedCodec = MemcachedCodec::new();
+        let mut get: Vec<u8> = header(0x80, 3, 3);
+        crate::ensure_eq!(matches!(codec.decode(&get[..10])?, Decoded::Incomplete(Some(24))), true);
+        crate::ensure_eq!(matches!(codec.decode(&get)?, Decoded::Incomplete(Some(27))), true);
+        get.extend_from_slice(b"keyleftover");
+        crate::ensure_eq!(matches!(codec.decode(&get)?, Decoded::Frame(27)), true);
+        crate::ensure_eq!(codec.encode(&get[..27]).is_ok(), true);
+
+        crate::ensure_eq!(codec.decode(&header(0x42, 0, 0)).is_err(), true);
+        crate::ensure_eq!(codec.decode(&header(0x81, 4, 3)).is_err(), true);
+        Ok(())
+    }
+}
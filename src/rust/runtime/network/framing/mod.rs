@@ -0,0 +1,276 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Exports
+//======================================================================================================================
+
+pub mod http;
+pub mod length;
+pub mod line;
+pub mod memcached;
+pub mod resp;
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    expect_some,
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+    },
+};
+use ::std::collections::VecDeque;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Largest frame that is handed out. Frames are handed out in a single buffer, thus they cannot be larger than one.
+pub const MAX_FRAME_SIZE: usize = u16::MAX as usize;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// What a [FrameCodec] found at the start of the data that was received and not framed yet.
+pub enum Decoded {
+    /// A frame that takes the first [usize] bytes, which are handed out as they are.
+    Frame(usize),
+    /// A frame that takes the first [usize] bytes, which are handed out as the [DemiBuffer] instead (e.g. once a
+    /// transfer coding was removed).
+    Rewritten(usize, DemiBuffer),
+    /// The frame is not whole yet. Holds its length, if the codec can tell it already.
+    Incomplete(Option<usize>),
+}
+
+/// Splits the byte stream of a connection into frames of an application protocol, like the messages of HTTP or the
+/// commands of a key-value store. Codecs only look at the data, while [Framer] keeps it.
+pub trait FrameCodec {
+    /// Looks for a frame at the start of [buf], which holds the data that was received and not framed yet. Once a
+    /// frame is returned, the codec may assume that it was handed out.
+    fn decode(&mut self, buf: &[u8]) -> Result<Decoded, Fail>;
+
+    /// Looks for a last frame in [buf], which holds all of the data that is left once the peer closed the connection.
+    /// Leftover data is a frame that was cut short by default.
+    fn decode_eof(&mut self, buf: &[u8]) -> Result<Option<Decoded>, Fail> {
+        if buf.is_empty() {
+            return Ok(None);
+        }
+        let cause: String = format!("connection was closed in the middle of a frame (len={:?})", buf.len());
+        warn!("decode_eof(): {}", cause);
+        Err(Fail::new(libc::EBADMSG, &cause))
+    }
+
+    /// Checks that [buf] holds exactly one frame that goes to the peer.
+    fn encode(&mut self, buf: &[u8]) -> Result<(), Fail>;
+}
+
+/// Frames the data that is received on a connection with a [FrameCodec]. Frames that arrive in a single buffer are
+/// handed out as a slice of it, while frames that span several buffers are copied into one.
+pub struct Framer {
+    codec: Box<dyn FrameCodec>,
+    /// Data that was received and not framed yet.
+    pending: VecDeque<DemiBuffer>,
+    /// Number of bytes in [pending].
+    pending_len: usize,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Framer {
+    pub fn new(codec: Box<dyn FrameCodec>) -> Self {
+        Self {
+            codec,
+            pending: VecDeque::new(),
+            pending_len: 0,
+        }
+    }
+
+    /// Queues data that was received from the peer.
+    pub fn receive(&mut self, buf: DemiBuffer) {
+        if buf.len() > 0 {
+            self.pending_len += buf.len();
+            self.pending.push_back(buf);
+        }
+    }
+
+    /// Hands out the next frame that was received, if it arrived whole.
+    pub fn next_frame(&mut self) -> Result<Option<DemiBuffer>, Fail> {
+        loop {
+            let front: &DemiBuffer = match self.pending.front() {
+                Some(front) => front,
+                None => return Ok(None),
+            };
+            let front_len: usize = front.len();
+            match self.codec.decode(&front[..])? {
+                Decoded::Frame(len) => return Ok(Some(self.take(len)?)),
+                Decoded::Rewritten(len, buf) => {
+                    self.take(len)?;
+                    return Ok(Some(buf));
+                },
+                Decoded::Incomplete(Some(len)) => {
+                    if len > MAX_FRAME_SIZE {
+                        return Err(too_large(len));
+                    }
+                    if len > self.pending_len || len <= front_len {
+                        return Ok(None);
+                    }
+                    self.gather(len)?;
+                },
+                Decoded::Incomplete(None) => {
+                    if front_len >= MAX_FRAME_SIZE {
+                        return Err(too_large(front_len));
+                    }
+                    let len: usize = self.pending_len.min(MAX_FRAME_SIZE);
+                    if len == front_len {
+                        return Ok(None);
+                    }
+                    self.gather(len)?;
+                },
+            }
+        }
+    }
+
+    /// Hands out the last frame once the peer closed the connection. Fails if it was cut short.
+    pub fn finish(&mut self) -> Result<Option<DemiBuffer>, Fail> {
+        if self.pending_len > MAX_FRAME_SIZE {
+            return Err(too_large(self.pending_len));
+        }
+        self.gather(self.pending_len)?;
+        let decoded: Option<Decoded> = match self.pending.front() {
+            Some(front) => self.codec.decode_eof(&front[..])?,
+            None => self.codec.decode_eof(&[])?,
+        };
+        match decoded {
+            Some(Decoded::Frame(len)) => Ok(Some(self.take(len)?)),
+            Some(Decoded::Rewritten(len, buf)) => {
+                self.take(len)?;
+                Ok(Some(buf))
+            },
+            Some(Decoded::Incomplete(_)) | None => Ok(None),
+        }
+    }
+
+    /// Checks that [buf] holds exactly one frame that goes to the peer.
+    pub fn send(&mut self, buf: &[u8]) -> Result<(), Fail> {
+        self.codec.encode(buf)
+    }
+
+    /// Copies pending data into the first buffer until it holds at least [len] bytes.
+    fn gather(&mut self, len: usize) -> Result<(), Fail> {
+        debug_assert!(len <= self.pending_len && len <= MAX_FRAME_SIZE);
+        if self.pending.front().map_or(0, |front| front.len()) >= len {
+            return Ok(());
+        }
+        let mut buf: DemiBuffer = DemiBuffer::new(len as u16);
+        let mut copied: usize = 0;
+        while copied < len {
+            let mut front: DemiBuffer = expect_some!(self.pending.pop_front(), "there is pending data");
+            let n: usize = front.len().min(len - copied);
+            buf[copied..copied + n].copy_from_slice(&front[..n]);
+            copied += n;
+            if n < front.len() {
+                front.adjust(n)?;
+                self.pending.push_front(front);
+            }
+        }
+        self.pending.push_front(buf);
+        Ok(())
+    }
+
+    /// Takes the first [len] bytes of pending data, which are in the first buffer.
+    fn take(&mut self, len: usize) -> Result<DemiBuffer, Fail> {
+        let front: &mut DemiBuffer = expect_some!(self.pending.front_mut(), "there is pending data");
+        debug_assert!(front.len() >= len);
+        let buf: DemiBuffer = if front.len() == len {
+            expect_some!(self.pending.pop_front(), "there is pending data")
+        } else {
+            front.split_front(len)?
+        };
+        self.pending_len -= len;
+        Ok(buf)
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Fails for a frame of [len] bytes, which does not fit in a buffer.
+pub fn too_large(len: usize) -> Fail {
+    let cause: String = format!("frame is too large (len={:?}, max={:?})", len, MAX_FRAME_SIZE);
+    warn!("too_large(): {}", cause);
+    Fail::new(libc::EMSGSIZE, &cause)
+}
+
+/// Fails for a frame that does not follow the protocol.
+pub fn bad_frame(cause: &str) -> Fail {
+    warn!("bad_frame(): {}", cause);
+    Fail::new(libc::EBADMSG, cause)
+}
+
+/// Fails for a frame that the application tries to send and that does not follow the protocol.
+pub fn invalid_frame(cause: &str) -> Fail {
+    warn!("invalid_frame(): {}", cause);
+    Fail::new(libc::EINVAL, cause)
+}
+
+/// Checks that [buf] holds exactly the frame that [decoded] describes, for codecs that check outgoing frames by decoding
+/// them.
+pub fn check_whole_frame(buf: &[u8], decoded: Decoded) -> Result<(), Fail> {
+    match decoded {
+        Decoded::Frame(len) if len == buf.len() => Ok(()),
+        Decoded::Rewritten(len, _) if len == buf.len() => Ok(()),
+        Decoded::Incomplete(_) => Err(invalid_frame("buffer does not hold a whole frame")),
+        _ => Err(invalid_frame("buffer holds more than one frame")),
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        length::LengthCodec,
+        Framer,
+    };
+    use crate::runtime::memory::DemiBuffer;
+    use ::anyhow::Result;
+
+    /// Tests that frames that arrive in a single buffer are sliced out of it, and that frames that span buffers are
+    /// gathered.
+    #[test]
+    fn test_framer_slices_and_gathers() -> Result<()> {
+        let mut framer: Framer = Framer::new(Box::new(LengthCodec::new(2, false)?));
+        let buf: DemiBuffer = DemiBuffer::from_slice(b"\x00\x03abc\x00\x02de\x00\x04fg")?;
+        let data: *const u8 = buf.as_ptr();
+        framer.receive(buf);
+
+        let first: Option<DemiBuffer> = framer.next_frame()?;
+        crate::ensure_eq!(first.as_ref().map(|buf| buf.as_ptr()), Some(data));
+        crate::ensure_eq!(first.map(|buf| buf[..].to_vec()), Some(b"\x00\x03abc".to_vec()));
+        crate::ensure_eq!(
+            framer.next_frame()?.map(|buf| buf[..].to_vec()),
+            Some(b"\x00\x02de".to_vec())
+        );
+        crate::ensure_eq!(framer.next_frame()?.is_none(), true);
+
+        framer.receive(DemiBuffer::from_slice(b"h")?);
+        crate::ensure_eq!(framer.next_frame()?.is_none(), true);
+        framer.receive(DemiBuffer::from_slice(b"i\x00")?);
+        crate::ensure_eq!(
+            framer.next_frame()?.map(|buf| buf[..].to_vec()),
+            Some(b"\x00\x04fghi".to_vec())
+        );
+        crate::ensure_eq!(framer.next_frame()?.is_none(), true);
+        crate::ensure_eq!(framer.finish().is_err(), true);
+        Ok(())
+    }
+}
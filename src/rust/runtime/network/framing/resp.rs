@@ -0,0 +1,194 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    network::framing::{
+        bad_frame,
+        check_whole_frame,
+        Decoded,
+        FrameCodec,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Deepest nesting of aggregates that is framed, so that peers cannot exhaust the stack.
+const MAX_DEPTH: usize = 32;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Frames the values of RESP, the protocol of Redis, in both its second and third versions. Each frame is a whole
+/// value, aggregates included. Clients may also send inline commands, which are lines of text.
+#[derive(Default)]
+pub struct RespCodec {}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl RespCodec {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl FrameCodec for RespCodec {
+    fn decode(&mut self, buf: &[u8]) -> Result<Decoded, Fail> {
+        Ok(match value_len(buf, 0)? {
+            Some(len) => Decoded::Frame(len),
+            None => Decoded::Incomplete(None),
+        })
+    }
+
+    fn encode(&mut self, buf: &[u8]) -> Result<(), Fail> {
+        check_whole_frame(buf, self.decode(buf)?)
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Returns the length of the value that starts [buf], if it arrived whole. [depth] is the number of aggregates that
+/// hold the value.
+fn value_len(buf: &[u8], depth: usize) -> Result<Option<usize>, Fail> {
+    let kind: u8 = match buf.first() {
+        Some(kind) => *kind,
+        None => return Ok(None),
+    };
+    match kind {
+        // Simple strings, errors, integers, nulls, booleans, doubles and big numbers.
+        b'+' | b'-' | b':' | b'_' | b'#' | b',' | b'(' => Ok(line_len(buf)),
+        // Bulk strings, bulk errors and verbatim strings.
+        b'$' | b'!' | b'=' => {
+            let (count, header_len): (i64, usize) = match header(buf)? {
+                Some(header) => header,
+                None => return Ok(None),
+            };
+            if count == -1 && kind == b'$' {
+                return Ok(Some(header_len));
+            }
+            let count: usize = usize::try_from(count).map_err(|_| bad_frame("negative length of bulk string"))?;
+            let len: usize = header_len.saturating_add(count).saturating_add(2);
+            if buf.len() < len {
+                return Ok(None);
+            }
+            if &buf[len - 2..len] != b"\r\n" {
+                return Err(bad_frame("bulk string does not end with CRLF"));
+            }
+            Ok(Some(len))
+        },
+        // Arrays, sets and pushes hold values, while maps and attributes hold pairs of them.
+        b'*' | b'~' | b'>' | b'%' | b'|' => {
+            if depth >= MAX_DEPTH {
+                return Err(bad_frame("aggregates are nested too deeply"));
+            }
+            let (count, mut len): (i64, usize) = match header(buf)? {
+                Some(header) => header,
+                None => return Ok(None),
+            };
+            if count == -1 && kind == b'*' {
+                return Ok(Some(len));
+            }
+            let count: u64 = u64::try_from(count).map_err(|_| bad_frame("negative length of aggregate"))?;
+            let count: u64 = if kind == b'%' || kind == b'|' {
+                count.saturating_mul(2)
+            } else {
+                count
+            };
+            // Attributes come before the value that they describe.
+            let count: u64 = if kind == b'|' { count.saturating_add(1) } else { count };
+            for _ in 0..count {
+                match value_len(&buf[len..], depth + 1)? {
+                    Some(element_len) => len += element_len,
+                    None => return Ok(None),
+                }
+            }
+            Ok(Some(len))
+        },
+        _ if depth == 0 => Ok(buf.iter().position(|byte| *byte == b'\n').map(|pos| pos + 1)),
+        _ => Err(bad_frame("unknown type of value")),
+    }
+}
+
+/// Returns the length of the line that starts [buf], CRLF included, if it arrived whole.
+fn line_len(buf: &[u8]) -> Option<usize> {
+    buf.windows(2).position(|window| window == b"\r\n").map(|pos| pos + 2)
+}
+
+/// Parses the count that follows the type of bulk strings and aggregates. Returns it with the length of its line.
+fn header(buf: &[u8]) -> Result<Option<(i64, usize)>, Fail> {
+    let len: usize = match line_len(buf) {
+        Some(len) => len,
+        None => return Ok(None),
+    };
+    match std::str::from_utf8(&buf[1..len - 2])
+        .ok()
+        .and_then(|count| count.parse().ok())
+    {
+        Some(count) if count >= -1 => Ok(Some((count, len))),
+        _ => Err(bad_frame("malformed length of value")),
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::RespCodec;
+    use crate::runtime::network::framing::{
+        Decoded,
+        FrameCodec,
+    };
+    use ::anyhow::Result;
+
+    /// Tests that commands are framed whole, nested aggregates included.
+    #[test]
+    fn test_resp_codec_frames_values() -> Result<()> {
+        let mut codec: RespCodec = RespCodec::new();
+        let set: &[u8] = b"*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$1\r\nv\r\n";
+        crate::ensure_eq!(
+            matches!(codec.decode(set)?, Decoded::Frame(len) if len == set.len()),
+            true
+        );
+        crate::ensure_eq!(
+            matches!(codec.decode(&set[..set.len() - 3])?, Decoded::Incomplete(None)),
+            true
+        );
+        crate::ensure_eq!(matches!(codec.decode(b"+OK\r\n:1\r\n")?, Decoded::Frame(5)), true);
+        crate::ensure_eq!(matches!(codec.decode(b"$-1\r\n")?, Decoded::Frame(5)), true);
+        crate::ensure_eq!(
+            matches!(codec.decode(b"%1\r\n+a\r\n*1\r\n_\r\n")?, Decoded::Frame(15)),
+            true
+        );
+        crate::ensure_eq!(matches!(codec.decode(b"PING\r\n")?, Decoded::Frame(6)), true);
+        crate::ensure_eq!(codec.encode(set).is_ok(), true);
+        Ok(())
+    }
+
+    /// Tests that malformed values are rejected.
+    #[test]
+    fn test_resp_codec_rejects_malformed_values() -> Result<()> {
+        let mut codec: RespCodec = RespCodec::new();
+        crate::ensure_eq!(codec.decode(b"$3\r\nabcd\r\n").is_err(), true);
+        crate::ensure_eq!(codec.decode(b"*x\r\n").is_err(), true);
+        crate::ensure_eq!(codec.decode(b"*1\r\n?\r\n").is_err(), true);
+        crate::ensure_eq!(codec.decode(&b"*1\r\n".repeat(64)).is_err(), true);
+        Ok(())
+    }
+}
@@ -0,0 +1,354 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Accounting of the memory that sockets buffer, so that one misbehaving peer cannot exhaust the buffers that all
+//! connections share.
+//!
+//! Each socket opens a [MemoryAccount] on the [SharedMemoryPool] of the stack and keeps the pool informed of how many
+//! bytes it buffers in its send buffer, receive queue and reassembly queue. Before buffering more data, sockets ask the
+//! pool to admit it. Once the global limit is reached, new data is dropped and, with [MemoryPolicy::AbortLargest], the
+//! TCP connection that buffers the most data is asked to abort.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    collections::async_value::SharedAsyncValue,
+    runtime::{
+        fail::Fail,
+        network::config::{
+            MemoryConfig,
+            MemoryPolicy,
+        },
+        SharedObject,
+    },
+};
+use ::std::{
+    collections::HashMap,
+    ops::{
+        Deref,
+        DerefMut,
+    },
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Places where a socket buffers data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemoryKind {
+    /// Data that was pushed by the application but is not acknowledged yet.
+    SendBuffer,
+    /// Data that was received in order but is not popped by the application yet.
+    RecvQueue,
+    /// Data that was received out of order.
+    Reassembly,
+}
+
+/// Number of bytes that a socket buffers, for each place.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    pub send_buffer: usize,
+    pub recv_queue: usize,
+    pub reassembly: usize,
+}
+
+/// State of an account in a pool.
+struct Account {
+    usage: MemoryUsage,
+    /// Whether the owner of the account can be aborted. Only TCP connections can.
+    abortable: bool,
+    /// Set to ask the owner of the account to abort.
+    abort: SharedAsyncValue<bool>,
+}
+
+/// Memory that all sockets of a network stack buffer.
+pub struct MemoryPool {
+    config: MemoryConfig,
+    /// Number of bytes buffered by all accounts.
+    used: usize,
+    accounts: HashMap<u64, Account>,
+    next_id: u64,
+    /// Number of times that data was not admitted, and of connections that were aborted to reclaim memory.
+    refusals: u64,
+    aborts: u64,
+}
+
+#[derive(Clone)]
+pub struct SharedMemoryPool(SharedObject<MemoryPool>);
+
+/// Memory that a socket buffers. The account is closed, and its bytes returned to the pool, when it is dropped.
+pub struct MemoryAccount {
+    id: u64,
+    pool: SharedMemoryPool,
+    abort: SharedAsyncValue<bool>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl MemoryUsage {
+    /// Gets the number of bytes buffered in all places.
+    pub fn total(&self) -> usize {
+        self.send_buffer + self.recv_queue + self.reassembly
+    }
+
+    /// Gets a mutable reference to the number of bytes buffered in [kind].
+    fn get_mut(&mut self, kind: MemoryKind) -> &mut usize {
+        match kind {
+            MemoryKind::SendBuffer => &mut self.send_buffer,
+            MemoryKind::RecvQueue => &mut self.recv_queue,
+            MemoryKind::Reassembly => &mut self.reassembly,
+        }
+    }
+}
+
+impl SharedMemoryPool {
+    /// Opens an account for a new socket. Accounts of TCP connections are [abortable], and are watched through
+    /// [MemoryAccount::watch_abort].
+    pub fn open_account(&mut self, abortable: bool) -> MemoryAccount {
+        let id: u64 = self.next_id;
+        self.next_id += 1;
+        let abort: SharedAsyncValue<bool> = SharedAsyncValue::new(false);
+        self.accounts.insert(
+            id,
+            Account {
+                usage: MemoryUsage::default(),
+                abortable,
+                abort: abort.clone(),
+            },
+        );
+        MemoryAccount {
+            id,
+            pool: self.clone(),
+            abort,
+        }
+    }
+
+    /// Gets the number of bytes that all sockets buffer.
+    pub fn get_used(&self) -> usize {
+        self.used
+    }
+
+    /// Sets the tunable [name] ("limit_bytes" or "policy") to [value].
+    pub fn set_tunable(&mut self, name: &str, value: &str) -> Result<(), Fail> {
+        self.config.set_tunable(name, value)
+    }
+
+    /// Gets the value of the tunable [name]. Besides those of [MemoryConfig], "used_bytes" is the number of bytes that
+    /// all sockets buffer, "refusals" the number of times that data was dropped or refused because of the limit, and
+    /// "aborts" the number of connections that were aborted to reclaim memory.
+    pub fn get_tunable(&self, name: &str) -> Result<String, Fail> {
+        match name {
+            "used_bytes" => Ok(self.used.to_string()),
+            "refusals" => Ok(self.refusals.to_string()),
+            "aborts" => Ok(self.aborts.to_string()),
+            _ => self.config.get_tunable(name),
+        }
+    }
+
+    /// Sets the number of bytes that account [id] buffers in [kind].
+    fn set(&mut self, id: u64, kind: MemoryKind, bytes: usize) {
+        let pool: &mut MemoryPool = &mut self.0;
+        if let Some(account) = pool.accounts.get_mut(&id) {
+            let current: &mut usize = account.usage.get_mut(kind);
+            pool.used = pool.used - *current + bytes;
+            *current = bytes;
+        }
+    }
+
+    /// Checks whether [bytes] more bytes fit within the limit. If not, the policy is applied.
+    fn admit(&mut self, bytes: usize) -> bool {
+        let limit: usize = match self.config.get_limit() {
+            Some(limit) => limit,
+            None => return true,
+        };
+        if self.used + bytes <= limit {
+            return true;
+        }
+        self.refusals += 1;
+        if self.config.get_policy() == MemoryPolicy::AbortLargest {
+            self.abort_largest();
+        }
+        false
+    }
+
+    /// Asks the abortable account that buffers the most bytes, and that was not asked before, to abort.
+    fn abort_largest(&mut self) {
+        let largest: Option<SharedAsyncValue<bool>> = self
+            .accounts
+            .values()
+            .filter(|account| account.abortable && !account.abort.get() && account.usage.total() > 0)
+            .max_by_key(|account| account.usage.total())
+            .map(|account| account.abort.clone());
+        if let Some(mut abort) = largest {
+            warn!(
+                "abort_largest(): aborting connection to reclaim memory (used={})",
+                self.used
+            );
+            self.aborts += 1;
+            abort.set(true);
+        }
+    }
+
+    /// Closes account [id], returning its bytes to the pool.
+    fn close(&mut self, id: u64) {
+        if let Some(account) = self.accounts.remove(&id) {
+            self.used -= account.usage.total();
+        }
+    }
+}
+
+impl MemoryAccount {
+    /// Sets the number of bytes that the socket buffers in [kind].
+    pub fn set(&mut self, kind: MemoryKind, bytes: usize) {
+        self.pool.set(self.id, kind, bytes)
+    }
+
+    /// Checks whether the socket may buffer [bytes] more bytes without exceeding the global limit. If not, the data
+    /// should be dropped (or refused), and the policy of the pool has been applied.
+    pub fn admit(&mut self, bytes: usize) -> bool {
+        self.pool.admit(bytes)
+    }
+
+    /// Gets the number of bytes that the socket buffers.
+    pub fn usage(&self) -> MemoryUsage {
+        match self.pool.accounts.get(&self.id) {
+            Some(account) => account.usage,
+            None => MemoryUsage::default(),
+        }
+    }
+
+    /// Gets the flag that is set when the pool asks the socket to abort. It is never set on accounts that are not
+    /// abortable.
+    pub fn watch_abort(&self) -> SharedAsyncValue<bool> {
+        self.abort.clone()
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Default for SharedMemoryPool {
+    fn default() -> Self {
+        Self(SharedObject::new(MemoryPool {
+            config: MemoryConfig::default(),
+            used: 0,
+            accounts: HashMap::new(),
+            next_id: 0,
+            refusals: 0,
+            aborts: 0,
+        }))
+    }
+}
+
+impl Deref for SharedMemoryPool {
+    type Target = MemoryPool;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl DerefMut for SharedMemoryPool {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+impl Drop for MemoryAccount {
+    fn drop(&mut self) {
+        self.pool.close(self.id)
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::network::memory::{
+        MemoryAccount,
+        MemoryKind,
+        MemoryUsage,
+        SharedMemoryPool,
+    };
+    use ::anyhow::Result;
+
+    #[test]
+    fn accounting() -> Result<()> {
+        let mut pool: SharedMemoryPool = SharedMemoryPool::default();
+        let mut tcp: MemoryAccount = pool.open_account(true);
+        let mut udp: MemoryAccount = pool.open_account(false);
+        tcp.set(MemoryKind::SendBuffer, 100);
+        tcp.set(MemoryKind::Reassembly, 50);
+        udp.set(MemoryKind::RecvQueue, 10);
+        crate::ensure_eq!(pool.get_used(), 160);
+        tcp.set(MemoryKind::SendBuffer, 20);
+        crate::ensure_eq!(
+            tcp.usage(),
+            MemoryUsage {
+                send_buffer: 20,
+                recv_queue: 0,
+                reassembly: 50
+            }
+        );
+        crate::ensure_eq!(pool.get_used(), 80);
+
+        // Closing an account returns its bytes.
+        drop(tcp);
+        crate::ensure_eq!(pool.get_used(), 10);
+        crate::ensure_eq!(pool.get_tunable("used_bytes")?, "10".to_string());
+
+        Ok(())
+    }
+
+    #[test]
+    fn drop_newest() -> Result<()> {
+        let mut pool: SharedMemoryPool = SharedMemoryPool::default();
+        pool.set_tunable("limit_bytes", "100")?;
+        let mut account: MemoryAccount = pool.open_account(true);
+        crate::ensure_eq!(account.admit(100), true);
+        account.set(MemoryKind::RecvQueue, 100);
+        crate::ensure_eq!(account.admit(1), false);
+        crate::ensure_eq!(account.watch_abort().get(), false);
+
+        // Data is admitted again once the socket frees some.
+        account.set(MemoryKind::RecvQueue, 60);
+        crate::ensure_eq!(account.admit(40), true);
+        crate::ensure_eq!(pool.get_tunable("refusals")?, "1".to_string());
+
+        Ok(())
+    }
+
+    #[test]
+    fn abort_largest() -> Result<()> {
+        let mut pool: SharedMemoryPool = SharedMemoryPool::default();
+        pool.set_tunable("limit_bytes", "100")?;
+        pool.set_tunable("policy", "abort_largest")?;
+        let mut small: MemoryAccount = pool.open_account(true);
+        let mut large: MemoryAccount = pool.open_account(true);
+        let mut udp: MemoryAccount = pool.open_account(false);
+        small.set(MemoryKind::RecvQueue, 10);
+        large.set(MemoryKind::SendBuffer, 30);
+        large.set(MemoryKind::Reassembly, 30);
+        udp.set(MemoryKind::RecvQueue, 30);
+
+        crate::ensure_eq!(small.admit(10), false);
+        crate::ensure_eq!(large.watch_abort().get(), true);
+        crate::ensure_eq!(small.watch_abort().get(), false);
+        crate::ensure_eq!(udp.watch_abort().get(), false);
+
+        // Connections are asked to abort once.
+        crate::ensure_eq!(small.admit(10), false);
+        crate::ensure_eq!(small.watch_abort().get(), true);
+        crate::ensure_eq!(pool.get_tunable("aborts")?, "2".to_string());
+
+        Ok(())
+    }
+}
@@ -5,11 +5,23 @@
 // Exports
 //======================================================================================================================
 
+pub mod admission;
+pub mod ancillary;
 pub mod config;
 pub mod consts;
+pub mod drops;
 pub mod ephemeral;
+pub mod flows;
+pub mod framing;
+pub mod memory;
+pub mod numa;
+pub mod ptp;
+pub mod quic;
 pub mod ring;
 pub mod socket;
+pub mod tcp_info;
+pub mod timestamp;
+pub mod tls;
 pub mod transport;
 pub mod types;
 
@@ -28,8 +40,10 @@ use crate::runtime::{
             TcpConfig,
             UdpConfig,
         },
-        consts::RECEIVE_BATCH_SIZE,
+        consts::MAX_BATCH_SIZE,
+        numa::NumaStats,
         socket::SocketId,
+        tls::offload::TlsOffload,
     },
     Fail,
     QDesc,
@@ -41,6 +55,7 @@ use ::std::{
         SocketAddr,
         SocketAddrV4,
     },
+    rc::Rc,
 };
 
 //======================================================================================================================
@@ -121,15 +136,46 @@ pub trait PacketBuf {
     fn body_size(&self) -> usize;
     /// Consumes and returns the body of the target [PacketBuf].
     fn take_body(&self) -> Option<DemiBuffer>;
+    /// Returns the size of the segments that the network runtime should split the body of the target [PacketBuf]
+    /// into, if it was handed down for segmentation offload.
+    fn tso_segment_size(&self) -> Option<usize> {
+        None
+    }
+    /// Returns the priority of the target [PacketBuf], if its socket was assigned one. Network runtimes that tag
+    /// frames use it as the priority code point.
+    fn priority(&self) -> Option<u8> {
+        None
+    }
+    /// Returns whether the transport checksum of the target [PacketBuf] must be computed. Datagrams that are sent
+    /// without a checksum return false, so that network runtimes do not offload its computation to the device.
+    fn needs_checksum(&self) -> bool {
+        true
+    }
+    /// Returns whether the device should stamp the target [PacketBuf] when it transmits it, so that the time can be
+    /// read back with [NetworkRuntime::read_tx_hw_timestamp].
+    fn tx_timestamp(&self) -> bool {
+        false
+    }
 }
 
+/// Batch of [PacketBuf] that is handed to a [NetworkRuntime] at once.
+pub type PacketBatch = ArrayVec<Box<dyn PacketBuf>, MAX_BATCH_SIZE>;
+
 /// Network Runtime
 pub trait NetworkRuntime: Clone + 'static + MemoryRuntime {
+    /// Transmits a batch of [PacketBuf]. The batch is submitted to the device at once where the runtime supports it,
+    /// so that the cost of notifying the device is amortized over the batch.
+    fn transmit(&mut self, pkts: PacketBatch);
+
     /// Transmits a single [PacketBuf].
-    fn transmit(&mut self, pkt: Box<dyn PacketBuf>);
+    fn transmit_one(&mut self, pkt: Box<dyn PacketBuf>) {
+        let mut pkts: PacketBatch = PacketBatch::new();
+        pkts.push(pkt);
+        self.transmit(pkts);
+    }
 
-    /// Receives a batch of [DemiBuffer].
-    fn receive(&mut self) -> ArrayVec<DemiBuffer, RECEIVE_BATCH_SIZE>;
+    /// Receives a batch of [DemiBuffer]. The size of the batch is configured in the runtime, up to [MAX_BATCH_SIZE].
+    fn receive(&mut self) -> ArrayVec<DemiBuffer, MAX_BATCH_SIZE>;
 
     /// Gets the UDP config options.
     fn get_udp_config(&self) -> UdpConfig;
@@ -137,4 +183,36 @@ pub trait NetworkRuntime: Clone + 'static + MemoryRuntime {
     fn get_tcp_config(&self) -> TcpConfig;
 
     fn get_arp_config(&self) -> ArpConfig;
+
+    /// Gets the hooks of a device that encrypts outgoing TLS records, if the runtime has one. TLS sessions fall back
+    /// to software crypto otherwise.
+    fn tls_offload(&self) -> Option<Rc<dyn TlsOffload>> {
+        None
+    }
+
+    /// Gets the time at which the device received [pkt], in nanoseconds of the clock of the device, if it stamps
+    /// incoming packets.
+    fn rx_hw_timestamp(&self, _pkt: &DemiBuffer) -> Option<u64> {
+        None
+    }
+
+    /// Reads the time at which the device transmitted the last packet that asked for a timestamp, in nanoseconds of
+    /// the clock of the device. Each timestamp is read once.
+    fn read_tx_hw_timestamp(&mut self) -> Option<u64> {
+        None
+    }
+
+    /// Gets where the runtime placed its buffer pools and the state of its queues, relative to the NUMA node of the
+    /// device.
+    fn get_numa_stats(&self) -> Result<NumaStats, Fail> {
+        let cause: &str = "NUMA statistics are not supported by this runtime";
+        error!("get_numa_stats(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, cause))
+    }
+
+    /// Waits for the device to transmit the packets that it was handed, and then releases the device. The runtime is
+    /// not used afterwards.
+    fn shutdown(&mut self) -> Result<(), Fail> {
+        Ok(())
+    }
 }
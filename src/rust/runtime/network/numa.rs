@@ -0,0 +1,127 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use ::std::{
+    fmt,
+    str::FromStr,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// NUMA node from which a network runtime allocates its buffer pools and the state of its queues.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NumaPlacement {
+    /// The node of the network device, so that the device does not reach across nodes for packets.
+    Nic,
+    /// The node of the core that runs the engine, so that the engine does not reach across nodes for packets.
+    Core,
+    /// Whatever node the allocator picks.
+    Any,
+}
+
+/// Where a network runtime placed its allocations, relative to the NUMA node of the network device.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NumaStats {
+    /// NUMA node of the network device, if it is known.
+    pub device_node: Option<u32>,
+    /// NUMA node of the core that runs the engine, if it is known.
+    pub core_node: Option<u32>,
+    /// Number of allocations that were placed on the node of the network device.
+    pub local_allocations: u64,
+    /// Number of allocations that were placed on another node, or on any node because the preferred one ran out of
+    /// memory.
+    pub remote_allocations: u64,
+}
+
+//======================================================================================================================
+// Associate Functions
+//======================================================================================================================
+
+impl NumaStats {
+    /// Creates the statistics of a network device on [device_node] and an engine on [core_node].
+    pub fn new(device_node: Option<u32>, core_node: Option<u32>) -> Self {
+        Self {
+            device_node,
+            core_node,
+            ..Default::default()
+        }
+    }
+
+    /// Accounts for an allocation that was placed on [node], or on any node if it is not set. Allocations are always
+    /// local if the node of the network device is not known, as happens on machines with a single node.
+    pub fn record(&mut self, node: Option<u32>) {
+        match self.device_node {
+            Some(device_node) if node != Some(device_node) => self.remote_allocations += 1,
+            _ => self.local_allocations += 1,
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl FromStr for NumaPlacement {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nic" => Ok(NumaPlacement::Nic),
+            "core" => Ok(NumaPlacement::Core),
+            "any" => Ok(NumaPlacement::Any),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for NumaPlacement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NumaPlacement::Nic => write!(f, "nic"),
+            NumaPlacement::Core => write!(f, "core"),
+            NumaPlacement::Any => write!(f, "any"),
+        }
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::network::numa::{
+        NumaPlacement,
+        NumaStats,
+    };
+    use ::anyhow::Result;
+
+    /// Tests that allocations are accounted for relative to the node of the network device.
+    #[test]
+    fn test_numa_stats_record() -> Result<()> {
+        let mut stats: NumaStats = NumaStats::new(Some(1), Some(0));
+        stats.record(Some(1));
+        stats.record(Some(0));
+        stats.record(None);
+        crate::ensure_eq!(stats.local_allocations, 1);
+        crate::ensure_eq!(stats.remote_allocations, 2);
+
+        // Without a known node for the device, nothing is remote.
+        let mut stats: NumaStats = NumaStats::new(None, Some(0));
+        stats.record(Some(0));
+        stats.record(None);
+        crate::ensure_eq!(stats.local_allocations, 2);
+        crate::ensure_eq!(stats.remote_allocations, 0);
+
+        crate::ensure_eq!("core".parse::<NumaPlacement>(), Ok(NumaPlacement::Core));
+        crate::ensure_eq!(NumaPlacement::Nic.to_string(), "nic".to_string());
+        crate::ensure_eq!("socket".parse::<NumaPlacement>().is_err(), true);
+        Ok(())
+    }
+}
@@ -0,0 +1,482 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::network::ptp::{
+    clock::{
+        system_time_to_nanos,
+        SharedPtpClock,
+    },
+    log_interval,
+    message::{
+        PortIdentity,
+        PtpBody,
+        PtpHeader,
+        PtpMessage,
+        PtpTimestamp,
+        UnicastRequest,
+        PTP_ANNOUNCE,
+        PTP_DELAY_REQ,
+        PTP_DELAY_RESP,
+        PTP_FLAG_PTP_TIMESCALE,
+        PTP_FLAG_TWO_STEP,
+        PTP_FLAG_UNICAST,
+        PTP_FLAG_UTC_OFFSET_VALID,
+        PTP_SIGNALING,
+        PTP_SYNC,
+    },
+    PtpConfig,
+    DEFAULT_UTC_OFFSET,
+    PTP_EVENT_PORT,
+    PTP_GENERAL_PORT,
+};
+use ::std::{
+    collections::VecDeque,
+    net::Ipv4Addr,
+    time::{
+        Instant,
+        SystemTime,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Weight of a new measurement in the mean path delay, as a power of two.
+const PATH_DELAY_SMOOTHING_SHIFT: u32 = 3;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Sync message that arrived in two steps and waits for its Follow_Up message.
+struct PendingSync {
+    sequence_id: u16,
+    /// Local time at which the Sync message arrived.
+    receive: SystemTime,
+    /// Correction field of the Sync message, in nanoseconds.
+    correction: i64,
+}
+
+/// Sync exchange that completed.
+struct SyncSample {
+    /// Time of the master, in nanoseconds of UTC, at which the Sync message left it, plus the corrections.
+    origin: i128,
+    /// Local time at which the Sync message arrived.
+    receive: SystemTime,
+}
+
+/// Delay_Req message that waits for its Delay_Resp message.
+struct PendingDelayReq {
+    sequence_id: u16,
+    /// Local time at which the Delay_Req message left, once the driver knows it.
+    transmit: Option<SystemTime>,
+}
+
+/// Ordinary clock in the slave state, which measures its offset from a master and feeds it to a clock. It consumes the
+/// messages that arrive on the event and general ports and builds the messages that the driver sends to the master.
+pub struct PtpClient {
+    config: PtpConfig,
+    clock: SharedPtpClock,
+    port_identity: PortIdentity,
+    /// Port of the master, once it sent a Sync message.
+    master: Option<PortIdentity>,
+    /// Offset of the timescale of the master to UTC, in seconds.
+    utc_offset: i16,
+    pending_sync: Option<PendingSync>,
+    last_sync: Option<SyncSample>,
+    pending_delay_req: Option<PendingDelayReq>,
+    /// Mean delay of the path to the master, in nanoseconds.
+    mean_path_delay: Option<i64>,
+    delay_req_sequence_id: u16,
+    signaling_sequence_id: u16,
+    /// Time at which the next Delay_Req message is sent, once the master is known.
+    delay_req_deadline: Option<Instant>,
+    /// Time at which unicast transmission is requested again, if it is requested.
+    grant_deadline: Option<Instant>,
+    /// Messages to send to the master, along with their destination port.
+    transmit: VecDeque<(u16, Vec<u8>)>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl PtpClient {
+    /// Creates a client with [config] that steers [clock] and starts at [now].
+    pub fn new(config: PtpConfig, clock: SharedPtpClock, now: Instant) -> Self {
+        let grant_deadline: Option<Instant> = if config.grant_duration().is_zero() {
+            None
+        } else {
+            Some(now)
+        };
+        Self {
+            port_identity: PortIdentity {
+                clock_identity: config.clock_identity(),
+                port_number: 1,
+            },
+            config,
+            clock,
+            master: None,
+            utc_offset: DEFAULT_UTC_OFFSET,
+            pending_sync: None,
+            last_sync: None,
+            pending_delay_req: None,
+            mean_path_delay: None,
+            delay_req_sequence_id: 0,
+            signaling_sequence_id: 0,
+            delay_req_deadline: None,
+            grant_deadline,
+            transmit: VecDeque::new(),
+        }
+    }
+
+    /// Consumes the [payload] of a datagram that arrived from [remote] at the local time [receive].
+    pub fn receive(&mut self, now: Instant, remote: Ipv4Addr, payload: &[u8], receive: SystemTime) {
+        if remote != self.config.master() {
+            debug!("receive(): ignoring message from unknown master (remote={:?})", remote);
+            return;
+        }
+        let message: PtpMessage = match PtpMessage::parse(payload) {
+            Ok(message) => message,
+            Err(e) => {
+                warn!("receive(): dropping malformed message ({:?})", e);
+                return;
+            },
+        };
+        let header: PtpHeader = message.header;
+        if header.domain != self.config.domain() {
+            debug!(
+                "receive(): ignoring message of other domain (domain={:?})",
+                header.domain
+            );
+            return;
+        }
+
+        match message.body {
+            PtpBody::Sync { origin } => {
+                if self.master != Some(header.source) {
+                    info!("receive(): following master {:?}", header.source);
+                    self.master = Some(header.source);
+                    self.last_sync = None;
+                    self.pending_delay_req = None;
+                    self.mean_path_delay = None;
+                    // Measure the delay to the new master right away.
+                    self.delay_req_deadline = Some(now);
+                }
+                if header.flags & PTP_FLAG_TWO_STEP != 0 {
+                    self.pending_sync = Some(PendingSync {
+                        sequence_id: header.sequence_id,
+                        receive,
+                        correction: header.correction_nanos(),
+                    });
+                } else {
+                    self.pending_sync = None;
+                    self.on_sync(origin, header.correction_nanos(), receive);
+                }
+            },
+            PtpBody::FollowUp { precise_origin } if self.master == Some(header.source) => {
+                match self.pending_sync.take() {
+                    Some(sync) if sync.sequence_id == header.sequence_id => self.on_sync(
+                        precise_origin,
+                        sync.correction + header.correction_nanos(),
+                        sync.receive,
+                    ),
+                    sync => self.pending_sync = sync,
+                }
+            },
+            PtpBody::DelayResp { receive, requesting }
+                if self.master == Some(header.source) && requesting == self.port_identity =>
+            {
+                match self.pending_delay_req.take() {
+                    Some(PendingDelayReq {
+                        sequence_id,
+                        transmit: Some(transmit),
+                    }) if sequence_id == header.sequence_id => {
+                        self.on_delay_resp(transmit, receive, header.correction_nanos())
+                    },
+                    delay_req => self.pending_delay_req = delay_req,
+                }
+            },
+            PtpBody::Announce { current_utc_offset } => {
+                if header.flags & PTP_FLAG_PTP_TIMESCALE == 0 {
+                    // The master keeps an arbitrary timescale, which is taken as is.
+                    self.utc_offset = 0;
+                } else if header.flags & PTP_FLAG_UTC_OFFSET_VALID != 0 {
+                    self.utc_offset = current_utc_offset;
+                }
+            },
+            _ => (),
+        }
+    }
+
+    /// Records the local time [transmit] at which the last Delay_Req message left.
+    pub fn on_delay_req_sent(&mut self, transmit: SystemTime) {
+        if let Some(delay_req) = self.pending_delay_req.as_mut() {
+            delay_req.transmit.get_or_insert(transmit);
+        }
+    }
+
+    /// Gets the time at which [on_timeout] should be called next.
+    pub fn timeout(&self) -> Option<Instant> {
+        match (self.delay_req_deadline, self.grant_deadline) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Sends the messages whose time came at [now].
+    pub fn on_timeout(&mut self, now: Instant) {
+        if self.grant_deadline.map_or(false, |deadline| now >= deadline) {
+            self.request_unicast();
+            self.grant_deadline = Some(now + self.config.grant_duration() / 2);
+        }
+        if self.delay_req_deadline.map_or(false, |deadline| now >= deadline) {
+            self.send_delay_req();
+            self.delay_req_deadline = Some(now + log_interval(self.config.log_delay_req_interval()));
+        }
+    }
+
+    /// Pops the next message to send, along with the port of the master to which it is sent. The driver reports when
+    /// messages to the event port left with [on_delay_req_sent].
+    pub fn poll_transmit(&mut self) -> Option<(u16, Vec<u8>)> {
+        self.transmit.pop_front()
+    }
+
+    pub fn config(&self) -> &PtpConfig {
+        &self.config
+    }
+
+    pub fn clock(&self) -> &SharedPtpClock {
+        &self.clock
+    }
+
+    /// Completes a Sync exchange whose message left the master at [origin] and arrived at the local time [receive],
+    /// and samples the offset of the clock if the delay of the path is known.
+    fn on_sync(&mut self, origin: PtpTimestamp, correction: i64, receive: SystemTime) {
+        let sync: SyncSample = SyncSample {
+            origin: self.to_utc(origin) + correction as i128,
+            receive,
+        };
+        if let (Some(master), Some(mean_path_delay)) = (self.master, self.mean_path_delay) {
+            let arrival: i128 = system_time_to_nanos(self.clock.time_at(sync.receive));
+            let offset: i128 = arrival - sync.origin - mean_path_delay as i128;
+            self.clock
+                .on_sample(sync.receive, master, saturate(offset), mean_path_delay);
+        }
+        self.last_sync = Some(sync);
+    }
+
+    /// Completes a delay request-response exchange whose Delay_Req message left at the local time [transmit] and
+    /// arrived at the master at [receive].
+    fn on_delay_resp(&mut self, transmit: SystemTime, receive: PtpTimestamp, correction: i64) {
+        let sync: &SyncSample = match self.last_sync.as_ref() {
+            Some(sync) => sync,
+            None => return,
+        };
+        let master_to_slave: i128 = system_time_to_nanos(self.clock.time_at(sync.receive)) - sync.origin;
+        let slave_to_master: i128 =
+            self.to_utc(receive) - correction as i128 - system_time_to_nanos(self.clock.time_at(transmit));
+        let delay: i64 = saturate((master_to_slave + slave_to_master) / 2).max(0);
+        self.mean_path_delay = Some(match self.mean_path_delay {
+            Some(mean) => mean + ((delay - mean) >> PATH_DELAY_SMOOTHING_SHIFT),
+            None => delay,
+        });
+    }
+
+    /// Queues a Delay_Req message.
+    fn send_delay_req(&mut self) {
+        let sequence_id: u16 = self.delay_req_sequence_id;
+        self.delay_req_sequence_id = sequence_id.wrapping_add(1);
+        let mut header: PtpHeader =
+            PtpHeader::new(PTP_DELAY_REQ, self.config.domain(), self.port_identity, sequence_id);
+        header.flags = PTP_FLAG_UNICAST;
+        let message: PtpMessage = PtpMessage {
+            header,
+            body: PtpBody::DelayReq {
+                origin: PtpTimestamp::default(),
+            },
+        };
+        self.pending_delay_req = Some(PendingDelayReq {
+            sequence_id,
+            transmit: None,
+        });
+        self.transmit.push_back((PTP_EVENT_PORT, message.serialize()));
+    }
+
+    /// Queues a Signaling message that asks the master for unicast transmission of the messages that the client uses.
+    fn request_unicast(&mut self) {
+        let sequence_id: u16 = self.signaling_sequence_id;
+        self.signaling_sequence_id = sequence_id.wrapping_add(1);
+        let duration: u32 = u32::try_from(self.config.grant_duration().as_secs()).unwrap_or(u32::MAX);
+        let request = |message_type: u8, log_inter_message_period: i8| UnicastRequest {
+            message_type,
+            log_inter_message_period,
+            duration,
+        };
+        let mut header: PtpHeader =
+            PtpHeader::new(PTP_SIGNALING, self.config.domain(), self.port_identity, sequence_id);
+        header.flags = PTP_FLAG_UNICAST;
+        let message: PtpMessage = PtpMessage {
+            header,
+            body: PtpBody::Signaling {
+                target: PortIdentity::ALL,
+                requests: vec![
+                    request(PTP_ANNOUNCE, self.config.log_sync_interval().max(0)),
+                    request(PTP_SYNC, self.config.log_sync_interval()),
+                    request(PTP_DELAY_RESP, self.config.log_delay_req_interval()),
+                ],
+            },
+        };
+        self.transmit.push_back((PTP_GENERAL_PORT, message.serialize()));
+    }
+
+    /// Converts [timestamp] of the master into nanoseconds of UTC.
+    fn to_utc(&self, timestamp: PtpTimestamp) -> i128 {
+        timestamp.as_nanos() - (self.utc_offset as i128) * 1_000_000_000
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Converts [nanos] into an i64, saturating on values that do not fit.
+fn saturate(nanos: i128) -> i64 {
+    nanos.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::PtpClient;
+    use crate::runtime::network::ptp::{
+        clock::{
+            system_time_to_nanos,
+            PtpState,
+            SharedPtpClock,
+        },
+        message::{
+            PortIdentity,
+            PtpBody,
+            PtpHeader,
+            PtpMessage,
+            PtpTimestamp,
+            PTP_DELAY_REQ,
+            PTP_DELAY_RESP,
+            PTP_FLAG_TWO_STEP,
+            PTP_FOLLOW_UP,
+            PTP_SIGNALING,
+            PTP_SYNC,
+        },
+        PtpConfig,
+        PTP_EVENT_PORT,
+        PTP_GENERAL_PORT,
+    };
+    use ::anyhow::Result;
+    use ::std::{
+        net::Ipv4Addr,
+        time::{
+            Duration,
+            Instant,
+            SystemTime,
+        },
+    };
+
+    const LOCAL: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 2);
+    const MASTER: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 1);
+    const MASTER_PORT: PortIdentity = PortIdentity {
+        clock_identity: [0xaa; 8],
+        port_number: 1,
+    };
+    const CLIENT_IDENTITY: [u8; 8] = [0xbb; 8];
+
+    /// Master that is 10 s ahead of the local clock, on the PTP timescale, behind a path of 50 us.
+    const MASTER_AHEAD: Duration = Duration::from_secs(10 + 37);
+    const PATH_DELAY: Duration = Duration::from_micros(50);
+
+    fn master_time(local: SystemTime) -> PtpTimestamp {
+        PtpTimestamp::from_nanos(system_time_to_nanos(local + MASTER_AHEAD))
+    }
+
+    /// Builds a two-step Sync message and its Follow_Up message, which the master sends [PATH_DELAY] before [arrival].
+    fn sync(sequence_id: u16, arrival: SystemTime) -> (Vec<u8>, Vec<u8>) {
+        let mut header: PtpHeader = PtpHeader::new(PTP_SYNC, 0, MASTER_PORT, sequence_id);
+        header.flags = PTP_FLAG_TWO_STEP;
+        let sync: PtpMessage = PtpMessage {
+            header,
+            body: PtpBody::Sync {
+                origin: PtpTimestamp::default(),
+            },
+        };
+        let follow_up: PtpMessage = PtpMessage {
+            header: PtpHeader::new(PTP_FOLLOW_UP, 0, MASTER_PORT, sequence_id),
+            body: PtpBody::FollowUp {
+                precise_origin: master_time(arrival - PATH_DELAY),
+            },
+        };
+        (sync.serialize(), follow_up.serialize())
+    }
+
+    /// Tests that the client measures the delay of the path and then steps the clock to the time of the master.
+    #[test]
+    fn test_ptp_client_synchronizes() -> Result<()> {
+        let now: Instant = Instant::now();
+        let start: SystemTime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let config: PtpConfig = PtpConfig::new(LOCAL, MASTER).with_clock_identity(CLIENT_IDENTITY);
+        let mut client: PtpClient = PtpClient::new(config, SharedPtpClock::new(), now);
+
+        // The client asks the master for unicast transmission right away.
+        crate::ensure_eq!(client.timeout(), Some(now));
+        client.on_timeout(now);
+        let (port, payload) = client.poll_transmit().unwrap();
+        crate::ensure_eq!(port, PTP_GENERAL_PORT);
+        crate::ensure_eq!(payload[0] & 0x0f, PTP_SIGNALING);
+        crate::ensure_eq!(client.poll_transmit(), None);
+
+        // Messages from other addresses are ignored.
+        let (sync_message, follow_up) = sync(0, start);
+        client.receive(now, Ipv4Addr::new(10, 0, 0, 1), &sync_message, start);
+        crate::ensure_eq!(client.timeout(), Some(now + Duration::from_secs(150)));
+
+        // The first Sync message from the master triggers a Delay_Req message.
+        client.receive(now, MASTER, &sync_message, start);
+        client.receive(now, MASTER, &follow_up, start);
+        crate::ensure_eq!(client.timeout(), Some(now));
+        client.on_timeout(now);
+        let (port, payload) = client.poll_transmit().unwrap();
+        crate::ensure_eq!(port, PTP_EVENT_PORT);
+        let delay_req: PtpMessage = PtpMessage::parse(&payload)?;
+        crate::ensure_eq!(delay_req.header.message_type, PTP_DELAY_REQ);
+        let transmit: SystemTime = start + Duration::from_millis(1);
+        client.on_delay_req_sent(transmit);
+        let delay_resp: PtpMessage = PtpMessage {
+            header: PtpHeader::new(PTP_DELAY_RESP, 0, MASTER_PORT, delay_req.header.sequence_id),
+            body: PtpBody::DelayResp {
+                receive: master_time(transmit + PATH_DELAY),
+                requesting: delay_req.header.source,
+            },
+        };
+        client.receive(now, MASTER, &delay_resp.serialize(), start + Duration::from_millis(2));
+        crate::ensure_eq!(client.clock().state(), PtpState::Listening);
+
+        // The next Sync exchange steps the clock.
+        let arrival: SystemTime = start + Duration::from_secs(1);
+        let (sync_message, follow_up) = sync(1, arrival);
+        client.receive(now, MASTER, &sync_message, arrival);
+        client.receive(now, MASTER, &follow_up, arrival);
+        crate::ensure_eq!(client.clock().state(), PtpState::Uncalibrated);
+        crate::ensure_eq!(client.clock().mean_path_delay(), 50_000);
+        crate::ensure_eq!(client.clock().last_offset(), -10_000_000_000);
+        crate::ensure_eq!(client.clock().time_at(arrival), arrival + Duration::from_secs(10));
+        crate::ensure_eq!(client.clock().master(), Some(MASTER_PORT));
+        Ok(())
+    }
+}
@@ -0,0 +1,281 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    network::ptp::message::PortIdentity,
+    SharedObject,
+};
+use ::std::{
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    time::{
+        Duration,
+        SystemTime,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Offsets from the master at or above which the clock is stepped instead of slewed, in nanoseconds.
+const STEP_THRESHOLD_NS: i64 = 1_000_000;
+
+/// Gains of the proportional-integral servo, like the defaults of linuxptp for software timestamps.
+const SERVO_KP: f64 = 0.7;
+const SERVO_KI: f64 = 0.3;
+
+/// Largest correction of the rate of the clock, in parts per billion.
+const MAX_FREQ_PPB: f64 = 500_000.0;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// States of a PTP clock, as seen by the application.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PtpState {
+    /// No offset from a master was measured yet, so the clock only follows the local clock.
+    Listening,
+    /// The clock was stepped to the time of the master and is not locked to it yet.
+    Uncalibrated,
+    /// The clock is locked to the time of the master.
+    Slave,
+}
+
+/// Clock that the network stack maintains as a linear function of the local clock, which a PTP client steers toward
+/// the time of a master. The clock keeps UTC, so its time is comparable to the local clock.
+pub struct PtpClock {
+    state: PtpState,
+    /// Local time at which the clock was last adjusted, in nanoseconds since the Unix epoch.
+    reference: i128,
+    /// Difference of the clock to the local clock at the reference, in nanoseconds.
+    offset: i128,
+    /// Rate of the clock relative to the local clock, in parts per billion.
+    freq_ppb: f64,
+    /// Local time of the last sample, in nanoseconds since the Unix epoch.
+    last_sample: Option<i128>,
+    /// Offset of the clock from the master when it was last sampled, in nanoseconds.
+    last_offset: i64,
+    /// Mean delay of the path to the master when it was last sampled, in nanoseconds.
+    mean_path_delay: i64,
+    num_samples: u64,
+    /// Port of the master that the clock follows.
+    master: Option<PortIdentity>,
+}
+
+#[derive(Clone)]
+pub struct SharedPtpClock(SharedObject<PtpClock>);
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl SharedPtpClock {
+    /// Creates a clock that follows the local clock until it is sampled.
+    pub fn new() -> Self {
+        Self(SharedObject::new(PtpClock {
+            state: PtpState::Listening,
+            reference: 0,
+            offset: 0,
+            freq_ppb: 0.0,
+            last_sample: None,
+            last_offset: 0,
+            mean_path_delay: 0,
+            num_samples: 0,
+            master: None,
+        }))
+    }
+
+    /// Gets the current time of the clock.
+    pub fn now(&self) -> SystemTime {
+        self.time_at(SystemTime::now())
+    }
+
+    /// Converts the local time [local] into the time of the clock.
+    pub fn time_at(&self, local: SystemTime) -> SystemTime {
+        nanos_to_system_time(self.at(system_time_to_nanos(local)))
+    }
+
+    /// Converts the time [time] of the clock into local time, e.g. to wait for a deadline that is set by the master.
+    pub fn to_local(&self, time: SystemTime) -> SystemTime {
+        let elapsed: f64 = (system_time_to_nanos(time) - self.reference - self.offset) as f64;
+        let local: i128 = self.reference + (elapsed / (1.0 + self.freq_ppb / 1e9)).round() as i128;
+        nanos_to_system_time(local)
+    }
+
+    /// Feeds the servo with the [offset] of the clock from the time of [master] at the local time [local], which is
+    /// positive if the clock is ahead, and with the [mean_path_delay] to the master, both in nanoseconds.
+    pub fn on_sample(&mut self, local: SystemTime, master: PortIdentity, offset: i64, mean_path_delay: i64) {
+        let local: i128 = system_time_to_nanos(local);
+        if self.master != Some(master) {
+            // Start over with a new master.
+            self.master = Some(master);
+            self.state = PtpState::Listening;
+            self.last_sample = None;
+        }
+        let elapsed: Option<i128> = self.last_sample.map(|last| local - last);
+
+        // Rebase the clock at the time of the sample, so that changes of the rate only apply from now on.
+        self.offset = self.at(local) - local;
+        self.reference = local;
+
+        if self.state == PtpState::Listening || offset.abs() >= STEP_THRESHOLD_NS {
+            info!("on_sample(): stepping PTP clock (offset={:?}ns)", offset);
+            self.offset -= offset as i128;
+            self.state = PtpState::Uncalibrated;
+        } else {
+            self.offset -= (SERVO_KP * offset as f64).round() as i128;
+            if let Some(elapsed) = elapsed.filter(|elapsed| *elapsed > 0) {
+                let freq_ppb: f64 = self.freq_ppb - SERVO_KI * (offset as f64) * 1e9 / (elapsed as f64);
+                self.freq_ppb = freq_ppb.clamp(-MAX_FREQ_PPB, MAX_FREQ_PPB);
+            }
+            self.state = PtpState::Slave;
+        }
+        self.last_sample = Some(local);
+        self.last_offset = offset;
+        self.mean_path_delay = mean_path_delay;
+        self.num_samples += 1;
+    }
+
+    /// Gets the time of the clock at the local time [local], both in nanoseconds since the Unix epoch.
+    fn at(&self, local: i128) -> i128 {
+        let elapsed: i128 = local - self.reference;
+        local + self.offset + ((elapsed as f64) * self.freq_ppb / 1e9).round() as i128
+    }
+
+    pub fn state(&self) -> PtpState {
+        self.state
+    }
+
+    /// Checks if the clock was set to the time of a master.
+    pub fn is_synchronized(&self) -> bool {
+        self.state != PtpState::Listening
+    }
+
+    pub fn last_offset(&self) -> i64 {
+        self.last_offset
+    }
+
+    pub fn mean_path_delay(&self) -> i64 {
+        self.mean_path_delay
+    }
+
+    pub fn freq_ppb(&self) -> f64 {
+        self.freq_ppb
+    }
+
+    pub fn num_samples(&self) -> u64 {
+        self.num_samples
+    }
+
+    pub fn master(&self) -> Option<PortIdentity> {
+        self.master
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Converts [time] into nanoseconds since the Unix epoch.
+pub fn system_time_to_nanos(time: SystemTime) -> i128 {
+    match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(duration) => duration.as_nanos() as i128,
+        Err(e) => -(e.duration().as_nanos() as i128),
+    }
+}
+
+/// Converts [nanos] since the Unix epoch into a time.
+pub fn nanos_to_system_time(nanos: i128) -> SystemTime {
+    let duration: Duration = Duration::from_nanos(nanos.unsigned_abs().min(u64::MAX as u128) as u64);
+    if nanos >= 0 {
+        SystemTime::UNIX_EPOCH + duration
+    } else {
+        SystemTime::UNIX_EPOCH - duration
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Default for SharedPtpClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for SharedPtpClock {
+    type Target = PtpClock;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl DerefMut for SharedPtpClock {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.deref_mut()
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        nanos_to_system_time,
+        system_time_to_nanos,
+        PtpState,
+        SharedPtpClock,
+    };
+    use crate::runtime::network::ptp::message::PortIdentity;
+    use ::anyhow::Result;
+    use ::std::time::{
+        Duration,
+        SystemTime,
+    };
+
+    /// Tests that the clock steps to the master and then locks to a master whose clock runs 100 ppm fast.
+    #[test]
+    fn test_ptp_clock_servo() -> Result<()> {
+        let mut clock: SharedPtpClock = SharedPtpClock::new();
+        let start: SystemTime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        crate::ensure_eq!(clock.state(), PtpState::Listening);
+        crate::ensure_eq!(clock.time_at(start), start);
+
+        // Time of the master at the local time [local].
+        let master = |local: i128| -> i128 {
+            let elapsed: i128 = local - system_time_to_nanos(start);
+            local + 5_000_000_000 + elapsed / 10_000
+        };
+        let mut offset: i64 = 0;
+        for i in 0..60 {
+            let local: i128 = system_time_to_nanos(start) + i * 1_000_000_000;
+            offset = (system_time_to_nanos(clock.time_at(nanos_to_system_time(local))) - master(local)) as i64;
+            if i == 0 {
+                crate::ensure_eq!(offset, -5_000_000_000);
+            }
+            clock.on_sample(nanos_to_system_time(local), PortIdentity::ALL, offset, 1_000);
+        }
+        crate::ensure_eq!(clock.state(), PtpState::Slave);
+        crate::ensure_eq!(offset.abs() < 1_000, true);
+        crate::ensure_eq!((clock.freq_ppb() - 100_000.0).abs() < 100.0, true);
+
+        // Local times and times of the clock convert back and forth.
+        let local: SystemTime = start + Duration::from_secs(100);
+        let time: SystemTime = clock.time_at(local);
+        let delta: i128 = system_time_to_nanos(clock.to_local(time)) - system_time_to_nanos(local);
+        crate::ensure_eq!(delta.abs() <= 1, true);
+        Ok(())
+    }
+}
@@ -0,0 +1,432 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::fail::Fail;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Version of PTP that is spoken.
+pub const PTP_VERSION: u8 = 2;
+
+/// Size of the common header of PTP messages.
+pub const PTP_HEADER_SIZE: usize = 34;
+
+/// Size of a PTP timestamp: 48 bits of seconds and 32 bits of nanoseconds.
+const PTP_TIMESTAMP_SIZE: usize = 10;
+
+/// Size of a port identity: a clock identity and a port number.
+const PORT_IDENTITY_SIZE: usize = 10;
+
+/// Types of messages (IEEE 1588-2019, section 13.3.2.2).
+pub const PTP_SYNC: u8 = 0x0;
+pub const PTP_DELAY_REQ: u8 = 0x1;
+pub const PTP_FOLLOW_UP: u8 = 0x8;
+pub const PTP_DELAY_RESP: u8 = 0x9;
+pub const PTP_ANNOUNCE: u8 = 0xb;
+pub const PTP_SIGNALING: u8 = 0xc;
+
+/// Bits of the flag field.
+pub const PTP_FLAG_TWO_STEP: u16 = 1 << 9;
+pub const PTP_FLAG_UNICAST: u16 = 1 << 10;
+pub const PTP_FLAG_UTC_OFFSET_VALID: u16 = 1 << 2;
+pub const PTP_FLAG_PTP_TIMESCALE: u16 = 1 << 3;
+
+/// Type of the TLV that asks a master for unicast transmission of a type of message (IEEE 1588-2019, section 16.1).
+const TLV_REQUEST_UNICAST_TRANSMISSION: u16 = 0x0004;
+
+/// Values of the control field, which only matter to PTPv1 devices.
+const CONTROL_SYNC: u8 = 0;
+const CONTROL_DELAY_REQ: u8 = 1;
+const CONTROL_OTHER: u8 = 5;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Time on the timescale of a PTP clock.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PtpTimestamp {
+    /// Seconds, of which only 48 bits are carried.
+    pub seconds: u64,
+    pub nanoseconds: u32,
+}
+
+/// Identity of a port of a PTP clock.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PortIdentity {
+    pub clock_identity: [u8; 8],
+    pub port_number: u16,
+}
+
+/// Common header of PTP messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PtpHeader {
+    pub message_type: u8,
+    pub domain: u8,
+    pub flags: u16,
+    /// Residence time in the transparent clocks along the path, in nanoseconds multiplied by 2^16.
+    pub correction: i64,
+    pub source: PortIdentity,
+    pub sequence_id: u16,
+    pub log_message_interval: i8,
+}
+
+/// Request for the unicast transmission of a type of message, which a master grants for some duration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnicastRequest {
+    pub message_type: u8,
+    pub log_inter_message_period: i8,
+    /// Duration of the transmission, in seconds.
+    pub duration: u32,
+}
+
+/// Body of a PTP message. Only the messages that an ordinary clock in the slave state uses are decoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PtpBody {
+    Sync {
+        origin: PtpTimestamp,
+    },
+    DelayReq {
+        origin: PtpTimestamp,
+    },
+    FollowUp {
+        precise_origin: PtpTimestamp,
+    },
+    DelayResp {
+        receive: PtpTimestamp,
+        requesting: PortIdentity,
+    },
+    Announce {
+        /// Offset of TAI to UTC, in seconds.
+        current_utc_offset: i16,
+    },
+    Signaling {
+        target: PortIdentity,
+        requests: Vec<UnicastRequest>,
+    },
+    /// A message that is not decoded.
+    Other,
+}
+
+/// A PTP message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PtpMessage {
+    pub header: PtpHeader,
+    pub body: PtpBody,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl PtpTimestamp {
+    /// Creates a timestamp from nanoseconds since the epoch of the timescale, which is clamped to the range that is
+    /// carried.
+    pub fn from_nanos(nanos: i128) -> Self {
+        let nanos: i128 = nanos.clamp(0, ((1 << 48) * 1_000_000_000) - 1);
+        Self {
+            seconds: (nanos / 1_000_000_000) as u64,
+            nanoseconds: (nanos % 1_000_000_000) as u32,
+        }
+    }
+
+    /// Gets the nanoseconds since the epoch of the timescale.
+    pub fn as_nanos(&self) -> i128 {
+        (self.seconds as i128) * 1_000_000_000 + (self.nanoseconds as i128)
+    }
+
+    fn parse(buf: &[u8]) -> Result<Self, Fail> {
+        let mut seconds: [u8; 8] = [0; 8];
+        seconds[2..].copy_from_slice(&buf[0..6]);
+        let nanoseconds: u32 = u32::from_be_bytes([buf[6], buf[7], buf[8], buf[9]]);
+        if nanoseconds >= 1_000_000_000 {
+            let cause: String = format!("invalid nanoseconds (nanoseconds={:?})", nanoseconds);
+            warn!("parse(): {}", cause);
+            return Err(Fail::new(libc::EBADMSG, &cause));
+        }
+        Ok(Self {
+            seconds: u64::from_be_bytes(seconds),
+            nanoseconds,
+        })
+    }
+
+    fn serialize(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.seconds.to_be_bytes()[2..]);
+        buf.extend_from_slice(&self.nanoseconds.to_be_bytes());
+    }
+}
+
+impl PortIdentity {
+    /// Identity that matches every port.
+    pub const ALL: Self = Self {
+        clock_identity: [0xff; 8],
+        port_number: 0xffff,
+    };
+
+    fn parse(buf: &[u8]) -> Self {
+        let mut clock_identity: [u8; 8] = [0; 8];
+        clock_identity.copy_from_slice(&buf[0..8]);
+        Self {
+            clock_identity,
+            port_number: u16::from_be_bytes([buf[8], buf[9]]),
+        }
+    }
+
+    fn serialize(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.clock_identity);
+        buf.extend_from_slice(&self.port_number.to_be_bytes());
+    }
+}
+
+impl PtpHeader {
+    /// Creates the header of a message of [message_type] that is sent by [source].
+    pub fn new(message_type: u8, domain: u8, source: PortIdentity, sequence_id: u16) -> Self {
+        Self {
+            message_type,
+            domain,
+            flags: 0,
+            correction: 0,
+            source,
+            sequence_id,
+            log_message_interval: 0x7f,
+        }
+    }
+
+    /// Gets the correction field in nanoseconds.
+    pub fn correction_nanos(&self) -> i64 {
+        self.correction >> 16
+    }
+}
+
+impl PtpMessage {
+    /// Parses the PTP message in [buf].
+    pub fn parse(buf: &[u8]) -> Result<Self, Fail> {
+        if buf.len() < PTP_HEADER_SIZE {
+            let cause: String = format!("message is too short (len={:?})", buf.len());
+            warn!("parse(): {}", cause);
+            return Err(Fail::new(libc::EBADMSG, &cause));
+        }
+        let version: u8 = buf[1] & 0x0f;
+        if version != PTP_VERSION {
+            let cause: String = format!("unsupported version (version={:?})", version);
+            warn!("parse(): {}", cause);
+            return Err(Fail::new(libc::ENOTSUP, &cause));
+        }
+        let length: usize = u16::from_be_bytes([buf[2], buf[3]]) as usize;
+        if length < PTP_HEADER_SIZE || length > buf.len() {
+            let cause: String = format!("invalid message length (length={:?}, len={:?})", length, buf.len());
+            warn!("parse(): {}", cause);
+            return Err(Fail::new(libc::EBADMSG, &cause));
+        }
+        let header: PtpHeader = PtpHeader {
+            message_type: buf[0] & 0x0f,
+            domain: buf[4],
+            flags: u16::from_be_bytes([buf[6], buf[7]]),
+            correction: i64::from_be_bytes(buf[8..16].try_into().expect("slice has the size of an i64")),
+            source: PortIdentity::parse(&buf[20..30]),
+            sequence_id: u16::from_be_bytes([buf[30], buf[31]]),
+            log_message_interval: buf[33] as i8,
+        };
+
+        let body: &[u8] = &buf[PTP_HEADER_SIZE..length];
+        let expected: usize = match header.message_type {
+            PTP_SYNC | PTP_DELAY_REQ | PTP_FOLLOW_UP => PTP_TIMESTAMP_SIZE,
+            PTP_DELAY_RESP => PTP_TIMESTAMP_SIZE + PORT_IDENTITY_SIZE,
+            // Origin timestamp and current UTC offset. The description of the grandmaster that follows is not used.
+            PTP_ANNOUNCE => PTP_TIMESTAMP_SIZE + 2,
+            _ => 0,
+        };
+        if body.len() < expected {
+            let cause: String = format!(
+                "message body is too short (type={:?}, len={:?})",
+                header.message_type,
+                body.len()
+            );
+            warn!("parse(): {}", cause);
+            return Err(Fail::new(libc::EBADMSG, &cause));
+        }
+        let body: PtpBody = match header.message_type {
+            PTP_SYNC => PtpBody::Sync {
+                origin: PtpTimestamp::parse(body)?,
+            },
+            PTP_DELAY_REQ => PtpBody::DelayReq {
+                origin: PtpTimestamp::parse(body)?,
+            },
+            PTP_FOLLOW_UP => PtpBody::FollowUp {
+                precise_origin: PtpTimestamp::parse(body)?,
+            },
+            PTP_DELAY_RESP => PtpBody::DelayResp {
+                receive: PtpTimestamp::parse(body)?,
+                requesting: PortIdentity::parse(&body[PTP_TIMESTAMP_SIZE..]),
+            },
+            PTP_ANNOUNCE => PtpBody::Announce {
+                current_utc_offset: i16::from_be_bytes([body[PTP_TIMESTAMP_SIZE], body[PTP_TIMESTAMP_SIZE + 1]]),
+            },
+            _ => PtpBody::Other,
+        };
+        Ok(Self { header, body })
+    }
+
+    /// Serializes the message.
+    pub fn serialize(&self) -> Vec<u8> {
+        let (control, message_type): (u8, u8) = match self.body {
+            PtpBody::Sync { .. } => (CONTROL_SYNC, PTP_SYNC),
+            PtpBody::DelayReq { .. } => (CONTROL_DELAY_REQ, PTP_DELAY_REQ),
+            _ => (CONTROL_OTHER, self.header.message_type),
+        };
+        let mut buf: Vec<u8> = Vec::with_capacity(PTP_HEADER_SIZE + 64);
+        buf.push(message_type & 0x0f);
+        buf.push(PTP_VERSION);
+        // Message length, which is filled in below.
+        buf.extend_from_slice(&[0, 0]);
+        buf.push(self.header.domain);
+        buf.push(0);
+        buf.extend_from_slice(&self.header.flags.to_be_bytes());
+        buf.extend_from_slice(&self.header.correction.to_be_bytes());
+        buf.extend_from_slice(&[0; 4]);
+        self.header.source.serialize(&mut buf);
+        buf.extend_from_slice(&self.header.sequence_id.to_be_bytes());
+        buf.push(control);
+        buf.push(self.header.log_message_interval as u8);
+
+        match &self.body {
+            PtpBody::Sync { origin } | PtpBody::DelayReq { origin } => origin.serialize(&mut buf),
+            PtpBody::FollowUp { precise_origin } => precise_origin.serialize(&mut buf),
+            PtpBody::DelayResp { receive, requesting } => {
+                receive.serialize(&mut buf);
+                requesting.serialize(&mut buf);
+            },
+            PtpBody::Announce { current_utc_offset } => {
+                // Only the fields that are decoded are carried, since a client never announces itself.
+                PtpTimestamp::default().serialize(&mut buf);
+                buf.extend_from_slice(&current_utc_offset.to_be_bytes());
+            },
+            PtpBody::Signaling { target, requests } => {
+                target.serialize(&mut buf);
+                for request in requests {
+                    buf.extend_from_slice(&TLV_REQUEST_UNICAST_TRANSMISSION.to_be_bytes());
+                    buf.extend_from_slice(&6u16.to_be_bytes());
+                    buf.push(request.message_type << 4);
+                    buf.push(request.log_inter_message_period as u8);
+                    buf.extend_from_slice(&request.duration.to_be_bytes());
+                }
+            },
+            PtpBody::Other => (),
+        }
+        let length: [u8; 2] = (buf.len() as u16).to_be_bytes();
+        buf[2..4].copy_from_slice(&length);
+        buf
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        PortIdentity,
+        PtpBody,
+        PtpHeader,
+        PtpMessage,
+        PtpTimestamp,
+        UnicastRequest,
+        PTP_DELAY_RESP,
+        PTP_FLAG_TWO_STEP,
+        PTP_SIGNALING,
+        PTP_SYNC,
+    };
+    use ::anyhow::Result;
+
+    const SOURCE: PortIdentity = PortIdentity {
+        clock_identity: [1, 2, 3, 4, 5, 6, 7, 8],
+        port_number: 1,
+    };
+
+    /// Tests that messages survive a round trip through their wire format.
+    #[test]
+    fn test_ptp_message_round_trip() -> Result<()> {
+        let mut header: PtpHeader = PtpHeader::new(PTP_SYNC, 24, SOURCE, 7);
+        header.flags = PTP_FLAG_TWO_STEP;
+        header.correction = 3 << 16;
+        let sync: PtpMessage = PtpMessage {
+            header,
+            body: PtpBody::Sync {
+                origin: PtpTimestamp {
+                    seconds: 1 << 40,
+                    nanoseconds: 999_999_999,
+                },
+            },
+        };
+        let buf: Vec<u8> = sync.serialize();
+        crate::ensure_eq!(buf.len(), 44);
+        let parsed: PtpMessage = PtpMessage::parse(&buf)?;
+        crate::ensure_eq!(parsed.header.correction_nanos(), 3);
+        crate::ensure_eq!(parsed, sync);
+
+        let delay_resp: PtpMessage = PtpMessage {
+            header: PtpHeader::new(PTP_DELAY_RESP, 0, SOURCE, 9),
+            body: PtpBody::DelayResp {
+                receive: PtpTimestamp::from_nanos(5_000_000_123),
+                requesting: PortIdentity::ALL,
+            },
+        };
+        let buf: Vec<u8> = delay_resp.serialize();
+        crate::ensure_eq!(buf.len(), 54);
+        crate::ensure_eq!(PtpMessage::parse(&buf)?, delay_resp);
+        Ok(())
+    }
+
+    /// Tests the wire format of a request for unicast transmission.
+    #[test]
+    fn test_ptp_signaling_serialize() -> Result<()> {
+        let signaling: PtpMessage = PtpMessage {
+            header: PtpHeader::new(PTP_SIGNALING, 0, SOURCE, 0),
+            body: PtpBody::Signaling {
+                target: PortIdentity::ALL,
+                requests: vec![UnicastRequest {
+                    message_type: PTP_SYNC,
+                    log_inter_message_period: -3,
+                    duration: 300,
+                }],
+            },
+        };
+        let buf: Vec<u8> = signaling.serialize();
+        crate::ensure_eq!(buf.len(), 54);
+        crate::ensure_eq!(
+            &buf[44..54],
+            &[0x00, 0x04, 0x00, 0x06, 0x00, 0xfd, 0x00, 0x00, 0x01, 0x2c]
+        );
+        crate::ensure_eq!(PtpMessage::parse(&buf)?.body, PtpBody::Other);
+        Ok(())
+    }
+
+    /// Tests that malformed messages are rejected.
+    #[test]
+    fn test_ptp_message_parse_malformed() -> Result<()> {
+        let sync: PtpMessage = PtpMessage {
+            header: PtpHeader::new(PTP_SYNC, 0, SOURCE, 0),
+            body: PtpBody::Sync {
+                origin: PtpTimestamp::default(),
+            },
+        };
+        let buf: Vec<u8> = sync.serialize();
+        crate::ensure_eq!(PtpMessage::parse(&buf[..40]).is_err(), true);
+
+        let mut version1: Vec<u8> = buf.clone();
+        version1[1] = 1;
+        crate::ensure_eq!(PtpMessage::parse(&version1).is_err(), true);
+
+        let mut bad_nanoseconds: Vec<u8> = buf.clone();
+        bad_nanoseconds[40..44].copy_from_slice(&1_000_000_000u32.to_be_bytes());
+        crate::ensure_eq!(PtpMessage::parse(&bad_nanoseconds).is_err(), true);
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Sans-I/O client of the Precision Time Protocol (IEEE 1588-2019) over UDP, which runs an ordinary clock in the slave
+//! state. The client measures the offset from a master with the delay request-response mechanism and steers a clock
+//! that the network stack maintains, so that applications can read the time of the master without a system call.
+
+//======================================================================================================================
+// Exports
+//======================================================================================================================
+
+pub mod client;
+pub mod clock;
+pub mod message;
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::fail::Fail;
+use ::std::{
+    net::Ipv4Addr,
+    time::Duration,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// UDP port of event messages, which are timestamped.
+pub const PTP_EVENT_PORT: u16 = 319;
+
+/// UDP port of general messages.
+pub const PTP_GENERAL_PORT: u16 = 320;
+
+/// Offset of TAI, which is the timescale of PTP, to UTC until a master announces it, in seconds.
+pub const DEFAULT_UTC_OFFSET: i16 = 37;
+
+/// Default intervals of messages, as logarithms to the base 2 of seconds.
+const DEFAULT_LOG_SYNC_INTERVAL: i8 = 0;
+const DEFAULT_LOG_DELAY_REQ_INTERVAL: i8 = 0;
+
+/// Range of the intervals of messages that may be configured, as logarithms to the base 2 of seconds.
+const MIN_LOG_INTERVAL: i8 = -7;
+const MAX_LOG_INTERVAL: i8 = 7;
+
+/// Default duration of the unicast transmissions that are requested from the master.
+const DEFAULT_GRANT_DURATION: Duration = Duration::from_secs(300);
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Configuration of a PTP client. Since the network stack does not join multicast groups, the master must send its
+/// messages to the unicast address of the client, either because it grants unicast transmission when the client asks
+/// for it or because it is configured to do so.
+#[derive(Clone, Debug)]
+pub struct PtpConfig {
+    /// Local address to which the event and general ports are bound.
+    local: Ipv4Addr,
+    /// Address of the master. Messages from other addresses are ignored.
+    master: Ipv4Addr,
+    /// PTP domain of the master.
+    domain: u8,
+    /// Identity of the clock of the client.
+    clock_identity: [u8; 8],
+    /// Interval of the Sync messages that are requested from the master.
+    log_sync_interval: i8,
+    /// Interval of the Delay_Req messages that are sent to the master.
+    log_delay_req_interval: i8,
+    /// Duration of the unicast transmissions that are requested from the master, or zero to not request them.
+    grant_duration: Duration,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl PtpConfig {
+    /// Creates a configuration that follows [master] from the [local] address, in the default domain and with a random
+    /// clock identity.
+    pub fn new(local: Ipv4Addr, master: Ipv4Addr) -> Self {
+        Self {
+            local,
+            master,
+            domain: 0,
+            clock_identity: ::rand::random(),
+            log_sync_interval: DEFAULT_LOG_SYNC_INTERVAL,
+            log_delay_req_interval: DEFAULT_LOG_DELAY_REQ_INTERVAL,
+            grant_duration: DEFAULT_GRANT_DURATION,
+        }
+    }
+
+    pub fn with_domain(mut self, domain: u8) -> Self {
+        self.domain = domain;
+        self
+    }
+
+    pub fn with_clock_identity(mut self, clock_identity: [u8; 8]) -> Self {
+        self.clock_identity = clock_identity;
+        self
+    }
+
+    /// Sets the intervals of Sync and Delay_Req messages, as logarithms to the base 2 of seconds.
+    pub fn with_intervals(mut self, log_sync_interval: i8, log_delay_req_interval: i8) -> Result<Self, Fail> {
+        let range = MIN_LOG_INTERVAL..=MAX_LOG_INTERVAL;
+        if !range.contains(&log_sync_interval) || !range.contains(&log_delay_req_interval) {
+            let cause: String = format!(
+                "invalid message intervals (log_sync_interval={:?}, log_delay_req_interval={:?})",
+                log_sync_interval, log_delay_req_interval
+            );
+            error!("with_intervals(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        self.log_sync_interval = log_sync_interval;
+        self.log_delay_req_interval = log_delay_req_interval;
+        Ok(self)
+    }
+
+    /// Sets the duration of the unicast transmissions that are requested from the master. Zero does not request them,
+    /// for masters that are configured to send to the client.
+    pub fn with_grant_duration(mut self, grant_duration: Duration) -> Self {
+        self.grant_duration = grant_duration;
+        self
+    }
+
+    pub fn local(&self) -> Ipv4Addr {
+        self.local
+    }
+
+    pub fn master(&self) -> Ipv4Addr {
+        self.master
+    }
+
+    pub fn domain(&self) -> u8 {
+        self.domain
+    }
+
+    pub fn clock_identity(&self) -> [u8; 8] {
+        self.clock_identity
+    }
+
+    pub fn log_sync_interval(&self) -> i8 {
+        self.log_sync_interval
+    }
+
+    pub fn log_delay_req_interval(&self) -> i8 {
+        self.log_delay_req_interval
+    }
+
+    pub fn grant_duration(&self) -> Duration {
+        self.grant_duration
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Converts the logarithm to the base 2 of an interval into the interval.
+pub fn log_interval(log_interval: i8) -> Duration {
+    Duration::from_secs_f64(2f64.powi(log_interval as i32))
+}
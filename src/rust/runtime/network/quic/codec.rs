@@ -0,0 +1,159 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::fail::Fail;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Largest value of a variable-length integer.
+pub const MAX_VARINT: u64 = (1 << 62) - 1;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Reads the fields of a QUIC packet or frame in network byte order. Reads past the end of the buffer fail.
+pub struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    /// Gets the number of bytes that are left to read.
+    pub fn remaining(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// Reads the next [len] bytes.
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8], Fail> {
+        if len > self.buf.len() {
+            let cause: String = format!("buffer is too short (len={:?}, expected={:?})", self.buf.len(), len);
+            warn!("bytes(): {}", cause);
+            return Err(Fail::new(libc::EBADMSG, &cause));
+        }
+        let (bytes, rest): (&'a [u8], &'a [u8]) = self.buf.split_at(len);
+        self.buf = rest;
+        Ok(bytes)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, Fail> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn u32(&mut self) -> Result<u32, Fail> {
+        let bytes: &[u8] = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Reads a variable-length integer (RFC 9000, section 16).
+    pub fn varint(&mut self) -> Result<u64, Fail> {
+        let first: u8 = self.u8()?;
+        let len: usize = 1 << (first >> 6);
+        let mut value: u64 = (first & 0x3f) as u64;
+        for byte in self.bytes(len - 1)? {
+            value = (value << 8) | *byte as u64;
+        }
+        Ok(value)
+    }
+
+    /// Reads a vector with a variable-length integer as length prefix.
+    pub fn varint_vec(&mut self) -> Result<&'a [u8], Fail> {
+        let len: u64 = self.varint()?;
+        if len > self.buf.len() as u64 {
+            let cause: String = format!("vector is too long (len={:?})", len);
+            warn!("varint_vec(): {}", cause);
+            return Err(Fail::new(libc::EBADMSG, &cause));
+        }
+        self.bytes(len as usize)
+    }
+
+    /// Reads the rest of the buffer.
+    pub fn rest(&mut self) -> &'a [u8] {
+        ::std::mem::take(&mut self.buf)
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Gets the number of bytes that [value] takes as a variable-length integer.
+pub fn varint_len(value: u64) -> usize {
+    match value {
+        0..=0x3f => 1,
+        0x40..=0x3fff => 2,
+        0x4000..=0x3fff_ffff => 4,
+        _ => 8,
+    }
+}
+
+/// Appends [value] to [out] as a variable-length integer.
+pub fn put_varint(out: &mut Vec<u8>, value: u64) {
+    debug_assert!(value <= MAX_VARINT);
+    match varint_len(value) {
+        1 => out.push(value as u8),
+        2 => out.extend_from_slice(&(value as u16 | 0x4000).to_be_bytes()),
+        4 => out.extend_from_slice(&(value as u32 | 0x8000_0000).to_be_bytes()),
+        _ => out.extend_from_slice(&(value | 0xc000_0000_0000_0000).to_be_bytes()),
+    }
+}
+
+/// Appends [data] to [out] with its length as a variable-length integer prefix.
+pub fn put_varint_vec(out: &mut Vec<u8>, data: &[u8]) {
+    put_varint(out, data.len() as u64);
+    out.extend_from_slice(data);
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        put_varint,
+        Reader,
+    };
+    use crate::runtime::crypto::unhex;
+    use ::anyhow::Result;
+
+    /// Examples of RFC 9000, appendix A.1.
+    #[test]
+    fn test_varint() -> Result<()> {
+        for (encoded, value) in [
+            ("c2197c5eff14e88c", 151288809941952652u64),
+            ("9d7f3e7d", 494878333),
+            ("7bbd", 15293),
+            ("25", 37),
+        ] {
+            let encoded: Vec<u8> = unhex(encoded);
+            let mut reader: Reader = Reader::new(&encoded);
+            crate::ensure_eq!(reader.varint()?, value);
+            crate::ensure_eq!(reader.is_empty(), true);
+            let mut out: Vec<u8> = Vec::new();
+            put_varint(&mut out, value);
+            crate::ensure_eq!(out, encoded);
+        }
+        // Two-byte encoding of 37, which is not the shortest.
+        crate::ensure_eq!(Reader::new(&unhex("4025")).varint()?, 37);
+        crate::ensure_eq!(Reader::new(&unhex("9d7f3e")).varint().is_err(), true);
+        Ok(())
+    }
+}
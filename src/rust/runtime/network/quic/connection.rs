@@ -0,0 +1,1961 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    expect_some,
+    runtime::{
+        fail::Fail,
+        network::{
+            quic::{
+                codec::{
+                    Reader,
+                    MAX_VARINT,
+                },
+                frame::{
+                    ack_ranges,
+                    Frame,
+                },
+                packet::{
+                    check_reserved_bits,
+                    decode_packet_number,
+                    header_len,
+                    initial_secrets,
+                    key_phase,
+                    packet_number_len,
+                    protect,
+                    put_long_header,
+                    put_short_header,
+                    unprotect_header,
+                    ConnectionId,
+                    Header,
+                    Keys,
+                    PacketKey,
+                    PacketType,
+                    PACKET_TAG_SIZE,
+                },
+                params::{
+                    TransportParameters,
+                    DEFAULT_ACK_DELAY_EXPONENT,
+                },
+                ranges::RangeSet,
+                recovery::{
+                    levels,
+                    AckOutcome,
+                    Recovery,
+                    SentFrame,
+                    SentPacket,
+                    TimeoutOutcome,
+                },
+                stream::{
+                    Chunk,
+                    RecvStream,
+                    SendStream,
+                },
+                QuicConfig,
+                APPLICATION_ERROR,
+                CONNECTION_ID_LIMIT_ERROR,
+                CRYPTO_BUFFER_EXCEEDED,
+                CRYPTO_ERROR,
+                FLOW_CONTROL_ERROR,
+                FRAME_ENCODING_ERROR,
+                INTERNAL_ERROR,
+                LOCAL_CID_LEN,
+                MAX_DATAGRAM_SIZE,
+                MIN_INITIAL_DATAGRAM_SIZE,
+                PROTOCOL_VIOLATION,
+                STREAM_LIMIT_ERROR,
+                STREAM_STATE_ERROR,
+                TRANSPORT_PARAMETER_ERROR,
+            },
+            tls::{
+                keys::Secret,
+                provider::CryptoProvider,
+                session::TlsSession,
+                EncryptionLevel,
+                TlsRole,
+            },
+        },
+    },
+};
+use ::std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+        VecDeque,
+    },
+    rc::Rc,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Most bytes of CRYPTO frames that are buffered ahead of the handshake (RFC 9000, section 7.5).
+const MAX_CRYPTO_BUFFER: u64 = 64 * 1024;
+
+/// Most ranges of packet numbers that are acknowledged and remembered to detect duplicates, in each space.
+const MAX_ACK_RANGES: usize = 32;
+
+/// Multiple of the bytes that arrived from an unvalidated address that a server may send (RFC 9000, section 8.1).
+const AMPLIFICATION_FACTOR: usize = 3;
+
+/// Smallest room for frames that makes building a packet worthwhile.
+const MIN_PAYLOAD_SIZE: usize = 24;
+
+/// Largest overhead of CRYPTO and STREAM frames in a packet that fits in [MAX_DATAGRAM_SIZE].
+const CRYPTO_FRAME_OVERHEAD: usize = 1 + 8 + 2;
+const STREAM_FRAME_OVERHEAD: usize = 1 + 8 + 8 + 2;
+
+/// Largest size of a RESET_STREAM frame.
+const RESET_STREAM_FRAME_SIZE: usize = 1 + 8 + 8 + 8;
+
+/// Bit of a stream ID that is set for streams that servers open, and the one that is set for unidirectional streams.
+const STREAM_SERVER_BIT: u64 = 0x1;
+const STREAM_UNI_BIT: u64 = 0x2;
+
+/// Number of probe timeouts that the closing and draining states last (RFC 9000, section 10.2).
+const CLOSE_PTO_COUNT: u32 = 3;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// State of a connection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    Handshaking,
+    Established,
+    /// Connection was closed on this side, which answers packets with CONNECTION_CLOSE frames until the deadline.
+    Closing(Instant),
+    /// Peer closed the connection, so nothing is sent until the deadline.
+    Draining(Instant),
+    Closed,
+}
+
+/// Packet number space of an encryption level.
+struct Space {
+    read: Option<Keys>,
+    write: Option<Keys>,
+    next_pn: u64,
+    received: RangeSet,
+    /// Packets below this number are dropped, as their range was forgotten.
+    ignore_below: u64,
+    /// Time at which the largest packet number arrived.
+    largest_received_time: Option<Instant>,
+    /// Whether an ack-eliciting packet arrived since the last ACK frame was sent.
+    ack_pending: bool,
+    /// Whether a probe must be sent, regardless of congestion control.
+    probe_pending: bool,
+    crypto_send: SendStream,
+    crypto_recv: RecvStream,
+}
+
+/// Bidirectional stream of a connection.
+struct Stream {
+    send: SendStream,
+    recv: RecvStream,
+    /// Whether the application closed the stream, so that data that arrives is dropped.
+    closed: bool,
+}
+
+/// Contents of the CONNECTION_CLOSE frame that closes the connection on this side.
+struct CloseReason {
+    error_code: u64,
+    /// Type of the frame that triggered a transport error, or none for errors of the application.
+    frame_type: Option<u64>,
+    reason: String,
+}
+
+/// Error that closes the connection, with the transport error code for the peer.
+struct TransportError {
+    code: u64,
+    fail: Fail,
+}
+
+/// Packet that was planned and must still be protected.
+struct PlannedPacket {
+    level: EncryptionLevel,
+    pn: u64,
+    pn_len: usize,
+    payload: Vec<u8>,
+    frames: Vec<SentFrame>,
+    ack_eliciting: bool,
+}
+
+/// QUIC connection (RFC 9000). Connections do no I/O: the caller feeds them the UDP payloads from the peer with
+/// [QuicConnection::receive], sends those of [QuicConnection::poll_transmit] and calls [QuicConnection::on_timeout]
+/// when [QuicConnection::timeout] expires. Only bidirectional streams are supported.
+pub struct QuicConnection {
+    config: QuicConfig,
+    role: TlsRole,
+    provider: Rc<dyn CryptoProvider>,
+    tls: TlsSession,
+    state: State,
+    spaces: [Space; 3],
+    /// Key phase of the application keys, which flips on every key update of the peer.
+    key_phase: bool,
+    /// Application key of the previous key phase, for packets that were reordered around a key update.
+    prev_read_key: Option<PacketKey>,
+    recovery: Recovery,
+    local_cid: ConnectionId,
+    remote_cid: ConnectionId,
+    /// Sequence number of [QuicConnection::remote_cid].
+    remote_cid_seq: u64,
+    /// Connection IDs that the peer issued and that are not used yet, by sequence number.
+    spare_remote_cids: BTreeMap<u64, ConnectionId>,
+    /// Sequence numbers of the connection IDs of the peer that must be retired.
+    retire_pending: Vec<u64>,
+    /// Destination connection ID of the first Initial packet of the client.
+    original_dcid: ConnectionId,
+    /// Whether a client learned the connection ID of the server.
+    remote_cid_confirmed: bool,
+    peer_params: Option<TransportParameters>,
+    handshake_confirmed: bool,
+    handshake_done_pending: bool,
+    /// Whether a server validated the address of the client, which lifts the anti-amplification limit.
+    address_validated: bool,
+    /// Whether a client knows that the server validated its address.
+    peer_validated: bool,
+    bytes_received: usize,
+    bytes_sent: usize,
+    idle_timeout: Duration,
+    last_activity: Instant,
+    streams: BTreeMap<u64, Stream>,
+    /// Streams that the peer opened and the application did not accept yet.
+    accept_queue: VecDeque<u64>,
+    /// Number of streams that were opened on this side.
+    local_streams_opened: u64,
+    /// Number of streams that the peer allows this side to open.
+    peer_max_streams: u64,
+    /// Number of streams that the peer opened.
+    peer_streams_opened: u64,
+    /// Number of streams that the peer is allowed to open.
+    local_max_streams: u64,
+    max_streams_pending: bool,
+    /// Stream that is served first in the next packet, so that streams share the bandwidth.
+    next_stream: u64,
+    /// Connection flow control of data that is sent.
+    peer_max_data: u64,
+    sent_data: u64,
+    /// Connection flow control of data that arrives.
+    local_max_data: u64,
+    received_data: u64,
+    consumed_data: u64,
+    max_data_pending: bool,
+    max_stream_data_pending: BTreeSet<u64>,
+    path_responses: Vec<[u8; 8]>,
+    close: Option<CloseReason>,
+    close_pending: bool,
+    error: Option<Fail>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Space {
+    fn new() -> Self {
+        Self {
+            read: None,
+            write: None,
+            next_pn: 0,
+            received: RangeSet::new(),
+            ignore_below: 0,
+            largest_received_time: None,
+            ack_pending: false,
+            probe_pending: false,
+            crypto_send: SendStream::new(MAX_VARINT),
+            crypto_recv: RecvStream::new(MAX_VARINT),
+        }
+    }
+}
+
+impl TransportError {
+    /// Builds a transport error with [code].
+    fn new(code: u64, cause: &str) -> Self {
+        warn!("transport error: {} (code={:#x})", cause, code);
+        Self {
+            code,
+            fail: Fail::new(libc::EPROTO, cause),
+        }
+    }
+}
+
+impl QuicConnection {
+    /// Starts the handshake of a client connection.
+    pub fn connect(config: QuicConfig, now: Instant) -> Self {
+        let provider: Rc<dyn CryptoProvider> = config.tls().crypto_provider();
+        let original_dcid: ConnectionId = ConnectionId::random(LOCAL_CID_LEN, provider.as_ref());
+        let local_cid: ConnectionId = ConnectionId::random(LOCAL_CID_LEN, provider.as_ref());
+        let mut params: TransportParameters = local_parameters(&config);
+        params.initial_source_connection_id = Some(local_cid.clone());
+        let tls: TlsSession = TlsSession::new_quic(TlsRole::Client, config.tls().clone(), &params.encode());
+        let mut connection: Self = Self::new(
+            config,
+            TlsRole::Client,
+            tls,
+            local_cid,
+            original_dcid.clone(),
+            original_dcid,
+            now,
+        );
+        // There is nothing that could fail before the peer answers.
+        if let Err(e) = connection.process_tls() {
+            connection.enter_closing(e, now);
+        }
+        connection
+    }
+
+    /// Accepts the connection that the client [datagram] opens on a server.
+    pub fn accept(config: QuicConfig, datagram: &[u8], now: Instant) -> Result<Self, Fail> {
+        let header: Header = Header::parse(datagram, LOCAL_CID_LEN)?;
+        if header.packet_type != PacketType::Initial
+            || datagram.len() < MIN_INITIAL_DATAGRAM_SIZE
+            || header.dcid.len() < LOCAL_CID_LEN
+        {
+            let cause: &str = "datagram does not open a connection";
+            warn!("accept(): {}", cause);
+            return Err(Fail::new(libc::EBADMSG, cause));
+        }
+        let provider: Rc<dyn CryptoProvider> = config.tls().crypto_provider();
+        let local_cid: ConnectionId = ConnectionId::random(LOCAL_CID_LEN, provider.as_ref());
+        let mut params: TransportParameters = local_parameters(&config);
+        params.original_destination_connection_id = Some(header.dcid.clone());
+        params.initial_source_connection_id = Some(local_cid.clone());
+        let tls: TlsSession = TlsSession::new_quic(TlsRole::Server, config.tls().clone(), &params.encode());
+        let mut connection: Self = Self::new(config, TlsRole::Server, tls, local_cid, header.scid, header.dcid, now);
+        connection.receive(datagram, now);
+        Ok(connection)
+    }
+
+    fn new(
+        config: QuicConfig,
+        role: TlsRole,
+        tls: TlsSession,
+        local_cid: ConnectionId,
+        remote_cid: ConnectionId,
+        original_dcid: ConnectionId,
+        now: Instant,
+    ) -> Self {
+        let mut spaces: [Space; 3] = [Space::new(), Space::new(), Space::new()];
+        let (client_secret, server_secret): (Secret, Secret) = initial_secrets(&original_dcid);
+        let (read_secret, write_secret): (Secret, Secret) = match role {
+            TlsRole::Client => (server_secret, client_secret),
+            TlsRole::Server => (client_secret, server_secret),
+        };
+        spaces[EncryptionLevel::Initial as usize].read = Some(Keys::initial(&read_secret));
+        spaces[EncryptionLevel::Initial as usize].write = Some(Keys::initial(&write_secret));
+        Self {
+            provider: config.tls().crypto_provider(),
+            idle_timeout: config.idle_timeout(),
+            local_max_streams: config.max_streams(),
+            local_max_data: config.max_data(),
+            config,
+            role,
+            tls,
+            state: State::Handshaking,
+            spaces,
+            key_phase: false,
+            prev_read_key: None,
+            recovery: Recovery::new(),
+            local_cid,
+            remote_cid,
+            remote_cid_seq: 0,
+            spare_remote_cids: BTreeMap::new(),
+            retire_pending: Vec::new(),
+            original_dcid,
+            remote_cid_confirmed: role == TlsRole::Server,
+            peer_params: None,
+            handshake_confirmed: false,
+            handshake_done_pending: false,
+            address_validated: role == TlsRole::Client,
+            peer_validated: role == TlsRole::Server,
+            bytes_received: 0,
+            bytes_sent: 0,
+            last_activity: now,
+            streams: BTreeMap::new(),
+            accept_queue: VecDeque::new(),
+            local_streams_opened: 0,
+            peer_max_streams: 0,
+            peer_streams_opened: 0,
+            max_streams_pending: false,
+            next_stream: 0,
+            peer_max_data: 0,
+            sent_data: 0,
+            received_data: 0,
+            consumed_data: 0,
+            max_data_pending: false,
+            max_stream_data_pending: BTreeSet::new(),
+            path_responses: Vec::new(),
+            close: None,
+            close_pending: false,
+            error: None,
+        }
+    }
+
+    /// Checks whether the handshake completed and the connection was not closed.
+    pub fn is_established(&self) -> bool {
+        self.state == State::Established
+    }
+
+    /// Checks whether the connection is over, so that it neither sends nor receives anything.
+    pub fn is_closed(&self) -> bool {
+        self.state == State::Closed
+    }
+
+    /// Gets the error that closed the connection, if it was closed.
+    pub fn error(&self) -> Option<Fail> {
+        self.error.clone()
+    }
+
+    /// Processes the UDP payload [datagram] that arrived from the peer. Packets that cannot be decrypted are dropped,
+    /// while violations of the protocol close the connection.
+    pub fn receive(&mut self, datagram: &[u8], now: Instant) {
+        match self.state {
+            State::Draining(_) | State::Closed => return,
+            State::Closing(_) => {
+                // Answer with the CONNECTION_CLOSE frame again, in case the previous one was lost.
+                self.close_pending = true;
+                return;
+            },
+            State::Handshaking | State::Established => (),
+        }
+        self.bytes_received += datagram.len();
+
+        let mut offset: usize = 0;
+        while offset < datagram.len() {
+            let header: Header = match Header::parse(&datagram[offset..], LOCAL_CID_LEN) {
+                Ok(header) => header,
+                Err(_) => break,
+            };
+            // Servers drop Initial packets in datagrams that are too small (RFC 9000, section 14.1).
+            if self.role == TlsRole::Server
+                && header.packet_type == PacketType::Initial
+                && datagram.len() < MIN_INITIAL_DATAGRAM_SIZE
+            {
+                break;
+            }
+            let packet: &[u8] = &datagram[offset..(offset + header.len)];
+            offset += header.len;
+            if let Err(e) = self.receive_packet(header, packet, now) {
+                self.enter_closing(e, now);
+                break;
+            }
+            if !matches!(self.state, State::Handshaking | State::Established) {
+                break;
+            }
+        }
+    }
+
+    /// Builds the next UDP payload for the peer, if anything must be sent.
+    pub fn poll_transmit(&mut self, now: Instant) -> Option<Vec<u8>> {
+        match self.state {
+            State::Draining(_) | State::Closed => return None,
+            State::Closing(_) => return self.transmit_close(now),
+            State::Handshaking | State::Established => (),
+        }
+
+        let budget: usize = self.send_budget();
+        let congestion_ok: bool = self.recovery.can_send(MAX_DATAGRAM_SIZE);
+        let mut packets: Vec<PlannedPacket> = Vec::new();
+        let mut size: usize = 0;
+        for level in levels() {
+            if self.spaces[level as usize].write.is_none() {
+                continue;
+            }
+            let pn_len: usize = self.next_pn_len(level);
+            let overhead: usize = self.packet_overhead(level, pn_len);
+            if size + overhead + MIN_PAYLOAD_SIZE > budget {
+                break;
+            }
+            if let Some(packet) = self.plan_packet(level, pn_len, budget - size - overhead, congestion_ok, now) {
+                size += overhead + packet.payload.len();
+                packets.push(packet);
+            }
+        }
+        if packets.is_empty() {
+            return None;
+        }
+        Some(self.seal(packets, size, budget, now))
+    }
+
+    /// Gets the time at which [QuicConnection::on_timeout] must be called.
+    pub fn timeout(&self) -> Option<Instant> {
+        match self.state {
+            State::Closing(deadline) | State::Draining(deadline) => Some(deadline),
+            State::Closed => None,
+            State::Handshaking | State::Established => {
+                let loss: Option<Instant> =
+                    self.recovery
+                        .timeout(self.handshake_confirmed, self.peer_validated, self.has_handshake_keys());
+                [self.idle_deadline(), loss].into_iter().flatten().min()
+            },
+        }
+    }
+
+    /// Acts upon the timers that expired by [now].
+    pub fn on_timeout(&mut self, now: Instant) {
+        match self.state {
+            State::Closing(deadline) | State::Draining(deadline) => {
+                if now >= deadline {
+                    self.state = State::Closed;
+                }
+                return;
+            },
+            State::Closed => return,
+            State::Handshaking | State::Established => (),
+        }
+
+        if self.idle_deadline().map_or(false, |deadline| now >= deadline) {
+            let cause: &str = "connection timed out";
+            warn!("on_timeout(): {}", cause);
+            self.error = Some(Fail::new(libc::ETIMEDOUT, cause));
+            self.state = State::Closed;
+            return;
+        }
+
+        let has_handshake_keys: bool = self.has_handshake_keys();
+        let loss: Option<Instant> =
+            self.recovery
+                .timeout(self.handshake_confirmed, self.peer_validated, has_handshake_keys);
+        if loss.map_or(true, |loss| now < loss) {
+            return;
+        }
+        match self
+            .recovery
+            .on_timeout(self.handshake_confirmed, self.peer_validated, has_handshake_keys, now)
+        {
+            Some(TimeoutOutcome::Lost(level, packets)) => {
+                for packet in packets {
+                    self.on_frames_lost(level, packet.frames);
+                }
+            },
+            Some(TimeoutOutcome::Probe(level)) => {
+                // Send the data of the packets in flight again, or a PING frame if there is none.
+                let frames: Vec<SentFrame> = self
+                    .recovery
+                    .unacked(level)
+                    .flat_map(|packet| packet.frames.iter().cloned())
+                    .collect();
+                self.on_frames_lost(level, frames);
+                self.spaces[level as usize].probe_pending = true;
+            },
+            None => (),
+        }
+    }
+
+    /// Closes the connection with the application [error_code].
+    pub fn close(&mut self, error_code: u64, now: Instant) {
+        let close: CloseReason = CloseReason {
+            error_code,
+            frame_type: None,
+            reason: String::new(),
+        };
+        let cause: &str = "connection was closed";
+        self.start_closing(close, Fail::new(libc::ECONNABORTED, cause), now);
+    }
+
+    /// Opens a stream and returns its ID.
+    pub fn open_stream(&mut self) -> Result<u64, Fail> {
+        self.check_established("open_stream")?;
+        if self.local_streams_opened >= self.peer_max_streams {
+            let cause: String = format!("peer does not allow more streams (limit={:?})", self.peer_max_streams);
+            warn!("open_stream(): {}", cause);
+            return Err(Fail::new(libc::EAGAIN, &cause));
+        }
+        let stream_id: u64 = self.stream_id(self.local_streams_opened, self.role);
+        self.local_streams_opened += 1;
+        let stream: Stream = self.new_stream(true);
+        self.streams.insert(stream_id, stream);
+        Ok(stream_id)
+    }
+
+    /// Takes the next stream that the peer opened, if there is one.
+    pub fn accept_stream(&mut self) -> Option<u64> {
+        self.accept_queue.pop_front()
+    }
+
+    /// Queues [data] on stream [stream_id].
+    pub fn stream_write(&mut self, stream_id: u64, data: &[u8]) -> Result<(), Fail> {
+        self.check_open("stream_write")?;
+        let stream: &mut Stream = self.get_stream("stream_write", stream_id)?;
+        if stream.send.is_finished() || stream.send.is_reset() {
+            let cause: String = format!("stream is shut down for writing (stream_id={:?})", stream_id);
+            warn!("stream_write(): {}", cause);
+            return Err(Fail::new(libc::EPIPE, &cause));
+        }
+        stream.send.write(data);
+        Ok(())
+    }
+
+    /// Ends stream [stream_id] after the data that was queued on it.
+    pub fn stream_finish(&mut self, stream_id: u64) -> Result<(), Fail> {
+        self.check_open("stream_finish")?;
+        let stream: &mut Stream = self.get_stream("stream_finish", stream_id)?;
+        if !stream.send.is_reset() {
+            stream.send.finish();
+        }
+        Ok(())
+    }
+
+    /// Reads up to [max_len] bytes from stream [stream_id]. An empty buffer means that the peer ended the stream, while
+    /// none means that no data arrived yet.
+    pub fn stream_read(&mut self, stream_id: u64, max_len: usize) -> Result<Option<Vec<u8>>, Fail> {
+        let stream: &mut Stream = self.get_stream("stream_read", stream_id)?;
+        if let Some(error_code) = stream.recv.reset_code() {
+            let cause: String = format!(
+                "peer reset the stream (stream_id={:?}, error_code={:?})",
+                stream_id, error_code
+            );
+            warn!("stream_read(): {}", cause);
+            return Err(Fail::new(libc::ECONNRESET, &cause));
+        }
+        if let Some(data) = stream.recv.read(max_len) {
+            let len: u64 = data.len() as u64;
+            self.on_consumed(stream_id, len);
+            return Ok(Some(data));
+        }
+        if stream.recv.is_finished() {
+            return Ok(Some(Vec::new()));
+        }
+        match self.error.as_ref() {
+            Some(e) => Err(e.clone()),
+            None => Ok(None),
+        }
+    }
+
+    /// Closes stream [stream_id] on this side. Data that was queued is still delivered, while data that arrives is
+    /// dropped.
+    pub fn stream_close(&mut self, stream_id: u64) -> Result<(), Fail> {
+        let stream: &mut Stream = self.get_stream("stream_close", stream_id)?;
+        if !stream.send.is_finished() && !stream.send.is_reset() {
+            stream.send.finish();
+        }
+        stream.closed = true;
+        if self.error.is_some() {
+            self.streams.remove(&stream_id);
+            return Ok(());
+        }
+        self.drain_stream(stream_id);
+        self.collect_streams();
+        Ok(())
+    }
+
+    /// Processes one packet of a datagram.
+    fn receive_packet(&mut self, header: Header, bytes: &[u8], now: Instant) -> Result<(), TransportError> {
+        let level: EncryptionLevel = match header.packet_type {
+            PacketType::Initial => EncryptionLevel::Initial,
+            PacketType::Handshake => EncryptionLevel::Handshake,
+            PacketType::OneRtt => EncryptionLevel::Application,
+            // Version negotiation, Retry and 0-RTT are not supported.
+            _ => return Ok(()),
+        };
+        let long_header: bool = header.packet_type != PacketType::OneRtt;
+        let known_dcid: bool = header.dcid == self.local_cid
+            || (self.role == TlsRole::Server && long_header && header.dcid == self.original_dcid);
+        if !known_dcid {
+            return Ok(());
+        }
+
+        // Remove packet protection, or drop the packet if that is not possible.
+        let mut packet: Vec<u8> = bytes.to_vec();
+        let (pn_len, truncated): (usize, u64) = match self.spaces[level as usize].read.as_ref() {
+            Some(keys) => match unprotect_header(&keys.header, &mut packet, header.pn_offset) {
+                Ok(result) => result,
+                Err(_) => return Ok(()),
+            },
+            None => return Ok(()),
+        };
+        let pn: u64 = decode_packet_number(self.spaces[level as usize].received.max(), truncated, pn_len);
+        let mut payload: Vec<u8> = packet.split_off(header.pn_offset + pn_len);
+        if !self.open_payload(level, pn, &packet, &mut payload) {
+            return Ok(());
+        }
+        let space: &Space = &self.spaces[level as usize];
+        if pn < space.ignore_below || space.received.contains(pn) {
+            return Ok(());
+        }
+        if !check_reserved_bits(packet[0]) {
+            return Err(TransportError::new(PROTOCOL_VIOLATION, "reserved bits are set"));
+        }
+
+        if self.role == TlsRole::Client && long_header && !self.remote_cid_confirmed {
+            self.remote_cid = header.scid;
+            self.remote_cid_confirmed = true;
+        }
+        self.last_activity = now;
+
+        let mut ack_eliciting: bool = false;
+        let mut reader: Reader = Reader::new(&payload);
+        if reader.is_empty() {
+            return Err(TransportError::new(PROTOCOL_VIOLATION, "packet carries no frames"));
+        }
+        while !reader.is_empty() {
+            let frame: Frame = Frame::parse(&mut reader).map_err(|e| TransportError {
+                code: FRAME_ENCODING_ERROR,
+                fail: Fail::new(libc::EPROTO, &e.cause),
+            })?;
+            if level != EncryptionLevel::Application && !frame.is_allowed_in_handshake() {
+                return Err(TransportError::new(
+                    PROTOCOL_VIOLATION,
+                    "frame is not allowed in handshake packets",
+                ));
+            }
+            ack_eliciting |= frame.is_ack_eliciting();
+            self.on_frame(level, frame, now)?;
+            if !matches!(self.state, State::Handshaking | State::Established) {
+                return Ok(());
+            }
+        }
+
+        let space: &mut Space = &mut self.spaces[level as usize];
+        if space.received.max().map_or(true, |largest| pn > largest) {
+            space.largest_received_time = Some(now);
+        }
+        space.received.insert(pn..(pn + 1));
+        if space.received.len() > MAX_ACK_RANGES {
+            space.received.truncate_front(MAX_ACK_RANGES);
+            space.ignore_below = space.received.first().map_or(0, |range| range.start);
+        }
+        space.ack_pending |= ack_eliciting;
+
+        // Servers validate the address of the client and drop the Initial keys on the first Handshake packet (RFC 9001,
+        // section 4.9.1).
+        if self.role == TlsRole::Server && level == EncryptionLevel::Handshake && !self.address_validated {
+            self.address_validated = true;
+            self.discard_keys(EncryptionLevel::Initial);
+        }
+        self.process_tls()
+    }
+
+    /// Decrypts [payload] of packet [pn] at [level] in place. Application packets of the other key phase are tried with
+    /// the key of the next phase, which means that the peer updated the keys, and then with that of the previous one
+    /// (RFC 9001, section 6).
+    fn open_payload(&mut self, level: EncryptionLevel, pn: u64, header: &[u8], payload: &mut Vec<u8>) -> bool {
+        let handshake_confirmed: bool = self.handshake_confirmed;
+        let space: &mut Space = &mut self.spaces[level as usize];
+        let keys: &mut Keys = match space.read.as_mut() {
+            Some(keys) => keys,
+            None => return false,
+        };
+        if level != EncryptionLevel::Application || key_phase(header[0]) == self.key_phase {
+            return keys.packet.open(pn, header, payload).is_ok();
+        }
+
+        let ciphertext: Vec<u8> = payload.clone();
+        if handshake_confirmed {
+            let next: PacketKey = keys.packet.next();
+            if next.open(pn, header, payload).is_ok() {
+                self.prev_read_key = Some(::std::mem::replace(&mut keys.packet, next));
+                if let Some(write) = space.write.as_mut() {
+                    write.packet = write.packet.next();
+                }
+                self.key_phase = !self.key_phase;
+                return true;
+            }
+        }
+        *payload = ciphertext;
+        match self.prev_read_key.as_ref() {
+            Some(key) => key.open(pn, header, payload).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Processes [frame] of a packet at [level].
+    fn on_frame(&mut self, level: EncryptionLevel, frame: Frame, now: Instant) -> Result<(), TransportError> {
+        match frame {
+            Frame::Padding | Frame::Ping => (),
+            Frame::Ack { delay, ranges } => self.on_ack_frame(level, delay, &ranges, now)?,
+            Frame::Crypto { offset, data } => self.on_crypto_frame(level, offset, data)?,
+            Frame::Stream {
+                stream_id,
+                offset,
+                data,
+                fin,
+            } => {
+                if !self.check_stream(stream_id, true)? {
+                    return Ok(());
+                }
+                let stream: &mut Stream = expect_some!(self.streams.get_mut(&stream_id), "stream was just checked");
+                let grown: u64 = stream
+                    .recv
+                    .receive(offset, data, fin)
+                    .map_err(|code| TransportError::new(code, "invalid STREAM frame"))?;
+                self.on_data_received(grown)?;
+                if stream_closed(&self.streams, stream_id) {
+                    self.drain_stream(stream_id);
+                    self.collect_streams();
+                }
+            },
+            Frame::ResetStream {
+                stream_id,
+                error_code,
+                final_size,
+            } => {
+                if !self.check_stream(stream_id, true)? {
+                    return Ok(());
+                }
+                let stream: &mut Stream = expect_some!(self.streams.get_mut(&stream_id), "stream was just checked");
+                let was_reset: bool = stream.recv.reset_code().is_some();
+                let read_offset: u64 = stream.recv.read_offset();
+                let grown: u64 = stream
+                    .recv
+                    .reset(error_code, final_size)
+                    .map_err(|code| TransportError::new(code, "invalid RESET_STREAM frame"))?;
+                self.on_data_received(grown)?;
+                if !was_reset {
+                    // The data that will never be read no longer counts against connection flow control.
+                    self.consumed_data += final_size - read_offset;
+                    self.update_max_data();
+                }
+                self.collect_streams();
+            },
+            Frame::StopSending { stream_id, error_code } => {
+                if self.check_stream(stream_id, false)? {
+                    let stream: &mut Stream = expect_some!(self.streams.get_mut(&stream_id), "stream was just checked");
+                    stream.send.reset(error_code);
+                }
+            },
+            // Tokens are only used for address validation of future connections, which is not supported.
+            Frame::NewToken | Frame::DataBlocked | Frame::StreamDataBlocked | Frame::StreamsBlocked => (),
+            Frame::MaxData(max) => self.peer_max_data = self.peer_max_data.max(max),
+            Frame::MaxStreamData { stream_id, max } => {
+                if self.check_stream(stream_id, false)? {
+                    let stream: &mut Stream = expect_some!(self.streams.get_mut(&stream_id), "stream was just checked");
+                    stream.send.set_max_data(max);
+                }
+            },
+            Frame::MaxStreams { bidi, max } => {
+                if bidi {
+                    self.peer_max_streams = self.peer_max_streams.max(max);
+                }
+            },
+            Frame::NewConnectionId {
+                seq,
+                retire_prior_to,
+                cid,
+            } => self.on_new_connection_id(seq, retire_prior_to, cid)?,
+            Frame::RetireConnectionId(seq) => {
+                // Only the connection ID of the handshake was issued, which cannot be retired while it is in use.
+                if seq != 0 {
+                    return Err(TransportError::new(
+                        PROTOCOL_VIOLATION,
+                        "peer retired a connection ID that was never issued",
+                    ));
+                }
+            },
+            Frame::PathChallenge(data) => self.path_responses.push(data),
+            Frame::PathResponse(_) => (),
+            Frame::ConnectionClose { error_code, reason, .. } => {
+                let cause: String = format!(
+                    "peer closed the connection (error_code={:#x}, reason={:?})",
+                    error_code,
+                    String::from_utf8_lossy(reason)
+                );
+                warn!("on_frame(): {}", cause);
+                self.error = Some(Fail::new(libc::ECONNRESET, &cause));
+                self.state = State::Draining(now + self.recovery.pto() * CLOSE_PTO_COUNT);
+            },
+            Frame::HandshakeDone => {
+                if self.role == TlsRole::Server {
+                    return Err(TransportError::new(PROTOCOL_VIOLATION, "client sent HANDSHAKE_DONE"));
+                }
+                if !self.handshake_confirmed {
+                    self.handshake_confirmed = true;
+                    self.peer_validated = true;
+                    self.discard_keys(EncryptionLevel::Handshake);
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// Processes an ACK frame of a packet at [level].
+    fn on_ack_frame(
+        &mut self,
+        level: EncryptionLevel,
+        delay: u64,
+        ranges: &[::std::ops::Range<u64>],
+        now: Instant,
+    ) -> Result<(), TransportError> {
+        let exponent: u64 = self
+            .peer_params
+            .as_ref()
+            .map_or(DEFAULT_ACK_DELAY_EXPONENT, |params| params.ack_delay_exponent);
+        let ack_delay: Duration = Duration::from_micros(delay.checked_mul(1 << exponent).unwrap_or(u64::MAX));
+        let outcome: AckOutcome = self
+            .recovery
+            .on_ack_received(level, ranges, ack_delay, self.handshake_confirmed, now)
+            .map_err(|e| TransportError {
+                code: PROTOCOL_VIOLATION,
+                fail: e,
+            })?;
+        if self.role == TlsRole::Client && level == EncryptionLevel::Handshake {
+            self.peer_validated = true;
+        }
+        for packet in outcome.acked {
+            self.on_frames_acked(level, packet.frames);
+        }
+        for packet in outcome.lost {
+            self.on_frames_lost(level, packet.frames);
+        }
+        self.collect_streams();
+        Ok(())
+    }
+
+    /// Processes a CRYPTO frame of a packet at [level], which carries handshake data from [offset].
+    fn on_crypto_frame(&mut self, level: EncryptionLevel, offset: u64, data: &[u8]) -> Result<(), TransportError> {
+        let space: &mut Space = &mut self.spaces[level as usize];
+        if offset + data.len() as u64 > space.crypto_recv.read_offset() + MAX_CRYPTO_BUFFER {
+            return Err(TransportError::new(
+                CRYPTO_BUFFER_EXCEEDED,
+                "too much handshake data is buffered",
+            ));
+        }
+        space
+            .crypto_recv
+            .receive(offset, data, false)
+            .map_err(|code| TransportError::new(code, "invalid CRYPTO frame"))?;
+        let mut incoming: Vec<u8> = Vec::new();
+        while let Some(chunk) = space.crypto_recv.read(usize::MAX) {
+            incoming.extend(chunk);
+        }
+        if incoming.is_empty() {
+            return Ok(());
+        }
+        self.tls
+            .receive_handshake(level, &incoming)
+            .map_err(|e| TransportError {
+                code: self
+                    .tls
+                    .handshake_alert()
+                    .map_or(INTERNAL_ERROR, |alert| CRYPTO_ERROR + alert as u64),
+                fail: e,
+            })
+    }
+
+    /// Processes a NEW_CONNECTION_ID frame. The connection ID in use is only replaced when the peer asks for it to be
+    /// retired.
+    fn on_new_connection_id(&mut self, seq: u64, retire_prior_to: u64, cid: &[u8]) -> Result<(), TransportError> {
+        let cid: ConnectionId =
+            ConnectionId::new(cid).map_err(|_| TransportError::new(FRAME_ENCODING_ERROR, "invalid connection ID"))?;
+        if seq < retire_prior_to {
+            self.retire_pending.push(seq);
+        } else if seq > self.remote_cid_seq {
+            self.spare_remote_cids.insert(seq, cid);
+        }
+
+        let retired: Vec<u64> = self
+            .spare_remote_cids
+            .range(..retire_prior_to)
+            .map(|(seq, _)| *seq)
+            .collect();
+        for seq in retired {
+            self.spare_remote_cids.remove(&seq);
+            self.retire_pending.push(seq);
+        }
+        if self.remote_cid_seq < retire_prior_to {
+            let (next_seq, next_cid): (u64, ConnectionId) = match self.spare_remote_cids.pop_first() {
+                Some(next) => next,
+                None => {
+                    return Err(TransportError::new(
+                        PROTOCOL_VIOLATION,
+                        "peer retired every connection ID",
+                    ))
+                },
+            };
+            self.retire_pending.push(self.remote_cid_seq);
+            self.remote_cid_seq = next_seq;
+            self.remote_cid = next_cid;
+        }
+        // Only the default number of connection IDs is accepted, which includes the one in use.
+        if self.spare_remote_cids.len() + 1 > local_parameters(&self.config).active_connection_id_limit as usize {
+            return Err(TransportError::new(
+                CONNECTION_ID_LIMIT_ERROR,
+                "peer issued too many connection IDs",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Moves the handshake data, the secrets and the transport parameters of the TLS session into the connection.
+    fn process_tls(&mut self) -> Result<(), TransportError> {
+        for level in levels() {
+            if let Some(data) = self.tls.take_handshake(level) {
+                self.spaces[level as usize].crypto_send.write(&data);
+            }
+            if let Some(secret) = self.tls.take_read_secret(level) {
+                self.spaces[level as usize].read = Some(Keys::new(&secret, self.provider.clone()));
+            }
+            if let Some(secret) = self.tls.take_write_secret(level) {
+                self.spaces[level as usize].write = Some(Keys::new(&secret, self.provider.clone()));
+            }
+        }
+
+        if self.peer_params.is_none() {
+            if let Some(encoded) = self.tls.peer_transport_parameters() {
+                let params: TransportParameters = TransportParameters::decode(encoded, self.role == TlsRole::Client)
+                    .map_err(|e| TransportError {
+                        code: TRANSPORT_PARAMETER_ERROR,
+                        fail: e,
+                    })?;
+                self.apply_peer_parameters(params)?;
+            }
+        }
+
+        if self.state == State::Handshaking && self.tls.is_handshake_complete() {
+            self.state = State::Established;
+            // Servers confirm the handshake once it completes, and tell clients (RFC 9001, section 4.1.2).
+            if self.role == TlsRole::Server {
+                self.handshake_confirmed = true;
+                self.handshake_done_pending = true;
+                self.discard_keys(EncryptionLevel::Handshake);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the transport parameters of the peer against the connection IDs of the handshake (RFC 9000, section
+    /// 7.3) and applies them.
+    fn apply_peer_parameters(&mut self, params: TransportParameters) -> Result<(), TransportError> {
+        let scid_matches: bool = params.initial_source_connection_id.as_ref() == Some(&self.remote_cid);
+        let odcid_matches: bool = self.role == TlsRole::Server
+            || params.original_destination_connection_id.as_ref() == Some(&self.original_dcid);
+        if !scid_matches || !odcid_matches {
+            return Err(TransportError::new(
+                TRANSPORT_PARAMETER_ERROR,
+                "connection IDs of the transport parameters do not match",
+            ));
+        }
+        self.peer_max_data = params.initial_max_data;
+        self.peer_max_streams = params.initial_max_streams_bidi;
+        self.recovery
+            .set_max_ack_delay(Duration::from_millis(params.max_ack_delay));
+        let peer_idle_timeout: Duration = Duration::from_millis(params.max_idle_timeout);
+        if !peer_idle_timeout.is_zero() && (self.idle_timeout.is_zero() || peer_idle_timeout < self.idle_timeout) {
+            self.idle_timeout = peer_idle_timeout;
+        }
+        self.peer_params = Some(params);
+        Ok(())
+    }
+
+    /// Builds a packet at [level] with up to [max_payload] bytes of frames, or none if nothing must be sent.
+    fn plan_packet(
+        &mut self,
+        level: EncryptionLevel,
+        pn_len: usize,
+        max_payload: usize,
+        congestion_ok: bool,
+        now: Instant,
+    ) -> Option<PlannedPacket> {
+        let mut payload: Vec<u8> = Vec::new();
+        let mut frames: Vec<SentFrame> = Vec::new();
+        let mut ack_eliciting: bool = false;
+
+        let space: &mut Space = &mut self.spaces[level as usize];
+        if space.ack_pending {
+            if let Some((frame, largest)) = ack_frame(space, max_payload, now) {
+                payload.extend(frame);
+                frames.push(SentFrame::Ack { largest });
+                space.ack_pending = false;
+            }
+        }
+
+        let probe: bool = space.probe_pending;
+        if congestion_ok || probe {
+            if level == EncryptionLevel::Application {
+                self.plan_control_frames(&mut payload, &mut frames, &mut ack_eliciting, max_payload);
+            }
+            let space: &mut Space = &mut self.spaces[level as usize];
+            loop {
+                let room: usize = max_payload.saturating_sub(payload.len() + CRYPTO_FRAME_OVERHEAD);
+                if room == 0 {
+                    break;
+                }
+                let chunk: Chunk = match space.crypto_send.emit(room, u64::MAX) {
+                    Some(chunk) => chunk,
+                    None => break,
+                };
+                Frame::Crypto {
+                    offset: chunk.offset,
+                    data: &chunk.data,
+                }
+                .encode(&mut payload);
+                frames.push(SentFrame::Crypto {
+                    offset: chunk.offset,
+                    len: chunk.data.len(),
+                });
+                ack_eliciting = true;
+            }
+            if level == EncryptionLevel::Application && self.state == State::Established {
+                self.plan_stream_frames(&mut payload, &mut frames, &mut ack_eliciting, max_payload);
+            }
+            let space: &mut Space = &mut self.spaces[level as usize];
+            if space.probe_pending {
+                if !ack_eliciting {
+                    Frame::Ping.encode(&mut payload);
+                    frames.push(SentFrame::Ping);
+                    ack_eliciting = true;
+                }
+                space.probe_pending = false;
+            }
+        }
+        if payload.is_empty() {
+            return None;
+        }
+
+        // Header protection samples the ciphertext four bytes past the start of the packet number.
+        if pn_len + payload.len() < 4 {
+            payload.resize(4 - pn_len, 0);
+        }
+        let space: &mut Space = &mut self.spaces[level as usize];
+        let pn: u64 = space.next_pn;
+        space.next_pn += 1;
+        Some(PlannedPacket {
+            level,
+            pn,
+            pn_len,
+            payload,
+            frames,
+            ack_eliciting,
+        })
+    }
+
+    /// Adds the pending frames that update flow control, manage connection IDs and answer path challenges to an
+    /// application packet.
+    fn plan_control_frames(
+        &mut self,
+        payload: &mut Vec<u8>,
+        frames: &mut Vec<SentFrame>,
+        ack_eliciting: &mut bool,
+        max_payload: usize,
+    ) {
+        let mut planned: Vec<(Frame, Option<SentFrame>)> = Vec::new();
+        if self.handshake_done_pending {
+            self.handshake_done_pending = false;
+            planned.push((Frame::HandshakeDone, Some(SentFrame::HandshakeDone)));
+        }
+        if self.max_data_pending {
+            self.max_data_pending = false;
+            planned.push((Frame::MaxData(self.local_max_data), Some(SentFrame::MaxData)));
+        }
+        if self.max_streams_pending {
+            self.max_streams_pending = false;
+            planned.push((
+                Frame::MaxStreams {
+                    bidi: true,
+                    max: self.local_max_streams,
+                },
+                Some(SentFrame::MaxStreams),
+            ));
+        }
+        for stream_id in ::std::mem::take(&mut self.max_stream_data_pending) {
+            if let Some(stream) = self.streams.get(&stream_id) {
+                planned.push((
+                    Frame::MaxStreamData {
+                        stream_id,
+                        max: stream.recv.max_data(),
+                    },
+                    Some(SentFrame::MaxStreamData { stream_id }),
+                ));
+            }
+        }
+        for seq in ::std::mem::take(&mut self.retire_pending) {
+            planned.push((Frame::RetireConnectionId(seq), Some(SentFrame::RetireConnectionId(seq))));
+        }
+        for data in ::std::mem::take(&mut self.path_responses) {
+            planned.push((Frame::PathResponse(data), None));
+        }
+
+        for (frame, sent) in planned {
+            let mut encoded: Vec<u8> = Vec::new();
+            frame.encode(&mut encoded);
+            if payload.len() + encoded.len() > max_payload {
+                // Keep the frame for the next packet.
+                match sent {
+                    Some(sent) => self.on_frames_lost(EncryptionLevel::Application, vec![sent]),
+                    None => {
+                        if let Frame::PathResponse(data) = frame {
+                            self.path_responses.push(data);
+                        }
+                    },
+                }
+                continue;
+            }
+            payload.extend(encoded);
+            frames.extend(sent);
+            *ack_eliciting = true;
+        }
+    }
+
+    /// Adds STREAM and RESET_STREAM frames to an application packet, starting with the stream after the last one that
+    /// was served.
+    fn plan_stream_frames(
+        &mut self,
+        payload: &mut Vec<u8>,
+        frames: &mut Vec<SentFrame>,
+        ack_eliciting: &mut bool,
+        max_payload: usize,
+    ) {
+        let stream_ids: Vec<u64> = self
+            .streams
+            .range(self.next_stream..)
+            .chain(self.streams.range(..self.next_stream))
+            .map(|(stream_id, _)| *stream_id)
+            .collect();
+        for stream_id in stream_ids {
+            let stream: &mut Stream = expect_some!(self.streams.get_mut(&stream_id), "stream was just listed");
+            if payload.len() + RESET_STREAM_FRAME_SIZE <= max_payload {
+                if let Some((error_code, final_size)) = stream.send.take_reset() {
+                    Frame::ResetStream {
+                        stream_id,
+                        error_code,
+                        final_size,
+                    }
+                    .encode(payload);
+                    frames.push(SentFrame::ResetStream { stream_id });
+                    *ack_eliciting = true;
+                }
+            }
+            loop {
+                let room: usize = max_payload.saturating_sub(payload.len() + STREAM_FRAME_OVERHEAD);
+                if room == 0 {
+                    return;
+                }
+                let sent_offset: u64 = stream.send.sent_offset();
+                let chunk: Chunk = match stream.send.emit(room, self.peer_max_data - self.sent_data) {
+                    Some(chunk) => chunk,
+                    None => break,
+                };
+                self.sent_data += stream.send.sent_offset() - sent_offset;
+                Frame::Stream {
+                    stream_id,
+                    offset: chunk.offset,
+                    data: &chunk.data,
+                    fin: chunk.fin,
+                }
+                .encode(payload);
+                frames.push(SentFrame::Stream {
+                    stream_id,
+                    offset: chunk.offset,
+                    len: chunk.data.len(),
+                    fin: chunk.fin,
+                });
+                *ack_eliciting = true;
+                self.next_stream = stream_id + 1;
+            }
+        }
+    }
+
+    /// Builds a datagram with the CONNECTION_CLOSE frame at every level that has keys, as the peer may not have the
+    /// keys of the highest one yet (RFC 9000, section 10.2.3).
+    fn transmit_close(&mut self, now: Instant) -> Option<Vec<u8>> {
+        if !self.close_pending {
+            return None;
+        }
+        self.close_pending = false;
+        let close: &CloseReason = self.close.as_ref()?;
+        let mut planned: Vec<(EncryptionLevel, Vec<u8>)> = Vec::new();
+        for level in levels() {
+            if self.spaces[level as usize].write.is_none() {
+                continue;
+            }
+            let mut payload: Vec<u8> = Vec::new();
+            // Errors of the application are only reported with application keys.
+            let frame: Frame = if level == EncryptionLevel::Application || close.frame_type.is_some() {
+                Frame::ConnectionClose {
+                    error_code: close.error_code,
+                    frame_type: close.frame_type,
+                    reason: close.reason.as_bytes(),
+                }
+            } else {
+                Frame::ConnectionClose {
+                    error_code: APPLICATION_ERROR,
+                    frame_type: Some(0),
+                    reason: &[],
+                }
+            };
+            frame.encode(&mut payload);
+            planned.push((level, payload));
+        }
+
+        let budget: usize = self.send_budget();
+        let mut packets: Vec<PlannedPacket> = Vec::new();
+        let mut size: usize = 0;
+        for (level, mut payload) in planned {
+            let pn_len: usize = self.next_pn_len(level);
+            let overhead: usize = self.packet_overhead(level, pn_len);
+            if size + overhead + payload.len() > budget {
+                break;
+            }
+            if pn_len + payload.len() < 4 {
+                payload.resize(4 - pn_len, 0);
+            }
+            size += overhead + payload.len();
+            let space: &mut Space = &mut self.spaces[level as usize];
+            packets.push(PlannedPacket {
+                level,
+                pn: space.next_pn,
+                pn_len,
+                payload,
+                frames: Vec::new(),
+                ack_eliciting: false,
+            });
+            space.next_pn += 1;
+        }
+        if packets.is_empty() {
+            return None;
+        }
+        Some(self.seal(packets, size, budget, now))
+    }
+
+    /// Pads and protects the [packets] of a datagram of [size] bytes, and records them for loss detection.
+    fn seal(&mut self, mut packets: Vec<PlannedPacket>, size: usize, budget: usize, now: Instant) -> Vec<u8> {
+        // Datagrams with Initial packets of clients, and ack-eliciting ones of servers, are padded (RFC 9000, section
+        // 14.1).
+        let pad: bool = packets.iter().any(|packet| {
+            packet.level == EncryptionLevel::Initial && (self.role == TlsRole::Client || packet.ack_eliciting)
+        });
+        let target: usize = MIN_INITIAL_DATAGRAM_SIZE.min(budget);
+        if pad && size < target {
+            let last: &mut PlannedPacket = expect_some!(packets.last_mut(), "datagram has packets");
+            let len: usize = last.payload.len() + target - size;
+            last.payload.resize(len, 0);
+        }
+
+        let mut datagram: Vec<u8> = Vec::with_capacity(MAX_DATAGRAM_SIZE);
+        let mut sent_handshake: bool = false;
+        for packet in packets {
+            let mut bytes: Vec<u8> = Vec::with_capacity(MAX_DATAGRAM_SIZE);
+            let pn_offset: usize = match packet.level {
+                EncryptionLevel::Application => {
+                    put_short_header(&mut bytes, &self.remote_cid, self.key_phase, packet.pn, packet.pn_len)
+                },
+                level => put_long_header(
+                    &mut bytes,
+                    packet_type(level),
+                    &self.remote_cid,
+                    &self.local_cid,
+                    packet.pn,
+                    packet.pn_len,
+                    packet.payload.len(),
+                ),
+            };
+            bytes.extend_from_slice(&packet.payload);
+            let keys: &Keys = expect_some!(
+                self.spaces[packet.level as usize].write.as_ref(),
+                "packets are only planned with keys"
+            );
+            protect(&keys.packet, &keys.header, packet.pn, &mut bytes, pn_offset);
+            sent_handshake |= packet.level == EncryptionLevel::Handshake;
+            self.recovery.on_packet_sent(
+                packet.level,
+                SentPacket {
+                    pn: packet.pn,
+                    time_sent: now,
+                    size: bytes.len(),
+                    ack_eliciting: packet.ack_eliciting,
+                    frames: packet.frames,
+                },
+            );
+            datagram.extend(bytes);
+        }
+        self.bytes_sent += datagram.len();
+
+        // Clients drop the Initial keys once they send a Handshake packet (RFC 9001, section 4.9.1).
+        if self.role == TlsRole::Client && sent_handshake {
+            self.discard_keys(EncryptionLevel::Initial);
+        }
+        datagram
+    }
+
+    /// Acts upon [frames] of a packet at [level] that the peer acknowledged.
+    fn on_frames_acked(&mut self, level: EncryptionLevel, frames: Vec<SentFrame>) {
+        for frame in frames {
+            match frame {
+                SentFrame::Crypto { offset, len } => self.spaces[level as usize].crypto_send.on_ack(offset, len, false),
+                SentFrame::Stream {
+                    stream_id,
+                    offset,
+                    len,
+                    fin,
+                } => {
+                    if let Some(stream) = self.streams.get_mut(&stream_id) {
+                        stream.send.on_ack(offset, len, fin);
+                    }
+                },
+                SentFrame::ResetStream { stream_id } => {
+                    if let Some(stream) = self.streams.get_mut(&stream_id) {
+                        stream.send.on_reset_outcome(true);
+                    }
+                },
+                SentFrame::Ack { .. }
+                | SentFrame::MaxData
+                | SentFrame::MaxStreamData { .. }
+                | SentFrame::MaxStreams
+                | SentFrame::RetireConnectionId(_)
+                | SentFrame::HandshakeDone
+                | SentFrame::Ping => (),
+            }
+        }
+    }
+
+    /// Queues the contents of [frames] of a packet at [level] that was lost again, as far as they are still relevant.
+    fn on_frames_lost(&mut self, level: EncryptionLevel, frames: Vec<SentFrame>) {
+        for frame in frames {
+            match frame {
+                SentFrame::Crypto { offset, len } => {
+                    self.spaces[level as usize].crypto_send.on_loss(offset, len, false)
+                },
+                SentFrame::Stream {
+                    stream_id,
+                    offset,
+                    len,
+                    fin,
+                } => {
+                    if let Some(stream) = self.streams.get_mut(&stream_id) {
+                        stream.send.on_loss(offset, len, fin);
+                    }
+                },
+                SentFrame::ResetStream { stream_id } => {
+                    if let Some(stream) = self.streams.get_mut(&stream_id) {
+                        stream.send.on_reset_outcome(false);
+                    }
+                },
+                SentFrame::MaxData => self.max_data_pending = true,
+                SentFrame::MaxStreamData { stream_id } => {
+                    if self.streams.contains_key(&stream_id) {
+                        self.max_stream_data_pending.insert(stream_id);
+                    }
+                },
+                SentFrame::MaxStreams => self.max_streams_pending = true,
+                SentFrame::RetireConnectionId(seq) => self.retire_pending.push(seq),
+                SentFrame::HandshakeDone => self.handshake_done_pending = true,
+                SentFrame::Ack { .. } | SentFrame::Ping => (),
+            }
+        }
+    }
+
+    /// Drops the keys and the packets in flight of [level].
+    fn discard_keys(&mut self, level: EncryptionLevel) {
+        let space: &mut Space = &mut self.spaces[level as usize];
+        if space.read.is_none() && space.write.is_none() {
+            return;
+        }
+        *space = Space::new();
+        self.recovery.discard(level);
+    }
+
+    /// Checks that stream [stream_id] of a frame is valid, and opens it and the streams of the peer below it if it does
+    /// not exist yet. Returns false if the stream was closed already. Streams that only the peer sends on are checked
+    /// with [receiving] set.
+    fn check_stream(&mut self, stream_id: u64, receiving: bool) -> Result<bool, TransportError> {
+        if self.streams.contains_key(&stream_id) {
+            return Ok(true);
+        }
+        let local: bool = is_local_stream(stream_id, self.role);
+        if stream_id & STREAM_UNI_BIT != 0 {
+            // Unidirectional streams are never opened, and peers are not allowed any.
+            let code: u64 = if local || !receiving {
+                STREAM_STATE_ERROR
+            } else {
+                STREAM_LIMIT_ERROR
+            };
+            return Err(TransportError::new(code, "unidirectional streams are not supported"));
+        }
+        let index: u64 = stream_id >> 2;
+        if local {
+            if index < self.local_streams_opened {
+                return Ok(false);
+            }
+            return Err(TransportError::new(STREAM_STATE_ERROR, "stream was not opened"));
+        }
+        if index < self.peer_streams_opened {
+            return Ok(false);
+        }
+        if index >= self.local_max_streams {
+            return Err(TransportError::new(STREAM_LIMIT_ERROR, "peer opened too many streams"));
+        }
+        let peer_role: TlsRole = match self.role {
+            TlsRole::Client => TlsRole::Server,
+            TlsRole::Server => TlsRole::Client,
+        };
+        for index in self.peer_streams_opened..=index {
+            let stream_id: u64 = self.stream_id(index, peer_role);
+            let stream: Stream = self.new_stream(false);
+            self.streams.insert(stream_id, stream);
+            self.accept_queue.push_back(stream_id);
+        }
+        self.peer_streams_opened = index + 1;
+        Ok(true)
+    }
+
+    /// Creates a stream that was opened on this side if [local] is set, or by the peer otherwise.
+    fn new_stream(&self, local: bool) -> Stream {
+        let max_data: u64 = self.peer_params.as_ref().map_or(0, |params| {
+            if local {
+                params.initial_max_stream_data_bidi_remote
+            } else {
+                params.initial_max_stream_data_bidi_local
+            }
+        });
+        Stream {
+            send: SendStream::new(max_data),
+            recv: RecvStream::new(self.config.max_stream_data()),
+            closed: false,
+        }
+    }
+
+    /// Gets the ID of the bidirectional stream with [index] that [role] opened.
+    fn stream_id(&self, index: u64, role: TlsRole) -> u64 {
+        match role {
+            TlsRole::Client => index << 2,
+            TlsRole::Server => (index << 2) | STREAM_SERVER_BIT,
+        }
+    }
+
+    /// Gets stream [stream_id] for an operation of the application.
+    fn get_stream(&mut self, fn_name: &str, stream_id: u64) -> Result<&mut Stream, Fail> {
+        match self.streams.get_mut(&stream_id) {
+            Some(stream) if !stream.closed => Ok(stream),
+            _ => {
+                let cause: String = format!("no such stream (stream_id={:?})", stream_id);
+                warn!("{}(): {}", fn_name, cause);
+                Err(Fail::new(libc::EBADF, &cause))
+            },
+        }
+    }
+
+    /// Accounts for [grown] bytes of stream data past the largest offset of a stream, which counts against connection
+    /// flow control.
+    fn on_data_received(&mut self, grown: u64) -> Result<(), TransportError> {
+        self.received_data += grown;
+        if self.received_data > self.local_max_data {
+            return Err(TransportError::new(
+                FLOW_CONTROL_ERROR,
+                "peer exceeded the connection flow control limit",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Accounts for [len] bytes of stream [stream_id] that were consumed, and raises the flow control limits if enough
+    /// of the windows was consumed.
+    fn on_consumed(&mut self, stream_id: u64, len: u64) {
+        self.consumed_data += len;
+        if let Some(stream) = self.streams.get_mut(&stream_id) {
+            if stream.recv.update_max_data().is_some() {
+                self.max_stream_data_pending.insert(stream_id);
+            }
+        }
+        self.update_max_data();
+    }
+
+    /// Raises the connection flow control limit once half of the window was consumed.
+    fn update_max_data(&mut self) {
+        let window: u64 = self.config.max_data();
+        if self.local_max_data - self.consumed_data < window / 2 {
+            self.local_max_data = self.consumed_data + window;
+            self.max_data_pending = true;
+        }
+    }
+
+    /// Drops the data that arrived on stream [stream_id], which the application closed.
+    fn drain_stream(&mut self, stream_id: u64) {
+        let mut len: u64 = 0;
+        if let Some(stream) = self.streams.get_mut(&stream_id) {
+            while let Some(data) = stream.recv.read(usize::MAX) {
+                len += data.len() as u64;
+            }
+        }
+        if len > 0 {
+            self.on_consumed(stream_id, len);
+        }
+    }
+
+    /// Forgets the streams that the application closed once both directions are over, and lets the peer open more in
+    /// place of its own.
+    fn collect_streams(&mut self) {
+        let done: Vec<u64> = self
+            .streams
+            .iter()
+            .filter(|(_, stream)| {
+                stream.closed
+                    && stream.send.is_done()
+                    && (stream.recv.is_finished() || stream.recv.reset_code().is_some())
+            })
+            .map(|(stream_id, _)| *stream_id)
+            .collect();
+        for stream_id in done {
+            self.streams.remove(&stream_id);
+            self.max_stream_data_pending.remove(&stream_id);
+            if !is_local_stream(stream_id, self.role) {
+                self.local_max_streams += 1;
+                self.max_streams_pending = true;
+            }
+        }
+    }
+
+    /// Closes the connection on this side after [e].
+    fn enter_closing(&mut self, e: TransportError, now: Instant) {
+        let close: CloseReason = CloseReason {
+            error_code: e.code,
+            frame_type: Some(0),
+            reason: e.fail.cause.clone(),
+        };
+        self.start_closing(close, e.fail, now);
+    }
+
+    /// Enters the closing state, where [close] is sent in answer to the packets of the peer.
+    fn start_closing(&mut self, close: CloseReason, fail: Fail, now: Instant) {
+        if !matches!(self.state, State::Handshaking | State::Established) {
+            return;
+        }
+        self.error = Some(fail);
+        self.close = Some(close);
+        self.close_pending = true;
+        self.state = State::Closing(now + self.recovery.pto() * CLOSE_PTO_COUNT);
+    }
+
+    /// Checks that the handshake completed for an operation of the application.
+    fn check_established(&self, fn_name: &str) -> Result<(), Fail> {
+        self.check_open(fn_name)?;
+        if self.state != State::Established {
+            let cause: &str = "handshake is not complete";
+            warn!("{}(): {}", fn_name, cause);
+            return Err(Fail::new(libc::ENOTCONN, cause));
+        }
+        Ok(())
+    }
+
+    /// Checks that the connection was not closed for an operation of the application.
+    fn check_open(&self, fn_name: &str) -> Result<(), Fail> {
+        match self.error.as_ref() {
+            Some(e) => {
+                warn!("{}(): connection is closed", fn_name);
+                Err(e.clone())
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// Gets the number of bytes that may be sent in the next datagram, which servers limit to three times what
+    /// arrived until the address of the client is validated.
+    fn send_budget(&self) -> usize {
+        if self.address_validated {
+            MAX_DATAGRAM_SIZE
+        } else {
+            (AMPLIFICATION_FACTOR * self.bytes_received)
+                .saturating_sub(self.bytes_sent)
+                .min(MAX_DATAGRAM_SIZE)
+        }
+    }
+
+    /// Gets the length of the packet number of the next packet at [level].
+    fn next_pn_len(&self, level: EncryptionLevel) -> usize {
+        packet_number_len(self.spaces[level as usize].next_pn, self.recovery.largest_acked(level))
+    }
+
+    /// Gets the size of a packet at [level] without its frames.
+    fn packet_overhead(&self, level: EncryptionLevel, pn_len: usize) -> usize {
+        header_len(packet_type(level), &self.remote_cid, &self.local_cid, pn_len) + PACKET_TAG_SIZE
+    }
+
+    fn has_handshake_keys(&self) -> bool {
+        self.spaces[EncryptionLevel::Handshake as usize].write.is_some()
+    }
+
+    /// Gets the time at which the connection times out for lack of activity, which is at least three probe timeouts
+    /// (RFC 9000, section 10.1).
+    fn idle_deadline(&self) -> Option<Instant> {
+        if self.idle_timeout.is_zero() {
+            return None;
+        }
+        Some(self.last_activity + self.idle_timeout.max(self.recovery.pto() * CLOSE_PTO_COUNT))
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Builds the transport parameters of this side, without the connection IDs.
+fn local_parameters(config: &QuicConfig) -> TransportParameters {
+    TransportParameters {
+        max_idle_timeout: config.idle_timeout().as_millis() as u64,
+        max_udp_payload_size: MAX_DATAGRAM_SIZE as u64,
+        initial_max_data: config.max_data(),
+        initial_max_stream_data_bidi_local: config.max_stream_data(),
+        initial_max_stream_data_bidi_remote: config.max_stream_data(),
+        initial_max_streams_bidi: config.max_streams(),
+        ..TransportParameters::default()
+    }
+}
+
+/// Builds an ACK frame for the packets that arrived in [space] that fits in [max_len] bytes, and returns it with the
+/// largest packet number that it acknowledges.
+fn ack_frame(space: &Space, max_len: usize, now: Instant) -> Option<(Vec<u8>, u64)> {
+    let delay: u64 = space.largest_received_time.map_or(0, |time| {
+        (now.saturating_duration_since(time).as_micros() as u64) >> DEFAULT_ACK_DELAY_EXPONENT
+    });
+    let mut ranges: Vec<::std::ops::Range<u64>> = ack_ranges(&space.received, MAX_ACK_RANGES);
+    let largest: u64 = ranges.first()?.end - 1;
+    // Drop the oldest ranges until the frame fits.
+    while !ranges.is_empty() {
+        let mut encoded: Vec<u8> = Vec::new();
+        Frame::Ack {
+            delay,
+            ranges: ranges.clone(),
+        }
+        .encode(&mut encoded);
+        if encoded.len() <= max_len {
+            return Some((encoded, largest));
+        }
+        ranges.pop();
+    }
+    None
+}
+
+/// Gets the type of the packets at [level].
+fn packet_type(level: EncryptionLevel) -> PacketType {
+    match level {
+        EncryptionLevel::Initial => PacketType::Initial,
+        EncryptionLevel::Handshake => PacketType::Handshake,
+        EncryptionLevel::Application => PacketType::OneRtt,
+    }
+}
+
+/// Checks whether stream [stream_id] was opened by [role].
+fn is_local_stream(stream_id: u64, role: TlsRole) -> bool {
+    (stream_id & STREAM_SERVER_BIT != 0) == (role == TlsRole::Server)
+}
+
+/// Checks whether the application closed stream [stream_id].
+fn stream_closed(streams: &BTreeMap<u64, Stream>, stream_id: u64) -> bool {
+    streams.get(&stream_id).map_or(false, |stream| stream.closed)
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::QuicConnection;
+    use crate::runtime::network::{
+        quic::QuicConfig,
+        tls::TlsConfig,
+    };
+    use ::anyhow::Result;
+    use ::std::time::{
+        Duration,
+        Instant,
+    };
+
+    fn new_config(psk: &[u8]) -> Result<QuicConfig> {
+        let config: QuicConfig = QuicConfig::new(TlsConfig::new(b"client", psk)?)
+            .with_flow_control(16 * 1024, 4 * 1024)?
+            .with_max_streams(2)?;
+        Ok(config)
+    }
+
+    /// Client and server connection that exchange datagrams in memory, on a clock that only moves forward when the
+    /// tests advance it.
+    struct Link {
+        client: QuicConnection,
+        server: Option<QuicConnection>,
+        now: Instant,
+        sent: usize,
+    }
+
+    impl Link {
+        fn new(client_psk: &[u8], server_psk: &[u8]) -> Result<Self> {
+            let now: Instant = Instant::now();
+            let client: QuicConnection = QuicConnection::connect(new_config(client_psk)?, now);
+            let mut link: Self = Self {
+                client,
+                server: None,
+                now,
+                sent: 0,
+            };
+            let datagram: Vec<u8> = link
+                .client
+                .poll_transmit(now)
+                .ok_or_else(|| anyhow::anyhow!("client sent nothing"))?;
+            crate::ensure_eq!(datagram.len(), 1200);
+            link.server = Some(QuicConnection::accept(new_config(server_psk)?, &datagram, now)?);
+            Ok(link)
+        }
+
+        fn server(&mut self) -> &mut QuicConnection {
+            self.server.as_mut().expect("server was accepted")
+        }
+
+        /// Exchanges datagrams until neither side sends anything, dropping every [drop_every]th one.
+        fn exchange(&mut self, drop_every: usize) {
+            loop {
+                let mut quiet: bool = true;
+                let now: Instant = self.now;
+                while let Some(datagram) = self.client.poll_transmit(now) {
+                    quiet = false;
+                    self.sent += 1;
+                    if drop_every == 0 || self.sent % drop_every != 0 {
+                        self.server().receive(&datagram, now);
+                    }
+                }
+                while let Some(datagram) = self.server().poll_transmit(now) {
+                    quiet = false;
+                    self.sent += 1;
+                    if drop_every == 0 || self.sent % drop_every != 0 {
+                        self.client.receive(&datagram, now);
+                    }
+                }
+                if quiet {
+                    return;
+                }
+                self.now += Duration::from_millis(5);
+            }
+        }
+
+        /// Advances time to the next timer of either side and runs it.
+        fn advance(&mut self) -> bool {
+            let next: Option<Instant> = [self.client.timeout(), self.server().timeout()]
+                .into_iter()
+                .flatten()
+                .min();
+            match next {
+                Some(next) => {
+                    self.now = self.now.max(next);
+                    let now: Instant = self.now;
+                    self.client.on_timeout(now);
+                    self.server().on_timeout(now);
+                    true
+                },
+                None => false,
+            }
+        }
+    }
+
+    /// Sends [data] from the client on a new stream and reads it all on the server, dropping every [drop_every]th
+    /// datagram.
+    fn transfer(link: &mut Link, data: &[u8], drop_every: usize) -> Result<Vec<u8>> {
+        let stream_id: u64 = link.client.open_stream()?;
+        link.client.stream_write(stream_id, data)?;
+        link.client.stream_finish(stream_id)?;
+        let mut received: Vec<u8> = Vec::new();
+        let mut server_stream: Option<u64> = None;
+        for _ in 0..10_000 {
+            link.exchange(drop_every);
+            if server_stream.is_none() {
+                server_stream = link.server().accept_stream();
+            }
+            let mut progress: bool = false;
+            if let Some(server_stream) = server_stream {
+                while let Some(chunk) = link.server().stream_read(server_stream, 1000)? {
+                    if chunk.is_empty() {
+                        link.server().stream_close(server_stream)?;
+                        link.client.stream_close(stream_id)?;
+                        return Ok(received);
+                    }
+                    received.extend(chunk);
+                    progress = true;
+                }
+            }
+            // Time only passes when nothing is left to do, as data that was read may raise flow control limits.
+            if !progress {
+                link.advance();
+            }
+        }
+        anyhow::bail!("transfer did not complete")
+    }
+
+    #[test]
+    fn test_handshake_and_streams() -> Result<()> {
+        let mut link: Link = Link::new(&[7; 32], &[7; 32])?;
+        link.exchange(0);
+        crate::ensure_eq!(link.client.is_established(), true);
+        crate::ensure_eq!(link.server().is_established(), true);
+
+        // The transfer exceeds both flow control windows, which must be raised along the way.
+        let data: Vec<u8> = (0..100_000).map(|i| i as u8).collect();
+        crate::ensure_eq!(transfer(&mut link, &data, 0)?, data);
+
+        // Closed streams make room for new ones of the peer.
+        for _ in 0..3 {
+            crate::ensure_eq!(transfer(&mut link, b"ping", 0)?, b"ping".to_vec());
+        }
+
+        // Closing the connection reaches the peer.
+        let now: Instant = link.now;
+        link.client.close(0, now);
+        link.exchange(0);
+        crate::ensure_eq!(link.server().error().map(|e| e.errno), Some(libc::ECONNRESET));
+        while link.advance() {}
+        crate::ensure_eq!(link.client.is_closed(), true);
+        crate::ensure_eq!(link.server().is_closed(), true);
+        Ok(())
+    }
+
+    #[test]
+    fn test_streams_recover_from_loss() -> Result<()> {
+        let mut link: Link = Link::new(&[7; 32], &[7; 32])?;
+        link.exchange(0);
+        let data: Vec<u8> = (0..50_000).map(|i| (i * 7) as u8).collect();
+        crate::ensure_eq!(transfer(&mut link, &data, 3)?, data);
+        Ok(())
+    }
+
+    #[test]
+    fn test_handshake_recovers_from_loss() -> Result<()> {
+        let mut link: Link = Link::new(&[7; 32], &[7; 32])?;
+        // The first flight of the server is lost.
+        let now: Instant = link.now;
+        while link.server().poll_transmit(now).is_some() {}
+        for _ in 0..100 {
+            link.exchange(0);
+            if link.client.is_established() && link.server().is_established() {
+                break;
+            }
+            link.advance();
+        }
+        crate::ensure_eq!(link.client.is_established(), true);
+        crate::ensure_eq!(link.server().is_established(), true);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_limit() -> Result<()> {
+        let mut link: Link = Link::new(&[7; 32], &[7; 32])?;
+        link.exchange(0);
+        link.client.open_stream()?;
+        link.client.open_stream()?;
+        crate::ensure_eq!(link.client.open_stream().err().map(|e| e.errno), Some(libc::EAGAIN));
+        Ok(())
+    }
+
+    #[test]
+    fn test_handshake_failure_and_idle_timeout() -> Result<()> {
+        // Mismatched keys abort the handshake on both sides.
+        let mut link: Link = Link::new(&[7; 32], &[8; 32])?;
+        link.exchange(0);
+        crate::ensure_eq!(link.server().error().map(|e| e.errno), Some(libc::EACCES));
+        crate::ensure_eq!(link.client.error().map(|e| e.errno), Some(libc::ECONNRESET));
+
+        // Connections time out once the peer goes silent.
+        let mut link: Link = Link::new(&[7; 32], &[7; 32])?;
+        link.exchange(0);
+        let deadline: Instant = link.client.timeout().unwrap_or(link.now);
+        link.client.on_timeout(deadline + Duration::from_secs(60));
+        crate::ensure_eq!(link.client.is_closed(), true);
+        crate::ensure_eq!(link.client.error().map(|e| e.errno), Some(libc::ETIMEDOUT));
+        Ok(())
+    }
+}
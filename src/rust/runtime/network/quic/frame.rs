@@ -0,0 +1,521 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    network::quic::{
+        codec::{
+            put_varint,
+            put_varint_vec,
+            varint_len,
+            Reader,
+            MAX_VARINT,
+        },
+        ranges::RangeSet,
+    },
+};
+use ::std::ops::Range;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Types of frames (RFC 9000, section 19).
+const FRAME_PADDING: u64 = 0x00;
+const FRAME_PING: u64 = 0x01;
+const FRAME_ACK: u64 = 0x02;
+const FRAME_ACK_ECN: u64 = 0x03;
+const FRAME_RESET_STREAM: u64 = 0x04;
+const FRAME_STOP_SENDING: u64 = 0x05;
+const FRAME_CRYPTO: u64 = 0x06;
+const FRAME_NEW_TOKEN: u64 = 0x07;
+const FRAME_STREAM: u64 = 0x08;
+const FRAME_STREAM_LAST: u64 = 0x0f;
+const FRAME_MAX_DATA: u64 = 0x10;
+const FRAME_MAX_STREAM_DATA: u64 = 0x11;
+const FRAME_MAX_STREAMS_BIDI: u64 = 0x12;
+const FRAME_MAX_STREAMS_UNI: u64 = 0x13;
+const FRAME_DATA_BLOCKED: u64 = 0x14;
+const FRAME_STREAM_DATA_BLOCKED: u64 = 0x15;
+const FRAME_STREAMS_BLOCKED_BIDI: u64 = 0x16;
+const FRAME_STREAMS_BLOCKED_UNI: u64 = 0x17;
+const FRAME_NEW_CONNECTION_ID: u64 = 0x18;
+const FRAME_RETIRE_CONNECTION_ID: u64 = 0x19;
+const FRAME_PATH_CHALLENGE: u64 = 0x1a;
+const FRAME_PATH_RESPONSE: u64 = 0x1b;
+const FRAME_CONNECTION_CLOSE: u64 = 0x1c;
+const FRAME_CONNECTION_CLOSE_APPLICATION: u64 = 0x1d;
+const FRAME_HANDSHAKE_DONE: u64 = 0x1e;
+
+/// Flags of the type of STREAM frames.
+const STREAM_FLAG_OFFSET: u64 = 0x04;
+const STREAM_FLAG_LENGTH: u64 = 0x02;
+const STREAM_FLAG_FIN: u64 = 0x01;
+
+/// Largest number of ranges that an incoming ACK frame may carry.
+const MAX_ACK_RANGES: u64 = 256;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Frame of a QUIC packet. Frames that carry data borrow it from the packet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Frame<'a> {
+    Padding,
+    Ping,
+    /// Acknowledges the packet numbers of [ranges], which are in descending order, [delay] time units after the
+    /// largest of them arrived.
+    Ack {
+        delay: u64,
+        ranges: Vec<Range<u64>>,
+    },
+    ResetStream {
+        stream_id: u64,
+        error_code: u64,
+        final_size: u64,
+    },
+    StopSending {
+        stream_id: u64,
+        error_code: u64,
+    },
+    Crypto {
+        offset: u64,
+        data: &'a [u8],
+    },
+    NewToken,
+    Stream {
+        stream_id: u64,
+        offset: u64,
+        data: &'a [u8],
+        fin: bool,
+    },
+    MaxData(u64),
+    MaxStreamData {
+        stream_id: u64,
+        max: u64,
+    },
+    MaxStreams {
+        bidi: bool,
+        max: u64,
+    },
+    DataBlocked,
+    StreamDataBlocked,
+    StreamsBlocked,
+    NewConnectionId {
+        seq: u64,
+        retire_prior_to: u64,
+        cid: &'a [u8],
+    },
+    RetireConnectionId(u64),
+    PathChallenge([u8; 8]),
+    PathResponse([u8; 8]),
+    /// Closes the connection. Transport errors carry the type of the frame that triggered them, while errors of the
+    /// application do not.
+    ConnectionClose {
+        error_code: u64,
+        frame_type: Option<u64>,
+        reason: &'a [u8],
+    },
+    HandshakeDone,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl<'a> Frame<'a> {
+    /// Parses the next frame of [reader].
+    pub fn parse(reader: &mut Reader<'a>) -> Result<Self, Fail> {
+        let frame_type: u64 = reader.varint()?;
+        let frame: Frame = match frame_type {
+            FRAME_PADDING => Frame::Padding,
+            FRAME_PING => Frame::Ping,
+            FRAME_ACK | FRAME_ACK_ECN => {
+                let largest: u64 = reader.varint()?;
+                let delay: u64 = reader.varint()?;
+                let range_count: u64 = reader.varint()?;
+                if range_count > MAX_ACK_RANGES {
+                    return Err(malformed("too many ACK ranges"));
+                }
+                let first_range: u64 = reader.varint()?;
+                let mut smallest: u64 = largest
+                    .checked_sub(first_range)
+                    .ok_or_else(|| malformed("ACK range below zero"))?;
+                let mut ranges: Vec<Range<u64>> = Vec::with_capacity(range_count as usize + 1);
+                ranges.push(smallest..(largest + 1));
+                for _ in 0..range_count {
+                    let gap: u64 = reader.varint()?;
+                    let len: u64 = reader.varint()?;
+                    let largest: u64 = smallest
+                        .checked_sub(gap + 2)
+                        .ok_or_else(|| malformed("ACK range below zero"))?;
+                    smallest = largest
+                        .checked_sub(len)
+                        .ok_or_else(|| malformed("ACK range below zero"))?;
+                    ranges.push(smallest..(largest + 1));
+                }
+                if frame_type == FRAME_ACK_ECN {
+                    for _ in 0..3 {
+                        reader.varint()?;
+                    }
+                }
+                Frame::Ack { delay, ranges }
+            },
+            FRAME_RESET_STREAM => Frame::ResetStream {
+                stream_id: reader.varint()?,
+                error_code: reader.varint()?,
+                final_size: reader.varint()?,
+            },
+            FRAME_STOP_SENDING => Frame::StopSending {
+                stream_id: reader.varint()?,
+                error_code: reader.varint()?,
+            },
+            FRAME_CRYPTO => {
+                let offset: u64 = reader.varint()?;
+                let data: &[u8] = reader.varint_vec()?;
+                check_final_offset(offset, data.len())?;
+                Frame::Crypto { offset, data }
+            },
+            FRAME_NEW_TOKEN => {
+                if reader.varint_vec()?.is_empty() {
+                    return Err(malformed("empty token"));
+                }
+                Frame::NewToken
+            },
+            FRAME_STREAM..=FRAME_STREAM_LAST => {
+                let stream_id: u64 = reader.varint()?;
+                let offset: u64 = if frame_type & STREAM_FLAG_OFFSET != 0 {
+                    reader.varint()?
+                } else {
+                    0
+                };
+                let data: &[u8] = if frame_type & STREAM_FLAG_LENGTH != 0 {
+                    reader.varint_vec()?
+                } else {
+                    reader.rest()
+                };
+                check_final_offset(offset, data.len())?;
+                Frame::Stream {
+                    stream_id,
+                    offset,
+                    data,
+                    fin: frame_type & STREAM_FLAG_FIN != 0,
+                }
+            },
+            FRAME_MAX_DATA => Frame::MaxData(reader.varint()?),
+            FRAME_MAX_STREAM_DATA => Frame::MaxStreamData {
+                stream_id: reader.varint()?,
+                max: reader.varint()?,
+            },
+            FRAME_MAX_STREAMS_BIDI | FRAME_MAX_STREAMS_UNI => {
+                let max: u64 = reader.varint()?;
+                if max > 1 << 60 {
+                    return Err(malformed("stream limit is too large"));
+                }
+                Frame::MaxStreams {
+                    bidi: frame_type == FRAME_MAX_STREAMS_BIDI,
+                    max,
+                }
+            },
+            FRAME_DATA_BLOCKED => {
+                reader.varint()?;
+                Frame::DataBlocked
+            },
+            FRAME_STREAM_DATA_BLOCKED => {
+                reader.varint()?;
+                reader.varint()?;
+                Frame::StreamDataBlocked
+            },
+            FRAME_STREAMS_BLOCKED_BIDI | FRAME_STREAMS_BLOCKED_UNI => {
+                reader.varint()?;
+                Frame::StreamsBlocked
+            },
+            FRAME_NEW_CONNECTION_ID => {
+                let seq: u64 = reader.varint()?;
+                let retire_prior_to: u64 = reader.varint()?;
+                let len: usize = reader.u8()? as usize;
+                if len == 0 || len > 20 || retire_prior_to > seq {
+                    return Err(malformed("invalid new connection ID"));
+                }
+                let cid: &[u8] = reader.bytes(len)?;
+                let _stateless_reset_token: &[u8] = reader.bytes(16)?;
+                Frame::NewConnectionId {
+                    seq,
+                    retire_prior_to,
+                    cid,
+                }
+            },
+            FRAME_RETIRE_CONNECTION_ID => Frame::RetireConnectionId(reader.varint()?),
+            FRAME_PATH_CHALLENGE => Frame::PathChallenge(read_path_data(reader)?),
+            FRAME_PATH_RESPONSE => Frame::PathResponse(read_path_data(reader)?),
+            FRAME_CONNECTION_CLOSE | FRAME_CONNECTION_CLOSE_APPLICATION => {
+                let error_code: u64 = reader.varint()?;
+                let frame_type: Option<u64> = if frame_type == FRAME_CONNECTION_CLOSE {
+                    Some(reader.varint()?)
+                } else {
+                    None
+                };
+                Frame::ConnectionClose {
+                    error_code,
+                    frame_type,
+                    reason: reader.varint_vec()?,
+                }
+            },
+            FRAME_HANDSHAKE_DONE => Frame::HandshakeDone,
+            _ => {
+                let cause: String = format!("unknown frame type (type={:#x})", frame_type);
+                warn!("parse(): {}", cause);
+                return Err(Fail::new(libc::EBADMSG, &cause));
+            },
+        };
+        Ok(frame)
+    }
+
+    /// Checks whether the frame asks the peer for an acknowledgement (RFC 9002, section 2).
+    pub fn is_ack_eliciting(&self) -> bool {
+        !matches!(self, Frame::Padding | Frame::Ack { .. } | Frame::ConnectionClose { .. })
+    }
+
+    /// Checks whether the frame may be carried by Initial and Handshake packets (RFC 9000, section 12.4).
+    pub fn is_allowed_in_handshake(&self) -> bool {
+        match self {
+            Frame::Padding | Frame::Ping | Frame::Ack { .. } | Frame::Crypto { .. } => true,
+            Frame::ConnectionClose { frame_type, .. } => frame_type.is_some(),
+            _ => false,
+        }
+    }
+
+    /// Appends the frame to [out].
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Frame::Padding => out.push(FRAME_PADDING as u8),
+            Frame::Ping => out.push(FRAME_PING as u8),
+            Frame::Ack { delay, ranges } => {
+                debug_assert!(!ranges.is_empty());
+                put_varint(out, FRAME_ACK);
+                put_varint(out, ranges[0].end - 1);
+                put_varint(out, *delay);
+                put_varint(out, ranges.len() as u64 - 1);
+                put_varint(out, ranges[0].end - 1 - ranges[0].start);
+                for pair in ranges.windows(2) {
+                    put_varint(out, pair[0].start - pair[1].end - 1);
+                    put_varint(out, pair[1].end - 1 - pair[1].start);
+                }
+            },
+            Frame::ResetStream {
+                stream_id,
+                error_code,
+                final_size,
+            } => {
+                put_varint(out, FRAME_RESET_STREAM);
+                put_varint(out, *stream_id);
+                put_varint(out, *error_code);
+                put_varint(out, *final_size);
+            },
+            Frame::StopSending { stream_id, error_code } => {
+                put_varint(out, FRAME_STOP_SENDING);
+                put_varint(out, *stream_id);
+                put_varint(out, *error_code);
+            },
+            Frame::Crypto { offset, data } => {
+                put_varint(out, FRAME_CRYPTO);
+                put_varint(out, *offset);
+                put_varint_vec(out, data);
+            },
+            Frame::Stream {
+                stream_id,
+                offset,
+                data,
+                fin,
+            } => {
+                let mut frame_type: u64 = FRAME_STREAM | STREAM_FLAG_LENGTH;
+                if *offset != 0 {
+                    frame_type |= STREAM_FLAG_OFFSET;
+                }
+                if *fin {
+                    frame_type |= STREAM_FLAG_FIN;
+                }
+                put_varint(out, frame_type);
+                put_varint(out, *stream_id);
+                if *offset != 0 {
+                    put_varint(out, *offset);
+                }
+                put_varint_vec(out, data);
+            },
+            Frame::MaxData(max) => {
+                put_varint(out, FRAME_MAX_DATA);
+                put_varint(out, *max);
+            },
+            Frame::MaxStreamData { stream_id, max } => {
+                put_varint(out, FRAME_MAX_STREAM_DATA);
+                put_varint(out, *stream_id);
+                put_varint(out, *max);
+            },
+            Frame::MaxStreams { bidi, max } => {
+                put_varint(
+                    out,
+                    if *bidi {
+                        FRAME_MAX_STREAMS_BIDI
+                    } else {
+                        FRAME_MAX_STREAMS_UNI
+                    },
+                );
+                put_varint(out, *max);
+            },
+            Frame::RetireConnectionId(seq) => {
+                put_varint(out, FRAME_RETIRE_CONNECTION_ID);
+                put_varint(out, *seq);
+            },
+            Frame::PathChallenge(data) => {
+                put_varint(out, FRAME_PATH_CHALLENGE);
+                out.extend_from_slice(data);
+            },
+            Frame::PathResponse(data) => {
+                put_varint(out, FRAME_PATH_RESPONSE);
+                out.extend_from_slice(data);
+            },
+            Frame::ConnectionClose {
+                error_code,
+                frame_type,
+                reason,
+            } => {
+                match frame_type {
+                    Some(frame_type) => {
+                        put_varint(out, FRAME_CONNECTION_CLOSE);
+                        put_varint(out, *error_code);
+                        put_varint(out, *frame_type);
+                    },
+                    None => {
+                        put_varint(out, FRAME_CONNECTION_CLOSE_APPLICATION);
+                        put_varint(out, *error_code);
+                    },
+                }
+                put_varint_vec(out, reason);
+            },
+            Frame::HandshakeDone => put_varint(out, FRAME_HANDSHAKE_DONE),
+            // These frames are never sent.
+            Frame::NewToken
+            | Frame::DataBlocked
+            | Frame::StreamDataBlocked
+            | Frame::StreamsBlocked
+            | Frame::NewConnectionId { .. } => unreachable!("frame is never sent: {:?}", self),
+        }
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Builds the ranges of an ACK frame for the packet numbers in [received], up to [max_ranges] of the largest ones.
+pub fn ack_ranges(received: &RangeSet, max_ranges: usize) -> Vec<Range<u64>> {
+    received.iter().rev().take(max_ranges).cloned().collect()
+}
+
+/// Gets the size of a STREAM frame for [stream_id] that carries [len] bytes from [offset], not counting the data.
+pub fn stream_frame_overhead(stream_id: u64, offset: u64, len: usize) -> usize {
+    let offset_len: usize = if offset != 0 { varint_len(offset) } else { 0 };
+    1 + varint_len(stream_id) + offset_len + varint_len(len as u64)
+}
+
+/// Gets the size of a CRYPTO frame that carries [len] bytes from [offset], not counting the data.
+pub fn crypto_frame_overhead(offset: u64, len: usize) -> usize {
+    1 + varint_len(offset) + varint_len(len as u64)
+}
+
+/// Checks that data of [len] bytes from [offset] does not end past the largest offset of a stream.
+fn check_final_offset(offset: u64, len: usize) -> Result<(), Fail> {
+    if offset.saturating_add(len as u64) > MAX_VARINT {
+        return Err(malformed("data ends past the largest offset"));
+    }
+    Ok(())
+}
+
+fn read_path_data(reader: &mut Reader) -> Result<[u8; 8], Fail> {
+    let mut data: [u8; 8] = [0; 8];
+    data.copy_from_slice(reader.bytes(8)?);
+    Ok(data)
+}
+
+/// Builds the error of a malformed frame.
+fn malformed(cause: &str) -> Fail {
+    warn!("parse(): {}", cause);
+    Fail::new(libc::EBADMSG, cause)
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::Frame;
+    use crate::runtime::network::quic::codec::Reader;
+    use ::anyhow::Result;
+
+    #[test]
+    fn test_frames_round_trip() -> Result<()> {
+        let frames: Vec<Frame> = vec![
+            Frame::Ping,
+            Frame::Ack {
+                delay: 10,
+                ranges: vec![20..31, 12..15, 0..1],
+            },
+            Frame::Crypto {
+                offset: 100,
+                data: b"hello",
+            },
+            Frame::Stream {
+                stream_id: 4,
+                offset: 0,
+                data: b"data",
+                fin: true,
+            },
+            Frame::Stream {
+                stream_id: 1,
+                offset: 70000,
+                data: b"",
+                fin: false,
+            },
+            Frame::MaxStreamData {
+                stream_id: 8,
+                max: 1 << 30,
+            },
+            Frame::ResetStream {
+                stream_id: 5,
+                error_code: 7,
+                final_size: 1000,
+            },
+            Frame::PathChallenge([1, 2, 3, 4, 5, 6, 7, 8]),
+            Frame::ConnectionClose {
+                error_code: 0x10a,
+                frame_type: Some(6),
+                reason: b"bad",
+            },
+            Frame::ConnectionClose {
+                error_code: 3,
+                frame_type: None,
+                reason: b"",
+            },
+            Frame::HandshakeDone,
+        ];
+        let mut out: Vec<u8> = Vec::new();
+        for frame in &frames {
+            frame.encode(&mut out);
+        }
+        let mut reader: Reader = Reader::new(&out);
+        for frame in frames {
+            crate::ensure_eq!(Frame::parse(&mut reader)?, frame);
+        }
+        crate::ensure_eq!(reader.is_empty(), true);
+
+        // Ranges that would go below zero are rejected.
+        crate::ensure_eq!(Frame::parse(&mut Reader::new(&[0x02, 5, 0, 0, 6])).is_err(), true);
+        Ok(())
+    }
+}
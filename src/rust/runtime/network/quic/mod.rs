@@ -0,0 +1,158 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Sans-I/O QUIC transport (RFC 9000) that is secured with the TLS sessions of this crate (RFC 9001) and recovers from
+//! loss with the algorithms of RFC 9002. Connections consume and produce UDP payloads, so they can run over any
+//! datagram socket.
+
+//======================================================================================================================
+// Exports
+//======================================================================================================================
+
+pub mod codec;
+pub mod connection;
+pub mod frame;
+pub mod packet;
+pub mod params;
+pub mod ranges;
+pub mod recovery;
+pub mod stream;
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    network::tls::TlsConfig,
+};
+use ::std::time::Duration;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// The only version of QUIC that is spoken.
+pub const QUIC_VERSION_1: u32 = 0x00000001;
+
+/// Smallest UDP payload that carries an Initial packet of a client, which proves that the path supports it (RFC 9000,
+/// section 14.1).
+pub const MIN_INITIAL_DATAGRAM_SIZE: usize = 1200;
+
+/// Largest UDP payload that is sent. Since path MTU discovery is not supported, this is the size that every QUIC path
+/// supports.
+pub const MAX_DATAGRAM_SIZE: usize = MIN_INITIAL_DATAGRAM_SIZE;
+
+/// Length of the connection IDs that are picked by this side.
+pub const LOCAL_CID_LEN: usize = 8;
+
+/// Transport error codes (RFC 9000, section 20.1).
+pub const NO_ERROR: u64 = 0x0;
+pub const INTERNAL_ERROR: u64 = 0x1;
+pub const FLOW_CONTROL_ERROR: u64 = 0x3;
+pub const STREAM_LIMIT_ERROR: u64 = 0x4;
+pub const STREAM_STATE_ERROR: u64 = 0x5;
+pub const FINAL_SIZE_ERROR: u64 = 0x6;
+pub const FRAME_ENCODING_ERROR: u64 = 0x7;
+pub const TRANSPORT_PARAMETER_ERROR: u64 = 0x8;
+pub const CONNECTION_ID_LIMIT_ERROR: u64 = 0x9;
+pub const PROTOCOL_VIOLATION: u64 = 0xa;
+pub const APPLICATION_ERROR: u64 = 0xc;
+pub const CRYPTO_BUFFER_EXCEEDED: u64 = 0xd;
+/// Errors of the TLS handshake are reported with this code plus the TLS alert (RFC 9001, section 4.8).
+pub const CRYPTO_ERROR: u64 = 0x100;
+
+/// Default limits of a connection.
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_MAX_DATA: u64 = 1 << 20;
+const DEFAULT_MAX_STREAM_DATA: u64 = 256 * 1024;
+const DEFAULT_MAX_STREAMS: u64 = 100;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Configuration of QUIC connections. Only bidirectional streams are offered to peers.
+#[derive(Clone)]
+pub struct QuicConfig {
+    /// Configuration of the TLS handshake, which authenticates peers.
+    tls: TlsConfig,
+    /// Time without activity after which a connection is dropped.
+    idle_timeout: Duration,
+    /// Bytes that the peer may send on all streams, ahead of what the application read.
+    max_data: u64,
+    /// Bytes that the peer may send on each stream, ahead of what the application read.
+    max_stream_data: u64,
+    /// Streams that the peer may have open at once.
+    max_streams: u64,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl QuicConfig {
+    /// Creates a configuration that secures connections with [tls] and the default limits.
+    pub fn new(tls: TlsConfig) -> Self {
+        Self {
+            tls,
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+            max_data: DEFAULT_MAX_DATA,
+            max_stream_data: DEFAULT_MAX_STREAM_DATA,
+            max_streams: DEFAULT_MAX_STREAMS,
+        }
+    }
+
+    /// Sets the time without activity after which a connection is dropped. Zero disables the timeout, unless the peer
+    /// asks for one.
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Sets the flow control windows of connections and of their streams.
+    pub fn with_flow_control(mut self, max_data: u64, max_stream_data: u64) -> Result<Self, Fail> {
+        if max_stream_data == 0 || max_data < max_stream_data || max_data >= 1 << 62 {
+            let cause: String = format!(
+                "invalid flow control windows (max_data={:?}, max_stream_data={:?})",
+                max_data, max_stream_data
+            );
+            error!("with_flow_control(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        self.max_data = max_data;
+        self.max_stream_data = max_stream_data;
+        Ok(self)
+    }
+
+    /// Sets the number of streams that peers may have open at once.
+    pub fn with_max_streams(mut self, max_streams: u64) -> Result<Self, Fail> {
+        if max_streams == 0 || max_streams > 1 << 60 {
+            let cause: String = format!("invalid stream limit (max_streams={:?})", max_streams);
+            error!("with_max_streams(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        self.max_streams = max_streams;
+        Ok(self)
+    }
+
+    pub fn tls(&self) -> &TlsConfig {
+        &self.tls
+    }
+
+    pub fn idle_timeout(&self) -> Duration {
+        self.idle_timeout
+    }
+
+    pub fn max_data(&self) -> u64 {
+        self.max_data
+    }
+
+    pub fn max_stream_data(&self) -> u64 {
+        self.max_stream_data
+    }
+
+    pub fn max_streams(&self) -> u64 {
+        self.max_streams
+    }
+}
@@ -0,0 +1,622 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    expect_some,
+    runtime::{
+        crypto::{
+            aes::{
+                Aes128,
+                AES128_KEY_SIZE,
+            },
+            aes_gcm::{
+                aes128_gcm_open,
+                aes128_gcm_seal,
+                AES_GCM_TAG_SIZE,
+            },
+            chacha20poly1305::{
+                chacha20_xor,
+                CHACHA20_POLY1305_KEY_SIZE,
+                CHACHA20_POLY1305_NONCE_SIZE,
+            },
+            runtime::AeadKey,
+            sha256::hkdf_extract,
+        },
+        fail::Fail,
+        network::{
+            quic::{
+                codec::Reader,
+                QUIC_VERSION_1,
+            },
+            tls::{
+                keys::{
+                    hkdf_expand_label,
+                    Secret,
+                },
+                provider::CryptoProvider,
+            },
+        },
+    },
+};
+use ::std::{
+    ops::Deref,
+    rc::Rc,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Longest connection ID of QUIC version 1.
+pub const MAX_CID_LEN: usize = 20;
+
+/// Size of the authentication tag of every AEAD that protects packets.
+pub const PACKET_TAG_SIZE: usize = AES_GCM_TAG_SIZE;
+
+/// Size of the sample of the ciphertext that header protection is computed from (RFC 9001, section 5.4.2).
+const SAMPLE_SIZE: usize = 16;
+
+/// Salt of the Initial secrets of QUIC version 1 (RFC 9001, section 5.2).
+const INITIAL_SALT: [u8; 20] = [
+    0x38, 0x76, 0x2c, 0xf7, 0xf5, 0x59, 0x34, 0xb3, 0x4d, 0x17, 0x9a, 0xe6, 0xa4, 0xc8, 0x0c, 0xad, 0xcc, 0xbb, 0x7f,
+    0x0a,
+];
+
+/// Bits of the first byte of a packet.
+const HEADER_FORM_LONG: u8 = 0x80;
+const FIXED_BIT: u8 = 0x40;
+const KEY_PHASE_BIT: u8 = 0x04;
+const LONG_RESERVED_BITS: u8 = 0x0c;
+const SHORT_RESERVED_BITS: u8 = 0x18;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Connection ID of QUIC, which is opaque and up to [MAX_CID_LEN] bytes long.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ConnectionId(Vec<u8>);
+
+/// Types of packets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PacketType {
+    Initial,
+    ZeroRtt,
+    Handshake,
+    Retry,
+    VersionNegotiation,
+    /// Packet with a short header, which is protected with the application keys.
+    OneRtt,
+}
+
+/// Header of a packet, as far as it can be read before header protection is removed.
+#[derive(Clone, Debug)]
+pub struct Header {
+    pub packet_type: PacketType,
+    pub dcid: ConnectionId,
+    /// Source connection ID, which packets with a short header do not carry.
+    pub scid: ConnectionId,
+    /// Offset of the packet number in the packet.
+    pub pn_offset: usize,
+    /// Length of the packet, since packets with a long header may be coalesced into one datagram.
+    pub len: usize,
+}
+
+/// Authenticated encryption of the payload of packets.
+#[derive(Clone)]
+enum Aead {
+    /// Initial packets are always protected with AES-128-GCM.
+    Aes128Gcm([u8; AES128_KEY_SIZE]),
+    ChaCha20Poly1305([u8; CHACHA20_POLY1305_KEY_SIZE], Rc<dyn CryptoProvider>),
+}
+
+/// Key that protects the payload of packets of one direction (RFC 9001, section 5.3).
+#[derive(Clone)]
+pub struct PacketKey {
+    aead: Aead,
+    iv: [u8; CHACHA20_POLY1305_NONCE_SIZE],
+    /// Traffic secret that the key was derived from, which the key of the next key phase is derived from.
+    secret: Secret,
+}
+
+/// Key that protects the headers of packets of one direction (RFC 9001, section 5.4).
+#[derive(Clone)]
+pub enum HeaderKey {
+    Aes128(Aes128),
+    ChaCha20([u8; CHACHA20_POLY1305_KEY_SIZE]),
+}
+
+/// Keys of one direction of an encryption level.
+#[derive(Clone)]
+pub struct Keys {
+    pub packet: PacketKey,
+    pub header: HeaderKey,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl ConnectionId {
+    /// Creates a connection ID from [bytes].
+    pub fn new(bytes: &[u8]) -> Result<Self, Fail> {
+        if bytes.len() > MAX_CID_LEN {
+            let cause: String = format!("connection ID is too long (len={:?})", bytes.len());
+            warn!("new(): {}", cause);
+            return Err(Fail::new(libc::EBADMSG, &cause));
+        }
+        Ok(Self(bytes.to_vec()))
+    }
+
+    /// Creates a random connection ID of [len] bytes with [provider].
+    pub fn random(len: usize, provider: &dyn CryptoProvider) -> Self {
+        debug_assert!(len <= MAX_CID_LEN);
+        let mut bytes: Vec<u8> = vec![0; len];
+        provider.fill_random(&mut bytes);
+        Self(bytes)
+    }
+}
+
+impl Header {
+    /// Parses the header of the first packet of [datagram]. Packets with a short header carry a destination connection
+    /// ID of [short_dcid_len] bytes.
+    pub fn parse(datagram: &[u8], short_dcid_len: usize) -> Result<Self, Fail> {
+        let mut reader: Reader = Reader::new(datagram);
+        let first: u8 = reader.u8()?;
+        if first & HEADER_FORM_LONG == 0 {
+            if first & FIXED_BIT == 0 {
+                return Err(malformed("fixed bit is not set"));
+            }
+            let dcid: ConnectionId = ConnectionId::new(reader.bytes(short_dcid_len)?)?;
+            return Ok(Self {
+                packet_type: PacketType::OneRtt,
+                dcid,
+                scid: ConnectionId::default(),
+                pn_offset: 1 + short_dcid_len,
+                len: datagram.len(),
+            });
+        }
+
+        let version: u32 = reader.u32()?;
+        let dcid_len: usize = reader_len(&mut reader)?;
+        let dcid: ConnectionId = ConnectionId::new(reader.bytes(dcid_len)?)?;
+        let scid_len: usize = reader_len(&mut reader)?;
+        let scid: ConnectionId = ConnectionId::new(reader.bytes(scid_len)?)?;
+        if version == 0 {
+            return Ok(Self {
+                packet_type: PacketType::VersionNegotiation,
+                dcid,
+                scid,
+                pn_offset: 0,
+                len: datagram.len(),
+            });
+        }
+        if version != QUIC_VERSION_1 {
+            let cause: String = format!("unsupported version (version={:#x})", version);
+            warn!("parse(): {}", cause);
+            return Err(Fail::new(libc::EPROTONOSUPPORT, &cause));
+        }
+        if first & FIXED_BIT == 0 {
+            return Err(malformed("fixed bit is not set"));
+        }
+        let packet_type: PacketType = match (first >> 4) & 0x03 {
+            0 => PacketType::Initial,
+            1 => PacketType::ZeroRtt,
+            2 => PacketType::Handshake,
+            _ => {
+                return Ok(Self {
+                    packet_type: PacketType::Retry,
+                    dcid,
+                    scid,
+                    pn_offset: 0,
+                    len: datagram.len(),
+                })
+            },
+        };
+        if packet_type == PacketType::Initial {
+            let _token: &[u8] = reader.varint_vec()?;
+        }
+        let len: u64 = reader.varint()?;
+        let pn_offset: usize = datagram.len() - reader.remaining();
+        if len > reader.remaining() as u64 {
+            return Err(malformed("packet is longer than the datagram"));
+        }
+        Ok(Self {
+            packet_type,
+            dcid,
+            scid,
+            pn_offset,
+            len: pn_offset + len as usize,
+        })
+    }
+}
+
+impl PacketKey {
+    /// Computes the nonce of packet number [pn].
+    fn nonce(&self, pn: u64) -> [u8; CHACHA20_POLY1305_NONCE_SIZE] {
+        let mut nonce: [u8; CHACHA20_POLY1305_NONCE_SIZE] = self.iv;
+        for (byte, pn_byte) in nonce[4..].iter_mut().zip(pn.to_be_bytes()) {
+            *byte ^= pn_byte;
+        }
+        nonce
+    }
+
+    /// Encrypts the [payload] of packet number [pn] in place and appends the tag, which also covers [header].
+    pub fn seal(&self, pn: u64, header: &[u8], payload: &mut Vec<u8>) {
+        let nonce: [u8; CHACHA20_POLY1305_NONCE_SIZE] = self.nonce(pn);
+        match &self.aead {
+            Aead::Aes128Gcm(key) => aes128_gcm_seal(key, &nonce, header, payload),
+            Aead::ChaCha20Poly1305(key, provider) => {
+                provider.aead_seal(&AeadKey::ChaCha20Poly1305(*key), &nonce, header, payload)
+            },
+        }
+    }
+
+    /// Checks the tag of the [payload] of packet number [pn] and [header], and decrypts it in place.
+    pub fn open(&self, pn: u64, header: &[u8], payload: &mut Vec<u8>) -> Result<(), Fail> {
+        let nonce: [u8; CHACHA20_POLY1305_NONCE_SIZE] = self.nonce(pn);
+        match &self.aead {
+            Aead::Aes128Gcm(key) => aes128_gcm_open(key, &nonce, header, payload),
+            Aead::ChaCha20Poly1305(key, provider) => {
+                provider.aead_open(&AeadKey::ChaCha20Poly1305(*key), &nonce, header, payload)
+            },
+        }
+    }
+
+    /// Derives the key of the next key phase (RFC 9001, section 6).
+    pub fn next(&self) -> Self {
+        let mut secret: Secret = [0; 32];
+        hkdf_expand_label(&self.secret, b"quic ku", &[], &mut secret);
+        let provider: Rc<dyn CryptoProvider> = match &self.aead {
+            Aead::ChaCha20Poly1305(_, provider) => provider.clone(),
+            Aead::Aes128Gcm(_) => unreachable!("keys of Initial packets are never updated"),
+        };
+        Keys::new(&secret, provider).packet
+    }
+}
+
+impl HeaderKey {
+    /// Computes the mask of header protection from [sample].
+    pub fn mask(&self, sample: &[u8; SAMPLE_SIZE]) -> [u8; 5] {
+        let mut mask: [u8; 5] = [0; 5];
+        match self {
+            HeaderKey::Aes128(aes) => {
+                let mut block: [u8; SAMPLE_SIZE] = *sample;
+                aes.encrypt_block(&mut block);
+                mask.copy_from_slice(&block[..5]);
+            },
+            HeaderKey::ChaCha20(key) => {
+                let counter: u32 = u32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]);
+                let mut nonce: [u8; CHACHA20_POLY1305_NONCE_SIZE] = [0; CHACHA20_POLY1305_NONCE_SIZE];
+                nonce.copy_from_slice(&sample[4..]);
+                chacha20_xor(key, counter, &nonce, &mut mask);
+            },
+        }
+        mask
+    }
+}
+
+impl Keys {
+    /// Derives the keys of Initial packets from [secret], which are protected with AES-128-GCM.
+    pub fn initial(secret: &Secret) -> Self {
+        let mut key: [u8; AES128_KEY_SIZE] = [0; AES128_KEY_SIZE];
+        let mut iv: [u8; CHACHA20_POLY1305_NONCE_SIZE] = [0; CHACHA20_POLY1305_NONCE_SIZE];
+        let mut hp: [u8; AES128_KEY_SIZE] = [0; AES128_KEY_SIZE];
+        hkdf_expand_label(secret, b"quic key", &[], &mut key);
+        hkdf_expand_label(secret, b"quic iv", &[], &mut iv);
+        hkdf_expand_label(secret, b"quic hp", &[], &mut hp);
+        Self {
+            packet: PacketKey {
+                aead: Aead::Aes128Gcm(key),
+                iv,
+                secret: *secret,
+            },
+            header: HeaderKey::Aes128(Aes128::new(&hp)),
+        }
+    }
+
+    /// Derives keys from the traffic [secret] of the TLS session, which negotiates TLS_CHACHA20_POLY1305_SHA256. The
+    /// payload is protected with [provider].
+    pub fn new(secret: &Secret, provider: Rc<dyn CryptoProvider>) -> Self {
+        let mut key: [u8; CHACHA20_POLY1305_KEY_SIZE] = [0; CHACHA20_POLY1305_KEY_SIZE];
+        let mut iv: [u8; CHACHA20_POLY1305_NONCE_SIZE] = [0; CHACHA20_POLY1305_NONCE_SIZE];
+        let mut hp: [u8; CHACHA20_POLY1305_KEY_SIZE] = [0; CHACHA20_POLY1305_KEY_SIZE];
+        hkdf_expand_label(secret, b"quic key", &[], &mut key);
+        hkdf_expand_label(secret, b"quic iv", &[], &mut iv);
+        hkdf_expand_label(secret, b"quic hp", &[], &mut hp);
+        Self {
+            packet: PacketKey {
+                aead: Aead::ChaCha20Poly1305(key, provider),
+                iv,
+                secret: *secret,
+            },
+            header: HeaderKey::ChaCha20(hp),
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Deref for ConnectionId {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Derives the client and server secrets of Initial packets from the destination connection ID [dcid] that the client
+/// picked (RFC 9001, section 5.2).
+pub fn initial_secrets(dcid: &[u8]) -> (Secret, Secret) {
+    let initial_secret: Secret = hkdf_extract(&INITIAL_SALT, dcid);
+    let mut client_secret: Secret = [0; 32];
+    let mut server_secret: Secret = [0; 32];
+    hkdf_expand_label(&initial_secret, b"client in", &[], &mut client_secret);
+    hkdf_expand_label(&initial_secret, b"server in", &[], &mut server_secret);
+    (client_secret, server_secret)
+}
+
+/// Gets the number of bytes that packet number [pn] is encoded with, so that the peer can recover it from the largest
+/// one that it acknowledged (RFC 9000, section 17.1).
+pub fn packet_number_len(pn: u64, largest_acked: Option<u64>) -> usize {
+    let unacked: u64 = match largest_acked {
+        Some(largest_acked) => pn - largest_acked,
+        None => pn + 1,
+    };
+    match unacked {
+        0..=0x7f => 1,
+        0x80..=0x7fff => 2,
+        0x8000..=0x7f_ffff => 3,
+        _ => 4,
+    }
+}
+
+/// Recovers a packet number from its [pn_len] least significant bytes, [truncated], and the largest packet number
+/// that was received so far (RFC 9000, appendix A.3).
+pub fn decode_packet_number(largest: Option<u64>, truncated: u64, pn_len: usize) -> u64 {
+    let expected: u64 = largest.map_or(0, |largest| largest + 1);
+    let window: u64 = 1 << (8 * pn_len);
+    let half_window: u64 = window / 2;
+    let candidate: u64 = (expected & !(window - 1)) | truncated;
+    if candidate + half_window <= expected && candidate < (1 << 62) - window {
+        candidate + window
+    } else if candidate > expected + half_window && candidate >= window {
+        candidate - window
+    } else {
+        candidate
+    }
+}
+
+/// Appends a long header of [packet_type] to [out], for a payload of [payload_len] bytes before encryption. Returns the
+/// offset of the packet number.
+pub fn put_long_header(
+    out: &mut Vec<u8>,
+    packet_type: PacketType,
+    dcid: &ConnectionId,
+    scid: &ConnectionId,
+    pn: u64,
+    pn_len: usize,
+    payload_len: usize,
+) -> usize {
+    let type_bits: u8 = match packet_type {
+        PacketType::Initial => 0,
+        PacketType::Handshake => 2,
+        _ => unreachable!("packets of type {:?} are never sent", packet_type),
+    };
+    out.push(HEADER_FORM_LONG | FIXED_BIT | (type_bits << 4) | (pn_len as u8 - 1));
+    out.extend_from_slice(&QUIC_VERSION_1.to_be_bytes());
+    out.push(dcid.len() as u8);
+    out.extend_from_slice(dcid);
+    out.push(scid.len() as u8);
+    out.extend_from_slice(scid);
+    if packet_type == PacketType::Initial {
+        // Tokens are not supported.
+        out.push(0);
+    }
+    // The length always takes two bytes, so that the size of the header is known before the payload is complete.
+    let len: usize = pn_len + payload_len + PACKET_TAG_SIZE;
+    debug_assert!(len < 1 << 14);
+    out.extend_from_slice(&(len as u16 | 0x4000).to_be_bytes());
+    let pn_offset: usize = out.len();
+    out.extend_from_slice(&pn.to_be_bytes()[(8 - pn_len)..]);
+    pn_offset
+}
+
+/// Appends a short header to [out]. Returns the offset of the packet number.
+pub fn put_short_header(out: &mut Vec<u8>, dcid: &ConnectionId, key_phase: bool, pn: u64, pn_len: usize) -> usize {
+    let key_phase_bit: u8 = if key_phase { KEY_PHASE_BIT } else { 0 };
+    out.push(FIXED_BIT | key_phase_bit | (pn_len as u8 - 1));
+    out.extend_from_slice(dcid);
+    let pn_offset: usize = out.len();
+    out.extend_from_slice(&pn.to_be_bytes()[(8 - pn_len)..]);
+    pn_offset
+}
+
+/// Gets the size of a header of [packet_type] with a packet number of [pn_len] bytes.
+pub fn header_len(packet_type: PacketType, dcid: &ConnectionId, scid: &ConnectionId, pn_len: usize) -> usize {
+    match packet_type {
+        PacketType::OneRtt => 1 + dcid.len() + pn_len,
+        PacketType::Initial => 1 + 4 + 1 + dcid.len() + 1 + scid.len() + 1 + 2 + pn_len,
+        _ => 1 + 4 + 1 + dcid.len() + 1 + scid.len() + 2 + pn_len,
+    }
+}
+
+/// Encrypts [packet], whose payload follows a packet number of [pn_len] bytes at [pn_offset], and protects its header.
+/// The payload must be long enough for the header protection sample.
+pub fn protect(packet_key: &PacketKey, header_key: &HeaderKey, pn: u64, packet: &mut Vec<u8>, pn_offset: usize) {
+    let pn_len: usize = (packet[0] & 0x03) as usize + 1;
+    let mut payload: Vec<u8> = packet.split_off(pn_offset + pn_len);
+    packet_key.seal(pn, packet, &mut payload);
+    packet.extend_from_slice(&payload);
+
+    let sample: [u8; SAMPLE_SIZE] = expect_sample(packet, pn_offset);
+    let mask: [u8; 5] = header_key.mask(&sample);
+    packet[0] ^= mask[0] & first_byte_mask(packet[0]);
+    for i in 0..pn_len {
+        packet[pn_offset + i] ^= mask[1 + i];
+    }
+}
+
+/// Removes the header protection of [packet] in place and returns the length of its packet number and its truncated
+/// value.
+pub fn unprotect_header(header_key: &HeaderKey, packet: &mut [u8], pn_offset: usize) -> Result<(usize, u64), Fail> {
+    if packet.len() < pn_offset + 4 + SAMPLE_SIZE {
+        return Err(malformed("packet is too short for header protection"));
+    }
+    let sample: [u8; SAMPLE_SIZE] = expect_sample(packet, pn_offset);
+    let mask: [u8; 5] = header_key.mask(&sample);
+    packet[0] ^= mask[0] & first_byte_mask(packet[0]);
+    let pn_len: usize = (packet[0] & 0x03) as usize + 1;
+    let mut truncated: u64 = 0;
+    for i in 0..pn_len {
+        packet[pn_offset + i] ^= mask[1 + i];
+        truncated = (truncated << 8) | packet[pn_offset + i] as u64;
+    }
+    Ok((pn_len, truncated))
+}
+
+/// Checks that the reserved bits of the first byte of a packet, after header protection was removed, are clear.
+pub fn check_reserved_bits(first: u8) -> bool {
+    let reserved: u8 = if first & HEADER_FORM_LONG != 0 {
+        LONG_RESERVED_BITS
+    } else {
+        SHORT_RESERVED_BITS
+    };
+    first & reserved == 0
+}
+
+/// Gets the key phase of a packet with a short header, after header protection was removed.
+pub fn key_phase(first: u8) -> bool {
+    first & KEY_PHASE_BIT != 0
+}
+
+/// Gets the bits of the first byte that header protection covers.
+fn first_byte_mask(first: u8) -> u8 {
+    if first & HEADER_FORM_LONG != 0 {
+        0x0f
+    } else {
+        0x1f
+    }
+}
+
+/// Takes the sample of header protection from [packet], which starts four bytes after the packet number offset.
+fn expect_sample(packet: &[u8], pn_offset: usize) -> [u8; SAMPLE_SIZE] {
+    let start: usize = pn_offset + 4;
+    expect_some!(
+        packet.get(start..(start + SAMPLE_SIZE)).and_then(|s| s.try_into().ok()),
+        "packets are padded for the sample of header protection"
+    )
+}
+
+/// Reads the length of a connection ID, which must not exceed [MAX_CID_LEN].
+fn reader_len(reader: &mut Reader) -> Result<usize, Fail> {
+    let len: usize = reader.u8()? as usize;
+    if len > MAX_CID_LEN {
+        return Err(malformed("connection ID is too long"));
+    }
+    Ok(len)
+}
+
+/// Builds the error of a malformed packet.
+fn malformed(cause: &str) -> Fail {
+    warn!("parse(): {}", cause);
+    Fail::new(libc::EBADMSG, cause)
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        decode_packet_number,
+        initial_secrets,
+        packet_number_len,
+        protect,
+        unprotect_header,
+        Aead,
+        HeaderKey,
+        Keys,
+    };
+    use crate::runtime::{
+        crypto::unhex,
+        network::tls::{
+            keys::Secret,
+            provider::SoftwareCryptoProvider,
+        },
+    };
+    use ::anyhow::Result;
+    use ::std::rc::Rc;
+
+    /// Keys of RFC 9001, appendix A.1.
+    #[test]
+    fn test_initial_keys() -> Result<()> {
+        let (client_secret, server_secret): (Secret, Secret) = initial_secrets(&unhex("8394c8f03e515708"));
+        crate::ensure_eq!(
+            client_secret.to_vec(),
+            unhex("c00cf151ca5be075ed0ebfb5c80323c42d6b7db67881289af4008f1f6c357aea")
+        );
+        crate::ensure_eq!(
+            server_secret.to_vec(),
+            unhex("3c199828fd139efd216c155ad844cc81fb82fa8d7446fa7d78be803acdda951b")
+        );
+        let keys: Keys = Keys::initial(&client_secret);
+        match keys.packet.aead {
+            Aead::Aes128Gcm(key) => crate::ensure_eq!(key.to_vec(), unhex("1f369613dd76d5467730efcbe3b1a22d")),
+            Aead::ChaCha20Poly1305(..) => anyhow::bail!("initial keys use AES-128-GCM"),
+        }
+        crate::ensure_eq!(keys.packet.iv.to_vec(), unhex("fa044b2f42a3fd3b46fb255c"));
+        let keys: Keys = Keys::initial(&server_secret);
+        crate::ensure_eq!(keys.packet.iv.to_vec(), unhex("0ac1493ca1905853b0bba03e"));
+        Ok(())
+    }
+
+    /// Protection of a short header packet with ChaCha20-Poly1305 from RFC 9001, appendix A.5.
+    #[test]
+    fn test_chacha20_short_header_packet() -> Result<()> {
+        let secret: Secret = unhex("9ac312a7f877468ebe69422748ad00a15443f18203a07d6060f688f30f21632b")
+            .try_into()
+            .unwrap();
+        let keys: Keys = Keys::new(&secret, Rc::new(SoftwareCryptoProvider));
+        match &keys.header {
+            HeaderKey::ChaCha20(hp) => crate::ensure_eq!(
+                hp.to_vec(),
+                unhex("25a282b9e82f06f21f488917a4fc8f1b73573685608597d0efcb076b0ab7a7a4")
+            ),
+            HeaderKey::Aes128(_) => anyhow::bail!("keys use ChaCha20"),
+        }
+        let pn: u64 = 654360564;
+        let mut packet: Vec<u8> = unhex("4200bff401");
+        protect(&keys.packet, &keys.header, pn, &mut packet, 1);
+        crate::ensure_eq!(packet, unhex("4cfe4189655e5cd55c41f69080575d7999c25a5bfb"));
+
+        let (pn_len, truncated): (usize, u64) = unprotect_header(&keys.header, &mut packet, 1)?;
+        crate::ensure_eq!(pn_len, 3);
+        crate::ensure_eq!(decode_packet_number(Some(pn - 1), truncated, pn_len), pn);
+        let mut payload: Vec<u8> = packet.split_off(4);
+        keys.packet.open(pn, &packet, &mut payload)?;
+        crate::ensure_eq!(payload, vec![0x01]);
+        Ok(())
+    }
+
+    /// Example of RFC 9000, appendix A.3.
+    #[test]
+    fn test_packet_numbers() -> Result<()> {
+        crate::ensure_eq!(decode_packet_number(Some(0xa82f30ea), 0x9b32, 2), 0xa82f9b32);
+        crate::ensure_eq!(decode_packet_number(None, 0, 1), 0);
+        crate::ensure_eq!(packet_number_len(0xac5c02, Some(0xabe8b3)), 2);
+        crate::ensure_eq!(packet_number_len(0xace8fe, Some(0xabe8b3)), 3);
+        Ok(())
+    }
+}
@@ -0,0 +1,282 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    network::quic::{
+        codec::{
+            put_varint,
+            put_varint_vec,
+            varint_len,
+            Reader,
+        },
+        packet::ConnectionId,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Identifiers of transport parameters (RFC 9000, section 18.2).
+const ORIGINAL_DESTINATION_CONNECTION_ID: u64 = 0x00;
+const MAX_IDLE_TIMEOUT: u64 = 0x01;
+const STATELESS_RESET_TOKEN: u64 = 0x02;
+const MAX_UDP_PAYLOAD_SIZE: u64 = 0x03;
+const INITIAL_MAX_DATA: u64 = 0x04;
+const INITIAL_MAX_STREAM_DATA_BIDI_LOCAL: u64 = 0x05;
+const INITIAL_MAX_STREAM_DATA_BIDI_REMOTE: u64 = 0x06;
+const INITIAL_MAX_STREAM_DATA_UNI: u64 = 0x07;
+const INITIAL_MAX_STREAMS_BIDI: u64 = 0x08;
+const INITIAL_MAX_STREAMS_UNI: u64 = 0x09;
+const ACK_DELAY_EXPONENT: u64 = 0x0a;
+const MAX_ACK_DELAY: u64 = 0x0b;
+const DISABLE_ACTIVE_MIGRATION: u64 = 0x0c;
+const PREFERRED_ADDRESS: u64 = 0x0d;
+const ACTIVE_CONNECTION_ID_LIMIT: u64 = 0x0e;
+const INITIAL_SOURCE_CONNECTION_ID: u64 = 0x0f;
+const RETRY_SOURCE_CONNECTION_ID: u64 = 0x10;
+
+/// Default values of transport parameters that are not sent.
+pub const DEFAULT_ACK_DELAY_EXPONENT: u64 = 3;
+pub const DEFAULT_MAX_ACK_DELAY_MS: u64 = 25;
+const DEFAULT_MAX_UDP_PAYLOAD_SIZE: u64 = 65527;
+const DEFAULT_ACTIVE_CONNECTION_ID_LIMIT: u64 = 2;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Transport parameters that an endpoint announces in the TLS handshake (RFC 9000, section 18). Parameters that are
+/// not supported are skipped when decoding and never sent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransportParameters {
+    /// Destination connection ID of the first Initial packet of the client, which only servers send.
+    pub original_destination_connection_id: Option<ConnectionId>,
+    /// Idle timeout in milliseconds, where zero means none.
+    pub max_idle_timeout: u64,
+    pub max_udp_payload_size: u64,
+    pub initial_max_data: u64,
+    pub initial_max_stream_data_bidi_local: u64,
+    pub initial_max_stream_data_bidi_remote: u64,
+    pub initial_max_stream_data_uni: u64,
+    pub initial_max_streams_bidi: u64,
+    pub initial_max_streams_uni: u64,
+    pub ack_delay_exponent: u64,
+    /// Largest delay of acknowledgements in milliseconds.
+    pub max_ack_delay: u64,
+    pub disable_active_migration: bool,
+    pub active_connection_id_limit: u64,
+    pub initial_source_connection_id: Option<ConnectionId>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl TransportParameters {
+    /// Encodes the transport parameters.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        if let Some(cid) = self.original_destination_connection_id.as_ref() {
+            put_parameter(&mut out, ORIGINAL_DESTINATION_CONNECTION_ID, cid);
+        }
+        put_int_parameter(&mut out, MAX_IDLE_TIMEOUT, self.max_idle_timeout);
+        put_int_parameter(&mut out, MAX_UDP_PAYLOAD_SIZE, self.max_udp_payload_size);
+        put_int_parameter(&mut out, INITIAL_MAX_DATA, self.initial_max_data);
+        put_int_parameter(
+            &mut out,
+            INITIAL_MAX_STREAM_DATA_BIDI_LOCAL,
+            self.initial_max_stream_data_bidi_local,
+        );
+        put_int_parameter(
+            &mut out,
+            INITIAL_MAX_STREAM_DATA_BIDI_REMOTE,
+            self.initial_max_stream_data_bidi_remote,
+        );
+        put_int_parameter(&mut out, INITIAL_MAX_STREAM_DATA_UNI, self.initial_max_stream_data_uni);
+        put_int_parameter(&mut out, INITIAL_MAX_STREAMS_BIDI, self.initial_max_streams_bidi);
+        put_int_parameter(&mut out, INITIAL_MAX_STREAMS_UNI, self.initial_max_streams_uni);
+        put_int_parameter(&mut out, ACK_DELAY_EXPONENT, self.ack_delay_exponent);
+        put_int_parameter(&mut out, MAX_ACK_DELAY, self.max_ack_delay);
+        if self.disable_active_migration {
+            put_parameter(&mut out, DISABLE_ACTIVE_MIGRATION, &[]);
+        }
+        put_int_parameter(&mut out, ACTIVE_CONNECTION_ID_LIMIT, self.active_connection_id_limit);
+        if let Some(cid) = self.initial_source_connection_id.as_ref() {
+            put_parameter(&mut out, INITIAL_SOURCE_CONNECTION_ID, cid);
+        }
+        out
+    }
+
+    /// Decodes and checks the transport parameters in [buf], which a server sent if [from_server] is set.
+    pub fn decode(buf: &[u8], from_server: bool) -> Result<Self, Fail> {
+        let mut params: Self = Self::default();
+        let mut seen: Vec<u64> = Vec::new();
+        let mut reader: Reader = Reader::new(buf);
+        while !reader.is_empty() {
+            let id: u64 = reader.varint()?;
+            let mut value: Reader = Reader::new(reader.varint_vec()?);
+            if seen.contains(&id) {
+                return Err(invalid(&format!("duplicate transport parameter (id={:#x})", id)));
+            }
+            seen.push(id);
+            match id {
+                ORIGINAL_DESTINATION_CONNECTION_ID
+                | STATELESS_RESET_TOKEN
+                | PREFERRED_ADDRESS
+                | RETRY_SOURCE_CONNECTION_ID
+                    if !from_server =>
+                {
+                    return Err(invalid(&format!(
+                        "client sent a transport parameter of servers (id={:#x})",
+                        id
+                    )));
+                },
+                // Retry is not supported, so no server may use it.
+                RETRY_SOURCE_CONNECTION_ID => return Err(invalid("server sent a Retry connection ID")),
+                ORIGINAL_DESTINATION_CONNECTION_ID => {
+                    params.original_destination_connection_id = Some(ConnectionId::new(value.rest())?)
+                },
+                INITIAL_SOURCE_CONNECTION_ID => {
+                    params.initial_source_connection_id = Some(ConnectionId::new(value.rest())?)
+                },
+                DISABLE_ACTIVE_MIGRATION => params.disable_active_migration = true,
+                // Only used for migration and stateless resets, which are not supported.
+                STATELESS_RESET_TOKEN | PREFERRED_ADDRESS => {
+                    value.rest();
+                },
+                MAX_IDLE_TIMEOUT => params.max_idle_timeout = value.varint()?,
+                MAX_UDP_PAYLOAD_SIZE => params.max_udp_payload_size = value.varint()?,
+                INITIAL_MAX_DATA => params.initial_max_data = value.varint()?,
+                INITIAL_MAX_STREAM_DATA_BIDI_LOCAL => params.initial_max_stream_data_bidi_local = value.varint()?,
+                INITIAL_MAX_STREAM_DATA_BIDI_REMOTE => params.initial_max_stream_data_bidi_remote = value.varint()?,
+                INITIAL_MAX_STREAM_DATA_UNI => params.initial_max_stream_data_uni = value.varint()?,
+                INITIAL_MAX_STREAMS_BIDI => params.initial_max_streams_bidi = value.varint()?,
+                INITIAL_MAX_STREAMS_UNI => params.initial_max_streams_uni = value.varint()?,
+                ACK_DELAY_EXPONENT => params.ack_delay_exponent = value.varint()?,
+                MAX_ACK_DELAY => params.max_ack_delay = value.varint()?,
+                ACTIVE_CONNECTION_ID_LIMIT => params.active_connection_id_limit = value.varint()?,
+                // Unknown parameters are ignored.
+                _ => {
+                    value.rest();
+                },
+            }
+            if !value.is_empty() {
+                return Err(invalid(&format!("malformed transport parameter (id={:#x})", id)));
+            }
+        }
+
+        if params.max_udp_payload_size < 1200 {
+            return Err(invalid("maximum UDP payload size is too small"));
+        }
+        if params.ack_delay_exponent > 20 {
+            return Err(invalid("ACK delay exponent is too large"));
+        }
+        if params.max_ack_delay >= 1 << 14 {
+            return Err(invalid("maximum ACK delay is too large"));
+        }
+        if params.active_connection_id_limit < 2 {
+            return Err(invalid("active connection ID limit is too small"));
+        }
+        if params.initial_max_streams_bidi > 1 << 60 || params.initial_max_streams_uni > 1 << 60 {
+            return Err(invalid("stream limit is too large"));
+        }
+        if params.initial_source_connection_id.is_none()
+            || (from_server && params.original_destination_connection_id.is_none())
+        {
+            return Err(invalid("connection IDs are missing"));
+        }
+        Ok(params)
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Default for TransportParameters {
+    /// Builds the values that apply to transport parameters that are not sent.
+    fn default() -> Self {
+        Self {
+            original_destination_connection_id: None,
+            max_idle_timeout: 0,
+            max_udp_payload_size: DEFAULT_MAX_UDP_PAYLOAD_SIZE,
+            initial_max_data: 0,
+            initial_max_stream_data_bidi_local: 0,
+            initial_max_stream_data_bidi_remote: 0,
+            initial_max_stream_data_uni: 0,
+            initial_max_streams_bidi: 0,
+            initial_max_streams_uni: 0,
+            ack_delay_exponent: DEFAULT_ACK_DELAY_EXPONENT,
+            max_ack_delay: DEFAULT_MAX_ACK_DELAY_MS,
+            disable_active_migration: false,
+            active_connection_id_limit: DEFAULT_ACTIVE_CONNECTION_ID_LIMIT,
+            initial_source_connection_id: None,
+        }
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+fn put_parameter(out: &mut Vec<u8>, id: u64, value: &[u8]) {
+    put_varint(out, id);
+    put_varint_vec(out, value);
+}
+
+fn put_int_parameter(out: &mut Vec<u8>, id: u64, value: u64) {
+    put_varint(out, id);
+    put_varint(out, varint_len(value) as u64);
+    put_varint(out, value);
+}
+
+/// Builds the error of invalid transport parameters.
+fn invalid(cause: &str) -> Fail {
+    warn!("decode(): {}", cause);
+    Fail::new(libc::EPROTO, cause)
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::TransportParameters;
+    use crate::runtime::network::quic::packet::ConnectionId;
+    use ::anyhow::Result;
+
+    #[test]
+    fn test_transport_parameters_round_trip() -> Result<()> {
+        let params: TransportParameters = TransportParameters {
+            original_destination_connection_id: Some(ConnectionId::new(&[1, 2, 3, 4, 5, 6, 7, 8])?),
+            max_idle_timeout: 30000,
+            initial_max_data: 1 << 20,
+            initial_max_stream_data_bidi_local: 1 << 16,
+            initial_max_stream_data_bidi_remote: 1 << 16,
+            initial_max_streams_bidi: 100,
+            disable_active_migration: true,
+            initial_source_connection_id: Some(ConnectionId::new(&[9; 8])?),
+            ..Default::default()
+        };
+        let encoded: Vec<u8> = params.encode();
+        crate::ensure_eq!(TransportParameters::decode(&encoded, true)?, params);
+        // Clients must not send the parameters of servers.
+        crate::ensure_eq!(TransportParameters::decode(&encoded, false).is_err(), true);
+
+        // Limits are checked.
+        let params: TransportParameters = TransportParameters {
+            original_destination_connection_id: None,
+            max_udp_payload_size: 1000,
+            ..params
+        };
+        crate::ensure_eq!(TransportParameters::decode(&params.encode(), false).is_err(), true);
+        Ok(())
+    }
+}
@@ -0,0 +1,155 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use ::std::ops::Range;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Set of integers that is kept as disjoint, non-adjacent ranges in ascending order. It tracks the packet numbers that
+/// were received and the stream offsets that were acknowledged or must be sent again.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RangeSet {
+    ranges: Vec<Range<u64>>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl RangeSet {
+    pub fn new() -> Self {
+        Self { ranges: Vec::new() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Gets the number of disjoint ranges.
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    /// Iterates over the ranges in ascending order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Range<u64>> {
+        self.ranges.iter()
+    }
+
+    /// Gets the lowest range.
+    pub fn first(&self) -> Option<Range<u64>> {
+        self.ranges.first().cloned()
+    }
+
+    /// Gets the largest integer of the set.
+    pub fn max(&self) -> Option<u64> {
+        self.ranges.last().map(|range| range.end - 1)
+    }
+
+    pub fn contains(&self, value: u64) -> bool {
+        self.ranges
+            .iter()
+            .any(|range| range.start <= value && value < range.end)
+    }
+
+    /// Checks whether all of [range] is in the set.
+    pub fn contains_range(&self, range: &Range<u64>) -> bool {
+        range.is_empty() || self.ranges.iter().any(|r| r.start <= range.start && range.end <= r.end)
+    }
+
+    /// Adds [range] to the set.
+    pub fn insert(&mut self, range: Range<u64>) {
+        if range.is_empty() {
+            return;
+        }
+        // Find the ranges that overlap or touch the new one and merge them.
+        let first: usize = self.ranges.partition_point(|r| r.end < range.start);
+        let last: usize = self.ranges.partition_point(|r| r.start <= range.end);
+        let mut merged: Range<u64> = range;
+        if first < last {
+            merged.start = merged.start.min(self.ranges[first].start);
+            merged.end = merged.end.max(self.ranges[last - 1].end);
+        }
+        self.ranges.splice(first..last, ::std::iter::once(merged));
+    }
+
+    /// Removes [range] from the set.
+    pub fn remove(&mut self, range: Range<u64>) {
+        if range.is_empty() {
+            return;
+        }
+        let mut kept: Vec<Range<u64>> = Vec::with_capacity(self.ranges.len() + 1);
+        for r in self.ranges.drain(..) {
+            if r.end <= range.start || range.end <= r.start {
+                kept.push(r);
+                continue;
+            }
+            if r.start < range.start {
+                kept.push(r.start..range.start);
+            }
+            if range.end < r.end {
+                kept.push(range.end..r.end);
+            }
+        }
+        self.ranges = kept;
+    }
+
+    /// Removes and returns up to [max_len] integers from the start of the lowest range.
+    pub fn pop_front(&mut self, max_len: u64) -> Option<Range<u64>> {
+        let first: &mut Range<u64> = self.ranges.first_mut()?;
+        let end: u64 = first.end.min(first.start.saturating_add(max_len));
+        let popped: Range<u64> = first.start..end;
+        first.start = end;
+        if first.is_empty() {
+            self.ranges.remove(0);
+        }
+        Some(popped)
+    }
+
+    /// Drops the lowest ranges, so that at most [max_len] are left.
+    pub fn truncate_front(&mut self, max_len: usize) {
+        if self.ranges.len() > max_len {
+            let excess: usize = self.ranges.len() - max_len;
+            self.ranges.drain(..excess);
+        }
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::RangeSet;
+    use ::anyhow::Result;
+    use ::std::ops::Range;
+
+    #[test]
+    fn test_insert_and_remove() -> Result<()> {
+        let mut set: RangeSet = RangeSet::new();
+        set.insert(10..20);
+        set.insert(30..40);
+        set.insert(20..25);
+        crate::ensure_eq!(set.iter().cloned().collect::<Vec<Range<u64>>>(), vec![10..25, 30..40]);
+        set.insert(5..35);
+        crate::ensure_eq!(set.iter().cloned().collect::<Vec<Range<u64>>>(), vec![5..40]);
+        set.remove(12..15);
+        crate::ensure_eq!(set.iter().cloned().collect::<Vec<Range<u64>>>(), vec![5..12, 15..40]);
+        crate::ensure_eq!(set.contains(12), false);
+        crate::ensure_eq!(set.contains_range(&(20..40)), true);
+        crate::ensure_eq!(set.max(), Some(39));
+        crate::ensure_eq!(set.pop_front(4), Some(5..9));
+        crate::ensure_eq!(set.pop_front(100), Some(9..12));
+        crate::ensure_eq!(set.first(), Some(15..40));
+        set.insert(50..51);
+        set.truncate_front(1);
+        crate::ensure_eq!(set.iter().cloned().collect::<Vec<Range<u64>>>(), vec![50..51]);
+        Ok(())
+    }
+}
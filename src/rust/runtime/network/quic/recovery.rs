@@ -0,0 +1,617 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    network::{
+        quic::{
+            params::DEFAULT_MAX_ACK_DELAY_MS,
+            MAX_DATAGRAM_SIZE,
+        },
+        tls::EncryptionLevel,
+    },
+};
+use ::std::{
+    collections::BTreeMap,
+    ops::Range,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Timer granularity (RFC 9002, section 6.1.2).
+const GRANULARITY: Duration = Duration::from_millis(1);
+
+/// Round-trip time that is assumed before the first sample (RFC 9002, section 6.2.2).
+const INITIAL_RTT: Duration = Duration::from_millis(333);
+
+/// Number of later packets that must be acknowledged before a packet is deemed lost (RFC 9002, section 6.1.1).
+const PACKET_THRESHOLD: u64 = 3;
+
+/// Multiple of the round-trip time, in eighths, after which a packet is deemed lost (RFC 9002, section 6.1.2).
+const TIME_THRESHOLD_EIGHTHS: u32 = 9;
+
+/// Number of probe timeouts that losses must span to be considered persistent congestion (RFC 9002, section 7.6).
+const PERSISTENT_CONGESTION_THRESHOLD: u32 = 3;
+
+/// Smallest congestion window (RFC 9002, section 7.2).
+const MIN_WINDOW: usize = 2 * MAX_DATAGRAM_SIZE;
+
+/// Number of packet number spaces, one for each encryption level.
+const NUM_SPACES: usize = 3;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Frame of a sent packet that must be acted upon when the packet is acknowledged or lost.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SentFrame {
+    /// ACK frame whose largest acknowledged packet number was [largest].
+    Ack {
+        largest: u64,
+    },
+    Crypto {
+        offset: u64,
+        len: usize,
+    },
+    Stream {
+        stream_id: u64,
+        offset: u64,
+        len: usize,
+        fin: bool,
+    },
+    ResetStream {
+        stream_id: u64,
+    },
+    MaxData,
+    MaxStreamData {
+        stream_id: u64,
+    },
+    MaxStreams,
+    RetireConnectionId(u64),
+    HandshakeDone,
+    Ping,
+}
+
+/// Packet that was sent and not yet acknowledged or deemed lost.
+#[derive(Clone, Debug)]
+pub struct SentPacket {
+    pub pn: u64,
+    pub time_sent: Instant,
+    pub size: usize,
+    pub ack_eliciting: bool,
+    pub frames: Vec<SentFrame>,
+}
+
+/// Estimator of the round-trip time (RFC 9002, section 5).
+#[derive(Clone, Debug)]
+pub struct RttEstimator {
+    latest: Duration,
+    smoothed: Duration,
+    variance: Duration,
+    min: Duration,
+    /// Time of the first sample.
+    first_sample: Option<Instant>,
+}
+
+/// State of loss detection of one packet number space.
+#[derive(Default)]
+struct SpaceRecovery {
+    sent: BTreeMap<u64, SentPacket>,
+    largest_sent: Option<u64>,
+    largest_acked: Option<u64>,
+    /// Time at which the next packet is deemed lost by the time threshold.
+    loss_time: Option<Instant>,
+    last_ack_eliciting: Option<Instant>,
+    /// Time at which the last packet of the space was sent, whether it elicits an acknowledgement or not.
+    last_sent: Option<Instant>,
+}
+
+/// Packets that an ACK frame acknowledged and those that it revealed to be lost.
+#[derive(Default)]
+pub struct AckOutcome {
+    pub acked: Vec<SentPacket>,
+    pub lost: Vec<SentPacket>,
+}
+
+/// What must be done when the loss detection timer expires.
+pub enum TimeoutOutcome {
+    /// Packets of a space were deemed lost by the time threshold.
+    Lost(EncryptionLevel, Vec<SentPacket>),
+    /// One ack-eliciting packet must be sent as a probe in the space.
+    Probe(EncryptionLevel),
+}
+
+/// Loss detection and NewReno congestion control of a connection (RFC 9002).
+pub struct Recovery {
+    rtt: RttEstimator,
+    spaces: [SpaceRecovery; NUM_SPACES],
+    /// Number of probe timeouts since the last acknowledgement.
+    pto_count: u32,
+    /// Largest delay of acknowledgements that the peer announced.
+    max_ack_delay: Duration,
+    bytes_in_flight: usize,
+    congestion_window: usize,
+    slow_start_threshold: usize,
+    /// Start of the current recovery period, if any.
+    recovery_start: Option<Instant>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl RttEstimator {
+    fn new() -> Self {
+        Self {
+            latest: Duration::ZERO,
+            smoothed: INITIAL_RTT,
+            variance: INITIAL_RTT / 2,
+            min: Duration::ZERO,
+            first_sample: None,
+        }
+    }
+
+    /// Adds the sample [latest], which includes the [ack_delay] of the peer.
+    fn update(&mut self, latest: Duration, ack_delay: Duration, now: Instant) {
+        self.latest = latest;
+        if self.first_sample.is_none() {
+            self.first_sample = Some(now);
+            self.min = latest;
+            self.smoothed = latest;
+            self.variance = latest / 2;
+            return;
+        }
+        self.min = self.min.min(latest);
+        // The delay of the peer is only taken out if the sample stays above the minimum.
+        let adjusted: Duration = if latest >= self.min + ack_delay {
+            latest - ack_delay
+        } else {
+            latest
+        };
+        let deviation: Duration = if self.smoothed > adjusted {
+            self.smoothed - adjusted
+        } else {
+            adjusted - self.smoothed
+        };
+        self.variance = (self.variance * 3 + deviation) / 4;
+        self.smoothed = (self.smoothed * 7 + adjusted) / 8;
+    }
+
+    pub fn smoothed(&self) -> Duration {
+        self.smoothed
+    }
+
+    /// Gets the probe timeout without the delay of acknowledgements and backoff.
+    pub fn pto_base(&self) -> Duration {
+        self.smoothed + (self.variance * 4).max(GRANULARITY)
+    }
+
+    /// Gets the time after which a packet is deemed lost if a later one was acknowledged.
+    fn loss_delay(&self) -> Duration {
+        (self.latest.max(self.smoothed) * TIME_THRESHOLD_EIGHTHS / 8).max(GRANULARITY)
+    }
+}
+
+impl Recovery {
+    pub fn new() -> Self {
+        Self {
+            rtt: RttEstimator::new(),
+            spaces: Default::default(),
+            pto_count: 0,
+            max_ack_delay: Duration::from_millis(DEFAULT_MAX_ACK_DELAY_MS),
+            bytes_in_flight: 0,
+            congestion_window: initial_window(),
+            slow_start_threshold: usize::MAX,
+            recovery_start: None,
+        }
+    }
+
+    pub fn rtt(&self) -> &RttEstimator {
+        &self.rtt
+    }
+
+    pub fn set_max_ack_delay(&mut self, max_ack_delay: Duration) {
+        self.max_ack_delay = max_ack_delay;
+    }
+
+    /// Gets the probe timeout of the application space, including the delay of acknowledgements and backoff.
+    pub fn pto(&self) -> Duration {
+        (self.rtt.pto_base() + self.max_ack_delay) * 2u32.saturating_pow(self.pto_count.min(16))
+    }
+
+    pub fn bytes_in_flight(&self) -> usize {
+        self.bytes_in_flight
+    }
+
+    pub fn congestion_window(&self) -> usize {
+        self.congestion_window
+    }
+
+    /// Checks whether the congestion window leaves room for an ack-eliciting packet of [size] bytes.
+    pub fn can_send(&self, size: usize) -> bool {
+        self.bytes_in_flight + size <= self.congestion_window
+    }
+
+    /// Gets the largest packet number of [level] that the peer acknowledged.
+    pub fn largest_acked(&self, level: EncryptionLevel) -> Option<u64> {
+        self.spaces[level as usize].largest_acked
+    }
+
+    /// Iterates over the packets of [level] that were neither acknowledged nor deemed lost.
+    pub fn unacked(&self, level: EncryptionLevel) -> impl Iterator<Item = &SentPacket> {
+        self.spaces[level as usize].sent.values()
+    }
+
+    /// Checks whether an ack-eliciting packet of any space is in flight.
+    pub fn has_ack_eliciting_in_flight(&self) -> bool {
+        self.spaces.iter().any(|space| space.last_ack_eliciting.is_some())
+    }
+
+    /// Records that [packet] was sent at [level]. Packets that only carry ACK frames do not count as in flight.
+    pub fn on_packet_sent(&mut self, level: EncryptionLevel, packet: SentPacket) {
+        let space: &mut SpaceRecovery = &mut self.spaces[level as usize];
+        space.largest_sent = Some(packet.pn);
+        space.last_sent = Some(packet.time_sent);
+        if packet.ack_eliciting {
+            space.last_ack_eliciting = Some(packet.time_sent);
+            self.bytes_in_flight += packet.size;
+            space.sent.insert(packet.pn, packet);
+        }
+    }
+
+    /// Processes an ACK frame of [level] that acknowledges the packet numbers of [ranges] in descending order, sent
+    /// [ack_delay] after the largest of them arrived.
+    pub fn on_ack_received(
+        &mut self,
+        level: EncryptionLevel,
+        ranges: &[Range<u64>],
+        ack_delay: Duration,
+        handshake_confirmed: bool,
+        now: Instant,
+    ) -> Result<AckOutcome, Fail> {
+        let largest: u64 = match ranges.first() {
+            Some(range) => range.end - 1,
+            None => return Ok(AckOutcome::default()),
+        };
+        let space: &mut SpaceRecovery = &mut self.spaces[level as usize];
+        if space.largest_sent.map_or(true, |largest_sent| largest > largest_sent) {
+            let cause: String = format!("peer acknowledged a packet that was not sent (pn={:?})", largest);
+            warn!("on_ack_received(): {}", cause);
+            return Err(Fail::new(libc::EPROTO, &cause));
+        }
+        space.largest_acked = Some(space.largest_acked.map_or(largest, |acked| acked.max(largest)));
+
+        let mut outcome: AckOutcome = AckOutcome::default();
+        for range in ranges {
+            let pns: Vec<u64> = space.sent.range(range.clone()).map(|(pn, _)| *pn).collect();
+            for pn in pns {
+                outcome.acked.extend(space.sent.remove(&pn));
+            }
+        }
+        if outcome.acked.is_empty() {
+            return Ok(outcome);
+        }
+
+        // Only the largest packet number gives an RTT sample, and only if it is newly acknowledged.
+        if let Some(packet) = outcome.acked.iter().find(|packet| packet.pn == largest) {
+            let ack_delay: Duration = match level {
+                EncryptionLevel::Application if handshake_confirmed => ack_delay.min(self.max_ack_delay),
+                EncryptionLevel::Application => ack_delay,
+                _ => Duration::ZERO,
+            };
+            self.rtt
+                .update(now.saturating_duration_since(packet.time_sent), ack_delay, now);
+        }
+
+        outcome.lost = self.detect_lost(level, now);
+        self.on_packets_acked(&outcome.acked);
+        self.on_packets_lost(&outcome.lost, now);
+        self.pto_count = 0;
+        self.update_last_ack_eliciting(level);
+        Ok(outcome)
+    }
+
+    /// Gets the time at which the loss detection timer expires (RFC 9002, appendix A.8). Clients that did not learn
+    /// that the server validated their address arm the timer even if nothing is in flight, as the server may be
+    /// blocked by the anti-amplification limit.
+    pub fn timeout(
+        &self,
+        handshake_confirmed: bool,
+        peer_validated: bool,
+        has_handshake_keys: bool,
+    ) -> Option<Instant> {
+        if let Some((loss_time, _)) = self.earliest_loss_time() {
+            return Some(loss_time);
+        }
+        self.pto_time_and_space(handshake_confirmed, peer_validated, has_handshake_keys)
+            .map(|(time, _)| time)
+    }
+
+    /// Acts upon the expiry of the loss detection timer (RFC 9002, appendix A.9).
+    pub fn on_timeout(
+        &mut self,
+        handshake_confirmed: bool,
+        peer_validated: bool,
+        has_handshake_keys: bool,
+        now: Instant,
+    ) -> Option<TimeoutOutcome> {
+        if let Some((_, level)) = self.earliest_loss_time() {
+            let lost: Vec<SentPacket> = self.detect_lost(level, now);
+            self.on_packets_lost(&lost, now);
+            self.update_last_ack_eliciting(level);
+            return Some(TimeoutOutcome::Lost(level, lost));
+        }
+        let (_, level): (Instant, EncryptionLevel) =
+            self.pto_time_and_space(handshake_confirmed, peer_validated, has_handshake_keys)?;
+        self.pto_count += 1;
+        Some(TimeoutOutcome::Probe(level))
+    }
+
+    /// Forgets the packets of [level], whose keys were discarded (RFC 9002, section 6.4).
+    pub fn discard(&mut self, level: EncryptionLevel) {
+        let space: &mut SpaceRecovery = &mut self.spaces[level as usize];
+        for packet in space.sent.values() {
+            self.bytes_in_flight -= packet.size;
+        }
+        *space = SpaceRecovery::default();
+        self.pto_count = 0;
+    }
+
+    /// Deems lost the packets of [level] that are far enough behind the largest acknowledged one, and arms the time
+    /// threshold for the others (RFC 9002, appendix A.10).
+    fn detect_lost(&mut self, level: EncryptionLevel, now: Instant) -> Vec<SentPacket> {
+        let loss_delay: Duration = self.rtt.loss_delay();
+        let space: &mut SpaceRecovery = &mut self.spaces[level as usize];
+        space.loss_time = None;
+        let largest_acked: u64 = match space.largest_acked {
+            Some(largest_acked) => largest_acked,
+            None => return Vec::new(),
+        };
+        let mut lost_pns: Vec<u64> = Vec::new();
+        for (pn, packet) in space.sent.range(..=largest_acked) {
+            if packet.time_sent + loss_delay <= now || largest_acked >= pn + PACKET_THRESHOLD {
+                lost_pns.push(*pn);
+            } else {
+                let loss_time: Instant = packet.time_sent + loss_delay;
+                space.loss_time = Some(space.loss_time.map_or(loss_time, |time| time.min(loss_time)));
+            }
+        }
+        lost_pns.iter().filter_map(|pn| space.sent.remove(pn)).collect()
+    }
+
+    /// Gets the earliest time at which packets are deemed lost by the time threshold, and their space.
+    fn earliest_loss_time(&self) -> Option<(Instant, EncryptionLevel)> {
+        levels()
+            .filter_map(|level| self.spaces[level as usize].loss_time.map(|time| (time, level)))
+            .min_by_key(|(time, _)| *time)
+    }
+
+    /// Gets the time at which the probe timeout expires and the space to probe (RFC 9002, appendix A.8).
+    fn pto_time_and_space(
+        &self,
+        handshake_confirmed: bool,
+        peer_validated: bool,
+        has_handshake_keys: bool,
+    ) -> Option<(Instant, EncryptionLevel)> {
+        let backoff: u32 = 2u32.saturating_pow(self.pto_count.min(16));
+        let duration: Duration = self.rtt.pto_base() * backoff;
+        if !self.has_ack_eliciting_in_flight() {
+            if peer_validated {
+                return None;
+            }
+            // Anti-deadlock probe of a client, which is timed from the last packet that it sent.
+            let level: EncryptionLevel = if has_handshake_keys {
+                EncryptionLevel::Handshake
+            } else {
+                EncryptionLevel::Initial
+            };
+            let last_sent: Instant = self.spaces.iter().filter_map(|space| space.last_sent).max()?;
+            return Some((last_sent + duration, level));
+        }
+        let mut pto: Option<(Instant, EncryptionLevel)> = None;
+        for level in levels() {
+            let last_ack_eliciting: Instant = match self.spaces[level as usize].last_ack_eliciting {
+                Some(time) => time,
+                None => continue,
+            };
+            let time: Instant = if level == EncryptionLevel::Application {
+                // The application space is not probed before the handshake is confirmed.
+                if !handshake_confirmed {
+                    break;
+                }
+                last_ack_eliciting + duration + self.max_ack_delay * backoff
+            } else {
+                last_ack_eliciting + duration
+            };
+            if pto.map_or(true, |(earliest, _)| time < earliest) {
+                pto = Some((time, level));
+            }
+        }
+        pto
+    }
+
+    /// Forgets the time of the last ack-eliciting packet of [level] once none is in flight.
+    fn update_last_ack_eliciting(&mut self, level: EncryptionLevel) {
+        let space: &mut SpaceRecovery = &mut self.spaces[level as usize];
+        if space.sent.is_empty() {
+            space.last_ack_eliciting = None;
+        }
+    }
+
+    /// Grows the congestion window for the acknowledged [packets] (RFC 9002, appendix B.5).
+    fn on_packets_acked(&mut self, packets: &[SentPacket]) {
+        for packet in packets {
+            self.bytes_in_flight -= packet.size;
+            if self.in_recovery(packet.time_sent) {
+                continue;
+            }
+            if self.congestion_window < self.slow_start_threshold {
+                self.congestion_window += packet.size;
+            } else {
+                self.congestion_window += MAX_DATAGRAM_SIZE * packet.size / self.congestion_window;
+            }
+        }
+    }
+
+    /// Shrinks the congestion window for the lost [packets] (RFC 9002, appendices B.6 and B.8).
+    fn on_packets_lost(&mut self, packets: &[SentPacket], now: Instant) {
+        let mut earliest: Option<Instant> = None;
+        let mut latest: Option<Instant> = None;
+        for packet in packets {
+            self.bytes_in_flight -= packet.size;
+            earliest = Some(earliest.map_or(packet.time_sent, |time| time.min(packet.time_sent)));
+            latest = Some(latest.map_or(packet.time_sent, |time| time.max(packet.time_sent)));
+        }
+        let latest: Instant = match latest {
+            Some(latest) => latest,
+            None => return,
+        };
+        if !self.in_recovery(latest) {
+            self.recovery_start = Some(now);
+            self.slow_start_threshold = (self.congestion_window / 2).max(MIN_WINDOW);
+            self.congestion_window = self.slow_start_threshold;
+        }
+
+        // Losses that span several probe timeouts, all sent after the first RTT sample, point to persistent
+        // congestion. The check is simplified in that it does not look for acknowledged packets among the lost ones.
+        let persistent_duration: Duration =
+            (self.rtt.pto_base() + self.max_ack_delay) * PERSISTENT_CONGESTION_THRESHOLD;
+        if let (Some(first_sample), Some(earliest)) = (self.rtt.first_sample, earliest) {
+            if earliest > first_sample && latest.saturating_duration_since(earliest) > persistent_duration {
+                self.congestion_window = MIN_WINDOW;
+                self.recovery_start = None;
+            }
+        }
+    }
+
+    /// Checks whether a packet that was sent at [time_sent] belongs to the current recovery period.
+    fn in_recovery(&self, time_sent: Instant) -> bool {
+        self.recovery_start.map_or(false, |start| time_sent <= start)
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Default for Recovery {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Gets the initial congestion window (RFC 9002, section 7.2).
+fn initial_window() -> usize {
+    (10 * MAX_DATAGRAM_SIZE).min((2 * MAX_DATAGRAM_SIZE).max(14720))
+}
+
+/// Iterates over the encryption levels, and so over the packet number spaces, in order.
+pub fn levels() -> impl Iterator<Item = EncryptionLevel> {
+    [
+        EncryptionLevel::Initial,
+        EncryptionLevel::Handshake,
+        EncryptionLevel::Application,
+    ]
+    .into_iter()
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Recovery,
+        SentPacket,
+        TimeoutOutcome,
+    };
+    use crate::runtime::network::tls::EncryptionLevel;
+    use ::anyhow::Result;
+    use ::std::time::{
+        Duration,
+        Instant,
+    };
+
+    fn send(recovery: &mut Recovery, pn: u64, time_sent: Instant) {
+        let packet: SentPacket = SentPacket {
+            pn,
+            time_sent,
+            size: 1000,
+            ack_eliciting: true,
+            frames: Vec::new(),
+        };
+        recovery.on_packet_sent(EncryptionLevel::Application, packet);
+    }
+
+    #[test]
+    fn test_loss_detection() -> Result<()> {
+        let mut recovery: Recovery = Recovery::new();
+        let start: Instant = Instant::now();
+        for pn in 0..5 {
+            send(&mut recovery, pn, start);
+        }
+        crate::ensure_eq!(recovery.bytes_in_flight(), 5000);
+
+        // Packets 0 and 1 are three packets behind the largest acknowledged one, while packet 2 is only lost by time.
+        let now: Instant = start + Duration::from_millis(100);
+        let outcome = recovery.on_ack_received(EncryptionLevel::Application, &[3..5], Duration::ZERO, true, now)?;
+        crate::ensure_eq!(outcome.acked.len(), 2);
+        crate::ensure_eq!(outcome.lost.iter().map(|p| p.pn).collect::<Vec<u64>>(), vec![0, 1]);
+        crate::ensure_eq!(recovery.rtt().smoothed(), Duration::from_millis(100));
+        let loss_time: Instant = recovery.timeout(true, true, true).unwrap();
+        crate::ensure_eq!(loss_time, start + Duration::from_micros(112500));
+        match recovery.on_timeout(true, true, true, loss_time) {
+            Some(TimeoutOutcome::Lost(EncryptionLevel::Application, lost)) => {
+                crate::ensure_eq!(lost.iter().map(|p| p.pn).collect::<Vec<u64>>(), vec![2]);
+            },
+            _ => anyhow::bail!("packets should be lost by the time threshold"),
+        }
+        crate::ensure_eq!(recovery.bytes_in_flight(), 0);
+        crate::ensure_eq!(recovery.timeout(true, true, true), None);
+
+        // Acknowledging a packet that was never sent is an error.
+        crate::ensure_eq!(
+            recovery
+                .on_ack_received(EncryptionLevel::Application, &[9..10], Duration::ZERO, true, now)
+                .is_err(),
+            true
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_probe_timeout() -> Result<()> {
+        let mut recovery: Recovery = Recovery::new();
+        let start: Instant = Instant::now();
+        send(&mut recovery, 0, start);
+        // Before any sample, the probe timeout is three times the initial RTT plus the delay of acknowledgements.
+        let pto: Instant = recovery.timeout(true, true, true).unwrap();
+        crate::ensure_eq!(pto, start + Duration::from_millis(999 + 25));
+        match recovery.on_timeout(true, true, true, pto) {
+            Some(TimeoutOutcome::Probe(EncryptionLevel::Application)) => (),
+            _ => anyhow::bail!("probe timeout should fire"),
+        }
+        // The timeout backs off.
+        crate::ensure_eq!(
+            recovery.timeout(true, true, true),
+            Some(start + Duration::from_millis(2 * (999 + 25)))
+        );
+        Ok(())
+    }
+}
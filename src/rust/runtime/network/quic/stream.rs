@@ -0,0 +1,510 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    expect_some,
+    runtime::network::quic::{
+        ranges::RangeSet,
+        FINAL_SIZE_ERROR,
+        FLOW_CONTROL_ERROR,
+    },
+};
+use ::std::{
+    collections::{
+        BTreeMap,
+        VecDeque,
+    },
+    ops::Range,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Chunk of data that is sent in a STREAM or CRYPTO frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chunk {
+    pub offset: u64,
+    pub data: Vec<u8>,
+    pub fin: bool,
+}
+
+/// Sending side of a stream, which keeps data until the peer acknowledges it. CRYPTO frames of each encryption level
+/// are sent from one too, without a flow control limit.
+#[derive(Debug)]
+pub struct SendStream {
+    /// Data from [SendStream::base] on, which was not acknowledged as a whole yet.
+    buf: VecDeque<u8>,
+    /// Offset of the first byte of [SendStream::buf]. Everything before was acknowledged.
+    base: u64,
+    /// Offset of the first byte that was never sent.
+    next: u64,
+    /// Ranges past [SendStream::base] that were acknowledged.
+    acked: RangeSet,
+    /// Ranges that were lost and must be sent again.
+    retransmit: RangeSet,
+    /// Offset up to which the peer allows data to be sent.
+    max_data: u64,
+    /// Whether the application finished the stream.
+    fin: bool,
+    fin_sent: bool,
+    /// Whether the end of the stream must be sent again.
+    fin_lost: bool,
+    fin_acked: bool,
+    /// Error code of the RESET_STREAM frame that abandoned the stream, if any.
+    reset: Option<u64>,
+    /// Whether the RESET_STREAM frame must be sent (again).
+    reset_pending: bool,
+    reset_acked: bool,
+}
+
+/// Receiving side of a stream, which reassembles the data that arrives out of order. CRYPTO frames of each encryption
+/// level are reassembled by one too.
+#[derive(Debug)]
+pub struct RecvStream {
+    /// Data past [RecvStream::read] that arrived, by offset.
+    chunks: BTreeMap<u64, Vec<u8>>,
+    /// Offset up to which the application read the data.
+    read: u64,
+    /// Offset past the largest byte that arrived.
+    highest: u64,
+    final_size: Option<u64>,
+    /// Offset up to which the peer may send data.
+    max_data: u64,
+    /// Flow control window, which is kept ahead of the data that the application read.
+    window: u64,
+    /// Error code of the RESET_STREAM frame of the peer, if it abandoned the stream.
+    reset: Option<u64>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl SendStream {
+    /// Creates a sending side that the peer allows to send [max_data] bytes.
+    pub fn new(max_data: u64) -> Self {
+        Self {
+            buf: VecDeque::new(),
+            base: 0,
+            next: 0,
+            acked: RangeSet::new(),
+            retransmit: RangeSet::new(),
+            max_data,
+            fin: false,
+            fin_sent: false,
+            fin_lost: false,
+            fin_acked: false,
+            reset: None,
+            reset_pending: false,
+            reset_acked: false,
+        }
+    }
+
+    /// Queues [data] for the peer.
+    pub fn write(&mut self, data: &[u8]) {
+        debug_assert!(!self.fin && self.reset.is_none());
+        self.buf.extend(data);
+    }
+
+    /// Ends the stream after the data that was queued.
+    pub fn finish(&mut self) {
+        self.fin = true;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.fin
+    }
+
+    /// Checks whether the stream was abandoned.
+    pub fn is_reset(&self) -> bool {
+        self.reset.is_some()
+    }
+
+    /// Gets the offset past the data that was queued.
+    pub fn end(&self) -> u64 {
+        self.base + self.buf.len() as u64
+    }
+
+    /// Gets the offset of the first byte that was never sent.
+    pub fn sent_offset(&self) -> u64 {
+        self.next
+    }
+
+    /// Gets the number of bytes that were queued and not acknowledged yet.
+    pub fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Raises the offset up to which the peer allows data to be sent.
+    pub fn set_max_data(&mut self, max_data: u64) {
+        self.max_data = self.max_data.max(max_data);
+    }
+
+    /// Checks whether a frame must be sent, if [credit] new bytes are allowed by connection flow control.
+    pub fn has_pending(&self, credit: u64) -> bool {
+        if self.reset.is_some() {
+            return false;
+        }
+        !self.retransmit.is_empty()
+            || (self.next < self.end() && self.next < self.max_data && credit > 0)
+            || (self.fin && (!self.fin_sent || self.fin_lost) && self.next == self.end())
+    }
+
+    /// Takes the next chunk of up to [max_len] bytes to send. Lost data goes first, then new data as far as stream flow
+    /// control and the [credit] of connection flow control allow.
+    pub fn emit(&mut self, max_len: usize, credit: u64) -> Option<Chunk> {
+        if self.reset.is_some() {
+            return None;
+        }
+        if let Some(range) = self.retransmit.pop_front(max_len as u64) {
+            let fin: bool = self.fin && range.end == self.end() && !self.fin_acked;
+            if fin {
+                self.fin_lost = false;
+            }
+            return Some(Chunk {
+                offset: range.start,
+                data: self.bytes(range),
+                fin,
+            });
+        }
+        let end: u64 = self
+            .end()
+            .min(self.max_data)
+            .min(self.next.saturating_add(credit))
+            .min(self.next + max_len as u64);
+        let fin: bool = self.fin && end == self.end() && (!self.fin_sent || self.fin_lost);
+        if end == self.next && !fin {
+            return None;
+        }
+        let chunk: Chunk = Chunk {
+            offset: self.next,
+            data: self.bytes(self.next..end),
+            fin,
+        };
+        self.next = end;
+        if fin {
+            self.fin_sent = true;
+            self.fin_lost = false;
+        }
+        Some(chunk)
+    }
+
+    /// Records that the peer acknowledged [len] bytes from [offset], and the end of the stream if [fin] is set.
+    pub fn on_ack(&mut self, offset: u64, len: usize, fin: bool) {
+        // Data that was released already may be acknowledged again, if it was sent more than once.
+        let range: Range<u64> = offset.max(self.base)..(offset + len as u64);
+        if range.start < range.end {
+            self.retransmit.remove(range.clone());
+            self.acked.insert(range);
+        }
+        if fin {
+            self.fin_acked = true;
+            self.fin_lost = false;
+        }
+        // Release the data that was acknowledged up to the first gap.
+        if let Some(first) = self.acked.first() {
+            if first.start <= self.base && first.end > self.base {
+                let len: usize = (first.end - self.base) as usize;
+                self.buf.drain(..len);
+                self.base = first.end;
+                self.acked.remove(0..self.base);
+            }
+        }
+    }
+
+    /// Records that the packet with [len] bytes from [offset], and the end of the stream if [fin] is set, was lost.
+    pub fn on_loss(&mut self, offset: u64, len: usize, fin: bool) {
+        if self.reset.is_some() {
+            return;
+        }
+        let start: u64 = offset.max(self.base);
+        let end: u64 = offset + len as u64;
+        if start < end {
+            self.retransmit.insert(start..end);
+            for acked in self.acked.iter() {
+                self.retransmit.remove(acked.clone());
+            }
+        }
+        if fin && !self.fin_acked {
+            self.fin_lost = true;
+        }
+    }
+
+    /// Abandons the stream with [error_code]. Returns false if the stream was already abandoned.
+    pub fn reset(&mut self, error_code: u64) -> bool {
+        if self.reset.is_some() || self.fin_acked {
+            return false;
+        }
+        self.reset = Some(error_code);
+        self.reset_pending = true;
+        self.retransmit = RangeSet::new();
+        true
+    }
+
+    /// Takes the RESET_STREAM frame that must be sent, as the error code and the final size.
+    pub fn take_reset(&mut self) -> Option<(u64, u64)> {
+        if !self.reset_pending {
+            return None;
+        }
+        self.reset_pending = false;
+        self.reset.map(|error_code| (error_code, self.next))
+    }
+
+    /// Records that the RESET_STREAM frame was acknowledged or lost.
+    pub fn on_reset_outcome(&mut self, acked: bool) {
+        if acked {
+            self.reset_acked = true;
+        } else if !self.reset_acked {
+            self.reset_pending = true;
+        }
+    }
+
+    /// Checks whether all data and the end of the stream were acknowledged, or the stream was reset.
+    pub fn is_done(&self) -> bool {
+        self.reset_acked || (self.fin_acked && self.buf.is_empty())
+    }
+
+    /// Copies the bytes of [range], which must not have been released.
+    fn bytes(&self, range: Range<u64>) -> Vec<u8> {
+        let start: usize = (range.start - self.base) as usize;
+        let end: usize = (range.end - self.base) as usize;
+        self.buf.range(start..end).copied().collect()
+    }
+}
+
+impl RecvStream {
+    /// Creates a receiving side that lets the peer send [window] bytes ahead of the application.
+    pub fn new(window: u64) -> Self {
+        Self {
+            chunks: BTreeMap::new(),
+            read: 0,
+            highest: 0,
+            final_size: None,
+            max_data: window,
+            window,
+            reset: None,
+        }
+    }
+
+    /// Stores [data] at [offset], which ends the stream if [fin] is set. Returns how far the largest offset grew, which
+    /// counts against connection flow control, or the transport error code of the violation.
+    pub fn receive(&mut self, offset: u64, data: &[u8], fin: bool) -> Result<u64, u64> {
+        let end: u64 = offset + data.len() as u64;
+        let grown: u64 = self.check_final_size(end, fin)?;
+        if self.reset.is_some() || end <= self.read {
+            return Ok(grown);
+        }
+        // Keep only what the application did not read yet, and do not replace a longer chunk at the same offset.
+        let skip: usize = self.read.saturating_sub(offset) as usize;
+        let start: u64 = offset + skip as u64;
+        if self
+            .chunks
+            .get(&start)
+            .map_or(true, |chunk| chunk.len() < data.len() - skip)
+        {
+            self.chunks.insert(start, data[skip..].to_vec());
+        }
+        Ok(grown)
+    }
+
+    /// Abandons the stream on a RESET_STREAM frame of the peer with [error_code] and [final_size]. Returns how far the
+    /// largest offset grew or the transport error code of the violation.
+    pub fn reset(&mut self, error_code: u64, final_size: u64) -> Result<u64, u64> {
+        let grown: u64 = self.check_final_size(final_size, true)?;
+        if self.reset.is_none() {
+            self.reset = Some(error_code);
+            self.chunks.clear();
+        }
+        Ok(grown)
+    }
+
+    /// Gets the error code that the peer abandoned the stream with.
+    pub fn reset_code(&self) -> Option<u64> {
+        self.reset
+    }
+
+    /// Takes up to [max_len] bytes of contiguous data for the application.
+    pub fn read(&mut self, max_len: usize) -> Option<Vec<u8>> {
+        let mut out: Vec<u8> = Vec::new();
+        while out.len() < max_len {
+            let offset: u64 = match self.chunks.first_key_value() {
+                Some((offset, _)) if *offset <= self.read => *offset,
+                _ => break,
+            };
+            let chunk: Vec<u8> = expect_some!(self.chunks.remove(&offset), "chunk was just found");
+            let chunk_end: u64 = offset + chunk.len() as u64;
+            if chunk_end <= self.read {
+                continue;
+            }
+            let start: usize = (self.read - offset) as usize;
+            let len: usize = (chunk.len() - start).min(max_len - out.len());
+            out.extend_from_slice(&chunk[start..(start + len)]);
+            self.read += len as u64;
+            if self.read < chunk_end {
+                self.chunks.insert(self.read, chunk[(start + len)..].to_vec());
+            }
+        }
+        if out.is_empty() {
+            None
+        } else {
+            Some(out)
+        }
+    }
+
+    /// Checks whether the application read everything up to the end of the stream.
+    pub fn is_finished(&self) -> bool {
+        self.final_size == Some(self.read)
+    }
+
+    /// Gets the offset up to which the application read the data.
+    pub fn read_offset(&self) -> u64 {
+        self.read
+    }
+
+    /// Raises the flow control limit once the application read half of the window, and returns the new limit that
+    /// must be announced in a MAX_STREAM_DATA frame.
+    pub fn update_max_data(&mut self) -> Option<u64> {
+        if self.final_size.is_some() || self.reset.is_some() {
+            return None;
+        }
+        if self.max_data - self.read < self.window / 2 {
+            self.max_data = self.read + self.window;
+            return Some(self.max_data);
+        }
+        None
+    }
+
+    /// Gets the offset up to which the peer may send data.
+    pub fn max_data(&self) -> u64 {
+        self.max_data
+    }
+
+    /// Checks data that ends at [end] against the final size and flow control, and records the final size if [fin] is
+    /// set (RFC 9000, section 4.5). Returns how far the largest offset grew.
+    fn check_final_size(&mut self, end: u64, fin: bool) -> Result<u64, u64> {
+        if let Some(final_size) = self.final_size {
+            if end > final_size || (fin && end != final_size) {
+                warn!(
+                    "check_final_size(): final size changed (end={:?}, final_size={:?})",
+                    end, final_size
+                );
+                return Err(FINAL_SIZE_ERROR);
+            }
+        }
+        if fin {
+            if end < self.highest {
+                warn!("check_final_size(): data past the final size (final_size={:?})", end);
+                return Err(FINAL_SIZE_ERROR);
+            }
+            self.final_size = Some(end);
+        }
+        if end > self.max_data {
+            warn!(
+                "check_final_size(): flow control limit exceeded (end={:?}, max_data={:?})",
+                end, self.max_data
+            );
+            return Err(FLOW_CONTROL_ERROR);
+        }
+        let grown: u64 = end.saturating_sub(self.highest);
+        self.highest = self.highest.max(end);
+        Ok(grown)
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Chunk,
+        RecvStream,
+        SendStream,
+    };
+    use crate::runtime::network::quic::{
+        FINAL_SIZE_ERROR,
+        FLOW_CONTROL_ERROR,
+    };
+    use ::anyhow::Result;
+
+    #[test]
+    fn test_send_stream_retransmits_lost_data() -> Result<()> {
+        let mut stream: SendStream = SendStream::new(8);
+        stream.write(b"0123456789");
+        stream.finish();
+        // Stream flow control holds back the last two bytes.
+        crate::ensure_eq!(
+            stream.emit(5, u64::MAX),
+            Some(Chunk {
+                offset: 0,
+                data: b"01234".to_vec(),
+                fin: false
+            })
+        );
+        crate::ensure_eq!(
+            stream.emit(5, u64::MAX),
+            Some(Chunk {
+                offset: 5,
+                data: b"567".to_vec(),
+                fin: false
+            })
+        );
+        crate::ensure_eq!(stream.emit(5, u64::MAX), None);
+        stream.set_max_data(100);
+        crate::ensure_eq!(
+            stream.emit(5, u64::MAX),
+            Some(Chunk {
+                offset: 8,
+                data: b"89".to_vec(),
+                fin: true
+            })
+        );
+
+        // The first chunk is lost and goes out again, while the others are acknowledged.
+        stream.on_loss(0, 5, false);
+        stream.on_ack(5, 3, false);
+        stream.on_ack(8, 2, true);
+        crate::ensure_eq!(stream.is_done(), false);
+        crate::ensure_eq!(
+            stream.emit(100, u64::MAX),
+            Some(Chunk {
+                offset: 0,
+                data: b"01234".to_vec(),
+                fin: false
+            })
+        );
+        stream.on_ack(0, 5, false);
+        crate::ensure_eq!(stream.buffered(), 0);
+        crate::ensure_eq!(stream.is_done(), true);
+        Ok(())
+    }
+
+    #[test]
+    fn test_recv_stream_reassembles_data() -> Result<()> {
+        let mut stream: RecvStream = RecvStream::new(10);
+        crate::ensure_eq!(stream.receive(4, b"4567", true), Ok(8));
+        crate::ensure_eq!(stream.read(100), None);
+        crate::ensure_eq!(stream.receive(0, b"0123", false), Ok(0));
+        crate::ensure_eq!(stream.receive(2, b"23", false), Ok(0));
+        crate::ensure_eq!(stream.read(3), Some(b"012".to_vec()));
+        crate::ensure_eq!(stream.read(100), Some(b"34567".to_vec()));
+        crate::ensure_eq!(stream.is_finished(), true);
+
+        // The final size cannot change.
+        crate::ensure_eq!(stream.receive(6, b"6789", false), Err(FINAL_SIZE_ERROR));
+
+        let mut stream: RecvStream = RecvStream::new(10);
+        crate::ensure_eq!(stream.receive(8, b"890", false), Err(FLOW_CONTROL_ERROR));
+        crate::ensure_eq!(stream.receive(0, b"01234567", false), Ok(8));
+        crate::ensure_eq!(stream.update_max_data(), None);
+        stream.read(100);
+        crate::ensure_eq!(stream.update_max_data(), Some(18));
+        Ok(())
+    }
+}
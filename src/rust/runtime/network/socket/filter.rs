@@ -0,0 +1,425 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    expect_ok,
+    runtime::{
+        fail::Fail,
+        memory::DemiBuffer,
+    },
+};
+use ::std::rc::Rc;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Largest number of instructions in a filter program.
+pub const BPF_MAXINSNS: usize = 4096;
+
+/// Number of words of scratch memory that are available to a filter program.
+const BPF_MEMWORDS: u32 = 16;
+
+// Instruction classes.
+const BPF_LD: u16 = 0x00;
+const BPF_LDX: u16 = 0x01;
+const BPF_ST: u16 = 0x02;
+const BPF_STX: u16 = 0x03;
+const BPF_ALU: u16 = 0x04;
+const BPF_JMP: u16 = 0x05;
+const BPF_RET: u16 = 0x06;
+const BPF_MISC: u16 = 0x07;
+
+// Sizes of loads.
+const BPF_W: u16 = 0x00;
+const BPF_H: u16 = 0x08;
+const BPF_B: u16 = 0x10;
+
+// Addressing modes of loads.
+const BPF_IMM: u16 = 0x00;
+const BPF_ABS: u16 = 0x20;
+const BPF_IND: u16 = 0x40;
+const BPF_MEM: u16 = 0x60;
+const BPF_LEN: u16 = 0x80;
+const BPF_MSH: u16 = 0xa0;
+
+// Arithmetic operations.
+const BPF_ADD: u16 = 0x00;
+const BPF_SUB: u16 = 0x10;
+const BPF_MUL: u16 = 0x20;
+const BPF_DIV: u16 = 0x30;
+const BPF_OR: u16 = 0x40;
+const BPF_AND: u16 = 0x50;
+const BPF_LSH: u16 = 0x60;
+const BPF_RSH: u16 = 0x70;
+const BPF_NEG: u16 = 0x80;
+const BPF_MOD: u16 = 0x90;
+const BPF_XOR: u16 = 0xa0;
+
+// Jumps.
+const BPF_JA: u16 = 0x00;
+const BPF_JEQ: u16 = 0x10;
+const BPF_JGT: u16 = 0x20;
+const BPF_JGE: u16 = 0x30;
+const BPF_JSET: u16 = 0x40;
+
+// Operand sources.
+const BPF_K: u16 = 0x00;
+const BPF_X: u16 = 0x08;
+
+// Return values.
+const BPF_A: u16 = 0x10;
+
+// Register transfers.
+const BPF_TAX: u16 = 0x00;
+const BPF_TXA: u16 = 0x80;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Instruction of a classic BPF program. The layout matches the one of `struct sock_filter`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BpfInstruction {
+    pub code: u16,
+    pub jt: u8,
+    pub jf: u8,
+    pub k: u32,
+}
+
+/// Receive filter that may be attached to a socket. The filter runs a classic BPF program on every packet before it
+/// is queued on the socket. The value that is returned by the program is the number of bytes of the packet to keep,
+/// so packets for which the program returns zero are dropped.
+#[derive(Clone, Debug)]
+pub struct SocketFilter {
+    program: Rc<[BpfInstruction]>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl BpfInstruction {
+    /// Creates an instruction that does not branch.
+    pub const fn stmt(code: u16, k: u32) -> Self {
+        Self { code, jt: 0, jf: 0, k }
+    }
+
+    /// Creates a conditional jump instruction.
+    pub const fn jump(code: u16, k: u32, jt: u8, jf: u8) -> Self {
+        Self { code, jt, jf, k }
+    }
+
+    /// Checks if this instruction is a valid instruction of classic BPF.
+    fn is_valid(&self) -> bool {
+        let code: u16 = self.code;
+        match code & 0x07 {
+            BPF_LD => match code & 0xe0 {
+                BPF_ABS | BPF_IND => matches!(code & 0x18, BPF_W | BPF_H | BPF_B),
+                BPF_IMM | BPF_LEN => code & 0x18 == BPF_W,
+                BPF_MEM => code & 0x18 == BPF_W && self.k < BPF_MEMWORDS,
+                _ => false,
+            },
+            BPF_LDX => match code {
+                c if c == BPF_LDX | BPF_W | BPF_IMM || c == BPF_LDX | BPF_W | BPF_LEN => true,
+                c if c == BPF_LDX | BPF_B | BPF_MSH => true,
+                c if c == BPF_LDX | BPF_W | BPF_MEM => self.k < BPF_MEMWORDS,
+                _ => false,
+            },
+            BPF_ST | BPF_STX => code & 0xf8 == 0 && self.k < BPF_MEMWORDS,
+            BPF_ALU => match code & 0xf0 {
+                BPF_NEG => code & 0x08 == BPF_K,
+                BPF_DIV | BPF_MOD => code & 0x08 == BPF_X || self.k != 0,
+                op => op <= BPF_XOR,
+            },
+            BPF_JMP => match code & 0xf0 {
+                BPF_JA => code & 0x08 == BPF_K,
+                BPF_JEQ | BPF_JGT | BPF_JGE | BPF_JSET => true,
+                _ => false,
+            },
+            BPF_RET => code == BPF_RET | BPF_K || code == BPF_RET | BPF_A,
+            BPF_MISC => code == BPF_MISC | BPF_TAX || code == BPF_MISC | BPF_TXA,
+            _ => unreachable!("instruction classes take three bits"),
+        }
+    }
+}
+
+impl SocketFilter {
+    /// Creates a filter that runs [program]. The program is checked up front, so that running it never fails: every
+    /// instruction must be valid, jumps may only go forward and within the program, and the last instruction must
+    /// return.
+    pub fn new(program: &[BpfInstruction]) -> Result<Self, Fail> {
+        if program.is_empty() || program.len() > BPF_MAXINSNS {
+            let cause: String = format!("invalid filter program length (len={:?})", program.len());
+            error!("new(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+
+        for (pc, insn) in program.iter().enumerate() {
+            if !insn.is_valid() {
+                let cause: String = format!("invalid filter instruction (pc={:?}, insn={:?})", pc, insn);
+                error!("new(): {}", cause);
+                return Err(Fail::new(libc::EINVAL, &cause));
+            }
+            if insn.code & 0x07 == BPF_JMP {
+                let next: usize = pc + 1;
+                let in_range = |offset: usize| next + offset < program.len();
+                let valid: bool = match insn.code & 0xf0 {
+                    BPF_JA => in_range(insn.k as usize),
+                    _ => in_range(insn.jt as usize) && in_range(insn.jf as usize),
+                };
+                if !valid {
+                    let cause: String = format!("filter jumps out of the program (pc={:?}, insn={:?})", pc, insn);
+                    error!("new(): {}", cause);
+                    return Err(Fail::new(libc::EINVAL, &cause));
+                }
+            }
+        }
+
+        if program[program.len() - 1].code & 0x07 != BPF_RET {
+            let cause: &str = "filter program does not end with a return";
+            error!("new(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, cause));
+        }
+
+        Ok(Self {
+            program: Rc::from(program),
+        })
+    }
+
+    /// Gets the program that is run by this filter.
+    pub fn program(&self) -> &[BpfInstruction] {
+        &self.program
+    }
+
+    /// Runs this filter on [packet]. Returns the number of bytes of the packet to keep. Loads beyond the end of the
+    /// packet and divisions by zero stop the program and drop the packet.
+    pub fn run(&self, packet: &[u8]) -> usize {
+        let len: u32 = packet.len() as u32;
+        let mut a: u32 = 0;
+        let mut x: u32 = 0;
+        let mut mem: [u32; BPF_MEMWORDS as usize] = [0; BPF_MEMWORDS as usize];
+        let mut pc: usize = 0;
+
+        loop {
+            let insn: BpfInstruction = self.program[pc];
+            let k: u32 = insn.k;
+            pc += 1;
+            match insn.code & 0x07 {
+                BPF_LD => {
+                    a = match insn.code & 0xe0 {
+                        BPF_IMM => k,
+                        BPF_ABS => match load(packet, k, insn.code & 0x18) {
+                            Some(value) => value,
+                            None => return 0,
+                        },
+                        BPF_IND => match load(packet, x.wrapping_add(k), insn.code & 0x18) {
+                            Some(value) => value,
+                            None => return 0,
+                        },
+                        BPF_MEM => mem[k as usize],
+                        _ => len,
+                    }
+                },
+                BPF_LDX => {
+                    x = match insn.code & 0xe0 {
+                        BPF_IMM => k,
+                        BPF_MEM => mem[k as usize],
+                        BPF_MSH => match load(packet, k, BPF_B) {
+                            Some(value) => (value & 0x0f) << 2,
+                            None => return 0,
+                        },
+                        _ => len,
+                    }
+                },
+                BPF_ST => mem[k as usize] = a,
+                BPF_STX => mem[k as usize] = x,
+                BPF_ALU => {
+                    let operand: u32 = if insn.code & 0x08 == BPF_X { x } else { k };
+                    a = match insn.code & 0xf0 {
+                        BPF_ADD => a.wrapping_add(operand),
+                        BPF_SUB => a.wrapping_sub(operand),
+                        BPF_MUL => a.wrapping_mul(operand),
+                        BPF_DIV => match a.checked_div(operand) {
+                            Some(value) => value,
+                            None => return 0,
+                        },
+                        BPF_MOD => match a.checked_rem(operand) {
+                            Some(value) => value,
+                            None => return 0,
+                        },
+                        BPF_OR => a | operand,
+                        BPF_AND => a & operand,
+                        BPF_LSH => a.checked_shl(operand).unwrap_or(0),
+                        BPF_RSH => a.checked_shr(operand).unwrap_or(0),
+                        BPF_NEG => a.wrapping_neg(),
+                        _ => a ^ operand,
+                    }
+                },
+                BPF_JMP => {
+                    let operand: u32 = if insn.code & 0x08 == BPF_X { x } else { k };
+                    let taken: bool = match insn.code & 0xf0 {
+                        BPF_JA => {
+                            pc += k as usize;
+                            continue;
+                        },
+                        BPF_JEQ => a == operand,
+                        BPF_JGT => a > operand,
+                        BPF_JGE => a >= operand,
+                        _ => a & operand != 0,
+                    };
+                    pc += if taken { insn.jt as usize } else { insn.jf as usize };
+                },
+                BPF_RET => {
+                    let value: u32 = if insn.code & 0x18 == BPF_A { a } else { k };
+                    return value.min(len) as usize;
+                },
+                _ => {
+                    if insn.code & 0xf8 == BPF_TXA {
+                        a = x;
+                    } else {
+                        x = a;
+                    }
+                },
+            }
+        }
+    }
+
+    /// Runs this filter on [buf], trimming it to the number of bytes that the program keeps. Returns false if the
+    /// packet should be dropped.
+    pub fn apply(&self, buf: &mut DemiBuffer) -> bool {
+        let keep: usize = self.run(&buf[..]);
+        if keep == 0 {
+            return false;
+        }
+        if keep < buf.len() {
+            expect_ok!(buf.trim(buf.len() - keep), "cannot trim more bytes than the buffer has");
+        }
+        true
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Loads a big endian value of [size] from [packet] at [offset].
+fn load(packet: &[u8], offset: u32, size: u16) -> Option<u32> {
+    let width: usize = match size {
+        BPF_W => 4,
+        BPF_H => 2,
+        _ => 1,
+    };
+    let start: usize = offset as usize;
+    let bytes: &[u8] = packet.get(start..start.checked_add(width)?)?;
+    Some(bytes.iter().fold(0, |value, byte| (value << 8) | *byte as u32))
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BpfInstruction,
+        SocketFilter,
+    };
+    use crate::runtime::memory::DemiBuffer;
+    use ::anyhow::Result;
+
+    /// Program that accepts packets whose first byte is [value], keeping at most four bytes of them.
+    fn first_byte_is(value: u8) -> Vec<BpfInstruction> {
+        vec![
+            // ldb [0]
+            BpfInstruction::stmt(0x30, 0),
+            // jeq #value, accept, drop
+            BpfInstruction::jump(0x15, value as u32, 0, 1),
+            // ret #4
+            BpfInstruction::stmt(0x06, 4),
+            // ret #0
+            BpfInstruction::stmt(0x06, 0),
+        ]
+    }
+
+    /// Tests that programs are checked before they are accepted.
+    #[test]
+    fn test_socket_filter_validation() -> Result<()> {
+        crate::ensure_eq!(SocketFilter::new(&[]).is_err(), true);
+        // Does not end with a return.
+        crate::ensure_eq!(SocketFilter::new(&[BpfInstruction::stmt(0x00, 1)]).is_err(), true);
+        // Jumps past the end.
+        crate::ensure_eq!(
+            SocketFilter::new(&[BpfInstruction::jump(0x15, 0, 1, 0), BpfInstruction::stmt(0x06, 0)]).is_err(),
+            true
+        );
+        // Divides by a constant zero.
+        crate::ensure_eq!(
+            SocketFilter::new(&[BpfInstruction::stmt(0x34, 0), BpfInstruction::stmt(0x06, 0)]).is_err(),
+            true
+        );
+        // Stores out of the scratch memory.
+        crate::ensure_eq!(
+            SocketFilter::new(&[BpfInstruction::stmt(0x02, 16), BpfInstruction::stmt(0x06, 0)]).is_err(),
+            true
+        );
+        crate::ensure_eq!(SocketFilter::new(&first_byte_is(1)).is_ok(), true);
+
+        Ok(())
+    }
+
+    /// Tests that filters accept, truncate, and drop packets.
+    #[test]
+    fn test_socket_filter_run() -> Result<()> {
+        let filter: SocketFilter = SocketFilter::new(&first_byte_is(1))?;
+        crate::ensure_eq!(filter.run(&[1, 2, 3, 4, 5, 6]), 4);
+        crate::ensure_eq!(filter.run(&[1, 2]), 2);
+        crate::ensure_eq!(filter.run(&[2, 2, 3, 4]), 0);
+        crate::ensure_eq!(filter.run(&[]), 0);
+
+        let mut buf: DemiBuffer = DemiBuffer::from_slice(&[1, 2, 3, 4, 5, 6])?;
+        crate::ensure_eq!(filter.apply(&mut buf), true);
+        crate::ensure_eq!(&buf[..], &[1, 2, 3, 4]);
+        let mut buf: DemiBuffer = DemiBuffer::from_slice(&[2, 2, 3, 4, 5, 6])?;
+        crate::ensure_eq!(filter.apply(&mut buf), false);
+
+        Ok(())
+    }
+
+    /// Tests indirect loads, scratch memory, and arithmetic.
+    #[test]
+    fn test_socket_filter_arithmetic() -> Result<()> {
+        let program: Vec<BpfInstruction> = vec![
+            // ldxb 4*([0]&0xf)
+            BpfInstruction::stmt(0xb1, 0),
+            // ldh [x+0]
+            BpfInstruction::stmt(0x48, 0),
+            // st M[3]
+            BpfInstruction::stmt(0x02, 3),
+            // ld M[3]
+            BpfInstruction::stmt(0x60, 3),
+            // mul #2
+            BpfInstruction::stmt(0x24, 2),
+            // ret a
+            BpfInstruction::stmt(0x16, 0),
+        ];
+        let filter: SocketFilter = SocketFilter::new(&program)?;
+        let packet: [u8; 64] = {
+            let mut packet: [u8; 64] = [0; 64];
+            packet[0] = 0x45;
+            packet[20] = 0x00;
+            packet[21] = 0x0a;
+            packet
+        };
+        crate::ensure_eq!(filter.run(&packet), 20);
+        // The indirect load goes beyond the end of the packet.
+        crate::ensure_eq!(filter.run(&packet[..21]), 0);
+
+        Ok(())
+    }
+}
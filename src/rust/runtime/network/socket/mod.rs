@@ -5,7 +5,10 @@
 // Exports
 //======================================================================================================================
 
+pub mod filter;
 pub mod operation;
+pub mod option;
+pub mod shaper;
 pub mod state;
 
 //======================================================================================================================
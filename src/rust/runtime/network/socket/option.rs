@@ -0,0 +1,668 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    network::{
+        consts::DEFAULT_IPV4_TTL,
+        timestamp::SOF_TIMESTAMPING_MASK,
+    },
+};
+use ::std::{
+    fmt,
+    net::Ipv4Addr,
+    time::Duration,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Largest priority that may be assigned to a socket. Priorities map onto IEEE 802.1Q priority code points.
+const MAX_SOCKET_PRIORITY: u8 = 7;
+
+/// Largest key of the TCP MD5 signature option, like on Linux.
+pub const TCP_MD5_MAX_KEY_SIZE: usize = 80;
+
+/// Largest master key of the TCP authentication option, like on Linux.
+pub const TCP_AO_MAX_KEY_SIZE: usize = 80;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Socket options that may be set and queried on a socket. Values are ignored when querying an option.
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SocketOption {
+    /// Type of service byte of outgoing IPv4 packets, that is, the DSCP in the upper six bits and the ECN field in
+    /// the lower two bits.
+    IP_TOS(u8),
+    /// Time to live of outgoing IPv4 packets. Zero is not a valid time to live.
+    IP_TTL(u8),
+    /// Priority of outgoing packets. When the socket sends VLAN tagged frames, this is their priority code point.
+    SO_PRIORITY(u8),
+    /// Largest rate at which the socket sends, in bytes of whole frames per second. [u64::MAX] lifts the limit.
+    SO_MAX_PACING_RATE(u64),
+    /// Whether the socket queues a record of each incoming packet that is dropped on its way to it, so that the
+    /// application can learn why.
+    IP_RECVERR(bool),
+    /// Whether outgoing UDP datagrams are sent without a checksum, which is only safe on fabrics that are trusted to not
+    /// corrupt them. Checksums of incoming datagrams are verified either way.
+    SO_NO_CHECK(bool),
+    /// Size of the UDP datagrams that larger payloads are split into when they are pushed, in bytes of payload, so that
+    /// one push sends a batch of datagrams. Zero sends each payload as a single datagram.
+    UDP_SEGMENT(u16),
+    /// Initial congestion window of TCP connections, in segments. Zero falls back to the configuration of the stack.
+    TCP_INIT_CWND(u32),
+    /// Retransmission timeout of TCP connections before any round-trip time is measured, in microseconds. Zero falls
+    /// back to the configuration of the stack.
+    TCP_RTO_INIT_US(u32),
+    /// Lower bound for the retransmission timeout of TCP connections, in microseconds. Zero falls back to the
+    /// configuration of the stack.
+    TCP_RTO_MIN_US(u32),
+    /// Upper bound for the retransmission timeout of TCP connections, in milliseconds. Zero falls back to the
+    /// configuration of the stack.
+    TCP_RTO_MAX_MS(u32),
+    /// Longest time that data which was sent on a TCP connection may stay unacknowledged, in milliseconds, before the
+    /// connection is aborted and its pending operations fail with ETIMEDOUT. This holds however often the data is
+    /// retransmitted, and also while our peer advertises a zero window. Zero never aborts connections.
+    TCP_USER_TIMEOUT(u32),
+    /// Which timestamps of outgoing packets the socket queues, as a combination of the SOF_TIMESTAMPING_TX_* flags.
+    /// Incoming packets are always stamped.
+    SO_TIMESTAMPING(u32),
+    /// Interface that outgoing packets leave through, whatever the routing table says, counting from one for the
+    /// primary interface. Zero lets routes pick the interface again.
+    SO_BINDTODEVICE(u32),
+    /// Whether pushes complete only once their data is delivered, rather than once it is accepted, so that the
+    /// application can pace itself on the bytes that really left. Pushes to TCP sockets then complete once our peer
+    /// acknowledges all of their data. Other sockets hand their datagrams to the network before their pushes complete
+    /// anyway.
+    SO_TXCOMPLETE(bool),
+    /// Whether pops on a memory queue sleep on a doorbell until the peer pushes data, instead of busy polling. This
+    /// overrides the configuration of the memory queue. Network sockets never sleep on a doorbell.
+    SO_DOORBELL(bool),
+    /// Key that TCP segments exchanged with some peers are signed with (RFC 2385). Each key replaces the one of the
+    /// same peers, and empty keys remove it. Keys cannot be read back.
+    TCP_MD5SIG(TcpMd5Key),
+    /// Master key tuple of the TCP authentication option (RFC 5925) that is added to the keys of the socket. Peers
+    /// that share several keys may rotate from one to another without disrupting their connections. Keys cannot be
+    /// read back.
+    TCP_AO_ADD_KEY(TcpAoKey),
+    /// Master key tuple of the TCP authentication option that is removed from the keys of the socket. The key with the
+    /// same peers and identifiers is removed, whatever its bytes. The current key of a connection cannot be removed.
+    TCP_AO_DEL_KEY(TcpAoKey),
+    /// Keys that a connection that is authenticated with the TCP authentication option uses. Setting this option
+    /// switches keys on the connection.
+    TCP_AO_INFO(TcpAoInfo),
+}
+
+/// MAC algorithms of the TCP authentication option. Both truncate their MACs to 96 bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TcpAoAlgorithm {
+    /// AES-128-CMAC-96 (RFC 5926), which every implementation supports.
+    Aes128Cmac,
+    /// HMAC-SHA-256, truncated to 96 bits, which Linux also supports.
+    HmacSha256,
+}
+
+/// Master key tuple of the TCP authentication option, which is shared with the peers whose address starts with the
+/// same [prefix_len] bits as [addr]. Segments that are signed with the key carry [send_id] as their key identifier,
+/// and those that our peers sign with it carry [recv_id].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct TcpAoKey {
+    addr: Ipv4Addr,
+    prefix_len: u8,
+    send_id: u8,
+    recv_id: u8,
+    algorithm: TcpAoAlgorithm,
+    len: u8,
+    key: [u8; TCP_AO_MAX_KEY_SIZE],
+}
+
+/// Keys that a connection that is authenticated with the TCP authentication option uses, by their identifiers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TcpAoInfo {
+    /// Send identifier of the key that outgoing segments are signed with.
+    pub current_key: u8,
+    /// Receive identifier of the key that our peer is asked to sign its segments with.
+    pub rnext_key: u8,
+    /// Send identifier of the key that our peer last asked us to sign our segments with. This is ignored when
+    /// switching keys.
+    pub peer_rnext_key: u8,
+}
+
+/// Key of the TCP MD5 signature option, which is shared with the peers whose address starts with the same
+/// [prefix_len] bits as [addr].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct TcpMd5Key {
+    addr: Ipv4Addr,
+    prefix_len: u8,
+    len: u8,
+    key: [u8; TCP_MD5_MAX_KEY_SIZE],
+}
+
+/// Options of a socket, with the values that are used when building the headers of outgoing packets.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SocketOptions {
+    tos: u8,
+    ttl: Option<u8>,
+    priority: Option<u8>,
+    max_pacing_rate: Option<u64>,
+    recv_err: bool,
+    no_check: Option<bool>,
+    segment_size: Option<u16>,
+    init_cwnd: Option<u32>,
+    rto_initial: Option<Duration>,
+    rto_min: Option<Duration>,
+    rto_max: Option<Duration>,
+    user_timeout: Option<Duration>,
+    timestamping: u32,
+    device: Option<usize>,
+    tx_complete: bool,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl SocketOptions {
+    /// Sets [option] on this socket.
+    pub fn set(&mut self, option: SocketOption) -> Result<(), Fail> {
+        match option {
+            SocketOption::IP_TOS(tos) => self.tos = tos,
+            SocketOption::IP_TTL(ttl) => {
+                if ttl == 0 {
+                    let cause: String = format!("invalid time to live (ttl={:?})", ttl);
+                    error!("set(): {}", cause);
+                    return Err(Fail::new(libc::EINVAL, &cause));
+                }
+                self.ttl = Some(ttl);
+            },
+            SocketOption::SO_PRIORITY(priority) => {
+                if priority > MAX_SOCKET_PRIORITY {
+                    let cause: String = format!("invalid socket priority (priority={:?})", priority);
+                    error!("set(): {}", cause);
+                    return Err(Fail::new(libc::EINVAL, &cause));
+                }
+                self.priority = Some(priority);
+            },
+            SocketOption::SO_MAX_PACING_RATE(rate) => {
+                if rate == 0 {
+                    let cause: String = format!("invalid pacing rate (rate={:?})", rate);
+                    error!("set(): {}", cause);
+                    return Err(Fail::new(libc::EINVAL, &cause));
+                }
+                self.max_pacing_rate = if rate == u64::MAX { None } else { Some(rate) };
+            },
+            SocketOption::IP_RECVERR(recv_err) => self.recv_err = recv_err,
+            SocketOption::SO_NO_CHECK(no_check) => self.no_check = Some(no_check),
+            SocketOption::UDP_SEGMENT(size) => self.segment_size = Some(size).filter(|size| *size != 0),
+            SocketOption::TCP_INIT_CWND(segments) => self.init_cwnd = Some(segments).filter(|n| *n != 0),
+            SocketOption::TCP_RTO_INIT_US(micros) => {
+                self.rto_initial = Some(Duration::from_micros(micros as u64)).filter(|d| !d.is_zero())
+            },
+            SocketOption::TCP_RTO_MIN_US(micros) => {
+                self.rto_min = Some(Duration::from_micros(micros as u64)).filter(|d| !d.is_zero())
+            },
+            SocketOption::TCP_RTO_MAX_MS(millis) => {
+                self.rto_max = Some(Duration::from_millis(millis as u64)).filter(|d| !d.is_zero())
+            },
+            SocketOption::TCP_USER_TIMEOUT(millis) => {
+                self.user_timeout = Some(Duration::from_millis(millis as u64)).filter(|d| !d.is_zero())
+            },
+            SocketOption::SO_TIMESTAMPING(flags) => {
+                if flags & !SOF_TIMESTAMPING_MASK != 0 {
+                    let cause: String = format!("invalid timestamping flags (flags={:#x})", flags);
+                    error!("set(): {}", cause);
+                    return Err(Fail::new(libc::EINVAL, &cause));
+                }
+                self.timestamping = flags;
+            },
+            SocketOption::SO_BINDTODEVICE(index) => self.device = index.checked_sub(1).map(|device| device as usize),
+            SocketOption::SO_TXCOMPLETE(tx_complete) => self.tx_complete = tx_complete,
+            SocketOption::SO_DOORBELL(_) => {
+                let cause: &str = "doorbells are kept by memory queues";
+                error!("set(): {}", cause);
+                return Err(Fail::new(libc::ENOPROTOOPT, cause));
+            },
+            SocketOption::TCP_MD5SIG(_)
+            | SocketOption::TCP_AO_ADD_KEY(_)
+            | SocketOption::TCP_AO_DEL_KEY(_)
+            | SocketOption::TCP_AO_INFO(_) => {
+                let cause: &str = "signature keys are kept by TCP sockets";
+                error!("set(): {}", cause);
+                return Err(Fail::new(libc::ENOPROTOOPT, cause));
+            },
+        }
+        Ok(())
+    }
+
+    /// Gets the current value of [option] on this socket.
+    pub fn get(&self, option: SocketOption) -> SocketOption {
+        match option {
+            SocketOption::IP_TOS(_) => SocketOption::IP_TOS(self.tos),
+            SocketOption::IP_TTL(_) => SocketOption::IP_TTL(self.ttl()),
+            SocketOption::SO_PRIORITY(_) => SocketOption::SO_PRIORITY(self.priority.unwrap_or(0)),
+            SocketOption::SO_MAX_PACING_RATE(_) => {
+                SocketOption::SO_MAX_PACING_RATE(self.max_pacing_rate.unwrap_or(u64::MAX))
+            },
+            SocketOption::IP_RECVERR(_) => SocketOption::IP_RECVERR(self.recv_err),
+            SocketOption::SO_NO_CHECK(_) => SocketOption::SO_NO_CHECK(self.no_check.unwrap_or(false)),
+            SocketOption::UDP_SEGMENT(_) => SocketOption::UDP_SEGMENT(self.segment_size.unwrap_or(0)),
+            SocketOption::TCP_INIT_CWND(_) => SocketOption::TCP_INIT_CWND(self.init_cwnd.unwrap_or(0)),
+            SocketOption::TCP_RTO_INIT_US(_) => {
+                SocketOption::TCP_RTO_INIT_US(self.rto_initial.map_or(0, |d| d.as_micros() as u32))
+            },
+            SocketOption::TCP_RTO_MIN_US(_) => {
+                SocketOption::TCP_RTO_MIN_US(self.rto_min.map_or(0, |d| d.as_micros() as u32))
+            },
+            SocketOption::TCP_RTO_MAX_MS(_) => {
+                SocketOption::TCP_RTO_MAX_MS(self.rto_max.map_or(0, |d| d.as_millis() as u32))
+            },
+            SocketOption::TCP_USER_TIMEOUT(_) => {
+                SocketOption::TCP_USER_TIMEOUT(self.user_timeout.map_or(0, |d| d.as_millis() as u32))
+            },
+            SocketOption::SO_TIMESTAMPING(_) => SocketOption::SO_TIMESTAMPING(self.timestamping),
+            SocketOption::SO_BINDTODEVICE(_) => {
+                SocketOption::SO_BINDTODEVICE(self.device.map_or(0, |device| device as u32 + 1))
+            },
+            SocketOption::SO_TXCOMPLETE(_) => SocketOption::SO_TXCOMPLETE(self.tx_complete),
+            SocketOption::SO_DOORBELL(_) => SocketOption::SO_DOORBELL(false),
+            SocketOption::TCP_MD5SIG(key) => SocketOption::TCP_MD5SIG(key),
+            SocketOption::TCP_AO_ADD_KEY(key) => SocketOption::TCP_AO_ADD_KEY(key),
+            SocketOption::TCP_AO_DEL_KEY(key) => SocketOption::TCP_AO_DEL_KEY(key),
+            SocketOption::TCP_AO_INFO(info) => SocketOption::TCP_AO_INFO(info),
+        }
+    }
+
+    /// Gets the type of service byte of outgoing IPv4 packets.
+    pub fn tos(&self) -> u8 {
+        self.tos
+    }
+
+    /// Gets the DSCP of outgoing IPv4 packets.
+    pub fn dscp(&self) -> u8 {
+        self.tos >> 2
+    }
+
+    /// Gets the time to live of outgoing IPv4 packets.
+    pub fn ttl(&self) -> u8 {
+        self.ttl.unwrap_or(DEFAULT_IPV4_TTL)
+    }
+
+    /// Gets the priority of outgoing packets, if the application has set one.
+    pub fn priority(&self) -> Option<u8> {
+        self.priority
+    }
+
+    /// Gets the largest rate at which the socket sends, in bytes per second, if the application has set one.
+    pub fn max_pacing_rate(&self) -> Option<u64> {
+        self.max_pacing_rate
+    }
+
+    /// Checks if the socket queues records of the incoming packets that are dropped on their way to it.
+    pub fn recv_err(&self) -> bool {
+        self.recv_err
+    }
+
+    /// Checks if outgoing UDP datagrams are sent without a checksum, if the application has set it.
+    pub fn no_check(&self) -> Option<bool> {
+        self.no_check
+    }
+
+    /// Gets the size of the UDP datagrams that larger payloads are split into, if the application has set one.
+    pub fn segment_size(&self) -> Option<u16> {
+        self.segment_size
+    }
+
+    /// Gets the initial congestion window of TCP connections, in segments, if the application has set one.
+    pub fn init_cwnd(&self) -> Option<u32> {
+        self.init_cwnd
+    }
+
+    /// Gets the retransmission timeout of TCP connections before any round-trip time is measured, if the application
+    /// has set one.
+    pub fn rto_initial(&self) -> Option<Duration> {
+        self.rto_initial
+    }
+
+    /// Gets the lower bound for the retransmission timeout of TCP connections, if the application has set one.
+    pub fn rto_min(&self) -> Option<Duration> {
+        self.rto_min
+    }
+
+    /// Gets the upper bound for the retransmission timeout of TCP connections, if the application has set one.
+    pub fn rto_max(&self) -> Option<Duration> {
+        self.rto_max
+    }
+
+    /// Gets the longest time that sent data may stay unacknowledged before TCP connections are aborted, if the
+    /// application has set one.
+    pub fn user_timeout(&self) -> Option<Duration> {
+        self.user_timeout
+    }
+
+    /// Gets the SOF_TIMESTAMPING_* flags of the timestamps of outgoing packets that the socket queues.
+    pub fn timestamping(&self) -> u32 {
+        self.timestamping
+    }
+
+    /// Gets the index of the interface that outgoing packets leave through, if the application has bound the socket to
+    /// one.
+    pub fn device(&self) -> Option<usize> {
+        self.device
+    }
+
+    /// Checks if pushes complete only once their data is delivered.
+    pub fn tx_complete(&self) -> bool {
+        self.tx_complete
+    }
+
+    /// Returns these options without the interface that the socket is bound to.
+    pub fn without_device(mut self) -> Self {
+        self.device = None;
+        self
+    }
+}
+
+impl TcpMd5Key {
+    /// Creates the key [key] of the peers in the subnet [addr]/[prefix_len]. Empty keys remove the key of these peers.
+    pub fn new(addr: Ipv4Addr, prefix_len: u8, key: &[u8]) -> Result<Self, Fail> {
+        if prefix_len > 32 || key.len() > TCP_MD5_MAX_KEY_SIZE {
+            let cause: String = format!(
+                "invalid signature key (addr={}, prefix_len={}, len={})",
+                addr,
+                prefix_len,
+                key.len()
+            );
+            error!("new(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        let mut me: Self = Self {
+            addr,
+            prefix_len,
+            len: key.len() as u8,
+            key: [0; TCP_MD5_MAX_KEY_SIZE],
+        };
+        me.key[..key.len()].copy_from_slice(key);
+        Ok(me)
+    }
+
+    /// Gets the address of the subnet of the peers.
+    pub fn addr(&self) -> Ipv4Addr {
+        self.addr
+    }
+
+    /// Gets the length of the prefix of the subnet of the peers, in bits.
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Gets the key.
+    pub fn key(&self) -> &[u8] {
+        &self.key[..(self.len as usize)]
+    }
+
+    /// Checks whether [addr] is the address of one of the peers.
+    pub fn matches(&self, addr: Ipv4Addr) -> bool {
+        let mask: u32 = u32::MAX.checked_shl(32 - self.prefix_len as u32).unwrap_or(0);
+        u32::from(addr) & mask == u32::from(self.addr) & mask
+    }
+
+    /// Checks whether this key and [other] belong to the same peers.
+    pub fn same_peers(&self, other: &TcpMd5Key) -> bool {
+        self.prefix_len == other.prefix_len && other.matches(self.addr)
+    }
+}
+
+impl TcpAoKey {
+    /// Creates the master key [key] of the peers in the subnet [addr]/[prefix_len], with identifiers [send_id] and
+    /// [recv_id], for [algorithm].
+    pub fn new(
+        addr: Ipv4Addr,
+        prefix_len: u8,
+        send_id: u8,
+        recv_id: u8,
+        algorithm: TcpAoAlgorithm,
+        key: &[u8],
+    ) -> Result<Self, Fail> {
+        if prefix_len > 32 || key.is_empty() || key.len() > TCP_AO_MAX_KEY_SIZE {
+            let cause: String = format!(
+                "invalid authentication key (addr={}, prefix_len={}, len={})",
+                addr,
+                prefix_len,
+                key.len()
+            );
+            error!("new(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        let mut me: Self = Self {
+            addr,
+            prefix_len,
+            send_id,
+            recv_id,
+            algorithm,
+            len: key.len() as u8,
+            key: [0; TCP_AO_MAX_KEY_SIZE],
+        };
+        me.key[..key.len()].copy_from_slice(key);
+        Ok(me)
+    }
+
+    /// Gets the address of the subnet of the peers.
+    pub fn addr(&self) -> Ipv4Addr {
+        self.addr
+    }
+
+    /// Gets the length of the prefix of the subnet of the peers, in bits.
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Gets the identifier that segments signed with this key carry.
+    pub fn send_id(&self) -> u8 {
+        self.send_id
+    }
+
+    /// Gets the identifier that segments of our peers signed with this key carry.
+    pub fn recv_id(&self) -> u8 {
+        self.recv_id
+    }
+
+    /// Gets the MAC algorithm.
+    pub fn algorithm(&self) -> TcpAoAlgorithm {
+        self.algorithm
+    }
+
+    /// Gets the master key.
+    pub fn key(&self) -> &[u8] {
+        &self.key[..(self.len as usize)]
+    }
+
+    /// Checks whether [addr] is the address of one of the peers.
+    pub fn matches(&self, addr: Ipv4Addr) -> bool {
+        let mask: u32 = u32::MAX.checked_shl(32 - self.prefix_len as u32).unwrap_or(0);
+        u32::from(addr) & mask == u32::from(self.addr) & mask
+    }
+
+    /// Checks whether this key and [other] belong to the same peers and have the same identifiers.
+    pub fn same_key(&self, other: &TcpAoKey) -> bool {
+        self.prefix_len == other.prefix_len
+            && other.matches(self.addr)
+            && self.send_id == other.send_id
+            && self.recv_id == other.recv_id
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+/// Keys are left out, so that they do not end up in logs.
+impl fmt::Debug for TcpMd5Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TcpMd5Key")
+            .field("addr", &self.addr)
+            .field("prefix_len", &self.prefix_len)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+/// Keys are left out, so that they do not end up in logs.
+impl fmt::Debug for TcpAoKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TcpAoKey")
+            .field("addr", &self.addr)
+            .field("prefix_len", &self.prefix_len)
+            .field("send_id", &self.send_id)
+            .field("recv_id", &self.recv_id)
+            .field("algorithm", &self.algorithm)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        SocketOption,
+        SocketOptions,
+    };
+    use crate::runtime::network::{
+        consts::DEFAULT_IPV4_TTL,
+        timestamp::{
+            SOF_TIMESTAMPING_TX_HARDWARE,
+            SOF_TIMESTAMPING_TX_SOFTWARE,
+        },
+    };
+    use ::anyhow::Result;
+    use ::std::time::Duration;
+
+    /// Tests that options are set and queried back, and that invalid values are rejected.
+    #[test]
+    fn test_socket_options() -> Result<()> {
+        let mut options: SocketOptions = SocketOptions::default();
+        crate::ensure_eq!(options.priority(), None);
+        crate::ensure_eq!(options.get(SocketOption::SO_PRIORITY(0)), SocketOption::SO_PRIORITY(0));
+
+        options.set(SocketOption::IP_TOS(0xb8))?;
+        options.set(SocketOption::SO_PRIORITY(5))?;
+        crate::ensure_eq!(options.get(SocketOption::IP_TOS(0)), SocketOption::IP_TOS(0xb8));
+        crate::ensure_eq!(options.priority(), Some(5));
+
+        crate::ensure_eq!(options.set(SocketOption::SO_PRIORITY(8)).is_err(), true);
+        crate::ensure_eq!(options.priority(), Some(5));
+
+        crate::ensure_eq!(options.ttl(), DEFAULT_IPV4_TTL);
+        options.set(SocketOption::IP_TTL(1))?;
+        crate::ensure_eq!(options.get(SocketOption::IP_TTL(0)), SocketOption::IP_TTL(1));
+        crate::ensure_eq!(options.set(SocketOption::IP_TTL(0)).is_err(), true);
+        crate::ensure_eq!(options.ttl(), 1);
+
+        crate::ensure_eq!(options.max_pacing_rate(), None);
+        options.set(SocketOption::SO_MAX_PACING_RATE(1_000_000))?;
+        crate::ensure_eq!(options.max_pacing_rate(), Some(1_000_000));
+        crate::ensure_eq!(options.set(SocketOption::SO_MAX_PACING_RATE(0)).is_err(), true);
+        options.set(SocketOption::SO_MAX_PACING_RATE(u64::MAX))?;
+        crate::ensure_eq!(options.max_pacing_rate(), None);
+        crate::ensure_eq!(
+            options.get(SocketOption::SO_MAX_PACING_RATE(0)),
+            SocketOption::SO_MAX_PACING_RATE(u64::MAX)
+        );
+
+        crate::ensure_eq!(options.recv_err(), false);
+        options.set(SocketOption::IP_RECVERR(true))?;
+        crate::ensure_eq!(
+            options.get(SocketOption::IP_RECVERR(false)),
+            SocketOption::IP_RECVERR(true)
+        );
+
+        crate::ensure_eq!(options.no_check(), None);
+        options.set(SocketOption::SO_NO_CHECK(true))?;
+        crate::ensure_eq!(
+            options.get(SocketOption::SO_NO_CHECK(false)),
+            SocketOption::SO_NO_CHECK(true)
+        );
+
+        crate::ensure_eq!(options.segment_size(), None);
+        options.set(SocketOption::UDP_SEGMENT(1400))?;
+        crate::ensure_eq!(options.segment_size(), Some(1400));
+        options.set(SocketOption::UDP_SEGMENT(0))?;
+        crate::ensure_eq!(
+            options.get(SocketOption::UDP_SEGMENT(1400)),
+            SocketOption::UDP_SEGMENT(0)
+        );
+
+        crate::ensure_eq!(options.rto_min(), None);
+        options.set(SocketOption::TCP_RTO_MIN_US(200))?;
+        options.set(SocketOption::TCP_RTO_MAX_MS(10))?;
+        crate::ensure_eq!(options.rto_min(), Some(Duration::from_micros(200)));
+        crate::ensure_eq!(
+            options.get(SocketOption::TCP_RTO_MAX_MS(0)),
+            SocketOption::TCP_RTO_MAX_MS(10)
+        );
+        options.set(SocketOption::TCP_RTO_MIN_US(0))?;
+        crate::ensure_eq!(options.rto_min(), None);
+        options.set(SocketOption::TCP_INIT_CWND(10))?;
+        crate::ensure_eq!(options.init_cwnd(), Some(10));
+
+        crate::ensure_eq!(options.user_timeout(), None);
+        options.set(SocketOption::TCP_USER_TIMEOUT(5000))?;
+        crate::ensure_eq!(options.user_timeout(), Some(Duration::from_secs(5)));
+        options.set(SocketOption::TCP_USER_TIMEOUT(0))?;
+        crate::ensure_eq!(
+            options.get(SocketOption::TCP_USER_TIMEOUT(5000)),
+            SocketOption::TCP_USER_TIMEOUT(0)
+        );
+
+        crate::ensure_eq!(options.timestamping(), 0);
+        options.set(SocketOption::SO_TIMESTAMPING(
+            SOF_TIMESTAMPING_TX_HARDWARE | SOF_TIMESTAMPING_TX_SOFTWARE,
+        ))?;
+        crate::ensure_eq!(options.set(SocketOption::SO_TIMESTAMPING(1 << 4)).is_err(), true);
+        crate::ensure_eq!(
+            options.get(SocketOption::SO_TIMESTAMPING(0)),
+            SocketOption::SO_TIMESTAMPING(SOF_TIMESTAMPING_TX_HARDWARE | SOF_TIMESTAMPING_TX_SOFTWARE)
+        );
+
+        crate::ensure_eq!(options.device(), None);
+        options.set(SocketOption::SO_BINDTODEVICE(2))?;
+        crate::ensure_eq!(options.device(), Some(1));
+        crate::ensure_eq!(
+            options.get(SocketOption::SO_BINDTODEVICE(0)),
+            SocketOption::SO_BINDTODEVICE(2)
+        );
+        crate::ensure_eq!(options.without_device().device(), None);
+        options.set(SocketOption::SO_BINDTODEVICE(0))?;
+        crate::ensure_eq!(options.device(), None);
+
+        crate::ensure_eq!(options.tx_complete(), false);
+        options.set(SocketOption::SO_TXCOMPLETE(true))?;
+        crate::ensure_eq!(
+            options.get(SocketOption::SO_TXCOMPLETE(false)),
+            SocketOption::SO_TXCOMPLETE(true)
+        );
+
+        // Only memory queues sleep on doorbells.
+        crate::ensure_eq!(
+            options.set(SocketOption::SO_DOORBELL(true)).unwrap_err().errno,
+            libc::ENOPROTOOPT
+        );
+        crate::ensure_eq!(
+            options.get(SocketOption::SO_DOORBELL(true)),
+            SocketOption::SO_DOORBELL(false)
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,190 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::timer;
+use ::std::time::{
+    Duration,
+    Instant,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Smallest burst of a token bucket, in bytes. This lets a socket send at least a full TSO segment at once.
+const MIN_BURST_SIZE: u64 = 64 * 1024;
+
+/// Number of nanoseconds in a second.
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Token bucket that caps the rate at which a socket sends. The bucket holds up to 10 ms worth of bytes, so a socket
+/// that was idle may send a short burst. Sends are never split: a send that takes more tokens than the bucket holds
+/// leaves it in debt, and the next send waits until the debt is paid.
+#[derive(Clone, Debug)]
+pub struct TokenBucket {
+    /// Rate at which tokens are added, in bytes per second.
+    rate: u64,
+    /// Largest number of tokens in the bucket, in bytes.
+    burst: u64,
+    /// Number of tokens in the bucket, in bytes. This is negative when the bucket is in debt.
+    tokens: i64,
+    /// Time up to which tokens were added.
+    last_refill: Instant,
+}
+
+/// Egress shaper of a socket. Sockets without a rate limit send right away.
+#[derive(Clone, Debug, Default)]
+pub struct Shaper {
+    bucket: Option<TokenBucket>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl TokenBucket {
+    /// Creates a full bucket that allows sending [rate] bytes per second. The rate must not be zero.
+    pub fn new(rate: u64, now: Instant) -> Self {
+        debug_assert!(rate > 0);
+        let burst: u64 = (rate / 100).max(MIN_BURST_SIZE);
+        Self {
+            rate,
+            burst,
+            tokens: burst as i64,
+            last_refill: now,
+        }
+    }
+
+    /// Gets the rate of this bucket, in bytes per second.
+    pub fn rate(&self) -> u64 {
+        self.rate
+    }
+
+    /// Gets the time at which the next send may go out, if it may not go out right away.
+    pub fn next_send_time(&mut self, now: Instant) -> Option<Instant> {
+        self.refill(now);
+        if self.tokens > 0 {
+            return None;
+        }
+        // Wait until the debt is paid and there is at least one token in the bucket.
+        let missing: u128 = (1 - self.tokens) as u128;
+        let nanos: u128 = (missing * NANOS_PER_SEC).div_ceil(self.rate as u128);
+        Some(self.last_refill + Duration::from_nanos(nanos as u64))
+    }
+
+    /// Takes [len] bytes worth of tokens out of the bucket.
+    pub fn consume(&mut self, now: Instant, len: usize) {
+        self.refill(now);
+        self.tokens = self.tokens.saturating_sub(len as i64);
+    }
+
+    /// Adds the tokens that were earned since the last refill.
+    fn refill(&mut self, now: Instant) {
+        let elapsed: Duration = now.saturating_duration_since(self.last_refill);
+        let earned: u128 = elapsed.as_nanos() * self.rate as u128 / NANOS_PER_SEC;
+        if earned == 0 {
+            return;
+        }
+        let tokens: i64 = self.tokens.saturating_add(earned.min(i64::MAX as u128) as i64);
+        if tokens >= self.burst as i64 {
+            self.tokens = self.burst as i64;
+            self.last_refill = now;
+        } else {
+            // Only move ahead by the time it took to earn whole tokens, so that fractions of tokens are not lost.
+            self.tokens = tokens;
+            self.last_refill += Duration::from_nanos((earned * NANOS_PER_SEC / self.rate as u128) as u64);
+        }
+    }
+}
+
+impl Shaper {
+    /// Limits sends to [rate] bytes per second, or lifts the limit if [rate] is `None`. Changing the rate starts
+    /// over with a full bucket.
+    pub fn set_rate(&mut self, rate: Option<u64>) {
+        if self.bucket.as_ref().map(|bucket| bucket.rate()) != rate {
+            self.bucket = rate.map(|rate| TokenBucket::new(rate, timer::global_get_time()));
+        }
+    }
+
+    /// Gets the time at which the next send may go out, if it may not go out right away.
+    pub fn next_send_time(&mut self) -> Option<Instant> {
+        let now: Instant = timer::global_get_time();
+        self.bucket.as_mut().and_then(|bucket| bucket.next_send_time(now))
+    }
+
+    /// Accounts for a send of [len] bytes.
+    pub fn consume(&mut self, len: usize) {
+        if let Some(bucket) = self.bucket.as_mut() {
+            bucket.consume(timer::global_get_time(), len);
+        }
+    }
+
+    /// Waits until a send of [len] bytes may go out and accounts for it. The wait goes through the timer, so other
+    /// coroutines run in the meantime.
+    pub async fn wait(&mut self, len: usize) {
+        while let Some(expiry) = self.next_send_time() {
+            timer::wait_until(expiry).await;
+        }
+        self.consume(len);
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        TokenBucket,
+        MIN_BURST_SIZE,
+    };
+    use ::anyhow::Result;
+    use ::std::time::{
+        Duration,
+        Instant,
+    };
+
+    /// Tests that a full bucket lets a burst out right away and then holds sends back until it refills.
+    #[test]
+    fn test_token_bucket_burst() -> Result<()> {
+        let now: Instant = Instant::now();
+        let mut bucket: TokenBucket = TokenBucket::new(1_000_000, now);
+        crate::ensure_eq!(bucket.next_send_time(now), None);
+
+        // Taking the whole burst and one more byte leaves the bucket in debt.
+        bucket.consume(now, MIN_BURST_SIZE as usize + 1);
+        crate::ensure_eq!(bucket.next_send_time(now), Some(now + Duration::from_micros(2)));
+        crate::ensure_eq!(bucket.next_send_time(now + Duration::from_micros(2)), None);
+
+        // The bucket never holds more than the burst.
+        let later: Instant = now + Duration::from_secs(10);
+        bucket.consume(later, MIN_BURST_SIZE as usize);
+        crate::ensure_eq!(bucket.next_send_time(later), Some(later + Duration::from_micros(1)));
+
+        Ok(())
+    }
+
+    /// Tests that refills do not lose fractions of tokens.
+    #[test]
+    fn test_token_bucket_slow_rate() -> Result<()> {
+        let now: Instant = Instant::now();
+        let mut bucket: TokenBucket = TokenBucket::new(1000, now);
+        bucket.consume(now, MIN_BURST_SIZE as usize + 1);
+
+        // After 1.5 ms, one token was earned and half of the next one is on its way.
+        let time: Instant = now + Duration::from_micros(1500);
+        crate::ensure_eq!(bucket.next_send_time(time), Some(now + Duration::from_millis(2)));
+        crate::ensure_eq!(bucket.next_send_time(now + Duration::from_millis(2)), None);
+
+        Ok(())
+    }
+}
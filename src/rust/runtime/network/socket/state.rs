@@ -61,7 +61,7 @@ impl SocketStateMachine {
     /// Constructs a new [SocketState] of type `typ` that is on unbound state.
     pub fn new_unbound(typ: Type) -> Self {
         // This was previously checked in the LibOS layer.
-        debug_assert!(typ == Type::STREAM || typ == Type::DGRAM);
+        debug_assert!(typ == Type::STREAM || typ == Type::DGRAM || typ == Type::RAW);
         Self {
             typ,
             current: SharedAsyncValue::new(SocketState::Unbound),
@@ -154,9 +154,10 @@ impl SocketStateMachine {
         self.ensure_not_closing()?;
         self.ensure_not_closed()?;
 
+        // Raw sockets are not bound to a port, so they may pop right away.
         if self.typ == Type::STREAM {
             self.ensure_established()?;
-        } else {
+        } else if self.typ == Type::DGRAM {
             self.ensure_bound()?;
         }
 
@@ -0,0 +1,211 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use ::std::{
+    collections::VecDeque,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Number of events that the timeline of a TCP connection keeps. Older events are overwritten.
+pub const TCP_TIMELINE_CAPACITY: usize = 32;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// States of the TCP state machine. The discriminants match the ones of Linux and are part of the C API.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum TcpState {
+    Established = 1,
+    SynSent = 2,
+    SynReceived = 3,
+    FinWait1 = 4,
+    FinWait2 = 5,
+    TimeWait = 6,
+    #[default]
+    Closed = 7,
+    CloseWait = 8,
+    LastAck = 9,
+    Listen = 10,
+    Closing = 11,
+}
+
+/// Snapshot of the state of a TCP connection.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TcpInfo {
+    /// Current state of the state machine.
+    pub state: TcpState,
+    /// Maximum segment size that is used to send data, in bytes.
+    pub mss: u32,
+    /// Congestion window, in bytes.
+    pub cwnd: u32,
+    /// Slow start threshold, in bytes. This is `u32::MAX` until the first congestion event.
+    pub ssthresh: u32,
+    /// Smoothed round-trip time. This is zero until the first sample is taken.
+    pub srtt: Duration,
+    /// Round-trip time variation.
+    pub rttvar: Duration,
+    /// Current retransmission timeout.
+    pub rto: Duration,
+    /// Number of segments that were retransmitted, for any reason.
+    pub retransmits: u64,
+    /// Number of times that the retransmission timer expired.
+    pub timeouts: u64,
+    /// Number of fast retransmissions that duplicate acknowledgements triggered.
+    pub fast_retransmits: u64,
+    /// Whether our peer advertises a zero window, so that only window probes are sent.
+    pub flow_control_stalled: bool,
+    /// Number of window probes that were sent while our peer advertised a zero window.
+    pub window_probes: u32,
+    /// Bytes that were sent but not acknowledged yet.
+    pub bytes_in_flight: u32,
+    /// Bytes that the application pushed but were not sent yet.
+    pub send_queue: u32,
+    /// Bytes that were received but not popped by the application yet.
+    pub recv_queue: u32,
+    /// Receive buffer, in bytes, which bounds the advertised window and grows with auto-tuning.
+    pub recv_space: u32,
+}
+
+/// Kinds of events that are kept in the timeline of a TCP connection. The discriminants are part of the C API.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum TcpEventKind {
+    /// The retransmission timer expired and the oldest unacknowledged segment was sent again.
+    RetransmitTimeout = 0,
+    /// Duplicate acknowledgements triggered a fast retransmission.
+    FastRetransmit = 1,
+    /// The retransmission timer was backed off.
+    RtoBackoff = 2,
+    /// Data is waiting to be sent but does not fit in the window that our peer advertises.
+    WindowStall = 3,
+    /// Our peer advertises a zero window, so that only window probes are sent.
+    ZeroWindow = 4,
+    /// Data that was sent stayed unacknowledged for longer than the user timeout, so the connection was aborted.
+    UserTimeout = 5,
+}
+
+/// Event in the timeline of a TCP connection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TcpEvent {
+    /// What happened.
+    pub kind: TcpEventKind,
+    /// When it happened, since the connection was established.
+    pub at: Duration,
+    /// Oldest unacknowledged sequence number (SND.UNA) when it happened.
+    pub seq_no: u32,
+    /// Bytes in flight for retransmissions and user timeouts, the new retransmission timeout in microseconds for back
+    /// offs, the send window for window stalls and the bytes waiting to be sent for zero windows.
+    pub value: u64,
+}
+
+/// Ring of the most recent retransmission and flow control events of a TCP connection, which helps to tell after the
+/// fact why a connection was slow or failed.
+#[derive(Debug)]
+pub struct TcpTimeline {
+    /// When the connection was established.
+    start: Instant,
+    /// Events, from the oldest to the newest.
+    events: VecDeque<TcpEvent>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl TcpInfo {
+    /// Creates a snapshot of a connection that is in [state] and has no data in flight.
+    pub fn with_state(state: TcpState) -> Self {
+        Self {
+            state,
+            ssthresh: u32::MAX,
+            ..Default::default()
+        }
+    }
+}
+
+impl TcpTimeline {
+    /// Creates an empty timeline for a connection that was established at [start].
+    pub fn new(start: Instant) -> Self {
+        Self {
+            start,
+            events: VecDeque::with_capacity(TCP_TIMELINE_CAPACITY),
+        }
+    }
+
+    /// Records an event that happened at [now], overwriting the oldest one if the timeline is full.
+    pub fn record(&mut self, now: Instant, kind: TcpEventKind, seq_no: u32, value: u64) {
+        if self.events.len() == TCP_TIMELINE_CAPACITY {
+            self.events.pop_front();
+        }
+        self.events.push_back(TcpEvent {
+            kind,
+            at: now.saturating_duration_since(self.start),
+            seq_no,
+            value,
+        });
+    }
+
+    /// Gets the events of the timeline, from the oldest to the newest.
+    pub fn events(&self) -> Vec<TcpEvent> {
+        self.events.iter().copied().collect()
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::network::tcp_info::{
+        TcpEvent,
+        TcpEventKind,
+        TcpTimeline,
+        TCP_TIMELINE_CAPACITY,
+    };
+    use ::anyhow::Result;
+    use ::std::time::{
+        Duration,
+        Instant,
+    };
+
+    /// Tests that the timeline keeps the most recent events, stamped with the time since the connection started.
+    #[test]
+    fn test_tcp_timeline_ring() -> Result<()> {
+        let start: Instant = Instant::now();
+        let mut timeline: TcpTimeline = TcpTimeline::new(start);
+        crate::ensure_eq!(timeline.events().is_empty(), true);
+
+        for i in 0..(TCP_TIMELINE_CAPACITY + 2) {
+            timeline.record(
+                start + Duration::from_millis(i as u64),
+                TcpEventKind::RtoBackoff,
+                1,
+                i as u64,
+            );
+        }
+        let events: Vec<TcpEvent> = timeline.events();
+        crate::ensure_eq!(events.len(), TCP_TIMELINE_CAPACITY);
+        crate::ensure_eq!(events[0].value, 2);
+        crate::ensure_eq!(events[0].at, Duration::from_millis(2));
+        crate::ensure_eq!(
+            events[TCP_TIMELINE_CAPACITY - 1].value,
+            (TCP_TIMELINE_CAPACITY + 1) as u64
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use ::std::{
+    collections::VecDeque,
+    time::SystemTime,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Outgoing packets are stamped by the device when they are transmitted.
+pub const SOF_TIMESTAMPING_TX_HARDWARE: u32 = 1 << 0;
+/// Outgoing packets are stamped by the network stack when they are handed to the device.
+pub const SOF_TIMESTAMPING_TX_SOFTWARE: u32 = 1 << 1;
+/// Flags that may be set with the SO_TIMESTAMPING socket option.
+pub const SOF_TIMESTAMPING_MASK: u32 = SOF_TIMESTAMPING_TX_HARDWARE | SOF_TIMESTAMPING_TX_SOFTWARE;
+
+/// Largest number of transmit timestamps that a socket holds. Older timestamps are discarded first.
+pub const TX_TIMESTAMP_QUEUE_CAPACITY: usize = 64;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Times at which an incoming packet was received, taken once per batch by the poll loop.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RxTimestamp {
+    /// Time at which the network stack received the batch.
+    pub software: SystemTime,
+    /// Time at which the device received the packet, in nanoseconds of the clock of the device, if it stamps them.
+    pub hardware: Option<u64>,
+}
+
+/// Times at which an outgoing packet was transmitted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TxTimestamp {
+    /// Identifier of the packet, counting from zero the packets that the socket sent since timestamping was enabled.
+    pub id: u32,
+    /// Time at which the network stack handed the packet to the device.
+    pub software: Option<SystemTime>,
+    /// Time at which the device transmitted the packet, in nanoseconds of the clock of the device.
+    pub hardware: Option<u64>,
+}
+
+/// Transmit timestamps of a socket, which are queued while the application enables them with SO_TIMESTAMPING.
+#[derive(Debug, Default)]
+pub struct TxTimestampQueue {
+    flags: u32,
+    next_id: u32,
+    timestamps: VecDeque<TxTimestamp>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl RxTimestamp {
+    /// Stamps a packet that the network stack receives now, without a timestamp from the device.
+    pub fn now() -> Self {
+        Self {
+            software: SystemTime::now(),
+            hardware: None,
+        }
+    }
+}
+
+impl TxTimestampQueue {
+    /// Sets the SOF_TIMESTAMPING_* [flags] of this queue. Disabling timestamps discards the ones that are queued and
+    /// restarts the numbering of packets.
+    pub fn set_flags(&mut self, flags: u32) {
+        if flags == 0 {
+            self.timestamps.clear();
+            self.next_id = 0;
+        }
+        self.flags = flags;
+    }
+
+    /// Checks if the device should stamp outgoing packets.
+    pub fn wants_hardware(&self) -> bool {
+        self.flags & SOF_TIMESTAMPING_TX_HARDWARE != 0
+    }
+
+    /// Queues the timestamp of a packet that is handed to the device, if timestamps are enabled.
+    pub fn record(&mut self) {
+        if self.flags == 0 {
+            return;
+        }
+        if self.timestamps.len() == TX_TIMESTAMP_QUEUE_CAPACITY {
+            self.timestamps.pop_front();
+        }
+        let software: Option<SystemTime> = if self.flags & SOF_TIMESTAMPING_TX_SOFTWARE != 0 {
+            Some(SystemTime::now())
+        } else {
+            None
+        };
+        self.timestamps.push_back(TxTimestamp {
+            id: self.next_id,
+            software,
+            hardware: None,
+        });
+        self.next_id = self.next_id.wrapping_add(1);
+    }
+
+    /// Pops the oldest timestamp. [read_hardware] reads the time at which the device transmitted it, if hardware
+    /// timestamps are enabled.
+    pub fn pop(&mut self, read_hardware: impl FnOnce() -> Option<u64>) -> Option<TxTimestamp> {
+        let mut timestamp: TxTimestamp = self.timestamps.pop_front()?;
+        if self.wants_hardware() {
+            timestamp.hardware = read_hardware();
+        }
+        Some(timestamp)
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        TxTimestampQueue,
+        SOF_TIMESTAMPING_TX_HARDWARE,
+        SOF_TIMESTAMPING_TX_SOFTWARE,
+        TX_TIMESTAMP_QUEUE_CAPACITY,
+    };
+    use ::anyhow::Result;
+
+    /// Tests that timestamps are only queued while they are enabled and that the oldest ones are discarded first.
+    #[test]
+    fn test_tx_timestamp_queue() -> Result<()> {
+        let mut queue: TxTimestampQueue = TxTimestampQueue::default();
+        queue.record();
+        crate::ensure_eq!(queue.pop(|| Some(1)), None);
+
+        queue.set_flags(SOF_TIMESTAMPING_TX_SOFTWARE);
+        queue.record();
+        let timestamp = queue.pop(|| Some(1)).unwrap();
+        crate::ensure_eq!(timestamp.id, 0);
+        crate::ensure_eq!(timestamp.software.is_some(), true);
+        crate::ensure_eq!(timestamp.hardware, None);
+
+        queue.set_flags(SOF_TIMESTAMPING_TX_HARDWARE);
+        for _ in 0..(TX_TIMESTAMP_QUEUE_CAPACITY + 1) {
+            queue.record();
+        }
+        let timestamp = queue.pop(|| Some(7)).unwrap();
+        crate::ensure_eq!(timestamp.id, 2);
+        crate::ensure_eq!(timestamp.software, None);
+        crate::ensure_eq!(timestamp.hardware, Some(7));
+
+        queue.set_flags(0);
+        crate::ensure_eq!(queue.pop(|| None), None);
+        queue.set_flags(SOF_TIMESTAMPING_TX_SOFTWARE);
+        queue.record();
+        crate::ensure_eq!(queue.pop(|| None).map(|timestamp| timestamp.id), Some(0));
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::fail::Fail;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Reads the fields of a TLS message in network byte order. Reads past the end of the message fail.
+pub struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    /// Gets the number of bytes that are left to read.
+    pub fn remaining(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// Reads the next [len] bytes.
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8], Fail> {
+        if len > self.buf.len() {
+            let cause: String = format!("message is too short (len={:?}, expected={:?})", self.buf.len(), len);
+            warn!("bytes(): {}", cause);
+            return Err(Fail::new(libc::EBADMSG, &cause));
+        }
+        let (bytes, rest): (&'a [u8], &'a [u8]) = self.buf.split_at(len);
+        self.buf = rest;
+        Ok(bytes)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, Fail> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16, Fail> {
+        let bytes: &[u8] = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn u24(&mut self) -> Result<u32, Fail> {
+        let bytes: &[u8] = self.bytes(3)?;
+        Ok(u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, Fail> {
+        let bytes: &[u8] = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Reads a vector with a one-byte length prefix.
+    pub fn vec8(&mut self) -> Result<&'a [u8], Fail> {
+        let len: usize = self.u8()? as usize;
+        self.bytes(len)
+    }
+
+    /// Reads a vector with a two-byte length prefix.
+    pub fn vec16(&mut self) -> Result<&'a [u8], Fail> {
+        let len: usize = self.u16()? as usize;
+        self.bytes(len)
+    }
+
+    /// Checks that the whole message was read.
+    pub fn finish(&self) -> Result<(), Fail> {
+        if !self.buf.is_empty() {
+            let cause: String = format!("trailing bytes in message (len={:?})", self.buf.len());
+            warn!("finish(): {}", cause);
+            return Err(Fail::new(libc::EBADMSG, &cause));
+        }
+        Ok(())
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Appends a vector with a length prefix of [len_size] bytes to [out]. The contents of the vector are written by [f].
+pub fn put_vec<F: FnOnce(&mut Vec<u8>)>(out: &mut Vec<u8>, len_size: usize, f: F) {
+    let start: usize = out.len();
+    out.resize(start + len_size, 0);
+    f(out);
+    let len: usize = out.len() - start - len_size;
+    debug_assert!(len < 1 << (8 * len_size));
+    out[start..(start + len_size)].copy_from_slice(&(len as u32).to_be_bytes()[(4 - len_size)..]);
+}
+
+/// Appends a handshake message of type [msg_type] to [out]. The body of the message is written by [f].
+pub fn put_handshake<F: FnOnce(&mut Vec<u8>)>(out: &mut Vec<u8>, msg_type: u8, f: F) {
+    out.push(msg_type);
+    put_vec(out, 3, f);
+}
+
+/// Appends an extension of type [ext_type] to [out]. The data of the extension is written by [f].
+pub fn put_extension<F: FnOnce(&mut Vec<u8>)>(out: &mut Vec<u8>, ext_type: u16, f: F) {
+    out.extend_from_slice(&ext_type.to_be_bytes());
+    put_vec(out, 2, f);
+}
@@ -0,0 +1,1286 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    expect_some,
+    runtime::{
+        crypto::{
+            chacha20poly1305::{
+                chacha20_xor,
+                CHACHA20_POLY1305_KEY_SIZE,
+                CHACHA20_POLY1305_NONCE_SIZE,
+                CHACHA20_POLY1305_TAG_SIZE,
+            },
+            runtime::AeadKey,
+            sha256::{
+                Sha256,
+                SHA256_DIGEST_SIZE,
+            },
+        },
+        fail::Fail,
+        network::tls::{
+            codec::{
+                put_vec,
+                Reader,
+            },
+            keys::{
+                hkdf_expand_prefixed_label,
+                Secret,
+                DTLS13_LABEL_PREFIX,
+            },
+            provider::CryptoProvider,
+            record::{
+                CONTENT_TYPE_ALERT,
+                CONTENT_TYPE_APPLICATION_DATA,
+                CONTENT_TYPE_HANDSHAKE,
+                MAX_PLAINTEXT_SIZE,
+            },
+            session::{
+                dtls_client_hello_cookie,
+                dtls_hello_retry_request,
+                TlsSession,
+            },
+            EncryptionLevel,
+            TlsConfig,
+            TlsRole,
+        },
+    },
+};
+use ::std::{
+    collections::{
+        BTreeMap,
+        VecDeque,
+    },
+    rc::Rc,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Largest datagram that handshake messages are packed into. Larger messages are fragmented.
+pub const DTLS_MTU: usize = 1200;
+
+/// Legacy protocol version of plaintext records.
+const LEGACY_RECORD_VERSION: u16 = 0xfefd;
+
+/// Size of the header of a plaintext record (DTLSPlaintext in RFC 9147, section 4).
+const PLAINTEXT_HEADER_SIZE: usize = 13;
+
+/// Size of the header of the encrypted records that this side sends, which carry the low 16 bits of the sequence
+/// number and a length (DTLSCiphertext in RFC 9147, section 4).
+const CIPHERTEXT_HEADER_SIZE: usize = 5;
+
+/// Bits of the first byte of the header of an encrypted record.
+const UNIFIED_HEADER_FIXED_BITS: u8 = 0x20;
+const UNIFIED_HEADER_FIXED_MASK: u8 = 0xe0;
+const UNIFIED_HEADER_CID: u8 = 0x10;
+const UNIFIED_HEADER_SEQ16: u8 = 0x08;
+const UNIFIED_HEADER_LENGTH: u8 = 0x04;
+const UNIFIED_HEADER_EPOCH_MASK: u8 = 0x03;
+
+/// Size of the ciphertext sample that masks the sequence number of an encrypted record (RFC 9147, section 4.2.3).
+const SAMPLE_SIZE: usize = 16;
+
+/// Size of the header of a handshake message (RFC 9147, section 5.2).
+const HANDSHAKE_HEADER_SIZE: usize = 12;
+
+/// Largest handshake message that is accepted.
+const MAX_HANDSHAKE_MESSAGE_SIZE: usize = 1 << 16;
+
+/// Handshake messages from the peer that are buffered ahead of the next one that is expected.
+const MAX_BUFFERED_MESSAGES: u16 = 4;
+
+/// Content type of acknowledgements (RFC 9147, section 7).
+const CONTENT_TYPE_ACK: u8 = 26;
+
+/// Epochs of the records that are protected with the handshake and the application traffic keys.
+const EPOCH_INITIAL: u16 = 0;
+const EPOCH_HANDSHAKE: u16 = 2;
+const EPOCH_APPLICATION: u16 = 3;
+
+/// Sequence numbers of records are 48 bits long.
+const MAX_RECORD_SEQ: u64 = (1 << 48) - 1;
+
+/// Number of records below the highest one that was received that are tracked to reject replays.
+const REPLAY_WINDOW_SIZE: u64 = 64;
+
+/// Timeout of the first retransmission of a flight (RFC 9147, section 5.8.2). It doubles with each retransmission.
+const INITIAL_RETRANSMISSION_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Longest timeout between retransmissions of a flight.
+const MAX_RETRANSMISSION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Retransmissions of a flight before the handshake is given up.
+const MAX_RETRANSMISSIONS: usize = 6;
+
+/// Size of a cookie, which holds the hash of the first ClientHello and a MAC over it and the address of the client.
+const COOKIE_SIZE: usize = 2 * SHA256_DIGEST_SIZE;
+
+/// Types of handshake messages.
+const HANDSHAKE_CLIENT_HELLO: u8 = 1;
+
+/// Levels of alerts.
+const ALERT_LEVEL_WARNING: u8 = 1;
+const ALERT_LEVEL_FATAL: u8 = 2;
+
+/// Descriptions of alerts.
+const ALERT_CLOSE_NOTIFY: u8 = 0;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Keys of the records of one epoch that flow in one direction of an association (RFC 9147, section 4.2).
+struct EpochKeys {
+    epoch: u16,
+    key: [u8; CHACHA20_POLY1305_KEY_SIZE],
+    iv: [u8; CHACHA20_POLY1305_NONCE_SIZE],
+    /// Key that masks the sequence numbers of records.
+    sn_key: [u8; CHACHA20_POLY1305_KEY_SIZE],
+    /// Sequence number of the next record that is sent.
+    next_seq: u64,
+    /// Highest sequence number that was received.
+    highest_seq: Option<u64>,
+    /// Records below the highest one that were received, one bit per sequence number.
+    replay_window: u64,
+    provider: Rc<dyn CryptoProvider>,
+}
+
+/// Fragment of a handshake message (RFC 9147, section 5.2).
+struct Fragment<'a> {
+    msg_type: u8,
+    length: usize,
+    message_seq: u16,
+    offset: usize,
+    data: &'a [u8],
+}
+
+/// Handshake message from the peer that is being reassembled from its fragments.
+struct PartialMessage {
+    /// Epoch of the records that carry the message.
+    epoch: u16,
+    msg_type: u8,
+    body: Vec<u8>,
+    /// Bytes of the body that arrived.
+    received: Vec<bool>,
+    /// Number of bytes of the body that are still missing.
+    missing: usize,
+}
+
+/// Handshake messages that this side sent last, which are retransmitted until the peer acknowledges them, either with
+/// its next flight or with an ACK (RFC 9147, section 5.8).
+struct Flight {
+    /// Messages with their DTLS header, along with the epoch that they are sent in.
+    messages: Vec<(u16, Vec<u8>)>,
+    /// Numbers of the records that carried the messages the last time that they were sent.
+    records: Vec<(u16, u64)>,
+    timeout: Duration,
+    deadline: Instant,
+    retransmissions: usize,
+}
+
+/// Sans-I/O DTLS 1.3 association (RFC 9147) that runs a [TlsSession] over datagrams. Datagrams that arrive from the
+/// peer are fed through [DtlsSession::receive] and datagrams that are bound to the peer are collected with
+/// [DtlsSession::take_output]. The handshake is retransmitted on [DtlsSession::on_timeout].
+///
+/// The session authenticates peers and protects data the same way as a [TlsSession]. Records that fail to decrypt,
+/// replayed records and malformed records are dropped, as datagram transports call for. Connection IDs, early data and
+/// key updates are not supported.
+pub struct DtlsSession {
+    role: TlsRole,
+    tls: TlsSession,
+    provider: Rc<dyn CryptoProvider>,
+    /// Keys of incoming records, by epoch.
+    read_keys: Vec<EpochKeys>,
+    /// Keys of outgoing records, by epoch.
+    write_keys: Vec<EpochKeys>,
+    /// Sequence number of the next plaintext record.
+    plaintext_seq: u64,
+    /// Sequence number of the next handshake message that is sent.
+    next_send_message_seq: u16,
+    /// Sequence number of the next handshake message that is expected from the peer.
+    next_receive_message_seq: u16,
+    /// Handshake messages from the peer that are not complete or not due yet, by sequence number.
+    partial: BTreeMap<u16, PartialMessage>,
+    /// Last flight of this side, until it is acknowledged.
+    flight: Option<Flight>,
+    /// Whether the peer retransmitted a flight, which means that the last flight of this side was lost.
+    retransmit: bool,
+    /// Records that carried encrypted handshake messages from the peer, which are yet to be acknowledged.
+    acks: Vec<(u16, u64)>,
+    /// Datagrams that are bound to the peer.
+    outgoing: VecDeque<Vec<u8>>,
+    /// Application data from the peer, one datagram at a time.
+    plaintext: VecDeque<Vec<u8>>,
+    /// Whether the peer closed its side of the association.
+    peer_closed: bool,
+    /// Whether this side closed its side of the association.
+    closed: bool,
+    /// Error that aborted the association.
+    error: Option<Fail>,
+}
+
+/// Stateless guard of a DTLS server, which answers ClientHellos with a HelloRetryRequest that carries a cookie and
+/// only starts a session once the cookie comes back (RFC 9147, section 5.1). This keeps clients from spoofing their
+/// address to have the server do the work of a handshake, or amplify their traffic.
+///
+/// Cookies are bound to the address of the client and its first ClientHello with a MAC under a secret of the guard. They
+/// do not expire, so guards should be replaced from time to time.
+pub struct DtlsCookieGuard {
+    secret: Secret,
+    provider: Rc<dyn CryptoProvider>,
+}
+
+/// What becomes of a datagram that arrives at a DTLS server from a peer without a session.
+pub enum DtlsAdmission {
+    /// The datagram carries a ClientHello with a valid cookie, so a session was started for the peer.
+    Accept(DtlsSession),
+    /// The datagram carries a ClientHello without a cookie, which is answered with the HelloRetryRequest in this
+    /// datagram.
+    Retry(Vec<u8>),
+    /// The datagram carries a ClientHello with a valid cookie, but the handshake failed. The peer learns about it from
+    /// the alert in this datagram.
+    Refuse(Vec<u8>),
+    /// The datagram is ignored.
+    Drop,
+}
+
+/// First ClientHello of a client, which must arrive whole in one datagram.
+struct FirstHello {
+    record_seq: u64,
+    message_seq: u16,
+    /// The message, with the header of TLS.
+    msg: Vec<u8>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl EpochKeys {
+    /// Derives the keys of [epoch] from the traffic [secret] (RFC 9147, section 4.2.3).
+    fn new(epoch: u16, secret: &Secret, provider: Rc<dyn CryptoProvider>) -> Self {
+        let mut key: [u8; CHACHA20_POLY1305_KEY_SIZE] = [0; CHACHA20_POLY1305_KEY_SIZE];
+        let mut iv: [u8; CHACHA20_POLY1305_NONCE_SIZE] = [0; CHACHA20_POLY1305_NONCE_SIZE];
+        let mut sn_key: [u8; CHACHA20_POLY1305_KEY_SIZE] = [0; CHACHA20_POLY1305_KEY_SIZE];
+        hkdf_expand_prefixed_label(secret, DTLS13_LABEL_PREFIX, b"key", &[], &mut key);
+        hkdf_expand_prefixed_label(secret, DTLS13_LABEL_PREFIX, b"iv", &[], &mut iv);
+        hkdf_expand_prefixed_label(secret, DTLS13_LABEL_PREFIX, b"sn", &[], &mut sn_key);
+        Self {
+            epoch,
+            key,
+            iv,
+            sn_key,
+            next_seq: 0,
+            highest_seq: None,
+            replay_window: 0,
+            provider,
+        }
+    }
+
+    /// Builds an encrypted record that carries [data] of [content_type], and returns it with its sequence number.
+    fn seal(&mut self, content_type: u8, data: &[u8]) -> Result<(Vec<u8>, u64), Fail> {
+        if self.next_seq > MAX_RECORD_SEQ {
+            let cause: &str = "record sequence number wrapped around";
+            error!("seal(): {}", cause);
+            return Err(Fail::new(libc::EOVERFLOW, cause));
+        }
+        let seq: u64 = self.next_seq;
+        self.next_seq += 1;
+
+        let mut payload: Vec<u8> = Vec::with_capacity(data.len() + 1 + CHACHA20_POLY1305_TAG_SIZE);
+        payload.extend_from_slice(data);
+        payload.push(content_type);
+        let seq_bytes: [u8; 2] = (seq as u16).to_be_bytes();
+        let len: [u8; 2] = ((payload.len() + CHACHA20_POLY1305_TAG_SIZE) as u16).to_be_bytes();
+        let mut header: [u8; CIPHERTEXT_HEADER_SIZE] = [
+            UNIFIED_HEADER_FIXED_BITS
+                | UNIFIED_HEADER_SEQ16
+                | UNIFIED_HEADER_LENGTH
+                | (self.epoch as u8 & UNIFIED_HEADER_EPOCH_MASK),
+            seq_bytes[0],
+            seq_bytes[1],
+            len[0],
+            len[1],
+        ];
+        self.provider.aead_seal(
+            &AeadKey::ChaCha20Poly1305(self.key),
+            &self.nonce(seq),
+            &header,
+            &mut payload,
+        );
+
+        // The sequence number is masked once the record is encrypted.
+        let mask: [u8; 2] = self.mask(&payload[..SAMPLE_SIZE]);
+        header[1] ^= mask[0];
+        header[2] ^= mask[1];
+        let mut record: Vec<u8> = Vec::with_capacity(header.len() + payload.len());
+        record.extend_from_slice(&header);
+        record.extend_from_slice(&payload);
+        Ok((record, seq))
+    }
+
+    /// Decrypts the [payload] of the record with [header], whose sequence number takes [seq_len] bytes. Returns the
+    /// sequence number and the content type of the record, unless it fails to decrypt or was received before. Padding
+    /// is stripped from [payload], which is left with the content of the record.
+    fn open(&mut self, header: &mut [u8], seq_len: usize, payload: &mut Vec<u8>) -> Option<(u64, u8)> {
+        if payload.len() < SAMPLE_SIZE.max(CHACHA20_POLY1305_TAG_SIZE + 1) {
+            return None;
+        }
+        let mask: [u8; 2] = self.mask(&payload[..SAMPLE_SIZE]);
+        let mut partial_seq: u64 = 0;
+        for i in 0..seq_len {
+            header[1 + i] ^= mask[i];
+            partial_seq = (partial_seq << 8) | header[1 + i] as u64;
+        }
+        let seq: u64 = self.reconstruct_seq(partial_seq, 8 * seq_len as u32);
+        if !self.is_fresh(seq) {
+            return None;
+        }
+        self.provider
+            .aead_open(&AeadKey::ChaCha20Poly1305(self.key), &self.nonce(seq), header, payload)
+            .ok()?;
+        self.mark_received(seq);
+        // The content type is the last byte that is not zero.
+        while let Some(content_type) = payload.pop() {
+            if content_type != 0 {
+                return Some((seq, content_type));
+            }
+        }
+        None
+    }
+
+    /// Computes the nonce of the record with sequence number [seq].
+    fn nonce(&self, seq: u64) -> [u8; CHACHA20_POLY1305_NONCE_SIZE] {
+        let mut nonce: [u8; CHACHA20_POLY1305_NONCE_SIZE] = self.iv;
+        for (byte, seq_byte) in nonce[4..].iter_mut().zip(seq.to_be_bytes()) {
+            *byte ^= seq_byte;
+        }
+        nonce
+    }
+
+    /// Computes the mask of the sequence number of a record from a [sample] of its ciphertext.
+    fn mask(&self, sample: &[u8]) -> [u8; 2] {
+        let counter: u32 = u32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]);
+        let mut nonce: [u8; CHACHA20_POLY1305_NONCE_SIZE] = [0; CHACHA20_POLY1305_NONCE_SIZE];
+        nonce.copy_from_slice(&sample[4..SAMPLE_SIZE]);
+        let mut mask: [u8; 2] = [0; 2];
+        chacha20_xor(&self.sn_key, counter, &nonce, &mut mask);
+        mask
+    }
+
+    /// Recovers the full sequence number from its low [bits], picking the one that is closest to the next one that is
+    /// expected (RFC 9147, section 4.2.2).
+    fn reconstruct_seq(&self, partial_seq: u64, bits: u32) -> u64 {
+        let expected: u64 = self.highest_seq.map_or(0, |seq| seq + 1);
+        let window: u64 = 1 << bits;
+        let candidate: u64 = (expected & !(window - 1)) | partial_seq;
+        [
+            candidate.checked_sub(window),
+            Some(candidate),
+            candidate.checked_add(window).filter(|seq| *seq <= MAX_RECORD_SEQ),
+        ]
+        .into_iter()
+        .flatten()
+        .min_by_key(|seq| seq.abs_diff(expected))
+        .unwrap_or(candidate)
+    }
+
+    /// Checks that the record with sequence number [seq] was not received before (RFC 9147, section 4.5.1).
+    fn is_fresh(&self, seq: u64) -> bool {
+        match self.highest_seq {
+            None => true,
+            Some(highest_seq) if seq > highest_seq => true,
+            Some(highest_seq) => {
+                let age: u64 = highest_seq - seq;
+                age != 0 && age <= REPLAY_WINDOW_SIZE && self.replay_window & (1 << (age - 1)) == 0
+            },
+        }
+    }
+
+    /// Records that the record with sequence number [seq] was received.
+    fn mark_received(&mut self, seq: u64) {
+        match self.highest_seq {
+            Some(highest_seq) if seq <= highest_seq => self.replay_window |= 1 << (highest_seq - seq - 1),
+            Some(highest_seq) => {
+                let shift: u64 = seq - highest_seq;
+                self.replay_window = if shift >= REPLAY_WINDOW_SIZE {
+                    0
+                } else {
+                    (self.replay_window << shift) | (1 << (shift - 1))
+                };
+                self.highest_seq = Some(seq);
+            },
+            None => self.highest_seq = Some(seq),
+        }
+    }
+}
+
+impl PartialMessage {
+    fn new(epoch: u16, msg_type: u8, length: usize) -> Self {
+        Self {
+            epoch,
+            msg_type,
+            body: vec![0; length],
+            received: vec![false; length],
+            missing: length,
+        }
+    }
+
+    /// Copies the data of [fragment] into the body of the message.
+    fn fill(&mut self, fragment: &Fragment) {
+        let range: ::std::ops::Range<usize> = fragment.offset..(fragment.offset + fragment.data.len());
+        self.body[range.clone()].copy_from_slice(fragment.data);
+        for received in &mut self.received[range] {
+            if !*received {
+                *received = true;
+                self.missing -= 1;
+            }
+        }
+    }
+}
+
+impl DtlsSession {
+    /// Starts the client side of an association, which queues its ClientHello right away.
+    pub fn new_client(config: TlsConfig, now: Instant) -> Self {
+        let provider: Rc<dyn CryptoProvider> = config.crypto_provider();
+        let mut session: Self = Self::create(TlsRole::Client, TlsSession::new_dtls(TlsRole::Client, config), provider);
+        // Queuing the first flight cannot fail, because it goes out in plaintext.
+        if let Err(e) = session.send_flight(now) {
+            warn!("new_client(): failed to send ClientHello ({:?})", e);
+        }
+        session
+    }
+
+    fn create(role: TlsRole, tls: TlsSession, provider: Rc<dyn CryptoProvider>) -> Self {
+        Self {
+            role,
+            tls,
+            provider,
+            read_keys: Vec::new(),
+            write_keys: Vec::new(),
+            plaintext_seq: 0,
+            next_send_message_seq: 0,
+            next_receive_message_seq: 0,
+            partial: BTreeMap::new(),
+            flight: None,
+            retransmit: false,
+            acks: Vec::new(),
+            outgoing: VecDeque::new(),
+            plaintext: VecDeque::new(),
+            peer_closed: false,
+            closed: false,
+            error: None,
+        }
+    }
+
+    /// Checks whether the handshake completed.
+    pub fn is_handshake_complete(&self) -> bool {
+        self.tls.is_handshake_complete()
+    }
+
+    /// Checks whether the peer closed its side of the association.
+    pub fn is_peer_closed(&self) -> bool {
+        self.peer_closed
+    }
+
+    /// Fails with the error that aborted the association, if any.
+    pub fn check_error(&self) -> Result<(), Fail> {
+        match self.error.as_ref() {
+            Some(e) => Err(e.clone()),
+            None => Ok(()),
+        }
+    }
+
+    /// Processes the [datagram] that arrived from the peer at [now]. Records that are not valid are dropped. On
+    /// failure, the association is aborted and an alert for the peer is queued.
+    pub fn receive(&mut self, datagram: &[u8], now: Instant) -> Result<(), Fail> {
+        self.check_error()?;
+        let mut offset: usize = 0;
+        while offset < datagram.len() {
+            match self.process_record(&datagram[offset..]) {
+                Ok(Some(len)) => offset += len,
+                // The rest of the datagram is dropped.
+                Ok(None) => break,
+                Err(e) => return Err(self.abort(e)),
+            }
+        }
+        if let Err(e) = self.send_flight(now) {
+            return Err(self.abort(e));
+        }
+        if ::std::mem::take(&mut self.retransmit) && self.flight.is_some() {
+            self.transmit_flight()?;
+        }
+        if !self.acks.is_empty() && self.is_handshake_complete() {
+            self.send_ack()?;
+        }
+        Ok(())
+    }
+
+    /// Gets the time at which [DtlsSession::on_timeout] must be called next, if the handshake is pending.
+    pub fn next_timeout(&self) -> Option<Instant> {
+        self.flight.as_ref().map(|flight| flight.deadline)
+    }
+
+    /// Retransmits the last flight of this side if it was not acknowledged by [now]. The association is aborted once
+    /// the peer does not answer for too long.
+    pub fn on_timeout(&mut self, now: Instant) -> Result<(), Fail> {
+        self.check_error()?;
+        let flight: &mut Flight = match self.flight.as_mut() {
+            Some(flight) if flight.deadline <= now => flight,
+            _ => return Ok(()),
+        };
+        if flight.retransmissions == MAX_RETRANSMISSIONS {
+            self.flight = None;
+            let cause: &str = "peer did not answer the handshake";
+            warn!("on_timeout(): {}", cause);
+            let e: Fail = Fail::new(libc::ETIMEDOUT, cause);
+            self.error = Some(e.clone());
+            return Err(e);
+        }
+        flight.retransmissions += 1;
+        flight.timeout = (flight.timeout * 2).min(MAX_RETRANSMISSION_TIMEOUT);
+        flight.deadline = now + flight.timeout;
+        self.transmit_flight()
+    }
+
+    /// Encrypts [data] for the peer, in one datagram.
+    pub fn write(&mut self, data: &[u8]) -> Result<(), Fail> {
+        self.check_error()?;
+        if !self.is_handshake_complete() || self.closed {
+            let cause: &str = "association is not established";
+            warn!("write(): {}", cause);
+            return Err(Fail::new(libc::ENOTCONN, cause));
+        }
+        if data.len() > MAX_PLAINTEXT_SIZE {
+            let cause: String = format!("datagram is too long (len={:?})", data.len());
+            warn!("write(): {}", cause);
+            return Err(Fail::new(libc::EMSGSIZE, &cause));
+        }
+        let (record, _): (Vec<u8>, u64) = self.seal_record(EPOCH_APPLICATION, CONTENT_TYPE_APPLICATION_DATA, data)?;
+        self.outgoing.push_back(record);
+        Ok(())
+    }
+
+    /// Takes the next datagram of application data that arrived from the peer, if there is any.
+    pub fn read(&mut self) -> Option<Vec<u8>> {
+        self.plaintext.pop_front()
+    }
+
+    /// Closes this side of the association by queuing a close_notify alert for the peer.
+    pub fn close(&mut self) {
+        if !self.closed && self.error.is_none() {
+            self.send_alert(ALERT_LEVEL_WARNING, ALERT_CLOSE_NOTIFY);
+            self.closed = true;
+        }
+    }
+
+    /// Takes the next datagram that is bound to the peer, if there is any.
+    pub fn take_output(&mut self) -> Option<Vec<u8>> {
+        self.outgoing.pop_front()
+    }
+
+    /// Aborts the association with [e], queuing the alert of the TLS session for the peer, if any.
+    fn abort(&mut self, e: Fail) -> Fail {
+        warn!("abort(): {:?}", e);
+        if let Some(alert) = self.tls.handshake_alert() {
+            self.send_alert(ALERT_LEVEL_FATAL, alert);
+        }
+        self.flight = None;
+        self.error = Some(e.clone());
+        e
+    }
+
+    /// Processes the record at the start of [buf] and returns its length, or None if the rest of the datagram must be
+    /// dropped.
+    fn process_record(&mut self, buf: &[u8]) -> Result<Option<usize>, Fail> {
+        if buf[0] & UNIFIED_HEADER_FIXED_MASK == UNIFIED_HEADER_FIXED_BITS {
+            return self.process_ciphertext(buf);
+        }
+        if buf.len() < PLAINTEXT_HEADER_SIZE {
+            return Ok(None);
+        }
+        let content_type: u8 = buf[0];
+        let epoch: u16 = u16::from_be_bytes([buf[3], buf[4]]);
+        let seq: u64 = u64::from_be_bytes([0, 0, buf[5], buf[6], buf[7], buf[8], buf[9], buf[10]]);
+        let len: usize = u16::from_be_bytes([buf[11], buf[12]]) as usize;
+        let end: usize = PLAINTEXT_HEADER_SIZE + len;
+        if end > buf.len() {
+            return Ok(None);
+        }
+        let payload: &[u8] = &buf[PLAINTEXT_HEADER_SIZE..end];
+        match content_type {
+            CONTENT_TYPE_HANDSHAKE if epoch == EPOCH_INITIAL => self.process_handshake(EPOCH_INITIAL, seq, payload)?,
+            // Plaintext alerts can only be trusted as much as the handshake that they abort.
+            CONTENT_TYPE_ALERT if epoch == EPOCH_INITIAL && !self.is_handshake_complete() => {
+                self.process_alert(payload)?
+            },
+            _ => debug!(
+                "process_record(): dropping plaintext record (content_type={:?})",
+                content_type
+            ),
+        }
+        Ok(Some(end))
+    }
+
+    /// Processes the encrypted record at the start of [buf] and returns its length, or None if the rest of the datagram
+    /// must be dropped.
+    fn process_ciphertext(&mut self, buf: &[u8]) -> Result<Option<usize>, Fail> {
+        let flags: u8 = buf[0];
+        if flags & UNIFIED_HEADER_CID != 0 {
+            return Ok(None);
+        }
+        let seq_len: usize = if flags & UNIFIED_HEADER_SEQ16 != 0 { 2 } else { 1 };
+        let mut header_len: usize = 1 + seq_len;
+        let len: usize = if flags & UNIFIED_HEADER_LENGTH != 0 {
+            if buf.len() < header_len + 2 {
+                return Ok(None);
+            }
+            header_len += 2;
+            u16::from_be_bytes([buf[header_len - 2], buf[header_len - 1]]) as usize
+        } else {
+            buf.len().saturating_sub(header_len)
+        };
+        let end: usize = header_len + len;
+        if end > buf.len() {
+            return Ok(None);
+        }
+        let keys: &mut EpochKeys = match self
+            .read_keys
+            .iter_mut()
+            .find(|keys| keys.epoch as u8 & UNIFIED_HEADER_EPOCH_MASK == flags & UNIFIED_HEADER_EPOCH_MASK)
+        {
+            Some(keys) => keys,
+            None => return Ok(Some(end)),
+        };
+        let mut header: Vec<u8> = buf[..header_len].to_vec();
+        let mut payload: Vec<u8> = buf[header_len..end].to_vec();
+        let (seq, content_type): (u64, u8) = match keys.open(&mut header, seq_len, &mut payload) {
+            Some(opened) => opened,
+            None => {
+                debug!("process_ciphertext(): dropping record that failed to decrypt");
+                return Ok(Some(end));
+            },
+        };
+        let epoch: u16 = keys.epoch;
+        match content_type {
+            CONTENT_TYPE_HANDSHAKE => self.process_handshake(epoch, seq, &payload)?,
+            CONTENT_TYPE_ALERT => self.process_alert(&payload)?,
+            CONTENT_TYPE_APPLICATION_DATA if epoch == EPOCH_APPLICATION && !self.peer_closed => {
+                self.plaintext.push_back(payload)
+            },
+            CONTENT_TYPE_ACK => self.process_ack(&payload),
+            _ => debug!(
+                "process_ciphertext(): dropping record (content_type={:?})",
+                content_type
+            ),
+        }
+        Ok(Some(end))
+    }
+
+    /// Processes an alert from the peer.
+    fn process_alert(&mut self, payload: &[u8]) -> Result<(), Fail> {
+        match payload {
+            [_, ALERT_CLOSE_NOTIFY] => {
+                self.peer_closed = true;
+                Ok(())
+            },
+            [ALERT_LEVEL_FATAL, alert] => {
+                let cause: String = format!("peer aborted the association (alert={:?})", alert);
+                warn!("process_alert(): {}", cause);
+                Err(Fail::new(libc::ECONNRESET, &cause))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Processes an acknowledgement, which acknowledges the last flight of this side if it lists any of its records.
+    fn process_ack(&mut self, payload: &[u8]) {
+        let records: Vec<(u64, u64)> = match parse_ack(payload) {
+            Ok(records) => records,
+            Err(_) => {
+                debug!("process_ack(): dropping malformed acknowledgement");
+                return;
+            },
+        };
+        let acked: bool = match self.flight.as_ref() {
+            Some(flight) => records
+                .iter()
+                .any(|(epoch, seq)| flight.records.contains(&(*epoch as u16, *seq))),
+            None => false,
+        };
+        if acked {
+            self.flight = None;
+        }
+    }
+
+    /// Processes the handshake fragments in the [payload] of the record with sequence number [record_seq] of [epoch].
+    fn process_handshake(&mut self, epoch: u16, record_seq: u64, payload: &[u8]) -> Result<(), Fail> {
+        let mut reader: Reader = Reader::new(payload);
+        while !reader.is_empty() {
+            let fragment: Fragment = match parse_fragment(&mut reader) {
+                Ok(fragment) => fragment,
+                Err(_) => {
+                    debug!("process_handshake(): dropping malformed handshake fragment");
+                    return Ok(());
+                },
+            };
+            self.process_fragment(epoch, record_seq, fragment)?;
+        }
+        Ok(())
+    }
+
+    /// Reassembles the handshake messages of the peer and hands them to the TLS session in order.
+    fn process_fragment(&mut self, epoch: u16, record_seq: u64, fragment: Fragment) -> Result<(), Fail> {
+        if fragment.message_seq < self.next_receive_message_seq {
+            // The peer sent a flight again, because the answer of this side was lost. A server acknowledges the last
+            // flight of the client, since it has nothing else to answer it with.
+            if self.role == TlsRole::Server && self.is_handshake_complete() {
+                self.acks.push((epoch, record_seq));
+            } else {
+                self.retransmit = true;
+            }
+            return Ok(());
+        }
+        if fragment.message_seq >= self.next_receive_message_seq.saturating_add(MAX_BUFFERED_MESSAGES) {
+            return Ok(());
+        }
+        let message: &mut PartialMessage = self
+            .partial
+            .entry(fragment.message_seq)
+            .or_insert_with(|| PartialMessage::new(epoch, fragment.msg_type, fragment.length));
+        if message.epoch != epoch || message.msg_type != fragment.msg_type || message.body.len() != fragment.length {
+            debug!("process_fragment(): dropping inconsistent handshake fragment");
+            return Ok(());
+        }
+        message.fill(&fragment);
+        if epoch != EPOCH_INITIAL && self.role == TlsRole::Server {
+            self.acks.push((epoch, record_seq));
+        }
+
+        while let Some(message) = self.partial.remove(&self.next_receive_message_seq) {
+            if message.missing != 0 {
+                self.partial.insert(self.next_receive_message_seq, message);
+                break;
+            }
+            self.next_receive_message_seq += 1;
+            // The peer moved on to its next flight, which acknowledges the last flight of this side.
+            self.flight = None;
+            let mut msg: Vec<u8> = Vec::with_capacity(4 + message.body.len());
+            msg.push(message.msg_type);
+            msg.extend_from_slice(&(message.body.len() as u32).to_be_bytes()[1..]);
+            msg.extend_from_slice(&message.body);
+            self.tls.receive_handshake(epoch_level(message.epoch), &msg)?;
+            // Records that follow in the same datagram may already be protected with new keys.
+            self.install_keys();
+        }
+        Ok(())
+    }
+
+    /// Installs the keys that the TLS session derived.
+    fn install_keys(&mut self) {
+        for (epoch, level) in [
+            (EPOCH_HANDSHAKE, EncryptionLevel::Handshake),
+            (EPOCH_APPLICATION, EncryptionLevel::Application),
+        ] {
+            if let Some(secret) = self.tls.take_read_secret(level) {
+                self.read_keys
+                    .push(EpochKeys::new(epoch, &secret, self.provider.clone()));
+            }
+            if let Some(secret) = self.tls.take_write_secret(level) {
+                self.write_keys
+                    .push(EpochKeys::new(epoch, &secret, self.provider.clone()));
+            }
+        }
+    }
+
+    /// Sends the handshake messages that the TLS session queued as the next flight of this side.
+    fn send_flight(&mut self, now: Instant) -> Result<(), Fail> {
+        self.install_keys();
+        let mut messages: Vec<(u16, Vec<u8>)> = Vec::new();
+        for (epoch, level) in [
+            (EPOCH_INITIAL, EncryptionLevel::Initial),
+            (EPOCH_HANDSHAKE, EncryptionLevel::Handshake),
+            (EPOCH_APPLICATION, EncryptionLevel::Application),
+        ] {
+            let output: Vec<u8> = match self.tls.take_handshake(level) {
+                Some(output) => output,
+                None => continue,
+            };
+            // Handshake messages of the TLS session have a 4-byte header, which is extended with the sequence number
+            // and the fragment of the message.
+            let mut reader: Reader = Reader::new(&output);
+            while !reader.is_empty() {
+                let msg_type: u8 = reader.u8()?;
+                let length: usize = reader.u24()? as usize;
+                let body: &[u8] = reader.bytes(length)?;
+                let mut message: Vec<u8> = Vec::with_capacity(HANDSHAKE_HEADER_SIZE + length);
+                put_fragment_header(&mut message, msg_type, length, self.next_send_message_seq, 0, length);
+                message.extend_from_slice(body);
+                self.next_send_message_seq += 1;
+                messages.push((epoch, message));
+            }
+        }
+        if messages.is_empty() {
+            return Ok(());
+        }
+        self.flight = Some(Flight {
+            messages,
+            records: Vec::new(),
+            timeout: INITIAL_RETRANSMISSION_TIMEOUT,
+            deadline: now + INITIAL_RETRANSMISSION_TIMEOUT,
+            retransmissions: 0,
+        });
+        self.transmit_flight()
+    }
+
+    /// Sends the last flight of this side, fragmenting its messages to fit in datagrams.
+    fn transmit_flight(&mut self) -> Result<(), Fail> {
+        let messages: Vec<(u16, Vec<u8>)> = match self.flight.as_ref() {
+            Some(flight) => flight.messages.clone(),
+            None => return Ok(()),
+        };
+        let mut records: Vec<(u16, u64)> = Vec::new();
+        let mut datagram: Vec<u8> = Vec::new();
+        for (epoch, message) in messages {
+            let overhead: usize = if epoch == EPOCH_INITIAL {
+                PLAINTEXT_HEADER_SIZE
+            } else {
+                CIPHERTEXT_HEADER_SIZE + 1 + CHACHA20_POLY1305_TAG_SIZE
+            };
+            let max_fragment_size: usize = DTLS_MTU - overhead - HANDSHAKE_HEADER_SIZE;
+            let body: &[u8] = &message[HANDSHAKE_HEADER_SIZE..];
+            let message_seq: u16 = u16::from_be_bytes([message[4], message[5]]);
+            let mut offset: usize = 0;
+            loop {
+                let len: usize = max_fragment_size.min(body.len() - offset);
+                let mut fragment: Vec<u8> = Vec::with_capacity(HANDSHAKE_HEADER_SIZE + len);
+                put_fragment_header(&mut fragment, message[0], body.len(), message_seq, offset, len);
+                fragment.extend_from_slice(&body[offset..(offset + len)]);
+                let (record, seq): (Vec<u8>, u64) = self.seal_record(epoch, CONTENT_TYPE_HANDSHAKE, &fragment)?;
+                records.push((epoch, seq));
+                if !datagram.is_empty() && datagram.len() + record.len() > DTLS_MTU {
+                    self.outgoing.push_back(::std::mem::take(&mut datagram));
+                }
+                datagram.extend_from_slice(&record);
+                offset += len;
+                if offset >= body.len() {
+                    break;
+                }
+            }
+        }
+        if !datagram.is_empty() {
+            self.outgoing.push_back(datagram);
+        }
+        if let Some(flight) = self.flight.as_mut() {
+            flight.records = records;
+        }
+        Ok(())
+    }
+
+    /// Acknowledges the records that carried encrypted handshake messages from the peer.
+    fn send_ack(&mut self) -> Result<(), Fail> {
+        let acks: Vec<(u16, u64)> = ::std::mem::take(&mut self.acks);
+        let mut body: Vec<u8> = Vec::with_capacity(2 + 16 * acks.len());
+        put_vec(&mut body, 2, |records| {
+            for (epoch, seq) in acks {
+                records.extend_from_slice(&(epoch as u64).to_be_bytes());
+                records.extend_from_slice(&seq.to_be_bytes());
+            }
+        });
+        let (record, _): (Vec<u8>, u64) = self.seal_record(EPOCH_APPLICATION, CONTENT_TYPE_ACK, &body)?;
+        self.outgoing.push_back(record);
+        Ok(())
+    }
+
+    /// Sends an alert of [level] with [description] in the latest epoch of this side.
+    fn send_alert(&mut self, level: u8, description: u8) {
+        let epoch: u16 = self.write_keys.last().map_or(EPOCH_INITIAL, |keys| keys.epoch);
+        match self.seal_record(epoch, CONTENT_TYPE_ALERT, &[level, description]) {
+            Ok((record, _)) => self.outgoing.push_back(record),
+            Err(e) => warn!("send_alert(): failed to send alert ({:?})", e),
+        }
+    }
+
+    /// Builds a record that carries [data] of [content_type] in [epoch], and returns it with its sequence number.
+    fn seal_record(&mut self, epoch: u16, content_type: u8, data: &[u8]) -> Result<(Vec<u8>, u64), Fail> {
+        if epoch == EPOCH_INITIAL {
+            let seq: u64 = self.plaintext_seq;
+            self.plaintext_seq += 1;
+            let mut record: Vec<u8> = Vec::with_capacity(PLAINTEXT_HEADER_SIZE + data.len());
+            put_plaintext_header(&mut record, content_type, seq, data.len());
+            record.extend_from_slice(data);
+            return Ok((record, seq));
+        }
+        let keys: &mut EpochKeys = expect_some!(
+            self.write_keys.iter_mut().find(|keys| keys.epoch == epoch),
+            "keys of an epoch are installed before records are sent in it"
+        );
+        keys.seal(content_type, data)
+    }
+}
+
+impl DtlsCookieGuard {
+    /// Creates a guard with a secret that is drawn from [provider], which also computes the MACs of cookies.
+    pub fn new(provider: Rc<dyn CryptoProvider>) -> Self {
+        let mut secret: Secret = [0; SHA256_DIGEST_SIZE];
+        provider.fill_random(&mut secret);
+        Self { secret, provider }
+    }
+
+    /// Decides what to do with the [datagram] that arrived at [now] from a client without a session, whose encoded
+    /// address is [peer]. Sessions that are accepted run with [config].
+    pub fn admit(&self, config: &TlsConfig, peer: &[u8], datagram: &[u8], now: Instant) -> DtlsAdmission {
+        let hello: FirstHello = match parse_first_hello(datagram) {
+            Ok(hello) => hello,
+            Err(e) => {
+                debug!("admit(): dropping datagram ({:?})", e);
+                return DtlsAdmission::Drop;
+            },
+        };
+        let cookie: Vec<u8> = match dtls_client_hello_cookie(&hello.msg) {
+            Ok(Some(cookie)) => cookie,
+            Ok(None) => return DtlsAdmission::Retry(self.hello_retry_request(peer, &hello)),
+            Err(e) => {
+                debug!("admit(): dropping datagram ({:?})", e);
+                return DtlsAdmission::Drop;
+            },
+        };
+        let first_hello_hash: Secret = match cookie.get(..SHA256_DIGEST_SIZE).map(|hash| hash.try_into()) {
+            Some(Ok(hash)) if cookie.len() == COOKIE_SIZE => hash,
+            _ => {
+                debug!("admit(): dropping ClientHello with a malformed cookie");
+                return DtlsAdmission::Drop;
+            },
+        };
+        if !constant_time_eq(&cookie[SHA256_DIGEST_SIZE..], &self.mac(peer, &first_hello_hash)) {
+            debug!("admit(): dropping ClientHello with a cookie that is not valid");
+            return DtlsAdmission::Drop;
+        }
+
+        // The second ClientHello picks up where the HelloRetryRequest left off.
+        let tls: TlsSession = TlsSession::new_dtls_retried(config.clone(), &first_hello_hash, &cookie);
+        let mut session: DtlsSession = DtlsSession::create(TlsRole::Server, tls, config.crypto_provider());
+        session.next_receive_message_seq = hello.message_seq;
+        session.next_send_message_seq = hello.message_seq;
+        match session.receive(datagram, now) {
+            Ok(()) => DtlsAdmission::Accept(session),
+            Err(e) => {
+                warn!("admit(): handshake failed ({:?})", e);
+                DtlsAdmission::Refuse(session.outgoing.into_iter().flatten().collect())
+            },
+        }
+    }
+
+    /// Builds the datagram that answers the first ClientHello [hello] of [peer] with a HelloRetryRequest. It echoes
+    /// the record and message sequence numbers of the ClientHello, since the server keeps no state of its own.
+    fn hello_retry_request(&self, peer: &[u8], hello: &FirstHello) -> Vec<u8> {
+        let first_hello_hash: Secret = Sha256::digest(&hello.msg);
+        let mut cookie: Vec<u8> = Vec::with_capacity(COOKIE_SIZE);
+        cookie.extend_from_slice(&first_hello_hash);
+        cookie.extend_from_slice(&self.mac(peer, &first_hello_hash));
+        let msg: Vec<u8> = dtls_hello_retry_request(&cookie);
+        let body: &[u8] = &msg[4..];
+
+        let mut fragment: Vec<u8> = Vec::with_capacity(HANDSHAKE_HEADER_SIZE + body.len());
+        put_fragment_header(&mut fragment, msg[0], body.len(), hello.message_seq, 0, body.len());
+        fragment.extend_from_slice(body);
+        let mut datagram: Vec<u8> = Vec::with_capacity(PLAINTEXT_HEADER_SIZE + fragment.len());
+        put_plaintext_header(&mut datagram, CONTENT_TYPE_HANDSHAKE, hello.record_seq, fragment.len());
+        datagram.extend_from_slice(&fragment);
+        datagram
+    }
+
+    /// Computes the MAC that binds a cookie to [peer] and the hash of its first ClientHello.
+    fn mac(&self, peer: &[u8], first_hello_hash: &Secret) -> Secret {
+        self.provider.hmac_sha256(&self.secret, &[peer, first_hello_hash])
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Gets the encryption level of the TLS session that handshake messages of [epoch] belong to.
+fn epoch_level(epoch: u16) -> EncryptionLevel {
+    match epoch {
+        EPOCH_INITIAL => EncryptionLevel::Initial,
+        EPOCH_HANDSHAKE => EncryptionLevel::Handshake,
+        _ => EncryptionLevel::Application,
+    }
+}
+
+/// Reads the next handshake fragment from [reader].
+fn parse_fragment<'a>(reader: &mut Reader<'a>) -> Result<Fragment<'a>, Fail> {
+    let msg_type: u8 = reader.u8()?;
+    let length: usize = reader.u24()? as usize;
+    let message_seq: u16 = reader.u16()?;
+    let offset: usize = reader.u24()? as usize;
+    let fragment_length: usize = reader.u24()? as usize;
+    let data: &[u8] = reader.bytes(fragment_length)?;
+    if length > MAX_HANDSHAKE_MESSAGE_SIZE || offset + fragment_length > length {
+        let cause: String = format!(
+            "handshake fragment out of bounds (length={:?}, offset={:?}, fragment_length={:?})",
+            length, offset, fragment_length
+        );
+        warn!("parse_fragment(): {}", cause);
+        return Err(Fail::new(libc::EBADMSG, &cause));
+    }
+    Ok(Fragment {
+        msg_type,
+        length,
+        message_seq,
+        offset,
+        data,
+    })
+}
+
+/// Reads the record numbers that the acknowledgement [payload] lists (RFC 9147, section 7).
+fn parse_ack(payload: &[u8]) -> Result<Vec<(u64, u64)>, Fail> {
+    let mut reader: Reader = Reader::new(payload);
+    let mut records: Reader = Reader::new(reader.vec16()?);
+    reader.finish()?;
+    let mut record_numbers: Vec<(u64, u64)> = Vec::with_capacity(records.remaining() / 16);
+    while !records.is_empty() {
+        let epoch: u64 = ((records.u32()? as u64) << 32) | records.u32()? as u64;
+        let seq: u64 = ((records.u32()? as u64) << 32) | records.u32()? as u64;
+        record_numbers.push((epoch, seq));
+    }
+    Ok(record_numbers)
+}
+
+/// Reads the ClientHello that starts a handshake from [datagram], which must carry it whole in its first record.
+fn parse_first_hello(datagram: &[u8]) -> Result<FirstHello, Fail> {
+    let mut reader: Reader = Reader::new(datagram);
+    let content_type: u8 = reader.u8()?;
+    let _legacy_version: u16 = reader.u16()?;
+    let epoch: u16 = reader.u16()?;
+    let record_seq: u64 = ((reader.u16()? as u64) << 32) | reader.u32()? as u64;
+    let mut record: Reader = Reader::new(reader.vec16()?);
+    if content_type != CONTENT_TYPE_HANDSHAKE || epoch != EPOCH_INITIAL {
+        let cause: String = format!(
+            "datagram does not start with a handshake (content_type={:?}, epoch={:?})",
+            content_type, epoch
+        );
+        debug!("parse_first_hello(): {}", cause);
+        return Err(Fail::new(libc::EBADMSG, &cause));
+    }
+    let fragment: Fragment = parse_fragment(&mut record)?;
+    if fragment.msg_type != HANDSHAKE_CLIENT_HELLO || fragment.data.len() != fragment.length {
+        let cause: &str = "datagram does not carry a whole ClientHello";
+        debug!("parse_first_hello(): {}", cause);
+        return Err(Fail::new(libc::EBADMSG, cause));
+    }
+    let mut msg: Vec<u8> = Vec::with_capacity(4 + fragment.length);
+    msg.push(fragment.msg_type);
+    msg.extend_from_slice(&(fragment.length as u32).to_be_bytes()[1..]);
+    msg.extend_from_slice(fragment.data);
+    Ok(FirstHello {
+        record_seq,
+        message_seq: fragment.message_seq,
+        msg,
+    })
+}
+
+/// Appends the header of a fragment of a handshake message to [out].
+fn put_fragment_header(out: &mut Vec<u8>, msg_type: u8, length: usize, message_seq: u16, offset: usize, len: usize) {
+    out.push(msg_type);
+    out.extend_from_slice(&(length as u32).to_be_bytes()[1..]);
+    out.extend_from_slice(&message_seq.to_be_bytes());
+    out.extend_from_slice(&(offset as u32).to_be_bytes()[1..]);
+    out.extend_from_slice(&(len as u32).to_be_bytes()[1..]);
+}
+
+/// Appends the header of a plaintext record of [content_type] with sequence number [seq] and [len] bytes to [out].
+fn put_plaintext_header(out: &mut Vec<u8>, content_type: u8, seq: u64, len: usize) {
+    out.push(content_type);
+    out.extend_from_slice(&LEGACY_RECORD_VERSION.to_be_bytes());
+    out.extend_from_slice(&EPOCH_INITIAL.to_be_bytes());
+    out.extend_from_slice(&seq.to_be_bytes()[2..]);
+    out.extend_from_slice(&(len as u16).to_be_bytes());
+}
+
+/// Compares [a] and [b] in constant time.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::network::tls::{
+        dtls::{
+            DtlsAdmission,
+            DtlsCookieGuard,
+            DtlsSession,
+            INITIAL_RETRANSMISSION_TIMEOUT,
+        },
+        provider::SoftwareCryptoProvider,
+        TlsConfig,
+    };
+    use ::anyhow::Result;
+    use ::std::{
+        rc::Rc,
+        time::Instant,
+    };
+
+    const CLIENT_ADDRESS: &[u8] = b"10.0.0.2:50000";
+
+    /// Moves the datagrams of [from] to [to].
+    fn deliver(from: &mut DtlsSession, to: &mut DtlsSession, now: Instant) -> Result<()> {
+        while let Some(datagram) = from.take_output() {
+            to.receive(&datagram, now)?;
+        }
+        Ok(())
+    }
+
+    /// Starts a client and has [guard] answer its first ClientHello, and returns the client along with its second
+    /// ClientHello.
+    fn start_client(guard: &DtlsCookieGuard, config: &TlsConfig, now: Instant) -> Result<(DtlsSession, Vec<u8>)> {
+        let mut client: DtlsSession = DtlsSession::new_client(config.clone(), now);
+        let first_hello: Vec<u8> = client.take_output().unwrap_or_default();
+        let retry: Vec<u8> = match guard.admit(config, CLIENT_ADDRESS, &first_hello, now) {
+            DtlsAdmission::Retry(retry) => retry,
+            _ => anyhow::bail!("first ClientHello should be retried"),
+        };
+        client.receive(&retry, now)?;
+        let second_hello: Vec<u8> = client.take_output().unwrap_or_default();
+        crate::ensure_eq!(client.take_output(), None);
+        Ok((client, second_hello))
+    }
+
+    /// Runs the handshake between a new client and server.
+    fn handshake(now: Instant) -> Result<(DtlsSession, DtlsSession)> {
+        let config: TlsConfig = TlsConfig::new(b"client", &[7; 32])?;
+        let guard: DtlsCookieGuard = DtlsCookieGuard::new(Rc::new(SoftwareCryptoProvider));
+        let (mut client, second_hello): (DtlsSession, Vec<u8>) = start_client(&guard, &config, now)?;
+        let mut server: DtlsSession = match guard.admit(&config, CLIENT_ADDRESS, &second_hello, now) {
+            DtlsAdmission::Accept(server) => server,
+            _ => anyhow::bail!("second ClientHello should be accepted"),
+        };
+        deliver(&mut server, &mut client, now)?;
+        crate::ensure_eq!(client.is_handshake_complete(), true);
+        // The Finished message of the client is retransmitted until the server acknowledges it.
+        crate::ensure_eq!(client.next_timeout().is_some(), true);
+        deliver(&mut client, &mut server, now)?;
+        crate::ensure_eq!(server.is_handshake_complete(), true);
+        deliver(&mut server, &mut client, now)?;
+        crate::ensure_eq!(client.next_timeout(), None);
+        crate::ensure_eq!(server.next_timeout(), None);
+        Ok((client, server))
+    }
+
+    #[test]
+    fn test_dtls_handshake_and_data_transfer() -> Result<()> {
+        let now: Instant = Instant::now();
+        let (mut client, mut server): (DtlsSession, DtlsSession) = handshake(now)?;
+
+        // Datagram boundaries are kept.
+        client.write(b"ping")?;
+        client.write(b"pong")?;
+        deliver(&mut client, &mut server, now)?;
+        crate::ensure_eq!(server.read(), Some(b"ping".to_vec()));
+        crate::ensure_eq!(server.read(), Some(b"pong".to_vec()));
+        crate::ensure_eq!(server.read(), None);
+        server.write(&[1; 4000])?;
+        deliver(&mut server, &mut client, now)?;
+        crate::ensure_eq!(client.read(), Some(vec![1; 4000]));
+
+        client.close();
+        deliver(&mut client, &mut server, now)?;
+        crate::ensure_eq!(server.is_peer_closed(), true);
+        crate::ensure_eq!(client.write(b"late").is_err(), true);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dtls_cookie_is_bound_to_client_address() -> Result<()> {
+        let now: Instant = Instant::now();
+        let config: TlsConfig = TlsConfig::new(b"client", &[7; 32])?;
+        let guard: DtlsCookieGuard = DtlsCookieGuard::new(Rc::new(SoftwareCryptoProvider));
+        let (_, second_hello): (DtlsSession, Vec<u8>) = start_client(&guard, &config, now)?;
+        let admission: DtlsAdmission = guard.admit(&config, b"10.0.0.3:50000", &second_hello, now);
+        crate::ensure_eq!(matches!(admission, DtlsAdmission::Drop), true);
+
+        // Cookies of another guard are not valid either.
+        let other: DtlsCookieGuard = DtlsCookieGuard::new(Rc::new(SoftwareCryptoProvider));
+        let admission: DtlsAdmission = other.admit(&config, CLIENT_ADDRESS, &second_hello, now);
+        crate::ensure_eq!(matches!(admission, DtlsAdmission::Drop), true);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dtls_lost_flight_is_retransmitted() -> Result<()> {
+        let now: Instant = Instant::now();
+        let config: TlsConfig = TlsConfig::new(b"client", &[7; 32])?;
+        let guard: DtlsCookieGuard = DtlsCookieGuard::new(Rc::new(SoftwareCryptoProvider));
+        let (mut client, second_hello): (DtlsSession, Vec<u8>) = start_client(&guard, &config, now)?;
+        let mut server: DtlsSession = match guard.admit(&config, CLIENT_ADDRESS, &second_hello, now) {
+            DtlsAdmission::Accept(server) => server,
+            _ => anyhow::bail!("second ClientHello should be accepted"),
+        };
+
+        // The first flight of the server is lost, so the client sends its ClientHello again, which makes the server
+        // send its flight again.
+        while server.take_output().is_some() {}
+        client.on_timeout(now)?;
+        crate::ensure_eq!(client.take_output(), None);
+        let later: Instant = now + INITIAL_RETRANSMISSION_TIMEOUT;
+        client.on_timeout(later)?;
+        deliver(&mut client, &mut server, later)?;
+        deliver(&mut server, &mut client, later)?;
+        crate::ensure_eq!(client.is_handshake_complete(), true);
+        deliver(&mut client, &mut server, later)?;
+        crate::ensure_eq!(server.is_handshake_complete(), true);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dtls_replayed_and_tampered_records_are_dropped() -> Result<()> {
+        let now: Instant = Instant::now();
+        let (mut client, mut server): (DtlsSession, DtlsSession) = handshake(now)?;
+        client.write(b"once")?;
+        let datagram: Vec<u8> = client.take_output().unwrap_or_default();
+        server.receive(&datagram, now)?;
+        server.receive(&datagram, now)?;
+        crate::ensure_eq!(server.read(), Some(b"once".to_vec()));
+        crate::ensure_eq!(server.read(), None);
+
+        client.write(b"tampered")?;
+        let mut datagram: Vec<u8> = client.take_output().unwrap_or_default();
+        let last: usize = datagram.len() - 1;
+        datagram[last] ^= 1;
+        server.receive(&datagram, now)?;
+        crate::ensure_eq!(server.read(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dtls_psk_mismatch_is_refused() -> Result<()> {
+        let now: Instant = Instant::now();
+        let client_config: TlsConfig = TlsConfig::new(b"client", &[7; 32])?;
+        let server_config: TlsConfig = TlsConfig::new(b"client", &[8; 32])?;
+        let guard: DtlsCookieGuard = DtlsCookieGuard::new(Rc::new(SoftwareCryptoProvider));
+        let (mut client, second_hello): (DtlsSession, Vec<u8>) = start_client(&guard, &client_config, now)?;
+        let alert: Vec<u8> = match guard.admit(&server_config, CLIENT_ADDRESS, &second_hello, now) {
+            DtlsAdmission::Refuse(alert) => alert,
+            _ => anyhow::bail!("handshake should fail"),
+        };
+        let e = client.receive(&alert, now).err().unwrap();
+        crate::ensure_eq!(e.errno, libc::ECONNRESET);
+        crate::ensure_eq!(client.is_handshake_complete(), false);
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    expect_some,
+    runtime::{
+        crypto::sha256::{
+            hkdf_expand,
+            hkdf_extract,
+            hmac_sha256,
+            Sha256,
+            SHA256_DIGEST_SIZE,
+        },
+        network::tls::codec::put_vec,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Prefix of the labels of HKDF-Expand-Label in TLS 1.3 (RFC 8446, section 7.1).
+pub const TLS13_LABEL_PREFIX: &[u8] = b"tls13 ";
+
+/// Prefix of the labels of HKDF-Expand-Label in DTLS 1.3 (RFC 9147, section 5.9).
+pub const DTLS13_LABEL_PREFIX: &[u8] = b"dtls13";
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// A secret of the TLS 1.3 key schedule (RFC 8446, section 7.1).
+pub type Secret = [u8; SHA256_DIGEST_SIZE];
+
+/// Secrets of a TLS 1.3 handshake that authenticates both sides with an external pre-shared key and agrees on the
+/// traffic keys with an ephemeral Diffie-Hellman exchange.
+pub struct KeySchedule {
+    /// Prefix of the labels that secrets are derived with.
+    label_prefix: &'static [u8],
+    early_secret: Secret,
+    handshake_secret: Option<Secret>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl KeySchedule {
+    /// Starts the key schedule from the pre-shared key [psk].
+    pub fn new(psk: &[u8]) -> Self {
+        Self::with_label_prefix(psk, TLS13_LABEL_PREFIX)
+    }
+
+    /// Starts the key schedule of DTLS 1.3 from the pre-shared key [psk].
+    pub fn new_dtls(psk: &[u8]) -> Self {
+        Self::with_label_prefix(psk, DTLS13_LABEL_PREFIX)
+    }
+
+    fn with_label_prefix(psk: &[u8], label_prefix: &'static [u8]) -> Self {
+        Self {
+            label_prefix,
+            early_secret: hkdf_extract(&[0; SHA256_DIGEST_SIZE], psk),
+            handshake_secret: None,
+        }
+    }
+
+    /// Computes the binder of an external pre-shared key over [transcript_hash], which is the hash of the ClientHello
+    /// up to the binders.
+    pub fn psk_binder(&self, transcript_hash: &Secret) -> Secret {
+        let binder_key: Secret = self.derive_secret(&self.early_secret, b"ext binder", &empty_hash());
+        self.finished_verify_data(&binder_key, transcript_hash)
+    }
+
+    /// Mixes the Diffie-Hellman [shared_secret] into the key schedule and returns the client and server handshake
+    /// traffic secrets for [transcript_hash], which is the hash of the ClientHello and ServerHello.
+    pub fn handshake_secrets(&mut self, shared_secret: &[u8], transcript_hash: &Secret) -> (Secret, Secret) {
+        let derived: Secret = self.derive_secret(&self.early_secret, b"derived", &empty_hash());
+        let handshake_secret: Secret = hkdf_extract(&derived, shared_secret);
+        self.handshake_secret = Some(handshake_secret);
+        (
+            self.derive_secret(&handshake_secret, b"c hs traffic", transcript_hash),
+            self.derive_secret(&handshake_secret, b"s hs traffic", transcript_hash),
+        )
+    }
+
+    /// Returns the client and server application traffic secrets for [transcript_hash], which is the hash of the
+    /// handshake up to the Finished message of the server.
+    pub fn application_secrets(&self, transcript_hash: &Secret) -> (Secret, Secret) {
+        let handshake_secret: Secret = expect_some!(
+            self.handshake_secret,
+            "handshake secrets must be derived before the application secrets"
+        );
+        let derived: Secret = self.derive_secret(&handshake_secret, b"derived", &empty_hash());
+        let master_secret: Secret = hkdf_extract(&derived, &[0; SHA256_DIGEST_SIZE]);
+        (
+            self.derive_secret(&master_secret, b"c ap traffic", transcript_hash),
+            self.derive_secret(&master_secret, b"s ap traffic", transcript_hash),
+        )
+    }
+
+    /// Computes the verify data of a Finished message that is sent under [traffic_secret] (RFC 8446, section 4.4.4).
+    pub fn finished_verify_data(&self, traffic_secret: &Secret, transcript_hash: &Secret) -> Secret {
+        let mut finished_key: Secret = [0; SHA256_DIGEST_SIZE];
+        hkdf_expand_prefixed_label(traffic_secret, self.label_prefix, b"finished", &[], &mut finished_key);
+        hmac_sha256(&finished_key, &[transcript_hash])
+    }
+
+    /// Derives a secret from [secret] for [label] and [transcript_hash] (Derive-Secret in RFC 8446, section 7.1).
+    fn derive_secret(&self, secret: &Secret, label: &[u8], transcript_hash: &Secret) -> Secret {
+        let mut out: Secret = [0; SHA256_DIGEST_SIZE];
+        hkdf_expand_prefixed_label(secret, self.label_prefix, label, transcript_hash, &mut out);
+        out
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Gets the hash of an empty transcript.
+fn empty_hash() -> Secret {
+    Sha256::digest(&[])
+}
+
+/// Expands [secret] into [out] for [label] and [context] (HKDF-Expand-Label in RFC 8446, section 7.1).
+pub fn hkdf_expand_label(secret: &Secret, label: &[u8], context: &[u8], out: &mut [u8]) {
+    hkdf_expand_prefixed_label(secret, TLS13_LABEL_PREFIX, label, context, out)
+}
+
+/// Expands [secret] into [out] for [label] and [context], with labels that start with [label_prefix] instead of the
+/// one of TLS 1.3.
+pub fn hkdf_expand_prefixed_label(secret: &Secret, label_prefix: &[u8], label: &[u8], context: &[u8], out: &mut [u8]) {
+    let mut info: Vec<u8> = Vec::with_capacity(4 + label_prefix.len() + label.len() + context.len());
+    info.extend_from_slice(&(out.len() as u16).to_be_bytes());
+    put_vec(&mut info, 1, |info| {
+        info.extend_from_slice(label_prefix);
+        info.extend_from_slice(label);
+    });
+    put_vec(&mut info, 1, |info| info.extend_from_slice(context));
+    hkdf_expand(secret, &info, out);
+}
+
+/// Computes the next traffic secret after a key update (RFC 8446, section 7.2).
+pub fn next_traffic_secret(traffic_secret: &Secret) -> Secret {
+    let mut out: Secret = [0; SHA256_DIGEST_SIZE];
+    hkdf_expand_label(traffic_secret, b"traffic upd", &[], &mut out);
+    out
+}
@@ -0,0 +1,126 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Exports
+//======================================================================================================================
+
+pub mod codec;
+pub mod dtls;
+pub mod keys;
+pub mod offload;
+pub mod provider;
+pub mod record;
+pub mod session;
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    network::tls::provider::{
+        CryptoProvider,
+        SoftwareCryptoProvider,
+    },
+};
+use ::std::rc::Rc;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// The only cipher suite that sessions negotiate.
+pub const TLS_CHACHA20_POLY1305_SHA256: u16 = 0x1303;
+
+/// Shortest pre-shared key that is accepted, so that it has at least 128 bits of entropy if it is random.
+pub const MIN_PSK_SIZE: usize = 16;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Side of a connection that a TLS session runs on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TlsRole {
+    Client,
+    Server,
+}
+
+/// Encryption level of the handshake messages of a session that runs over QUIC (RFC 9001, section 4.1.4). Levels
+/// are ordered as they are entered in the handshake.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EncryptionLevel {
+    Initial = 0,
+    Handshake = 1,
+    Application = 2,
+}
+
+/// Configuration of TLS sessions.
+#[derive(Clone)]
+pub struct TlsConfig {
+    /// Identity of the pre-shared key, which is sent in the clear.
+    psk_identity: Vec<u8>,
+    /// Pre-shared key that both sides authenticate each other with.
+    psk: Vec<u8>,
+    /// Provider of the cryptographic operations of sessions.
+    provider: Rc<dyn CryptoProvider>,
+    /// Whether sessions may leave the encryption of transmitted records to the device.
+    hardware_offload: bool,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl TlsConfig {
+    /// Creates a configuration that authenticates peers with the pre-shared key [psk], which is known by
+    /// [psk_identity]. Sessions use the software crypto provider unless another one is set.
+    pub fn new(psk_identity: &[u8], psk: &[u8]) -> Result<Self, Fail> {
+        if psk_identity.is_empty() || psk_identity.len() > u16::MAX as usize {
+            let cause: String = format!("invalid PSK identity length (len={:?})", psk_identity.len());
+            error!("new(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        if psk.len() < MIN_PSK_SIZE {
+            let cause: String = format!("PSK is too short (len={:?})", psk.len());
+            error!("new(): {}", cause);
+            return Err(Fail::new(libc::EINVAL, &cause));
+        }
+        Ok(Self {
+            psk_identity: psk_identity.to_vec(),
+            psk: psk.to_vec(),
+            provider: Rc::new(SoftwareCryptoProvider),
+            hardware_offload: true,
+        })
+    }
+
+    /// Sets the provider of the cryptographic operations of sessions.
+    pub fn with_crypto_provider(mut self, provider: Rc<dyn CryptoProvider>) -> Self {
+        self.provider = provider;
+        self
+    }
+
+    /// Sets whether sessions may leave the encryption of transmitted records to devices that support it. This is the
+    /// default; otherwise, all records are encrypted by the crypto provider.
+    pub fn with_hardware_offload(mut self, hardware_offload: bool) -> Self {
+        self.hardware_offload = hardware_offload;
+        self
+    }
+
+    pub fn psk_identity(&self) -> &[u8] {
+        &self.psk_identity
+    }
+
+    pub fn psk(&self) -> &[u8] {
+        &self.psk
+    }
+
+    pub fn crypto_provider(&self) -> Rc<dyn CryptoProvider> {
+        self.provider.clone()
+    }
+
+    pub fn hardware_offload(&self) -> bool {
+        self.hardware_offload
+    }
+}
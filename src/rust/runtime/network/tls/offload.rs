@@ -0,0 +1,83 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    crypto::chacha20poly1305::{
+        CHACHA20_POLY1305_KEY_SIZE,
+        CHACHA20_POLY1305_NONCE_SIZE,
+    },
+    fail::Fail,
+};
+use ::std::net::SocketAddrV4;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// TLS work that a device is able to take over.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TlsOffloadCapabilities {
+    /// Cipher suites that the device encrypts transmitted records with.
+    pub tx_cipher_suites: Vec<u16>,
+}
+
+/// Connection whose transmitted records a device encrypts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TlsOffloadFlow {
+    pub local: SocketAddrV4,
+    pub remote: SocketAddrV4,
+    /// TCP sequence number of the first byte of the first record that the device encrypts.
+    pub tcp_seq: u32,
+}
+
+/// Traffic key of the records that flow in one direction of a TLS session.
+#[derive(Clone)]
+pub struct TlsOffloadKey {
+    pub cipher_suite: u16,
+    pub key: [u8; CHACHA20_POLY1305_KEY_SIZE],
+    pub iv: [u8; CHACHA20_POLY1305_NONCE_SIZE],
+    /// Sequence number of the first record that the device encrypts.
+    pub record_seq: u64,
+}
+
+/// Key that is installed on a device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TlsOffloadHandle(pub u64);
+
+//======================================================================================================================
+// Traits
+//======================================================================================================================
+
+/// Hooks of a device that encrypts TLS records inline, as they are transmitted.
+///
+/// Once a key is installed for a flow, the TLS layer hands plaintext records to the transport instead of encrypted
+/// ones: each record has its final header, its plaintext followed by the inner content type, and room for the
+/// authentication tag, which the device fills in along with the ciphertext. The device tracks record boundaries from
+/// the TCP sequence number of the flow, so it must also be able to resynchronize on retransmitted segments. Received
+/// records are always decrypted in software.
+pub trait TlsOffload {
+    /// Queries the TLS work that the device is able to take over. Sessions whose cipher suite is not listed stay in
+    /// software.
+    fn capabilities(&self) -> TlsOffloadCapabilities;
+
+    /// Installs [key] for the records that are transmitted on [flow].
+    fn install_tx_key(&self, flow: &TlsOffloadFlow, key: &TlsOffloadKey) -> Result<TlsOffloadHandle, Fail>;
+
+    /// Removes a key that was installed on the device.
+    fn remove_key(&self, handle: TlsOffloadHandle);
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl TlsOffloadCapabilities {
+    /// Checks whether the device encrypts transmitted records with [cipher_suite].
+    pub fn supports_tx(&self, cipher_suite: u16) -> bool {
+        self.tx_cipher_suites.contains(&cipher_suite)
+    }
+}
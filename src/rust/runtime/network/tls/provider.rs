@@ -0,0 +1,40 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+pub use crate::runtime::crypto::runtime::SoftwareCryptoRuntime as SoftwareCryptoProvider;
+use crate::runtime::crypto::{
+    runtime::{
+        CryptoRuntime,
+        SoftwareCryptoRuntime,
+    },
+    x25519::{
+        x25519,
+        X25519_KEY_SIZE,
+    },
+};
+
+//======================================================================================================================
+// Traits
+//======================================================================================================================
+
+/// Cryptographic operations that TLS sessions delegate, so that they can be carried out by a hardware accelerator or
+/// a third-party library instead of the software implementations of this crate. Records are protected through the
+/// [CryptoRuntime] that the provider builds on, and the provider adds the key exchange of the handshake.
+pub trait CryptoProvider: CryptoRuntime {
+    /// Computes the X25519 function of [scalar] and [point].
+    fn x25519(&self, scalar: &[u8; X25519_KEY_SIZE], point: &[u8; X25519_KEY_SIZE]) -> [u8; X25519_KEY_SIZE];
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl CryptoProvider for SoftwareCryptoRuntime {
+    fn x25519(&self, scalar: &[u8; X25519_KEY_SIZE], point: &[u8; X25519_KEY_SIZE]) -> [u8; X25519_KEY_SIZE] {
+        x25519(scalar, point)
+    }
+}
@@ -0,0 +1,174 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    crypto::{
+        chacha20poly1305::{
+            CHACHA20_POLY1305_KEY_SIZE,
+            CHACHA20_POLY1305_NONCE_SIZE,
+            CHACHA20_POLY1305_TAG_SIZE,
+        },
+        runtime::AeadKey,
+    },
+    fail::Fail,
+    network::tls::{
+        keys::{
+            hkdf_expand_label,
+            next_traffic_secret,
+            Secret,
+        },
+        offload::TlsOffloadKey,
+        provider::CryptoProvider,
+    },
+};
+use ::std::rc::Rc;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Size of the header of a TLS record.
+pub const RECORD_HEADER_SIZE: usize = 5;
+
+/// Largest plaintext that a TLS record carries.
+pub const MAX_PLAINTEXT_SIZE: usize = 1 << 14;
+
+/// Largest encrypted payload of a TLS record.
+pub const MAX_CIPHERTEXT_SIZE: usize = MAX_PLAINTEXT_SIZE + 256;
+
+/// Legacy protocol version that all TLS 1.3 records carry.
+pub const LEGACY_RECORD_VERSION: u16 = 0x0303;
+
+/// Content types of TLS records.
+pub const CONTENT_TYPE_CHANGE_CIPHER_SPEC: u8 = 20;
+pub const CONTENT_TYPE_ALERT: u8 = 21;
+pub const CONTENT_TYPE_HANDSHAKE: u8 = 22;
+pub const CONTENT_TYPE_APPLICATION_DATA: u8 = 23;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Protection of the records that flow in one direction of a TLS session, under one traffic secret.
+pub struct RecordProtection {
+    secret: Secret,
+    key: [u8; CHACHA20_POLY1305_KEY_SIZE],
+    iv: [u8; CHACHA20_POLY1305_NONCE_SIZE],
+    seq: u64,
+    provider: Rc<dyn CryptoProvider>,
+    /// Whether a device encrypts the records, as they are transmitted.
+    offloaded: bool,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl RecordProtection {
+    /// Derives the traffic key of [secret] (RFC 8446, section 7.3).
+    pub fn new(secret: Secret, provider: Rc<dyn CryptoProvider>) -> Self {
+        let mut key: [u8; CHACHA20_POLY1305_KEY_SIZE] = [0; CHACHA20_POLY1305_KEY_SIZE];
+        let mut iv: [u8; CHACHA20_POLY1305_NONCE_SIZE] = [0; CHACHA20_POLY1305_NONCE_SIZE];
+        hkdf_expand_label(&secret, b"key", &[], &mut key);
+        hkdf_expand_label(&secret, b"iv", &[], &mut iv);
+        Self {
+            secret,
+            key,
+            iv,
+            seq: 0,
+            provider,
+            offloaded: false,
+        }
+    }
+
+    /// Moves on to the next traffic secret after a key update.
+    pub fn update(&mut self) {
+        *self = Self::new(next_traffic_secret(&self.secret), self.provider.clone());
+    }
+
+    /// Gets the traffic key of the records that are sealed next, so that it can be installed on a device.
+    pub fn offload_key(&self, cipher_suite: u16) -> TlsOffloadKey {
+        TlsOffloadKey {
+            cipher_suite,
+            key: self.key,
+            iv: self.iv,
+            record_seq: self.seq,
+        }
+    }
+
+    /// Leaves the encryption of the records that are sealed from now on to a device, which the key of
+    /// [RecordProtection::offload_key] was installed on.
+    pub fn set_offloaded(&mut self) {
+        self.offloaded = true;
+    }
+
+    /// Appends a record that carries [data] of [content_type] to [out]. [data] must fit in one record. If the records
+    /// are offloaded, the record is left in plaintext with room for the authentication tag.
+    pub fn seal(&mut self, content_type: u8, data: &[u8], out: &mut Vec<u8>) -> Result<(), Fail> {
+        debug_assert!(data.len() <= MAX_PLAINTEXT_SIZE);
+        let nonce: [u8; CHACHA20_POLY1305_NONCE_SIZE] = self.next_nonce()?;
+        let mut payload: Vec<u8> = Vec::with_capacity(data.len() + 1 + CHACHA20_POLY1305_TAG_SIZE);
+        payload.extend_from_slice(data);
+        payload.push(content_type);
+        let header: [u8; RECORD_HEADER_SIZE] = record_header(
+            CONTENT_TYPE_APPLICATION_DATA,
+            payload.len() + CHACHA20_POLY1305_TAG_SIZE,
+        );
+        if self.offloaded {
+            payload.resize(payload.len() + CHACHA20_POLY1305_TAG_SIZE, 0);
+        } else {
+            self.provider
+                .aead_seal(&AeadKey::ChaCha20Poly1305(self.key), &nonce, &header, &mut payload);
+        }
+        out.extend_from_slice(&header);
+        out.extend_from_slice(&payload);
+        Ok(())
+    }
+
+    /// Decrypts the [payload] of the record with [header] and returns its content type. Padding is stripped from
+    /// [payload], which is left with the content of the record.
+    pub fn open(&mut self, header: &[u8], payload: &mut Vec<u8>) -> Result<u8, Fail> {
+        let nonce: [u8; CHACHA20_POLY1305_NONCE_SIZE] = self.next_nonce()?;
+        self.provider
+            .aead_open(&AeadKey::ChaCha20Poly1305(self.key), &nonce, header, payload)?;
+        // The content type is the last byte that is not zero.
+        while let Some(content_type) = payload.pop() {
+            if content_type != 0 {
+                return Ok(content_type);
+            }
+        }
+        let cause: &str = "record has no content type";
+        warn!("open(): {}", cause);
+        Err(Fail::new(libc::EPROTO, cause))
+    }
+
+    /// Computes the nonce of the next record (RFC 8446, section 5.3).
+    fn next_nonce(&mut self) -> Result<[u8; CHACHA20_POLY1305_NONCE_SIZE], Fail> {
+        if self.seq == u64::MAX {
+            let cause: &str = "record sequence number wrapped around";
+            error!("next_nonce(): {}", cause);
+            return Err(Fail::new(libc::EOVERFLOW, cause));
+        }
+        let mut nonce: [u8; CHACHA20_POLY1305_NONCE_SIZE] = self.iv;
+        for (byte, seq_byte) in nonce[4..].iter_mut().zip(self.seq.to_be_bytes()) {
+            *byte ^= seq_byte;
+        }
+        self.seq += 1;
+        Ok(nonce)
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Builds the header of a record of [content_type] with a payload of [len] bytes.
+pub fn record_header(content_type: u8, len: usize) -> [u8; RECORD_HEADER_SIZE] {
+    let version: [u8; 2] = LEGACY_RECORD_VERSION.to_be_bytes();
+    let len: [u8; 2] = (len as u16).to_be_bytes();
+    [content_type, version[0], version[1], len[0], len[1]]
+}
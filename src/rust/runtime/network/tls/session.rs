@@ -0,0 +1,1940 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    expect_some,
+    runtime::{
+        crypto::{
+            sha256::Sha256,
+            x25519::{
+                X25519_BASE_POINT,
+                X25519_KEY_SIZE,
+            },
+        },
+        fail::Fail,
+        network::tls::{
+            codec::{
+                put_extension,
+                put_handshake,
+                put_vec,
+                Reader,
+            },
+            keys::{
+                KeySchedule,
+                Secret,
+            },
+            offload::{
+                TlsOffload,
+                TlsOffloadFlow,
+                TlsOffloadHandle,
+                TlsOffloadKey,
+            },
+            provider::CryptoProvider,
+            record::{
+                record_header,
+                RecordProtection,
+                CONTENT_TYPE_ALERT,
+                CONTENT_TYPE_APPLICATION_DATA,
+                CONTENT_TYPE_CHANGE_CIPHER_SPEC,
+                CONTENT_TYPE_HANDSHAKE,
+                MAX_CIPHERTEXT_SIZE,
+                MAX_PLAINTEXT_SIZE,
+                RECORD_HEADER_SIZE,
+            },
+            EncryptionLevel,
+            TlsConfig,
+            TlsRole,
+            TLS_CHACHA20_POLY1305_SHA256,
+        },
+    },
+};
+use ::std::{
+    collections::VecDeque,
+    rc::Rc,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Legacy protocol version of hello messages.
+const LEGACY_VERSION: u16 = 0x0303;
+
+/// Protocol version of TLS 1.3.
+const TLS13_VERSION: u16 = 0x0304;
+
+/// Legacy protocol version of the hello messages of DTLS 1.3 (RFC 9147, section 5.3).
+const DTLS_LEGACY_VERSION: u16 = 0xfefd;
+
+/// Protocol version of DTLS 1.3.
+const DTLS13_VERSION: u16 = 0xfefc;
+
+/// The only group that sessions exchange keys on.
+const GROUP_X25519: u16 = 0x001d;
+
+/// Key exchange mode of pre-shared keys that are combined with an ephemeral Diffie-Hellman exchange.
+const PSK_DHE_KE: u8 = 1;
+
+/// Largest handshake message that is accepted.
+const MAX_HANDSHAKE_MESSAGE_SIZE: usize = 1 << 16;
+
+/// Size of the list of binders of a ClientHello that offers one pre-shared key.
+const BINDERS_SIZE: usize = 2 + 1 + 32;
+
+/// Random value of a ServerHello that is a HelloRetryRequest (RFC 8446, section 4.1.3).
+const HELLO_RETRY_REQUEST_RANDOM: [u8; 32] = [
+    0xcf, 0x21, 0xad, 0x74, 0xe5, 0x9a, 0x61, 0x11, 0xbe, 0x1d, 0x8c, 0x02, 0x1e, 0x65, 0xb8, 0x91, 0xc2, 0xa2, 0x11,
+    0x16, 0x7a, 0xbb, 0x8c, 0x5e, 0x07, 0x9e, 0x09, 0xe2, 0xc8, 0xa8, 0x33, 0x9c,
+];
+
+/// Types of handshake messages.
+const HANDSHAKE_CLIENT_HELLO: u8 = 1;
+const HANDSHAKE_SERVER_HELLO: u8 = 2;
+const HANDSHAKE_NEW_SESSION_TICKET: u8 = 4;
+const HANDSHAKE_ENCRYPTED_EXTENSIONS: u8 = 8;
+const HANDSHAKE_FINISHED: u8 = 20;
+const HANDSHAKE_KEY_UPDATE: u8 = 24;
+const HANDSHAKE_MESSAGE_HASH: u8 = 254;
+
+/// Types of extensions.
+const EXTENSION_SUPPORTED_GROUPS: u16 = 10;
+const EXTENSION_PRE_SHARED_KEY: u16 = 41;
+const EXTENSION_SUPPORTED_VERSIONS: u16 = 43;
+const EXTENSION_COOKIE: u16 = 44;
+const EXTENSION_PSK_KEY_EXCHANGE_MODES: u16 = 45;
+const EXTENSION_KEY_SHARE: u16 = 51;
+const EXTENSION_QUIC_TRANSPORT_PARAMETERS: u16 = 57;
+
+/// Number of encryption levels of a session that does not run over TLS records.
+const NUM_ENCRYPTION_LEVELS: usize = 3;
+
+/// Levels of alerts.
+const ALERT_LEVEL_WARNING: u8 = 1;
+const ALERT_LEVEL_FATAL: u8 = 2;
+
+/// Descriptions of alerts.
+const ALERT_CLOSE_NOTIFY: u8 = 0;
+const ALERT_UNEXPECTED_MESSAGE: u8 = 10;
+const ALERT_BAD_RECORD_MAC: u8 = 20;
+const ALERT_RECORD_OVERFLOW: u8 = 22;
+const ALERT_HANDSHAKE_FAILURE: u8 = 40;
+const ALERT_ILLEGAL_PARAMETER: u8 = 47;
+const ALERT_DECODE_ERROR: u8 = 50;
+const ALERT_DECRYPT_ERROR: u8 = 51;
+const ALERT_PROTOCOL_VERSION: u8 = 70;
+const ALERT_INTERNAL_ERROR: u8 = 80;
+const ALERT_USER_CANCELED: u8 = 90;
+const ALERT_MISSING_EXTENSION: u8 = 109;
+const ALERT_UNSUPPORTED_EXTENSION: u8 = 110;
+const ALERT_UNKNOWN_PSK_IDENTITY: u8 = 115;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Handshake message that a session waits for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum HandshakeState {
+    ServerHello,
+    EncryptedExtensions,
+    ServerFinished,
+    ClientHello,
+    ClientFinished,
+    /// The handshake is complete.
+    Connected,
+}
+
+/// Error that aborts a session.
+struct Abort {
+    /// Alert that is sent to the peer, if the error was detected on this side.
+    alert: Option<u8>,
+    cause: String,
+}
+
+/// Device that encrypts the outgoing records of a session.
+struct TxOffload {
+    device: Rc<dyn TlsOffload>,
+    flow: TlsOffloadFlow,
+    /// Key that the device encrypts new records with.
+    handle: TlsOffloadHandle,
+    /// Keys that were replaced by a key update. The device may still need them to encrypt retransmitted records, so
+    /// they are only removed along with the session.
+    retired: Vec<TlsOffloadHandle>,
+    /// Bytes that were taken from the session since [TxOffload::flow] was captured.
+    sent: u32,
+}
+
+/// Protocol that carries the handshake messages of a session and protects its data, instead of TLS records.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Carrier {
+    /// QUIC (RFC 9001) carries handshake messages in CRYPTO frames.
+    Quic,
+    /// The DTLS 1.3 record layer (RFC 9147) carries handshake messages in datagrams.
+    Dtls,
+}
+
+/// State of a session that runs over QUIC or DTLS, which carry handshake messages on their own and protect their
+/// data with secrets that are exported from the session.
+struct ExternalHandshake {
+    carrier: Carrier,
+    /// Transport parameters of this side, over QUIC.
+    local_parameters: Vec<u8>,
+    /// Transport parameters of the peer, once they arrived.
+    peer_parameters: Option<Vec<u8>>,
+    /// Level of the handshake messages that are expected from the peer.
+    read_level: EncryptionLevel,
+    /// Level of the handshake messages that are sent to the peer.
+    write_level: EncryptionLevel,
+    /// Handshake messages that are bound to the peer, by level.
+    output: [Vec<u8>; NUM_ENCRYPTION_LEVELS],
+    /// Secrets of incoming packets, by level, until they are taken.
+    read_secrets: [Option<Secret>; NUM_ENCRYPTION_LEVELS],
+    /// Secrets of outgoing packets, by level, until they are taken.
+    write_secrets: [Option<Secret>; NUM_ENCRYPTION_LEVELS],
+    /// Alert that aborted the session, which the carrier lets the peer know about.
+    alert: Option<u8>,
+    /// Cookie of a HelloRetryRequest, which a DTLS client echoes in its second ClientHello.
+    cookie: Option<Vec<u8>>,
+}
+
+/// Sans-I/O TLS 1.3 session (RFC 8446). Records that arrive from the peer are fed through [TlsSession::receive] and
+/// records that are bound to the peer are collected with [TlsSession::take_output], so the session can run over any
+/// byte stream.
+///
+/// Peers authenticate each other with an external pre-shared key and agree on the traffic keys with an ephemeral X25519
+/// exchange (the psk_dhe_ke mode), so the session has forward secrecy. Records are protected with
+/// TLS_CHACHA20_POLY1305_SHA256. Certificates, session resumption and early data are not supported.
+pub struct TlsSession {
+    role: TlsRole,
+    config: TlsConfig,
+    state: HandshakeState,
+    /// Hash of the handshake messages so far.
+    transcript: Sha256,
+    key_schedule: KeySchedule,
+    /// Ephemeral X25519 secret of a client, until the ServerHello arrives.
+    key_share: [u8; X25519_KEY_SIZE],
+    /// Random value of the ClientHello of a client, which is sent again after a HelloRetryRequest.
+    client_random: [u8; 32],
+    client_handshake_secret: Secret,
+    server_handshake_secret: Secret,
+    /// Application traffic secret of the client, which a server switches to once the client is authenticated.
+    client_application_secret: Option<Secret>,
+    /// Protection of incoming records, once the peer encrypts them.
+    read: Option<RecordProtection>,
+    /// Protection of outgoing records, once this side encrypts them.
+    write: Option<RecordProtection>,
+    /// Bytes from the peer that do not make up a whole record yet.
+    incoming: Vec<u8>,
+    /// Handshake bytes that do not make up a whole handshake message yet.
+    handshake: Vec<u8>,
+    /// Records that are bound to the peer.
+    outgoing: Vec<u8>,
+    /// Application data from the peer.
+    plaintext: VecDeque<u8>,
+    /// Whether the peer closed its side of the session.
+    peer_closed: bool,
+    /// Whether this side closed its side of the session.
+    closed: bool,
+    /// Whether the session was aborted.
+    failed: bool,
+    /// Device that encrypts outgoing records, if they are offloaded.
+    tx_offload: Option<TxOffload>,
+    /// State of a session that runs over QUIC or DTLS, which has no TLS records.
+    external: Option<ExternalHandshake>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Abort {
+    /// Aborts with [alert].
+    fn new(alert: u8, cause: String) -> Self {
+        Self {
+            alert: Some(alert),
+            cause,
+        }
+    }
+
+    /// Gets the error code that is reported to the application.
+    fn errno(&self) -> i32 {
+        match self.alert {
+            // The peer aborted the session.
+            None => libc::ECONNRESET,
+            Some(ALERT_DECODE_ERROR) | Some(ALERT_BAD_RECORD_MAC) | Some(ALERT_RECORD_OVERFLOW) => libc::EBADMSG,
+            Some(ALERT_DECRYPT_ERROR) | Some(ALERT_UNKNOWN_PSK_IDENTITY) => libc::EACCES,
+            Some(_) => libc::EPROTO,
+        }
+    }
+}
+
+impl ExternalHandshake {
+    fn new(carrier: Carrier, local_parameters: &[u8]) -> Self {
+        Self {
+            carrier,
+            local_parameters: local_parameters.to_vec(),
+            peer_parameters: None,
+            read_level: EncryptionLevel::Initial,
+            write_level: EncryptionLevel::Initial,
+            output: Default::default(),
+            read_secrets: Default::default(),
+            write_secrets: Default::default(),
+            alert: None,
+            cookie: None,
+        }
+    }
+}
+
+impl TlsSession {
+    /// Creates a session on the [role] side of a connection. Clients queue their ClientHello right away.
+    pub fn new(role: TlsRole, config: TlsConfig) -> Self {
+        Self::create(role, config, None)
+    }
+
+    /// Creates a session on the [role] side of a QUIC connection, which sends the encoded [transport_parameters] to
+    /// the peer. Handshake messages are exchanged with [TlsSession::receive_handshake] and
+    /// [TlsSession::take_handshake], and the secrets of each encryption level are taken as they become available.
+    pub fn new_quic(role: TlsRole, config: TlsConfig, transport_parameters: &[u8]) -> Self {
+        let external: ExternalHandshake = ExternalHandshake::new(Carrier::Quic, transport_parameters);
+        Self::create(role, config, Some(external))
+    }
+
+    /// Creates a session on the [role] side of a DTLS 1.3 association. Handshake messages are exchanged and secrets
+    /// are taken the same way as over QUIC, with the epochs of DTLS in place of the encryption levels. Servers that
+    /// answer the first ClientHello with a HelloRetryRequest use [TlsSession::new_dtls_retried] instead.
+    pub fn new_dtls(role: TlsRole, config: TlsConfig) -> Self {
+        let external: ExternalHandshake = ExternalHandshake::new(Carrier::Dtls, &[]);
+        Self::create(role, config, Some(external))
+    }
+
+    /// Creates the server side of a DTLS 1.3 association whose first ClientHello, which hashes to [first_hello_hash],
+    /// was answered statelessly with the HelloRetryRequest of [dtls_hello_retry_request] for [cookie]. The session
+    /// picks up the handshake at the second ClientHello (RFC 8446, section 4.4.1).
+    pub fn new_dtls_retried(config: TlsConfig, first_hello_hash: &Secret, cookie: &[u8]) -> Self {
+        let mut session: Self = Self::new_dtls(TlsRole::Server, config);
+        session.transcript.update(&message_hash(first_hello_hash));
+        session.transcript.update(&dtls_hello_retry_request(cookie));
+        session
+    }
+
+    fn create(role: TlsRole, config: TlsConfig, external: Option<ExternalHandshake>) -> Self {
+        let key_schedule: KeySchedule = match external.as_ref().map(|external| external.carrier) {
+            Some(Carrier::Dtls) => KeySchedule::new_dtls(config.psk()),
+            Some(Carrier::Quic) | None => KeySchedule::new(config.psk()),
+        };
+        let mut session: Self = Self {
+            role,
+            config,
+            state: match role {
+                TlsRole::Client => HandshakeState::ServerHello,
+                TlsRole::Server => HandshakeState::ClientHello,
+            },
+            transcript: Sha256::new(),
+            key_schedule,
+            key_share: [0; X25519_KEY_SIZE],
+            client_random: [0; 32],
+            client_handshake_secret: [0; 32],
+            server_handshake_secret: [0; 32],
+            client_application_secret: None,
+            read: None,
+            write: None,
+            incoming: Vec::new(),
+            handshake: Vec::new(),
+            outgoing: Vec::new(),
+            plaintext: VecDeque::new(),
+            peer_closed: false,
+            closed: false,
+            failed: false,
+            tx_offload: None,
+            external,
+        };
+        if role == TlsRole::Client {
+            session.send_client_hello();
+        }
+        session
+    }
+
+    /// Checks whether the handshake completed.
+    pub fn is_handshake_complete(&self) -> bool {
+        self.state == HandshakeState::Connected
+    }
+
+    /// Checks whether the peer closed its side of the session.
+    pub fn is_peer_closed(&self) -> bool {
+        self.peer_closed
+    }
+
+    /// Processes [data] that arrived from the peer. On failure, the session is aborted and an alert for the peer is
+    /// queued.
+    pub fn receive(&mut self, data: &[u8]) -> Result<(), Fail> {
+        if self.external.is_some() {
+            let cause: &str = "session does not run over TLS records";
+            warn!("receive(): {}", cause);
+            return Err(Fail::new(libc::EOPNOTSUPP, cause));
+        }
+        if self.failed {
+            let cause: &str = "session was aborted";
+            warn!("receive(): {}", cause);
+            return Err(Fail::new(libc::EPROTO, cause));
+        }
+        self.incoming.extend_from_slice(data);
+        let result: Result<(), Abort> = self.process_records();
+        self.check_abort(result)
+    }
+
+    /// Processes the handshake bytes [data] that arrived from the peer at encryption [level] of a QUIC connection or
+    /// DTLS association, in order. On failure, the session is aborted and the alert for the peer is kept.
+    pub fn receive_handshake(&mut self, level: EncryptionLevel, data: &[u8]) -> Result<(), Fail> {
+        let read_level: EncryptionLevel = match self.external.as_ref() {
+            Some(external) => external.read_level,
+            None => {
+                let cause: &str = "session runs over TLS records";
+                warn!("receive_handshake(): {}", cause);
+                return Err(Fail::new(libc::EOPNOTSUPP, cause));
+            },
+        };
+        if self.failed {
+            let cause: &str = "session was aborted";
+            warn!("receive_handshake(): {}", cause);
+            return Err(Fail::new(libc::EPROTO, cause));
+        }
+        let result: Result<(), Abort> = if level != read_level {
+            Err(Abort::new(
+                ALERT_UNEXPECTED_MESSAGE,
+                format!(
+                    "handshake data at unexpected level (level={:?}, expected={:?})",
+                    level, read_level
+                ),
+            ))
+        } else {
+            self.handshake.extend_from_slice(data);
+            self.process_handshake()
+        };
+        self.check_abort(result)
+    }
+
+    /// Takes the handshake messages that are bound to the peer at encryption [level] of a QUIC connection or DTLS
+    /// association, if there are any.
+    pub fn take_handshake(&mut self, level: EncryptionLevel) -> Option<Vec<u8>> {
+        let output: &mut Vec<u8> = &mut self.external.as_mut()?.output[level as usize];
+        if output.is_empty() {
+            None
+        } else {
+            Some(::std::mem::take(output))
+        }
+    }
+
+    /// Takes the secret of incoming packets at encryption [level] of a QUIC connection or DTLS association, once it is
+    /// available.
+    pub fn take_read_secret(&mut self, level: EncryptionLevel) -> Option<Secret> {
+        self.external.as_mut()?.read_secrets[level as usize].take()
+    }
+
+    /// Takes the secret of outgoing packets at encryption [level] of a QUIC connection or DTLS association, once it is
+    /// available.
+    pub fn take_write_secret(&mut self, level: EncryptionLevel) -> Option<Secret> {
+        self.external.as_mut()?.write_secrets[level as usize].take()
+    }
+
+    /// Gets the encoded transport parameters of the peer of a QUIC connection, once they arrived.
+    pub fn peer_transport_parameters(&self) -> Option<&[u8]> {
+        self.external.as_ref()?.peer_parameters.as_deref()
+    }
+
+    /// Gets the alert that aborted a session that runs over QUIC or DTLS, if it was detected on this side.
+    pub fn handshake_alert(&self) -> Option<u8> {
+        self.external.as_ref()?.alert
+    }
+
+    /// Encrypts [data] for the peer.
+    pub fn write(&mut self, data: &[u8]) -> Result<(), Fail> {
+        if self.external.is_some() {
+            let cause: &str = "session does not run over TLS records";
+            warn!("write(): {}", cause);
+            return Err(Fail::new(libc::EOPNOTSUPP, cause));
+        }
+        if self.state != HandshakeState::Connected || self.failed || self.closed {
+            let cause: &str = "session is not established";
+            warn!("write(): {}", cause);
+            return Err(Fail::new(libc::ENOTCONN, cause));
+        }
+        let write: &mut RecordProtection = expect_write(&mut self.write);
+        for chunk in data.chunks(MAX_PLAINTEXT_SIZE) {
+            write.seal(CONTENT_TYPE_APPLICATION_DATA, chunk, &mut self.outgoing)?;
+        }
+        Ok(())
+    }
+
+    /// Takes up to [size] bytes of the application data that arrived from the peer, if there is any.
+    pub fn read(&mut self, size: usize) -> Option<Vec<u8>> {
+        if self.plaintext.is_empty() {
+            return None;
+        }
+        let len: usize = size.min(self.plaintext.len());
+        Some(self.plaintext.drain(..len).collect())
+    }
+
+    /// Closes this side of the session by queuing a close_notify alert for the peer.
+    pub fn close(&mut self) {
+        if !self.closed && !self.failed {
+            self.send_alert(ALERT_LEVEL_WARNING, ALERT_CLOSE_NOTIFY);
+            self.closed = true;
+        }
+    }
+
+    /// Takes the records that are bound to the peer, if there are any. Once outgoing records are offloaded, they must be
+    /// transmitted in order and right away, because the device locates them by their TCP sequence number.
+    pub fn take_output(&mut self) -> Option<Vec<u8>> {
+        if self.outgoing.is_empty() {
+            None
+        } else {
+            if let Some(tx_offload) = self.tx_offload.as_mut() {
+                tx_offload.sent = tx_offload.sent.wrapping_add(self.outgoing.len() as u32);
+            }
+            Some(::std::mem::take(&mut self.outgoing))
+        }
+    }
+
+    /// Leaves the encryption of outgoing records to [device], which transmits them on [flow]. From now on, records are
+    /// handed out in plaintext. This fails with ENOTSUP if the device or the configuration of the session rule it out, in
+    /// which case records are still encrypted by the crypto provider. Records that were already queued must be taken
+    /// first.
+    pub fn offload_transmit(&mut self, device: Rc<dyn TlsOffload>, flow: TlsOffloadFlow) -> Result<(), Fail> {
+        if self.state != HandshakeState::Connected || self.failed || self.closed {
+            let cause: &str = "session is not established";
+            warn!("offload_transmit(): {}", cause);
+            return Err(Fail::new(libc::ENOTCONN, cause));
+        }
+        if self.tx_offload.is_some() {
+            let cause: &str = "outgoing records are already offloaded";
+            warn!("offload_transmit(): {}", cause);
+            return Err(Fail::new(libc::EALREADY, cause));
+        }
+        if !self.outgoing.is_empty() {
+            let cause: &str = "outgoing records are pending";
+            warn!("offload_transmit(): {}", cause);
+            return Err(Fail::new(libc::EBUSY, cause));
+        }
+        if self.external.is_some() {
+            let cause: &str = "session does not run over TLS records";
+            debug!("offload_transmit(): {}", cause);
+            return Err(Fail::new(libc::ENOTSUP, cause));
+        }
+        if !self.config.hardware_offload() {
+            let cause: &str = "hardware offload is disabled";
+            debug!("offload_transmit(): {}", cause);
+            return Err(Fail::new(libc::ENOTSUP, cause));
+        }
+        if !device.capabilities().supports_tx(TLS_CHACHA20_POLY1305_SHA256) {
+            let cause: &str = "device does not support the cipher suite";
+            debug!("offload_transmit(): {}", cause);
+            return Err(Fail::new(libc::ENOTSUP, cause));
+        }
+        let write: &mut RecordProtection = expect_write(&mut self.write);
+        let key: TlsOffloadKey = write.offload_key(TLS_CHACHA20_POLY1305_SHA256);
+        let handle: TlsOffloadHandle = device.install_tx_key(&flow, &key)?;
+        write.set_offloaded();
+        self.tx_offload = Some(TxOffload {
+            device,
+            flow,
+            handle,
+            retired: Vec::new(),
+            sent: 0,
+        });
+        Ok(())
+    }
+
+    /// Checks whether outgoing records are encrypted by a device.
+    pub fn is_transmit_offloaded(&self) -> bool {
+        self.tx_offload.is_some()
+    }
+
+    /// Aborts the session if [result] failed, queuing the alert for the peer.
+    fn check_abort(&mut self, result: Result<(), Abort>) -> Result<(), Fail> {
+        match result {
+            Ok(()) => Ok(()),
+            Err(abort) => {
+                warn!("check_abort(): {} (alert={:?})", abort.cause, abort.alert);
+                self.failed = true;
+                if let Some(alert) = abort.alert {
+                    self.send_alert(ALERT_LEVEL_FATAL, alert);
+                }
+                Err(Fail::new(abort.errno(), &abort.cause))
+            },
+        }
+    }
+
+    /// Switches to [secret] for incoming records. Over QUIC and DTLS, the secret is exported for the next encryption
+    /// level.
+    fn set_read_secret(&mut self, secret: Secret) {
+        match self.external.as_mut() {
+            Some(external) => {
+                external.read_level = next_level(external.read_level);
+                external.read_secrets[external.read_level as usize] = Some(secret);
+            },
+            None => self.read = Some(RecordProtection::new(secret, self.provider())),
+        }
+    }
+
+    /// Switches to [secret] for outgoing records. Over QUIC and DTLS, the secret is exported for the next encryption
+    /// level.
+    fn set_write_secret(&mut self, secret: Secret) {
+        match self.external.as_mut() {
+            Some(external) => {
+                external.write_level = next_level(external.write_level);
+                external.write_secrets[external.write_level as usize] = Some(secret);
+            },
+            None => self.write = Some(RecordProtection::new(secret, self.provider())),
+        }
+    }
+
+    /// Gets the hash of the handshake messages so far.
+    fn transcript_hash(&self) -> Secret {
+        self.transcript.clone().finalize()
+    }
+
+    fn provider(&self) -> Rc<dyn CryptoProvider> {
+        self.config.crypto_provider()
+    }
+
+    fn carrier(&self) -> Option<Carrier> {
+        self.external.as_ref().map(|external| external.carrier)
+    }
+
+    /// Gets the legacy version and the version of the protocol that the session runs.
+    fn versions(&self) -> (u16, u16) {
+        match self.carrier() {
+            Some(Carrier::Dtls) => (DTLS_LEGACY_VERSION, DTLS13_VERSION),
+            Some(Carrier::Quic) | None => (LEGACY_VERSION, TLS13_VERSION),
+        }
+    }
+
+    /// Gets the transport parameters of this side, which are only sent over QUIC.
+    fn local_transport_parameters(&self) -> Option<&[u8]> {
+        match self.external.as_ref() {
+            Some(external) if external.carrier == Carrier::Quic => Some(&external.local_parameters),
+            _ => None,
+        }
+    }
+
+    /// Splits the incoming bytes into records and processes them.
+    fn process_records(&mut self) -> Result<(), Abort> {
+        while self.incoming.len() >= RECORD_HEADER_SIZE {
+            // Anything that follows a close_notify alert is ignored.
+            if self.peer_closed {
+                self.incoming.clear();
+                break;
+            }
+            let content_type: u8 = self.incoming[0];
+            let len: usize = u16::from_be_bytes([self.incoming[3], self.incoming[4]]) as usize;
+            if len > MAX_CIPHERTEXT_SIZE {
+                return Err(Abort::new(
+                    ALERT_RECORD_OVERFLOW,
+                    format!("record is too long (len={:?})", len),
+                ));
+            }
+            if self.incoming.len() < RECORD_HEADER_SIZE + len {
+                break;
+            }
+            let mut payload: Vec<u8> = self.incoming.drain(..(RECORD_HEADER_SIZE + len)).collect();
+            let header: Vec<u8> = payload.drain(..RECORD_HEADER_SIZE).collect();
+            self.process_record(content_type, &header, payload)?;
+        }
+        Ok(())
+    }
+
+    /// Processes a record of [content_type] with [header] and [payload].
+    fn process_record(&mut self, content_type: u8, header: &[u8], mut payload: Vec<u8>) -> Result<(), Abort> {
+        // Peers in middlebox compatibility mode send dummy change_cipher_spec records during the handshake.
+        if content_type == CONTENT_TYPE_CHANGE_CIPHER_SPEC && self.state != HandshakeState::Connected && payload == [1]
+        {
+            return Ok(());
+        }
+
+        let content_type: u8 = match self.read.as_mut() {
+            None if content_type == CONTENT_TYPE_HANDSHAKE || content_type == CONTENT_TYPE_ALERT => content_type,
+            Some(read) if content_type == CONTENT_TYPE_APPLICATION_DATA => match read.open(header, &mut payload) {
+                Ok(content_type) => content_type,
+                Err(e) if e.errno == libc::EBADMSG => return Err(Abort::new(ALERT_BAD_RECORD_MAC, e.cause)),
+                Err(e) => return Err(Abort::new(ALERT_UNEXPECTED_MESSAGE, e.cause)),
+            },
+            _ => {
+                return Err(Abort::new(
+                    ALERT_UNEXPECTED_MESSAGE,
+                    format!("unexpected record (content_type={:?})", content_type),
+                ))
+            },
+        };
+        if payload.len() > MAX_PLAINTEXT_SIZE {
+            return Err(Abort::new(
+                ALERT_RECORD_OVERFLOW,
+                format!("record is too long (len={:?})", payload.len()),
+            ));
+        }
+
+        // Handshake messages may span records, but must not be interleaved with other records.
+        if content_type != CONTENT_TYPE_HANDSHAKE && !self.handshake.is_empty() {
+            return Err(Abort::new(
+                ALERT_UNEXPECTED_MESSAGE,
+                format!(
+                    "record interrupts a handshake message (content_type={:?})",
+                    content_type
+                ),
+            ));
+        }
+        match content_type {
+            CONTENT_TYPE_HANDSHAKE if !payload.is_empty() => {
+                self.handshake.extend_from_slice(&payload);
+                self.process_handshake()
+            },
+            CONTENT_TYPE_APPLICATION_DATA if self.state == HandshakeState::Connected => {
+                self.plaintext.extend(payload);
+                Ok(())
+            },
+            CONTENT_TYPE_ALERT => self.process_alert(&payload),
+            _ => Err(Abort::new(
+                ALERT_UNEXPECTED_MESSAGE,
+                format!("unexpected record (content_type={:?})", content_type),
+            )),
+        }
+    }
+
+    /// Processes an alert from the peer.
+    fn process_alert(&mut self, payload: &[u8]) -> Result<(), Abort> {
+        if payload.len() != 2 {
+            return Err(Abort::new(
+                ALERT_DECODE_ERROR,
+                format!("malformed alert (len={:?})", payload.len()),
+            ));
+        }
+        match payload[1] {
+            ALERT_CLOSE_NOTIFY => {
+                self.peer_closed = true;
+                Ok(())
+            },
+            ALERT_USER_CANCELED => Ok(()),
+            alert => Err(Abort {
+                alert: None,
+                cause: format!("peer aborted the session (alert={:?})", alert),
+            }),
+        }
+    }
+
+    /// Splits the handshake bytes into messages and processes them.
+    fn process_handshake(&mut self) -> Result<(), Abort> {
+        while self.handshake.len() >= 4 {
+            let len: usize = u32::from_be_bytes([0, self.handshake[1], self.handshake[2], self.handshake[3]]) as usize;
+            if len > MAX_HANDSHAKE_MESSAGE_SIZE {
+                return Err(Abort::new(
+                    ALERT_DECODE_ERROR,
+                    format!("handshake message is too long (len={:?})", len),
+                ));
+            }
+            if self.handshake.len() < 4 + len {
+                break;
+            }
+            let msg: Vec<u8> = self.handshake.drain(..(4 + len)).collect();
+            match (self.state, msg[0]) {
+                (HandshakeState::ServerHello, HANDSHAKE_SERVER_HELLO) => self.process_server_hello(&msg)?,
+                (HandshakeState::EncryptedExtensions, HANDSHAKE_ENCRYPTED_EXTENSIONS) => {
+                    self.process_encrypted_extensions(&msg)?
+                },
+                (HandshakeState::ServerFinished, HANDSHAKE_FINISHED) => self.process_server_finished(&msg)?,
+                (HandshakeState::ClientHello, HANDSHAKE_CLIENT_HELLO) => self.process_client_hello(&msg)?,
+                (HandshakeState::ClientFinished, HANDSHAKE_FINISHED) => self.process_client_finished(&msg)?,
+                // Tickets are only good for resumption, which is not supported.
+                (HandshakeState::Connected, HANDSHAKE_NEW_SESSION_TICKET) if self.role == TlsRole::Client => (),
+                // QUIC updates keys with its own mechanism (RFC 9001, section 6). Key updates are not supported over DTLS,
+                // where they take acknowledgements.
+                (HandshakeState::Connected, HANDSHAKE_KEY_UPDATE) if self.external.is_none() => {
+                    self.process_key_update(&msg)?
+                },
+                (state, msg_type) => {
+                    return Err(Abort::new(
+                        ALERT_UNEXPECTED_MESSAGE,
+                        format!("unexpected handshake message (type={:?}, state={:?})", msg_type, state),
+                    ))
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that no handshake message is pending when the keys of incoming records change, since it would have been
+    /// protected with the old keys.
+    fn check_key_change(&self) -> Result<(), Abort> {
+        if !self.handshake.is_empty() {
+            return Err(Abort::new(
+                ALERT_UNEXPECTED_MESSAGE,
+                "handshake message spans a key change".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Sends the handshake message [msg], in as many records as it takes.
+    fn send_handshake(&mut self, msg: &[u8]) -> Result<(), Abort> {
+        if let Some(external) = self.external.as_mut() {
+            external.output[external.write_level as usize].extend_from_slice(msg);
+            return Ok(());
+        }
+        for chunk in msg.chunks(MAX_PLAINTEXT_SIZE) {
+            match self.write.as_mut() {
+                Some(write) => write
+                    .seal(CONTENT_TYPE_HANDSHAKE, chunk, &mut self.outgoing)
+                    .map_err(|e| Abort::new(ALERT_INTERNAL_ERROR, e.cause))?,
+                None => {
+                    self.outgoing
+                        .extend_from_slice(&record_header(CONTENT_TYPE_HANDSHAKE, chunk.len()));
+                    self.outgoing.extend_from_slice(chunk);
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends an alert of [level] with [description].
+    fn send_alert(&mut self, level: u8, description: u8) {
+        // Over QUIC and DTLS, fatal alerts are sent by the carrier and closure is up to it.
+        if let Some(external) = self.external.as_mut() {
+            if level == ALERT_LEVEL_FATAL {
+                external.alert = Some(description);
+            }
+            return;
+        }
+        let alert: [u8; 2] = [level, description];
+        match self.write.as_mut() {
+            Some(write) => {
+                if let Err(e) = write.seal(CONTENT_TYPE_ALERT, &alert, &mut self.outgoing) {
+                    warn!("send_alert(): failed to send alert ({:?})", e);
+                }
+            },
+            None => {
+                self.outgoing
+                    .extend_from_slice(&record_header(CONTENT_TYPE_ALERT, alert.len()));
+                self.outgoing.extend_from_slice(&alert);
+            },
+        }
+    }
+
+    /// Sends the ClientHello, which offers the pre-shared key of the configuration and an X25519 key share.
+    fn send_client_hello(&mut self) {
+        let provider: Rc<dyn CryptoProvider> = self.provider();
+        provider.fill_random(&mut self.client_random);
+        provider.fill_random(&mut self.key_share);
+        self.write_client_hello();
+    }
+
+    /// Writes the ClientHello with the random value and key share of this side, and the cookie of a HelloRetryRequest
+    /// if one arrived.
+    fn write_client_hello(&mut self) {
+        let public: [u8; X25519_KEY_SIZE] = self.provider().x25519(&self.key_share, &X25519_BASE_POINT);
+        let random: [u8; 32] = self.client_random;
+        let (legacy_version, version): (u16, u16) = self.versions();
+        let is_dtls: bool = self.carrier() == Some(Carrier::Dtls);
+        let cookie: Option<&[u8]> = self.external.as_ref().and_then(|external| external.cookie.as_deref());
+        let identity: &[u8] = self.config.psk_identity();
+        let transport_parameters: Option<&[u8]> = self.local_transport_parameters();
+
+        let mut msg: Vec<u8> = Vec::new();
+        put_handshake(&mut msg, HANDSHAKE_CLIENT_HELLO, |body| {
+            body.extend_from_slice(&legacy_version.to_be_bytes());
+            body.extend_from_slice(&random);
+            put_vec(body, 1, |_| ());
+            // The legacy cookie of DTLS 1.2 is left empty (RFC 9147, section 5.3).
+            if is_dtls {
+                put_vec(body, 1, |_| ());
+            }
+            put_vec(body, 2, |suites| {
+                suites.extend_from_slice(&TLS_CHACHA20_POLY1305_SHA256.to_be_bytes())
+            });
+            put_vec(body, 1, |methods| methods.push(0));
+            put_vec(body, 2, |extensions| {
+                put_extension(extensions, EXTENSION_SUPPORTED_VERSIONS, |data| {
+                    put_vec(data, 1, |versions| versions.extend_from_slice(&version.to_be_bytes()))
+                });
+                if let Some(cookie) = cookie {
+                    put_extension(extensions, EXTENSION_COOKIE, |data| {
+                        put_vec(data, 2, |cookie_data| cookie_data.extend_from_slice(cookie))
+                    });
+                }
+                put_extension(extensions, EXTENSION_SUPPORTED_GROUPS, |data| {
+                    put_vec(data, 2, |groups| groups.extend_from_slice(&GROUP_X25519.to_be_bytes()))
+                });
+                put_extension(extensions, EXTENSION_KEY_SHARE, |data| {
+                    put_vec(data, 2, |shares| {
+                        shares.extend_from_slice(&GROUP_X25519.to_be_bytes());
+                        put_vec(shares, 2, |key| key.extend_from_slice(&public));
+                    })
+                });
+                put_extension(extensions, EXTENSION_PSK_KEY_EXCHANGE_MODES, |data| {
+                    put_vec(data, 1, |modes| modes.push(PSK_DHE_KE))
+                });
+                if let Some(transport_parameters) = transport_parameters {
+                    put_extension(extensions, EXTENSION_QUIC_TRANSPORT_PARAMETERS, |data| {
+                        data.extend_from_slice(transport_parameters)
+                    });
+                }
+                // The pre-shared key extension must come last. Its binder is filled in once the message is complete.
+                put_extension(extensions, EXTENSION_PRE_SHARED_KEY, |data| {
+                    put_vec(data, 2, |identities| {
+                        put_vec(identities, 2, |id| id.extend_from_slice(identity));
+                        identities.extend_from_slice(&0u32.to_be_bytes());
+                    });
+                    put_vec(data, 2, |binders| {
+                        put_vec(binders, 1, |binder| binder.extend_from_slice(&[0; 32]))
+                    });
+                });
+            });
+        });
+
+        // The binder covers the transcript up to the list of binders of the ClientHello.
+        let len: usize = msg.len();
+        let mut transcript: Sha256 = self.transcript.clone();
+        transcript.update(&msg[..(len - BINDERS_SIZE)]);
+        let binder: Secret = self.key_schedule.psk_binder(&transcript.finalize());
+        msg[(len - binder.len())..].copy_from_slice(&binder);
+
+        self.transcript.update(&msg);
+        // Sending a plaintext handshake message cannot fail.
+        let _ = self.send_handshake(&msg);
+    }
+
+    /// Processes the ServerHello and switches to the handshake traffic keys.
+    fn process_server_hello(&mut self, msg: &[u8]) -> Result<(), Abort> {
+        let mut reader: Reader = Reader::new(&msg[4..]);
+        let _legacy_version: u16 = reader.u16()?;
+        if reader.bytes(32)? == HELLO_RETRY_REQUEST_RANDOM {
+            return self.process_hello_retry_request(msg);
+        }
+        if !reader.vec8()?.is_empty() {
+            return Err(Abort::new(
+                ALERT_ILLEGAL_PARAMETER,
+                "server echoed a session ID that was not sent".to_string(),
+            ));
+        }
+        let cipher_suite: u16 = reader.u16()?;
+        if cipher_suite != TLS_CHACHA20_POLY1305_SHA256 {
+            return Err(Abort::new(
+                ALERT_ILLEGAL_PARAMETER,
+                format!(
+                    "server selected a cipher suite that was not offered (cipher_suite={:#x})",
+                    cipher_suite
+                ),
+            ));
+        }
+        if reader.u8()? != 0 {
+            return Err(Abort::new(
+                ALERT_ILLEGAL_PARAMETER,
+                "server selected a compression method".to_string(),
+            ));
+        }
+        let mut extensions: Reader = Reader::new(reader.vec16()?);
+        reader.finish()?;
+
+        let mut version: Option<u16> = None;
+        let mut server_share: Option<&[u8]> = None;
+        let mut selected_identity: Option<u16> = None;
+        while !extensions.is_empty() {
+            let extension_type: u16 = extensions.u16()?;
+            let mut data: Reader = Reader::new(extensions.vec16()?);
+            match extension_type {
+                EXTENSION_SUPPORTED_VERSIONS => version = Some(data.u16()?),
+                EXTENSION_KEY_SHARE => {
+                    let group: u16 = data.u16()?;
+                    if group != GROUP_X25519 {
+                        return Err(Abort::new(
+                            ALERT_ILLEGAL_PARAMETER,
+                            format!("server selected a group that was not offered (group={:#x})", group),
+                        ));
+                    }
+                    server_share = Some(data.vec16()?);
+                },
+                EXTENSION_PRE_SHARED_KEY => selected_identity = Some(data.u16()?),
+                _ => {
+                    return Err(Abort::new(
+                        ALERT_UNSUPPORTED_EXTENSION,
+                        format!(
+                            "server sent an extension that was not offered (type={:?})",
+                            extension_type
+                        ),
+                    ))
+                },
+            }
+            data.finish()?;
+        }
+
+        if version != Some(self.versions().1) {
+            return Err(Abort::new(
+                ALERT_PROTOCOL_VERSION,
+                format!("server did not select version 1.3 (version={:?})", version),
+            ));
+        }
+        match selected_identity {
+            Some(0) => (),
+            Some(identity) => {
+                return Err(Abort::new(
+                    ALERT_ILLEGAL_PARAMETER,
+                    format!(
+                        "server selected a pre-shared key that was not offered (identity={:?})",
+                        identity
+                    ),
+                ))
+            },
+            None => {
+                return Err(Abort::new(
+                    ALERT_HANDSHAKE_FAILURE,
+                    "server did not accept the pre-shared key".to_string(),
+                ))
+            },
+        }
+        let server_share: [u8; X25519_KEY_SIZE] = match server_share.map(|share| share.try_into()) {
+            Some(Ok(share)) => share,
+            Some(Err(_)) => return Err(Abort::new(ALERT_ILLEGAL_PARAMETER, "malformed key share".to_string())),
+            None => {
+                return Err(Abort::new(
+                    ALERT_MISSING_EXTENSION,
+                    "server did not send a key share".to_string(),
+                ))
+            },
+        };
+
+        let shared_secret: [u8; X25519_KEY_SIZE] = self.provider().x25519(&self.key_share, &server_share);
+        self.key_share = [0; X25519_KEY_SIZE];
+        check_shared_secret(&shared_secret)?;
+
+        self.transcript.update(msg);
+        let transcript_hash: Secret = self.transcript_hash();
+        (self.client_handshake_secret, self.server_handshake_secret) =
+            self.key_schedule.handshake_secrets(&shared_secret, &transcript_hash);
+        self.check_key_change()?;
+        self.set_read_secret(self.server_handshake_secret);
+        self.set_write_secret(self.client_handshake_secret);
+        self.state = HandshakeState::EncryptedExtensions;
+        Ok(())
+    }
+
+    /// Processes a HelloRetryRequest, which a DTLS server sends to make the client prove that it owns its address
+    /// before the server keeps any state. The ClientHello is sent again with the cookie of the server. Since this side
+    /// only offers the key share that the server would ask for, other HelloRetryRequests are rejected.
+    fn process_hello_retry_request(&mut self, msg: &[u8]) -> Result<(), Abort> {
+        let cookie_allowed: bool = match self.external.as_ref() {
+            Some(external) => external.carrier == Carrier::Dtls && external.cookie.is_none(),
+            None => false,
+        };
+        if !cookie_allowed {
+            return Err(Abort::new(
+                ALERT_HANDSHAKE_FAILURE,
+                "server asked for a key share that is not supported".to_string(),
+            ));
+        }
+        let mut reader: Reader = Reader::new(&msg[(4 + 2 + 32)..]);
+        if !reader.vec8()?.is_empty() {
+            return Err(Abort::new(
+                ALERT_ILLEGAL_PARAMETER,
+                "server echoed a session ID that was not sent".to_string(),
+            ));
+        }
+        let cipher_suite: u16 = reader.u16()?;
+        if cipher_suite != TLS_CHACHA20_POLY1305_SHA256 || reader.u8()? != 0 {
+            return Err(Abort::new(
+                ALERT_ILLEGAL_PARAMETER,
+                "server retried with parameters that were not offered".to_string(),
+            ));
+        }
+        let mut extensions: Reader = Reader::new(reader.vec16()?);
+        reader.finish()?;
+
+        let mut version: Option<u16> = None;
+        let mut cookie: Option<&[u8]> = None;
+        while !extensions.is_empty() {
+            let extension_type: u16 = extensions.u16()?;
+            let mut data: Reader = Reader::new(extensions.vec16()?);
+            match extension_type {
+                EXTENSION_SUPPORTED_VERSIONS => version = Some(data.u16()?),
+                EXTENSION_COOKIE => cookie = Some(data.vec16()?).filter(|cookie| !cookie.is_empty()),
+                _ => {
+                    return Err(Abort::new(
+                        ALERT_HANDSHAKE_FAILURE,
+                        format!(
+                            "server asked for a change that is not supported (type={:?})",
+                            extension_type
+                        ),
+                    ))
+                },
+            }
+            data.finish()?;
+        }
+        if version != Some(DTLS13_VERSION) {
+            return Err(Abort::new(
+                ALERT_PROTOCOL_VERSION,
+                format!("server did not select DTLS 1.3 (version={:?})", version),
+            ));
+        }
+        let cookie: Vec<u8> = match cookie {
+            Some(cookie) => cookie.to_vec(),
+            None => {
+                return Err(Abort::new(
+                    ALERT_ILLEGAL_PARAMETER,
+                    "server retried without a cookie".to_string(),
+                ))
+            },
+        };
+
+        // The first ClientHello is replaced by its hash in the transcript (RFC 8446, section 4.4.1).
+        let first_hello_hash: Secret = self.transcript_hash();
+        self.transcript = Sha256::new();
+        self.transcript.update(&message_hash(&first_hello_hash));
+        self.transcript.update(msg);
+        if let Some(external) = self.external.as_mut() {
+            external.cookie = Some(cookie);
+        }
+        self.write_client_hello();
+        Ok(())
+    }
+
+    /// Processes the EncryptedExtensions. Apart from the transport parameters of a QUIC server, the client only sends
+    /// extensions that the server answers in the ServerHello, so there is nothing else to act upon.
+    fn process_encrypted_extensions(&mut self, msg: &[u8]) -> Result<(), Abort> {
+        let mut reader: Reader = Reader::new(&msg[4..]);
+        let mut extensions: Reader = Reader::new(reader.vec16()?);
+        reader.finish()?;
+        let mut transport_parameters: Option<&[u8]> = None;
+        while !extensions.is_empty() {
+            let extension_type: u16 = extensions.u16()?;
+            let data: &[u8] = extensions.vec16()?;
+            if extension_type == EXTENSION_QUIC_TRANSPORT_PARAMETERS {
+                transport_parameters = Some(data);
+            }
+        }
+        self.set_peer_transport_parameters(transport_parameters)?;
+        self.transcript.update(msg);
+        self.state = HandshakeState::ServerFinished;
+        Ok(())
+    }
+
+    /// Processes the Finished message of the server, answers it with the Finished message of the client and switches
+    /// to the application traffic keys.
+    fn process_server_finished(&mut self, msg: &[u8]) -> Result<(), Abort> {
+        let expected: Secret = self
+            .key_schedule
+            .finished_verify_data(&self.server_handshake_secret, &self.transcript_hash());
+        check_verify_data(&msg[4..], &expected)?;
+        self.transcript.update(msg);
+        let (client_secret, server_secret): (Secret, Secret) =
+            self.key_schedule.application_secrets(&self.transcript_hash());
+
+        let verify_data: Secret = self
+            .key_schedule
+            .finished_verify_data(&self.client_handshake_secret, &self.transcript_hash());
+        let mut finished: Vec<u8> = Vec::new();
+        put_handshake(&mut finished, HANDSHAKE_FINISHED, |body| {
+            body.extend_from_slice(&verify_data)
+        });
+        self.transcript.update(&finished);
+        self.send_handshake(&finished)?;
+
+        self.check_key_change()?;
+        self.set_read_secret(server_secret);
+        self.set_write_secret(client_secret);
+        self.state = HandshakeState::Connected;
+        Ok(())
+    }
+
+    /// Processes the ClientHello and answers it with the ServerHello, the EncryptedExtensions and the Finished message
+    /// of the server.
+    fn process_client_hello(&mut self, msg: &[u8]) -> Result<(), Abort> {
+        let mut reader: Reader = Reader::new(&msg[4..]);
+        let _legacy_version: u16 = reader.u16()?;
+        let _random: &[u8] = reader.bytes(32)?;
+        let session_id: &[u8] = reader.vec8()?;
+        if session_id.len() > 32 {
+            return Err(Abort::new(
+                ALERT_ILLEGAL_PARAMETER,
+                format!("session ID is too long (len={:?})", session_id.len()),
+            ));
+        }
+        // There is no middlebox compatibility mode over QUIC or DTLS (RFC 9001, section 8.4 and RFC 9147, section 5.3).
+        if self.external.is_some() && !session_id.is_empty() {
+            return Err(Abort::new(
+                ALERT_ILLEGAL_PARAMETER,
+                "client sent a legacy session ID".to_string(),
+            ));
+        }
+        if self.carrier() == Some(Carrier::Dtls) && !reader.vec8()?.is_empty() {
+            return Err(Abort::new(
+                ALERT_ILLEGAL_PARAMETER,
+                "client sent a legacy cookie".to_string(),
+            ));
+        }
+        let cipher_suites: &[u8] = reader.vec16()?;
+        if !cipher_suites
+            .chunks_exact(2)
+            .any(|suite| suite == TLS_CHACHA20_POLY1305_SHA256.to_be_bytes())
+        {
+            return Err(Abort::new(
+                ALERT_HANDSHAKE_FAILURE,
+                "client does not support TLS_CHACHA20_POLY1305_SHA256".to_string(),
+            ));
+        }
+        if reader.vec8()? != [0] {
+            return Err(Abort::new(
+                ALERT_ILLEGAL_PARAMETER,
+                "client offered compression methods".to_string(),
+            ));
+        }
+        let mut extensions: Reader = Reader::new(reader.vec16()?);
+        reader.finish()?;
+
+        let (legacy_version, version): (u16, u16) = self.versions();
+        let mut supports_version: bool = false;
+        let mut client_share: Option<[u8; X25519_KEY_SIZE]> = None;
+        let mut supports_psk_dhe_ke: bool = false;
+        let mut pre_shared_key: Option<&[u8]> = None;
+        let mut transport_parameters: Option<&[u8]> = None;
+        while !extensions.is_empty() {
+            let extension_type: u16 = extensions.u16()?;
+            let mut data: Reader = Reader::new(extensions.vec16()?);
+            match extension_type {
+                EXTENSION_SUPPORTED_VERSIONS => {
+                    supports_version = data
+                        .vec8()?
+                        .chunks_exact(2)
+                        .any(|offered| offered == version.to_be_bytes());
+                },
+                EXTENSION_KEY_SHARE => {
+                    let mut shares: Reader = Reader::new(data.vec16()?);
+                    while !shares.is_empty() {
+                        let group: u16 = shares.u16()?;
+                        let key: &[u8] = shares.vec16()?;
+                        if group == GROUP_X25519 && client_share.is_none() {
+                            client_share = key.try_into().ok();
+                        }
+                    }
+                },
+                EXTENSION_PSK_KEY_EXCHANGE_MODES => supports_psk_dhe_ke = data.vec8()?.contains(&PSK_DHE_KE),
+                EXTENSION_PRE_SHARED_KEY => {
+                    if !extensions.is_empty() {
+                        return Err(Abort::new(
+                            ALERT_ILLEGAL_PARAMETER,
+                            "pre-shared key extension is not the last one".to_string(),
+                        ));
+                    }
+                    pre_shared_key = Some(data.bytes(data.remaining())?);
+                },
+                EXTENSION_QUIC_TRANSPORT_PARAMETERS => transport_parameters = Some(data.bytes(data.remaining())?),
+                // Extensions that are not supported are ignored.
+                _ => continue,
+            }
+            data.finish()?;
+        }
+
+        if !supports_version {
+            return Err(Abort::new(
+                ALERT_PROTOCOL_VERSION,
+                format!("client does not support version 1.3 (version={:#x})", version),
+            ));
+        }
+        let pre_shared_key: &[u8] = match pre_shared_key {
+            Some(pre_shared_key) if supports_psk_dhe_ke => pre_shared_key,
+            _ => {
+                return Err(Abort::new(
+                    ALERT_HANDSHAKE_FAILURE,
+                    "client did not offer a pre-shared key with a key exchange".to_string(),
+                ))
+            },
+        };
+        let client_share: [u8; X25519_KEY_SIZE] = match client_share {
+            Some(client_share) => client_share,
+            None => {
+                return Err(Abort::new(
+                    ALERT_HANDSHAKE_FAILURE,
+                    "client did not offer an X25519 key share".to_string(),
+                ))
+            },
+        };
+        let selected_identity: u16 = self.check_psk_binder(msg, pre_shared_key)?;
+        self.set_peer_transport_parameters(transport_parameters)?;
+
+        // Answer with the ServerHello, which carries the ephemeral key share of the server.
+        let provider: Rc<dyn CryptoProvider> = self.provider();
+        let mut random: [u8; 32] = [0; 32];
+        provider.fill_random(&mut random);
+        let mut key_share: [u8; X25519_KEY_SIZE] = [0; X25519_KEY_SIZE];
+        provider.fill_random(&mut key_share);
+        let public: [u8; X25519_KEY_SIZE] = provider.x25519(&key_share, &X25519_BASE_POINT);
+        let shared_secret: [u8; X25519_KEY_SIZE] = provider.x25519(&key_share, &client_share);
+        check_shared_secret(&shared_secret)?;
+
+        let mut server_hello: Vec<u8> = Vec::new();
+        put_handshake(&mut server_hello, HANDSHAKE_SERVER_HELLO, |body| {
+            body.extend_from_slice(&legacy_version.to_be_bytes());
+            body.extend_from_slice(&random);
+            put_vec(body, 1, |id| id.extend_from_slice(session_id));
+            body.extend_from_slice(&TLS_CHACHA20_POLY1305_SHA256.to_be_bytes());
+            body.push(0);
+            put_vec(body, 2, |extensions| {
+                put_extension(extensions, EXTENSION_SUPPORTED_VERSIONS, |data| {
+                    data.extend_from_slice(&version.to_be_bytes())
+                });
+                put_extension(extensions, EXTENSION_KEY_SHARE, |data| {
+                    data.extend_from_slice(&GROUP_X25519.to_be_bytes());
+                    put_vec(data, 2, |key| key.extend_from_slice(&public));
+                });
+                put_extension(extensions, EXTENSION_PRE_SHARED_KEY, |data| {
+                    data.extend_from_slice(&selected_identity.to_be_bytes())
+                });
+            });
+        });
+        self.transcript.update(msg);
+        self.transcript.update(&server_hello);
+        self.send_handshake(&server_hello)?;
+
+        let transcript_hash: Secret = self.transcript_hash();
+        (self.client_handshake_secret, self.server_handshake_secret) =
+            self.key_schedule.handshake_secrets(&shared_secret, &transcript_hash);
+        self.check_key_change()?;
+        self.set_read_secret(self.client_handshake_secret);
+        self.set_write_secret(self.server_handshake_secret);
+
+        let transport_parameters: Option<&[u8]> = self.local_transport_parameters();
+        let mut encrypted_extensions: Vec<u8> = Vec::new();
+        put_handshake(&mut encrypted_extensions, HANDSHAKE_ENCRYPTED_EXTENSIONS, |body| {
+            put_vec(body, 2, |extensions| {
+                if let Some(transport_parameters) = transport_parameters {
+                    put_extension(extensions, EXTENSION_QUIC_TRANSPORT_PARAMETERS, |data| {
+                        data.extend_from_slice(transport_parameters)
+                    });
+                }
+            })
+        });
+        self.transcript.update(&encrypted_extensions);
+        self.send_handshake(&encrypted_extensions)?;
+
+        let verify_data: Secret = self
+            .key_schedule
+            .finished_verify_data(&self.server_handshake_secret, &self.transcript_hash());
+        let mut finished: Vec<u8> = Vec::new();
+        put_handshake(&mut finished, HANDSHAKE_FINISHED, |body| {
+            body.extend_from_slice(&verify_data)
+        });
+        self.transcript.update(&finished);
+        self.send_handshake(&finished)?;
+
+        // The server sends with the application traffic keys right away, but only accepts application data once the
+        // client has proven that it holds the pre-shared key too.
+        let (client_secret, server_secret): (Secret, Secret) =
+            self.key_schedule.application_secrets(&self.transcript_hash());
+        self.set_write_secret(server_secret);
+        self.client_application_secret = Some(client_secret);
+        self.state = HandshakeState::ClientFinished;
+        Ok(())
+    }
+
+    /// Keeps the [transport_parameters] of the peer of a QUIC connection, which it must send (RFC 9001, section 8.2).
+    /// Sessions that do not run over QUIC ignore them.
+    fn set_peer_transport_parameters(&mut self, transport_parameters: Option<&[u8]>) -> Result<(), Abort> {
+        if let Some(external) = self
+            .external
+            .as_mut()
+            .filter(|external| external.carrier == Carrier::Quic)
+        {
+            match transport_parameters {
+                Some(transport_parameters) => external.peer_parameters = Some(transport_parameters.to_vec()),
+                None => {
+                    return Err(Abort::new(
+                        ALERT_MISSING_EXTENSION,
+                        "peer did not send its transport parameters".to_string(),
+                    ))
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Looks for the pre-shared key of the configuration among those that the client offered in [pre_shared_key],
+    /// checks its binder against the transcript up to the ClientHello [msg] and returns its index.
+    fn check_psk_binder(&self, msg: &[u8], pre_shared_key: &[u8]) -> Result<u16, Abort> {
+        let mut reader: Reader = Reader::new(pre_shared_key);
+        let mut identities: Reader = Reader::new(reader.vec16()?);
+        // The binders come last in the ClientHello.
+        let binders_size: usize = reader.remaining();
+        let mut binders: Reader = Reader::new(reader.vec16()?);
+        reader.finish()?;
+
+        let mut selected_identity: Option<usize> = None;
+        let mut num_identities: usize = 0;
+        while !identities.is_empty() {
+            let identity: &[u8] = identities.vec16()?;
+            let _obfuscated_ticket_age: u32 = identities.u32()?;
+            if selected_identity.is_none() && identity == self.config.psk_identity() {
+                selected_identity = Some(num_identities);
+            }
+            num_identities += 1;
+        }
+        let mut binder_list: Vec<&[u8]> = Vec::with_capacity(num_identities);
+        while !binders.is_empty() {
+            binder_list.push(binders.vec8()?);
+        }
+        if binder_list.len() != num_identities {
+            return Err(Abort::new(
+                ALERT_ILLEGAL_PARAMETER,
+                format!(
+                    "number of binders does not match number of identities (binders={:?}, identities={:?})",
+                    binder_list.len(),
+                    num_identities
+                ),
+            ));
+        }
+
+        let selected_identity: usize = match selected_identity {
+            Some(selected_identity) => selected_identity,
+            None => {
+                return Err(Abort::new(
+                    ALERT_UNKNOWN_PSK_IDENTITY,
+                    "client did not offer the configured pre-shared key".to_string(),
+                ))
+            },
+        };
+        let mut transcript: Sha256 = self.transcript.clone();
+        transcript.update(&msg[..(msg.len() - binders_size)]);
+        let expected: Secret = self.key_schedule.psk_binder(&transcript.finalize());
+        check_verify_data(binder_list[selected_identity], &expected)?;
+        Ok(selected_identity as u16)
+    }
+
+    /// Processes the Finished message of the client and switches to the application traffic keys.
+    fn process_client_finished(&mut self, msg: &[u8]) -> Result<(), Abort> {
+        let expected: Secret = self
+            .key_schedule
+            .finished_verify_data(&self.client_handshake_secret, &self.transcript_hash());
+        check_verify_data(&msg[4..], &expected)?;
+        self.transcript.update(msg);
+        self.check_key_change()?;
+        let client_secret: Secret = expect_client_secret(self.client_application_secret.take());
+        self.set_read_secret(client_secret);
+        self.state = HandshakeState::Connected;
+        Ok(())
+    }
+
+    /// Processes a KeyUpdate and answers it if the peer asks to.
+    fn process_key_update(&mut self, msg: &[u8]) -> Result<(), Abort> {
+        let mut reader: Reader = Reader::new(&msg[4..]);
+        let request_update: u8 = reader.u8()?;
+        reader.finish()?;
+        if request_update > 1 {
+            return Err(Abort::new(
+                ALERT_ILLEGAL_PARAMETER,
+                format!("malformed key update (request_update={:?})", request_update),
+            ));
+        }
+        self.check_key_change()?;
+        if let Some(read) = self.read.as_mut() {
+            read.update();
+        }
+        if request_update == 1 && !self.closed {
+            let mut key_update: Vec<u8> = Vec::new();
+            put_handshake(&mut key_update, HANDSHAKE_KEY_UPDATE, |body| body.push(0));
+            self.send_handshake(&key_update)?;
+            if let Some(write) = self.write.as_mut() {
+                write.update();
+            }
+            self.reinstall_tx_key()?;
+        }
+        Ok(())
+    }
+
+    /// Installs the new key of outgoing records on the device that encrypts them, after a key update. The new key
+    /// applies from the first record that is queued after the KeyUpdate message.
+    fn reinstall_tx_key(&mut self) -> Result<(), Abort> {
+        let tx_offload: &mut TxOffload = match self.tx_offload.as_mut() {
+            Some(tx_offload) => tx_offload,
+            None => return Ok(()),
+        };
+        let write: &mut RecordProtection = expect_write(&mut self.write);
+        let offset: u32 = tx_offload.sent.wrapping_add(self.outgoing.len() as u32);
+        let flow: TlsOffloadFlow = TlsOffloadFlow {
+            tcp_seq: tx_offload.flow.tcp_seq.wrapping_add(offset),
+            ..tx_offload.flow
+        };
+        let key: TlsOffloadKey = write.offload_key(TLS_CHACHA20_POLY1305_SHA256);
+        let handle: TlsOffloadHandle = tx_offload
+            .device
+            .install_tx_key(&flow, &key)
+            .map_err(|e| Abort::new(ALERT_INTERNAL_ERROR, e.cause))?;
+        write.set_offloaded();
+        tx_offload.retired.push(tx_offload.handle);
+        tx_offload.handle = handle;
+        Ok(())
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Drop for TxOffload {
+    /// Removes the keys of the session from the device.
+    fn drop(&mut self) {
+        for handle in self.retired.drain(..) {
+            self.device.remove_key(handle);
+        }
+        self.device.remove_key(self.handle);
+    }
+}
+
+impl From<Fail> for Abort {
+    /// Errors of the message parser are decoding errors.
+    fn from(e: Fail) -> Self {
+        Abort::new(ALERT_DECODE_ERROR, e.cause)
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Builds the HelloRetryRequest that a DTLS 1.3 server answers a ClientHello without a cookie with, so that the client
+/// sends it again with [cookie]. The message only depends on [cookie], so that a server can rebuild it once the cookie
+/// comes back instead of keeping it.
+pub fn dtls_hello_retry_request(cookie: &[u8]) -> Vec<u8> {
+    let mut msg: Vec<u8> = Vec::new();
+    put_handshake(&mut msg, HANDSHAKE_SERVER_HELLO, |body| {
+        body.extend_from_slice(&DTLS_LEGACY_VERSION.to_be_bytes());
+        body.extend_from_slice(&HELLO_RETRY_REQUEST_RANDOM);
+        put_vec(body, 1, |_| ());
+        body.extend_from_slice(&TLS_CHACHA20_POLY1305_SHA256.to_be_bytes());
+        body.push(0);
+        put_vec(body, 2, |extensions| {
+            put_extension(extensions, EXTENSION_SUPPORTED_VERSIONS, |data| {
+                data.extend_from_slice(&DTLS13_VERSION.to_be_bytes())
+            });
+            put_extension(extensions, EXTENSION_COOKIE, |data| {
+                put_vec(data, 2, |cookie_data| cookie_data.extend_from_slice(cookie))
+            });
+        });
+    });
+    msg
+}
+
+/// Gets the cookie that the DTLS 1.3 ClientHello [msg] echoes, if any.
+pub fn dtls_client_hello_cookie(msg: &[u8]) -> Result<Option<Vec<u8>>, Fail> {
+    let mut reader: Reader = Reader::new(msg);
+    if reader.u8()? != HANDSHAKE_CLIENT_HELLO {
+        let cause: &str = "message is not a ClientHello";
+        warn!("dtls_client_hello_cookie(): {}", cause);
+        return Err(Fail::new(libc::EBADMSG, cause));
+    }
+    let len: usize = reader.u24()? as usize;
+    let mut body: Reader = Reader::new(reader.bytes(len)?);
+    reader.finish()?;
+    let _legacy_version: u16 = body.u16()?;
+    let _random: &[u8] = body.bytes(32)?;
+    let _legacy_session_id: &[u8] = body.vec8()?;
+    let _legacy_cookie: &[u8] = body.vec8()?;
+    let _cipher_suites: &[u8] = body.vec16()?;
+    let _compression_methods: &[u8] = body.vec8()?;
+    let mut extensions: Reader = Reader::new(body.vec16()?);
+    while !extensions.is_empty() {
+        let extension_type: u16 = extensions.u16()?;
+        let mut data: Reader = Reader::new(extensions.vec16()?);
+        if extension_type == EXTENSION_COOKIE {
+            let cookie: &[u8] = data.vec16()?;
+            data.finish()?;
+            return Ok(Some(cookie.to_vec()));
+        }
+    }
+    Ok(None)
+}
+
+/// Builds the message that stands in for a ClientHello with [hash] in the transcript, after a HelloRetryRequest.
+fn message_hash(hash: &Secret) -> Vec<u8> {
+    let mut msg: Vec<u8> = Vec::new();
+    put_handshake(&mut msg, HANDSHAKE_MESSAGE_HASH, |body| body.extend_from_slice(hash));
+    msg
+}
+
+/// Gets the protection of outgoing records of an established session.
+fn expect_write(write: &mut Option<RecordProtection>) -> &mut RecordProtection {
+    expect_some!(write.as_mut(), "established sessions encrypt records")
+}
+
+/// Gets the application traffic secret of the client on a server that waits for the Finished message of the client.
+fn expect_client_secret(secret: Option<Secret>) -> Secret {
+    expect_some!(
+        secret,
+        "servers derive the client secret before they wait for its Finished message"
+    )
+}
+
+/// Gets the encryption level that follows [level] in the handshake.
+fn next_level(level: EncryptionLevel) -> EncryptionLevel {
+    match level {
+        EncryptionLevel::Initial => EncryptionLevel::Handshake,
+        EncryptionLevel::Handshake | EncryptionLevel::Application => EncryptionLevel::Application,
+    }
+}
+
+/// Compares the verify data of a Finished message or a binder with [expected], in constant time.
+fn check_verify_data(verify_data: &[u8], expected: &Secret) -> Result<(), Abort> {
+    let diff: u8 = if verify_data.len() == expected.len() {
+        verify_data
+            .iter()
+            .zip(expected.iter())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+    } else {
+        1
+    };
+    if diff != 0 {
+        return Err(Abort::new(ALERT_DECRYPT_ERROR, "verify data mismatch".to_string()));
+    }
+    Ok(())
+}
+
+/// Rejects the all-zero X25519 shared secret, which results from a key share of small order (RFC 8446, section 7.4.2).
+fn check_shared_secret(shared_secret: &[u8; X25519_KEY_SIZE]) -> Result<(), Abort> {
+    if shared_secret.iter().all(|byte| *byte == 0) {
+        return Err(Abort::new(
+            ALERT_ILLEGAL_PARAMETER,
+            "key share of small order".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::{
+        crypto::chacha20poly1305::{
+            chacha20_poly1305_seal,
+            CHACHA20_POLY1305_NONCE_SIZE,
+            CHACHA20_POLY1305_TAG_SIZE,
+        },
+        fail::Fail,
+        network::tls::{
+            offload::{
+                TlsOffload,
+                TlsOffloadCapabilities,
+                TlsOffloadFlow,
+                TlsOffloadHandle,
+                TlsOffloadKey,
+            },
+            record::RECORD_HEADER_SIZE,
+            session::TlsSession,
+            EncryptionLevel,
+            TlsConfig,
+            TlsRole,
+            TLS_CHACHA20_POLY1305_SHA256,
+        },
+    };
+    use ::anyhow::Result;
+    use ::std::{
+        cell::RefCell,
+        collections::HashMap,
+        net::{
+            Ipv4Addr,
+            SocketAddrV4,
+        },
+        rc::Rc,
+    };
+
+    /// Device that encrypts records in software, the way a NIC with TLS offload does on transmission.
+    #[derive(Default)]
+    struct FakeOffload {
+        tx_cipher_suites: Vec<u16>,
+        keys: RefCell<HashMap<TlsOffloadHandle, (TlsOffloadFlow, TlsOffloadKey)>>,
+        next_handle: RefCell<u64>,
+    }
+
+    impl FakeOffload {
+        /// Encrypts the plaintext records in [data], which carries the TCP payload from the start of [flow].
+        fn transmit(&self, flow: &TlsOffloadFlow, data: &mut [u8]) {
+            let (_, key): (TlsOffloadFlow, TlsOffloadKey) = self
+                .keys
+                .borrow()
+                .values()
+                .find(|(installed, _)| installed == flow)
+                .cloned()
+                .expect("key should be installed");
+            let mut record_seq: u64 = key.record_seq;
+            let mut offset: usize = 0;
+            while offset < data.len() {
+                let len: usize = u16::from_be_bytes([data[offset + 3], data[offset + 4]]) as usize;
+                let header: Vec<u8> = data[offset..(offset + RECORD_HEADER_SIZE)].to_vec();
+                let payload_start: usize = offset + RECORD_HEADER_SIZE;
+                let payload_end: usize = payload_start + len - CHACHA20_POLY1305_TAG_SIZE;
+                let mut nonce: [u8; CHACHA20_POLY1305_NONCE_SIZE] = key.iv;
+                for (byte, seq_byte) in nonce[4..].iter_mut().zip(record_seq.to_be_bytes()) {
+                    *byte ^= seq_byte;
+                }
+                let mut payload: Vec<u8> = data[payload_start..payload_end].to_vec();
+                chacha20_poly1305_seal(&key.key, &nonce, &header, &mut payload);
+                data[payload_start..(payload_start + len)].copy_from_slice(&payload);
+                record_seq += 1;
+                offset = payload_start + len;
+            }
+        }
+    }
+
+    impl TlsOffload for FakeOffload {
+        fn capabilities(&self) -> TlsOffloadCapabilities {
+            TlsOffloadCapabilities {
+                tx_cipher_suites: self.tx_cipher_suites.clone(),
+            }
+        }
+
+        fn install_tx_key(&self, flow: &TlsOffloadFlow, key: &TlsOffloadKey) -> Result<TlsOffloadHandle, Fail> {
+            let mut next_handle = self.next_handle.borrow_mut();
+            let handle: TlsOffloadHandle = TlsOffloadHandle(*next_handle);
+            *next_handle += 1;
+            self.keys.borrow_mut().insert(handle, (*flow, key.clone()));
+            Ok(handle)
+        }
+
+        fn remove_key(&self, handle: TlsOffloadHandle) {
+            self.keys.borrow_mut().remove(&handle);
+        }
+    }
+
+    /// Moves the records of [from] to [to], [chunk_size] bytes at a time.
+    fn deliver(from: &mut TlsSession, to: &mut TlsSession, chunk_size: usize) -> Result<()> {
+        if let Some(records) = from.take_output() {
+            for chunk in records.chunks(chunk_size) {
+                to.receive(chunk)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the handshake between [client] and [server].
+    fn handshake(client: &mut TlsSession, server: &mut TlsSession, chunk_size: usize) -> Result<()> {
+        deliver(client, server, chunk_size)?;
+        crate::ensure_eq!(server.is_handshake_complete(), false);
+        deliver(server, client, chunk_size)?;
+        crate::ensure_eq!(client.is_handshake_complete(), true);
+        deliver(client, server, chunk_size)?;
+        crate::ensure_eq!(server.is_handshake_complete(), true);
+        Ok(())
+    }
+
+    fn new_sessions(client_psk: &[u8], server_psk: &[u8]) -> Result<(TlsSession, TlsSession)> {
+        let client: TlsSession = TlsSession::new(TlsRole::Client, TlsConfig::new(b"client", client_psk)?);
+        let server: TlsSession = TlsSession::new(TlsRole::Server, TlsConfig::new(b"client", server_psk)?);
+        Ok((client, server))
+    }
+
+    /// Moves the handshake messages of [from] to [to], level by level.
+    fn deliver_quic(from: &mut TlsSession, to: &mut TlsSession) -> Result<()> {
+        for level in [
+            EncryptionLevel::Initial,
+            EncryptionLevel::Handshake,
+            EncryptionLevel::Application,
+        ] {
+            if let Some(data) = from.take_handshake(level) {
+                to.receive_handshake(level, &data)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn new_quic_sessions(client_psk: &[u8], server_psk: &[u8]) -> Result<(TlsSession, TlsSession)> {
+        let client_config: TlsConfig = TlsConfig::new(b"client", client_psk)?;
+        let server_config: TlsConfig = TlsConfig::new(b"client", server_psk)?;
+        let client: TlsSession = TlsSession::new_quic(TlsRole::Client, client_config, b"client parameters");
+        let server: TlsSession = TlsSession::new_quic(TlsRole::Server, server_config, b"server parameters");
+        Ok((client, server))
+    }
+
+    /// Reads all application data of [session].
+    fn read_all(session: &mut TlsSession) -> Vec<u8> {
+        let mut data: Vec<u8> = Vec::new();
+        while let Some(chunk) = session.read(1000) {
+            data.extend(chunk);
+        }
+        data
+    }
+
+    #[test]
+    fn test_handshake_and_data_transfer() -> Result<()> {
+        let (mut client, mut server): (TlsSession, TlsSession) = new_sessions(&[7; 32], &[7; 32])?;
+        handshake(&mut client, &mut server, usize::MAX)?;
+
+        client.write(b"ping")?;
+        deliver(&mut client, &mut server, usize::MAX)?;
+        crate::ensure_eq!(server.read(2), Some(b"pi".to_vec()));
+        crate::ensure_eq!(server.read(100), Some(b"ng".to_vec()));
+        crate::ensure_eq!(server.read(100), None);
+
+        // Data that does not fit in one record.
+        let data: Vec<u8> = (0..40000).map(|i| i as u8).collect();
+        server.write(&data)?;
+        deliver(&mut server, &mut client, usize::MAX)?;
+        crate::ensure_eq!(read_all(&mut client), data);
+
+        client.close();
+        deliver(&mut client, &mut server, usize::MAX)?;
+        crate::ensure_eq!(server.is_peer_closed(), true);
+        crate::ensure_eq!(client.write(b"late").is_err(), true);
+        Ok(())
+    }
+
+    #[test]
+    fn test_records_delivered_byte_by_byte() -> Result<()> {
+        let (mut client, mut server): (TlsSession, TlsSession) = new_sessions(&[7; 32], &[7; 32])?;
+        handshake(&mut client, &mut server, 1)?;
+        server.write(b"pong")?;
+        deliver(&mut server, &mut client, 1)?;
+        crate::ensure_eq!(read_all(&mut client), b"pong".to_vec());
+        Ok(())
+    }
+
+    #[test]
+    fn test_psk_mismatch_fails_handshake() -> Result<()> {
+        let (mut client, mut server): (TlsSession, TlsSession) = new_sessions(&[7; 32], &[8; 32])?;
+        let records: Vec<u8> = client.take_output().unwrap_or_default();
+        let e = server.receive(&records).err().unwrap();
+        crate::ensure_eq!(e.errno, libc::EACCES);
+        crate::ensure_eq!(server.write(b"data").is_err(), true);
+
+        // The client learns about the failure from the alert of the server.
+        let alert: Vec<u8> = server.take_output().unwrap_or_default();
+        let e = client.receive(&alert).err().unwrap();
+        crate::ensure_eq!(e.errno, libc::ECONNRESET);
+        crate::ensure_eq!(client.is_handshake_complete(), false);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tampered_record_is_rejected() -> Result<()> {
+        let (mut client, mut server): (TlsSession, TlsSession) = new_sessions(&[7; 32], &[7; 32])?;
+        handshake(&mut client, &mut server, usize::MAX)?;
+        client.write(b"secret")?;
+        let mut records: Vec<u8> = client.take_output().unwrap_or_default();
+        let last: usize = records.len() - 1;
+        records[last] ^= 1;
+        let e = server.receive(&records).err().unwrap();
+        crate::ensure_eq!(e.errno, libc::EBADMSG);
+        crate::ensure_eq!(server.read(100), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_transmit_offload() -> Result<()> {
+        let (mut client, mut server): (TlsSession, TlsSession) = new_sessions(&[7; 32], &[7; 32])?;
+        handshake(&mut client, &mut server, usize::MAX)?;
+
+        let device: Rc<FakeOffload> = Rc::new(FakeOffload {
+            tx_cipher_suites: vec![TLS_CHACHA20_POLY1305_SHA256],
+            ..Default::default()
+        });
+        let flow: TlsOffloadFlow = TlsOffloadFlow {
+            local: SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 443),
+            remote: SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 2), 50000),
+            tcp_seq: 1000,
+        };
+        client.offload_transmit(device.clone(), flow)?;
+        crate::ensure_eq!(client.is_transmit_offloaded(), true);
+        crate::ensure_eq!(device.keys.borrow().len(), 1);
+
+        // Records are handed out in plaintext and only become valid once the device encrypts them.
+        let data: Vec<u8> = (0..20000).map(|i| i as u8).collect();
+        client.write(&data)?;
+        let mut records: Vec<u8> = client.take_output().unwrap_or_default();
+        crate::ensure_eq!(records[RECORD_HEADER_SIZE..(RECORD_HEADER_SIZE + 100)], data[..100]);
+        device.transmit(&flow, &mut records);
+        server.receive(&records)?;
+        crate::ensure_eq!(read_all(&mut server), data);
+
+        // Keys are removed from the device along with the session.
+        drop(client);
+        crate::ensure_eq!(device.keys.borrow().is_empty(), true);
+        Ok(())
+    }
+
+    #[test]
+    fn test_transmit_offload_falls_back_to_software() -> Result<()> {
+        let (mut client, mut server): (TlsSession, TlsSession) = new_sessions(&[7; 32], &[7; 32])?;
+        let flow: TlsOffloadFlow = TlsOffloadFlow {
+            local: SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 443),
+            remote: SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 2), 50000),
+            tcp_seq: 0,
+        };
+        let device: Rc<FakeOffload> = Rc::new(FakeOffload::default());
+        let e = client.offload_transmit(device.clone(), flow).err().unwrap();
+        crate::ensure_eq!(e.errno, libc::ENOTCONN);
+
+        handshake(&mut client, &mut server, usize::MAX)?;
+        let e = client.offload_transmit(device.clone(), flow).err().unwrap();
+        crate::ensure_eq!(e.errno, libc::ENOTSUP);
+        crate::ensure_eq!(device.keys.borrow().is_empty(), true);
+
+        client.write(b"ping")?;
+        deliver(&mut client, &mut server, usize::MAX)?;
+        crate::ensure_eq!(read_all(&mut server), b"ping".to_vec());
+        Ok(())
+    }
+
+    #[test]
+    fn test_quic_handshake() -> Result<()> {
+        let (mut client, mut server): (TlsSession, TlsSession) = new_quic_sessions(&[7; 32], &[7; 32])?;
+        crate::ensure_eq!(client.take_write_secret(EncryptionLevel::Initial), None);
+        deliver_quic(&mut client, &mut server)?;
+        deliver_quic(&mut server, &mut client)?;
+        crate::ensure_eq!(client.is_handshake_complete(), true);
+        deliver_quic(&mut client, &mut server)?;
+        crate::ensure_eq!(server.is_handshake_complete(), true);
+
+        // Both sides exported matching secrets for every level past the initial one.
+        for level in [EncryptionLevel::Handshake, EncryptionLevel::Application] {
+            let client_write = client.take_write_secret(level);
+            crate::ensure_eq!(client_write.is_some(), true);
+            crate::ensure_eq!(client_write, server.take_read_secret(level));
+            let server_write = server.take_write_secret(level);
+            crate::ensure_eq!(server_write.is_some(), true);
+            crate::ensure_eq!(server_write, client.take_read_secret(level));
+        }
+        crate::ensure_eq!(client.peer_transport_parameters(), Some(&b"server parameters"[..]));
+        crate::ensure_eq!(server.peer_transport_parameters(), Some(&b"client parameters"[..]));
+
+        // There are no records over QUIC.
+        crate::ensure_eq!(client.write(b"data").is_err(), true);
+        crate::ensure_eq!(client.take_output(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_quic_handshake_failure_keeps_alert() -> Result<()> {
+        let (mut client, mut server): (TlsSession, TlsSession) = new_quic_sessions(&[7; 32], &[8; 32])?;
+        let e = deliver_quic(&mut client, &mut server).err().unwrap();
+        crate::ensure_eq!(e.downcast::<Fail>()?.errno, libc::EACCES);
+        crate::ensure_eq!(server.handshake_alert(), Some(super::ALERT_DECRYPT_ERROR));
+        crate::ensure_eq!(server.take_handshake(EncryptionLevel::Initial), None);
+
+        // Handshake data must arrive at the level that the session expects.
+        let (mut client, mut server): (TlsSession, TlsSession) = new_quic_sessions(&[7; 32], &[7; 32])?;
+        let client_hello: Vec<u8> = client.take_handshake(EncryptionLevel::Initial).unwrap_or_default();
+        crate::ensure_eq!(
+            server
+                .receive_handshake(EncryptionLevel::Handshake, &client_hello)
+                .is_err(),
+            true
+        );
+        crate::ensure_eq!(server.handshake_alert(), Some(super::ALERT_UNEXPECTED_MESSAGE));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_config() -> Result<()> {
+        crate::ensure_eq!(TlsConfig::new(b"", &[7; 32]).is_err(), true);
+        crate::ensure_eq!(TlsConfig::new(b"client", &[7; 8]).is_err(), true);
+        Ok(())
+    }
+}
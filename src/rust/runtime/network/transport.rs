@@ -11,15 +11,47 @@ use crate::runtime::{
         DemiBuffer,
         MemoryRuntime,
     },
+    network::{
+        ancillary::AncillaryData,
+        config::{
+            IpsecDirection,
+            Route,
+            SecurityAssociation,
+        },
+        drops::{
+            DropRecord,
+            DropStats,
+        },
+        numa::NumaStats,
+        socket::{
+            filter::SocketFilter,
+            option::SocketOption,
+        },
+        tcp_info::{
+            TcpEvent,
+            TcpInfo,
+        },
+        timestamp::TxTimestamp,
+        tls::offload::{
+            TlsOffload,
+            TlsOffloadFlow,
+        },
+    },
     SharedDemiRuntime,
 };
 use ::socket2::{
     Domain,
+    Protocol,
     Type,
 };
 use ::std::{
     fmt::Debug,
-    net::SocketAddr,
+    net::{
+        Ipv4Addr,
+        SocketAddr,
+    },
+    os::fd::RawFd,
+    rc::Rc,
 };
 
 //======================================================================================================================
@@ -31,8 +63,8 @@ use ::std::{
 pub trait NetworkTransport: Clone + 'static + MemoryRuntime {
     type SocketDescriptor: Debug;
 
-    /// Create a socket using the network transport layer.
-    fn socket(&mut self, domain: Domain, typ: Type) -> Result<Self::SocketDescriptor, Fail>;
+    /// Create a socket using the network transport layer. The [protocol] is only used by raw sockets.
+    fn socket(&mut self, domain: Domain, typ: Type, protocol: Protocol) -> Result<Self::SocketDescriptor, Fail>;
 
     /// Bind an address to the socket.
     fn bind(&mut self, sd: &mut Self::SocketDescriptor, local: SocketAddr) -> Result<(), Fail>;
@@ -40,6 +72,21 @@ pub trait NetworkTransport: Clone + 'static + MemoryRuntime {
     /// Listen on this socket in the network transport layer.
     fn listen(&mut self, sd: &mut Self::SocketDescriptor, backlog: usize) -> Result<(), Fail>;
 
+    /// Set [option] on this socket.
+    fn set_socket_option(&mut self, sd: &mut Self::SocketDescriptor, option: SocketOption) -> Result<(), Fail>;
+
+    /// Attach a receive filter to this socket, or detach the current one if [filter] is `None`. Incoming packets that
+    /// the filter drops are never queued on the socket.
+    fn set_receive_filter(&mut self, sd: &mut Self::SocketDescriptor, filter: Option<SocketFilter>)
+        -> Result<(), Fail>;
+
+    /// Get the current value of [option] on this socket.
+    fn get_socket_option(
+        &mut self,
+        sd: &mut Self::SocketDescriptor,
+        option: SocketOption,
+    ) -> Result<SocketOption, Fail>;
+
     /// Forcibly close this socket in the network transport layer. This function should only be used in Drop and other
     /// internal functions, never exposed to the application.
     fn hard_close(&mut self, sd: &mut Self::SocketDescriptor) -> Result<(), Fail>;
@@ -57,6 +104,62 @@ pub trait NetworkTransport: Clone + 'static + MemoryRuntime {
         remote: SocketAddr,
     ) -> impl std::future::Future<Output = Result<(), Fail>>;
 
+    /// Bind the socket to [name], so that processes on the same host connect to it by name rather than by address.
+    fn bind_name(&mut self, _sd: &mut Self::SocketDescriptor, name: &str) -> Result<(), Fail> {
+        let cause: String = format!("named endpoints are not supported (name={:?})", name);
+        error!("bind_name(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, &cause))
+    }
+
+    /// Asynchronously connect this socket to the socket that is bound to [name].
+    fn connect_name(
+        &mut self,
+        _sd: &mut Self::SocketDescriptor,
+        name: &str,
+    ) -> impl std::future::Future<Output = Result<(), Fail>> {
+        let cause: String = format!("named endpoints are not supported (name={:?})", name);
+        error!("connect_name(): {}", cause);
+        async move { Err(Fail::new(libc::ENOTSUP, &cause)) }
+    }
+
+    /// Hand the connection of this socket off to another process through the Unix domain socket [channel]. The socket
+    /// is then released in this process, without closing the connection.
+    fn export(&mut self, _sd: &mut Self::SocketDescriptor, channel: RawFd) -> Result<(), Fail> {
+        let cause: String = format!(
+            "queue handoff is not supported by this transport (channel={:?})",
+            channel
+        );
+        error!("export(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, &cause))
+    }
+
+    /// Take over a connection that another process handed off through the Unix domain socket [channel]. This returns
+    /// the socket along with the local and remote addresses of the connection.
+    fn import(&mut self, channel: RawFd) -> Result<(Self::SocketDescriptor, SocketAddr, SocketAddr), Fail> {
+        let cause: String = format!(
+            "queue handoff is not supported by this transport (channel={:?})",
+            channel
+        );
+        error!("import(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, &cause))
+    }
+
+    /// Freeze the connection of this socket into a blob that another instance of the network stack may thaw. The
+    /// connection then stops here without letting the peer know, as it carries on elsewhere.
+    fn freeze(&mut self, _sd: &mut Self::SocketDescriptor) -> Result<Vec<u8>, Fail> {
+        let cause: &str = "connection migration is not supported by this transport";
+        error!("freeze(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, cause))
+    }
+
+    /// Thaw a connection that another instance of the network stack froze into [blob]. This returns the socket along
+    /// with the local and remote addresses of the connection.
+    fn thaw(&mut self, _blob: &[u8]) -> Result<(Self::SocketDescriptor, SocketAddr, SocketAddr), Fail> {
+        let cause: &str = "connection migration is not supported by this transport";
+        error!("thaw(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, cause))
+    }
+
     /// Push data to a connected socket.
     fn push(
         &mut self,
@@ -65,16 +168,119 @@ pub trait NetworkTransport: Clone + 'static + MemoryRuntime {
         addr: Option<SocketAddr>,
     ) -> impl std::future::Future<Output = Result<(), Fail>>;
 
-    /// Pop data from a connected socket.
+    /// Pop data from a connected socket, along with the metadata of the datagram that the transport knows.
     fn pop(
         &mut self,
         sd: &mut Self::SocketDescriptor,
         size: usize,
-    ) -> impl std::future::Future<Output = Result<(Option<SocketAddr>, DemiBuffer), Fail>>;
+    ) -> impl std::future::Future<Output = Result<(Option<SocketAddr>, DemiBuffer, AncillaryData), Fail>>;
 
     /// Asynchronously close a socket.
     fn close(&mut self, sd: &mut Self::SocketDescriptor) -> impl std::future::Future<Output = Result<(), Fail>>;
 
+    /// Get the device that encrypts the outgoing TLS records of a connected socket, along with the flow that the
+    /// next pushed byte starts, if the transport offloads TLS.
+    fn tls_offload(&mut self, _sd: &mut Self::SocketDescriptor) -> Option<(Rc<dyn TlsOffload>, TlsOffloadFlow)> {
+        None
+    }
+
+    /// Add an IPsec security association, if the transport protects its traffic with IPsec.
+    fn ipsec_add_security_association(&mut self, _sa: SecurityAssociation) -> Result<(), Fail> {
+        let cause: &str = "IPsec is not supported by this transport";
+        error!("ipsec_add_security_association(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, cause))
+    }
+
+    /// Remove the IPsec security association with security parameter index [spi] in [direction].
+    fn ipsec_remove_security_association(&mut self, _spi: u32, _direction: IpsecDirection) -> Result<(), Fail> {
+        let cause: &str = "IPsec is not supported by this transport";
+        error!("ipsec_remove_security_association(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, cause))
+    }
+
+    /// Add [route] to the routing table, if the transport routes its IPv4 traffic.
+    fn add_route(&mut self, _route: Route) -> Result<(), Fail> {
+        let cause: &str = "routing is not supported by this transport";
+        error!("add_route(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, cause))
+    }
+
+    /// Remove the route toward the subnet [destination]/[prefix_len] from the routing table.
+    fn remove_route(&mut self, _destination: Ipv4Addr, _prefix_len: u8) -> Result<(), Fail> {
+        let cause: &str = "routing is not supported by this transport";
+        error!("remove_route(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, cause))
+    }
+
+    /// Set the tunable [name] of the network stack to [value], if the transport has tunables.
+    fn set_tunable(&mut self, name: &str, _value: &str) -> Result<(), Fail> {
+        let cause: String = format!("tunable \"{}\" is not supported by this transport", name);
+        error!("set_tunable(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, &cause))
+    }
+
+    /// Get the value of the tunable [name] of the network stack.
+    fn get_tunable(&self, name: &str) -> Result<String, Fail> {
+        let cause: String = format!("tunable \"{}\" is not supported by this transport", name);
+        error!("get_tunable(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, &cause))
+    }
+
+    /// Get the number of incoming packets that the network stack dropped, for each reason.
+    fn get_drop_stats(&self) -> Result<DropStats, Fail> {
+        let cause: &str = "drop statistics are not supported by this transport";
+        error!("get_drop_stats(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, cause))
+    }
+
+    /// Get where the network device placed its buffer pools and the state of its queues, relative to its NUMA node.
+    fn get_numa_stats(&self) -> Result<NumaStats, Fail> {
+        let cause: &str = "NUMA statistics are not supported by this transport";
+        error!("get_numa_stats(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, cause))
+    }
+
+    /// Flush whatever is left to transmit and release the resources of the network device, once all sockets are
+    /// closed. The transport is not used afterwards.
+    fn shutdown(&mut self) -> Result<(), Fail> {
+        Ok(())
+    }
+
+    /// Get the number of incoming packets that were dropped on their way to this socket, for each reason.
+    fn get_socket_drop_stats(&mut self, _sd: &mut Self::SocketDescriptor) -> Result<DropStats, Fail> {
+        let cause: &str = "drop statistics are not supported by this transport";
+        error!("get_socket_drop_stats(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, cause))
+    }
+
+    /// Pop the oldest record from the error queue of this socket, which is enabled by the IP_RECVERR option.
+    fn pop_drop_record(&mut self, _sd: &mut Self::SocketDescriptor) -> Result<Option<DropRecord>, Fail> {
+        let cause: &str = "error queues are not supported by this transport";
+        error!("pop_drop_record(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, cause))
+    }
+
+    /// Pop the oldest transmit timestamp of this socket, which are enabled by the SO_TIMESTAMPING option.
+    fn pop_tx_timestamp(&mut self, _sd: &mut Self::SocketDescriptor) -> Result<Option<TxTimestamp>, Fail> {
+        let cause: &str = "transmit timestamps are not supported by this transport";
+        error!("pop_tx_timestamp(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, cause))
+    }
+
+    /// Get a snapshot of the state of this TCP connection.
+    fn get_tcp_info(&mut self, _sd: &mut Self::SocketDescriptor) -> Result<TcpInfo, Fail> {
+        let cause: &str = "connection introspection is not supported by this transport";
+        error!("get_tcp_info(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, cause))
+    }
+
+    /// Get the recent retransmission and flow control events of this TCP connection.
+    fn get_tcp_timeline(&mut self, _sd: &mut Self::SocketDescriptor) -> Result<Vec<TcpEvent>, Fail> {
+        let cause: &str = "connection introspection is not supported by this transport";
+        error!("get_tcp_timeline(): {}", cause);
+        Err(Fail::new(libc::ENOTSUP, cause))
+    }
+
     /// Pull the common runtime out of the transport. We only need this because traits do not support members.
     fn get_runtime(&self) -> &SharedDemiRuntime;
 }
@@ -0,0 +1,200 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! This module implements a pollable file descriptor that lets applications embed Demikernel inside an external event
+//! loop (e.g. epoll or tokio). The descriptor is an epoll instance that becomes readable when operations completed and
+//! their results are waiting to be collected, or when a doorbell that some coroutine waits on rings. The runtime only
+//! updates it when it is polled, much like the global timer.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::fail::Fail;
+use ::std::{
+    mem,
+    os::fd::RawFd,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+pub struct Notifier {
+    /// Epoll instance that we hand out to the application.
+    epoll_fd: RawFd,
+    /// Eventfd that we signal while there are completed operations.
+    event_fd: RawFd,
+    /// Whether [event_fd] is currently signalled.
+    signalled: bool,
+    /// Doorbells that are currently registered in [epoll_fd], sorted.
+    doorbells: Vec<RawFd>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Notifier {
+    pub fn new() -> Result<Self, Fail> {
+        let epoll_fd: RawFd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+        if epoll_fd < 0 {
+            let errno: libc::c_int = unsafe { *libc::__errno_location() };
+            let cause: String = format!("failed to create epoll instance (errno={:?})", errno);
+            error!("new(): {}", cause);
+            return Err(Fail::new(errno, &cause));
+        }
+        let event_fd: RawFd = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) };
+        if event_fd < 0 {
+            let errno: libc::c_int = unsafe { *libc::__errno_location() };
+            unsafe { libc::close(epoll_fd) };
+            let cause: String = format!("failed to create eventfd (errno={:?})", errno);
+            error!("new(): {}", cause);
+            return Err(Fail::new(errno, &cause));
+        }
+        // From here on, dropping the notifier closes both file descriptors.
+        let notifier: Self = Self {
+            epoll_fd,
+            event_fd,
+            signalled: false,
+            doorbells: Vec::new(),
+        };
+        notifier.register(event_fd)?;
+        Ok(notifier)
+    }
+
+    /// Returns the file descriptor that applications should poll for readability.
+    pub fn get_fd(&self) -> RawFd {
+        self.epoll_fd
+    }
+
+    /// Brings the notifier up to date: it is signalled if [has_completions] is set, and it watches [doorbells].
+    pub fn update(&mut self, has_completions: bool, mut doorbells: Vec<RawFd>) {
+        if has_completions != self.signalled {
+            if has_completions {
+                let value: u64 = 1;
+                unsafe {
+                    libc::write(
+                        self.event_fd,
+                        &value as *const u64 as *const libc::c_void,
+                        mem::size_of::<u64>(),
+                    )
+                };
+            } else {
+                // Reset the counter. This fails with EAGAIN if it is already zero, which is fine.
+                let mut value: u64 = 0;
+                unsafe {
+                    libc::read(
+                        self.event_fd,
+                        &mut value as *mut u64 as *mut libc::c_void,
+                        mem::size_of::<u64>(),
+                    )
+                };
+            }
+            self.signalled = has_completions;
+        }
+
+        doorbells.sort_unstable();
+        doorbells.dedup();
+        if doorbells != self.doorbells {
+            for fd in self.doorbells.iter().filter(|fd| doorbells.binary_search(fd).is_err()) {
+                // The doorbell may have been closed already, which implicitly removed it from the epoll instance.
+                unsafe { libc::epoll_ctl(self.epoll_fd, libc::EPOLL_CTL_DEL, *fd, ::std::ptr::null_mut()) };
+            }
+            for fd in doorbells.iter().filter(|fd| self.doorbells.binary_search(fd).is_err()) {
+                if let Err(e) = self.register(*fd) {
+                    warn!("update(): cannot watch doorbell (fd={:?}, error={:?})", fd, e);
+                }
+            }
+            self.doorbells = doorbells;
+        }
+    }
+
+    /// Adds [fd] to the epoll instance, watching for readability.
+    fn register(&self, fd: RawFd) -> Result<(), Fail> {
+        let mut epoll_event: libc::epoll_event = libc::epoll_event {
+            events: libc::EPOLLIN as u32,
+            u64: fd as u64,
+        };
+        match unsafe { libc::epoll_ctl(self.epoll_fd, libc::EPOLL_CTL_ADD, fd, &mut epoll_event) } {
+            0 => Ok(()),
+            _ => {
+                let errno: libc::c_int = unsafe { *libc::__errno_location() };
+                let cause: String = format!(
+                    "failed to add file descriptor to epoll (fd={:?}, errno={:?})",
+                    fd, errno
+                );
+                error!("register(): {}", cause);
+                Err(Fail::new(errno, &cause))
+            },
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Drop for Notifier {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.event_fd);
+            libc::close(self.epoll_fd);
+        }
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::Notifier;
+    use ::anyhow::Result;
+    use ::std::os::fd::RawFd;
+
+    /// Checks whether [fd] is readable, without blocking.
+    fn is_readable(fd: RawFd) -> bool {
+        let mut pollfd: libc::pollfd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        unsafe { libc::poll(&mut pollfd, 1, 0) > 0 }
+    }
+
+    #[test]
+    fn notifier_completions_and_doorbells() -> Result<()> {
+        let mut notifier: Notifier = Notifier::new()?;
+        crate::ensure_eq!(is_readable(notifier.get_fd()), false);
+
+        // Pending completions make the notifier readable until they are collected.
+        notifier.update(true, Vec::new());
+        crate::ensure_eq!(is_readable(notifier.get_fd()), true);
+        notifier.update(false, Vec::new());
+        crate::ensure_eq!(is_readable(notifier.get_fd()), false);
+
+        // So does a doorbell that rings.
+        let mut fds: [libc::c_int; 2] = [0; 2];
+        crate::ensure_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        notifier.update(false, vec![fds[0]]);
+        crate::ensure_eq!(is_readable(notifier.get_fd()), false);
+        let byte: u8 = 1;
+        crate::ensure_eq!(
+            unsafe { libc::write(fds[1], &byte as *const u8 as *const libc::c_void, 1) },
+            1
+        );
+        crate::ensure_eq!(is_readable(notifier.get_fd()), true);
+
+        // Doorbells that no coroutine waits on anymore are not watched.
+        notifier.update(false, Vec::new());
+        crate::ensure_eq!(is_readable(notifier.get_fd()), false);
+
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+        Ok(())
+    }
+}
@@ -10,15 +10,14 @@ mod qtype;
 // Imports
 //======================================================================================================================
 
-use crate::runtime::{
-    fail::Fail,
-    scheduler::TaskWithResult,
+use crate::{
+    collections::generational_slab::GenerationalSlab,
+    runtime::{
+        fail::Fail,
+        scheduler::TaskWithResult,
+    },
 };
 use ::futures::future::FusedFuture;
-use ::slab::{
-    Iter,
-    Slab,
-};
 use ::std::{
     any::Any,
     net::SocketAddrV4,
@@ -58,9 +57,10 @@ pub trait NetworkQueue: IoQueue {
     fn remote(&self) -> Option<SocketAddrV4>;
 }
 
-/// I/O queue descriptors table.
+/// I/O queue descriptors table. Lookups are O(1) and take no locks, as the table has a single writer. Queues are
+/// stored in fixed-size chunks, so growing the table never moves nor copies the queues that are already open.
 pub struct IoQueueTable {
-    table: Slab<Box<dyn IoQueue>>,
+    table: GenerationalSlab<Box<dyn IoQueue>>,
 }
 
 //======================================================================================================================
@@ -82,14 +82,15 @@ impl IoQueueTable {
     pub fn alloc<T: IoQueue>(&mut self, queue: T) -> QDesc {
         let index: usize = self.table.insert(Box::new(queue));
 
-        // Ensure that the allocation would yield to a safe conversion between usize to u32.
-        // Note: This imposes a limit on the number of open queue descriptors in u32::MAX.
+        // Ensure that the index fits in the slot of a queue descriptor.
+        // Note: This imposes a limit on the number of open queue descriptors in QDesc::MAX_SLOT - BASE_QD.
         assert!(
-            (index as u32) + Self::BASE_QD <= u32::MAX,
+            index <= (QDesc::MAX_SLOT - Self::BASE_QD) as usize,
             "I/O descriptors table overflow"
         );
 
-        QDesc::from((index as u32) + Self::BASE_QD)
+        let generation: u32 = self.table.generation(index).expect("entry should exist");
+        QDesc::new((index as u32) + Self::BASE_QD, generation)
     }
 
     /// Gets the type of the queue.
@@ -162,27 +163,54 @@ impl IoQueueTable {
                 return Err(Fail::new(libc::EBADF, &cause));
             },
         };
-        Ok(downcast_queue::<T>(self.table.remove(index as usize))?)
+        // The queue is checked to be of type T before it is removed, so that a mismatched free leaves it open.
+        if let Some(boxed_queue_ptr) = self.table.get(index as usize) {
+            downcast_queue_ptr::<T>(boxed_queue_ptr)?;
+        }
+        match self.table.remove(index as usize) {
+            Some(boxed_queue) => Ok(downcast_queue::<T>(boxed_queue)?),
+            None => {
+                let cause: String = format!("invalid queue descriptor (qd={:?})", qd);
+                error!("free(): {}", &cause);
+                Err(Fail::new(libc::EBADF, &cause))
+            },
+        }
     }
 
     /// Gets an iterator over all registered queues.
-    pub fn get_values(&self) -> Iter<'_, Box<dyn IoQueue>> {
-        self.table.iter()
+    pub fn get_values(&self) -> impl Iterator<Item = &Box<dyn IoQueue>> {
+        self.table.iter().map(|(_, boxed_queue)| boxed_queue)
     }
 
-    pub fn drain(&mut self) -> slab::Drain<'_, Box<dyn IoQueue>> {
+    /// Gets the I/O queue descriptors of all registered queues.
+    pub fn get_qds(&self) -> Vec<QDesc> {
+        self.table
+            .iter()
+            .filter_map(|(index, _)| {
+                let generation: u32 = self.table.generation(index)?;
+                Some(QDesc::new((index as u32) + Self::BASE_QD, generation))
+            })
+            .collect()
+    }
+
+    /// Removes all queues from the table and returns an iterator over them.
+    pub fn drain(&mut self) -> impl Iterator<Item = Box<dyn IoQueue>> + '_ {
         self.table.drain()
     }
 
-    /// Gets the index in the I/O queue descriptors table to which a given I/O queue descriptor refers to.
+    /// Gets the index in the I/O queue descriptors table to which a given I/O queue descriptor refers to. Queue
+    /// descriptors whose generation does not match that of their entry were closed, and do not refer to any queue.
     fn get_index(&self, qd: &QDesc) -> Option<u32> {
-        if Into::<u32>::into(*qd) < Self::BASE_QD {
+        if qd.slot() < Self::BASE_QD {
             None
         } else {
-            let rawqd: u32 = Into::<u32>::into(*qd) - Self::BASE_QD;
+            let rawqd: u32 = qd.slot() - Self::BASE_QD;
             if !self.table.contains(rawqd as usize) {
                 return None;
             }
+            if self.table.generation(rawqd as usize)? & QDesc::GENERATION_MASK != qd.generation() {
+                return None;
+            }
             Some(rawqd)
         }
     }
@@ -245,7 +273,7 @@ pub fn downcast_queue<T: IoQueue>(boxed_queue: Box<dyn IoQueue>) -> Result<T, Fa
 impl Default for IoQueueTable {
     fn default() -> Self {
         Self {
-            table: Slab::<Box<dyn IoQueue>>::new(),
+            table: GenerationalSlab::<Box<dyn IoQueue>>::default(),
         }
     }
 }
@@ -265,6 +293,7 @@ mod tests {
         QDesc,
         QType,
     };
+    use ::anyhow::Result;
     use ::std::any::Any;
     use ::test::{
         black_box,
@@ -290,6 +319,72 @@ mod tests {
         }
     }
 
+    #[test]
+    fn stale_qd_is_rejected() -> Result<()> {
+        let mut ioqueue_table: IoQueueTable = IoQueueTable::default();
+        let qd: QDesc = ioqueue_table.alloc::<TestQueue>(TestQueue {});
+        crate::ensure_eq!(ioqueue_table.get_type(&qd)?, QType::TestQueue);
+        ioqueue_table.free::<TestQueue>(&qd)?;
+
+        crate::ensure_eq!(ioqueue_table.get_type(&qd).unwrap_err().errno, libc::EBADF);
+        crate::ensure_eq!(ioqueue_table.get::<TestQueue>(&qd).err().unwrap().errno, libc::EBADF);
+        crate::ensure_eq!(ioqueue_table.free::<TestQueue>(&qd).err().unwrap().errno, libc::EBADF);
+        crate::ensure_eq!(
+            ioqueue_table.get::<TestQueue>(&QDesc::from(0)).err().unwrap().errno,
+            libc::EBADF
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn reused_qd_is_rejected() -> Result<()> {
+        let mut ioqueue_table: IoQueueTable = IoQueueTable::default();
+        let old_qd: QDesc = ioqueue_table.alloc::<TestQueue>(TestQueue {});
+        ioqueue_table.free::<TestQueue>(&old_qd)?;
+
+        // The new queue reuses the slot of the old one, but not its generation.
+        let new_qd: QDesc = ioqueue_table.alloc::<TestQueue>(TestQueue {});
+        crate::ensure_eq!(new_qd.slot(), old_qd.slot());
+        crate::ensure_neq!(new_qd, old_qd);
+        crate::ensure_eq!(
+            ioqueue_table.get::<TestQueue>(&old_qd).err().unwrap().errno,
+            libc::EBADF
+        );
+        crate::ensure_eq!(
+            ioqueue_table.free::<TestQueue>(&old_qd).err().unwrap().errno,
+            libc::EBADF
+        );
+        ioqueue_table.get::<TestQueue>(&new_qd)?;
+
+        // Queue descriptors are positive C integers, even after many reuses.
+        let mut qd: QDesc = new_qd;
+        for _ in 0..(2 << QDesc::GENERATION_BITS) {
+            ioqueue_table.free::<TestQueue>(&qd)?;
+            qd = ioqueue_table.alloc::<TestQueue>(TestQueue {});
+            crate::ensure_eq!(i32::from(qd) > 0, true);
+            ioqueue_table.get::<TestQueue>(&qd)?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn growth_keeps_queues() -> Result<()> {
+        let mut ioqueue_table: IoQueueTable = IoQueueTable::default();
+        let qds: Vec<QDesc> = (0..1024)
+            .map(|_| ioqueue_table.alloc::<TestQueue>(TestQueue {}))
+            .collect();
+        for qd in &qds {
+            crate::ensure_eq!(ioqueue_table.get_type(qd)?, QType::TestQueue);
+        }
+        crate::ensure_eq!(ioqueue_table.get_qds(), qds.clone());
+        crate::ensure_eq!(ioqueue_table.drain().count(), qds.len());
+        crate::ensure_eq!(ioqueue_table.get_values().count(), 0);
+
+        Ok(())
+    }
+
     #[bench]
     fn bench_alloc_free(b: &mut Bencher) {
         let mut ioqueue_table: IoQueueTable = IoQueueTable::default();
@@ -8,6 +8,7 @@
 use crate::runtime::{
     fail::Fail,
     memory::DemiBuffer,
+    network::ancillary::AncillaryData,
     QDesc,
 };
 use ::std::{
@@ -23,9 +24,16 @@ use ::std::{
 pub enum OperationResult {
     Connect,
     Accept((QDesc, SocketAddrV4)),
-    Push,
-    Pop(Option<SocketAddrV4>, DemiBuffer),
+    /// A push was accepted with this many bytes.
+    Push(usize),
+    Pop(Option<SocketAddrV4>, DemiBuffer, AncillaryData),
     Close,
+    /// An application coroutine completed successfully.
+    Task,
+    /// A splice moved this many bytes.
+    Splice(usize),
+    /// A batched pop took these datagrams, or one coalesced buffer of a stream.
+    PopMany(Vec<(Option<SocketAddrV4>, DemiBuffer, AncillaryData)>),
     Failed(Fail),
 }
 
@@ -38,9 +46,12 @@ impl fmt::Debug for OperationResult {
         match self {
             OperationResult::Connect => write!(f, "Connect"),
             OperationResult::Accept(..) => write!(f, "Accept"),
-            OperationResult::Push => write!(f, "Push"),
+            OperationResult::Push(nbytes) => write!(f, "Push({:?})", nbytes),
             OperationResult::Pop(..) => write!(f, "Pop"),
             OperationResult::Close => write!(f, "Close"),
+            OperationResult::Task => write!(f, "Task"),
+            OperationResult::Splice(nbytes) => write!(f, "Splice({:?})", nbytes),
+            OperationResult::PopMany(batch) => write!(f, "PopMany({:?})", batch.len()),
             OperationResult::Failed(ref e) => write!(f, "Failed({:?})", e),
         }
     }
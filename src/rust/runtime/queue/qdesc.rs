@@ -6,6 +6,12 @@
 //==============================================================================
 
 /// IO Queue Descriptor
+///
+/// The low [QDesc::SLOT_BITS] bits of a queue descriptor identify the slot of the queue in the I/O queue descriptors
+/// table, and the next [QDesc::GENERATION_BITS] bits hold the generation of that slot when the queue was allocated. A
+/// queue descriptor that is used after it was closed thus no longer matches its slot, even if the slot was reused by
+/// another queue. The most significant bit is always clear, so that queue descriptors are positive when handed out as
+/// C integers.
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
 pub struct QDesc(u32);
 
@@ -14,9 +20,37 @@ pub struct QDesc(u32);
 //==============================================================================
 
 impl QDesc {
+    /// Number of bits that hold the generation of a queue descriptor.
+    pub const GENERATION_BITS: u32 = 11;
+    /// Mask of the generation of a queue descriptor. Generations wrap around, so a descriptor is only recognized as
+    /// stale if its slot was reused less than 2^[QDesc::GENERATION_BITS] times.
+    pub const GENERATION_MASK: u32 = (1 << Self::GENERATION_BITS) - 1;
     pub const MAX: u32 = u32::MAX;
+    /// Largest slot of a queue descriptor.
+    pub const MAX_SLOT: u32 = (1 << Self::SLOT_BITS) - 1;
+    /// Number of bits that identify the slot of a queue descriptor.
+    pub const SLOT_BITS: u32 = 20;
+
+    /// Builds a queue descriptor for [slot], which must not exceed [QDesc::MAX_SLOT], at [generation].
+    pub fn new(slot: u32, generation: u32) -> Self {
+        debug_assert!(slot <= Self::MAX_SLOT);
+        QDesc(((generation & Self::GENERATION_MASK) << Self::SLOT_BITS) | slot)
+    }
+
+    /// Gets the slot of the target queue descriptor.
+    pub fn slot(&self) -> u32 {
+        self.0 & Self::MAX_SLOT
+    }
+
+    /// Gets the generation of the target queue descriptor.
+    pub fn generation(&self) -> u32 {
+        (self.0 >> Self::SLOT_BITS) & Self::GENERATION_MASK
+    }
 }
 
+// Queue descriptors must fit in a positive C integer.
+const _: () = assert!(QDesc::SLOT_BITS + QDesc::GENERATION_BITS < u32::BITS);
+
 //==============================================================================
 // Trait Implementations
 //==============================================================================
@@ -13,6 +13,13 @@ pub enum QType {
     TcpSocket = 0x0002,
     MemoryQueue = 0x003,
     TestQueue = 0x004,
+    RawSocket = 0x005,
+    QuicSocket = 0x006,
+    QuicStream = 0x007,
+    DtlsSocket = 0x008,
+    FileQueue = 0x009,
+    BlockQueue = 0x00a,
+    RdmaQueue = 0x00b,
 }
 
 //==============================================================================
@@ -27,6 +34,13 @@ impl From<QType> for u32 {
             QType::TcpSocket => 0x0002,
             QType::MemoryQueue => 0x0003,
             QType::TestQueue => 0x0004,
+            QType::RawSocket => 0x0005,
+            QType::QuicSocket => 0x0006,
+            QType::QuicStream => 0x0007,
+            QType::DtlsSocket => 0x0008,
+            QType::FileQueue => 0x0009,
+            QType::BlockQueue => 0x000a,
+            QType::RdmaQueue => 0x000b,
         }
     }
 }
@@ -41,6 +55,13 @@ impl TryFrom<u32> for QType {
             0x0002 => Ok(QType::TcpSocket),
             0x0003 => Ok(QType::MemoryQueue),
             0x0004 => Ok(QType::TestQueue),
+            0x0005 => Ok(QType::RawSocket),
+            0x0006 => Ok(QType::QuicSocket),
+            0x0007 => Ok(QType::QuicStream),
+            0x0008 => Ok(QType::DtlsSocket),
+            0x0009 => Ok(QType::FileQueue),
+            0x000a => Ok(QType::BlockQueue),
+            0x000b => Ok(QType::RdmaQueue),
             _ => Err("invalid qtype"),
         }
     }
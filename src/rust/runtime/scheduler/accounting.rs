@@ -0,0 +1,213 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Accounting of the time that the scheduler spends polling each task, which attributes latency spikes to the tasks
+//! that cause them.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::scheduler::TaskId;
+use ::std::{
+    cmp::Reverse,
+    collections::HashMap,
+    fmt,
+    time::Duration,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Accounting of a task that is still in the scheduler, or of all finished tasks with the same name.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TaskStats {
+    /// Name of the task.
+    name: &'static str,
+    /// Identifier of the task. None if these are the aggregate stats of finished tasks.
+    task_id: Option<TaskId>,
+    /// Number of tasks that these stats cover.
+    num_tasks: usize,
+    /// Number of times that the task was polled.
+    num_polls: u64,
+    /// Cumulative time spent polling the task.
+    runtime: Duration,
+    /// Longest time spent in a single poll of the task.
+    longest_poll: Duration,
+}
+
+/// Per-task accounting of the scheduler. This is disabled by default, because it reads the clock twice per poll.
+#[derive(Default)]
+pub struct TaskAccounting {
+    enabled: bool,
+    /// Stats of the tasks that are still in the scheduler.
+    tasks: HashMap<TaskId, TaskStats>,
+    /// Aggregate stats of finished tasks, by name.
+    finished_tasks: HashMap<&'static str, TaskStats>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl TaskStats {
+    fn new(name: &'static str, task_id: Option<TaskId>) -> Self {
+        Self {
+            name,
+            task_id,
+            num_tasks: 1,
+            num_polls: 0,
+            runtime: Duration::ZERO,
+            longest_poll: Duration::ZERO,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn task_id(&self) -> Option<TaskId> {
+        self.task_id
+    }
+
+    pub fn num_tasks(&self) -> usize {
+        self.num_tasks
+    }
+
+    pub fn num_polls(&self) -> u64 {
+        self.num_polls
+    }
+
+    pub fn runtime(&self) -> Duration {
+        self.runtime
+    }
+
+    pub fn longest_poll(&self) -> Duration {
+        self.longest_poll
+    }
+
+    /// Folds the stats of [other] into these ones.
+    fn merge(&mut self, other: &TaskStats) {
+        self.num_tasks += other.num_tasks;
+        self.num_polls += other.num_polls;
+        self.runtime += other.runtime;
+        self.longest_poll = self.longest_poll.max(other.longest_poll);
+    }
+}
+
+impl TaskAccounting {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Turns accounting on or off. Turning it off drops the stats collected so far.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.tasks.clear();
+            self.finished_tasks.clear();
+        }
+    }
+
+    /// Accounts a poll of the task [task_id] named [name] that took [elapsed].
+    pub fn record_poll(&mut self, task_id: TaskId, name: &'static str, elapsed: Duration) {
+        let stats: &mut TaskStats = self
+            .tasks
+            .entry(task_id)
+            .or_insert_with(|| TaskStats::new(name, Some(task_id)));
+        stats.num_polls += 1;
+        stats.runtime += elapsed;
+        stats.longest_poll = stats.longest_poll.max(elapsed);
+    }
+
+    /// Moves the stats of the task [task_id], which left the scheduler, into the aggregate stats for its name.
+    pub fn finish(&mut self, task_id: TaskId) {
+        if let Some(stats) = self.tasks.remove(&task_id) {
+            match self.finished_tasks.get_mut(stats.name) {
+                Some(finished) => finished.merge(&stats),
+                None => {
+                    self.finished_tasks
+                        .insert(stats.name, TaskStats { task_id: None, ..stats });
+                },
+            }
+        }
+    }
+
+    /// Returns the stats of the [max_tasks] worst offenders, first ordered by their longest poll and then by their
+    /// cumulative runtime.
+    pub fn get_top_tasks(&self, max_tasks: usize) -> Vec<TaskStats> {
+        let mut stats: Vec<TaskStats> = self
+            .tasks
+            .values()
+            .chain(self.finished_tasks.values())
+            .cloned()
+            .collect();
+        stats.sort_by_key(|stats| (Reverse(stats.longest_poll), Reverse(stats.runtime)));
+        stats.truncate(max_tasks);
+        stats
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl fmt::Display for TaskStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.task_id {
+            Some(task_id) => write!(f, "{} (id={:?})", self.name, task_id)?,
+            None => write!(f, "{} (finished={})", self.name, self.num_tasks)?,
+        }
+        write!(
+            f,
+            ": polls={}, runtime={:?}, longest_poll={:?}",
+            self.num_polls, self.runtime, self.longest_poll
+        )
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        TaskAccounting,
+        TaskStats,
+    };
+    use crate::runtime::scheduler::TaskId;
+    use ::anyhow::Result;
+    use ::std::time::Duration;
+
+    /// Tests that finished tasks are aggregated by name and that the worst offenders come first.
+    #[test]
+    fn test_top_tasks() -> Result<()> {
+        let mut accounting: TaskAccounting = TaskAccounting::default();
+        accounting.set_enabled(true);
+        accounting.record_poll(TaskId::from(1), "push", Duration::from_micros(5));
+        accounting.record_poll(TaskId::from(1), "push", Duration::from_micros(20));
+        accounting.record_poll(TaskId::from(2), "push", Duration::from_micros(10));
+        accounting.record_poll(TaskId::from(3), "poll", Duration::from_micros(15));
+        accounting.finish(TaskId::from(1));
+        accounting.finish(TaskId::from(2));
+
+        let top: Vec<TaskStats> = accounting.get_top_tasks(8);
+        crate::ensure_eq!(top.len(), 2);
+        crate::ensure_eq!(top[0].name(), "push");
+        crate::ensure_eq!(top[0].task_id(), None);
+        crate::ensure_eq!(top[0].num_tasks(), 2);
+        crate::ensure_eq!(top[0].num_polls(), 3);
+        crate::ensure_eq!(top[0].runtime(), Duration::from_micros(35));
+        crate::ensure_eq!(top[0].longest_poll(), Duration::from_micros(20));
+        crate::ensure_eq!(top[1].task_id(), Some(TaskId::from(3)));
+        crate::ensure_eq!(accounting.get_top_tasks(1).len(), 1);
+
+        // Turning accounting off drops the stats.
+        accounting.set_enabled(false);
+        crate::ensure_eq!(accounting.get_top_tasks(8).is_empty(), true);
+
+        Ok(())
+    }
+}
@@ -22,7 +22,10 @@ use crate::{
             WakerPageRef,
             WakerRef,
         },
-        scheduler::InternalId,
+        scheduler::{
+            InternalId,
+            TaskPriority,
+        },
         waker64::{
             WAKER_BIT_LENGTH,
             WAKER_BIT_LENGTH_SHIFT,
@@ -56,6 +59,8 @@ pub struct TaskGroup {
     tasks: PinSlab<Box<dyn Task>>,
     /// Holds the waker bits for controlling task scheduling.
     waker_page_refs: Vec<WakerPageRef>,
+    /// Priority of the tasks in this group.
+    priority: TaskPriority,
 }
 
 //======================================================================================================================
@@ -63,6 +68,18 @@ pub struct TaskGroup {
 //======================================================================================================================
 
 impl TaskGroup {
+    /// Creates an empty task group whose tasks run with [priority].
+    pub fn new(priority: TaskPriority) -> Self {
+        Self {
+            priority,
+            ..Default::default()
+        }
+    }
+
+    pub fn get_priority(&self) -> TaskPriority {
+        self.priority
+    }
+
     /// Given a handle to a task, remove it from the scheduler
     pub fn remove(&mut self, task_id: TaskId) -> Option<Box<dyn Task>> {
         // We should not have a scheduler handle that refers to an invalid id, so unwrap and expect are safe here.
@@ -90,12 +107,13 @@ impl TaskGroup {
         }
     }
 
-    /// Insert a new task into our scheduler returning a handle corresponding to it.
-    pub fn insert(&mut self, task: Box<dyn Task>) -> Option<TaskId> {
+    /// Insert a new task into our scheduler under [task_id], which the scheduler allocates so that it is unique across
+    /// all groups.
+    pub fn insert(&mut self, task_id: TaskId, task: Box<dyn Task>) -> Option<TaskId> {
         let task_name: &'static str = task.get_name();
         // The pin slab index can be reverse-computed in a page index and an offset within the page.
         let pin_slab_index: usize = self.tasks.insert(task)?;
-        let task_id: TaskId = self.ids.insert_with_new_id(pin_slab_index.into());
+        self.ids.insert(task_id, pin_slab_index.into());
 
         self.add_new_pages_up_to_pin_slab_index(pin_slab_index.into());
 
@@ -149,6 +167,11 @@ impl TaskGroup {
         (waker_page_index << WAKER_BIT_LENGTH_SHIFT) + waker_page_offset
     }
 
+    /// Checks whether any task in this group is ready to run, without taking out the notification flags.
+    pub fn has_ready_tasks(&self) -> bool {
+        self.waker_page_refs.iter().any(|page| page.has_notified())
+    }
+
     pub fn get_offsets_for_ready_tasks(&mut self) -> Vec<InternalId> {
         let mut result: Vec<InternalId> = vec![];
         for i in 0..self.get_num_waker_pages() {
@@ -168,6 +191,11 @@ impl TaskGroup {
         expect_some!(self.tasks.get(internal_id.into()), "Invalid offset: {:?}", internal_id).get_id()
     }
 
+    /// Gets the name of a task. Expects the task to exist.
+    pub fn unchecked_get_name(&self, internal_id: InternalId) -> &'static str {
+        expect_some!(self.tasks.get(internal_id.into()), "Invalid offset: {:?}", internal_id).get_name()
+    }
+
     pub fn unchecked_external_to_internal_id(&self, task_id: &TaskId) -> InternalId {
         expect_some!(self.ids.get(task_id), "Invalid id: {:?}", task_id)
     }
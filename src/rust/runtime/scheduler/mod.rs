@@ -30,6 +30,7 @@
 //! task. Coroutines that are capable of yielding when they are blocked contain a [Yielder] to give CPU cycles back to
 //! the scheduler. The [YielderHandle] identifies a specific blocked coroutine and can be used to wake the coroutine.
 
+pub mod accounting;
 mod group;
 mod page;
 pub mod scheduler;
@@ -41,7 +42,11 @@ mod waker64;
 //==============================================================================
 
 pub use self::{
-    scheduler::SharedScheduler,
+    accounting::TaskStats,
+    scheduler::{
+        SharedScheduler,
+        TaskPriority,
+    },
     task::{
         Task,
         TaskId,
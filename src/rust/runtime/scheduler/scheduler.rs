@@ -15,7 +15,12 @@ use crate::{
     collections::id_map::IdMap,
     expect_some,
     runtime::{
+        budget,
         scheduler::{
+            accounting::{
+                TaskAccounting,
+                TaskStats,
+            },
             group::TaskGroup,
             Task,
             TaskId,
@@ -30,12 +35,34 @@ use ::std::{
         DerefMut,
     },
     task::Waker,
+    time::{
+        Duration,
+        Instant,
+    },
 };
 
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Number of times in a row that low priority tasks that are ready to run may be passed over in favor of high priority
+/// ones, before they get a turn anyways.
+const MAX_LOW_PRIORITY_SKIPS: usize = 16;
+
 //======================================================================================================================
 // Structures
 //======================================================================================================================
 
+/// Priority class of a task. High priority tasks, such as those that process packets or run I/O operations, run ahead
+/// of low priority ones, such as those that refresh caches or send heartbeats. Low priority tasks are not starved,
+/// because they get a turn after being passed over [MAX_LOW_PRIORITY_SKIPS] times in a row.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TaskPriority {
+    #[default]
+    High,
+    Low,
+}
+
 /// Internal offset into the slab that holds the task state.
 #[derive(Eq, PartialEq, Clone, Copy, Debug)]
 pub struct InternalId(usize);
@@ -45,21 +72,29 @@ pub struct Scheduler {
     // Mapping between external task ids and internal ids (which currently represent the offset into the slab where the
     // task lives).
     ids: IdMap<TaskId, InternalId>,
-    // A group of tasks used for resource management. Currently all of our tasks are in a single group but we will
-    // eventually break them up by Demikernel queue for fairness and performance isolation.
+    // A group of tasks used for resource management. Currently our tasks are only split into groups by priority but we
+    // will eventually break them up by Demikernel queue for fairness and performance isolation.
     groups: Slab<TaskGroup>,
     // Track the currently running task id. This is entirely for external use. If there are no coroutines running (i.
     // e.g, we did not enter the scheduler through a wait), this MUST be set to none because we cannot yield or wake ///
     // unless inside a task/async coroutine.
     current_running_task: Box<Option<TaskId>>,
 
-    // These global variables are for our scheduling policy. For now, we simply use round robin.
+    // These global variables are for our scheduling policy. For now, we use round robin among the groups of the same
+    // priority.
     // The index of the current or last task that we ran.
     current_task_id: InternalId,
     // The group index of the current or last task that we ran.
     current_group_id: InternalId,
     // The current set of ready tasks in the group.
     current_ready_tasks: Vec<InternalId>,
+    // The groups that hold high and low priority tasks that are not inserted into a specific group.
+    high_priority_group_id: InternalId,
+    low_priority_group_id: InternalId,
+    // The number of times in a row that a high priority group was chosen while a low priority group was ready to run.
+    low_priority_skips: usize,
+    // Runtime and poll count of each task, if enabled.
+    accounting: TaskAccounting,
 }
 
 #[derive(Clone)]
@@ -72,7 +107,12 @@ pub struct SharedScheduler(SharedObject<Scheduler>);
 impl Scheduler {
     /// Creates a new task group. Returns an identifier for the group.
     pub fn create_group(&mut self) -> TaskId {
-        let internal_id: InternalId = self.groups.insert(TaskGroup::default()).into();
+        self.create_group_with_priority(TaskPriority::High)
+    }
+
+    /// Creates a new task group whose tasks run with [priority]. Returns an identifier for the group.
+    pub fn create_group_with_priority(&mut self, priority: TaskPriority) -> TaskId {
+        let internal_id: InternalId = self.groups.insert(TaskGroup::new(priority)).into();
         self.ids.insert_with_new_id(internal_id)
     }
 
@@ -118,14 +158,7 @@ impl Scheduler {
     /// group.
     pub fn insert_task<T: Task>(&mut self, task: T) -> Option<TaskId> {
         // Use the currently running task id to find the task group for this task.
-        let group: &mut TaskGroup = self.groups.get_mut(self.current_group_id.into())?;
-        // Insert the task into the task group.
-        let new_task_id: TaskId = group.insert(Box::new(task))?;
-        // Add a mapping so we can use this new task id to find the task in the future.
-        if let Some(existing) = self.ids.insert(new_task_id, self.current_group_id) {
-            panic!("should not exist an id: {:?}", existing);
-        }
-        Some(new_task_id)
+        self.insert_task_into_group(self.current_group_id, task)
     }
 
     /// Insert a task into a task group. The parent id can either be the id of the group or another task in the same
@@ -133,22 +166,52 @@ impl Scheduler {
     pub fn insert_task_with_group_id<T: Task>(&mut self, group_id: TaskId, task: T) -> Option<TaskId> {
         // Get the internal id of the parent task or group.
         let group_id: InternalId = self.ids.get(&group_id)?;
-        // Use that to find the task group for this task.
-        let group: &mut TaskGroup = self.groups.get_mut(group_id.into())?;
-        // Insert the task into the task group.
-        let new_task_id: TaskId = group.insert(Box::new(task))?;
+        self.insert_task_into_group(group_id, task)
+    }
+
+    /// Insert a task into the default task group of [priority].
+    pub fn insert_task_with_priority<T: Task>(&mut self, priority: TaskPriority, task: T) -> Option<TaskId> {
+        let group_id: InternalId = match priority {
+            TaskPriority::High => self.high_priority_group_id,
+            TaskPriority::Low => self.low_priority_group_id,
+        };
+        self.insert_task_into_group(group_id, task)
+    }
+
+    /// Insert a task into the task group with internal id [group_id]. Task ids are allocated here, rather than by the
+    /// group, so that they are unique across groups.
+    fn insert_task_into_group<T: Task>(&mut self, group_id: InternalId, task: T) -> Option<TaskId> {
+        if !self.groups.contains(group_id.into()) {
+            return None;
+        }
         // Add a mapping so we can use this new task id to find the task in the future.
-        self.ids.insert(new_task_id, group_id);
-        Some(new_task_id)
+        let new_task_id: TaskId = self.ids.insert_with_new_id(group_id);
+        // Insert the task into the task group.
+        match self.groups[group_id.into()].insert(new_task_id, Box::new(task)) {
+            Some(task_id) => Some(task_id),
+            None => {
+                self.ids.remove(&new_task_id);
+                None
+            },
+        }
     }
 
     pub fn remove_task(&mut self, task_id: TaskId) -> Option<Box<dyn Task>> {
+        let group_id: InternalId = self.ids.get(&task_id)?;
         // Use that to find the task group for this task.
         let group: &mut TaskGroup = self.get_mut_group(&task_id)?;
+        let internal_id: InternalId = group.unchecked_external_to_internal_id(&task_id);
         // Remove the task into the task group.
         let task: Box<dyn Task> = group.remove(task_id)?;
         // Remove the task mapping.
         self.ids.remove(&task_id)?;
+        if self.accounting.is_enabled() {
+            self.accounting.finish(task_id);
+        }
+        // Do not poll the task later on if it was about to run.
+        if group_id == self.current_group_id {
+            self.current_ready_tasks.retain(|id| *id != internal_id);
+        }
         Some(task)
     }
 
@@ -158,43 +221,75 @@ impl Scheduler {
             "task group should exist: "
         );
         assert!(self.current_running_task.is_none());
-        *self.current_running_task = Some(group.unchecked_internal_to_external_id(self.current_task_id));
+        let task_id: TaskId = group.unchecked_internal_to_external_id(self.current_task_id);
+        *self.current_running_task = Some(task_id);
         assert!(self.current_running_task.is_some());
+        // Only read the clock if we are accounting for the time spent in each task.
+        let (name, start): (&'static str, Option<Instant>) = if self.accounting.is_enabled() {
+            (group.unchecked_get_name(self.current_task_id), Some(Instant::now()))
+        } else {
+            ("", None)
+        };
+        // Every poll starts with a fresh cooperative yield budget.
+        budget::reset_budget();
         let result: Option<Box<dyn Task>> = group.poll_notified_task_and_remove_if_ready(self.current_task_id);
+        if let Some(start) = start {
+            let elapsed: Duration = start.elapsed();
+            self.accounting.record_poll(task_id, name, elapsed);
+            if result.is_some() {
+                self.accounting.finish(task_id);
+            }
+        }
         assert!(self.current_running_task.is_some());
         *self.current_running_task = None;
         assert!(self.current_running_task.is_none());
         result
     }
 
-    /// Poll all tasks which are ready to run for [max_iterations]. This does the same thing as get_next_completed task
-    /// but does not stop until it has reached [max_iterations] and collects all of the
+    /// Turns per-task accounting of runtime and poll counts on or off. Turning it off drops the stats collected so far.
+    pub fn set_accounting(&mut self, enabled: bool) {
+        self.accounting.set_enabled(enabled);
+    }
+
+    /// Returns the accounting of the [max_tasks] tasks with the longest single polls. Tasks that already finished are
+    /// aggregated by name.
+    pub fn get_task_stats(&self, max_tasks: usize) -> Vec<TaskStats> {
+        self.accounting.get_top_tasks(max_tasks)
+    }
+
+    /// Poll all tasks which are ready to run once. Unlike get_next_completed_task, every group with ready tasks gets a
+    /// turn, so priorities only decide the order: the tasks of high priority groups run before those of low priority
+    /// ones. Collects all of the tasks that completed.
     pub fn poll_all(&mut self) -> Vec<Box<dyn Task>> {
         let mut completed_tasks: Vec<Box<dyn Task>> = vec![];
-        let start_group = self.current_group_id;
-        loop {
-            self.current_task_id = {
-                match self.current_ready_tasks.pop() {
-                    Some(index) => index,
-                    None => {
-                        self.next_runnable_group();
-                        if self.current_group_id == start_group {
-                            break;
-                        }
-                        match self.current_ready_tasks.pop() {
-                            Some(index) => index,
-                            None => return completed_tasks,
-                        }
-                    },
-                }
-            };
+        // Finish running the tasks that were already chosen.
+        self.poll_current_ready_tasks(&mut completed_tasks);
+        for priority in [TaskPriority::High, TaskPriority::Low] {
+            let group_ids: Vec<InternalId> = self
+                .groups
+                .iter()
+                .filter(|(_, group)| group.get_priority() == priority && group.has_ready_tasks())
+                .map(|(index, _)| InternalId::from(index))
+                .collect();
+            for group_id in group_ids {
+                self.current_group_id = group_id;
+                self.current_ready_tasks = self.groups[group_id.into()].get_offsets_for_ready_tasks();
+                self.poll_current_ready_tasks(&mut completed_tasks);
+            }
+        }
+        // Low priority groups just had their turn.
+        self.low_priority_skips = 0;
+        completed_tasks
+    }
 
-            // Now that we have a runnable task, actually poll it.
+    /// Polls the current set of ready tasks, adding those that complete to [completed_tasks].
+    fn poll_current_ready_tasks(&mut self, completed_tasks: &mut Vec<Box<dyn Task>>) {
+        while let Some(index) = self.current_ready_tasks.pop() {
+            self.current_task_id = index;
             if let Some(task) = self.poll_notified_task_and_remove_if_ready() {
                 completed_tasks.push(task);
             }
         }
-        completed_tasks
     }
 
     /// Poll all tasks until one completes. Remove that task and return it or fail after polling [max_iteration] number
@@ -223,29 +318,54 @@ impl Scheduler {
     }
 
     /// Poll over all of the groups looking for a group with runnable tasks. Sets the current_group_id to the next
-    /// runnable task group and current_ready_tasks to a list of tasks that are runnable in that group.
+    /// runnable task group and current_ready_tasks to a list of tasks that are runnable in that group. High priority
+    /// groups are preferred, unless low priority ones have been passed over for too long. If no group has runnable
+    /// tasks, the current group does not change.
     fn next_runnable_group(&mut self) {
-        let starting_group_index: InternalId = self.current_group_id;
-        self.current_group_id = self.get_next_group_index();
-
-        loop {
-            self.current_ready_tasks = self.groups[self.current_group_id.into()].get_offsets_for_ready_tasks();
-            if !self.current_ready_tasks.is_empty() {
-                return;
-            }
-            // If we reach this point, then we have looped all the way around without finding any runnable tasks.
-            if self.current_group_id == starting_group_index {
+        let (preferred, other): (TaskPriority, TaskPriority) = if self.low_priority_skips >= MAX_LOW_PRIORITY_SKIPS {
+            (TaskPriority::Low, TaskPriority::High)
+        } else {
+            (TaskPriority::High, TaskPriority::Low)
+        };
+        let group_id: InternalId = match self
+            .get_next_ready_group(preferred)
+            .or_else(|| self.get_next_ready_group(other))
+        {
+            Some(group_id) => group_id,
+            None => {
+                self.current_ready_tasks.clear();
                 return;
-            }
-            // Update the current_group_id
-            self.current_group_id = self.get_next_group_index();
+            },
+        };
+
+        // Keep track of how long low priority tasks that are ready to run have been waiting.
+        match self.groups[group_id.into()].get_priority() {
+            TaskPriority::Low => self.low_priority_skips = 0,
+            TaskPriority::High if self.get_next_ready_group(TaskPriority::Low).is_some() => {
+                self.low_priority_skips += 1
+            },
+            TaskPriority::High => {},
         }
+
+        self.current_group_id = group_id;
+        self.current_ready_tasks = self.groups[group_id.into()].get_offsets_for_ready_tasks();
+    }
+
+    /// Choose the next group of [priority] that has runnable tasks, going around the list of groups starting after the
+    /// current one.
+    fn get_next_ready_group(&self, priority: TaskPriority) -> Option<InternalId> {
+        let current_group_index: usize = self.current_group_id.into();
+        self.groups
+            .iter()
+            .filter(|(index, _)| *index > current_group_index)
+            .chain(self.groups.iter().filter(|(index, _)| *index <= current_group_index))
+            .find(|(_, group)| group.get_priority() == priority && group.has_ready_tasks())
+            .map(|(index, _)| InternalId::from(index))
     }
 
-    /// Choose the index of the next group to run.
-    fn get_next_group_index(&self) -> InternalId {
-        // For now, we just choose the next group in the list.
-        InternalId::from((usize::from(self.current_group_id) + 1) % self.groups.len())
+    /// Returns whether any task is ready to run.
+    pub fn has_ready_tasks(&self) -> bool {
+        !self.current_ready_tasks.is_empty() || self.groups.iter().any(|(_, group)| group.has_ready_tasks())
     }
 
     #[allow(unused)]
@@ -293,6 +413,8 @@ impl Default for Scheduler {
         // Use 0 as a special task id for the root.
         let current_task: TaskId = TaskId::from(0);
         ids.insert(current_task, internal_id);
+        let low_priority_group_id: InternalId = groups.insert(TaskGroup::new(TaskPriority::Low)).into();
+        ids.insert_with_new_id(low_priority_group_id);
         Self {
             ids,
             groups,
@@ -300,6 +422,10 @@ impl Default for Scheduler {
             current_group_id: internal_id,
             current_task_id: InternalId(0),
             current_ready_tasks: vec![],
+            high_priority_group_id: internal_id,
+            low_priority_group_id,
+            low_priority_skips: 0,
+            accounting: TaskAccounting::default(),
         }
     }
 }
@@ -360,8 +486,11 @@ mod tests {
             scheduler::{
                 Scheduler,
                 TaskId,
+                TaskPriority,
+                MAX_LOW_PRIORITY_SKIPS,
             },
             task::TaskWithResult,
+            TaskStats,
         },
     };
     use ::anyhow::Result;
@@ -410,6 +539,18 @@ mod tests {
         }
     }
 
+    /// A coroutine that never completes but is always ready to run again.
+    struct BusyCoroutine;
+
+    impl Future for BusyCoroutine {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
+            ctx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
     type DummyTask = TaskWithResult<()>;
 
     /// Tests if when inserting multiple tasks into the scheduler at once each, of them gets a unique identifier.
@@ -586,6 +727,101 @@ mod tests {
         Ok(())
     }
 
+    /// Tests that high priority tasks run before low priority ones that were inserted earlier.
+    #[test]
+    fn high_priority_tasks_run_first() -> Result<()> {
+        let mut scheduler: Scheduler = Scheduler::default();
+
+        let low: DummyTask = DummyTask::new("low", Box::pin(DummyCoroutine::new(0).fuse()));
+        let Some(low_task_id) = scheduler.insert_task_with_priority(TaskPriority::Low, low) else {
+            anyhow::bail!("insert() failed")
+        };
+        let high: DummyTask = DummyTask::new("high", Box::pin(DummyCoroutine::new(0).fuse()));
+        let Some(high_task_id) = scheduler.insert_task_with_priority(TaskPriority::High, high) else {
+            anyhow::bail!("insert() failed")
+        };
+
+        let Some(task) = scheduler.get_next_completed_task(1) else {
+            anyhow::bail!("high priority task should have completed")
+        };
+        crate::ensure_eq!(task.get_id(), high_task_id);
+        let Some(task) = scheduler.get_next_completed_task(1) else {
+            anyhow::bail!("low priority task should have completed")
+        };
+        crate::ensure_eq!(task.get_id(), low_task_id);
+
+        Ok(())
+    }
+
+    /// Tests that low priority tasks still run while high priority tasks are always ready.
+    #[test]
+    fn low_priority_tasks_are_not_starved() -> Result<()> {
+        let mut scheduler: Scheduler = Scheduler::default();
+
+        let busy: DummyTask = DummyTask::new("busy", Box::pin(BusyCoroutine.fuse()));
+        if scheduler.insert_task_with_priority(TaskPriority::High, busy).is_none() {
+            anyhow::bail!("insert() failed")
+        }
+        let low: DummyTask = DummyTask::new("low", Box::pin(DummyCoroutine::new(0).fuse()));
+        let Some(low_task_id) = scheduler.insert_task_with_priority(TaskPriority::Low, low) else {
+            anyhow::bail!("insert() failed")
+        };
+
+        // The busy task runs once per turn, so the low priority one must get its turn right after being skipped.
+        crate::ensure_eq!(
+            scheduler.get_next_completed_task(MAX_LOW_PRIORITY_SKIPS).is_none(),
+            true
+        );
+        let Some(task) = scheduler.get_next_completed_task(1) else {
+            anyhow::bail!("low priority task should have completed")
+        };
+        crate::ensure_eq!(task.get_id(), low_task_id);
+
+        Ok(())
+    }
+
+    /// Tests that the scheduler accounts for the polls of each task once accounting is enabled.
+    #[test]
+    fn accounting_counts_polls() -> Result<()> {
+        let mut scheduler: Scheduler = Scheduler::default();
+
+        // Nothing is accounted while accounting is disabled.
+        let task: DummyTask = DummyTask::new("disabled", Box::pin(DummyCoroutine::new(0).fuse()));
+        if scheduler.insert_task(task).is_none() {
+            anyhow::bail!("insert() failed")
+        }
+        crate::ensure_eq!(scheduler.get_next_completed_task(1).is_some(), true);
+        crate::ensure_eq!(scheduler.get_task_stats(8).is_empty(), true);
+
+        scheduler.set_accounting(true);
+        let busy: DummyTask = DummyTask::new("busy", Box::pin(BusyCoroutine.fuse()));
+        let Some(busy_task_id) = scheduler.insert_task(busy) else {
+            anyhow::bail!("insert() failed")
+        };
+        let task: DummyTask = DummyTask::new("testing", Box::pin(DummyCoroutine::new(1).fuse()));
+        if scheduler.insert_task(task).is_none() {
+            anyhow::bail!("insert() failed")
+        }
+        for _ in 0..3 {
+            scheduler.poll_all();
+        }
+
+        let stats: Vec<TaskStats> = scheduler.get_task_stats(8);
+        crate::ensure_eq!(stats.len(), 2);
+        let Some(busy_stats) = stats.iter().find(|stats| stats.name() == "busy") else {
+            anyhow::bail!("busy task should be accounted")
+        };
+        crate::ensure_eq!(busy_stats.task_id(), Some(busy_task_id));
+        crate::ensure_eq!(busy_stats.num_polls(), 3);
+        let Some(finished_stats) = stats.iter().find(|stats| stats.name() == "testing") else {
+            anyhow::bail!("finished task should be accounted")
+        };
+        crate::ensure_eq!(finished_stats.task_id(), None);
+        crate::ensure_eq!(finished_stats.num_polls(), 2);
+
+        Ok(())
+    }
+
     #[bench]
     fn benchmark_insert(b: &mut Bencher) {
         let mut scheduler: Scheduler = Scheduler::default();
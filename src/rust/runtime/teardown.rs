@@ -0,0 +1,169 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Releases resources that outlive the process, such as the ports of a NIC, hugepages and named shared memory regions,
+//! when the process panics or exits without dropping them. Otherwise, a crashed process leaves the NIC unusable and
+//! stale files in /dev/shm that break the next run.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use ::std::{
+    panic,
+    sync::{
+        atomic::{
+            AtomicU64,
+            Ordering,
+        },
+        Mutex,
+        Once,
+        TryLockError,
+    },
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Action that releases a resource.
+type Action = Box<dyn FnOnce() + Send>;
+
+/// Registration of a teardown action. Dropping it withdraws the action without running it, once the resource was
+/// released the regular way.
+pub struct TeardownGuard {
+    id: u64,
+}
+
+//======================================================================================================================
+// Static Variables
+//======================================================================================================================
+
+/// Guardian to the installation of the hooks.
+static INIT_HOOKS: Once = Once::new();
+
+/// Identifier of the next action.
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Actions that were registered and not withdrawn yet, from the oldest to the newest.
+static ACTIONS: Mutex<Vec<(u64, &'static str, Action)>> = Mutex::new(Vec::new());
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Registers [action], which releases the resource [name] if the process panics or exits before the returned guard is
+/// dropped. Actions are run at most once, from the newest to the oldest, so that resources are released in the reverse
+/// order of their acquisition. They may run from any thread, and should check that they run in the process that
+/// acquired the resource, as children inherit the registrations of their parent across fork.
+pub fn register(name: &'static str, action: impl FnOnce() + Send + 'static) -> TeardownGuard {
+    install_hooks();
+    let id: u64 = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    match ACTIONS.lock() {
+        Ok(mut actions) => actions.push((id, name, Box::new(action))),
+        Err(e) => e.into_inner().push((id, name, Box::new(action))),
+    }
+    TeardownGuard { id }
+}
+
+/// Runs the actions that were not withdrawn yet, and forgets them.
+pub fn run() {
+    run_matching(|_| true)
+}
+
+/// Runs the actions that were not withdrawn yet and whose resource name satisfies [filter], and forgets them.
+fn run_matching(filter: impl Fn(&str) -> bool) {
+    // Do not wait on a panic that happened while the actions were being registered or withdrawn, but do run them if
+    // another panic poisoned the lock, as this is when they matter most.
+    let mut actions = match ACTIONS.try_lock() {
+        Ok(actions) => actions,
+        Err(TryLockError::Poisoned(e)) => e.into_inner(),
+        Err(TryLockError::WouldBlock) => return,
+    };
+    let (matching, others): (Vec<(u64, &'static str, Action)>, _) =
+        actions.drain(..).partition(|(_, name, _)| filter(name));
+    *actions = others;
+    drop(actions);
+    for (_, name, action) in matching.into_iter().rev() {
+        info!("run(): releasing {}", name);
+        action();
+    }
+}
+
+/// Installs the hooks that run the actions on panic and on exit.
+fn install_hooks() {
+    INIT_HOOKS.call_once(|| {
+        // Panics that unwind drop the resources on their way, whereas panics that abort the process do not.
+        if cfg!(panic = "abort") {
+            let hook = panic::take_hook();
+            panic::set_hook(Box::new(move |info| {
+                hook(info);
+                run();
+            }));
+        }
+        if unsafe { libc::atexit(run_at_exit) } != 0 {
+            warn!("install_hooks(): failed to install exit hook");
+        }
+    });
+}
+
+extern "C" fn run_at_exit() {
+    run();
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Drop for TeardownGuard {
+    fn drop(&mut self) {
+        let mut actions = match ACTIONS.lock() {
+            Ok(actions) => actions,
+            Err(e) => e.into_inner(),
+        };
+        actions.retain(|(id, _, _)| *id != self.id);
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::teardown;
+    use ::anyhow::Result;
+    use ::std::sync::{
+        atomic::{
+            AtomicU32,
+            Ordering,
+        },
+        Arc,
+    };
+
+    /// Tests that actions run once unless their guard is dropped first.
+    #[test]
+    fn test_teardown_runs_pending_actions() -> Result<()> {
+        let released: Arc<AtomicU32> = Arc::new(AtomicU32::new(0));
+        let withdrawn = {
+            let released: Arc<AtomicU32> = released.clone();
+            teardown::register("test: withdrawn", move || {
+                released.fetch_add(10, Ordering::Relaxed);
+            })
+        };
+        let _pending = {
+            let released: Arc<AtomicU32> = released.clone();
+            teardown::register("test: pending", move || {
+                released.fetch_add(1, Ordering::Relaxed);
+            })
+        };
+        drop(withdrawn);
+
+        // Only run the actions of this test, as other tests hold resources of their own.
+        teardown::run_matching(|name| name.starts_with("test:"));
+        teardown::run_matching(|name| name.starts_with("test:"));
+        crate::ensure_eq!(released.load(Ordering::Relaxed), 1);
+
+        Ok(())
+    }
+}
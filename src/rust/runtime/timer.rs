@@ -127,6 +127,11 @@ impl SharedTimer {
     fn remove_timeout(&mut self, id: YieldPointId) {
         self.heap.retain(|entry| entry.0.id != id);
     }
+
+    #[cfg(target_os = "linux")]
+    fn next_expiry(&self) -> Option<Instant> {
+        self.heap.peek().map(|entry| entry.0.expiry)
+    }
 }
 
 //======================================================================================================================
@@ -152,6 +157,12 @@ pub fn global_get_time() -> Instant {
     THREAD_TIME.with(|s| s.now())
 }
 
+/// Gets the earliest time at which a timeout is set to expire, if any.
+#[cfg(target_os = "linux")]
+pub fn global_next_expiry() -> Option<Instant> {
+    THREAD_TIME.with(|s| s.next_expiry())
+}
+
 /// Blocks until the system time moves
 pub async fn wait(timeout: Duration) {
     let now: Instant = global_get_time();
@@ -0,0 +1,238 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! Structured tracing of the datapath. Operations and connections open spans, which carry fields that identify them
+//! (e.g. the queue descriptor and queue token of an operation, or the 4-tuple of a connection), and the datapath emits
+//! events for state transitions, retransmissions and drops. Events are prefixed with the spans that are open when they
+//! are emitted, so that they can be attributed to the operation or connection that caused them.
+//!
+//! Tracing is filtered at compile time by the "tracing" feature: without it, the [trace_event], [trace_scope] and
+//! [trace_instrument] macros expand to nothing. At runtime, tracing goes through the logger and it is filtered like
+//! any other log line, by the RUST_LOG environment variable or the "log.level" tunable. Events are logged at debug
+//! level to targets under [TARGET] that are named after their category (e.g. "demikernel::trace::tcp"), and spans are
+//! only recorded when [TARGET] itself is enabled, so "demikernel::trace=debug" enables everything.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use ::futures::future::FusedFuture;
+use ::std::{
+    cell::RefCell,
+    fmt::{
+        Debug,
+        Write,
+    },
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Target of tracing. Events are logged to sub-targets that are named after their category.
+pub const TARGET: &str = "demikernel::trace";
+
+//======================================================================================================================
+// Thread Local Variables
+//======================================================================================================================
+
+thread_local! {
+    /// Spans that are open on this thread, from the outermost to the innermost.
+    static CURRENT: RefCell<Vec<Rc<SpanData>>> = const { RefCell::new(Vec::new()) };
+}
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+struct SpanData {
+    name: &'static str,
+    fields: RefCell<String>,
+}
+
+/// A span, which identifies an operation or a connection. Spans are cheap to clone and they are inert if tracing is
+/// disabled at runtime when they are created.
+#[derive(Clone)]
+pub struct Span(Option<Rc<SpanData>>);
+
+/// Guard that keeps a span open on the current thread until it is dropped.
+pub struct Entered(bool);
+
+/// A future that opens [span] whenever it is polled, so that the span follows the future across await points.
+pub struct Instrumented<F: Future> {
+    future: Pin<Box<F>>,
+    span: Span,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Span {
+    /// Creates a span named [name]. Fields are added with [record].
+    pub fn new(name: &'static str) -> Self {
+        if !is_enabled() {
+            return Self(None);
+        }
+        Self(Some(Rc::new(SpanData {
+            name,
+            fields: RefCell::new(String::new()),
+        })))
+    }
+
+    /// Adds the field [key] to this span. This may be called after the span was opened, for fields that are only known
+    /// later (e.g. the queue token of an operation).
+    pub fn record(&self, key: &str, value: &dyn Debug) {
+        if let Some(data) = &self.0 {
+            let mut fields = data.fields.borrow_mut();
+            if !fields.is_empty() {
+                fields.push(' ');
+            }
+            let _ = write!(fields, "{}={:?}", key, value);
+        }
+    }
+
+    /// Opens this span on the current thread, until the returned guard is dropped.
+    pub fn enter(&self) -> Entered {
+        match &self.0 {
+            Some(data) => {
+                CURRENT.with(|current| current.borrow_mut().push(data.clone()));
+                Entered(true)
+            },
+            None => Entered(false),
+        }
+    }
+}
+
+impl<F: Future> Instrumented<F> {
+    pub fn new(future: Pin<Box<F>>, span: Span) -> Self {
+        Self { future, span }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Drop for Entered {
+    fn drop(&mut self) {
+        if self.0 {
+            CURRENT.with(|current| current.borrow_mut().pop());
+        }
+    }
+}
+
+impl<F: Future> Future for Instrumented<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        let self_: &mut Self = self.get_mut();
+        let _entered: Entered = self_.span.enter();
+        self_.future.as_mut().poll(ctx)
+    }
+}
+
+impl<F: FusedFuture> FusedFuture for Instrumented<F> {
+    fn is_terminated(&self) -> bool {
+        self.future.is_terminated()
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Checks if spans are recorded.
+pub fn is_enabled() -> bool {
+    log_enabled!(target: TARGET, log::Level::Debug)
+}
+
+/// Formats the spans that are open on the current thread, like "op{qd=3 qt=7} > tcp{local=.. remote=..}".
+pub fn current_spans() -> String {
+    CURRENT.with(|current| {
+        let mut spans: String = String::new();
+        for span in current.borrow().iter() {
+            if !spans.is_empty() {
+                spans.push_str(" > ");
+            }
+            let _ = write!(spans, "{}{{{}}}", span.name, span.fields.borrow());
+        }
+        spans
+    })
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        current_spans,
+        Instrumented,
+        Span,
+        SpanData,
+    };
+    use ::anyhow::Result;
+    use ::futures::FutureExt;
+    use ::std::{
+        cell::RefCell,
+        rc::Rc,
+    };
+
+    /// Creates a span regardless of the logging level, which is not set in unit tests.
+    fn span(name: &'static str) -> Span {
+        Span(Some(Rc::new(SpanData {
+            name,
+            fields: RefCell::new(String::new()),
+        })))
+    }
+
+    /// Tests that spans nest and close in order, and that fields can be recorded after a span was opened.
+    #[test]
+    fn test_span_nesting() -> Result<()> {
+        let op: Span = span("op");
+        op.record("qd", &3);
+        {
+            let _op = op.enter();
+            op.record("qt", &7);
+            crate::ensure_eq!(current_spans(), "op{qd=3 qt=7}");
+            let tcp: Span = span("tcp");
+            tcp.record("remote", &"10.0.0.1:80");
+            {
+                let _tcp = tcp.enter();
+                crate::ensure_eq!(current_spans(), "op{qd=3 qt=7} > tcp{remote=\"10.0.0.1:80\"}");
+            }
+            crate::ensure_eq!(current_spans(), "op{qd=3 qt=7}");
+        }
+        crate::ensure_eq!(current_spans(), "");
+
+        // Inert spans are never opened.
+        let _none = Span(None).enter();
+        crate::ensure_eq!(current_spans(), "");
+        Ok(())
+    }
+
+    /// Tests that instrumented futures open their span only while they are polled.
+    #[test]
+    fn test_instrumented_future() -> Result<()> {
+        let op: Span = span("op");
+        op.record("qd", &1);
+        let spans: Rc<RefCell<String>> = Rc::new(RefCell::new(String::new()));
+        let spans_: Rc<RefCell<String>> = spans.clone();
+        let future = Box::pin(async move { *spans_.borrow_mut() = current_spans() });
+        let mut future: Instrumented<_> = Instrumented::new(future, op);
+        crate::ensure_eq!(current_spans(), "");
+        crate::ensure_eq!((&mut future).now_or_never(), Some(()));
+        crate::ensure_eq!(spans.borrow().as_str(), "op{qd=1}");
+        crate::ensure_eq!(current_spans(), "");
+        Ok(())
+    }
+}
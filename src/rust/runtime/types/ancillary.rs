@@ -0,0 +1,148 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+#![allow(non_camel_case_types)]
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    network::ancillary::AncillaryData,
+    types::timestamp::nanos_since_epoch,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// The destination address of the datagram is valid.
+pub const DEMI_ANCILLARY_DST_ADDR: u32 = 1 << 0;
+/// The time to live of the datagram is valid.
+pub const DEMI_ANCILLARY_TTL: u32 = 1 << 1;
+/// The type of service of the datagram is valid.
+pub const DEMI_ANCILLARY_TOS: u32 = 1 << 2;
+/// The receive timestamp of the datagram is valid.
+pub const DEMI_ANCILLARY_TIMESTAMP: u32 = 1 << 3;
+/// The hardware receive timestamp of the datagram is valid.
+pub const DEMI_ANCILLARY_HW_TIMESTAMP: u32 = 1 << 4;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Metadata of a popped datagram.
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct demi_ancillary_t {
+    /// Which of the fields below are valid, as a combination of the `DEMI_ANCILLARY_*` flags.
+    pub flags: u32,
+    /// Destination IPv4 address of the datagram, in network byte order.
+    pub dst_addr: u32,
+    /// Time at which the datagram was received, in nanoseconds since the Unix epoch.
+    pub timestamp_ns: u64,
+    /// Time at which the device received the datagram, in nanoseconds of the clock of the device.
+    pub hw_timestamp_ns: u64,
+    /// Time to live of the datagram when it arrived.
+    pub ttl: u8,
+    /// Type of service of the datagram.
+    pub tos: u8,
+    /// Reserved, so that scatter-gather arrays keep a size that is a multiple of eight bytes.
+    pub reserved: [u8; 6],
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl From<AncillaryData> for demi_ancillary_t {
+    fn from(ancillary: AncillaryData) -> Self {
+        let mut flags: u32 = 0;
+        if ancillary.local.is_some() {
+            flags |= DEMI_ANCILLARY_DST_ADDR;
+        }
+        if ancillary.ttl.is_some() {
+            flags |= DEMI_ANCILLARY_TTL;
+        }
+        if ancillary.tos.is_some() {
+            flags |= DEMI_ANCILLARY_TOS;
+        }
+        let timestamp_ns: Option<u64> = ancillary.timestamp.map(nanos_since_epoch);
+        if timestamp_ns.is_some() {
+            flags |= DEMI_ANCILLARY_TIMESTAMP;
+        }
+        if ancillary.hw_timestamp.is_some() {
+            flags |= DEMI_ANCILLARY_HW_TIMESTAMP;
+        }
+        Self {
+            flags,
+            dst_addr: ancillary.local.map_or(0, |addr| u32::from_ne_bytes(addr.octets())),
+            timestamp_ns: timestamp_ns.unwrap_or(0),
+            hw_timestamp_ns: ancillary.hw_timestamp.unwrap_or(0),
+            ttl: ancillary.ttl.unwrap_or(0),
+            tos: ancillary.tos.unwrap_or(0),
+            reserved: [0; 6],
+        }
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod test {
+    use crate::runtime::types::ancillary::*;
+    use ::std::{
+        mem,
+        net::Ipv4Addr,
+        time::{
+            Duration,
+            SystemTime,
+        },
+    };
+
+    /// Tests if the `demi_ancillary_t` structure has the expected size.
+    #[test]
+    fn test_size_demi_ancillary_t() -> Result<(), anyhow::Error> {
+        // Size of a u32.
+        const FLAGS_SIZE: usize = 4;
+        // Size of an in_addr structure.
+        const DST_ADDR_SIZE: usize = 4;
+        // Size of two u64.
+        const TIMESTAMPS_SIZE: usize = 16;
+        // Size of two u8.
+        const TTL_TOS_SIZE: usize = 2;
+        // Size of the reserved bytes.
+        const RESERVED_SIZE: usize = 6;
+        crate::ensure_eq!(
+            mem::size_of::<demi_ancillary_t>(),
+            FLAGS_SIZE + DST_ADDR_SIZE + TIMESTAMPS_SIZE + TTL_TOS_SIZE + RESERVED_SIZE
+        );
+        Ok(())
+    }
+
+    /// Tests that only the fields that are known are flagged as valid.
+    #[test]
+    fn test_demi_ancillary_t_from() -> Result<(), anyhow::Error> {
+        let empty: demi_ancillary_t = AncillaryData::default().into();
+        crate::ensure_eq!({ empty.flags }, 0);
+
+        let ancillary: demi_ancillary_t = AncillaryData {
+            local: Some(Ipv4Addr::new(192, 168, 1, 2)),
+            ttl: Some(64),
+            tos: None,
+            timestamp: Some(SystemTime::UNIX_EPOCH + Duration::from_nanos(1_500)),
+            hw_timestamp: None,
+        }
+        .into();
+        crate::ensure_eq!(
+            { ancillary.flags },
+            DEMI_ANCILLARY_DST_ADDR | DEMI_ANCILLARY_TTL | DEMI_ANCILLARY_TIMESTAMP
+        );
+        crate::ensure_eq!({ ancillary.dst_addr }.to_ne_bytes(), [192, 168, 1, 2]);
+        crate::ensure_eq!({ ancillary.ttl }, 64);
+        crate::ensure_eq!({ ancillary.timestamp_ns }, 1_500);
+        Ok(())
+    }
+}
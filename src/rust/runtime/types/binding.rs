@@ -0,0 +1,132 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+#![allow(non_camel_case_types)]
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::affinity::{
+    BindingConfig,
+    EngineBinding,
+    MAX_CORES,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// The cores of the binding are set.
+pub const DEMI_BINDING_CORES: u32 = 1 << 0;
+/// The niceness of the binding is set.
+pub const DEMI_BINDING_NICE: u32 = 1 << 1;
+/// The real-time priority of the binding is set.
+pub const DEMI_BINDING_REALTIME_PRIORITY: u32 = 1 << 2;
+/// The timer slack of the binding is set.
+pub const DEMI_BINDING_TIMER_SLACK: u32 = 1 << 3;
+
+/// Number of words in the mask of cores of a binding.
+const CORE_MASK_WORDS: usize = MAX_CORES / 64;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// How the thread that runs the engine is bound to cores and scheduled by the kernel.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct demi_binding_t {
+    /// Mask of the cores that the engine may run on. Core `i` is bit `i % 64` of word `i / 64`.
+    pub cores: [u64; CORE_MASK_WORDS],
+    /// Combination of the `DEMI_BINDING_*` flags, telling which of the fields are set.
+    pub flags: u32,
+    /// Core that the engine runs on right now. This is ignored when binding the engine.
+    pub current_core: u32,
+    /// Niceness of the engine, from -20 (highest priority) to 19 (lowest priority).
+    pub nice: i32,
+    /// Real-time priority of the engine under the FIFO policy, or zero for the default policy.
+    pub realtime_priority: u32,
+    /// Slack that the kernel may add to the timers of the engine, in nanoseconds.
+    pub timer_slack_ns: u64,
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl From<&EngineBinding> for demi_binding_t {
+    fn from(binding: &EngineBinding) -> Self {
+        let mut cores: [u64; CORE_MASK_WORDS] = [0; CORE_MASK_WORDS];
+        for core in binding.cores.iter() {
+            cores[core / 64] |= 1 << (core % 64);
+        }
+        Self {
+            cores,
+            flags: DEMI_BINDING_CORES | DEMI_BINDING_NICE | DEMI_BINDING_REALTIME_PRIORITY | DEMI_BINDING_TIMER_SLACK,
+            current_core: binding.current_core as u32,
+            nice: binding.nice,
+            realtime_priority: binding.realtime_priority.unwrap_or(0),
+            timer_slack_ns: binding.timer_slack_ns,
+        }
+    }
+}
+
+impl From<&demi_binding_t> for BindingConfig {
+    fn from(binding: &demi_binding_t) -> Self {
+        let cores: Vec<usize> = (0..MAX_CORES)
+            .filter(|core| binding.cores[core / 64] & (1 << (core % 64)) != 0)
+            .collect();
+        Self {
+            cores: (binding.flags & DEMI_BINDING_CORES != 0).then_some(cores),
+            nice: (binding.flags & DEMI_BINDING_NICE != 0).then_some(binding.nice),
+            realtime_priority: (binding.flags & DEMI_BINDING_REALTIME_PRIORITY != 0)
+                .then_some(binding.realtime_priority),
+            timer_slack_ns: (binding.flags & DEMI_BINDING_TIMER_SLACK != 0).then_some(binding.timer_slack_ns),
+        }
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod test {
+    use crate::runtime::types::binding::*;
+    use ::std::mem;
+
+    /// Tests if the binding structure has the expected size.
+    #[test]
+    fn test_size_demi_binding_t() -> Result<(), anyhow::Error> {
+        // A mask of 1024 cores, four u32 and a u64.
+        crate::ensure_eq!(mem::size_of::<demi_binding_t>(), 128 + 16 + 8);
+        Ok(())
+    }
+
+    /// Tests that cores and flags are carried across the structure.
+    #[test]
+    fn test_convert_demi_binding_t() -> Result<(), anyhow::Error> {
+        let binding: EngineBinding = EngineBinding {
+            cores: vec![1, 64, 1023],
+            current_core: 64,
+            nice: -5,
+            realtime_priority: None,
+            timer_slack_ns: 50000,
+        };
+        let mut raw: demi_binding_t = demi_binding_t::from(&binding);
+        crate::ensure_eq!(raw.cores[0], 1 << 1);
+        crate::ensure_eq!(raw.cores[1], 1);
+        crate::ensure_eq!(raw.cores[15], 1 << 63);
+        crate::ensure_eq!(raw.current_core, 64);
+        crate::ensure_eq!(raw.realtime_priority, 0);
+
+        raw.flags = DEMI_BINDING_CORES | DEMI_BINDING_TIMER_SLACK;
+        let config: BindingConfig = BindingConfig::from(&raw);
+        crate::ensure_eq!(config.cores, Some(vec![1, 64, 1023]));
+        crate::ensure_eq!(config.nice, None);
+        crate::ensure_eq!(config.realtime_priority, None);
+        crate::ensure_eq!(config.timer_slack_ns, Some(50000));
+        Ok(())
+    }
+}
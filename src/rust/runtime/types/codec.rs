@@ -0,0 +1,38 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+#![allow(non_camel_case_types)]
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Frames that start with the length of their payload.
+pub const DEMI_CODEC_LENGTH: u32 = 0;
+
+/// Frames that end with a delimiter byte.
+pub const DEMI_CODEC_LINE: u32 = 1;
+
+/// Values of RESP, the protocol of Redis.
+pub const DEMI_CODEC_RESP: u32 = 2;
+
+/// Packets of the binary protocol of memcached.
+pub const DEMI_CODEC_MEMCACHED: u32 = 3;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Codec that frames the data of a TCP socket.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct demi_codec_t {
+    /// Codec.
+    pub codec_type: u32,
+    /// Size of length prefixes in bytes (1, 2, 4 or 8), for [DEMI_CODEC_LENGTH].
+    pub length_size: u32,
+    /// Whether length prefixes are little endian, for [DEMI_CODEC_LENGTH].
+    pub little_endian: u8,
+    /// Byte that ends frames, for [DEMI_CODEC_LINE].
+    pub delimiter: u8,
+}
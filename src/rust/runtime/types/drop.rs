@@ -0,0 +1,44 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+#![allow(non_camel_case_types)]
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    pal::data_structures::SockAddr,
+    runtime::network::drops::DropReason,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Number of reasons for which packets are dropped. Reasons are numbered from zero, in the order of [DropReason].
+pub const DEMI_DROP_REASONS: usize = DropReason::COUNT;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Number of incoming packets that were dropped, for each reason.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct demi_drop_stats_t {
+    /// Counts, indexed by reason.
+    pub drops: [u64; DEMI_DROP_REASONS],
+}
+
+/// An incoming packet that was dropped on its way to a socket.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct demi_drop_record_t {
+    /// Why the packet was dropped.
+    pub reason: u32,
+    /// Length of the payload of the packet, in bytes.
+    pub len: u32,
+    /// Address of the sender of the packet, or all zeros if it is not known.
+    pub addr: SockAddr,
+}
@@ -0,0 +1,44 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+#![allow(non_camel_case_types)]
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Security association that protects the traffic that is received from the peer.
+pub const DEMI_IPSEC_INBOUND: u32 = 0;
+
+/// Security association that protects the traffic that is sent to the peer.
+pub const DEMI_IPSEC_OUTBOUND: u32 = 1;
+
+/// AES-128 in Galois/Counter Mode (see RFC 4106).
+pub const DEMI_IPSEC_AES_128_GCM: u32 = 0;
+
+/// ChaCha20 with Poly1305 (see RFC 7634).
+pub const DEMI_IPSEC_CHACHA20_POLY1305: u32 = 1;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Static security association of transport-mode ESP.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct demi_ipsec_sa_t {
+    /// Security parameter index.
+    pub spi: u32,
+    /// Direction of the protected traffic.
+    pub direction: u32,
+    /// IPv4 address of the peer, in network byte order.
+    pub peer: u32,
+    /// AEAD algorithm.
+    pub algorithm: u32,
+    /// Key of the AEAD algorithm.
+    pub key: *const u8,
+    /// Length of the key.
+    pub key_len: usize,
+    /// Salt of the AEAD nonce.
+    pub salt: [u8; 4],
+}
@@ -7,7 +7,10 @@
 // Imports
 //======================================================================================================================
 
-use crate::pal::data_structures::SockAddr;
+use crate::{
+    pal::data_structures::SockAddr,
+    runtime::types::ancillary::demi_ancillary_t,
+};
 
 //======================================================================================================================
 // Constants
@@ -42,6 +45,8 @@ pub struct demi_sgarray_t {
     pub sga_segs: [demi_sgaseg_t; DEMI_SGARRAY_MAXLEN],
     /// Source address of the data contained in this scatter-gather array (if present).
     pub sga_addr: SockAddr,
+    /// Metadata of the datagram contained in this scatter-gather array (if popped).
+    pub sga_ancillary: demi_ancillary_t,
 }
 
 //======================================================================================================================
@@ -77,10 +82,12 @@ mod test {
         const SGA_SEGS_SIZE: usize = mem::size_of::<demi_sgaseg_t>() * DEMI_SGARRAY_MAXLEN;
         // Size of a SockAddr structure.
         const SGA_ADDR_SIZE: usize = mem::size_of::<SockAddr>();
+        // Size of a demi_ancillary_t structure.
+        const SGA_ANCILLARY_SIZE: usize = mem::size_of::<demi_ancillary_t>();
         // Size of a demi_sgarray_t structure.
         crate::ensure_eq!(
             mem::size_of::<demi_sgarray_t>(),
-            SGA_BUF_SIZE + SGA_NUMSEGS_SIZE + SGA_SEGS_SIZE + SGA_ADDR_SIZE
+            SGA_BUF_SIZE + SGA_NUMSEGS_SIZE + SGA_SEGS_SIZE + SGA_ADDR_SIZE + SGA_ANCILLARY_SIZE
         );
         Ok(())
     }
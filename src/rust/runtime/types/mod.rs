@@ -1,25 +1,95 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
+mod ancillary;
+mod binding;
+mod codec;
+mod drop;
+mod ipsec;
 mod memory;
+mod numa;
 mod ops;
+mod ptp;
 mod queue;
+mod quic;
+mod rdma;
+mod route;
+mod tcp_info;
+mod timestamp;
+mod tls;
 
 //==============================================================================
 // Exports
 //==============================================================================
 
 pub use self::{
+    ancillary::{
+        demi_ancillary_t,
+        DEMI_ANCILLARY_DST_ADDR,
+        DEMI_ANCILLARY_HW_TIMESTAMP,
+        DEMI_ANCILLARY_TIMESTAMP,
+        DEMI_ANCILLARY_TOS,
+        DEMI_ANCILLARY_TTL,
+    },
+    binding::{
+        demi_binding_t,
+        DEMI_BINDING_CORES,
+        DEMI_BINDING_NICE,
+        DEMI_BINDING_REALTIME_PRIORITY,
+        DEMI_BINDING_TIMER_SLACK,
+    },
+    codec::{
+        demi_codec_t,
+        DEMI_CODEC_LENGTH,
+        DEMI_CODEC_LINE,
+        DEMI_CODEC_MEMCACHED,
+        DEMI_CODEC_RESP,
+    },
+    drop::{
+        demi_drop_record_t,
+        demi_drop_stats_t,
+        DEMI_DROP_REASONS,
+    },
+    ipsec::{
+        demi_ipsec_sa_t,
+        DEMI_IPSEC_AES_128_GCM,
+        DEMI_IPSEC_CHACHA20_POLY1305,
+        DEMI_IPSEC_INBOUND,
+        DEMI_IPSEC_OUTBOUND,
+    },
     memory::{
         demi_sgarray_t,
         demi_sgaseg_t,
         DEMI_SGARRAY_MAXLEN,
     },
+    numa::demi_numa_stats_t,
     ops::{
         demi_accept_result_t,
         demi_opcode_t,
         demi_qr_value_t,
         demi_qresult_t,
+        demi_sgarray_batch_t,
+    },
+    ptp::{
+        demi_ptp_config_t,
+        demi_ptp_status_t,
+        DEMI_PTP_LISTENING,
+        DEMI_PTP_NO_NEGOTIATION,
+        DEMI_PTP_SLAVE,
+        DEMI_PTP_UNCALIBRATED,
     },
     queue::demi_qtoken_t,
+    quic::demi_quic_config_t,
+    rdma::demi_rdma_region_t,
+    route::demi_route_t,
+    tcp_info::{
+        demi_tcp_event_t,
+        demi_tcp_info_t,
+    },
+    timestamp::{
+        demi_tx_timestamp_t,
+        DEMI_TX_TIMESTAMP_HARDWARE,
+        DEMI_TX_TIMESTAMP_SOFTWARE,
+    },
+    tls::demi_tls_config_t,
 };
@@ -0,0 +1,43 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+#![allow(non_camel_case_types)]
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::network::numa::NumaStats;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Where the network device placed its buffer pools and the state of its queues, relative to its NUMA node.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct demi_numa_stats_t {
+    /// NUMA node of the network device, or -1 if it is not known.
+    pub device_node: i32,
+    /// NUMA node of the core that runs the engine, or -1 if it is not known.
+    pub core_node: i32,
+    /// Number of allocations that were placed on the node of the network device.
+    pub local_allocations: u64,
+    /// Number of allocations that were placed on another node.
+    pub remote_allocations: u64,
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl From<&NumaStats> for demi_numa_stats_t {
+    fn from(stats: &NumaStats) -> Self {
+        Self {
+            device_node: stats.device_node.map_or(-1, |node| node as i32),
+            core_node: stats.core_node.map_or(-1, |node| node as i32),
+            local_allocations: stats.local_allocations,
+            remote_allocations: stats.remote_allocations,
+        }
+    }
+}
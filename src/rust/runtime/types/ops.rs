@@ -30,6 +30,9 @@ pub enum demi_opcode_t {
     DEMI_OPC_CONNECT,
     DEMI_OPC_CLOSE,
     DEMI_OPC_FAILED,
+    DEMI_OPC_TASK,
+    DEMI_OPC_SPLICE,
+    DEMI_OPC_POP_MANY,
 }
 
 /// Result for `accept()`
@@ -40,10 +43,19 @@ pub struct demi_accept_result_t {
     pub addr: SockAddr,
 }
 
+/// Result for `pop_many()`
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct demi_sgarray_batch_t {
+    pub count: u32,
+    pub sgas: *mut demi_sgarray_t,
+}
+
 #[repr(C)]
 pub union demi_qr_value_t {
     pub sga: demi_sgarray_t,
     pub ares: demi_accept_result_t,
+    pub batch: demi_sgarray_batch_t,
 }
 
 /// Result
@@ -78,6 +90,18 @@ mod test {
         Ok(())
     }
 
+    /// Tests if `demi_sgarray_batch_t` has the expected size.
+    #[test]
+    fn test_size_demi_sgarray_batch_t() -> Result<(), anyhow::Error> {
+        // Size of a u32.
+        const COUNT_SIZE: usize = 4;
+        // Size of a pointer.
+        const SGAS_SIZE: usize = 8;
+        // Size of a demi_sgarray_batch_t structure.
+        crate::ensure_eq!(mem::size_of::<demi_sgarray_batch_t>(), COUNT_SIZE + SGAS_SIZE);
+        Ok(())
+    }
+
     /// Tests if `demi_qr_value_t` has the expected size.
     #[test]
     fn test_size_demi_qr_value_t() -> Result<(), anyhow::Error> {
@@ -85,8 +109,13 @@ mod test {
         const SGA_SIZE: usize = mem::size_of::<demi_sgarray_t>();
         // Size of a demi_accept_result_t structure.
         const ARES_SIZE: usize = mem::size_of::<demi_accept_result_t>();
+        // Size of a demi_sgarray_batch_t structure.
+        const BATCH_SIZE: usize = mem::size_of::<demi_sgarray_batch_t>();
         // Size of a demi_qr_value_t structure.
-        crate::ensure_eq!(mem::size_of::<demi_qr_value_t>(), std::cmp::max(SGA_SIZE, ARES_SIZE));
+        crate::ensure_eq!(
+            mem::size_of::<demi_qr_value_t>(),
+            std::cmp::max(SGA_SIZE, std::cmp::max(ARES_SIZE, BATCH_SIZE))
+        );
         Ok(())
     }
 
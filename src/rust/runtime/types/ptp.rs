@@ -0,0 +1,125 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+#![allow(non_camel_case_types)]
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::network::ptp::clock::{
+    PtpState,
+    SharedPtpClock,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// The client does not ask the master for unicast transmission, because the master is configured to send to it.
+pub const DEMI_PTP_NO_NEGOTIATION: u32 = 1 << 0;
+
+/// No offset from a master was measured yet.
+pub const DEMI_PTP_LISTENING: u32 = 0;
+/// The clock was stepped to the time of the master and is not locked to it yet.
+pub const DEMI_PTP_UNCALIBRATED: u32 = 1;
+/// The clock is locked to the time of the master.
+pub const DEMI_PTP_SLAVE: u32 = 2;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Configuration of a PTP client. Fields that are zero keep their default values.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct demi_ptp_config_t {
+    /// IPv4 address to which the event and general ports are bound, in network byte order.
+    pub local: u32,
+    /// IPv4 address of the master, in network byte order.
+    pub master: u32,
+    /// Combination of the `DEMI_PTP_*` configuration flags.
+    pub flags: u32,
+    /// Duration of the unicast transmissions that are requested from the master, in seconds.
+    pub grant_duration_s: u32,
+    /// PTP domain of the master.
+    pub domain: u8,
+    /// Interval of the Sync messages that are requested from the master, as a logarithm to the base 2 of seconds.
+    pub log_sync_interval: i8,
+    /// Interval of the Delay_Req messages that are sent to the master, as a logarithm to the base 2 of seconds.
+    pub log_delay_req_interval: i8,
+    /// Reserved, must be zero.
+    pub reserved: u8,
+    /// Identity of the clock of the client. A random identity is picked if it is zero.
+    pub clock_identity: [u8; 8],
+}
+
+/// State of the clock that a PTP client steers.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct demi_ptp_status_t {
+    /// One of the `DEMI_PTP_*` states.
+    pub state: u32,
+    /// Reserved, so that the fields below are aligned.
+    pub reserved: u32,
+    /// Offset of the clock from the master when it was last sampled, in nanoseconds.
+    pub offset_ns: i64,
+    /// Mean delay of the path to the master, in nanoseconds.
+    pub mean_path_delay_ns: i64,
+    /// Correction of the rate of the clock relative to the local clock, in parts per billion.
+    pub freq_ppb: i64,
+    /// Number of times that the offset from the master was measured.
+    pub num_samples: u64,
+    /// Identity of the clock of the master.
+    pub master_clock_identity: [u8; 8],
+    /// Port number of the master.
+    pub master_port_number: u16,
+    /// Reserved, so that the size is a multiple of eight bytes.
+    pub reserved2: [u8; 6],
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl From<&SharedPtpClock> for demi_ptp_status_t {
+    fn from(clock: &SharedPtpClock) -> Self {
+        let state: u32 = match clock.state() {
+            PtpState::Listening => DEMI_PTP_LISTENING,
+            PtpState::Uncalibrated => DEMI_PTP_UNCALIBRATED,
+            PtpState::Slave => DEMI_PTP_SLAVE,
+        };
+        let master = clock.master().unwrap_or_default();
+        Self {
+            state,
+            reserved: 0,
+            offset_ns: clock.last_offset(),
+            mean_path_delay_ns: clock.mean_path_delay(),
+            freq_ppb: clock.freq_ppb().round() as i64,
+            num_samples: clock.num_samples(),
+            master_clock_identity: master.clock_identity,
+            master_port_number: master.port_number,
+            reserved2: [0; 6],
+        }
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod test {
+    use crate::runtime::types::ptp::*;
+    use ::std::mem;
+
+    /// Tests if the PTP structures have the expected sizes.
+    #[test]
+    fn test_size_demi_ptp_t() -> Result<(), anyhow::Error> {
+        // Four u32, four u8 and a clock identity.
+        crate::ensure_eq!(mem::size_of::<demi_ptp_config_t>(), 16 + 4 + 8);
+        // Two u32, four u64, a clock identity, a u16 and the reserved bytes.
+        crate::ensure_eq!(mem::size_of::<demi_ptp_status_t>(), 8 + 32 + 8 + 2 + 6);
+        Ok(())
+    }
+}
@@ -0,0 +1,30 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+#![allow(non_camel_case_types)]
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::types::demi_tls_config_t;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Configuration of QUIC connections. Limits that are zero keep their default values.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct demi_quic_config_t {
+    /// Configuration of the TLS handshake, which authenticates peers.
+    pub tls: demi_tls_config_t,
+    /// Time without activity after which a connection is dropped, in milliseconds.
+    pub idle_timeout_ms: u64,
+    /// Bytes that the peer may send on all streams, ahead of what the application read.
+    pub max_data: u64,
+    /// Bytes that the peer may send on each stream, ahead of what the application read.
+    pub max_stream_data: u64,
+    /// Streams that the peer may have open at once.
+    pub max_streams: u64,
+}
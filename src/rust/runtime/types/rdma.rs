@@ -0,0 +1,20 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+#![allow(non_camel_case_types)]
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Memory that the peer of an RDMA queue exposed for one-sided reads and writes.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct demi_rdma_region_t {
+    /// Address of the memory in the address space of the peer.
+    pub addr: u64,
+    /// Length of the memory in bytes.
+    pub len: u32,
+    /// Remote key that grants access to the memory.
+    pub rkey: u32,
+}
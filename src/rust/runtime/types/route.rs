@@ -0,0 +1,22 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+#![allow(non_camel_case_types)]
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Route toward an IPv4 subnet.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct demi_route_t {
+    /// Address of the subnet, in network byte order.
+    pub destination: u32,
+    /// Length of the prefix of the subnet, in bits.
+    pub prefix_len: u32,
+    /// IPv4 address of the gateway, in network byte order, or zero if the subnet is directly connected.
+    pub gateway: u32,
+    /// Index of the network interface that the traffic leaves through, zero for the primary one.
+    pub interface: u32,
+}
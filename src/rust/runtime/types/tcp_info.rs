@@ -0,0 +1,109 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+#![allow(non_camel_case_types)]
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::network::tcp_info::{
+    TcpEvent,
+    TcpInfo,
+};
+use ::std::time::Duration;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Snapshot of the state of a TCP connection.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct demi_tcp_info_t {
+    /// State of the state machine, numbered as in Linux (e.g. TCP_ESTABLISHED).
+    pub state: u32,
+    /// Maximum segment size that is used to send data, in bytes.
+    pub mss: u32,
+    /// Congestion window, in bytes.
+    pub cwnd: u32,
+    /// Slow start threshold, in bytes.
+    pub ssthresh: u32,
+    /// Smoothed round-trip time, in microseconds.
+    pub srtt_us: u32,
+    /// Round-trip time variation, in microseconds.
+    pub rttvar_us: u32,
+    /// Retransmission timeout, in microseconds.
+    pub rto_us: u32,
+    /// Bytes that were sent but not acknowledged yet.
+    pub bytes_in_flight: u32,
+    /// Bytes that were pushed but not sent yet.
+    pub send_queue: u32,
+    /// Bytes that were received but not popped yet.
+    pub recv_queue: u32,
+    /// Receive buffer, in bytes, which bounds the advertised window and grows with auto-tuning.
+    pub recv_space: u32,
+    /// Non-zero if our peer advertises a zero window, so that only window probes are sent.
+    pub flow_control_stalled: u32,
+    /// Number of window probes.
+    pub window_probes: u32,
+    /// Number of segments that were retransmitted.
+    pub retransmits: u64,
+    /// Number of times that the retransmission timer expired.
+    pub timeouts: u64,
+    /// Number of fast retransmissions.
+    pub fast_retransmits: u64,
+}
+
+/// Event in the timeline of a TCP connection.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct demi_tcp_event_t {
+    /// Kind of event (e.g. DEMI_TCP_EVENT_RETRANSMIT_TIMEOUT).
+    pub kind: u32,
+    /// Oldest unacknowledged sequence number when the event happened.
+    pub seq_no: u32,
+    /// Time since the connection was established, in microseconds.
+    pub time_us: u64,
+    /// Value that depends on the kind of event.
+    pub value: u64,
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl From<TcpInfo> for demi_tcp_info_t {
+    fn from(info: TcpInfo) -> Self {
+        let micros = |duration: Duration| -> u32 { u32::try_from(duration.as_micros()).unwrap_or(u32::MAX) };
+        Self {
+            state: info.state as u32,
+            mss: info.mss,
+            cwnd: info.cwnd,
+            ssthresh: info.ssthresh,
+            srtt_us: micros(info.srtt),
+            rttvar_us: micros(info.rttvar),
+            rto_us: micros(info.rto),
+            bytes_in_flight: info.bytes_in_flight,
+            send_queue: info.send_queue,
+            recv_queue: info.recv_queue,
+            recv_space: info.recv_space,
+            flow_control_stalled: info.flow_control_stalled as u32,
+            window_probes: info.window_probes,
+            retransmits: info.retransmits,
+            timeouts: info.timeouts,
+            fast_retransmits: info.fast_retransmits,
+        }
+    }
+}
+
+impl From<TcpEvent> for demi_tcp_event_t {
+    fn from(event: TcpEvent) -> Self {
+        Self {
+            kind: event.kind as u32,
+            seq_no: event.seq_no,
+            time_us: u64::try_from(event.at.as_micros()).unwrap_or(u64::MAX),
+            value: event.value,
+        }
+    }
+}
@@ -0,0 +1,72 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+#![allow(non_camel_case_types)]
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::network::timestamp::TxTimestamp;
+use ::std::time::SystemTime;
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// The software transmit timestamp is valid.
+pub const DEMI_TX_TIMESTAMP_SOFTWARE: u32 = 1 << 0;
+/// The hardware transmit timestamp is valid.
+pub const DEMI_TX_TIMESTAMP_HARDWARE: u32 = 1 << 1;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Times at which an outgoing packet was transmitted.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct demi_tx_timestamp_t {
+    /// Identifier of the packet, counting from zero the packets that the socket sent since timestamping was enabled.
+    pub id: u32,
+    /// Which of the timestamps below are valid, as a combination of the `DEMI_TX_TIMESTAMP_*` flags.
+    pub flags: u32,
+    /// Time at which the network stack handed the packet to the device, in nanoseconds since the Unix epoch.
+    pub timestamp_ns: u64,
+    /// Time at which the device transmitted the packet, in nanoseconds of the clock of the device.
+    pub hw_timestamp_ns: u64,
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Converts [timestamp] into nanoseconds since the Unix epoch, saturating on times that do not fit.
+pub fn nanos_since_epoch(timestamp: SystemTime) -> u64 {
+    let since_epoch: u128 = timestamp
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_nanos());
+    u64::try_from(since_epoch).unwrap_or(u64::MAX)
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl From<TxTimestamp> for demi_tx_timestamp_t {
+    fn from(timestamp: TxTimestamp) -> Self {
+        let mut flags: u32 = 0;
+        if timestamp.software.is_some() {
+            flags |= DEMI_TX_TIMESTAMP_SOFTWARE;
+        }
+        if timestamp.hardware.is_some() {
+            flags |= DEMI_TX_TIMESTAMP_HARDWARE;
+        }
+        Self {
+            id: timestamp.id,
+            flags,
+            timestamp_ns: timestamp.software.map_or(0, nanos_since_epoch),
+            hw_timestamp_ns: timestamp.hardware.unwrap_or(0),
+        }
+    }
+}
@@ -0,0 +1,22 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+#![allow(non_camel_case_types)]
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Configuration of a TLS session that authenticates both peers with an external pre-shared key.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct demi_tls_config_t {
+    /// Identity of the pre-shared key.
+    pub psk_identity: *const u8,
+    /// Length of the identity of the pre-shared key.
+    pub psk_identity_len: usize,
+    /// Pre-shared key.
+    pub psk: *const u8,
+    /// Length of the pre-shared key.
+    pub psk_len: usize,
+}
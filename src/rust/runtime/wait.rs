@@ -0,0 +1,171 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::runtime::{
+    fail::Fail,
+    network::config::{
+        parse_tunable,
+        unknown_tunable,
+    },
+};
+use ::std::{
+    fmt,
+    str::FromStr,
+    time::Duration,
+};
+
+//======================================================================================================================
+// Constants
+//======================================================================================================================
+
+/// Default time that a hybrid wait polls before it sleeps.
+const DEFAULT_SPIN: Duration = Duration::from_micros(50);
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// What a wait does once no coroutine is ready to run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WaitPolicy {
+    /// The wait keeps polling, which has the lowest latency but takes a whole core.
+    BusyPoll,
+    /// The wait keeps polling for a while, and then sleeps.
+    Hybrid,
+    /// The wait sleeps right away.
+    Block,
+}
+
+/// Wait Configuration Descriptor
+#[derive(Clone, Debug)]
+pub struct WaitConfig {
+    /// What to do when Idle
+    policy: WaitPolicy,
+    /// How Long a Hybrid Wait Polls before Sleeping
+    spin: Duration,
+}
+
+//======================================================================================================================
+// Associate Functions
+//======================================================================================================================
+
+/// Associate functions for Wait Configuration Descriptor
+impl WaitConfig {
+    /// Gets what to do when idle in the target [WaitConfig].
+    pub fn get_policy(&self) -> WaitPolicy {
+        self.policy
+    }
+
+    /// Checks whether a wait that has been idle for [idle] may sleep.
+    pub fn may_sleep(&self, idle: Duration) -> bool {
+        match self.policy {
+            WaitPolicy::BusyPoll => false,
+            WaitPolicy::Hybrid => idle >= self.spin,
+            WaitPolicy::Block => true,
+        }
+    }
+
+    /// Sets the tunable [name] to [value] in the target [WaitConfig]. Changes apply to the next wait.
+    pub fn set_tunable(&mut self, name: &str, value: &str) -> Result<(), Fail> {
+        match name {
+            "policy" => {
+                self.policy = parse_tunable::<WaitPolicy>(name, value)?;
+                Ok(())
+            },
+            "spin_micros" => {
+                self.spin = Duration::from_micros(parse_tunable::<u64>(name, value)?);
+                Ok(())
+            },
+            _ => unknown_tunable(name),
+        }
+    }
+
+    /// Gets the value of the tunable [name] in the target [WaitConfig].
+    pub fn get_tunable(&self, name: &str) -> Result<String, Fail> {
+        match name {
+            "policy" => Ok(self.policy.to_string()),
+            "spin_micros" => Ok(self.spin.as_micros().to_string()),
+            _ => unknown_tunable(name),
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+/// Default Trait Implementation for Wait Configuration Descriptor
+impl Default for WaitConfig {
+    /// Creates a Wait Configuration Descriptor with the default values.
+    fn default() -> Self {
+        WaitConfig {
+            policy: WaitPolicy::Block,
+            spin: DEFAULT_SPIN,
+        }
+    }
+}
+
+impl FromStr for WaitPolicy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "busy_poll" => Ok(WaitPolicy::BusyPoll),
+            "hybrid" => Ok(WaitPolicy::Hybrid),
+            "block" => Ok(WaitPolicy::Block),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for WaitPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WaitPolicy::BusyPoll => write!(f, "busy_poll"),
+            WaitPolicy::Hybrid => write!(f, "hybrid"),
+            WaitPolicy::Block => write!(f, "block"),
+        }
+    }
+}
+
+//======================================================================================================================
+// Unit Tests
+//======================================================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::wait::{
+        WaitConfig,
+        WaitPolicy,
+    };
+    use ::anyhow::Result;
+    use ::std::time::Duration;
+
+    /// Tests that tunables of [WaitConfig] can be changed at runtime and are validated.
+    #[test]
+    fn test_wait_config_tunables() -> Result<()> {
+        let mut config: WaitConfig = WaitConfig::default();
+        crate::ensure_eq!(config.get_policy(), WaitPolicy::Block);
+        crate::ensure_eq!(config.may_sleep(Duration::ZERO), true);
+
+        config.set_tunable("policy", "busy_poll")?;
+        crate::ensure_eq!(config.may_sleep(Duration::from_secs(1)), false);
+
+        config.set_tunable("policy", "hybrid")?;
+        config.set_tunable("spin_micros", "100")?;
+        crate::ensure_eq!(config.get_tunable("spin_micros")?, "100".to_string());
+        crate::ensure_eq!(config.may_sleep(Duration::from_micros(99)), false);
+        crate::ensure_eq!(config.may_sleep(Duration::from_micros(100)), true);
+
+        crate::ensure_eq!(config.set_tunable("policy", "spin").unwrap_err().errno, libc::EINVAL);
+        crate::ensure_eq!(config.set_tunable("spin_micros", "-1").unwrap_err().errno, libc::EINVAL);
+        crate::ensure_eq!(config.set_tunable("spin", "1").unwrap_err().errno, libc::ENOENT);
+        crate::ensure_eq!(config.get_tunable("policy")?, "hybrid".to_string());
+
+        Ok(())
+    }
+}
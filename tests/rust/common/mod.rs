@@ -27,7 +27,7 @@ use ::std::{
 };
 
 // Alice Address
-pub const ALICE_IPV4: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 1);
+pub const ALICE_IPV4: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 1);
 pub const ALICE_IP: IpAddr = IpAddr::V4(ALICE_IPV4);
 pub const ALICE_MAC: MacAddress = MacAddress::new([0x12, 0x23, 0x45, 0x67, 0x89, 0xab]);
 
@@ -53,7 +53,7 @@ impl PipeClient {
 
             // Wait for operation to complete or timeout.
             match self.libos.wait(qt, Some(Duration::from_secs(1))) {
-                Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_PUSH && qr.qr_ret == 0 => {},
+                Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_PUSH => {},
                 Err(e) if e.errno == libc::ETIMEDOUT => break,
                 Ok(_) => {
                     anyhow::bail!("wait() should not complete successfully with an opcode other than DEMI_OPC_PUSH")
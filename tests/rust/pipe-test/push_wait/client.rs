@@ -65,7 +65,7 @@ impl PipeClient {
         // Poll once to ensure that the co-routine runs.
         match self.libos.wait(qt, Some(Duration::from_micros(0))) {
             Err(e) if e.errno == libc::ETIMEDOUT => {},
-            Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_PUSH && qr.qr_ret == 0 => push_completed = true,
+            Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_PUSH => push_completed = true,
             Ok(_) => anyhow::bail!("wait() should not complete successfully with an opcode other than DEMI_OPC_PUSH"),
             Err(e) => anyhow::bail!("wait() should not fail wth error other than ETIMEDOUT (error={:?})", e),
         }
@@ -86,7 +86,7 @@ impl PipeClient {
                 Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_FAILED && qr.qr_ret == libc::ECANCELED as i64 => {
                     Ok(())
                 },
-                Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_PUSH && qr.qr_ret == 0 => Ok(()),
+                Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_PUSH => Ok(()),
                 Ok(_) => anyhow::bail!("wait() complete successfully or fail with ECANCELED"),
                 Err(e) => anyhow::bail!("wait() should not fail (error={:?})", e),
             }
@@ -112,7 +112,7 @@ impl PipeClient {
         // Poll once to ensure that the co-routine runs.
         match self.libos.wait(qt, Some(Duration::from_micros(0))) {
             Err(e) if e.errno == libc::ETIMEDOUT => {},
-            Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_PUSH && qr.qr_ret == 0 => push_completed = true,
+            Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_PUSH => push_completed = true,
             Ok(_) => anyhow::bail!("wait() should not complete successfully with an opcode other than DEMI_OPC_PUSH"),
             Err(e) => anyhow::bail!("wait() should not fail wth error other than ETIMEDOUT (error={:?})", e),
         }
@@ -136,7 +136,7 @@ impl PipeClient {
             match self.libos.wait(qt, None) {
                 Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_FAILED && qr.qr_ret == libc::EBADF as i64 => {},
                 Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_FAILED && qr.qr_ret == libc::ECANCELED as i64 => {},
-                Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_PUSH && qr.qr_ret == 0 => {},
+                Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_PUSH => {},
                 Ok(_) => anyhow::bail!("wait() should complete successfully or fail with ECANCELED"),
                 Err(e) => anyhow::bail!("wait() should not fail (error={:?})", e),
             }
@@ -151,7 +151,7 @@ impl PipeClient {
 
         // Wait for operation to complete.
         match self.libos.wait(qt, None) {
-            Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_PUSH && qr.qr_ret == 0 => Ok(()),
+            Ok(qr) if qr.qr_opcode == demi_opcode_t::DEMI_OPC_PUSH => Ok(()),
             Ok(_) => anyhow::bail!("wait() should not complete successfully with an opcode other than DEMI_OPC_PUSH"),
             Err(e) => anyhow::bail!("wait() should not fail (error={:?})", e),
         }
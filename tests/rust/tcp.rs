@@ -33,6 +33,10 @@ mod test {
                 DemiBuffer,
                 MemoryRuntime,
             },
+            network::{
+                framing::http::HttpMessage,
+                tls::TlsConfig,
+            },
             OperationResult,
             QDesc,
             QToken,
@@ -328,7 +332,7 @@ mod test {
             let qt: QToken = safe_pop(&mut libos, qd)?;
             let (qd, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
             match qr {
-                OperationResult::Pop(_, _) => (),
+                OperationResult::Pop(_, _, _) => (),
                 _ => {
                     // Close socket on error.
                     // FIXME: https://github.com/demikernel/demikernel/issues/633
@@ -370,7 +374,7 @@ mod test {
             let qt: QToken = safe_push(&mut libos, sockqd, buf)?;
             let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
             match qr {
-                OperationResult::Push => (),
+                OperationResult::Push(_) => (),
                 _ => {
                     // Close socket on error.
                     // FIXME: https://github.com/demikernel/demikernel/issues/633
@@ -392,6 +396,220 @@ mod test {
         Ok(())
     }
 
+    /// Tests that data pushed to a TLS session on one side pops out in the clear on the other side.
+    #[test]
+    fn tcp_push_remote_tls() -> Result<()> {
+        let (alice_tx, alice_rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+        let (bob_tx, bob_rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+
+        let bob_barrier: Arc<Barrier> = Arc::new(Barrier::new(2));
+        let alice_barrier: Arc<Barrier> = bob_barrier.clone();
+
+        let alice: JoinHandle<Result<()>> = thread::spawn(move || {
+            let mut libos: DummyLibOS = match DummyLibOS::new(ALICE_MAC, ALICE_IPV4, alice_tx, bob_rx, arp()) {
+                Ok(libos) => libos,
+                Err(e) => anyhow::bail!("Could not create inetstack: {:?}", e),
+            };
+
+            let port: u16 = PORT_BASE;
+            let local: SocketAddr = SocketAddr::new(ALICE_IP, port);
+
+            // Open connection.
+            let sockqd: QDesc = safe_socket(&mut libos)?;
+            safe_bind(&mut libos, sockqd, local)?;
+            safe_listen(&mut libos, sockqd)?;
+            let qt: QToken = safe_accept(&mut libos, sockqd)?;
+            let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
+            let qd: QDesc = match qr {
+                OperationResult::Accept((qd, addr)) if addr.ip() == &BOB_IPV4 => qd,
+                _ => anyhow::bail!("accept() has failed"),
+            };
+            let qd: QDesc = libos.tls_accept(qd, TlsConfig::new(b"bob", &[7; 32])?)?;
+
+            // Pop data, which runs the handshake first.
+            let qt: QToken = safe_pop(&mut libos, qd)?;
+            let (qd, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
+            match qr {
+                OperationResult::Pop(_, buf, _) if buf.len() == 32 && buf[..].iter().all(|byte| *byte == b'a') => (),
+                _ => anyhow::bail!("pop() has has failed {:?}", qr),
+            }
+
+            // Close connection.
+            safe_close_active(&mut libos, qd)?;
+            safe_close_passive(&mut libos, sockqd)?;
+            alice_barrier.wait();
+            Ok(())
+        });
+
+        let bob: JoinHandle<Result<()>> = thread::spawn(move || {
+            let mut libos: DummyLibOS = match DummyLibOS::new(BOB_MAC, BOB_IPV4, bob_tx, alice_rx, arp()) {
+                Ok(libos) => libos,
+                Err(e) => anyhow::bail!("Could not create inetstack: {:?}", e),
+            };
+
+            let port: u16 = PORT_BASE;
+            let remote: SocketAddr = SocketAddr::new(ALICE_IP, port);
+
+            // Open connection.
+            let sockqd: QDesc = safe_socket(&mut libos)?;
+            let qt: QToken = safe_connect(&mut libos, sockqd, remote)?;
+            let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
+            match qr {
+                OperationResult::Connect => (),
+                _ => anyhow::bail!("connect() has failed"),
+            }
+            let qd: QDesc = libos.tls_connect(sockqd, TlsConfig::new(b"bob", &[7; 32])?)?;
+
+            // The socket is now only reachable through the TLS session.
+            let buf = libos.cook_data(32)?;
+            match libos.push(sockqd, &buf, None) {
+                Err(e) if e.errno == libc::EBADF => (),
+                _ => anyhow::bail!("push() to the socket of a TLS session should have failed"),
+            }
+
+            // Push data, which runs the handshake first.
+            let qt: QToken = safe_push(&mut libos, qd, buf)?;
+            let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
+            match qr {
+                OperationResult::Push(_) => (),
+                _ => anyhow::bail!("push() has failed"),
+            }
+
+            // Close connection.
+            safe_close_active(&mut libos, qd)?;
+            bob_barrier.wait();
+
+            Ok(())
+        });
+        // It is safe to use unwrap here because there should not be any reason that we can't join the thread and if there
+        // is, there is nothing to clean up here on the main thread.
+        alice.join().unwrap()?;
+        bob.join().unwrap()?;
+
+        Ok(())
+    }
+
+    /// Tests that HTTP queues pop whole messages, whatever their transfer coding, and that pushes must carry one.
+    #[test]
+    fn tcp_push_remote_http() -> Result<()> {
+        let (alice_tx, alice_rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+        let (bob_tx, bob_rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+
+        let bob_barrier: Arc<Barrier> = Arc::new(Barrier::new(2));
+        let alice_barrier: Arc<Barrier> = bob_barrier.clone();
+
+        let alice: JoinHandle<Result<()>> = thread::spawn(move || {
+            let mut libos: DummyLibOS = match DummyLibOS::new(ALICE_MAC, ALICE_IPV4, alice_tx, bob_rx, arp()) {
+                Ok(libos) => libos,
+                Err(e) => anyhow::bail!("Could not create inetstack: {:?}", e),
+            };
+
+            let port: u16 = PORT_BASE;
+            let local: SocketAddr = SocketAddr::new(ALICE_IP, port);
+
+            // Open connection.
+            let sockqd: QDesc = safe_socket(&mut libos)?;
+            safe_bind(&mut libos, sockqd, local)?;
+            safe_listen(&mut libos, sockqd)?;
+            let qt: QToken = safe_accept(&mut libos, sockqd)?;
+            let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
+            let qd: QDesc = match qr {
+                OperationResult::Accept((qd, addr)) if addr.ip() == &BOB_IPV4 => qd,
+                _ => anyhow::bail!("accept() has failed"),
+            };
+            let qd: QDesc = libos.http_accept(qd)?;
+
+            // Pop the request, whose chunked body is decoded.
+            let qt: QToken = safe_pop(&mut libos, qd)?;
+            let (qd, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
+            let request: HttpMessage = match qr {
+                OperationResult::Pop(_, buf, _) => HttpMessage::parse(buf)?,
+                _ => anyhow::bail!("pop() has has failed {:?}", qr),
+            };
+            if request.head.header("Host") != Some("alice") || &request.body[..] != b"hello world" {
+                anyhow::bail!("pop() returned the wrong request");
+            }
+
+            // Push the response.
+            let buf: DemiBuffer = DemiBuffer::from_slice(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")?;
+            let sga: demi_sgarray_t = libos.get_transport().into_sgarray(buf)?;
+            let qt: QToken = safe_push(&mut libos, qd, sga)?;
+            let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
+            match qr {
+                OperationResult::Push(_) => (),
+                _ => anyhow::bail!("push() has failed"),
+            }
+
+            // Close connection.
+            safe_close_active(&mut libos, qd)?;
+            safe_close_passive(&mut libos, sockqd)?;
+            alice_barrier.wait();
+            Ok(())
+        });
+
+        let bob: JoinHandle<Result<()>> = thread::spawn(move || {
+            let mut libos: DummyLibOS = match DummyLibOS::new(BOB_MAC, BOB_IPV4, bob_tx, alice_rx, arp()) {
+                Ok(libos) => libos,
+                Err(e) => anyhow::bail!("Could not create inetstack: {:?}", e),
+            };
+
+            let port: u16 = PORT_BASE;
+            let remote: SocketAddr = SocketAddr::new(ALICE_IP, port);
+
+            // Open connection.
+            let sockqd: QDesc = safe_socket(&mut libos)?;
+            let qt: QToken = safe_connect(&mut libos, sockqd, remote)?;
+            let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
+            match qr {
+                OperationResult::Connect => (),
+                _ => anyhow::bail!("connect() has failed"),
+            }
+            let qd: QDesc = libos.http_connect(sockqd)?;
+
+            // Pushes that do not carry a whole request fail.
+            let buf: DemiBuffer = DemiBuffer::from_slice(b"GET / HTTP/1.1\r\n")?;
+            let sga: demi_sgarray_t = libos.get_transport().into_sgarray(buf)?;
+            let qt: QToken = safe_push(&mut libos, qd, sga)?;
+            let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
+            match qr {
+                OperationResult::Failed(e) if e.errno == libc::EINVAL => (),
+                _ => anyhow::bail!("push() of a partial request should have failed"),
+            }
+
+            // Push the request.
+            let buf: DemiBuffer = DemiBuffer::from_slice(
+                b"POST / HTTP/1.1\r\nHost: alice\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n",
+            )?;
+            let sga: demi_sgarray_t = libos.get_transport().into_sgarray(buf)?;
+            let qt: QToken = safe_push(&mut libos, qd, sga)?;
+            let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
+            match qr {
+                OperationResult::Push(_) => (),
+                _ => anyhow::bail!("push() has failed"),
+            }
+
+            // Pop the response.
+            let qt: QToken = safe_pop(&mut libos, qd)?;
+            let (qd, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
+            match qr {
+                OperationResult::Pop(_, buf, _) if &buf[..] == b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok" => (),
+                _ => anyhow::bail!("pop() has has failed {:?}", qr),
+            }
+
+            // Close connection.
+            safe_close_active(&mut libos, qd)?;
+            bob_barrier.wait();
+
+            Ok(())
+        });
+        // It is safe to use unwrap here because there should not be any reason that we can't join the thread and if there
+        // is, there is nothing to clean up here on the main thread.
+        alice.join().unwrap()?;
+        bob.join().unwrap()?;
+
+        Ok(())
+    }
+
     //======================================================================================================================
     // Bad Socket
     //======================================================================================================================
@@ -638,7 +856,7 @@ mod test {
         };
 
         // Invalid queue descriptor.
-        match libos.accept(QDesc::from(0)) {
+        match libos.accept(QDesc::from(0), None) {
             Err(e) if e.errno == libc::EBADF => (),
             _ => {
                 // Close socket if we somehow got a socket back?
@@ -703,7 +921,7 @@ mod test {
             let remote: SocketAddr = SocketAddr::new(ALICE_IP, port);
 
             // Bad queue descriptor.
-            match libos.connect(QDesc::from(0), remote) {
+            match libos.connect(QDesc::from(0), remote, None) {
                 Err(e) if e.errno == libc::EBADF => (),
                 _ => {
                     // Close socket if not error because this test cannot continue.
@@ -903,7 +1121,7 @@ mod test {
             let qt: QToken = safe_pop(&mut libos, qd)?;
             let (qd, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
             match qr {
-                OperationResult::Pop(_, _) => (),
+                OperationResult::Pop(_, _, _) => (),
                 _ => {
                     // Close socket if error.
                     // FIXME: https://github.com/demikernel/demikernel/issues/633
@@ -943,7 +1161,7 @@ mod test {
 
             // Cook some data and push to a bad socket.
             let bytes = libos.cook_data(32)?;
-            match libos.push(QDesc::from(2), &bytes) {
+            match libos.push(QDesc::from(2), &bytes, None) {
                 Ok(_) => {
                     // Close socket if not error because this test cannot continue.
                     // FIXME: https://github.com/demikernel/demikernel/issues/633
@@ -960,7 +1178,7 @@ mod test {
             };
             let data: demi_sgarray_t = libos.get_transport().into_sgarray(buf)?;
 
-            match libos.push(sockqd, &data) {
+            match libos.push(sockqd, &data, None) {
                 Ok(_) =>
                 // Close socket if not error because this test cannot continue.
                 // FIXME: https://github.com/demikernel/demikernel/issues/633
@@ -975,7 +1193,7 @@ mod test {
             let qt: QToken = safe_push(&mut libos, sockqd, bytes)?;
             let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
             match qr {
-                OperationResult::Push => (),
+                OperationResult::Push(_) => (),
                 _ => {
                     // Close socket if error.
                     // FIXME: https://github.com/demikernel/demikernel/issues/633
@@ -1035,7 +1253,7 @@ mod test {
             };
 
             // Pop from bad socket.
-            match libos.pop(QDesc::from(2), None) {
+            match libos.pop(QDesc::from(2), None, None) {
                 Ok(_) => {
                     // Close socket if not error.
                     // FIXME: https://github.com/demikernel/demikernel/issues/633
@@ -1048,7 +1266,7 @@ mod test {
             let qt: QToken = safe_pop(&mut libos, qd)?;
             let (qd, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
             match qr {
-                OperationResult::Pop(_, _) => (),
+                OperationResult::Pop(_, _, _) => (),
                 _ => {
                     // Close socket if error.
                     // FIXME: https://github.com/demikernel/demikernel/issues/633
@@ -1091,7 +1309,7 @@ mod test {
             let qt: QToken = safe_push(&mut libos, sockqd, bytes)?;
             let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
             match qr {
-                OperationResult::Push => (),
+                OperationResult::Push(_) => (),
                 _ => {
                     // Close socket if error.
                     // FIXME: https://github.com/demikernel/demikernel/issues/633
@@ -1114,6 +1332,210 @@ mod test {
         Ok(())
     }
 
+    //======================================================================================================================
+    // Shutdown
+    //======================================================================================================================
+
+    /// Tests that shutting down aborts connections that do not close within the grace period.
+    #[test]
+    fn tcp_shutdown_all_aborts() -> Result<()> {
+        let (tx, rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+        let mut libos: DummyLibOS = DummyLibOS::new(ALICE_MAC, ALICE_IPV4, tx, rx.clone(), arp())?;
+
+        // Nobody answers this connection.
+        let sockqd: QDesc = safe_socket(&mut libos)?;
+        safe_bind(&mut libos, sockqd, SocketAddr::new(ALICE_IP, PORT_BASE))?;
+        safe_listen(&mut libos, sockqd)?;
+        let qd: QDesc = safe_socket(&mut libos)?;
+        safe_connect(&mut libos, qd, SocketAddr::new(BOB_IP, PORT_BASE))?;
+
+        libos.shutdown_all(Some(BAD_WAIT_TIMEOUT))?;
+        for qd in [sockqd, qd] {
+            match libos.async_close(qd) {
+                Err(e) if e.errno == libc::EBADF => (),
+                _ => anyhow::bail!("queues should be released by shutdown_all()"),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Tests that shutting down closes established connections gracefully.
+    #[test]
+    fn tcp_shutdown_all_drains() -> Result<()> {
+        let (alice_tx, alice_rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+        let (bob_tx, bob_rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+
+        let bob_barrier: Arc<Barrier> = Arc::new(Barrier::new(2));
+        let alice_barrier: Arc<Barrier> = bob_barrier.clone();
+
+        let alice: JoinHandle<Result<()>> = thread::spawn(move || {
+            let mut libos: DummyLibOS = match DummyLibOS::new(ALICE_MAC, ALICE_IPV4, alice_tx, bob_rx, arp()) {
+                Ok(libos) => libos,
+                Err(e) => anyhow::bail!("Could not create inetstack: {:?}", e),
+            };
+
+            // Accept a connection and shut down right away.
+            let sockqd: QDesc = safe_socket(&mut libos)?;
+            safe_bind(&mut libos, sockqd, SocketAddr::new(ALICE_IP, PORT_BASE))?;
+            safe_listen(&mut libos, sockqd)?;
+            let qt: QToken = safe_accept(&mut libos, sockqd)?;
+            let qd: QDesc = match safe_wait(&mut libos, qt)? {
+                (_, OperationResult::Accept((qd, addr))) if addr.ip() == &BOB_IPV4 => qd,
+                _ => anyhow::bail!("accept() has failed"),
+            };
+            libos.shutdown_all(Some(Duration::from_secs(1)))?;
+            if libos.async_close(qd).is_ok() {
+                anyhow::bail!("connection should be released by shutdown_all()");
+            }
+
+            alice_barrier.wait();
+            Ok(())
+        });
+
+        let bob: JoinHandle<Result<()>> = thread::spawn(move || {
+            let mut libos: DummyLibOS = match DummyLibOS::new(BOB_MAC, BOB_IPV4, bob_tx, alice_rx, arp()) {
+                Ok(libos) => libos,
+                Err(e) => anyhow::bail!("Could not create inetstack: {:?}", e),
+            };
+
+            // Connect and wait for the other side to close.
+            let sockqd: QDesc = safe_socket(&mut libos)?;
+            let qt: QToken = safe_connect(&mut libos, sockqd, SocketAddr::new(ALICE_IP, PORT_BASE))?;
+            match safe_wait(&mut libos, qt)? {
+                (_, OperationResult::Connect) => (),
+                _ => anyhow::bail!("connect() has failed"),
+            }
+            let qt: QToken = safe_pop(&mut libos, sockqd)?;
+            match safe_wait(&mut libos, qt)? {
+                (_, OperationResult::Pop(_, buf, _)) if buf.len() == 0 => (),
+                (_, qr) => anyhow::bail!("pop() should see the end of the connection: {:?}", qr),
+            }
+            match safe_close_active(&mut libos, sockqd)? {
+                (_, OperationResult::Close) => (),
+                (_, qr) => anyhow::bail!("close() has failed: {:?}", qr),
+            }
+
+            bob_barrier.wait();
+            Ok(())
+        });
+
+        alice.join().unwrap()?;
+        bob.join().unwrap()?;
+
+        Ok(())
+    }
+
+    /// Tests that a connection which is frozen in one instance of the stack carries on once thawed in another one.
+    #[test]
+    fn tcp_freeze_thaw() -> Result<()> {
+        let (alice_tx, alice_rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+        let (bob_tx, bob_rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+
+        let bob_barrier: Arc<Barrier> = Arc::new(Barrier::new(2));
+        let alice_barrier: Arc<Barrier> = bob_barrier.clone();
+
+        let alice: JoinHandle<Result<()>> = thread::spawn(move || {
+            let mut libos: DummyLibOS =
+                match DummyLibOS::new(ALICE_MAC, ALICE_IPV4, alice_tx.clone(), bob_rx.clone(), arp()) {
+                    Ok(libos) => libos,
+                    Err(e) => anyhow::bail!("Could not create inetstack: {:?}", e),
+                };
+
+            // Accept a connection and pop the first message.
+            let sockqd: QDesc = safe_socket(&mut libos)?;
+            safe_bind(&mut libos, sockqd, SocketAddr::new(ALICE_IP, PORT_BASE))?;
+            safe_listen(&mut libos, sockqd)?;
+            let qt: QToken = safe_accept(&mut libos, sockqd)?;
+            let qd: QDesc = match safe_wait(&mut libos, qt)? {
+                (_, OperationResult::Accept((qd, addr))) if addr.ip() == &BOB_IPV4 => qd,
+                _ => anyhow::bail!("accept() has failed"),
+            };
+            let qt: QToken = safe_pop(&mut libos, qd)?;
+            match safe_wait(&mut libos, qt)? {
+                (_, OperationResult::Pop(_, _, _)) => (),
+                (_, qr) => anyhow::bail!("pop() has failed: {:?}", qr),
+            }
+
+            // Freeze the connection and thaw it in a new instance of the stack, as if it restarted.
+            let blob: Vec<u8> = match libos.freeze_queue(qd) {
+                Ok(blob) => blob,
+                Err(e) => anyhow::bail!("freeze_queue() should succeed: {:?}", e),
+            };
+            if libos.async_close(qd).is_ok() {
+                anyhow::bail!("frozen connection should be released");
+            }
+            drop(libos);
+            let mut libos: DummyLibOS = match DummyLibOS::new(ALICE_MAC, ALICE_IPV4, alice_tx, bob_rx, arp()) {
+                Ok(libos) => libos,
+                Err(e) => anyhow::bail!("Could not create inetstack: {:?}", e),
+            };
+            let qd: QDesc = match libos.thaw_queue(&blob) {
+                Ok(qd) => qd,
+                Err(e) => anyhow::bail!("thaw_queue() should succeed: {:?}", e),
+            };
+            alice_barrier.wait();
+
+            // Data flows both ways on the thawed connection.
+            let qt: QToken = safe_pop(&mut libos, qd)?;
+            match safe_wait(&mut libos, qt)? {
+                (_, OperationResult::Pop(_, buf, _)) if buf.len() > 0 => (),
+                (_, qr) => anyhow::bail!("pop() on thawed connection has failed: {:?}", qr),
+            }
+            let buf: demi_sgarray_t = libos.cook_data(32)?;
+            let qt: QToken = safe_push(&mut libos, qd, buf)?;
+            match safe_wait(&mut libos, qt)? {
+                (_, OperationResult::Push(_)) => (),
+                (_, qr) => anyhow::bail!("push() on thawed connection has failed: {:?}", qr),
+            }
+
+            safe_close_active(&mut libos, qd)?;
+            alice_barrier.wait();
+            Ok(())
+        });
+
+        let bob: JoinHandle<Result<()>> = thread::spawn(move || {
+            let mut libos: DummyLibOS = match DummyLibOS::new(BOB_MAC, BOB_IPV4, bob_tx, alice_rx, arp()) {
+                Ok(libos) => libos,
+                Err(e) => anyhow::bail!("Could not create inetstack: {:?}", e),
+            };
+
+            let sockqd: QDesc = safe_socket(&mut libos)?;
+            let qt: QToken = safe_connect(&mut libos, sockqd, SocketAddr::new(ALICE_IP, PORT_BASE))?;
+            match safe_wait(&mut libos, qt)? {
+                (_, OperationResult::Connect) => (),
+                _ => anyhow::bail!("connect() has failed"),
+            }
+
+            // Push one message before the connection is frozen on the other side, and one after it is thawed.
+            for round in 0..2 {
+                if round == 1 {
+                    bob_barrier.wait();
+                }
+                let buf: demi_sgarray_t = libos.cook_data(32)?;
+                let qt: QToken = safe_push(&mut libos, sockqd, buf)?;
+                match safe_wait(&mut libos, qt)? {
+                    (_, OperationResult::Push(_)) => (),
+                    (_, qr) => anyhow::bail!("push() has failed: {:?}", qr),
+                }
+            }
+            let qt: QToken = safe_pop(&mut libos, sockqd)?;
+            match safe_wait(&mut libos, qt)? {
+                (_, OperationResult::Pop(_, buf, _)) if buf.len() > 0 => (),
+                (_, qr) => anyhow::bail!("pop() has failed: {:?}", qr),
+            }
+
+            safe_close_active(&mut libos, sockqd)?;
+            bob_barrier.wait();
+            Ok(())
+        });
+
+        alice.join().unwrap()?;
+        bob.join().unwrap()?;
+
+        Ok(())
+    }
+
     //======================================================================================================================
     // Standalone Functions
     //======================================================================================================================
@@ -1128,7 +1550,7 @@ mod test {
 
     /// Safe call to `connect()`.
     fn safe_connect(libos: &mut DummyLibOS, sockqd: QDesc, remote: SocketAddr) -> Result<QToken> {
-        match libos.connect(sockqd, remote) {
+        match libos.connect(sockqd, remote, None) {
             Ok(qt) => Ok(qt),
             Err(e) => {
                 // Close socket on error.
@@ -1164,7 +1586,7 @@ mod test {
 
     /// Safe call to `accept()`.
     fn safe_accept(libos: &mut DummyLibOS, sockqd: QDesc) -> Result<QToken> {
-        match libos.accept(sockqd) {
+        match libos.accept(sockqd, None) {
             Ok(qt) => Ok(qt),
             Err(e) => {
                 // Close socket on error.
@@ -1176,7 +1598,7 @@ mod test {
 
     /// Safe call to `pop()`.
     fn safe_pop(libos: &mut DummyLibOS, qd: QDesc) -> Result<QToken> {
-        match libos.pop(qd, None) {
+        match libos.pop(qd, None, None) {
             Ok(qt) => Ok(qt),
             Err(e) => {
                 // Close socket on error.
@@ -1188,7 +1610,7 @@ mod test {
 
     /// Safe call to `push()`
     fn safe_push(libos: &mut DummyLibOS, sockqd: QDesc, bytes: demi_sgarray_t) -> Result<QToken> {
-        match libos.push(sockqd, &bytes) {
+        match libos.push(sockqd, &bytes, None) {
             Ok(qt) => Ok(qt),
             Err(e) => {
                 // Close socket on error.
@@ -32,6 +32,10 @@ mod test {
             DemiBuffer,
             MemoryRuntime,
         },
+        network::{
+            quic::QuicConfig,
+            tls::TlsConfig,
+        },
         OperationResult,
         QDesc,
         QToken,
@@ -238,7 +242,7 @@ mod test {
 
             // Cook some data and push.
             let bytes = libos.cook_data(32)?;
-            let qt: QToken = match libos.pushto(sockfd, &bytes, bob_addr) {
+            let qt: QToken = match libos.pushto(sockfd, &bytes, bob_addr, None) {
                 Ok(qt) => qt,
                 Err(e) => {
                     // Close socket on error.
@@ -248,7 +252,7 @@ mod test {
             };
             let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
             match qr {
-                OperationResult::Push => (),
+                OperationResult::Push(_) => (),
                 _ => {
                     // Close socket on error.
                     // FIXME: https://github.com/demikernel/demikernel/issues/633
@@ -257,7 +261,7 @@ mod test {
             }
 
             // Pop data.
-            let qt: QToken = match libos.pop(sockfd, None) {
+            let qt: QToken = match libos.pop(sockfd, None, None) {
                 Ok(qt) => qt,
                 Err(e) => {
                     // Close socket on error.
@@ -267,7 +271,7 @@ mod test {
             };
             let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
             match qr {
-                OperationResult::Pop(_, _) => (),
+                OperationResult::Pop(_, _, _) => (),
                 _ => {
                     // Close socket on error.
                     // FIXME: https://github.com/demikernel/demikernel/issues/633
@@ -307,7 +311,7 @@ mod test {
             };
 
             // Pop data.
-            let qt: QToken = match libos.pop(sockfd, None) {
+            let qt: QToken = match libos.pop(sockfd, None, None) {
                 Ok(qt) => qt,
                 Err(e) => {
                     // Close socket on error.
@@ -317,7 +321,7 @@ mod test {
             };
             let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
             let bytes: DemiBuffer = match qr {
-                OperationResult::Pop(_, bytes) => bytes,
+                OperationResult::Pop(_, bytes, _) => bytes,
                 _ => {
                     // Close socket on error.
                     // FIXME: https://github.com/demikernel/demikernel/issues/633
@@ -327,7 +331,7 @@ mod test {
 
             // Push data.
             let buf = libos.get_transport().into_sgarray(bytes)?;
-            let qt: QToken = match libos.pushto(sockfd, &buf, alice_addr) {
+            let qt: QToken = match libos.pushto(sockfd, &buf, alice_addr, None) {
                 Ok(qt) => qt,
                 Err(e) => {
                     // Close socket on error.
@@ -337,7 +341,7 @@ mod test {
             };
             let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
             match qr {
-                OperationResult::Push => (),
+                OperationResult::Push(_) => (),
                 _ => {
                     // Close socket on error.
                     // FIXME: https://github.com/demikernel/demikernel/issues/633
@@ -364,19 +368,172 @@ mod test {
         Ok(())
     }
 
-    /// Tests if data can be successfully pushed/popped in loopback mode.
+    /// Tests if data can be successfully pushed/popped in loopback mode. Datagrams toward the address of the stack are
+    /// looped back within it, so nothing reaches the wire.
     #[test]
     fn udp_loopback() -> Result<()> {
+        let (tx, rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+        let (_, wire): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+        let mut libos: DummyLibOS = match DummyLibOS::new(ALICE_MAC, ALICE_IPV4, tx, wire, arp()) {
+            Ok(libos) => libos,
+            Err(e) => anyhow::bail!("Could not create inetstack: {:?}", e),
+        };
+
+        let port: u16 = PORT_BASE;
+        let local: SocketAddr = SocketAddr::new(ALICE_IP, port);
+
+        // Open connection.
+        let sockfd: QDesc = match libos.socket(Domain::IPV4, Type::DGRAM, Protocol::UDP) {
+            Ok(qd) => qd,
+            Err(e) => anyhow::bail!("failed to create socket: {:?}", e),
+        };
+        match libos.bind(sockfd, local) {
+            Ok(_) => (),
+            Err(e) => {
+                // Close socket on error.
+                // FIXME: https://github.com/demikernel/demikernel/issues/633
+                anyhow::bail!("bind() failed: {:?}", e)
+            },
+        };
+
+        // Cook some data and push it to ourselves.
+        let bytes = libos.cook_data(32)?;
+        let qt: QToken = match libos.pushto(sockfd, &bytes, local, None) {
+            Ok(qt) => qt,
+            Err(e) => {
+                // Close socket on error.
+                // FIXME: https://github.com/demikernel/demikernel/issues/633
+                anyhow::bail!("push() failed: {:?}", e)
+            },
+        };
+        let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
+        match qr {
+            OperationResult::Push(_) => (),
+            _ => {
+                // Close socket on error.
+                // FIXME: https://github.com/demikernel/demikernel/issues/633
+                anyhow::bail!("wait on push() failed")
+            },
+        }
+
+        // Pop data.
+        let qt: QToken = match libos.pop(sockfd, None, None) {
+            Ok(qt) => qt,
+            Err(e) => {
+                // Close socket on error.
+                // FIXME: https://github.com/demikernel/demikernel/issues/633
+                anyhow::bail!("pop() failed: {:?}", e)
+            },
+        };
+        let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
+        match qr {
+            OperationResult::Pop(Some(addr), bytes, _) if SocketAddr::V4(addr) == local && bytes.len() == 32 => (),
+            _ => {
+                // Close socket on error.
+                // FIXME: https://github.com/demikernel/demikernel/issues/633
+                anyhow::bail!("wait on pop() failed")
+            },
+        }
+        if !rx.is_empty() {
+            anyhow::bail!("looped back datagram reached the wire")
+        }
+
+        // Close connection.
+        match libos.async_close(sockfd) {
+            Ok(qt) => {
+                safe_wait(&mut libos, qt)?;
+                Ok(())
+            },
+            Err(e) => anyhow::bail!("close() failed: {:?}", e),
+        }
+    }
+
+    /// Tests if a batched pop completes with every datagram that was already received, and only with those.
+    #[test]
+    fn udp_pop_many_loopback() -> Result<()> {
+        let (tx, _): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+        let (_, wire): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+        let mut libos: DummyLibOS = match DummyLibOS::new(ALICE_MAC, ALICE_IPV4, tx, wire, arp()) {
+            Ok(libos) => libos,
+            Err(e) => anyhow::bail!("Could not create inetstack: {:?}", e),
+        };
+
+        let local: SocketAddr = SocketAddr::new(ALICE_IP, PORT_BASE);
+        let sockfd: QDesc = match libos.socket(Domain::IPV4, Type::DGRAM, Protocol::UDP) {
+            Ok(qd) => qd,
+            Err(e) => anyhow::bail!("failed to create socket: {:?}", e),
+        };
+        if let Err(e) = libos.bind(sockfd, local) {
+            anyhow::bail!("bind() failed: {:?}", e)
+        }
+
+        // Push three datagrams to ourselves.
+        for len in [16, 32, 48] {
+            let bytes = libos.cook_data(len)?;
+            let qt: QToken = match libos.pushto(sockfd, &bytes, local, None) {
+                Ok(qt) => qt,
+                Err(e) => anyhow::bail!("push() failed: {:?}", e),
+            };
+            match safe_wait(&mut libos, qt)? {
+                (_, OperationResult::Push(_)) => (),
+                _ => anyhow::bail!("wait on push() failed"),
+            }
+        }
+
+        // Pop all of them at once.
+        let qt: QToken = match libos.pop_many(sockfd, 8, None) {
+            Ok(qt) => qt,
+            Err(e) => anyhow::bail!("pop_many() failed: {:?}", e),
+        };
+        match safe_wait(&mut libos, qt)? {
+            (_, OperationResult::PopMany(batch))
+                if batch
+                    .iter()
+                    .map(|(addr, buf, _)| (addr.map(SocketAddr::V4), buf.len()))
+                    .eq([(Some(local), 16), (Some(local), 32), (Some(local), 48)]) => {},
+            (_, qr) => anyhow::bail!("wait on pop_many() failed: {:?}", qr),
+        }
+
+        // A later batched pop takes only what arrived since.
+        let bytes = libos.cook_data(8)?;
+        let qt: QToken = match libos.pushto(sockfd, &bytes, local, None) {
+            Ok(qt) => qt,
+            Err(e) => anyhow::bail!("push() failed: {:?}", e),
+        };
+        safe_wait(&mut libos, qt)?;
+        let qt: QToken = match libos.pop_many(sockfd, 2, None) {
+            Ok(qt) => qt,
+            Err(e) => anyhow::bail!("pop_many() failed: {:?}", e),
+        };
+        match safe_wait(&mut libos, qt)? {
+            (_, OperationResult::PopMany(batch)) if batch.len() == 1 && batch[0].1.len() == 8 => (),
+            (_, qr) => anyhow::bail!("wait on pop_many() failed: {:?}", qr),
+        }
+
+        match libos.async_close(sockfd) {
+            Ok(qt) => {
+                safe_wait(&mut libos, qt)?;
+                Ok(())
+            },
+            Err(e) => anyhow::bail!("close() failed: {:?}", e),
+        }
+    }
+
+    //==============================================================================
+    // QUIC
+    //==============================================================================
+
+    /// How long to wait for an operation that runs the QUIC handshake or waits for the peer.
+    const QUIC_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Tests that a QUIC client and server exchange data on a stream and then close the connection.
+    #[test]
+    fn udp_quic_echo() -> Result<()> {
         let (alice_tx, alice_rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
         let (bob_tx, bob_rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
 
-        let bob_port: u16 = PORT_BASE;
-        let bob_addr: SocketAddr = SocketAddr::new(ALICE_IP, bob_port);
-        let alice_port: u16 = PORT_BASE;
-        let alice_addr: SocketAddr = SocketAddr::new(ALICE_IP, alice_port);
-
-        let bob_barrier: Arc<Barrier> = Arc::new(Barrier::new(2));
-        let alice_barrier: Arc<Barrier> = bob_barrier.clone();
+        let bob_addr: SocketAddr = SocketAddr::new(BOB_IP, PORT_BASE);
+        let alice_addr: SocketAddr = SocketAddr::new(ALICE_IP, PORT_BASE);
 
         let alice: JoinHandle<Result<()>> = thread::spawn(move || {
             let mut libos: DummyLibOS = match DummyLibOS::new(ALICE_MAC, ALICE_IPV4, alice_tx, bob_rx, arp()) {
@@ -384,129 +541,211 @@ mod test {
                 Err(e) => anyhow::bail!("Could not create inetstack: {:?}", e),
             };
 
-            // Open connection.
-            let sockfd: QDesc = match libos.socket(Domain::IPV4, Type::DGRAM, Protocol::UDP) {
-                Ok(qd) => qd,
-                Err(e) => anyhow::bail!("failed to create socket: {:?}", e),
+            // Listen for QUIC clients.
+            let sockqd: QDesc = libos.socket(Domain::IPV4, Type::DGRAM, Protocol::UDP)?;
+            libos.bind(sockqd, alice_addr)?;
+            let qd: QDesc = libos.quic_socket(sockqd, QuicConfig::new(TlsConfig::new(b"bob", &[7; 32])?))?;
+            libos.listen(qd, 16)?;
+
+            // Accept a connection and the stream that the client opens on it.
+            let qt: QToken = libos.accept(qd, None)?;
+            let connqd: QDesc = match libos.wait(qt, Some(QUIC_TIMEOUT))? {
+                (_, OperationResult::Accept((connqd, addr))) if addr.ip() == &BOB_IPV4 => connqd,
+                (_, qr) => anyhow::bail!("accept() of a connection has failed: {:?}", qr),
             };
-            match libos.bind(sockfd, alice_addr) {
-                Ok(_) => (),
-                Err(e) => {
-                    // Close socket on error.
-                    // FIXME: https://github.com/demikernel/demikernel/issues/633
-                    anyhow::bail!("bind() failed: {:?}", e)
-                },
+            let qt: QToken = libos.accept(connqd, None)?;
+            let streamqd: QDesc = match libos.wait(qt, Some(QUIC_TIMEOUT))? {
+                (_, OperationResult::Accept((streamqd, _))) => streamqd,
+                (_, qr) => anyhow::bail!("accept() of a stream has failed: {:?}", qr),
             };
-            // Cook some data and push.
-            let bytes = libos.cook_data(32)?;
-            let qt: QToken = match libos.pushto(sockfd, &bytes, bob_addr) {
-                Ok(qt) => qt,
-                Err(e) => {
-                    // Close socket on error.
-                    // FIXME: https://github.com/demikernel/demikernel/issues/633
-                    anyhow::bail!("push() failed: {:?}", e)
-                },
+
+            // Echo data back.
+            let qt: QToken = libos.pop(streamqd, None, None)?;
+            let buf: DemiBuffer = match libos.wait(qt, Some(QUIC_TIMEOUT))? {
+                (_, OperationResult::Pop(_, buf, _)) if buf.len() == 32 => buf,
+                (_, qr) => anyhow::bail!("pop() has failed: {:?}", qr),
             };
-            let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
-            match qr {
-                OperationResult::Push => (),
-                _ => {
-                    // Close socket on error.
-                    // FIXME: https://github.com/demikernel/demikernel/issues/633
-                    anyhow::bail!("wait on push() failed")
-                },
+            let sga = libos.get_transport().into_sgarray(buf)?;
+            let qt: QToken = libos.push(streamqd, &sga, None)?;
+            match libos.wait(qt, Some(QUIC_TIMEOUT))? {
+                (_, OperationResult::Push(_)) => (),
+                (_, qr) => anyhow::bail!("push() has failed: {:?}", qr),
             }
 
-            // Pop data.
-            let qt: QToken = match libos.pop(sockfd, None) {
-                Ok(qt) => qt,
-                Err(e) => {
-                    // Close socket on error.
-                    // FIXME: https://github.com/demikernel/demikernel/issues/633
-                    anyhow::bail!("pop() failed: {:?}", e)
-                },
-            };
-            let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
-            match qr {
-                OperationResult::Pop(_, _) => (),
-                _ => {
-                    // Close socket on error.
-                    // FIXME: https://github.com/demikernel/demikernel/issues/633
-                    anyhow::bail!("wait on pop() failed")
-                },
+            // Wait for the client to end the stream.
+            let qt: QToken = libos.pop(streamqd, None, None)?;
+            match libos.wait(qt, Some(QUIC_TIMEOUT))? {
+                (_, OperationResult::Pop(_, buf, _)) if buf.len() == 0 => (),
+                (_, qr) => anyhow::bail!("pop() should have returned the end of the stream: {:?}", qr),
             }
 
-            // Close connection.
-            match libos.async_close(sockfd) {
-                Ok(qt) => {
-                    safe_wait(&mut libos, qt)?;
-                    alice_barrier.wait();
-                    Ok(())
-                },
-                Err(e) => anyhow::bail!("close() failed: {:?}", e),
+            // Close the stream, the connection and the listening socket.
+            for qd in [streamqd, connqd, qd] {
+                let qt: QToken = libos.async_close(qd)?;
+                match libos.wait(qt, Some(QUIC_TIMEOUT))? {
+                    (_, OperationResult::Close) => (),
+                    (_, qr) => anyhow::bail!("close() has failed: {:?}", qr),
+                }
             }
+            Ok(())
         });
 
-        let bob = thread::spawn(move || {
-            let mut libos: DummyLibOS = match DummyLibOS::new(ALICE_MAC, ALICE_IPV4, bob_tx, alice_rx, arp()) {
+        let bob: JoinHandle<Result<()>> = thread::spawn(move || {
+            let mut libos: DummyLibOS = match DummyLibOS::new(BOB_MAC, BOB_IPV4, bob_tx, alice_rx, arp()) {
                 Ok(libos) => libos,
                 Err(e) => anyhow::bail!("Could not create inetstack: {:?}", e),
             };
 
-            // Open connection.
-            let sockfd: QDesc = match libos.socket(Domain::IPV4, Type::DGRAM, Protocol::UDP) {
-                Ok(qd) => qd,
-                Err(e) => anyhow::bail!("failed to create socket: {:?}", e),
-            };
-            match libos.bind(sockfd, bob_addr) {
-                Ok(_) => (),
-                Err(e) => {
-                    // Close socket on error.
-                    // FIXME: https://github.com/demikernel/demikernel/issues/633
-                    anyhow::bail!("bind() failed: {:?}", e)
+            // Connect to the server.
+            let sockqd: QDesc = libos.socket(Domain::IPV4, Type::DGRAM, Protocol::UDP)?;
+            libos.bind(sockqd, bob_addr)?;
+            let qd: QDesc = libos.quic_socket(sockqd, QuicConfig::new(TlsConfig::new(b"bob", &[7; 32])?))?;
+            let qt: QToken = libos.connect(qd, alice_addr, None)?;
+            match libos.wait(qt, Some(QUIC_TIMEOUT))? {
+                (_, OperationResult::Connect) => (),
+                (_, qr) => anyhow::bail!("connect() has failed: {:?}", qr),
+            }
+
+            // The UDP socket is now only reachable through the QUIC socket.
+            match libos.pop(sockqd, None, None) {
+                Err(e) if e.errno == libc::EBADF => (),
+                _ => anyhow::bail!("pop() on the socket of a QUIC connection should have failed"),
+            }
+
+            // Push data on a stream and wait for it to come back.
+            let streamqd: QDesc = libos.quic_open_stream(qd)?;
+            let sga = libos.cook_data(32)?;
+            let qt: QToken = libos.push(streamqd, &sga, None)?;
+            match libos.wait(qt, Some(QUIC_TIMEOUT))? {
+                (_, OperationResult::Push(_)) => (),
+                (_, qr) => anyhow::bail!("push() has failed: {:?}", qr),
+            }
+            let qt: QToken = libos.pop(streamqd, None, None)?;
+            match libos.wait(qt, Some(QUIC_TIMEOUT))? {
+                (_, OperationResult::Pop(_, buf, _)) if buf.len() == 32 && buf[..].iter().all(|byte| *byte == b'a') => {
+                    ()
                 },
+                (_, qr) => anyhow::bail!("pop() has failed: {:?}", qr),
+            }
+
+            // End the stream and wait for the server to close the connection.
+            let qt: QToken = libos.async_close(streamqd)?;
+            match libos.wait(qt, Some(QUIC_TIMEOUT))? {
+                (_, OperationResult::Close) => (),
+                (_, qr) => anyhow::bail!("close() of the stream has failed: {:?}", qr),
+            }
+            let qt: QToken = libos.accept(qd, None)?;
+            match libos.wait(qt, Some(QUIC_TIMEOUT))? {
+                (_, OperationResult::Failed(e)) if e.errno == libc::ECONNRESET => (),
+                (_, qr) => anyhow::bail!("accept() should have failed: {:?}", qr),
+            }
+            let qt: QToken = libos.async_close(qd)?;
+            match libos.wait(qt, Some(QUIC_TIMEOUT))? {
+                (_, OperationResult::Close) => (),
+                (_, qr) => anyhow::bail!("close() has failed: {:?}", qr),
+            }
+            Ok(())
+        });
+
+        // It is safe to use unwrap here because there should not be any reason that we can't join the thread and if there
+        // is, there is nothing to clean up here on the main thread.
+        alice.join().unwrap()?;
+        bob.join().unwrap()?;
+
+        Ok(())
+    }
+
+    /// How long to wait for an operation that runs the DTLS handshake or waits for the peer.
+    const DTLS_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Tests that a DTLS client and server exchange datagrams and then close the association.
+    #[test]
+    fn udp_dtls_echo() -> Result<()> {
+        let (alice_tx, alice_rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+        let (bob_tx, bob_rx): (Sender<DemiBuffer>, Receiver<DemiBuffer>) = crossbeam_channel::unbounded();
+
+        let bob_addr: SocketAddr = SocketAddr::new(BOB_IP, PORT_BASE);
+        let alice_addr: SocketAddr = SocketAddr::new(ALICE_IP, PORT_BASE);
+
+        let alice: JoinHandle<Result<()>> = thread::spawn(move || {
+            let mut libos: DummyLibOS = match DummyLibOS::new(ALICE_MAC, ALICE_IPV4, alice_tx, bob_rx, arp()) {
+                Ok(libos) => libos,
+                Err(e) => anyhow::bail!("Could not create inetstack: {:?}", e),
             };
-            // Pop data.
-            let qt: QToken = match libos.pop(sockfd, None) {
-                Ok(qt) => qt,
-                Err(e) => {
-                    // Close socket on error.
-                    // FIXME: https://github.com/demikernel/demikernel/issues/633
-                    anyhow::bail!("pop() failed: {:?}", e)
-                },
+
+            // Wait for a DTLS client.
+            let sockqd: QDesc = libos.socket(Domain::IPV4, Type::DGRAM, Protocol::UDP)?;
+            libos.bind(sockqd, alice_addr)?;
+            let qd: QDesc = libos.dtls_accept(sockqd, TlsConfig::new(b"bob", &[7; 32])?)?;
+
+            // Echo a datagram back.
+            let qt: QToken = libos.pop(qd, None, None)?;
+            let buf: DemiBuffer = match libos.wait(qt, Some(DTLS_TIMEOUT))? {
+                (_, OperationResult::Pop(Some(addr), buf, _)) if addr.ip() == &BOB_IPV4 && buf.len() == 32 => buf,
+                (_, qr) => anyhow::bail!("pop() has failed: {:?}", qr),
             };
-            let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
-            let bytes: DemiBuffer = match qr {
-                OperationResult::Pop(_, bytes) => bytes,
-                _ => {
-                    // Close socket on error.
-                    // FIXME: https://github.com/demikernel/demikernel/issues/633
-                    anyhow::bail!("pop() failed")
-                },
+            let sga = libos.get_transport().into_sgarray(buf)?;
+            let qt: QToken = libos.push(qd, &sga, None)?;
+            match libos.wait(qt, Some(DTLS_TIMEOUT))? {
+                (_, OperationResult::Push(_)) => (),
+                (_, qr) => anyhow::bail!("push() has failed: {:?}", qr),
+            }
+
+            // Wait for the client to close the association.
+            let qt: QToken = libos.pop(qd, None, None)?;
+            match libos.wait(qt, Some(DTLS_TIMEOUT))? {
+                (_, OperationResult::Pop(_, buf, _)) if buf.len() == 0 => (),
+                (_, qr) => anyhow::bail!("pop() should have returned the end of the association: {:?}", qr),
+            }
+
+            let qt: QToken = libos.async_close(qd)?;
+            match libos.wait(qt, Some(DTLS_TIMEOUT))? {
+                (_, OperationResult::Close) => (),
+                (_, qr) => anyhow::bail!("close() has failed: {:?}", qr),
+            }
+            Ok(())
+        });
+
+        let bob: JoinHandle<Result<()>> = thread::spawn(move || {
+            let mut libos: DummyLibOS = match DummyLibOS::new(BOB_MAC, BOB_IPV4, bob_tx, alice_rx, arp()) {
+                Ok(libos) => libos,
+                Err(e) => anyhow::bail!("Could not create inetstack: {:?}", e),
             };
 
-            // Push data.
-            let buf = libos.get_transport().into_sgarray(bytes)?;
-            let qt: QToken = libos.pushto(sockfd, &buf, alice_addr).unwrap();
-            let (_, qr): (QDesc, OperationResult) = safe_wait(&mut libos, qt)?;
-            match qr {
-                OperationResult::Push => (),
-                _ => {
-                    // Close socket on error.
-                    // FIXME: https://github.com/demikernel/demikernel/issues/633
-                    anyhow::bail!("push() failed")
-                },
+            // Start an association with the server.
+            let sockqd: QDesc = libos.socket(Domain::IPV4, Type::DGRAM, Protocol::UDP)?;
+            libos.bind(sockqd, bob_addr)?;
+            let qd: QDesc = libos.dtls_connect(sockqd, alice_addr, TlsConfig::new(b"bob", &[7; 32])?)?;
+
+            // The UDP socket is now only reachable through the DTLS socket.
+            match libos.pop(sockqd, None, None) {
+                Err(e) if e.errno == libc::EBADF => (),
+                _ => anyhow::bail!("pop() on the socket of a DTLS association should have failed"),
             }
 
-            // Close connection.
-            match libos.async_close(sockfd) {
-                Ok(qt) => {
-                    safe_wait(&mut libos, qt)?;
-                    bob_barrier.wait();
-                    Ok(())
+            // Push a datagram and wait for it to come back.
+            let sga = libos.cook_data(32)?;
+            let qt: QToken = libos.push(qd, &sga, None)?;
+            match libos.wait(qt, Some(DTLS_TIMEOUT))? {
+                (_, OperationResult::Push(_)) => (),
+                (_, qr) => anyhow::bail!("push() has failed: {:?}", qr),
+            }
+            let qt: QToken = libos.pop(qd, None, None)?;
+            match libos.wait(qt, Some(DTLS_TIMEOUT))? {
+                (_, OperationResult::Pop(Some(addr), buf, _))
+                    if addr.ip() == &ALICE_IPV4 && buf.len() == 32 && buf[..].iter().all(|byte| *byte == b'a') =>
+                {
+                    ()
                 },
-                Err(e) => anyhow::bail!("close() failed: {:?}", e),
+                (_, qr) => anyhow::bail!("pop() has failed: {:?}", qr),
+            }
+
+            let qt: QToken = libos.async_close(qd)?;
+            match libos.wait(qt, Some(DTLS_TIMEOUT))? {
+                (_, OperationResult::Close) => (),
+                (_, qr) => anyhow::bail!("close() has failed: {:?}", qr),
             }
+            Ok(())
         });
 
         // It is safe to use unwrap here because there should not be any reason that we can't join the thread and if there
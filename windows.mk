@@ -91,6 +91,11 @@ CARGO_FEATURES = $(CARGO_FEATURES) --features=$(DRIVER)
 CARGO_FEATURES = $(CARGO_FEATURES) --features=profiler
 !endif
 
+# Switch for tracing.
+!if "$(TRACING)" == "yes"
+CARGO_FEATURES = $(CARGO_FEATURES) --features=tracing
+!endif
+
 CARGO_FEATURES = $(CARGO_FEATURES) $(FEATURES)
 
 #=======================================================================================================================
@@ -220,7 +225,7 @@ MTU = 1500
 !endif
 
 !ifndef MSS
-MSS = 1500
+MSS = 1460
 !endif
 
 !ifndef PEER